@@ -1,7 +1,11 @@
-use axum::{Router, middleware, routing::get};
+use axum::{Router, extract::DefaultBodyLimit, middleware, routing::get};
+use mms_api::middleware::body_limit::{BodyLimits, body_limit_middleware};
+use mms_api::middleware::drain::{DrainState, drain_middleware};
+use mms_api::middleware::load_shed::{LoadShedder, load_shed_middleware};
 use mms_api::middleware::request_id::request_id_middleware;
 use mms_api::{config::ApiConfig, state::ApiState};
-use tower_http::trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer};
+use std::time::Duration;
+use tower_http::trace::{DefaultOnResponse, TraceLayer};
 use tracing::Level;
 
 #[tokio::main]
@@ -10,51 +14,102 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let config = ApiConfig::from_env()?;
 
     // Initialize tracing/logging based on environment
-    mms_api::tracing::init_tracing(&config.env);
+    mms_api::tracing::init_tracing(&config.env, config.log_format);
+
+    // Post-deploy gate: run the scripted journey in a scratch schema and exit
+    if std::env::args().skip(1).any(|arg| arg == "--smoke-test") {
+        let passed = smoke_test(config).await;
+        std::process::exit(if passed { 0 } else { 1 });
+    }
 
     // Initialize Prometheus metrics exporter
     let metrics_handle = mms_api::metrics::init_metrics()?;
     tracing::info!("Prometheus metrics exporter initialized");
 
     // Initialize database pool and run migrations
-    let pool = mms_db::create_pool(&config.database_url, config.database_max_connections).await?;
+    let pools = mms_db::create_pool_pair(
+        &config.database_url,
+        config.database_replica_url.as_deref(),
+        &config.pool_settings(),
+    )
+    .await?;
     let create_db_if_missing = config.env == mms_api::config::Environment::Development;
-    mms_db::ensure_db_and_migrate(&config.database_url, &pool, create_db_if_missing).await?;
+    mms_db::ensure_db_and_migrate(&config.database_url, pools.primary(), create_db_if_missing)
+        .await?;
 
     // Extract values needed after state construction, then consume config
     let allowed_origins = config.parsed_allowed_origins();
+    let load_shedder = LoadShedder::from_config(&config);
+    let body_limits = BodyLimits::from_config(&config);
+    let drain_period = Duration::from_secs(config.shutdown_drain_seconds);
     let environment = config.env.clone();
     let port = config.port;
+    let streak_reminder_hours = config.streak_reminder_hours_before_midnight;
 
     // Initialize the application state (consumes config)
-    let state = ApiState::new(config, pool).await?;
+    let state = ApiState::new(config, pools).await?;
+    let drain = state.drain.clone();
+
+    // Warm up before binding the listener so readiness is only reported once
+    // connections, prepared statements and regexes are ready
+    mms_api::warmup::warm_up(&state).await;
 
     // Start background jobs for periodic maintenance
-    let _job_handles = mms_api::jobs::start_background_jobs(state.pool.clone());
-    tracing::info!("Background jobs started (token cleanup, unverified account cleanup)");
+    let _job_handles = mms_api::jobs::start_background_jobs(
+        mms_api::jobs::queue::JobContext::from_state(&state),
+        (*state.schedules).clone(),
+        state.email_tx.clone(),
+        state.events.clone(),
+        state.auth.feed_keys.clone(),
+        streak_reminder_hours,
+    );
+    tracing::info!(
+        "Background jobs started (job queue, token cleanup, unverified account cleanup, index advisor, review and streak reminders, weekly digest)"
+    );
 
     // Configure CORS with allowed origins from config
     let cors = mms_api::middleware::cors::create_cors_layer(allowed_origins);
 
-    // Configure HTTP request/response tracing with request ID
+    // Configure HTTP request/response tracing with request ID; credential headers are masked
     let trace_layer = TraceLayer::new_for_http()
-        .make_span_with(
-            DefaultMakeSpan::new()
-                .level(Level::INFO)
-                .include_headers(true),
-        )
+        .make_span_with(mms_api::tracing::redact::RedactedMakeSpan)
         .on_response(DefaultOnResponse::new().level(Level::INFO));
 
     // Create metrics endpoint (separate from main app for better isolation)
     let metrics_app = Router::new()
         .route("/metrics", get(mms_api::metrics::metrics_handler))
-        .with_state(metrics_handle);
+        .with_state(mms_api::metrics::MetricsState {
+            handle: metrics_handle,
+            pool: state.pool.clone(),
+        });
 
     // Create the application router with endpoint-specific rate limiting
     // Note: Rate limiting is now applied per-route in the route handlers for better granularity
-    let app = mms_api::router::router()
-        .merge(metrics_app)
+    let mut app = mms_api::router::router().merge(metrics_app);
+
+    // Interactive API docs stay out of production; the spec itself is always served
+    if !environment.is_production() {
+        app = app
+            .merge(mms_api::openapi::swagger_ui())
+            .merge(mms_api::mailer::preview::routes());
+    }
+
+    let app = app
         .with_state(state)
+        // Our limit replaces axum's fixed 2 MiB one so imports can be larger
+        .layer(middleware::from_fn_with_state(
+            body_limits,
+            body_limit_middleware,
+        ))
+        .layer(DefaultBodyLimit::disable())
+        .layer(middleware::from_fn_with_state(
+            load_shedder,
+            load_shed_middleware,
+        ))
+        .layer(middleware::from_fn_with_state(
+            drain.clone(),
+            drain_middleware,
+        ))
         .layer(middleware::from_fn(request_id_middleware))
         .layer(middleware::from_fn(mms_api::metrics::track_metrics))
         .layer(trace_layer)
@@ -73,11 +128,25 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing::info!("  - Prometheus metrics at /metrics");
     tracing::info!("  - Health check at /health (liveness)");
     tracing::info!("  - Readiness check at /health/ready");
+    tracing::info!("  - OpenAPI document at /v1/openapi.json");
+    if !environment.is_production() {
+        tracing::info!("  - Swagger UI at /docs");
+        tracing::info!("  - Email previews at /dev/emails/{{template}}?locale=");
+    }
+    tracing::info!("  - JWKS at /.well-known/jwks.json (RS256/EdDSA signing only)");
+    tracing::info!("  - Streaming NDJSON content ingestion at /v1/admin/content/ingest");
     tracing::info!("  - Request ID tracing (X-Request-ID header)");
-    tracing::info!("  - Background jobs (token cleanup every 6h, unverified accounts daily)");
+    tracing::info!(
+        "  - Background jobs (token cleanup every 6h, unverified accounts, index advisor and card difficulty daily)"
+    );
     tracing::info!(
         "  - Endpoint-specific rate limiting (auth: 5/s, sensitive: 2/min, general: 10/s)"
     );
+    tracing::info!(
+        "  - Request body limits (larger for imports) and JSON depth/duplicate key checks"
+    );
+    tracing::info!("  - Load shedding (503 + Retry-After when route classes are saturated)");
+    tracing::info!("  - Connection draining on shutdown (readiness fails, Connection: close)");
     tracing::info!("  - SameSite::Strict cookies");
     tracing::info!("  - Security headers (X-Content-Type-Options, X-Frame-Options, HSTS)");
     tracing::info!("  - Timing-safe responses for sensitive endpoints");
@@ -90,7 +159,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     );
 
     // Graceful shutdown with signal handling
-    let graceful = server.with_graceful_shutdown(shutdown_signal());
+    let graceful = server.with_graceful_shutdown(shutdown_signal(drain, drain_period));
 
     tracing::info!("Server ready to accept connections");
     graceful.await?;
@@ -99,8 +168,53 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Run the smoke-test journey against a temporary schema; returns whether it passed
+///
+/// The schema is migrated from scratch and dropped afterwards, pass or fail, so
+/// the journey never touches real data.
+async fn smoke_test(config: ApiConfig) -> bool {
+    let schema = format!("smoke_{}", &uuid::Uuid::new_v4().simple().to_string()[..12]);
+    let database_url = config.database_url.clone();
+    tracing::info!("Smoke test: running in temporary schema {}", schema);
+
+    let result = async {
+        let pool = mms_db::create_schema_pool(&database_url, 5, &schema).await?;
+        mms_db::ensure_db_and_migrate(&database_url, &pool, false).await?;
+        let state = ApiState::new(config, mms_db::PoolPair::new(pool.clone(), None)).await?;
+        let steps = mms_api::smoke::run_journey(state).await;
+        pool.close().await;
+        anyhow::Ok(steps?)
+    }
+    .await;
+
+    match mms_db::create_pool(&database_url, 1).await {
+        Ok(pool) => {
+            if let Err(e) = mms_db::drop_schema(&pool, &schema).await {
+                tracing::error!("Smoke test: failed to drop schema {}: {:#}", schema, e);
+            }
+        }
+        Err(e) => tracing::error!("Smoke test: failed to drop schema {}: {:#}", schema, e),
+    }
+
+    match result {
+        Ok(steps) => {
+            tracing::info!("Smoke test passed: {}", steps.join(" -> "));
+            true
+        }
+        Err(e) => {
+            tracing::error!("Smoke test failed: {:#}", e);
+            false
+        }
+    }
+}
+
 /// Handle shutdown signals for graceful termination
-async fn shutdown_signal() {
+///
+/// On the first signal the instance starts draining: readiness fails and responses
+/// announce `Connection: close`. The server keeps accepting requests for
+/// `drain_period` so load balancers can deregister it, then stops accepting new
+/// connections and waits for in-flight requests to complete.
+async fn shutdown_signal(drain: DrainState, drain_period: Duration) {
     use tokio::signal;
 
     let ctrl_c = async {
@@ -128,4 +242,13 @@ async fn shutdown_signal() {
             tracing::info!("Received SIGTERM, starting graceful shutdown...");
         },
     }
+
+    drain.start_draining();
+    if !drain_period.is_zero() {
+        tracing::info!(
+            "Draining connections for {}s before closing the listener",
+            drain_period.as_secs()
+        );
+        tokio::time::sleep(drain_period).await;
+    }
 }
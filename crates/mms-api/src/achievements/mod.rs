@@ -0,0 +1,13 @@
+//! Achievements unlocked by practising.
+//!
+//! Definitions live in the `achievements` table: each unlocks once one of the
+//! user's metrics (plausible reviews, longest streak, mastered decks) reaches
+//! its threshold. The review handler calls [`unlock_reached`] in its
+//! transaction; every newly unlocked achievement becomes an
+//! `achievement_unlocked` notification, published once the review commits.
+
+pub mod routes;
+pub mod unlock;
+
+pub use routes::routes;
+pub use unlock::unlock_reached;
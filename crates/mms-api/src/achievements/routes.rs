@@ -0,0 +1,44 @@
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    routing::get,
+};
+use sqlx::types::Uuid;
+
+use crate::{
+    ApiState,
+    auth::{AuthUser, require_self},
+    error::{ApiError, ErrorResponse},
+};
+
+use mms_db::models::UserAchievement;
+use mms_db::repositories::achievement as achievement_repo;
+
+/// Create the achievement routes
+pub fn routes() -> Router<ApiState> {
+    Router::new().route("/users/{user_id}/achievements", get(get_achievements))
+}
+
+/// Every achievement with the user's progress towards it and when it was unlocked
+#[utoipa::path(
+    get,
+    path = "/v1/users/{user_id}/achievements",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    params(("user_id" = Uuid, Path, description = "Must be the signed-in user")),
+    responses(
+        (status = 200, description = "Achievements in display order", body = Vec<UserAchievement>),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Another user's achievements", body = ErrorResponse),
+    )
+)]
+async fn get_achievements(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<Vec<UserAchievement>>, ApiError> {
+    require_self(&auth_user, user_id)?;
+
+    let achievements = achievement_repo::list_for_user(&state.pool, user_id).await?;
+    Ok(Json(achievements))
+}
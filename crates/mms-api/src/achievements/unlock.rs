@@ -0,0 +1,40 @@
+use sqlx::{Postgres, Transaction};
+use uuid::Uuid;
+
+use mms_db::{
+    models::Notification,
+    repositories::{achievement as achievement_repo, notification as notification_repo},
+};
+
+use crate::notifications::NotificationKind;
+
+/// Client route listing the user's achievements
+const ACHIEVEMENTS_LINK: &str = "/achievements";
+
+/// Unlock the achievements the user has reached and store a notification for each.
+///
+/// Returns the notifications so the caller can publish them after committing.
+pub async fn unlock_reached(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+) -> Result<Vec<Notification>, sqlx::Error> {
+    let unlocked = achievement_repo::unlock_reached(&mut **tx, user_id).await?;
+
+    let mut notifications = Vec::with_capacity(unlocked.len());
+    for achievement in unlocked {
+        tracing::debug!(user_id = %user_id, code = %achievement.code, "Achievement unlocked");
+        notifications.push(
+            notification_repo::create(
+                &mut **tx,
+                user_id,
+                NotificationKind::AchievementUnlocked.as_str(),
+                &achievement.title,
+                &achievement.description,
+                Some(ACHIEVEMENTS_LINK),
+            )
+            .await?,
+        );
+    }
+
+    Ok(notifications)
+}
@@ -0,0 +1,280 @@
+//! Moving cards between decks and merging duplicate cards.
+//!
+//! A move unlinks cards from one deck and links them to another of the same
+//! language pair; the cards themselves, and learners' progress on them, stay
+//! as they are.
+//!
+//! A merge folds one card into another: the kept card accepts the other's
+//! translation and accepted answers too, takes its place in every deck, and
+//! inherits its learners' progress, review history and reports. The merged
+//! card is then deleted.
+//!
+//! Both run in one transaction and refresh the stored progress of everyone
+//! who started an affected deck. Authors only move and merge within decks of
+//! their own organizations and the public catalogue; operators anywhere.
+
+use serde::Serialize;
+use sqlx::{Executor, PgConnection, PgPool, Postgres};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use mms_db::{
+    models::FlashcardWithAnswers,
+    repositories::{
+        deck as deck_repo, flashcard as flashcard_repo, practice as practice_repo,
+        report as report_repo,
+    },
+    tenancy::Tenant,
+};
+
+use crate::{
+    auth::policy::Principal,
+    error::ApiError,
+    live::{EventBus, LiveEvent},
+    normalization::normalize_for_comparison,
+};
+
+/// Cards accepted in one move
+pub const MAX_MOVED_CARDS: u64 = 1_000;
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct MoveSummary {
+    /// Cards now in the target deck that were not before
+    pub moved: u64,
+    /// Cards the target deck already had; they were only removed from the source
+    pub already_in_target: u64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MergeSummary {
+    /// The kept card, with the merged card's answers added
+    pub card: FlashcardWithAnswers,
+    /// Decks the kept card is in now
+    pub decks: Vec<Uuid>,
+    /// Learners whose progress on the merged card moved to the kept card
+    pub learners: u64,
+}
+
+/// Check that the principal may change every deck among `deck_ids`
+pub async fn check_decks_editable<'e, E>(
+    executor: E,
+    principal: &Principal,
+    deck_ids: &[Uuid],
+) -> Result<(), ApiError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    // Operators reach every deck; the move itself reports missing ones
+    let Principal::User(user) = principal else {
+        return Ok(());
+    };
+    let mut wanted = deck_ids.to_vec();
+    wanted.sort_unstable();
+    wanted.dedup();
+    let visible = deck_repo::list_by_ids(executor, &wanted, user.user_id).await?;
+    if visible.len() != wanted.len() {
+        return Err(ApiError::NotFound("Deck not found".to_string()));
+    }
+    Ok(())
+}
+
+/// Check that the principal may merge `source_id` into `target_id`: an author
+/// must see a deck of each card, and every deck either card is in, since the
+/// merge changes them all.
+///
+/// Runs inside the merge transaction after the cards are locked, and locks the
+/// deck links it checks, so no deck joins or leaves the cards unchecked.
+async fn check_merge_allowed(
+    conn: &mut PgConnection,
+    principal: &Principal,
+    source_id: Uuid,
+    target_id: Uuid,
+) -> Result<(), ApiError> {
+    let Principal::User(user) = principal else {
+        return Ok(());
+    };
+    let tenant = Tenant::Member(user.user_id);
+    for card_id in [source_id, target_id] {
+        if !flashcard_repo::card_in_visible_deck(&mut *conn, card_id, tenant).await? {
+            return Err(ApiError::NotFound("Card not found".to_string()));
+        }
+    }
+    let decks = flashcard_repo::lock_decks_containing(&mut *conn, &[source_id, target_id]).await?;
+    check_decks_editable(&mut *conn, principal, &decks)
+        .await
+        .map_err(|_| ApiError::NotFound("Card not found".to_string()))
+}
+
+/// Move cards of `from_deck_id` to `to_deck_id`
+pub async fn move_cards(
+    pool: &PgPool,
+    events: &EventBus,
+    from_deck_id: Uuid,
+    to_deck_id: Uuid,
+    flashcard_ids: &[Uuid],
+) -> Result<MoveSummary, ApiError> {
+    if from_deck_id == to_deck_id {
+        return Err(ApiError::Validation(
+            "Cards are already in that deck".to_string(),
+        ));
+    }
+
+    let mut tx = pool.begin().await?;
+    // Locked in id order so concurrent moves between the same decks cannot deadlock
+    let (first, second) = if from_deck_id < to_deck_id {
+        (from_deck_id, to_deck_id)
+    } else {
+        (to_deck_id, from_deck_id)
+    };
+    let first_languages = flashcard_repo::lock_deck_languages(&mut *tx, first).await?;
+    let second_languages = flashcard_repo::lock_deck_languages(&mut *tx, second).await?;
+    let (Some(first_languages), Some(second_languages)) = (first_languages, second_languages)
+    else {
+        return Err(ApiError::NotFound("Deck not found".to_string()));
+    };
+    if first_languages != second_languages {
+        return Err(ApiError::Validation(
+            "Cards can only move between decks of the same language pair".to_string(),
+        ));
+    }
+
+    let mut ids = flashcard_ids.to_vec();
+    ids.sort_unstable();
+    ids.dedup();
+    let found = flashcard_repo::cards_in_deck(&mut *tx, from_deck_id, &ids).await?;
+    if found.len() != ids.len() {
+        let missing = ids.iter().filter(|id| !found.contains(id)).count();
+        return Err(ApiError::NotFound(format!(
+            "{missing} of the cards are not in the deck"
+        )));
+    }
+
+    let moved = flashcard_repo::move_cards(&mut *tx, from_deck_id, to_deck_id, &ids).await?;
+    practice_repo::refresh_learners_deck_progress(
+        &mut *tx,
+        &[from_deck_id, to_deck_id],
+        mms_srs::MASTERY_THRESHOLD,
+    )
+    .await?;
+    tx.commit().await?;
+
+    events.broadcast(LiveEvent::DeckUpdated {
+        deck_id: from_deck_id,
+    });
+    events.broadcast(LiveEvent::DeckUpdated {
+        deck_id: to_deck_id,
+    });
+    tracing::info!(%from_deck_id, %to_deck_id, cards = ids.len(), moved, "Cards moved");
+
+    Ok(MoveSummary {
+        moved,
+        already_in_target: ids.len() as u64 - moved,
+    })
+}
+
+/// Merge the card `source_id` into `target_id` and delete it, if the principal
+/// may change every deck either card is in
+pub async fn merge_cards(
+    pool: &PgPool,
+    events: &EventBus,
+    principal: &Principal,
+    source_id: Uuid,
+    target_id: Uuid,
+) -> Result<MergeSummary, ApiError> {
+    if source_id == target_id {
+        return Err(ApiError::Validation(
+            "A card cannot be merged into itself".to_string(),
+        ));
+    }
+
+    let mut tx = pool.begin().await?;
+    let cards = flashcard_repo::lock_cards(&mut *tx, &[source_id, target_id]).await?;
+    let (Some(source), Some(mut target)) = (
+        cards.iter().find(|card| card.id == source_id),
+        cards.iter().find(|card| card.id == target_id).cloned(),
+    ) else {
+        return Err(ApiError::NotFound("Card not found".to_string()));
+    };
+    check_merge_allowed(&mut tx, principal, source_id, target_id).await?;
+    if (&source.language_from, &source.language_to) != (&target.language_from, &target.language_to)
+    {
+        return Err(ApiError::Validation(
+            "Only cards of the same language pair can be merged".to_string(),
+        ));
+    }
+
+    target.accepted_answers = combine_answers(&target, source);
+    flashcard_repo::set_accepted_answers(&mut *tx, target_id, &target.accepted_answers).await?;
+    flashcard_repo::relink_decks(&mut *tx, source_id, target_id).await?;
+    let learners =
+        flashcard_repo::merge_progress(&mut *tx, source_id, target_id, mms_srs::MASTERY_THRESHOLD)
+            .await?;
+    flashcard_repo::reassign_history(&mut *tx, source_id, target_id).await?;
+    report_repo::delete_card(&mut *tx, source_id).await?;
+
+    let decks = flashcard_repo::decks_containing(&mut *tx, &[target_id]).await?;
+    practice_repo::refresh_learners_deck_progress(&mut *tx, &decks, mms_srs::MASTERY_THRESHOLD)
+        .await?;
+    tx.commit().await?;
+
+    for &deck_id in &decks {
+        events.broadcast(LiveEvent::DeckUpdated { deck_id });
+    }
+    tracing::info!(%source_id, %target_id, learners, "Cards merged");
+
+    Ok(MergeSummary {
+        card: target,
+        decks,
+        learners,
+    })
+}
+
+/// The target's accepted answers followed by the source's translation and
+/// accepted answers, skipping any that grade the same as an answer before it
+fn combine_answers(target: &FlashcardWithAnswers, source: &FlashcardWithAnswers) -> Vec<String> {
+    let mut seen = vec![normalize_for_comparison(&target.translation)];
+    let mut answers = Vec::new();
+    for answer in target
+        .accepted_answers
+        .iter()
+        .chain(std::iter::once(&source.translation))
+        .chain(&source.accepted_answers)
+    {
+        let normalized = normalize_for_comparison(answer);
+        if normalized.is_empty() || seen.contains(&normalized) {
+            continue;
+        }
+        seen.push(normalized);
+        answers.push(answer.clone());
+    }
+    answers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(translation: &str, accepted_answers: &[&str]) -> FlashcardWithAnswers {
+        FlashcardWithAnswers {
+            id: Uuid::new_v4(),
+            term: "cat".to_string(),
+            translation: translation.to_string(),
+            language_from: "en".to_string(),
+            language_to: "es".to_string(),
+            accepted_answers: accepted_answers.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_combine_answers() {
+        let target = card("gato", &["minino"]);
+        let source = card("Gató!", &["felino", "MININO", "michi"]);
+        assert_eq!(
+            combine_answers(&target, &source),
+            ["minino", "felino", "michi"]
+        );
+
+        let source = card("felino", &[]);
+        assert_eq!(combine_answers(&card("gato", &[]), &source), ["felino"]);
+    }
+}
@@ -0,0 +1,539 @@
+//! Card import from flashcard exports of other apps.
+//!
+//! Quizlet exports one card per line with a tab between term and definition;
+//! Memrise course exports are CSV files with a header row. Both are read into
+//! rows of text columns. A preview reports the detected columns with sample
+//! values and a suggested mapping, and the import itself takes the columns
+//! the author picked as term and translation.
+//!
+//! Exports describe a single deck, so a whole file is read into memory and
+//! imported in one transaction.
+//!
+//! A row whose term matches another card of the deck once normalized with
+//! [`normalize_for_comparison`] (case, accents and punctuation ignored) is a
+//! duplicate: the author chooses whether to add it anyway and have it
+//! flagged, reject it, or merge it into the card already there.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use mms_db::repositories::content as content_repo;
+
+use crate::{
+    admin::ingest::IngestLineError,
+    error::ApiError,
+    live::{EventBus, LiveEvent},
+    normalization::normalize_for_comparison,
+};
+
+/// Rows accepted in one file
+pub const MAX_ROWS: usize = 10_000;
+
+/// Rows returned by a preview
+const SAMPLE_ROWS: usize = 5;
+
+/// Values shown per column in a preview
+const SAMPLE_VALUES: usize = 3;
+
+/// Row errors included in the summary; the rest are only counted
+const MAX_REPORTED_ERRORS: usize = 100;
+
+/// Header names that usually hold the term, compared case-insensitively
+const TERM_HEADERS: [&str; 5] = ["term", "word", "learnable", "front", "prompt"];
+
+/// Header names that usually hold the translation
+const TRANSLATION_HEADERS: [&str; 5] = ["definition", "translation", "meaning", "back", "answer"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportFormat {
+    /// Tab between term and definition, one card per line
+    Quizlet,
+    /// Comma-separated with quoting
+    Memrise,
+}
+
+impl ImportFormat {
+    /// Whether exports in this format start with a header row
+    #[must_use]
+    pub const fn has_header(self) -> bool {
+        match self {
+            Self::Quizlet => false,
+            Self::Memrise => true,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Row {
+    /// Line the row starts on, counting from 1
+    line: u64,
+    fields: Vec<String>,
+}
+
+#[derive(Debug)]
+struct Table {
+    headers: Option<Vec<String>>,
+    rows: Vec<Row>,
+}
+
+impl Table {
+    fn width(&self) -> usize {
+        self.rows
+            .iter()
+            .map(|row| row.fields.len())
+            .chain(self.headers.iter().map(Vec::len))
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn column_name(&self, index: usize) -> String {
+        self.headers
+            .as_ref()
+            .and_then(|headers| headers.get(index))
+            .filter(|name| !name.trim().is_empty())
+            .map_or_else(
+                || format!("Column {}", index + 1),
+                |name| name.trim().to_string(),
+            )
+    }
+
+    fn field(&self, row: &Row, index: usize) -> Option<String> {
+        row.fields
+            .get(index)
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    }
+}
+
+/// Split an export into rows, skipping blank lines
+fn parse(format: ImportFormat, text: &str, has_header: bool) -> Result<Table, ApiError> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+
+    let mut rows = match format {
+        ImportFormat::Quizlet => text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| Row {
+                line: index as u64 + 1,
+                fields: line.split('\t').map(str::to_string).collect(),
+            })
+            .collect::<Vec<_>>(),
+        ImportFormat::Memrise => {
+            let mut reader = csv::ReaderBuilder::new()
+                .has_headers(false)
+                .flexible(true)
+                .from_reader(text.as_bytes());
+            let mut rows = Vec::new();
+            for record in reader.records() {
+                let record =
+                    record.map_err(|e| ApiError::Validation(format!("Invalid CSV: {e}")))?;
+                if record.iter().all(|field| field.trim().is_empty()) {
+                    continue;
+                }
+                rows.push(Row {
+                    line: record.position().map_or(0, csv::Position::line),
+                    fields: record.iter().map(str::to_string).collect(),
+                });
+            }
+            rows
+        }
+    };
+
+    let headers = (has_header && !rows.is_empty()).then(|| rows.remove(0).fields);
+    if rows.len() > MAX_ROWS {
+        return Err(ApiError::Validation(format!(
+            "Exports can hold at most {MAX_ROWS} cards"
+        )));
+    }
+
+    Ok(Table { headers, rows })
+}
+
+/// Columns holding the term and the translation, numbered from 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+pub struct ColumnMapping {
+    pub term_column: usize,
+    pub translation_column: usize,
+}
+
+/// Guess the mapping from header names, falling back to the first two columns
+fn suggest_mapping(table: &Table) -> ColumnMapping {
+    let find = |names: &[&str]| {
+        table.headers.as_ref().and_then(|headers| {
+            headers
+                .iter()
+                .position(|header| names.contains(&header.trim().to_lowercase().as_str()))
+        })
+    };
+
+    let term_column = find(&TERM_HEADERS).unwrap_or(0);
+    let translation_column = find(&TRANSLATION_HEADERS)
+        .filter(|column| *column != term_column)
+        .unwrap_or(if term_column == 0 { 1 } else { 0 });
+
+    ColumnMapping {
+        term_column,
+        translation_column,
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportColumn {
+    pub index: usize,
+    /// Header, or `Column N` for files without one
+    pub name: String,
+    /// First non-empty values
+    pub samples: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportPreview {
+    pub format: ImportFormat,
+    pub has_header: bool,
+    pub columns: Vec<ImportColumn>,
+    /// First rows, as they would be read
+    pub sample_rows: Vec<Vec<String>>,
+    /// Rows that would be imported or rejected, excluding the header
+    pub total_rows: usize,
+    pub suggested_mapping: ColumnMapping,
+}
+
+/// Detect the columns of an export without importing anything
+pub fn preview(
+    format: ImportFormat,
+    text: &str,
+    has_header: Option<bool>,
+) -> Result<ImportPreview, ApiError> {
+    let has_header = has_header.unwrap_or_else(|| format.has_header());
+    let table = parse(format, text, has_header)?;
+
+    let columns = (0..table.width())
+        .map(|index| ImportColumn {
+            index,
+            name: table.column_name(index),
+            samples: table
+                .rows
+                .iter()
+                .filter_map(|row| table.field(row, index))
+                .take(SAMPLE_VALUES)
+                .collect(),
+        })
+        .collect();
+
+    Ok(ImportPreview {
+        format,
+        has_header,
+        columns,
+        sample_rows: table
+            .rows
+            .iter()
+            .take(SAMPLE_ROWS)
+            .map(|row| row.fields.clone())
+            .collect(),
+        total_rows: table.rows.len(),
+        suggested_mapping: suggest_mapping(&table),
+    })
+}
+
+/// What to do with a row whose term duplicates another card of the deck
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicatePolicy {
+    /// Add it anyway and list it in the summary
+    #[default]
+    Flag,
+    /// Leave it out as a rejected row
+    Reject,
+    /// Leave it out and keep the card already in the deck
+    Merge,
+}
+
+/// A row whose term matches a card already in the deck or added from an
+/// earlier row
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportDuplicate {
+    pub line: u64,
+    pub term: String,
+    pub translation: String,
+    /// The card it matches
+    pub duplicate_of: Uuid,
+    pub existing_term: String,
+    pub existing_translation: String,
+}
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct ImportSummary {
+    pub rows: u64,
+    /// Cards added to the deck or already in it
+    pub cards: u64,
+    pub rejected: u64,
+    /// First rejected rows, by line
+    pub errors: Vec<IngestLineError>,
+    /// Rows found to be duplicates, whatever was done with them
+    pub duplicates: u64,
+    /// First duplicate rows, by line
+    pub duplicate_rows: Vec<ImportDuplicate>,
+}
+
+impl ImportSummary {
+    fn reject(&mut self, line: u64, message: String) {
+        self.rejected += 1;
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors.push(IngestLineError { line, message });
+        }
+    }
+
+    fn duplicate(&mut self, duplicate: ImportDuplicate) {
+        self.duplicates += 1;
+        if self.duplicate_rows.len() < MAX_REPORTED_ERRORS {
+            self.duplicate_rows.push(duplicate);
+        }
+    }
+}
+
+/// How to read an export and what to do with duplicates
+#[derive(Debug, Clone, Copy)]
+pub struct ImportOptions {
+    /// Whether the file starts with a header row; the format's usual layout when unset
+    pub has_header: Option<bool>,
+    pub mapping: ColumnMapping,
+    pub on_duplicate: DuplicatePolicy,
+}
+
+/// A card of the deck as duplicates are matched against it
+struct KnownCard {
+    id: Uuid,
+    term: String,
+    translation: String,
+}
+
+/// Add every row of an export to a deck, using the author's column mapping
+pub async fn import(
+    pool: &PgPool,
+    events: &EventBus,
+    deck_id: Uuid,
+    format: ImportFormat,
+    text: &str,
+    options: ImportOptions,
+) -> Result<ImportSummary, ApiError> {
+    let ImportOptions {
+        has_header,
+        mapping,
+        on_duplicate,
+    } = options;
+    if mapping.term_column == mapping.translation_column {
+        return Err(ApiError::Validation(
+            "Term and translation must come from different columns".to_string(),
+        ));
+    }
+    let table = parse(
+        format,
+        text,
+        has_header.unwrap_or_else(|| format.has_header()),
+    )?;
+    if mapping.term_column.max(mapping.translation_column) >= table.width() {
+        return Err(ApiError::Validation(format!(
+            "The export only has {} columns",
+            table.width()
+        )));
+    }
+
+    let mut tx = pool.begin().await?;
+    if !content_repo::deck_exists(&mut *tx, deck_id).await? {
+        return Err(ApiError::NotFound("Deck not found".to_string()));
+    }
+
+    // Cards by normalized term; the first card with a term is the one kept
+    let mut known: HashMap<String, KnownCard> = HashMap::new();
+    for card in content_repo::list_deck_cards(&mut *tx, deck_id).await? {
+        known
+            .entry(normalize_for_comparison(&card.term))
+            .or_insert(KnownCard {
+                id: card.id,
+                term: card.term,
+                translation: card.translation,
+            });
+    }
+
+    let mut summary = ImportSummary::default();
+    let mut seen = HashSet::new();
+    for row in &table.rows {
+        summary.rows += 1;
+        let (Some(term), Some(translation)) = (
+            table.field(row, mapping.term_column),
+            table.field(row, mapping.translation_column),
+        ) else {
+            summary.reject(row.line, "Missing term or translation".to_string());
+            continue;
+        };
+        // The same card twice in one file only counts once
+        if !seen.insert((term.clone(), translation.clone())) {
+            continue;
+        }
+
+        let key = normalize_for_comparison(&term);
+        // Re-importing a card the deck already has is not a duplicate
+        if let Some(existing) = known.get(&key).filter(|card| {
+            !key.is_empty() && (card.term != term || card.translation != translation)
+        }) {
+            summary.duplicate(ImportDuplicate {
+                line: row.line,
+                term: term.clone(),
+                translation: translation.clone(),
+                duplicate_of: existing.id,
+                existing_term: existing.term.clone(),
+                existing_translation: existing.translation.clone(),
+            });
+            match on_duplicate {
+                DuplicatePolicy::Flag => {}
+                DuplicatePolicy::Reject => {
+                    let message = format!("Duplicate of the card \"{}\"", existing.term);
+                    summary.reject(row.line, message);
+                    continue;
+                }
+                DuplicatePolicy::Merge => continue,
+            }
+        }
+
+        if let Some(id) =
+            content_repo::upsert_deck_flashcard(&mut *tx, deck_id, &term, &translation).await?
+        {
+            known.entry(key).or_insert(KnownCard {
+                id,
+                term,
+                translation,
+            });
+        }
+        summary.cards += 1;
+    }
+    tx.commit().await?;
+
+    if summary.cards > 0 {
+        events.broadcast(LiveEvent::DeckUpdated { deck_id });
+    }
+    tracing::info!(
+        %deck_id,
+        ?format,
+        rows = summary.rows,
+        cards = summary.cards,
+        rejected = summary.rejected,
+        duplicates = summary.duplicates,
+        "Card import finished"
+    );
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_quizlet_export() {
+        let table = parse(
+            ImportFormat::Quizlet,
+            "cat\tgato\n\ndog\tperro, can\n",
+            false,
+        )
+        .unwrap();
+
+        assert!(table.headers.is_none());
+        assert_eq!(
+            table.rows,
+            vec![
+                Row {
+                    line: 1,
+                    fields: vec!["cat".into(), "gato".into()]
+                },
+                Row {
+                    line: 3,
+                    fields: vec!["dog".into(), "perro, can".into()]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_memrise_csv_with_quotes_and_bom() {
+        let table = parse(
+            ImportFormat::Memrise,
+            "\u{feff}Level,Word,Definition\n1,\"hola, amigo\",\"hello, friend\"\n1,gato,\"cat\nfeline\"\n2,perro,dog\n",
+            true,
+        )
+        .unwrap();
+
+        assert_eq!(
+            table.headers.as_deref(),
+            Some(&["Level".to_string(), "Word".into(), "Definition".into()][..])
+        );
+        assert_eq!(table.rows.len(), 3);
+        assert_eq!(table.rows[0].fields[1], "hola, amigo");
+        assert_eq!(table.rows[1].fields[2], "cat\nfeline");
+        // The quoted newline makes the third row start on line 5
+        assert_eq!(table.rows[2].line, 5);
+    }
+
+    #[test]
+    fn test_suggest_mapping_from_headers() {
+        let table = parse(
+            ImportFormat::Memrise,
+            "Level,Definition,Word\n1,hello,hola\n",
+            true,
+        )
+        .unwrap();
+
+        assert_eq!(
+            suggest_mapping(&table),
+            ColumnMapping {
+                term_column: 2,
+                translation_column: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_suggest_mapping_defaults_to_first_columns() {
+        let table = parse(ImportFormat::Quizlet, "cat\tgato\n", false).unwrap();
+
+        assert_eq!(
+            suggest_mapping(&table),
+            ColumnMapping {
+                term_column: 0,
+                translation_column: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_preview_reports_columns_and_samples() {
+        let preview = preview(
+            ImportFormat::Memrise,
+            "Word,Definition,Part of Speech\nhola,hello,\ngato,cat,noun\n",
+            None,
+        )
+        .unwrap();
+
+        assert!(preview.has_header);
+        assert_eq!(preview.total_rows, 2);
+        assert_eq!(preview.columns.len(), 3);
+        assert_eq!(preview.columns[2].name, "Part of Speech");
+        assert_eq!(preview.columns[2].samples, vec!["noun".to_string()]);
+        assert_eq!(
+            preview.columns[0].samples,
+            vec!["hola".to_string(), "gato".into()]
+        );
+    }
+
+    #[test]
+    fn test_rejects_oversized_exports() {
+        let text = "a\tb\n".repeat(MAX_ROWS + 1);
+
+        assert!(parse(ImportFormat::Quizlet, &text, false).is_err());
+    }
+}
@@ -0,0 +1,406 @@
+//! Bulk course content ingestion from NDJSON.
+//!
+//! Each line of the request body is one roadmap, deck, card or roadmap node.
+//! Lines are applied as they arrive and committed every [`CHECKPOINT_LINES`]
+//! lines, so an import of hundreds of thousands of cards never has to fit in
+//! memory or in a single transaction. All writes are upserts: after a failed
+//! upload, re-sending the same file is safe.
+//!
+//! A bad line (malformed JSON, invalid field, unknown deck, foreign key
+//! violation) is reported in the summary and skipped without affecting the
+//! rest of the import. Records are applied in file order, so decks must come
+//! before their cards and parent nodes before their children.
+//!
+//! Decks changed by a checkpoint are announced to live clients once it commits.
+
+use std::collections::HashSet;
+
+use axum::body::Body;
+use serde::{Deserialize, Serialize};
+use sqlx::{Connection, PgConnection, PgPool};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use mms_db::repositories::content as content_repo;
+
+use crate::{
+    error::ApiError,
+    known_words::CefrLevel,
+    live::{EventBus, LiveEvent},
+    streaming::{NdjsonLine, NdjsonReader},
+    validation::validate_language_code,
+};
+
+/// Lines applied per transaction
+pub const CHECKPOINT_LINES: u64 = 1000;
+
+/// Longest accepted line; anything longer is reported and skipped
+const MAX_LINE_BYTES: usize = 64 * 1024;
+
+/// Line errors included in the summary; the rest are only counted
+const MAX_REPORTED_ERRORS: usize = 100;
+
+/// One line of an ingest file
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum IngestRecord {
+    Roadmap {
+        id: Uuid,
+        title: String,
+        description: Option<String>,
+        language_from: String,
+        language_to: String,
+    },
+    Deck {
+        id: Uuid,
+        title: String,
+        description: Option<String>,
+        language_from: String,
+        language_to: String,
+        /// Level of the deck's vocabulary, matched by known-word imports
+        #[serde(default)]
+        cefr_level: Option<CefrLevel>,
+    },
+    /// Created in the deck's language pair; identical cards are shared between decks
+    Card {
+        deck_id: Uuid,
+        term: String,
+        translation: String,
+    },
+    Node {
+        id: Uuid,
+        roadmap_id: Uuid,
+        deck_id: Uuid,
+        parent_node_id: Option<Uuid>,
+        #[serde(default)]
+        pos_x: i32,
+        #[serde(default)]
+        pos_y: i32,
+    },
+}
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct IngestSummary {
+    /// Lines read, including blank and rejected ones
+    pub lines: u64,
+    pub roadmaps: u64,
+    pub decks: u64,
+    pub cards: u64,
+    pub nodes: u64,
+    pub rejected: u64,
+    /// Transactions committed
+    pub checkpoints: u64,
+    /// First rejected lines, in order
+    pub errors: Vec<IngestLineError>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct IngestLineError {
+    pub line: u64,
+    pub message: String,
+}
+
+impl IngestRecord {
+    /// Deck whose details or cards the record changes
+    const fn deck_id(&self) -> Option<Uuid> {
+        match self {
+            Self::Deck { id, .. } => Some(*id),
+            Self::Card { deck_id, .. } => Some(*deck_id),
+            Self::Roadmap { .. } | Self::Node { .. } => None,
+        }
+    }
+}
+
+impl IngestSummary {
+    fn reject(&mut self, line: u64, message: String) {
+        self.rejected += 1;
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors.push(IngestLineError { line, message });
+        }
+    }
+}
+
+/// Apply an NDJSON body line by line and summarize the result
+pub async fn ingest(
+    pool: &PgPool,
+    body: Body,
+    events: &EventBus,
+) -> Result<IngestSummary, ApiError> {
+    let mut reader = NdjsonReader::new(body.into_data_stream(), MAX_LINE_BYTES);
+    let mut summary = IngestSummary::default();
+    // Decks changed since the last checkpoint
+    let mut updated_decks = HashSet::new();
+    let mut committed_through = 0;
+    let mut tx = pool.begin().await?;
+
+    loop {
+        let line = match reader.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(e) => {
+                // Earlier checkpoints stay committed; re-sending the file resumes the import
+                tracing::warn!(
+                    error = %e,
+                    committed_through,
+                    "Content ingest body ended unexpectedly"
+                );
+                return Err(ApiError::Validation(format!(
+                    "Failed to read request body; lines up to {committed_through} were imported"
+                )));
+            }
+        };
+
+        match line {
+            NdjsonLine::TooLong { number } => summary.reject(
+                number,
+                format!("Line is longer than {MAX_LINE_BYTES} bytes"),
+            ),
+            NdjsonLine::Line { number, bytes } => match parse_record(&bytes) {
+                Ok(record) => {
+                    let deck_id = record.deck_id();
+                    match apply(&mut tx, record, &mut summary).await? {
+                        Ok(()) => updated_decks.extend(deck_id),
+                        Err(message) => summary.reject(number, message),
+                    }
+                }
+                Err(message) => summary.reject(number, message),
+            },
+        }
+
+        summary.lines = reader.line_number();
+        if summary.lines - committed_through >= CHECKPOINT_LINES {
+            tx.commit().await?;
+            announce(events, &mut updated_decks);
+            tx = pool.begin().await?;
+            committed_through = summary.lines;
+            summary.checkpoints += 1;
+            tracing::info!(
+                lines = summary.lines,
+                rejected = summary.rejected,
+                "Content ingest checkpoint"
+            );
+        }
+    }
+
+    tx.commit().await?;
+    announce(events, &mut updated_decks);
+    summary.lines = reader.line_number();
+    summary.checkpoints += 1;
+
+    tracing::info!(
+        lines = summary.lines,
+        roadmaps = summary.roadmaps,
+        decks = summary.decks,
+        cards = summary.cards,
+        nodes = summary.nodes,
+        rejected = summary.rejected,
+        "Content ingest finished"
+    );
+
+    Ok(summary)
+}
+
+fn announce(events: &EventBus, updated_decks: &mut HashSet<Uuid>) {
+    for deck_id in updated_decks.drain() {
+        events.broadcast(LiveEvent::DeckUpdated { deck_id });
+    }
+}
+
+fn parse_record(bytes: &[u8]) -> Result<IngestRecord, String> {
+    let mut record: IngestRecord =
+        serde_json::from_slice(bytes).map_err(|e| format!("Invalid record: {e}"))?;
+
+    match &mut record {
+        IngestRecord::Roadmap {
+            title,
+            language_from,
+            language_to,
+            ..
+        }
+        | IngestRecord::Deck {
+            title,
+            language_from,
+            language_to,
+            ..
+        } => {
+            require_text("title", title)?;
+            for code in [language_from, language_to] {
+                validate_language_code(code).map_err(|e| e.to_string())?;
+                *code = code.to_lowercase();
+            }
+        }
+        IngestRecord::Card {
+            term, translation, ..
+        } => {
+            require_text("term", term)?;
+            require_text("translation", translation)?;
+        }
+        IngestRecord::Node { .. } => {}
+    }
+
+    Ok(record)
+}
+
+/// Trim a required text field in place
+fn require_text(field: &str, value: &mut String) -> Result<(), String> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        return Err(format!("{field} cannot be empty"));
+    }
+    *value = trimmed.to_string();
+    Ok(())
+}
+
+/// Write one record inside a savepoint.
+///
+/// The outer error aborts the import (lost connection, pool closed); the inner
+/// one rejects just this line.
+async fn apply(
+    conn: &mut PgConnection,
+    record: IngestRecord,
+    summary: &mut IngestSummary,
+) -> Result<Result<(), String>, ApiError> {
+    let mut savepoint = conn.begin().await?;
+
+    let result = match &record {
+        IngestRecord::Roadmap {
+            id,
+            title,
+            description,
+            language_from,
+            language_to,
+        } => content_repo::upsert_roadmap(
+            &mut savepoint,
+            *id,
+            title,
+            description.as_deref(),
+            language_from,
+            language_to,
+        )
+        .await
+        .map(|()| true),
+        IngestRecord::Deck {
+            id,
+            title,
+            description,
+            language_from,
+            language_to,
+            cefr_level,
+        } => content_repo::upsert_deck(
+            &mut savepoint,
+            *id,
+            title,
+            description.as_deref(),
+            language_from,
+            language_to,
+            cefr_level.map(CefrLevel::as_str),
+        )
+        .await
+        .map(|()| true),
+        IngestRecord::Card {
+            deck_id,
+            term,
+            translation,
+        } => content_repo::upsert_deck_flashcard(&mut *savepoint, *deck_id, term, translation)
+            .await
+            .map(|card| card.is_some()),
+        IngestRecord::Node {
+            id,
+            roadmap_id,
+            deck_id,
+            parent_node_id,
+            pos_x,
+            pos_y,
+        } => content_repo::upsert_roadmap_node(
+            &mut *savepoint,
+            *id,
+            *roadmap_id,
+            *deck_id,
+            *parent_node_id,
+            *pos_x,
+            *pos_y,
+        )
+        .await
+        .map(|()| true),
+    };
+
+    match result {
+        Ok(true) => {
+            savepoint.commit().await?;
+            match record {
+                IngestRecord::Roadmap { .. } => summary.roadmaps += 1,
+                IngestRecord::Deck { .. } => summary.decks += 1,
+                IngestRecord::Card { .. } => summary.cards += 1,
+                IngestRecord::Node { .. } => summary.nodes += 1,
+            }
+            Ok(Ok(()))
+        }
+        Ok(false) => {
+            savepoint.rollback().await?;
+            Ok(Err("Unknown deck".to_string()))
+        }
+        // Constraint violations only affect this line
+        Err(sqlx::Error::Database(e)) => {
+            savepoint.rollback().await?;
+            Ok(Err(e.message().to_string()))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_deck_normalizes_fields() {
+        let record = parse_record(
+            br#"{"type":"deck","id":"9b2f7c1e-3f4a-4a39-8d7e-0c1f2a3b4c5d","title":" Basics ","language_from":"EN","language_to":"es"}"#,
+        )
+        .unwrap();
+
+        let IngestRecord::Deck {
+            title,
+            language_from,
+            ..
+        } = record
+        else {
+            panic!("Expected a deck");
+        };
+        assert_eq!(title, "Basics");
+        assert_eq!(language_from, "en");
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_records() {
+        let deck_id = "9b2f7c1e-3f4a-4a39-8d7e-0c1f2a3b4c5d";
+
+        assert!(parse_record(b"not json").is_err());
+        assert!(parse_record(br#"{"type":"lesson"}"#).is_err());
+        assert!(
+            parse_record(
+                format!(r#"{{"type":"card","deck_id":"{deck_id}","term":" ","translation":"x"}}"#)
+                    .as_bytes()
+            )
+            .is_err()
+        );
+        assert!(
+            parse_record(
+                format!(r#"{{"type":"deck","id":"{deck_id}","title":"T","language_from":"xx","language_to":"es"}}"#)
+                    .as_bytes()
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_reported_errors_are_capped() {
+        let mut summary = IngestSummary::default();
+        for line in 0..(MAX_REPORTED_ERRORS as u64 + 10) {
+            summary.reject(line, "bad".to_string());
+        }
+
+        assert_eq!(summary.rejected, MAX_REPORTED_ERRORS as u64 + 10);
+        assert_eq!(summary.errors.len(), MAX_REPORTED_ERRORS);
+    }
+}
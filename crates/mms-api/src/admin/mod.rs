@@ -0,0 +1,7 @@
+pub mod cards;
+pub mod import;
+pub mod ingest;
+pub mod routes;
+pub mod seed;
+
+pub use routes::routes;
@@ -0,0 +1,852 @@
+use axum::{
+    Json, Router,
+    body::Body,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode, header::CONTENT_TYPE},
+    routing::{delete, get, patch, post, put},
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+use mms_db::{
+    models::{AdminUserSummary, ApiKey, ClientError, EmailSuppression, JobSummary, UsernameChange},
+    repositories::{
+        api_key as api_key_repo, client_error as client_error_repo, content as content_repo,
+        email_suppression as suppression_repo, job as job_repo, practice as practice_repo,
+        user as user_repo,
+    },
+};
+
+use crate::{
+    ApiState,
+    admin::{
+        cards::{self, MAX_MOVED_CARDS, MergeSummary, MoveSummary},
+        import::{
+            self, ColumnMapping, DuplicatePolicy, ImportFormat, ImportOptions, ImportPreview,
+            ImportSummary,
+        },
+        ingest::{self, IngestSummary},
+        seed::{self, SeedBundle, SeedReport},
+    },
+    auth::{
+        RequirePermission,
+        permissions::{AdminMaintenance, ContentWrite},
+    },
+    error::{ApiError, ErrorResponse},
+    index_advisor::{self, IndexAdvisorReport},
+    jobs::{queue, schedule::ScheduleView},
+    public_api::MAX_DAILY_QUOTA,
+    slugs,
+    user::ACCOUNT_DELETION_GRACE_DAYS,
+    validation::ValidJson,
+};
+
+/// Create the admin routes
+pub fn routes() -> Router<ApiState> {
+    Router::new()
+        .route("/admin/index-report", get(get_index_report))
+        .route("/admin/content/ingest", post(ingest_content))
+        .route("/admin/content/import/preview", post(preview_import))
+        .route("/admin/content/import", post(import_cards))
+        .route("/admin/content/seed", post(seed_content))
+        .route("/admin/roadmaps/{roadmap_id}/slug", put(set_roadmap_slug))
+        .route("/admin/decks/{deck_id}/slug", put(set_deck_slug))
+        .route("/admin/decks/{deck_id}/cards/move", post(move_cards))
+        .route(
+            "/admin/flashcards/{flashcard_id}/merge",
+            post(merge_flashcard),
+        )
+        .route("/admin/users/{user_id}", get(get_user))
+        .route(
+            "/admin/users/{user_id}/email-suppression",
+            delete(clear_email_suppression),
+        )
+        .route("/admin/users/{user_id}/restore", post(restore_user))
+        .route(
+            "/admin/users/{user_id}/deck-progress/recompute",
+            post(recompute_deck_progress),
+        )
+        .route("/admin/api-keys/{api_key_id}", patch(update_api_key))
+        .route("/admin/client-errors", get(list_client_errors))
+        .route("/admin/jobs", get(list_jobs))
+        .route("/admin/jobs/{job_id}/retry", post(retry_job))
+        .route("/admin/schedules", get(list_schedules))
+        .route("/admin/schedules/{job}/run", post(run_scheduled_job))
+}
+
+/// Client error reports returned when no limit is given
+const DEFAULT_CLIENT_ERROR_LIMIT: i64 = 50;
+
+/// Client error reports returned at most
+const MAX_CLIENT_ERROR_LIMIT: i64 = 200;
+
+/// Jobs returned when no limit is given
+const DEFAULT_JOB_LIMIT: i64 = 50;
+
+/// Jobs returned at most
+const MAX_JOB_LIMIT: i64 = 200;
+
+/// Missing, unused and redundant indexes plus the hottest statements
+#[utoipa::path(
+    get,
+    path = "/v1/admin/index-report",
+    tag = "admin",
+    security(("cookie_auth" = ["admin:maintenance"]), ("admin_token" = [])),
+    responses(
+        (status = 200, description = "Index advisor report", body = IndexAdvisorReport),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `admin:maintenance` permission", body = ErrorResponse),
+    )
+)]
+async fn get_index_report(
+    _access: RequirePermission<AdminMaintenance>,
+    State(state): State<ApiState>,
+) -> Result<Json<IndexAdvisorReport>, ApiError> {
+    let report = index_advisor::build_report(&state.pool).await?;
+    Ok(Json(report))
+}
+
+/// Import roadmaps, decks, cards and nodes from a streamed NDJSON body
+#[utoipa::path(
+    post,
+    path = "/v1/admin/content/ingest",
+    tag = "admin",
+    security(("cookie_auth" = ["content:write"]), ("admin_token" = [])),
+    request_body(
+        content = String,
+        content_type = "application/x-ndjson",
+        description = "One `roadmap`, `deck`, `card` or `node` record per line",
+    ),
+    responses(
+        (status = 200, description = "Import summary, including rejected lines", body = IngestSummary),
+        (status = 400, description = "The body ended unexpectedly", body = ErrorResponse),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `content:write` permission", body = ErrorResponse),
+    )
+)]
+async fn ingest_content(
+    _access: RequirePermission<ContentWrite>,
+    State(state): State<ApiState>,
+    body: Body,
+) -> Result<Json<IngestSummary>, ApiError> {
+    let summary = ingest::ingest(&state.pool, body, &state.events).await?;
+    Ok(Json(summary))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct PreviewQuery {
+    #[param(inline)]
+    format: ImportFormat,
+    /// Whether the first row holds column names (default: `false` for Quizlet, `true` for Memrise)
+    #[serde(default)]
+    has_header: Option<bool>,
+}
+
+/// Detect the columns of a Quizlet or Memrise export and suggest which hold the term and translation
+#[utoipa::path(
+    post,
+    path = "/v1/admin/content/import/preview",
+    tag = "admin",
+    security(("cookie_auth" = ["content:write"]), ("admin_token" = [])),
+    params(PreviewQuery),
+    request_body(content = String, content_type = "text/plain", description = "The exported file"),
+    responses(
+        (status = 200, description = "Detected columns and sample rows", body = ImportPreview),
+        (status = 400, description = "Unreadable or oversized export", body = ErrorResponse),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `content:write` permission", body = ErrorResponse),
+    )
+)]
+async fn preview_import(
+    _access: RequirePermission<ContentWrite>,
+    Query(query): Query<PreviewQuery>,
+    body: String,
+) -> Result<Json<ImportPreview>, ApiError> {
+    let preview = import::preview(query.format, &body, query.has_header)?;
+    Ok(Json(preview))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ImportQuery {
+    #[param(inline)]
+    format: ImportFormat,
+    /// Deck the cards are added to; cards take its language pair
+    deck_id: Uuid,
+    /// Column holding the term, numbered from 0
+    term_column: usize,
+    /// Column holding the translation, numbered from 0
+    translation_column: usize,
+    #[serde(default)]
+    has_header: Option<bool>,
+    /// What to do with rows whose term, ignoring case, accents and
+    /// punctuation, matches another card of the deck (default `flag`)
+    #[serde(default)]
+    #[param(inline)]
+    on_duplicate: DuplicatePolicy,
+}
+
+/// Add the cards of a Quizlet or Memrise export to a deck
+#[utoipa::path(
+    post,
+    path = "/v1/admin/content/import",
+    tag = "admin",
+    security(("cookie_auth" = ["content:write"]), ("admin_token" = [])),
+    params(ImportQuery),
+    request_body(content = String, content_type = "text/plain", description = "The exported file"),
+    responses(
+        (status = 200, description = "Import summary, including rejected and duplicate rows", body = ImportSummary),
+        (status = 400, description = "Unreadable or oversized export, or invalid columns", body = ErrorResponse),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `content:write` permission", body = ErrorResponse),
+        (status = 404, description = "Deck not found", body = ErrorResponse),
+    )
+)]
+async fn import_cards(
+    _access: RequirePermission<ContentWrite>,
+    State(state): State<ApiState>,
+    Query(query): Query<ImportQuery>,
+    body: String,
+) -> Result<Json<ImportSummary>, ApiError> {
+    let summary = import::import(
+        &state.pool,
+        &state.events,
+        query.deck_id,
+        query.format,
+        &body,
+        ImportOptions {
+            has_header: query.has_header,
+            mapping: ColumnMapping {
+                term_column: query.term_column,
+                translation_column: query.translation_column,
+            },
+            on_duplicate: query.on_duplicate,
+        },
+    )
+    .await?;
+    Ok(Json(summary))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct SeedQuery {
+    /// Report what the bundle would change without keeping it
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Create or update decks, cards and roadmaps from a YAML or JSON bundle.
+///
+/// Decks and roadmaps are matched by slug, so loading the same bundle again
+/// only applies what changed. The bundle is read as JSON when sent as
+/// `application/json` and as YAML otherwise.
+#[utoipa::path(
+    post,
+    path = "/v1/admin/content/seed",
+    tag = "admin",
+    security(("cookie_auth" = ["content:write"]), ("admin_token" = [])),
+    params(SeedQuery),
+    request_body(
+        content = SeedBundle,
+        content_type = "application/yaml",
+        description = "Decks with their cards and roadmaps with their nodes; also accepted as `application/json`",
+    ),
+    responses(
+        (status = 200, description = "What was, or with `dry_run` would be, created or updated", body = SeedReport),
+        (status = 400, description = "Unreadable or invalid bundle; `details` names each problem", body = ErrorResponse),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `content:write` permission", body = ErrorResponse),
+        (status = 409, description = "A new deck or roadmap clashes with existing content", body = ErrorResponse),
+    )
+)]
+async fn seed_content(
+    _access: RequirePermission<ContentWrite>,
+    State(state): State<ApiState>,
+    Query(query): Query<SeedQuery>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Json<SeedReport>, ApiError> {
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+    let bundle = seed::parse(&body, content_type)?;
+    let report = seed::seed(&state.pool, &state.events, &bundle, query.dry_run).await?;
+    Ok(Json(report))
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+struct SlugRequest {
+    /// Lowercase letters and digits separated by single dashes, at most 100 characters
+    #[validate(custom(function = "slugs::slug"))]
+    #[schema(example = "spanish-basics")]
+    slug: String,
+}
+
+#[derive(Serialize, ToSchema)]
+struct SlugChanged {
+    id: Uuid,
+    slug: String,
+}
+
+/// Rename the slug a roadmap is shared by; links using the old slug stop working
+#[utoipa::path(
+    put,
+    path = "/v1/admin/roadmaps/{roadmap_id}/slug",
+    tag = "admin",
+    security(("cookie_auth" = ["content:write"]), ("admin_token" = [])),
+    params(("roadmap_id" = Uuid, Path, description = "Roadmap ID")),
+    request_body = SlugRequest,
+    responses(
+        (status = 200, description = "Slug changed", body = SlugChanged),
+        (status = 400, description = "Not a valid slug", body = ErrorResponse),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `content:write` permission", body = ErrorResponse),
+        (status = 404, description = "Roadmap not found", body = ErrorResponse),
+        (status = 409, description = "Another roadmap has the slug", body = ErrorResponse),
+    )
+)]
+async fn set_roadmap_slug(
+    _access: RequirePermission<ContentWrite>,
+    State(state): State<ApiState>,
+    Path(roadmap_id): Path<Uuid>,
+    ValidJson(request): ValidJson<SlugRequest>,
+) -> Result<Json<SlugChanged>, ApiError> {
+    let found = content_repo::set_roadmap_slug(&state.pool, roadmap_id, &request.slug)
+        .await
+        .map_err(|e| slug_error(e, "roadmap", &request.slug))?;
+    if !found {
+        return Err(ApiError::NotFound("Roadmap not found".to_string()));
+    }
+    tracing::info!(%roadmap_id, slug = %request.slug, "Roadmap slug changed");
+    Ok(Json(SlugChanged {
+        id: roadmap_id,
+        slug: request.slug,
+    }))
+}
+
+/// Rename the slug a deck is shared by; links using the old slug stop working
+#[utoipa::path(
+    put,
+    path = "/v1/admin/decks/{deck_id}/slug",
+    tag = "admin",
+    security(("cookie_auth" = ["content:write"]), ("admin_token" = [])),
+    params(("deck_id" = Uuid, Path, description = "Deck ID")),
+    request_body = SlugRequest,
+    responses(
+        (status = 200, description = "Slug changed", body = SlugChanged),
+        (status = 400, description = "Not a valid slug", body = ErrorResponse),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `content:write` permission", body = ErrorResponse),
+        (status = 404, description = "Deck not found", body = ErrorResponse),
+        (status = 409, description = "Another deck has the slug", body = ErrorResponse),
+    )
+)]
+async fn set_deck_slug(
+    _access: RequirePermission<ContentWrite>,
+    State(state): State<ApiState>,
+    Path(deck_id): Path<Uuid>,
+    ValidJson(request): ValidJson<SlugRequest>,
+) -> Result<Json<SlugChanged>, ApiError> {
+    let found = content_repo::set_deck_slug(&state.pool, deck_id, &request.slug)
+        .await
+        .map_err(|e| slug_error(e, "deck", &request.slug))?;
+    if !found {
+        return Err(ApiError::NotFound("Deck not found".to_string()));
+    }
+    tracing::info!(%deck_id, slug = %request.slug, "Deck slug changed");
+    Ok(Json(SlugChanged {
+        id: deck_id,
+        slug: request.slug,
+    }))
+}
+
+fn slug_error(error: sqlx::Error, kind: &str, slug: &str) -> ApiError {
+    match &error {
+        sqlx::Error::Database(e) if e.is_unique_violation() => {
+            ApiError::Conflict(format!("Another {kind} already has the slug `{slug}`"))
+        }
+        _ => error.into(),
+    }
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+struct MoveCardsRequest {
+    /// Cards of the deck to move, at most 1000
+    #[validate(length(
+        min = 1,
+        max = MAX_MOVED_CARDS,
+        message = "Between 1 and 1000 cards can be moved at once"
+    ))]
+    flashcard_ids: Vec<Uuid>,
+    /// Deck the cards move to; it must have the same language pair
+    to_deck_id: Uuid,
+}
+
+/// Move cards from one deck to another of the same language pair
+///
+/// Learners keep their progress on the cards; both decks' stored progress is
+/// refreshed.
+#[utoipa::path(
+    post,
+    path = "/v1/admin/decks/{deck_id}/cards/move",
+    tag = "admin",
+    security(("cookie_auth" = ["content:write"]), ("admin_token" = [])),
+    params(("deck_id" = Uuid, Path, description = "Deck the cards are in")),
+    request_body = MoveCardsRequest,
+    responses(
+        (status = 200, description = "Cards moved", body = MoveSummary),
+        (status = 400, description = "Same deck, no cards, or different language pairs", body = ErrorResponse),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `content:write` permission", body = ErrorResponse),
+        (status = 404, description = "A deck was not found or belongs to another organization, or a card is not in the deck", body = ErrorResponse),
+    )
+)]
+async fn move_cards(
+    access: RequirePermission<ContentWrite>,
+    State(state): State<ApiState>,
+    Path(deck_id): Path<Uuid>,
+    ValidJson(request): ValidJson<MoveCardsRequest>,
+) -> Result<Json<MoveSummary>, ApiError> {
+    cards::check_decks_editable(
+        &state.pool,
+        &access.principal,
+        &[deck_id, request.to_deck_id],
+    )
+    .await?;
+    let summary = cards::move_cards(
+        &state.pool,
+        &state.events,
+        deck_id,
+        request.to_deck_id,
+        &request.flashcard_ids,
+    )
+    .await?;
+    Ok(Json(summary))
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+struct MergeFlashcardRequest {
+    /// Card kept; the card in the path is merged into it and deleted
+    into: Uuid,
+}
+
+/// Merge a duplicate card into another card
+///
+/// The kept card also accepts the merged card's translation and accepted
+/// answers, replaces it in every deck, and takes over learners' progress,
+/// review history and reports. Learners who studied both cards keep one
+/// combined progress record.
+#[utoipa::path(
+    post,
+    path = "/v1/admin/flashcards/{flashcard_id}/merge",
+    tag = "admin",
+    security(("cookie_auth" = ["content:write"]), ("admin_token" = [])),
+    params(("flashcard_id" = Uuid, Path, description = "Card merged and deleted")),
+    request_body = MergeFlashcardRequest,
+    responses(
+        (status = 200, description = "Cards merged", body = MergeSummary),
+        (status = 400, description = "Same card, or different language pairs", body = ErrorResponse),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `content:write` permission", body = ErrorResponse),
+        (status = 404, description = "Card not found, or in a deck of another organization", body = ErrorResponse),
+    )
+)]
+async fn merge_flashcard(
+    access: RequirePermission<ContentWrite>,
+    State(state): State<ApiState>,
+    Path(flashcard_id): Path<Uuid>,
+    ValidJson(request): ValidJson<MergeFlashcardRequest>,
+) -> Result<Json<MergeSummary>, ApiError> {
+    let summary = cards::merge_cards(
+        &state.pool,
+        &state.events,
+        &access.principal,
+        flashcard_id,
+        request.into,
+    )
+    .await?;
+    Ok(Json(summary))
+}
+
+#[derive(Serialize, ToSchema)]
+struct AdminUserView {
+    #[serde(flatten)]
+    user: AdminUserSummary,
+    /// Why email to the user's address stopped, if it did
+    email_suppression: Option<EmailSuppression>,
+    /// Earlier usernames, newest change first
+    username_history: Vec<UsernameChange>,
+}
+
+/// Account details, email deliverability and username history of a user, for support
+#[utoipa::path(
+    get,
+    path = "/v1/admin/users/{user_id}",
+    tag = "admin",
+    security(("cookie_auth" = ["admin:maintenance"]), ("admin_token" = [])),
+    params(("user_id" = Uuid, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "The user", body = AdminUserView),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `admin:maintenance` permission", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
+async fn get_user(
+    _access: RequirePermission<AdminMaintenance>,
+    State(state): State<ApiState>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<AdminUserView>, ApiError> {
+    let user = user_repo::find_admin_summary(&state.pool, user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+    let email_suppression = suppression_repo::find(&state.pool, &user.email).await?;
+    let username_history = user_repo::list_username_history(&state.pool, user_id).await?;
+
+    Ok(Json(AdminUserView {
+        user,
+        email_suppression,
+        username_history,
+    }))
+}
+
+/// Resume email to a user whose address was suppressed, e.g. after they fixed their mailbox
+#[utoipa::path(
+    delete,
+    path = "/v1/admin/users/{user_id}/email-suppression",
+    tag = "admin",
+    security(("cookie_auth" = ["admin:maintenance"]), ("admin_token" = [])),
+    params(("user_id" = Uuid, Path, description = "User ID")),
+    responses(
+        (status = 204, description = "Suppression cleared"),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `admin:maintenance` permission", body = ErrorResponse),
+        (status = 404, description = "User not found or address not suppressed", body = ErrorResponse),
+    )
+)]
+async fn clear_email_suppression(
+    _access: RequirePermission<AdminMaintenance>,
+    State(state): State<ApiState>,
+    Path(user_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    let user = user_repo::find_admin_summary(&state.pool, user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    if !suppression_repo::delete(&state.pool, &user.email).await? {
+        return Err(ApiError::NotFound(
+            "Email address is not suppressed".to_string(),
+        ));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Restore a deleted account during its grace period, e.g. for a Google account
+///
+/// Email accounts can restore themselves with `POST /v1/users/restore`.
+#[utoipa::path(
+    post,
+    path = "/v1/admin/users/{user_id}/restore",
+    tag = "admin",
+    security(("cookie_auth" = ["admin:maintenance"]), ("admin_token" = [])),
+    params(("user_id" = Uuid, Path, description = "User ID")),
+    responses(
+        (status = 204, description = "Account restored"),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `admin:maintenance` permission", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+        (status = 409, description = "The account is not deleted, or its grace period is over", body = ErrorResponse),
+    )
+)]
+async fn restore_user(
+    _access: RequirePermission<AdminMaintenance>,
+    State(state): State<ApiState>,
+    Path(user_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    user_repo::find_admin_summary(&state.pool, user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    if !user_repo::restore_user(&state.pool, user_id, ACCOUNT_DELETION_GRACE_DAYS).await? {
+        return Err(ApiError::Conflict(
+            "This account is not deleted or can no longer be restored".to_string(),
+        ));
+    }
+    tracing::info!(%user_id, "Account restored by an admin");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Serialize, ToSchema)]
+struct DeckProgressRecomputed {
+    /// Decks whose stored progress had drifted and was rewritten
+    corrected: u64,
+}
+
+/// Recompute a user's stored deck progress from their card progress
+///
+/// The nightly consistency job does the same for every user.
+#[utoipa::path(
+    post,
+    path = "/v1/admin/users/{user_id}/deck-progress/recompute",
+    tag = "admin",
+    security(("cookie_auth" = ["admin:maintenance"]), ("admin_token" = [])),
+    params(("user_id" = Uuid, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "Progress recomputed", body = DeckProgressRecomputed),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `admin:maintenance` permission", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
+async fn recompute_deck_progress(
+    _access: RequirePermission<AdminMaintenance>,
+    State(state): State<ApiState>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<DeckProgressRecomputed>, ApiError> {
+    user_repo::find_admin_summary(&state.pool, user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    let corrected = practice_repo::recompute_deck_progress(
+        &state.pool,
+        Some(user_id),
+        mms_srs::MASTERY_THRESHOLD,
+    )
+    .await?;
+    if corrected > 0 {
+        tracing::warn!(%user_id, corrected, "Corrected drifted deck progress");
+    }
+    Ok(Json(DeckProgressRecomputed { corrected }))
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+struct UpdateApiKeyRequest {
+    /// Requests allowed per UTC day, 1 to 1,000,000
+    #[validate(range(
+        min = 1,
+        max = MAX_DAILY_QUOTA,
+        message = "Daily quota must be between 1 and 1000000"
+    ))]
+    daily_quota: i32,
+}
+
+/// Change the daily quota of a public API key, e.g. for an integrator who needs more
+#[utoipa::path(
+    patch,
+    path = "/v1/admin/api-keys/{api_key_id}",
+    tag = "admin",
+    security(("cookie_auth" = ["admin:maintenance"]), ("admin_token" = [])),
+    params(("api_key_id" = Uuid, Path, description = "API key ID")),
+    request_body = UpdateApiKeyRequest,
+    responses(
+        (status = 200, description = "Quota changed; it applies to today's count too", body = ApiKey),
+        (status = 400, description = "Quota out of range", body = ErrorResponse),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `admin:maintenance` permission", body = ErrorResponse),
+        (status = 404, description = "API key not found", body = ErrorResponse),
+    )
+)]
+async fn update_api_key(
+    _access: RequirePermission<AdminMaintenance>,
+    State(state): State<ApiState>,
+    Path(api_key_id): Path<Uuid>,
+    ValidJson(request): ValidJson<UpdateApiKeyRequest>,
+) -> Result<Json<ApiKey>, ApiError> {
+    let api_key = api_key_repo::set_daily_quota(&state.pool, api_key_id, request.daily_quota)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("API key not found".to_string()))?;
+    tracing::info!(%api_key_id, daily_quota = request.daily_quota, "API key quota changed");
+    Ok(Json(api_key))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ClientErrorQuery {
+    /// Only reports tagged with this `X-Request-ID`
+    #[serde(default)]
+    request_id: Option<String>,
+    /// Reports to return, newest first (default: 50, at most 200)
+    #[serde(default)]
+    limit: Option<i64>,
+}
+
+/// Recent crash reports from the apps, e.g. those tagged with a request id found in the server logs
+#[utoipa::path(
+    get,
+    path = "/v1/admin/client-errors",
+    tag = "admin",
+    security(("cookie_auth" = ["admin:maintenance"]), ("admin_token" = [])),
+    params(ClientErrorQuery),
+    responses(
+        (status = 200, description = "Reports, newest first", body = Vec<ClientError>),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `admin:maintenance` permission", body = ErrorResponse),
+    )
+)]
+async fn list_client_errors(
+    _access: RequirePermission<AdminMaintenance>,
+    State(state): State<ApiState>,
+    Query(query): Query<ClientErrorQuery>,
+) -> Result<Json<Vec<ClientError>>, ApiError> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_CLIENT_ERROR_LIMIT)
+        .clamp(1, MAX_CLIENT_ERROR_LIMIT);
+    let reports =
+        client_error_repo::list_recent(&state.pool, query.request_id.as_deref(), limit).await?;
+    Ok(Json(reports))
+}
+
+/// Job state an operator can list
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+enum JobStatus {
+    Pending,
+    Running,
+    Succeeded,
+    /// Given up on after the last attempt
+    #[default]
+    Dead,
+}
+
+impl JobStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            JobStatus::Pending => "pending",
+            JobStatus::Running => "running",
+            JobStatus::Succeeded => "succeeded",
+            JobStatus::Dead => "dead",
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct JobQuery {
+    /// Jobs in this state (default: `dead`)
+    #[serde(default)]
+    #[param(inline)]
+    status: JobStatus,
+    /// Jobs to return, newest first (default: 50, at most 200)
+    #[serde(default)]
+    limit: Option<i64>,
+}
+
+/// Queued jobs by state; dead jobs by default, with the error of their last run
+#[utoipa::path(
+    get,
+    path = "/v1/admin/jobs",
+    tag = "admin",
+    security(("cookie_auth" = ["admin:maintenance"]), ("admin_token" = [])),
+    params(JobQuery),
+    responses(
+        (status = 200, description = "Jobs, newest first", body = Vec<JobSummary>),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `admin:maintenance` permission", body = ErrorResponse),
+    )
+)]
+async fn list_jobs(
+    _access: RequirePermission<AdminMaintenance>,
+    State(state): State<ApiState>,
+    Query(query): Query<JobQuery>,
+) -> Result<Json<Vec<JobSummary>>, ApiError> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_JOB_LIMIT)
+        .clamp(1, MAX_JOB_LIMIT);
+    let jobs = job_repo::list_by_status(&state.pool, query.status.as_str(), limit).await?;
+    Ok(Json(jobs))
+}
+
+/// Run a dead job again, with a fresh set of attempts
+#[utoipa::path(
+    post,
+    path = "/v1/admin/jobs/{job_id}/retry",
+    tag = "admin",
+    security(("cookie_auth" = ["admin:maintenance"]), ("admin_token" = [])),
+    params(("job_id" = Uuid, Path, description = "Job ID")),
+    responses(
+        (status = 204, description = "Job queued again"),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `admin:maintenance` permission", body = ErrorResponse),
+        (status = 404, description = "No dead job with this ID", body = ErrorResponse),
+    )
+)]
+async fn retry_job(
+    _access: RequirePermission<AdminMaintenance>,
+    State(state): State<ApiState>,
+    Path(job_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    if !job_repo::retry_dead(&state.pool, job_id).await? {
+        return Err(ApiError::NotFound("Dead job not found".to_string()));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The cron schedules of the queued maintenance jobs and when each runs next
+#[utoipa::path(
+    get,
+    path = "/v1/admin/schedules",
+    tag = "admin",
+    security(("cookie_auth" = ["admin:maintenance"]), ("admin_token" = [])),
+    responses(
+        (status = 200, description = "Schedules", body = Vec<ScheduleView>),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `admin:maintenance` permission", body = ErrorResponse),
+    )
+)]
+async fn list_schedules(
+    _access: RequirePermission<AdminMaintenance>,
+    State(state): State<ApiState>,
+) -> Json<Vec<ScheduleView>> {
+    let now = Utc::now();
+    Json(
+        state
+            .schedules
+            .jobs
+            .iter()
+            .map(|job| job.view(now))
+            .collect(),
+    )
+}
+
+#[derive(Serialize, ToSchema)]
+struct QueuedJob {
+    job_id: Uuid,
+}
+
+/// Queue a scheduled maintenance job now, outside its schedule
+#[utoipa::path(
+    post,
+    path = "/v1/admin/schedules/{job}/run",
+    tag = "admin",
+    security(("cookie_auth" = ["admin:maintenance"]), ("admin_token" = [])),
+    params(("job" = String, Path, description = "Job kind, e.g. `token_cleanup`")),
+    responses(
+        (status = 202, description = "Job queued", body = QueuedJob),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `admin:maintenance` permission", body = ErrorResponse),
+        (status = 404, description = "No scheduled job of this kind", body = ErrorResponse),
+        (status = 409, description = "The job is already waiting or running", body = ErrorResponse),
+    )
+)]
+async fn run_scheduled_job(
+    _access: RequirePermission<AdminMaintenance>,
+    State(state): State<ApiState>,
+    Path(job): Path<String>,
+) -> Result<(StatusCode, Json<QueuedJob>), ApiError> {
+    let scheduled = state
+        .schedules
+        .find(&job)
+        .ok_or_else(|| ApiError::NotFound("Scheduled job not found".to_string()))?;
+
+    let job_id = queue::enqueue(&state.pool, &scheduled.job)
+        .await?
+        .ok_or_else(|| ApiError::Conflict("Job is already waiting or running".to_string()))?;
+    tracing::info!(kind = scheduled.job.kind(), %job_id, "Scheduled job queued by hand");
+    Ok((StatusCode::ACCEPTED, Json(QueuedJob { job_id })))
+}
@@ -0,0 +1,651 @@
+//! Declarative content seeding from YAML or JSON bundles.
+//!
+//! A bundle lists decks with their cards and roadmaps with their nodes, each
+//! deck and roadmap named by a stable slug. Loading a bundle creates what is
+//! missing and updates what changed, so the same bundle can be loaded again
+//! after every edit. Decks and roadmaps are matched by slug, nodes by the deck
+//! they show within their roadmap, and cards by term and translation within
+//! their deck. Nothing missing from the bundle is removed.
+//!
+//! The whole bundle is checked before anything is written, then applied in
+//! one transaction. A dry run applies it and rolls back, so its report is
+//! exactly what loading the bundle would change.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use mms_db::repositories::content as content_repo;
+
+use crate::{
+    error::{ApiError, FieldError},
+    known_words::CefrLevel,
+    live::{EventBus, LiveEvent},
+    slugs, validation,
+};
+
+/// Cards accepted in one bundle, across all decks
+pub const MAX_CARDS: usize = 50_000;
+
+/// Problems reported for an invalid bundle; the rest are dropped
+const MAX_REPORTED_ERRORS: usize = 100;
+
+/// Decks and roadmaps to load
+#[derive(Debug, Default, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SeedBundle {
+    #[serde(default)]
+    pub decks: Vec<SeedDeck>,
+    #[serde(default)]
+    pub roadmaps: Vec<SeedRoadmap>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SeedDeck {
+    /// Lowercase letters, digits and single dashes, e.g. `spanish-basics`
+    pub slug: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub language_from: String,
+    pub language_to: String,
+    #[serde(default)]
+    pub cefr_level: Option<CefrLevel>,
+    /// Created in the deck's language pair; identical cards are shared between decks
+    #[serde(default)]
+    pub cards: Vec<SeedCard>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SeedCard {
+    pub term: String,
+    pub translation: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SeedRoadmap {
+    /// Lowercase letters, digits and single dashes, e.g. `spanish-a1`
+    pub slug: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub language_from: String,
+    pub language_to: String,
+    /// Parents before their children
+    #[serde(default)]
+    pub nodes: Vec<SeedNode>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SeedNode {
+    /// Slug of the deck shown, from this bundle or already loaded; at most one node per deck
+    pub deck: String,
+    /// Deck slug of the parent node, listed earlier in the same roadmap
+    #[serde(default)]
+    pub parent: Option<String>,
+    #[serde(default)]
+    pub pos_x: i32,
+    #[serde(default)]
+    pub pos_y: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SeedAction {
+    Created,
+    Updated,
+    Unchanged,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeckChange {
+    pub slug: String,
+    /// `None` for a deck a dry run would create
+    pub id: Option<Uuid>,
+    /// What happens to the deck's own fields
+    pub action: SeedAction,
+    /// Fields that differ from the stored deck
+    pub changed: Vec<&'static str>,
+    /// Cards the deck does not have yet
+    pub cards_added: u64,
+    pub cards_unchanged: u64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RoadmapChange {
+    pub slug: String,
+    /// `None` for a roadmap a dry run would create
+    pub id: Option<Uuid>,
+    /// What happens to the roadmap's own fields
+    pub action: SeedAction,
+    /// Fields that differ from the stored roadmap
+    pub changed: Vec<&'static str>,
+    pub nodes_added: u64,
+    /// Nodes moved or given another parent
+    pub nodes_updated: u64,
+    pub nodes_unchanged: u64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SeedReport {
+    /// Whether the changes were rolled back
+    pub dry_run: bool,
+    pub decks: Vec<DeckChange>,
+    pub roadmaps: Vec<RoadmapChange>,
+}
+
+/// Read a bundle, as JSON when `content_type` says so and as YAML otherwise,
+/// and check it; text fields come back trimmed and language codes lowercased
+pub fn parse(body: &str, content_type: Option<&str>) -> Result<SeedBundle, ApiError> {
+    let json = content_type.is_some_and(|content_type| content_type.contains("json"));
+    let mut bundle: SeedBundle = if json {
+        serde_json::from_str(body)
+            .map_err(|e| ApiError::Validation(format!("Invalid bundle: {e}")))?
+    } else {
+        serde_yaml::from_str(body)
+            .map_err(|e| ApiError::Validation(format!("Invalid bundle: {e}")))?
+    };
+    validate(&mut bundle)?;
+    Ok(bundle)
+}
+
+/// Problems found in a bundle, each tied to the path of the value concerned
+#[derive(Default)]
+struct Problems(Vec<FieldError>);
+
+impl Problems {
+    fn add(&mut self, path: String, message: impl Into<String>) {
+        self.0.push(FieldError::new(path, message));
+    }
+
+    fn check_slug(&mut self, path: String, slug: &str) {
+        if let Err(error) = slugs::slug(slug) {
+            self.add(path, error.message.unwrap_or_default());
+        }
+    }
+
+    fn require_text(&mut self, path: String, value: &mut String) {
+        let trimmed = value.trim();
+        if trimmed.is_empty() {
+            self.add(path, "Cannot be empty");
+        } else if trimmed.len() != value.len() {
+            *value = trimmed.to_string();
+        }
+    }
+
+    fn check_language(&mut self, path: String, code: &mut String) {
+        match validation::language_code(code) {
+            Ok(()) => *code = code.to_lowercase(),
+            Err(error) => self.add(path, error.message.unwrap_or_default()),
+        }
+    }
+
+    fn into_result(mut self) -> Result<(), ApiError> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+        self.0.truncate(MAX_REPORTED_ERRORS);
+        Err(ApiError::InvalidFields(self.0))
+    }
+}
+
+/// Blank descriptions are no description
+fn trim_description(description: &mut Option<String>) {
+    *description = description
+        .take()
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty());
+}
+
+fn validate(bundle: &mut SeedBundle) -> Result<(), ApiError> {
+    let mut problems = Problems::default();
+
+    let cards: usize = bundle.decks.iter().map(|deck| deck.cards.len()).sum();
+    if cards > MAX_CARDS {
+        problems.add(
+            "decks".to_string(),
+            format!("At most {MAX_CARDS} cards can be loaded at once"),
+        );
+    }
+
+    let mut deck_slugs = HashSet::new();
+    for (d, deck) in bundle.decks.iter_mut().enumerate() {
+        problems.check_slug(format!("decks[{d}].slug"), &deck.slug);
+        if !deck_slugs.insert(deck.slug.clone()) {
+            problems.add(format!("decks[{d}].slug"), "Listed more than once");
+        }
+        problems.require_text(format!("decks[{d}].title"), &mut deck.title);
+        trim_description(&mut deck.description);
+        problems.check_language(format!("decks[{d}].language_from"), &mut deck.language_from);
+        problems.check_language(format!("decks[{d}].language_to"), &mut deck.language_to);
+
+        let mut seen = HashSet::new();
+        for (c, card) in deck.cards.iter_mut().enumerate() {
+            problems.require_text(format!("decks[{d}].cards[{c}].term"), &mut card.term);
+            problems.require_text(
+                format!("decks[{d}].cards[{c}].translation"),
+                &mut card.translation,
+            );
+            if !seen.insert((card.term.clone(), card.translation.clone())) {
+                problems.add(format!("decks[{d}].cards[{c}]"), "Listed more than once");
+            }
+        }
+    }
+
+    let mut roadmap_slugs = HashSet::new();
+    for (r, roadmap) in bundle.roadmaps.iter_mut().enumerate() {
+        problems.check_slug(format!("roadmaps[{r}].slug"), &roadmap.slug);
+        if !roadmap_slugs.insert(roadmap.slug.clone()) {
+            problems.add(format!("roadmaps[{r}].slug"), "Listed more than once");
+        }
+        problems.require_text(format!("roadmaps[{r}].title"), &mut roadmap.title);
+        trim_description(&mut roadmap.description);
+        problems.check_language(
+            format!("roadmaps[{r}].language_from"),
+            &mut roadmap.language_from,
+        );
+        problems.check_language(
+            format!("roadmaps[{r}].language_to"),
+            &mut roadmap.language_to,
+        );
+
+        let mut placed = HashSet::new();
+        for (n, node) in roadmap.nodes.iter().enumerate() {
+            problems.check_slug(format!("roadmaps[{r}].nodes[{n}].deck"), &node.deck);
+            if let Some(parent) = &node.parent
+                && !placed.contains(parent.as_str())
+            {
+                problems.add(
+                    format!("roadmaps[{r}].nodes[{n}].parent"),
+                    format!("No earlier node of this roadmap shows deck `{parent}`"),
+                );
+            }
+            if !placed.insert(node.deck.as_str()) {
+                problems.add(
+                    format!("roadmaps[{r}].nodes[{n}].deck"),
+                    "The roadmap already has a node for this deck",
+                );
+            }
+        }
+    }
+
+    problems.into_result()
+}
+
+/// Load a checked bundle, or report what loading it would change when `dry_run`
+pub async fn seed(
+    pool: &PgPool,
+    events: &EventBus,
+    bundle: &SeedBundle,
+    dry_run: bool,
+) -> Result<SeedReport, ApiError> {
+    let mut tx = pool.begin().await?;
+
+    // Decks the roadmaps show without the bundle listing them must already be loaded
+    let mut deck_ids = HashMap::new();
+    let listed: HashSet<&str> = bundle.decks.iter().map(|deck| deck.slug.as_str()).collect();
+    let mut problems = Problems::default();
+    for (r, roadmap) in bundle.roadmaps.iter().enumerate() {
+        for (n, node) in roadmap.nodes.iter().enumerate() {
+            let slug = node.deck.as_str();
+            if listed.contains(slug) || deck_ids.contains_key(slug) {
+                continue;
+            }
+            match content_repo::find_deck_by_slug(&mut *tx, slug).await? {
+                Some(deck) => {
+                    deck_ids.insert(slug, deck.id);
+                }
+                None => problems.add(
+                    format!("roadmaps[{r}].nodes[{n}].deck"),
+                    format!("Unknown deck `{slug}`"),
+                ),
+            }
+        }
+    }
+    problems.into_result()?;
+
+    let mut report = SeedReport {
+        dry_run,
+        decks: Vec::with_capacity(bundle.decks.len()),
+        roadmaps: Vec::with_capacity(bundle.roadmaps.len()),
+    };
+    for deck in &bundle.decks {
+        let (id, change) = apply_deck(&mut tx, deck)
+            .await
+            .map_err(|e| write_error(e, "Deck", &deck.slug))?;
+        deck_ids.insert(deck.slug.as_str(), id);
+        report.decks.push(change);
+    }
+    for roadmap in &bundle.roadmaps {
+        let change = apply_roadmap(&mut tx, roadmap, &deck_ids)
+            .await
+            .map_err(|e| write_error(e, "Roadmap", &roadmap.slug))?;
+        report.roadmaps.push(change);
+    }
+
+    if dry_run {
+        tx.rollback().await?;
+        for change in &mut report.decks {
+            change.id = change.id.filter(|_| change.action != SeedAction::Created);
+        }
+        for change in &mut report.roadmaps {
+            change.id = change.id.filter(|_| change.action != SeedAction::Created);
+        }
+        return Ok(report);
+    }
+
+    tx.commit().await?;
+    for change in &report.decks {
+        if let Some(deck_id) = change.id
+            && (change.action != SeedAction::Unchanged || change.cards_added > 0)
+        {
+            events.broadcast(LiveEvent::DeckUpdated { deck_id });
+        }
+    }
+
+    tracing::info!(
+        decks = report.decks.len(),
+        roadmaps = report.roadmaps.len(),
+        cards_added = report.decks.iter().map(|d| d.cards_added).sum::<u64>(),
+        "Content seeded"
+    );
+
+    Ok(report)
+}
+
+/// Another row already holding a slug or title is the bundle's to fix
+fn write_error(error: sqlx::Error, kind: &str, slug: &str) -> ApiError {
+    match &error {
+        sqlx::Error::Database(e) if e.is_unique_violation() => ApiError::Conflict(format!(
+            "{kind} `{slug}` clashes with existing content: {}",
+            e.message()
+        )),
+        _ => error.into(),
+    }
+}
+
+/// Names of the fields whose stored and bundled values differ
+fn changed_fields<const N: usize>(fields: [(&'static str, bool); N]) -> Vec<&'static str> {
+    fields
+        .into_iter()
+        .filter(|(_, same)| !same)
+        .map(|(name, _)| name)
+        .collect()
+}
+
+async fn apply_deck(
+    conn: &mut PgConnection,
+    deck: &SeedDeck,
+) -> Result<(Uuid, DeckChange), sqlx::Error> {
+    let cefr_level = deck.cefr_level.map(CefrLevel::as_str);
+    let (id, action, changed) =
+        match content_repo::find_deck_by_slug(&mut *conn, &deck.slug).await? {
+            None => {
+                let id = content_repo::create_deck(
+                    &mut *conn,
+                    &deck.slug,
+                    &deck.title,
+                    deck.description.as_deref(),
+                    &deck.language_from,
+                    &deck.language_to,
+                    cefr_level,
+                )
+                .await?;
+                (id, SeedAction::Created, Vec::new())
+            }
+            Some(stored) => {
+                let changed = changed_fields([
+                    ("title", stored.title == deck.title),
+                    ("description", stored.description == deck.description),
+                    ("language_from", stored.language_from == deck.language_from),
+                    ("language_to", stored.language_to == deck.language_to),
+                    ("cefr_level", stored.cefr_level.as_deref() == cefr_level),
+                ]);
+                if changed.is_empty() {
+                    (stored.id, SeedAction::Unchanged, changed)
+                } else {
+                    content_repo::upsert_deck(
+                        &mut *conn,
+                        stored.id,
+                        &deck.title,
+                        deck.description.as_deref(),
+                        &deck.language_from,
+                        &deck.language_to,
+                        cefr_level,
+                    )
+                    .await?;
+                    (stored.id, SeedAction::Updated, changed)
+                }
+            }
+        };
+
+    let mut cards_added = 0;
+    for card in &deck.cards {
+        if content_repo::link_deck_flashcard(&mut *conn, id, &card.term, &card.translation).await? {
+            cards_added += 1;
+        }
+    }
+
+    Ok((
+        id,
+        DeckChange {
+            slug: deck.slug.clone(),
+            id: Some(id),
+            action,
+            changed,
+            cards_added,
+            cards_unchanged: deck.cards.len() as u64 - cards_added,
+        },
+    ))
+}
+
+async fn apply_roadmap(
+    conn: &mut PgConnection,
+    roadmap: &SeedRoadmap,
+    deck_ids: &HashMap<&str, Uuid>,
+) -> Result<RoadmapChange, sqlx::Error> {
+    let (id, action, changed) =
+        match content_repo::find_roadmap_by_slug(&mut *conn, &roadmap.slug).await? {
+            None => {
+                let id = content_repo::create_roadmap(
+                    &mut *conn,
+                    &roadmap.slug,
+                    &roadmap.title,
+                    roadmap.description.as_deref(),
+                    &roadmap.language_from,
+                    &roadmap.language_to,
+                )
+                .await?;
+                (id, SeedAction::Created, Vec::new())
+            }
+            Some(stored) => {
+                let changed = changed_fields([
+                    ("title", stored.title == roadmap.title),
+                    ("description", stored.description == roadmap.description),
+                    (
+                        "language_from",
+                        stored.language_from == roadmap.language_from,
+                    ),
+                    ("language_to", stored.language_to == roadmap.language_to),
+                ]);
+                if changed.is_empty() {
+                    (stored.id, SeedAction::Unchanged, changed)
+                } else {
+                    content_repo::upsert_roadmap(
+                        &mut *conn,
+                        stored.id,
+                        &roadmap.title,
+                        roadmap.description.as_deref(),
+                        &roadmap.language_from,
+                        &roadmap.language_to,
+                    )
+                    .await?;
+                    (stored.id, SeedAction::Updated, changed)
+                }
+            }
+        };
+
+    let mut change = RoadmapChange {
+        slug: roadmap.slug.clone(),
+        id: Some(id),
+        action,
+        changed,
+        nodes_added: 0,
+        nodes_updated: 0,
+        nodes_unchanged: 0,
+    };
+    // Node ids by the slug of the deck they show
+    let mut node_ids: HashMap<&str, Uuid> = HashMap::new();
+    for node in &roadmap.nodes {
+        // Checked before anything was written
+        let deck_id = deck_ids[node.deck.as_str()];
+        let parent_node_id = node.parent.as_deref().map(|parent| node_ids[parent]);
+
+        let stored = content_repo::find_roadmap_node(&mut *conn, id, deck_id).await?;
+        let node_id = match &stored {
+            Some(stored)
+                if stored.parent_node_id == parent_node_id
+                    && (stored.pos_x, stored.pos_y) == (node.pos_x, node.pos_y) =>
+            {
+                change.nodes_unchanged += 1;
+                stored.id
+            }
+            _ => {
+                let node_id = stored
+                    .as_ref()
+                    .map_or_else(Uuid::new_v4, |stored| stored.id);
+                content_repo::upsert_roadmap_node(
+                    &mut *conn,
+                    node_id,
+                    id,
+                    deck_id,
+                    parent_node_id,
+                    node.pos_x,
+                    node.pos_y,
+                )
+                .await?;
+                if stored.is_some() {
+                    change.nodes_updated += 1;
+                } else {
+                    change.nodes_added += 1;
+                }
+                node_id
+            }
+        };
+        node_ids.insert(node.deck.as_str(), node_id);
+    }
+
+    Ok(change)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUNDLE: &str = r#"
+decks:
+  - slug: spanish-basics
+    title: " Basics "
+    language_from: EN
+    language_to: es
+    cefr_level: A1
+    cards:
+      - { term: cat, translation: gato }
+      - { term: dog, translation: perro }
+roadmaps:
+  - slug: spanish-a1
+    title: Spanish A1
+    description: "  "
+    language_from: en
+    language_to: es
+    nodes:
+      - deck: spanish-basics
+      - { deck: spanish-food, parent: spanish-basics, pos_x: 1 }
+"#;
+
+    fn field_errors(error: ApiError) -> Vec<String> {
+        let ApiError::InvalidFields(fields) = error else {
+            panic!("Expected field errors, got {error:?}");
+        };
+        fields.into_iter().map(|field| field.field).collect()
+    }
+
+    #[test]
+    fn test_parse_yaml_bundle_normalizes_fields() {
+        let bundle = parse(BUNDLE, Some("application/yaml")).unwrap();
+
+        let deck = &bundle.decks[0];
+        assert_eq!(deck.title, "Basics");
+        assert_eq!(deck.language_from, "en");
+        assert_eq!(deck.cefr_level, Some(CefrLevel::A1));
+        assert_eq!(deck.cards.len(), 2);
+        let roadmap = &bundle.roadmaps[0];
+        assert_eq!(roadmap.description, None);
+        assert_eq!(roadmap.nodes[1].parent.as_deref(), Some("spanish-basics"));
+        assert_eq!(roadmap.nodes[1].pos_x, 1);
+    }
+
+    #[test]
+    fn test_parse_json_bundle() {
+        let bundle = parse(
+            r#"{"decks":[{"slug":"d1","title":"D","language_from":"en","language_to":"fr"}]}"#,
+            Some("application/json; charset=utf-8"),
+        )
+        .unwrap();
+        assert_eq!(bundle.decks[0].slug, "d1");
+        assert!(bundle.roadmaps.is_empty());
+
+        assert!(matches!(
+            parse(r#"{"decks":[],"lessons":[]}"#, Some("application/json")),
+            Err(ApiError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_bundle_reports_every_problem() {
+        let error = parse(
+            r#"
+decks:
+  - { slug: Basics, title: B, language_from: en, language_to: xx }
+  - slug: food
+    title: " "
+    language_from: en
+    language_to: es
+    cards:
+      - { term: apple, translation: manzana }
+      - { term: " apple", translation: manzana }
+  - { slug: food, title: F, language_from: en, language_to: es }
+roadmaps:
+  - slug: a1
+    title: A1
+    language_from: en
+    language_to: es
+    nodes:
+      - { deck: food, parent: basics }
+      - { deck: food }
+"#,
+            None,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            field_errors(error),
+            [
+                "decks[0].slug",
+                "decks[0].language_to",
+                "decks[1].title",
+                "decks[1].cards[1]",
+                "decks[2].slug",
+                "roadmaps[0].nodes[0].parent",
+                "roadmaps[0].nodes[1].deck",
+            ]
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use axum_extra::extract::cookie::{Cookie, SameSite};
+use chrono::{DateTime, Utc};
 
 use crate::config::Environment;
 
@@ -72,6 +73,23 @@ pub fn create_refresh_token_cookie(
     )
 }
 
+/// Create a refresh token cookie that lasts until the token expires
+pub fn create_refresh_token_cookie_until(
+    token: String,
+    environment: &Environment,
+    expires_at: DateTime<Utc>,
+    cookie_domain: &str,
+) -> Cookie<'static> {
+    let remaining = (expires_at - Utc::now()).num_seconds().max(0);
+    build_cookie(
+        "refresh_token",
+        token,
+        time::Duration::seconds(remaining),
+        environment,
+        cookie_domain,
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,8 +1,12 @@
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OidcFlowData {
     pub csrf_token: String,
     pub nonce: String,
     pub pkce_verifier: String,
+    /// Set when a signed-in user links Google to their account instead of signing in
+    #[serde(default)]
+    pub link_user_id: Option<Uuid>,
 }
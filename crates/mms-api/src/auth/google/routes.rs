@@ -1,23 +1,33 @@
 use axum::{
     Router,
     extract::{Query, State},
-    response::{IntoResponse, Redirect},
+    http::StatusCode,
+    response::{Html, Redirect},
     routing::get,
 };
 use axum_extra::extract::{PrivateCookieJar, cookie::Cookie};
 use oauth2::{AuthorizationCode, CsrfToken, PkceCodeChallenge, PkceCodeVerifier, Scope};
 use openidconnect::{AuthenticationFlow, Nonce, TokenResponse, core::CoreResponseType};
 use serde::Deserialize;
+use utoipa::IntoParams;
+use uuid::Uuid;
 
 use super::{models::OidcFlowData, service};
-use crate::auth::{cookies, jwt, refresh_token as rt};
-use crate::{ApiState, error::ApiError, middleware::rate_limit};
+use crate::auth::{
+    AuthUser, Role, cookies, jwt, refresh_token as rt, validation::validate_profile_picture_url,
+};
+use crate::{
+    ApiState,
+    error::{ApiError, ErrorResponse},
+    middleware::rate_limit,
+};
 
 pub fn routes() -> Router<ApiState> {
     use crate::make_rate_limit_layer;
 
     Router::new()
         .route("/auth/google", get(google_auth))
+        .route("/auth/google/link", get(link_google).delete(unlink_google))
         .route("/auth/callback", get(auth_callback))
         .layer(make_rate_limit_layer!(
             rate_limit::GENERAL_RATE_PER_SECOND,
@@ -25,9 +35,68 @@ pub fn routes() -> Router<ApiState> {
         ))
 }
 
+/// Start Google sign-in
+#[utoipa::path(
+    get,
+    path = "/v1/auth/google",
+    tag = "auth",
+    responses((status = 303, description = "Redirect to Google; sets the encrypted `oidc_flow` cookie"))
+)]
 async fn google_auth(
     State(state): State<ApiState>,
     jar: PrivateCookieJar,
+) -> Result<(PrivateCookieJar, Redirect), ApiError> {
+    start_flow(&state, jar, None)
+}
+
+/// Start linking a Google account to the signed-in account
+///
+/// Google redirects back to `/v1/auth/callback`, which links the account
+/// instead of signing in.
+#[utoipa::path(
+    get,
+    path = "/v1/auth/google/link",
+    tag = "auth",
+    security(("cookie_auth" = [])),
+    responses(
+        (status = 303, description = "Redirect to Google; sets the encrypted `oidc_flow` cookie"),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+async fn link_google(
+    auth: AuthUser,
+    State(state): State<ApiState>,
+    jar: PrivateCookieJar,
+) -> Result<(PrivateCookieJar, Redirect), ApiError> {
+    start_flow(&state, jar, Some(auth.user_id))
+}
+
+/// Unlink the Google account; the account must have a password to sign in with instead
+#[utoipa::path(
+    delete,
+    path = "/v1/auth/google/link",
+    tag = "auth",
+    security(("cookie_auth" = [])),
+    responses(
+        (status = 204, description = "Google account unlinked"),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "No Google account is linked", body = ErrorResponse),
+        (status = 409, description = "The account has no password and would be locked out", body = ErrorResponse),
+    )
+)]
+async fn unlink_google(
+    auth: AuthUser,
+    State(state): State<ApiState>,
+) -> Result<StatusCode, ApiError> {
+    service::unlink_google_account(&state.pool, auth.user_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Redirect to Google, remembering the flow in an encrypted cookie
+fn start_flow(
+    state: &ApiState,
+    jar: PrivateCookieJar,
+    link_user_id: Option<Uuid>,
 ) -> Result<(PrivateCookieJar, Redirect), ApiError> {
     // Generate PKCE code verifier and challenge
     let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
@@ -51,6 +120,7 @@ async fn google_auth(
         csrf_token: csrf_token.secret().clone(),
         nonce: nonce.secret().clone(),
         pkce_verifier: pkce_verifier.secret().clone(),
+        link_user_id,
     };
 
     let oidc_json = serde_json::to_string(&oidc_data)
@@ -67,17 +137,32 @@ async fn google_auth(
     Ok((jar, Redirect::to(auth_url.as_str())))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct AuthRequest {
+    /// Authorization code from Google
     code: String,
+    /// CSRF token echoed back by Google
     state: String,
 }
 
+/// Finish Google sign-in, or linking when the flow was started from `/v1/auth/google/link`
+#[utoipa::path(
+    get,
+    path = "/v1/auth/callback",
+    tag = "auth",
+    params(AuthRequest),
+    responses(
+        (status = 200, description = "Sets the auth cookies and posts `google-auth-success` to the opener window; when linking, posts `google-link-success` instead", content_type = "text/html"),
+        (status = 400, description = "Missing flow cookie, CSRF mismatch or invalid ID token", body = ErrorResponse),
+        (status = 409, description = "Linking: the Google account belongs to another user, or another Google account is linked", body = ErrorResponse),
+    )
+)]
 async fn auth_callback(
     State(state): State<ApiState>,
     jar: PrivateCookieJar,
     Query(query): Query<AuthRequest>,
-) -> Result<(PrivateCookieJar, impl IntoResponse), ApiError> {
+) -> Result<(PrivateCookieJar, Html<String>), ApiError> {
     // Retrieve OIDC flow data from cookie
     let oidc_cookie = jar
         .get("oidc_flow")
@@ -129,12 +214,18 @@ async fn auth_callback(
     let picture = id_token_claims
         .picture()
         .and_then(|p| p.get(None))
-        .map(|p| p.to_string());
+        .map(|p| p.to_string())
+        .filter(|p| validate_profile_picture_url(p).is_ok());
 
     if !email_verified {
         return Err(ApiError::Oidc("Email not verified".to_string()));
     }
 
+    if let Some(user_id) = oidc_data.link_user_id {
+        service::link_google_account(&state.pool, user_id, &google_id).await?;
+        return Ok((jar, popup_response(&state, "google-link-success")?));
+    }
+
     // Find or create user in database
     let user = service::find_or_create_google_user(
         &state.pool,
@@ -149,7 +240,8 @@ async fn auth_callback(
     let token = jwt::generate_jwt_token(
         user.id,
         user.email.clone(),
-        &state.auth.jwt_secret,
+        Role::from_db(&user.role),
+        &state.auth.jwt_keys,
         state.auth.jwt_expiry_hours,
     )?;
 
@@ -180,7 +272,11 @@ async fn auth_callback(
     );
     let jar = jar.add(auth_cookie).add(refresh_cookie);
 
-    // Create HTML response with frontend URL from config
+    Ok((jar, popup_response(&state, "google-auth-success")?))
+}
+
+/// Page that posts `message_type` to the window that opened the Google popup, then closes it
+fn popup_response(state: &ApiState, message_type: &str) -> Result<Html<String>, ApiError> {
     // The origin is JSON-serialized to prevent XSS via script injection
     let origin_json = serde_json::to_string(state.oidc.frontend_url.as_ref())
         .map_err(|e| ApiError::Oidc(format!("Failed to serialize frontend URL: {e}")))?;
@@ -191,7 +287,7 @@ async fn auth_callback(
             <head><title>Authentication Successful</title></head>
             <body>
                 <script>
-                    window.opener.postMessage({{ type: 'google-auth-success' }}, {origin_json});
+                    window.opener.postMessage({{ type: '{message_type}' }}, {origin_json});
                     window.close();
                 </script>
             </body>
@@ -199,5 +295,5 @@ async fn auth_callback(
         "#
     );
 
-    Ok((jar, axum::response::Html(html)))
+    Ok(Html(html))
 }
@@ -1,6 +1,7 @@
-use crate::error::ApiError;
+use crate::{error::ApiError, user::USERNAME_RESERVATION_DAYS};
 use mms_db::models::UserProfile;
 use sqlx::PgPool;
+use uuid::Uuid;
 
 use mms_db::repositories::auth as auth_repo;
 use mms_db::repositories::user as user_repo;
@@ -12,6 +13,8 @@ use mms_db::repositories::user as user_repo;
 /// 2. If not, check if a user exists with this email
 /// 3. If not, create a new user
 ///
+/// Fails when the account found was deleted.
+///
 /// Returns the user's ID, username, email, and profile picture URL
 pub async fn find_or_create_google_user(
     pool: &PgPool,
@@ -22,18 +25,12 @@ pub async fn find_or_create_google_user(
 ) -> Result<UserProfile, ApiError> {
     // First, try to find existing user by Google ID
     if let Some(user) = auth_repo::find_by_google_id(pool, google_id).await? {
-        // Update profile picture if it has changed
-        if let Some(pic) = picture
-            && picture != user.profile_picture_url.as_deref()
-        {
-            let updated = auth_repo::update_profile_picture(pool, user.id, pic).await?;
-            if !updated {
-                tracing::warn!(user_id = %user.id, "failed to update profile picture: user not found");
-            }
-        }
+        ensure_not_deleted(pool, user.id).await?;
 
+        let profile_picture_url =
+            sync_picture(pool, user.id, user.profile_picture_url, picture).await?;
         return Ok(UserProfile {
-            profile_picture_url: picture.map(|p| p.to_string()).or(user.profile_picture_url),
+            profile_picture_url,
             ..user
         });
     }
@@ -41,28 +38,26 @@ pub async fn find_or_create_google_user(
     // If not found by Google ID, check if user exists with this email
     // This handles the case where user registered with email/password first
     if let Some(user) = auth_repo::find_by_email_with_google_id(pool, email).await? {
+        ensure_not_deleted(pool, user.id).await?;
+
         // If user exists but doesn't have google_id, link the Google account
         if user.google_id.is_none() {
-            let linked = auth_repo::link_google_account(pool, user.id, google_id, picture).await?;
+            let linked = auth_repo::link_google_account(pool, user.id, google_id).await?;
             if !linked {
                 tracing::warn!(user_id = %user.id, "failed to link google account: user not found");
             }
-        } else if let Some(pic) = picture
-            && picture != user.profile_picture_url.as_deref()
-        {
-            let updated = auth_repo::update_profile_picture(pool, user.id, pic).await?;
-            if !updated {
-                tracing::warn!(user_id = %user.id, "failed to update profile picture: user not found");
-            }
         }
+        let profile_picture_url =
+            sync_picture(pool, user.id, user.profile_picture_url, picture).await?;
 
         return Ok(UserProfile {
             id: user.id,
             username: user.username,
             email: user.email,
-            profile_picture_url: picture.map(|p| p.to_string()).or(user.profile_picture_url),
+            profile_picture_url,
             native_language: user.native_language,
             learning_language: user.learning_language,
+            role: user.role,
         });
     }
 
@@ -78,6 +73,15 @@ pub async fn find_or_create_google_user(
     let max_retries = 10;
 
     for attempt in 0..max_retries {
+        // Leave usernames other accounts gave up recently to them
+        let status =
+            user_repo::find_username_status(pool, &final_username, None, USERNAME_RESERVATION_DAYS)
+                .await?;
+        if status.reserved {
+            final_username = format!("{}{}", username, attempt + 2);
+            continue;
+        }
+
         match auth_repo::create_google_user(pool, &final_username, email, google_id, picture).await
         {
             Ok(user_id) => {
@@ -91,6 +95,7 @@ pub async fn find_or_create_google_user(
                     profile_picture_url: picture.map(|p| p.to_string()),
                     native_language: None,
                     learning_language: None,
+                    role: "learner".to_string(),
                 });
             }
             Err(sqlx::Error::Database(db_err))
@@ -107,3 +112,94 @@ pub async fn find_or_create_google_user(
         "Unable to generate a unique username. Please try again.".to_string(),
     ))
 }
+
+/// Show the picture from Google unless the user uploaded an avatar, returning
+/// the profile picture that applies
+async fn sync_picture(
+    pool: &PgPool,
+    user_id: Uuid,
+    current: Option<String>,
+    picture: Option<&str>,
+) -> Result<Option<String>, ApiError> {
+    match picture {
+        Some(picture)
+            if Some(picture) != current.as_deref()
+                && auth_repo::update_profile_picture(pool, user_id, picture).await? =>
+        {
+            Ok(Some(picture.to_string()))
+        }
+        _ => Ok(current),
+    }
+}
+
+/// Deleted accounts cannot sign in until restored
+async fn ensure_not_deleted(pool: &PgPool, user_id: Uuid) -> Result<(), ApiError> {
+    if user_repo::find_deleted_at(pool, user_id).await?.is_some() {
+        return Err(ApiError::Forbidden(
+            "This account was deleted. Contact support to restore it.".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Link the Google account `google_id` to the user, who signed in some other way
+///
+/// Linking the account already linked is a no-op.
+pub async fn link_google_account(
+    pool: &PgPool,
+    user_id: Uuid,
+    google_id: &str,
+) -> Result<(), ApiError> {
+    if let Some(owner) = auth_repo::find_by_google_id(pool, google_id).await? {
+        if owner.id == user_id {
+            return Ok(());
+        }
+        return Err(ApiError::Conflict(
+            "This Google account is already linked to another user".to_string(),
+        ));
+    }
+
+    match auth_repo::link_google_id(pool, user_id, google_id).await {
+        Ok(true) => {
+            tracing::info!(%user_id, "Google account linked");
+            Ok(())
+        }
+        Ok(false) => {
+            user_repo::find_sign_in_methods(pool, user_id)
+                .await?
+                .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+            Err(ApiError::Conflict(
+                "Another Google account is linked. Unlink it first.".to_string(),
+            ))
+        }
+        // Linked to someone else meanwhile
+        Err(sqlx::Error::Database(db_err))
+            if db_err.constraint() == Some("users_google_id_key") =>
+        {
+            Err(ApiError::Conflict(
+                "This Google account is already linked to another user".to_string(),
+            ))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Unlink the user's Google account, keeping the password as the way to sign in
+pub async fn unlink_google_account(pool: &PgPool, user_id: Uuid) -> Result<(), ApiError> {
+    if auth_repo::unlink_google_id(pool, user_id).await? {
+        tracing::info!(%user_id, "Google account unlinked");
+        return Ok(());
+    }
+
+    let methods = user_repo::find_sign_in_methods(pool, user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+    if methods.google_id.is_none() {
+        return Err(ApiError::NotFound(
+            "No Google account is linked".to_string(),
+        ));
+    }
+    Err(ApiError::Conflict(
+        "Set a password before unlinking Google, or you could not sign in anymore".to_string(),
+    ))
+}
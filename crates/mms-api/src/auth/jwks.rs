@@ -0,0 +1,219 @@
+//! Public JSON Web Keys for asymmetric token signing.
+//!
+//! When access tokens are signed with RS256 or EdDSA, other services fetch
+//! `/.well-known/jwks.json` and verify tokens locally, picking the key by the
+//! token's `kid`. Shared HMAC secrets are never published.
+
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use jsonwebtoken::errors::{Error as JwtError, ErrorKind};
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, KeyAlgorithm,
+    OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+
+use super::jwt::JwtAlgorithm;
+use crate::error::ApiError;
+
+/// DER prefix of an Ed25519 `SubjectPublicKeyInfo`; the 32-byte key follows
+const ED25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+const DER_SEQUENCE: u8 = 0x30;
+const DER_INTEGER: u8 = 0x02;
+const DER_BIT_STRING: u8 = 0x03;
+
+/// Build the public JWK for a PEM public key, or `None` for HMAC secrets
+pub(crate) fn public_jwk(
+    algorithm: JwtAlgorithm,
+    kid: &str,
+    public_pem: &[u8],
+) -> Result<Option<Jwk>, ApiError> {
+    let (key_algorithm, parameters) = match algorithm {
+        JwtAlgorithm::Hs256 => return Ok(None),
+        JwtAlgorithm::Rs256 => {
+            let der = pem_to_der(public_pem)?;
+            let (n, e) = rsa_components(&der).ok_or_else(invalid_key)?;
+            (
+                KeyAlgorithm::RS256,
+                AlgorithmParameters::RSA(RSAKeyParameters {
+                    key_type: RSAKeyType::RSA,
+                    n: URL_SAFE_NO_PAD.encode(n),
+                    e: URL_SAFE_NO_PAD.encode(e),
+                }),
+            )
+        }
+        JwtAlgorithm::EdDsa => {
+            let der = pem_to_der(public_pem)?;
+            let x = der
+                .strip_prefix(&ED25519_SPKI_PREFIX)
+                .filter(|key| key.len() == 32)
+                .ok_or_else(invalid_key)?;
+            (
+                KeyAlgorithm::EdDSA,
+                AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                    key_type: OctetKeyPairType::OctetKeyPair,
+                    curve: EllipticCurve::Ed25519,
+                    x: URL_SAFE_NO_PAD.encode(x),
+                }),
+            )
+        }
+    };
+
+    Ok(Some(Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(key_algorithm),
+            key_id: Some(kid.to_string()),
+            ..CommonParameters::default()
+        },
+        algorithm: parameters,
+    }))
+}
+
+fn invalid_key() -> ApiError {
+    ApiError::Jwt(JwtError::from(ErrorKind::InvalidKeyFormat))
+}
+
+fn pem_to_der(pem: &[u8]) -> Result<Vec<u8>, ApiError> {
+    let body: String = std::str::from_utf8(pem)
+        .map_err(|_| invalid_key())?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with("-----"))
+        .collect();
+
+    STANDARD.decode(body).map_err(|_| invalid_key())
+}
+
+/// Read one DER element, returning its tag, contents and the remaining input
+fn read_der(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = input.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+
+    let (len, rest) = if first < 0x80 {
+        (usize::from(first), rest)
+    } else {
+        let octets = usize::from(first & 0x7f);
+        if octets == 0 || octets > 4 || rest.len() < octets {
+            return None;
+        }
+        let len = rest[..octets]
+            .iter()
+            .fold(0usize, |len, byte| (len << 8) | usize::from(*byte));
+        (len, &rest[octets..])
+    };
+
+    (rest.len() >= len).then(|| (tag, &rest[..len], &rest[len..]))
+}
+
+/// Modulus and exponent of an RSA public key in SPKI (`BEGIN PUBLIC KEY`) or
+/// PKCS#1 (`BEGIN RSA PUBLIC KEY`) form, without DER sign padding
+fn rsa_components(der: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (tag, outer, _) = read_der(der)?;
+    if tag != DER_SEQUENCE {
+        return None;
+    }
+
+    // SPKI wraps the PKCS#1 key in an algorithm identifier and a bit string
+    let (tag, _, rest) = read_der(outer)?;
+    let pkcs1 = match tag {
+        DER_INTEGER => outer,
+        DER_SEQUENCE => {
+            let (tag, bits, _) = read_der(rest)?;
+            if tag != DER_BIT_STRING {
+                return None;
+            }
+            let (&unused_bits, key) = bits.split_first()?;
+            if unused_bits != 0 {
+                return None;
+            }
+            let (tag, pkcs1, _) = read_der(key)?;
+            if tag != DER_SEQUENCE {
+                return None;
+            }
+            pkcs1
+        }
+        _ => return None,
+    };
+
+    let (tag, n, rest) = read_der(pkcs1)?;
+    if tag != DER_INTEGER {
+        return None;
+    }
+    let (tag, e, _) = read_der(rest)?;
+    if tag != DER_INTEGER {
+        return None;
+    }
+
+    Some((strip_sign_byte(n), strip_sign_byte(e)))
+}
+
+fn strip_sign_byte(integer: &[u8]) -> &[u8] {
+    match integer {
+        [0, rest @ ..] if !rest.is_empty() => rest,
+        _ => integer,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ED25519_PUBLIC_KEY: &str = "-----BEGIN PUBLIC KEY-----
+MCowBQYDK2VwAyEAKe7fFZ1Zd01oTG7VdApxQwGzmrdTq5hFDg5CTTP1KZs=
+-----END PUBLIC KEY-----";
+
+    const RSA_PUBLIC_KEY: &str = "-----BEGIN PUBLIC KEY-----
+MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEA2IuV809QSfNO4R12Bca+
+XeHYDKx2MjK9xg3KNlwueXUw2s79u0ygL5zzZG2eyY2UkXq+PDUpKcspcHh/1XYx
+PlCF6dhAJb4uuTAuawTyYerGpLXzvys8zDi9UfkJbkYdYz4BoYGvH+u8Wp8zNoWV
+fIA2jHmuwp7+uezDxfCl0Uw5qF+3qM8HuXwBeRuXxrOWy7pja/bG+uqVSO513gxp
+2oHH3XirtwodYItdxeNS/6TBcW5DmwOg+RHDEXbHULgIOvS3rka/eLqMHOxOPTD0
+QuhpqbqCIQNBLE8MD9Nq00R4sSM0exoqzNdhtBSq5xBDGeUwk2jVAlssTjbyNgO5
+2QIDAQAB
+-----END PUBLIC KEY-----";
+
+    #[test]
+    fn test_hmac_keys_are_not_published() {
+        let jwk = public_jwk(JwtAlgorithm::Hs256, "kid", b"secret").unwrap();
+        assert!(jwk.is_none());
+    }
+
+    #[test]
+    fn test_ed25519_jwk() {
+        let jwk = public_jwk(JwtAlgorithm::EdDsa, "ed-kid", ED25519_PUBLIC_KEY.as_bytes())
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(jwk.common.key_id.as_deref(), Some("ed-kid"));
+        let AlgorithmParameters::OctetKeyPair(params) = jwk.algorithm else {
+            panic!("Expected an OKP key");
+        };
+        assert_eq!(params.curve, EllipticCurve::Ed25519);
+        assert_eq!(URL_SAFE_NO_PAD.decode(params.x).unwrap().len(), 32);
+    }
+
+    #[test]
+    fn test_rsa_jwk() {
+        let jwk = public_jwk(JwtAlgorithm::Rs256, "rsa-kid", RSA_PUBLIC_KEY.as_bytes())
+            .unwrap()
+            .unwrap();
+
+        let AlgorithmParameters::RSA(params) = jwk.algorithm else {
+            panic!("Expected an RSA key");
+        };
+        assert_eq!(params.e, "AQAB");
+        let n = URL_SAFE_NO_PAD.decode(params.n).unwrap();
+        assert_eq!(n.len(), 256);
+        assert_eq!(&n[..4], &[0xd8, 0x8b, 0x95, 0xf3]);
+    }
+
+    #[test]
+    fn test_malformed_key_is_rejected() {
+        let pem = "-----BEGIN PUBLIC KEY-----\nAAAA\n-----END PUBLIC KEY-----";
+        assert!(public_jwk(JwtAlgorithm::Rs256, "kid", pem.as_bytes()).is_err());
+        assert!(public_jwk(JwtAlgorithm::EdDsa, "kid", pem.as_bytes()).is_err());
+    }
+}
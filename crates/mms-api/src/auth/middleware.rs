@@ -3,15 +3,19 @@ use axum::{
     http::request::Parts,
 };
 use axum_extra::extract::{PrivateCookieJar, cookie::Key};
-use sqlx::types::Uuid;
+use sqlx::{PgPool, types::Uuid};
+
+use mms_db::repositories::user as user_repo;
 
 use super::jwt::verify_jwt_token;
+use super::policy::Role;
 use crate::{error::ApiError, state::AuthConfig};
 
 /// Authenticated user extractor
 ///
 /// Use this in route handlers to ensure the user is authenticated.
-/// It will automatically validate the JWT token from the cookie.
+/// It will automatically validate the JWT token from the cookie, and rejects
+/// tokens of accounts deleted since they were issued.
 ///
 /// # Example
 /// ```
@@ -31,12 +35,16 @@ use crate::{error::ApiError, state::AuthConfig};
 pub struct AuthUser {
     pub user_id: Uuid,
     pub email: String,
+    pub role: Role,
+    /// Permission scopes from the token; see [`super::policy::require_permission`]
+    pub scopes: Vec<String>,
 }
 
 impl<S> FromRequestParts<S> for AuthUser
 where
     AuthConfig: FromRef<S>,
     Key: FromRef<S>,
+    PgPool: FromRef<S>,
     S: Send + Sync,
 {
     type Rejection = ApiError;
@@ -58,15 +66,22 @@ where
             .to_owned();
 
         // Verify the token
-        let claims = verify_jwt_token(&token, &auth_config.jwt_secret)?;
+        let claims = verify_jwt_token(&token, &auth_config.jwt_keys)?;
 
         // Parse user_id from claims
         let user_id = Uuid::parse_str(&claims.sub)
             .map_err(|_| ApiError::Auth("Invalid user ID in token".to_string()))?;
 
+        // Access tokens outlive a deletion; the account itself must still be there
+        if !user_repo::is_active(&PgPool::from_ref(state), user_id).await? {
+            return Err(ApiError::Auth("Not authenticated".to_string()));
+        }
+
         Ok(AuthUser {
             user_id,
             email: claims.email,
+            role: claims.role,
+            scopes: claims.scopes,
         })
     }
 }
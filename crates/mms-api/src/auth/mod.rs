@@ -1,10 +1,15 @@
 pub mod cookies;
 pub mod google;
+pub mod jwks;
 pub mod jwt;
 pub mod middleware;
+pub mod policy;
 pub mod refresh_token;
 pub mod routes;
 pub mod validation;
 
 pub use middleware::AuthUser;
+pub use policy::{
+    Permission, RequirePermission, Role, permissions, require_permission, require_self,
+};
 pub use routes::routes;
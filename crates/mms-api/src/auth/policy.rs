@@ -0,0 +1,274 @@
+//! Roles, permissions and the extractor that enforces them.
+//!
+//! A user's role is read from the database when an access token is issued and
+//! embedded in the token together with the permission scopes it grants.
+//! Handlers declare what they need with [`RequirePermission`] instead of
+//! comparing user ids, so a role change only touches [`Role::permissions`].
+
+use std::marker::PhantomData;
+
+use axum::{
+    extract::{FromRef, FromRequestParts},
+    http::{header, request::Parts},
+};
+use axum_extra::extract::cookie::Key;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use utoipa::ToSchema;
+
+use super::middleware::AuthUser;
+use crate::{error::ApiError, state::AuthConfig, token_service::hash_token};
+
+/// Account role, stored in `users.role`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, ToSchema, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    Learner,
+    /// Creates and imports course content
+    Author,
+    /// Authors content, moderates reports and operates the service
+    Admin,
+}
+
+impl Role {
+    /// Parse the database representation; unknown values get no extra permissions
+    #[must_use]
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "author" => Self::Author,
+            "admin" => Self::Admin,
+            _ => Self::Learner,
+        }
+    }
+
+    /// Permissions granted to every token issued for this role
+    #[must_use]
+    pub const fn permissions(self) -> &'static [Permission] {
+        match self {
+            Self::Learner => &[],
+            Self::Author => &[Permission::ContentWrite],
+            Self::Admin => &[
+                Permission::ContentWrite,
+                Permission::ContentModerate,
+                Permission::AdminMaintenance,
+            ],
+        }
+    }
+
+    /// Scope strings embedded in access tokens
+    #[must_use]
+    pub fn scopes(self) -> Vec<String> {
+        self.permissions()
+            .iter()
+            .map(|permission| permission.scope().to_string())
+            .collect()
+    }
+}
+
+/// Something a handler can require
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    /// Create and import roadmaps, decks and cards
+    ContentWrite,
+    /// Work through reported content and act on it
+    ContentModerate,
+    /// Database reports and other operator endpoints
+    AdminMaintenance,
+}
+
+impl Permission {
+    #[must_use]
+    pub const fn scope(self) -> &'static str {
+        match self {
+            Self::ContentWrite => "content:write",
+            Self::ContentModerate => "content:moderate",
+            Self::AdminMaintenance => "admin:maintenance",
+        }
+    }
+}
+
+/// Type-level permission for use with [`RequirePermission`]
+pub trait RequiredPermission {
+    const PERMISSION: Permission;
+}
+
+/// Marker types naming each [`Permission`]
+pub mod permissions {
+    use super::{Permission, RequiredPermission};
+
+    pub struct ContentWrite;
+
+    impl RequiredPermission for ContentWrite {
+        const PERMISSION: Permission = Permission::ContentWrite;
+    }
+
+    pub struct ContentModerate;
+
+    impl RequiredPermission for ContentModerate {
+        const PERMISSION: Permission = Permission::ContentModerate;
+    }
+
+    pub struct AdminMaintenance;
+
+    impl RequiredPermission for AdminMaintenance {
+        const PERMISSION: Permission = Permission::AdminMaintenance;
+    }
+}
+
+/// Who was granted access by [`RequirePermission`]
+#[derive(Debug, Clone)]
+pub enum Principal {
+    /// A signed-in user whose token carries the permission
+    User(AuthUser),
+    /// A script presenting `ADMIN_API_TOKEN`, which holds every permission
+    Operator,
+}
+
+/// Check that the caller holds a permission
+pub fn require_permission(auth_user: &AuthUser, permission: Permission) -> Result<(), ApiError> {
+    if auth_user
+        .scopes
+        .iter()
+        .any(|scope| scope == permission.scope())
+    {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden(format!(
+            "Missing permission: {}",
+            permission.scope()
+        )))
+    }
+}
+
+/// Check that a `/users/{user_id}/...` route names the caller's own account
+pub fn require_self(auth_user: &AuthUser, user_id: uuid::Uuid) -> Result<(), ApiError> {
+    if auth_user.user_id == user_id {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden(
+            "You can only access your own account".to_string(),
+        ))
+    }
+}
+
+/// Extractor that only lets callers holding `P` through.
+///
+/// Accepts either a user access token (cookie) whose scopes include the
+/// permission, or `Authorization: Bearer <ADMIN_API_TOKEN>` for scripts and
+/// operators. Unauthenticated callers get 401, signed-in users without the
+/// permission get 403.
+///
+/// # Example
+/// ```
+/// use mms_api::auth::{RequirePermission, permissions::ContentWrite};
+///
+/// async fn import(_access: RequirePermission<ContentWrite>) {}
+/// ```
+pub struct RequirePermission<P> {
+    pub principal: Principal,
+    permission: PhantomData<P>,
+}
+
+impl<P, S> FromRequestParts<S> for RequirePermission<P>
+where
+    P: RequiredPermission,
+    AuthConfig: FromRef<S>,
+    Key: FromRef<S>,
+    PgPool: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let granted = |principal| Self {
+            principal,
+            permission: PhantomData,
+        };
+
+        let auth_config = AuthConfig::from_ref(state);
+        let bearer = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        if let Some(provided) = bearer {
+            // Compare digests so the comparison time does not depend on how much of the token matches
+            let is_operator = auth_config
+                .admin_api_token
+                .as_deref()
+                .is_some_and(|token| hash_token(provided) == hash_token(token));
+            if !is_operator {
+                return Err(ApiError::Auth("Not authenticated".to_string()));
+            }
+            return Ok(granted(Principal::Operator));
+        }
+
+        let auth_user = AuthUser::from_request_parts(parts, state).await?;
+        require_permission(&auth_user, P::PERMISSION)?;
+        Ok(granted(Principal::User(auth_user)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn user_with(role: Role) -> AuthUser {
+        AuthUser {
+            user_id: Uuid::new_v4(),
+            email: "user@example.com".to_string(),
+            role,
+            scopes: role.scopes(),
+        }
+    }
+
+    #[test]
+    fn test_role_from_db() {
+        assert_eq!(Role::from_db("admin"), Role::Admin);
+        assert_eq!(Role::from_db("author"), Role::Author);
+        assert_eq!(Role::from_db("learner"), Role::Learner);
+        assert_eq!(Role::from_db("superuser"), Role::Learner);
+    }
+
+    #[test]
+    fn test_role_permissions() {
+        let learner = user_with(Role::Learner);
+        assert!(require_permission(&learner, Permission::ContentWrite).is_err());
+
+        let author = user_with(Role::Author);
+        assert!(require_permission(&author, Permission::ContentWrite).is_ok());
+        assert!(require_permission(&author, Permission::ContentModerate).is_err());
+        assert!(require_permission(&author, Permission::AdminMaintenance).is_err());
+
+        let admin = user_with(Role::Admin);
+        assert!(require_permission(&admin, Permission::ContentWrite).is_ok());
+        assert!(require_permission(&admin, Permission::ContentModerate).is_ok());
+        assert!(require_permission(&admin, Permission::AdminMaintenance).is_ok());
+    }
+
+    #[test]
+    fn test_require_self() {
+        let learner = user_with(Role::Learner);
+
+        assert!(require_self(&learner, learner.user_id).is_ok());
+        assert!(matches!(
+            require_self(&learner, Uuid::new_v4()),
+            Err(ApiError::Forbidden(_))
+        ));
+    }
+
+    #[test]
+    fn test_permission_comes_from_token_scopes_not_role() {
+        // A token narrowed to fewer scopes than its role allows is respected
+        let mut admin = user_with(Role::Admin);
+        admin.scopes.clear();
+
+        assert!(matches!(
+            require_permission(&admin, Permission::AdminMaintenance),
+            Err(ApiError::Forbidden(_))
+        ));
+    }
+}
@@ -1,13 +1,58 @@
 use base64::Engine;
-use chrono::Utc;
+use chrono::{DateTime, Duration, Utc};
 use rand::Rng;
 use sqlx::{PgPool, types::Uuid};
 
 use crate::error::ApiError;
-use crate::user::token::hash_token;
+use crate::metrics;
+use crate::token_service::hash_token;
 
+use mms_db::models::EncryptedString;
 use mms_db::repositories::auth as auth_repo;
 
+/// How long refresh tokens and the sessions they keep alive last
+#[derive(Debug, Clone, Copy)]
+pub struct SessionLifetime {
+    /// Lifetime of each refresh token
+    pub expiry_days: i64,
+    /// Whether a rotated token gets a full `expiry_days` again, rather than
+    /// keeping the expiry of the token it replaces
+    pub sliding: bool,
+    /// Maximum age of a session since sign-in, however often it is refreshed
+    pub max_session_days: i64,
+}
+
+impl SessionLifetime {
+    /// Expiry of a token replacing one that expires at `expires_at`, or
+    /// `None` once the session has reached its maximum age
+    fn rotated_expiry(
+        &self,
+        now: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
+        session_started_at: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        let session_ends_at = session_started_at + Duration::days(self.max_session_days);
+        if now >= session_ends_at {
+            return None;
+        }
+
+        let renewed = if self.sliding {
+            now + Duration::days(self.expiry_days)
+        } else {
+            expires_at
+        };
+        Some(renewed.min(session_ends_at))
+    }
+}
+
+/// The token issued by a rotation
+#[derive(Debug)]
+pub struct RotatedRefreshToken {
+    pub user_id: Uuid,
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
 /// Generate a cryptographically secure random refresh token
 /// Returns the token string (to send to client) and its SHA-256 hash (to store in DB)
 #[must_use]
@@ -32,7 +77,7 @@ pub async fn store_refresh_token(
     ip_address: Option<&str>,
     expiry_days: i64,
 ) -> Result<Uuid, ApiError> {
-    let expires_at = Utc::now() + chrono::Duration::days(expiry_days);
+    let expires_at = Utc::now() + Duration::days(expiry_days);
 
     let token_id = auth_repo::store_refresh_token(
         pool,
@@ -41,6 +86,7 @@ pub async fn store_refresh_token(
         device_info,
         ip_address,
         expires_at,
+        None,
     )
     .await
     .map_err(ApiError::Database)?;
@@ -48,52 +94,70 @@ pub async fn store_refresh_token(
     Ok(token_id)
 }
 
-/// Verify a refresh token and return the user_id if valid
-/// Also updates the last_used_at timestamp and rotates the token
+/// Verify a refresh token and rotate it
+///
+/// The replacement keeps the session's start, so its expiry never passes the
+/// session's maximum age. A session past that age is ended and must sign in again.
 pub async fn verify_and_rotate_refresh_token(
     pool: &PgPool,
     token: &str,
-    expiry_days: i64,
-) -> Result<(Uuid, String, String), ApiError> {
+    lifetime: SessionLifetime,
+) -> Result<RotatedRefreshToken, ApiError> {
     let token_hash = hash_token(token);
 
     // Start a transaction for atomic token rotation
     let mut tx = pool.begin().await?;
 
     // Fetch and verify the token
-    let record = auth_repo::find_refresh_token_by_hash(&mut *tx, &token_hash)
-        .await?
-        .ok_or_else(|| ApiError::Auth("Invalid refresh token".to_string()))?;
+    let Some(record) = auth_repo::find_refresh_token_by_hash(&mut *tx, &token_hash).await? else {
+        metrics::record_refresh_rotation("invalid");
+        return Err(ApiError::Auth("Invalid refresh token".to_string()));
+    };
 
     // Check if token is expired
-    if record.expires_at < Utc::now() {
+    let now = Utc::now();
+    if record.expires_at < now {
         // Delete expired token
         auth_repo::delete_refresh_token(&mut *tx, record.id).await?;
         tx.commit().await?;
+        metrics::record_refresh_rotation("expired");
         return Err(ApiError::Auth("Refresh token expired".to_string()));
     }
 
     // Token is valid - delete the old token
     auth_repo::delete_refresh_token(&mut *tx, record.id).await?;
 
-    // Generate a new refresh token
-    let (new_token, new_token_hash) = generate_refresh_token();
-    let new_expires_at = Utc::now() + chrono::Duration::days(expiry_days);
+    let Some(new_expires_at) =
+        lifetime.rotated_expiry(now, record.expires_at, record.session_started_at)
+    else {
+        tx.commit().await?;
+        metrics::record_refresh_rotation("session_ended");
+        return Err(ApiError::Auth(
+            "Session expired. Please sign in again.".to_string(),
+        ));
+    };
 
-    // Store the new refresh token
+    // Generate and store the new refresh token
+    let (new_token, new_token_hash) = generate_refresh_token();
     auth_repo::store_refresh_token(
         &mut *tx,
         record.user_id,
         &new_token_hash,
-        record.device_info.as_deref(),
-        record.ip_address.as_deref(),
+        record.device_info.as_ref().map(EncryptedString::expose),
+        record.ip_address.as_ref().map(EncryptedString::expose),
         new_expires_at,
+        Some(record.session_started_at),
     )
     .await?;
 
     tx.commit().await?;
+    metrics::record_refresh_rotation("rotated");
 
-    Ok((record.user_id, new_token, new_token_hash))
+    Ok(RotatedRefreshToken {
+        user_id: record.user_id,
+        token: new_token,
+        expires_at: new_expires_at,
+    })
 }
 
 /// Revoke a specific refresh token
@@ -122,3 +186,42 @@ pub async fn cleanup_expired_tokens(pool: &PgPool) -> Result<u64, ApiError> {
     let rows = auth_repo::cleanup_expired_refresh_tokens(pool).await?;
     Ok(rows)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lifetime(sliding: bool) -> SessionLifetime {
+        SessionLifetime {
+            expiry_days: 30,
+            sliding,
+            max_session_days: 90,
+        }
+    }
+
+    #[test]
+    fn sliding_rotation_extends_expiry() {
+        let started = Utc::now() - Duration::days(10);
+        let now = Utc::now();
+        let expires_at = now + Duration::days(5);
+
+        let renewed = lifetime(true).rotated_expiry(now, expires_at, started);
+        assert_eq!(renewed, Some(now + Duration::days(30)));
+
+        let kept = lifetime(false).rotated_expiry(now, expires_at, started);
+        assert_eq!(kept, Some(expires_at));
+    }
+
+    #[test]
+    fn rotation_never_outlives_the_session() {
+        let now = Utc::now();
+        let started = now - Duration::days(80);
+
+        let renewed = lifetime(true).rotated_expiry(now, now + Duration::days(1), started);
+        assert_eq!(renewed, Some(started + Duration::days(90)));
+
+        let over =
+            lifetime(true).rotated_expiry(now, now + Duration::days(1), now - Duration::days(90));
+        assert_eq!(over, None);
+    }
+}
@@ -6,20 +6,51 @@ use axum::{
 use axum_extra::extract::{PrivateCookieJar, cookie::Cookie};
 use serde::{Deserialize, Serialize};
 use sqlx::types::Uuid;
+use utoipa::ToSchema;
+use validator::Validate;
 
-use super::{cookies, jwt, middleware::AuthUser, refresh_token as rt};
-use crate::{ApiState, error::ApiError, middleware::rate_limit, validation};
+use super::{cookies, jwt, middleware::AuthUser, policy::Role, refresh_token as rt};
+use crate::{
+    ApiState,
+    error::{ApiError, ErrorResponse},
+    middleware::rate_limit,
+    validation::{ValidJson, language_code},
+    versioning::{ApiVersion, Deprecation, deprecated},
+};
 
 use mms_db::models::{UserCredentials, UserProfile};
+use mms_db::repositories::profile as profile_repo;
 use mms_db::repositories::user as user_repo;
 
-pub fn routes() -> Router<ApiState> {
+/// v1 endpoints that return access and refresh tokens in the response body.
+///
+/// The cookies carry the session already; echoing the tokens exposes them to
+/// scripts. v2 returns the same responses without them.
+pub(crate) const TOKENS_IN_BODY_DEPRECATION: Deprecation = Deprecation {
+    // 2026-10-17T00:00:00Z
+    since: 1_792_195_200,
+    sunset: None,
+    successor: None,
+};
+
+pub fn routes(version: ApiVersion) -> Router<ApiState> {
     use crate::make_rate_limit_layer;
 
+    let refresh = match version {
+        ApiVersion::V1 => deprecated(
+            post(refresh_token),
+            Deprecation {
+                successor: Some("/v2/auth/refresh"),
+                ..TOKENS_IN_BODY_DEPRECATION
+            },
+        ),
+        ApiVersion::V2 => post(refresh_token_v2),
+    };
+
     // Authenticated routes with general rate limiting
     Router::new()
         .route("/auth/me", get(auth_me))
-        .route("/auth/refresh", post(refresh_token))
+        .route("/auth/refresh", refresh)
         .route("/auth/logout", post(logout))
         .route(
             "/users/me/language-preferences",
@@ -31,14 +62,20 @@ pub fn routes() -> Router<ApiState> {
         ))
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct AuthResponse {
     pub token: String,
     pub refresh_token: String,
     pub user: UserResponse,
 }
 
-#[derive(Debug, Serialize)]
+/// Sign-in response for versions where the session only lives in cookies
+#[derive(Serialize, ToSchema)]
+pub struct SessionResponse {
+    pub user: UserResponse,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct UserResponse {
     pub id: Uuid,
     pub username: String,
@@ -46,6 +83,7 @@ pub struct UserResponse {
     pub profile_picture_url: Option<String>,
     pub native_language: Option<String>,
     pub learning_language: Option<String>,
+    pub role: Role,
 }
 
 impl From<UserProfile> for UserResponse {
@@ -57,6 +95,7 @@ impl From<UserProfile> for UserResponse {
             profile_picture_url: user.profile_picture_url,
             native_language: user.native_language,
             learning_language: user.learning_language,
+            role: Role::from_db(&user.role),
         }
     }
 }
@@ -70,10 +109,22 @@ impl From<UserCredentials> for UserResponse {
             profile_picture_url: user.profile_picture_url,
             native_language: user.native_language,
             learning_language: user.learning_language,
+            role: Role::from_db(&user.role),
         }
     }
 }
 
+/// The signed-in user's profile
+#[utoipa::path(
+    get,
+    path = "/v1/auth/me",
+    tag = "auth",
+    security(("cookie_auth" = [])),
+    responses(
+        (status = 200, description = "Current user", body = UserResponse),
+        (status = 401, description = "Not signed in or the user no longer exists", body = ErrorResponse),
+    )
+)]
 async fn auth_me(
     auth_user: AuthUser,
     State(state): State<ApiState>,
@@ -87,10 +138,73 @@ async fn auth_me(
     Ok(Json(user.into()))
 }
 
+#[derive(Debug, Serialize, ToSchema)]
+struct RefreshResponse {
+    token: String,
+    message: String,
+}
+
+/// Rotate the `refresh_token` cookie and issue a new access token
+///
+/// Deprecated in favour of `/v2/auth/refresh`, which does not echo the token in the body.
+#[utoipa::path(
+    post,
+    path = "/v1/auth/refresh",
+    tag = "auth",
+    responses(
+        (status = 200, description = "New access token; both cookies are replaced", body = RefreshResponse),
+        (status = 401, description = "Missing, expired or revoked refresh token, or unverified email", body = ErrorResponse),
+    )
+)]
 async fn refresh_token(
     State(state): State<ApiState>,
     jar: PrivateCookieJar,
-) -> Result<(PrivateCookieJar, Json<serde_json::Value>), ApiError> {
+) -> Result<(PrivateCookieJar, Json<RefreshResponse>), ApiError> {
+    let (jar, token) = rotate_session(&state, jar).await?;
+
+    Ok((
+        jar,
+        Json(RefreshResponse {
+            token,
+            message: "Token refreshed successfully".to_string(),
+        }),
+    ))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct RefreshedResponse {
+    message: String,
+}
+
+/// Rotate the `refresh_token` cookie and replace the `auth_token` cookie
+#[utoipa::path(
+    post,
+    path = "/v2/auth/refresh",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Both cookies are replaced", body = RefreshedResponse),
+        (status = 401, description = "Missing, expired or revoked refresh token, or unverified email", body = ErrorResponse),
+    )
+)]
+async fn refresh_token_v2(
+    State(state): State<ApiState>,
+    jar: PrivateCookieJar,
+) -> Result<(PrivateCookieJar, Json<RefreshedResponse>), ApiError> {
+    let (jar, _) = rotate_session(&state, jar).await?;
+
+    Ok((
+        jar,
+        Json(RefreshedResponse {
+            message: "Token refreshed successfully".to_string(),
+        }),
+    ))
+}
+
+/// Verify and rotate the refresh token, set both cookies and return the new access token
+async fn rotate_session(
+    state: &ApiState,
+    jar: PrivateCookieJar,
+) -> Result<(PrivateCookieJar, String), ApiError> {
     // Get refresh token from cookie
     let refresh_cookie = jar
         .get("refresh_token")
@@ -99,12 +213,13 @@ async fn refresh_token(
     let old_refresh_token = refresh_cookie.value();
 
     // Verify and rotate the refresh token
-    let (user_id, new_refresh_token, _) = rt::verify_and_rotate_refresh_token(
+    let rotated = rt::verify_and_rotate_refresh_token(
         &state.pool,
         old_refresh_token,
-        state.auth.refresh_token_expiry_days,
+        state.auth.refresh_session_lifetime(),
     )
     .await?;
+    let user_id = rotated.user_id;
 
     // Fetch user email and verify account status
     let status = user_repo::find_email_verified_status(&state.pool, user_id)
@@ -119,10 +234,12 @@ async fn refresh_token(
     }
 
     // Generate new JWT access token
+    // Re-read the role so a promotion or demotion applies from the next refresh
     let new_access_token = jwt::generate_jwt_token(
         user_id,
         status.email,
-        &state.auth.jwt_secret,
+        Role::from_db(&status.role),
+        &state.auth.jwt_keys,
         state.auth.jwt_expiry_hours,
     )?;
 
@@ -133,27 +250,33 @@ async fn refresh_token(
         state.auth.jwt_expiry_hours,
         &state.cookie.cookie_domain,
     );
-    let refresh_cookie = cookies::create_refresh_token_cookie(
-        new_refresh_token,
+    let refresh_cookie = cookies::create_refresh_token_cookie_until(
+        rotated.token,
         &state.cookie.environment,
-        state.auth.refresh_token_expiry_days,
+        rotated.expires_at,
         &state.cookie.cookie_domain,
     );
     let jar = jar.add(auth_cookie).add(refresh_cookie);
 
-    Ok((
-        jar,
-        Json(serde_json::json!({
-            "token": new_access_token,
-            "message": "Token refreshed successfully"
-        })),
-    ))
+    Ok((jar, new_access_token))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct LogoutResponse {
+    message: String,
 }
 
+/// Revoke the refresh token and clear both cookies
+#[utoipa::path(
+    post,
+    path = "/v1/auth/logout",
+    tag = "auth",
+    responses((status = 200, description = "Signed out", body = LogoutResponse))
+)]
 async fn logout(
     State(state): State<ApiState>,
     jar: PrivateCookieJar,
-) -> (PrivateCookieJar, Json<serde_json::Value>) {
+) -> (PrivateCookieJar, Json<LogoutResponse>) {
     // Revoke refresh token if present
     if let Some(refresh_cookie) = jar.get("refresh_token")
         && let Err(e) = rt::revoke_refresh_token(&state.pool, refresh_cookie.value()).await
@@ -177,40 +300,66 @@ async fn logout(
 
     (
         jar,
-        Json(serde_json::json!({ "message": "Logged out successfully" })),
+        Json(LogoutResponse {
+            message: "Logged out successfully".to_string(),
+        }),
     )
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 struct UpdateLanguagePreferencesRequest {
+    #[validate(custom(function = "language_code"))]
     native_language: String,
+    #[validate(custom(function = "language_code"))]
     learning_language: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct UpdateLanguagePreferencesResponse {
     message: String,
     user: UserResponse,
 }
 
+/// Set the user's native and learning languages; creates a learning profile for the pair if needed
+#[utoipa::path(
+    patch,
+    path = "/v1/users/me/language-preferences",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    request_body = UpdateLanguagePreferencesRequest,
+    responses(
+        (status = 200, description = "Preferences updated", body = UpdateLanguagePreferencesResponse),
+        (status = 400, description = "Unsupported language code", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
 async fn update_language_preferences(
     auth_user: AuthUser,
     State(state): State<ApiState>,
-    Json(payload): Json<UpdateLanguagePreferencesRequest>,
+    ValidJson(payload): ValidJson<UpdateLanguagePreferencesRequest>,
 ) -> Result<Json<UpdateLanguagePreferencesResponse>, ApiError> {
-    // Validate language codes against the allowed whitelist
-    validation::validate_language_code(&payload.native_language)?;
-    validation::validate_language_code(&payload.learning_language)?;
+    let mut tx = state.pool.begin().await?;
 
     // Update both language preferences
     let updated_user = user_repo::update_language_preferences(
-        &state.pool,
+        &mut *tx,
         auth_user.user_id,
         &payload.native_language,
         &payload.learning_language,
     )
     .await?;
 
+    // The preferred pair always has a learning profile
+    profile_repo::ensure_exists(
+        &mut *tx,
+        auth_user.user_id,
+        &payload.native_language.to_lowercase(),
+        &payload.learning_language.to_lowercase(),
+    )
+    .await?;
+
+    tx.commit().await?;
+
     Ok(Json(UpdateLanguagePreferencesResponse {
         message: "Language preferences updated successfully".to_string(),
         user: updated_user.into(),
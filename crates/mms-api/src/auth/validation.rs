@@ -1,31 +1,36 @@
-use crate::error::ApiError;
-use validator::ValidateEmail;
+//! Account field constraints, used by request types as
+//! `#[validate(custom(function = "validate_email"))]`.
+
+use crate::validation::invalid;
+use validator::{ValidateEmail, ValidationError};
 
 /// Validate email format using the validator crate
-pub fn validate_email(email: &str) -> Result<(), ApiError> {
+pub fn validate_email(email: &str) -> Result<(), ValidationError> {
     if email.is_empty() {
-        return Err(ApiError::Validation("Email cannot be empty".to_string()));
+        return Err(invalid("email", "Email cannot be empty"));
     }
 
     // Use the validator crate for proper email validation
     if !email.validate_email() {
-        return Err(ApiError::Validation("Invalid email format".to_string()));
+        return Err(invalid("email", "Invalid email format"));
     }
 
     Ok(())
 }
 
 /// Validate password strength
-pub fn validate_password(password: &str) -> Result<(), ApiError> {
+pub fn validate_password(password: &str) -> Result<(), ValidationError> {
     if password.len() < 8 {
-        return Err(ApiError::Validation(
-            "Password must be at least 8 characters long".to_string(),
+        return Err(invalid(
+            "password",
+            "Password must be at least 8 characters long",
         ));
     }
 
     if password.len() > 128 {
-        return Err(ApiError::Validation(
-            "Password must be at most 128 characters long".to_string(),
+        return Err(invalid(
+            "password",
+            "Password must be at most 128 characters long",
         ));
     }
 
@@ -34,8 +39,9 @@ pub fn validate_password(password: &str) -> Result<(), ApiError> {
     let has_number = password.chars().any(|c| c.is_numeric());
 
     if !has_letter || !has_number {
-        return Err(ApiError::Validation(
-            "Password must contain at least one letter and one number".to_string(),
+        return Err(invalid(
+            "password",
+            "Password must contain at least one letter and one number",
         ));
     }
 
@@ -43,20 +49,22 @@ pub fn validate_password(password: &str) -> Result<(), ApiError> {
 }
 
 /// Validate username
-pub fn validate_username(username: &str) -> Result<(), ApiError> {
+pub fn validate_username(username: &str) -> Result<(), ValidationError> {
     if username.is_empty() {
-        return Err(ApiError::Validation("Username cannot be empty".to_string()));
+        return Err(invalid("username", "Username cannot be empty"));
     }
 
     if username.len() < 3 {
-        return Err(ApiError::Validation(
-            "Username must be at least 3 characters long".to_string(),
+        return Err(invalid(
+            "username",
+            "Username must be at least 3 characters long",
         ));
     }
 
     if username.len() > 30 {
-        return Err(ApiError::Validation(
-            "Username must be at most 30 characters long".to_string(),
+        return Err(invalid(
+            "username",
+            "Username must be at most 30 characters long",
         ));
     }
 
@@ -66,33 +74,31 @@ pub fn validate_username(username: &str) -> Result<(), ApiError> {
         .chars()
         .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
     {
-        return Err(ApiError::Validation(
-            "Username can only contain letters, numbers, underscores, and hyphens".to_string(),
+        return Err(invalid(
+            "username",
+            "Username can only contain letters, numbers, underscores, and hyphens",
         ));
     }
 
     Ok(())
 }
 
-/// Validate profile picture URL
-/// Only allows HTTPS URLs from trusted domains or data URIs
-pub fn validate_profile_picture_url(url: &str) -> Result<(), ApiError> {
+/// Validate a profile picture URL from an identity provider
+///
+/// Only HTTPS URLs are allowed; pictures users choose themselves are uploaded
+/// as avatars instead.
+pub fn validate_profile_picture_url(url: &str) -> Result<(), ValidationError> {
     if url.is_empty() {
         return Ok(()); // Empty is fine, means no profile picture
     }
 
     // Check length
     if url.len() > 2048 {
-        return Err(ApiError::Validation(
-            "Profile picture URL is too long".to_string(),
-        ));
+        return Err(invalid("url", "Profile picture URL is too long"));
     }
 
-    // Must be HTTPS or data URI (for base64 images)
-    if !url.starts_with("https://") && !url.starts_with("data:image/") {
-        return Err(ApiError::Validation(
-            "Profile picture URL must use HTTPS or be a data URI".to_string(),
-        ));
+    if !url.starts_with("https://") {
+        return Err(invalid("url", "Profile picture URL must use HTTPS"));
     }
 
     // Reject URLs with dangerous patterns
@@ -103,8 +109,9 @@ pub fn validate_profile_picture_url(url: &str) -> Result<(), ApiError> {
         || url_lower.contains("onerror=")
         || url_lower.contains("onload=")
     {
-        return Err(ApiError::Validation(
-            "Profile picture URL contains invalid patterns".to_string(),
+        return Err(invalid(
+            "url",
+            "Profile picture URL contains invalid patterns",
         ));
     }
 
@@ -162,10 +169,11 @@ mod tests {
         // Valid URLs
         assert!(validate_profile_picture_url("").is_ok()); // Empty is fine
         assert!(validate_profile_picture_url("https://example.com/image.jpg").is_ok());
-        assert!(validate_profile_picture_url("data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==").is_ok());
 
         // Invalid URLs
         assert!(validate_profile_picture_url("http://example.com/image.jpg").is_err()); // HTTP not allowed
+        // Uploaded avatars replace data URIs
+        assert!(validate_profile_picture_url("data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==").is_err());
         assert!(validate_profile_picture_url("javascript:alert('xss')").is_err());
         assert!(
             validate_profile_picture_url("data:text/html,<script>alert('xss')</script>").is_err()
@@ -0,0 +1,187 @@
+//! Minimal iCalendar (RFC 5545) writer for all-day events.
+
+use std::fmt::Write;
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+/// Longest content line in octets, excluding the line break
+const MAX_LINE_OCTETS: usize = 75;
+
+#[derive(Debug)]
+pub struct Calendar {
+    /// Shown by calendar apps as the subscription's name
+    pub name: String,
+    /// How often subscribers should re-fetch the feed
+    pub refresh_interval: Duration,
+    pub events: Vec<AllDayEvent>,
+}
+
+#[derive(Debug)]
+pub struct AllDayEvent {
+    /// Stable across fetches so apps update the event instead of duplicating it
+    pub uid: String,
+    pub date: NaiveDate,
+    pub summary: String,
+    pub description: Option<String>,
+    pub url: Option<String>,
+    /// Pop-up reminder this long after the start of the day
+    pub reminder: Option<Duration>,
+}
+
+impl Calendar {
+    /// Render the calendar; `now` is the timestamp every event is stamped with
+    #[must_use]
+    pub fn render(&self, now: DateTime<Utc>) -> String {
+        let mut out = String::new();
+        let refresh = duration(self.refresh_interval);
+        let stamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+
+        line(&mut out, "BEGIN:VCALENDAR");
+        line(&mut out, "VERSION:2.0");
+        line(&mut out, "PRODID:-//Matcha Time//Reviews//EN");
+        line(&mut out, "CALSCALE:GREGORIAN");
+        line(&mut out, "METHOD:PUBLISH");
+        line(&mut out, &format!("X-WR-CALNAME:{}", escape(&self.name)));
+        line(
+            &mut out,
+            &format!("REFRESH-INTERVAL;VALUE=DURATION:{refresh}"),
+        );
+        line(&mut out, &format!("X-PUBLISHED-TTL:{refresh}"));
+
+        for event in &self.events {
+            line(&mut out, "BEGIN:VEVENT");
+            line(&mut out, &format!("UID:{}", escape(&event.uid)));
+            line(&mut out, &format!("DTSTAMP:{stamp}"));
+            line(
+                &mut out,
+                &format!("DTSTART;VALUE=DATE:{}", event.date.format("%Y%m%d")),
+            );
+            if let Some(end) = event.date.succ_opt() {
+                line(
+                    &mut out,
+                    &format!("DTEND;VALUE=DATE:{}", end.format("%Y%m%d")),
+                );
+            }
+            line(&mut out, &format!("SUMMARY:{}", escape(&event.summary)));
+            if let Some(description) = &event.description {
+                line(&mut out, &format!("DESCRIPTION:{}", escape(description)));
+            }
+            if let Some(url) = &event.url {
+                line(&mut out, &format!("URL:{url}"));
+            }
+            // Reviews do not block time in the user's schedule
+            line(&mut out, "TRANSP:TRANSPARENT");
+            if let Some(reminder) = event.reminder {
+                line(&mut out, "BEGIN:VALARM");
+                line(&mut out, "ACTION:DISPLAY");
+                line(&mut out, &format!("DESCRIPTION:{}", escape(&event.summary)));
+                line(&mut out, &format!("TRIGGER:{}", duration(reminder)));
+                line(&mut out, "END:VALARM");
+            }
+            line(&mut out, "END:VEVENT");
+        }
+
+        line(&mut out, "END:VCALENDAR");
+        out
+    }
+}
+
+/// Escape a TEXT value
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A positive duration in `PT#H#M` form
+fn duration(value: Duration) -> String {
+    let minutes = value.num_minutes().max(0);
+    let mut out = String::from("PT");
+    if minutes >= 60 {
+        let _ = write!(out, "{}H", minutes / 60);
+    }
+    if minutes % 60 != 0 || minutes == 0 {
+        let _ = write!(out, "{}M", minutes % 60);
+    }
+    out
+}
+
+/// Append a content line, folding it at 75 octets without splitting a character
+fn line(out: &mut String, content: &str) {
+    let mut width = 0;
+    for c in content.chars() {
+        if width + c.len_utf8() > MAX_LINE_OCTETS {
+            out.push_str("\r\n ");
+            // The leading space of a continuation line counts towards its length
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_all_day_event_with_reminder() {
+        let calendar = Calendar {
+            name: "Reviews".to_string(),
+            refresh_interval: Duration::hours(6),
+            events: vec![AllDayEvent {
+                uid: "due-20261017@matcha-time".to_string(),
+                date: NaiveDate::from_ymd_opt(2026, 10, 17).unwrap(),
+                summary: "12 reviews due".to_string(),
+                description: Some("en → es: 8\nen → fr: 4".to_string()),
+                url: Some("https://matcha-time.app".to_string()),
+                reminder: Some(Duration::hours(9)),
+            }],
+        };
+        let now = DateTime::from_timestamp(1_792_195_200, 0).unwrap();
+        let ics = calendar.render(now);
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.contains("REFRESH-INTERVAL;VALUE=DURATION:PT6H\r\n"));
+        assert!(ics.contains("DTSTAMP:20261017T000000Z\r\n"));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20261017\r\nDTEND;VALUE=DATE:20261018\r\n"));
+        assert!(ics.contains("DESCRIPTION:en → es: 8\\nen → fr: 4\r\n"));
+        assert!(ics.contains("BEGIN:VALARM\r\nACTION:DISPLAY\r\n"));
+        assert!(ics.contains("TRIGGER:PT9H\r\n"));
+    }
+
+    #[test]
+    fn test_escape_text() {
+        assert_eq!(escape("a, b; c\\d\r\ne"), "a\\, b\\; c\\\\d\\ne");
+    }
+
+    #[test]
+    fn test_duration() {
+        assert_eq!(duration(Duration::hours(9)), "PT9H");
+        assert_eq!(duration(Duration::minutes(90)), "PT1H30M");
+        assert_eq!(duration(Duration::zero()), "PT0M");
+    }
+
+    #[test]
+    fn test_long_lines_are_folded_on_character_boundaries() {
+        let mut out = String::new();
+        line(&mut out, &format!("SUMMARY:{}", "é".repeat(60)));
+
+        for folded in out.trim_end_matches("\r\n").split("\r\n") {
+            assert!(folded.len() <= MAX_LINE_OCTETS, "{folded:?} is too long");
+        }
+        let unfolded = out.replace("\r\n ", "");
+        assert_eq!(unfolded, format!("SUMMARY:{}\r\n", "é".repeat(60)));
+    }
+}
@@ -0,0 +1,10 @@
+//! iCal feed of upcoming reviews that users subscribe to from their calendar app.
+//!
+//! Calendar apps fetch the feed without cookies, so it is authorised by a signed
+//! token in the URL (see [`token`]).
+
+pub mod ical;
+pub mod routes;
+pub mod token;
+
+pub use routes::routes;
@@ -0,0 +1,223 @@
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use chrono::{Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    ApiState,
+    auth::AuthUser,
+    calendar::{
+        ical::{AllDayEvent, Calendar},
+        token,
+    },
+    error::{ApiError, ErrorResponse},
+};
+
+use mms_db::models::DueReviewDay;
+use mms_db::repositories::user as user_repo;
+
+/// Days of upcoming reviews included in the feed
+const FEED_DAYS: i32 = 14;
+
+/// How often calendar apps are asked to re-fetch the feed
+const FEED_REFRESH_HOURS: i64 = 1;
+
+/// Reminder time on days with reviews, in the subscriber's local time
+const REMINDER_HOUR: i64 = 9;
+
+/// Create the calendar feed routes
+pub fn routes() -> Router<ApiState> {
+    Router::new()
+        .route(
+            "/users/me/calendar-token",
+            post(create_calendar_token).delete(revoke_calendar_token),
+        )
+        .route("/users/{user_id}/calendar.ics", get(calendar_feed))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct CalendarTokenResponse {
+    token: String,
+    /// Feed path with the token, relative to the API origin
+    path: String,
+}
+
+/// Issue a calendar feed URL; URLs issued before stop working
+#[utoipa::path(
+    post,
+    path = "/v1/users/me/calendar-token",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    responses(
+        (status = 200, description = "New feed token", body = CalendarTokenResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+async fn create_calendar_token(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+) -> Result<Json<CalendarTokenResponse>, ApiError> {
+    let version = user_repo::bump_calendar_token_version(&state.pool, auth_user.user_id).await?;
+    let token = token::issue(auth_user.user_id, version, &state.auth.feed_keys)?;
+
+    Ok(Json(CalendarTokenResponse {
+        path: format!("/v1/users/{}/calendar.ics?token={token}", auth_user.user_id),
+        token,
+    }))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct RevokeCalendarTokenResponse {
+    message: String,
+}
+
+/// Stop serving the calendar feed to every URL issued so far
+#[utoipa::path(
+    delete,
+    path = "/v1/users/me/calendar-token",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    responses(
+        (status = 200, description = "Feed URLs revoked", body = RevokeCalendarTokenResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+async fn revoke_calendar_token(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+) -> Result<Json<RevokeCalendarTokenResponse>, ApiError> {
+    user_repo::bump_calendar_token_version(&state.pool, auth_user.user_id).await?;
+
+    Ok(Json(RevokeCalendarTokenResponse {
+        message: "Calendar feed revoked".to_string(),
+    }))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct FeedQuery {
+    /// Token from `POST /v1/users/me/calendar-token`
+    token: String,
+}
+
+/// iCal feed with one all-day event per day with reviews due over the next two weeks
+#[utoipa::path(
+    get,
+    path = "/v1/users/{user_id}/calendar.ics",
+    tag = "users",
+    params(("user_id" = Uuid, Path), FeedQuery),
+    responses(
+        (status = 200, description = "iCalendar feed", body = String, content_type = "text/calendar"),
+        (status = 401, description = "Invalid or revoked token", body = ErrorResponse),
+    )
+)]
+async fn calendar_feed(
+    State(state): State<ApiState>,
+    Path(user_id): Path<Uuid>,
+    Query(query): Query<FeedQuery>,
+) -> Result<Response, ApiError> {
+    let revoked = || ApiError::Auth("Invalid or revoked calendar token".to_string());
+
+    let (token_user_id, version) =
+        token::verify(&query.token, &state.auth.feed_keys).map_err(|_| revoked())?;
+    if token_user_id != user_id {
+        return Err(revoked());
+    }
+    let current = user_repo::get_calendar_token_version(&state.pool, user_id).await?;
+    if current != Some(version) {
+        return Err(revoked());
+    }
+
+    let due = user_repo::count_due_reviews_by_day(&state.pool, user_id, FEED_DAYS).await?;
+    let calendar = Calendar {
+        name: "Matcha Time reviews".to_string(),
+        refresh_interval: Duration::hours(FEED_REFRESH_HOURS),
+        events: due_events(user_id, &due, &state.oidc.frontend_url),
+    };
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/calendar; charset=utf-8"),
+            (header::CACHE_CONTROL, "private, no-cache"),
+        ],
+        calendar.render(Utc::now()),
+    )
+        .into_response())
+}
+
+/// One event per day, with the per-language-pair breakdown in the description
+fn due_events(user_id: Uuid, due: &[DueReviewDay], frontend_url: &str) -> Vec<AllDayEvent> {
+    let mut events: Vec<(NaiveDate, i64, Vec<String>)> = Vec::new();
+    for row in due {
+        let breakdown = format!("{} → {}: {}", row.language_from, row.language_to, row.cards);
+        match events.last_mut() {
+            Some((date, total, lines)) if *date == row.due_date => {
+                *total += row.cards;
+                lines.push(breakdown);
+            }
+            _ => events.push((row.due_date, row.cards, vec![breakdown])),
+        }
+    }
+
+    events
+        .into_iter()
+        .map(|(date, total, lines)| AllDayEvent {
+            uid: format!("due-{}-{user_id}@matcha-time", date.format("%Y%m%d")),
+            date,
+            summary: match total {
+                1 => "1 review due".to_string(),
+                n => format!("{n} reviews due"),
+            },
+            description: Some(lines.join("\n")),
+            url: Some(frontend_url.to_string()),
+            reminder: Some(Duration::hours(REMINDER_HOUR)),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(date: NaiveDate, language_to: &str, cards: i64) -> DueReviewDay {
+        DueReviewDay {
+            due_date: date,
+            language_from: "en".to_string(),
+            language_to: language_to.to_string(),
+            cards,
+        }
+    }
+
+    #[test]
+    fn test_due_events_group_language_pairs_by_day() {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 17).unwrap();
+        let tomorrow = today.succ_opt().unwrap();
+        let user_id = Uuid::new_v4();
+
+        let events = due_events(
+            user_id,
+            &[
+                day(today, "es", 8),
+                day(today, "fr", 4),
+                day(tomorrow, "es", 1),
+            ],
+            "https://matcha-time.app",
+        );
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].summary, "12 reviews due");
+        assert_eq!(
+            events[0].description.as_deref(),
+            Some("en → es: 8\nen → fr: 4")
+        );
+        assert_eq!(events[0].uid, format!("due-20261017-{user_id}@matcha-time"));
+        assert_eq!(events[1].summary, "1 review due");
+    }
+}
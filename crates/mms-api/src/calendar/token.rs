@@ -0,0 +1,93 @@
+//! Signed tokens authorising a calendar feed URL.
+//!
+//! Tokens are signed with the feed token key rather than the access token keys,
+//! so subscriptions survive access key rotation, and carry their own audience so
+//! neither is accepted in place of an access token. They do not expire: calendar
+//! subscriptions are meant to keep working. Each token carries the user's feed
+//! version and stops working once the version is bumped.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+
+use crate::{auth::jwt::JwtKeys, error::ApiError};
+
+/// Audience of calendar feed tokens
+const AUDIENCE: &str = "calendar";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CalendarClaims {
+    sub: String,
+    aud: String,
+    iat: usize,
+    /// `users.calendar_token_version` when the token was issued
+    ver: i32,
+}
+
+/// Sign a feed token for the user at the given feed version
+pub fn issue(user_id: Uuid, version: i32, keys: &JwtKeys) -> Result<String, ApiError> {
+    keys.sign(&CalendarClaims {
+        sub: user_id.to_string(),
+        aud: AUDIENCE.to_string(),
+        iat: Utc::now().timestamp() as usize,
+        ver: version,
+    })
+}
+
+/// Check the signature and audience; returns the user and feed version the token was issued for
+pub fn verify(token: &str, keys: &JwtKeys) -> Result<(Uuid, i32), ApiError> {
+    let claims: CalendarClaims = keys.verify_with(token, |validation| {
+        validation.set_audience(&[AUDIENCE]);
+        validation.set_required_spec_claims(&["aud", "sub"]);
+        validation.validate_exp = false;
+    })?;
+
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| ApiError::Auth("Invalid or expired token".to_string()))?;
+    Ok((user_id, claims.ver))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{Role, jwt};
+
+    const SECRET: &str = "test_jwt_secret_minimum_32_characters_long";
+
+    #[test]
+    fn test_issue_and_verify() {
+        let keys = JwtKeys::hmac(SECRET);
+        let user_id = Uuid::new_v4();
+
+        let token = issue(user_id, 3, &keys).unwrap();
+
+        assert_eq!(verify(&token, &keys).unwrap(), (user_id, 3));
+    }
+
+    #[test]
+    fn test_tokens_are_not_interchangeable_with_access_tokens() {
+        let keys = JwtKeys::hmac(SECRET);
+        let user_id = Uuid::new_v4();
+
+        let access =
+            jwt::generate_jwt_token(user_id, "a@example.com".into(), Role::Learner, &keys, 1)
+                .unwrap();
+        assert!(verify(&access, &keys).is_err());
+
+        let calendar = issue(user_id, 1, &keys).unwrap();
+        assert!(jwt::verify_jwt_token(&calendar, &keys).is_err());
+    }
+
+    #[test]
+    fn test_rejects_other_keys() {
+        let token = issue(Uuid::new_v4(), 1, &JwtKeys::hmac(SECRET)).unwrap();
+
+        assert!(
+            verify(
+                &token,
+                &JwtKeys::hmac("another_secret_minimum_32_characters_long")
+            )
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,164 @@
+//! Captcha verification for abuse-prone endpoints.
+//!
+//! Verification goes through the [`CaptchaVerifier`] trait so handlers do not
+//! care which provider is configured, and tests can plug in a stub. When captcha
+//! is disabled, [`require_captcha`] is a no-op.
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::error::ApiError;
+
+/// Boxed future returned by [`CaptchaVerifier::verify`]
+pub type VerifyFuture<'a> = Pin<Box<dyn Future<Output = Result<bool, ApiError>> + Send + 'a>>;
+
+/// Verifies a captcha response token produced by the frontend widget
+pub trait CaptchaVerifier: Send + Sync + fmt::Debug {
+    /// Returns `Ok(true)` if the token is valid, `Ok(false)` if the provider rejected it
+    fn verify<'a>(&'a self, token: &'a str) -> VerifyFuture<'a>;
+}
+
+/// Supported captcha providers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptchaProvider {
+    Hcaptcha,
+    Turnstile,
+}
+
+impl CaptchaProvider {
+    const fn verify_url(self) -> &'static str {
+        match self {
+            Self::Hcaptcha => "https://api.hcaptcha.com/siteverify",
+            Self::Turnstile => "https://challenges.cloudflare.com/turnstile/v0/siteverify",
+        }
+    }
+}
+
+/// Verifier calling the provider's `siteverify` endpoint.
+///
+/// hCaptcha and Cloudflare Turnstile share the same request and response shape.
+#[derive(Clone)]
+pub struct HttpCaptchaVerifier {
+    provider: CaptchaProvider,
+    secret: Arc<str>,
+    client: reqwest::Client,
+}
+
+impl fmt::Debug for HttpCaptchaVerifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpCaptchaVerifier")
+            .field("provider", &self.provider)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Deserialize)]
+struct SiteVerifyResponse {
+    success: bool,
+    #[serde(default, rename = "error-codes")]
+    error_codes: Vec<String>,
+}
+
+impl HttpCaptchaVerifier {
+    #[must_use]
+    pub fn new(provider: CaptchaProvider, secret: &str) -> Self {
+        Self {
+            provider,
+            secret: secret.into(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn verify_token(&self, token: &str) -> Result<bool, ApiError> {
+        let response = self
+            .client
+            .post(self.provider.verify_url())
+            .form(&[("secret", &*self.secret), ("response", token)])
+            .send()
+            .await
+            .map_err(|e| ApiError::Captcha(format!("Captcha provider unreachable: {e}")))?;
+
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| ApiError::Captcha(format!("Invalid captcha provider response: {e}")))?;
+        let result: SiteVerifyResponse = serde_json::from_slice(&body)
+            .map_err(|e| ApiError::Captcha(format!("Invalid captcha provider response: {e}")))?;
+
+        if !result.success {
+            tracing::debug!(errors = ?result.error_codes, "Captcha token rejected");
+        }
+
+        Ok(result.success)
+    }
+}
+
+impl CaptchaVerifier for HttpCaptchaVerifier {
+    fn verify<'a>(&'a self, token: &'a str) -> VerifyFuture<'a> {
+        Box::pin(self.verify_token(token))
+    }
+}
+
+/// Require a valid captcha token when a verifier is configured.
+///
+/// Missing and rejected tokens both fail with the same validation error so the
+/// response does not reveal which check failed.
+pub async fn require_captcha(
+    verifier: Option<&dyn CaptchaVerifier>,
+    token: Option<&str>,
+) -> Result<(), ApiError> {
+    let Some(verifier) = verifier else {
+        return Ok(());
+    };
+
+    let token = token
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| ApiError::Validation("Captcha verification failed".to_string()))?;
+
+    if verifier.verify(token).await? {
+        Ok(())
+    } else {
+        Err(ApiError::Validation(
+            "Captcha verification failed".to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct AcceptToken(&'static str);
+
+    impl CaptchaVerifier for AcceptToken {
+        fn verify<'a>(&'a self, token: &'a str) -> VerifyFuture<'a> {
+            Box::pin(async move { Ok(token == self.0) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_disabled_captcha_always_passes() {
+        assert!(require_captcha(None, None).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_missing_token_is_rejected() {
+        let verifier = AcceptToken("good");
+        assert!(require_captcha(Some(&verifier), None).await.is_err());
+        assert!(require_captcha(Some(&verifier), Some("  ")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_token_checked_by_verifier() {
+        let verifier = AcceptToken("good");
+        assert!(require_captcha(Some(&verifier), Some("good")).await.is_ok());
+        assert!(require_captcha(Some(&verifier), Some("bad")).await.is_err());
+    }
+}
@@ -0,0 +1,13 @@
+//! Crash reports from the web and mobile apps.
+//!
+//! Clients post a report to `/client-errors` tagged with the `X-Request-ID` of
+//! the API response they were handling when they failed. The report is stored
+//! and logged with that id as `client_request_id`, so it can be found next to
+//! the server logs of the request; support looks reports up by request id on
+//! the admin routes. A [`Sampler`] keeps a crash loop from flooding the table.
+
+pub mod routes;
+pub mod sampler;
+
+pub use routes::routes;
+pub use sampler::Sampler;
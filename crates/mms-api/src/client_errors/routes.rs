@@ -0,0 +1,186 @@
+use axum::{
+    Router,
+    extract::State,
+    http::{HeaderMap, StatusCode, header},
+    routing::post,
+};
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
+
+use mms_db::{models::NewClientError, repositories::client_error as client_error_repo};
+
+use crate::{
+    ApiState,
+    auth::AuthUser,
+    error::{ApiError, ErrorResponse},
+    middleware::rate_limit,
+    validation::{ValidJson, invalid},
+};
+
+/// Longest error message kept; longer ones are cut
+const MAX_MESSAGE_CHARS: usize = 2_000;
+
+/// Longest stack trace kept; longer ones are cut
+const MAX_STACK_CHARS: usize = 16_000;
+
+/// Longest page, app version or user agent kept; longer ones are cut
+const MAX_FIELD_CHARS: usize = 512;
+
+/// Longest request id accepted; the API issues UUIDs
+const MAX_REQUEST_ID_CHARS: usize = 128;
+
+/// Create the client error reporting routes
+pub fn routes() -> Router<ApiState> {
+    use crate::make_rate_limit_layer;
+
+    Router::new()
+        .route("/client-errors", post(report_client_error))
+        .layer(make_rate_limit_layer!(
+            rate_limit::GENERAL_RATE_PER_SECOND,
+            rate_limit::GENERAL_BURST_SIZE
+        ))
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+enum ClientPlatform {
+    Web,
+    Ios,
+    Android,
+}
+
+impl ClientPlatform {
+    fn as_str(self) -> &'static str {
+        match self {
+            ClientPlatform::Web => "web",
+            ClientPlatform::Ios => "ios",
+            ClientPlatform::Android => "android",
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+struct ClientErrorReport {
+    /// `X-Request-ID` of the API response the client was handling, if any
+    #[validate(custom(function = "validate_request_id"))]
+    request_id: Option<String>,
+    platform: ClientPlatform,
+    app_version: Option<String>,
+    #[validate(custom(function = "validate_message"))]
+    message: String,
+    stack: Option<String>,
+    /// Page or screen the error happened on; the query string is dropped
+    page: Option<String>,
+}
+
+fn validate_message(message: &str) -> Result<(), ValidationError> {
+    if message.trim().is_empty() {
+        return Err(invalid("required", "Message is required"));
+    }
+    Ok(())
+}
+
+fn validate_request_id(id: &str) -> Result<(), ValidationError> {
+    let id = id.trim();
+    if id.is_empty()
+        || id.len() > MAX_REQUEST_ID_CHARS
+        || !id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(invalid("request_id", "Invalid request id"));
+    }
+    Ok(())
+}
+
+/// The first `max` characters of `value`
+fn truncate(value: &str, max: usize) -> &str {
+    value
+        .char_indices()
+        .nth(max)
+        .map_or(value, |(end, _)| &value[..end])
+}
+
+/// Report a crash in the web or mobile app
+///
+/// Reports are sampled, so a `202` does not mean this one was stored.
+/// Signed-in users' reports are linked to their account.
+#[utoipa::path(
+    post,
+    path = "/v1/client-errors",
+    tag = "client-errors",
+    request_body = ClientErrorReport,
+    responses(
+        (status = 202, description = "Report received"),
+        (status = 400, description = "Empty message or malformed request id", body = ErrorResponse),
+        (status = 429, description = "Too many reports from this address", body = ErrorResponse),
+    )
+)]
+async fn report_client_error(
+    State(state): State<ApiState>,
+    auth_user: Result<AuthUser, ApiError>,
+    headers: HeaderMap,
+    ValidJson(report): ValidJson<ClientErrorReport>,
+) -> Result<StatusCode, ApiError> {
+    let message = truncate(report.message.trim(), MAX_MESSAGE_CHARS);
+    let request_id = report.request_id.as_deref().map(str::trim);
+
+    if !state.client_errors.admit() {
+        return Ok(StatusCode::ACCEPTED);
+    }
+
+    let user_id = auth_user.ok().map(|user| user.user_id);
+    // Links in apps carry tokens in the query string, so only the path is kept
+    let page = report
+        .page
+        .as_deref()
+        .and_then(|page| page.split(['?', '#']).next())
+        .map(|page| truncate(page, MAX_FIELD_CHARS));
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| truncate(value, MAX_FIELD_CHARS));
+
+    let id = client_error_repo::insert(
+        &state.pool,
+        &NewClientError {
+            request_id,
+            user_id,
+            platform: report.platform.as_str(),
+            app_version: report
+                .app_version
+                .as_deref()
+                .map(|version| truncate(version, MAX_FIELD_CHARS)),
+            message,
+            stack: report
+                .stack
+                .as_deref()
+                .map(|stack| truncate(stack, MAX_STACK_CHARS)),
+            page,
+            user_agent,
+        },
+    )
+    .await?;
+
+    tracing::warn!(
+        client_error_id = %id,
+        client_request_id = request_id.unwrap_or("-"),
+        platform = report.platform.as_str(),
+        "Client reported an error: {message}"
+    );
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_respects_char_boundaries() {
+        assert_eq!(truncate("hello", 10), "hello");
+        assert_eq!(truncate("hello", 2), "he");
+        assert_eq!(truncate("ñandú", 3), "ñan");
+    }
+}
@@ -0,0 +1,99 @@
+use std::{
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+/// Length of the window the hourly limit counts reports in
+const WINDOW: Duration = Duration::from_secs(3600);
+
+/// Default share of reports kept
+pub const DEFAULT_SAMPLE_RATE: f64 = 1.0;
+
+/// Default number of reports kept per hour
+pub const DEFAULT_HOURLY_LIMIT: u32 = 1_000;
+
+/// Decides which client error reports are stored.
+///
+/// A report is kept with probability `sample_rate`, and at most `hourly_limit`
+/// are kept per hour. Counts live in memory, so the limit applies per instance.
+#[derive(Debug, Clone)]
+pub struct Sampler(Arc<Inner>);
+
+#[derive(Debug)]
+struct Inner {
+    sample_rate: f64,
+    hourly_limit: u32,
+    window: Mutex<Window>,
+}
+
+#[derive(Debug)]
+struct Window {
+    started: Instant,
+    kept: u32,
+}
+
+impl Sampler {
+    #[must_use]
+    pub fn new(sample_rate: f64, hourly_limit: u32) -> Self {
+        Self(Arc::new(Inner {
+            sample_rate,
+            hourly_limit,
+            window: Mutex::new(Window {
+                started: Instant::now(),
+                kept: 0,
+            }),
+        }))
+    }
+
+    /// Whether to store the next report
+    pub fn admit(&self) -> bool {
+        if self.0.sample_rate < 1.0 && rand::random::<f64>() >= self.0.sample_rate {
+            return false;
+        }
+
+        let mut window = self.0.window.lock().unwrap_or_else(PoisonError::into_inner);
+        if window.started.elapsed() >= WINDOW {
+            window.started = Instant::now();
+            window.kept = 0;
+        }
+        if window.kept >= self.0.hourly_limit {
+            return false;
+        }
+        window.kept += 1;
+        true
+    }
+}
+
+impl Default for Sampler {
+    fn default() -> Self {
+        Self::new(DEFAULT_SAMPLE_RATE, DEFAULT_HOURLY_LIMIT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hourly_limit_caps_kept_reports() {
+        let sampler = Sampler::new(1.0, 3);
+        let kept = (0..10).filter(|_| sampler.admit()).count();
+        assert_eq!(kept, 3);
+    }
+
+    #[test]
+    fn test_zero_sample_rate_keeps_nothing() {
+        let sampler = Sampler::new(0.0, 100);
+        assert!((0..100).all(|_| !sampler.admit()));
+    }
+
+    #[test]
+    fn test_window_resets_after_an_hour() {
+        let sampler = Sampler::new(1.0, 1);
+        assert!(sampler.admit());
+        assert!(!sampler.admit());
+
+        sampler.0.window.lock().unwrap().started = Instant::now() - WINDOW;
+        assert!(sampler.admit());
+    }
+}
@@ -1,5 +1,18 @@
+use std::time::Duration;
+
+use mms_db::PoolSettings;
+use mms_db::encryption::{EncryptionError, Keyring};
 use serde::Deserialize;
 
+use crate::auth::jwt::JwtAlgorithm;
+use crate::captcha::CaptchaProvider;
+use crate::jobs::schedule::JobSchedules;
+use crate::mailer::{EmailProvider, FromAddress};
+use crate::media::MediaProvider;
+use crate::middleware::rate_limit::RateLimitBackend;
+use crate::speech::SttProvider;
+use crate::tracing::LogFormat;
+
 /// Environment mode for the application
 #[derive(Default, Clone, Debug, PartialEq, Eq, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -39,6 +52,41 @@ pub struct ApiConfig {
     pub jwt_secret: String,
     pub cookie_secret: String,
 
+    /// Comma-separated secrets from previous rotations, still accepted when
+    /// verifying access tokens until those tokens expire
+    #[serde(default)]
+    pub jwt_previous_secrets: String,
+
+    /// Algorithm for signing access tokens: "HS256" (default), "RS256" or "EdDSA"
+    #[serde(default)]
+    pub jwt_algorithm: JwtAlgorithm,
+    /// PEM private key used to sign tokens with RS256 or EdDSA
+    pub jwt_private_key: Option<String>,
+    /// PEM public key matching `jwt_private_key`
+    pub jwt_public_key: Option<String>,
+    /// PEM public key of the previous key pair, still accepted for verification
+    pub jwt_previous_public_key: Option<String>,
+    /// Identifier placed in the `kid` header of new tokens. When unset, HS256
+    /// keys get a random id at startup and key pairs one derived from the public key
+    pub jwt_key_id: Option<String>,
+
+    /// Secret signing calendar feed, widget and email preferences tokens. Kept
+    /// apart from the access token keys so subscribed feeds, embedded badges and
+    /// unsubscribe links survive their rotation; falls back to `JWT_SECRET` when unset
+    pub feed_token_secret: Option<String>,
+
+    /// Base64 AES-256 key for encrypting PII columns at rest (optional)
+    pub pii_encryption_key: Option<String>,
+    /// Comma-separated keys from previous rotations, still used for decryption
+    #[serde(default)]
+    pub pii_encryption_previous_keys: String,
+
+    /// Bearer token for maintenance endpoints under `/v1/admin` (optional)
+    pub admin_api_token: Option<String>,
+
+    /// Shared secret expected as `?token=` on email provider webhooks (optional)
+    pub email_webhook_secret: Option<String>,
+
     /// Bcrypt cost factor for password hashing (default: 10)
     /// Higher values are more secure but slower (each increment doubles the time)
     /// Recommended: 10 (fast, ~100ms), 11 (medium, ~200ms), 12 (secure, ~400ms)
@@ -53,17 +101,56 @@ pub struct ApiConfig {
     #[serde(default = "default_refresh_token_expiry_days")]
     pub refresh_token_expiry_days: i64,
 
+    /// Whether each refresh restarts the refresh token's lifetime (default: true)
+    #[serde(default = "default_refresh_token_sliding")]
+    pub refresh_token_sliding: bool,
+
+    /// Maximum session age in days, however often it is refreshed (default: 90)
+    #[serde(default = "default_refresh_token_max_session_days")]
+    pub refresh_token_max_session_days: i64,
+
     /// OIDC flow cookie expiry in minutes (default: 10)
     #[serde(default = "default_oidc_flow_expiry_minutes")]
     pub oidc_flow_expiry_minutes: i64,
 
-    // Email / SMTP (optional)
+    // Email (optional)
+    /// Email provider: "smtp", "sendgrid", "ses" or "console" (default: smtp when SMTP_HOST is set)
+    pub email_provider: Option<EmailProvider>,
+    /// Sender address for every provider (falls back to SMTP_FROM_EMAIL)
+    pub email_from: Option<String>,
+    /// Sender display name (falls back to SMTP_FROM_NAME, then "Matcha Time")
+    pub email_from_name: Option<String>,
+
     pub smtp_host: Option<String>,
     pub smtp_username: Option<String>,
     pub smtp_password: Option<String>,
     pub smtp_from_email: Option<String>,
     pub smtp_from_name: Option<String>,
 
+    pub sendgrid_api_key: Option<String>,
+
+    pub ses_region: Option<String>,
+    pub ses_access_key_id: Option<String>,
+    pub ses_secret_access_key: Option<String>,
+
+    // Captcha (optional)
+    /// Require captcha on registration, password reset requests and repeated failed logins
+    #[serde(default)]
+    pub captcha_enabled: bool,
+    /// Captcha provider: "hcaptcha" or "turnstile"
+    pub captcha_provider: Option<CaptchaProvider>,
+    pub captcha_secret: Option<String>,
+
+    /// Consecutive failed logins after which login requires a captcha (default: 5)
+    #[serde(default = "default_captcha_login_failure_threshold")]
+    pub captcha_login_failure_threshold: i32,
+
+    // Speech-to-text (optional)
+    /// Provider transcribing pronunciation practice recordings: "deepgram";
+    /// pronunciation practice is unavailable without one
+    pub stt_provider: Option<SttProvider>,
+    pub stt_api_key: Option<String>,
+
     // Database
     pub database_url: String,
 
@@ -71,6 +158,33 @@ pub struct ApiConfig {
     #[serde(default = "default_database_max_connections")]
     pub database_max_connections: u32,
 
+    /// Connections each pool keeps open while idle (default: 1)
+    #[serde(default = "default_database_min_connections")]
+    pub database_min_connections: u32,
+
+    /// Seconds a query waits for a free connection before failing (default: 5)
+    #[serde(default = "default_database_acquire_timeout_seconds")]
+    pub database_acquire_timeout_seconds: u64,
+
+    /// Seconds before an idle connection above the minimum is closed, 0 keeps them (default: 600)
+    #[serde(default = "default_database_idle_timeout_seconds")]
+    pub database_idle_timeout_seconds: u64,
+
+    /// Milliseconds after which Postgres cancels a statement, 0 keeps the server's setting (default: 0)
+    #[serde(default)]
+    pub database_statement_timeout_ms: u64,
+
+    /// Name the connections report in `pg_stat_activity` (default: mms-api)
+    #[serde(default = "default_database_application_name")]
+    pub database_application_name: String,
+
+    /// Seconds to keep trying to reach the database at startup before giving up (default: 30)
+    #[serde(default = "default_database_connect_timeout_seconds")]
+    pub database_connect_timeout_seconds: u64,
+
+    /// Read replica connection URL; reads that tolerate lag go there when set
+    pub database_replica_url: Option<String>,
+
     // Server Configuration
     /// Port to run the server on (default: 3000)
     #[serde(default = "default_port")]
@@ -97,6 +211,105 @@ pub struct ApiConfig {
     #[serde(default = "default_rate_limit_burst_size")]
     pub rate_limit_burst_size: u32,
 
+    /// Where rate limit buckets are kept: "memory" (default, per replica) or
+    /// "redis" (shared by every replica)
+    #[serde(default)]
+    pub rate_limit_backend: RateLimitBackend,
+
+    /// Redis connection URL, required when RATE_LIMIT_BACKEND is redis
+    pub redis_url: Option<String>,
+
+    // Load Shedding
+    /// Maximum concurrent requests for standard routes (default: 512)
+    #[serde(default = "default_load_shed_max_concurrency")]
+    pub load_shed_max_concurrency: usize,
+
+    /// Maximum standard requests waiting for a slot before shedding (default: 256)
+    #[serde(default = "default_load_shed_max_queue")]
+    pub load_shed_max_queue: usize,
+
+    /// Maximum concurrent requests for credential routes doing bcrypt work (default: 32)
+    #[serde(default = "default_load_shed_auth_max_concurrency")]
+    pub load_shed_auth_max_concurrency: usize,
+
+    /// Maximum credential requests waiting for a slot before shedding (default: 64)
+    #[serde(default = "default_load_shed_auth_max_queue")]
+    pub load_shed_auth_max_queue: usize,
+
+    /// How long a queued request may wait for a slot, in milliseconds (default: 2000)
+    #[serde(default = "default_load_shed_queue_timeout_ms")]
+    pub load_shed_queue_timeout_ms: u64,
+
+    // Request Bodies
+    /// Largest request body accepted, in bytes (default: 1 MiB)
+    #[serde(default = "default_max_request_body_bytes")]
+    pub max_request_body_bytes: usize,
+
+    /// Largest body accepted by content and known-word imports, in bytes (default: 64 MiB)
+    #[serde(default = "default_max_import_body_bytes")]
+    pub max_import_body_bytes: usize,
+
+    /// Largest avatar upload accepted, in bytes (default: 5 MiB)
+    #[serde(default = "default_max_avatar_body_bytes")]
+    pub max_avatar_body_bytes: usize,
+
+    // Media
+    /// Where uploaded media such as avatars is kept: "database" (default) or
+    /// "local" (files under MEDIA_DIR)
+    #[serde(default)]
+    pub media_provider: MediaProvider,
+
+    /// Directory for media files, required when MEDIA_PROVIDER is local
+    pub media_dir: Option<String>,
+
+    /// Seconds to keep serving after a shutdown signal while readiness fails,
+    /// giving load balancers time to deregister the instance (default: 5)
+    #[serde(default = "default_shutdown_drain_seconds")]
+    pub shutdown_drain_seconds: u64,
+
+    /// Hours before each user's local midnight from which a streak at risk
+    /// triggers a reminder, 1 to 23 (default: 4)
+    #[serde(default = "default_streak_reminder_hours_before_midnight")]
+    pub streak_reminder_hours_before_midnight: u32,
+
+    /// Share of client error reports stored, 0.0 to 1.0 (default: 1.0)
+    #[serde(default = "default_client_error_sample_rate")]
+    pub client_error_sample_rate: f64,
+
+    /// Client error reports stored per hour at most, per instance (default: 1000)
+    #[serde(default = "default_client_error_hourly_limit")]
+    pub client_error_hourly_limit: u32,
+
+    // Job Schedules
+    /// Cron expression, in UTC, for deleting expired tokens (default: every 6 hours)
+    #[serde(default = "default_job_schedule_token_cleanup")]
+    pub job_schedule_token_cleanup: String,
+
+    /// Cron expression, in UTC, for deleting accounts never verified (default: daily at 02:00)
+    #[serde(default = "default_job_schedule_unverified_accounts_cleanup")]
+    pub job_schedule_unverified_accounts_cleanup: String,
+
+    /// Cron expression, in UTC, for purging accounts deleted more than 30 days ago
+    /// (default: daily at 02:30)
+    #[serde(default = "default_job_schedule_deleted_accounts_purge")]
+    pub job_schedule_deleted_accounts_purge: String,
+
+    /// Cron expression, in UTC, for correcting drifted deck progress (default: daily at 03:00)
+    #[serde(default = "default_job_schedule_deck_progress")]
+    pub job_schedule_deck_progress: String,
+
+    /// Cron expression, in UTC, for deleting expired practice sessions (default: hourly)
+    #[serde(default = "default_job_schedule_practice_session_cleanup")]
+    pub job_schedule_practice_session_cleanup: String,
+
+    /// Longest random delay, in seconds, each replica adds before queueing a
+    /// scheduled job, so replicas do not all queue at once (default: 300)
+    #[serde(default = "default_job_schedule_jitter_seconds")]
+    pub job_schedule_jitter_seconds: u64,
+
+    /// Log output: "pretty" or "json" (default: pretty in development, json otherwise)
+    pub log_format: Option<LogFormat>,
+
     /// Environment mode (development/production)
     #[serde(default)]
     pub env: Environment,
@@ -122,11 +335,41 @@ fn default_rate_limit_burst_size() -> u32 {
     100
 }
 
+/// Default value for captcha_login_failure_threshold
+fn default_captcha_login_failure_threshold() -> i32 {
+    5
+}
+
 /// Default value for database_max_connections
 fn default_database_max_connections() -> u32 {
     10
 }
 
+/// Default value for database_min_connections
+fn default_database_min_connections() -> u32 {
+    1
+}
+
+/// Default value for database_acquire_timeout_seconds
+fn default_database_acquire_timeout_seconds() -> u64 {
+    5
+}
+
+/// Default value for database_idle_timeout_seconds
+fn default_database_idle_timeout_seconds() -> u64 {
+    600
+}
+
+/// Default value for database_application_name
+fn default_database_application_name() -> String {
+    "mms-api".to_string()
+}
+
+/// Default value for database_connect_timeout_seconds
+fn default_database_connect_timeout_seconds() -> u64 {
+    30
+}
+
 /// Default value for port
 fn default_port() -> u16 {
     3000
@@ -142,11 +385,111 @@ fn default_refresh_token_expiry_days() -> i64 {
     30
 }
 
+/// Default value for sliding refresh token renewal
+fn default_refresh_token_sliding() -> bool {
+    true
+}
+
+/// Default value for the maximum session age (90 days)
+fn default_refresh_token_max_session_days() -> i64 {
+    90
+}
+
 /// Default value for OIDC flow cookie expiry (10 minutes)
 fn default_oidc_flow_expiry_minutes() -> i64 {
     10
 }
 
+/// Default value for load_shed_max_concurrency
+fn default_load_shed_max_concurrency() -> usize {
+    512
+}
+
+/// Default value for load_shed_max_queue
+fn default_load_shed_max_queue() -> usize {
+    256
+}
+
+/// Default value for load_shed_auth_max_concurrency
+fn default_load_shed_auth_max_concurrency() -> usize {
+    32
+}
+
+/// Default value for load_shed_auth_max_queue
+fn default_load_shed_auth_max_queue() -> usize {
+    64
+}
+
+/// Default value for load_shed_queue_timeout_ms
+fn default_load_shed_queue_timeout_ms() -> u64 {
+    2000
+}
+
+/// Default value for max_request_body_bytes
+fn default_max_request_body_bytes() -> usize {
+    1024 * 1024
+}
+
+/// Default value for max_import_body_bytes
+fn default_max_import_body_bytes() -> usize {
+    64 * 1024 * 1024
+}
+
+/// Default value for max_avatar_body_bytes
+fn default_max_avatar_body_bytes() -> usize {
+    5 * 1024 * 1024
+}
+
+/// Default value for shutdown_drain_seconds
+fn default_shutdown_drain_seconds() -> u64 {
+    5
+}
+
+/// Default value for streak_reminder_hours_before_midnight
+fn default_streak_reminder_hours_before_midnight() -> u32 {
+    4
+}
+
+/// Default value for client_error_sample_rate
+fn default_client_error_sample_rate() -> f64 {
+    crate::client_errors::sampler::DEFAULT_SAMPLE_RATE
+}
+
+/// Default value for client_error_hourly_limit
+fn default_client_error_hourly_limit() -> u32 {
+    crate::client_errors::sampler::DEFAULT_HOURLY_LIMIT
+}
+
+/// Default value for job_schedule_token_cleanup
+fn default_job_schedule_token_cleanup() -> String {
+    crate::jobs::schedule::DEFAULT_TOKEN_CLEANUP.to_string()
+}
+
+/// Default value for job_schedule_unverified_accounts_cleanup
+fn default_job_schedule_unverified_accounts_cleanup() -> String {
+    crate::jobs::schedule::DEFAULT_UNVERIFIED_ACCOUNTS_CLEANUP.to_string()
+}
+
+/// Default value for job_schedule_deleted_accounts_purge
+fn default_job_schedule_deleted_accounts_purge() -> String {
+    crate::jobs::schedule::DEFAULT_DELETED_ACCOUNTS_PURGE.to_string()
+}
+
+/// Default value for job_schedule_deck_progress
+fn default_job_schedule_deck_progress() -> String {
+    crate::jobs::schedule::DEFAULT_DECK_PROGRESS.to_string()
+}
+
+/// Default value for job_schedule_practice_session_cleanup
+fn default_job_schedule_practice_session_cleanup() -> String {
+    crate::jobs::schedule::DEFAULT_PRACTICE_SESSION_CLEANUP.to_string()
+}
+
+/// Default value for job_schedule_jitter_seconds
+fn default_job_schedule_jitter_seconds() -> u64 {
+    crate::jobs::schedule::DEFAULT_JITTER.as_secs()
+}
+
 /// Custom error type for configuration
 #[derive(Debug, thiserror::Error)]
 pub enum ConfigError {
@@ -199,6 +542,74 @@ impl ApiConfig {
             ));
         }
 
+        // Previous secrets are still trusted, so hold them to the same bar
+        if self
+            .parsed_jwt_previous_secrets()
+            .iter()
+            .any(|secret| secret.len() < 32)
+        {
+            return Err(ConfigError::ValidationError(
+                "Each JWT_PREVIOUS_SECRETS entry must be at least 32 characters long".to_string(),
+            ));
+        }
+
+        if self
+            .feed_token_secret
+            .as_deref()
+            .is_some_and(|secret| !secret.is_empty() && secret.len() < 32)
+        {
+            return Err(ConfigError::ValidationError(
+                "FEED_TOKEN_SECRET must be at least 32 characters long".to_string(),
+            ));
+        }
+
+        // Asymmetric signing needs both halves of the key pair
+        if self.jwt_algorithm != JwtAlgorithm::Hs256
+            && (self.jwt_private_key.as_deref().is_none_or(str::is_empty)
+                || self.jwt_public_key.as_deref().is_none_or(str::is_empty))
+        {
+            return Err(ConfigError::ValidationError(
+                "JWT_PRIVATE_KEY and JWT_PUBLIC_KEY are required when JWT_ALGORITHM is RS256 or EdDSA"
+                    .to_string(),
+            ));
+        }
+
+        // Catch malformed keys at startup rather than on the first write
+        if let Err(e) = self.pii_keyring() {
+            return Err(ConfigError::ValidationError(format!(
+                "PII_ENCRYPTION_KEY and PII_ENCRYPTION_PREVIOUS_KEYS: {e}"
+            )));
+        }
+        if self.pii_encryption_key.as_deref().is_none_or(str::is_empty)
+            && !self.pii_encryption_previous_keys.trim().is_empty()
+        {
+            return Err(ConfigError::ValidationError(
+                "PII_ENCRYPTION_PREVIOUS_KEYS requires PII_ENCRYPTION_KEY".to_string(),
+            ));
+        }
+
+        // The admin token guards database internals, so require a real secret
+        if self
+            .admin_api_token
+            .as_deref()
+            .is_some_and(|token| token.len() < 32)
+        {
+            return Err(ConfigError::ValidationError(
+                "ADMIN_API_TOKEN must be at least 32 characters long".to_string(),
+            ));
+        }
+
+        // Anyone holding the webhook secret can stop email to any address
+        if self
+            .email_webhook_secret
+            .as_deref()
+            .is_some_and(|secret| secret.len() < 32)
+        {
+            return Err(ConfigError::ValidationError(
+                "EMAIL_WEBHOOK_SECRET must be at least 32 characters long".to_string(),
+            ));
+        }
+
         // Validate cookie secret length
         if self.cookie_secret.len() < 64 {
             return Err(ConfigError::ValidationError(
@@ -226,9 +637,192 @@ impl ApiConfig {
             ));
         }
 
+        // Captcha needs a provider and a secret once enabled
+        if self.captcha_enabled
+            && (self.captcha_provider.is_none()
+                || self.captcha_secret.as_deref().is_none_or(str::is_empty))
+        {
+            return Err(ConfigError::ValidationError(
+                "CAPTCHA_PROVIDER and CAPTCHA_SECRET are required when CAPTCHA_ENABLED is true"
+                    .to_string(),
+            ));
+        }
+
+        if self.stt_provider.is_some() && self.stt_api_key.as_deref().is_none_or(str::is_empty) {
+            return Err(ConfigError::ValidationError(
+                "STT_API_KEY is required when STT_PROVIDER is set".to_string(),
+            ));
+        }
+
+        // A pool that cannot hold its own minimum, or never waits, is a typo
+        if self.database_max_connections == 0
+            || self.database_min_connections > self.database_max_connections
+        {
+            return Err(ConfigError::ValidationError(
+                "DATABASE_MAX_CONNECTIONS must be greater than 0 and at least DATABASE_MIN_CONNECTIONS"
+                    .to_string(),
+            ));
+        }
+        if self.database_acquire_timeout_seconds == 0 {
+            return Err(ConfigError::ValidationError(
+                "DATABASE_ACQUIRE_TIMEOUT_SECONDS must be greater than 0".to_string(),
+            ));
+        }
+
+        // Buckets shared between replicas need somewhere to live
+        if self.rate_limit_backend == RateLimitBackend::Redis
+            && self.redis_url.as_deref().is_none_or(str::is_empty)
+        {
+            return Err(ConfigError::ValidationError(
+                "REDIS_URL is required when RATE_LIMIT_BACKEND is redis".to_string(),
+            ));
+        }
+
+        // Zero concurrency would shed every request
+        if self.load_shed_max_concurrency == 0 || self.load_shed_auth_max_concurrency == 0 {
+            return Err(ConfigError::ValidationError(
+                "LOAD_SHED_MAX_CONCURRENCY and LOAD_SHED_AUTH_MAX_CONCURRENCY must be greater than 0"
+                    .to_string(),
+            ));
+        }
+
+        // A zero limit would refuse every body; imports are the routes allowed more
+        if self.max_request_body_bytes == 0
+            || self.max_import_body_bytes < self.max_request_body_bytes
+        {
+            return Err(ConfigError::ValidationError(
+                "MAX_REQUEST_BODY_BYTES must be greater than 0 and at most MAX_IMPORT_BODY_BYTES"
+                    .to_string(),
+            ));
+        }
+
+        if self.max_avatar_body_bytes == 0 {
+            return Err(ConfigError::ValidationError(
+                "MAX_AVATAR_BODY_BYTES must be greater than 0".to_string(),
+            ));
+        }
+
+        // Files need somewhere to go
+        if self.media_provider == MediaProvider::Local
+            && self.media_dir.as_deref().is_none_or(str::is_empty)
+        {
+            return Err(ConfigError::ValidationError(
+                "MEDIA_DIR is required when MEDIA_PROVIDER is local".to_string(),
+            ));
+        }
+
+        // A session cap below the token lifetime would cut every new session short
+        if self.refresh_token_max_session_days < self.refresh_token_expiry_days {
+            return Err(ConfigError::ValidationError(
+                "REFRESH_TOKEN_MAX_SESSION_DAYS must be at least REFRESH_TOKEN_EXPIRY_DAYS"
+                    .to_string(),
+            ));
+        }
+
+        // Each provider needs its credentials and a sender address
+        if let Some(provider) = self.email_provider() {
+            let required: &[(&str, &Option<String>)] = match provider {
+                EmailProvider::Smtp => &[("SMTP_HOST", &self.smtp_host)],
+                EmailProvider::Sendgrid => &[("SENDGRID_API_KEY", &self.sendgrid_api_key)],
+                EmailProvider::Ses => &[
+                    ("SES_REGION", &self.ses_region),
+                    ("SES_ACCESS_KEY_ID", &self.ses_access_key_id),
+                    ("SES_SECRET_ACCESS_KEY", &self.ses_secret_access_key),
+                ],
+                EmailProvider::Console => &[],
+            };
+            let missing: Vec<&str> = required
+                .iter()
+                .filter(|(_, value)| value.as_deref().is_none_or(str::is_empty))
+                .map(|(name, _)| *name)
+                .collect();
+            if !missing.is_empty() {
+                return Err(ConfigError::ValidationError(format!(
+                    "{} required when EMAIL_PROVIDER is {provider:?}",
+                    missing.join(", ")
+                )));
+            }
+            if provider != EmailProvider::Console && self.email_from_address().is_none() {
+                return Err(ConfigError::ValidationError(
+                    "EMAIL_FROM (or SMTP_FROM_EMAIL) is required to send email".to_string(),
+                ));
+            }
+        }
+
+        // Zero would never remind; 24 or more would start before the streak is at risk
+        if !(1..=23).contains(&self.streak_reminder_hours_before_midnight) {
+            return Err(ConfigError::ValidationError(
+                "STREAK_REMINDER_HOURS_BEFORE_MIDNIGHT must be between 1 and 23".to_string(),
+            ));
+        }
+
+        if !(0.0..=1.0).contains(&self.client_error_sample_rate) {
+            return Err(ConfigError::ValidationError(
+                "CLIENT_ERROR_SAMPLE_RATE must be between 0.0 and 1.0".to_string(),
+            ));
+        }
+
+        JobSchedules::from_config(self)?;
+
         Ok(())
     }
 
+    /// Parse previous JWT secrets into a vector
+    #[must_use]
+    pub fn parsed_jwt_previous_secrets(&self) -> Vec<String> {
+        self.jwt_previous_secrets
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect()
+    }
+
+    /// Build the PII keyring, or `None` when encryption is not configured
+    pub fn pii_keyring(&self) -> Result<Option<Keyring>, EncryptionError> {
+        let Some(current) = self.pii_encryption_key.as_deref().filter(|k| !k.is_empty()) else {
+            return Ok(None);
+        };
+        let previous: Vec<&str> = self
+            .pii_encryption_previous_keys
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .collect();
+        Keyring::from_base64(current, &previous).map(Some)
+    }
+
+    /// The configured email provider; SMTP when only `SMTP_HOST` is set, `None` when email is off
+    #[must_use]
+    pub fn email_provider(&self) -> Option<EmailProvider> {
+        self.email_provider.or_else(|| {
+            self.smtp_host
+                .as_deref()
+                .filter(|host| !host.is_empty())
+                .map(|_| EmailProvider::Smtp)
+        })
+    }
+
+    /// Sender address, from `EMAIL_FROM` or the older `SMTP_FROM_*` settings
+    #[must_use]
+    pub fn email_from_address(&self) -> Option<FromAddress> {
+        let email = self
+            .email_from
+            .as_ref()
+            .or(self.smtp_from_email.as_ref())
+            .filter(|email| !email.is_empty())?;
+        let name = self
+            .email_from_name
+            .as_ref()
+            .or(self.smtp_from_name.as_ref())
+            .filter(|name| !name.is_empty())
+            .map_or("Matcha Time", String::as_str);
+
+        Some(FromAddress {
+            email: email.clone(),
+            name: name.to_string(),
+        })
+    }
+
     /// Parse allowed origins into a vector
     #[must_use]
     pub fn parsed_allowed_origins(&self) -> Vec<String> {
@@ -238,4 +832,19 @@ impl ApiConfig {
             .filter(|s| !s.is_empty())
             .collect()
     }
+
+    /// Settings of the primary and replica connection pools
+    #[must_use]
+    pub fn pool_settings(&self) -> PoolSettings {
+        let nonzero = |duration: Duration| (!duration.is_zero()).then_some(duration);
+        PoolSettings {
+            max_connections: self.database_max_connections,
+            min_connections: self.database_min_connections,
+            acquire_timeout: Duration::from_secs(self.database_acquire_timeout_seconds),
+            idle_timeout: nonzero(Duration::from_secs(self.database_idle_timeout_seconds)),
+            statement_timeout: nonzero(Duration::from_millis(self.database_statement_timeout_ms)),
+            application_name: self.database_application_name.clone(),
+            connect_timeout: Duration::from_secs(self.database_connect_timeout_seconds),
+        }
+    }
 }
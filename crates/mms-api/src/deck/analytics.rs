@@ -0,0 +1,148 @@
+//! Deck difficulty analytics for content authors.
+//!
+//! Aggregates every learner's answers to a deck's cards: overall accuracy, the
+//! lapse rate once cards are learned, how long cards take to master and which
+//! cards learners keep forgetting. The nightly job stores the per-card figures
+//! in `flashcard_analytics`, so a request only sums the deck's rows.
+
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    routing::get,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, types::Uuid};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    ApiState,
+    auth::{RequirePermission, permissions::ContentWrite, policy::Principal},
+    error::{ApiError, ErrorResponse},
+    middleware::rate_limit,
+    stats::detailed::LEARNING_DAYS,
+};
+
+use mms_db::models::HardCard;
+use mms_db::repositories::{
+    content as content_repo, deck as deck_repo, deck_analytics as analytics_repo,
+};
+use mms_db::tenancy::Tenant;
+
+/// Answers a card needs, once learned, before its lapse rate is trusted
+const MIN_RECALL_REVIEWS: i32 = 5;
+
+const DEFAULT_HARDEST_LIMIT: i64 = 10;
+const MAX_HARDEST_LIMIT: i64 = 50;
+
+/// Create the deck analytics routes
+pub fn routes() -> Router<ApiState> {
+    use crate::make_rate_limit_layer;
+
+    Router::new()
+        .route("/decks/{deck_id}/analytics", get(get_deck_analytics))
+        .layer(make_rate_limit_layer!(
+            rate_limit::GENERAL_RATE_PER_SECOND,
+            rate_limit::GENERAL_BURST_SIZE
+        ))
+}
+
+/// Recompute the analytics of every reviewed card.
+///
+/// Returns the number of cards written.
+pub async fn refresh(pool: &PgPool) -> Result<u64, ApiError> {
+    let mut tx = pool.begin().await?;
+    let written = analytics_repo::refresh(&mut *tx, LEARNING_DAYS).await?;
+    analytics_repo::delete_stale(&mut *tx).await?;
+    tx.commit().await?;
+    Ok(written)
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct AnalyticsQuery {
+    /// Hardest cards to list, 1 to 50 (default 10)
+    #[serde(default)]
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct DeckAnalytics {
+    /// Cards in the deck
+    cards: i64,
+    /// Every answer to the deck's cards, by every learner
+    reviews: i64,
+    /// Share of `reviews` answered correctly; absent without reviews
+    accuracy: Option<f64>,
+    /// Share of answers to learned cards (scheduled a day or more ahead) that were wrong
+    lapse_rate: Option<f64>,
+    /// Cards mastered, counted once per learner
+    mastered: i64,
+    /// Mean days from a learner's first review of a card to mastering it
+    average_days_to_master: Option<f64>,
+    /// Highest lapse rate first, among cards with enough answers to tell
+    hardest_cards: Vec<HardCard>,
+    /// When the figures were last recomputed; absent before the first nightly run
+    computed_at: Option<DateTime<Utc>>,
+}
+
+/// Difficulty analytics for a deck, aggregated across all learners
+///
+/// Figures are recomputed nightly. Lapses and mastery times come from the
+/// review log, so they only cover reviews made since it was introduced.
+#[utoipa::path(
+    get,
+    path = "/v1/decks/{deck_id}/analytics",
+    tag = "decks",
+    security(("cookie_auth" = ["content:write"]), ("admin_token" = [])),
+    params(("deck_id" = Uuid, Path), AnalyticsQuery),
+    responses(
+        (status = 200, description = "The deck's analytics", body = DeckAnalytics),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `content:write` permission", body = ErrorResponse),
+        (status = 404, description = "Deck not found", body = ErrorResponse),
+    )
+)]
+async fn get_deck_analytics(
+    access: RequirePermission<ContentWrite>,
+    State(state): State<ApiState>,
+    Path(deck_id): Path<Uuid>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<DeckAnalytics>, ApiError> {
+    // Authors only see the decks of their own organizations; operators see all
+    let visible = match access.principal {
+        Principal::User(user) => {
+            deck_repo::find_by_id(&state.pool, Tenant::Member(user.user_id), deck_id)
+                .await?
+                .is_some()
+        }
+        Principal::Operator => content_repo::deck_exists(&state.pool, deck_id).await?,
+    };
+    if !visible {
+        return Err(ApiError::NotFound("Deck not found".to_string()));
+    }
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_HARDEST_LIMIT)
+        .clamp(1, MAX_HARDEST_LIMIT);
+    let (totals, hardest_cards) = tokio::try_join!(
+        analytics_repo::totals(&state.read_pool, deck_id),
+        analytics_repo::hardest_cards(&state.read_pool, deck_id, MIN_RECALL_REVIEWS, limit),
+    )?;
+
+    Ok(Json(DeckAnalytics {
+        cards: totals.cards,
+        reviews: totals.reviews,
+        accuracy: rate(totals.correct, totals.reviews),
+        lapse_rate: rate(totals.lapses, totals.recall_reviews),
+        mastered: totals.mastered,
+        average_days_to_master: totals.avg_days_to_master,
+        hardest_cards,
+        computed_at: totals.computed_at,
+    }))
+}
+
+fn rate(part: i64, whole: i64) -> Option<f64> {
+    (whole > 0).then(|| part as f64 / whole as f64)
+}
@@ -1,3 +1,8 @@
+pub mod analytics;
+pub mod modes;
+pub mod reviews;
 pub mod routes;
+pub mod settings;
+pub mod simulate;
 
 pub use routes::routes;
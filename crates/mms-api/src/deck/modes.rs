@@ -0,0 +1,72 @@
+//! Per-mode progress in a deck.
+//!
+//! Every answer also counts towards the learner's score on the card in the
+//! mode it was practised in, so a learner who reads a card well but cannot
+//! catch it by ear sees that here, and the practice queue serves the card in
+//! listening mode until they can.
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    routing::get,
+};
+use sqlx::types::Uuid;
+
+use crate::{
+    ApiState,
+    auth::AuthUser,
+    error::{ApiError, ErrorResponse},
+    middleware::rate_limit,
+};
+
+use mms_db::models::ModeProgress;
+use mms_db::repositories::{deck as deck_repo, practice as practice_repo};
+use mms_db::tenancy::Tenant;
+
+/// Create the per-mode progress routes
+pub fn routes() -> Router<ApiState> {
+    use crate::make_rate_limit_layer;
+
+    Router::new()
+        .route("/decks/{deck_id}/progress/modes", get(get_mode_progress))
+        .layer(make_rate_limit_layer!(
+            rate_limit::GENERAL_RATE_PER_SECOND,
+            rate_limit::GENERAL_BURST_SIZE
+        ))
+}
+
+/// The signed-in user's progress in a deck, per mode they practised it in
+#[utoipa::path(
+    get,
+    path = "/v1/decks/{deck_id}/progress/modes",
+    tag = "decks",
+    security(("cookie_auth" = [])),
+    params(("deck_id" = Uuid, Path)),
+    responses(
+        (status = 200, description = "Progress per mode, modes in alphabetical order", body = Vec<ModeProgress>),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Deck not found", body = ErrorResponse),
+    )
+)]
+async fn get_mode_progress(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(deck_id): Path<Uuid>,
+) -> Result<Json<Vec<ModeProgress>>, ApiError> {
+    let mut conn = state.read_pool.acquire().await?;
+    if deck_repo::find_by_id(&mut *conn, Tenant::Member(auth_user.user_id), deck_id)
+        .await?
+        .is_none()
+    {
+        return Err(ApiError::NotFound("Deck not found".to_string()));
+    }
+
+    let progress = practice_repo::progress_by_mode(
+        &mut *conn,
+        auth_user.user_id,
+        deck_id,
+        mms_srs::MASTERY_THRESHOLD,
+    )
+    .await?;
+    Ok(Json(progress))
+}
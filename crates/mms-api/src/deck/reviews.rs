@@ -0,0 +1,389 @@
+//! Ratings and comments on public decks.
+//!
+//! Learners rate a deck from 1 to 5 stars, once each, and comment on it.
+//! Commenters edit and delete their own comments; content authors moderate by
+//! deleting comments or hiding them, which keeps them out of the listing.
+
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{get, put},
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sqlx::types::Uuid;
+use utoipa::{IntoParams, ToSchema};
+use validator::{Validate, ValidationError};
+
+use crate::{
+    ApiState,
+    auth::{
+        AuthUser, Permission, RequirePermission, permissions::ContentWrite, policy::Principal,
+        require_permission,
+    },
+    error::{ApiError, ErrorResponse},
+    middleware::rate_limit,
+    validation::{ValidJson, invalid},
+};
+
+use mms_db::models::{DeckComment, DeckRating};
+use mms_db::repositories::deck_review as review_repo;
+
+/// Longest comment accepted, in characters
+const MAX_COMMENT_CHARS: usize = 2000;
+
+const DEFAULT_COMMENT_LIMIT: i64 = 20;
+const MAX_COMMENT_LIMIT: i64 = 100;
+
+/// Create the deck rating and comment routes
+pub fn routes() -> Router<ApiState> {
+    use crate::make_rate_limit_layer;
+
+    Router::new()
+        .route(
+            "/decks/{deck_id}/rating",
+            get(get_rating).put(rate_deck).delete(unrate_deck),
+        )
+        .route(
+            "/decks/{deck_id}/comments",
+            get(list_comments).post(create_comment),
+        )
+        .route(
+            "/decks/{deck_id}/comments/{comment_id}",
+            put(update_comment).delete(delete_comment),
+        )
+        .route(
+            "/decks/{deck_id}/comments/{comment_id}/hidden",
+            put(hide_comment).delete(unhide_comment),
+        )
+        .layer(make_rate_limit_layer!(
+            rate_limit::GENERAL_RATE_PER_SECOND,
+            rate_limit::GENERAL_BURST_SIZE
+        ))
+}
+
+/// Fail with 404 unless the deck is in the public catalogue
+async fn require_public(state: &ApiState, deck_id: Uuid) -> Result<(), ApiError> {
+    if review_repo::is_public(&state.pool, deck_id).await? {
+        Ok(())
+    } else {
+        Err(ApiError::NotFound("Deck not found".to_string()))
+    }
+}
+
+/// Comment text is trimmed, then must be 1 to [`MAX_COMMENT_CHARS`] characters
+fn validate_comment_body(body: &str) -> Result<(), ValidationError> {
+    let body = body.trim();
+    if body.is_empty() || body.chars().count() > MAX_COMMENT_CHARS {
+        return Err(invalid(
+            "length",
+            format!("Comment must be 1 to {MAX_COMMENT_CHARS} characters"),
+        ));
+    }
+    Ok(())
+}
+
+/// A public deck's rating and the signed-in user's own
+#[utoipa::path(
+    get,
+    path = "/v1/decks/{deck_id}/rating",
+    tag = "decks",
+    security(("cookie_auth" = [])),
+    params(("deck_id" = Uuid, Path)),
+    responses(
+        (status = 200, description = "The deck's rating", body = DeckRating),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Deck not found or not public", body = ErrorResponse),
+    )
+)]
+async fn get_rating(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(deck_id): Path<Uuid>,
+) -> Result<Json<DeckRating>, ApiError> {
+    let rating = review_repo::find_rating(&state.pool, deck_id, auth_user.user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Deck not found".to_string()))?;
+    Ok(Json(rating))
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+struct RateRequest {
+    /// 1 to 5
+    #[validate(range(min = 1, max = 5, message = "Rating must be 1 to 5 stars"))]
+    stars: i16,
+}
+
+/// Rate a public deck, replacing the signed-in user's earlier rating
+#[utoipa::path(
+    put,
+    path = "/v1/decks/{deck_id}/rating",
+    tag = "decks",
+    security(("cookie_auth" = [])),
+    params(("deck_id" = Uuid, Path)),
+    request_body = RateRequest,
+    responses(
+        (status = 200, description = "The deck's updated rating", body = DeckRating),
+        (status = 400, description = "Stars outside 1 to 5", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Deck not found or not public", body = ErrorResponse),
+    )
+)]
+async fn rate_deck(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(deck_id): Path<Uuid>,
+    ValidJson(request): ValidJson<RateRequest>,
+) -> Result<Json<DeckRating>, ApiError> {
+    require_public(&state, deck_id).await?;
+
+    review_repo::rate(&state.pool, deck_id, auth_user.user_id, request.stars).await?;
+    let rating = review_repo::find_rating(&state.pool, deck_id, auth_user.user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Deck not found".to_string()))?;
+    Ok(Json(rating))
+}
+
+/// Withdraw the signed-in user's rating
+#[utoipa::path(
+    delete,
+    path = "/v1/decks/{deck_id}/rating",
+    tag = "decks",
+    security(("cookie_auth" = [])),
+    params(("deck_id" = Uuid, Path)),
+    responses(
+        (status = 204, description = "Rating withdrawn"),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Deck not rated by the user", body = ErrorResponse),
+    )
+)]
+async fn unrate_deck(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(deck_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    if !review_repo::unrate(&state.pool, deck_id, auth_user.user_id).await? {
+        return Err(ApiError::NotFound("Rating not found".to_string()));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct CommentQuery {
+    /// Only comments written before this time, for the next page
+    #[serde(default)]
+    before: Option<DateTime<Utc>>,
+    /// Page size, 1 to 100 (default 20)
+    #[serde(default)]
+    limit: Option<i64>,
+}
+
+/// A public deck's comments, newest first; hidden comments are left out
+#[utoipa::path(
+    get,
+    path = "/v1/decks/{deck_id}/comments",
+    tag = "decks",
+    params(("deck_id" = Uuid, Path), CommentQuery),
+    responses(
+        (status = 200, description = "One page of comments", body = Vec<DeckComment>),
+        (status = 404, description = "Deck not found or not public", body = ErrorResponse),
+    )
+)]
+async fn list_comments(
+    State(state): State<ApiState>,
+    Path(deck_id): Path<Uuid>,
+    Query(query): Query<CommentQuery>,
+) -> Result<Json<Vec<DeckComment>>, ApiError> {
+    require_public(&state, deck_id).await?;
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_COMMENT_LIMIT)
+        .clamp(1, MAX_COMMENT_LIMIT);
+    let comments = review_repo::list_comments(&state.pool, deck_id, query.before, limit).await?;
+    Ok(Json(comments))
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+struct CommentRequest {
+    /// 1 to 2000 characters
+    #[validate(custom(function = "validate_comment_body"))]
+    body: String,
+}
+
+/// Comment on a public deck
+#[utoipa::path(
+    post,
+    path = "/v1/decks/{deck_id}/comments",
+    tag = "decks",
+    security(("cookie_auth" = [])),
+    params(("deck_id" = Uuid, Path)),
+    request_body = CommentRequest,
+    responses(
+        (status = 201, description = "Comment posted", body = DeckComment),
+        (status = 400, description = "Empty or overlong comment", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Deck not found or not public", body = ErrorResponse),
+    )
+)]
+async fn create_comment(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(deck_id): Path<Uuid>,
+    ValidJson(request): ValidJson<CommentRequest>,
+) -> Result<(StatusCode, Json<DeckComment>), ApiError> {
+    let body = request.body.trim();
+    require_public(&state, deck_id).await?;
+
+    let comment =
+        review_repo::create_comment(&state.pool, deck_id, auth_user.user_id, body).await?;
+    Ok((StatusCode::CREATED, Json(comment)))
+}
+
+/// Edit the signed-in user's own comment
+#[utoipa::path(
+    put,
+    path = "/v1/decks/{deck_id}/comments/{comment_id}",
+    tag = "decks",
+    security(("cookie_auth" = [])),
+    params(("deck_id" = Uuid, Path), ("comment_id" = Uuid, Path)),
+    request_body = CommentRequest,
+    responses(
+        (status = 200, description = "Comment edited", body = DeckComment),
+        (status = 400, description = "Empty or overlong comment", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Someone else's comment", body = ErrorResponse),
+        (status = 404, description = "Comment not found", body = ErrorResponse),
+    )
+)]
+async fn update_comment(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path((deck_id, comment_id)): Path<(Uuid, Uuid)>,
+    ValidJson(request): ValidJson<CommentRequest>,
+) -> Result<Json<DeckComment>, ApiError> {
+    let body = request.body.trim();
+
+    let comment =
+        review_repo::update_comment(&state.pool, deck_id, comment_id, auth_user.user_id, body)
+            .await?;
+    match comment {
+        Some(comment) => Ok(Json(comment)),
+        None => match review_repo::find_comment_author(&state.pool, deck_id, comment_id).await? {
+            Some(_) => Err(ApiError::Forbidden(
+                "You can only edit your own comments".to_string(),
+            )),
+            None => Err(ApiError::NotFound("Comment not found".to_string())),
+        },
+    }
+}
+
+/// Delete a comment: the signed-in user's own, or any with `content:write`
+#[utoipa::path(
+    delete,
+    path = "/v1/decks/{deck_id}/comments/{comment_id}",
+    tag = "decks",
+    security(("cookie_auth" = [])),
+    params(("deck_id" = Uuid, Path), ("comment_id" = Uuid, Path)),
+    responses(
+        (status = 204, description = "Comment deleted"),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Someone else's comment, without `content:write`", body = ErrorResponse),
+        (status = 404, description = "Comment not found", body = ErrorResponse),
+    )
+)]
+async fn delete_comment(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path((deck_id, comment_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, ApiError> {
+    let author = review_repo::find_comment_author(&state.pool, deck_id, comment_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Comment not found".to_string()))?;
+    if author != auth_user.user_id {
+        require_permission(&auth_user, Permission::ContentWrite)?;
+    }
+
+    if !review_repo::delete_comment(&state.pool, deck_id, comment_id).await? {
+        return Err(ApiError::NotFound("Comment not found".to_string()));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Hide a comment from the listing, keeping it for the record
+#[utoipa::path(
+    put,
+    path = "/v1/decks/{deck_id}/comments/{comment_id}/hidden",
+    tag = "decks",
+    security(("cookie_auth" = ["content:write"]), ("admin_token" = [])),
+    params(("deck_id" = Uuid, Path), ("comment_id" = Uuid, Path)),
+    responses(
+        (status = 204, description = "Comment hidden"),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `content:write` permission", body = ErrorResponse),
+        (status = 404, description = "Comment not found", body = ErrorResponse),
+    )
+)]
+async fn hide_comment(
+    access: RequirePermission<ContentWrite>,
+    State(state): State<ApiState>,
+    Path((deck_id, comment_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, ApiError> {
+    let moderator_id = match access.principal {
+        Principal::User(user) => Some(user.user_id),
+        Principal::Operator => None,
+    };
+    set_hidden(&state, deck_id, comment_id, moderator_id, true).await
+}
+
+/// Show a hidden comment again
+#[utoipa::path(
+    delete,
+    path = "/v1/decks/{deck_id}/comments/{comment_id}/hidden",
+    tag = "decks",
+    security(("cookie_auth" = ["content:write"]), ("admin_token" = [])),
+    params(("deck_id" = Uuid, Path), ("comment_id" = Uuid, Path)),
+    responses(
+        (status = 204, description = "Comment shown"),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `content:write` permission", body = ErrorResponse),
+        (status = 404, description = "Comment not found", body = ErrorResponse),
+    )
+)]
+async fn unhide_comment(
+    _access: RequirePermission<ContentWrite>,
+    State(state): State<ApiState>,
+    Path((deck_id, comment_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, ApiError> {
+    set_hidden(&state, deck_id, comment_id, None, false).await
+}
+
+async fn set_hidden(
+    state: &ApiState,
+    deck_id: Uuid,
+    comment_id: Uuid,
+    moderator_id: Option<Uuid>,
+    hidden: bool,
+) -> Result<StatusCode, ApiError> {
+    if !review_repo::set_comment_hidden(&state.pool, deck_id, comment_id, moderator_id, hidden)
+        .await?
+    {
+        return Err(ApiError::NotFound("Comment not found".to_string()));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comment_body_is_trimmed_and_bounded() {
+        assert!(validate_comment_body("  Great deck!\n").is_ok());
+        assert!(validate_comment_body("   ").is_err());
+        assert!(validate_comment_body(&format!(" {} ", "a".repeat(MAX_COMMENT_CHARS))).is_ok());
+        assert!(validate_comment_body(&"é".repeat(MAX_COMMENT_CHARS + 1)).is_err());
+    }
+}
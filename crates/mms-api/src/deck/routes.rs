@@ -1,43 +1,263 @@
 use axum::{
     Json, Router,
     extract::{Path, Query, State},
+    http::HeaderMap,
+    middleware,
+    response::Response,
     routing::get,
 };
+use futures_util::TryStreamExt;
 use serde::Deserialize;
 use sqlx::types::Uuid;
+use utoipa::{IntoParams, ToSchema};
 
-use crate::{ApiState, auth::AuthUser, error::ApiError};
+use crate::{
+    ApiState,
+    auth::AuthUser,
+    error::{ApiError, ErrorResponse},
+    middleware::etag,
+    practice::scheduler::NewCardOrder,
+    slugs,
+    streaming::{StreamFormat, json_stream},
+    validation,
+};
+
+use mms_db::models::{Flashcard, PracticeCard, PublicDeck};
+use mms_db::repositories::deck::{self as deck_repo, PublicDeckOrder};
+use mms_db::repositories::practice as practice_repo;
+use mms_db::repositories::profile as profile_repo;
+use mms_db::tenancy::Tenant;
 
-use mms_db::models::PracticeCard;
-use mms_db::repositories::deck as deck_repo;
+pub(crate) const DEFAULT_PRACTICE_LIMIT: i64 = 20;
+pub(crate) const MAX_PRACTICE_LIMIT: i64 = 50;
 
-const DEFAULT_PRACTICE_LIMIT: i64 = 20;
-const MAX_PRACTICE_LIMIT: i64 = 50;
+const DEFAULT_CATALOGUE_LIMIT: i64 = 50;
+const MAX_CATALOGUE_LIMIT: i64 = 100;
 
 /// Create the deck routes
 pub fn routes() -> Router<ApiState> {
-    Router::new().route("/decks/{deck_id}/practice", get(get_practice_session))
+    Router::new()
+        .route("/decks", get(list_public_decks))
+        .route("/decks/{deck_id}/practice", get(get_practice_session))
+        .route("/decks/{deck_id}/export", get(export_deck))
+        .layer(middleware::from_fn(etag::etag_middleware))
+        .merge(super::analytics::routes())
+        .merge(super::modes::routes())
+        .merge(super::reviews::routes())
+        .merge(super::settings::routes())
+        .merge(super::simulate::routes())
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum CatalogueSort {
+    /// Highest average rating first, then most rated; unrated decks last
+    #[default]
+    Rating,
+    Newest,
+    Title,
 }
 
-#[derive(Deserialize)]
+impl From<CatalogueSort> for PublicDeckOrder {
+    fn from(sort: CatalogueSort) -> Self {
+        match sort {
+            CatalogueSort::Rating => PublicDeckOrder::Rating,
+            CatalogueSort::Newest => PublicDeckOrder::Newest,
+            CatalogueSort::Title => PublicDeckOrder::Title,
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct CatalogueQuery {
+    /// `rating` (default), `newest` or `title`
+    #[serde(default)]
+    #[param(inline)]
+    sort: CatalogueSort,
+    /// Only decks for learners of this language; requires `language_to`
+    #[serde(default)]
+    language_from: Option<String>,
+    /// Only decks teaching this language; requires `language_from`
+    #[serde(default)]
+    language_to: Option<String>,
+    /// Page size, 1 to 100 (default 50)
+    #[serde(default)]
+    limit: Option<i64>,
+    /// Decks to skip (default 0)
+    #[serde(default)]
+    offset: Option<i64>,
+}
+
+/// Decks in the public catalogue with their ratings
+#[utoipa::path(
+    get,
+    path = "/v1/decks",
+    tag = "decks",
+    params(CatalogueQuery),
+    responses(
+        (status = 200, description = "One page of public decks", body = Vec<PublicDeck>),
+        (status = 400, description = "Unsupported language code or only one of the pair", body = ErrorResponse),
+    )
+)]
+async fn list_public_decks(
+    State(state): State<ApiState>,
+    Query(query): Query<CatalogueQuery>,
+) -> Result<Json<Vec<PublicDeck>>, ApiError> {
+    let languages = match (query.language_from.as_deref(), query.language_to.as_deref()) {
+        (Some(language_from), Some(language_to)) => {
+            ApiError::check_fields([
+                (
+                    "language_from",
+                    validation::validate_language_code(language_from),
+                ),
+                (
+                    "language_to",
+                    validation::validate_language_code(language_to),
+                ),
+            ])?;
+            Some((language_from, language_to))
+        }
+        (None, None) => None,
+        _ => {
+            return Err(ApiError::Validation(
+                "language_from and language_to must be given together".to_string(),
+            ));
+        }
+    };
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_CATALOGUE_LIMIT)
+        .clamp(1, MAX_CATALOGUE_LIMIT);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let decks = deck_repo::list_public(
+        &state.read_pool,
+        languages,
+        query.sort.into(),
+        limit,
+        offset,
+    )
+    .await?;
+    Ok(Json(decks))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct PracticeQuery {
+    /// Cards to return, 1 to 50 (default 20, or the profile's session size)
     #[serde(default)]
     limit: Option<i64>,
+    /// Learning profile to practise in; the deck must be in its language pair
+    #[serde(default)]
+    profile_id: Option<Uuid>,
 }
 
+/// Cards due for review in a deck, new cards first, easiest to read first
+///
+/// The deck may be named by its id or its slug.
+#[utoipa::path(
+    get,
+    path = "/v1/decks/{deck_id}/practice",
+    tag = "decks",
+    security(("cookie_auth" = [])),
+    params(("deck_id" = String, Path, description = "Deck id or slug"), PracticeQuery),
+    responses(
+        (status = 200, description = "Due cards", body = Vec<PracticeCard>),
+        (status = 400, description = "Deck is not in the profile's language pair", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Profile or deck not found, or the deck belongs to another organization", body = ErrorResponse),
+    )
+)]
 async fn get_practice_session(
     auth_user: AuthUser,
     State(state): State<ApiState>,
-    Path(deck_id): Path<Uuid>,
+    Path(deck): Path<String>,
     Query(query): Query<PracticeQuery>,
 ) -> Result<Json<Vec<PracticeCard>>, ApiError> {
+    let tenant = Tenant::Member(auth_user.user_id);
+    let deck_id = slugs::deck_id(&state.pool, tenant, &deck).await?;
+    // Another organization's deck is as good as missing
+    let deck = deck_repo::find_by_id(&state.pool, tenant, deck_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Deck not found".to_string()))?;
+
+    let mut default_limit = DEFAULT_PRACTICE_LIMIT;
+    if let Some(profile_id) = query.profile_id {
+        let profile = profile_repo::find_for_user(&state.pool, auth_user.user_id, profile_id)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Learning profile not found".to_string()))?;
+
+        if deck.language_from != profile.native_language
+            || deck.language_to != profile.learning_language
+        {
+            return Err(ApiError::Validation(
+                "Deck is not in this learning profile's language pair".to_string(),
+            ));
+        }
+        default_limit = i64::from(profile.session_size);
+    }
+
     let limit = query
         .limit
-        .unwrap_or(DEFAULT_PRACTICE_LIMIT)
+        .unwrap_or(default_limit)
         .clamp(1, MAX_PRACTICE_LIMIT);
 
-    let cards =
-        deck_repo::get_practice_cards(&state.pool, deck_id, auth_user.user_id, limit).await?;
+    let cards = practice_repo::get_practice_cards(
+        &state.pool,
+        deck_id,
+        auth_user.user_id,
+        limit,
+        mms_srs::KNOWN_WORD_SCORE,
+        NewCardOrder::default().as_str(),
+    )
+    .await?;
 
     Ok(Json(cards))
 }
+
+/// Stream every flashcard in a deck as a JSON array, or NDJSON when requested
+///
+/// The deck may be named by its id or its slug.
+#[utoipa::path(
+    get,
+    path = "/v1/decks/{deck_id}/export",
+    tag = "decks",
+    security(("cookie_auth" = [])),
+    params(("deck_id" = String, Path, description = "Deck id or slug")),
+    responses(
+        (status = 200, description = "Streamed flashcards; NDJSON with `Accept: application/x-ndjson`", content(
+            (Vec<Flashcard> = "application/json"),
+            (Flashcard = "application/x-ndjson"),
+        )),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Deck not found", body = ErrorResponse),
+    )
+)]
+async fn export_deck(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(deck): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let tenant = Tenant::Member(auth_user.user_id);
+    let deck_id = slugs::deck_id(&state.pool, tenant, &deck).await?;
+    if deck_repo::find_by_id(&state.pool, tenant, deck_id)
+        .await?
+        .is_none()
+    {
+        return Err(ApiError::NotFound("Deck not found".to_string()));
+    }
+
+    let pool = state.read_pool.clone();
+    Ok(json_stream(
+        StreamFormat::from_headers(&headers),
+        move |tx| async move {
+            let mut cards = deck_repo::stream_flashcards(&pool, deck_id);
+            while let Some(card) = cards.try_next().await? {
+                tx.send(card).await?;
+            }
+            Ok(())
+        },
+    ))
+}
@@ -0,0 +1,191 @@
+//! Per-deck scheduling settings.
+//!
+//! Content authors can give a deck its own longest interval, ease and order
+//! for new cards; see [`crate::practice::scheduler`] for how they combine with
+//! the learner's preferences. They also choose the modes the deck's cards are
+//! practised in.
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    routing::get,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::{
+    ApiState,
+    auth::{AuthUser, RequirePermission, permissions::ContentWrite, policy::Principal},
+    error::{ApiError, ErrorResponse},
+    middleware::rate_limit,
+    practice::{
+        ReviewMode,
+        scheduler::{
+            self, DeckSettings, MAX_INTERVAL_DAYS_LIMIT, MAX_STARTING_EASE, MIN_STARTING_EASE,
+            NewCardOrder, SchedulerConfig,
+        },
+    },
+    validation::ValidJson,
+};
+
+use mms_db::repositories::{
+    content as content_repo, deck as deck_repo, deck_settings as deck_settings_repo,
+};
+use mms_db::tenancy::Tenant;
+
+/// Create the deck settings routes
+pub fn routes() -> Router<ApiState> {
+    use crate::make_rate_limit_layer;
+
+    Router::new()
+        .route(
+            "/decks/{deck_id}/settings",
+            get(get_deck_settings).put(update_deck_settings),
+        )
+        .layer(make_rate_limit_layer!(
+            rate_limit::GENERAL_RATE_PER_SECOND,
+            rate_limit::GENERAL_BURST_SIZE
+        ))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct DeckScheduling {
+    /// The deck's own settings
+    settings: DeckSettings,
+    /// What the signed-in user's reviews in this deck are scheduled with
+    effective: SchedulerConfig,
+    /// Modes the deck's cards are practised in
+    modes: Vec<ReviewMode>,
+}
+
+/// A deck's scheduling settings, and what they come to for the signed-in user
+#[utoipa::path(
+    get,
+    path = "/v1/decks/{deck_id}/settings",
+    tag = "decks",
+    security(("cookie_auth" = [])),
+    params(("deck_id" = Uuid, Path)),
+    responses(
+        (status = 200, description = "The deck's settings", body = DeckScheduling),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Deck not found", body = ErrorResponse),
+    )
+)]
+async fn get_deck_settings(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(deck_id): Path<Uuid>,
+) -> Result<Json<DeckScheduling>, ApiError> {
+    let mut conn = state.pool.acquire().await?;
+    if deck_repo::find_by_id(&mut *conn, Tenant::Member(auth_user.user_id), deck_id)
+        .await?
+        .is_none()
+    {
+        return Err(ApiError::NotFound("Deck not found".to_string()));
+    }
+
+    let settings = deck_settings_repo::find(&mut *conn, deck_id)
+        .await?
+        .map(DeckSettings::from)
+        .unwrap_or_default();
+    let effective = scheduler::load(&mut conn, auth_user.user_id, deck_id).await?;
+    let modes = settings.enabled_modes();
+    Ok(Json(DeckScheduling {
+        settings,
+        effective,
+        modes,
+    }))
+}
+
+/// Every setting is replaced; `null` or a missing field restores the default
+#[derive(Deserialize, ToSchema, Validate)]
+struct UpdateDeckSettings {
+    /// 1 to 365
+    #[serde(default)]
+    #[validate(range(
+        min = 1,
+        max = MAX_INTERVAL_DAYS_LIMIT,
+        message = "Max interval days must be between 1 and 365"
+    ))]
+    max_interval_days: Option<i32>,
+    /// 0.5 to 2.0
+    #[serde(default)]
+    #[validate(range(
+        min = MIN_STARTING_EASE,
+        max = MAX_STARTING_EASE,
+        message = "Starting ease must be between 0.5 and 2.0"
+    ))]
+    starting_ease: Option<f64>,
+    #[serde(default)]
+    new_card_order: Option<NewCardOrder>,
+    /// At least one; `pronunciation` needs a speech-to-text provider
+    #[serde(default)]
+    #[validate(length(min = 1, message = "At least one mode must be enabled"))]
+    modes: Option<Vec<ReviewMode>>,
+}
+
+/// Replace a deck's scheduling settings
+#[utoipa::path(
+    put,
+    path = "/v1/decks/{deck_id}/settings",
+    tag = "decks",
+    security(("cookie_auth" = ["content:write"]), ("admin_token" = [])),
+    params(("deck_id" = Uuid, Path)),
+    request_body = UpdateDeckSettings,
+    responses(
+        (status = 200, description = "The deck's updated settings", body = DeckSettings),
+        (status = 400, description = "A setting out of range, no mode, or pronunciation without a speech-to-text provider", body = ErrorResponse),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `content:write` permission", body = ErrorResponse),
+        (status = 404, description = "Deck not found", body = ErrorResponse),
+    )
+)]
+async fn update_deck_settings(
+    access: RequirePermission<ContentWrite>,
+    State(state): State<ApiState>,
+    Path(deck_id): Path<Uuid>,
+    ValidJson(payload): ValidJson<UpdateDeckSettings>,
+) -> Result<Json<DeckSettings>, ApiError> {
+    // Authors only change the decks of their own organizations; operators any
+    let visible = match access.principal {
+        Principal::User(user) => {
+            deck_repo::find_by_id(&state.pool, Tenant::Member(user.user_id), deck_id)
+                .await?
+                .is_some()
+        }
+        Principal::Operator => content_repo::deck_exists(&state.pool, deck_id).await?,
+    };
+    if !visible {
+        return Err(ApiError::NotFound("Deck not found".to_string()));
+    }
+
+    if state.speech.is_none()
+        && payload
+            .modes
+            .as_ref()
+            .is_some_and(|modes| modes.contains(&ReviewMode::Pronunciation))
+    {
+        return Err(ApiError::Validation(
+            "Pronunciation practice needs a speech-to-text provider".to_string(),
+        ));
+    }
+
+    // Stored once each, in the order of `ReviewMode::ALL`
+    let modes = payload.modes.map(|modes| {
+        ReviewMode::ALL
+            .into_iter()
+            .filter(|mode| modes.contains(mode))
+            .collect()
+    });
+    let settings = DeckSettings {
+        max_interval_days: payload.max_interval_days,
+        starting_ease: payload.starting_ease,
+        new_card_order: payload.new_card_order,
+        modes,
+        updated_at: None,
+    };
+    let saved = deck_settings_repo::save(&state.pool, deck_id, &settings.to_new()).await?;
+    Ok(Json(saved.into()))
+}
@@ -0,0 +1,240 @@
+//! Workload and retention previews for a deck.
+//!
+//! Runs [`mms_srs::simulate`] from where the signed-in user stands with the
+//! deck, so they can see what studying it will cost before changing their
+//! daily new cards or the deck's scheduling settings.
+
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    routing::get,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+use mms_srs::simulate::{self, AccuracyModel, Simulation, StudiedCard};
+
+use crate::{
+    ApiState,
+    auth::AuthUser,
+    error::{ApiError, ErrorResponse},
+    middleware::rate_limit,
+    practice::scheduler::{
+        DeckSettings, MAX_INTERVAL_DAYS_LIMIT, MAX_STARTING_EASE, MIN_STARTING_EASE,
+        SchedulerConfig,
+    },
+    preferences::{MAX_NEW_CARDS_PER_DAY, Preferences},
+};
+
+use mms_db::repositories::{
+    deck as deck_repo, deck_settings as deck_settings_repo, practice as practice_repo,
+    preference as preference_repo,
+};
+use mms_db::tenancy::Tenant;
+
+const DEFAULT_SIMULATION_DAYS: u32 = 30;
+const MAX_SIMULATION_DAYS: u32 = 365;
+
+/// Create the deck simulation routes
+pub fn routes() -> Router<ApiState> {
+    use crate::make_rate_limit_layer;
+
+    Router::new()
+        .route("/decks/{deck_id}/simulate", get(simulate_deck))
+        .layer(make_rate_limit_layer!(
+            rate_limit::GENERAL_RATE_PER_SECOND,
+            rate_limit::GENERAL_BURST_SIZE
+        ))
+}
+
+#[derive(Debug, Deserialize, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+struct SimulateQuery {
+    /// Days to simulate, today included, 1 to 365 (default 30)
+    #[serde(default)]
+    #[validate(range(
+        min = 1,
+        max = MAX_SIMULATION_DAYS,
+        message = "Days must be between 1 and 365"
+    ))]
+    days: Option<u32>,
+    /// New cards a day, 0 to 500 (default: the user's `new_cards_per_day`)
+    #[serde(default)]
+    #[validate(range(
+        min = 0,
+        max = MAX_NEW_CARDS_PER_DAY,
+        message = "New cards per day must be between 0 and 500"
+    ))]
+    new_cards_per_day: Option<i32>,
+    /// Chance the first answer to a new card is right, 0 to 1 (default 0.6)
+    #[serde(default)]
+    #[validate(range(
+        min = 0.0,
+        max = 1.0,
+        message = "New card accuracy must be between 0 and 1"
+    ))]
+    new_card_accuracy: Option<f64>,
+    /// Chance a card is recalled after its default interval, 0 to 1 (default 0.9)
+    #[serde(default)]
+    #[validate(range(min = 0.0, max = 1.0, message = "Recall must be between 0 and 1"))]
+    recall: Option<f64>,
+    /// A longest interval to try, 1 to 365, in place of the effective one
+    #[serde(default)]
+    #[validate(range(
+        min = 1,
+        max = MAX_INTERVAL_DAYS_LIMIT,
+        message = "Max interval days must be between 1 and 365"
+    ))]
+    max_interval_days: Option<i32>,
+    /// An ease to try, 0.5 to 2.0, in place of the effective one
+    #[serde(default)]
+    #[validate(range(
+        min = MIN_STARTING_EASE,
+        max = MAX_STARTING_EASE,
+        message = "Starting ease must be between 0.5 and 2.0"
+    ))]
+    starting_ease: Option<f64>,
+}
+
+/// Expected figures of one day; counts are averages, so they can be fractional
+#[derive(Debug, Serialize, ToSchema)]
+struct SimulatedDay {
+    /// Days from today, 0 being today
+    day: u32,
+    /// Cards studied for the first time
+    new_cards: u32,
+    /// Reviews of cards studied on an earlier day
+    reviews: f64,
+    /// Share of `reviews` answered correctly; absent without reviews
+    retention: Option<f64>,
+    /// Cards mastered at the end of the day
+    mastered: f64,
+}
+
+impl From<simulate::SimulatedDay> for SimulatedDay {
+    fn from(day: simulate::SimulatedDay) -> Self {
+        Self {
+            day: day.day,
+            new_cards: day.new_cards,
+            reviews: round(day.reviews, 1),
+            retention: day.retention().map(|retention| round(retention, 3)),
+            mastered: round(day.mastered, 1),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct DeckSimulation {
+    /// The settings simulated
+    config: SchedulerConfig,
+    new_cards_per_day: i32,
+    /// Cards of the deck the user has studied
+    studied_cards: usize,
+    /// Cards of the deck the user has not studied yet
+    new_cards: u32,
+    /// Reviews over the whole simulation
+    total_reviews: f64,
+    /// Share of all reviews answered correctly; absent without reviews
+    retention: Option<f64>,
+    days: Vec<SimulatedDay>,
+}
+
+/// Preview the daily workload and retention of studying a deck
+///
+/// Starts from the signed-in user's progress with the deck's cards and the
+/// settings their reviews in it are scheduled with; `max_interval_days` and
+/// `starting_ease` try other values in their place. The learner is simulated
+/// as studying once a day, clearing every due card, and answering as the
+/// accuracy parameters say. Counts are expected values, not a random run.
+#[utoipa::path(
+    get,
+    path = "/v1/decks/{deck_id}/simulate",
+    tag = "decks",
+    security(("cookie_auth" = [])),
+    params(("deck_id" = Uuid, Path), SimulateQuery),
+    responses(
+        (status = 200, description = "The projected workload", body = DeckSimulation),
+        (status = 400, description = "A parameter out of range", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Deck not found", body = ErrorResponse),
+    )
+)]
+async fn simulate_deck(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(deck_id): Path<Uuid>,
+    Query(query): Query<SimulateQuery>,
+) -> Result<Json<DeckSimulation>, ApiError> {
+    query.validate()?;
+
+    let mut conn = state.read_pool.acquire().await?;
+    if deck_repo::find_by_id(&mut *conn, Tenant::Member(auth_user.user_id), deck_id)
+        .await?
+        .is_none()
+    {
+        return Err(ApiError::NotFound("Deck not found".to_string()));
+    }
+
+    let preferences = preference_repo::find(&mut *conn, auth_user.user_id)
+        .await?
+        .map(Preferences::from)
+        .unwrap_or_default();
+    let deck = deck_settings_repo::find(&mut *conn, deck_id)
+        .await?
+        .map(DeckSettings::from)
+        .unwrap_or_default();
+    let mut config = SchedulerConfig::resolve(&preferences, &deck);
+    if let Some(max_interval_days) = query.max_interval_days {
+        config.max_interval_days = max_interval_days;
+    }
+    if let Some(starting_ease) = query.starting_ease {
+        config.starting_ease = starting_ease;
+    }
+
+    let mut studied = Vec::new();
+    let mut new_cards = 0;
+    for card in practice_repo::card_states(&mut *conn, auth_user.user_id, deck_id).await? {
+        match (card.score, card.due_in_days) {
+            (Some(score), Some(due_in_days)) => studied.push(StudiedCard {
+                score,
+                due_in_days: due_in_days.max(0) as u32,
+            }),
+            _ => new_cards += 1,
+        }
+    }
+
+    let defaults = AccuracyModel::default();
+    let new_cards_per_day = query
+        .new_cards_per_day
+        .unwrap_or(preferences.new_cards_per_day);
+    let simulation = Simulation {
+        options: config.schedule_options(),
+        accuracy: AccuracyModel {
+            new_card: query.new_card_accuracy.unwrap_or(defaults.new_card),
+            recall: query.recall.unwrap_or(defaults.recall),
+        },
+        days: query.days.unwrap_or(DEFAULT_SIMULATION_DAYS),
+        new_cards,
+        new_cards_per_day: new_cards_per_day.max(0) as u32,
+    };
+    let forecast = simulate::simulate(&simulation, &studied);
+
+    let total_reviews: f64 = forecast.iter().map(|day| day.reviews).sum();
+    let correct: f64 = forecast.iter().map(|day| day.correct).sum();
+    Ok(Json(DeckSimulation {
+        config,
+        new_cards_per_day,
+        studied_cards: studied.len(),
+        new_cards,
+        total_reviews: round(total_reviews, 1),
+        retention: (total_reviews > 0.0).then(|| round(correct / total_reviews, 3)),
+        days: forecast.into_iter().map(SimulatedDay::from).collect(),
+    }))
+}
+
+fn round(value: f64, decimals: i32) -> f64 {
+    let scale = 10f64.powi(decimals);
+    (value * scale).round() / scale
+}
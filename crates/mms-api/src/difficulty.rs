@@ -0,0 +1,65 @@
+//! Global flashcard difficulty.
+//!
+//! Aggregates every learner's progress into one score per card (see
+//! [`mms_srs::difficulty_score`]) and stores it in `flashcard_difficulty`.
+//! Practice payloads expose the score so clients can warn learners about hard
+//! cards, and reviews use it to shorten the first intervals of those cards.
+//! Rebuilt daily by the background job; cards with fewer than
+//! [`mms_srs::MIN_DIFFICULTY_REVIEWS`] reviews are left without a score.
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use mms_db::repositories::difficulty as difficulty_repo;
+
+use crate::error::ApiError;
+
+/// Cards written per statement
+const UPSERT_BATCH_SIZE: usize = 1000;
+
+/// Recompute the difficulty of every card with enough reviews.
+///
+/// Returns the number of cards scored.
+pub async fn refresh_difficulty_scores(pool: &PgPool) -> Result<u64, ApiError> {
+    let totals =
+        difficulty_repo::list_card_review_totals(pool, mms_srs::MIN_DIFFICULTY_REVIEWS).await?;
+
+    let mut tx = pool.begin().await?;
+    let mut scored = 0;
+
+    for batch in totals.chunks(UPSERT_BATCH_SIZE) {
+        let mut flashcard_ids: Vec<Uuid> = Vec::with_capacity(batch.len());
+        let mut scores = Vec::with_capacity(batch.len());
+        let mut failure_rates = Vec::with_capacity(batch.len());
+        let mut avg_response_ms = Vec::with_capacity(batch.len());
+        let mut reviews = Vec::with_capacity(batch.len());
+        let mut learners = Vec::with_capacity(batch.len());
+
+        for card in batch {
+            let failure_rate = card.failures as f64 / card.reviews as f64;
+            let avg_ms = (card.timed_reviews > 0)
+                .then(|| card.total_response_ms as f64 / card.timed_reviews as f64);
+
+            flashcard_ids.push(card.flashcard_id);
+            scores.push(mms_srs::difficulty_score(failure_rate, avg_ms) as f32);
+            failure_rates.push(failure_rate as f32);
+            avg_response_ms.push(avg_ms.map(|ms| ms.round() as i32));
+            reviews.push(i32::try_from(card.reviews).unwrap_or(i32::MAX));
+            learners.push(i32::try_from(card.learners).unwrap_or(i32::MAX));
+        }
+
+        scored += difficulty_repo::upsert_difficulty_scores(
+            &mut *tx,
+            &flashcard_ids,
+            &scores,
+            &failure_rates,
+            &avg_response_ms,
+            &reviews,
+            &learners,
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(scored)
+}
@@ -0,0 +1,10 @@
+//! Email preferences, reachable from the unsubscribe link in every digest.
+//!
+//! Signed-in users manage them under `/users/me`; the link in an email carries
+//! a signed token instead (see [`token`]), so people can unsubscribe without
+//! signing in.
+
+pub mod routes;
+pub mod token;
+
+pub use routes::routes;
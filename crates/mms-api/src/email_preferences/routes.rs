@@ -0,0 +1,161 @@
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    routing::get,
+};
+use serde::Deserialize;
+use sqlx::{PgPool, types::Uuid};
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+use crate::{
+    ApiState,
+    auth::AuthUser,
+    email_preferences::token,
+    error::{ApiError, ErrorResponse},
+    validation::ValidJson,
+};
+
+use mms_db::models::EmailPreferences;
+use mms_db::repositories::user as user_repo;
+
+/// Create the email preferences routes
+pub fn routes() -> Router<ApiState> {
+    Router::new()
+        .route(
+            "/users/me/email-preferences",
+            get(get_my_email_preferences).patch(update_my_email_preferences),
+        )
+        .route(
+            "/email-preferences",
+            get(get_email_preferences).patch(update_email_preferences),
+        )
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct PreferencesQuery {
+    /// Token from the link at the bottom of an email
+    token: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+struct UpdateEmailPreferences {
+    /// Weekly progress digest
+    #[serde(default)]
+    weekly_digest: Option<bool>,
+    /// Daily review reminder
+    #[serde(default)]
+    review_reminders: Option<bool>,
+    /// Email when the streak is about to end
+    #[serde(default)]
+    streak_reminders: Option<bool>,
+}
+
+/// The signed-in user's email preferences
+#[utoipa::path(
+    get,
+    path = "/v1/users/me/email-preferences",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    responses(
+        (status = 200, description = "Email preferences", body = EmailPreferences),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
+async fn get_my_email_preferences(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+) -> Result<Json<EmailPreferences>, ApiError> {
+    Ok(Json(load(&state.pool, auth_user.user_id).await?))
+}
+
+/// Turn the signed-in user's emails on or off
+#[utoipa::path(
+    patch,
+    path = "/v1/users/me/email-preferences",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    request_body = UpdateEmailPreferences,
+    responses(
+        (status = 200, description = "Email preferences updated", body = EmailPreferences),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
+async fn update_my_email_preferences(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    ValidJson(payload): ValidJson<UpdateEmailPreferences>,
+) -> Result<Json<EmailPreferences>, ApiError> {
+    Ok(Json(
+        update(&state.pool, auth_user.user_id, &payload).await?,
+    ))
+}
+
+/// Email preferences of the user an email link was sent to
+#[utoipa::path(
+    get,
+    path = "/v1/email-preferences",
+    tag = "users",
+    params(PreferencesQuery),
+    responses(
+        (status = 200, description = "Email preferences", body = EmailPreferences),
+        (status = 401, description = "Invalid token", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
+async fn get_email_preferences(
+    State(state): State<ApiState>,
+    Query(query): Query<PreferencesQuery>,
+) -> Result<Json<EmailPreferences>, ApiError> {
+    let user_id = token::verify(&query.token, &state.auth.feed_keys)?;
+
+    Ok(Json(load(&state.pool, user_id).await?))
+}
+
+/// Unsubscribe from an email link, or turn emails back on, without signing in
+#[utoipa::path(
+    patch,
+    path = "/v1/email-preferences",
+    tag = "users",
+    params(PreferencesQuery),
+    request_body = UpdateEmailPreferences,
+    responses(
+        (status = 200, description = "Email preferences updated", body = EmailPreferences),
+        (status = 401, description = "Invalid token", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
+async fn update_email_preferences(
+    State(state): State<ApiState>,
+    Query(query): Query<PreferencesQuery>,
+    ValidJson(payload): ValidJson<UpdateEmailPreferences>,
+) -> Result<Json<EmailPreferences>, ApiError> {
+    let user_id = token::verify(&query.token, &state.auth.feed_keys)?;
+
+    Ok(Json(update(&state.pool, user_id, &payload).await?))
+}
+
+async fn load(pool: &PgPool, user_id: Uuid) -> Result<EmailPreferences, ApiError> {
+    user_repo::get_email_preferences(pool, user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))
+}
+
+async fn update(
+    pool: &PgPool,
+    user_id: Uuid,
+    payload: &UpdateEmailPreferences,
+) -> Result<EmailPreferences, ApiError> {
+    user_repo::update_email_preferences(
+        pool,
+        user_id,
+        payload.weekly_digest,
+        payload.review_reminders,
+        payload.streak_reminders,
+    )
+    .await?
+    .ok_or_else(|| ApiError::NotFound("User not found".to_string()))
+}
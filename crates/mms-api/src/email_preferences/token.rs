@@ -0,0 +1,68 @@
+//! Signed tokens authorising the email preferences link.
+//!
+//! Like calendar feed tokens, they are signed with the feed token key under
+//! their own audience and do not expire: an unsubscribe link in an old email
+//! must keep working, including after the access token keys rotate. They only
+//! grant access to the user's email preferences.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+
+use crate::{auth::jwt::JwtKeys, error::ApiError};
+
+/// Audience of email preferences tokens
+const AUDIENCE: &str = "email-preferences";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PreferencesClaims {
+    sub: String,
+    aud: String,
+    iat: usize,
+}
+
+/// Sign a preferences token for the user
+pub fn issue(user_id: Uuid, keys: &JwtKeys) -> Result<String, ApiError> {
+    keys.sign(&PreferencesClaims {
+        sub: user_id.to_string(),
+        aud: AUDIENCE.to_string(),
+        iat: Utc::now().timestamp() as usize,
+    })
+}
+
+/// Check the signature and audience; returns the user the token was issued for
+pub fn verify(token: &str, keys: &JwtKeys) -> Result<Uuid, ApiError> {
+    let claims: PreferencesClaims = keys.verify_with(token, |validation| {
+        validation.set_audience(&[AUDIENCE]);
+        validation.set_required_spec_claims(&["aud", "sub"]);
+        validation.validate_exp = false;
+    })?;
+
+    Uuid::parse_str(&claims.sub).map_err(|_| ApiError::Auth("Invalid or expired token".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar;
+
+    const SECRET: &str = "test_jwt_secret_minimum_32_characters_long";
+
+    #[test]
+    fn test_issue_and_verify() {
+        let keys = JwtKeys::hmac(SECRET);
+        let user_id = Uuid::new_v4();
+
+        let token = issue(user_id, &keys).unwrap();
+
+        assert_eq!(verify(&token, &keys).unwrap(), user_id);
+    }
+
+    #[test]
+    fn test_calendar_tokens_are_rejected() {
+        let keys = JwtKeys::hmac(SECRET);
+        let calendar = calendar::token::issue(Uuid::new_v4(), 1, &keys).unwrap();
+
+        assert!(verify(&calendar, &keys).is_err());
+    }
+}
@@ -1,9 +1,15 @@
 use axum::{
     Json,
-    http::StatusCode,
+    extract::Request,
+    http::{StatusCode, header},
+    middleware::Next,
     response::{IntoResponse, Response},
 };
+use serde::Serialize;
 use thiserror::Error;
+use utoipa::ToSchema;
+
+use crate::middleware::request_id;
 
 #[derive(Error, Debug)]
 pub enum ApiError {
@@ -17,78 +23,421 @@ pub enum ApiError {
     InvalidIdToken(String),
     #[error("Authentication error: {0}")]
     Auth(String),
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
     #[error("Validation error: {0}")]
     Validation(String),
+    /// Validation errors tied to the request fields they concern
+    #[error("Invalid fields: {0:?}")]
+    InvalidFields(Vec<FieldError>),
     #[error("Conflict: {0}")]
     Conflict(String),
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
     #[error("Database error: {0}")]
     Database(#[from] sqlx::Error),
     #[error("Password hashing error: {0}")]
     Bcrypt(#[from] bcrypt::BcryptError),
     #[error("Email error: {0}")]
     Email(String),
+    #[error("Media store error: {0}")]
+    Media(String),
     #[error("Not found: {0}")]
     NotFound(String),
+    #[error("Captcha error: {0}")]
+    Captcha(String),
+    #[error("Speech-to-text error: {0}")]
+    Speech(String),
+}
+
+/// What went wrong, for clients to branch on; unlike messages, codes never change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// The request could not be read: malformed JSON, a missing query parameter
+    BadRequest,
+    /// A value was read but is not acceptable; `details` lists the fields when known
+    ValidationFailed,
+    /// The OIDC flow cookie is missing or does not match
+    InvalidCookie,
+    /// The identity provider's ID token was rejected
+    InvalidIdToken,
+    /// Not signed in, or wrong credentials
+    Unauthenticated,
+    /// The access token is invalid or expired
+    InvalidToken,
+    /// Signed in but not allowed
+    Forbidden,
+    NotFound,
+    MethodNotAllowed,
+    /// The resource already exists or changed meanwhile
+    Conflict,
+    PayloadTooLarge,
+    UnsupportedMediaType,
+    /// Retry after the `Retry-After` header
+    RateLimited,
+    /// The captcha provider could not be reached
+    CaptchaUnavailable,
+    /// Speech recognition is not configured or its provider could not be reached
+    SpeechUnavailable,
+    /// The server is overloaded or shutting down; retry after `Retry-After`
+    Unavailable,
+    InternalError,
+}
+
+impl ErrorCode {
+    /// The code for an error response that did not come with one
+    #[must_use]
+    pub fn from_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::BAD_REQUEST => Self::BadRequest,
+            StatusCode::UNAUTHORIZED => Self::Unauthenticated,
+            StatusCode::FORBIDDEN => Self::Forbidden,
+            StatusCode::NOT_FOUND => Self::NotFound,
+            StatusCode::METHOD_NOT_ALLOWED => Self::MethodNotAllowed,
+            StatusCode::CONFLICT => Self::Conflict,
+            StatusCode::PAYLOAD_TOO_LARGE => Self::PayloadTooLarge,
+            StatusCode::UNSUPPORTED_MEDIA_TYPE => Self::UnsupportedMediaType,
+            StatusCode::UNPROCESSABLE_ENTITY => Self::ValidationFailed,
+            StatusCode::TOO_MANY_REQUESTS => Self::RateLimited,
+            StatusCode::SERVICE_UNAVAILABLE => Self::Unavailable,
+            status if status.is_client_error() => Self::BadRequest,
+            _ => Self::InternalError,
+        }
+    }
+}
+
+/// A request field and why it was rejected
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct FieldError {
+    /// Name of the field as sent, e.g. `new_password`
+    #[schema(example = "email")]
+    pub field: String,
+    #[schema(example = "Invalid email format")]
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Body of every error response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub code: ErrorCode,
+    #[schema(example = "Not authenticated")]
+    pub message: String,
+    /// Rejected fields, for validation errors; empty otherwise
+    pub details: Vec<FieldError>,
+    /// The `X-Request-ID` of the request, to quote when reporting a problem
+    pub request_id: Option<String>,
+}
+
+impl ErrorResponse {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            details: Vec::new(),
+            request_id: request_id::current(),
+        }
+    }
+
+    pub fn with_details(mut self, details: Vec<FieldError>) -> Self {
+        self.details = details;
+        self
+    }
+
+    /// Respond with this body and `status`
+    pub fn into_response_with(self, status: StatusCode) -> Response {
+        (status, Json(self)).into_response()
+    }
 }
 
+impl ApiError {
+    /// A validation error for a single field
+    pub fn field(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::InvalidFields(vec![FieldError::new(field, message)])
+    }
+
+    /// Run several field checks and report every failure at once.
+    ///
+    /// [`ApiError::Validation`] failures are attributed to their field; any
+    /// other error is returned as is.
+    pub fn check_fields<'a>(
+        checks: impl IntoIterator<Item = (&'a str, Result<(), ApiError>)>,
+    ) -> Result<(), ApiError> {
+        let mut fields = Vec::new();
+        for (field, check) in checks {
+            match check {
+                Ok(()) => {}
+                Err(ApiError::Validation(message)) => fields.push(FieldError::new(field, message)),
+                Err(ApiError::InvalidFields(errors)) => fields.extend(errors),
+                Err(e) => return Err(e),
+            }
+        }
+        if fields.is_empty() {
+            Ok(())
+        } else {
+            Err(ApiError::InvalidFields(fields))
+        }
+    }
+}
+
+const INTERNAL_ERROR_MESSAGE: &str = "An internal error occurred. Please try again later.";
+
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
-        let (status, message) = match self {
+        let (status, body) = self.into_error_response();
+        body.into_response_with(status)
+    }
+}
+
+impl ApiError {
+    /// The status and body the error is reported with; internal errors are
+    /// logged here and their details left out of the body
+    pub fn into_error_response(self) -> (StatusCode, ErrorResponse) {
+        let (status, code, message) = match self {
             ApiError::Oidc(msg) => {
                 tracing::error!(error = %msg, "OIDC error occurred");
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
-                    "An internal error occurred. Please try again later.".to_string(),
+                    ErrorCode::InternalError,
+                    INTERNAL_ERROR_MESSAGE.to_string(),
                 )
             }
-            ApiError::Cookie(msg) => (StatusCode::BAD_REQUEST, msg),
+            ApiError::Cookie(msg) => (StatusCode::BAD_REQUEST, ErrorCode::InvalidCookie, msg),
             ApiError::Jwt(e) => {
                 tracing::error!(error = %e, "JWT error occurred");
                 (
                     StatusCode::UNAUTHORIZED,
+                    ErrorCode::InvalidToken,
                     "Invalid or expired token".to_string(),
                 )
             }
-            ApiError::InvalidIdToken(msg) => (StatusCode::BAD_REQUEST, msg),
-            ApiError::Auth(msg) => (StatusCode::UNAUTHORIZED, msg),
-            ApiError::Validation(msg) => (StatusCode::BAD_REQUEST, msg),
-            ApiError::Conflict(msg) => (StatusCode::CONFLICT, msg),
+            ApiError::InvalidIdToken(msg) => {
+                (StatusCode::BAD_REQUEST, ErrorCode::InvalidIdToken, msg)
+            }
+            ApiError::Auth(msg) => (StatusCode::UNAUTHORIZED, ErrorCode::Unauthenticated, msg),
+            ApiError::Forbidden(msg) => (StatusCode::FORBIDDEN, ErrorCode::Forbidden, msg),
+            ApiError::Validation(msg) => {
+                (StatusCode::BAD_REQUEST, ErrorCode::ValidationFailed, msg)
+            }
+            ApiError::InvalidFields(fields) => {
+                let message = fields
+                    .iter()
+                    .map(|field| field.message.as_str())
+                    .collect::<Vec<_>>()
+                    .join("; ");
+                return (
+                    StatusCode::BAD_REQUEST,
+                    ErrorResponse::new(ErrorCode::ValidationFailed, message).with_details(fields),
+                );
+            }
+            ApiError::Conflict(msg) => (StatusCode::CONFLICT, ErrorCode::Conflict, msg),
+            ApiError::PayloadTooLarge(msg) => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                ErrorCode::PayloadTooLarge,
+                msg,
+            ),
             ApiError::Bcrypt(e) => {
                 tracing::error!(error = %e, "Password hashing error occurred");
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
-                    "An internal error occurred. Please try again later.".to_string(),
+                    ErrorCode::InternalError,
+                    INTERNAL_ERROR_MESSAGE.to_string(),
                 )
             }
             ApiError::Email(msg) => {
                 tracing::error!(error = %msg, "Email error occurred");
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
-                    "An internal error occurred. Please try again later.".to_string(),
+                    ErrorCode::InternalError,
+                    INTERNAL_ERROR_MESSAGE.to_string(),
+                )
+            }
+            ApiError::Media(msg) => {
+                tracing::error!(error = %msg, "Media store error occurred");
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ErrorCode::InternalError,
+                    INTERNAL_ERROR_MESSAGE.to_string(),
+                )
+            }
+            ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, ErrorCode::NotFound, msg),
+            ApiError::Captcha(msg) => {
+                // Fail closed: without a verdict from the provider we cannot let the request through
+                tracing::error!(error = %msg, "Captcha verification error occurred");
+                (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    ErrorCode::CaptchaUnavailable,
+                    "Captcha verification is temporarily unavailable. Please try again later."
+                        .to_string(),
+                )
+            }
+            ApiError::Speech(msg) => {
+                tracing::error!(error = %msg, "Speech-to-text error occurred");
+                (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    ErrorCode::SpeechUnavailable,
+                    "Speech recognition is temporarily unavailable. Please try again later."
+                        .to_string(),
                 )
             }
-            ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
             ApiError::Database(e) => {
                 if matches!(&e, sqlx::Error::RowNotFound) {
-                    return (
+                    (
                         StatusCode::NOT_FOUND,
-                        Json(serde_json::json!({ "error": "Resource not found" })),
+                        ErrorCode::NotFound,
+                        "Resource not found".to_string(),
+                    )
+                } else {
+                    // Log the actual error for debugging
+                    tracing::error!(error = %e, "Database error occurred");
+
+                    // Never expose internal database errors to users
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        ErrorCode::InternalError,
+                        INTERNAL_ERROR_MESSAGE.to_string(),
                     )
-                        .into_response();
                 }
+            }
+        };
 
-                // Log the actual error for debugging
-                tracing::error!(error = %e, "Database error occurred");
+        (status, ErrorResponse::new(code, message))
+    }
+}
 
-                // Never expose internal database errors to users
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "An internal error occurred. Please try again later.".to_string(),
-                )
-            }
+/// Longest plain-text error body rewritten by [`structure_plain_errors`]
+const MAX_PLAIN_ERROR_BYTES: usize = 4096;
+
+/// Give error responses that were not built from [`ApiError`] the same JSON
+/// shape, so clients can rely on it for every error.
+///
+/// Extractor rejections (malformed JSON, a bad path or query parameter) come
+/// back from axum as plain text; their text becomes the message and the code
+/// follows the status. Bodiless errors such as 405 get a message from the
+/// status line.
+pub async fn structure_plain_errors(request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+    let status = response.status();
+    if !(status.is_client_error() || status.is_server_error()) {
+        return response;
+    }
+    let is_plain = match response.headers().get(header::CONTENT_TYPE) {
+        Some(content_type) => content_type
+            .to_str()
+            .is_ok_and(|value| value.starts_with("text/plain")),
+        None => true,
+    };
+    if !is_plain {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let message = match axum::body::to_bytes(body, MAX_PLAIN_ERROR_BYTES).await {
+        Ok(bytes) if !bytes.is_empty() => String::from_utf8_lossy(&bytes).into_owned(),
+        _ => status
+            .canonical_reason()
+            .unwrap_or("Request failed")
+            .to_string(),
+    };
+
+    let mut structured =
+        ErrorResponse::new(ErrorCode::from_status(status), message).into_response_with(status);
+    for (name, value) in &parts.headers {
+        if name != header::CONTENT_TYPE && name != header::CONTENT_LENGTH {
+            structured.headers_mut().append(name, value.clone());
+        }
+    }
+    structured
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, middleware, routing::get};
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    async fn body(response: Response) -> Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_field_errors_are_reported_together() {
+        let error = ApiError::check_fields([
+            (
+                "email",
+                Err(ApiError::Validation("Invalid email format".into())),
+            ),
+            ("username", Ok(())),
+            ("password", Err(ApiError::Validation("Too short".into()))),
+        ])
+        .unwrap_err();
+
+        let response = error.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let json = body(response).await;
+        assert_eq!(json["code"], "validation_failed");
+        assert_eq!(json["message"], "Invalid email format; Too short");
+        assert_eq!(json["details"][0]["field"], "email");
+        assert_eq!(json["details"][1]["field"], "password");
+        assert!(json["request_id"].is_null());
+
+        assert!(ApiError::check_fields([("email", Ok(()))]).is_ok());
+        assert!(matches!(
+            ApiError::check_fields([("email", Err(ApiError::Conflict("taken".into())))]),
+            Err(ApiError::Conflict(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_internal_errors_are_masked() {
+        let response = ApiError::Email("smtp down".into()).into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let json = body(response).await;
+        assert_eq!(json["code"], "internal_error");
+        assert_eq!(json["message"], INTERNAL_ERROR_MESSAGE);
+        assert_eq!(json["details"], Value::Array(Vec::new()));
+    }
+
+    #[tokio::test]
+    async fn test_plain_text_rejections_are_structured() {
+        let app = Router::new()
+            .route(
+                "/plain",
+                get(|| async { (StatusCode::UNPROCESSABLE_ENTITY, "missing field `deck_id`") }),
+            )
+            .layer(middleware::from_fn(structure_plain_errors));
+        let call = |method: &'static str| {
+            app.clone().oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri("/plain")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
         };
 
-        let error = Json(serde_json::json!({ "error": message }));
-        (status, error).into_response()
+        let json = body(call("GET").await.unwrap()).await;
+        assert_eq!(json["code"], "validation_failed");
+        assert_eq!(json["message"], "missing field `deck_id`");
+
+        let response = call("POST").await.unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert!(response.headers().contains_key(header::ALLOW));
+        let json = body(response).await;
+        assert_eq!(json["code"], "method_not_allowed");
+        assert_eq!(json["message"], "Method Not Allowed");
     }
 }
@@ -0,0 +1,90 @@
+//! Flashcard edits and their history.
+//!
+//! Changing a card's term or translation records the previous text, who made
+//! the change and when. Anyone who can see one of the card's decks can read
+//! its history. Since a card's text is shared by every deck it is in, editors
+//! (the `content:write` permission) may only edit it, or revert it to the text
+//! it had before any recorded edit, when they can change all of those decks.
+//! A revert is recorded like any other edit, so it can be reverted in turn.
+
+pub mod routes;
+
+pub use routes::routes;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use mms_db::{
+    models::FlashcardWithAnswers, repositories::flashcard as flashcard_repo, tenancy::Tenant,
+};
+
+use crate::{
+    admin::cards::check_decks_editable,
+    auth::policy::Principal,
+    error::ApiError,
+    live::{EventBus, LiveEvent},
+};
+
+/// Longest term or translation an editor can set
+pub const MAX_TEXT_CHARS: u64 = 500;
+
+/// Change the card's term and translation, recording the previous text.
+///
+/// Surrounding whitespace is trimmed. Text equal to the card's current text
+/// is not an edit and records nothing. Authors must be able to change every
+/// deck the card is in, which is checked with the card and its deck links
+/// locked so no deck joins unchecked; operators edit any card.
+pub async fn edit_card(
+    pool: &PgPool,
+    events: &EventBus,
+    principal: &Principal,
+    flashcard_id: Uuid,
+    term: &str,
+    translation: &str,
+) -> Result<FlashcardWithAnswers, ApiError> {
+    let (term, translation) = (term.trim(), translation.trim());
+    let not_found = || ApiError::NotFound("Card not found".to_string());
+
+    let mut tx = pool.begin().await?;
+    let mut card = flashcard_repo::lock_cards(&mut *tx, &[flashcard_id])
+        .await?
+        .pop()
+        .ok_or_else(not_found)?;
+    let decks = flashcard_repo::lock_decks_containing(&mut *tx, &[flashcard_id]).await?;
+    let edited_by = match principal {
+        Principal::User(user) => {
+            let tenant = Tenant::Member(user.user_id);
+            if !flashcard_repo::card_in_visible_deck(&mut *tx, flashcard_id, tenant).await? {
+                return Err(not_found());
+            }
+            check_decks_editable(&mut *tx, principal, &decks)
+                .await
+                .map_err(|_| not_found())?;
+            Some(user.user_id)
+        }
+        Principal::Operator => None,
+    };
+    if card.term == term && card.translation == translation {
+        return Ok(card);
+    }
+
+    flashcard_repo::set_text(&mut *tx, flashcard_id, term, translation)
+        .await
+        .map_err(|e| match &e {
+            sqlx::Error::Database(db) if db.is_unique_violation() => ApiError::Conflict(
+                "Another card of the language pair has this term and translation".to_string(),
+            ),
+            _ => e.into(),
+        })?;
+    flashcard_repo::record_edit(&mut *tx, &card, term, translation, edited_by).await?;
+    tx.commit().await?;
+
+    for deck_id in decks {
+        events.broadcast(LiveEvent::DeckUpdated { deck_id });
+    }
+    tracing::info!(%flashcard_id, ?edited_by, "Card edited");
+
+    card.term = term.to_string();
+    card.translation = translation.to_string();
+    Ok(card)
+}
@@ -0,0 +1,161 @@
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    routing::{get, patch, post},
+};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use mms_db::{
+    models::{FlashcardEdit, FlashcardWithAnswers},
+    repositories::flashcard as flashcard_repo,
+    tenancy::Tenant,
+};
+
+use crate::{
+    ApiState,
+    auth::{AuthUser, RequirePermission, permissions::ContentWrite},
+    error::{ApiError, ErrorResponse},
+    flashcards::{MAX_TEXT_CHARS, edit_card},
+    validation::{ValidJson, not_blank},
+};
+
+/// Create the flashcard edit and history routes
+pub fn routes() -> Router<ApiState> {
+    Router::new()
+        .route("/flashcards/{flashcard_id}", patch(update_flashcard))
+        .route("/flashcards/{flashcard_id}/history", get(get_history))
+        .route(
+            "/flashcards/{flashcard_id}/history/{edit_id}/revert",
+            post(revert_edit),
+        )
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+struct FlashcardUpdate {
+    /// New term, up to 500 characters; surrounding whitespace is trimmed
+    #[validate(
+        length(max = MAX_TEXT_CHARS, message = "Term must be at most 500 characters long"),
+        custom(function = "not_blank", message = "Term cannot be empty")
+    )]
+    term: String,
+    /// New translation, up to 500 characters; surrounding whitespace is trimmed
+    #[validate(
+        length(max = MAX_TEXT_CHARS, message = "Translation must be at most 500 characters long"),
+        custom(function = "not_blank", message = "Translation cannot be empty")
+    )]
+    translation: String,
+}
+
+/// Change a card's term and translation
+///
+/// The previous text is kept in the card's history. Sending the current text
+/// changes nothing and records no edit.
+#[utoipa::path(
+    patch,
+    path = "/v1/flashcards/{flashcard_id}",
+    tag = "flashcards",
+    security(("cookie_auth" = ["content:write"]), ("admin_token" = [])),
+    params(("flashcard_id" = Uuid, Path, description = "Card to edit")),
+    request_body = FlashcardUpdate,
+    responses(
+        (status = 200, description = "The card as edited", body = FlashcardWithAnswers),
+        (status = 400, description = "Empty or too long term or translation", body = ErrorResponse),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `content:write` permission", body = ErrorResponse),
+        (status = 404, description = "Card not found, or in a deck of another organization", body = ErrorResponse),
+        (status = 409, description = "Another card has this term and translation", body = ErrorResponse),
+    )
+)]
+async fn update_flashcard(
+    access: RequirePermission<ContentWrite>,
+    State(state): State<ApiState>,
+    Path(flashcard_id): Path<Uuid>,
+    ValidJson(request): ValidJson<FlashcardUpdate>,
+) -> Result<Json<FlashcardWithAnswers>, ApiError> {
+    let card = edit_card(
+        &state.pool,
+        &state.events,
+        &access.principal,
+        flashcard_id,
+        &request.term,
+        &request.translation,
+    )
+    .await?;
+    Ok(Json(card))
+}
+
+#[derive(Serialize, ToSchema)]
+struct FlashcardHistory {
+    /// Edits of the card, newest first
+    edits: Vec<FlashcardEdit>,
+}
+
+/// List the edits made to a card
+#[utoipa::path(
+    get,
+    path = "/v1/flashcards/{flashcard_id}/history",
+    tag = "flashcards",
+    security(("cookie_auth" = [])),
+    params(("flashcard_id" = Uuid, Path, description = "Card to list the edits of")),
+    responses(
+        (status = 200, description = "The card's edits, newest first", body = FlashcardHistory),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Card not found, hidden by a moderator, or only in other organizations' decks", body = ErrorResponse),
+    )
+)]
+async fn get_history(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(flashcard_id): Path<Uuid>,
+) -> Result<Json<FlashcardHistory>, ApiError> {
+    let tenant = Tenant::Member(auth_user.user_id);
+    if !flashcard_repo::card_visible(&state.pool, flashcard_id, tenant).await? {
+        return Err(ApiError::NotFound("Card not found".to_string()));
+    }
+    let edits = flashcard_repo::list_history(&state.pool, flashcard_id).await?;
+    Ok(Json(FlashcardHistory { edits }))
+}
+
+/// Revert a card to the text it had before an edit
+///
+/// Later edits are undone too; the revert is recorded as a new edit.
+#[utoipa::path(
+    post,
+    path = "/v1/flashcards/{flashcard_id}/history/{edit_id}/revert",
+    tag = "flashcards",
+    security(("cookie_auth" = ["content:write"]), ("admin_token" = [])),
+    params(
+        ("flashcard_id" = Uuid, Path, description = "Card to revert"),
+        ("edit_id" = Uuid, Path, description = "Edit whose previous text the card gets back"),
+    ),
+    responses(
+        (status = 200, description = "The card as reverted", body = FlashcardWithAnswers),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `content:write` permission", body = ErrorResponse),
+        (status = 404, description = "Card or edit not found, or the card is in a deck of another organization", body = ErrorResponse),
+        (status = 409, description = "Another card has the previous term and translation", body = ErrorResponse),
+    )
+)]
+async fn revert_edit(
+    access: RequirePermission<ContentWrite>,
+    State(state): State<ApiState>,
+    Path((flashcard_id, edit_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<FlashcardWithAnswers>, ApiError> {
+    let edit = flashcard_repo::find_edit(&state.pool, flashcard_id, edit_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Edit not found".to_string()))?;
+    let card = edit_card(
+        &state.pool,
+        &state.events,
+        &access.principal,
+        flashcard_id,
+        &edit.old_term,
+        &edit.old_translation,
+    )
+    .await?;
+    tracing::info!(%flashcard_id, %edit_id, "Card reverted");
+    Ok(Json(card))
+}
@@ -0,0 +1,164 @@
+//! Daily goals.
+//!
+//! Each user picks a daily goal of a number of reviews or of minutes studied,
+//! 20 reviews by default. The review handler calls [`mark_reached`] in its
+//! transaction; the first review that meets the goal marks the day in
+//! `user_activity`, which counts towards the `goal_days` achievements, and
+//! the handler then publishes [`LiveEvent::DailyGoalReached`]. Flagged
+//! reviews count towards neither kind of goal.
+
+pub mod routes;
+
+pub use routes::routes;
+
+use async_graphql::{Enum, SimpleObject};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::ValidationError;
+
+use mms_db::models::DailyGoalProgress;
+use mms_db::repositories::{practice as practice_repo, user as user_repo};
+
+use crate::{error::ApiError, live::LiveEvent, validation::invalid};
+
+/// Largest reviews goal accepted
+pub const MAX_REVIEWS_GOAL: i32 = 500;
+
+/// Largest minutes goal accepted, four hours
+pub const MAX_MINUTES_GOAL: i32 = 240;
+
+/// What the daily goal counts
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema, Enum)]
+#[serde(rename_all = "lowercase")]
+pub enum DailyGoalKind {
+    /// Plausible reviews
+    #[default]
+    Reviews,
+    /// Minutes studied, from the answer times sent with reviews
+    Minutes,
+}
+
+impl DailyGoalKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DailyGoalKind::Reviews => "reviews",
+            DailyGoalKind::Minutes => "minutes",
+        }
+    }
+
+    fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "reviews" => Some(DailyGoalKind::Reviews),
+            "minutes" => Some(DailyGoalKind::Minutes),
+            _ => None,
+        }
+    }
+
+    fn max_target(self) -> i32 {
+        match self {
+            DailyGoalKind::Reviews => MAX_REVIEWS_GOAL,
+            DailyGoalKind::Minutes => MAX_MINUTES_GOAL,
+        }
+    }
+}
+
+/// Check that a goal of `target` reviews or minutes is within range
+pub fn validate_target(kind: DailyGoalKind, target: i32) -> Result<(), ValidationError> {
+    let max = kind.max_target();
+    if !(1..=max).contains(&target) {
+        return Err(invalid(
+            "range",
+            format!("A {} goal must be between 1 and {max}", kind.as_str()),
+        ));
+    }
+    Ok(())
+}
+
+/// The user's daily goal and today's progress towards it
+#[derive(Debug, Clone, Serialize, ToSchema, SimpleObject)]
+pub struct DailyGoalStatus {
+    pub kind: DailyGoalKind,
+    /// Reviews or minutes to reach, per `kind`
+    pub goal: i32,
+    /// Plausible reviews today, in the user's timezone
+    pub reviews_today: i32,
+    /// Whole minutes studied today
+    pub minutes_today: i64,
+    pub reached: bool,
+}
+
+impl From<DailyGoalProgress> for DailyGoalStatus {
+    fn from(progress: DailyGoalProgress) -> Self {
+        Self {
+            kind: DailyGoalKind::parse(&progress.kind).unwrap_or_default(),
+            goal: progress.target,
+            reviews_today: progress.reviews_today,
+            minutes_today: progress.study_ms_today / 60_000,
+            reached: progress.reached,
+        }
+    }
+}
+
+impl From<&DailyGoalStatus> for LiveEvent {
+    fn from(status: &DailyGoalStatus) -> Self {
+        Self::DailyGoalReached {
+            kind: status.kind,
+            goal: status.goal,
+            reviews_today: status.reviews_today,
+            minutes_today: status.minutes_today,
+        }
+    }
+}
+
+/// The user's daily goal and today's progress
+pub async fn status(pool: &PgPool, user_id: Uuid) -> Result<DailyGoalStatus, ApiError> {
+    user_repo::get_daily_goal(pool, user_id)
+        .await?
+        .map(DailyGoalStatus::from)
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))
+}
+
+/// Mark today's goal reached if today's activity now meets it.
+///
+/// Returns the status only the first time the goal is reached on a day, so
+/// the caller can publish it after committing. Run it before unlocking
+/// achievements so the day counts towards them.
+pub async fn mark_reached(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+) -> Result<Option<DailyGoalStatus>, sqlx::Error> {
+    let reached = practice_repo::mark_goal_reached(&mut **tx, user_id).await?;
+    if reached.is_some() {
+        tracing::debug!(user_id = %user_id, "Daily goal reached");
+    }
+    Ok(reached.map(DailyGoalStatus::from))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_targets_are_bounded_per_kind() {
+        assert!(validate_target(DailyGoalKind::Reviews, 0).is_err());
+        assert!(validate_target(DailyGoalKind::Reviews, 1).is_ok());
+        assert!(validate_target(DailyGoalKind::Reviews, MAX_REVIEWS_GOAL).is_ok());
+        assert!(validate_target(DailyGoalKind::Minutes, MAX_MINUTES_GOAL).is_ok());
+        assert!(validate_target(DailyGoalKind::Minutes, MAX_MINUTES_GOAL + 1).is_err());
+    }
+
+    #[test]
+    fn test_minutes_are_whole() {
+        let status = DailyGoalStatus::from(DailyGoalProgress {
+            kind: "minutes".to_string(),
+            target: 10,
+            reviews_today: 30,
+            study_ms_today: 599_999,
+            reached: false,
+        });
+        assert_eq!(status.kind, DailyGoalKind::Minutes);
+        assert_eq!(status.minutes_today, 9);
+    }
+}
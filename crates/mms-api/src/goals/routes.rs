@@ -0,0 +1,113 @@
+use axum::{Json, Router, extract::State, routing::get};
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::{Validate, ValidationErrors};
+
+use crate::{
+    ApiState, achievements,
+    auth::AuthUser,
+    error::{ApiError, ErrorResponse},
+    live::LiveEvent,
+    validation::ValidJson,
+};
+
+use mms_db::repositories::user as user_repo;
+
+use super::{DailyGoalKind, DailyGoalStatus, mark_reached, status, validate_target};
+
+/// Create the daily goal routes
+pub fn routes() -> Router<ApiState> {
+    Router::new().route(
+        "/users/me/daily-goal",
+        get(get_daily_goal).put(update_daily_goal),
+    )
+}
+
+/// The signed-in user's daily goal and today's progress
+#[utoipa::path(
+    get,
+    path = "/v1/users/me/daily-goal",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    responses(
+        (status = 200, description = "Daily goal", body = DailyGoalStatus),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
+async fn get_daily_goal(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+) -> Result<Json<DailyGoalStatus>, ApiError> {
+    Ok(Json(status(&state.pool, auth_user.user_id).await?))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct DailyGoalSettings {
+    kind: DailyGoalKind,
+    /// Reviews (1 to 500) or minutes (1 to 240), per `kind`
+    target: i32,
+}
+
+// The range of `target` depends on `kind`, which field validators cannot see
+impl Validate for DailyGoalSettings {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if let Err(error) = validate_target(self.kind, self.target) {
+            errors.add("target", error);
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Set the signed-in user's daily goal
+///
+/// Takes effect today. A goal that today's activity already meets is reached
+/// immediately.
+#[utoipa::path(
+    put,
+    path = "/v1/users/me/daily-goal",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    request_body = DailyGoalSettings,
+    responses(
+        (status = 200, description = "Daily goal set", body = DailyGoalStatus),
+        (status = 400, description = "Target out of range", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
+async fn update_daily_goal(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    ValidJson(payload): ValidJson<DailyGoalSettings>,
+) -> Result<Json<DailyGoalStatus>, ApiError> {
+    let user_id = auth_user.user_id;
+
+    let mut tx = state.pool.begin().await?;
+    if !user_repo::update_daily_goal(&mut *tx, user_id, payload.kind.as_str(), payload.target)
+        .await?
+    {
+        return Err(ApiError::NotFound("User not found".to_string()));
+    }
+    let reached = mark_reached(&mut tx, user_id).await?;
+    let unlocked = if reached.is_some() {
+        achievements::unlock_reached(&mut tx, user_id).await?
+    } else {
+        Vec::new()
+    };
+    tx.commit().await?;
+
+    for notification in &unlocked {
+        state.events.publish(user_id, LiveEvent::from(notification));
+    }
+    if let Some(reached) = &reached {
+        state.events.publish(user_id, LiveEvent::from(reached));
+    }
+
+    Ok(Json(status(&state.pool, user_id).await?))
+}
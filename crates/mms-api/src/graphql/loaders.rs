@@ -0,0 +1,141 @@
+//! Batch loaders over the repositories.
+//!
+//! Each loader collects the ids requested while a query resolves and fetches
+//! them in one query. Loaders are built per request for the signed-in user, so
+//! their cache never outlives the request or crosses users.
+
+use std::collections::HashMap;
+
+use async_graphql::dataloader::{DataLoader, Loader};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use mms_db::models::{Deck, DeckProgress, Roadmap, RoadmapMetadata, RoadmapNode};
+use mms_db::repositories::{deck as deck_repo, roadmap as roadmap_repo};
+
+use super::graphql_error;
+
+/// The pool and the user a loader fetches for
+#[derive(Debug, Clone)]
+pub struct Scope {
+    pool: PgPool,
+    user_id: Uuid,
+}
+
+/// Roadmaps the user may see, by id
+#[derive(Debug)]
+pub struct RoadmapLoader(Scope);
+
+/// The user's progress on roadmaps, by roadmap id
+#[derive(Debug)]
+pub struct RoadmapProgressLoader(Scope);
+
+/// The nodes of roadmaps, by roadmap id
+#[derive(Debug)]
+pub struct RoadmapNodesLoader(Scope);
+
+/// Decks the user may see, by id
+#[derive(Debug)]
+pub struct DeckLoader(Scope);
+
+/// The user's progress on decks, by deck id
+#[derive(Debug)]
+pub struct DeckProgressLoader(Scope);
+
+/// The loaders of one request
+pub struct Loaders {
+    pub roadmaps: DataLoader<RoadmapLoader>,
+    pub roadmap_progress: DataLoader<RoadmapProgressLoader>,
+    pub roadmap_nodes: DataLoader<RoadmapNodesLoader>,
+    pub decks: DataLoader<DeckLoader>,
+    pub deck_progress: DataLoader<DeckProgressLoader>,
+}
+
+impl Loaders {
+    pub fn new(pool: PgPool, user_id: Uuid) -> Self {
+        let scope = Scope { pool, user_id };
+        Self {
+            roadmaps: DataLoader::new(RoadmapLoader(scope.clone()), tokio::spawn),
+            roadmap_progress: DataLoader::new(RoadmapProgressLoader(scope.clone()), tokio::spawn),
+            roadmap_nodes: DataLoader::new(RoadmapNodesLoader(scope.clone()), tokio::spawn),
+            decks: DataLoader::new(DeckLoader(scope.clone()), tokio::spawn),
+            deck_progress: DataLoader::new(DeckProgressLoader(scope), tokio::spawn),
+        }
+    }
+}
+
+impl Loader<Uuid> for RoadmapLoader {
+    type Value = Roadmap;
+    type Error = async_graphql::Error;
+
+    async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, Roadmap>, Self::Error> {
+        let roadmaps = roadmap_repo::list_by_ids(&self.0.pool, keys, self.0.user_id)
+            .await
+            .map_err(graphql_error)?;
+        Ok(roadmaps
+            .into_iter()
+            .map(|roadmap| (roadmap.id, roadmap))
+            .collect())
+    }
+}
+
+impl Loader<Uuid> for RoadmapProgressLoader {
+    type Value = RoadmapMetadata;
+    type Error = async_graphql::Error;
+
+    async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, RoadmapMetadata>, Self::Error> {
+        let progress =
+            roadmap_repo::list_metadata_with_progress(&self.0.pool, keys, self.0.user_id)
+                .await
+                .map_err(graphql_error)?;
+        Ok(progress
+            .into_iter()
+            .map(|roadmap| (roadmap.id, roadmap))
+            .collect())
+    }
+}
+
+impl Loader<Uuid> for RoadmapNodesLoader {
+    type Value = Vec<RoadmapNode>;
+    type Error = async_graphql::Error;
+
+    async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, Vec<RoadmapNode>>, Self::Error> {
+        let nodes = roadmap_repo::list_nodes(&self.0.pool, keys, self.0.user_id)
+            .await
+            .map_err(graphql_error)?;
+        // Roadmaps without visible nodes get an empty list rather than nothing
+        let mut by_roadmap: HashMap<Uuid, Vec<RoadmapNode>> =
+            keys.iter().map(|&id| (id, Vec::new())).collect();
+        for node in nodes {
+            by_roadmap.entry(node.roadmap_id).or_default().push(node);
+        }
+        Ok(by_roadmap)
+    }
+}
+
+impl Loader<Uuid> for DeckLoader {
+    type Value = Deck;
+    type Error = async_graphql::Error;
+
+    async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, Deck>, Self::Error> {
+        let decks = deck_repo::list_by_ids(&self.0.pool, keys, self.0.user_id)
+            .await
+            .map_err(graphql_error)?;
+        Ok(decks.into_iter().map(|deck| (deck.id, deck)).collect())
+    }
+}
+
+impl Loader<Uuid> for DeckProgressLoader {
+    type Value = DeckProgress;
+    type Error = async_graphql::Error;
+
+    async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, DeckProgress>, Self::Error> {
+        let progress = deck_repo::list_progress(&self.0.pool, keys, self.0.user_id)
+            .await
+            .map_err(graphql_error)?;
+        Ok(progress
+            .into_iter()
+            .map(|progress| (progress.deck_id, progress))
+            .collect())
+    }
+}
@@ -0,0 +1,98 @@
+//! GraphQL endpoint for the dashboard and progress views.
+//!
+//! `POST /v1/graphql` answers in one round trip what the dashboard otherwise
+//! gathers from the stats, heatmap, roadmap progress and due count routes. It
+//! only reads: the schema has no mutations or subscriptions, and every query
+//! runs as the signed-in user, who sees what the REST routes would show them.
+//! Being read-only, every resolver reads from the read replica.
+//!
+//! Roadmaps, decks and progress are fetched through [`loaders`], so a query
+//! touching many roadmaps or decks costs one database query per kind of data
+//! rather than one per item. `GET /v1/graphql/schema` returns the schema in
+//! SDL for code generators.
+//!
+//! Errors follow the GraphQL convention, in the `errors` array with a 200
+//! response; each carries the REST error `code` in its `extensions`.
+
+pub mod loaders;
+pub mod query;
+pub mod routes;
+
+use std::sync::LazyLock;
+
+use async_graphql::{EmptyMutation, EmptySubscription, ErrorExtensions, Schema};
+
+use crate::error::ApiError;
+
+pub use routes::routes;
+
+/// The GraphQL schema
+pub type DashboardSchema = Schema<query::Query, EmptyMutation, EmptySubscription>;
+
+/// Deepest selection accepted; roadmap → node → deck → progress is 5 deep
+const MAX_DEPTH: usize = 8;
+
+/// Largest query accepted, counting each selected field as 1
+const MAX_COMPLEXITY: usize = 500;
+
+/// Built once; the schema is the same for every request
+static SCHEMA: LazyLock<DashboardSchema> = LazyLock::new(|| {
+    Schema::build(query::Query, EmptyMutation, EmptySubscription)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish()
+});
+
+/// The schema shared by every request
+pub fn schema() -> &'static DashboardSchema {
+    &SCHEMA
+}
+
+/// The signed-in user a query runs as
+#[derive(Debug, Clone, Copy)]
+pub struct Viewer {
+    pub user_id: uuid::Uuid,
+}
+
+/// Report an [`ApiError`] as a GraphQL error with its REST error code
+pub fn graphql_error(error: impl Into<ApiError>) -> async_graphql::Error {
+    let (_, body) = error.into().into_error_response();
+    async_graphql::Error::new(body.message).extend_with(|_, extensions| {
+        if let Ok(code) = async_graphql::to_value(body.code) {
+            extensions.set("code", code);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_errors_carry_the_rest_code() {
+        let error = graphql_error(ApiError::NotFound("Deck not found".to_string()));
+        assert_eq!(error.message, "Deck not found");
+        let extensions = error.extensions.unwrap();
+        assert_eq!(
+            extensions.get("code"),
+            Some(&async_graphql::Value::from("not_found"))
+        );
+    }
+
+    #[test]
+    fn test_database_errors_are_not_exposed() {
+        let error = graphql_error(sqlx::Error::PoolTimedOut);
+        assert!(!error.message.contains("pool"));
+        assert_eq!(
+            error.extensions.unwrap().get("code"),
+            Some(&async_graphql::Value::from("internal_error"))
+        );
+    }
+
+    #[test]
+    fn test_schema_is_read_only() {
+        let sdl = schema().sdl();
+        assert!(sdl.contains("type Query"));
+        assert!(!sdl.contains("type Mutation"));
+    }
+}
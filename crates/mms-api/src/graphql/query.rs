@@ -0,0 +1,390 @@
+//! The query root and the types it returns.
+//!
+//! Repository rows are wrapped rather than exposed directly, so renaming a
+//! column never changes the schema.
+
+use async_graphql::{Context, Object, Result, SimpleObject};
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use mms_db::models;
+use mms_db::repositories::{roadmap as roadmap_repo, user as user_repo, xp as xp_repo};
+use mms_db::tenancy::Tenant;
+
+use crate::{error::ApiError, goals::DailyGoalStatus, validation, xp::XpProgress};
+
+use super::{Viewer, graphql_error, loaders::Loaders};
+
+const DEFAULT_PAGE_LIMIT: i64 = 50;
+const MAX_PAGE_LIMIT: i64 = 100;
+
+/// Days of activity in the heatmap when no `days` is given
+const DEFAULT_HEATMAP_DAYS: i32 = 365;
+
+/// The query root
+#[derive(Debug)]
+pub struct Query;
+
+#[Object]
+impl Query {
+    /// The signed-in user
+    async fn me(&self, ctx: &Context<'_>) -> Result<User> {
+        let viewer = ctx.data::<Viewer>()?;
+        user_repo::find_profile_by_id(ctx.data::<PgPool>()?, viewer.user_id)
+            .await
+            .map_err(graphql_error)?
+            .map(User)
+            .ok_or_else(|| graphql_error(ApiError::NotFound("User not found".to_string())))
+    }
+
+    /// Roadmaps, newest first, optionally for one language pair
+    async fn roadmaps(
+        &self,
+        ctx: &Context<'_>,
+        language_from: Option<String>,
+        language_to: Option<String>,
+        #[graphql(default_with = "DEFAULT_PAGE_LIMIT")] limit: i64,
+        #[graphql(default = 0)] offset: i64,
+    ) -> Result<Vec<Roadmap>> {
+        let viewer = ctx.data::<Viewer>()?;
+        let pool = ctx.data::<PgPool>()?;
+        let tenant = Tenant::Member(viewer.user_id);
+        let (limit, offset) = (limit.clamp(1, MAX_PAGE_LIMIT), offset.max(0));
+
+        let roadmaps =
+            match validation::language_pair(language_from.as_deref(), language_to.as_deref())
+                .map_err(graphql_error)?
+            {
+                Some((language_from, language_to)) => {
+                    roadmap_repo::list_by_language(
+                        pool,
+                        tenant,
+                        language_from,
+                        language_to,
+                        limit,
+                        offset,
+                    )
+                    .await
+                }
+                None => roadmap_repo::list_all(pool, tenant, limit, offset).await,
+            }
+            .map_err(graphql_error)?;
+        Ok(roadmaps.into_iter().map(Roadmap).collect())
+    }
+
+    /// A roadmap, or null if it does not exist or is not visible
+    async fn roadmap(&self, ctx: &Context<'_>, id: Uuid) -> Result<Option<Roadmap>> {
+        let roadmap = ctx.data::<Loaders>()?.roadmaps.load_one(id).await?;
+        Ok(roadmap.map(Roadmap))
+    }
+
+    /// A deck, or null if it does not exist or is not visible
+    async fn deck(&self, ctx: &Context<'_>, id: Uuid) -> Result<Option<Deck>> {
+        let deck = ctx.data::<Loaders>()?.decks.load_one(id).await?;
+        Ok(deck.map(Deck))
+    }
+}
+
+/// A user
+#[derive(Debug)]
+pub struct User(models::UserProfile);
+
+#[Object]
+impl User {
+    async fn id(&self) -> Uuid {
+        self.0.id
+    }
+
+    async fn username(&self) -> &str {
+        &self.0.username
+    }
+
+    async fn email(&self) -> &str {
+        &self.0.email
+    }
+
+    async fn profile_picture_url(&self) -> Option<&str> {
+        self.0.profile_picture_url.as_deref()
+    }
+
+    async fn native_language(&self) -> Option<&str> {
+        self.0.native_language.as_deref()
+    }
+
+    async fn learning_language(&self) -> Option<&str> {
+        self.0.learning_language.as_deref()
+    }
+
+    /// Streaks and totals
+    async fn stats(&self, ctx: &Context<'_>) -> Result<Stats> {
+        let stats = user_repo::get_user_stats(ctx.data::<PgPool>()?, self.0.id)
+            .await
+            .map_err(graphql_error)?;
+        Ok(stats.into())
+    }
+
+    /// Reviews per day over the last `days` days, oldest first; days without reviews are left out
+    async fn heatmap(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(
+            default_with = "DEFAULT_HEATMAP_DAYS",
+            validator(minimum = 1, maximum = 366)
+        )]
+        days: i32,
+    ) -> Result<Vec<ActivityDay>> {
+        let activity = user_repo::get_user_activity(ctx.data::<PgPool>()?, self.0.id, days)
+            .await
+            .map_err(graphql_error)?;
+        Ok(activity.into_iter().map(ActivityDay::from).collect())
+    }
+
+    async fn xp(&self, ctx: &Context<'_>) -> Result<XpProgress> {
+        let xp = xp_repo::get_total(ctx.data::<PgPool>()?, self.0.id)
+            .await
+            .map_err(graphql_error)?;
+        Ok(xp.into())
+    }
+
+    /// Today's goal and progress towards it
+    async fn daily_goal(&self, ctx: &Context<'_>) -> Result<DailyGoalStatus> {
+        crate::goals::status(ctx.data::<PgPool>()?, self.0.id)
+            .await
+            .map_err(graphql_error)
+    }
+
+    /// Reviewed cards due now and today
+    async fn due(&self, ctx: &Context<'_>) -> Result<DueCounts> {
+        let counts = user_repo::get_home_counts(ctx.data::<PgPool>()?, self.0.id)
+            .await
+            .map_err(graphql_error)?;
+        Ok(DueCounts {
+            now: counts.due_now,
+            today: counts.due_today,
+        })
+    }
+}
+
+/// Streaks and totals
+#[derive(Debug, SimpleObject)]
+pub struct Stats {
+    current_streak_days: i32,
+    longest_streak_days: i32,
+    total_reviews: i32,
+    total_cards_learned: i32,
+    last_review_date: Option<NaiveDate>,
+}
+
+impl From<models::UserStats> for Stats {
+    fn from(stats: models::UserStats) -> Self {
+        Self {
+            current_streak_days: stats.current_streak_days,
+            longest_streak_days: stats.longest_streak_days,
+            total_reviews: stats.total_reviews,
+            total_cards_learned: stats.total_cards_learned,
+            last_review_date: stats.last_review_date,
+        }
+    }
+}
+
+/// Reviews on one day, in the user's timezone
+#[derive(Debug, SimpleObject)]
+pub struct ActivityDay {
+    activity_date: NaiveDate,
+    reviews_count: i32,
+}
+
+impl From<models::ActivityDay> for ActivityDay {
+    fn from(day: models::ActivityDay) -> Self {
+        Self {
+            activity_date: day.activity_date,
+            reviews_count: day.reviews_count,
+        }
+    }
+}
+
+/// Reviewed cards due; new cards are not counted
+#[derive(Debug, SimpleObject)]
+pub struct DueCounts {
+    /// Due now
+    now: i64,
+    /// Due before the end of the day in the user's timezone, including `now`
+    today: i64,
+}
+
+/// A roadmap of decks
+#[derive(Debug)]
+pub struct Roadmap(models::Roadmap);
+
+#[Object]
+impl Roadmap {
+    async fn id(&self) -> Uuid {
+        self.0.id
+    }
+
+    /// Names the roadmap in shared links, in place of its id
+    async fn slug(&self) -> &str {
+        &self.0.slug
+    }
+
+    async fn title(&self) -> &str {
+        &self.0.title
+    }
+
+    async fn description(&self) -> Option<&str> {
+        self.0.description.as_deref()
+    }
+
+    async fn language_from(&self) -> &str {
+        &self.0.language_from
+    }
+
+    async fn language_to(&self) -> &str {
+        &self.0.language_to
+    }
+
+    /// How many of the roadmap's decks the user has mastered
+    async fn progress(&self, ctx: &Context<'_>) -> Result<RoadmapProgress> {
+        let progress = ctx
+            .data::<Loaders>()?
+            .roadmap_progress
+            .load_one(self.0.id)
+            .await?
+            .ok_or_else(|| graphql_error(ApiError::NotFound("Roadmap not found".to_string())))?;
+        Ok(progress.into())
+    }
+
+    /// The roadmap's decks, top to bottom, left to right
+    async fn nodes(&self, ctx: &Context<'_>) -> Result<Vec<RoadmapNode>> {
+        let nodes = ctx
+            .data::<Loaders>()?
+            .roadmap_nodes
+            .load_one(self.0.id)
+            .await?
+            .unwrap_or_default();
+        Ok(nodes.into_iter().map(RoadmapNode).collect())
+    }
+}
+
+/// The user's progress on a roadmap; a deck counts once all its cards are mastered
+#[derive(Debug, SimpleObject)]
+pub struct RoadmapProgress {
+    total_nodes: i32,
+    completed_nodes: i32,
+    progress_percentage: f64,
+}
+
+impl From<models::RoadmapMetadata> for RoadmapProgress {
+    fn from(roadmap: models::RoadmapMetadata) -> Self {
+        Self {
+            total_nodes: roadmap.total_nodes,
+            completed_nodes: roadmap.completed_nodes,
+            progress_percentage: roadmap.progress_percentage,
+        }
+    }
+}
+
+/// A deck's place on a roadmap
+#[derive(Debug)]
+pub struct RoadmapNode(models::RoadmapNode);
+
+#[Object]
+impl RoadmapNode {
+    async fn id(&self) -> Uuid {
+        self.0.id
+    }
+
+    /// The node leading to this one; null for the roadmap's starting points
+    async fn parent_node_id(&self) -> Option<Uuid> {
+        self.0.parent_node_id
+    }
+
+    async fn pos_x(&self) -> i32 {
+        self.0.pos_x
+    }
+
+    async fn pos_y(&self) -> i32 {
+        self.0.pos_y
+    }
+
+    async fn deck(&self, ctx: &Context<'_>) -> Result<Deck> {
+        ctx.data::<Loaders>()?
+            .decks
+            .load_one(self.0.deck_id)
+            .await?
+            .map(Deck)
+            .ok_or_else(|| graphql_error(ApiError::NotFound("Deck not found".to_string())))
+    }
+}
+
+/// A deck of flashcards
+#[derive(Debug)]
+pub struct Deck(models::Deck);
+
+#[Object]
+impl Deck {
+    async fn id(&self) -> Uuid {
+        self.0.id
+    }
+
+    /// Names the deck in shared links, in place of its id
+    async fn slug(&self) -> &str {
+        &self.0.slug
+    }
+
+    async fn title(&self) -> &str {
+        &self.0.title
+    }
+
+    async fn description(&self) -> Option<&str> {
+        self.0.description.as_deref()
+    }
+
+    async fn language_from(&self) -> &str {
+        &self.0.language_from
+    }
+
+    async fn language_to(&self) -> &str {
+        &self.0.language_to
+    }
+
+    /// The user's progress on the deck; zero before their first practice
+    async fn progress(&self, ctx: &Context<'_>) -> Result<DeckProgress> {
+        let progress = ctx
+            .data::<Loaders>()?
+            .deck_progress
+            .load_one(self.0.id)
+            .await?
+            .ok_or_else(|| graphql_error(ApiError::NotFound("Deck not found".to_string())))?;
+        Ok(progress.into())
+    }
+}
+
+/// The user's progress on a deck
+#[derive(Debug, SimpleObject)]
+pub struct DeckProgress {
+    total_cards: i32,
+    mastered_cards: i32,
+    /// Cards due now, new cards included
+    cards_due: i32,
+    total_practices: i32,
+    last_practiced_at: Option<DateTime<Utc>>,
+    progress_percentage: f64,
+    /// When the next card falls due; null while cards are due
+    next_practice_at: Option<DateTime<Utc>>,
+}
+
+impl From<models::DeckProgress> for DeckProgress {
+    fn from(progress: models::DeckProgress) -> Self {
+        Self {
+            total_cards: progress.total_cards,
+            mastered_cards: progress.mastered_cards,
+            cards_due: progress.cards_due,
+            total_practices: progress.total_practices,
+            last_practiced_at: progress.last_practiced_at,
+            progress_percentage: progress.progress_percentage,
+            next_practice_at: progress.next_practice_at,
+        }
+    }
+}
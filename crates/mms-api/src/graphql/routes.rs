@@ -0,0 +1,67 @@
+use axum::{
+    Json, Router,
+    extract::State,
+    http::header,
+    response::IntoResponse,
+    routing::{get, post},
+};
+
+use crate::{
+    ApiState, auth::AuthUser, error::ErrorResponse, make_rate_limit_layer, middleware::rate_limit,
+};
+
+use super::{Viewer, loaders::Loaders, schema};
+
+/// Create the GraphQL routes
+pub fn routes() -> Router<ApiState> {
+    Router::new()
+        .route("/graphql", post(execute))
+        .route("/graphql/schema", get(get_schema))
+        .layer(make_rate_limit_layer!(
+            rate_limit::GENERAL_RATE_PER_SECOND,
+            rate_limit::GENERAL_BURST_SIZE
+        ))
+}
+
+/// Run a GraphQL query as the signed-in user
+///
+/// Takes `{"query": ..., "variables": ..., "operationName": ...}` and answers
+/// `{"data": ..., "errors": [...]}`; errors in the query come back with a 200.
+#[utoipa::path(
+    post,
+    path = "/v1/graphql",
+    tag = "graphql",
+    security(("cookie_auth" = [])),
+    request_body(content = Object, description = "A GraphQL request"),
+    responses(
+        (status = 200, description = "The query's data and errors", body = Object),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+async fn execute(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    let request = request
+        .data(Viewer {
+            user_id: auth_user.user_id,
+        })
+        .data(Loaders::new(state.read_pool.clone(), auth_user.user_id))
+        .data(state.read_pool.clone());
+    Json(schema().execute(request).await)
+}
+
+/// The GraphQL schema in SDL
+#[utoipa::path(
+    get,
+    path = "/v1/graphql/schema",
+    tag = "graphql",
+    responses((status = 200, description = "The schema", body = String, content_type = "text/plain"))
+)]
+async fn get_schema() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        schema().sdl(),
+    )
+}
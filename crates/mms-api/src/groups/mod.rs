@@ -0,0 +1,86 @@
+//! Study groups: classrooms where teachers assign decks and follow their learners.
+//!
+//! A group is an organization (see [`mms_db::tenancy`]), so decks and roadmaps
+//! owned by it are only visible to its members. Learners join with the group's
+//! invite code, which teachers share directly or by email. Anyone outside a
+//! group gets a 404 for it, so group ids cannot be probed.
+
+pub mod routes;
+
+pub use routes::routes;
+
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::error::ApiError;
+
+use mms_db::repositories::group as group_repo;
+
+/// A member's role in a group
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupRole {
+    /// Practises the assigned decks
+    Member,
+    /// Invites learners, assigns decks and sees everyone's progress
+    Teacher,
+    /// Created the group; a teacher who can also promote members to teachers
+    Owner,
+}
+
+impl GroupRole {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GroupRole::Member => "member",
+            GroupRole::Teacher => "teacher",
+            GroupRole::Owner => "owner",
+        }
+    }
+
+    fn parse(role: &str) -> Option<Self> {
+        match role {
+            "member" => Some(GroupRole::Member),
+            "teacher" => Some(GroupRole::Teacher),
+            "owner" => Some(GroupRole::Owner),
+            _ => None,
+        }
+    }
+}
+
+/// The user's role in the group; 404 if they are not a member, 403 if the role is below `min`
+pub async fn require_role(
+    pool: &PgPool,
+    group_id: Uuid,
+    user_id: Uuid,
+    min: GroupRole,
+) -> Result<GroupRole, ApiError> {
+    let role = group_repo::find_role(pool, group_id, user_id)
+        .await?
+        .and_then(|role| GroupRole::parse(&role))
+        .ok_or_else(|| ApiError::NotFound("Group not found".to_string()))?;
+
+    if role < min {
+        return Err(ApiError::Forbidden(format!(
+            "Only a group {} can do this",
+            min.as_str()
+        )));
+    }
+    Ok(role)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roles_are_ordered_by_privilege() {
+        assert!(GroupRole::Member < GroupRole::Teacher);
+        assert!(GroupRole::Teacher < GroupRole::Owner);
+        for role in [GroupRole::Member, GroupRole::Teacher, GroupRole::Owner] {
+            assert_eq!(GroupRole::parse(role.as_str()), Some(role));
+        }
+        assert_eq!(GroupRole::parse("admin"), None);
+    }
+}
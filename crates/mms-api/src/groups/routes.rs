@@ -0,0 +1,622 @@
+use std::collections::BTreeMap;
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, post, put},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
+
+use crate::{
+    ApiState,
+    auth::{AuthUser, validation::validate_email},
+    error::{ApiError, ErrorResponse},
+    groups::{GroupRole, require_role},
+    middleware::rate_limit,
+    user::email::EmailJob,
+    validation::{ValidJson, invalid},
+};
+
+use mms_db::models::{Group, GroupAssignment, GroupMember, GroupSummary, MemberDeckProgress};
+use mms_db::repositories::{group as group_repo, user as user_repo};
+
+/// Longest group name accepted, in characters
+const MAX_NAME_CHARS: usize = 100;
+
+/// Create the study group routes
+pub fn routes() -> Router<ApiState> {
+    use crate::make_rate_limit_layer;
+
+    Router::new()
+        .route("/groups", get(list_groups).post(create_group))
+        .route("/groups/join", post(join_group))
+        .route("/groups/{group_id}", get(get_group))
+        .route("/groups/{group_id}/invites", post(invite_by_email))
+        .route(
+            "/groups/{group_id}/members/{user_id}",
+            put(update_member_role).delete(remove_member),
+        )
+        .route(
+            "/groups/{group_id}/decks/{deck_id}",
+            put(assign_deck).delete(unassign_deck),
+        )
+        .route(
+            "/groups/{group_id}/roadmaps/{roadmap_id}",
+            put(assign_roadmap).delete(unassign_roadmap),
+        )
+        .route("/groups/{group_id}/dashboard", get(get_dashboard))
+        .layer(make_rate_limit_layer!(
+            rate_limit::GENERAL_RATE_PER_SECOND,
+            rate_limit::GENERAL_BURST_SIZE
+        ))
+}
+
+#[derive(Serialize, ToSchema)]
+struct GroupView {
+    id: Uuid,
+    name: String,
+    /// The signed-in user's role
+    role: GroupRole,
+    /// Code learners join with; only shown to teachers
+    invite_code: Option<String>,
+    members: Vec<GroupMember>,
+    assignments: Vec<GroupAssignment>,
+    created_at: DateTime<Utc>,
+}
+
+impl GroupView {
+    fn new(
+        group: Group,
+        role: GroupRole,
+        members: Vec<GroupMember>,
+        assignments: Vec<GroupAssignment>,
+    ) -> Self {
+        Self {
+            id: group.id,
+            name: group.name,
+            role,
+            invite_code: (role >= GroupRole::Teacher).then_some(group.invite_code),
+            members,
+            assignments,
+            created_at: group.created_at,
+        }
+    }
+}
+
+/// Groups the signed-in user belongs to
+#[utoipa::path(
+    get,
+    path = "/v1/groups",
+    tag = "groups",
+    security(("cookie_auth" = [])),
+    responses(
+        (status = 200, description = "Groups, by name", body = Vec<GroupSummary>),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+async fn list_groups(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+) -> Result<Json<Vec<GroupSummary>>, ApiError> {
+    let groups = group_repo::list_for_user(&state.pool, auth_user.user_id).await?;
+    Ok(Json(groups))
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+struct CreateGroupRequest {
+    /// 1 to 100 characters
+    #[validate(custom(function = "group_name"))]
+    name: String,
+}
+
+fn group_name(name: &str) -> Result<(), ValidationError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_CHARS {
+        return Err(invalid(
+            "length",
+            format!("Group name must be 1 to {MAX_NAME_CHARS} characters"),
+        ));
+    }
+    Ok(())
+}
+
+/// Create a group owned by the signed-in user
+#[utoipa::path(
+    post,
+    path = "/v1/groups",
+    tag = "groups",
+    security(("cookie_auth" = [])),
+    request_body = CreateGroupRequest,
+    responses(
+        (status = 201, description = "Group created", body = GroupView),
+        (status = 400, description = "Empty or overlong name", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+async fn create_group(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    ValidJson(request): ValidJson<CreateGroupRequest>,
+) -> Result<(StatusCode, Json<GroupView>), ApiError> {
+    let name = request.name.trim();
+
+    let group = group_repo::create(&state.pool, name, auth_user.user_id).await?;
+    let members = group_repo::list_members(&state.pool, group.id).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(GroupView::new(group, GroupRole::Owner, members, Vec::new())),
+    ))
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+struct JoinGroupRequest {
+    /// Code from a teacher, in any case
+    #[validate(length(min = 1, max = 64, message = "Invite code must be 1 to 64 characters"))]
+    invite_code: String,
+}
+
+/// Join a group with its invite code
+#[utoipa::path(
+    post,
+    path = "/v1/groups/join",
+    tag = "groups",
+    security(("cookie_auth" = [])),
+    request_body = JoinGroupRequest,
+    responses(
+        (status = 200, description = "Joined, or already a member", body = GroupView),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "No group has this code", body = ErrorResponse),
+    )
+)]
+async fn join_group(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    ValidJson(request): ValidJson<JoinGroupRequest>,
+) -> Result<Json<GroupView>, ApiError> {
+    let group = group_repo::find_by_invite_code(&state.pool, request.invite_code.trim())
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Invalid invite code".to_string()))?;
+
+    group_repo::add_member(&state.pool, group.id, auth_user.user_id).await?;
+    group_view(&state, group, auth_user.user_id).await.map(Json)
+}
+
+/// A group with its members and assignments
+#[utoipa::path(
+    get,
+    path = "/v1/groups/{group_id}",
+    tag = "groups",
+    security(("cookie_auth" = [])),
+    params(("group_id" = Uuid, Path)),
+    responses(
+        (status = 200, description = "The group", body = GroupView),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Group not found or not a member", body = ErrorResponse),
+    )
+)]
+async fn get_group(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(group_id): Path<Uuid>,
+) -> Result<Json<GroupView>, ApiError> {
+    require_role(&state.pool, group_id, auth_user.user_id, GroupRole::Member).await?;
+    let group = group_repo::find(&state.pool, group_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Group not found".to_string()))?;
+
+    group_view(&state, group, auth_user.user_id).await.map(Json)
+}
+
+async fn group_view(state: &ApiState, group: Group, user_id: Uuid) -> Result<GroupView, ApiError> {
+    let role = require_role(&state.pool, group.id, user_id, GroupRole::Member).await?;
+    let (members, assignments) = tokio::try_join!(
+        group_repo::list_members(&state.pool, group.id),
+        group_repo::list_assignments(&state.pool, group.id, user_id),
+    )?;
+    Ok(GroupView::new(group, role, members, assignments))
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+struct InviteRequest {
+    /// Surrounding whitespace is ignored
+    #[validate(custom(function = "invite_email"))]
+    email: String,
+}
+
+fn invite_email(email: &str) -> Result<(), ValidationError> {
+    validate_email(email.trim())
+}
+
+#[derive(Serialize, ToSchema)]
+struct InviteResponse {
+    message: String,
+}
+
+/// Email the group's invite code to someone
+#[utoipa::path(
+    post,
+    path = "/v1/groups/{group_id}/invites",
+    tag = "groups",
+    security(("cookie_auth" = [])),
+    params(("group_id" = Uuid, Path)),
+    request_body = InviteRequest,
+    responses(
+        (status = 202, description = "Invite queued", body = InviteResponse),
+        (status = 400, description = "Invalid email", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Not a teacher of the group", body = ErrorResponse),
+        (status = 404, description = "Group not found or not a member", body = ErrorResponse),
+    )
+)]
+async fn invite_by_email(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(group_id): Path<Uuid>,
+    ValidJson(request): ValidJson<InviteRequest>,
+) -> Result<(StatusCode, Json<InviteResponse>), ApiError> {
+    let email = request.email.trim();
+    require_role(&state.pool, group_id, auth_user.user_id, GroupRole::Teacher).await?;
+
+    let group = group_repo::find(&state.pool, group_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Group not found".to_string()))?;
+    let inviter = user_repo::find_profile_by_id(&state.pool, auth_user.user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    let job = EmailJob::GroupInvite {
+        to_email: email.to_string(),
+        inviter: inviter.username,
+        group_name: group.name,
+        invite_code: group.invite_code,
+    };
+    match &state.email_tx {
+        Some(tx) => {
+            if let Err(e) = tx.send(job) {
+                tracing::error!(error = %e, group_id = %group_id, "Failed to queue group invite");
+            }
+        }
+        None => {
+            tracing::info!(group_id = %group_id, "Email worker not available - group invite not sent")
+        }
+    }
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(InviteResponse {
+            message: "Invite sent".to_string(),
+        }),
+    ))
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+struct UpdateRoleRequest {
+    /// `teacher` or `member`
+    #[validate(custom(function = "assignable_role"))]
+    role: GroupRole,
+}
+
+fn assignable_role(role: &GroupRole) -> Result<(), ValidationError> {
+    if *role == GroupRole::Owner {
+        return Err(invalid("owner", "A group has exactly one owner"));
+    }
+    Ok(())
+}
+
+/// Make a member a teacher or back
+#[utoipa::path(
+    put,
+    path = "/v1/groups/{group_id}/members/{user_id}",
+    tag = "groups",
+    security(("cookie_auth" = [])),
+    params(("group_id" = Uuid, Path), ("user_id" = Uuid, Path)),
+    request_body = UpdateRoleRequest,
+    responses(
+        (status = 204, description = "Role updated"),
+        (status = 400, description = "Tried to make someone owner", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Not the group's owner", body = ErrorResponse),
+        (status = 404, description = "Group or member not found, or the member is the owner", body = ErrorResponse),
+    )
+)]
+async fn update_member_role(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path((group_id, user_id)): Path<(Uuid, Uuid)>,
+    ValidJson(request): ValidJson<UpdateRoleRequest>,
+) -> Result<StatusCode, ApiError> {
+    require_role(&state.pool, group_id, auth_user.user_id, GroupRole::Owner).await?;
+
+    if !group_repo::update_member_role(&state.pool, group_id, user_id, request.role.as_str())
+        .await?
+    {
+        return Err(ApiError::NotFound("Member not found".to_string()));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Leave a group, or remove a learner from it
+///
+/// Members can remove themselves. Teachers can remove learners; only the owner
+/// can remove teachers. The owner cannot leave.
+#[utoipa::path(
+    delete,
+    path = "/v1/groups/{group_id}/members/{user_id}",
+    tag = "groups",
+    security(("cookie_auth" = [])),
+    params(("group_id" = Uuid, Path), ("user_id" = Uuid, Path)),
+    responses(
+        (status = 204, description = "Member removed"),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Not allowed to remove this member", body = ErrorResponse),
+        (status = 404, description = "Group or member not found, or the member is the owner", body = ErrorResponse),
+    )
+)]
+async fn remove_member(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path((group_id, user_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, ApiError> {
+    let role = require_role(&state.pool, group_id, auth_user.user_id, GroupRole::Member).await?;
+
+    if user_id != auth_user.user_id {
+        let target = group_repo::find_role(&state.pool, group_id, user_id)
+            .await?
+            .and_then(|role| GroupRole::parse(&role))
+            .ok_or_else(|| ApiError::NotFound("Member not found".to_string()))?;
+        let allowed = match target {
+            GroupRole::Member => role >= GroupRole::Teacher,
+            GroupRole::Teacher => role == GroupRole::Owner,
+            GroupRole::Owner => false,
+        };
+        if !allowed {
+            return Err(ApiError::Forbidden(
+                "You cannot remove this member".to_string(),
+            ));
+        }
+    }
+
+    if !group_repo::remove_member(&state.pool, group_id, user_id).await? {
+        return Err(ApiError::NotFound("Member not found".to_string()));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Assign a deck to the group
+///
+/// The deck must be in the public catalogue or belong to the group.
+#[utoipa::path(
+    put,
+    path = "/v1/groups/{group_id}/decks/{deck_id}",
+    tag = "groups",
+    security(("cookie_auth" = [])),
+    params(("group_id" = Uuid, Path), ("deck_id" = Uuid, Path)),
+    responses(
+        (status = 200, description = "Deck assigned, or already assigned", body = GroupAssignment),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Not a teacher of the group", body = ErrorResponse),
+        (status = 404, description = "Group or deck not found", body = ErrorResponse),
+    )
+)]
+async fn assign_deck(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path((group_id, deck_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<GroupAssignment>, ApiError> {
+    require_role(&state.pool, group_id, auth_user.user_id, GroupRole::Teacher).await?;
+
+    let assignment = group_repo::assign_deck(&state.pool, group_id, deck_id, auth_user.user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Deck not found".to_string()))?;
+    Ok(Json(assignment))
+}
+
+/// Stop assigning a deck to the group
+#[utoipa::path(
+    delete,
+    path = "/v1/groups/{group_id}/decks/{deck_id}",
+    tag = "groups",
+    security(("cookie_auth" = [])),
+    params(("group_id" = Uuid, Path), ("deck_id" = Uuid, Path)),
+    responses(
+        (status = 204, description = "Deck unassigned"),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Not a teacher of the group", body = ErrorResponse),
+        (status = 404, description = "Group not found or deck not assigned", body = ErrorResponse),
+    )
+)]
+async fn unassign_deck(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path((group_id, deck_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, ApiError> {
+    require_role(&state.pool, group_id, auth_user.user_id, GroupRole::Teacher).await?;
+
+    if !group_repo::unassign_deck(&state.pool, group_id, deck_id).await? {
+        return Err(ApiError::NotFound("Deck is not assigned".to_string()));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Assign a roadmap to the group; every deck on it counts as assigned
+///
+/// The roadmap must be in the public catalogue or belong to the group.
+#[utoipa::path(
+    put,
+    path = "/v1/groups/{group_id}/roadmaps/{roadmap_id}",
+    tag = "groups",
+    security(("cookie_auth" = [])),
+    params(("group_id" = Uuid, Path), ("roadmap_id" = Uuid, Path)),
+    responses(
+        (status = 200, description = "Roadmap assigned, or already assigned", body = GroupAssignment),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Not a teacher of the group", body = ErrorResponse),
+        (status = 404, description = "Group or roadmap not found", body = ErrorResponse),
+    )
+)]
+async fn assign_roadmap(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path((group_id, roadmap_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<GroupAssignment>, ApiError> {
+    require_role(&state.pool, group_id, auth_user.user_id, GroupRole::Teacher).await?;
+
+    let assignment =
+        group_repo::assign_roadmap(&state.pool, group_id, roadmap_id, auth_user.user_id)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Roadmap not found".to_string()))?;
+    Ok(Json(assignment))
+}
+
+/// Stop assigning a roadmap to the group
+#[utoipa::path(
+    delete,
+    path = "/v1/groups/{group_id}/roadmaps/{roadmap_id}",
+    tag = "groups",
+    security(("cookie_auth" = [])),
+    params(("group_id" = Uuid, Path), ("roadmap_id" = Uuid, Path)),
+    responses(
+        (status = 204, description = "Roadmap unassigned"),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Not a teacher of the group", body = ErrorResponse),
+        (status = 404, description = "Group not found or roadmap not assigned", body = ErrorResponse),
+    )
+)]
+async fn unassign_roadmap(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path((group_id, roadmap_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, ApiError> {
+    require_role(&state.pool, group_id, auth_user.user_id, GroupRole::Teacher).await?;
+
+    if !group_repo::unassign_roadmap(&state.pool, group_id, roadmap_id).await? {
+        return Err(ApiError::NotFound("Roadmap is not assigned".to_string()));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, PartialEq, Serialize, ToSchema)]
+struct DeckProgress {
+    deck_id: Uuid,
+    deck_title: String,
+    total_cards: i32,
+    mastered_cards: i32,
+    progress_percentage: f64,
+    /// Absent until the learner practises the deck
+    last_practiced_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, PartialEq, Serialize, ToSchema)]
+struct LearnerProgress {
+    user_id: Uuid,
+    username: String,
+    /// Every assigned deck, including those on assigned roadmaps, by title
+    decks: Vec<DeckProgress>,
+}
+
+/// Each learner's progress on every assigned deck
+#[utoipa::path(
+    get,
+    path = "/v1/groups/{group_id}/dashboard",
+    tag = "groups",
+    security(("cookie_auth" = [])),
+    params(("group_id" = Uuid, Path)),
+    responses(
+        (status = 200, description = "Learners by username; teachers are left out", body = Vec<LearnerProgress>),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Not a teacher of the group", body = ErrorResponse),
+        (status = 404, description = "Group not found or not a member", body = ErrorResponse),
+    )
+)]
+async fn get_dashboard(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(group_id): Path<Uuid>,
+) -> Result<Json<Vec<LearnerProgress>>, ApiError> {
+    require_role(&state.pool, group_id, auth_user.user_id, GroupRole::Teacher).await?;
+
+    let (members, progress) = tokio::try_join!(
+        group_repo::list_members(&state.pool, group_id),
+        group_repo::member_progress(&state.pool, group_id, auth_user.user_id),
+    )?;
+    Ok(Json(learner_progress(&members, progress)))
+}
+
+/// Group deck rows by learner, keeping learners who have no assigned decks yet
+fn learner_progress(
+    members: &[GroupMember],
+    rows: Vec<MemberDeckProgress>,
+) -> Vec<LearnerProgress> {
+    let mut decks: BTreeMap<Uuid, Vec<DeckProgress>> = BTreeMap::new();
+    for row in rows {
+        decks.entry(row.user_id).or_default().push(DeckProgress {
+            deck_id: row.deck_id,
+            deck_title: row.deck_title,
+            total_cards: row.total_cards,
+            mastered_cards: row.mastered_cards,
+            progress_percentage: row.progress_percentage,
+            last_practiced_at: row.last_practiced_at,
+        });
+    }
+
+    members
+        .iter()
+        .filter(|member| member.role == GroupRole::Member.as_str())
+        .map(|member| LearnerProgress {
+            user_id: member.user_id,
+            username: member.username.clone(),
+            decks: decks.remove(&member.user_id).unwrap_or_default(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(username: &str, role: &str) -> GroupMember {
+        GroupMember {
+            user_id: Uuid::new_v4(),
+            username: username.to_string(),
+            role: role.to_string(),
+            joined_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_learner_progress_groups_rows_by_learner() {
+        let members = [
+            member("teacher", "owner"),
+            member("ana", "member"),
+            member("bo", "member"),
+        ];
+        let ana_id = members[1].user_id;
+
+        let learners = learner_progress(
+            &members,
+            vec![MemberDeckProgress {
+                user_id: ana_id,
+                username: "ana".to_string(),
+                deck_id: Uuid::new_v4(),
+                deck_title: "Greetings".to_string(),
+                total_cards: 10,
+                mastered_cards: 4,
+                progress_percentage: 40.0,
+                last_practiced_at: None,
+            }],
+        );
+
+        assert_eq!(learners.len(), 2);
+        assert_eq!(learners[0].username, "ana");
+        assert_eq!(learners[0].decks.len(), 1);
+        assert_eq!(learners[0].decks[0].mastered_cards, 4);
+        assert_eq!(learners[1].username, "bo");
+        assert!(learners[1].decks.is_empty());
+    }
+}
@@ -0,0 +1,273 @@
+//! Readiness checks of the services the API depends on.
+//!
+//! `/health/ready` runs every check at once, each bounded by [`CHECK_TIMEOUT`].
+//! Postgres and its migrations are required: without them the instance is
+//! unhealthy and should be taken out of rotation, unless cached public listings
+//! let it keep serving degraded. Redis and the email provider are not: rate
+//! limits fall back to in-process buckets and emails wait in the outbox, so
+//! losing them only degrades the instance. The email provider is a third-party
+//! API, so its result is reused for [`EMAIL_CHECK_TTL`] rather than asked on
+//! every probe.
+
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use mms_db::repositories::maintenance as maintenance_repo;
+
+use crate::{middleware::rate_limit, state::ApiState};
+
+/// Longest a single dependency may take to answer
+pub const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long an email provider check is reused
+pub const EMAIL_CHECK_TTL: Duration = Duration::from_secs(60);
+
+/// Outcome of checking one dependency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Up,
+    Down,
+    /// Not configured on this instance
+    Disabled,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DependencyCheck {
+    pub status: CheckStatus,
+    /// Time the check took; absent when it was not run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    /// Why the dependency is down
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl DependencyCheck {
+    fn disabled() -> Self {
+        Self {
+            status: CheckStatus::Disabled,
+            latency_ms: None,
+            error: None,
+        }
+    }
+
+    fn is_down(&self) -> bool {
+        self.status == CheckStatus::Down
+    }
+}
+
+/// Overall readiness of the instance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Readiness {
+    /// Every dependency is up
+    Ready,
+    /// Serving, but an optional dependency is down or public listings come from cache
+    Degraded,
+    /// A required dependency is down; stop routing traffic here
+    Unhealthy,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Checks {
+    /// Postgres answers queries
+    pub database: DependencyCheck,
+    /// Every migration this build ships is applied
+    pub migrations: DependencyCheck,
+    /// Redis holding shared rate limits, when `RATE_LIMIT_BACKEND=redis`
+    pub redis: DependencyCheck,
+    /// The configured email provider accepts connections and credentials
+    pub email: DependencyCheck,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ReadinessReport {
+    pub status: Readiness,
+    pub version: &'static str,
+    pub checks: Checks,
+}
+
+/// Results kept between probes
+#[derive(Debug, Clone, Default)]
+pub struct HealthState {
+    email: Arc<Mutex<Option<(Instant, DependencyCheck)>>>,
+}
+
+/// Check every dependency and decide whether the instance is ready
+pub async fn check(state: &ApiState) -> ReadinessReport {
+    let (database, migrations, redis, email) = tokio::join!(
+        check_database(state),
+        check_migrations(state),
+        check_redis(),
+        check_email(state),
+    );
+
+    if database.is_down() {
+        state.public_cache.mark_unavailable();
+    } else {
+        state.public_cache.mark_available();
+    }
+
+    let checks = Checks {
+        database,
+        migrations,
+        redis,
+        email,
+    };
+    ReadinessReport {
+        status: readiness(&checks, state.public_cache.has_entries()),
+        version: env!("CARGO_PKG_VERSION"),
+        checks,
+    }
+}
+
+/// Unhealthy when a required dependency is down, degraded when an optional one is
+fn readiness(checks: &Checks, has_cached_listings: bool) -> Readiness {
+    if checks.database.is_down() {
+        // Migrations cannot be checked either; cached listings are all there is to serve
+        return if has_cached_listings {
+            Readiness::Degraded
+        } else {
+            Readiness::Unhealthy
+        };
+    }
+    if checks.migrations.is_down() {
+        return Readiness::Unhealthy;
+    }
+    if checks.redis.is_down() || checks.email.is_down() {
+        return Readiness::Degraded;
+    }
+    Readiness::Ready
+}
+
+/// Run `probe` within [`CHECK_TIMEOUT`], timing it
+async fn timed<E: std::fmt::Display>(
+    probe: impl Future<Output = Result<(), E>>,
+) -> DependencyCheck {
+    let started = Instant::now();
+    let result = tokio::time::timeout(CHECK_TIMEOUT, probe).await;
+    let latency_ms = Some(started.elapsed().as_millis() as u64);
+    let error = match result {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(_) => Some(format!("No answer within {} ms", CHECK_TIMEOUT.as_millis())),
+    };
+    DependencyCheck {
+        status: if error.is_some() {
+            CheckStatus::Down
+        } else {
+            CheckStatus::Up
+        },
+        latency_ms,
+        error,
+    }
+}
+
+async fn check_database(state: &ApiState) -> DependencyCheck {
+    timed(async { maintenance_repo::ping(&state.pool).await }).await
+}
+
+async fn check_migrations(state: &ApiState) -> DependencyCheck {
+    timed(async {
+        let pending = mms_db::pending_migrations(&state.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        match pending.as_slice() {
+            [] => Ok(()),
+            [version, ..] => Err(format!(
+                "{} migration(s) not applied, starting with {version}",
+                pending.len()
+            )),
+        }
+    })
+    .await
+}
+
+async fn check_redis() -> DependencyCheck {
+    match rate_limit::installed_redis() {
+        Some(redis) => timed(redis.ping()).await,
+        None => DependencyCheck::disabled(),
+    }
+}
+
+async fn check_email(state: &ApiState) -> DependencyCheck {
+    let Some(mailer) = &state.mailer else {
+        return DependencyCheck::disabled();
+    };
+
+    if let Some((checked_at, check)) = state.health.email.lock().unwrap().as_ref()
+        && checked_at.elapsed() < EMAIL_CHECK_TTL
+    {
+        return check.clone();
+    }
+
+    let check = timed(mailer.check()).await;
+    if check.is_down() {
+        tracing::warn!(error = ?check.error, "Email provider check failed");
+    }
+    *state.health.email.lock().unwrap() = Some((Instant::now(), check.clone()));
+    check
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn up() -> DependencyCheck {
+        DependencyCheck {
+            status: CheckStatus::Up,
+            latency_ms: Some(1),
+            error: None,
+        }
+    }
+
+    fn down(error: &str) -> DependencyCheck {
+        DependencyCheck {
+            status: CheckStatus::Down,
+            latency_ms: Some(1),
+            error: Some(error.to_string()),
+        }
+    }
+
+    fn checks() -> Checks {
+        Checks {
+            database: up(),
+            migrations: up(),
+            redis: DependencyCheck::disabled(),
+            email: up(),
+        }
+    }
+
+    #[test]
+    fn test_required_dependencies_make_the_instance_unhealthy() {
+        assert_eq!(readiness(&checks(), false), Readiness::Ready);
+
+        let mut pending = checks();
+        pending.migrations = down("1 migration(s) not applied");
+        assert_eq!(readiness(&pending, true), Readiness::Unhealthy);
+
+        let mut no_database = checks();
+        no_database.database = down("connection refused");
+        no_database.migrations = down("connection refused");
+        assert_eq!(readiness(&no_database, false), Readiness::Unhealthy);
+        assert_eq!(readiness(&no_database, true), Readiness::Degraded);
+    }
+
+    #[test]
+    fn test_optional_dependencies_only_degrade() {
+        let mut no_email = checks();
+        no_email.email = down("SendGrid unreachable");
+        assert_eq!(readiness(&no_email, false), Readiness::Degraded);
+
+        let mut no_redis = checks();
+        no_redis.redis = down("connection refused");
+        assert_eq!(readiness(&no_redis, false), Readiness::Degraded);
+    }
+}
@@ -0,0 +1,8 @@
+//! Everything the app shows on startup, in one response.
+//!
+//! The shape is versioned: fields may be added at any time, and `version` is
+//! bumped when one is removed or changes meaning.
+
+pub mod routes;
+
+pub use routes::routes;
@@ -0,0 +1,131 @@
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    routing::get,
+};
+use serde::Serialize;
+use sqlx::types::Uuid;
+use utoipa::ToSchema;
+
+use crate::{
+    ApiState,
+    auth::{AuthUser, require_self, routes::UserResponse},
+    error::{ApiError, ErrorResponse},
+    goals::DailyGoalStatus,
+};
+
+use mms_db::models::{ActivityDay, DeckDueCount, LearningProfile, RoadmapMetadata, UserStats};
+use mms_db::repositories::{
+    deck as deck_repo, profile as profile_repo, roadmap as roadmap_repo, user as user_repo,
+};
+
+/// Version of the [`Home`] shape
+pub const HOME_VERSION: u32 = 1;
+
+/// Days of activity in the heatmap, as on the dashboard
+const HEATMAP_DAYS: i32 = 365;
+
+/// Create the home screen routes
+pub fn routes() -> Router<ApiState> {
+    Router::new().route("/users/{user_id}/home", get(get_home))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct DueCounts {
+    /// Reviewed cards due now; new cards are not counted
+    now: i64,
+    /// Reviewed cards due before the end of the day in the user's timezone, including `now`
+    today: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct ProfileSummary {
+    #[serde(flatten)]
+    profile: LearningProfile,
+    due_now: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct Home {
+    /// Shape version; see the module documentation
+    version: u32,
+    user: UserResponse,
+    stats: UserStats,
+    /// Reviews per day over the last year, oldest first; days without reviews are left out
+    heatmap: Vec<ActivityDay>,
+    daily_goal: DailyGoalStatus,
+    due: DueCounts,
+    /// Reviewed cards due now per deck, most due first; decks with nothing due are left out
+    decks_due: Vec<DeckDueCount>,
+    /// Roadmaps the user has practised, most recently practised first
+    roadmaps: Vec<RoadmapMetadata>,
+    /// Oldest first
+    profiles: Vec<ProfileSummary>,
+    unread_notifications: i64,
+}
+
+/// The user, their stats and heatmap, today's goal, due counts, active roadmaps, learning profiles
+/// and unread notifications
+#[utoipa::path(
+    get,
+    path = "/v1/users/{user_id}/home",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    params(("user_id" = Uuid, Path, description = "Must be the signed-in user")),
+    responses(
+        (status = 200, description = "Home screen data", body = Home),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Another user's home", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
+async fn get_home(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<Home>, ApiError> {
+    require_self(&auth_user, user_id)?;
+
+    let pool = &state.pool;
+    let (user, stats, heatmap, daily_goal, counts, decks_due, roadmaps, profiles, profile_due) = tokio::try_join!(
+        user_repo::find_profile_by_id(pool, user_id),
+        user_repo::get_user_stats(pool, user_id),
+        user_repo::get_user_activity(pool, user_id, HEATMAP_DAYS),
+        user_repo::get_daily_goal(pool, user_id),
+        user_repo::get_home_counts(pool, user_id),
+        deck_repo::count_due_by_deck(pool, user_id),
+        roadmap_repo::list_active_with_progress(pool, user_id),
+        profile_repo::list_for_user(pool, user_id),
+        profile_repo::count_due_by_profile(pool, user_id),
+    )?;
+    let (Some(user), Some(daily_goal)) = (user, daily_goal) else {
+        return Err(ApiError::NotFound("User not found".to_string()));
+    };
+
+    let profiles = profiles
+        .into_iter()
+        .map(|profile| ProfileSummary {
+            due_now: profile_due
+                .iter()
+                .find(|due| due.profile_id == profile.id)
+                .map_or(0, |due| due.due_now),
+            profile,
+        })
+        .collect();
+
+    Ok(Json(Home {
+        version: HOME_VERSION,
+        user: user.into(),
+        stats,
+        heatmap,
+        daily_goal: daily_goal.into(),
+        due: DueCounts {
+            now: counts.due_now,
+            today: counts.due_today,
+        },
+        decks_due,
+        roadmaps,
+        profiles,
+        unread_notifications: counts.unread_notifications,
+    }))
+}
@@ -0,0 +1,302 @@
+//! Safe retries for POSTs.
+//!
+//! A client that sends `Idempotency-Key` with a POST can retry it after a
+//! timeout or a dropped connection: the first response to the key is stored
+//! and sent again instead of running the request twice. Keys belong to the
+//! signed-in user, or the client IP before sign-in, and are kept for
+//! [`RETENTION_HOURS`].
+//!
+//! This is an extractor wrapping the body extractor rather than a layer,
+//! because route layers are built before the state and the stored responses
+//! live in the database. Only the status, `Content-Type` and body are
+//! replayed, so it suits endpoints that set no cookies.
+//!
+//! Requests are told apart by a fingerprint keyed with the cookie signing key:
+//! bodies such as a registration carry a password, and a plain hash stored for
+//! a day could be brute-forced offline.
+
+use std::net::SocketAddr;
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{ConnectInfo, FromRequest, FromRequestParts, OriginalUri, Request},
+    http::{HeaderName, HeaderValue, Method, StatusCode, header::CONTENT_TYPE},
+    response::{IntoResponse, Response},
+};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use sqlx::PgPool;
+
+use mms_db::repositories::idempotency as idempotency_repo;
+
+use crate::{ApiState, auth::AuthUser, error::ApiError};
+
+pub const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+
+/// Set on responses replayed from an earlier request
+pub const IDEMPOTENT_REPLAYED: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+/// Longest key accepted; UUIDs are the expected choice
+const MAX_KEY_LENGTH: usize = 255;
+
+/// Hours a response is kept for retries
+pub const RETENTION_HOURS: i32 = 24;
+
+/// Seconds after which a request that never finished, e.g. because the server
+/// restarted, gives its key up to a retry
+const ABANDONED_AFTER_SECS: i32 = 60;
+
+/// Extracts `T` from a request that may carry an `Idempotency-Key`.
+///
+/// A retry is answered with the stored response before `T` is extracted or the
+/// handler runs. The handler passes its response through
+/// [`Idempotency::finish`] so retries can get it.
+pub struct Idempotent<T>(pub Idempotency, pub T);
+
+/// The key a request holds, if it sent one
+pub struct Idempotency {
+    claim: Option<Claim>,
+}
+
+struct Claim {
+    pool: PgPool,
+    scope: String,
+    key: String,
+}
+
+impl<T> FromRequest<ApiState> for Idempotent<T>
+where
+    T: FromRequest<ApiState> + Send,
+{
+    type Rejection = Response;
+
+    async fn from_request(request: Request, state: &ApiState) -> Result<Self, Self::Rejection> {
+        let key = match request.headers().get(&IDEMPOTENCY_KEY).map(parse_key) {
+            Some(key) => key.map_err(IntoResponse::into_response)?,
+            None => return extract_untracked(request, state).await,
+        };
+        if request.method() != Method::POST {
+            return extract_untracked(request, state).await;
+        }
+
+        let (mut parts, body) = request.into_parts();
+        let scope = match AuthUser::from_request_parts(&mut parts, state).await {
+            Ok(user) => format!("user:{}", user.user_id),
+            Err(_) => match parts.extensions.get::<ConnectInfo<SocketAddr>>() {
+                Some(ConnectInfo(addr)) => format!("ip:{}", addr.ip()),
+                None => return extract_untracked(Request::from_parts(parts, body), state).await,
+            },
+        };
+
+        let bytes = Bytes::from_request(Request::from_parts(parts.clone(), body), state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        // Nested routers strip the version prefix from `parts.uri`
+        let path = match parts.extensions.get::<OriginalUri>() {
+            Some(OriginalUri(uri)) => uri.path(),
+            None => parts.uri.path(),
+        };
+        let hash = request_hash(
+            state.cookie.cookie_key.signing(),
+            &parts.method,
+            path,
+            &bytes,
+        );
+
+        let pool = state.pool.clone();
+        let claimed = idempotency_repo::claim(&pool, &scope, &key, &hash, ABANDONED_AFTER_SECS)
+            .await
+            .map_err(|e| ApiError::from(e).into_response())?;
+        if !claimed {
+            return Err(replay(&pool, &scope, &key, &hash).await);
+        }
+
+        let claim = Claim { pool, scope, key };
+        let inner = T::from_request(Request::from_parts(parts, Body::from(bytes)), state)
+            .await
+            .map_err(IntoResponse::into_response);
+        match inner {
+            Ok(inner) => Ok(Self(Idempotency { claim: Some(claim) }, inner)),
+            Err(rejection) => Err(claim.finish(rejection).await),
+        }
+    }
+}
+
+async fn extract_untracked<T>(request: Request, state: &ApiState) -> Result<Idempotent<T>, Response>
+where
+    T: FromRequest<ApiState>,
+{
+    let inner = T::from_request(request, state)
+        .await
+        .map_err(IntoResponse::into_response)?;
+    Ok(Idempotent(Idempotency { claim: None }, inner))
+}
+
+impl Idempotency {
+    /// Store `response` for retries with the same key, then return it.
+    ///
+    /// Server errors are not stored: the key is freed so a retry runs again.
+    pub async fn finish(self, response: impl IntoResponse) -> Response {
+        let response = response.into_response();
+        match self.claim {
+            Some(claim) => claim.finish(response).await,
+            None => response,
+        }
+    }
+}
+
+impl Claim {
+    async fn finish(self, response: Response) -> Response {
+        if response.status().is_server_error() {
+            self.release().await;
+            return response;
+        }
+
+        let (parts, body) = response.into_parts();
+        let Ok(bytes) = axum::body::to_bytes(body, usize::MAX).await else {
+            self.release().await;
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        };
+        let content_type = parts
+            .headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok());
+        let stored = idempotency_repo::complete(
+            &self.pool,
+            &self.scope,
+            &self.key,
+            parts.status.as_u16() as i16,
+            content_type,
+            &bytes,
+        )
+        .await;
+        if let Err(e) = stored {
+            tracing::warn!(error = %e, "Failed to store response for idempotency key");
+            self.release().await;
+        }
+
+        Response::from_parts(parts, Body::from(bytes))
+    }
+
+    async fn release(&self) {
+        if let Err(e) = idempotency_repo::release(&self.pool, &self.scope, &self.key).await {
+            tracing::warn!(error = %e, "Failed to release idempotency key");
+        }
+    }
+}
+
+/// The response stored for a key that is already taken
+async fn replay(pool: &PgPool, scope: &str, key: &str, hash: &[u8]) -> Response {
+    let stored = match idempotency_repo::find(pool, scope, key).await {
+        Ok(stored) => stored,
+        Err(e) => return ApiError::from(e).into_response(),
+    };
+    // Gone since the claim means the first request just failed and freed it
+    let Some(stored) = stored else {
+        return in_progress();
+    };
+    if stored.request_hash != hash {
+        return ApiError::Validation(
+            "Idempotency-Key was already used for a different request".to_string(),
+        )
+        .into_response();
+    }
+    let (Some(status), Some(body)) = (stored.status, stored.body) else {
+        return in_progress();
+    };
+
+    let status = u16::try_from(status)
+        .ok()
+        .and_then(|status| StatusCode::from_u16(status).ok())
+        .unwrap_or(StatusCode::OK);
+    let mut response = (status, body).into_response();
+    let headers = response.headers_mut();
+    match stored.content_type.map(HeaderValue::try_from) {
+        Some(Ok(content_type)) => {
+            headers.insert(CONTENT_TYPE, content_type);
+        }
+        _ => {
+            headers.remove(CONTENT_TYPE);
+        }
+    }
+    headers.insert(IDEMPOTENT_REPLAYED, HeaderValue::from_static("true"));
+    response
+}
+
+fn in_progress() -> Response {
+    ApiError::Conflict(
+        "A request with this Idempotency-Key is still being processed, retry shortly".to_string(),
+    )
+    .into_response()
+}
+
+fn parse_key(value: &HeaderValue) -> Result<String, ApiError> {
+    match value.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LENGTH => Ok(key.to_string()),
+        _ => Err(ApiError::Validation(format!(
+            "Idempotency-Key must be 1 to {MAX_KEY_LENGTH} visible ASCII characters"
+        ))),
+    }
+}
+
+/// Fingerprint of what the request asks for, so a key cannot be reused for
+/// a different one
+/// Fingerprint of the method, path and body, keyed with `secret` so stored
+/// fingerprints reveal nothing about the bodies
+pub fn request_hash(secret: &[u8], method: &Method, path: &str, body: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(method.as_str().as_bytes());
+    mac.update(b" ");
+    mac.update(path.as_bytes());
+    mac.update(b"\n");
+    mac.update(body);
+    mac.finalize().into_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_must_be_short_visible_ascii() {
+        assert!(parse_key(&HeaderValue::from_static("3f2b6c1e-retry")).is_ok());
+        assert!(parse_key(&HeaderValue::from_static("")).is_err());
+        assert!(
+            parse_key(&HeaderValue::from_str(&"k".repeat(MAX_KEY_LENGTH + 1)).unwrap()).is_err()
+        );
+        assert!(parse_key(&HeaderValue::from_bytes(b"caf\xc3\xa9").unwrap()).is_err());
+    }
+
+    #[test]
+    fn hash_covers_path_and_body() {
+        let hash = request_hash(b"secret", &Method::POST, "/v1/sync/push", b"{}");
+        assert_eq!(
+            hash,
+            request_hash(b"secret", &Method::POST, "/v1/sync/push", b"{}")
+        );
+        assert_ne!(
+            hash,
+            request_hash(b"secret", &Method::POST, "/v2/sync/push", b"{}")
+        );
+        assert_ne!(
+            hash,
+            request_hash(b"secret", &Method::POST, "/v1/sync/push", b"{ }")
+        );
+    }
+
+    #[test]
+    fn hash_depends_on_the_secret() {
+        use sha2::Digest;
+
+        let body = br#"{"password":"hunter22"}"#;
+        let hash = request_hash(b"secret", &Method::POST, "/v1/users/register", body);
+        assert_ne!(
+            hash,
+            request_hash(b"other", &Method::POST, "/v1/users/register", body)
+        );
+
+        let mut plain = b"POST /v1/users/register\n".to_vec();
+        plain.extend_from_slice(body);
+        assert_ne!(hash, Sha256::digest(&plain).to_vec());
+    }
+}
@@ -0,0 +1,361 @@
+//! Index advisor report.
+//!
+//! Compares the live database against the indexes declared in
+//! [`mms_db::indexes::EXPECTED_INDEXES`] and the statistics Postgres keeps about
+//! how queries actually run: which expected indexes are missing, which indexes
+//! are never scanned or duplicate another one, which tables are mostly read by
+//! sequential scans, and (when `pg_stat_statements` is available) which
+//! statements take the most time.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use utoipa::ToSchema;
+
+use mms_db::indexes::{EXPECTED_INDEXES, ExpectedIndex};
+use mms_db::models::{IndexStats, StatementStats, TableScanStats};
+use mms_db::repositories::maintenance as maintenance_repo;
+
+use crate::error::ApiError;
+
+/// Number of statements included in the hot query list
+const HOT_QUERY_LIMIT: i64 = 20;
+
+/// Tables smaller than this are cheaper to scan sequentially, so they never get scan hints
+const SEQ_SCAN_MIN_ROWS: i64 = 1000;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct IndexAdvisorReport {
+    pub generated_at: DateTime<Utc>,
+    /// False when `pg_stat_statements` is not installed or not preloaded
+    pub statement_stats_available: bool,
+    pub hot_queries: Vec<HotQuery>,
+    pub missing_indexes: Vec<MissingIndex>,
+    pub unused_indexes: Vec<UnusedIndex>,
+    pub redundant_indexes: Vec<RedundantIndex>,
+    pub scan_hints: Vec<ScanHint>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HotQuery {
+    pub query: String,
+    pub calls: i64,
+    pub total_exec_ms: f64,
+    pub mean_exec_ms: f64,
+    pub rows: i64,
+    /// Share of blocks served from shared buffers
+    pub cache_hit_ratio: f64,
+    /// Application tables the statement mentions
+    pub tables: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema, PartialEq, Eq)]
+pub struct MissingIndex {
+    pub name: &'static str,
+    pub table: &'static str,
+    pub columns: &'static [&'static str],
+    pub used_by: &'static str,
+}
+
+#[derive(Debug, Serialize, ToSchema, PartialEq, Eq)]
+pub struct UnusedIndex {
+    pub table: String,
+    pub index: String,
+    pub size_bytes: i64,
+    /// Declared in the expected index list; may just not have been exercised yet
+    pub expected: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema, PartialEq, Eq)]
+pub struct RedundantIndex {
+    pub table: String,
+    pub index: String,
+    /// Index whose key starts with the same columns
+    pub covered_by: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ScanHint {
+    pub table: String,
+    pub seq_scans: i64,
+    pub index_scans: i64,
+    pub live_rows: i64,
+    pub message: String,
+}
+
+/// Collect statistics and build the report
+pub async fn build_report(pool: &PgPool) -> Result<IndexAdvisorReport, ApiError> {
+    let indexes = maintenance_repo::list_index_stats(pool).await?;
+    let tables = maintenance_repo::list_table_scan_stats(pool).await?;
+    let statements = load_hot_statements(pool).await?;
+
+    let table_names: Vec<&str> = tables.iter().map(|t| t.table_name.as_str()).collect();
+    let statement_stats_available = statements.is_some();
+    let hot_queries = statements
+        .unwrap_or_default()
+        .into_iter()
+        .map(|s| hot_query(s, &table_names))
+        .collect();
+
+    Ok(IndexAdvisorReport {
+        generated_at: Utc::now(),
+        statement_stats_available,
+        hot_queries,
+        missing_indexes: find_missing(EXPECTED_INDEXES, &indexes),
+        unused_indexes: find_unused(EXPECTED_INDEXES, &indexes),
+        redundant_indexes: find_redundant(EXPECTED_INDEXES, &indexes),
+        scan_hints: scan_hints(&tables),
+    })
+}
+
+/// `None` when `pg_stat_statements` cannot be queried
+async fn load_hot_statements(pool: &PgPool) -> Result<Option<Vec<StatementStats>>, ApiError> {
+    if !maintenance_repo::statement_stats_installed(pool).await? {
+        return Ok(None);
+    }
+
+    // Installed but not in shared_preload_libraries fails at query time
+    match maintenance_repo::list_hot_statements(pool, HOT_QUERY_LIMIT).await {
+        Ok(statements) => Ok(Some(statements)),
+        Err(e) => {
+            tracing::warn!(error = %e, "pg_stat_statements is installed but cannot be queried");
+            Ok(None)
+        }
+    }
+}
+
+fn hot_query(stats: StatementStats, table_names: &[&str]) -> HotQuery {
+    let blocks = stats.shared_blocks_hit + stats.shared_blocks_read;
+    let cache_hit_ratio = if blocks == 0 {
+        1.0
+    } else {
+        stats.shared_blocks_hit as f64 / blocks as f64
+    };
+
+    let tables = referenced_tables(&stats.query, table_names);
+
+    HotQuery {
+        query: stats.query,
+        calls: stats.calls,
+        total_exec_ms: stats.total_exec_ms,
+        mean_exec_ms: stats.mean_exec_ms,
+        rows: stats.rows,
+        cache_hit_ratio,
+        tables,
+    }
+}
+
+/// Application tables named in a statement, matched on whole identifiers
+fn referenced_tables(query: &str, table_names: &[&str]) -> Vec<String> {
+    let query = query.to_lowercase();
+    let identifiers: Vec<&str> = query
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .collect();
+
+    table_names
+        .iter()
+        .filter(|table| identifiers.contains(table))
+        .map(|table| (*table).to_string())
+        .collect()
+}
+
+fn starts_with_columns(index: &IndexStats, columns: &[&str]) -> bool {
+    index.columns.len() >= columns.len() && index.columns.iter().zip(columns).all(|(a, b)| a == b)
+}
+
+/// Expected indexes with no index on the table covering their columns
+fn find_missing(expected: &[ExpectedIndex], indexes: &[IndexStats]) -> Vec<MissingIndex> {
+    expected
+        .iter()
+        .filter(|e| {
+            !indexes
+                .iter()
+                .any(|i| i.table_name == e.table && starts_with_columns(i, e.columns))
+        })
+        .map(|e| MissingIndex {
+            name: e.name,
+            table: e.table,
+            columns: e.columns,
+            used_by: e.used_by,
+        })
+        .collect()
+}
+
+/// Indexes never scanned since statistics were last reset.
+///
+/// Unique and primary key indexes enforce constraints and are never reported.
+fn find_unused(expected: &[ExpectedIndex], indexes: &[IndexStats]) -> Vec<UnusedIndex> {
+    indexes
+        .iter()
+        .filter(|i| i.scans == 0 && !i.is_unique && !i.is_primary)
+        .map(|i| UnusedIndex {
+            table: i.table_name.clone(),
+            index: i.index_name.clone(),
+            size_bytes: i.size_bytes,
+            expected: expected.iter().any(|e| e.name == i.index_name),
+        })
+        .collect()
+}
+
+/// Plain indexes whose key is a prefix of another index on the same table.
+///
+/// Expected and partial indexes are kept on purpose and never reported.
+fn find_redundant(expected: &[ExpectedIndex], indexes: &[IndexStats]) -> Vec<RedundantIndex> {
+    indexes
+        .iter()
+        .filter(|i| !i.is_unique && !i.is_primary && !i.is_partial && !i.columns.is_empty())
+        .filter(|i| !expected.iter().any(|e| e.name == i.index_name))
+        .filter_map(|i| {
+            let columns: Vec<&str> = i.columns.iter().map(String::as_str).collect();
+            indexes
+                .iter()
+                .filter(|other| other.index_name != i.index_name && !other.is_partial)
+                .filter(|other| other.table_name == i.table_name)
+                .filter(|other| starts_with_columns(other, &columns))
+                // Of two identical plain indexes, only report one
+                .find(|other| {
+                    other.columns.len() > columns.len()
+                        || other.is_unique
+                        || other.is_primary
+                        || other.index_name < i.index_name
+                })
+                .map(|other| RedundantIndex {
+                    table: i.table_name.clone(),
+                    index: i.index_name.clone(),
+                    covered_by: other.index_name.clone(),
+                })
+        })
+        .collect()
+}
+
+/// Tables of meaningful size that are read mostly by sequential scans
+fn scan_hints(tables: &[TableScanStats]) -> Vec<ScanHint> {
+    tables
+        .iter()
+        .filter(|t| t.live_rows >= SEQ_SCAN_MIN_ROWS && t.seq_scans > t.index_scans)
+        .map(|t| ScanHint {
+            table: t.table_name.clone(),
+            seq_scans: t.seq_scans,
+            index_scans: t.index_scans,
+            live_rows: t.live_rows,
+            message: format!(
+                "{} is read by sequential scans more often than by index scans ({} vs {}); \
+                 check the hot queries touching it for a missing composite index",
+                t.table_name, t.seq_scans, t.index_scans
+            ),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(table: &str, name: &str, columns: &[&str]) -> IndexStats {
+        IndexStats {
+            table_name: table.to_string(),
+            index_name: name.to_string(),
+            columns: columns.iter().map(ToString::to_string).collect(),
+            is_unique: false,
+            is_primary: false,
+            is_partial: false,
+            scans: 1,
+            size_bytes: 8192,
+        }
+    }
+
+    const EXPECTED: &[ExpectedIndex] = &[ExpectedIndex {
+        name: "idx_progress_due",
+        table: "user_card_progress",
+        columns: &["user_id", "next_review_at"],
+        used_by: "due cards",
+    }];
+
+    #[test]
+    fn test_missing_index_detected() {
+        let indexes = [index("user_card_progress", "idx_user", &["user_id"])];
+        let missing = find_missing(EXPECTED, &indexes);
+
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].name, "idx_progress_due");
+    }
+
+    #[test]
+    fn test_expected_index_satisfied_by_wider_index_with_other_name() {
+        let indexes = [index(
+            "user_card_progress",
+            "idx_renamed",
+            &["user_id", "next_review_at", "flashcard_id"],
+        )];
+
+        assert!(find_missing(EXPECTED, &indexes).is_empty());
+    }
+
+    #[test]
+    fn test_unused_skips_constraint_indexes() {
+        let mut unique = index("users", "users_email_key", &["email"]);
+        unique.is_unique = true;
+        unique.scans = 0;
+        let mut plain = index("users", "idx_users_name", &["username"]);
+        plain.scans = 0;
+
+        let unused = find_unused(EXPECTED, &[unique, plain]);
+
+        assert_eq!(unused.len(), 1);
+        assert_eq!(unused[0].index, "idx_users_name");
+        assert!(!unused[0].expected);
+    }
+
+    #[test]
+    fn test_redundant_prefix_index() {
+        let mut unique = index(
+            "refresh_tokens",
+            "refresh_tokens_token_hash_key",
+            &["token_hash"],
+        );
+        unique.is_unique = true;
+        let indexes = [
+            unique,
+            index("refresh_tokens", "idx_refresh_tokens_hash", &["token_hash"]),
+            index("refresh_tokens", "idx_refresh_tokens_user", &["user_id"]),
+        ];
+
+        let redundant = find_redundant(EXPECTED, &indexes);
+
+        assert_eq!(
+            redundant,
+            vec![RedundantIndex {
+                table: "refresh_tokens".to_string(),
+                index: "idx_refresh_tokens_hash".to_string(),
+                covered_by: "refresh_tokens_token_hash_key".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_scan_hints_ignore_small_tables() {
+        let table = |name: &str, live_rows| TableScanStats {
+            table_name: name.to_string(),
+            seq_scans: 100,
+            seq_rows_read: 1000,
+            index_scans: 10,
+            live_rows,
+        };
+
+        let hints = scan_hints(&[table("roadmaps", 10), table("user_card_progress", 50_000)]);
+
+        assert_eq!(hints.len(), 1);
+        assert_eq!(hints[0].table, "user_card_progress");
+    }
+
+    #[test]
+    fn test_referenced_tables_match_whole_identifiers() {
+        let tables = ["users", "user_stats", "decks"];
+        let found = referenced_tables(
+            "SELECT * FROM user_stats JOIN users u ON u.id = $1",
+            &tables,
+        );
+
+        assert_eq!(found, vec!["users".to_string(), "user_stats".to_string()]);
+    }
+}
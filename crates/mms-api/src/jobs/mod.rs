@@ -0,0 +1,593 @@
+//! Background jobs for periodic maintenance tasks.
+//!
+//! This module provides scheduled cleanup tasks that complement the database triggers.
+//! While triggers handle cleanup opportunistically on INSERT operations, these jobs
+//! ensure cleanup happens even during periods of low activity.
+//!
+//! Work that must not be lost to a restart goes through the persistent
+//! [`queue`]: the token, account and practice session cleanups and the deck
+//! progress check are queued on their cron [`schedule`] and run by whichever instance's worker claims them first.
+//!
+//! The other periodic jobs run on a timer in every instance, started by
+//! [`spawn_periodic`]; each run is first claimed in the `periodic_job_runs`
+//! table, so only one replica does it.
+
+pub mod queue;
+pub mod schedule;
+
+use chrono::Utc;
+use sqlx::PgPool;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::{sync::mpsc, task::JoinHandle, time::interval};
+
+use mms_db::repositories::{
+    client_error as client_error_repo, data_export as export_repo, email_outbox as outbox_repo,
+    idempotency as idempotency_repo, job as job_repo, notification as notification_repo,
+};
+
+use crate::{
+    auth::jwt::JwtKeys,
+    deck, difficulty, idempotency, index_advisor, leaderboards,
+    live::EventBus,
+    metrics, plans, reminders, stats,
+    user::{email::EmailJob, export},
+};
+use queue::JobContext;
+use schedule::JobSchedules;
+
+const HOUR: Duration = Duration::from_secs(3600);
+
+const DAY: Duration = Duration::from_secs(86400);
+
+/// Days a notification is kept, read or not
+const NOTIFICATION_RETENTION_DAYS: i32 = 90;
+
+/// Days a sent email is kept in the outbox
+const OUTBOX_SENT_RETENTION_DAYS: i32 = 7;
+
+/// Days an email that could not be sent is kept, for investigating delivery problems
+const OUTBOX_FAILED_RETENTION_DAYS: i32 = 30;
+
+/// Days a client error report is kept
+const CLIENT_ERROR_RETENTION_DAYS: i32 = 30;
+
+/// Days a job that succeeded is kept
+const JOB_SUCCEEDED_RETENTION_DAYS: i32 = 2;
+
+/// Days a dead job is kept, for an operator to look at or retry
+const JOB_DEAD_RETENTION_DAYS: i32 = 30;
+
+/// Start all background jobs
+///
+/// The queue worker and the schedulers of the queued maintenance jobs always
+/// run. Email jobs only start when an email worker
+/// is running; streak reminders always run and email only when one is.
+/// Returns a vector of join handles that can be awaited on shutdown
+pub fn start_background_jobs(
+    queue: JobContext,
+    schedules: JobSchedules,
+    email_tx: Option<mpsc::UnboundedSender<EmailJob>>,
+    events: EventBus,
+    feed_keys: JwtKeys,
+    streak_reminder_hours_before_midnight: u32,
+) -> Vec<JoinHandle<()>> {
+    let pool = queue.pool.clone();
+    let mut handles = vec![
+        tokio::spawn(queue::worker_loop(queue)),
+        // Daily jobs start hours apart so their runs do not overlap
+        spawn_periodic(
+            &pool,
+            "job_cleanup",
+            HOUR * 12,
+            DAY,
+            periodic_job_cleanup_job,
+        ),
+        // The first report waits for the statistics to reflect real traffic
+        spawn_periodic(
+            &pool,
+            "index_advisor",
+            HOUR * 3,
+            DAY,
+            periodic_index_advisor_job,
+        ),
+        spawn_periodic(&pool, "difficulty", HOUR * 4, DAY, periodic_difficulty_job),
+        spawn_periodic(
+            &pool,
+            "deck_analytics",
+            HOUR * 11,
+            DAY,
+            periodic_deck_analytics_job,
+        ),
+        spawn_periodic(
+            &pool,
+            "notification_cleanup",
+            HOUR * 5,
+            DAY,
+            periodic_notification_cleanup_job,
+        ),
+        spawn_periodic(
+            &pool,
+            "outbox_cleanup",
+            HOUR * 7,
+            DAY,
+            periodic_outbox_cleanup_job,
+        ),
+        spawn_periodic(
+            &pool,
+            "client_error_cleanup",
+            HOUR * 8,
+            DAY,
+            periodic_client_error_cleanup_job,
+        ),
+        spawn_periodic(
+            &pool,
+            "idempotency_key_cleanup",
+            HOUR / 2,
+            HOUR,
+            periodic_idempotency_key_cleanup_job,
+        ),
+        spawn_periodic(
+            &pool,
+            "interval_snapshot",
+            HOUR * 6,
+            DAY,
+            periodic_interval_snapshot_job,
+        ),
+        spawn_periodic(&pool, "forecast", HOUR * 10, DAY, periodic_forecast_job),
+        spawn_periodic(
+            &pool,
+            "leaderboard_refresh",
+            Duration::ZERO,
+            Duration::from_secs(300),
+            periodic_leaderboard_refresh_job,
+        ),
+        spawn_periodic(&pool, "plan_check", HOUR * 9, DAY, {
+            let events = events.clone();
+            move |pool| periodic_plan_check_job(pool, events.clone())
+        }),
+        spawn_periodic(
+            &pool,
+            "streak_reminder",
+            Duration::ZERO,
+            Duration::from_secs(600),
+            {
+                let email_tx = email_tx.clone();
+                move |pool| {
+                    periodic_streak_reminder_job(
+                        pool,
+                        events.clone(),
+                        email_tx.clone(),
+                        streak_reminder_hours_before_midnight,
+                    )
+                }
+            },
+        ),
+    ];
+    for scheduled in schedules.jobs {
+        handles.push(tokio::spawn(schedule::scheduler_loop(
+            pool.clone(),
+            scheduled,
+            schedules.jitter,
+        )));
+    }
+    if let Some(email_tx) = email_tx {
+        handles.push(spawn_periodic(
+            &pool,
+            "weekly_digest",
+            Duration::ZERO,
+            HOUR / 2,
+            {
+                let email_tx = email_tx.clone();
+                move |pool| periodic_weekly_digest_job(pool, email_tx.clone(), feed_keys.clone())
+            },
+        ));
+        handles.push(spawn_periodic(
+            &pool,
+            "review_reminder",
+            Duration::ZERO,
+            Duration::from_secs(600),
+            move |pool| periodic_review_reminder_job(pool, email_tx.clone()),
+        ));
+    }
+    handles
+}
+
+/// Delete finished jobs and expired data exports, runs daily
+async fn periodic_job_cleanup_job(pool: PgPool) {
+    let cleanup = async {
+        let jobs =
+            job_repo::delete_finished(&pool, JOB_SUCCEEDED_RETENTION_DAYS, JOB_DEAD_RETENTION_DAYS)
+                .await?;
+        let exports = export_repo::delete_older_than(&pool, export::RETENTION_DAYS).await?;
+        Ok::<_, sqlx::Error>((jobs, exports))
+    };
+    match run_timed("job_cleanup", cleanup).await {
+        Ok((jobs, exports)) if jobs + exports > 0 => {
+            tracing::info!(
+                "Deleted {} finished jobs and {} expired data exports",
+                jobs,
+                exports
+            );
+        }
+        Ok(_) => {
+            tracing::debug!("No finished jobs or expired data exports to delete");
+        }
+        Err(e) => {
+            tracing::error!("Failed to clean up the job queue: {}", e);
+        }
+    }
+}
+
+/// Log the index advisor report daily
+///
+/// Findings are logged as warnings so missing indexes surface in alerting before
+/// they surface as slow endpoints. The full report is served at `/v1/admin/index-report`.
+async fn periodic_index_advisor_job(pool: PgPool) {
+    match run_timed("index_advisor", index_advisor::build_report(&pool)).await {
+        Ok(report) => {
+            for missing in &report.missing_indexes {
+                tracing::warn!(
+                    "Expected index {} on {}({}) is missing (used by {})",
+                    missing.name,
+                    missing.table,
+                    missing.columns.join(", "),
+                    missing.used_by
+                );
+            }
+            for redundant in &report.redundant_indexes {
+                tracing::warn!(
+                    "Index {} on {} is redundant with {}",
+                    redundant.index,
+                    redundant.table,
+                    redundant.covered_by
+                );
+            }
+            for hint in &report.scan_hints {
+                tracing::warn!("{}", hint.message);
+            }
+            tracing::info!(
+                "Index advisor: {} missing, {} unused, {} redundant, {} scan hints, {} hot queries",
+                report.missing_indexes.len(),
+                report.unused_indexes.len(),
+                report.redundant_indexes.len(),
+                report.scan_hints.len(),
+                report.hot_queries.len()
+            );
+        }
+        Err(e) => {
+            tracing::error!("Failed to build index advisor report: {}", e);
+        }
+    }
+}
+
+/// Recompute global flashcard difficulty scores nightly
+///
+/// Scores only move as reviews accumulate across many users, so once a day is plenty.
+async fn periodic_difficulty_job(pool: PgPool) {
+    match run_timed("difficulty", difficulty::refresh_difficulty_scores(&pool)).await {
+        Ok(scored) => {
+            tracing::info!("Flashcard difficulty refreshed for {} cards", scored);
+        }
+        Err(e) => {
+            tracing::error!("Failed to refresh flashcard difficulty: {}", e);
+        }
+    }
+}
+
+/// Recompute the per-card analytics behind deck analytics, runs daily
+///
+/// Aggregating every learner's progress and review log is too heavy for a
+/// request; authors see figures up to a day old.
+async fn periodic_deck_analytics_job(pool: PgPool) {
+    match run_timed("deck_analytics", deck::analytics::refresh(&pool)).await {
+        Ok(cards) => {
+            tracing::info!("Deck analytics recomputed for {} cards", cards);
+        }
+        Err(e) => {
+            tracing::error!("Failed to recompute deck analytics: {}", e);
+        }
+    }
+}
+
+/// Delete notifications older than the retention period, runs daily
+async fn periodic_notification_cleanup_job(pool: PgPool) {
+    match run_timed(
+        "notification_cleanup",
+        notification_repo::delete_older_than(&pool, NOTIFICATION_RETENTION_DAYS),
+    )
+    .await
+    {
+        Ok(deleted) if deleted > 0 => {
+            tracing::info!(
+                "Deleted {} notifications older than {} days",
+                deleted,
+                NOTIFICATION_RETENTION_DAYS
+            );
+        }
+        Ok(_) => {
+            tracing::debug!("No old notifications to delete");
+        }
+        Err(e) => {
+            tracing::error!("Failed to delete old notifications: {}", e);
+        }
+    }
+}
+
+/// Delete finished emails from the outbox, runs daily
+async fn periodic_outbox_cleanup_job(pool: PgPool) {
+    match run_timed(
+        "outbox_cleanup",
+        outbox_repo::delete_finished(
+            &pool,
+            OUTBOX_SENT_RETENTION_DAYS,
+            OUTBOX_FAILED_RETENTION_DAYS,
+        ),
+    )
+    .await
+    {
+        Ok(deleted) if deleted > 0 => {
+            tracing::info!("Deleted {} finished emails from the outbox", deleted);
+        }
+        Ok(_) => {
+            tracing::debug!("No finished emails to delete from the outbox");
+        }
+        Err(e) => {
+            tracing::error!("Failed to clean up the email outbox: {}", e);
+        }
+    }
+}
+
+/// Delete client error reports older than the retention period, runs daily
+async fn periodic_client_error_cleanup_job(pool: PgPool) {
+    match run_timed(
+        "client_error_cleanup",
+        client_error_repo::delete_older_than(&pool, CLIENT_ERROR_RETENTION_DAYS),
+    )
+    .await
+    {
+        Ok(deleted) if deleted > 0 => {
+            tracing::info!(
+                "Deleted {} client error reports older than {} days",
+                deleted,
+                CLIENT_ERROR_RETENTION_DAYS
+            );
+        }
+        Ok(_) => {
+            tracing::debug!("No old client error reports to delete");
+        }
+        Err(e) => {
+            tracing::error!("Failed to delete old client error reports: {}", e);
+        }
+    }
+}
+
+/// Delete stored idempotent responses older than the retention period, runs hourly
+async fn periodic_idempotency_key_cleanup_job(pool: PgPool) {
+    match run_timed(
+        "idempotency_key_cleanup",
+        idempotency_repo::delete_older_than(&pool, idempotency::RETENTION_HOURS),
+    )
+    .await
+    {
+        Ok(deleted) if deleted > 0 => {
+            tracing::info!(
+                "Deleted {} idempotency keys older than {} hours",
+                deleted,
+                idempotency::RETENTION_HOURS
+            );
+        }
+        Ok(_) => {
+            tracing::debug!("No old idempotency keys to delete");
+        }
+        Err(e) => {
+            tracing::error!("Failed to delete old idempotency keys: {}", e);
+        }
+    }
+}
+
+/// Snapshot every user's interval distribution as this week's, runs daily
+///
+/// Each run overwrites the current week, so the week keeps its last state.
+async fn periodic_interval_snapshot_job(pool: PgPool) {
+    match run_timed(
+        "interval_snapshot",
+        stats::intervals::snapshot_intervals(&pool),
+    )
+    .await
+    {
+        Ok(rows) => {
+            tracing::info!("Interval snapshots written: {} rows", rows);
+        }
+        Err(e) => {
+            tracing::error!("Failed to snapshot card intervals: {}", e);
+        }
+    }
+}
+
+/// Recompute the review forecasts of recently active users, runs daily
+///
+/// Reviews keep the stored counts current in between; the full recompute picks
+/// up the users whose local date moved on and anything a review missed.
+async fn periodic_forecast_job(pool: PgPool) {
+    match run_timed("forecast", stats::forecast::refresh_active(&pool)).await {
+        Ok(users) => {
+            tracing::info!("Review forecasts recomputed for {} users", users);
+        }
+        Err(e) => {
+            tracing::error!("Failed to recompute review forecasts: {}", e);
+        }
+    }
+}
+
+/// Email users whose daily reminder hour has come, every 10 minutes
+///
+/// Reminder hours are local, so every hour is somebody's; running several
+/// times an hour keeps reminders close to the hour users picked.
+async fn periodic_review_reminder_job(pool: PgPool, email_tx: mpsc::UnboundedSender<EmailJob>) {
+    match run_timed(
+        "review_reminder",
+        reminders::job::send_due_reminders(&pool, &email_tx),
+    )
+    .await
+    {
+        Ok(queued) if queued > 0 => {
+            tracing::info!("Queued {} review reminder emails", queued);
+        }
+        Ok(_) => {
+            tracing::debug!("No review reminders due");
+        }
+        Err(e) => {
+            tracing::error!("Failed to send review reminders: {}", e);
+        }
+    }
+}
+
+/// Notify users who fell behind on their study plan, runs daily
+async fn periodic_plan_check_job(pool: PgPool, events: EventBus) {
+    match run_timed("plan_check", plans::notify_behind(&pool, &events)).await {
+        Ok(notified) if notified > 0 => {
+            tracing::info!("Notified {} users behind on their study plan", notified);
+        }
+        Ok(_) => {
+            tracing::debug!("No users behind on their study plan");
+        }
+        Err(e) => {
+            tracing::error!("Failed to check study plans: {}", e);
+        }
+    }
+}
+
+/// Recompute the leaderboard totals every 5 minutes
+async fn periodic_leaderboard_refresh_job(pool: PgPool) {
+    match run_timed("leaderboard_refresh", leaderboards::refresh(&pool)).await {
+        Ok(()) => {
+            tracing::debug!("Leaderboards refreshed");
+        }
+        Err(e) => {
+            tracing::error!("Failed to refresh leaderboards: {}", e);
+        }
+    }
+}
+
+/// Remind users whose streak ends at local midnight, every 10 minutes
+///
+/// Like review reminders, the evening comes at a different time for every
+/// timezone, so the job runs often and each user is reminded once a day.
+async fn periodic_streak_reminder_job(
+    pool: PgPool,
+    events: EventBus,
+    email_tx: Option<mpsc::UnboundedSender<EmailJob>>,
+    hours_before_midnight: u32,
+) {
+    match run_timed(
+        "streak_reminder",
+        reminders::streak::send_streak_reminders(
+            &pool,
+            &events,
+            email_tx.as_ref(),
+            hours_before_midnight,
+            Utc::now(),
+        ),
+    )
+    .await
+    {
+        Ok(reminded) if reminded > 0 => {
+            tracing::info!("Reminded {} users of a streak at risk", reminded);
+        }
+        Ok(_) => {
+            tracing::debug!("No streaks at risk");
+        }
+        Err(e) => {
+            tracing::error!("Failed to send streak reminders: {}", e);
+        }
+    }
+}
+
+/// Email the weekly digest to users whose Monday morning has come, every 30 minutes
+///
+/// Monday morning arrives at a different time in every timezone; each user
+/// gets one digest a week.
+async fn periodic_weekly_digest_job(
+    pool: PgPool,
+    email_tx: mpsc::UnboundedSender<EmailJob>,
+    feed_keys: JwtKeys,
+) {
+    match run_timed(
+        "weekly_digest",
+        reminders::digest::send_weekly_digests(&pool, &email_tx, &feed_keys, Utc::now()),
+    )
+    .await
+    {
+        Ok(queued) if queued > 0 => {
+            tracing::info!("Queued {} weekly digest emails", queued);
+        }
+        Ok(_) => {
+            tracing::debug!("No weekly digests due");
+        }
+        Err(e) => {
+            tracing::error!("Failed to send weekly digests: {}", e);
+        }
+    }
+}
+
+/// Run `job` every `period` on this instance, the first time after `initial_delay`
+///
+/// Each run is only done when this instance [claims](claim_run) it, so the job
+/// runs on one replica at a time.
+fn spawn_periodic<F, Fut>(
+    pool: &PgPool,
+    name: &'static str,
+    initial_delay: Duration,
+    period: Duration,
+    mut job: F,
+) -> JoinHandle<()>
+where
+    F: FnMut(PgPool) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let pool = pool.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(initial_delay).await;
+        let mut interval = interval(period);
+
+        loop {
+            interval.tick().await;
+            if claim_run(&pool, name, period).await {
+                job(pool.clone()).await;
+            }
+        }
+    })
+}
+
+/// Claim this run of a periodic job for this instance
+///
+/// Every replica runs the periodic jobs on its own timer. A run is only claimed
+/// when the job last started, on any replica, at least most of a `period` ago,
+/// so it runs about once a period however many replicas there are. The slack
+/// keeps a late tick of the replica that ran last time from skipping a period.
+async fn claim_run(pool: &PgPool, job: &'static str, period: Duration) -> bool {
+    let min_gap = period - period / 10;
+    match job_repo::claim_periodic_run(pool, job, min_gap.as_secs_f64()).await {
+        Ok(true) => true,
+        Ok(false) => {
+            tracing::debug!(job, "Periodic job already ran on another instance");
+            false
+        }
+        Err(e) => {
+            tracing::error!(job, "Failed to claim periodic job run: {}", e);
+            false
+        }
+    }
+}
+
+/// Run one pass of a job, recording its outcome and duration in the metrics
+async fn run_timed<T, E>(
+    job: &'static str,
+    run: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    let started = Instant::now();
+    let result = run.await;
+    metrics::record_job_run(job, started.elapsed().as_secs_f64(), result.is_ok());
+    result
+}
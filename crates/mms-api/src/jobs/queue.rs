@@ -0,0 +1,403 @@
+//! Persistent job queue.
+//!
+//! Jobs are rows in the `jobs` table, so they outlive the instance that
+//! queued them: [`worker_loop`] on every instance claims due jobs, runs them
+//! and records the outcome. A failed run is retried with exponential backoff;
+//! after [`MAX_ATTEMPTS`] runs the job is dead-lettered and left for an
+//! operator, who can list and retry dead jobs from the admin API. A worker
+//! that dies mid-run leaves its jobs leased; they are claimed again once the
+//! lease runs out.
+//!
+//! Jobs must be safe to run twice: a run whose outcome could not be recorded
+//! is run again.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use futures_util::{StreamExt, stream};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+use sqlx::{Executor, PgPool, Postgres};
+
+use mms_db::models::ClaimedJob;
+use mms_db::repositories::{
+    job as job_repo, login_failure as login_failure_repo, practice as practice_repo,
+    practice_session as practice_session_repo, token as token_repo, user as user_repo,
+};
+
+use crate::{
+    mailer::{
+        EmailSender,
+        webhooks::{self, Feedback},
+    },
+    media::MediaStore,
+    metrics,
+    state::ApiState,
+    user::{
+        ACCOUNT_DELETION_GRACE_DAYS, LOGIN_FAILURE_WINDOW_HOURS, UNVERIFIED_ACCOUNT_DAYS, avatar,
+        email::{self, EmailJob},
+        export,
+    },
+};
+
+/// Runs before a job is dead-lettered
+pub const MAX_ATTEMPTS: i32 = 5;
+
+/// Delay before the first retry; each later retry waits four times longer
+const FIRST_RETRY: Duration = Duration::from_secs(30);
+
+/// How long a claimed job is hidden from other workers
+const LEASE: Duration = Duration::from_secs(600);
+
+/// Jobs claimed at a time
+const BATCH_SIZE: i64 = 20;
+
+/// Jobs of a batch run at once
+const CONCURRENCY: usize = 4;
+
+/// How often [`worker_loop`] looks for due jobs
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Work for the queue, stored as JSON tagged with its kind
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Job {
+    /// Delete expired password reset, verification and refresh tokens
+    TokenCleanup,
+    /// Delete accounts never verified within 7 days
+    UnverifiedAccountsCleanup,
+    /// Delete for good the accounts deleted longer ago than the grace period
+    DeletedAccountsPurge,
+    /// Rewrite stored deck progress that drifted from the card progress
+    DeckProgress,
+    /// Delete expired practice sessions
+    PracticeSessionCleanup,
+    /// Render an email and hand it to the outbox
+    Email { email: EmailJob },
+    /// Build a data export a user requested
+    DataExport { export_id: Uuid, user_id: Uuid },
+    /// Suppress the addresses an email provider reported as undeliverable
+    EmailFeedback {
+        provider: String,
+        feedback: Vec<Feedback>,
+    },
+}
+
+impl Job {
+    /// Name stored in `jobs.kind` and used as the metrics label
+    #[must_use]
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Job::TokenCleanup => "token_cleanup",
+            Job::UnverifiedAccountsCleanup => "unverified_accounts_cleanup",
+            Job::DeletedAccountsPurge => "deleted_accounts_purge",
+            Job::DeckProgress => "deck_progress",
+            Job::PracticeSessionCleanup => "practice_session_cleanup",
+            Job::Email { .. } => "email",
+            Job::DataExport { .. } => "data_export",
+            Job::EmailFeedback { .. } => "email_feedback",
+        }
+    }
+
+    /// Jobs sharing a key are not queued twice, so a maintenance job run by hand
+    /// does not overlap a scheduled run
+    fn unique_key(&self) -> Option<&'static str> {
+        match self {
+            Job::TokenCleanup
+            | Job::UnverifiedAccountsCleanup
+            | Job::DeletedAccountsPurge
+            | Job::DeckProgress
+            | Job::PracticeSessionCleanup => Some(self.kind()),
+            Job::Email { .. } | Job::DataExport { .. } | Job::EmailFeedback { .. } => None,
+        }
+    }
+}
+
+/// What running jobs needs
+#[derive(Clone)]
+pub struct JobContext {
+    pub pool: PgPool,
+    /// Email jobs fail while no provider is configured
+    pub mailer: Option<Arc<dyn EmailSender>>,
+    pub frontend_url: Arc<str>,
+    /// Avatars of purged accounts are deleted from here
+    pub media: Arc<dyn MediaStore>,
+}
+
+impl JobContext {
+    #[must_use]
+    pub fn from_state(state: &ApiState) -> Self {
+        Self {
+            pool: state.pool.clone(),
+            mailer: state.mailer.clone(),
+            frontend_url: state.oidc.frontend_url.clone(),
+            media: state.media.clone(),
+        }
+    }
+}
+
+/// Queue `job` to run as soon as a worker is free.
+///
+/// Returns `None` when a job with the same unique key is already waiting or running.
+pub async fn enqueue<'e, E>(executor: E, job: &Job) -> Result<Option<Uuid>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    insert(executor, job, None).await
+}
+
+/// Queue the run of `job`'s schedule due at `slot`, unless another replica already did
+pub async fn enqueue_scheduled<'e, E>(
+    executor: E,
+    job: &Job,
+    slot: DateTime<Utc>,
+) -> Result<Option<Uuid>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    insert(executor, job, Some(slot)).await
+}
+
+async fn insert<'e, E>(
+    executor: E,
+    job: &Job,
+    scheduled_for: Option<DateTime<Utc>>,
+) -> Result<Option<Uuid>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let payload = serde_json::to_string(job).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    job_repo::enqueue(
+        executor,
+        job.kind(),
+        &payload,
+        job.unique_key(),
+        scheduled_for,
+        MAX_ATTEMPTS,
+        Utc::now(),
+    )
+    .await
+}
+
+/// Run every job that is due; returns how many ran, successfully or not
+pub async fn run_due(ctx: &JobContext) -> Result<usize, sqlx::Error> {
+    let mut ran = 0;
+
+    loop {
+        let claimed = job_repo::claim_due(&ctx.pool, Utc::now() + LEASE, BATCH_SIZE).await?;
+        let count = claimed.len();
+
+        stream::iter(claimed)
+            .for_each_concurrent(CONCURRENCY, |row| run_claimed(ctx, row))
+            .await;
+        ran += count;
+
+        if (count as i64) < BATCH_SIZE {
+            break;
+        }
+    }
+
+    Ok(ran)
+}
+
+/// Run due jobs every few seconds, for as long as the instance runs
+pub async fn worker_loop(ctx: JobContext) {
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match run_due(&ctx).await {
+            Ok(ran) if ran > 0 => tracing::debug!("Ran {} queued jobs", ran),
+            Ok(_) => {}
+            Err(e) => tracing::error!("Failed to claim queued jobs: {}", e),
+        }
+    }
+}
+
+async fn run_claimed(ctx: &JobContext, row: ClaimedJob) {
+    let job = match serde_json::from_str::<Job>(row.payload.expose()) {
+        Ok(job) => job,
+        Err(e) => {
+            tracing::error!(error = %e, job_id = %row.id, kind = %row.kind, "Unreadable job, giving up");
+            record(ctx, row.id, Err(format!("Unreadable payload: {e}")), None).await;
+            return;
+        }
+    };
+
+    let started = Instant::now();
+    let result = run(ctx, &job).await;
+    metrics::record_job_run(job.kind(), started.elapsed().as_secs_f64(), result.is_ok());
+
+    let Err(e) = result else {
+        record(ctx, row.id, Ok(()), None).await;
+        return;
+    };
+
+    let error = format!("{e:#}");
+    if row.attempts < row.max_attempts {
+        tracing::warn!(error, job_id = %row.id, kind = job.kind(), attempts = row.attempts, "Job failed, will retry");
+        record(ctx, row.id, Err(error), Some(backoff(row.attempts))).await;
+        return;
+    }
+
+    tracing::error!(error, job_id = %row.id, kind = job.kind(), attempts = row.attempts, "Job failed, giving up");
+    metrics::record_job_dead_lettered(job.kind());
+    if let Err(e) = dead_lettered(ctx, &job).await {
+        tracing::error!(error = %e, job_id = %row.id, "Failed to clean up after a dead job");
+    }
+    record(ctx, row.id, Err(error), None).await;
+}
+
+async fn run(ctx: &JobContext, job: &Job) -> anyhow::Result<()> {
+    match job {
+        Job::TokenCleanup => {
+            let cleaned = token_repo::cleanup_all_expired_tokens(&ctx.pool).await?;
+            if cleaned.total_cleaned > 0 {
+                tracing::info!(
+                    "Token cleanup complete: {} password reset, {} email verification, {} refresh tokens ({} total)",
+                    cleaned.password_reset_cleaned,
+                    cleaned.email_verification_cleaned,
+                    cleaned.refresh_tokens_cleaned,
+                    cleaned.total_cleaned
+                );
+            } else {
+                tracing::debug!("Token cleanup complete: no expired tokens found");
+            }
+            let lapsed =
+                login_failure_repo::delete_lapsed(&ctx.pool, LOGIN_FAILURE_WINDOW_HOURS).await?;
+            if lapsed > 0 {
+                tracing::info!("Cleaned up {} lapsed login failure counts", lapsed);
+            }
+        }
+        Job::UnverifiedAccountsCleanup => {
+            let deleted =
+                user_repo::delete_unverified_users(&ctx.pool, UNVERIFIED_ACCOUNT_DAYS).await?;
+            if deleted > 0 {
+                tracing::info!(
+                    "Cleaned up {} unverified accounts older than {} days",
+                    deleted,
+                    UNVERIFIED_ACCOUNT_DAYS
+                );
+            } else {
+                tracing::debug!("No old unverified accounts to clean up");
+            }
+        }
+        Job::DeletedAccountsPurge => {
+            let avatars =
+                user_repo::purge_deleted_users(&ctx.pool, ACCOUNT_DELETION_GRACE_DAYS).await?;
+            let purged = avatars.len();
+            for avatar_key in avatars.iter().flatten() {
+                avatar::delete(ctx.media.as_ref(), avatar_key).await?;
+            }
+            if purged > 0 {
+                tracing::info!(
+                    "Purged {} accounts deleted more than {} days ago",
+                    purged,
+                    ACCOUNT_DELETION_GRACE_DAYS
+                );
+            } else {
+                tracing::debug!("No deleted accounts to purge");
+            }
+        }
+        Job::DeckProgress => {
+            // Deck progress is refreshed after every review, but cards added to
+            // or removed from a deck, merged or deleted change it without one
+            let corrected =
+                practice_repo::recompute_deck_progress(&ctx.pool, None, mms_srs::MASTERY_THRESHOLD)
+                    .await?;
+            if corrected > 0 {
+                tracing::warn!("Corrected {} drifted deck progress rows", corrected);
+            } else {
+                tracing::debug!("Deck progress is consistent");
+            }
+        }
+        Job::PracticeSessionCleanup => {
+            let deleted = practice_session_repo::delete_expired(&ctx.pool).await?;
+            if deleted > 0 {
+                tracing::info!("Deleted {} expired practice sessions", deleted);
+            } else {
+                tracing::debug!("No expired practice sessions to delete");
+            }
+        }
+        Job::Email { email } => {
+            let sender = ctx
+                .mailer
+                .as_deref()
+                .context("No email provider is configured")?;
+            email::deliver(&ctx.pool, sender, &ctx.frontend_url, email).await?;
+        }
+        Job::DataExport { export_id, user_id } => {
+            export::run(&ctx.pool, *export_id, *user_id).await?;
+        }
+        Job::EmailFeedback { provider, feedback } => {
+            webhooks::suppress(&ctx.pool, provider, feedback).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Settle whatever was waiting on a job that will not run again
+async fn dead_lettered(ctx: &JobContext, job: &Job) -> anyhow::Result<()> {
+    match job {
+        Job::DataExport { export_id, .. } => export::give_up(&ctx.pool, *export_id).await,
+        _ => Ok(()),
+    }
+}
+
+/// Delay before the retry following the given number of failed runs
+fn backoff(failed_attempts: i32) -> Duration {
+    let exponent = u32::try_from(failed_attempts.saturating_sub(1)).unwrap_or(0);
+    FIRST_RETRY * 4u32.saturating_pow(exponent)
+}
+
+/// Record a run: succeeded, failed and retried after `retry_in`, or failed for good
+async fn record(
+    ctx: &JobContext,
+    id: Uuid,
+    result: Result<(), String>,
+    retry_in: Option<Duration>,
+) {
+    let recorded = match result {
+        Ok(()) => job_repo::mark_succeeded(&ctx.pool, id).await,
+        Err(error) => {
+            let retry_at = retry_in.map(|delay| Utc::now() + delay);
+            job_repo::mark_failed(&ctx.pool, id, &error, retry_at).await
+        }
+    };
+
+    if let Err(e) = recorded {
+        tracing::error!(error = %e, job_id = %id, "Failed to record job run");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_grows_fourfold() {
+        assert_eq!(backoff(1), Duration::from_secs(30));
+        assert_eq!(backoff(2), Duration::from_secs(120));
+        assert_eq!(backoff(4), Duration::from_secs(1920));
+    }
+
+    #[test]
+    fn test_payload_is_tagged_with_the_kind() {
+        let job = Job::DataExport {
+            export_id: Uuid::nil(),
+            user_id: Uuid::nil(),
+        };
+        let payload = serde_json::to_value(&job).unwrap();
+        assert_eq!(payload["kind"], job.kind());
+
+        let cleanup = serde_json::to_string(&Job::TokenCleanup).unwrap();
+        assert_eq!(cleanup, r#"{"kind":"token_cleanup"}"#);
+        assert!(matches!(
+            serde_json::from_str::<Job>(&cleanup).unwrap(),
+            Job::TokenCleanup
+        ));
+    }
+}
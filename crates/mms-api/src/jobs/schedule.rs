@@ -0,0 +1,268 @@
+//! Cron schedules of the queued maintenance jobs.
+//!
+//! Every replica runs a scheduler loop per job that sleeps until the next run
+//! of its cron expression, plus a random jitter so replicas do not all hit the
+//! database at the same instant, then queues the job for that run. The queue
+//! records which run a job is for, so the first replica queues it and the
+//! others find it already there. Expressions are evaluated in UTC.
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde::Serialize;
+use sqlx::PgPool;
+use utoipa::ToSchema;
+
+use super::queue::{self, Job};
+use crate::config::{ApiConfig, ConfigError};
+
+/// Default schedule of [`Job::TokenCleanup`]: every 6 hours
+pub const DEFAULT_TOKEN_CLEANUP: &str = "0 */6 * * *";
+
+/// Default schedule of [`Job::UnverifiedAccountsCleanup`]: daily at 02:00 UTC
+pub const DEFAULT_UNVERIFIED_ACCOUNTS_CLEANUP: &str = "0 2 * * *";
+
+/// Default schedule of [`Job::DeletedAccountsPurge`]: daily at 02:30 UTC
+pub const DEFAULT_DELETED_ACCOUNTS_PURGE: &str = "30 2 * * *";
+
+/// Default schedule of [`Job::DeckProgress`]: daily at 03:00 UTC
+pub const DEFAULT_DECK_PROGRESS: &str = "0 3 * * *";
+
+/// Default schedule of [`Job::PracticeSessionCleanup`]: hourly at minute 40
+pub const DEFAULT_PRACTICE_SESSION_CLEANUP: &str = "40 * * * *";
+
+/// Default longest random delay a replica adds before queueing a run
+pub const DEFAULT_JITTER: Duration = Duration::from_secs(300);
+
+/// A parsed cron expression, remembering how it was written
+#[derive(Debug, Clone)]
+pub struct CronSchedule {
+    expression: String,
+    schedule: cron::Schedule,
+}
+
+impl CronSchedule {
+    /// Parse a standard five-field expression (`minute hour day month weekday`),
+    /// or six and seven fields with seconds first and a year last
+    pub fn parse(expression: &str) -> Result<Self, cron::error::Error> {
+        let expression = expression.trim();
+        let schedule = if expression.split_whitespace().count() == 5 {
+            cron::Schedule::from_str(&format!("0 {expression}"))?
+        } else {
+            cron::Schedule::from_str(expression)?
+        };
+        Ok(Self {
+            expression: expression.to_string(),
+            schedule,
+        })
+    }
+
+    /// The expression as configured
+    #[must_use]
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /// First run strictly after `after`; `None` when the schedule never runs again
+    #[must_use]
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.schedule.after(&after).next()
+    }
+}
+
+/// A maintenance job that runs on a schedule
+#[derive(Debug, Clone)]
+pub struct ScheduledJob {
+    pub job: Job,
+    pub schedule: CronSchedule,
+}
+
+/// The schedules of every maintenance job, and the jitter applied to them
+#[derive(Debug, Clone)]
+pub struct JobSchedules {
+    pub jobs: Vec<ScheduledJob>,
+    pub jitter: Duration,
+}
+
+impl JobSchedules {
+    /// Schedules from `JOB_SCHEDULE_*` settings
+    pub fn from_config(config: &ApiConfig) -> Result<Self, ConfigError> {
+        let parse = |name: &str, expression: &str| {
+            CronSchedule::parse(expression).map_err(|e| {
+                ConfigError::ValidationError(format!("{name} is not a valid cron expression: {e}"))
+            })
+        };
+        Ok(Self {
+            jobs: vec![
+                ScheduledJob {
+                    job: Job::TokenCleanup,
+                    schedule: parse(
+                        "JOB_SCHEDULE_TOKEN_CLEANUP",
+                        &config.job_schedule_token_cleanup,
+                    )?,
+                },
+                ScheduledJob {
+                    job: Job::UnverifiedAccountsCleanup,
+                    schedule: parse(
+                        "JOB_SCHEDULE_UNVERIFIED_ACCOUNTS_CLEANUP",
+                        &config.job_schedule_unverified_accounts_cleanup,
+                    )?,
+                },
+                ScheduledJob {
+                    job: Job::DeletedAccountsPurge,
+                    schedule: parse(
+                        "JOB_SCHEDULE_DELETED_ACCOUNTS_PURGE",
+                        &config.job_schedule_deleted_accounts_purge,
+                    )?,
+                },
+                ScheduledJob {
+                    job: Job::DeckProgress,
+                    schedule: parse(
+                        "JOB_SCHEDULE_DECK_PROGRESS",
+                        &config.job_schedule_deck_progress,
+                    )?,
+                },
+                ScheduledJob {
+                    job: Job::PracticeSessionCleanup,
+                    schedule: parse(
+                        "JOB_SCHEDULE_PRACTICE_SESSION_CLEANUP",
+                        &config.job_schedule_practice_session_cleanup,
+                    )?,
+                },
+            ],
+            jitter: Duration::from_secs(config.job_schedule_jitter_seconds),
+        })
+    }
+
+    /// The scheduled job of this kind
+    #[must_use]
+    pub fn find(&self, kind: &str) -> Option<&ScheduledJob> {
+        self.jobs
+            .iter()
+            .find(|scheduled| scheduled.job.kind() == kind)
+    }
+}
+
+impl Default for JobSchedules {
+    fn default() -> Self {
+        let parse = |expression| CronSchedule::parse(expression).expect("Valid default schedule");
+        Self {
+            jobs: vec![
+                ScheduledJob {
+                    job: Job::TokenCleanup,
+                    schedule: parse(DEFAULT_TOKEN_CLEANUP),
+                },
+                ScheduledJob {
+                    job: Job::UnverifiedAccountsCleanup,
+                    schedule: parse(DEFAULT_UNVERIFIED_ACCOUNTS_CLEANUP),
+                },
+                ScheduledJob {
+                    job: Job::DeletedAccountsPurge,
+                    schedule: parse(DEFAULT_DELETED_ACCOUNTS_PURGE),
+                },
+                ScheduledJob {
+                    job: Job::DeckProgress,
+                    schedule: parse(DEFAULT_DECK_PROGRESS),
+                },
+                ScheduledJob {
+                    job: Job::PracticeSessionCleanup,
+                    schedule: parse(DEFAULT_PRACTICE_SESSION_CLEANUP),
+                },
+            ],
+            jitter: DEFAULT_JITTER,
+        }
+    }
+}
+
+/// A schedule as operators see it
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ScheduleView {
+    /// Job kind, as in the job queue
+    pub job: &'static str,
+    /// Cron expression, in UTC
+    pub cron: String,
+    /// Next run, before jitter; absent when the schedule never runs again
+    pub next_run_at: Option<DateTime<Utc>>,
+}
+
+impl ScheduledJob {
+    #[must_use]
+    pub fn view(&self, now: DateTime<Utc>) -> ScheduleView {
+        ScheduleView {
+            job: self.job.kind(),
+            cron: self.schedule.expression().to_string(),
+            next_run_at: self.schedule.next_after(now),
+        }
+    }
+}
+
+/// Queue `scheduled` at every run of its schedule, for as long as the instance runs
+pub async fn scheduler_loop(pool: PgPool, scheduled: ScheduledJob, jitter: Duration) {
+    let kind = scheduled.job.kind();
+
+    loop {
+        let now = Utc::now();
+        let Some(slot) = scheduled.schedule.next_after(now) else {
+            tracing::warn!(kind, "Job schedule has no future runs, not scheduling it");
+            return;
+        };
+        let until_slot = (slot - now).to_std().unwrap_or_default();
+        tokio::time::sleep(until_slot + random_jitter(jitter)).await;
+
+        match queue::enqueue_scheduled(&pool, &scheduled.job, slot).await {
+            Ok(Some(_)) => tracing::debug!(kind, %slot, "Queued scheduled job"),
+            Ok(None) => tracing::debug!(kind, %slot, "Scheduled job already queued"),
+            Err(e) => tracing::error!(kind, %slot, "Failed to queue scheduled job: {}", e),
+        }
+    }
+}
+
+fn random_jitter(max: Duration) -> Duration {
+    if max.is_zero() {
+        return Duration::ZERO;
+    }
+    Duration::from_millis(rand::thread_rng().gen_range(0..=max.as_millis() as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_five_field_expressions_run_on_the_minute() {
+        let schedule = CronSchedule::parse(DEFAULT_TOKEN_CLEANUP).unwrap();
+        let after = Utc.with_ymd_and_hms(2026, 10, 18, 6, 0, 0).unwrap();
+        assert_eq!(
+            schedule.next_after(after),
+            Some(Utc.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap())
+        );
+        assert_eq!(schedule.expression(), "0 */6 * * *");
+
+        let daily = CronSchedule::parse(DEFAULT_UNVERIFIED_ACCOUNTS_CLEANUP).unwrap();
+        assert_eq!(
+            daily.next_after(after),
+            Some(Utc.with_ymd_and_hms(2026, 10, 19, 2, 0, 0).unwrap())
+        );
+
+        // Six fields start with seconds
+        let seconds = CronSchedule::parse("30 0 * * * *").unwrap();
+        assert_eq!(
+            seconds.next_after(after),
+            Some(Utc.with_ymd_and_hms(2026, 10, 18, 6, 0, 30).unwrap())
+        );
+
+        assert!(CronSchedule::parse("every day").is_err());
+        assert!(CronSchedule::parse("0 25 * * *").is_err());
+    }
+
+    #[test]
+    fn test_jitter_stays_within_bounds() {
+        assert_eq!(random_jitter(Duration::ZERO), Duration::ZERO);
+        for _ in 0..100 {
+            assert!(random_jitter(Duration::from_secs(2)) <= Duration::from_secs(2));
+        }
+    }
+}
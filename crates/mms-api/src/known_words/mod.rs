@@ -0,0 +1,71 @@
+//! Known-word imports for experienced learners.
+//!
+//! Instead of meeting every basic card as new, a learner can send the words
+//! they already know, or their CEFR level. Matching cards they have not
+//! started get progress as if reviewed a few times
+//! ([`mms_srs::ALREADY_KNOWN_SCORE`]) and come up again weeks later; cards
+//! with existing progress keep it.
+
+pub mod routes;
+
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+pub use routes::routes;
+
+/// Common European Framework of Reference level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, ToSchema)]
+pub enum CefrLevel {
+    A1,
+    A2,
+    B1,
+    B2,
+    C1,
+    C2,
+}
+
+impl CefrLevel {
+    pub const ALL: [CefrLevel; 6] = [
+        CefrLevel::A1,
+        CefrLevel::A2,
+        CefrLevel::B1,
+        CefrLevel::B2,
+        CefrLevel::C1,
+        CefrLevel::C2,
+    ];
+
+    /// Value stored in `decks.cefr_level`
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            CefrLevel::A1 => "A1",
+            CefrLevel::A2 => "A2",
+            CefrLevel::B1 => "B1",
+            CefrLevel::B2 => "B2",
+            CefrLevel::C1 => "C1",
+            CefrLevel::C2 => "C2",
+        }
+    }
+
+    /// This level and every level below it
+    #[must_use]
+    pub fn and_below(self) -> Vec<&'static str> {
+        Self::ALL
+            .into_iter()
+            .filter(|level| *level <= self)
+            .map(Self::as_str)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels_include_the_ones_below() {
+        assert_eq!(CefrLevel::A1.and_below(), ["A1"]);
+        assert_eq!(CefrLevel::B1.and_below(), ["A1", "A2", "B1"]);
+        assert_eq!(CefrLevel::C2.and_below().len(), 6);
+    }
+}
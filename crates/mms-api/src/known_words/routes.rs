@@ -0,0 +1,128 @@
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    routing::post,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::{
+    ApiState,
+    auth::{AuthUser, require_self},
+    error::{ApiError, ErrorResponse},
+    known_words::CefrLevel,
+    validation::{ValidJson, language_code},
+};
+
+use mms_db::repositories::forecast as forecast_repo;
+use mms_db::repositories::known_word as known_word_repo;
+
+/// Most words accepted in one import
+const MAX_WORDS: u64 = 5_000;
+
+/// Create the known-word routes
+pub fn routes() -> Router<ApiState> {
+    Router::new().route("/users/{user_id}/known-words", post(import_known_words))
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+struct KnownWordsImport {
+    /// Language the words are in, i.e. the `language_to` of the cards to mark
+    #[validate(custom(function = "language_code"))]
+    language: String,
+    /// Words the learner knows; matches cards made only of these words
+    #[serde(default)]
+    #[validate(length(max = MAX_WORDS, message = "At most 5000 words can be imported at once"))]
+    words: Option<Vec<String>>,
+    /// The learner's level; matches every card of decks at or below it
+    #[serde(default)]
+    #[schema(inline)]
+    cefr_level: Option<CefrLevel>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct KnownWordsResult {
+    /// Cards given progress; cards already started are not counted
+    cards_marked: usize,
+}
+
+/// Mark cards as already known from a word list or a CEFR level
+#[utoipa::path(
+    post,
+    path = "/v1/users/{user_id}/known-words",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    params(("user_id" = Uuid, Path, description = "Must be the signed-in user")),
+    request_body = KnownWordsImport,
+    responses(
+        (status = 200, description = "Cards marked as known", body = KnownWordsResult),
+        (status = 400, description = "Invalid language, or not exactly one of `words` and `cefr_level`", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Another user's account", body = ErrorResponse),
+    )
+)]
+async fn import_known_words(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(user_id): Path<Uuid>,
+    ValidJson(payload): ValidJson<KnownWordsImport>,
+) -> Result<Json<KnownWordsResult>, ApiError> {
+    require_self(&auth_user, user_id)?;
+    let language = payload.language.to_lowercase();
+
+    let score = mms_srs::ALREADY_KNOWN_SCORE;
+    let interval_hours = mms_srs::get_interval_for_score(score);
+
+    let mut tx = state.pool.begin().await?;
+    let marked = match (payload.words, payload.cefr_level) {
+        (Some(words), None) => {
+            // Cards are indexed by their lowercased words
+            let words: Vec<String> = words
+                .iter()
+                .map(|word| word.trim().to_lowercase())
+                .filter(|word| !word.is_empty())
+                .collect();
+            known_word_repo::mark_cards_with_words(
+                &mut *tx,
+                user_id,
+                &language,
+                &words,
+                score,
+                interval_hours,
+            )
+            .await?
+        }
+        (None, Some(level)) => {
+            known_word_repo::mark_cards_at_levels(
+                &mut *tx,
+                user_id,
+                &language,
+                &level.and_below(),
+                score,
+                interval_hours,
+            )
+            .await?
+        }
+        _ => {
+            return Err(ApiError::Validation(
+                "Send either words or cefr_level".to_string(),
+            ));
+        }
+    };
+    known_word_repo::refresh_decks_containing(
+        &mut *tx,
+        user_id,
+        &marked,
+        mms_srs::MASTERY_THRESHOLD,
+    )
+    .await?;
+    forecast_repo::invalidate(&mut *tx, user_id).await?;
+    tx.commit().await?;
+
+    tracing::info!(user_id = %user_id, cards = marked.len(), "Imported known words");
+    Ok(Json(KnownWordsResult {
+        cards_marked: marked.len(),
+    }))
+}
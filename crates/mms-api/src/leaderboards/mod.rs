@@ -0,0 +1,20 @@
+//! XP leaderboards and the friends they can be limited to.
+//!
+//! Rankings read `leaderboard_totals`, a materialized view of each user's XP
+//! for the current week and month that [`refresh`] rebuilds every few minutes
+//! from the background jobs, so totals lag reviews by up to that long. Users
+//! can opt out with `PATCH /users/me/privacy`; they disappear from every
+//! leaderboard at once, without waiting for a refresh.
+
+pub mod routes;
+
+use sqlx::PgPool;
+
+use mms_db::repositories::leaderboard as leaderboard_repo;
+
+pub use routes::routes;
+
+/// Recompute the leaderboard totals
+pub async fn refresh(pool: &PgPool) -> Result<(), sqlx::Error> {
+    leaderboard_repo::refresh(pool).await
+}
@@ -0,0 +1,246 @@
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{delete, get},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+use utoipa::{IntoParams, ToSchema};
+use validator::{Validate, ValidationError};
+
+use crate::{
+    ApiState,
+    auth::{AuthUser, validation::validate_username},
+    error::{ApiError, ErrorResponse},
+    middleware::rate_limit,
+    validation::ValidJson,
+};
+
+use mms_db::models::{Friend, LeaderboardEntry};
+use mms_db::repositories::{
+    friend as friend_repo, leaderboard as leaderboard_repo, user as user_repo,
+};
+
+/// Entries returned when no limit is given
+const DEFAULT_LIMIT: i64 = 50;
+
+/// Entries returned at most
+const MAX_LIMIT: i64 = 100;
+
+/// Create the leaderboard and friend routes
+pub fn routes() -> Router<ApiState> {
+    use crate::make_rate_limit_layer;
+
+    Router::new()
+        .route("/leaderboards", get(get_leaderboard))
+        .route("/users/me/friends", get(list_friends).post(add_friend))
+        .route("/users/me/friends/{friend_id}", delete(remove_friend))
+        .layer(make_rate_limit_layer!(
+            rate_limit::GENERAL_RATE_PER_SECOND,
+            rate_limit::GENERAL_BURST_SIZE
+        ))
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+enum Scope {
+    #[default]
+    Global,
+    /// The signed-in user and the users they added
+    Friends,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+enum Period {
+    /// Since Monday
+    #[default]
+    Week,
+    /// Since the 1st
+    Month,
+}
+
+impl Period {
+    fn as_str(self) -> &'static str {
+        match self {
+            Period::Week => "week",
+            Period::Month => "month",
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct LeaderboardQuery {
+    /// `global` (default) or `friends`
+    #[serde(default)]
+    #[param(inline)]
+    scope: Scope,
+    /// `week` (default) or `month`
+    #[serde(default)]
+    #[param(inline)]
+    period: Period,
+    /// Entries, 1 to 100 (default 50)
+    #[serde(default)]
+    limit: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct Leaderboard {
+    #[schema(inline)]
+    scope: Scope,
+    #[schema(inline)]
+    period: Period,
+    /// Highest XP first
+    entries: Vec<LeaderboardEntry>,
+    /// The signed-in user's entry, also when outside `entries`; absent without
+    /// XP in the period or when opted out
+    me: Option<LeaderboardEntry>,
+    /// When the totals were computed; reviews since then are not counted yet
+    refreshed_at: Option<DateTime<Utc>>,
+}
+
+/// Users ranked by XP earned this week or month
+#[utoipa::path(
+    get,
+    path = "/v1/leaderboards",
+    tag = "leaderboards",
+    security(("cookie_auth" = [])),
+    params(LeaderboardQuery),
+    responses(
+        (status = 200, description = "Leaderboard", body = Leaderboard),
+        (status = 400, description = "Unknown scope or period", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+async fn get_leaderboard(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Query(query): Query<LeaderboardQuery>,
+) -> Result<Json<Leaderboard>, ApiError> {
+    let user_id = auth_user.user_id;
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let (mut entries, refreshed_at) = tokio::try_join!(
+        leaderboard_repo::list(
+            &state.pool,
+            user_id,
+            query.period.as_str(),
+            matches!(query.scope, Scope::Friends),
+            limit,
+        ),
+        leaderboard_repo::refreshed_at(&state.pool),
+    )?;
+
+    // The user's own entry comes back even when it ranks below the limit
+    let me = entries
+        .iter()
+        .find(|entry| entry.user_id == user_id)
+        .cloned();
+    entries.truncate(limit as usize);
+
+    Ok(Json(Leaderboard {
+        scope: query.scope,
+        period: query.period,
+        entries,
+        me,
+        refreshed_at,
+    }))
+}
+
+/// The users the signed-in user added as friends
+#[utoipa::path(
+    get,
+    path = "/v1/users/me/friends",
+    tag = "leaderboards",
+    security(("cookie_auth" = [])),
+    responses(
+        (status = 200, description = "Friends by username", body = Vec<Friend>),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+async fn list_friends(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+) -> Result<Json<Vec<Friend>>, ApiError> {
+    Ok(Json(
+        friend_repo::list(&state.pool, auth_user.user_id).await?,
+    ))
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+struct AddFriendRequest {
+    /// Surrounding whitespace is ignored
+    #[validate(custom(function = "friend_username"))]
+    username: String,
+}
+
+fn friend_username(username: &str) -> Result<(), ValidationError> {
+    validate_username(username.trim())
+}
+
+/// Add a user to the signed-in user's friends leaderboard
+///
+/// Friendship is one-way: the other user is not notified and does not see
+/// the signed-in user unless they add them too.
+#[utoipa::path(
+    post,
+    path = "/v1/users/me/friends",
+    tag = "leaderboards",
+    security(("cookie_auth" = [])),
+    request_body = AddFriendRequest,
+    responses(
+        (status = 201, description = "Friend added"),
+        (status = 204, description = "Already a friend"),
+        (status = 400, description = "Own username", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "No user with that username", body = ErrorResponse),
+    )
+)]
+async fn add_friend(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    ValidJson(request): ValidJson<AddFriendRequest>,
+) -> Result<StatusCode, ApiError> {
+    let friend_id = user_repo::find_id_by_username(&state.pool, request.username.trim())
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+    if friend_id == auth_user.user_id {
+        return Err(ApiError::Validation(
+            "You cannot add yourself as a friend".to_string(),
+        ));
+    }
+
+    if friend_repo::add(&state.pool, auth_user.user_id, friend_id).await? {
+        Ok(StatusCode::CREATED)
+    } else {
+        Ok(StatusCode::NO_CONTENT)
+    }
+}
+
+/// Remove a user from the signed-in user's friends
+#[utoipa::path(
+    delete,
+    path = "/v1/users/me/friends/{friend_id}",
+    tag = "leaderboards",
+    security(("cookie_auth" = [])),
+    params(("friend_id" = Uuid, Path, description = "User id of the friend")),
+    responses(
+        (status = 204, description = "Friend removed"),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Not a friend", body = ErrorResponse),
+    )
+)]
+async fn remove_friend(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(friend_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    if friend_repo::remove(&state.pool, auth_user.user_id, friend_id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::NotFound("Friend not found".to_string()))
+    }
+}
@@ -1,19 +1,61 @@
+pub mod achievements;
+pub mod admin;
 pub mod auth;
+pub mod calendar;
+pub mod captcha;
+pub mod client_errors;
 pub mod config;
 pub mod deck;
+pub mod difficulty;
+pub mod email_preferences;
 pub mod error;
+pub mod flashcards;
+pub mod goals;
+pub mod graphql;
+pub mod groups;
+pub mod health;
+pub mod home;
+pub mod idempotency;
+pub mod index_advisor;
 pub mod jobs;
+pub mod known_words;
+pub mod leaderboards;
+pub mod live;
+pub mod mailer;
+pub mod media;
 pub mod metrics;
 pub mod middleware;
 pub mod normalization;
+pub mod notifications;
+pub mod openapi;
+pub mod plans;
 pub mod practice;
+pub mod preferences;
+pub mod profile;
+pub mod public_api;
+pub mod public_cache;
+pub mod reminders;
+pub mod reports;
 pub mod roadmap;
 pub mod router;
+pub mod slugs;
+pub mod smoke;
+pub mod speech;
 pub mod state;
+pub mod stats;
+pub mod streaming;
+pub mod sync;
+pub mod token_service;
 pub mod tracing;
 pub mod user;
 pub mod v1;
+pub mod v2;
 pub mod validation;
+pub mod versioning;
+pub mod vocabulary;
+pub mod warmup;
+pub mod widgets;
+pub mod xp;
 
 pub use config::ApiConfig;
 pub use state::{ApiState, AuthConfig, CookieConfig, OidcConfig};
@@ -0,0 +1,378 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::goals::DailyGoalKind;
+
+/// Events buffered per subscriber before it starts missing some
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// Recent events kept for clients resuming a stream
+const HISTORY_CAPACITY: usize = 256;
+
+/// An event sent to connected clients as a JSON text message
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveEvent {
+    /// A review was graded, possibly from another tab or device
+    ReviewRecorded {
+        flashcard_id: Uuid,
+        deck_id: Uuid,
+        is_correct: bool,
+        next_review_at: DateTime<Utc>,
+    },
+    /// The first review of the day extended or restarted the streak
+    StreakUpdated {
+        current_streak_days: i32,
+        longest_streak_days: i32,
+    },
+    /// Today's reviews or study time reached the user's daily goal
+    DailyGoalReached {
+        kind: DailyGoalKind,
+        goal: i32,
+        reviews_today: i32,
+        minutes_today: i64,
+    },
+    /// A deck's details or cards changed; cached copies should be refetched
+    DeckUpdated { deck_id: Uuid },
+    /// A notification was added to the user's list
+    NotificationCreated {
+        notification_id: Uuid,
+        kind: String,
+        title: String,
+        body: String,
+        link: Option<String>,
+    },
+    /// Events were dropped, at most `missed` of them (omitted when unknown); refetch state
+    Lagged {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        missed: Option<u64>,
+    },
+}
+
+#[derive(Debug)]
+struct Envelope {
+    /// Increasing per bus
+    id: u64,
+    /// `None` for events every user receives
+    recipient: Option<Uuid>,
+    event: LiveEvent,
+}
+
+#[derive(Debug, Default)]
+struct History {
+    last_id: u64,
+    recent: VecDeque<Arc<Envelope>>,
+}
+
+/// In-process fan-out of live events.
+///
+/// Publishing never blocks: a subscriber that falls more than
+/// `CHANNEL_CAPACITY` events behind receives a [`LiveEvent::Lagged`] instead
+/// of the events it missed. The last `HISTORY_CAPACITY` events are kept so a
+/// client can resume from the id of the last event it saw. Events only reach
+/// clients connected to this instance, and ids are only meaningful to it.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Arc<Envelope>>,
+    history: Arc<Mutex<History>>,
+    /// Distinguishes this bus's ids from those of another process
+    epoch: u32,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::Sender::new(CHANNEL_CAPACITY),
+            history: Arc::default(),
+            epoch: rand::random(),
+        }
+    }
+}
+
+/// An event with the id a client can resume after
+#[derive(Debug)]
+pub struct Delivery {
+    /// `None` for [`LiveEvent::Lagged`] notices
+    pub id: Option<String>,
+    pub event: LiveEvent,
+}
+
+impl EventBus {
+    /// Send an event to one user's connections
+    pub fn publish(&self, user_id: Uuid, event: LiveEvent) {
+        self.send(Some(user_id), event);
+    }
+
+    /// Send an event to every connection
+    pub fn broadcast(&self, event: LiveEvent) {
+        self.send(None, event);
+    }
+
+    fn send(&self, recipient: Option<Uuid>, event: LiveEvent) {
+        // Held while sending so history and channel agree on the order
+        let mut history = self.history.lock().unwrap_or_else(PoisonError::into_inner);
+        history.last_id += 1;
+        let envelope = Arc::new(Envelope {
+            id: history.last_id,
+            recipient,
+            event,
+        });
+
+        if history.recent.len() == HISTORY_CAPACITY {
+            history.recent.pop_front();
+        }
+        history.recent.push_back(Arc::clone(&envelope));
+        // Fails only when nobody is connected
+        let _ = self.sender.send(envelope);
+    }
+
+    /// Receive the events addressed to a user from now on
+    #[must_use]
+    pub fn subscribe(&self, user_id: Uuid) -> Subscription {
+        self.resume(user_id, None)
+    }
+
+    /// Receive the events addressed to a user, starting after `last_event_id`.
+    ///
+    /// Events still in the history are replayed first. If some are gone, or the
+    /// id was issued by another process, a [`LiveEvent::Lagged`] comes first.
+    #[must_use]
+    pub fn resume(&self, user_id: Uuid, last_event_id: Option<&str>) -> Subscription {
+        let history = self.history.lock().unwrap_or_else(PoisonError::into_inner);
+        // Subscribed under the lock: the channel only carries events after the history
+        let receiver = self.sender.subscribe();
+        let mut backlog = VecDeque::new();
+
+        if let Some(last_event_id) = last_event_id {
+            let last = self
+                .parse_id(last_event_id)
+                .filter(|last| *last <= history.last_id);
+            let oldest = history.recent.front().map_or(history.last_id + 1, |e| e.id);
+
+            match last {
+                Some(last) if last + 1 >= oldest => {}
+                Some(last) => backlog.push_back(Delivery {
+                    id: None,
+                    event: LiveEvent::Lagged {
+                        missed: Some(oldest - last - 1),
+                    },
+                }),
+                None => backlog.push_back(Delivery {
+                    id: None,
+                    event: LiveEvent::Lagged { missed: None },
+                }),
+            }
+
+            let after = last.unwrap_or(history.last_id);
+            backlog.extend(
+                history
+                    .recent
+                    .iter()
+                    .filter(|e| e.id > after && e.recipient.is_none_or(|id| id == user_id))
+                    .map(|e| self.deliver(e)),
+            );
+        }
+
+        Subscription {
+            bus: self.clone(),
+            user_id,
+            receiver,
+            backlog,
+        }
+    }
+
+    fn deliver(&self, envelope: &Envelope) -> Delivery {
+        Delivery {
+            id: Some(format!("{:x}-{}", self.epoch, envelope.id)),
+            event: envelope.event.clone(),
+        }
+    }
+
+    fn parse_id(&self, value: &str) -> Option<u64> {
+        let (epoch, id) = value.split_once('-')?;
+        (u32::from_str_radix(epoch, 16).ok()? == self.epoch)
+            .then(|| id.parse().ok())
+            .flatten()
+    }
+}
+
+/// A user's view of the event stream
+#[derive(Debug)]
+pub struct Subscription {
+    bus: EventBus,
+    user_id: Uuid,
+    receiver: broadcast::Receiver<Arc<Envelope>>,
+    /// Replayed events, delivered before live ones
+    backlog: VecDeque<Delivery>,
+}
+
+impl Subscription {
+    /// Wait for the next event for this user; `None` once the bus is gone
+    pub async fn next(&mut self) -> Option<Delivery> {
+        if let Some(delivery) = self.backlog.pop_front() {
+            return Some(delivery);
+        }
+
+        loop {
+            match self.receiver.recv().await {
+                Ok(envelope) if envelope.recipient.is_none_or(|id| id == self.user_id) => {
+                    return Some(self.bus.deliver(&envelope));
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(missed)) => {
+                    return Some(Delivery {
+                        id: None,
+                        event: LiveEvent::Lagged {
+                            missed: Some(missed),
+                        },
+                    });
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deck_updated() -> LiveEvent {
+        LiveEvent::DeckUpdated {
+            deck_id: Uuid::nil(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_events_reach_only_their_recipient() {
+        let bus = EventBus::default();
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut subscription = bus.subscribe(alice);
+
+        bus.publish(bob, LiveEvent::Lagged { missed: Some(1) });
+        bus.publish(
+            alice,
+            LiveEvent::DailyGoalReached {
+                kind: DailyGoalKind::Reviews,
+                goal: 20,
+                reviews_today: 20,
+                minutes_today: 12,
+            },
+        );
+        let deck_id = Uuid::new_v4();
+        bus.broadcast(LiveEvent::DeckUpdated { deck_id });
+
+        assert!(matches!(
+            subscription.next().await.map(|d| d.event),
+            Some(LiveEvent::DailyGoalReached { goal: 20, .. })
+        ));
+        assert!(matches!(
+            subscription.next().await.map(|d| d.event),
+            Some(LiveEvent::DeckUpdated { deck_id: id }) if id == deck_id
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_slow_subscriber_is_told_how_many_events_it_missed() {
+        let bus = EventBus::default();
+        let user_id = Uuid::new_v4();
+        let mut subscription = bus.subscribe(user_id);
+
+        for _ in 0..CHANNEL_CAPACITY + 5 {
+            bus.broadcast(deck_updated());
+        }
+
+        let lagged = subscription.next().await.unwrap();
+        assert!(lagged.id.is_none());
+        assert!(matches!(
+            lagged.event,
+            LiveEvent::Lagged { missed: Some(5) }
+        ));
+        assert!(matches!(
+            subscription.next().await.map(|d| d.event),
+            Some(LiveEvent::DeckUpdated { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_resume_replays_events_after_the_last_id() {
+        let bus = EventBus::default();
+        let user_id = Uuid::new_v4();
+        let mut first = bus.subscribe(user_id);
+
+        bus.broadcast(deck_updated());
+        bus.publish(Uuid::new_v4(), deck_updated());
+        bus.publish(user_id, deck_updated());
+        let seen = first.next().await.unwrap().id.unwrap();
+        let missed = first.next().await.unwrap().id.unwrap();
+
+        let mut resumed = bus.resume(user_id, Some(&seen));
+        bus.broadcast(deck_updated());
+        let live = first.next().await.unwrap().id.unwrap();
+
+        assert_eq!(resumed.next().await.unwrap().id, Some(missed));
+        assert_eq!(resumed.next().await.unwrap().id, Some(live));
+    }
+
+    #[tokio::test]
+    async fn test_resume_reports_events_no_longer_held() {
+        let bus = EventBus::default();
+        let user_id = Uuid::new_v4();
+        let mut first = bus.subscribe(user_id);
+
+        bus.broadcast(deck_updated());
+        let seen = first.next().await.unwrap().id.unwrap();
+        for _ in 0..HISTORY_CAPACITY + 3 {
+            bus.broadcast(deck_updated());
+        }
+
+        let mut resumed = bus.resume(user_id, Some(&seen));
+        assert!(matches!(
+            resumed.next().await.map(|d| d.event),
+            Some(LiveEvent::Lagged { missed: Some(3) })
+        ));
+        assert!(resumed.next().await.unwrap().id.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_resume_from_another_process_starts_with_lagged() {
+        let bus = EventBus::default();
+        let user_id = Uuid::new_v4();
+        bus.broadcast(deck_updated());
+
+        let mut resumed = bus.resume(user_id, Some("not-an-id"));
+        bus.broadcast(deck_updated());
+
+        assert!(matches!(
+            resumed.next().await.map(|d| d.event),
+            Some(LiveEvent::Lagged { missed: None })
+        ));
+        // Only events published after the reconnect follow
+        assert!(resumed.next().await.unwrap().id.unwrap().ends_with("-2"));
+    }
+
+    #[test]
+    fn test_events_are_tagged_with_their_type() {
+        let json = serde_json::to_value(LiveEvent::StreakUpdated {
+            current_streak_days: 3,
+            longest_streak_days: 7,
+        })
+        .unwrap();
+
+        assert_eq!(json["type"], "streak_updated");
+        assert_eq!(json["current_streak_days"], 3);
+    }
+
+    #[test]
+    fn test_unknown_gap_omits_missed() {
+        let json = serde_json::to_value(LiveEvent::Lagged { missed: None }).unwrap();
+
+        assert_eq!(json, serde_json::json!({ "type": "lagged" }));
+    }
+}
@@ -0,0 +1,111 @@
+//! Flat JSON form of live events for no-code automation tools.
+//!
+//! Tools such as Zapier and IFTTT map top-level fields of a payload and do not
+//! follow nested objects, so each event is one object of scalars: an id, the
+//! event type, when it happened, the user and the event's own fields.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{goals::DailyGoalKind, live::LiveEvent};
+
+/// Event types with a flat form; `lagged` only concerns WebSocket connections
+pub const EVENT_TYPES: [&str; 5] = [
+    "review_recorded",
+    "streak_updated",
+    "daily_goal_reached",
+    "deck_updated",
+    "notification_created",
+];
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FlatEvent {
+    /// Unique per event, for de-duplicating deliveries
+    pub id: Uuid,
+    pub occurred_at: DateTime<Utc>,
+    /// `None` for events about shared content
+    pub user_id: Option<Uuid>,
+    /// `type` and the event's fields
+    #[serde(flatten)]
+    pub event: LiveEvent,
+}
+
+impl FlatEvent {
+    /// Flatten an event delivered to a user
+    #[must_use]
+    pub fn for_user(user_id: Uuid, event: LiveEvent, occurred_at: DateTime<Utc>) -> Self {
+        let user_id = (!matches!(event, LiveEvent::DeckUpdated { .. })).then_some(user_id);
+        Self {
+            id: Uuid::new_v4(),
+            occurred_at,
+            user_id,
+            event,
+        }
+    }
+}
+
+/// Representative event of a type, for setting up field mappings
+#[must_use]
+pub fn sample(event_type: &str, user_id: Uuid, now: DateTime<Utc>) -> Option<FlatEvent> {
+    let event = match event_type {
+        "review_recorded" => LiveEvent::ReviewRecorded {
+            flashcard_id: Uuid::nil(),
+            deck_id: Uuid::nil(),
+            is_correct: true,
+            next_review_at: now + chrono::Duration::hours(4),
+        },
+        "streak_updated" => LiveEvent::StreakUpdated {
+            current_streak_days: 4,
+            longest_streak_days: 9,
+        },
+        "daily_goal_reached" => LiveEvent::DailyGoalReached {
+            kind: DailyGoalKind::Reviews,
+            goal: 20,
+            reviews_today: 20,
+            minutes_today: 12,
+        },
+        "deck_updated" => LiveEvent::DeckUpdated {
+            deck_id: Uuid::nil(),
+        },
+        "notification_created" => LiveEvent::NotificationCreated {
+            notification_id: Uuid::nil(),
+            kind: "streak_reminder".to_string(),
+            title: "Keep your streak going".to_string(),
+            body: "Review a few cards today to keep your 4 day streak.".to_string(),
+            link: Some("/practice".to_string()),
+        },
+        _ => return None,
+    };
+
+    Some(FlatEvent::for_user(user_id, event, now))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_event_type_has_a_flat_sample() {
+        let user_id = Uuid::new_v4();
+
+        for event_type in EVENT_TYPES {
+            let event = sample(event_type, user_id, Utc::now()).unwrap();
+            let json = serde_json::to_value(&event).unwrap();
+            let fields = json.as_object().unwrap();
+
+            assert_eq!(fields["type"], event_type);
+            assert!(fields.contains_key("id") && fields.contains_key("occurred_at"));
+            assert!(
+                fields.values().all(|v| !v.is_object() && !v.is_array()),
+                "{event_type} is not flat: {json}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_unknown_type_has_no_sample() {
+        assert!(sample("lagged", Uuid::new_v4(), Utc::now()).is_none());
+    }
+}
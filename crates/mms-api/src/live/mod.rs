@@ -0,0 +1,13 @@
+//! Live updates pushed to signed-in clients over a WebSocket or Server-Sent Events.
+//!
+//! Handlers publish [`LiveEvent`]s on the [`EventBus`] in [`crate::ApiState`]
+//! after their transaction commits; `GET /ws` and `GET /notifications/stream`
+//! forward each connection the events addressed to its user, either as tagged
+//! JSON or in the [flat form](flat) automation tools consume.
+
+pub mod events;
+pub mod flat;
+pub mod routes;
+
+pub use events::{EventBus, LiveEvent};
+pub use routes::routes;
@@ -0,0 +1,288 @@
+use std::{convert::Infallible, time::Duration};
+
+use axum::{
+    Json, Router,
+    body::Bytes,
+    extract::{
+        Path, Query, State,
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade, close_code},
+    },
+    http::HeaderMap,
+    response::{
+        Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+    routing::get,
+};
+use chrono::Utc;
+use futures_util::{Stream, StreamExt, stream};
+use serde::Deserialize;
+use sqlx::types::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    ApiState,
+    auth::AuthUser,
+    error::{ApiError, ErrorResponse},
+    live::{
+        events::{LiveEvent, Subscription},
+        flat::{self, FlatEvent},
+    },
+    metrics,
+    middleware::drain::DrainState,
+};
+
+/// Interval between pings that keep idle connections open through proxies
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Interval between SSE comment lines, shorter since proxies buffer and time out plain HTTP sooner
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Create the live update routes
+pub fn routes() -> Router<ApiState> {
+    Router::new()
+        .route("/ws", get(live_updates))
+        .route("/notifications/stream", get(notification_stream))
+        .route("/events/samples", get(list_event_samples))
+        .route("/events/samples/{event_type}", get(get_event_sample))
+}
+
+/// How events are written to the connection
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+enum EventFormat {
+    /// `type` and the event's fields
+    #[default]
+    Standard,
+    /// Standard fields plus `id`, `occurred_at` and `user_id`, see `GET /v1/events/samples`
+    Flat,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct LiveQuery {
+    #[serde(default)]
+    #[param(inline)]
+    format: EventFormat,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct StreamQuery {
+    #[serde(default)]
+    #[param(inline)]
+    format: EventFormat,
+    /// Resume after this event, for clients that cannot send `Last-Event-ID`
+    last_event_id: Option<String>,
+}
+
+/// Counts a connection in the live connection gauge until dropped
+struct ConnectionGauge(&'static str);
+
+impl ConnectionGauge {
+    fn open(transport: &'static str) -> Self {
+        metrics::record_live_connection(transport, true);
+        Self(transport)
+    }
+}
+
+impl Drop for ConnectionGauge {
+    fn drop(&mut self) {
+        metrics::record_live_connection(self.0, false);
+    }
+}
+
+/// Live updates for the signed-in user.
+///
+/// Upgrades to a WebSocket on which the server sends one JSON text message per
+/// event. Messages from the client are ignored.
+#[utoipa::path(
+    get,
+    path = "/v1/ws",
+    tag = "live",
+    security(("cookie_auth" = [])),
+    params(LiveQuery),
+    responses(
+        (status = 101, description = "Switched to a WebSocket carrying events", body = LiveEvent),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+async fn live_updates(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Query(query): Query<LiveQuery>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    // Subscribe before the handshake completes so no event in between is lost
+    let subscription = state.events.subscribe(auth_user.user_id);
+    let drain = state.drain.clone();
+
+    ws.on_upgrade(move |socket| {
+        forward_events(socket, subscription, query.format, auth_user.user_id, drain)
+    })
+}
+
+fn encode(event: LiveEvent, format: EventFormat, user_id: Uuid) -> serde_json::Result<String> {
+    match format {
+        EventFormat::Standard => serde_json::to_string(&event),
+        EventFormat::Flat => {
+            serde_json::to_string(&FlatEvent::for_user(user_id, event, Utc::now()))
+        }
+    }
+}
+
+async fn forward_events(
+    mut socket: WebSocket,
+    mut subscription: Subscription,
+    format: EventFormat,
+    user_id: Uuid,
+    drain: DrainState,
+) {
+    let _gauge = ConnectionGauge::open("websocket");
+
+    let mut ping = tokio::time::interval(PING_INTERVAL);
+    ping.reset();
+    let draining = drain.draining();
+    tokio::pin!(draining);
+
+    loop {
+        tokio::select! {
+            delivery = subscription.next() => {
+                let Some(delivery) = delivery else { break };
+                let text = match encode(delivery.event, format, user_id) {
+                    Ok(text) => text,
+                    Err(e) => {
+                        tracing::error!(error = %e, "Failed to serialize live event");
+                        continue;
+                    }
+                };
+                if socket.send(Message::Text(text.into())).await.is_err() {
+                    break;
+                }
+            }
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                // axum answers pings; anything else from the client is ignored
+                Some(Ok(_)) => {}
+            },
+            _ = ping.tick() => {
+                if socket.send(Message::Ping(Bytes::new())).await.is_err() {
+                    break;
+                }
+            }
+            () = &mut draining => {
+                let _ = socket
+                    .send(Message::Close(Some(CloseFrame {
+                        code: close_code::AWAY,
+                        reason: "Server shutting down".into(),
+                    })))
+                    .await;
+                break;
+            }
+        }
+    }
+}
+
+/// Live updates for the signed-in user as Server-Sent Events.
+///
+/// For networks that block WebSockets. Each event is a `message` whose data is
+/// the same JSON as on `GET /v1/ws` and whose id can be sent back in
+/// `Last-Event-ID` to replay what was missed while disconnected.
+#[utoipa::path(
+    get,
+    path = "/v1/notifications/stream",
+    tag = "live",
+    security(("cookie_auth" = [])),
+    params(
+        StreamQuery,
+        ("Last-Event-ID" = Option<String>, Header, description = "Id of the last event received; sent by `EventSource` when it reconnects"),
+    ),
+    responses(
+        (status = 200, description = "`text/event-stream` of events", body = LiveEvent, content_type = "text/event-stream"),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+async fn notification_stream(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Query(query): Query<StreamQuery>,
+    headers: HeaderMap,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .or(query.last_event_id.as_deref());
+    let subscription = state.events.resume(auth_user.user_id, last_event_id);
+    let drain = state.drain.clone();
+
+    let connection = (
+        subscription,
+        ConnectionGauge::open("sse"),
+        query.format,
+        auth_user.user_id,
+    );
+    let events = stream::unfold(connection, |mut connection| async move {
+        let (subscription, _, format, user_id) = &mut connection;
+        loop {
+            let delivery = subscription.next().await?;
+            match encode(delivery.event, *format, *user_id) {
+                Ok(data) => {
+                    let event = Event::default().data(data);
+                    let event = match delivery.id {
+                        Some(id) => event.id(id),
+                        None => event,
+                    };
+                    return Some((Ok(event), connection));
+                }
+                Err(e) => tracing::error!(error = %e, "Failed to serialize live event"),
+            }
+        }
+    })
+    // Ending the response makes the client reconnect, to another instance
+    .take_until(async move { drain.draining().await });
+
+    Sse::new(events).keep_alive(KeepAlive::new().interval(KEEP_ALIVE_INTERVAL))
+}
+
+/// A sample of every event type in flat form
+#[utoipa::path(
+    get,
+    path = "/v1/events/samples",
+    tag = "live",
+    security(("cookie_auth" = [])),
+    responses(
+        (status = 200, description = "One sample per event type", body = Vec<FlatEvent>),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+async fn list_event_samples(auth_user: AuthUser) -> Json<Vec<FlatEvent>> {
+    let now = Utc::now();
+    Json(
+        flat::EVENT_TYPES
+            .iter()
+            .filter_map(|event_type| flat::sample(event_type, auth_user.user_id, now))
+            .collect(),
+    )
+}
+
+/// A sample event in flat form, for mapping fields in automation tools
+#[utoipa::path(
+    get,
+    path = "/v1/events/samples/{event_type}",
+    tag = "live",
+    security(("cookie_auth" = [])),
+    params(("event_type" = String, Path, description = "`review_recorded`, `streak_updated`, `daily_goal_reached`, `deck_updated` or `notification_created`")),
+    responses(
+        (status = 200, description = "Sample event", body = FlatEvent),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Unknown event type", body = ErrorResponse),
+    )
+)]
+async fn get_event_sample(
+    auth_user: AuthUser,
+    Path(event_type): Path<String>,
+) -> Result<Json<FlatEvent>, ApiError> {
+    flat::sample(&event_type, auth_user.user_id, Utc::now())
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("Unknown event type: {event_type}")))
+}
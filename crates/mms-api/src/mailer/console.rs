@@ -0,0 +1,21 @@
+//! Development sender that logs emails instead of sending them.
+
+use crate::mailer::{EmailSender, OutgoingEmail, SendFuture};
+
+/// Writes every email, links included, to the log
+#[derive(Debug, Clone, Copy)]
+pub struct ConsoleSender;
+
+impl EmailSender for ConsoleSender {
+    fn send<'a>(&'a self, email: &'a OutgoingEmail) -> SendFuture<'a> {
+        Box::pin(async move {
+            tracing::info!(
+                to = %email.to,
+                subject = %email.subject,
+                "Email not sent (console provider):\n{}",
+                email.body
+            );
+            Ok(())
+        })
+    }
+}
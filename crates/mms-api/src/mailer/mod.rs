@@ -0,0 +1,133 @@
+//! Outgoing email delivery.
+//!
+//! Emails are rendered from localized [`templates`] and handed to an
+//! [`EmailSender`], chosen with `EMAIL_PROVIDER`: SMTP, SendGrid, Amazon SES or,
+//! for development, the console. Every email goes through the [`outbox`] first,
+//! so sends that fail are retried in the background. Addresses the provider
+//! reports as bouncing through its [`webhooks`] are skipped.
+
+pub mod console;
+pub mod outbox;
+pub mod preview;
+pub mod sendgrid;
+pub mod ses;
+pub mod smtp;
+pub mod templates;
+pub mod webhooks;
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::{ApiConfig, error::ApiError};
+
+/// Boxed future returned by [`EmailSender::send`]
+pub type SendFuture<'a> = Pin<Box<dyn Future<Output = Result<(), ApiError>> + Send + 'a>>;
+
+/// A rendered plain-text email
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutgoingEmail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Delivers rendered emails through one provider
+pub trait EmailSender: Send + Sync + fmt::Debug {
+    fn send<'a>(&'a self, email: &'a OutgoingEmail) -> SendFuture<'a>;
+
+    /// Check that the provider can be reached and accepts the credentials,
+    /// without sending anything
+    fn check(&self) -> SendFuture<'_> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Supported email providers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailProvider {
+    Smtp,
+    Sendgrid,
+    Ses,
+    /// Log emails instead of sending them
+    Console,
+}
+
+impl EmailProvider {
+    /// Name used in `EMAIL_PROVIDER` and webhook paths
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            EmailProvider::Smtp => "smtp",
+            EmailProvider::Sendgrid => "sendgrid",
+            EmailProvider::Ses => "ses",
+            EmailProvider::Console => "console",
+        }
+    }
+}
+
+/// Address emails are sent from
+#[derive(Debug, Clone)]
+pub struct FromAddress {
+    pub email: String,
+    pub name: String,
+}
+
+impl fmt::Display for FromAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} <{}>", self.name, self.email)
+    }
+}
+
+/// Build the sender for the configured provider; `None` when email is not configured.
+///
+/// Missing settings are caught by config validation, so they only show up here
+/// as errors if validation was bypassed.
+pub fn sender_from_config(config: &ApiConfig) -> Result<Option<Arc<dyn EmailSender>>, ApiError> {
+    let Some(provider) = config.email_provider() else {
+        return Ok(None);
+    };
+    let from = config.email_from_address();
+    let missing = |name: &str| ApiError::Email(format!("{name} is required for {provider:?}"));
+
+    let sender: Arc<dyn EmailSender> = match provider {
+        EmailProvider::Smtp => Arc::new(smtp::SmtpSender::new(
+            config
+                .smtp_host
+                .as_deref()
+                .ok_or_else(|| missing("SMTP_HOST"))?,
+            config.smtp_username.as_deref().unwrap_or_default(),
+            config.smtp_password.as_deref().unwrap_or_default(),
+            from.ok_or_else(|| missing("EMAIL_FROM"))?,
+        )?),
+        EmailProvider::Sendgrid => Arc::new(sendgrid::SendgridSender::new(
+            config
+                .sendgrid_api_key
+                .as_deref()
+                .ok_or_else(|| missing("SENDGRID_API_KEY"))?,
+            from.ok_or_else(|| missing("EMAIL_FROM"))?,
+        )),
+        EmailProvider::Ses => Arc::new(ses::SesSender::new(
+            config
+                .ses_region
+                .as_deref()
+                .ok_or_else(|| missing("SES_REGION"))?,
+            config
+                .ses_access_key_id
+                .as_deref()
+                .ok_or_else(|| missing("SES_ACCESS_KEY_ID"))?,
+            config
+                .ses_secret_access_key
+                .as_deref()
+                .ok_or_else(|| missing("SES_SECRET_ACCESS_KEY"))?,
+            from.ok_or_else(|| missing("EMAIL_FROM"))?,
+        )),
+        EmailProvider::Console => Arc::new(console::ConsoleSender),
+    };
+
+    Ok(Some(sender))
+}
@@ -0,0 +1,173 @@
+//! Retries for emails that failed to send.
+//!
+//! [`deliver`] records each email in `email_outbox` before the first attempt
+//! and marks the result. Failed emails are picked up again by [`retry_due`]
+//! with exponential backoff, until they are sent or run out of attempts.
+//! Addresses the provider reported as bouncing are never sent to.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use mms_db::repositories::{email_outbox as outbox_repo, email_suppression as suppression_repo};
+
+use crate::mailer::{EmailSender, OutgoingEmail};
+use crate::metrics;
+
+/// Attempts before an email is given up on
+pub const MAX_ATTEMPTS: i32 = 5;
+
+/// Delay before the first retry; each later retry waits four times longer
+const FIRST_RETRY: Duration = Duration::from_secs(60);
+
+/// How long an email being sent is hidden from other workers
+const LEASE: Duration = Duration::from_secs(300);
+
+/// Emails retried at a time
+const BATCH_SIZE: i64 = 50;
+
+/// How often [`retry_loop`] looks for due retries
+const RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Record `email` in the outbox and send it; a failed send is left for [`retry_due`].
+///
+/// If the outbox cannot be written, the email is still sent once, without retries.
+/// Emails to suppressed addresses are dropped.
+pub async fn deliver(pool: &PgPool, sender: &dyn EmailSender, email: &OutgoingEmail) {
+    if is_suppressed(pool, &email.to).await {
+        tracing::info!(subject = %email.subject, "Not sending email to a suppressed address");
+        return;
+    }
+
+    let lease_until = Utc::now() + LEASE;
+    let id = match outbox_repo::insert(pool, &email.to, &email.subject, &email.body, lease_until)
+        .await
+    {
+        Ok(id) => Some(id),
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to record email in outbox, sending without retries");
+            None
+        }
+    };
+
+    let result = sender.send(email).await;
+    metrics::record_email_send("first", result.is_ok());
+    match id {
+        Some(id) => record_attempt(pool, id, 0, result).await,
+        None => {
+            if let Err(e) = result {
+                tracing::error!(error = %e, subject = %email.subject, "Failed to send email");
+            }
+        }
+    }
+}
+
+/// Send every outbox email whose retry is due; returns how many were sent
+pub async fn retry_due(pool: &PgPool, sender: &dyn EmailSender) -> Result<usize, sqlx::Error> {
+    let mut sent = 0;
+
+    loop {
+        let claimed = outbox_repo::claim_due(pool, Utc::now() + LEASE, BATCH_SIZE).await?;
+        let count = claimed.len();
+
+        for row in claimed {
+            if is_suppressed(pool, row.to_email.expose()).await {
+                if let Err(e) =
+                    outbox_repo::mark_failed(pool, row.id, "Address is suppressed", None).await
+                {
+                    tracing::error!(error = %e, email_id = %row.id, "Failed to record email attempt");
+                }
+                continue;
+            }
+
+            let email = OutgoingEmail {
+                to: row.to_email.into_inner(),
+                subject: row.subject,
+                body: row.body.into_inner(),
+            };
+            let result = sender.send(&email).await;
+            metrics::record_email_send("retry", result.is_ok());
+            if result.is_ok() {
+                sent += 1;
+            }
+            record_attempt(pool, row.id, row.attempts, result).await;
+        }
+
+        if (count as i64) < BATCH_SIZE {
+            break;
+        }
+    }
+
+    Ok(sent)
+}
+
+/// Retry due emails every minute, for as long as the email worker runs
+pub async fn retry_loop(pool: PgPool, sender: Arc<dyn EmailSender>) {
+    let mut interval = tokio::time::interval(RETRY_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match retry_due(&pool, sender.as_ref()).await {
+            Ok(sent) if sent > 0 => tracing::info!("Sent {} emails on retry", sent),
+            Ok(_) => {}
+            Err(e) => tracing::error!("Failed to retry outbox emails: {}", e),
+        }
+    }
+}
+
+/// Whether the provider reported `to` as undeliverable; lookup failures count as deliverable
+async fn is_suppressed(pool: &PgPool, to: &str) -> bool {
+    suppression_repo::is_suppressed(pool, to)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "Failed to check email suppressions, sending anyway");
+            false
+        })
+}
+
+/// Delay before the retry following the given number of failed attempts
+fn backoff(failed_attempts: i32) -> Duration {
+    let exponent = u32::try_from(failed_attempts.saturating_sub(1)).unwrap_or(0);
+    FIRST_RETRY * 4u32.saturating_pow(exponent)
+}
+
+async fn record_attempt(
+    pool: &PgPool,
+    id: Uuid,
+    previous_attempts: i32,
+    result: Result<(), crate::error::ApiError>,
+) {
+    let recorded = match result {
+        Ok(()) => outbox_repo::mark_sent(pool, id).await,
+        Err(e) => {
+            let attempts = previous_attempts + 1;
+            let retry_at = (attempts < MAX_ATTEMPTS).then(|| Utc::now() + backoff(attempts));
+            if retry_at.is_some() {
+                tracing::warn!(error = %e, email_id = %id, attempts, "Failed to send email, will retry");
+            } else {
+                tracing::error!(error = %e, email_id = %id, attempts, "Failed to send email, giving up");
+            }
+            outbox_repo::mark_failed(pool, id, &e.to_string(), retry_at).await
+        }
+    };
+
+    if let Err(e) = recorded {
+        tracing::error!(error = %e, email_id = %id, "Failed to record email attempt");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_grows_fourfold() {
+        assert_eq!(backoff(1), Duration::from_secs(60));
+        assert_eq!(backoff(2), Duration::from_secs(240));
+        assert_eq!(backoff(4), Duration::from_secs(3840));
+    }
+}
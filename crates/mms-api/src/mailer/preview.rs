@@ -0,0 +1,110 @@
+//! Development-only previews of every email with sample data.
+//!
+//! `GET /dev/emails/{template}?locale=es` renders a template the way the email
+//! worker would. The route is only mounted outside production, next to the
+//! Swagger UI; the snapshot tests use the same sample data.
+
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    routing::get,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use mms_db::models::WeeklyDigest;
+
+use crate::{
+    error::ApiError,
+    mailer::templates::{self, Template},
+    state::ApiState,
+    user::email::EmailJob,
+};
+
+/// Create the email preview routes
+pub fn routes() -> Router<ApiState> {
+    Router::new().route("/dev/emails/{template}", get(preview_email))
+}
+
+#[derive(Deserialize)]
+struct PreviewQuery {
+    #[serde(default)]
+    locale: Option<String>,
+}
+
+#[derive(Serialize)]
+struct EmailPreview {
+    /// Locale the email was rendered in, after falling back
+    locale: &'static str,
+    to: String,
+    subject: String,
+    body: String,
+}
+
+async fn preview_email(
+    State(state): State<ApiState>,
+    Path(template): Path<Template>,
+    Query(query): Query<PreviewQuery>,
+) -> Result<Json<EmailPreview>, ApiError> {
+    let locale = templates::resolve_locale(query.locale.as_deref());
+    let email = sample_job(template).render(&state.oidc.frontend_url, locale)?;
+
+    Ok(Json(EmailPreview {
+        locale,
+        to: email.to,
+        subject: email.subject,
+        body: email.body,
+    }))
+}
+
+/// A job for `template` filled with sample data
+#[must_use]
+pub fn sample_job(template: Template) -> EmailJob {
+    let to_email = "ana@example.com".to_string();
+    let username = "ana".to_string();
+
+    match template {
+        Template::Verification => EmailJob::Verification {
+            to_email,
+            username,
+            verification_token: "sample-verification-token".to_string(),
+        },
+        Template::PasswordReset => EmailJob::PasswordReset {
+            to_email,
+            username,
+            reset_token: "sample-reset-token".to_string(),
+        },
+        Template::PasswordChanged => EmailJob::PasswordChanged { to_email, username },
+        Template::ReviewReminder => EmailJob::ReviewReminder {
+            to_email,
+            username,
+            cards_due: 12,
+        },
+        Template::StreakReminder => EmailJob::StreakReminder {
+            to_email,
+            username,
+            streak_days: 7,
+        },
+        Template::WeeklyDigest => EmailJob::WeeklyDigest {
+            digest: Box::new(WeeklyDigest {
+                user_id: Uuid::nil(),
+                email: to_email,
+                username,
+                week_start: chrono::NaiveDate::from_ymd_opt(2026, 10, 5)
+                    .unwrap_or(chrono::NaiveDate::MIN),
+                reviews: 42,
+                correct_reviews: 29,
+                graded_reviews: 40,
+                cards_learned: 6,
+                streak_days: 9,
+            }),
+            preferences_token: "sample-preferences-token".to_string(),
+        },
+        Template::GroupInvite => EmailJob::GroupInvite {
+            to_email,
+            inviter: "ms_garcia".to_string(),
+            group_name: "Spanish 101".to_string(),
+            invite_code: "3F9A1C07".to_string(),
+        },
+    }
+}
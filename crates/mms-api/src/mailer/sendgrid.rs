@@ -0,0 +1,100 @@
+//! SendGrid v3 mail send API.
+
+use std::fmt;
+use std::sync::Arc;
+
+use serde_json::json;
+
+use crate::{
+    error::ApiError,
+    mailer::{EmailSender, FromAddress, OutgoingEmail, SendFuture},
+};
+
+const SEND_URL: &str = "https://api.sendgrid.com/v3/mail/send";
+
+/// Lists the API key's permissions; any key may call it
+const SCOPES_URL: &str = "https://api.sendgrid.com/v3/scopes";
+
+#[derive(Clone)]
+pub struct SendgridSender {
+    api_key: Arc<str>,
+    from: FromAddress,
+    client: reqwest::Client,
+}
+
+impl fmt::Debug for SendgridSender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendgridSender")
+            .field("from", &self.from)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SendgridSender {
+    #[must_use]
+    pub fn new(api_key: &str, from: FromAddress) -> Self {
+        Self {
+            api_key: api_key.into(),
+            from,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn send_email(&self, email: &OutgoingEmail) -> Result<(), ApiError> {
+        let payload = json!({
+            "personalizations": [{ "to": [{ "email": email.to }] }],
+            "from": { "email": self.from.email, "name": self.from.name },
+            "subject": email.subject,
+            "content": [{ "type": "text/plain", "value": email.body }],
+        });
+
+        let response = self
+            .client
+            .post(SEND_URL)
+            .bearer_auth(&*self.api_key)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(payload.to_string())
+            .send()
+            .await
+            .map_err(|e| ApiError::Email(format!("SendGrid unreachable: {e}")))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(ApiError::Email(format!(
+                "SendGrid rejected the email ({status}): {body}"
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn check_key(&self) -> Result<(), ApiError> {
+        let response = self
+            .client
+            .get(SCOPES_URL)
+            .bearer_auth(&*self.api_key)
+            .send()
+            .await
+            .map_err(|e| ApiError::Email(format!("SendGrid unreachable: {e}")))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(ApiError::Email(format!(
+                "SendGrid rejected the API key ({status})"
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl EmailSender for SendgridSender {
+    fn send<'a>(&'a self, email: &'a OutgoingEmail) -> SendFuture<'a> {
+        Box::pin(self.send_email(email))
+    }
+
+    fn check(&self) -> SendFuture<'_> {
+        Box::pin(self.check_key())
+    }
+}
@@ -0,0 +1,221 @@
+//! Amazon SES v2 `SendEmail` API, signed with AWS Signature Version 4.
+
+use std::fmt;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    error::ApiError,
+    mailer::{EmailSender, FromAddress, OutgoingEmail, SendFuture},
+};
+
+const SERVICE: &str = "ses";
+const SEND_PATH: &str = "/v2/email/outbound-emails";
+/// `GetAccount`, to check the credentials without sending
+const ACCOUNT_PATH: &str = "/v2/email/account";
+const SIGNED_HEADERS: &str = "content-type;host;x-amz-date";
+
+#[derive(Clone)]
+pub struct SesSender {
+    region: Arc<str>,
+    access_key_id: Arc<str>,
+    secret_access_key: Arc<str>,
+    from: FromAddress,
+    client: reqwest::Client,
+}
+
+impl fmt::Debug for SesSender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SesSender")
+            .field("region", &self.region)
+            .field("from", &self.from)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SesSender {
+    #[must_use]
+    pub fn new(
+        region: &str,
+        access_key_id: &str,
+        secret_access_key: &str,
+        from: FromAddress,
+    ) -> Self {
+        Self {
+            region: region.into(),
+            access_key_id: access_key_id.into(),
+            secret_access_key: secret_access_key.into(),
+            from,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn host(&self) -> String {
+        format!("email.{}.amazonaws.com", self.region)
+    }
+
+    /// `Authorization` header for a request to `path` with `body` sent at `now`
+    fn authorization(&self, method: &str, path: &str, body: &[u8], now: DateTime<Utc>) -> String {
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let scope = format!("{date}/{}/{SERVICE}/aws4_request", self.region);
+
+        let canonical_request = format!(
+            "{method}\n{path}\n\ncontent-type:application/json\nhost:{}\nx-amz-date:{amz_date}\n\n{SIGNED_HEADERS}\n{}",
+            self.host(),
+            hex::encode(Sha256::digest(body)),
+        );
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            hex::encode(Sha256::digest(canonical_request.as_bytes())),
+        );
+
+        let key = signing_key(&self.secret_access_key, &date, &self.region, SERVICE);
+        let signature = hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()));
+
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={SIGNED_HEADERS}, Signature={signature}",
+            self.access_key_id
+        )
+    }
+
+    async fn send_email(&self, email: &OutgoingEmail) -> Result<(), ApiError> {
+        let body = serde_json::to_vec(&json!({
+            "FromEmailAddress": self.from.to_string(),
+            "Destination": { "ToAddresses": [email.to] },
+            "Content": {
+                "Simple": {
+                    "Subject": { "Data": email.subject, "Charset": "UTF-8" },
+                    "Body": { "Text": { "Data": email.body, "Charset": "UTF-8" } },
+                },
+            },
+        }))
+        .map_err(|e| ApiError::Email(format!("Failed to build email: {e}")))?;
+
+        let now = Utc::now();
+        let response = self
+            .client
+            .post(format!("https://{}{SEND_PATH}", self.host()))
+            .header("content-type", "application/json")
+            .header("x-amz-date", now.format("%Y%m%dT%H%M%SZ").to_string())
+            .header(
+                "authorization",
+                self.authorization("POST", SEND_PATH, &body, now),
+            )
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| ApiError::Email(format!("SES unreachable: {e}")))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(ApiError::Email(format!(
+                "SES rejected the email ({status}): {body}"
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn check_account(&self) -> Result<(), ApiError> {
+        let now = Utc::now();
+        let response = self
+            .client
+            .get(format!("https://{}{ACCOUNT_PATH}", self.host()))
+            .header("content-type", "application/json")
+            .header("x-amz-date", now.format("%Y%m%dT%H%M%SZ").to_string())
+            .header(
+                "authorization",
+                self.authorization("GET", ACCOUNT_PATH, b"", now),
+            )
+            .send()
+            .await
+            .map_err(|e| ApiError::Email(format!("SES unreachable: {e}")))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(ApiError::Email(format!(
+                "SES rejected the credentials ({status})"
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl EmailSender for SesSender {
+    fn send<'a>(&'a self, email: &'a OutgoingEmail) -> SendFuture<'a> {
+        Box::pin(self.send_email(email))
+    }
+
+    fn check(&self) -> SendFuture<'_> {
+        Box::pin(self.check_account())
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// SigV4 key derived from the secret for one day, region and service
+fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac_sha256(format!("AWS4{secret}").as_bytes(), date.as_bytes());
+    let key = hmac_sha256(&key, region.as_bytes());
+    let key = hmac_sha256(&key, service.as_bytes());
+    hmac_sha256(&key, b"aws4_request")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signing_key_matches_aws_example() {
+        // Example from the AWS Signature Version 4 documentation
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        );
+
+        assert_eq!(
+            hex::encode(key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+
+    #[test]
+    fn test_authorization_header() {
+        let sender = SesSender::new(
+            "eu-west-1",
+            "AKIDEXAMPLE",
+            "secret",
+            FromAddress {
+                email: "noreply@example.com".to_string(),
+                name: "Matcha Time".to_string(),
+            },
+        );
+        let now = "2026-10-18T09:30:00Z".parse().unwrap();
+
+        let header = sender.authorization("POST", SEND_PATH, b"{}", now);
+
+        assert!(header.starts_with(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20261018/eu-west-1/ses/aws4_request, SignedHeaders=content-type;host;x-amz-date, Signature="
+        ));
+        assert_eq!(header.rsplit('=').next().unwrap().len(), 64);
+        assert_eq!(header, sender.authorization("POST", SEND_PATH, b"{}", now));
+        assert_ne!(header, sender.authorization("POST", SEND_PATH, b"[]", now));
+        assert_ne!(
+            header,
+            sender.authorization("GET", ACCOUNT_PATH, b"{}", now)
+        );
+    }
+}
@@ -0,0 +1,94 @@
+//! SMTP relay sender.
+
+use lettre::{
+    Message, SmtpTransport, Transport, message::Mailbox,
+    transport::smtp::authentication::Credentials,
+};
+
+use crate::{
+    error::ApiError,
+    mailer::{EmailSender, FromAddress, OutgoingEmail, SendFuture},
+};
+
+/// Sends through an SMTP relay over TLS (port 587)
+#[derive(Clone)]
+pub struct SmtpSender {
+    transport: SmtpTransport,
+    from: Mailbox,
+}
+
+impl std::fmt::Debug for SmtpSender {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SmtpSender")
+            .field("from", &self.from)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SmtpSender {
+    pub fn new(
+        host: &str,
+        username: &str,
+        password: &str,
+        from: FromAddress,
+    ) -> Result<Self, ApiError> {
+        let from: Mailbox = from
+            .to_string()
+            .parse()
+            .map_err(|e| ApiError::Email(format!("Invalid from email: {e}")))?;
+
+        let transport = SmtpTransport::relay(host)
+            .map_err(|e| ApiError::Email(format!("Failed to create SMTP transport: {e}")))?
+            .credentials(Credentials::new(username.to_string(), password.to_string()))
+            .build();
+
+        Ok(Self { transport, from })
+    }
+
+    fn send_blocking(&self, email: &OutgoingEmail) -> Result<(), ApiError> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(email
+                .to
+                .parse()
+                .map_err(|e| ApiError::Validation(format!("Invalid recipient email: {e}")))?)
+            .subject(&email.subject)
+            .body(email.body.clone())
+            .map_err(|e| ApiError::Email(format!("Failed to build email: {e}")))?;
+
+        self.transport
+            .send(&message)
+            .map_err(|e| ApiError::Email(format!("Failed to send email: {e}")))?;
+
+        Ok(())
+    }
+}
+
+impl EmailSender for SmtpSender {
+    fn send<'a>(&'a self, email: &'a OutgoingEmail) -> SendFuture<'a> {
+        let sender = self.clone();
+        let email = email.clone();
+        Box::pin(async move {
+            // Run blocking SMTP I/O off the async runtime
+            tokio::task::spawn_blocking(move || sender.send_blocking(&email))
+                .await
+                .map_err(|e| ApiError::Email(format!("Email send task panicked: {e}")))?
+        })
+    }
+
+    fn check(&self) -> SendFuture<'_> {
+        let transport = self.transport.clone();
+        Box::pin(async move {
+            let connected = tokio::task::spawn_blocking(move || transport.test_connection())
+                .await
+                .map_err(|e| ApiError::Email(format!("SMTP check task panicked: {e}")))?
+                .map_err(|e| ApiError::Email(format!("SMTP server unreachable: {e}")))?;
+            if !connected {
+                return Err(ApiError::Email(
+                    "SMTP server did not accept the connection".to_string(),
+                ));
+            }
+            Ok(())
+        })
+    }
+}
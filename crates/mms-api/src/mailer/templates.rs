@@ -0,0 +1,225 @@
+//! Localized email templates.
+//!
+//! Each email is a handlebars file at `templates/email/<locale>/<name>.hbs`,
+//! embedded at compile time. The first line of the rendered file is the
+//! subject and everything after the blank line below it is the body. Emails
+//! are plain text, so values are not HTML-escaped.
+//!
+//! Recipients get their native language when there is a template for it and
+//! English otherwise. Templates can use `{{plural count "card" "cards"}}`,
+//! which renders the count followed by the matching word.
+
+use std::sync::LazyLock;
+
+use chrono::{Datelike, NaiveDate};
+use handlebars::{Handlebars, handlebars_helper};
+use serde::{Deserialize, Serialize};
+
+use crate::error::ApiError;
+
+/// Locale used when there are no templates in the recipient's language
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Locales with a full set of templates
+pub const LOCALES: &[&str] = &["en", "es"];
+
+/// Every email the API sends
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Template {
+    Verification,
+    PasswordReset,
+    PasswordChanged,
+    ReviewReminder,
+    StreakReminder,
+    WeeklyDigest,
+    GroupInvite,
+}
+
+impl Template {
+    pub const ALL: [Template; 7] = [
+        Template::Verification,
+        Template::PasswordReset,
+        Template::PasswordChanged,
+        Template::ReviewReminder,
+        Template::StreakReminder,
+        Template::WeeklyDigest,
+        Template::GroupInvite,
+    ];
+
+    /// File name of the template, without the extension
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Template::Verification => "verification",
+            Template::PasswordReset => "password_reset",
+            Template::PasswordChanged => "password_changed",
+            Template::ReviewReminder => "review_reminder",
+            Template::StreakReminder => "streak_reminder",
+            Template::WeeklyDigest => "weekly_digest",
+            Template::GroupInvite => "group_invite",
+        }
+    }
+}
+
+macro_rules! template_files {
+    ($($locale:literal: [$($name:literal),* $(,)?]),* $(,)?) => {
+        &[$($((
+            $locale,
+            $name,
+            include_str!(concat!("../../templates/email/", $locale, "/", $name, ".hbs")),
+        )),*),*]
+    };
+}
+
+/// (locale, name, source) of every embedded template
+const FILES: &[(&str, &str, &str)] = template_files! {
+    "en": [
+        "verification",
+        "password_reset",
+        "password_changed",
+        "review_reminder",
+        "streak_reminder",
+        "weekly_digest",
+        "group_invite",
+    ],
+    "es": [
+        "verification",
+        "password_reset",
+        "password_changed",
+        "review_reminder",
+        "streak_reminder",
+        "weekly_digest",
+        "group_invite",
+    ],
+};
+
+handlebars_helper!(plural: |count: i64, one: str, other: str| {
+    format!("{count} {}", if count == 1 { one } else { other })
+});
+
+static REGISTRY: LazyLock<Handlebars<'static>> = LazyLock::new(|| {
+    let mut registry = Handlebars::new();
+    registry.set_strict_mode(true);
+    registry.register_escape_fn(handlebars::no_escape);
+    registry.register_helper("plural", Box::new(plural));
+
+    for (locale, name, source) in FILES {
+        registry
+            .register_template_string(&format!("{locale}/{name}"), *source)
+            .unwrap_or_else(|e| panic!("Invalid email template {locale}/{name}: {e}"));
+    }
+
+    registry
+});
+
+/// The supported locale closest to `requested`, e.g. `es-MX` gives `es`
+#[must_use]
+pub fn resolve_locale(requested: Option<&str>) -> &'static str {
+    let primary = requested
+        .and_then(|locale| locale.split(['-', '_']).next())
+        .map(str::to_ascii_lowercase);
+
+    LOCALES
+        .iter()
+        .find(|locale| primary.as_deref() == Some(**locale))
+        .copied()
+        .unwrap_or(DEFAULT_LOCALE)
+}
+
+/// Render a template to its subject and body
+pub fn render(
+    template: Template,
+    locale: &str,
+    data: &serde_json::Value,
+) -> Result<(String, String), ApiError> {
+    let key = format!("{}/{}", resolve_locale(Some(locale)), template.name());
+    let rendered = REGISTRY
+        .render(&key, data)
+        .map_err(|e| ApiError::Email(format!("Failed to render {key}: {e}")))?;
+
+    let (subject, body) = rendered
+        .split_once("\n\n")
+        .ok_or_else(|| ApiError::Email(format!("Template {key} has no subject line")))?;
+
+    Ok((subject.trim().to_string(), body.trim_end().to_string()))
+}
+
+/// A date as a day of the month in `locale`, e.g. "October 5" or "5 de octubre"
+#[must_use]
+pub fn month_day(date: NaiveDate, locale: &str) -> String {
+    const SPANISH_MONTHS: [&str; 12] = [
+        "enero",
+        "febrero",
+        "marzo",
+        "abril",
+        "mayo",
+        "junio",
+        "julio",
+        "agosto",
+        "septiembre",
+        "octubre",
+        "noviembre",
+        "diciembre",
+    ];
+
+    match resolve_locale(Some(locale)) {
+        "es" => format!(
+            "{} de {}",
+            date.day(),
+            SPANISH_MONTHS[date.month0() as usize]
+        ),
+        _ => date.format("%B %-d").to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mailer::preview::sample_job;
+
+    const FRONTEND_URL: &str = "https://app.example.com";
+
+    #[test]
+    fn test_every_template_renders_in_every_locale() {
+        for locale in LOCALES {
+            for template in Template::ALL {
+                let email = sample_job(template).render(FRONTEND_URL, locale).unwrap();
+                insta::assert_snapshot!(
+                    format!("{locale}_{}", template.name()),
+                    format!("Subject: {}\n\n{}", email.subject, email.body)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_resolve_locale_falls_back_to_english() {
+        assert_eq!(resolve_locale(Some("es")), "es");
+        assert_eq!(resolve_locale(Some("es-MX")), "es");
+        assert_eq!(resolve_locale(Some("ES_es")), "es");
+        assert_eq!(resolve_locale(Some("fr")), "en");
+        assert_eq!(resolve_locale(None), "en");
+    }
+
+    #[test]
+    fn test_plural_and_month_day() {
+        let data = serde_json::json!({
+            "username": "ana",
+            "frontend_url": FRONTEND_URL,
+            "cards_due": 1,
+        });
+        let (subject, _) = render(Template::ReviewReminder, "en", &data).unwrap();
+        assert_eq!(subject, "You have 1 card due for review");
+
+        let date = NaiveDate::from_ymd_opt(2026, 10, 5).unwrap();
+        assert_eq!(month_day(date, "en"), "October 5");
+        assert_eq!(month_day(date, "es"), "5 de octubre");
+    }
+
+    #[test]
+    fn test_missing_values_fail_to_render() {
+        let data = serde_json::json!({ "username": "ana" });
+        assert!(render(Template::Verification, "en", &data).is_err());
+    }
+}
@@ -0,0 +1,492 @@
+//! Bounce and complaint webhooks from the email providers.
+//!
+//! SendGrid's Event Webhook and Amazon SES notifications (delivered through
+//! SNS) post to `/webhooks/email/{provider}`. Both providers send credentials
+//! embedded in the subscribed URL as basic auth, so `EMAIL_WEBHOOK_SECRET` is
+//! taken as the basic auth password (or a bearer token) and stays out of the
+//! request line. The `?token=` parameter of older subscriptions still works;
+//! request logging redacts it.
+//! Permanent bounces and spam complaints are handed to the job queue, which
+//! suppresses the address so the [`outbox`](super::outbox) stops sending to
+//! it; a database hiccup then means a retry instead of a lost event.
+//! Temporary failures and other events are ignored. Support sees and clears
+//! suppressions on the admin user view.
+
+use axum::{
+    Json, Router,
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{HeaderMap, header},
+    routing::post,
+};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use utoipa::{IntoParams, ToSchema};
+
+use mms_db::repositories::email_suppression as suppression_repo;
+
+use crate::{
+    error::{ApiError, ErrorResponse},
+    jobs::queue::{self, Job},
+    mailer::EmailProvider,
+    state::ApiState,
+    token_service::hash_token,
+};
+
+/// Create the email webhook routes
+pub fn routes() -> Router<ApiState> {
+    Router::new().route("/webhooks/email/{provider}", post(receive_email_events))
+}
+
+/// Why an address stopped getting email
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedbackKind {
+    /// The address permanently rejects mail
+    Bounce,
+    /// The recipient marked an email as spam
+    Complaint,
+}
+
+impl FeedbackKind {
+    /// Value stored in `email_suppressions.reason`
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FeedbackKind::Bounce => "bounce",
+            FeedbackKind::Complaint => "complaint",
+        }
+    }
+}
+
+/// One undeliverable address reported by a provider
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Feedback {
+    pub email: String,
+    pub kind: FeedbackKind,
+    pub detail: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SendgridEvent {
+    email: String,
+    event: String,
+    /// `bounce` for permanent bounces, `blocked` for temporary ones
+    #[serde(default, rename = "type")]
+    bounce_type: Option<String>,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+/// Parse a SendGrid Event Webhook batch
+pub fn parse_sendgrid(body: &[u8]) -> Result<Vec<Feedback>, ApiError> {
+    let events: Vec<SendgridEvent> = serde_json::from_slice(body)
+        .map_err(|e| ApiError::Validation(format!("Invalid SendGrid event batch: {e}")))?;
+
+    Ok(events
+        .into_iter()
+        .filter_map(|event| {
+            let kind = match event.event.as_str() {
+                "bounce" if event.bounce_type.as_deref() != Some("blocked") => FeedbackKind::Bounce,
+                "spamreport" => FeedbackKind::Complaint,
+                _ => return None,
+            };
+            Some(Feedback {
+                email: event.email,
+                kind,
+                detail: event.reason,
+            })
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "Type")]
+enum SnsMessage {
+    SubscriptionConfirmation {
+        #[serde(rename = "SubscribeURL")]
+        subscribe_url: String,
+    },
+    Notification {
+        #[serde(rename = "Message")]
+        message: String,
+    },
+    UnsubscribeConfirmation {},
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SesNotification {
+    notification_type: String,
+    #[serde(default)]
+    bounce: Option<SesBounce>,
+    #[serde(default)]
+    complaint: Option<SesComplaint>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SesBounce {
+    bounce_type: String,
+    bounced_recipients: Vec<SesRecipient>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SesComplaint {
+    complained_recipients: Vec<SesRecipient>,
+    #[serde(default)]
+    complaint_feedback_type: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SesRecipient {
+    email_address: String,
+    #[serde(default)]
+    diagnostic_code: Option<String>,
+}
+
+/// What an SNS delivery asks of us
+#[derive(Debug, PartialEq, Eq)]
+pub enum SesEvent {
+    /// SNS wants the subscription confirmed by fetching this URL
+    Confirm(String),
+    Feedback(Vec<Feedback>),
+}
+
+/// Parse an SNS delivery carrying an SES notification
+pub fn parse_ses(body: &[u8]) -> Result<SesEvent, ApiError> {
+    let invalid = |e: serde_json::Error| ApiError::Validation(format!("Invalid SNS message: {e}"));
+
+    let message = match serde_json::from_slice(body).map_err(invalid)? {
+        SnsMessage::SubscriptionConfirmation { subscribe_url } => {
+            return Ok(SesEvent::Confirm(subscribe_url));
+        }
+        SnsMessage::UnsubscribeConfirmation {} => return Ok(SesEvent::Feedback(Vec::new())),
+        SnsMessage::Notification { message } => message,
+    };
+    let notification: SesNotification = serde_json::from_str(&message).map_err(invalid)?;
+
+    let feedback = match notification.notification_type.as_str() {
+        "Bounce" => notification
+            .bounce
+            .filter(|bounce| bounce.bounce_type == "Permanent")
+            .map(|bounce| {
+                bounce
+                    .bounced_recipients
+                    .into_iter()
+                    .map(|recipient| Feedback {
+                        email: recipient.email_address,
+                        kind: FeedbackKind::Bounce,
+                        detail: recipient.diagnostic_code,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+        "Complaint" => notification
+            .complaint
+            .map(|complaint| {
+                let detail = complaint.complaint_feedback_type;
+                complaint
+                    .complained_recipients
+                    .into_iter()
+                    .map(|recipient| Feedback {
+                        email: recipient.email_address,
+                        kind: FeedbackKind::Complaint,
+                        detail: detail.clone(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+
+    Ok(SesEvent::Feedback(feedback))
+}
+
+/// Whether `url` is an SNS endpoint we may fetch to confirm a subscription
+fn is_sns_url(url: &str) -> bool {
+    let Some(host) = url
+        .strip_prefix("https://")
+        .and_then(|rest| rest.split(['/', '?']).next())
+    else {
+        return false;
+    };
+    host.starts_with("sns.") && host.ends_with(".amazonaws.com") && !host.contains(['@', ':'])
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct WebhookQuery {
+    /// `EMAIL_WEBHOOK_SECRET`, for subscriptions that cannot send it in the
+    /// `Authorization` header
+    token: Option<String>,
+}
+
+/// The secret a delivery presents: the basic auth password, a bearer token or,
+/// failing both, the `token` query parameter
+fn presented_secret(headers: &HeaderMap, query: WebhookQuery) -> Option<String> {
+    let authorization = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+
+    if let Some(token) = authorization.and_then(|value| value.strip_prefix("Bearer ")) {
+        return Some(token.to_string());
+    }
+    let basic = authorization
+        .and_then(|value| value.strip_prefix("Basic "))
+        .and_then(|encoded| STANDARD.decode(encoded.trim()).ok())
+        .and_then(|decoded| String::from_utf8(decoded).ok())
+        .and_then(|credentials| {
+            credentials
+                .split_once(':')
+                .map(|(_, password)| password.to_string())
+        });
+    basic.or(query.token)
+}
+
+#[derive(Serialize, ToSchema)]
+struct WebhookResponse {
+    /// Undeliverable addresses in this delivery, queued to be suppressed
+    accepted: usize,
+}
+
+/// Receive bounce and complaint events from SendGrid or Amazon SES.
+///
+/// `EMAIL_WEBHOOK_SECRET` is the basic auth password, set by subscribing
+/// `https://webhook:<secret>@host/v1/webhooks/email/{provider}`, or a bearer token.
+#[utoipa::path(
+    post,
+    path = "/v1/webhooks/email/{provider}",
+    tag = "webhooks",
+    params(
+        ("provider" = String, Path, description = "`sendgrid` or `ses`"),
+        WebhookQuery,
+    ),
+    request_body(content = String, description = "The provider's event payload"),
+    responses(
+        (status = 200, description = "Events accepted", body = WebhookResponse),
+        (status = 400, description = "Malformed payload", body = ErrorResponse),
+        (status = 401, description = "Wrong or missing secret", body = ErrorResponse),
+        (status = 404, description = "Webhooks disabled or unknown provider", body = ErrorResponse),
+    )
+)]
+async fn receive_email_events(
+    State(state): State<ApiState>,
+    Path(provider): Path<String>,
+    Query(query): Query<WebhookQuery>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<WebhookResponse>, ApiError> {
+    let not_found = || ApiError::NotFound("Webhook not found".to_string());
+    let secret = state
+        .auth
+        .email_webhook_secret
+        .as_deref()
+        .ok_or_else(not_found)?;
+    // Compare digests so the comparison time does not depend on how much of the token matches
+    let presented = presented_secret(&headers, query).unwrap_or_default();
+    if hash_token(&presented) != hash_token(secret) {
+        return Err(ApiError::Auth("Invalid webhook token".to_string()));
+    }
+
+    let provider: EmailProvider =
+        serde_json::from_value(serde_json::Value::String(provider)).map_err(|_| not_found())?;
+    let feedback = match provider {
+        EmailProvider::Sendgrid => parse_sendgrid(&body)?,
+        EmailProvider::Ses => match parse_ses(&body)? {
+            SesEvent::Confirm(url) => {
+                confirm_sns_subscription(&url).await?;
+                Vec::new()
+            }
+            SesEvent::Feedback(feedback) => feedback,
+        },
+        EmailProvider::Smtp | EmailProvider::Console => return Err(not_found()),
+    };
+
+    let accepted = feedback.len();
+    if !feedback.is_empty() {
+        let job = Job::EmailFeedback {
+            provider: provider.as_str().to_string(),
+            feedback,
+        };
+        queue::enqueue(&state.pool, &job).await?;
+    }
+
+    Ok(Json(WebhookResponse { accepted }))
+}
+
+/// Suppress the addresses a provider reported; run by the job queue
+pub async fn suppress(
+    pool: &PgPool,
+    provider: &str,
+    feedback: &[Feedback],
+) -> Result<(), sqlx::Error> {
+    for item in feedback {
+        suppression_repo::record(
+            pool,
+            &item.email,
+            item.kind.as_str(),
+            provider,
+            item.detail.as_deref(),
+        )
+        .await?;
+        tracing::info!(
+            provider,
+            reason = item.kind.as_str(),
+            "Suppressed an undeliverable email address"
+        );
+    }
+    Ok(())
+}
+
+async fn confirm_sns_subscription(url: &str) -> Result<(), ApiError> {
+    if !is_sns_url(url) {
+        return Err(ApiError::Validation(
+            "SubscribeURL is not an SNS endpoint".to_string(),
+        ));
+    }
+
+    reqwest::get(url)
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| ApiError::Email(format!("Failed to confirm SNS subscription: {e}")))?;
+    tracing::info!("Confirmed SNS subscription for SES notifications");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sendgrid_keeps_permanent_bounces_and_spam_reports() {
+        let body = json!([
+            { "email": "gone@example.com", "event": "bounce", "type": "bounce", "reason": "550 5.1.1 no such user" },
+            { "email": "full@example.com", "event": "bounce", "type": "blocked" },
+            { "email": "angry@example.com", "event": "spamreport" },
+            { "email": "fine@example.com", "event": "delivered" },
+        ]);
+
+        let feedback = parse_sendgrid(body.to_string().as_bytes()).unwrap();
+
+        assert_eq!(
+            feedback,
+            vec![
+                Feedback {
+                    email: "gone@example.com".to_string(),
+                    kind: FeedbackKind::Bounce,
+                    detail: Some("550 5.1.1 no such user".to_string()),
+                },
+                Feedback {
+                    email: "angry@example.com".to_string(),
+                    kind: FeedbackKind::Complaint,
+                    detail: None,
+                },
+            ]
+        );
+    }
+
+    fn sns(message: serde_json::Value) -> Vec<u8> {
+        json!({ "Type": "Notification", "Message": message.to_string() })
+            .to_string()
+            .into_bytes()
+    }
+
+    #[test]
+    fn test_ses_bounces_and_complaints() {
+        let permanent = sns(json!({
+            "notificationType": "Bounce",
+            "bounce": {
+                "bounceType": "Permanent",
+                "bouncedRecipients": [{ "emailAddress": "gone@example.com", "diagnosticCode": "smtp; 550" }],
+            },
+        }));
+        let SesEvent::Feedback(feedback) = parse_ses(&permanent).unwrap() else {
+            panic!("expected feedback");
+        };
+        assert_eq!(feedback.len(), 1);
+        assert_eq!(feedback[0].kind, FeedbackKind::Bounce);
+        assert_eq!(feedback[0].detail.as_deref(), Some("smtp; 550"));
+
+        let transient = sns(json!({
+            "notificationType": "Bounce",
+            "bounce": { "bounceType": "Transient", "bouncedRecipients": [{ "emailAddress": "full@example.com" }] },
+        }));
+        assert_eq!(
+            parse_ses(&transient).unwrap(),
+            SesEvent::Feedback(Vec::new())
+        );
+
+        let complaint = sns(json!({
+            "notificationType": "Complaint",
+            "complaint": {
+                "complainedRecipients": [{ "emailAddress": "angry@example.com" }],
+                "complaintFeedbackType": "abuse",
+            },
+        }));
+        let SesEvent::Feedback(feedback) = parse_ses(&complaint).unwrap() else {
+            panic!("expected feedback");
+        };
+        assert_eq!(feedback[0].kind, FeedbackKind::Complaint);
+        assert_eq!(feedback[0].detail.as_deref(), Some("abuse"));
+    }
+
+    #[test]
+    fn test_sns_subscription_urls_are_checked() {
+        let confirm = json!({
+            "Type": "SubscriptionConfirmation",
+            "SubscribeURL": "https://sns.eu-west-1.amazonaws.com/?Action=ConfirmSubscription",
+        });
+        let SesEvent::Confirm(url) = parse_ses(confirm.to_string().as_bytes()).unwrap() else {
+            panic!("expected a confirmation");
+        };
+        assert!(is_sns_url(&url));
+
+        assert!(!is_sns_url("http://sns.eu-west-1.amazonaws.com/"));
+        assert!(!is_sns_url("https://sns.evil.example.com/"));
+        assert!(!is_sns_url("https://sns.x.amazonaws.com@evil.example.com/"));
+        assert!(!is_sns_url("https://169.254.169.254/latest"));
+    }
+
+    #[test]
+    fn test_secret_is_read_from_authorization_before_query() {
+        let query = || WebhookQuery {
+            token: Some("from-query".to_string()),
+        };
+        let with = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::AUTHORIZATION, value.parse().unwrap());
+            headers
+        };
+
+        let basic = with(&format!("Basic {}", STANDARD.encode("webhook:s3cret")));
+        assert_eq!(presented_secret(&basic, query()).as_deref(), Some("s3cret"));
+        assert_eq!(
+            presented_secret(&with("Bearer s3cret"), query()).as_deref(),
+            Some("s3cret")
+        );
+        assert_eq!(
+            presented_secret(&HeaderMap::new(), query()).as_deref(),
+            Some("from-query")
+        );
+        assert_eq!(
+            presented_secret(&HeaderMap::new(), WebhookQuery { token: None }),
+            None
+        );
+    }
+
+    #[test]
+    fn test_provider_names_match_webhook_paths() {
+        for provider in [EmailProvider::Sendgrid, EmailProvider::Ses] {
+            let parsed: EmailProvider =
+                serde_json::from_value(serde_json::Value::String(provider.as_str().to_string()))
+                    .unwrap();
+            assert_eq!(parsed, provider);
+        }
+    }
+}
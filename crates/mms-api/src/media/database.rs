@@ -0,0 +1,45 @@
+//! Default store keeping objects in the database.
+
+use sqlx::PgPool;
+
+use mms_db::repositories::media as media_repo;
+
+use crate::media::{MediaFuture, MediaObject, MediaStore};
+
+/// Objects in the `media_objects` table
+#[derive(Debug, Clone)]
+pub struct DatabaseStore {
+    pool: PgPool,
+}
+
+impl DatabaseStore {
+    #[must_use]
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl MediaStore for DatabaseStore {
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        content_type: &'a str,
+        bytes: Vec<u8>,
+    ) -> MediaFuture<'a, ()> {
+        Box::pin(async move {
+            media_repo::put(&self.pool, key, content_type, &bytes).await?;
+            Ok(())
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> MediaFuture<'a, Option<MediaObject>> {
+        Box::pin(async move { Ok(media_repo::get(&self.pool, key).await?) })
+    }
+
+    fn delete_prefix<'a>(&'a self, prefix: &'a str) -> MediaFuture<'a, ()> {
+        Box::pin(async move {
+            media_repo::delete_prefix(&self.pool, prefix).await?;
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,144 @@
+//! Store keeping objects as files in a local directory.
+//!
+//! Suits a single instance with a persistent disk; replicas would each see
+//! their own files. The content type follows from the file extension.
+
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use crate::error::ApiError;
+use crate::media::{MediaFuture, MediaObject, MediaStore, is_valid_key};
+
+/// Files under a root directory, one per key
+#[derive(Debug, Clone)]
+pub struct LocalStore {
+    root: PathBuf,
+}
+
+impl LocalStore {
+    #[must_use]
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf, ApiError> {
+        if !is_valid_key(key) {
+            return Err(ApiError::Media(format!("Invalid media key: {key}")));
+        }
+        Ok(self.root.join(key))
+    }
+}
+
+/// Content type of a stored file, from its extension
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("png") => "image/png",
+        Some("webp") => "image/webp",
+        Some("gif") => "image/gif",
+        _ => "application/octet-stream",
+    }
+}
+
+fn io_error(action: &str, path: &Path, e: std::io::Error) -> ApiError {
+    ApiError::Media(format!("Failed to {action} {}: {e}", path.display()))
+}
+
+impl MediaStore for LocalStore {
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        _content_type: &'a str,
+        bytes: Vec<u8>,
+    ) -> MediaFuture<'a, ()> {
+        Box::pin(async move {
+            let path = self.path(key)?;
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent)
+                    .await
+                    .map_err(|e| io_error("create", parent, e))?;
+            }
+            tokio::fs::write(&path, bytes)
+                .await
+                .map_err(|e| io_error("write", &path, e))
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> MediaFuture<'a, Option<MediaObject>> {
+        Box::pin(async move {
+            let path = self.path(key)?;
+            match tokio::fs::read(&path).await {
+                Ok(bytes) => Ok(Some(MediaObject {
+                    content_type: content_type(&path).to_string(),
+                    bytes,
+                })),
+                Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(io_error("read", &path, e)),
+            }
+        })
+    }
+
+    fn delete_prefix<'a>(&'a self, prefix: &'a str) -> MediaFuture<'a, ()> {
+        Box::pin(async move {
+            // Everything in the prefix's directory whose name starts with the rest
+            let (dir, start) = prefix.rsplit_once('/').unwrap_or(("", prefix));
+            let dir = if dir.is_empty() {
+                self.root.clone()
+            } else {
+                self.path(dir)?
+            };
+            let mut entries = match tokio::fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+                Err(e) => return Err(io_error("list", &dir, e)),
+            };
+            while let Some(entry) = entries
+                .next_entry()
+                .await
+                .map_err(|e| io_error("list", &dir, e))?
+            {
+                if !entry.file_name().to_string_lossy().starts_with(start) {
+                    continue;
+                }
+                let path = entry.path();
+                let removed = if entry.file_type().await.is_ok_and(|kind| kind.is_dir()) {
+                    tokio::fs::remove_dir_all(&path).await
+                } else {
+                    tokio::fs::remove_file(&path).await
+                };
+                removed.map_err(|e| io_error("delete", &path, e))?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_objects_round_trip_and_delete_by_prefix() {
+        let root = std::env::temp_dir().join(format!("mms-media-{}", uuid::Uuid::new_v4()));
+        let store = LocalStore::new(&root);
+
+        store
+            .put("avatars/a/1/256.jpg", "image/jpeg", vec![1, 2, 3])
+            .await
+            .unwrap();
+        store
+            .put("avatars/a/2/256.jpg", "image/jpeg", vec![4])
+            .await
+            .unwrap();
+        let object = store.get("avatars/a/1/256.jpg").await.unwrap().unwrap();
+        assert_eq!(object.content_type, "image/jpeg");
+        assert_eq!(object.bytes, [1, 2, 3]);
+
+        store.delete_prefix("avatars/a/1/").await.unwrap();
+        assert!(store.get("avatars/a/1/256.jpg").await.unwrap().is_none());
+        assert!(store.get("avatars/a/2/256.jpg").await.unwrap().is_some());
+
+        assert!(store.get("../outside.jpg").await.is_err());
+        tokio::fs::remove_dir_all(root).await.unwrap();
+    }
+}
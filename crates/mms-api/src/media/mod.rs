@@ -0,0 +1,110 @@
+//! Storage for uploaded media such as avatars.
+//!
+//! Objects are kept under string keys in a [`MediaStore`], chosen with
+//! `MEDIA_PROVIDER`: the database (the default, nothing else to run) or a local
+//! directory. Whatever the store, objects are served by the [`routes`] at
+//! `/v1/media/{key}`. Keys never change content, so responses are cached for good.
+
+pub mod database;
+pub mod local;
+pub mod routes;
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use serde::Deserialize;
+use sqlx::PgPool;
+
+use crate::{ApiConfig, error::ApiError};
+
+pub use mms_db::models::MediaObject;
+pub use routes::routes;
+
+/// Boxed future returned by [`MediaStore`] methods
+pub type MediaFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, ApiError>> + Send + 'a>>;
+
+/// Keeps media objects under string keys
+pub trait MediaStore: Send + Sync + fmt::Debug {
+    /// Store an object, replacing any object with the same key
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        content_type: &'a str,
+        bytes: Vec<u8>,
+    ) -> MediaFuture<'a, ()>;
+
+    fn get<'a>(&'a self, key: &'a str) -> MediaFuture<'a, Option<MediaObject>>;
+
+    /// Delete every object whose key starts with `prefix`
+    fn delete_prefix<'a>(&'a self, prefix: &'a str) -> MediaFuture<'a, ()>;
+}
+
+/// Supported media stores
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaProvider {
+    /// Objects in the `media_objects` table
+    #[default]
+    Database,
+    /// Files under `MEDIA_DIR`
+    Local,
+}
+
+/// Build the store for the configured provider.
+///
+/// A missing `MEDIA_DIR` is caught by config validation, so it only shows up
+/// here if validation was bypassed.
+pub fn store_from_config(
+    config: &ApiConfig,
+    pool: PgPool,
+) -> Result<Arc<dyn MediaStore>, ApiError> {
+    let store: Arc<dyn MediaStore> = match config.media_provider {
+        MediaProvider::Database => Arc::new(database::DatabaseStore::new(pool)),
+        MediaProvider::Local => {
+            let dir = config
+                .media_dir
+                .as_deref()
+                .ok_or_else(|| ApiError::Media("MEDIA_DIR is required for Local".to_string()))?;
+            Arc::new(local::LocalStore::new(dir))
+        }
+    };
+    Ok(store)
+}
+
+/// Path the object under `key` is served at
+#[must_use]
+pub fn url(key: &str) -> String {
+    format!("/v1/media/{key}")
+}
+
+/// Whether `key` is one the API could have written: slash-separated segments of
+/// letters, digits, `-`, `_` and `.`, none of them empty or only dots
+#[must_use]
+pub fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key.split('/').all(|segment| {
+            !segment.is_empty()
+                && !segment.chars().all(|c| c == '.')
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_cannot_leave_the_store() {
+        assert!(is_valid_key("avatars/550e8400/256.jpg"));
+        assert!(!is_valid_key(""));
+        assert!(!is_valid_key("avatars/../secrets"));
+        assert!(!is_valid_key("/etc/passwd"));
+        assert!(!is_valid_key("avatars//256.jpg"));
+        assert!(!is_valid_key("avatars/a b.jpg"));
+        assert!(!is_valid_key("avatars\\..\\x"));
+    }
+}
@@ -0,0 +1,55 @@
+use axum::{
+    Router,
+    extract::{Path, State},
+    http::header,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+
+use crate::{
+    ApiState,
+    error::{ApiError, ErrorResponse},
+    media::is_valid_key,
+};
+
+/// Create the media routes
+pub fn routes() -> Router<ApiState> {
+    Router::new().route("/media/{*key}", get(get_media))
+}
+
+/// A stored media object, such as a resized avatar
+///
+/// Keys are never reused for different content, so responses may be cached
+/// indefinitely.
+#[utoipa::path(
+    get,
+    path = "/v1/media/{key}",
+    tag = "media",
+    params(("key" = String, Path, description = "Object key, e.g. `avatars/{user_id}/{upload_id}/256.jpg`")),
+    responses(
+        (status = 200, description = "The object", body = Vec<u8>, content_type = "image/jpeg"),
+        (status = 404, description = "No such object", body = ErrorResponse),
+    )
+)]
+async fn get_media(
+    State(state): State<ApiState>,
+    Path(key): Path<String>,
+) -> Result<Response, ApiError> {
+    let not_found = || ApiError::NotFound("Media not found".to_string());
+    if !is_valid_key(&key) {
+        return Err(not_found());
+    }
+    let object = state.media.get(&key).await?.ok_or_else(not_found)?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, object.content_type),
+            (
+                header::CACHE_CONTROL,
+                "public, max-age=31536000, immutable".to_string(),
+            ),
+        ],
+        object.bytes,
+    )
+        .into_response())
+}
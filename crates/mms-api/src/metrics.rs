@@ -1,13 +1,19 @@
 //! Prometheus metrics for monitoring API performance and health.
+//!
+//! Besides HTTP requests, this covers the database pool (sampled on each
+//! scrape), background job runs, email sends, refresh token rotations,
+//! reviews by grade with the intervals they schedule, and how often the
+//! public listing cache and conditional GETs save work.
 
 use axum::{
-    extract::Request,
+    extract::{Request, State},
     http::StatusCode,
     middleware::Next,
     response::{IntoResponse, Response},
 };
 use metrics::{counter, gauge, histogram};
 use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use sqlx::PgPool;
 use std::sync::LazyLock;
 use std::time::Instant;
 
@@ -16,6 +22,12 @@ static UUID_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
 });
 static NUMBER_RE: LazyLock<regex::Regex> = LazyLock::new(|| regex::Regex::new(r"/\d+").unwrap());
 
+/// Compile the path normalization regexes ahead of the first request
+pub(crate) fn precompile_patterns() {
+    LazyLock::force(&UUID_RE);
+    LazyLock::force(&NUMBER_RE);
+}
+
 /// Initialize Prometheus metrics exporter
 pub fn init_metrics() -> anyhow::Result<PrometheusHandle> {
     let builder = PrometheusBuilder::new();
@@ -27,6 +39,16 @@ pub fn init_metrics() -> anyhow::Result<PrometheusHandle> {
             0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
         ],
     )?;
+    let builder = builder.set_buckets_for_metric(
+        Matcher::Full("background_job_duration_seconds".to_string()),
+        &[0.1, 0.5, 1.0, 5.0, 15.0, 60.0, 300.0, 900.0, 3600.0],
+    )?;
+    let builder = builder.set_buckets_for_metric(
+        Matcher::Full("review_interval_days".to_string()),
+        &[
+            0.5, 1.0, 2.0, 4.0, 7.0, 14.0, 30.0, 60.0, 120.0, 240.0, 365.0,
+        ],
+    )?;
 
     // Install the exporter and get the handle
     let handle = builder.install_recorder()?;
@@ -84,11 +106,27 @@ fn normalize_path(path: &str) -> String {
     NUMBER_RE.replace_all(&normalized, "/:id").into_owned()
 }
 
+/// What the /metrics endpoint reads
+#[derive(Clone)]
+pub struct MetricsState {
+    pub handle: PrometheusHandle,
+    /// Sampled on each scrape
+    pub pool: PgPool,
+}
+
 /// Handler for the /metrics endpoint
-pub async fn metrics_handler(
-    axum::extract::State(handle): axum::extract::State<PrometheusHandle>,
-) -> impl IntoResponse {
-    (StatusCode::OK, handle.render())
+pub async fn metrics_handler(State(state): State<MetricsState>) -> impl IntoResponse {
+    record_pool_usage(&state.pool);
+    (StatusCode::OK, state.handle.render())
+}
+
+/// Record the pool's connections in use, idle ones and its limit
+pub fn record_pool_usage(pool: &PgPool) {
+    let size = pool.size() as f64;
+    let idle = pool.num_idle() as f64;
+    gauge!("db_pool_connections", "state" => "idle").set(idle);
+    gauge!("db_pool_connections", "state" => "in_use").set((size - idle).max(0.0));
+    gauge!("db_pool_max_connections").set(pool.options().get_max_connections() as f64);
 }
 
 /// Record database query metrics
@@ -122,6 +160,72 @@ pub fn record_auth_event(event_type: &str, method: &str, success: bool) {
     .increment(1);
 }
 
+/// Record a review flagged as implausible, by reason (`burst` or `too_fast`)
+pub fn record_suspicious_review(reason: &'static str) {
+    counter!("suspicious_reviews_total", "reason" => reason).increment(1);
+}
+
+/// Count requests rate limited in-process because Redis did not answer
+pub fn record_rate_limit_fallback() {
+    counter!("rate_limit_fallbacks_total").increment(1);
+}
+
+/// Track open live update connections by transport (`websocket` or `sse`)
+pub fn record_live_connection(transport: &'static str, opened: bool) {
+    let delta = if opened { 1.0 } else { -1.0 };
+    gauge!("live_connections", "transport" => transport).increment(delta);
+}
+
+/// Record one run of a background job
+pub fn record_job_run(job: &'static str, duration_secs: f64, success: bool) {
+    let status = if success { "success" } else { "failure" };
+
+    counter!("background_job_runs_total", "job" => job, "status" => status).increment(1);
+    histogram!("background_job_duration_seconds", "job" => job).record(duration_secs);
+}
+
+/// Record a queued job given up on after its last attempt
+pub fn record_job_dead_lettered(job: &'static str) {
+    counter!("jobs_dead_lettered_total", "job" => job).increment(1);
+}
+
+/// Record an attempt to send an email through the provider, `first` or a `retry`
+pub fn record_email_send(attempt: &'static str, success: bool) {
+    let status = if success { "success" } else { "failure" };
+
+    counter!("email_send_attempts_total", "attempt" => attempt, "status" => status).increment(1);
+}
+
+/// Record a refresh by outcome: `rotated`, `invalid`, `expired` or `session_ended`
+pub fn record_refresh_rotation(outcome: &'static str) {
+    counter!("refresh_token_rotations_total", "outcome" => outcome).increment(1);
+}
+
+/// Record a practice review by grade with the interval it scheduled the card for
+pub fn record_review(is_correct: bool, interval_days: f64) {
+    let grade = if is_correct { "correct" } else { "wrong" };
+
+    counter!("reviews_total", "grade" => grade).increment(1);
+    histogram!("review_interval_days", "grade" => grade).record(interval_days);
+}
+
+/// Record how a public listing was answered: `fresh` from the database,
+/// `stale` from the cache while the database is unreachable, or a `miss`
+pub fn record_public_cache(outcome: &'static str) {
+    counter!("public_cache_requests_total", "outcome" => outcome).increment(1);
+}
+
+/// Record a conditional GET, answered with 304 when the client's copy was current
+pub fn record_conditional_get(not_modified: bool) {
+    let result = if not_modified {
+        "not_modified"
+    } else {
+        "modified"
+    };
+
+    counter!("conditional_requests_total", "result" => result).increment(1);
+}
+
 /// Record email sending events
 pub fn record_email_event(email_type: &str, success: bool) {
     let status = if success { "success" } else { "failure" };
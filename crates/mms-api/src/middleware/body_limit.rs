@@ -0,0 +1,330 @@
+//! Request body limits.
+//!
+//! Every body is capped before a handler sees it: most routes get the default
+//! limit, content imports and avatar uploads their own. A declared `Content-Length` over the
+//! limit is refused before anything is read. JSON bodies are also checked for
+//! their shape, so deeply nested documents and objects repeating a key are
+//! rejected before they reach the extractors, which would otherwise recurse
+//! through the former and silently keep the last value of the latter.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{HeaderMap, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use http_body_util::{LengthLimitError, Limited};
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+
+use crate::{config::ApiConfig, error::ApiError, versioning::strip_version_prefix};
+
+/// Deepest nesting of arrays and objects accepted in a JSON body
+pub const MAX_JSON_DEPTH: usize = 32;
+
+/// Body size limits per route class
+#[derive(Debug, Clone, Copy)]
+pub struct BodyLimits {
+    /// Bytes accepted by most routes
+    pub default: usize,
+    /// Bytes accepted by content and known-word imports
+    pub import: usize,
+    /// Bytes accepted by avatar uploads
+    pub avatar: usize,
+}
+
+impl BodyLimits {
+    /// Limits from application configuration
+    #[must_use]
+    pub fn from_config(config: &ApiConfig) -> Self {
+        Self {
+            default: config.max_request_body_bytes,
+            import: config.max_import_body_bytes,
+            avatar: config.max_avatar_body_bytes,
+        }
+    }
+
+    /// Limit for a request path
+    #[must_use]
+    pub fn for_path(&self, path: &str) -> usize {
+        match strip_version_prefix(path) {
+            Some(path) if path.starts_with("/admin/content/") || path.ends_with("/known-words") => {
+                self.import
+            }
+            Some("/users/me/avatar") => self.avatar,
+            _ => self.default,
+        }
+    }
+}
+
+/// Middleware enforcing the body limit and rejecting malformed JSON shapes.
+///
+/// Axum's own `DefaultBodyLimit` should be disabled alongside, otherwise it
+/// caps buffered bodies at 2 MiB whatever the limit here is.
+pub async fn body_limit_middleware(
+    State(limits): State<BodyLimits>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let limit = limits.for_path(request.uri().path());
+    if declared_length(request.headers()).is_some_and(|length| length > limit) {
+        return too_large(limit);
+    }
+
+    let (parts, body) = request.into_parts();
+    if !is_json(&parts.headers) {
+        let body = Body::new(Limited::new(body, limit));
+        return next.run(Request::from_parts(parts, body)).await;
+    }
+
+    let bytes = match axum::body::to_bytes(body, limit).await {
+        Ok(bytes) => bytes,
+        Err(e) if is_length_limit(&e) => return too_large(limit),
+        Err(_) => {
+            return ApiError::Validation("Failed to read request body".to_string()).into_response();
+        }
+    };
+    if let Err(message) = check_json_shape(&bytes) {
+        return ApiError::Validation(message).into_response();
+    }
+    next.run(Request::from_parts(parts, Body::from(bytes)))
+        .await
+}
+
+fn declared_length(headers: &HeaderMap) -> Option<usize> {
+    headers
+        .get(header::CONTENT_LENGTH)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// `application/json` and the `+json` types, the bodies `Json` accepts
+fn is_json(headers: &HeaderMap) -> bool {
+    let Some(content_type) = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return false;
+    };
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    essence == "application/json"
+        || (essence.starts_with("application/") && essence.ends_with("+json"))
+}
+
+fn is_length_limit(error: &axum::Error) -> bool {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(error);
+    while let Some(error) = source {
+        if error.is::<LengthLimitError>() {
+            return true;
+        }
+        source = error.source();
+    }
+    false
+}
+
+fn too_large(limit: usize) -> Response {
+    ApiError::PayloadTooLarge(format!("Request body must be at most {limit} bytes")).into_response()
+}
+
+/// Reject JSON nested deeper than [`MAX_JSON_DEPTH`] or repeating a key in an object.
+///
+/// Syntax errors are left to the handler's extractor, which reports them as it
+/// always has.
+fn check_json_shape(body: &[u8]) -> Result<(), String> {
+    let mut deserializer = serde_json::Deserializer::from_slice(body);
+    let shape = Shape { depth: 0 }.deserialize(&mut deserializer);
+    match shape {
+        Err(e) if e.is_data() => Err(format!("Invalid JSON body: {e}")),
+        _ => Ok(()),
+    }
+}
+
+/// Walks a JSON value without keeping it, tracking how deep it is
+#[derive(Clone, Copy)]
+struct Shape {
+    depth: usize,
+}
+
+impl Shape {
+    fn nested<E: de::Error>(&self) -> Result<Self, E> {
+        if self.depth >= MAX_JSON_DEPTH {
+            return Err(E::custom(format_args!(
+                "nested deeper than {MAX_JSON_DEPTH} levels"
+            )));
+        }
+        Ok(Self {
+            depth: self.depth + 1,
+        })
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for Shape {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Shape {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON value")
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_unit<E>(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let inner = self.nested()?;
+        while seq.next_element_seed(inner)?.is_some() {}
+        Ok(())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let inner = self.nested()?;
+        let mut keys = HashSet::new();
+        while let Some(key) = map.next_key::<String>()? {
+            if keys.contains(&key) {
+                return Err(de::Error::custom(format_args!("duplicate key \"{key}\"")));
+            }
+            keys.insert(key);
+            map.next_value_seed(inner)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, http::StatusCode, middleware, routing::post};
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        let limits = BodyLimits {
+            default: 64,
+            import: 256,
+            avatar: 128,
+        };
+        Router::new()
+            .route("/v1/echo", post(|body: String| async move { body }))
+            .route(
+                "/v1/admin/content/import",
+                post(|body: String| async move { body }),
+            )
+            .layer(middleware::from_fn_with_state(
+                limits,
+                body_limit_middleware,
+            ))
+    }
+
+    async fn call(uri: &str, content_type: &str, body: String) -> StatusCode {
+        app()
+            .oneshot(
+                axum::http::Request::post(uri)
+                    .header(header::CONTENT_TYPE, content_type)
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[test]
+    fn test_imports_and_avatars_get_their_own_limit() {
+        let limits = BodyLimits {
+            default: 1,
+            import: 2,
+            avatar: 3,
+        };
+        assert_eq!(limits.for_path("/v1/admin/content/ingest"), 2);
+        assert_eq!(limits.for_path("/v2/users/abc/known-words"), 2);
+        assert_eq!(limits.for_path("/v1/users/me/avatar"), 3);
+        assert_eq!(limits.for_path("/v1/sync/push"), 1);
+        assert_eq!(limits.for_path("/admin/content/import"), 1);
+    }
+
+    #[test]
+    fn test_json_shape() {
+        assert!(check_json_shape(br#"{"a": [1, {"b": null}], "c": "a"}"#).is_ok());
+        assert!(check_json_shape(br#"{"a": {"a": 1}}"#).is_ok());
+        assert!(check_json_shape(b"not json").is_ok());
+
+        let error = check_json_shape(br#"{"a": 1, "a": 2}"#).unwrap_err();
+        assert!(error.contains("duplicate key \"a\""), "{error}");
+
+        let fits = format!(
+            "{}{}",
+            "[".repeat(MAX_JSON_DEPTH),
+            "]".repeat(MAX_JSON_DEPTH)
+        );
+        assert!(check_json_shape(fits.as_bytes()).is_ok());
+        let deeper = format!("[{fits}]");
+        assert!(check_json_shape(deeper.as_bytes()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_oversized_bodies_are_refused() {
+        let small = "a".repeat(64);
+        let large = "a".repeat(65);
+        assert_eq!(call("/v1/echo", "text/plain", small).await, StatusCode::OK);
+        assert_eq!(
+            call("/v1/echo", "text/plain", large.clone()).await,
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+        assert_eq!(
+            call("/v1/admin/content/import", "text/plain", large).await,
+            StatusCode::OK
+        );
+
+        let json = format!("\"{}\"", "a".repeat(70));
+        assert_eq!(
+            call("/v1/echo", "application/json", json).await,
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_keys_are_bad_requests() {
+        let body = r#"{"a": 1, "a": 2}"#.to_string();
+        assert_eq!(
+            call("/v1/echo", "application/json; charset=utf-8", body.clone()).await,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(call("/v1/echo", "text/plain", body).await, StatusCode::OK);
+    }
+}
@@ -1,6 +1,8 @@
 use axum::http::{Method, header};
 use tower_http::cors::{AllowOrigin, CorsLayer};
 
+use crate::idempotency::{IDEMPOTENCY_KEY, IDEMPOTENT_REPLAYED};
+
 /// Creates a CORS layer with configured allowed origins and standard settings
 ///
 /// # Arguments
@@ -10,7 +12,7 @@ use tower_http::cors::{AllowOrigin, CorsLayer};
 /// A configured `CorsLayer` with:
 /// - Allowed origins parsed from the provided list
 /// - Standard HTTP methods (GET, POST, PUT, PATCH, DELETE, OPTIONS)
-/// - Standard headers (Content-Type, Accept)
+/// - Standard headers (Content-Type, Accept), `If-None-Match` and `Idempotency-Key`
 /// - Credentials enabled
 pub fn create_cors_layer(allowed_origins: Vec<String>) -> CorsLayer {
     let origins = allowed_origins
@@ -33,7 +35,9 @@ pub fn create_cors_layer(allowed_origins: Vec<String>) -> CorsLayer {
             header::ACCEPT,
             header::AUTHORIZATION,
             header::COOKIE,
+            header::IF_NONE_MATCH,
+            IDEMPOTENCY_KEY,
         ])
-        .expose_headers([header::SET_COOKIE])
+        .expose_headers([header::SET_COOKIE, header::ETAG, IDEMPOTENT_REPLAYED])
         .allow_credentials(true)
 }
@@ -0,0 +1,115 @@
+//! Connection draining during graceful shutdown.
+//!
+//! Once shutdown starts, the readiness probe fails immediately and every
+//! response carries `Connection: close` plus a `Retry-After` hint, so clients and
+//! load balancers move to another instance while in-flight requests finish.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::{HeaderValue, header},
+    middleware::Next,
+    response::Response,
+};
+use tokio::sync::watch;
+
+/// Seconds clients are asked to wait before retrying against another instance
+const RETRY_AFTER_SECS: &str = "5";
+
+/// Shared flag flipped when the server starts shutting down
+#[derive(Debug, Clone)]
+pub struct DrainState(Arc<watch::Sender<bool>>);
+
+impl Default for DrainState {
+    fn default() -> Self {
+        Self(Arc::new(watch::Sender::new(false)))
+    }
+}
+
+impl DrainState {
+    /// Mark the instance as draining
+    pub fn start_draining(&self) {
+        self.0.send_replace(true);
+    }
+
+    /// Returns true once shutdown has started
+    #[must_use]
+    pub fn is_draining(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Resolves once shutdown has started; long-lived connections use it to close early
+    pub async fn draining(&self) {
+        let mut rx = self.0.subscribe();
+        // The sender lives in `self`, so the channel cannot close while we wait
+        let _ = rx.wait_for(|draining| *draining).await;
+    }
+}
+
+/// Middleware announcing that the connection will be closed while draining
+pub async fn drain_middleware(
+    State(drain): State<DrainState>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let mut response = next.run(req).await;
+
+    if drain.is_draining() {
+        let headers = response.headers_mut();
+        headers.insert(header::CONNECTION, HeaderValue::from_static("close"));
+        headers.insert(
+            header::RETRY_AFTER,
+            HeaderValue::from_static(RETRY_AFTER_SECS),
+        );
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, middleware, routing::get};
+    use tower::ServiceExt;
+
+    async fn test_handler() -> &'static str {
+        "OK"
+    }
+
+    fn app(drain: DrainState) -> Router {
+        Router::new()
+            .route("/test", get(test_handler))
+            .layer(middleware::from_fn_with_state(drain, drain_middleware))
+    }
+
+    fn request() -> axum::http::Request<Body> {
+        axum::http::Request::builder()
+            .uri("/test")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_no_headers_before_shutdown() {
+        let response = app(DrainState::default()).oneshot(request()).await.unwrap();
+
+        assert!(response.headers().get(header::CONNECTION).is_none());
+        assert!(response.headers().get(header::RETRY_AFTER).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_headers_while_draining() {
+        let drain = DrainState::default();
+        let app = app(drain.clone());
+        drain.start_draining();
+
+        let response = app.oneshot(request()).await.unwrap();
+
+        assert_eq!(response.headers().get(header::CONNECTION).unwrap(), "close");
+        assert_eq!(
+            response.headers().get(header::RETRY_AFTER).unwrap(),
+            RETRY_AFTER_SECS
+        );
+    }
+}
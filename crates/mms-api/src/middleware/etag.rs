@@ -0,0 +1,102 @@
+//! Conditional GETs for listings clients poll.
+//!
+//! Successful GET responses get an `ETag` hashed from their body. A request
+//! whose `If-None-Match` lists that tag gets `304 Not Modified` without the
+//! body, so a client polling an unchanged deck or roadmap only pays for the
+//! headers. Only bodies of known length are hashed; streamed responses pass
+//! through untouched.
+
+use axum::{
+    body::{Body, HttpBody},
+    extract::Request,
+    http::{HeaderMap, HeaderValue, Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+
+/// Add an `ETag` to the response and answer matching `If-None-Match` with 304
+pub async fn etag_middleware(request: Request, next: Next) -> Response {
+    let conditional = matches!(*request.method(), Method::GET | Method::HEAD);
+    let if_none_match = request.headers().get(header::IF_NONE_MATCH).cloned();
+
+    let response = next.run(request).await;
+    if !conditional
+        || response.status() != StatusCode::OK
+        || response.headers().contains_key(header::ETAG)
+        || response.body().size_hint().exact().is_none()
+    {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, usize::MAX).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let tag = entity_tag(&bytes);
+
+    let not_modified = if_none_match.map(|value| matches_any(&value, &tag));
+    if let Some(not_modified) = not_modified {
+        crate::metrics::record_conditional_get(not_modified);
+    }
+    if not_modified == Some(true) {
+        parts.status = StatusCode::NOT_MODIFIED;
+        strip_content_headers(&mut parts.headers);
+        parts.headers.insert(header::ETAG, tag);
+        return Response::from_parts(parts, Body::empty());
+    }
+
+    parts.headers.insert(header::ETAG, tag);
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+/// Weak, so the tag stays valid whatever content coding the body is sent with
+fn entity_tag(body: &[u8]) -> HeaderValue {
+    let digest = Sha256::digest(body);
+    let tag = format!("W/\"{}\"", hex::encode(&digest[..16]));
+    HeaderValue::try_from(tag).expect("hex digest is a valid header value")
+}
+
+/// Whether `If-None-Match` lists `tag`, compared weakly as RFC 9110 requires
+fn matches_any(if_none_match: &HeaderValue, tag: &HeaderValue) -> bool {
+    let Ok(candidates) = if_none_match.to_str() else {
+        return false;
+    };
+    let tag = opaque(tag.to_str().unwrap_or_default());
+    candidates
+        .split(',')
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || opaque(candidate) == tag)
+}
+
+fn opaque(tag: &str) -> &str {
+    tag.strip_prefix("W/").unwrap_or(tag)
+}
+
+/// A 304 has no body, so the headers describing it go too
+fn strip_content_headers(headers: &mut HeaderMap) {
+    headers.remove(header::CONTENT_LENGTH);
+    headers.remove(header::CONTENT_TYPE);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn if_none_match_compares_weakly_and_accepts_lists() {
+        let tag = entity_tag(b"[]");
+        let value = tag.to_str().unwrap();
+        let strong = value.strip_prefix("W/").unwrap();
+
+        assert!(matches_any(&tag, &tag));
+        assert!(matches_any(&HeaderValue::from_str(strong).unwrap(), &tag));
+        assert!(matches_any(
+            &HeaderValue::from_str(&format!("\"other\", {value}")).unwrap(),
+            &tag
+        ));
+        assert!(matches_any(&HeaderValue::from_static("*"), &tag));
+        assert!(!matches_any(&HeaderValue::from_static("\"other\""), &tag));
+        assert_ne!(tag, entity_tag(b"[{}]"));
+    }
+}
@@ -0,0 +1,305 @@
+//! Load shedding middleware.
+//!
+//! Each route class gets a bounded number of in-flight requests plus a bounded
+//! wait queue. When both are full, or a queued request waits longer than the
+//! queue timeout, the request is rejected immediately with `503 Service Unavailable`
+//! instead of piling up and dragging latency down for everyone.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    extract::{Request, State},
+    http::{HeaderValue, StatusCode, header},
+    middleware::Next,
+    response::Response,
+};
+use metrics::{counter, gauge};
+use tokio::sync::Semaphore;
+
+use crate::{
+    config::ApiConfig,
+    error::{ErrorCode, ErrorResponse},
+    versioning::strip_version_prefix,
+};
+
+/// Seconds clients are asked to wait before retrying a shed request
+const RETRY_AFTER_SECS: &str = "1";
+
+/// Classes of routes with independent concurrency budgets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteClass {
+    /// Health probes and metrics; never shed
+    Exempt,
+    /// Credential endpoints doing bcrypt work; small budget so they cannot starve the rest
+    Auth,
+    /// Everything else
+    Standard,
+}
+
+impl RouteClass {
+    /// Classify a request path
+    #[must_use]
+    pub fn from_path(path: &str) -> Self {
+        if path == "/metrics" || path.starts_with("/health") {
+            return Self::Exempt;
+        }
+
+        // Same in every API version
+        const AUTH_PATHS: &[&str] = &[
+            "/users/register",
+            "/users/login",
+            "/users/reset-password",
+            "/users/me/password",
+            "/auth/refresh",
+        ];
+        if strip_version_prefix(path).is_some_and(|path| AUTH_PATHS.contains(&path)) {
+            return Self::Auth;
+        }
+
+        Self::Standard
+    }
+
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Exempt => "exempt",
+            Self::Auth => "auth",
+            Self::Standard => "standard",
+        }
+    }
+}
+
+/// Limits for a single route class
+#[derive(Debug, Clone, Copy)]
+pub struct ClassLimits {
+    /// Requests allowed to run concurrently
+    pub max_concurrency: usize,
+    /// Requests allowed to wait for a slot
+    pub max_queue: usize,
+}
+
+#[derive(Debug)]
+struct ClassBudget {
+    /// Permits for running plus queued requests; failing to get one means the queue is full
+    admission: Semaphore,
+    /// Permits for running requests
+    execution: Semaphore,
+}
+
+impl ClassBudget {
+    fn new(limits: ClassLimits) -> Self {
+        Self {
+            admission: Semaphore::new(limits.max_concurrency + limits.max_queue),
+            execution: Semaphore::new(limits.max_concurrency),
+        }
+    }
+}
+
+/// Shared load shedder state
+#[derive(Debug, Clone)]
+pub struct LoadShedder {
+    auth: Arc<ClassBudget>,
+    standard: Arc<ClassBudget>,
+    queue_timeout: Duration,
+}
+
+impl LoadShedder {
+    /// Create a load shedder with explicit limits
+    #[must_use]
+    pub fn new(auth: ClassLimits, standard: ClassLimits, queue_timeout: Duration) -> Self {
+        Self {
+            auth: Arc::new(ClassBudget::new(auth)),
+            standard: Arc::new(ClassBudget::new(standard)),
+            queue_timeout,
+        }
+    }
+
+    /// Create a load shedder from application configuration
+    #[must_use]
+    pub fn from_config(config: &ApiConfig) -> Self {
+        Self::new(
+            ClassLimits {
+                max_concurrency: config.load_shed_auth_max_concurrency,
+                max_queue: config.load_shed_auth_max_queue,
+            },
+            ClassLimits {
+                max_concurrency: config.load_shed_max_concurrency,
+                max_queue: config.load_shed_max_queue,
+            },
+            Duration::from_millis(config.load_shed_queue_timeout_ms),
+        )
+    }
+
+    fn budget(&self, class: RouteClass) -> Option<&ClassBudget> {
+        match class {
+            RouteClass::Exempt => None,
+            RouteClass::Auth => Some(&self.auth),
+            RouteClass::Standard => Some(&self.standard),
+        }
+    }
+}
+
+/// Middleware rejecting requests once their route class is saturated
+pub async fn load_shed_middleware(
+    State(shedder): State<LoadShedder>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let class = RouteClass::from_path(req.uri().path());
+    let Some(budget) = shedder.budget(class) else {
+        return next.run(req).await;
+    };
+
+    // Admission: fail fast when both the running slots and the queue are taken
+    let Ok(_admitted) = budget.admission.try_acquire() else {
+        return shed(class, "queue_full");
+    };
+
+    // Wait for an execution slot, but never longer than the queue timeout
+    gauge!("load_shed_queue_depth", "class" => class.as_str()).increment(1.0);
+    let permit = tokio::time::timeout(shedder.queue_timeout, budget.execution.acquire()).await;
+    gauge!("load_shed_queue_depth", "class" => class.as_str()).decrement(1.0);
+
+    match permit {
+        Ok(Ok(_running)) => next.run(req).await,
+        // The semaphore is never closed, so only the timeout can get us here
+        Ok(Err(_)) | Err(_) => shed(class, "queue_timeout"),
+    }
+}
+
+fn shed(class: RouteClass, reason: &'static str) -> Response {
+    counter!(
+        "http_requests_shed_total",
+        "class" => class.as_str(),
+        "reason" => reason
+    )
+    .increment(1);
+    tracing::warn!(
+        class = class.as_str(),
+        reason,
+        "Shedding request under load"
+    );
+
+    let mut response = ErrorResponse::new(
+        ErrorCode::Unavailable,
+        "The server is temporarily overloaded. Please retry shortly.",
+    )
+    .into_response_with(StatusCode::SERVICE_UNAVAILABLE);
+    response.headers_mut().insert(
+        header::RETRY_AFTER,
+        HeaderValue::from_static(RETRY_AFTER_SECS),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, middleware, routing::get};
+    use tower::ServiceExt;
+
+    async fn slow_handler() -> &'static str {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        "OK"
+    }
+
+    fn app(standard: ClassLimits, queue_timeout: Duration) -> Router {
+        let shedder = LoadShedder::new(
+            ClassLimits {
+                max_concurrency: 1,
+                max_queue: 0,
+            },
+            standard,
+            queue_timeout,
+        );
+        Router::new()
+            .route("/slow", get(slow_handler))
+            .route("/health", get(slow_handler))
+            .layer(middleware::from_fn_with_state(
+                shedder,
+                load_shed_middleware,
+            ))
+    }
+
+    async fn call(app: Router, uri: &str) -> StatusCode {
+        app.oneshot(
+            axum::http::Request::builder()
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .status()
+    }
+
+    #[test]
+    fn test_route_classification() {
+        assert_eq!(RouteClass::from_path("/health/ready"), RouteClass::Exempt);
+        assert_eq!(RouteClass::from_path("/metrics"), RouteClass::Exempt);
+        assert_eq!(RouteClass::from_path("/v1/users/login"), RouteClass::Auth);
+        assert_eq!(RouteClass::from_path("/v2/users/login"), RouteClass::Auth);
+        assert_eq!(RouteClass::from_path("/v1/roadmaps"), RouteClass::Standard);
+        assert_eq!(RouteClass::from_path("/users/login"), RouteClass::Standard);
+    }
+
+    #[tokio::test]
+    async fn test_sheds_when_queue_full() {
+        let app = app(
+            ClassLimits {
+                max_concurrency: 1,
+                max_queue: 0,
+            },
+            Duration::from_secs(1),
+        );
+
+        let (a, b) = tokio::join!(call(app.clone(), "/slow"), call(app, "/slow"));
+        let mut statuses = [a, b];
+        statuses.sort();
+        assert_eq!(statuses, [StatusCode::OK, StatusCode::SERVICE_UNAVAILABLE]);
+    }
+
+    #[tokio::test]
+    async fn test_queued_request_waits_for_slot() {
+        let app = app(
+            ClassLimits {
+                max_concurrency: 1,
+                max_queue: 1,
+            },
+            Duration::from_secs(1),
+        );
+
+        let (a, b) = tokio::join!(call(app.clone(), "/slow"), call(app, "/slow"));
+        assert_eq!(a, StatusCode::OK);
+        assert_eq!(b, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_sheds_on_queue_timeout() {
+        let app = app(
+            ClassLimits {
+                max_concurrency: 1,
+                max_queue: 1,
+            },
+            Duration::from_millis(20),
+        );
+
+        let (a, b) = tokio::join!(call(app.clone(), "/slow"), call(app, "/slow"));
+        let mut statuses = [a, b];
+        statuses.sort();
+        assert_eq!(statuses, [StatusCode::OK, StatusCode::SERVICE_UNAVAILABLE]);
+    }
+
+    #[tokio::test]
+    async fn test_exempt_routes_never_shed() {
+        let app = app(
+            ClassLimits {
+                max_concurrency: 0,
+                max_queue: 0,
+            },
+            Duration::from_millis(20),
+        );
+
+        assert_eq!(call(app, "/health").await, StatusCode::OK);
+    }
+}
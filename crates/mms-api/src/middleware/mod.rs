@@ -1,4 +1,8 @@
+pub mod body_limit;
 pub mod cors;
+pub mod drain;
+pub mod etag;
+pub mod load_shed;
 pub mod rate_limit;
 pub mod request_id;
 pub mod security_headers;
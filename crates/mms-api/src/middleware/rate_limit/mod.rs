@@ -0,0 +1,291 @@
+//! Per-route rate limits by client IP.
+//!
+//! Each [`make_rate_limit_layer!`](crate::make_rate_limit_layer) call is its
+//! own limiter. By default buckets live in the process, so every replica
+//! allows the full rate; with `RATE_LIMIT_BACKEND=redis` they are kept in
+//! Redis ([`redis`]) and enforced across replicas. If Redis stops answering,
+//! requests fall back to the in-process buckets rather than failing.
+
+pub mod redis;
+
+use axum::{
+    extract::{ConnectInfo, Request},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header::RETRY_AFTER},
+    middleware::Next,
+    response::Response,
+};
+use futures_util::future::BoxFuture;
+use serde::Deserialize;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::OnceLock;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tower::{Layer, Service, ServiceExt};
+pub use tower_governor::{GovernorLayer, governor::GovernorConfigBuilder};
+
+use self::redis::{Decision, RedisLimiter};
+use crate::error::{ErrorCode, ErrorResponse};
+
+/// Rate limits for different endpoint types
+pub const AUTH_RATE_PER_SECOND: u64 = 5;
+// Reduced from 10 to 5 to prevent rapid brute force attempts
+pub const AUTH_BURST_SIZE: u32 = 5;
+
+pub const SENSITIVE_RATE_PER_SECOND: u64 = 2;
+pub const SENSITIVE_BURST_SIZE: u32 = 3;
+
+pub const GENERAL_RATE_PER_SECOND: u64 = 10;
+pub const GENERAL_BURST_SIZE: u32 = 20;
+
+/// Where rate limit buckets are kept
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitBackend {
+    /// In this process; each replica enforces its own limits
+    #[default]
+    Memory,
+    /// In Redis, shared by every replica
+    Redis,
+}
+
+static REDIS: OnceLock<RedisLimiter> = OnceLock::new();
+
+/// Keep buckets in Redis from now on; call once at startup.
+///
+/// Returns false if a limiter was already installed.
+pub fn install_redis(limiter: RedisLimiter) -> bool {
+    REDIS.set(limiter).is_ok()
+}
+
+/// The Redis limiter, if buckets are kept in Redis
+pub fn installed_redis() -> Option<&'static RedisLimiter> {
+    REDIS.get()
+}
+
+/// How many requests a bucket allows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    /// Time to replenish one request
+    pub period: Duration,
+    /// Requests allowed at once
+    pub burst: u32,
+}
+
+impl Quota {
+    /// One request replenished every `seconds`, up to `burst` at once
+    #[must_use]
+    pub const fn per_seconds(seconds: u64, burst: u32) -> Self {
+        Self {
+            period: Duration::from_secs(seconds),
+            burst,
+        }
+    }
+}
+
+/// Helper macro to create a rate limiter with specific settings
+/// Keys on the peer IP from `ConnectInfo`; the call site names the limiter's
+/// Redis buckets, so each call keeps its own limits across replicas
+#[macro_export]
+macro_rules! make_rate_limit_layer {
+    ($per_second:expr, $burst:expr) => {{
+        let config = $crate::middleware::rate_limit::GovernorConfigBuilder::default()
+            .per_second($per_second)
+            .burst_size($burst)
+            .use_headers()
+            .finish()
+            .expect("Failed to build rate limiter configuration");
+        $crate::middleware::rate_limit::RateLimitLayer::new(
+            concat!(module_path!(), ":", line!()),
+            $crate::middleware::rate_limit::Quota::per_seconds($per_second, $burst),
+            $crate::middleware::rate_limit::GovernorLayer::new(config),
+        )
+    }};
+}
+
+/// Rate limits requests in Redis when it is installed, with `local` otherwise
+#[derive(Clone)]
+pub struct RateLimitLayer<L> {
+    scope: &'static str,
+    quota: Quota,
+    local: L,
+}
+
+impl<L> RateLimitLayer<L> {
+    /// `scope` names the limiter's buckets and must be unique per limiter
+    pub const fn new(scope: &'static str, quota: Quota, local: L) -> Self {
+        Self {
+            scope,
+            quota,
+            local,
+        }
+    }
+}
+
+impl<L, S> Layer<S> for RateLimitLayer<L>
+where
+    L: Layer<S>,
+    S: Clone,
+{
+    type Service = RateLimit<L::Service, S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimit {
+            scope: self.scope,
+            quota: self.quota,
+            local: self.local.layer(inner.clone()),
+            inner,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimit<L, S> {
+    scope: &'static str,
+    quota: Quota,
+    /// `inner` behind the in-process limiter
+    local: L,
+    inner: S,
+}
+
+impl<L, S> Service<Request> for RateLimit<L, S>
+where
+    L: Service<Request, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    L::Future: Send,
+    S: Service<Request, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Response, Infallible>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // Each call drives a clone of the services to readiness
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let local = self.local.clone();
+        let peer = request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+        let quota = self.quota;
+        let (Some(redis), Some(peer)) = (REDIS.get(), peer) else {
+            return Box::pin(limit_locally(local, request, quota));
+        };
+
+        let inner = self.inner.clone();
+        let key = format!("ratelimit:{}:{peer}", self.scope);
+        Box::pin(async move {
+            match redis.check(&key, quota).await {
+                Ok(Decision::Allowed { remaining, reset }) => {
+                    let mut response = inner.oneshot(request).await?;
+                    insert_headers(response.headers_mut(), quota, remaining, reset);
+                    Ok(response)
+                }
+                Ok(Decision::Limited { wait, reset }) => Ok(too_many_requests(quota, wait, reset)),
+                Err(e) => {
+                    tracing::warn!(error = %e, "Redis rate limiter unavailable, limiting locally");
+                    crate::metrics::record_rate_limit_fallback();
+                    limit_locally(local, request, quota).await
+                }
+            }
+        })
+    }
+}
+
+const X_RATELIMIT_LIMIT: HeaderName = HeaderName::from_static("x-ratelimit-limit");
+const X_RATELIMIT_REMAINING: HeaderName = HeaderName::from_static("x-ratelimit-remaining");
+const X_RATELIMIT_AFTER: HeaderName = HeaderName::from_static("x-ratelimit-after");
+const X_RATELIMIT_RESET: HeaderName = HeaderName::from_static("x-ratelimit-reset");
+
+/// Whole seconds, rounded up so clients never come back too early
+fn ceil_secs(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
+/// The headers every rate limited route sends: the burst size, requests left
+/// and seconds until the whole burst is available again
+fn insert_headers(headers: &mut HeaderMap, quota: Quota, remaining: u32, reset: Duration) {
+    headers.insert(X_RATELIMIT_LIMIT, HeaderValue::from(quota.burst));
+    headers.insert(X_RATELIMIT_REMAINING, HeaderValue::from(remaining));
+    headers.insert(X_RATELIMIT_RESET, HeaderValue::from(ceil_secs(reset)));
+}
+
+/// The response the in-process limiter gives when a bucket is empty
+fn too_many_requests(quota: Quota, wait: Duration, reset: Duration) -> Response {
+    let wait_secs = ceil_secs(wait);
+    let mut response = ErrorResponse::new(
+        ErrorCode::RateLimited,
+        format!("Too Many Requests! Wait for {wait_secs}s"),
+    )
+    .into_response_with(StatusCode::TOO_MANY_REQUESTS);
+    let headers = response.headers_mut();
+    insert_headers(headers, quota, 0, reset);
+    headers.insert(X_RATELIMIT_AFTER, HeaderValue::from(wait_secs));
+    headers.insert(RETRY_AFTER, HeaderValue::from(wait_secs));
+    response
+}
+
+/// Run `request` through the in-process limiter, which reports the limit,
+/// what remains and, once limited, the wait; the reset is worked out from those
+async fn limit_locally<L>(local: L, request: Request, quota: Quota) -> Result<Response, Infallible>
+where
+    L: Service<Request, Response = Response, Error = Infallible>,
+{
+    let mut response = local.oneshot(request).await?;
+    let headers = response.headers_mut();
+    let header = |name: &HeaderName| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u32>().ok())
+    };
+    // Methods the limiter skips carry no limit headers
+    let Some(remaining) = header(&X_RATELIMIT_REMAINING) else {
+        return Ok(response);
+    };
+    let reset = match header(&X_RATELIMIT_AFTER) {
+        Some(wait) => Duration::from_secs(wait.into()) + quota.period * (quota.burst - 1),
+        None => quota.period * quota.burst.saturating_sub(remaining),
+    };
+    headers.insert(X_RATELIMIT_RESET, HeaderValue::from(ceil_secs(reset)));
+    Ok(response)
+}
+
+/// Timing-safe middleware to prevent timing attacks on sensitive endpoints.
+/// Pads every response to a minimum fixed duration so that the total time
+/// is constant regardless of how fast the handler completes.
+const TIMING_SAFE_MIN_DURATION: Duration = Duration::from_millis(250);
+
+pub async fn timing_safe_middleware(req: Request, next: Next) -> Response {
+    let start = Instant::now();
+    let response = next.run(req).await;
+
+    let elapsed = start.elapsed();
+    if elapsed < TIMING_SAFE_MIN_DURATION {
+        tokio::time::sleep(TIMING_SAFE_MIN_DURATION - elapsed).await;
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limited_response_rounds_the_wait_up() {
+        let response = too_many_requests(
+            Quota::per_seconds(GENERAL_RATE_PER_SECOND, GENERAL_BURST_SIZE),
+            Duration::from_millis(1_200),
+            Duration::from_millis(191_200),
+        );
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()["retry-after"], "2");
+        assert_eq!(response.headers()["x-ratelimit-limit"], "20");
+        assert_eq!(response.headers()["x-ratelimit-remaining"], "0");
+        assert_eq!(response.headers()["x-ratelimit-reset"], "192");
+    }
+}
@@ -0,0 +1,102 @@
+//! Rate limits shared by every replica, kept in Redis.
+//!
+//! Each bucket is one key holding its theoretical arrival time (GCRA, the
+//! algorithm the in-process limiter uses), updated by a Lua script so checks
+//! from different replicas never race. Time comes from the Redis server, so
+//! replica clocks do not need to agree.
+
+use std::time::Duration;
+
+use redis::{
+    Client, RedisError, Script,
+    aio::{ConnectionManager, ConnectionManagerConfig},
+};
+
+use super::Quota;
+
+/// Longest wait for Redis to answer before the local limiter is used instead
+const RESPONSE_TIMEOUT: Duration = Duration::from_millis(250);
+
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// KEYS[1]: bucket; ARGV[1]: ms to replenish one request; ARGV[2]: burst size.
+/// Returns {allowed, ms until the next request is allowed, remaining burst,
+/// ms until the whole burst is available again}.
+const GCRA_SCRIPT: &str = r"
+local now_parts = redis.call('TIME')
+local now = tonumber(now_parts[1]) * 1000 + math.floor(tonumber(now_parts[2]) / 1000)
+local period = tonumber(ARGV[1])
+local burst = tonumber(ARGV[2])
+
+local tat = tonumber(redis.call('GET', KEYS[1]) or now)
+if tat < now then
+    tat = now
+end
+
+local allow_at = tat - period * (burst - 1)
+if now < allow_at then
+    return {0, allow_at - now, 0, tat - now}
+end
+
+local new_tat = tat + period
+redis.call('SET', KEYS[1], new_tat, 'PX', new_tat - now)
+return {1, 0, math.floor((now - (new_tat - period * burst)) / period), new_tat - now}
+";
+
+/// Outcome of taking a request from a bucket
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Allowed { remaining: u32, reset: Duration },
+    Limited { wait: Duration, reset: Duration },
+}
+
+/// Connection to the Redis instance holding the buckets
+#[derive(Clone)]
+pub struct RedisLimiter {
+    connection: ConnectionManager,
+    script: Script,
+}
+
+impl RedisLimiter {
+    /// Connect to `url`, e.g. `redis://redis:6379`; fails if Redis is unreachable
+    pub async fn connect(url: &str) -> Result<Self, RedisError> {
+        let client = Client::open(url)?;
+        let config = ConnectionManagerConfig::new()
+            .set_connection_timeout(CONNECTION_TIMEOUT)
+            .set_response_timeout(RESPONSE_TIMEOUT);
+        let connection = ConnectionManager::new_with_config(client, config).await?;
+
+        Ok(Self {
+            connection,
+            script: Script::new(GCRA_SCRIPT),
+        })
+    }
+
+    /// Check that Redis answers, within the response timeout
+    pub async fn ping(&self) -> Result<(), RedisError> {
+        let mut connection = self.connection.clone();
+        redis::cmd("PING").query_async(&mut connection).await
+    }
+
+    /// Take a request from the bucket at `key`
+    pub async fn check(&self, key: &str, quota: Quota) -> Result<Decision, RedisError> {
+        let mut connection = self.connection.clone();
+        let (allowed, wait_ms, remaining, reset_ms): (i64, u64, u32, u64) = self
+            .script
+            .key(key)
+            .arg(quota.period.as_millis() as u64)
+            .arg(quota.burst)
+            .invoke_async(&mut connection)
+            .await?;
+
+        let reset = Duration::from_millis(reset_ms);
+        Ok(if allowed == 1 {
+            Decision::Allowed { remaining, reset }
+        } else {
+            Decision::Limited {
+                wait: Duration::from_millis(wait_ms),
+                reset,
+            }
+        })
+    }
+}
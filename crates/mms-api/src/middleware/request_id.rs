@@ -10,6 +10,17 @@ use uuid::Uuid;
 /// Header name for the request ID
 pub const REQUEST_ID_HEADER: &str = "X-Request-ID";
 
+tokio::task_local! {
+    /// ID of the request the current task is serving
+    static CURRENT: RequestId;
+}
+
+/// ID of the request being served, for code that has no access to the request
+/// itself, such as error responses
+pub fn current() -> Option<String> {
+    CURRENT.try_with(|id| id.0.clone()).ok()
+}
+
 /// Middleware to add request ID to each request
 ///
 /// If the client provides an X-Request-ID header, it will be preserved.
@@ -25,16 +36,22 @@ pub async fn request_id_middleware(mut req: Request, next: Next) -> Response {
     // Add request ID to request extensions so it can be accessed by handlers
     req.extensions_mut().insert(RequestId(request_id.clone()));
 
-    // Create a tracing span with the request ID
+    // Create a tracing span with the request ID.
+    // The query string is left out: calendar feed and email links carry tokens in it.
     let span = tracing::info_span!(
         "request",
         request_id = %request_id,
         method = %req.method(),
-        uri = %req.uri(),
+        uri = %req.uri().path(),
     );
 
     // Process request within the span (use Instrument, not span.enter(), in async context)
-    let mut response = next.run(req).instrument(span).await;
+    let mut response = CURRENT
+        .scope(
+            RequestId(request_id.clone()),
+            next.run(req).instrument(span),
+        )
+        .await;
     if let Ok(header_value) = request_id.parse() {
         response
             .headers_mut()
@@ -71,4 +88,35 @@ mod tests {
         assert_eq!(id.to_string(), "test-123");
         assert_eq!(id.as_str(), "test-123");
     }
+
+    #[tokio::test]
+    async fn test_error_responses_quote_the_request_id() {
+        use axum::{Router, body::Body, middleware, routing::get};
+        use tower::ServiceExt;
+
+        use crate::error::ApiError;
+
+        let app = Router::new()
+            .route(
+                "/missing",
+                get(|| async { ApiError::NotFound("Deck not found".to_string()) }),
+            )
+            .layer(middleware::from_fn(request_id_middleware));
+        let response = app
+            .oneshot(
+                Request::get("/missing")
+                    .header(REQUEST_ID_HEADER, "req-42")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(json["request_id"], "req-42");
+        assert!(current().is_none());
+    }
 }
@@ -0,0 +1,11 @@
+//! In-app notifications.
+//!
+//! Subsystems create notifications with [`notify`], which stores them and pushes
+//! a [`crate::live::LiveEvent::NotificationCreated`] to the user's open
+//! connections. The routes let the user page through, mark read and delete them.
+
+pub mod notify;
+pub mod routes;
+
+pub use notify::{NotificationKind, notify};
+pub use routes::routes;
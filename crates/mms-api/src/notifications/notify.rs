@@ -0,0 +1,61 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use mms_db::{models::Notification, repositories::notification as notification_repo};
+
+use crate::live::{EventBus, LiveEvent};
+
+/// What a notification is about; stored as its `snake_case` name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationKind {
+    StreakReminder,
+    DeckShared,
+    AchievementUnlocked,
+    PlanBehind,
+    ContentWarning,
+}
+
+impl NotificationKind {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::StreakReminder => "streak_reminder",
+            Self::DeckShared => "deck_shared",
+            Self::AchievementUnlocked => "achievement_unlocked",
+            Self::PlanBehind => "plan_behind",
+            Self::ContentWarning => "content_warning",
+        }
+    }
+}
+
+impl From<&Notification> for LiveEvent {
+    fn from(notification: &Notification) -> Self {
+        Self::NotificationCreated {
+            notification_id: notification.id,
+            kind: notification.kind.clone(),
+            title: notification.title.clone(),
+            body: notification.body.clone(),
+            link: notification.link.clone(),
+        }
+    }
+}
+
+/// Store a notification for a user and push it to their open connections.
+///
+/// Producers writing in a transaction should insert with
+/// `notification_repo::create` and publish the event after committing instead.
+pub async fn notify(
+    pool: &PgPool,
+    events: &EventBus,
+    user_id: Uuid,
+    kind: NotificationKind,
+    title: &str,
+    body: &str,
+    link: Option<&str>,
+) -> Result<Notification, sqlx::Error> {
+    let notification =
+        notification_repo::create(pool, user_id, kind.as_str(), title, body, link).await?;
+    events.publish(user_id, LiveEvent::from(&notification));
+
+    Ok(notification)
+}
@@ -0,0 +1,184 @@
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    routing::{delete, get, post},
+};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    ApiState,
+    auth::AuthUser,
+    error::{ApiError, ErrorResponse},
+};
+
+use mms_db::models::Notification;
+use mms_db::repositories::notification as notification_repo;
+
+const DEFAULT_PAGE_LIMIT: i64 = 20;
+const MAX_PAGE_LIMIT: i64 = 100;
+
+/// Create the notification routes
+pub fn routes() -> Router<ApiState> {
+    Router::new()
+        .route("/notifications", get(list_notifications))
+        .route("/notifications/read-all", post(mark_all_read))
+        .route("/notifications/{notification_id}/read", post(mark_read))
+        .route(
+            "/notifications/{notification_id}",
+            delete(delete_notification),
+        )
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ListQuery {
+    /// Only notifications not yet read (default false)
+    #[serde(default)]
+    unread_only: bool,
+    /// Continue after this notification, the `next_before` of the previous page
+    #[serde(default)]
+    before: Option<Uuid>,
+    /// Page size, 1 to 100 (default 20)
+    #[serde(default)]
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct NotificationPage {
+    /// Newest first
+    notifications: Vec<Notification>,
+    /// Across all pages
+    unread_count: i64,
+    /// Pass as `before` to fetch the next page; absent on the last page
+    next_before: Option<Uuid>,
+}
+
+/// The signed-in user's notifications, newest first
+#[utoipa::path(
+    get,
+    path = "/v1/notifications",
+    tag = "notifications",
+    security(("cookie_auth" = [])),
+    params(ListQuery),
+    responses(
+        (status = 200, description = "A page of notifications", body = NotificationPage),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+async fn list_notifications(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Query(query): Query<ListQuery>,
+) -> Result<Json<NotificationPage>, ApiError> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_LIMIT)
+        .clamp(1, MAX_PAGE_LIMIT);
+
+    // One extra row tells whether another page follows
+    let mut notifications = notification_repo::list_for_user(
+        &state.pool,
+        auth_user.user_id,
+        query.unread_only,
+        query.before,
+        limit + 1,
+    )
+    .await?;
+    let has_more = notifications.len() as i64 > limit;
+    notifications.truncate(limit as usize);
+
+    let unread_count = notification_repo::count_unread(&state.pool, auth_user.user_id).await?;
+
+    Ok(Json(NotificationPage {
+        next_before: has_more
+            .then(|| notifications.last().map(|n| n.id))
+            .flatten(),
+        notifications,
+        unread_count,
+    }))
+}
+
+/// Mark a notification read; marking it again keeps the first read time
+#[utoipa::path(
+    post,
+    path = "/v1/notifications/{notification_id}/read",
+    tag = "notifications",
+    security(("cookie_auth" = [])),
+    params(("notification_id" = Uuid, Path)),
+    responses(
+        (status = 200, description = "Notification marked read", body = Notification),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Notification not found", body = ErrorResponse),
+    )
+)]
+async fn mark_read(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(notification_id): Path<Uuid>,
+) -> Result<Json<Notification>, ApiError> {
+    let notification =
+        notification_repo::mark_read(&state.pool, auth_user.user_id, notification_id)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Notification not found".to_string()))?;
+
+    Ok(Json(notification))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct MarkAllReadResponse {
+    /// Notifications that were unread
+    updated: u64,
+}
+
+/// Mark every notification read
+#[utoipa::path(
+    post,
+    path = "/v1/notifications/read-all",
+    tag = "notifications",
+    security(("cookie_auth" = [])),
+    responses(
+        (status = 200, description = "All notifications marked read", body = MarkAllReadResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+async fn mark_all_read(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+) -> Result<Json<MarkAllReadResponse>, ApiError> {
+    let updated = notification_repo::mark_all_read(&state.pool, auth_user.user_id).await?;
+    Ok(Json(MarkAllReadResponse { updated }))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct DeleteNotificationResponse {
+    message: String,
+}
+
+/// Remove a notification from the list
+#[utoipa::path(
+    delete,
+    path = "/v1/notifications/{notification_id}",
+    tag = "notifications",
+    security(("cookie_auth" = [])),
+    params(("notification_id" = Uuid, Path)),
+    responses(
+        (status = 200, description = "Notification deleted", body = DeleteNotificationResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Notification not found", body = ErrorResponse),
+    )
+)]
+async fn delete_notification(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(notification_id): Path<Uuid>,
+) -> Result<Json<DeleteNotificationResponse>, ApiError> {
+    if !notification_repo::delete(&state.pool, auth_user.user_id, notification_id).await? {
+        return Err(ApiError::NotFound("Notification not found".to_string()));
+    }
+
+    Ok(Json(DeleteNotificationResponse {
+        message: "Notification deleted".to_string(),
+    }))
+}
@@ -0,0 +1,339 @@
+//! OpenAPI document generated from the route handlers.
+//!
+//! Each handler carries a `#[utoipa::path]` annotation next to its code and is
+//! listed in [`ApiDoc`]; request and response types derive `ToSchema`. The
+//! document is served at `/v1/openapi.json`. Outside production a Swagger UI
+//! for it is mounted at [`SWAGGER_UI_PATH`].
+
+use axum::{Json, Router, routing::get};
+use utoipa::{
+    Modify, OpenApi,
+    openapi::security::{ApiKey, ApiKeyValue, Http, HttpAuthScheme, SecurityScheme},
+};
+use utoipa_swagger_ui::{Config, SwaggerUi};
+
+use crate::{
+    ApiState, achievements, admin, auth, calendar, client_errors, deck, email_preferences,
+    error::ErrorResponse, flashcards, goals, graphql, groups, home, known_words, leaderboards,
+    live, mailer, media, notifications, plans, practice, preferences, profile, public_api,
+    reminders, reports, roadmap, router, stats, sync, user, vocabulary, widgets, xp,
+};
+
+/// Where the document is served
+pub const SPEC_PATH: &str = "/v1/openapi.json";
+
+/// Where the Swagger UI is mounted outside production
+pub const SWAGGER_UI_PATH: &str = "/docs";
+
+/// Security scheme for the `auth_token` cookie set at login; handlers refer to it by name
+const COOKIE_AUTH: &str = "cookie_auth";
+
+/// Security scheme for `Authorization: Bearer <ADMIN_API_TOKEN>`
+const ADMIN_TOKEN_AUTH: &str = "admin_token";
+
+/// Security scheme for the `X-API-Key` header of the public API
+const API_KEY_AUTH: &str = "api_key";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Matcha Time API",
+        description = "Spaced repetition vocabulary practice organised into roadmaps of decks.\n\n\
+            Every route is served under both `/v1` and `/v2`; only the routes listed with a `/v2` \
+            path behave differently. v1 routes that return tokens in the body are deprecated and \
+            send `Deprecation` and `Link` headers pointing at their v2 replacement."
+    ),
+    paths(
+        router::health,
+        router::readiness,
+        router::jwks,
+        auth::routes::auth_me,
+        auth::routes::refresh_token,
+        auth::routes::refresh_token_v2,
+        auth::routes::logout,
+        auth::routes::update_language_preferences,
+        auth::google::routes::google_auth,
+        auth::google::routes::auth_callback,
+        auth::google::routes::link_google,
+        auth::google::routes::unlink_google,
+        calendar::routes::create_calendar_token,
+        calendar::routes::revoke_calendar_token,
+        calendar::routes::calendar_feed,
+        widgets::routes::create_widget_token,
+        widgets::routes::revoke_widget_token,
+        widgets::routes::streak_badge,
+        widgets::routes::cards_learned_badge,
+        widgets::routes::widget_stats,
+        groups::routes::list_groups,
+        groups::routes::create_group,
+        groups::routes::join_group,
+        groups::routes::get_group,
+        groups::routes::invite_by_email,
+        groups::routes::update_member_role,
+        groups::routes::remove_member,
+        groups::routes::assign_deck,
+        groups::routes::unassign_deck,
+        groups::routes::assign_roadmap,
+        groups::routes::unassign_roadmap,
+        groups::routes::get_dashboard,
+        email_preferences::routes::get_my_email_preferences,
+        email_preferences::routes::update_my_email_preferences,
+        email_preferences::routes::get_email_preferences,
+        email_preferences::routes::update_email_preferences,
+        home::routes::get_home,
+        achievements::routes::get_achievements,
+        known_words::routes::import_known_words,
+        vocabulary::routes::search_vocabulary,
+        leaderboards::routes::get_leaderboard,
+        leaderboards::routes::list_friends,
+        leaderboards::routes::add_friend,
+        leaderboards::routes::remove_friend,
+        xp::routes::get_xp_history,
+        plans::routes::get_plan,
+        plans::routes::set_plan,
+        plans::routes::delete_plan,
+        stats::routes::get_intervals,
+        stats::routes::get_detailed_stats,
+        stats::routes::get_forecast,
+        stats::routes::get_due_counts,
+        stats::routes::get_activity,
+        goals::routes::get_daily_goal,
+        goals::routes::update_daily_goal,
+        graphql::routes::execute,
+        graphql::routes::get_schema,
+        reminders::routes::get_reminder_settings,
+        reminders::routes::update_reminder_settings,
+        preferences::routes::get_preferences,
+        preferences::routes::update_preferences,
+        live::routes::live_updates,
+        live::routes::notification_stream,
+        live::routes::list_event_samples,
+        live::routes::get_event_sample,
+        notifications::routes::list_notifications,
+        notifications::routes::mark_read,
+        notifications::routes::mark_all_read,
+        notifications::routes::delete_notification,
+        user::routes::create_user,
+        user::routes::login_user,
+        user::routes::login_user_v2,
+        user::routes::restore_account,
+        user::routes::request_password_reset,
+        user::routes::reset_password,
+        user::routes::verify_email,
+        user::routes::resend_verification_email,
+        user::routes::get_user_dashboard,
+        user::routes::export_user_data,
+        user::routes::request_export,
+        user::routes::get_export,
+        user::routes::download_export,
+        user::routes::set_password,
+        user::routes::change_password,
+        user::routes::get_sign_in_methods,
+        user::routes::change_username,
+        user::routes::check_username,
+        user::routes::upload_avatar,
+        user::routes::delete_avatar,
+        user::routes::change_timezone,
+        user::routes::get_privacy,
+        user::routes::update_privacy,
+        user::routes::delete_user,
+        roadmap::routes::list_roadmaps,
+        roadmap::routes::get_roadmaps_by_language,
+        roadmap::routes::get_roadmap_nodes,
+        roadmap::routes::get_roadmap_with_progress,
+        deck::routes::list_public_decks,
+        deck::routes::get_practice_session,
+        deck::routes::export_deck,
+        deck::analytics::get_deck_analytics,
+        deck::modes::get_mode_progress,
+        deck::reviews::get_rating,
+        deck::reviews::rate_deck,
+        deck::reviews::unrate_deck,
+        deck::reviews::list_comments,
+        deck::reviews::create_comment,
+        deck::reviews::update_comment,
+        deck::reviews::delete_comment,
+        deck::reviews::hide_comment,
+        deck::reviews::unhide_comment,
+        deck::settings::get_deck_settings,
+        deck::settings::update_deck_settings,
+        deck::simulate::simulate_deck,
+        practice::routes::submit_review,
+        practice::pronunciation::submit_pronunciation,
+        practice::reschedule::reschedule,
+        practice::sessions::create_session,
+        practice::sessions::get_session,
+        practice::sessions::next_card,
+        practice::sessions::submit_answer,
+        profile::routes::list_profiles,
+        profile::routes::create_profile,
+        profile::routes::update_profile,
+        profile::routes::delete_profile,
+        profile::routes::get_due_cards,
+        public_api::routes::list_roadmaps,
+        public_api::routes::list_decks,
+        public_api::keys::create_api_key,
+        public_api::keys::list_api_keys,
+        public_api::keys::revoke_api_key,
+        public_api::keys::get_usage,
+        sync::routes::get_changes,
+        sync::routes::push_changes,
+        sync::routes::upload_reviews,
+        admin::routes::get_index_report,
+        admin::routes::ingest_content,
+        admin::routes::preview_import,
+        admin::routes::import_cards,
+        admin::routes::seed_content,
+        admin::routes::set_roadmap_slug,
+        admin::routes::set_deck_slug,
+        admin::routes::move_cards,
+        admin::routes::merge_flashcard,
+        admin::routes::get_user,
+        admin::routes::clear_email_suppression,
+        admin::routes::restore_user,
+        admin::routes::recompute_deck_progress,
+        admin::routes::update_api_key,
+        mailer::webhooks::receive_email_events,
+        media::routes::get_media,
+        client_errors::routes::report_client_error,
+        admin::routes::list_client_errors,
+        admin::routes::list_jobs,
+        admin::routes::retry_job,
+        admin::routes::list_schedules,
+        admin::routes::run_scheduled_job,
+        flashcards::routes::update_flashcard,
+        flashcards::routes::get_history,
+        flashcards::routes::revert_edit,
+        reports::routes::create_report,
+        reports::routes::list_my_reports,
+        reports::routes::list_reports,
+        reports::routes::act_on_report,
+    ),
+    components(schemas(ErrorResponse)),
+    modifiers(&SecuritySchemes),
+    tags(
+        (name = "health", description = "Liveness, readiness and token verification keys"),
+        (name = "auth", description = "Sessions, token refresh and Google sign-in"),
+        (name = "users", description = "Accounts, passwords and personal data"),
+        (name = "roadmaps", description = "Learning paths and progress through them"),
+        (name = "decks", description = "The public deck catalogue, ratings, comments, practice sessions and deck exports"),
+        (name = "flashcards", description = "Card edits and their history"),
+        (name = "practice", description = "Review submission, practice sessions and scheduling"),
+        (name = "profiles", description = "Language pairs a user studies, each with its own settings and due queue"),
+        (name = "graphql", description = "Dashboard and progress data in one read-only GraphQL query"),
+        (name = "public", description = "Read-only catalogue for integrators, authorised by an API key with a daily quota"),
+        (name = "media", description = "Uploaded images such as avatars"),
+        (name = "live", description = "Real-time events over a WebSocket or Server-Sent Events"),
+        (name = "leaderboards", description = "XP rankings and the friends they can be limited to"),
+        (name = "groups", description = "Study groups where teachers assign decks and follow their learners' progress"),
+        (name = "widgets", description = "Embeddable badges of a user's progress, authorised by a token in the URL"),
+        (name = "notifications", description = "In-app notifications and their read state"),
+        (name = "sync", description = "Delta sync for offline clients"),
+        (name = "reports", description = "Reporting public content to the moderators"),
+        (name = "admin", description = "Operator reports, content ingestion, moderation and user support"),
+        (name = "client-errors", description = "Crash reports from the web and mobile apps"),
+        (name = "webhooks", description = "Delivery events from the email providers"),
+    )
+)]
+pub struct ApiDoc;
+
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            COOKIE_AUTH,
+            SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::new("auth_token"))),
+        );
+        components.add_security_scheme(
+            ADMIN_TOKEN_AUTH,
+            SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+        );
+        components.add_security_scheme(
+            API_KEY_AUTH,
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-API-Key"))),
+        );
+    }
+}
+
+/// Route serving the OpenAPI document
+pub fn routes() -> Router<ApiState> {
+    Router::new().route("/openapi.json", get(openapi_json))
+}
+
+/// Swagger UI reading the document from [`SPEC_PATH`]; only mount outside production
+pub fn swagger_ui<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    SwaggerUi::new(SWAGGER_UI_PATH)
+        .config(Config::from(SPEC_PATH))
+        .into()
+}
+
+async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_builds_and_serializes() {
+        let json = ApiDoc::openapi()
+            .to_pretty_json()
+            .expect("OpenAPI document should serialize");
+        let spec: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(spec["info"]["title"], "Matcha Time API");
+        assert!(spec["components"]["securitySchemes"][COOKIE_AUTH].is_object());
+        assert!(spec["components"]["schemas"]["ErrorResponse"].is_object());
+    }
+
+    #[test]
+    fn test_every_operation_is_tagged_and_documents_a_response() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let paths = spec["paths"].as_object().unwrap();
+        assert!(paths.contains_key("/v1/practice/{flashcard_id}/review"));
+        assert!(paths.contains_key("/v1/admin/content/ingest"));
+
+        for (path, item) in paths {
+            for (method, operation) in item.as_object().unwrap() {
+                assert!(
+                    operation["tags"].as_array().is_some_and(|t| !t.is_empty()),
+                    "{method} {path} has no tag"
+                );
+                assert!(
+                    operation["responses"]
+                        .as_object()
+                        .is_some_and(|r| !r.is_empty()),
+                    "{method} {path} documents no responses"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_schema_references_resolve() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let schemas = spec["components"]["schemas"].as_object().unwrap();
+
+        let mut stack = vec![&spec["paths"]];
+        stack.extend(schemas.values());
+        while let Some(value) = stack.pop() {
+            match value {
+                serde_json::Value::Object(map) => {
+                    if let Some(reference) = map.get("$ref").and_then(|r| r.as_str()) {
+                        let name = reference.trim_start_matches("#/components/schemas/");
+                        assert!(schemas.contains_key(name), "Unresolved schema {reference}");
+                    }
+                    stack.extend(map.values());
+                }
+                serde_json::Value::Array(items) => stack.extend(items),
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,205 @@
+//! Study plans: finish a roadmap by a target date.
+//!
+//! From the roadmap's cards the user has not started and the days left, the
+//! plan derives how many new cards a day they need, and with the SRS schedule
+//! ([`mms_srs::reviews_in_first_days`]) the daily reviews that pace brings.
+//! When the plan is set, the pace it needs is stored as a baseline. Needing
+//! more than [`BEHIND_MARGIN_PERCENT`] over the baseline later means the user fell
+//! behind; the daily [`notify_behind`] job then notifies them, at most once
+//! a week.
+
+pub mod routes;
+
+use chrono::NaiveDate;
+use serde::Serialize;
+use sqlx::PgPool;
+use utoipa::ToSchema;
+
+use mms_db::{models::RoadmapPlanCounts, repositories::plan as plan_repo};
+
+use crate::{
+    live::EventBus,
+    notifications::{NotificationKind, notify},
+};
+
+pub use routes::routes;
+
+/// Percent above the baseline pace a plan can need before the user is behind
+pub const BEHIND_MARGIN_PERCENT: i64 = 10;
+
+/// Local days between two notifications that a user is behind
+const BEHIND_NOTIFY_INTERVAL_DAYS: i32 = 7;
+
+/// Days of reviews counted per new card when forecasting the review load
+const FORECAST_DAYS: i64 = 30;
+
+/// Client route showing the plan
+const PLAN_LINK: &str = "/plan";
+
+/// How a plan is going
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanStatus {
+    /// Every card of the roadmap is started
+    Complete,
+    OnTrack,
+    /// More new cards a day are needed than when the plan was set
+    Behind,
+    /// The target date passed with cards left
+    Overdue,
+}
+
+/// The pace a plan needs today
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pace {
+    /// Days to study, today and the target date included
+    pub days_left: i64,
+    pub required_daily_new_cards: i64,
+    /// Reviews a day once the required pace settles in, on top of those already due
+    pub estimated_daily_reviews: i64,
+    pub status: PlanStatus,
+}
+
+/// New cards a day needed to start `remaining_cards` in `days_left` days
+pub fn required_daily_new_cards(remaining_cards: i64, days_left: i64) -> i64 {
+    if remaining_cards <= 0 {
+        0
+    } else if days_left <= 0 {
+        remaining_cards
+    } else {
+        (remaining_cards + days_left - 1) / days_left
+    }
+}
+
+/// The pace a plan to finish by `target_date` needs given `counts`
+pub fn compute_pace(
+    counts: &RoadmapPlanCounts,
+    target_date: NaiveDate,
+    baseline_daily_new_cards: i32,
+) -> Pace {
+    let days_left = ((target_date - counts.today).num_days() + 1).max(0);
+    let required = required_daily_new_cards(counts.remaining_cards, days_left);
+
+    let baseline = i64::from(baseline_daily_new_cards);
+    let allowed = baseline + (baseline * BEHIND_MARGIN_PERCENT + 99) / 100;
+    let status = if counts.remaining_cards == 0 {
+        PlanStatus::Complete
+    } else if days_left == 0 {
+        PlanStatus::Overdue
+    } else if required > allowed {
+        PlanStatus::Behind
+    } else {
+        PlanStatus::OnTrack
+    };
+
+    let due_per_day = (counts.reviews_due_next_week + 6) / 7;
+    Pace {
+        days_left,
+        required_daily_new_cards: required,
+        estimated_daily_reviews: due_per_day
+            + required * mms_srs::reviews_in_first_days(FORECAST_DAYS),
+        status,
+    }
+}
+
+/// Notify every user who fell behind on their plan; returns how many were notified.
+///
+/// A user is told at most once every [`BEHIND_NOTIFY_INTERVAL_DAYS`] local days.
+pub async fn notify_behind(pool: &PgPool, events: &EventBus) -> Result<usize, sqlx::Error> {
+    let mut notified = 0;
+
+    for user_id in plan_repo::list_users_to_check(pool, BEHIND_NOTIFY_INTERVAL_DAYS).await? {
+        let Some(plan) = plan_repo::find(pool, user_id).await? else {
+            continue;
+        };
+        let Some(counts) = plan_repo::roadmap_counts(pool, user_id, plan.roadmap_id).await? else {
+            continue;
+        };
+        let pace = compute_pace(&counts, plan.target_date, plan.baseline_daily_new_cards);
+
+        let (title, body) = match pace.status {
+            PlanStatus::Behind => (
+                format!("You're falling behind on {}", counts.roadmap_title),
+                format!(
+                    "Learn {} new cards a day to finish by {}.",
+                    pace.required_daily_new_cards,
+                    plan.target_date.format("%B %-d")
+                ),
+            ),
+            PlanStatus::Overdue => (
+                format!("Your target for {} has passed", counts.roadmap_title),
+                format!(
+                    "{} cards are left. Set a new date to keep planning.",
+                    counts.remaining_cards
+                ),
+            ),
+            PlanStatus::Complete | PlanStatus::OnTrack => continue,
+        };
+
+        if let Err(e) = notify(
+            pool,
+            events,
+            user_id,
+            NotificationKind::PlanBehind,
+            &title,
+            &body,
+            Some(PLAN_LINK),
+        )
+        .await
+        {
+            tracing::error!(error = %e, user_id = %user_id, "Failed to notify plan behind");
+            continue;
+        }
+        plan_repo::mark_behind_notified(pool, user_id).await?;
+        notified += 1;
+    }
+
+    Ok(notified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(today: NaiveDate, remaining_cards: i64) -> RoadmapPlanCounts {
+        RoadmapPlanCounts {
+            roadmap_title: "A2".to_string(),
+            today,
+            total_cards: 500,
+            remaining_cards,
+            reviews_due_next_week: 70,
+        }
+    }
+
+    #[test]
+    fn test_compute_pace() {
+        let today = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let target = NaiveDate::from_ymd_opt(2026, 3, 10).unwrap();
+
+        let pace = compute_pace(&counts(today, 95), target, 10);
+        assert_eq!(pace.days_left, 10);
+        assert_eq!(pace.required_daily_new_cards, 10);
+        assert_eq!(pace.estimated_daily_reviews, 10 + 10 * 7);
+        assert_eq!(pace.status, PlanStatus::OnTrack);
+
+        // 11 is within the margin over 10, 12 is not
+        assert_eq!(
+            compute_pace(&counts(today, 110), target, 10).status,
+            PlanStatus::OnTrack
+        );
+        assert_eq!(
+            compute_pace(&counts(today, 111), target, 10).status,
+            PlanStatus::Behind
+        );
+
+        let late = NaiveDate::from_ymd_opt(2026, 3, 11).unwrap();
+        assert_eq!(
+            compute_pace(&counts(late, 5), target, 10).status,
+            PlanStatus::Overdue
+        );
+        assert_eq!(
+            compute_pace(&counts(late, 0), target, 10).status,
+            PlanStatus::Complete
+        );
+    }
+}
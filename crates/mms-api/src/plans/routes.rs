@@ -0,0 +1,203 @@
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::get,
+};
+use chrono::{Days, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
+
+use crate::{
+    ApiState,
+    auth::{AuthUser, require_self},
+    error::{ApiError, ErrorResponse},
+    plans::{PlanStatus, compute_pace, required_daily_new_cards},
+    validation::{ValidJson, invalid},
+};
+
+use mms_db::models::{RoadmapPlanCounts, StudyPlan};
+use mms_db::repositories::plan as plan_repo;
+
+/// Create the study plan routes
+pub fn routes() -> Router<ApiState> {
+    Router::new().route(
+        "/users/{user_id}/plan",
+        get(get_plan).put(set_plan).delete(delete_plan),
+    )
+}
+
+/// Furthest ahead a target date can be, in days
+const MAX_PLAN_DAYS: u64 = 3650;
+
+#[derive(Deserialize, ToSchema, Validate)]
+struct PlanRequest {
+    roadmap_id: Uuid,
+    /// Last day to start the roadmap's cards on, in the user's timezone; must be
+    /// after today and within 10 years
+    #[validate(custom(function = "plausible_target_date"))]
+    target_date: NaiveDate,
+}
+
+/// Reject dates already past in every timezone, or too far ahead; whether the
+/// date is after the user's today needs their timezone, so the handler checks it
+fn plausible_target_date(date: &NaiveDate) -> Result<(), ValidationError> {
+    let utc_today = Utc::now().date_naive();
+    if *date < utc_today {
+        return Err(invalid("past", "Target date must be after today"));
+    }
+    if *date > utc_today + Days::new(MAX_PLAN_DAYS) {
+        return Err(invalid("too_far", "Target date must be within 10 years"));
+    }
+    Ok(())
+}
+
+#[derive(Serialize, ToSchema)]
+struct PlanView {
+    roadmap_id: Uuid,
+    roadmap_title: String,
+    target_date: NaiveDate,
+    /// Days to study, today and the target date included
+    days_left: i64,
+    total_cards: i64,
+    /// Cards of the roadmap not started yet
+    remaining_cards: i64,
+    /// New cards a day needed from today to finish on time
+    required_daily_new_cards: i64,
+    required_weekly_new_cards: i64,
+    /// New cards a day the plan needed when it was set
+    baseline_daily_new_cards: i32,
+    /// Reviews a day once the required pace settles in, including cards already started
+    estimated_daily_reviews: i64,
+    status: PlanStatus,
+}
+
+impl PlanView {
+    fn new(plan: &StudyPlan, counts: RoadmapPlanCounts) -> Self {
+        let pace = compute_pace(&counts, plan.target_date, plan.baseline_daily_new_cards);
+        PlanView {
+            roadmap_id: plan.roadmap_id,
+            roadmap_title: counts.roadmap_title,
+            target_date: plan.target_date,
+            days_left: pace.days_left,
+            total_cards: counts.total_cards,
+            remaining_cards: counts.remaining_cards,
+            required_daily_new_cards: pace.required_daily_new_cards,
+            required_weekly_new_cards: pace.required_daily_new_cards * 7,
+            baseline_daily_new_cards: plan.baseline_daily_new_cards,
+            estimated_daily_reviews: pace.estimated_daily_reviews,
+            status: pace.status,
+        }
+    }
+}
+
+/// The user's study plan and the pace it needs today
+#[utoipa::path(
+    get,
+    path = "/v1/users/{user_id}/plan",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    params(("user_id" = Uuid, Path, description = "Must be the signed-in user")),
+    responses(
+        (status = 200, description = "Plan with the pace it needs", body = PlanView),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Another user's plan", body = ErrorResponse),
+        (status = 404, description = "No plan set", body = ErrorResponse),
+    )
+)]
+async fn get_plan(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<PlanView>, ApiError> {
+    require_self(&auth_user, user_id)?;
+
+    let not_found = || ApiError::NotFound("No study plan set".to_string());
+    let plan = plan_repo::find(&state.pool, user_id)
+        .await?
+        .ok_or_else(not_found)?;
+    // The roadmap cascades to the plan, so it only goes missing in a race
+    let counts = plan_repo::roadmap_counts(&state.pool, user_id, plan.roadmap_id)
+        .await?
+        .ok_or_else(not_found)?;
+
+    Ok(Json(PlanView::new(&plan, counts)))
+}
+
+/// Set a target date to finish a roadmap by, replacing any previous plan
+#[utoipa::path(
+    put,
+    path = "/v1/users/{user_id}/plan",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    params(("user_id" = Uuid, Path, description = "Must be the signed-in user")),
+    request_body = PlanRequest,
+    responses(
+        (status = 200, description = "Plan set", body = PlanView),
+        (status = 400, description = "Target date not after today", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Another user's plan", body = ErrorResponse),
+        (status = 404, description = "Roadmap not found", body = ErrorResponse),
+    )
+)]
+async fn set_plan(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(user_id): Path<Uuid>,
+    ValidJson(request): ValidJson<PlanRequest>,
+) -> Result<Json<PlanView>, ApiError> {
+    require_self(&auth_user, user_id)?;
+
+    let counts = plan_repo::roadmap_counts(&state.pool, user_id, request.roadmap_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Roadmap not found".to_string()))?;
+    if request.target_date <= counts.today {
+        return Err(ApiError::field(
+            "target_date",
+            "Target date must be after today",
+        ));
+    }
+
+    let days_left = (request.target_date - counts.today).num_days() + 1;
+    let baseline = required_daily_new_cards(counts.remaining_cards, days_left);
+    let plan = plan_repo::upsert(
+        &state.pool,
+        user_id,
+        request.roadmap_id,
+        request.target_date,
+        i32::try_from(baseline).unwrap_or(i32::MAX),
+    )
+    .await?;
+
+    Ok(Json(PlanView::new(&plan, counts)))
+}
+
+/// Remove the user's study plan
+#[utoipa::path(
+    delete,
+    path = "/v1/users/{user_id}/plan",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    params(("user_id" = Uuid, Path, description = "Must be the signed-in user")),
+    responses(
+        (status = 204, description = "Plan removed"),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Another user's plan", body = ErrorResponse),
+        (status = 404, description = "No plan set", body = ErrorResponse),
+    )
+)]
+async fn delete_plan(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(user_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    require_self(&auth_user, user_id)?;
+
+    if plan_repo::delete(&state.pool, user_id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::NotFound("No study plan set".to_string()))
+    }
+}
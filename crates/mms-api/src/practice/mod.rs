@@ -1,3 +1,48 @@
+pub mod plausibility;
+pub mod pronunciation;
+pub mod reschedule;
 pub mod routes;
+pub mod scheduler;
+pub mod sessions;
 
 pub use routes::routes;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// How a card is practised
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewMode {
+    /// The term is shown and its translation typed
+    #[default]
+    Translation,
+    /// Dictation: only the audio of the translation is played, and the
+    /// learner types what they heard
+    Listening,
+    /// The term is shown and its translation said aloud, graded from a
+    /// transcript of the recording
+    Pronunciation,
+}
+
+impl ReviewMode {
+    /// Every mode, in the order a deck lists them
+    pub const ALL: [Self; 3] = [Self::Translation, Self::Listening, Self::Pronunciation];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Translation => "translation",
+            Self::Listening => "listening",
+            Self::Pronunciation => "pronunciation",
+        }
+    }
+
+    pub fn parse(mode: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.as_str() == mode)
+    }
+
+    /// Whether the answer is typed, rather than recorded
+    pub const fn is_typed(self) -> bool {
+        !matches!(self, Self::Pronunciation)
+    }
+}
@@ -0,0 +1,76 @@
+//! Plausibility checks for submitted reviews.
+//!
+//! Reviews that arrive faster than a person can read a card and type an answer
+//! are still graded, so a false positive costs the learner nothing, but they
+//! are counted as flagged and kept out of rankings and difficulty scores.
+
+/// Length of the window reviews are counted in
+pub const BURST_WINDOW_SECS: i32 = 10;
+
+/// Reviews allowed in one window; above this is faster than one every half second
+pub const MAX_REVIEWS_PER_WINDOW: i32 = 20;
+
+/// Correct answers reported faster than this cannot have been typed
+pub const MIN_CORRECT_RESPONSE_MS: u32 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Suspicion {
+    /// Too many reviews in the current window
+    Burst,
+    /// A correct answer in less time than typing it takes
+    TooFast,
+}
+
+impl Suspicion {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Burst => "burst",
+            Self::TooFast => "too_fast",
+        }
+    }
+}
+
+/// Decide whether a review looks automated.
+///
+/// Quick wrong answers are plausible (skipping a card), so the response time
+/// only counts against correct ones.
+#[must_use]
+pub fn assess(
+    reviews_in_window: i32,
+    response_time_ms: Option<u32>,
+    is_correct: bool,
+) -> Option<Suspicion> {
+    if reviews_in_window > MAX_REVIEWS_PER_WINDOW {
+        Some(Suspicion::Burst)
+    } else if is_correct && response_time_ms.is_some_and(|ms| ms < MIN_CORRECT_RESPONSE_MS) {
+        Some(Suspicion::TooFast)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normal_pace_is_plausible() {
+        assert_eq!(assess(1, Some(2_500), true), None);
+        assert_eq!(assess(MAX_REVIEWS_PER_WINDOW, None, true), None);
+    }
+
+    #[test]
+    fn test_burst_is_flagged() {
+        assert_eq!(
+            assess(MAX_REVIEWS_PER_WINDOW + 1, Some(2_500), false),
+            Some(Suspicion::Burst)
+        );
+    }
+
+    #[test]
+    fn test_instant_answers_are_flagged_only_when_correct() {
+        assert_eq!(assess(1, Some(80), true), Some(Suspicion::TooFast));
+        assert_eq!(assess(1, Some(80), false), None);
+    }
+}
@@ -0,0 +1,137 @@
+//! Pronunciation practice.
+//!
+//! The learner says a card's translation aloud and uploads the recording. The
+//! configured [`SpeechToText`](crate::speech::SpeechToText) provider
+//! transcribes it in the card's `language_to`, and the transcript is graded
+//! and scheduled like a typed answer, normalization included. The review log
+//! records the review as a pronunciation review. The deck must list
+//! `pronunciation` among its modes.
+
+use axum::{
+    Json, Router,
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{HeaderMap, header::CONTENT_TYPE},
+    routing::post,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    ApiState,
+    auth::AuthUser,
+    error::{ApiError, ErrorResponse},
+    normalization::normalize_for_comparison,
+    practice::{
+        ReviewMode,
+        routes::{ReviewResponse, ReviewSubmission, review},
+    },
+    speech,
+};
+
+use mms_db::repositories::flashcard as flashcard_repo;
+
+/// Create the pronunciation practice routes
+pub fn routes() -> Router<ApiState> {
+    Router::new().route(
+        "/practice/{flashcard_id}/pronunciation",
+        post(submit_pronunciation),
+    )
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct PronunciationParams {
+    /// Deck the card is practised in
+    deck_id: Uuid,
+    /// Time from showing the card to the end of the recording
+    #[serde(default)]
+    response_time_ms: Option<u32>,
+    /// Time from showing the card to moving on, feedback included
+    #[serde(default)]
+    elapsed_ms: Option<u32>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct PronunciationResult {
+    #[serde(flatten)]
+    review: ReviewResponse,
+    /// What the speech-to-text provider heard
+    transcript: String,
+}
+
+/// Grade a recording of the card's translation and schedule its next review
+#[utoipa::path(
+    post,
+    path = "/v1/practice/{flashcard_id}/pronunciation",
+    tag = "practice",
+    security(("cookie_auth" = [])),
+    params(("flashcard_id" = Uuid, Path), PronunciationParams),
+    request_body(content = Vec<u8>, content_type = "audio/*", description = "Short recording, e.g. `audio/webm` or `audio/wav`, up to MAX_REQUEST_BODY_BYTES"),
+    responses(
+        (status = 200, description = "Recording graded", body = PronunciationResult),
+        (status = 400, description = "Not audio, no speech recognised, card not in the deck or not due yet, or the deck is not practised in pronunciation mode", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Card not found", body = ErrorResponse),
+        (status = 503, description = "No speech-to-text provider configured, or it could not be reached", body = ErrorResponse),
+    )
+)]
+async fn submit_pronunciation(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(flashcard_id): Path<Uuid>,
+    Query(params): Query<PronunciationParams>,
+    headers: HeaderMap,
+    audio: Bytes,
+) -> Result<Json<PronunciationResult>, ApiError> {
+    let transcriber = state
+        .speech
+        .as_deref()
+        .ok_or_else(|| ApiError::Speech("No speech-to-text provider is configured".to_string()))?;
+
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| speech::is_audio(value))
+        .ok_or_else(|| {
+            ApiError::Validation(
+                "The recording must be sent with an audio/* Content-Type".to_string(),
+            )
+        })?;
+    if audio.is_empty() {
+        return Err(ApiError::Validation("The recording is empty".to_string()));
+    }
+
+    let card = flashcard_repo::find_visible_card(&state.pool, flashcard_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Card not found".to_string()))?;
+
+    // Transcribed before the review starts, so no lock is held while the provider works
+    let transcript = transcriber
+        .transcribe(&audio, content_type, card.language_to.trim())
+        .await?;
+    if normalize_for_comparison(&transcript).is_empty() {
+        return Err(ApiError::Validation(
+            "No speech was recognised in the recording".to_string(),
+        ));
+    }
+
+    let Json(review) = review(
+        auth_user.user_id,
+        &state,
+        flashcard_id,
+        ReviewSubmission {
+            user_answer: transcript.clone(),
+            deck_id: params.deck_id,
+            response_time_ms: params.response_time_ms,
+            elapsed_ms: params.elapsed_ms,
+            mode: ReviewMode::Pronunciation,
+        },
+        None,
+        None,
+    )
+    .await?;
+
+    Ok(Json(PronunciationResult { review, transcript }))
+}
@@ -0,0 +1,149 @@
+//! Bulk rescheduling for learners returning after a break.
+//!
+//! A long break leaves a pile of overdue cards. Rather than review them all at
+//! once, a learner can spread them over the coming days, start over on the
+//! cards they keep failing, or bring in reviews scheduled far ahead. Each
+//! strategy is a single set-based update of the user's progress.
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    routing::post,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+use utoipa::ToSchema;
+use validator::{Validate, ValidationErrors};
+
+use crate::{
+    ApiState,
+    auth::{AuthUser, require_self},
+    error::{ApiError, ErrorResponse},
+    middleware::rate_limit,
+    validation::{ValidJson, invalid},
+};
+
+use mms_db::repositories::{
+    forecast as forecast_repo, known_word as known_word_repo, practice as practice_repo,
+};
+
+/// Longest span overdue cards can be spread over
+const MAX_SPREAD_DAYS: i32 = 90;
+
+/// Wrong answers that make an unmastered card a leech, unless the request says otherwise
+const DEFAULT_LEECH_LAPSES: i32 = 8;
+
+const MAX_LEECH_LAPSES: i32 = 100;
+
+/// Longest interval a cap can leave; intervals never exceed this anyway
+const MAX_CAP_DAYS: i32 = 90;
+
+/// Create the reschedule routes
+pub fn routes() -> Router<ApiState> {
+    use crate::make_rate_limit_layer;
+
+    Router::new()
+        .route("/users/{user_id}/reschedule", post(reschedule))
+        .layer(make_rate_limit_layer!(
+            rate_limit::GENERAL_RATE_PER_SECOND,
+            rate_limit::GENERAL_BURST_SIZE
+        ))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+enum RescheduleRequest {
+    /// Spread overdue cards evenly over the next `days` days (1 to 90), weakest cards first
+    SpreadOverdue { days: i32 },
+    /// Start over on unmastered cards answered wrong at least `min_lapses` times (1 to 100, default 8)
+    ResetLeeches {
+        #[serde(default)]
+        min_lapses: Option<i32>,
+    },
+    /// Bring reviews scheduled more than `max_days` days ahead (1 to 90) forward to then
+    CapInterval { max_days: i32 },
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct RescheduleResult {
+    cards_rescheduled: u64,
+}
+
+// Derived validation does not cover enums; each strategy bounds its own parameter
+impl Validate for RescheduleRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let (field, value, max) = match *self {
+            RescheduleRequest::SpreadOverdue { days } => ("days", days, MAX_SPREAD_DAYS),
+            RescheduleRequest::ResetLeeches { min_lapses } => (
+                "min_lapses",
+                min_lapses.unwrap_or(DEFAULT_LEECH_LAPSES),
+                MAX_LEECH_LAPSES,
+            ),
+            RescheduleRequest::CapInterval { max_days } => ("max_days", max_days, MAX_CAP_DAYS),
+        };
+        let mut errors = ValidationErrors::new();
+        if !(1..=max).contains(&value) {
+            errors.add(
+                field,
+                invalid("range", format!("{field} must be between 1 and {max}")),
+            );
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Reschedule many cards at once after a break
+#[utoipa::path(
+    post,
+    path = "/v1/users/{user_id}/reschedule",
+    tag = "practice",
+    security(("cookie_auth" = [])),
+    params(("user_id" = Uuid, Path, description = "Must be the signed-in user")),
+    request_body = RescheduleRequest,
+    responses(
+        (status = 200, description = "Cards rescheduled", body = RescheduleResult),
+        (status = 400, description = "Parameter out of range", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Another user's account", body = ErrorResponse),
+    )
+)]
+async fn reschedule(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(user_id): Path<Uuid>,
+    ValidJson(payload): ValidJson<RescheduleRequest>,
+) -> Result<Json<RescheduleResult>, ApiError> {
+    require_self(&auth_user, user_id)?;
+
+    let mut tx = state.pool.begin().await?;
+    let cards_rescheduled = match payload {
+        RescheduleRequest::SpreadOverdue { days } => {
+            practice_repo::spread_overdue(&mut *tx, user_id, days).await?
+        }
+        RescheduleRequest::ResetLeeches { min_lapses } => {
+            let min_lapses = min_lapses.unwrap_or(DEFAULT_LEECH_LAPSES);
+            let reset = practice_repo::reset_leeches(&mut *tx, user_id, min_lapses).await?;
+            // Resetting can take cards out of a deck's learned count
+            known_word_repo::refresh_decks_containing(
+                &mut *tx,
+                user_id,
+                &reset,
+                mms_srs::MASTERY_THRESHOLD,
+            )
+            .await?;
+            reset.len() as u64
+        }
+        RescheduleRequest::CapInterval { max_days } => {
+            practice_repo::cap_intervals(&mut *tx, user_id, max_days).await?
+        }
+    };
+    forecast_repo::invalidate(&mut *tx, user_id).await?;
+    tx.commit().await?;
+
+    tracing::info!(user_id = %user_id, cards = cards_rescheduled, "Rescheduled cards");
+    Ok(Json(RescheduleResult { cards_rescheduled }))
+}
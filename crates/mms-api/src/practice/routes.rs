@@ -1,44 +1,154 @@
 use axum::{
     Json, Router,
     extract::{Path, State},
+    response::Response,
     routing::post,
 };
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::types::Uuid;
+use utoipa::ToSchema;
 
-use crate::{ApiState, auth::middleware::AuthUser, error::ApiError};
+use crate::{
+    ApiState, achievements,
+    auth::middleware::AuthUser,
+    error::{ApiError, ErrorResponse},
+    goals,
+    idempotency::Idempotent,
+    live::LiveEvent,
+    metrics,
+    practice::{ReviewMode, plausibility, scheduler},
+    stats::forecast,
+    xp,
+};
 
+use mms_db::models::{NewReviewLog, ReviewedProgress};
+use mms_db::repositories::deck_settings as deck_settings_repo;
 use mms_db::repositories::practice as practice_repo;
+use mms_db::repositories::practice_session as session_repo;
+use mms_db::repositories::review_log as review_log_repo;
+use mms_db::repositories::user as user_repo;
 
 /// Create the practice routes
 pub fn routes() -> Router<ApiState> {
-    Router::new().route("/practice/{flashcard_id}/review", post(submit_review))
+    Router::new()
+        .route("/practice/{flashcard_id}/review", post(submit_review))
+        .merge(super::pronunciation::routes())
+        .merge(super::reschedule::routes())
+        .merge(super::sessions::routes())
+}
+
+/// Answer times above this are treated as the learner stepping away
+const MAX_RESPONSE_TIME_MS: u32 = 60_000;
+
+/// Time on a card above this is treated as the learner stepping away
+const MAX_ELAPSED_MS: u32 = 120_000;
+
+/// Days from one review to the next, fractional
+fn interval_days(from: DateTime<Utc>, to: DateTime<Utc>) -> f64 {
+    (to - from).num_seconds() as f64 / 86_400.0
+}
+
+#[derive(Deserialize, ToSchema)]
+pub(crate) struct ReviewSubmission {
+    pub(crate) user_answer: String,
+    pub(crate) deck_id: Uuid,
+    /// Time from showing the card to submitting, feeds the card's global difficulty
+    #[serde(default)]
+    pub(crate) response_time_ms: Option<u32>,
+    /// Time from showing the card to moving on, feedback included; counts as
+    /// study time instead of `response_time_ms` when given
+    #[serde(default)]
+    pub(crate) elapsed_ms: Option<u32>,
+    /// The mode the card was practised in, `translation` by default;
+    /// recordings go to the pronunciation endpoint
+    #[serde(default)]
+    pub(crate) mode: ReviewMode,
 }
 
-#[derive(Deserialize)]
-struct ReviewSubmission {
-    user_answer: String,
-    deck_id: Uuid,
+impl ReviewSubmission {
+    /// Reject a typed answer claiming to be a recording
+    pub(crate) fn check_typed(&self) -> Result<(), ApiError> {
+        if self.mode.is_typed() {
+            Ok(())
+        } else {
+            Err(ApiError::Validation(
+                "Pronunciation answers are submitted as recordings".to_string(),
+            ))
+        }
+    }
 }
 
-#[derive(Serialize)]
-struct ReviewResponse {
+#[derive(Serialize, ToSchema)]
+pub(crate) struct ReviewResponse {
     is_correct: bool,
     correct_answer: String,
 }
 
+/// Grade an answer and schedule the card's next review
+#[utoipa::path(
+    post,
+    path = "/v1/practice/{flashcard_id}/review",
+    tag = "practice",
+    security(("cookie_auth" = [])),
+    params(
+        ("flashcard_id" = Uuid, Path),
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key get the first response again"),
+    ),
+    request_body = ReviewSubmission,
+    responses(
+        (status = 200, description = "Answer graded", body = ReviewResponse),
+        (status = 400, description = "Card not in the deck, not due yet, or a mode the deck does not practise", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
 async fn submit_review(
     auth_user: AuthUser,
     State(state): State<ApiState>,
     Path(flashcard_id): Path<Uuid>,
-    Json(payload): Json<ReviewSubmission>,
+    Idempotent(idempotency, Json(payload)): Idempotent<Json<ReviewSubmission>>,
+) -> Response {
+    let result = match payload.check_typed() {
+        Ok(()) => review(auth_user.user_id, &state, flashcard_id, payload, None, None).await,
+        Err(err) => Err(err),
+    };
+    idempotency.finish(result).await
+}
+
+/// Grade an answer and schedule the card's next review, recording it against
+/// `session_id` when the card was served by a practice session.
+///
+/// The answer, typed or transcribed from a recording depending on the
+/// submission's mode, is checked against the card's translation and accepted
+/// answers. The deck must practise its cards in that mode.
+///
+/// The review happens at `reviewed_at` when given, for answers recorded
+/// offline, and at the state's clock otherwise.
+pub(crate) async fn review(
+    user_id: Uuid,
+    state: &ApiState,
+    flashcard_id: Uuid,
+    payload: ReviewSubmission,
+    session_id: Option<Uuid>,
+    reviewed_at: Option<DateTime<Utc>>,
 ) -> Result<Json<ReviewResponse>, ApiError> {
-    let user_id = auth_user.user_id;
-    let now = Utc::now();
+    let now = reviewed_at.unwrap_or_else(|| state.clock.now());
 
-    // Single transaction for atomicity
+    // Single transaction for atomicity. Concurrent reviews of the user wait for
+    // this one, so each reads the progress and counters the previous one left.
     let mut tx = state.pool.begin().await?;
+    if !practice_repo::lock_user_reviews(&mut *tx, user_id).await? {
+        return Err(ApiError::NotFound("User not found".to_string()));
+    }
+
+    // Checked under the lock, so a card answered twice at once is graded once
+    if let Some(session_id) = session_id
+        && !session_repo::is_unanswered(&mut *tx, session_id, flashcard_id).await?
+    {
+        return Err(ApiError::Conflict(
+            "This card is not waiting for an answer in this session".to_string(),
+        ));
+    }
 
     // Verify the flashcard actually belongs to the submitted deck
     let belongs =
@@ -48,15 +158,39 @@ async fn submit_review(
             "Flashcard does not belong to the specified deck".to_string(),
         ));
     }
+    let mode = payload.mode;
+    let settings = deck_settings_repo::find(&mut *tx, payload.deck_id)
+        .await?
+        .map(scheduler::DeckSettings::from)
+        .unwrap_or_default();
+    if !settings.enabled_modes().contains(&mode) {
+        return Err(ApiError::Validation(format!(
+            "This deck is not practised in {} mode",
+            mode.as_str()
+        )));
+    }
 
-    // Fetch the flashcard's correct translation
-    let correct_translation =
-        practice_repo::get_flashcard_translation(&mut *tx, flashcard_id).await?;
+    // Fetch the flashcard's answers and global difficulty
+    let flashcard = practice_repo::get_flashcard_for_review(&mut *tx, flashcard_id).await?;
+    let correct_translation = flashcard.translation;
 
     // Fetch current progress to check if we should update
     let current_progress =
         practice_repo::get_card_progress(&mut *tx, user_id, flashcard_id).await?;
 
+    // A replayed review cannot go before one already recorded. Live reviews
+    // read the clock before waiting for the lock, so they are not checked.
+    if reviewed_at.is_some()
+        && current_progress
+            .as_ref()
+            .and_then(|p| p.last_review_at)
+            .is_some_and(|last| now < last)
+    {
+        return Err(ApiError::Conflict(
+            "A later review of this card is already recorded".to_string(),
+        ));
+    }
+
     // If review is too early, reject without revealing the answer
     let too_early = current_progress
         .as_ref()
@@ -69,12 +203,14 @@ async fn submit_review(
         ));
     }
 
-    // Validate the user's answer by normalizing both strings
+    // Validate the user's answer by normalizing it and every accepted answer
     let normalized_user_answer =
         crate::normalization::normalize_for_comparison(&payload.user_answer);
-    let normalized_correct_answer =
-        crate::normalization::normalize_for_comparison(&correct_translation);
-    let is_correct = normalized_user_answer == normalized_correct_answer;
+    let is_correct = std::iter::once(&correct_translation)
+        .chain(&flashcard.accepted_answers)
+        .any(|answer| {
+            crate::normalization::normalize_for_comparison(answer) == normalized_user_answer
+        });
 
     let (mut new_times_correct, mut new_times_wrong) = current_progress
         .as_ref()
@@ -93,18 +229,99 @@ async fn submit_review(
     let mastered = mms_srs::is_mastered(new_times_correct, new_times_wrong);
     let newly_mastered = mastered && !was_mastered;
 
-    // Compute the next review date based on the new score
-    let next_review_at = mms_srs::compute_next_review(new_times_correct, new_times_wrong, now);
+    // Compute the next review date based on the new score; hard cards come back
+    // sooner, and the deck and the user's preferences bound the interval
+    let config = scheduler::load(&mut tx, user_id, payload.deck_id).await?;
+    let next_review_at = mms_srs::compute_next_review_with_options(
+        new_times_correct,
+        new_times_wrong,
+        flashcard.difficulty.map(f64::from),
+        config.schedule_options(),
+        now,
+    );
 
     // Update the progress (including mastered_at)
     practice_repo::upsert_card_progress(
         &mut *tx,
         user_id,
         flashcard_id,
+        &ReviewedProgress {
+            next_review_at,
+            times_correct: new_times_correct,
+            times_wrong: new_times_wrong,
+            mastered,
+            reviewed_at: now,
+        },
+    )
+    .await?;
+    practice_repo::record_mode_review(
+        &mut *tx,
+        user_id,
+        flashcard_id,
+        mode.as_str(),
+        is_correct,
+        now,
+    )
+    .await?;
+    forecast::apply_review(
+        &mut tx,
+        user_id,
+        current_progress.as_ref().map(|p| p.next_review_at),
         next_review_at,
-        new_times_correct,
-        new_times_wrong,
-        mastered,
+    )
+    .await?;
+
+    // Replayed reviews arrive together by design, so only live ones count towards the pace
+    let reviews_in_window = match reviewed_at {
+        Some(_) => 0,
+        None => {
+            practice_repo::record_review_pace(&mut *tx, user_id, plausibility::BURST_WINDOW_SECS)
+                .await?
+        }
+    };
+    let suspicion = plausibility::assess(reviews_in_window, payload.response_time_ms, is_correct);
+    if let Some(suspicion) = suspicion {
+        tracing::warn!(
+            user_id = %user_id,
+            reason = suspicion.as_str(),
+            reviews_in_window,
+            response_time_ms = payload.response_time_ms,
+            "Implausible review flagged"
+        );
+        metrics::record_suspicious_review(suspicion.as_str());
+    }
+    let flagged = suspicion.is_some();
+
+    let response_time_ms = payload
+        .response_time_ms
+        .map(|ms| ms.min(MAX_RESPONSE_TIME_MS) as i32);
+    let elapsed_ms = payload.elapsed_ms.map(|ms| ms.min(MAX_ELAPSED_MS) as i32);
+
+    // Flagged answer times would skew the card's global difficulty and the study time
+    if let Some(response_time_ms) = response_time_ms.filter(|_| !flagged) {
+        practice_repo::record_response_time(&mut *tx, user_id, flashcard_id, response_time_ms)
+            .await?;
+    }
+    let study_ms = elapsed_ms.or(response_time_ms).filter(|_| !flagged);
+
+    review_log_repo::insert(
+        &mut *tx,
+        &NewReviewLog {
+            user_id,
+            flashcard_id,
+            deck_id: payload.deck_id,
+            is_correct,
+            previous_interval_days: current_progress.as_ref().and_then(|p| {
+                p.last_review_at
+                    .map(|last| interval_days(last, p.next_review_at))
+            }),
+            interval_days: interval_days(now, next_review_at),
+            response_time_ms,
+            elapsed_ms,
+            flagged,
+            reviewed_at: now,
+            mode: mode.as_str(),
+        },
     )
     .await?;
 
@@ -118,11 +335,21 @@ async fn submit_review(
     .await?;
 
     // Record activity
-    practice_repo::record_activity(&mut *tx, user_id).await?;
+    let reviews_today = practice_repo::record_activity(
+        &mut *tx,
+        user_id,
+        is_correct,
+        flagged,
+        study_ms.unwrap_or(0),
+        current_progress.is_none(),
+        now,
+    )
+    .await?;
+    practice_repo::record_profile_activity(&mut *tx, user_id, flashcard_id, now).await?;
 
     // Update user stats (increment total_cards_learned if newly mastered)
     let stats_updated =
-        practice_repo::increment_review_stats(&mut *tx, user_id, newly_mastered).await?;
+        practice_repo::increment_review_stats(&mut *tx, user_id, newly_mastered, flagged).await?;
     if !stats_updated {
         tracing::warn!(user_id = %user_id, "user_stats row missing for authenticated user");
     }
@@ -130,7 +357,60 @@ async fn submit_review(
     // Update streak (must run after record_activity so today's entry exists)
     practice_repo::update_streak(&mut *tx, user_id).await?;
 
+    // Only the first review of the day can move the streak
+    let streak = if reviews_today == 1 && stats_updated {
+        Some(user_repo::get_user_stats(&mut *tx, user_id).await?)
+    } else {
+        None
+    };
+
+    if !flagged {
+        xp::award_for_review(
+            &mut tx,
+            user_id,
+            is_correct,
+            flashcard.difficulty.map(f64::from),
+        )
+        .await?;
+    }
+
+    if let Some(session_id) = session_id {
+        session_repo::record_answer(&mut *tx, session_id, flashcard_id, is_correct).await?;
+    }
+
+    // Before unlocking, so a reached goal counts towards the goal achievements
+    let goal_reached = goals::mark_reached(&mut tx, user_id).await?;
+
+    // Flagged reviews are left out of the review count, so they cannot unlock anything
+    let unlocked = achievements::unlock_reached(&mut tx, user_id).await?;
+
     tx.commit().await?;
+    metrics::record_review(is_correct, interval_days(now, next_review_at));
+
+    state.events.publish(
+        user_id,
+        LiveEvent::ReviewRecorded {
+            flashcard_id,
+            deck_id: payload.deck_id,
+            is_correct,
+            next_review_at,
+        },
+    );
+    if let Some(stats) = streak {
+        state.events.publish(
+            user_id,
+            LiveEvent::StreakUpdated {
+                current_streak_days: stats.current_streak_days,
+                longest_streak_days: stats.longest_streak_days,
+            },
+        );
+    }
+    for notification in &unlocked {
+        state.events.publish(user_id, LiveEvent::from(notification));
+    }
+    if let Some(status) = &goal_reached {
+        state.events.publish(user_id, LiveEvent::from(status));
+    }
 
     Ok(Json(ReviewResponse {
         is_correct,
@@ -0,0 +1,249 @@
+//! Scheduling settings resolved for a review.
+//!
+//! A card's schedule comes from three layers: the defaults of the SRS crate,
+//! the learner's preferences and the settings of the deck the card is practised
+//! in. A deck's ease and new-card order replace the defaults. For the longest
+//! interval the shorter of the learner's and the deck's applies, so a learner
+//! who caps their intervals, e.g. before an exam, keeps the cap in every deck.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use mms_db::models::{self, NewDeckSettings};
+use mms_db::repositories::{deck_settings as deck_settings_repo, preference as preference_repo};
+
+use crate::{practice::ReviewMode, preferences::Preferences};
+
+/// Longest interval a deck or learner can set
+pub const MAX_INTERVAL_DAYS_LIMIT: i32 = 365;
+
+/// Lowest ease a deck can set
+pub const MIN_STARTING_EASE: f64 = 0.5;
+
+/// Highest ease a deck can set
+pub const MAX_STARTING_EASE: f64 = 2.0;
+
+/// Order new cards are introduced in
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NewCardOrder {
+    /// Cards with the fewest unknown words first
+    #[default]
+    EasiestFirst,
+    /// Oldest card first
+    Added,
+    /// Shuffled
+    Random,
+}
+
+impl NewCardOrder {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NewCardOrder::EasiestFirst => "easiest_first",
+            NewCardOrder::Added => "added",
+            NewCardOrder::Random => "random",
+        }
+    }
+
+    fn parse(order: &str) -> Option<Self> {
+        match order {
+            "easiest_first" => Some(NewCardOrder::EasiestFirst),
+            "added" => Some(NewCardOrder::Added),
+            "random" => Some(NewCardOrder::Random),
+            _ => None,
+        }
+    }
+}
+
+/// A deck's scheduling overrides; `null` keeps the default
+#[derive(Debug, Default, Clone, PartialEq, Serialize, ToSchema)]
+pub struct DeckSettings {
+    /// Longest interval in days, 1 to 365
+    pub max_interval_days: Option<i32>,
+    /// Multiplies the day-based intervals, 0.5 to 2.0
+    pub starting_ease: Option<f64>,
+    pub new_card_order: Option<NewCardOrder>,
+    /// Modes the deck's cards are practised in; `null` means translation only
+    pub modes: Option<Vec<ReviewMode>>,
+    /// When the settings were last changed, `null` while the deck has none
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl From<models::DeckSettings> for DeckSettings {
+    fn from(stored: models::DeckSettings) -> Self {
+        Self {
+            max_interval_days: stored.max_interval_days,
+            starting_ease: stored.starting_ease,
+            new_card_order: stored
+                .new_card_order
+                .as_deref()
+                .and_then(NewCardOrder::parse),
+            modes: stored.modes.map(|modes| {
+                modes
+                    .iter()
+                    .filter_map(|mode| ReviewMode::parse(mode))
+                    .collect()
+            }),
+            updated_at: stored.updated_at,
+        }
+    }
+}
+
+impl DeckSettings {
+    pub fn to_new(&self) -> NewDeckSettings<'static> {
+        NewDeckSettings {
+            max_interval_days: self.max_interval_days,
+            starting_ease: self.starting_ease,
+            new_card_order: self.new_card_order.map(NewCardOrder::as_str),
+            modes: self
+                .modes
+                .as_ref()
+                .map(|modes| modes.iter().map(|mode| mode.as_str()).collect()),
+        }
+    }
+
+    /// The modes the deck's cards are practised in
+    pub fn enabled_modes(&self) -> Vec<ReviewMode> {
+        self.modes
+            .clone()
+            .unwrap_or_else(|| vec![ReviewMode::Translation])
+    }
+}
+
+/// The settings a card is scheduled with
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+pub struct SchedulerConfig {
+    /// Longest interval in days
+    pub max_interval_days: i32,
+    /// Multiplies the day-based intervals; 1.0 keeps the default schedule
+    pub starting_ease: f64,
+    pub new_card_order: NewCardOrder,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            max_interval_days: mms_srs::MAX_INTERVAL_DAYS,
+            starting_ease: mms_srs::DEFAULT_EASE,
+            new_card_order: NewCardOrder::default(),
+        }
+    }
+}
+
+impl SchedulerConfig {
+    /// Merge the learner's preferences and the deck's settings over the defaults
+    pub fn resolve(preferences: &Preferences, deck: &DeckSettings) -> Self {
+        let defaults = Self::default();
+        let max_interval_days = deck
+            .max_interval_days
+            .map_or(preferences.max_interval_days, |days| {
+                days.min(preferences.max_interval_days)
+            });
+        Self {
+            max_interval_days,
+            starting_ease: deck.starting_ease.unwrap_or(defaults.starting_ease),
+            new_card_order: deck.new_card_order.unwrap_or(defaults.new_card_order),
+        }
+    }
+
+    /// What the SRS crate needs to compute the next review
+    pub fn schedule_options(&self) -> mms_srs::ScheduleOptions {
+        mms_srs::ScheduleOptions {
+            ease: self.starting_ease,
+            max_interval_days: self.max_interval_days,
+        }
+    }
+}
+
+/// The settings a user's review of a card in `deck_id` is scheduled with
+pub async fn load(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    deck_id: Uuid,
+) -> Result<SchedulerConfig, sqlx::Error> {
+    let preferences = preference_repo::find(&mut *conn, user_id)
+        .await?
+        .map(Preferences::from)
+        .unwrap_or_default();
+    let deck = deck_settings_repo::find(&mut *conn, deck_id)
+        .await?
+        .map(DeckSettings::from)
+        .unwrap_or_default();
+    Ok(SchedulerConfig::resolve(&preferences, &deck))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_keep_the_plain_schedule() {
+        let config = SchedulerConfig::resolve(&Preferences::default(), &DeckSettings::default());
+        assert_eq!(config, SchedulerConfig::default());
+        assert_eq!(
+            config.schedule_options(),
+            mms_srs::ScheduleOptions::default()
+        );
+    }
+
+    #[test]
+    fn test_deck_overrides_defaults_and_the_shorter_cap_wins() {
+        let deck = DeckSettings {
+            max_interval_days: Some(30),
+            starting_ease: Some(1.5),
+            new_card_order: Some(NewCardOrder::Random),
+            modes: None,
+            updated_at: None,
+        };
+        let config = SchedulerConfig::resolve(&Preferences::default(), &deck);
+        assert_eq!(config.max_interval_days, 30);
+        assert_eq!(config.starting_ease, 1.5);
+        assert_eq!(config.new_card_order, NewCardOrder::Random);
+
+        let cautious = Preferences {
+            max_interval_days: 14,
+            ..Preferences::default()
+        };
+        assert_eq!(
+            SchedulerConfig::resolve(&cautious, &deck).max_interval_days,
+            14
+        );
+        assert_eq!(
+            SchedulerConfig::resolve(&cautious, &DeckSettings::default()).max_interval_days,
+            14
+        );
+    }
+
+    #[test]
+    fn test_new_card_orders_round_trip() {
+        for order in [
+            NewCardOrder::EasiestFirst,
+            NewCardOrder::Added,
+            NewCardOrder::Random,
+        ] {
+            assert_eq!(NewCardOrder::parse(order.as_str()), Some(order));
+        }
+    }
+
+    #[test]
+    fn test_decks_practise_translation_only_by_default() {
+        assert_eq!(
+            DeckSettings::default().enabled_modes(),
+            [ReviewMode::Translation]
+        );
+
+        let stored = models::DeckSettings {
+            modes: Some(vec!["translation".to_string(), "listening".to_string()]),
+            ..models::DeckSettings::default()
+        };
+        let deck = DeckSettings::from(stored);
+        assert_eq!(
+            deck.enabled_modes(),
+            [ReviewMode::Translation, ReviewMode::Listening]
+        );
+        assert_eq!(deck.to_new().modes, Some(vec!["translation", "listening"]));
+    }
+}
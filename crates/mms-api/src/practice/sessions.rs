@@ -0,0 +1,452 @@
+//! Practice sessions with their queue kept on the server.
+//!
+//! Starting a session freezes the due cards of a deck, several decks or a
+//! roadmap into a queue. `next` serves the first card not answered yet, and
+//! `answer` grades it like a plain review while marking it answered, so a card
+//! is never served after its answer or graded twice, whichever tab or retry
+//! sends it. Cards reviewed elsewhere, hidden or taken out of their deck after
+//! the session started are skipped.
+//!
+//! Across several decks the queue takes a card from each deck in turn, so
+//! related cards are spaced apart rather than reviewed back to back. Each card
+//! keeps the deck it was drawn from, and its answer counts towards that deck.
+//!
+//! A session stays open for [`SESSION_TTL_HOURS`] after its last activity.
+//! Starting a session on the same decks as an open one resumes it.
+
+use std::collections::{HashSet, VecDeque};
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::{
+    ApiState,
+    auth::AuthUser,
+    deck::routes::{DEFAULT_PRACTICE_LIMIT, MAX_PRACTICE_LIMIT},
+    error::{ApiError, ErrorResponse},
+    idempotency::Idempotent,
+    practice::{ReviewMode, scheduler::NewCardOrder},
+    validation::ValidJson,
+};
+
+use mms_db::models::{PracticeSession, SessionCard};
+use mms_db::repositories::deck as deck_repo;
+use mms_db::repositories::practice as practice_repo;
+use mms_db::repositories::practice_session as session_repo;
+use mms_db::repositories::roadmap as roadmap_repo;
+use mms_db::tenancy::Tenant;
+
+use super::routes::{ReviewResponse, ReviewSubmission, review};
+
+/// Hours a session stays open after the last card served or answered
+pub const SESSION_TTL_HOURS: i32 = 24;
+
+/// Most decks a session can be started on by listing them
+const MAX_SESSION_DECKS: u64 = 20;
+
+/// Create the practice session routes
+pub fn routes() -> Router<ApiState> {
+    Router::new()
+        .route("/practice/sessions", post(create_session))
+        .route("/practice/sessions/{session_id}", get(get_session))
+        .route("/practice/sessions/{session_id}/next", get(next_card))
+        .route(
+            "/practice/sessions/{session_id}/answer",
+            post(submit_answer),
+        )
+}
+
+/// Give exactly one of `deck_id`, `deck_ids` or `roadmap_id`
+#[derive(Deserialize, ToSchema, Validate)]
+struct CreateSession {
+    #[serde(default)]
+    deck_id: Option<Uuid>,
+    /// Up to 20 decks, interleaved in the order given
+    #[serde(default)]
+    #[validate(length(
+        min = 1,
+        max = MAX_SESSION_DECKS,
+        message = "deck_ids must list between 1 and 20 decks"
+    ))]
+    deck_ids: Option<Vec<Uuid>>,
+    /// Every deck of the roadmap, interleaved top to bottom
+    #[serde(default)]
+    roadmap_id: Option<Uuid>,
+    /// Cards to queue across all decks, 1 to 50 (default 20)
+    #[serde(default)]
+    #[validate(range(
+        min = 1,
+        max = MAX_PRACTICE_LIMIT,
+        message = "Limit must be between 1 and 50"
+    ))]
+    limit: Option<i64>,
+}
+
+#[derive(Deserialize, ToSchema)]
+struct SessionAnswer {
+    flashcard_id: Uuid,
+    user_answer: String,
+    /// Time from showing the card to submitting
+    #[serde(default)]
+    response_time_ms: Option<u32>,
+    /// Time from showing the card to moving on, feedback included
+    #[serde(default)]
+    elapsed_ms: Option<u32>,
+    /// The mode the card was served in, `translation` by default
+    #[serde(default)]
+    mode: ReviewMode,
+}
+
+#[derive(Serialize, ToSchema)]
+struct SessionAnswerResponse {
+    #[serde(flatten)]
+    review: ReviewResponse,
+    /// The session after the answer
+    session: PracticeSession,
+}
+
+fn session_not_found() -> ApiError {
+    ApiError::NotFound("Practice session not found or expired".to_string())
+}
+
+/// The decks a session is started on, in interleaving order, and its roadmap
+async fn session_decks(
+    state: &ApiState,
+    user_id: Uuid,
+    payload: &CreateSession,
+) -> Result<(Vec<Uuid>, Option<Uuid>), ApiError> {
+    match (&payload.deck_id, &payload.deck_ids, payload.roadmap_id) {
+        (Some(deck_id), None, None) => {
+            deck_repo::find_by_id(&state.pool, Tenant::Member(user_id), *deck_id)
+                .await?
+                .ok_or_else(|| ApiError::NotFound("Deck not found".to_string()))?;
+            Ok((vec![*deck_id], None))
+        }
+        (None, Some(deck_ids), None) => {
+            let mut seen = HashSet::new();
+            let deck_ids: Vec<Uuid> = deck_ids
+                .iter()
+                .copied()
+                .filter(|id| seen.insert(*id))
+                .collect();
+            // Another organization's deck is as good as missing
+            let visible = deck_repo::list_by_ids(&state.pool, &deck_ids, user_id).await?;
+            if visible.len() != deck_ids.len() {
+                return Err(ApiError::NotFound("Deck not found".to_string()));
+            }
+            Ok((deck_ids, None))
+        }
+        (None, None, Some(roadmap_id)) => {
+            if roadmap_repo::list_by_ids(&state.pool, &[roadmap_id], user_id)
+                .await?
+                .is_empty()
+            {
+                return Err(ApiError::NotFound("Roadmap not found".to_string()));
+            }
+            let nodes = roadmap_repo::list_nodes(&state.pool, &[roadmap_id], user_id).await?;
+            let mut seen = HashSet::new();
+            let deck_ids = nodes
+                .into_iter()
+                .map(|node| node.deck_id)
+                .filter(|id| seen.insert(*id))
+                .collect();
+            Ok((deck_ids, Some(roadmap_id)))
+        }
+        _ => Err(ApiError::Validation(
+            "Give exactly one of deck_id, deck_ids or roadmap_id".to_string(),
+        )),
+    }
+}
+
+/// Queue up to `limit` of `cards`, given as (flashcard, deck) grouped by deck,
+/// taking one card from each deck in turn. A card in several decks is queued
+/// once, from the first deck to reach it.
+fn interleave(cards: &[(Uuid, Uuid)], limit: usize) -> Vec<(Uuid, Uuid)> {
+    let mut decks: Vec<VecDeque<(Uuid, Uuid)>> = Vec::new();
+    for &card in cards {
+        match decks.last_mut() {
+            Some(deck) if deck.front().is_some_and(|&(_, deck_id)| deck_id == card.1) => {
+                deck.push_back(card)
+            }
+            _ => decks.push(VecDeque::from([card])),
+        }
+    }
+
+    let mut queued = HashSet::new();
+    let mut queue = Vec::new();
+    while queue.len() < limit && decks.iter().any(|deck| !deck.is_empty()) {
+        for deck in &mut decks {
+            if queue.len() == limit {
+                break;
+            }
+            while let Some(card) = deck.pop_front() {
+                if queued.insert(card.0) {
+                    queue.push(card);
+                    break;
+                }
+            }
+        }
+    }
+    queue
+}
+
+/// Start a practice session on one or more decks, or resume the open one
+///
+/// Each deck's due cards are taken in the order `GET /v1/decks/{deck_id}/practice`
+/// would return them, one deck after the other in turn.
+#[utoipa::path(
+    post,
+    path = "/v1/practice/sessions",
+    tag = "practice",
+    security(("cookie_auth" = [])),
+    request_body = CreateSession,
+    responses(
+        (status = 201, description = "Session started", body = PracticeSession),
+        (status = 200, description = "The deck's open session, resumed", body = PracticeSession),
+        (status = 400, description = "Not exactly one of deck_id, deck_ids or roadmap_id, or no card is due", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Deck or roadmap not found", body = ErrorResponse),
+    )
+)]
+async fn create_session(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    ValidJson(payload): ValidJson<CreateSession>,
+) -> Result<Response, ApiError> {
+    let user_id = auth_user.user_id;
+    let (deck_ids, roadmap_id) = session_decks(&state, user_id, &payload).await?;
+    let mut sorted_deck_ids = deck_ids.clone();
+    sorted_deck_ids.sort_unstable();
+    let limit = payload.limit.unwrap_or(DEFAULT_PRACTICE_LIMIT);
+
+    // Under the review lock, so two starts at once open a single session
+    let mut tx = state.pool.begin().await?;
+    if !practice_repo::lock_user_reviews(&mut *tx, user_id).await? {
+        return Err(ApiError::NotFound("User not found".to_string()));
+    }
+
+    let (session_id, status) =
+        match session_repo::find_open(&mut *tx, user_id, &sorted_deck_ids, roadmap_id).await? {
+            Some(session_id) => (session_id, StatusCode::OK),
+            None => {
+                let cards = practice_repo::get_practice_cards_for_decks(
+                    &mut *tx,
+                    &deck_ids,
+                    user_id,
+                    limit,
+                    mms_srs::KNOWN_WORD_SCORE,
+                    NewCardOrder::default().as_str(),
+                )
+                .await?;
+                let cards: Vec<(Uuid, Uuid)> = cards
+                    .iter()
+                    .map(|card| (card.card.id, card.deck_id))
+                    .collect();
+                let queue = interleave(&cards, limit as usize);
+                if queue.is_empty() {
+                    return Err(ApiError::Validation(
+                        "No card in these decks is due for review".to_string(),
+                    ));
+                }
+                let session_id = session_repo::create(
+                    &mut *tx,
+                    user_id,
+                    &sorted_deck_ids,
+                    roadmap_id,
+                    &queue,
+                    SESSION_TTL_HOURS,
+                )
+                .await?;
+                (session_id, StatusCode::CREATED)
+            }
+        };
+    session_repo::touch(&mut *tx, session_id, SESSION_TTL_HOURS).await?;
+    let session = session_repo::find(&mut *tx, session_id, user_id)
+        .await?
+        .ok_or_else(session_not_found)?;
+    tx.commit().await?;
+
+    Ok((status, Json(session)).into_response())
+}
+
+/// A practice session and its progress
+#[utoipa::path(
+    get,
+    path = "/v1/practice/sessions/{session_id}",
+    tag = "practice",
+    security(("cookie_auth" = [])),
+    params(("session_id" = Uuid, Path)),
+    responses(
+        (status = 200, description = "The session", body = PracticeSession),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Session not found or expired", body = ErrorResponse),
+    )
+)]
+async fn get_session(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(session_id): Path<Uuid>,
+) -> Result<Json<PracticeSession>, ApiError> {
+    let session = session_repo::find(&state.pool, session_id, auth_user.user_id)
+        .await?
+        .ok_or_else(session_not_found)?;
+    Ok(Json(session))
+}
+
+/// The next card of a practice session
+///
+/// Returns the same card until it is answered, and 204 once no card is left.
+#[utoipa::path(
+    get,
+    path = "/v1/practice/sessions/{session_id}/next",
+    tag = "practice",
+    security(("cookie_auth" = [])),
+    params(("session_id" = Uuid, Path)),
+    responses(
+        (status = 200, description = "The card to practise", body = SessionCard),
+        (status = 204, description = "No card left; the session is completed"),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Session not found or expired", body = ErrorResponse),
+    )
+)]
+async fn next_card(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(session_id): Path<Uuid>,
+) -> Result<Response, ApiError> {
+    let user_id = auth_user.user_id;
+    let mut tx = state.pool.begin().await?;
+    if session_repo::find(&mut *tx, session_id, user_id)
+        .await?
+        .is_none()
+    {
+        return Err(session_not_found());
+    }
+    let card = session_repo::next_card(&mut *tx, session_id, user_id).await?;
+    session_repo::touch(&mut *tx, session_id, SESSION_TTL_HOURS).await?;
+    tx.commit().await?;
+
+    Ok(match card {
+        Some(card) => Json(card).into_response(),
+        None => StatusCode::NO_CONTENT.into_response(),
+    })
+}
+
+/// Answer a card of a practice session
+///
+/// Graded and scheduled like `POST /v1/practice/{flashcard_id}/review` in the
+/// deck the card was drawn from. Each card of the session is answered once;
+/// answering it again is a conflict.
+#[utoipa::path(
+    post,
+    path = "/v1/practice/sessions/{session_id}/answer",
+    tag = "practice",
+    security(("cookie_auth" = [])),
+    params(
+        ("session_id" = Uuid, Path),
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key get the first response again"),
+    ),
+    request_body = SessionAnswer,
+    responses(
+        (status = 200, description = "Answer graded", body = SessionAnswerResponse),
+        (status = 400, description = "Card no longer in the deck, not due, or a mode the deck does not practise", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Session not found or expired", body = ErrorResponse),
+        (status = 409, description = "Card not in the session or already answered", body = ErrorResponse),
+    )
+)]
+async fn submit_answer(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(session_id): Path<Uuid>,
+    Idempotent(idempotency, Json(payload)): Idempotent<Json<SessionAnswer>>,
+) -> Response {
+    idempotency
+        .finish(answer(auth_user.user_id, &state, session_id, payload).await)
+        .await
+}
+
+async fn answer(
+    user_id: Uuid,
+    state: &ApiState,
+    session_id: Uuid,
+    payload: SessionAnswer,
+) -> Result<Json<SessionAnswerResponse>, ApiError> {
+    session_repo::find(&state.pool, session_id, user_id)
+        .await?
+        .ok_or_else(session_not_found)?;
+    let deck_id = session_repo::card_deck(&state.pool, session_id, payload.flashcard_id)
+        .await?
+        .ok_or_else(|| {
+            ApiError::Conflict("This card is not waiting for an answer in this session".to_string())
+        })?;
+
+    let submission = ReviewSubmission {
+        user_answer: payload.user_answer,
+        deck_id,
+        response_time_ms: payload.response_time_ms,
+        elapsed_ms: payload.elapsed_ms,
+        mode: payload.mode,
+    };
+    submission.check_typed()?;
+    let Json(review) = review(
+        user_id,
+        state,
+        payload.flashcard_id,
+        submission,
+        Some(session_id),
+        None,
+    )
+    .await?;
+
+    session_repo::touch(&state.pool, session_id, SESSION_TTL_HOURS).await?;
+    let session = session_repo::find(&state.pool, session_id, user_id)
+        .await?
+        .ok_or_else(session_not_found)?;
+    Ok(Json(SessionAnswerResponse { review, session }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interleave_takes_decks_in_turn() {
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let cards: Vec<Uuid> = (0..6).map(|_| Uuid::new_v4()).collect();
+        let grouped = [
+            (cards[0], a),
+            (cards[1], a),
+            (cards[2], a),
+            (cards[3], b),
+            (cards[4], c),
+            (cards[5], c),
+        ];
+
+        let queue = interleave(&grouped, 10);
+        let order: Vec<Uuid> = queue.iter().map(|&(card, _)| card).collect();
+        assert_eq!(
+            order,
+            [cards[0], cards[3], cards[4], cards[1], cards[5], cards[2]]
+        );
+        assert_eq!(interleave(&grouped, 4).len(), 4);
+    }
+
+    #[test]
+    fn test_interleave_queues_shared_cards_once() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let (shared, only_b) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let queue = interleave(&[(shared, a), (shared, b), (only_b, b)], 10);
+        assert_eq!(queue, [(shared, a), (only_b, b)]);
+    }
+}
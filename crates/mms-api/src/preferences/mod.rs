@@ -0,0 +1,186 @@
+//! User preferences.
+//!
+//! Settings the apps apply on the client: the theme, whether audio plays on its
+//! own, how strictly typed answers are checked, how many new cards and reviews
+//! to serve a day, and the longest interval between reviews, which the server
+//! applies when scheduling. A user who never changed one gets
+//! [`Preferences::default`]; nothing is stored until the first change.
+//! Reminders and the daily goal have their own settings.
+
+pub mod routes;
+
+pub use routes::routes;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use mms_db::models::{NewUserPreferences, UserPreferences};
+use mms_db::repositories::preference as preference_repo;
+
+#[cfg(test)]
+use crate::practice::scheduler::MAX_INTERVAL_DAYS_LIMIT;
+
+/// Largest number of new cards a day accepted
+pub const MAX_NEW_CARDS_PER_DAY: i32 = 500;
+
+/// Largest number of reviews a day accepted
+pub const MAX_REVIEWS_PER_DAY: i32 = 5000;
+
+/// Colour scheme of the apps
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Follow the device
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl Theme {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Theme::System => "system",
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+        }
+    }
+
+    fn parse(theme: &str) -> Option<Self> {
+        match theme {
+            "system" => Some(Theme::System),
+            "light" => Some(Theme::Light),
+            "dark" => Some(Theme::Dark),
+            _ => None,
+        }
+    }
+}
+
+/// How closely a typed answer must match the translation
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AnswerChecking {
+    /// Exactly, accents and case included
+    Strict,
+    /// Ignoring case, whitespace and punctuation
+    #[default]
+    Normal,
+    /// Also ignoring accents and small typos
+    Lenient,
+}
+
+impl AnswerChecking {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AnswerChecking::Strict => "strict",
+            AnswerChecking::Normal => "normal",
+            AnswerChecking::Lenient => "lenient",
+        }
+    }
+
+    fn parse(answer_checking: &str) -> Option<Self> {
+        match answer_checking {
+            "strict" => Some(AnswerChecking::Strict),
+            "normal" => Some(AnswerChecking::Normal),
+            "lenient" => Some(AnswerChecking::Lenient),
+            _ => None,
+        }
+    }
+}
+
+/// The user's preferences
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct Preferences {
+    pub theme: Theme,
+    /// Play a card's audio as soon as it is shown
+    pub audio_autoplay: bool,
+    pub answer_checking: AnswerChecking,
+    /// New cards to introduce a day, 0 to pause new cards
+    pub new_cards_per_day: i32,
+    /// Most reviews to serve a day
+    pub reviews_per_day: i32,
+    /// No review is scheduled further ahead, whatever the deck allows
+    pub max_interval_days: i32,
+    /// When a preference was last changed, `null` while all are the defaults
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            theme: Theme::default(),
+            audio_autoplay: true,
+            answer_checking: AnswerChecking::default(),
+            new_cards_per_day: 20,
+            reviews_per_day: 200,
+            max_interval_days: mms_srs::MAX_INTERVAL_DAYS,
+            updated_at: None,
+        }
+    }
+}
+
+impl From<UserPreferences> for Preferences {
+    fn from(stored: UserPreferences) -> Self {
+        Self {
+            theme: Theme::parse(&stored.theme).unwrap_or_default(),
+            audio_autoplay: stored.audio_autoplay,
+            answer_checking: AnswerChecking::parse(&stored.answer_checking).unwrap_or_default(),
+            new_cards_per_day: stored.new_cards_per_day,
+            reviews_per_day: stored.reviews_per_day,
+            max_interval_days: stored.max_interval_days,
+            updated_at: Some(stored.updated_at),
+        }
+    }
+}
+
+impl Preferences {
+    fn to_new(&self) -> NewUserPreferences<'static> {
+        NewUserPreferences {
+            theme: self.theme.as_str(),
+            audio_autoplay: self.audio_autoplay,
+            answer_checking: self.answer_checking.as_str(),
+            new_cards_per_day: self.new_cards_per_day,
+            reviews_per_day: self.reviews_per_day,
+            max_interval_days: self.max_interval_days,
+        }
+    }
+}
+
+/// The user's preferences, or the defaults while they never changed one
+pub async fn load(pool: &PgPool, user_id: Uuid) -> Result<Preferences, sqlx::Error> {
+    Ok(preference_repo::find(pool, user_id)
+        .await?
+        .map(Preferences::from)
+        .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stored_values_round_trip() {
+        for theme in [Theme::System, Theme::Light, Theme::Dark] {
+            assert_eq!(Theme::parse(theme.as_str()), Some(theme));
+        }
+        for checking in [
+            AnswerChecking::Strict,
+            AnswerChecking::Normal,
+            AnswerChecking::Lenient,
+        ] {
+            assert_eq!(AnswerChecking::parse(checking.as_str()), Some(checking));
+        }
+    }
+
+    #[test]
+    fn test_defaults_fit_the_limits() {
+        let defaults = Preferences::default();
+        assert!((0..=MAX_NEW_CARDS_PER_DAY).contains(&defaults.new_cards_per_day));
+        assert!((1..=MAX_REVIEWS_PER_DAY).contains(&defaults.reviews_per_day));
+        assert!((1..=MAX_INTERVAL_DAYS_LIMIT).contains(&defaults.max_interval_days));
+        assert_eq!(defaults.updated_at, None);
+    }
+}
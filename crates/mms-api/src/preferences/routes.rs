@@ -0,0 +1,131 @@
+use axum::{Json, Router, extract::State, routing::get};
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::{
+    ApiState,
+    auth::AuthUser,
+    error::{ApiError, ErrorResponse},
+    practice::scheduler::MAX_INTERVAL_DAYS_LIMIT,
+    validation::ValidJson,
+};
+
+use mms_db::repositories::preference as preference_repo;
+
+use super::{AnswerChecking, MAX_NEW_CARDS_PER_DAY, MAX_REVIEWS_PER_DAY, Preferences, Theme, load};
+
+/// Create the preferences routes
+pub fn routes() -> Router<ApiState> {
+    Router::new().route(
+        "/users/me/preferences",
+        get(get_preferences).patch(update_preferences),
+    )
+}
+
+/// The signed-in user's preferences
+#[utoipa::path(
+    get,
+    path = "/v1/users/me/preferences",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    responses(
+        (status = 200, description = "Preferences, the defaults for those never changed", body = Preferences),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+async fn get_preferences(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+) -> Result<Json<Preferences>, ApiError> {
+    Ok(Json(load(&state.pool, auth_user.user_id).await?))
+}
+
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+struct UpdatePreferences {
+    #[serde(default)]
+    theme: Option<Theme>,
+    #[serde(default)]
+    audio_autoplay: Option<bool>,
+    #[serde(default)]
+    answer_checking: Option<AnswerChecking>,
+    /// 0 to 500
+    #[serde(default)]
+    #[validate(range(
+        min = 0,
+        max = MAX_NEW_CARDS_PER_DAY,
+        message = "New cards per day must be between 0 and 500"
+    ))]
+    new_cards_per_day: Option<i32>,
+    /// 1 to 5000
+    #[serde(default)]
+    #[validate(range(
+        min = 1,
+        max = MAX_REVIEWS_PER_DAY,
+        message = "Reviews per day must be between 1 and 5000"
+    ))]
+    reviews_per_day: Option<i32>,
+    /// 1 to 365
+    #[serde(default)]
+    #[validate(range(
+        min = 1,
+        max = MAX_INTERVAL_DAYS_LIMIT,
+        message = "Max interval days must be between 1 and 365"
+    ))]
+    max_interval_days: Option<i32>,
+}
+
+/// Change some of the signed-in user's preferences; omitted fields keep their value
+#[utoipa::path(
+    patch,
+    path = "/v1/users/me/preferences",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    request_body = UpdatePreferences,
+    responses(
+        (status = 200, description = "Preferences updated", body = Preferences),
+        (status = 400, description = "Unknown value or limit out of range", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
+async fn update_preferences(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    ValidJson(payload): ValidJson<UpdatePreferences>,
+) -> Result<Json<Preferences>, ApiError> {
+    let user_id = auth_user.user_id;
+
+    let mut tx = state.pool.begin().await?;
+    if !preference_repo::lock_user(&mut *tx, user_id).await? {
+        return Err(ApiError::NotFound("User not found".to_string()));
+    }
+    let mut preferences = preference_repo::find(&mut *tx, user_id)
+        .await?
+        .map(Preferences::from)
+        .unwrap_or_default();
+
+    if let Some(theme) = payload.theme {
+        preferences.theme = theme;
+    }
+    if let Some(audio_autoplay) = payload.audio_autoplay {
+        preferences.audio_autoplay = audio_autoplay;
+    }
+    if let Some(answer_checking) = payload.answer_checking {
+        preferences.answer_checking = answer_checking;
+    }
+    if let Some(new_cards_per_day) = payload.new_cards_per_day {
+        preferences.new_cards_per_day = new_cards_per_day;
+    }
+    if let Some(reviews_per_day) = payload.reviews_per_day {
+        preferences.reviews_per_day = reviews_per_day;
+    }
+    if let Some(max_interval_days) = payload.max_interval_days {
+        preferences.max_interval_days = max_interval_days;
+    }
+
+    let saved = preference_repo::save(&mut *tx, user_id, &preferences.to_new()).await?;
+    tx.commit().await?;
+
+    Ok(Json(Preferences::from(saved)))
+}
@@ -0,0 +1,235 @@
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{get, patch},
+};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+use utoipa::{IntoParams, ToSchema};
+use validator::{Validate, ValidationError};
+
+use crate::{
+    ApiState,
+    auth::AuthUser,
+    deck::routes::{DEFAULT_PRACTICE_LIMIT, MAX_PRACTICE_LIMIT},
+    error::{ApiError, ErrorResponse},
+    validation::{ValidJson, invalid, language_code},
+};
+
+use mms_db::models::{DueCard, LearningProfile};
+use mms_db::repositories::profile as profile_repo;
+
+/// Create the learning profile routes
+pub fn routes() -> Router<ApiState> {
+    Router::new()
+        .route(
+            "/users/me/profiles",
+            get(list_profiles).post(create_profile),
+        )
+        .route(
+            "/users/me/profiles/{profile_id}",
+            patch(update_profile).delete(delete_profile),
+        )
+        .route("/users/me/profiles/{profile_id}/due", get(get_due_cards))
+}
+
+fn validate_session_size(session_size: i32) -> Result<(), ValidationError> {
+    if !(1..=MAX_PRACTICE_LIMIT).contains(&i64::from(session_size)) {
+        return Err(invalid(
+            "range",
+            format!("Session size must be between 1 and {MAX_PRACTICE_LIMIT}"),
+        ));
+    }
+    Ok(())
+}
+
+/// The signed-in user's learning profiles, oldest first
+#[utoipa::path(
+    get,
+    path = "/v1/users/me/profiles",
+    tag = "profiles",
+    security(("cookie_auth" = [])),
+    responses(
+        (status = 200, description = "Learning profiles", body = Vec<LearningProfile>),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+async fn list_profiles(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+) -> Result<Json<Vec<LearningProfile>>, ApiError> {
+    let profiles = profile_repo::list_for_user(&state.pool, auth_user.user_id).await?;
+    Ok(Json(profiles))
+}
+
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+struct CreateProfileRequest {
+    #[validate(custom(function = "language_code"))]
+    native_language: String,
+    #[validate(custom(function = "language_code"))]
+    learning_language: String,
+    /// Cards per practice session, 1 to 50 (default 20)
+    #[serde(default)]
+    #[validate(custom(function = "validate_session_size"))]
+    session_size: Option<i32>,
+}
+
+/// Start studying a new language pair
+#[utoipa::path(
+    post,
+    path = "/v1/users/me/profiles",
+    tag = "profiles",
+    security(("cookie_auth" = [])),
+    request_body = CreateProfileRequest,
+    responses(
+        (status = 201, description = "Profile created", body = LearningProfile),
+        (status = 400, description = "Unsupported language code or session size", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 409, description = "A profile for this language pair already exists", body = ErrorResponse),
+    )
+)]
+async fn create_profile(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    ValidJson(payload): ValidJson<CreateProfileRequest>,
+) -> Result<(StatusCode, Json<LearningProfile>), ApiError> {
+    let native_language = payload.native_language.to_lowercase();
+    let learning_language = payload.learning_language.to_lowercase();
+    if native_language == learning_language {
+        return Err(ApiError::field(
+            "learning_language",
+            "Native and learning language must differ",
+        ));
+    }
+
+    let session_size = payload
+        .session_size
+        .unwrap_or(DEFAULT_PRACTICE_LIMIT as i32);
+
+    let profile = profile_repo::create(
+        &state.pool,
+        auth_user.user_id,
+        &native_language,
+        &learning_language,
+        session_size,
+    )
+    .await?
+    .ok_or_else(|| {
+        ApiError::Conflict("A learning profile for this language pair already exists".to_string())
+    })?;
+
+    Ok((StatusCode::CREATED, Json(profile)))
+}
+
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+struct UpdateProfileRequest {
+    /// Cards per practice session, 1 to 50
+    #[validate(custom(function = "validate_session_size"))]
+    session_size: i32,
+}
+
+/// Change a learning profile's settings
+#[utoipa::path(
+    patch,
+    path = "/v1/users/me/profiles/{profile_id}",
+    tag = "profiles",
+    security(("cookie_auth" = [])),
+    params(("profile_id" = Uuid, Path)),
+    request_body = UpdateProfileRequest,
+    responses(
+        (status = 200, description = "Profile updated", body = LearningProfile),
+        (status = 400, description = "Invalid session size", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Profile not found", body = ErrorResponse),
+    )
+)]
+async fn update_profile(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(profile_id): Path<Uuid>,
+    ValidJson(payload): ValidJson<UpdateProfileRequest>,
+) -> Result<Json<LearningProfile>, ApiError> {
+    let profile = profile_repo::update_settings(
+        &state.pool,
+        auth_user.user_id,
+        profile_id,
+        payload.session_size,
+    )
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Learning profile not found".to_string()))?;
+
+    Ok(Json(profile))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct DeleteProfileResponse {
+    message: String,
+}
+
+/// Delete a learning profile and its activity history; card progress is kept
+#[utoipa::path(
+    delete,
+    path = "/v1/users/me/profiles/{profile_id}",
+    tag = "profiles",
+    security(("cookie_auth" = [])),
+    params(("profile_id" = Uuid, Path)),
+    responses(
+        (status = 200, description = "Profile deleted", body = DeleteProfileResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Profile not found", body = ErrorResponse),
+    )
+)]
+async fn delete_profile(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(profile_id): Path<Uuid>,
+) -> Result<Json<DeleteProfileResponse>, ApiError> {
+    if !profile_repo::delete(&state.pool, auth_user.user_id, profile_id).await? {
+        return Err(ApiError::NotFound("Learning profile not found".to_string()));
+    }
+
+    Ok(Json(DeleteProfileResponse {
+        message: "Learning profile deleted".to_string(),
+    }))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct DueQuery {
+    /// Cards to return, 1 to 50 (default: the profile's session size)
+    #[serde(default)]
+    limit: Option<i64>,
+}
+
+/// The profile's due queue: reviewed cards in its language pair across all decks
+#[utoipa::path(
+    get,
+    path = "/v1/users/me/profiles/{profile_id}/due",
+    tag = "profiles",
+    security(("cookie_auth" = [])),
+    params(("profile_id" = Uuid, Path), DueQuery),
+    responses(
+        (status = 200, description = "Due cards, most overdue first", body = Vec<DueCard>),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Profile not found", body = ErrorResponse),
+    )
+)]
+async fn get_due_cards(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(profile_id): Path<Uuid>,
+    Query(query): Query<DueQuery>,
+) -> Result<Json<Vec<DueCard>>, ApiError> {
+    let profile = profile_repo::find_for_user(&state.pool, auth_user.user_id, profile_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Learning profile not found".to_string()))?;
+
+    let limit = query
+        .limit
+        .unwrap_or(i64::from(profile.session_size))
+        .clamp(1, MAX_PRACTICE_LIMIT);
+
+    let cards = profile_repo::get_due_cards(&state.pool, profile.id, limit).await?;
+    Ok(Json(cards))
+}
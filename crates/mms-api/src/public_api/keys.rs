@@ -0,0 +1,203 @@
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{delete, get},
+};
+use chrono::{Days, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+use crate::{
+    ApiState,
+    auth::AuthUser,
+    error::{ApiError, ErrorResponse},
+    token_service::hash_token,
+    validation::{ValidJson, not_blank},
+};
+
+use mms_db::models::{ApiKey, ApiKeyUsage};
+use mms_db::repositories::api_key as api_key_repo;
+
+use super::{DEFAULT_DAILY_QUOTA, MAX_KEYS_PER_USER, generate_key};
+
+/// Days of usage reported when no `days` is given
+const DEFAULT_USAGE_DAYS: u64 = 30;
+
+/// Days of usage reported at most
+const MAX_USAGE_DAYS: u64 = 90;
+
+/// Create the API key management routes
+pub fn routes() -> Router<ApiState> {
+    Router::new()
+        .route(
+            "/users/me/api-keys",
+            get(list_api_keys).post(create_api_key),
+        )
+        .route("/users/me/api-keys/{api_key_id}", delete(revoke_api_key))
+        .route("/users/me/api-keys/{api_key_id}/usage", get(get_usage))
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+struct CreateApiKeyRequest {
+    /// What the key is for, 1 to 100 characters
+    #[validate(
+        length(min = 1, max = 100, message = "Name must be 1 to 100 characters"),
+        custom(function = "not_blank", message = "Name must be 1 to 100 characters")
+    )]
+    name: String,
+}
+
+#[derive(Serialize, ToSchema)]
+struct CreatedApiKey {
+    api_key: ApiKey,
+    /// The key itself; it is not shown again
+    key: String,
+}
+
+/// Create an API key for the public catalogue API
+#[utoipa::path(
+    post,
+    path = "/v1/users/me/api-keys",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 201, description = "Key created; copy it now", body = CreatedApiKey),
+        (status = 400, description = "Invalid name", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 409, description = "Too many active keys", body = ErrorResponse),
+    )
+)]
+async fn create_api_key(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    ValidJson(request): ValidJson<CreateApiKeyRequest>,
+) -> Result<(StatusCode, Json<CreatedApiKey>), ApiError> {
+    let name = request.name.trim();
+
+    // Count and insert under the user's lock so concurrent requests cannot pass the limit
+    let mut tx = state.pool.begin().await?;
+    if !api_key_repo::lock_user(&mut *tx, auth_user.user_id).await? {
+        return Err(ApiError::NotFound("User not found".to_string()));
+    }
+    if api_key_repo::count_active(&mut *tx, auth_user.user_id).await? >= MAX_KEYS_PER_USER {
+        return Err(ApiError::Conflict(format!(
+            "You already have {MAX_KEYS_PER_USER} API keys; revoke one first"
+        )));
+    }
+
+    let new_key = generate_key();
+    let api_key = api_key_repo::create(
+        &mut *tx,
+        auth_user.user_id,
+        name,
+        &new_key.key_prefix,
+        &hash_token(&new_key.key),
+        DEFAULT_DAILY_QUOTA,
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedApiKey {
+            api_key,
+            key: new_key.key,
+        }),
+    ))
+}
+
+/// The signed-in user's API keys, revoked ones included, newest first
+#[utoipa::path(
+    get,
+    path = "/v1/users/me/api-keys",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    responses(
+        (status = 200, description = "API keys", body = Vec<ApiKey>),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+async fn list_api_keys(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+) -> Result<Json<Vec<ApiKey>>, ApiError> {
+    Ok(Json(
+        api_key_repo::list(&state.pool, auth_user.user_id).await?,
+    ))
+}
+
+/// Revoke an API key; requests made with it are refused from now on
+#[utoipa::path(
+    delete,
+    path = "/v1/users/me/api-keys/{api_key_id}",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    params(("api_key_id" = Uuid, Path)),
+    responses(
+        (status = 204, description = "Key revoked"),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "No such key, or already revoked", body = ErrorResponse),
+    )
+)]
+async fn revoke_api_key(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(api_key_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    if !api_key_repo::revoke(&state.pool, auth_user.user_id, api_key_id).await? {
+        return Err(ApiError::NotFound("API key not found".to_string()));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct UsageQuery {
+    /// Days to report, today included, 1 to 90 (default 30)
+    #[serde(default)]
+    days: Option<u64>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct ApiKeyUsageReport {
+    api_key: ApiKey,
+    /// Requests per UTC day, oldest first; days without requests are left out
+    days: Vec<ApiKeyUsage>,
+}
+
+/// Requests made with one of the signed-in user's API keys, per day
+#[utoipa::path(
+    get,
+    path = "/v1/users/me/api-keys/{api_key_id}/usage",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    params(("api_key_id" = Uuid, Path), UsageQuery),
+    responses(
+        (status = 200, description = "Usage per day", body = ApiKeyUsageReport),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "No such key", body = ErrorResponse),
+    )
+)]
+async fn get_usage(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(api_key_id): Path<Uuid>,
+    Query(query): Query<UsageQuery>,
+) -> Result<Json<ApiKeyUsageReport>, ApiError> {
+    let api_key = api_key_repo::find(&state.pool, auth_user.user_id, api_key_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("API key not found".to_string()))?;
+
+    let days = query
+        .days
+        .unwrap_or(DEFAULT_USAGE_DAYS)
+        .clamp(1, MAX_USAGE_DAYS);
+    let today = Utc::now().date_naive();
+    let since = today.checked_sub_days(Days::new(days - 1)).unwrap_or(today);
+    let days = api_key_repo::usage(&state.pool, api_key_id, since).await?;
+    Ok(Json(ApiKeyUsageReport { api_key, days }))
+}
@@ -0,0 +1,217 @@
+//! Read-only catalogue for integrators under `/v1/public`, authorised by API keys.
+//!
+//! Users create keys under `/v1/users/me/api-keys` (see [`keys`]); a key is
+//! shown once and only its hash is stored. Requests send it in the `X-API-Key`
+//! header. Each key has a daily quota, counted per UTC day in Postgres so every
+//! replica sees the same count; the counts double as the key's usage report.
+//! Every response carries the quota headers, and once the quota is used up
+//! requests get 429 until midnight UTC.
+
+pub mod keys;
+pub mod routes;
+
+use axum::{
+    extract::FromRequestParts,
+    http::{HeaderName, HeaderValue, StatusCode, header::RETRY_AFTER, request::Parts},
+    response::{IntoResponse, IntoResponseParts, Response, ResponseParts},
+};
+use chrono::{DateTime, Days, Utc};
+use sqlx::types::Uuid;
+
+use crate::{
+    ApiState,
+    error::{ApiError, ErrorCode, ErrorResponse},
+    token_service::{generate_token, hash_token},
+};
+
+use mms_db::repositories::api_key as api_key_repo;
+
+pub use routes::routes;
+
+/// Header carrying the API key
+pub const API_KEY_HEADER: HeaderName = HeaderName::from_static("x-api-key");
+
+/// Daily quota of new keys; operators can change a key's quota
+pub const DEFAULT_DAILY_QUOTA: i32 = 1000;
+
+/// Largest daily quota an operator can give a key
+pub const MAX_DAILY_QUOTA: i32 = 1_000_000;
+
+/// Keys a user may have at once, revoked ones not counted
+pub const MAX_KEYS_PER_USER: i64 = 10;
+
+/// Every key starts with this, so leaked keys are easy to recognise
+const KEY_PREFIX: &str = "mms_";
+
+/// Characters of a key kept in the clear to tell keys apart
+const DISPLAYED_KEY_CHARS: usize = 12;
+
+const X_QUOTA_LIMIT: HeaderName = HeaderName::from_static("x-quota-limit");
+const X_QUOTA_REMAINING: HeaderName = HeaderName::from_static("x-quota-remaining");
+const X_QUOTA_RESET: HeaderName = HeaderName::from_static("x-quota-reset");
+
+/// A new key and the part of it that is stored in the clear
+pub struct NewKey {
+    pub key: String,
+    pub key_prefix: String,
+}
+
+/// Generate a key for the owner to copy
+#[must_use]
+pub fn generate_key() -> NewKey {
+    let key = format!("{KEY_PREFIX}{}", generate_token());
+    NewKey {
+        key_prefix: key[..DISPLAYED_KEY_CHARS].to_string(),
+        key,
+    }
+}
+
+/// Where a key stands against its daily quota, sent back as `X-Quota-*` headers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    /// Requests allowed per UTC day
+    pub limit: i32,
+    /// Requests served today, this one included
+    pub used: i32,
+    /// Seconds until the count starts over at midnight UTC
+    pub reset_secs: i64,
+}
+
+impl Quota {
+    fn remaining(self) -> i32 {
+        (self.limit - self.used).max(0)
+    }
+}
+
+impl IntoResponseParts for Quota {
+    type Error = std::convert::Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        let headers = res.headers_mut();
+        headers.insert(X_QUOTA_LIMIT, HeaderValue::from(self.limit));
+        headers.insert(X_QUOTA_REMAINING, HeaderValue::from(self.remaining()));
+        headers.insert(X_QUOTA_RESET, HeaderValue::from(self.reset_secs));
+        Ok(res)
+    }
+}
+
+/// Seconds from `now` until the next midnight UTC
+fn secs_until_midnight(now: DateTime<Utc>) -> i64 {
+    let midnight = now
+        .date_naive()
+        .checked_add_days(Days::new(1))
+        .and_then(|day| day.and_hms_opt(0, 0, 0))
+        .map(|midnight| midnight.and_utc());
+    midnight.map_or(0, |midnight| (midnight - now).num_seconds().max(1))
+}
+
+/// The response once a key's quota is used up
+fn quota_exceeded(quota: Quota) -> Response {
+    let message = format!(
+        "Daily quota of {} requests used up; it resets at midnight UTC",
+        quota.limit
+    );
+    let mut response = (
+        quota,
+        ErrorResponse::new(ErrorCode::RateLimited, message)
+            .into_response_with(StatusCode::TOO_MANY_REQUESTS),
+    )
+        .into_response();
+    response
+        .headers_mut()
+        .insert(RETRY_AFTER, HeaderValue::from(quota.reset_secs));
+    response
+}
+
+/// Extractor admitting requests with a valid API key that is within its quota.
+///
+/// Each request is counted against the key, refused ones included. Send
+/// [`ApiKeyAccess::quota`] back with the response so clients can pace themselves.
+pub struct ApiKeyAccess {
+    pub api_key_id: Uuid,
+    pub quota: Quota,
+}
+
+impl FromRequestParts<ApiState> for ApiKeyAccess {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &ApiState,
+    ) -> Result<Self, Self::Rejection> {
+        let invalid = || ApiError::Auth("Invalid or revoked API key".to_string()).into_response();
+
+        let key = parts
+            .headers
+            .get(&API_KEY_HEADER)
+            .ok_or_else(|| ApiError::Auth("Missing X-API-Key header".to_string()).into_response())?
+            .to_str()
+            .map_err(|_| invalid())?;
+        if !key.starts_with(KEY_PREFIX) {
+            return Err(invalid());
+        }
+
+        let now = Utc::now();
+        let day = now.date_naive();
+        let request = api_key_repo::record_request(&state.pool, &hash_token(key), day)
+            .await
+            .map_err(|e| ApiError::Database(e).into_response())?
+            .ok_or_else(invalid)?;
+
+        let served = request.requests - request.rejected;
+        let quota = Quota {
+            limit: request.daily_quota,
+            used: served.min(request.daily_quota),
+            reset_secs: secs_until_midnight(now),
+        };
+        if served > request.daily_quota {
+            api_key_repo::record_rejection(&state.pool, request.api_key_id, day)
+                .await
+                .map_err(|e| ApiError::Database(e).into_response())?;
+            return Err(quota_exceeded(quota));
+        }
+
+        Ok(Self {
+            api_key_id: request.api_key_id,
+            quota,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_generated_keys() {
+        let new_key = generate_key();
+        assert!(new_key.key.starts_with(KEY_PREFIX));
+        assert!(new_key.key.len() > 60);
+        assert_eq!(new_key.key_prefix.len(), DISPLAYED_KEY_CHARS);
+        assert!(new_key.key.starts_with(&new_key.key_prefix));
+        assert_ne!(generate_key().key, new_key.key);
+    }
+
+    #[test]
+    fn test_quota_resets_at_midnight_utc() {
+        let evening = Utc.with_ymd_and_hms(2026, 3, 31, 23, 0, 0).unwrap();
+        assert_eq!(secs_until_midnight(evening), 3600);
+        let midnight = Utc.with_ymd_and_hms(2026, 4, 1, 0, 0, 0).unwrap();
+        assert_eq!(secs_until_midnight(midnight), 86_400);
+    }
+
+    #[test]
+    fn test_quota_exceeded_response() {
+        let response = quota_exceeded(Quota {
+            limit: 5,
+            used: 5,
+            reset_secs: 120,
+        });
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()["retry-after"], "120");
+        assert_eq!(response.headers()["x-quota-limit"], "5");
+        assert_eq!(response.headers()["x-quota-remaining"], "0");
+        assert_eq!(response.headers()["x-quota-reset"], "120");
+    }
+}
@@ -0,0 +1,143 @@
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    routing::get,
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::{
+    ApiState,
+    deck::routes::CatalogueSort,
+    error::{ApiError, ErrorResponse},
+    make_rate_limit_layer,
+    middleware::rate_limit,
+    validation,
+};
+
+use mms_db::models::{CatalogDeck, CatalogRoadmap};
+use mms_db::repositories::deck as deck_repo;
+use mms_db::repositories::roadmap as roadmap_repo;
+
+use super::{ApiKeyAccess, Quota};
+
+const DEFAULT_PAGE_LIMIT: i64 = 50;
+const MAX_PAGE_LIMIT: i64 = 100;
+
+/// Create the public API routes and the routes managing its keys
+pub fn routes() -> Router<ApiState> {
+    Router::new()
+        .route("/public/roadmaps", get(list_roadmaps))
+        .route("/public/decks", get(list_decks))
+        .layer(make_rate_limit_layer!(
+            rate_limit::GENERAL_RATE_PER_SECOND,
+            rate_limit::GENERAL_BURST_SIZE
+        ))
+        .merge(super::keys::routes())
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct RoadmapCatalogQuery {
+    /// Only roadmaps for learners of this language; requires `language_to`
+    #[serde(default)]
+    language_from: Option<String>,
+    /// Only roadmaps teaching this language; requires `language_from`
+    #[serde(default)]
+    language_to: Option<String>,
+    /// Page size, 1 to 100 (default 50)
+    #[serde(default)]
+    limit: Option<i64>,
+    /// Roadmaps to skip (default 0)
+    #[serde(default)]
+    offset: Option<i64>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct DeckCatalogQuery {
+    /// `rating` (default), `newest` or `title`
+    #[serde(default)]
+    #[param(inline)]
+    sort: CatalogueSort,
+    /// Only decks for learners of this language; requires `language_to`
+    #[serde(default)]
+    language_from: Option<String>,
+    /// Only decks teaching this language; requires `language_from`
+    #[serde(default)]
+    language_to: Option<String>,
+    /// Page size, 1 to 100 (default 50)
+    #[serde(default)]
+    limit: Option<i64>,
+    /// Decks to skip (default 0)
+    #[serde(default)]
+    offset: Option<i64>,
+}
+
+fn page(limit: Option<i64>, offset: Option<i64>) -> (i64, i64) {
+    (
+        limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_PAGE_LIMIT),
+        offset.unwrap_or(0).max(0),
+    )
+}
+
+/// Public roadmaps with how many decks and cards each has, newest first
+#[utoipa::path(
+    get,
+    path = "/v1/public/roadmaps",
+    tag = "public",
+    security(("api_key" = [])),
+    params(RoadmapCatalogQuery),
+    responses(
+        (status = 200, description = "One page of roadmaps", body = Vec<CatalogRoadmap>),
+        (status = 400, description = "Unsupported language code or only one of the pair", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or revoked API key", body = ErrorResponse),
+        (status = 429, description = "Daily quota used up", body = ErrorResponse),
+    )
+)]
+async fn list_roadmaps(
+    access: ApiKeyAccess,
+    State(state): State<ApiState>,
+    Query(query): Query<RoadmapCatalogQuery>,
+) -> Result<(Quota, Json<Vec<CatalogRoadmap>>), ApiError> {
+    let languages =
+        validation::language_pair(query.language_from.as_deref(), query.language_to.as_deref())?;
+    let (limit, offset) = page(query.limit, query.offset);
+
+    let roadmaps = roadmap_repo::list_catalog(&state.read_pool, languages, limit, offset).await?;
+    Ok((access.quota, Json(roadmaps)))
+}
+
+/// Decks in the public catalogue with their card counts and ratings
+#[utoipa::path(
+    get,
+    path = "/v1/public/decks",
+    tag = "public",
+    security(("api_key" = [])),
+    params(DeckCatalogQuery),
+    responses(
+        (status = 200, description = "One page of public decks", body = Vec<CatalogDeck>),
+        (status = 400, description = "Unsupported language code or only one of the pair", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or revoked API key", body = ErrorResponse),
+        (status = 429, description = "Daily quota used up", body = ErrorResponse),
+    )
+)]
+async fn list_decks(
+    access: ApiKeyAccess,
+    State(state): State<ApiState>,
+    Query(query): Query<DeckCatalogQuery>,
+) -> Result<(Quota, Json<Vec<CatalogDeck>>), ApiError> {
+    let languages =
+        validation::language_pair(query.language_from.as_deref(), query.language_to.as_deref())?;
+    let (limit, offset) = page(query.limit, query.offset);
+
+    let decks = deck_repo::list_catalog(
+        &state.read_pool,
+        languages,
+        query.sort.into(),
+        limit,
+        offset,
+    )
+    .await?;
+    Ok((access.quota, Json(decks)))
+}
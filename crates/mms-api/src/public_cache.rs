@@ -0,0 +1,214 @@
+//! Last-known-good copies of public listings, served while Postgres is unreachable.
+//!
+//! Public read handlers go through [`PublicCache::fetch`]: every successful
+//! response is remembered under a key for the request, and when the database
+//! cannot be reached the remembered copy is served with a `Warning` header
+//! instead of a 500. Only connection failures fall back; query errors and 404s
+//! pass through. The cache lives in memory, so each instance only holds what it
+//! has served since it started.
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{
+        Arc, RwLock,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use axum::{
+    Json,
+    body::Bytes,
+    http::{HeaderValue, header},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+
+use crate::{error::ApiError, metrics};
+
+/// Responses remembered at most; once full, only existing keys are refreshed
+const MAX_ENTRIES: usize = 1_000;
+
+/// RFC 7234 warning attached to responses served from the cache
+const STALE_WARNING: &str = "111 - \"Revalidation Failed\"";
+
+/// Shared cache of public responses plus whether the database was last seen unreachable
+#[derive(Debug, Clone, Default)]
+pub struct PublicCache(Arc<Inner>);
+
+#[derive(Debug, Default)]
+struct Inner {
+    entries: RwLock<HashMap<String, Bytes>>,
+    degraded: AtomicBool,
+}
+
+impl PublicCache {
+    /// Run `fetch` and remember its JSON under `key`; if the database is
+    /// unreachable, serve the last copy remembered under `key` instead
+    pub async fn fetch<T, F>(&self, key: String, fetch: F) -> Result<Response, ApiError>
+    where
+        T: Serialize,
+        F: Future<Output = Result<T, ApiError>>,
+    {
+        match fetch.await {
+            Ok(value) => {
+                self.mark_available();
+                metrics::record_public_cache("fresh");
+                let Ok(body) = serde_json::to_vec(&value) else {
+                    // Let Json report the serialization failure
+                    return Ok(Json(value).into_response());
+                };
+                let body = Bytes::from(body);
+                self.insert(key, body.clone());
+                Ok(json_response(body))
+            }
+            Err(ApiError::Database(e)) if is_unavailable(&e) => {
+                self.mark_unavailable();
+                match self.get(&key) {
+                    Some(body) => {
+                        metrics::record_public_cache("stale");
+                        tracing::warn!(error = %e, key = %key, "Database unreachable, serving cached response");
+                        let mut response = json_response(body);
+                        response
+                            .headers_mut()
+                            .insert(header::WARNING, HeaderValue::from_static(STALE_WARNING));
+                        Ok(response)
+                    }
+                    None => {
+                        metrics::record_public_cache("miss");
+                        Err(ApiError::Database(e))
+                    }
+                }
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Whether the last database access failed to connect
+    #[must_use]
+    pub fn is_degraded(&self) -> bool {
+        self.0.degraded.load(Ordering::Relaxed)
+    }
+
+    /// Whether any response can be served while the database is unreachable
+    #[must_use]
+    pub fn has_entries(&self) -> bool {
+        !self.read().is_empty()
+    }
+
+    pub fn mark_available(&self) {
+        if self.0.degraded.swap(false, Ordering::Relaxed) {
+            tracing::info!("Database reachable again, leaving degraded mode");
+        }
+    }
+
+    pub fn mark_unavailable(&self) {
+        if !self.0.degraded.swap(true, Ordering::Relaxed) {
+            tracing::warn!("Database unreachable, serving public content from cache");
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<Bytes> {
+        self.read().get(key).cloned()
+    }
+
+    fn insert(&self, key: String, body: Bytes) {
+        let mut entries = self
+            .0
+            .entries
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if entries.len() < MAX_ENTRIES || entries.contains_key(&key) {
+            entries.insert(key, body);
+        }
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, HashMap<String, Bytes>> {
+        self.0
+            .entries
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Errors meaning the database could not be reached, as opposed to a failed query
+#[must_use]
+pub fn is_unavailable(error: &sqlx::Error) -> bool {
+    matches!(
+        error,
+        sqlx::Error::PoolTimedOut
+            | sqlx::Error::PoolClosed
+            | sqlx::Error::Io(_)
+            | sqlx::Error::Tls(_)
+            | sqlx::Error::WorkerCrashed
+    )
+}
+
+fn json_response(body: Bytes) -> Response {
+    (
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        )],
+        body,
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+
+    fn unavailable() -> Result<Vec<i32>, ApiError> {
+        Err(ApiError::Database(sqlx::Error::PoolTimedOut))
+    }
+
+    #[tokio::test]
+    async fn test_serves_last_copy_while_unavailable() {
+        let cache = PublicCache::default();
+
+        let fresh = cache
+            .fetch("list".into(), async { Ok(vec![1, 2]) })
+            .await
+            .unwrap();
+        assert!(fresh.headers().get(header::WARNING).is_none());
+        assert!(!cache.is_degraded());
+
+        let stale = cache
+            .fetch("list".into(), async { unavailable() })
+            .await
+            .unwrap();
+        assert_eq!(stale.status(), StatusCode::OK);
+        assert_eq!(stale.headers()[header::WARNING], STALE_WARNING);
+        assert!(cache.is_degraded());
+
+        cache
+            .fetch("list".into(), async { Ok(vec![3]) })
+            .await
+            .unwrap();
+        assert!(!cache.is_degraded());
+    }
+
+    #[tokio::test]
+    async fn test_other_errors_and_unknown_keys_pass_through() {
+        let cache = PublicCache::default();
+        cache
+            .fetch("list".into(), async { Ok(vec![1]) })
+            .await
+            .unwrap();
+
+        let missing = cache.fetch("other".into(), async { unavailable() }).await;
+        assert!(matches!(missing, Err(ApiError::Database(_))));
+
+        let not_found = cache
+            .fetch("list".into(), async {
+                Err::<Vec<i32>, _>(ApiError::Database(sqlx::Error::RowNotFound))
+            })
+            .await;
+        assert!(matches!(
+            not_found,
+            Err(ApiError::Database(sqlx::Error::RowNotFound))
+        ));
+    }
+}
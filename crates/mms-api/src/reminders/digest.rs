@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use tokio::sync::mpsc;
+
+use mms_db::repositories::user as user_repo;
+
+use crate::{auth::jwt::JwtKeys, email_preferences::token, user::email::EmailJob};
+
+/// Local hour on Monday from which the digest is sent
+pub const DIGEST_HOUR: i32 = 8;
+
+/// Users claimed and queued at a time
+const BATCH_SIZE: i64 = 100;
+
+/// Digests queued in one run; the rest are picked up by the next run
+const MAX_PER_RUN: usize = 5_000;
+
+/// Queue the weekly digest for every user whose digest is due at `now`; returns how many were queued.
+///
+/// Each email links to the user's email preferences with a token signed by
+/// `keys`, the feed token keys, so they can unsubscribe without signing in. `now` is a parameter so tests can
+/// pick the local day and hour. Users are marked as sent when claimed, so a
+/// digest that fails to send is not retried that week.
+pub async fn send_weekly_digests(
+    pool: &PgPool,
+    email_tx: &mpsc::UnboundedSender<EmailJob>,
+    keys: &JwtKeys,
+    now: DateTime<Utc>,
+) -> Result<usize, sqlx::Error> {
+    let mut queued = 0;
+
+    while queued < MAX_PER_RUN {
+        let digests = user_repo::claim_weekly_digests(pool, now, DIGEST_HOUR, BATCH_SIZE).await?;
+        let claimed = digests.len();
+
+        for digest in digests {
+            let user_id = digest.user_id;
+            let preferences_token = match token::issue(user_id, keys) {
+                Ok(token) => token,
+                Err(e) => {
+                    tracing::error!(error = %e, user_id = %user_id, "Failed to sign email preferences token");
+                    continue;
+                }
+            };
+            let job = EmailJob::WeeklyDigest {
+                digest: Box::new(digest),
+                preferences_token,
+            };
+            if let Err(e) = email_tx.send(job) {
+                tracing::error!(error = %e, user_id = %user_id, "Failed to queue weekly digest");
+                continue;
+            }
+            queued += 1;
+        }
+
+        if (claimed as i64) < BATCH_SIZE {
+            break;
+        }
+    }
+
+    Ok(queued)
+}
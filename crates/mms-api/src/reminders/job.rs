@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use sqlx::PgPool;
+use tokio::sync::mpsc;
+
+use mms_db::repositories::user as user_repo;
+
+use crate::user::email::EmailJob;
+
+/// Users claimed and queued at a time
+const BATCH_SIZE: i64 = 100;
+
+/// Pause between full batches so a busy hour does not flood the SMTP relay
+const BATCH_PAUSE: Duration = Duration::from_secs(5);
+
+/// Reminders queued in one run; the rest are picked up by the next run within the hour
+const MAX_PER_RUN: usize = 5_000;
+
+/// Queue reminder emails for every user whose reminder is due; returns how many were queued.
+///
+/// Users are marked as reminded when claimed, so a reminder that fails to send
+/// is not retried the same day.
+pub async fn send_due_reminders(
+    pool: &PgPool,
+    email_tx: &mpsc::UnboundedSender<EmailJob>,
+) -> Result<usize, sqlx::Error> {
+    let mut queued = 0;
+
+    while queued < MAX_PER_RUN {
+        let recipients = user_repo::claim_due_reminders(pool, BATCH_SIZE).await?;
+        let claimed = recipients.len();
+
+        for recipient in recipients {
+            let job = EmailJob::ReviewReminder {
+                to_email: recipient.email,
+                username: recipient.username,
+                cards_due: recipient.cards_due,
+            };
+            if let Err(e) = email_tx.send(job) {
+                tracing::error!(error = %e, user_id = %recipient.user_id, "Failed to queue review reminder");
+                continue;
+            }
+            queued += 1;
+        }
+
+        if (claimed as i64) < BATCH_SIZE {
+            break;
+        }
+        tokio::time::sleep(BATCH_PAUSE).await;
+    }
+
+    Ok(queued)
+}
@@ -0,0 +1,16 @@
+//! Daily review reminder emails, streak-at-risk reminders and the weekly digest.
+//!
+//! Users opt in and pick a local hour; a background job (see [`crate::jobs`])
+//! calls [`job::send_due_reminders`] every few minutes and emails those whose
+//! hour has come and who have cards due. Another job calls
+//! [`streak::send_streak_reminders`] to warn users, in the evening, that the
+//! streak they kept yesterday ends at local midnight unless they review, and
+//! [`digest::send_weekly_digests`] sends a summary of the past week on Monday
+//! morning.
+
+pub mod digest;
+pub mod job;
+pub mod routes;
+pub mod streak;
+
+pub use routes::routes;
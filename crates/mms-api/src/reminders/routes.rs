@@ -0,0 +1,102 @@
+use axum::{Json, Router, extract::State, routing::get};
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::{
+    ApiState,
+    auth::AuthUser,
+    error::{ApiError, ErrorResponse},
+    validation::{ValidJson, validate_timezone},
+};
+
+use mms_db::models::ReminderSettings;
+use mms_db::repositories::user as user_repo;
+
+/// Create the reminder settings routes
+pub fn routes() -> Router<ApiState> {
+    Router::new().route(
+        "/users/me/reminders",
+        get(get_reminder_settings).patch(update_reminder_settings),
+    )
+}
+
+/// The signed-in user's daily reminder and streak reminder settings
+#[utoipa::path(
+    get,
+    path = "/v1/users/me/reminders",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    responses(
+        (status = 200, description = "Reminder settings", body = ReminderSettings),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
+async fn get_reminder_settings(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+) -> Result<Json<ReminderSettings>, ApiError> {
+    let settings = user_repo::get_reminder_settings(&state.pool, auth_user.user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    Ok(Json(settings))
+}
+
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+struct UpdateReminderSettings {
+    #[serde(default)]
+    enabled: Option<bool>,
+    /// Local hour, 0 to 23
+    #[serde(default)]
+    #[validate(range(min = 0, max = 23, message = "Hour must be between 0 and 23"))]
+    hour: Option<i16>,
+    /// IANA timezone name, e.g. `Europe/Madrid`
+    #[serde(default)]
+    timezone: Option<String>,
+    /// Email when the streak is about to end
+    #[serde(default)]
+    streak_email: Option<bool>,
+    /// In-app notification when the streak is about to end
+    #[serde(default)]
+    streak_in_app: Option<bool>,
+}
+
+/// Turn the daily reminder on or off, change when it is sent, or choose how streak reminders arrive
+#[utoipa::path(
+    patch,
+    path = "/v1/users/me/reminders",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    request_body = UpdateReminderSettings,
+    responses(
+        (status = 200, description = "Reminder settings updated", body = ReminderSettings),
+        (status = 400, description = "Invalid hour or unknown timezone", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
+async fn update_reminder_settings(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    ValidJson(payload): ValidJson<UpdateReminderSettings>,
+) -> Result<Json<ReminderSettings>, ApiError> {
+    if let Some(timezone) = &payload.timezone {
+        ApiError::check_fields([("timezone", validate_timezone(&state.pool, timezone).await)])?;
+    }
+
+    let settings = user_repo::update_reminder_settings(
+        &state.pool,
+        auth_user.user_id,
+        payload.enabled,
+        payload.hour,
+        payload.timezone.as_deref(),
+        payload.streak_email,
+        payload.streak_in_app,
+    )
+    .await?
+    .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    Ok(Json(settings))
+}
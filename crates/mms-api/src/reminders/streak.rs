@@ -0,0 +1,84 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use tokio::sync::mpsc;
+
+use mms_db::repositories::user as user_repo;
+
+use crate::{
+    live::EventBus,
+    notifications::{NotificationKind, notify},
+    user::email::EmailJob,
+};
+
+/// Users claimed and reminded at a time
+const BATCH_SIZE: i64 = 100;
+
+/// Users reminded in one run; the rest are picked up by the next run
+const MAX_PER_RUN: usize = 5_000;
+
+/// Remind every user whose streak is at risk at `now`; returns how many were reminded.
+///
+/// Each user gets an in-app notification, an email, or both, following their
+/// settings; emails are only sent when `email_tx` is set. `now` is a parameter
+/// so tests can pick the local time of day. Users are marked as reminded when
+/// claimed, so a reminder that fails is not retried the same day.
+pub async fn send_streak_reminders(
+    pool: &PgPool,
+    events: &EventBus,
+    email_tx: Option<&mpsc::UnboundedSender<EmailJob>>,
+    hours_before_midnight: u32,
+    now: DateTime<Utc>,
+) -> Result<usize, sqlx::Error> {
+    let hours_before_midnight = i32::try_from(hours_before_midnight).unwrap_or(i32::MAX);
+    let mut reminded = 0;
+
+    while reminded < MAX_PER_RUN {
+        let users = user_repo::claim_streaks_at_risk(
+            pool,
+            now,
+            hours_before_midnight,
+            email_tx.is_some(),
+            BATCH_SIZE,
+        )
+        .await?;
+        let claimed = users.len();
+
+        for user in users {
+            if user.send_in_app
+                && let Err(e) = notify(
+                    pool,
+                    events,
+                    user.user_id,
+                    NotificationKind::StreakReminder,
+                    &format!("Your {}-day streak ends at midnight", user.streak_days),
+                    "Review a few cards before midnight to keep it going.",
+                    Some("/practice"),
+                )
+                .await
+            {
+                tracing::error!(error = %e, user_id = %user.user_id, "Failed to notify streak at risk");
+            }
+
+            if user.send_email
+                && let Some(email_tx) = email_tx
+            {
+                let job = EmailJob::StreakReminder {
+                    to_email: user.email,
+                    username: user.username,
+                    streak_days: user.streak_days,
+                };
+                if let Err(e) = email_tx.send(job) {
+                    tracing::error!(error = %e, user_id = %user.user_id, "Failed to queue streak reminder");
+                }
+            }
+
+            reminded += 1;
+        }
+
+        if (claimed as i64) < BATCH_SIZE {
+            break;
+        }
+    }
+
+    Ok(reminded)
+}
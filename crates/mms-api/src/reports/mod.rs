@@ -0,0 +1,271 @@
+//! Content reports and the moderation queue.
+//!
+//! Learners report public decks, cards and comments. Moderators (the
+//! `content:moderate` permission) work through the open reports and act on
+//! the content: hiding keeps it for the record but out of sight, deleting
+//! removes it, warning notifies a comment's author, and dismissing leaves the
+//! content alone. An action resolves every open report on the same content.
+
+pub mod routes;
+
+pub use routes::routes;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    ApiState,
+    error::ApiError,
+    notifications::{NotificationKind, notify},
+};
+
+use mms_db::repositories::report as report_repo;
+
+/// What a report is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportTarget {
+    /// A deck in the public catalogue
+    Deck,
+    /// A card in a public deck
+    Card,
+    /// A comment on a public deck
+    Comment,
+}
+
+impl ReportTarget {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReportTarget::Deck => "deck",
+            ReportTarget::Card => "card",
+            ReportTarget::Comment => "comment",
+        }
+    }
+
+    fn parse(target_type: &str) -> Option<Self> {
+        match target_type {
+            "deck" => Some(ReportTarget::Deck),
+            "card" => Some(ReportTarget::Card),
+            "comment" => Some(ReportTarget::Comment),
+            _ => None,
+        }
+    }
+}
+
+/// Why the content was reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportReason {
+    Spam,
+    Offensive,
+    /// Wrong translations or misleading content
+    Incorrect,
+    Copyright,
+    Other,
+}
+
+impl ReportReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReportReason::Spam => "spam",
+            ReportReason::Offensive => "offensive",
+            ReportReason::Incorrect => "incorrect",
+            ReportReason::Copyright => "copyright",
+            ReportReason::Other => "other",
+        }
+    }
+}
+
+/// Where a report stands
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportStatus {
+    /// Waiting for a moderator
+    #[default]
+    Open,
+    /// A moderator acted on the content
+    Resolved,
+    /// A moderator found nothing wrong
+    Dismissed,
+}
+
+impl ReportStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReportStatus::Open => "open",
+            ReportStatus::Resolved => "resolved",
+            ReportStatus::Dismissed => "dismissed",
+        }
+    }
+}
+
+/// What a moderator does about reported content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ModerationAction {
+    /// Take the deck out of the catalogue, stop the card from being practised
+    /// or hide the comment
+    Hide,
+    /// Delete the content
+    Delete,
+    /// Notify the comment's author
+    Warn,
+    /// Close the reports without touching the content
+    Dismiss,
+}
+
+impl ModerationAction {
+    /// The status the reports are closed with
+    pub fn status(self) -> ReportStatus {
+        match self {
+            ModerationAction::Dismiss => ReportStatus::Dismissed,
+            _ => ReportStatus::Resolved,
+        }
+    }
+
+    /// The action recorded on the reports; dismissing records none
+    pub fn recorded(self) -> Option<&'static str> {
+        match self {
+            ModerationAction::Hide => Some("hide"),
+            ModerationAction::Delete => Some("delete"),
+            ModerationAction::Warn => Some("warn"),
+            ModerationAction::Dismiss => None,
+        }
+    }
+}
+
+/// Act on the content a report is about and close every open report on it.
+///
+/// Returns the number of reports closed.
+pub async fn moderate(
+    state: &ApiState,
+    report_id: Uuid,
+    action: ModerationAction,
+    moderator_id: Option<Uuid>,
+    note: Option<&str>,
+) -> Result<u64, ApiError> {
+    let report = report_repo::find_target(&state.pool, report_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Report not found".to_string()))?;
+    if report.status != ReportStatus::Open.as_str() {
+        return Err(ApiError::Conflict("Report is already closed".to_string()));
+    }
+    let target = ReportTarget::parse(&report.target_type)
+        .ok_or_else(|| ApiError::NotFound("Report not found".to_string()))?;
+    let target_id = report.target_id;
+    let gone = || {
+        ApiError::NotFound("The reported content no longer exists; dismiss the report".to_string())
+    };
+
+    let mut tx = state.pool.begin().await?;
+    let mut warn = None;
+    match action {
+        ModerationAction::Hide => {
+            let hidden = match target {
+                ReportTarget::Deck => report_repo::hide_deck(&mut *tx, target_id).await?,
+                ReportTarget::Card => report_repo::hide_card(&mut *tx, target_id).await?,
+                ReportTarget::Comment => {
+                    report_repo::hide_comment(&mut *tx, target_id, moderator_id).await?
+                }
+            };
+            if !hidden {
+                return Err(gone());
+            }
+        }
+        ModerationAction::Delete => {
+            let deleted = match target {
+                ReportTarget::Deck => {
+                    if report_repo::deck_in_roadmap(&mut *tx, target_id).await? {
+                        return Err(ApiError::Conflict(
+                            "The deck is part of a roadmap; hide it instead".to_string(),
+                        ));
+                    }
+                    report_repo::delete_deck(&mut *tx, target_id).await?
+                }
+                ReportTarget::Card => report_repo::delete_card(&mut *tx, target_id).await?,
+                ReportTarget::Comment => report_repo::delete_comment(&mut *tx, target_id).await?,
+            };
+            if !deleted {
+                return Err(gone());
+            }
+        }
+        ModerationAction::Warn => {
+            if target != ReportTarget::Comment {
+                return Err(ApiError::Validation(
+                    "Only comments have an author to warn".to_string(),
+                ));
+            }
+            let author = report_repo::comment_author(&mut *tx, target_id)
+                .await?
+                .ok_or_else(gone)?;
+            warn = Some(author);
+        }
+        ModerationAction::Dismiss => {}
+    }
+
+    let closed = report_repo::resolve(
+        &mut *tx,
+        target.as_str(),
+        target_id,
+        action.status().as_str(),
+        action.recorded(),
+        moderator_id,
+        note,
+    )
+    .await?;
+    tx.commit().await?;
+
+    if let Some(author) = warn {
+        let body = match note {
+            Some(note) => format!("A moderator reviewed a report about your comment: {note}"),
+            None => "A moderator reviewed a report about your comment. Please keep to the community guidelines.".to_string(),
+        };
+        if let Err(e) = notify(
+            &state.pool,
+            &state.events,
+            author,
+            NotificationKind::ContentWarning,
+            "Your comment was reported",
+            &body,
+            None,
+        )
+        .await
+        {
+            tracing::error!(error = %e, user_id = %author, "Failed to send content warning");
+        }
+    }
+
+    Ok(closed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dismissing_records_no_action() {
+        assert_eq!(ModerationAction::Dismiss.status(), ReportStatus::Dismissed);
+        assert_eq!(ModerationAction::Dismiss.recorded(), None);
+        for action in [
+            ModerationAction::Hide,
+            ModerationAction::Delete,
+            ModerationAction::Warn,
+        ] {
+            assert_eq!(action.status(), ReportStatus::Resolved);
+            assert!(action.recorded().is_some());
+        }
+    }
+
+    #[test]
+    fn test_targets_round_trip() {
+        for target in [
+            ReportTarget::Deck,
+            ReportTarget::Card,
+            ReportTarget::Comment,
+        ] {
+            assert_eq!(ReportTarget::parse(target.as_str()), Some(target));
+        }
+        assert_eq!(ReportTarget::parse("roadmap"), None);
+    }
+}
@@ -0,0 +1,263 @@
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{get, post},
+};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+use utoipa::{IntoParams, ToSchema};
+use validator::{Validate, ValidationError};
+
+use crate::{
+    ApiState,
+    auth::{AuthUser, RequirePermission, permissions::ContentModerate, policy::Principal},
+    error::{ApiError, ErrorResponse},
+    middleware::rate_limit,
+    reports::{ModerationAction, ReportReason, ReportStatus, ReportTarget, moderate},
+    validation::{ValidJson, invalid},
+};
+
+use mms_db::models::{ContentReport, ModerationReport};
+use mms_db::repositories::report as report_repo;
+
+/// Longest report details or moderator note accepted, in characters
+const MAX_TEXT_CHARS: usize = 1000;
+
+/// Reports returned when no limit is given
+const DEFAULT_REPORT_LIMIT: i64 = 50;
+
+/// Reports returned at most
+const MAX_REPORT_LIMIT: i64 = 200;
+
+/// Create the content report and moderation routes
+pub fn routes() -> Router<ApiState> {
+    use crate::make_rate_limit_layer;
+
+    Router::new()
+        .route("/reports", get(list_my_reports).post(create_report))
+        .route("/admin/reports", get(list_reports))
+        .route("/admin/reports/{report_id}/actions", post(act_on_report))
+        .layer(make_rate_limit_layer!(
+            rate_limit::GENERAL_RATE_PER_SECOND,
+            rate_limit::GENERAL_BURST_SIZE
+        ))
+}
+
+/// Trimmed optional text; blank becomes None
+fn optional_text(text: Option<&str>) -> Option<&str> {
+    text.map(str::trim).filter(|text| !text.is_empty())
+}
+
+fn validate_text(text: &str, field: &str) -> Result<(), ValidationError> {
+    if text.trim().chars().count() > MAX_TEXT_CHARS {
+        return Err(invalid(
+            "length",
+            format!("{field} must be at most {MAX_TEXT_CHARS} characters"),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_details(details: &str) -> Result<(), ValidationError> {
+    validate_text(details, "Details")
+}
+
+fn validate_note(note: &str) -> Result<(), ValidationError> {
+    validate_text(note, "Note")
+}
+
+fn report_limit(limit: Option<i64>) -> i64 {
+    limit
+        .unwrap_or(DEFAULT_REPORT_LIMIT)
+        .clamp(1, MAX_REPORT_LIMIT)
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+struct ReportRequest {
+    target_type: ReportTarget,
+    target_id: Uuid,
+    reason: ReportReason,
+    /// Anything the moderators should know, up to 1000 characters
+    #[serde(default)]
+    #[validate(custom(function = "validate_details"))]
+    details: Option<String>,
+}
+
+/// Report a public deck, card or comment to the moderators
+#[utoipa::path(
+    post,
+    path = "/v1/reports",
+    tag = "reports",
+    security(("cookie_auth" = [])),
+    request_body = ReportRequest,
+    responses(
+        (status = 201, description = "Report filed", body = ContentReport),
+        (status = 400, description = "Overlong details", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "No such public content", body = ErrorResponse),
+        (status = 409, description = "The user's earlier report on it is still open", body = ErrorResponse),
+    )
+)]
+async fn create_report(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    ValidJson(request): ValidJson<ReportRequest>,
+) -> Result<(StatusCode, Json<ContentReport>), ApiError> {
+    let details = optional_text(request.details.as_deref());
+    let target_type = request.target_type.as_str();
+    if !report_repo::target_is_public(&state.pool, target_type, request.target_id).await? {
+        return Err(ApiError::NotFound("Content not found".to_string()));
+    }
+
+    let report = report_repo::create(
+        &state.pool,
+        auth_user.user_id,
+        target_type,
+        request.target_id,
+        request.reason.as_str(),
+        details,
+    )
+    .await?
+    .ok_or_else(|| ApiError::Conflict("You have already reported this".to_string()))?;
+    Ok((StatusCode::CREATED, Json(report)))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct MyReportsQuery {
+    /// Reports to return, newest first (default: 50, at most 200)
+    #[serde(default)]
+    limit: Option<i64>,
+}
+
+/// The signed-in user's reports and what became of them
+#[utoipa::path(
+    get,
+    path = "/v1/reports",
+    tag = "reports",
+    security(("cookie_auth" = [])),
+    params(MyReportsQuery),
+    responses(
+        (status = 200, description = "Reports, newest first", body = Vec<ContentReport>),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+async fn list_my_reports(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Query(query): Query<MyReportsQuery>,
+) -> Result<Json<Vec<ContentReport>>, ApiError> {
+    let reports =
+        report_repo::list_for_reporter(&state.pool, auth_user.user_id, report_limit(query.limit))
+            .await?;
+    Ok(Json(reports))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct QueueQuery {
+    /// Reports with this status (default: `open`)
+    #[serde(default)]
+    status: ReportStatus,
+    /// Only reports about this kind of content
+    #[serde(default)]
+    target_type: Option<ReportTarget>,
+    /// Reports to return (default: 50, at most 200)
+    #[serde(default)]
+    limit: Option<i64>,
+}
+
+/// The moderation queue: open reports oldest first, or closed ones newest first
+#[utoipa::path(
+    get,
+    path = "/v1/admin/reports",
+    tag = "admin",
+    security(("cookie_auth" = ["content:moderate"]), ("admin_token" = [])),
+    params(QueueQuery),
+    responses(
+        (status = 200, description = "Reports with a preview of their content", body = Vec<ModerationReport>),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `content:moderate` permission", body = ErrorResponse),
+    )
+)]
+async fn list_reports(
+    _access: RequirePermission<ContentModerate>,
+    State(state): State<ApiState>,
+    Query(query): Query<QueueQuery>,
+) -> Result<Json<Vec<ModerationReport>>, ApiError> {
+    let reports = report_repo::list_queue(
+        &state.pool,
+        query.status.as_str(),
+        query.target_type.map(ReportTarget::as_str),
+        report_limit(query.limit),
+    )
+    .await?;
+    Ok(Json(reports))
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+struct ActionRequest {
+    action: ModerationAction,
+    /// Kept with the reports; a warning also passes it to the comment's author
+    #[serde(default)]
+    #[validate(custom(function = "validate_note"))]
+    note: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct ActionOutcome {
+    status: ReportStatus,
+    /// Open reports on the same content, closed together with this one
+    reports_closed: u64,
+}
+
+/// Act on reported content and close its open reports
+#[utoipa::path(
+    post,
+    path = "/v1/admin/reports/{report_id}/actions",
+    tag = "admin",
+    security(("cookie_auth" = ["content:moderate"]), ("admin_token" = [])),
+    params(("report_id" = Uuid, Path)),
+    request_body = ActionRequest,
+    responses(
+        (status = 200, description = "Content acted on and reports closed", body = ActionOutcome),
+        (status = 400, description = "Warning about content without an author, or an overlong note", body = ErrorResponse),
+        (status = 401, description = "Not signed in or wrong admin token", body = ErrorResponse),
+        (status = 403, description = "Missing the `content:moderate` permission", body = ErrorResponse),
+        (status = 404, description = "Report not found, or its content is already gone", body = ErrorResponse),
+        (status = 409, description = "Report already closed, or deleting a deck a roadmap uses", body = ErrorResponse),
+    )
+)]
+async fn act_on_report(
+    access: RequirePermission<ContentModerate>,
+    State(state): State<ApiState>,
+    Path(report_id): Path<Uuid>,
+    ValidJson(request): ValidJson<ActionRequest>,
+) -> Result<Json<ActionOutcome>, ApiError> {
+    let note = optional_text(request.note.as_deref());
+    let moderator_id = match access.principal {
+        Principal::User(user) => Some(user.user_id),
+        Principal::Operator => None,
+    };
+
+    let reports_closed = moderate(&state, report_id, request.action, moderator_id, note).await?;
+    Ok(Json(ActionOutcome {
+        status: request.action.status(),
+        reports_closed,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_optional_text_is_trimmed_and_bounded() {
+        assert_eq!(optional_text(None), None);
+        assert_eq!(optional_text(Some("  ")), None);
+        assert_eq!(optional_text(Some(" spam ")), Some("spam"));
+        assert!(validate_note(&format!(" {} ", "é".repeat(MAX_TEXT_CHARS))).is_ok());
+        assert!(validate_note(&"é".repeat(MAX_TEXT_CHARS + 1)).is_err());
+    }
+}
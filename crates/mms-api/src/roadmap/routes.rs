@@ -1,23 +1,35 @@
 use axum::{
     Json, Router,
     extract::{Path, Query, State},
+    middleware,
+    response::Response,
     routing::get,
 };
 use serde::Deserialize;
-use sqlx::types::Uuid;
-
-use crate::{ApiState, auth::AuthUser, error::ApiError, validation};
+use utoipa::IntoParams;
+
+use crate::{
+    ApiState,
+    auth::AuthUser,
+    error::{ApiError, ErrorResponse},
+    middleware::etag,
+    slugs, validation,
+};
 
 use mms_db::models::{Roadmap, RoadmapWithProgress};
 use mms_db::repositories::roadmap as roadmap_repo;
+use mms_db::tenancy::Tenant;
 
-const DEFAULT_PAGE_LIMIT: i64 = 50;
+pub(crate) const DEFAULT_PAGE_LIMIT: i64 = 50;
 const MAX_PAGE_LIMIT: i64 = 100;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct PaginationQuery {
+    /// Page size, 1 to 100 (default 50)
     #[serde(default)]
     limit: Option<i64>,
+    /// Roadmaps to skip (default 0)
     #[serde(default)]
     offset: Option<i64>,
 }
@@ -47,61 +59,147 @@ pub fn routes() -> Router<ApiState> {
             "/roadmaps/{roadmap_id}/progress",
             get(get_roadmap_with_progress),
         )
+        .layer(middleware::from_fn(etag::etag_middleware))
 }
 
+/// All roadmaps, alphabetically
+///
+/// Served from the last copy, with a `Warning` header, while the database is unreachable.
+#[utoipa::path(
+    get,
+    path = "/v1/roadmaps",
+    tag = "roadmaps",
+    params(PaginationQuery),
+    responses((status = 200, description = "One page of roadmaps", body = Vec<Roadmap>))
+)]
 async fn list_roadmaps(
     State(state): State<ApiState>,
     Query(pagination): Query<PaginationQuery>,
-) -> Result<Json<Vec<Roadmap>>, ApiError> {
-    let roadmaps =
-        roadmap_repo::list_all(&state.pool, pagination.limit(), pagination.offset()).await?;
-
-    Ok(Json(roadmaps))
+) -> Result<Response, ApiError> {
+    let (limit, offset) = (pagination.limit(), pagination.offset());
+
+    state
+        .public_cache
+        .fetch(format!("roadmaps:{limit}:{offset}"), async {
+            Ok(roadmap_repo::list_all(&state.read_pool, Tenant::Public, limit, offset).await?)
+        })
+        .await
 }
 
+/// Roadmaps for one language pair
+///
+/// Served from the last copy, with a `Warning` header, while the database is unreachable.
+#[utoipa::path(
+    get,
+    path = "/v1/roadmaps/{language_from}/{language_to}",
+    tag = "roadmaps",
+    params(
+        ("language_from" = String, Path, description = "Language the learner speaks", example = "en"),
+        ("language_to" = String, Path, description = "Language being learned", example = "es"),
+        PaginationQuery,
+    ),
+    responses(
+        (status = 200, description = "One page of roadmaps", body = Vec<Roadmap>),
+        (status = 400, description = "Unsupported language code", body = ErrorResponse),
+    )
+)]
 async fn get_roadmaps_by_language(
     State(state): State<ApiState>,
     Path((language_from, language_to)): Path<(String, String)>,
     Query(pagination): Query<PaginationQuery>,
-) -> Result<Json<Vec<Roadmap>>, ApiError> {
-    // Validate language codes
-    validation::validate_language_code(&language_from)?;
-    validation::validate_language_code(&language_to)?;
-
-    let roadmaps = roadmap_repo::list_by_language(
-        &state.pool,
-        &language_from,
-        &language_to,
-        pagination.limit(),
-        pagination.offset(),
-    )
-    .await?;
-
-    Ok(Json(roadmaps))
+) -> Result<Response, ApiError> {
+    ApiError::check_fields([
+        (
+            "language_from",
+            validation::validate_language_code(&language_from),
+        ),
+        (
+            "language_to",
+            validation::validate_language_code(&language_to),
+        ),
+    ])?;
+
+    let (limit, offset) = (pagination.limit(), pagination.offset());
+    let key = format!("roadmaps:{language_from}:{language_to}:{limit}:{offset}");
+
+    state
+        .public_cache
+        .fetch(key, async {
+            Ok(roadmap_repo::list_by_language(
+                &state.read_pool,
+                Tenant::Public,
+                &language_from,
+                &language_to,
+                limit,
+                offset,
+            )
+            .await?)
+        })
+        .await
 }
 
+/// A roadmap and its deck nodes, without user progress
+///
+/// The roadmap may be named by its id or its slug.
+///
+/// Served from the last copy, with a `Warning` header, while the database is unreachable.
+#[utoipa::path(
+    get,
+    path = "/v1/roadmaps/{roadmap_id}/nodes",
+    tag = "roadmaps",
+    params(("roadmap_id" = String, Path, description = "Roadmap id or slug")),
+    responses(
+        (status = 200, description = "Roadmap with zeroed progress", body = RoadmapWithProgress),
+        (status = 404, description = "Roadmap not found", body = ErrorResponse),
+    )
+)]
 async fn get_roadmap_nodes(
     State(state): State<ApiState>,
-    Path(roadmap_id): Path<Uuid>,
-) -> Result<Json<RoadmapWithProgress>, ApiError> {
-    // Fetch roadmap metadata (public - no user-specific progress)
-    let roadmap_metadata = roadmap_repo::get_metadata(&state.pool, roadmap_id).await?;
-
-    // Fetch all nodes (public - no user-specific progress)
-    let nodes = roadmap_repo::get_nodes(&state.pool, roadmap_id).await?;
-
-    Ok(Json(RoadmapWithProgress {
-        roadmap: roadmap_metadata,
-        nodes,
-    }))
+    Path(roadmap): Path<String>,
+) -> Result<Response, ApiError> {
+    state
+        .public_cache
+        .fetch(format!("roadmap-nodes:{roadmap}"), async {
+            let roadmap_id = slugs::roadmap_id(&state.read_pool, Tenant::Public, &roadmap).await?;
+
+            // Fetch roadmap metadata (public - no user-specific progress)
+            let roadmap_metadata =
+                roadmap_repo::get_metadata(&state.read_pool, Tenant::Public, roadmap_id).await?;
+
+            // Fetch all nodes (public - no user-specific progress)
+            let nodes =
+                roadmap_repo::get_nodes(&state.read_pool, Tenant::Public, roadmap_id).await?;
+
+            Ok(RoadmapWithProgress {
+                roadmap: roadmap_metadata,
+                nodes,
+            })
+        })
+        .await
 }
 
+/// A roadmap and its deck nodes with the signed-in user's progress
+///
+/// The roadmap may be named by its id or its slug.
+#[utoipa::path(
+    get,
+    path = "/v1/roadmaps/{roadmap_id}/progress",
+    tag = "roadmaps",
+    security(("cookie_auth" = [])),
+    params(("roadmap_id" = String, Path, description = "Roadmap id or slug")),
+    responses(
+        (status = 200, description = "Roadmap with progress", body = RoadmapWithProgress),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Roadmap not found", body = ErrorResponse),
+    )
+)]
 async fn get_roadmap_with_progress(
     auth_user: AuthUser,
     State(state): State<ApiState>,
-    Path(roadmap_id): Path<Uuid>,
+    Path(roadmap): Path<String>,
 ) -> Result<Json<RoadmapWithProgress>, ApiError> {
     let user_id = auth_user.user_id;
+    let roadmap_id = slugs::roadmap_id(&state.pool, Tenant::Member(user_id), &roadmap).await?;
 
     // Fetch roadmap metadata with progress statistics
     let roadmap_metadata =
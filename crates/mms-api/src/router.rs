@@ -1,60 +1,156 @@
-use axum::{Json, Router, extract::State, http::StatusCode, response::IntoResponse, routing::get};
+use axum::{
+    Json, Router,
+    extract::{MatchedPath, Request, State},
+    http::{HeaderValue, Method, StatusCode, header},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::get,
+};
 use serde::Serialize;
+use tower_http::compression::CompressionLayer;
+use utoipa::ToSchema;
 
-use crate::{state::ApiState, v1};
+use crate::{
+    error::{self, ApiError},
+    health::{self, Readiness, ReadinessReport},
+    state::ApiState,
+    versioning,
+};
 
 pub fn router() -> Router<ApiState> {
     Router::new()
         .route("/health", get(health))
         .route("/health/ready", get(readiness))
-        .nest("/v1", v1::routes())
+        .route("/.well-known/jwks.json", get(jwks))
+        .merge(versioning::routes())
         .fallback(handler_404)
+        .layer(middleware::from_fn(error::structure_plain_errors))
+        .layer(middleware::from_fn(cache_control))
+        // gzip or brotli when the client accepts it; live event streams are left alone
+        .layer(CompressionLayer::new())
 }
 
-#[derive(Serialize)]
-struct HealthResponse {
-    status: &'static str,
-    version: &'static str,
+/// Routes whose responses are the same for every caller, under any version prefix
+const PUBLIC_ROUTES: &[&str] = &[
+    "/roadmaps",
+    "/roadmaps/{language_from}/{language_to}",
+    "/roadmaps/{roadmap_id}/nodes",
+    "/decks",
+    "/openapi.json",
+];
+
+/// Public content may be reused for 5 minutes, then revalidated with its `ETag`
+const PUBLIC_CACHE_CONTROL: &str = "public, max-age=300";
+
+/// Anything else may depend on who asks, so it is never stored
+const PRIVATE_CACHE_CONTROL: &str = "no-store";
+
+/// Set `Cache-Control` on responses whose handler did not choose one
+async fn cache_control(request: Request, next: Next) -> Response {
+    let public = request.method() == Method::GET
+        && request
+            .extensions()
+            .get::<MatchedPath>()
+            .and_then(|path| versioning::strip_version_prefix(path.as_str()))
+            .is_some_and(|path| PUBLIC_ROUTES.contains(&path));
+
+    let mut response = next.run(request).await;
+    let cacheable = response.status().is_success() || response.status() == StatusCode::NOT_MODIFIED;
+    let policy = if public && cacheable {
+        PUBLIC_CACHE_CONTROL
+    } else {
+        PRIVATE_CACHE_CONTROL
+    };
+    response
+        .headers_mut()
+        .entry(header::CACHE_CONTROL)
+        .or_insert(HeaderValue::from_static(policy));
+    response
 }
 
-#[derive(Serialize)]
-struct ReadinessResponse {
+#[derive(Serialize, ToSchema)]
+struct HealthResponse {
     status: &'static str,
-    database: &'static str,
     version: &'static str,
 }
 
 /// Simple liveness check - returns 200 if the server is running
-async fn health() -> Json<HealthResponse> {
+///
+/// `status` is "degraded" while the database is unreachable and public
+/// listings are served from cache.
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses((status = 200, description = "The server is running", body = HealthResponse))
+)]
+async fn health(State(state): State<ApiState>) -> Json<HealthResponse> {
     Json(HealthResponse {
-        status: "healthy",
+        status: if state.public_cache.is_degraded() {
+            "degraded"
+        } else {
+            "healthy"
+        },
         version: env!("CARGO_PKG_VERSION"),
     })
 }
 
-/// Readiness check - verifies database connectivity
-async fn readiness(State(state): State<ApiState>) -> Result<Json<ReadinessResponse>, StatusCode> {
-    // Check database connectivity
-    let db_status = sqlx::query("SELECT 1")
-        .fetch_one(&state.pool)
-        .await
-        .map(|_| "connected")
-        .unwrap_or("disconnected");
-
-    if db_status == "disconnected" {
-        return Err(StatusCode::SERVICE_UNAVAILABLE);
+/// Readiness check - verifies Postgres, migrations, Redis and the email provider
+///
+/// Fails as soon as graceful shutdown starts so load balancers stop routing here.
+/// A required dependency being down makes the instance "unhealthy" (503), except
+/// that while the database is unreachable it stays "degraded" as long as there
+/// are cached public listings to serve. Redis or the email provider being down
+/// only makes it "degraded".
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "Ready to serve traffic, possibly degraded", body = ReadinessReport),
+        (status = 503, description = "Unhealthy, with the failing checks", body = ReadinessReport),
+    )
+)]
+async fn readiness(State(state): State<ApiState>) -> Response {
+    if state.drain.is_draining() {
+        return StatusCode::SERVICE_UNAVAILABLE.into_response();
     }
 
-    Ok(Json(ReadinessResponse {
-        status: "ready",
-        database: db_status,
-        version: env!("CARGO_PKG_VERSION"),
-    }))
+    let report = health::check(&state).await;
+    let status = match report.status {
+        Readiness::Unhealthy => StatusCode::SERVICE_UNAVAILABLE,
+        Readiness::Ready | Readiness::Degraded => StatusCode::OK,
+    };
+    (status, Json(report)).into_response()
 }
 
-async fn handler_404() -> impl IntoResponse {
-    (
-        StatusCode::NOT_FOUND,
-        "The requested resource was not found",
+/// Public keys for verifying access tokens signed with RS256 or EdDSA
+///
+/// Not found while tokens are signed with a shared secret.
+#[utoipa::path(
+    get,
+    path = "/.well-known/jwks.json",
+    tag = "health",
+    responses(
+        (status = 200, description = "JSON Web Key Set (RFC 7517)", body = serde_json::Value),
+        (status = 404, description = "Tokens are signed with a shared secret"),
     )
+)]
+async fn jwks(State(state): State<ApiState>) -> Result<Response, StatusCode> {
+    let set = state.auth.jwt_keys.jwks();
+    if set.keys.is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let mut response = Json(set).into_response();
+    // Verifiers refetch on an unknown kid, so a short cache is enough during rotation
+    response.headers_mut().insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("public, max-age=300"),
+    );
+    Ok(response)
+}
+
+async fn handler_404() -> ApiError {
+    ApiError::NotFound("The requested resource was not found".to_string())
 }
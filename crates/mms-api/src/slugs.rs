@@ -0,0 +1,88 @@
+//! Roadmaps and decks named by slug.
+//!
+//! Every roadmap and deck has a slug, generated from its title when it is
+//! created and editable by admins. Routes that readers share take either the
+//! id or the slug in the same path segment: whatever parses as a UUID is an
+//! id, anything else a slug.
+
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::ValidationError;
+
+use crate::{error::ApiError, validation::invalid};
+
+use mms_db::repositories::{deck as deck_repo, roadmap as roadmap_repo};
+use mms_db::tenancy::Tenant;
+
+/// Longest accepted slug
+pub const MAX_SLUG_LEN: usize = 100;
+
+/// Lowercase ASCII letters and digits in runs separated by single dashes
+pub fn is_slug(slug: &str) -> bool {
+    !slug.is_empty()
+        && slug.len() <= MAX_SLUG_LEN
+        && slug.split('-').all(|part| {
+            !part.is_empty()
+                && part
+                    .bytes()
+                    .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+        })
+}
+
+/// Slug constraint for request types: `#[validate(custom(function = "slug"))]`
+pub fn slug(slug: &str) -> Result<(), ValidationError> {
+    if is_slug(slug) {
+        Ok(())
+    } else {
+        Err(invalid(
+            "slug",
+            "Slugs are lowercase letters and digits separated by single dashes",
+        ))
+    }
+}
+
+/// The id of the roadmap `roadmap` names, by id or slug; 404 when it does not
+/// exist or the tenant may not see it
+pub async fn roadmap_id(pool: &PgPool, tenant: Tenant, roadmap: &str) -> Result<Uuid, ApiError> {
+    if let Ok(roadmap_id) = Uuid::parse_str(roadmap) {
+        return Ok(roadmap_id);
+    }
+    roadmap_repo::find_id_by_slug(pool, tenant, roadmap)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Roadmap not found".to_string()))
+}
+
+/// The id of the deck `deck` names, by id or slug; 404 when it does not
+/// exist or the tenant may not see it
+pub async fn deck_id(pool: &PgPool, tenant: Tenant, deck: &str) -> Result<Uuid, ApiError> {
+    if let Ok(deck_id) = Uuid::parse_str(deck) {
+        return Ok(deck_id);
+    }
+    deck_repo::find_id_by_slug(pool, tenant, deck)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Deck not found".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slugs() {
+        for slug in ["a", "spanish-a1", "2024-top-100"] {
+            assert!(is_slug(slug), "{slug}");
+        }
+        for slug in [
+            "",
+            "Spanish",
+            "a--b",
+            "-a",
+            "a-",
+            "a_b",
+            "é",
+            &"a".repeat(101),
+        ] {
+            assert!(!is_slug(slug), "{slug}");
+        }
+    }
+}
@@ -0,0 +1,276 @@
+//! Scripted user journey for checking a deployment end to end.
+//!
+//! [`run_journey`] drives the router in-process the way a new learner would:
+//! register, verify the email from the link that would have been sent, sign
+//! in, practise a card and open the dashboard. Every step checks the status
+//! and the parts of the body later steps depend on, and the first failure is
+//! returned with the step it happened in.
+//!
+//! The journey writes a user and a one-card deck, so it is meant to run
+//! against a throwaway schema; `serv --smoke-test` sets one up and drops it
+//! afterwards.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use axum::{
+    Router,
+    body::Body,
+    extract::ConnectInfo,
+    http::{Method, Request, StatusCode, header},
+};
+use serde_json::{Value, json};
+use tokio::sync::mpsc;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use mms_db::repositories::content as content_repo;
+
+use crate::{state::ApiState, user::email::EmailJob};
+
+/// Largest response body the journey reads
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// Password of the journey's user; only ever used in a throwaway schema
+const PASSWORD: &str = "Smoke-test-password-1";
+
+/// A failed journey step
+#[derive(Debug, thiserror::Error)]
+#[error("{step}: {message}")]
+pub struct SmokeError {
+    pub step: &'static str,
+    pub message: String,
+}
+
+fn fail(step: &'static str, message: impl Into<String>) -> SmokeError {
+    SmokeError {
+        step,
+        message: message.into(),
+    }
+}
+
+/// Run the journey against `state`; returns the names of the steps that passed.
+///
+/// Verification emails are captured instead of sent and captcha is skipped,
+/// whatever the configuration says.
+pub async fn run_journey(mut state: ApiState) -> Result<Vec<&'static str>, SmokeError> {
+    let (email_tx, mut email_rx) = mpsc::unbounded_channel();
+    state.email_tx = Some(email_tx);
+    state.captcha = None;
+
+    let (deck_id, card_id, translation) = seed_deck(&state)
+        .await
+        .map_err(|e| fail("seed deck", e.to_string()))?;
+
+    let mut client = Client {
+        router: crate::router::router().with_state(state),
+        cookies: Vec::new(),
+    };
+    let mut passed = Vec::new();
+
+    let suffix = &Uuid::new_v4().simple().to_string()[..8];
+    let email = format!("smoke+{suffix}@example.com");
+    let username = format!("smoke_{suffix}");
+
+    let step = "register";
+    client
+        .send(
+            step,
+            Method::POST,
+            "/v2/users/register",
+            Some(json!({ "username": username, "email": email, "password": PASSWORD })),
+        )
+        .await?;
+    passed.push(step);
+
+    let step = "verify email";
+    let token = verification_token(&mut email_rx, &email)
+        .ok_or_else(|| fail(step, "no verification email was queued"))?;
+    client
+        .send(
+            step,
+            Method::GET,
+            &format!("/v2/users/verify-email?token={token}"),
+            None,
+        )
+        .await?;
+    passed.push(step);
+
+    let step = "log in";
+    client
+        .send(
+            step,
+            Method::POST,
+            "/v2/users/login",
+            Some(json!({ "email": email, "password": PASSWORD })),
+        )
+        .await?;
+    if client.cookies.is_empty() {
+        return Err(fail(step, "no session cookies were set"));
+    }
+    passed.push(step);
+
+    let step = "load practice session";
+    let cards = client
+        .send(
+            step,
+            Method::GET,
+            &format!("/v2/decks/{deck_id}/practice"),
+            None,
+        )
+        .await?;
+    let card_listed = cards
+        .as_array()
+        .is_some_and(|cards| cards.iter().any(|card| card["id"] == card_id.to_string()));
+    if !card_listed {
+        return Err(fail(step, "the seeded card is not in the session"));
+    }
+    passed.push(step);
+
+    let step = "submit review";
+    let review = client
+        .send(
+            step,
+            Method::POST,
+            &format!("/v2/practice/{card_id}/review"),
+            Some(json!({ "user_answer": translation, "deck_id": deck_id })),
+        )
+        .await?;
+    if review["is_correct"] != true {
+        return Err(fail(step, format!("correct answer was graded {review}")));
+    }
+    passed.push(step);
+
+    let step = "load dashboard";
+    let dashboard = client
+        .send(step, Method::GET, "/v2/users/me/dashboard", None)
+        .await?;
+    if dashboard["stats"]["total_reviews"] != 1 {
+        return Err(fail(
+            step,
+            format!("expected 1 review, got {}", dashboard["stats"]),
+        ));
+    }
+    passed.push(step);
+
+    Ok(passed)
+}
+
+/// Insert a deck with one card; content is otherwise only added by admins
+async fn seed_deck(state: &ApiState) -> Result<(Uuid, Uuid, String), sqlx::Error> {
+    let translation = "hola".to_string();
+    let deck_id = Uuid::new_v4();
+    let mut tx = state.pool.begin().await?;
+
+    content_repo::upsert_deck(
+        &mut tx,
+        deck_id,
+        "Smoke test",
+        Some("Created by the smoke test"),
+        "en",
+        "es",
+        None,
+    )
+    .await?;
+    let card_id = content_repo::upsert_deck_flashcard(
+        &mut *tx,
+        deck_id,
+        &format!("hello {deck_id}"),
+        &translation,
+    )
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)?;
+
+    tx.commit().await?;
+    Ok((deck_id, card_id, translation))
+}
+
+/// Token from the verification email queued for `email`
+fn verification_token(rx: &mut mpsc::UnboundedReceiver<EmailJob>, email: &str) -> Option<String> {
+    while let Ok(job) = rx.try_recv() {
+        if let EmailJob::Verification {
+            to_email,
+            verification_token,
+            ..
+        } = job
+            && to_email == email
+        {
+            return Some(verification_token);
+        }
+    }
+    None
+}
+
+/// In-process client that keeps the cookies the API sets
+struct Client {
+    router: Router,
+    cookies: Vec<(String, String)>,
+}
+
+impl Client {
+    /// Send a request and return its JSON body, failing `step` on a non-2xx status
+    async fn send(
+        &mut self,
+        step: &'static str,
+        method: Method,
+        uri: &str,
+        body: Option<Value>,
+    ) -> Result<Value, SmokeError> {
+        let mut request = Request::builder().method(method).uri(uri);
+        if !self.cookies.is_empty() {
+            let cookies = self
+                .cookies
+                .iter()
+                .map(|(name, value)| format!("{name}={value}"))
+                .collect::<Vec<_>>()
+                .join("; ");
+            request = request.header(header::COOKIE, cookies);
+        }
+        let body = match body {
+            Some(body) => {
+                request = request.header(header::CONTENT_TYPE, "application/json");
+                Body::from(body.to_string())
+            }
+            None => Body::empty(),
+        };
+        let mut request = request.body(body).map_err(|e| fail(step, e.to_string()))?;
+
+        // Rate limiting keys on the peer address, which in-process requests lack
+        let peer = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0);
+        request.extensions_mut().insert(ConnectInfo(peer));
+
+        let response = self
+            .router
+            .clone()
+            .oneshot(request)
+            .await
+            .map_err(|e| fail(step, e.to_string()))?;
+        let status = response.status();
+
+        for value in response.headers().get_all(header::SET_COOKIE) {
+            let Some((name, value)) = value
+                .to_str()
+                .ok()
+                .and_then(|cookie| cookie.split(';').next())
+                .and_then(|pair| pair.split_once('='))
+            else {
+                continue;
+            };
+            self.cookies.retain(|(existing, _)| existing != name);
+            self.cookies.push((name.to_string(), value.to_string()));
+        }
+
+        let bytes = axum::body::to_bytes(response.into_body(), MAX_BODY_BYTES)
+            .await
+            .map_err(|e| fail(step, format!("failed to read response: {e}")))?;
+        if !status.is_success() {
+            return Err(fail(
+                step,
+                format!("{status}: {}", String::from_utf8_lossy(&bytes)),
+            ));
+        }
+        if bytes.is_empty() || status == StatusCode::NO_CONTENT {
+            return Ok(Value::Null);
+        }
+        serde_json::from_slice(&bytes).map_err(|e| fail(step, format!("invalid JSON: {e}")))
+    }
+}
@@ -0,0 +1,178 @@
+//! Speech-to-text for pronunciation practice.
+//!
+//! Transcription goes through the [`SpeechToText`] trait so handlers do not
+//! care which provider is configured, and tests can plug in a stub. Without a
+//! configured provider, pronunciation practice is unavailable.
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::error::ApiError;
+
+/// Boxed future returned by [`SpeechToText::transcribe`]
+pub type TranscribeFuture<'a> = Pin<Box<dyn Future<Output = Result<String, ApiError>> + Send + 'a>>;
+
+/// Turns a short recording into text
+pub trait SpeechToText: Send + Sync + fmt::Debug {
+    /// Transcribe `audio`, of type `content_type`, spoken in `language`
+    /// (ISO 639-1). Silence transcribes to an empty string.
+    fn transcribe<'a>(
+        &'a self,
+        audio: &'a [u8],
+        content_type: &'a str,
+        language: &'a str,
+    ) -> TranscribeFuture<'a>;
+}
+
+/// Supported speech-to-text providers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SttProvider {
+    Deepgram,
+}
+
+const DEEPGRAM_LISTEN_URL: &str = "https://api.deepgram.com/v1/listen";
+
+/// Transcriber calling Deepgram's pre-recorded audio endpoint
+#[derive(Clone)]
+pub struct DeepgramSpeechToText {
+    api_key: Arc<str>,
+    client: reqwest::Client,
+}
+
+impl fmt::Debug for DeepgramSpeechToText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeepgramSpeechToText")
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Deserialize)]
+struct ListenResponse {
+    results: ListenResults,
+}
+
+#[derive(Deserialize)]
+struct ListenResults {
+    channels: Vec<ListenChannel>,
+}
+
+#[derive(Deserialize)]
+struct ListenChannel {
+    alternatives: Vec<ListenAlternative>,
+}
+
+#[derive(Deserialize)]
+struct ListenAlternative {
+    transcript: String,
+}
+
+impl DeepgramSpeechToText {
+    #[must_use]
+    pub fn new(api_key: &str) -> Self {
+        Self {
+            api_key: api_key.into(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn transcribe_audio(
+        &self,
+        audio: &[u8],
+        content_type: &str,
+        language: &str,
+    ) -> Result<String, ApiError> {
+        let response = self
+            .client
+            .post(DEEPGRAM_LISTEN_URL)
+            .query(&[("language", language), ("smart_format", "false")])
+            .header(
+                reqwest::header::AUTHORIZATION,
+                format!("Token {}", self.api_key),
+            )
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .body(audio.to_vec())
+            .send()
+            .await
+            .map_err(|e| ApiError::Speech(format!("Speech provider unreachable: {e}")))?;
+
+        let status = response.status();
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| ApiError::Speech(format!("Invalid speech provider response: {e}")))?;
+        if status.is_client_error() {
+            // The provider could not decode the clip, which is the client's doing
+            tracing::debug!(%status, "Speech provider rejected the audio");
+            return Err(ApiError::Validation(
+                "The recording could not be read".to_string(),
+            ));
+        }
+        if !status.is_success() {
+            return Err(ApiError::Speech(format!(
+                "Speech provider responded with {status}"
+            )));
+        }
+
+        let result: ListenResponse = serde_json::from_slice(&body)
+            .map_err(|e| ApiError::Speech(format!("Invalid speech provider response: {e}")))?;
+        Ok(result
+            .results
+            .channels
+            .into_iter()
+            .next()
+            .and_then(|channel| channel.alternatives.into_iter().next())
+            .map(|alternative| alternative.transcript)
+            .unwrap_or_default())
+    }
+}
+
+impl SpeechToText for DeepgramSpeechToText {
+    fn transcribe<'a>(
+        &'a self,
+        audio: &'a [u8],
+        content_type: &'a str,
+        language: &'a str,
+    ) -> TranscribeFuture<'a> {
+        Box::pin(self.transcribe_audio(audio, content_type, language))
+    }
+}
+
+/// Whether `content_type` is an audio type worth sending to the provider
+#[must_use]
+pub fn is_audio(content_type: &str) -> bool {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+        .starts_with("audio/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_audio_types_are_accepted() {
+        assert!(is_audio("audio/webm"));
+        assert!(is_audio("Audio/Ogg; codecs=opus"));
+        assert!(!is_audio("application/octet-stream"));
+        assert!(!is_audio(""));
+    }
+
+    #[test]
+    fn test_transcript_is_read_from_the_first_alternative() {
+        let body = r#"{"results":{"channels":[{"alternatives":[{"transcript":"gato","confidence":0.98}]}]}}"#;
+        let result: ListenResponse = serde_json::from_str(body).unwrap();
+        assert_eq!(
+            result.results.channels[0].alternatives[0].transcript,
+            "gato"
+        );
+    }
+}
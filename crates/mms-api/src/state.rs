@@ -1,24 +1,61 @@
 use std::sync::Arc;
 
+use anyhow::Context;
 use axum::extract::FromRef;
 use axum_extra::extract::cookie::Key;
 use tokio::sync::mpsc;
 
 use crate::auth::google::{self, OpenIdClient};
+use crate::auth::jwt::JwtKeys;
+use crate::auth::refresh_token::SessionLifetime;
+use crate::captcha::{CaptchaVerifier, HttpCaptchaVerifier};
+use crate::health::HealthState;
+use crate::jobs::schedule::JobSchedules;
+use crate::mailer::EmailSender;
+use crate::media::MediaStore;
+use crate::middleware::rate_limit::{self, RateLimitBackend, redis::RedisLimiter};
+use crate::speech::{DeepgramSpeechToText, SpeechToText, SttProvider};
 use crate::{
-    ApiConfig,
-    config::Environment,
-    user::email::{EmailJob, EmailService},
+    ApiConfig, client_errors, config::Environment, live::EventBus, middleware::drain::DrainState,
+    public_cache::PublicCache, user::email::EmailJob,
 };
+use mms_db::PoolPair;
+use mms_srs::clock::{Clock, SystemClock};
 use sqlx::PgPool;
 
 /// JWT and password-hashing configuration.
 #[derive(Clone)]
 pub struct AuthConfig {
-    pub jwt_secret: Arc<str>,
+    /// Signing key and keys still accepted for verification
+    pub jwt_keys: JwtKeys,
+    /// Stable key for calendar feed, widget and email preferences tokens, never
+    /// rotated with `jwt_keys`
+    pub feed_keys: JwtKeys,
     pub bcrypt_cost: u32,
     pub jwt_expiry_hours: i64,
     pub refresh_token_expiry_days: i64,
+    /// Whether each refresh restarts the refresh token's lifetime
+    pub refresh_token_sliding: bool,
+    /// Maximum age of a session, after which the user must sign in again
+    pub refresh_token_max_session_days: i64,
+    /// Consecutive failed logins after which a captcha is required
+    pub login_captcha_threshold: i32,
+    /// Bearer token for maintenance endpoints, `None` disables them
+    pub admin_api_token: Option<Arc<str>>,
+    /// Secret email provider webhooks must present, `None` disables them
+    pub email_webhook_secret: Option<Arc<str>>,
+}
+
+impl AuthConfig {
+    /// Lifetime rules applied when a refresh token is rotated
+    #[must_use]
+    pub fn refresh_session_lifetime(&self) -> SessionLifetime {
+        SessionLifetime {
+            expiry_days: self.refresh_token_expiry_days,
+            sliding: self.refresh_token_sliding,
+            max_session_days: self.refresh_token_max_session_days,
+        }
+    }
 }
 
 /// Cookie-related configuration.
@@ -43,14 +80,115 @@ pub struct ApiState {
     pub cookie: CookieConfig,
     pub oidc: OidcConfig,
     pub pool: PgPool,
+    /// Pool for read-only queries that tolerate replication lag; the primary
+    /// when no replica is configured
+    pub read_pool: PgPool,
     pub email_tx: Option<mpsc::UnboundedSender<EmailJob>>,
+    /// Email provider, checked by the readiness probe; `None` when email is not configured
+    pub mailer: Option<Arc<dyn EmailSender>>,
+    pub drain: DrainState,
+    /// Live events pushed to WebSocket clients
+    pub events: EventBus,
+    /// Where uploaded avatars are kept
+    pub media: Arc<dyn MediaStore>,
+    /// Captcha verifier, `None` when captcha is disabled
+    pub captcha: Option<Arc<dyn CaptchaVerifier>>,
+    /// Transcriber for pronunciation practice, `None` when no provider is configured
+    pub speech: Option<Arc<dyn SpeechToText>>,
+    /// Public listings served while the database is unreachable
+    pub public_cache: PublicCache,
+    /// Dependency checks kept between readiness probes
+    pub health: HealthState,
+    /// Decides which client error reports are stored
+    pub client_errors: client_errors::Sampler,
+    /// Cron schedules of the queued maintenance jobs
+    pub schedules: Arc<JobSchedules>,
+    /// Current time for scheduling reviews; frozen in tests
+    pub clock: Arc<dyn Clock>,
 }
 
 impl ApiState {
-    pub async fn new(config: ApiConfig, pool: PgPool) -> anyhow::Result<Self> {
+    pub async fn new(config: ApiConfig, pools: PoolPair) -> anyhow::Result<Self> {
+        let pool = pools.primary().clone();
+        let read_pool = pools.reader().clone();
+        if pools.replica().is_some() {
+            tracing::info!("Read replica configured for catalogue, roadmap and statistics reads");
+        }
+
+        let jwt_keys = JwtKeys::from_config(&config)?;
+        tracing::info!(
+            "JWT signing key {} ({:?}), {} verification key(s)",
+            jwt_keys.active().kid(),
+            jwt_keys.active().algorithm(),
+            jwt_keys.verification_keys().len()
+        );
+
+        let feed_keys = match config
+            .feed_token_secret
+            .as_deref()
+            .filter(|s| !s.is_empty())
+        {
+            Some(secret) => JwtKeys::hmac(secret),
+            None => {
+                tracing::warn!(
+                    "FEED_TOKEN_SECRET not set; calendar feed, widget and unsubscribe links stop working when JWT_SECRET rotates"
+                );
+                JwtKeys::hmac(&config.jwt_secret)
+            }
+        };
+
+        match config.pii_keyring()? {
+            Some(keyring) => {
+                tracing::info!("PII encryption enabled ({keyring:?})");
+                mms_db::encryption::install_keyring(keyring);
+            }
+            None => {
+                tracing::warn!("PII_ENCRYPTION_KEY not set; PII columns are stored in plain text")
+            }
+        }
+
+        if config.rate_limit_backend == RateLimitBackend::Redis {
+            let url = config.redis_url.as_deref().unwrap_or_default();
+            let limiter = RedisLimiter::connect(url)
+                .await
+                .context("Failed to connect to Redis for rate limiting")?;
+            tracing::info!("Rate limits shared through Redis");
+            rate_limit::install_redis(limiter);
+        }
+
+        let schedules = Arc::new(JobSchedules::from_config(&config)?);
+
         // Create cookie key
         let cookie_key = Key::from(config.cookie_secret.as_bytes());
 
+        // Start the email worker if an email provider is configured
+        let mailer = match crate::mailer::sender_from_config(&config) {
+            Ok(sender) => sender,
+            Err(e) => {
+                tracing::error!("Failed to initialize email provider: {e}");
+                None
+            }
+        };
+        let email_tx = match &mailer {
+            Some(sender) => {
+                tracing::info!(provider = ?config.email_provider(), "Email background worker started");
+                Some(crate::user::email::start_email_worker(
+                    sender.clone(),
+                    pool.clone(),
+                    config.frontend_url.clone(),
+                ))
+            }
+            None => {
+                tracing::warn!(
+                    "Email not configured, set EMAIL_PROVIDER or SMTP_HOST to send email"
+                );
+                None
+            }
+        };
+
+        let media = crate::media::store_from_config(&config, pool.clone())?;
+        tracing::info!(provider = ?config.media_provider, "Media store configured");
+
         // Create Google OIDC client
         let oidc_client = google::create_oidc_client(
             config.google_client_id,
@@ -59,50 +197,23 @@ impl ApiState {
         )
         .await?;
 
-        // Initialize email worker if SMTP is configured
-        let email_tx = if let (
-            Some(host),
-            Some(username),
-            Some(password),
-            Some(from_email),
-            Some(from_name),
-        ) = (
-            config.smtp_host.as_ref(),
-            config.smtp_username.as_ref(),
-            config.smtp_password.as_ref(),
-            config.smtp_from_email.as_ref(),
-            config.smtp_from_name.as_ref(),
-        ) {
-            tracing::info!("Initializing email service with host: {}", host);
-            match EmailService::new(
-                host,
-                username,
-                password,
-                from_email,
-                from_name,
-                &config.frontend_url,
-            ) {
-                Ok(service) => {
-                    tracing::info!("Email service initialized successfully");
-                    let tx = crate::user::email::start_email_worker(service);
-                    tracing::info!("Email background worker started");
-                    Some(tx)
+        let captcha: Option<Arc<dyn CaptchaVerifier>> =
+            match (&config.captcha_provider, &config.captcha_secret) {
+                (Some(provider), Some(secret)) if config.captcha_enabled => {
+                    tracing::info!("Captcha verification enabled with provider: {:?}", provider);
+                    Some(Arc::new(HttpCaptchaVerifier::new(*provider, secret)))
                 }
-                Err(e) => {
-                    tracing::error!("Failed to initialize email service: {e}");
-                    None
+                _ => None,
+            };
+
+        let speech: Option<Arc<dyn SpeechToText>> =
+            match (&config.stt_provider, &config.stt_api_key) {
+                (Some(SttProvider::Deepgram), Some(api_key)) => {
+                    tracing::info!("Speech-to-text enabled with provider: Deepgram");
+                    Some(Arc::new(DeepgramSpeechToText::new(api_key)))
                 }
-            }
-        } else {
-            tracing::warn!(
-                "Email service not configured. SMTP config: host={:?}, username={:?}, password=***, from_email={:?}, from_name={:?}",
-                config.smtp_host,
-                config.smtp_username,
-                config.smtp_from_email,
-                config.smtp_from_name
-            );
-            None
-        };
+                _ => None,
+            };
 
         tracing::info!(
             "Initializing ApiState with bcrypt_cost: {} (estimated login time: ~{}ms)",
@@ -112,10 +223,16 @@ impl ApiState {
 
         Ok(Self {
             auth: AuthConfig {
-                jwt_secret: config.jwt_secret.into(),
+                jwt_keys,
+                feed_keys,
                 bcrypt_cost: config.bcrypt_cost,
                 jwt_expiry_hours: config.jwt_expiry_hours,
                 refresh_token_expiry_days: config.refresh_token_expiry_days,
+                refresh_token_sliding: config.refresh_token_sliding,
+                refresh_token_max_session_days: config.refresh_token_max_session_days,
+                login_captcha_threshold: config.captcha_login_failure_threshold,
+                admin_api_token: config.admin_api_token.map(Into::into),
+                email_webhook_secret: config.email_webhook_secret.map(Into::into),
             },
             cookie: CookieConfig {
                 cookie_domain: config.cookie_domain.into(),
@@ -128,7 +245,22 @@ impl ApiState {
                 frontend_url: config.frontend_url.into(),
             },
             pool,
+            read_pool,
             email_tx,
+            mailer,
+            drain: DrainState::default(),
+            events: EventBus::default(),
+            media,
+            captcha,
+            speech,
+            public_cache: PublicCache::default(),
+            health: HealthState::default(),
+            schedules,
+            client_errors: client_errors::Sampler::new(
+                config.client_error_sample_rate,
+                config.client_error_hourly_limit,
+            ),
+            clock: Arc::new(SystemClock),
         })
     }
 }
@@ -157,6 +289,12 @@ impl FromRef<ApiState> for OidcConfig {
     }
 }
 
+impl FromRef<ApiState> for DrainState {
+    fn from_ref(state: &ApiState) -> Self {
+        state.drain.clone()
+    }
+}
+
 impl FromRef<ApiState> for PgPool {
     fn from_ref(state: &ApiState) -> Self {
         state.pool.clone()
@@ -0,0 +1,172 @@
+//! Activity over a chosen range, by day, week or month, for the heatmap.
+//!
+//! Dates are local to the user. The range defaults to the year up to today,
+//! which is what the dashboard heatmap shows.
+
+use chrono::{Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use mms_db::models::ActivityPeriod;
+use mms_db::repositories::user as user_repo;
+
+use crate::error::ApiError;
+
+/// Days covered when `from` is omitted, today included
+pub const DEFAULT_RANGE_DAYS: u64 = 365;
+
+/// Longest range accepted, three years
+pub const MAX_RANGE_DAYS: u64 = 1096;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    #[default]
+    Day,
+    /// Weeks start on Monday
+    Week,
+    Month,
+}
+
+impl Granularity {
+    /// Unit understood by PostgreSQL's `date_trunc`
+    fn unit(self) -> &'static str {
+        match self {
+            Self::Day => "day",
+            Self::Week => "week",
+            Self::Month => "month",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ActivityBucket {
+    /// First day of the period: the day itself, the week's Monday or the month's 1st
+    pub start: NaiveDate,
+    pub reviews: i64,
+    pub correct: i64,
+    /// Answer times reported with the period's reviews, rounded to a tenth
+    pub minutes_studied: f64,
+    /// Cards reviewed for the first time
+    pub new_cards: i64,
+}
+
+impl From<ActivityPeriod> for ActivityBucket {
+    fn from(period: ActivityPeriod) -> Self {
+        Self {
+            start: period.period_start,
+            reviews: period.reviews,
+            correct: period.correct,
+            minutes_studied: (period.study_ms as f64 / 6_000.0).round() / 10.0,
+            new_cards: period.new_cards,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Activity {
+    /// First local day covered
+    pub from: NaiveDate,
+    /// Last local day covered
+    pub to: NaiveDate,
+    pub granularity: Granularity,
+    /// Oldest first; periods without activity are missing. The first and last
+    /// periods only count the days within the range.
+    pub periods: Vec<ActivityBucket>,
+}
+
+/// The range to report: `to` defaults to `today` and `from` to the
+/// [`DEFAULT_RANGE_DAYS`] days ending on `to`
+fn resolve_range(
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    today: NaiveDate,
+) -> Result<(NaiveDate, NaiveDate), ApiError> {
+    let to = to.unwrap_or(today);
+    let from = match from {
+        Some(from) => from,
+        None => to
+            .checked_sub_days(Days::new(DEFAULT_RANGE_DAYS - 1))
+            .unwrap_or(NaiveDate::MIN),
+    };
+
+    if from > to {
+        return Err(ApiError::Validation(
+            "from must not be after to".to_string(),
+        ));
+    }
+    if (to - from).num_days() >= MAX_RANGE_DAYS as i64 {
+        return Err(ApiError::Validation(format!(
+            "Range must be at most {MAX_RANGE_DAYS} days"
+        )));
+    }
+    Ok((from, to))
+}
+
+/// The user's activity from `from` to `to`, both inclusive, per `granularity`
+pub async fn get(
+    pool: &PgPool,
+    user_id: Uuid,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    granularity: Granularity,
+) -> Result<Activity, ApiError> {
+    let today = user_repo::local_date(pool, user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+    let (from, to) = resolve_range(from, to, today)?;
+
+    let periods =
+        user_repo::get_activity_periods(pool, user_id, from, to, granularity.unit()).await?;
+    Ok(Activity {
+        from,
+        to,
+        granularity,
+        periods: periods.into_iter().map(ActivityBucket::from).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, day).unwrap()
+    }
+
+    #[test]
+    fn range_defaults_to_the_year_up_to_today() {
+        let (from, to) = resolve_range(None, None, date(18)).unwrap();
+        assert_eq!(to, date(18));
+        assert_eq!((to - from).num_days(), DEFAULT_RANGE_DAYS as i64 - 1);
+
+        let (from, to) = resolve_range(Some(date(1)), None, date(18)).unwrap();
+        assert_eq!((from, to), (date(1), date(18)));
+    }
+
+    #[test]
+    fn inverted_or_too_long_ranges_are_rejected() {
+        assert!(resolve_range(Some(date(10)), Some(date(9)), date(18)).is_err());
+        assert!(resolve_range(Some(date(10)), Some(date(10)), date(18)).is_ok());
+
+        let to = date(18);
+        let longest = to.checked_sub_days(Days::new(MAX_RANGE_DAYS - 1)).unwrap();
+        assert!(resolve_range(Some(longest), Some(to), to).is_ok());
+        let too_long = longest.pred_opt().unwrap();
+        assert!(resolve_range(Some(too_long), Some(to), to).is_err());
+    }
+
+    #[test]
+    fn study_time_is_reported_in_minutes() {
+        let bucket = ActivityBucket::from(ActivityPeriod {
+            period_start: date(5),
+            reviews: 10,
+            correct: 8,
+            study_ms: 200_000,
+            new_cards: 3,
+        });
+        assert_eq!(bucket.minutes_studied, 3.3);
+    }
+}
@@ -0,0 +1,218 @@
+//! Detailed study statistics: accuracy over time, retention, when the user
+//! studies and how well their cards are learned.
+//!
+//! The curves come from the review log, so they start with the first logged
+//! review. The interval and maturity figures describe the cards as they are now.
+
+use chrono::NaiveDate;
+use serde::Serialize;
+use sqlx::PgPool;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use mms_db::models::{CardAnswerTime, DeckAnswerTime, MaturityCounts, SlotReviewCounts};
+use mms_db::repositories::{review_log as review_log_repo, stats as stats_repo};
+
+use crate::error::ApiError;
+
+pub const DEFAULT_DAYS: i32 = 30;
+pub const MAX_DAYS: i32 = 365;
+
+/// Cards at shorter intervals are still being learned
+pub(crate) const LEARNING_DAYS: f64 = 1.0;
+
+/// Cards at this interval or longer are mature
+const MATURE_DAYS: f64 = 21.0;
+
+/// Timed answers a card needs to be listed among the slowest
+const MIN_TIMED_REVIEWS: i64 = 2;
+
+/// Slowest cards listed
+const SLOWEST_CARDS: i64 = 10;
+
+/// Reviews and how many were answered correctly
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+pub struct Accuracy {
+    pub reviews: i64,
+    pub correct: i64,
+    /// `correct / reviews`; absent without reviews
+    pub rate: Option<f64>,
+}
+
+impl Accuracy {
+    fn new(reviews: i64, correct: i64) -> Self {
+        Self {
+            reviews,
+            correct,
+            rate: (reviews > 0).then(|| correct as f64 / reviews as f64),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AccuracyDay {
+    /// Local date in the user's timezone
+    pub date: NaiveDate,
+    pub reviews: i64,
+    pub correct: i64,
+    pub rate: f64,
+}
+
+/// Cards by how well they are learned
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Maturity {
+    /// Never reviewed cards in the decks the user practises
+    pub new: i64,
+    /// Interval under a day
+    pub learning: i64,
+    /// Interval under 21 days
+    pub young: i64,
+    /// Interval of 21 days or more
+    pub mature: i64,
+}
+
+impl From<MaturityCounts> for Maturity {
+    fn from(counts: MaturityCounts) -> Self {
+        Self {
+            new: counts.new_cards,
+            learning: counts.learning,
+            young: counts.young,
+            mature: counts.mature,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DetailedStats {
+    /// Local days covered by the review figures, today included
+    pub days: i32,
+    /// Accuracy per day, oldest first; days without reviews are missing
+    pub accuracy: Vec<AccuracyDay>,
+    /// Mean interval of the reviewed cards; absent before the first review
+    pub average_interval_days: Option<f64>,
+    /// Answers to cards recalled after at least a day, leaving out learning steps
+    pub true_retention: Accuracy,
+    /// The part of `true_retention` on mature cards
+    pub mature_retention: Accuracy,
+    /// Seven entries, Monday first, in the user's timezone
+    pub by_weekday: Vec<Accuracy>,
+    /// 24 entries, midnight first, in the user's timezone
+    pub by_hour: Vec<Accuracy>,
+    pub maturity: Maturity,
+    /// Mean time to answer, over the reviews that reported one
+    pub average_answer_ms: Option<f64>,
+    /// Mean time to answer in each deck, most reviewed first
+    pub answer_time_by_deck: Vec<DeckAnswerTime>,
+    /// Up to 10 cards the user takes longest to answer, slowest first
+    pub slowest_cards: Vec<CardAnswerTime>,
+}
+
+/// Compute the user's detailed statistics over the last `days` local days
+pub async fn compute(pool: &PgPool, user_id: Uuid, days: i32) -> Result<DetailedStats, ApiError> {
+    let (daily, weekdays, hours, retention, average_interval_days, maturity) = tokio::try_join!(
+        review_log_repo::daily_counts(pool, user_id, days),
+        review_log_repo::weekday_counts(pool, user_id, days),
+        review_log_repo::hour_counts(pool, user_id, days),
+        review_log_repo::retention_counts(pool, user_id, days, LEARNING_DAYS, MATURE_DAYS),
+        stats_repo::mean_interval_days(pool, user_id),
+        stats_repo::maturity_counts(pool, user_id, LEARNING_DAYS, MATURE_DAYS),
+    )?;
+    let (answer_time_by_deck, slowest_cards) = tokio::try_join!(
+        review_log_repo::answer_times_by_deck(pool, user_id, days),
+        review_log_repo::slowest_cards(pool, user_id, days, MIN_TIMED_REVIEWS, SLOWEST_CARDS),
+    )?;
+
+    Ok(DetailedStats {
+        days,
+        accuracy: daily
+            .into_iter()
+            .map(|day| AccuracyDay {
+                date: day.date,
+                reviews: day.reviews,
+                correct: day.correct,
+                rate: day.correct as f64 / day.reviews as f64,
+            })
+            .collect(),
+        average_interval_days,
+        true_retention: Accuracy::new(
+            retention.young_reviews + retention.mature_reviews,
+            retention.young_correct + retention.mature_correct,
+        ),
+        mature_retention: Accuracy::new(retention.mature_reviews, retention.mature_correct),
+        by_weekday: spread(&weekdays, 1, 7),
+        by_hour: spread(&hours, 0, 24),
+        maturity: maturity.into(),
+        average_answer_ms: overall_average(&answer_time_by_deck),
+        answer_time_by_deck,
+        slowest_cards,
+    })
+}
+
+/// The mean over all decks, weighting each deck by its timed reviews
+fn overall_average(decks: &[DeckAnswerTime]) -> Option<f64> {
+    let reviews: i64 = decks.iter().map(|deck| deck.reviews).sum();
+    let total_ms: f64 = decks
+        .iter()
+        .map(|deck| deck.average_ms * deck.reviews as f64)
+        .sum();
+    (reviews > 0).then(|| total_ms / reviews as f64)
+}
+
+/// One entry per slot from `first` on, with zeroes where the query returned no row
+fn spread(counts: &[SlotReviewCounts], first: i32, slots: usize) -> Vec<Accuracy> {
+    let mut spread = vec![Accuracy::new(0, 0); slots];
+    for count in counts {
+        if let Some(slot) = usize::try_from(count.slot - first)
+            .ok()
+            .and_then(|i| spread.get_mut(i))
+        {
+            *slot = Accuracy::new(count.reviews, count.correct);
+        }
+    }
+    spread
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accuracy_has_no_rate_without_reviews() {
+        assert_eq!(Accuracy::new(0, 0).rate, None);
+        assert_eq!(Accuracy::new(4, 3).rate, Some(0.75));
+    }
+
+    #[test]
+    fn overall_answer_time_weights_decks_by_reviews() {
+        let deck = |reviews, average_ms| DeckAnswerTime {
+            deck_id: None,
+            title: None,
+            reviews,
+            average_ms,
+        };
+        assert_eq!(overall_average(&[]), None);
+        assert_eq!(
+            overall_average(&[deck(3, 2000.0), deck(1, 6000.0)]),
+            Some(3000.0)
+        );
+    }
+
+    #[test]
+    fn weekdays_start_on_monday_and_fill_gaps() {
+        let count = |slot, reviews, correct| SlotReviewCounts {
+            slot,
+            reviews,
+            correct,
+        };
+        let weekdays = spread(&[count(1, 10, 9), count(7, 2, 1), count(8, 5, 5)], 1, 7);
+
+        assert_eq!(weekdays.len(), 7);
+        assert_eq!(weekdays[0], Accuracy::new(10, 9));
+        assert_eq!(weekdays[3], Accuracy::new(0, 0));
+        assert_eq!(weekdays[6], Accuracy::new(2, 1));
+
+        let hours = spread(&[count(0, 1, 1), count(23, 3, 0)], 0, 24);
+        assert_eq!(hours[0].reviews, 1);
+        assert_eq!(hours[23].rate, Some(0.0));
+    }
+}
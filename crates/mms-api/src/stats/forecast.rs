@@ -0,0 +1,205 @@
+//! Reviews due per day over the coming weeks, served from a precomputed row.
+//!
+//! Counting due cards scans a user's whole collection, so the counts are kept
+//! in `review_forecasts`: the nightly job recomputes them for active users and
+//! each review moves its card to its new day. A request only reads the row and
+//! drops the days that have passed since it was computed, which is why
+//! [`STORED_DAYS`] exceeds [`FORECAST_DAYS`]. Users whose row is missing or too
+//! old get theirs computed on the spot.
+//!
+//! Longer spans and forecasts for a single deck are not stored; they are
+//! counted from the user's progress on each request.
+
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use mms_db::models::StoredForecast;
+use mms_db::repositories::{forecast as forecast_repo, practice as practice_repo};
+
+use crate::error::ApiError;
+
+/// Days served by default, starting with today; the stored row covers up to this many
+pub const FORECAST_DAYS: usize = 30;
+
+/// Longest span a request may ask for
+pub const MAX_FORECAST_DAYS: usize = 365;
+
+/// Days stored; the excess lets a row serve a full forecast for a week
+const STORED_DAYS: usize = FORECAST_DAYS + 7;
+
+/// Users who practised this recently are refreshed by the nightly job
+const ACTIVE_DAYS: i32 = 30;
+
+/// Cards due on each local day from `starts_on`; the first day includes overdue cards
+#[derive(Debug, PartialEq)]
+pub struct Forecast {
+    pub starts_on: NaiveDate,
+    pub due: Vec<i32>,
+    pub computed_at: DateTime<Utc>,
+}
+
+/// Recompute the forecasts of every recently active user
+pub async fn refresh_active(pool: &PgPool) -> Result<u64, ApiError> {
+    Ok(forecast_repo::refresh(pool, None, STORED_DAYS as i32, ACTIVE_DAYS).await?)
+}
+
+/// The user's forecast for `days` days from their local today, of all their
+/// cards or only those in `deck_id`
+pub async fn get(
+    pool: &PgPool,
+    user_id: Uuid,
+    days: usize,
+    deck_id: Option<Uuid>,
+) -> Result<Forecast, ApiError> {
+    let not_found = || ApiError::NotFound("User not found".to_string());
+
+    if deck_id.is_some() || days > FORECAST_DAYS {
+        return practice_repo::due_forecast(pool, user_id, days as i32, deck_id)
+            .await?
+            .and_then(|counted| current(counted, days))
+            .ok_or_else(not_found);
+    }
+
+    let stored = forecast_repo::find(pool, user_id).await?;
+    if let Some(forecast) = stored.and_then(|stored| current(stored, days)) {
+        return Ok(forecast);
+    }
+
+    forecast_repo::refresh(pool, Some(user_id), STORED_DAYS as i32, ACTIVE_DAYS).await?;
+    forecast_repo::find(pool, user_id)
+        .await?
+        .and_then(|stored| current(stored, days))
+        .ok_or_else(not_found)
+}
+
+/// Keep the stored forecast in step with a review that rescheduled a card
+pub async fn apply_review(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    previous_due: Option<DateTime<Utc>>,
+    next_due: DateTime<Utc>,
+) -> Result<(), ApiError> {
+    forecast_repo::apply_review(&mut **tx, user_id, previous_due, next_due).await?;
+    Ok(())
+}
+
+/// The stored forecast as of its `today`, or None if it no longer covers
+/// `days` days from there
+fn current(stored: StoredForecast, days: usize) -> Option<Forecast> {
+    let elapsed = usize::try_from((stored.today - stored.starts_on).num_days()).ok()?;
+    if days == 0 || stored.due_counts.len() < elapsed + days {
+        return None;
+    }
+
+    // Cards due on the days that passed are overdue now, so due today
+    let mut due = stored.due_counts[elapsed..elapsed + days].to_vec();
+    due[0] += stored.due_counts[..elapsed].iter().sum::<i32>();
+
+    Some(Forecast {
+        starts_on: stored.today,
+        due,
+        computed_at: stored.computed_at,
+    })
+}
+
+impl Forecast {
+    /// `(date, cards due)` for each day
+    pub fn days(&self) -> impl Iterator<Item = (NaiveDate, i32)> + '_ {
+        self.due.iter().enumerate().map(|(offset, &due)| {
+            let date = self
+                .starts_on
+                .checked_add_days(Days::new(offset as u64))
+                .unwrap_or(NaiveDate::MAX);
+            (date, due)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored(starts_on: NaiveDate, today: NaiveDate, due_counts: Vec<i32>) -> StoredForecast {
+        StoredForecast {
+            starts_on,
+            today,
+            due_counts,
+            computed_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn fresh_forecast_serves_the_first_days() {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 5).unwrap();
+        let counts: Vec<i32> = (1..=STORED_DAYS as i32).collect();
+        let forecast = current(stored(today, today, counts), FORECAST_DAYS).unwrap();
+
+        assert_eq!(forecast.starts_on, today);
+        assert_eq!(forecast.due.len(), FORECAST_DAYS);
+        assert_eq!(forecast.due[0], 1);
+        assert_eq!(forecast.due[FORECAST_DAYS - 1], FORECAST_DAYS as i32);
+    }
+
+    #[test]
+    fn passed_days_become_overdue() {
+        let computed_on = NaiveDate::from_ymd_opt(2026, 10, 5).unwrap();
+        let today = NaiveDate::from_ymd_opt(2026, 10, 7).unwrap();
+        let mut counts = vec![0; STORED_DAYS];
+        counts[0] = 4;
+        counts[1] = 2;
+        counts[2] = 1;
+        counts[3] = 5;
+        let forecast = current(stored(computed_on, today, counts), FORECAST_DAYS).unwrap();
+
+        assert_eq!(forecast.starts_on, today);
+        assert_eq!(&forecast.due[..2], &[7, 5]);
+        assert_eq!(
+            forecast.days().nth(1),
+            NaiveDate::from_ymd_opt(2026, 10, 8).map(|date| (date, 5))
+        );
+    }
+
+    #[test]
+    fn forecast_too_old_or_from_the_future_is_recomputed() {
+        let computed_on = NaiveDate::from_ymd_opt(2026, 10, 5).unwrap();
+        let week_later = NaiveDate::from_ymd_opt(2026, 10, 12).unwrap();
+        let day_later = NaiveDate::from_ymd_opt(2026, 10, 13).unwrap();
+
+        assert!(
+            current(
+                stored(computed_on, week_later, vec![0; STORED_DAYS]),
+                FORECAST_DAYS
+            )
+            .is_some()
+        );
+        assert!(
+            current(
+                stored(computed_on, day_later, vec![0; STORED_DAYS]),
+                FORECAST_DAYS
+            )
+            .is_none()
+        );
+        // The user moved to a timezone that is still on the previous day
+        assert!(
+            current(
+                stored(week_later, computed_on, vec![0; STORED_DAYS]),
+                FORECAST_DAYS
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn shorter_forecasts_serve_from_an_older_row() {
+        let computed_on = NaiveDate::from_ymd_opt(2026, 10, 5).unwrap();
+        let later = NaiveDate::from_ymd_opt(2026, 10, 15).unwrap();
+        let counts: Vec<i32> = (1..=STORED_DAYS as i32).collect();
+
+        assert!(current(stored(computed_on, later, counts.clone()), FORECAST_DAYS).is_none());
+        let forecast = current(stored(computed_on, later, counts.clone()), 7).unwrap();
+        assert_eq!(forecast.due.len(), 7);
+        assert_eq!(forecast.due[1], 12);
+        assert!(current(stored(computed_on, computed_on, counts), 0).is_none());
+    }
+}
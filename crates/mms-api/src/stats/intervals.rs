@@ -0,0 +1,131 @@
+//! Card interval distribution and its weekly history.
+//!
+//! A card's interval is the time between its last review and its next one, so
+//! it grows as the card is learned. Cards are grouped into the fixed
+//! [`BUCKETS`]; the snapshot job stores each user's counts once a week so the
+//! distribution can be charted over time.
+
+use chrono::NaiveDate;
+use sqlx::PgPool;
+
+use mms_db::models::{IntervalBucketCount, IntervalSnapshot};
+use mms_db::repositories::stats as stats_repo;
+
+use crate::error::ApiError;
+
+/// Interval buckets as `(label, min_days)`; each ends where the next begins.
+///
+/// Stored snapshots refer to buckets by index, so only append to this list.
+pub const BUCKETS: [(&str, u32); 9] = [
+    ("< 1 day", 0),
+    ("1 day", 1),
+    ("2-3 days", 2),
+    ("4-7 days", 4),
+    ("1-2 weeks", 8),
+    ("2 weeks - 1 month", 15),
+    ("1-3 months", 31),
+    ("3-6 months", 91),
+    ("6+ months", 181),
+];
+
+/// Lower bounds of every bucket but the first, as the repository expects them
+pub fn lower_bounds_days() -> Vec<f64> {
+    BUCKETS[1..]
+        .iter()
+        .map(|&(_, min)| f64::from(min))
+        .collect()
+}
+
+/// Spread per-bucket counts into one slot per bucket, in bucket order
+pub fn counts_by_bucket(counts: &[IntervalBucketCount]) -> Vec<i64> {
+    let mut cards = vec![0; BUCKETS.len()];
+    for count in counts {
+        if let Some(slot) = usize::try_from(count.bucket)
+            .ok()
+            .and_then(|i| cards.get_mut(i))
+        {
+            *slot += count.cards;
+        }
+    }
+    cards
+}
+
+/// Group stored snapshot rows by week, oldest first, one count per bucket
+pub fn weekly_counts(snapshots: &[IntervalSnapshot]) -> Vec<(NaiveDate, Vec<i64>)> {
+    let mut weeks: Vec<(NaiveDate, Vec<i64>)> = Vec::new();
+    for snapshot in snapshots {
+        if weeks
+            .last()
+            .is_none_or(|(week, _)| *week != snapshot.week_start)
+        {
+            weeks.push((snapshot.week_start, vec![0; BUCKETS.len()]));
+        }
+        let (_, cards) = weeks.last_mut().expect("pushed above");
+        if let Some(slot) = usize::try_from(snapshot.bucket)
+            .ok()
+            .and_then(|i| cards.get_mut(i))
+        {
+            *slot += i64::from(snapshot.cards);
+        }
+    }
+    weeks
+}
+
+/// Store every user's current distribution as this week's snapshot.
+///
+/// Rewrites the current week, so running it several times a week keeps the
+/// last state. Returns the number of rows written.
+pub async fn snapshot_intervals(pool: &PgPool) -> Result<u64, ApiError> {
+    let mut tx = pool.begin().await?;
+    stats_repo::delete_current_week_snapshots(&mut *tx).await?;
+    let written = stats_repo::insert_current_week_snapshots(&mut *tx, &lower_bounds_days()).await?;
+    tx.commit().await?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_ascend() {
+        assert_eq!(BUCKETS[0].1, 0);
+        assert!(BUCKETS.windows(2).all(|pair| pair[0].1 < pair[1].1));
+        assert_eq!(lower_bounds_days().len(), BUCKETS.len() - 1);
+    }
+
+    #[test]
+    fn counts_fill_missing_buckets_and_ignore_unknown_ones() {
+        let counts = [
+            IntervalBucketCount {
+                bucket: 1,
+                cards: 4,
+            },
+            IntervalBucketCount {
+                bucket: 8,
+                cards: 2,
+            },
+            IntervalBucketCount {
+                bucket: 42,
+                cards: 7,
+            },
+        ];
+        assert_eq!(counts_by_bucket(&counts), vec![0, 4, 0, 0, 0, 0, 0, 0, 2]);
+    }
+
+    #[test]
+    fn snapshots_group_by_week() {
+        let monday = NaiveDate::from_ymd_opt(2026, 10, 5).unwrap();
+        let next_monday = NaiveDate::from_ymd_opt(2026, 10, 12).unwrap();
+        let row = |week_start, bucket, cards| IntervalSnapshot {
+            week_start,
+            bucket,
+            cards,
+        };
+        let weeks = weekly_counts(&[row(monday, 0, 3), row(monday, 2, 1), row(next_monday, 2, 4)]);
+
+        assert_eq!(weeks.len(), 2);
+        assert_eq!(weeks[0], (monday, vec![3, 0, 1, 0, 0, 0, 0, 0, 0]));
+        assert_eq!(weeks[1], (next_monday, vec![0, 0, 4, 0, 0, 0, 0, 0, 0]));
+    }
+}
@@ -0,0 +1,9 @@
+//! Learning statistics for charts.
+
+pub mod activity;
+pub mod detailed;
+pub mod forecast;
+pub mod intervals;
+pub mod routes;
+
+pub use routes::routes;
@@ -0,0 +1,372 @@
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    routing::get,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    ApiState,
+    auth::{AuthUser, require_self},
+    error::{ApiError, ErrorResponse},
+};
+
+use mms_db::repositories::stats as stats_repo;
+
+use super::{
+    activity::{self, Activity, Granularity},
+    detailed::{self, DetailedStats, LEARNING_DAYS},
+    forecast,
+    intervals::{self, BUCKETS},
+};
+
+const DEFAULT_HISTORY_WEEKS: i32 = 26;
+const MAX_HISTORY_WEEKS: i32 = 104;
+
+/// Create the statistics routes
+pub fn routes() -> Router<ApiState> {
+    Router::new()
+        .route("/users/{user_id}/stats/intervals", get(get_intervals))
+        .route("/users/{user_id}/stats/detailed", get(get_detailed_stats))
+        .route("/users/{user_id}/forecast", get(get_forecast))
+        .route("/users/{user_id}/due-counts", get(get_due_counts))
+        .route("/users/{user_id}/activity", get(get_activity))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct IntervalsQuery {
+    /// Weeks of history, including the current one, 1 to 104 (default 26)
+    #[serde(default)]
+    weeks: Option<i32>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct IntervalBucket {
+    label: &'static str,
+    /// Inclusive
+    min_days: u32,
+    /// Exclusive; absent on the last bucket
+    max_days: Option<u32>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct IntervalWeek {
+    /// Monday of the week (UTC)
+    week_start: NaiveDate,
+    /// Cards per bucket, in `buckets` order, at the end of the week
+    cards: Vec<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct IntervalStats {
+    buckets: Vec<IntervalBucket>,
+    /// Cards per bucket right now, in `buckets` order
+    current: Vec<i64>,
+    /// Absent until a card has been reviewed
+    median_days: Option<f64>,
+    /// Weekly snapshots, oldest first; weeks without reviewed cards are missing
+    history: Vec<IntervalWeek>,
+}
+
+/// Distribution of the user's card intervals, now and week by week
+///
+/// Only reviewed cards have an interval. The current week's snapshot is
+/// refreshed daily, so it can lag `current` by up to a day.
+#[utoipa::path(
+    get,
+    path = "/v1/users/{user_id}/stats/intervals",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    params(
+        ("user_id" = Uuid, Path, description = "Must be the signed-in user"),
+        IntervalsQuery,
+    ),
+    responses(
+        (status = 200, description = "Interval distribution and history", body = IntervalStats),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Another user's statistics", body = ErrorResponse),
+    )
+)]
+async fn get_intervals(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(user_id): Path<Uuid>,
+    Query(query): Query<IntervalsQuery>,
+) -> Result<Json<IntervalStats>, ApiError> {
+    require_self(&auth_user, user_id)?;
+
+    let weeks = query
+        .weeks
+        .unwrap_or(DEFAULT_HISTORY_WEEKS)
+        .clamp(1, MAX_HISTORY_WEEKS);
+
+    let pool = &state.read_pool;
+    let lower_bounds = intervals::lower_bounds_days();
+    let (current, median_days, snapshots) = tokio::try_join!(
+        stats_repo::interval_distribution(pool, user_id, &lower_bounds),
+        stats_repo::median_interval_days(pool, user_id),
+        stats_repo::list_interval_snapshots(pool, user_id, weeks),
+    )?;
+
+    let buckets = BUCKETS
+        .iter()
+        .enumerate()
+        .map(|(i, &(label, min_days))| IntervalBucket {
+            label,
+            min_days,
+            max_days: BUCKETS.get(i + 1).map(|&(_, next)| next),
+        })
+        .collect();
+
+    Ok(Json(IntervalStats {
+        buckets,
+        current: intervals::counts_by_bucket(&current),
+        median_days,
+        history: intervals::weekly_counts(&snapshots)
+            .into_iter()
+            .map(|(week_start, cards)| IntervalWeek { week_start, cards })
+            .collect(),
+    }))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct DetailedQuery {
+    /// Local days of reviews to include, today included, 1 to 365 (default 30)
+    #[serde(default)]
+    days: Option<i32>,
+}
+
+/// Accuracy over time, retention, study times and card maturity
+///
+/// Review figures come from the review log and leave out implausible reviews;
+/// the interval and maturity figures describe the cards now.
+#[utoipa::path(
+    get,
+    path = "/v1/users/{user_id}/stats/detailed",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    params(
+        ("user_id" = Uuid, Path, description = "Must be the signed-in user"),
+        DetailedQuery,
+    ),
+    responses(
+        (status = 200, description = "Detailed statistics", body = DetailedStats),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Another user's statistics", body = ErrorResponse),
+    )
+)]
+async fn get_detailed_stats(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(user_id): Path<Uuid>,
+    Query(query): Query<DetailedQuery>,
+) -> Result<Json<DetailedStats>, ApiError> {
+    require_self(&auth_user, user_id)?;
+
+    let days = query
+        .days
+        .unwrap_or(detailed::DEFAULT_DAYS)
+        .clamp(1, detailed::MAX_DAYS);
+    Ok(Json(
+        detailed::compute(&state.read_pool, user_id, days).await?,
+    ))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct ForecastDay {
+    /// Local date in the user's timezone
+    date: NaiveDate,
+    /// Cards scheduled for the day; today's count includes overdue cards
+    due: i32,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct ReviewForecast {
+    /// One entry per day, starting today
+    days: Vec<ForecastDay>,
+    /// When the counts were last recomputed in full; reviews since are included.
+    /// Counts computed for this request carry the current time.
+    computed_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ForecastQuery {
+    /// Days to forecast, today included, 1 to 365 (default 30)
+    #[serde(default)]
+    days: Option<usize>,
+    /// Only count the cards of this deck
+    #[serde(default)]
+    deck_id: Option<Uuid>,
+}
+
+/// Cards due for review on each of the next days
+///
+/// Forecasts of all cards up to 30 days are served from counts precomputed
+/// nightly and kept up to date by every review; longer ones and those for a
+/// single deck are counted on request.
+#[utoipa::path(
+    get,
+    path = "/v1/users/{user_id}/forecast",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    params(
+        ("user_id" = Uuid, Path, description = "Must be the signed-in user"),
+        ForecastQuery,
+    ),
+    responses(
+        (status = 200, description = "Reviews due per day", body = ReviewForecast),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Another user's forecast", body = ErrorResponse),
+    )
+)]
+async fn get_forecast(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(user_id): Path<Uuid>,
+    Query(query): Query<ForecastQuery>,
+) -> Result<Json<ReviewForecast>, ApiError> {
+    require_self(&auth_user, user_id)?;
+
+    let days = query
+        .days
+        .unwrap_or(forecast::FORECAST_DAYS)
+        .clamp(1, forecast::MAX_FORECAST_DAYS);
+    let forecast = forecast::get(&state.read_pool, user_id, days, query.deck_id).await?;
+    Ok(Json(ReviewForecast {
+        days: forecast
+            .days()
+            .map(|(date, due)| ForecastDay { date, due })
+            .collect(),
+        computed_at: forecast.computed_at,
+    }))
+}
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+struct CardCounts {
+    /// Cards never reviewed
+    new: i64,
+    /// Due now, at an interval under a day
+    learning: i64,
+    /// Due now, at an interval of a day or more
+    due: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct DeckDueCounts {
+    deck_id: Uuid,
+    title: String,
+    #[serde(flatten)]
+    counts: CardCounts,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct DueCounts {
+    /// Decks the user has started, by title
+    decks: Vec<DeckDueCounts>,
+    /// All those decks together; a card in several decks counts once
+    total: CardCounts,
+}
+
+/// New, learning and due cards in each deck the user has started, and in total
+///
+/// Counted in one grouped query, for deck list badges; hidden cards are left out.
+#[utoipa::path(
+    get,
+    path = "/v1/users/{user_id}/due-counts",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    params(("user_id" = Uuid, Path, description = "Must be the signed-in user")),
+    responses(
+        (status = 200, description = "Card counts per deck and in total", body = DueCounts),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Another user's counts", body = ErrorResponse),
+    )
+)]
+async fn get_due_counts(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<DueCounts>, ApiError> {
+    require_self(&auth_user, user_id)?;
+
+    let rows = stats_repo::due_counts_by_deck(&state.read_pool, user_id, LEARNING_DAYS).await?;
+    let mut due_counts = DueCounts {
+        decks: Vec::with_capacity(rows.len()),
+        total: CardCounts::default(),
+    };
+    for row in rows {
+        let counts = CardCounts {
+            new: row.new_cards,
+            learning: row.learning,
+            due: row.due,
+        };
+        match (row.deck_id, row.title) {
+            (Some(deck_id), Some(title)) => due_counts.decks.push(DeckDueCounts {
+                deck_id,
+                title,
+                counts,
+            }),
+            _ => due_counts.total = counts,
+        }
+    }
+    Ok(Json(due_counts))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ActivityQuery {
+    /// First local day, inclusive (default: 364 days before `to`)
+    #[serde(default)]
+    from: Option<NaiveDate>,
+    /// Last local day, inclusive (default: today)
+    #[serde(default)]
+    to: Option<NaiveDate>,
+    /// `day` (default), `week` or `month`
+    #[serde(default)]
+    #[param(inline)]
+    granularity: Granularity,
+}
+
+/// Reviews, study time and new cards per day, week or month
+///
+/// Ranges span up to three years; dates are in the user's timezone.
+#[utoipa::path(
+    get,
+    path = "/v1/users/{user_id}/activity",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    params(
+        ("user_id" = Uuid, Path, description = "Must be the signed-in user"),
+        ActivityQuery,
+    ),
+    responses(
+        (status = 200, description = "Activity per period", body = Activity),
+        (status = 400, description = "`from` after `to` or range too long", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Another user's activity", body = ErrorResponse),
+    )
+)]
+async fn get_activity(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(user_id): Path<Uuid>,
+    Query(query): Query<ActivityQuery>,
+) -> Result<Json<Activity>, ApiError> {
+    require_self(&auth_user, user_id)?;
+
+    let activity = activity::get(
+        &state.read_pool,
+        user_id,
+        query.from,
+        query.to,
+        query.granularity,
+    )
+    .await?;
+    Ok(Json(activity))
+}
@@ -0,0 +1,492 @@
+//! Streaming JSON and NDJSON responses.
+//!
+//! Exports and histories can run to tens of megabytes, so instead of collecting
+//! rows into a `Vec` and serializing one large body, handlers hand a producer to
+//! [`json_stream`]. The producer runs in its own task and pushes rows through a
+//! bounded channel; the response body serializes them in chunks as the client
+//! reads. Memory stays flat regardless of result size, and a client that
+//! disconnects stops the producer at its next send.
+//!
+//! [`NdjsonReader`] is the request-side counterpart: it splits a streamed NDJSON
+//! body into lines as chunks arrive, so bulk uploads are processed without
+//! buffering the whole body.
+
+use std::future::Future;
+use std::io;
+
+use axum::{
+    body::{Body, Bytes},
+    http::{HeaderMap, HeaderValue, header},
+    response::{IntoResponse, Response},
+};
+use futures_util::{Stream, StreamExt, stream};
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+use crate::error::ApiError;
+
+/// Rows buffered between the producer and the response body
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Target size of each body chunk
+const CHUNK_BYTES: usize = 64 * 1024;
+
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Wire format of a streamed response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFormat {
+    /// A single JSON array, written element by element
+    JsonArray,
+    /// One JSON document per line
+    Ndjson,
+}
+
+impl StreamFormat {
+    /// NDJSON when the client asks for it in `Accept`, a JSON array otherwise
+    #[must_use]
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let wants_ndjson = headers
+            .get_all(header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .any(|value| value.contains(NDJSON_CONTENT_TYPE));
+
+        if wants_ndjson {
+            Self::Ndjson
+        } else {
+            Self::JsonArray
+        }
+    }
+
+    const fn content_type(self) -> &'static str {
+        match self {
+            Self::JsonArray => "application/json",
+            Self::Ndjson => NDJSON_CONTENT_TYPE,
+        }
+    }
+}
+
+/// Why a streaming producer stopped early
+#[derive(Debug)]
+pub enum StreamError {
+    /// The client went away; nothing to report
+    Disconnected,
+    /// The producer failed after the response had started
+    Failed(ApiError),
+}
+
+impl From<ApiError> for StreamError {
+    fn from(e: ApiError) -> Self {
+        Self::Failed(e)
+    }
+}
+
+impl From<sqlx::Error> for StreamError {
+    fn from(e: sqlx::Error) -> Self {
+        Self::Failed(e.into())
+    }
+}
+
+/// Sending half handed to a streaming producer
+pub struct RowSender<T> {
+    tx: mpsc::Sender<T>,
+}
+
+impl<T> RowSender<T> {
+    /// Queue a row, waiting while the client catches up
+    pub async fn send(&self, row: T) -> Result<(), StreamError> {
+        self.tx
+            .send(row)
+            .await
+            .map_err(|_| StreamError::Disconnected)
+    }
+}
+
+/// Stream rows produced by `produce` as a JSON array or NDJSON body.
+///
+/// The status and headers are sent before the producer runs, so anything that
+/// can fail with a proper error status (not found, forbidden) must be checked
+/// before calling this. A failure midway aborts the body, which clients see as
+/// a truncated transfer rather than a silently incomplete export.
+pub fn json_stream<T, F, Fut>(format: StreamFormat, produce: F) -> Response
+where
+    T: Serialize + Send + 'static,
+    F: FnOnce(RowSender<T>) -> Fut,
+    Fut: Future<Output = Result<(), StreamError>> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+    let (error_tx, error_rx) = tokio::sync::oneshot::channel();
+
+    let producer = produce(RowSender { tx });
+    tokio::spawn(async move {
+        match producer.await {
+            Ok(()) | Err(StreamError::Disconnected) => {}
+            Err(StreamError::Failed(e)) => {
+                tracing::error!(error = %e, "Streaming response aborted");
+                let _ = error_tx.send(());
+            }
+        }
+    });
+
+    let body = Body::from_stream(encode(format, rx, error_rx));
+    (
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static(format.content_type()),
+        )],
+        body,
+    )
+        .into_response()
+}
+
+struct Encoder<T> {
+    format: StreamFormat,
+    rx: mpsc::Receiver<T>,
+    failed: tokio::sync::oneshot::Receiver<()>,
+    started: bool,
+    finished: bool,
+}
+
+/// Turn received rows into body chunks of roughly [`CHUNK_BYTES`]
+fn encode<T: Serialize + Send + 'static>(
+    format: StreamFormat,
+    rx: mpsc::Receiver<T>,
+    failed: tokio::sync::oneshot::Receiver<()>,
+) -> impl futures_util::Stream<Item = Result<Bytes, io::Error>> + Send + 'static {
+    let encoder = Encoder {
+        format,
+        rx,
+        failed,
+        started: false,
+        finished: false,
+    };
+
+    stream::unfold(encoder, |mut encoder| async move {
+        if encoder.finished {
+            return None;
+        }
+
+        let mut buf = Vec::with_capacity(CHUNK_BYTES);
+        let Some(first) = encoder.rx.recv().await else {
+            // Producer is done; a failed producer must not look like a complete document.
+            // Its rows sender is dropped before the failure is reported, so wait for
+            // the outcome: the signal when it failed, a dropped sender when it did not.
+            encoder.finished = true;
+            if (&mut encoder.failed).await.is_ok() {
+                let error = io::Error::other("streaming response aborted");
+                return Some((Err(error), encoder));
+            }
+            match encoder.format {
+                StreamFormat::JsonArray if encoder.started => buf.push(b']'),
+                StreamFormat::JsonArray => buf.extend_from_slice(b"[]"),
+                StreamFormat::Ndjson => return None,
+            }
+            return Some((Ok(Bytes::from(buf)), encoder));
+        };
+
+        let mut next = Some(first);
+        while let Some(row) = next {
+            if let Err(e) = encoder.write_row(&mut buf, &row) {
+                encoder.finished = true;
+                return Some((Err(io::Error::other(e)), encoder));
+            }
+            next = if buf.len() < CHUNK_BYTES {
+                encoder.rx.try_recv().ok()
+            } else {
+                None
+            };
+        }
+
+        Some((Ok(Bytes::from(buf)), encoder))
+    })
+}
+
+impl<T: Serialize> Encoder<T> {
+    fn write_row(&mut self, buf: &mut Vec<u8>, row: &T) -> serde_json::Result<()> {
+        match self.format {
+            StreamFormat::JsonArray => {
+                buf.push(if self.started { b',' } else { b'[' });
+                serde_json::to_writer(&mut *buf, row)?;
+            }
+            StreamFormat::Ndjson => {
+                serde_json::to_writer(&mut *buf, row)?;
+                buf.push(b'\n');
+            }
+        }
+        self.started = true;
+        Ok(())
+    }
+}
+
+/// One line of an NDJSON request body
+#[derive(Debug, PartialEq, Eq)]
+pub enum NdjsonLine {
+    /// A non-blank line, without its terminator
+    Line { number: u64, bytes: Vec<u8> },
+    /// A line longer than the reader's limit; its contents were discarded
+    TooLong { number: u64 },
+}
+
+/// Splits a streamed body into NDJSON lines, holding at most one line in memory.
+///
+/// Line numbers are 1-based and count blank lines, so they match what an editor
+/// shows for the uploaded file.
+pub struct NdjsonReader<S> {
+    body: S,
+    buf: Vec<u8>,
+    max_line_bytes: usize,
+    line_number: u64,
+    /// Dropping the rest of an oversized line
+    skipping: bool,
+    eof: bool,
+}
+
+impl<S, E> NdjsonReader<S>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    pub fn new(body: S, max_line_bytes: usize) -> Self {
+        Self {
+            body,
+            buf: Vec::new(),
+            max_line_bytes,
+            line_number: 0,
+            skipping: false,
+            eof: false,
+        }
+    }
+
+    /// Number of the last line returned
+    #[must_use]
+    pub fn line_number(&self) -> u64 {
+        self.line_number
+    }
+
+    /// Next line, or `None` at the end of the body
+    pub async fn next_line(&mut self) -> Result<Option<NdjsonLine>, io::Error> {
+        loop {
+            let line = match self.buf.iter().position(|b| *b == b'\n') {
+                Some(end) => {
+                    let mut line: Vec<u8> = self.buf.drain(..=end).collect();
+                    line.pop();
+                    Some(line)
+                }
+                None if self.eof && (!self.buf.is_empty() || self.skipping) => {
+                    Some(std::mem::take(&mut self.buf))
+                }
+                None if self.eof => return Ok(None),
+                None => None,
+            };
+
+            if let Some(mut line) = line {
+                self.line_number += 1;
+                if std::mem::take(&mut self.skipping) || line.len() > self.max_line_bytes {
+                    return Ok(Some(NdjsonLine::TooLong {
+                        number: self.line_number,
+                    }));
+                }
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+                if line.iter().all(u8::is_ascii_whitespace) {
+                    continue;
+                }
+                return Ok(Some(NdjsonLine::Line {
+                    number: self.line_number,
+                    bytes: line,
+                }));
+            }
+
+            if self.buf.len() > self.max_line_bytes {
+                self.skipping = true;
+                self.buf.clear();
+            }
+
+            match self.body.next().await {
+                Some(Ok(chunk)) if self.skipping => {
+                    // Keep only what follows the end of the oversized line
+                    if let Some(end) = chunk.iter().position(|b| *b == b'\n') {
+                        self.buf.extend_from_slice(&chunk[end..]);
+                    }
+                }
+                Some(Ok(chunk)) => self.buf.extend_from_slice(&chunk),
+                Some(Err(e)) => return Err(io::Error::other(e)),
+                None => self.eof = true,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::BodyExt;
+
+    async fn body_of(response: Response) -> String {
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    fn numbers(format: StreamFormat, count: u32) -> Response {
+        json_stream(format, move |tx| async move {
+            for n in 0..count {
+                tx.send(serde_json::json!({ "n": n })).await?;
+            }
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_format_from_accept_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            StreamFormat::from_headers(&headers),
+            StreamFormat::JsonArray
+        );
+
+        headers.insert(
+            header::ACCEPT,
+            HeaderValue::from_static("application/x-ndjson"),
+        );
+        assert_eq!(StreamFormat::from_headers(&headers), StreamFormat::Ndjson);
+    }
+
+    #[tokio::test]
+    async fn test_json_array_stream() {
+        let response = numbers(StreamFormat::JsonArray, 3);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+
+        let body: serde_json::Value = serde_json::from_str(&body_of(response).await).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!([{ "n": 0 }, { "n": 1 }, { "n": 2 }])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_empty_json_array_stream() {
+        assert_eq!(body_of(numbers(StreamFormat::JsonArray, 0)).await, "[]");
+    }
+
+    #[tokio::test]
+    async fn test_ndjson_stream() {
+        let body = body_of(numbers(StreamFormat::Ndjson, 2)).await;
+        assert_eq!(body, "{\"n\":0}\n{\"n\":1}\n");
+    }
+
+    #[tokio::test]
+    async fn test_large_stream_is_chunked() {
+        let body = body_of(numbers(StreamFormat::JsonArray, 20_000)).await;
+        let rows: Vec<serde_json::Value> = serde_json::from_str(&body).unwrap();
+        assert_eq!(rows.len(), 20_000);
+    }
+
+    #[tokio::test]
+    async fn test_failed_producer_aborts_body() {
+        for format in [StreamFormat::JsonArray, StreamFormat::Ndjson] {
+            let response = json_stream(format, |tx| async move {
+                tx.send(1).await?;
+                Err(StreamError::Failed(ApiError::NotFound("gone".to_string())))
+            });
+
+            assert!(response.into_body().collect().await.is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failure_reported_after_rows_end_aborts_body() {
+        for format in [StreamFormat::JsonArray, StreamFormat::Ndjson] {
+            let response = json_stream(format, |tx| async move {
+                tx.send(1).await?;
+                // The body sees the end of the rows before the failure
+                drop(tx);
+                tokio::task::yield_now().await;
+                Err(StreamError::Failed(ApiError::NotFound("gone".to_string())))
+            });
+
+            assert!(response.into_body().collect().await.is_err());
+        }
+    }
+
+    fn reader(
+        chunks: &[&'static str],
+        max_line_bytes: usize,
+    ) -> NdjsonReader<impl Stream<Item = Result<Bytes, io::Error>> + Unpin> {
+        let chunks: Vec<Result<Bytes, io::Error>> = chunks
+            .iter()
+            .map(|chunk| Ok(Bytes::from_static(chunk.as_bytes())))
+            .collect();
+        NdjsonReader::new(stream::iter(chunks), max_line_bytes)
+    }
+
+    async fn read_all<S>(mut reader: NdjsonReader<S>) -> Vec<NdjsonLine>
+    where
+        S: Stream<Item = Result<Bytes, io::Error>> + Unpin,
+    {
+        let mut lines = Vec::new();
+        while let Some(line) = reader.next_line().await.unwrap() {
+            lines.push(line);
+        }
+        lines
+    }
+
+    fn line(number: u64, text: &str) -> NdjsonLine {
+        NdjsonLine::Line {
+            number,
+            bytes: text.as_bytes().to_vec(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ndjson_reader_splits_across_chunks() {
+        let lines = read_all(reader(
+            &["{\"a\":", "1}\n{\"b\"", ":2}\r\n\n  \n{\"c\":3}"],
+            1024,
+        ))
+        .await;
+
+        assert_eq!(
+            lines,
+            vec![
+                line(1, "{\"a\":1}"),
+                line(2, "{\"b\":2}"),
+                line(5, "{\"c\":3}")
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ndjson_reader_skips_oversized_lines() {
+        let lines = read_all(reader(
+            &["{\"a\":1}\n0123456789", "0123456789", "01\n{\"b\":2}\n"],
+            16,
+        ))
+        .await;
+
+        assert_eq!(
+            lines,
+            vec![
+                line(1, "{\"a\":1}"),
+                NdjsonLine::TooLong { number: 2 },
+                line(3, "{\"b\":2}"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ndjson_reader_oversized_last_line() {
+        let lines = read_all(reader(&["0123456789\n", "0123456789"], 8)).await;
+        assert_eq!(
+            lines,
+            vec![
+                NdjsonLine::TooLong { number: 1 },
+                NdjsonLine::TooLong { number: 2 }
+            ]
+        );
+    }
+}
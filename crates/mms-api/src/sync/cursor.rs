@@ -0,0 +1,98 @@
+//! Opaque position in the change stream.
+//!
+//! Between syncs the cursor is the transaction watermark taken at the end of
+//! the previous sync. While paging through a batch it also holds the last
+//! change served and the watermark to continue from once the batch is done,
+//! so changes committed while a client pages are picked up by the next sync.
+
+use std::fmt;
+
+use crate::error::ApiError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Cursor {
+    /// Changes from transactions at or above this id are served
+    pub watermark: i64,
+    /// Last change served in the current batch; 0 between batches
+    pub after: i64,
+    /// Watermark for the next batch, set while paging
+    pub next_watermark: Option<i64>,
+}
+
+impl Cursor {
+    /// Position after a finished batch
+    #[must_use]
+    pub fn caught_up(watermark: i64) -> Self {
+        Self {
+            watermark,
+            after: 0,
+            next_watermark: None,
+        }
+    }
+
+    /// Parse a cursor from a previous response; an absent cursor starts from the beginning
+    pub fn parse(value: Option<&str>) -> Result<Self, ApiError> {
+        let Some(value) = value.filter(|v| !v.is_empty()) else {
+            return Ok(Self::default());
+        };
+        let invalid = || ApiError::Validation("Invalid sync cursor".to_string());
+
+        let parts = value
+            .split('.')
+            .map(|part| part.parse::<i64>().ok().filter(|n| *n >= 0))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(invalid)?;
+
+        match parts[..] {
+            [watermark] => Ok(Self::caught_up(watermark)),
+            [watermark, after, next] => Ok(Self {
+                watermark,
+                after,
+                next_watermark: Some(next),
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.next_watermark {
+            Some(next) => write!(f, "{}.{}.{next}", self.watermark, self.after),
+            None => write!(f, "{}", self.watermark),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        for cursor in [
+            Cursor::default(),
+            Cursor::caught_up(812),
+            Cursor {
+                watermark: 800,
+                after: 4031,
+                next_watermark: Some(812),
+            },
+        ] {
+            assert_eq!(Cursor::parse(Some(&cursor.to_string())).unwrap(), cursor);
+        }
+    }
+
+    #[test]
+    fn test_missing_cursor_starts_from_the_beginning() {
+        assert_eq!(Cursor::parse(None).unwrap(), Cursor::default());
+        assert_eq!(Cursor::parse(Some("")).unwrap(), Cursor::default());
+    }
+
+    #[test]
+    fn test_rejects_malformed_cursors() {
+        for value in ["abc", "1.2", "1.2.3.4", "-5", "1..3"] {
+            assert!(Cursor::parse(Some(value)).is_err(), "{value} was accepted");
+        }
+    }
+}
@@ -0,0 +1,10 @@
+//! Delta sync for offline-first clients.
+//!
+//! Clients pull changed decks, cards and their own progress with
+//! `GET /sync/changes`, passing back the cursor of the previous response, and
+//! upload progress recorded offline with `POST /sync/push`.
+
+pub mod cursor;
+pub mod routes;
+
+pub use routes::routes;
@@ -0,0 +1,572 @@
+use std::collections::HashSet;
+
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    response::Response,
+    routing::{get, post},
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+use utoipa::{IntoParams, ToSchema};
+use validator::{Validate, ValidationError};
+
+use crate::{
+    ApiState,
+    auth::AuthUser,
+    error::{ApiError, ErrorResponse},
+    idempotency::Idempotent,
+    practice::{
+        ReviewMode,
+        routes::{ReviewResponse, ReviewSubmission, review},
+    },
+    sync::cursor::Cursor,
+    validation::{self, ValidJson},
+};
+
+use mms_db::models::{ProgressState, SyncCard, SyncDeck, SyncProgress, SyncTombstone};
+use mms_db::repositories::forecast as forecast_repo;
+use mms_db::repositories::practice as practice_repo;
+use mms_db::repositories::sync as sync_repo;
+
+/// Changes per page when the request does not set a limit
+const DEFAULT_PAGE_SIZE: i64 = 500;
+
+const MAX_PAGE_SIZE: i64 = 2000;
+
+/// Progress changes accepted in one push
+const MAX_PUSH_CHANGES: u64 = 500;
+
+/// Offline reviews accepted in one upload
+const MAX_OFFLINE_REVIEWS: u64 = 200;
+
+/// How far a review may be ahead of the server's clock, for clients whose clock runs fast
+const MAX_CLOCK_SKEW_SECS: i64 = 300;
+
+/// Reviews older than this are too stale to schedule from
+const MAX_OFFLINE_REVIEW_AGE_DAYS: i64 = 30;
+
+/// Create the sync routes
+pub fn routes() -> Router<ApiState> {
+    Router::new()
+        .route("/sync/changes", get(get_changes))
+        .route("/sync/push", post(push_changes))
+        .route("/sync/reviews", post(upload_reviews))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ChangesQuery {
+    /// Cursor from the previous response; omit for a full sync
+    #[serde(default)]
+    since: Option<String>,
+    /// Changes per page, 1 to 2000 (default 500)
+    #[serde(default)]
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct ChangesResponse {
+    /// Pass as `since` on the next request
+    cursor: String,
+    /// More changes are waiting; request again right away
+    has_more: bool,
+    decks: Vec<SyncDeck>,
+    cards: Vec<SyncCard>,
+    progress: Vec<SyncProgress>,
+    deleted: Vec<SyncTombstone>,
+}
+
+/// Decks, cards and the user's progress changed since the cursor.
+///
+/// Every item carries its `change_seq`; apply items and deletions in that
+/// order. The same change can be served more than once.
+#[utoipa::path(
+    get,
+    path = "/v1/sync/changes",
+    tag = "sync",
+    security(("cookie_auth" = [])),
+    params(ChangesQuery),
+    responses(
+        (status = 200, description = "A page of changes", body = ChangesResponse),
+        (status = 400, description = "Invalid cursor", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+async fn get_changes(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Query(query): Query<ChangesQuery>,
+) -> Result<Json<ChangesResponse>, ApiError> {
+    let cursor = Cursor::parse(query.since.as_deref())?;
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let user_id = auth_user.user_id;
+
+    // One snapshot for the whole page, so every table is read as of the watermark
+    let mut tx = state.pool.begin().await?;
+    sync_repo::begin_snapshot(&mut *tx).await?;
+
+    let next_watermark = match cursor.next_watermark {
+        Some(next) => next,
+        None => sync_repo::current_watermark(&mut *tx).await?,
+    };
+    let bounds =
+        sync_repo::page_bounds(&mut *tx, user_id, cursor.watermark, cursor.after, limit).await?;
+
+    let Some(upto) = bounds.last_seq else {
+        tx.commit().await?;
+        return Ok(Json(ChangesResponse {
+            cursor: Cursor::caught_up(next_watermark).to_string(),
+            has_more: false,
+            decks: Vec::new(),
+            cards: Vec::new(),
+            progress: Vec::new(),
+            deleted: Vec::new(),
+        }));
+    };
+
+    let (watermark, after) = (cursor.watermark, cursor.after);
+    let decks = sync_repo::changed_decks(&mut *tx, user_id, watermark, after, upto).await?;
+    let cards = sync_repo::changed_cards(&mut *tx, watermark, after, upto).await?;
+    let progress = sync_repo::changed_progress(&mut *tx, user_id, watermark, after, upto).await?;
+    let deleted = sync_repo::tombstones(&mut *tx, user_id, watermark, after, upto).await?;
+    tx.commit().await?;
+
+    let next = if bounds.has_more {
+        Cursor {
+            watermark,
+            after: upto,
+            next_watermark: Some(next_watermark),
+        }
+    } else {
+        Cursor::caught_up(next_watermark)
+    };
+
+    Ok(Json(ChangesResponse {
+        cursor: next.to_string(),
+        has_more: bounds.has_more,
+        decks,
+        cards,
+        progress,
+        deleted,
+    }))
+}
+
+/// How a pushed change is resolved when the server's row changed since the client last saw it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+enum ConflictPolicy {
+    /// Keep the server's row
+    ServerWins,
+    /// Keep whichever side was modified last
+    #[default]
+    LastWriteWins,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+enum Resolution {
+    ClientWins,
+    ServerWins,
+}
+
+// Serialize is for the offending value that validation errors carry
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+struct ProgressChange {
+    flashcard_id: Uuid,
+    /// `change_seq` of the server row the client's copy is based on; omit for cards first studied offline
+    #[serde(default)]
+    base_change_seq: Option<i64>,
+    #[serde(flatten)]
+    state: ProgressState,
+    /// When the client last modified its copy
+    updated_at: DateTime<Utc>,
+    #[serde(default)]
+    on_conflict: ConflictPolicy,
+}
+
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+struct PushRequest {
+    /// At most 500 changes, one per card
+    #[validate(
+        length(
+            max = MAX_PUSH_CHANGES,
+            message = "At most 500 changes can be pushed at once"
+        ),
+        custom(function = "validate_changes")
+    )]
+    progress: Vec<ProgressChange>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct SyncConflict {
+    flashcard_id: Uuid,
+    resolution: Resolution,
+    /// The server's row when it was kept; `None` if the card was deleted or the client won
+    server: Option<SyncProgress>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct PushResponse {
+    /// Rows written, with their new `change_seq`
+    applied: Vec<SyncProgress>,
+    conflicts: Vec<SyncConflict>,
+}
+
+/// Whether a change conflicts with the server's current row, and who wins if so
+fn resolve(change: &ProgressChange, server: Option<&SyncProgress>) -> Option<Resolution> {
+    let server = server?;
+    if change.base_change_seq == Some(server.change_seq) {
+        return None;
+    }
+
+    Some(match change.on_conflict {
+        ConflictPolicy::ServerWins => Resolution::ServerWins,
+        ConflictPolicy::LastWriteWins
+            if server.updated_at.is_none_or(|at| change.updated_at > at) =>
+        {
+            Resolution::ClientWins
+        }
+        ConflictPolicy::LastWriteWins => Resolution::ServerWins,
+    })
+}
+
+fn validate_changes(progress: &[ProgressChange]) -> Result<(), ValidationError> {
+    let mut seen = HashSet::new();
+    for change in progress {
+        if !seen.insert(change.flashcard_id) {
+            return Err(validation::invalid(
+                "unique",
+                format!("Card {} appears more than once", change.flashcard_id),
+            ));
+        }
+        if change.state.times_correct < 0 || change.state.times_wrong < 0 {
+            return Err(validation::invalid(
+                "range",
+                "Review counts cannot be negative",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Upload progress recorded offline.
+///
+/// A change conflicts when the server's row moved past `base_change_seq`;
+/// `on_conflict` picks the winner. Pushed progress is not counted towards
+/// review stats or streaks.
+#[utoipa::path(
+    post,
+    path = "/v1/sync/push",
+    tag = "sync",
+    security(("cookie_auth" = [])),
+    params(("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key get the first response again")),
+    request_body = PushRequest,
+    responses(
+        (status = 200, description = "Changes applied or resolved", body = PushResponse),
+        (status = 400, description = "Too many, duplicate or invalid changes", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+async fn push_changes(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Idempotent(idempotency, ValidJson(payload)): Idempotent<ValidJson<PushRequest>>,
+) -> Response {
+    idempotency
+        .finish(push(auth_user, &state, payload).await)
+        .await
+}
+
+async fn push(
+    auth_user: AuthUser,
+    state: &ApiState,
+    mut payload: PushRequest,
+) -> Result<Json<PushResponse>, ApiError> {
+    let user_id = auth_user.user_id;
+
+    // Lock rows in a stable order so concurrent pushes cannot deadlock
+    payload.progress.sort_by_key(|change| change.flashcard_id);
+
+    let mut applied = Vec::new();
+    let mut conflicts = Vec::new();
+    let mut tx = state.pool.begin().await?;
+
+    for change in &payload.progress {
+        let server =
+            sync_repo::get_progress_for_update(&mut *tx, user_id, change.flashcard_id).await?;
+        let resolution = resolve(change, server.as_ref());
+
+        if resolution == Some(Resolution::ServerWins) {
+            conflicts.push(SyncConflict {
+                flashcard_id: change.flashcard_id,
+                resolution: Resolution::ServerWins,
+                server,
+            });
+            continue;
+        }
+
+        let mastered = mms_srs::is_mastered(change.state.times_correct, change.state.times_wrong);
+        match sync_repo::put_progress(
+            &mut *tx,
+            user_id,
+            change.flashcard_id,
+            &change.state,
+            mastered,
+        )
+        .await?
+        {
+            Some(progress) => {
+                applied.push(progress);
+                if let Some(resolution) = resolution {
+                    conflicts.push(SyncConflict {
+                        flashcard_id: change.flashcard_id,
+                        resolution,
+                        server: None,
+                    });
+                }
+            }
+            None => conflicts.push(SyncConflict {
+                flashcard_id: change.flashcard_id,
+                resolution: Resolution::ServerWins,
+                server: None,
+            }),
+        }
+    }
+
+    let card_ids: Vec<Uuid> = applied.iter().map(|p| p.flashcard_id).collect();
+    for deck_id in sync_repo::deck_ids_for_cards(&mut *tx, &card_ids).await? {
+        practice_repo::refresh_deck_progress(
+            &mut *tx,
+            user_id,
+            deck_id,
+            mms_srs::MASTERY_THRESHOLD,
+        )
+        .await?;
+    }
+    if !applied.is_empty() {
+        forecast_repo::invalidate(&mut *tx, user_id).await?;
+    }
+    tx.commit().await?;
+
+    Ok(Json(PushResponse { applied, conflicts }))
+}
+
+/// An answer given while offline
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+struct OfflineReview {
+    flashcard_id: Uuid,
+    deck_id: Uuid,
+    user_answer: String,
+    /// When the learner answered; the review is scheduled from this time
+    reviewed_at: DateTime<Utc>,
+    #[serde(default)]
+    response_time_ms: Option<u32>,
+    #[serde(default)]
+    elapsed_ms: Option<u32>,
+    /// `translation` by default, or `listening`
+    #[serde(default)]
+    mode: ReviewMode,
+}
+
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+struct OfflineReviewsRequest {
+    /// At most 200 reviews, in any order
+    #[validate(length(
+        min = 1,
+        max = MAX_OFFLINE_REVIEWS,
+        message = "Between 1 and 200 reviews can be uploaded at once"
+    ))]
+    reviews: Vec<OfflineReview>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct ReplayedReview {
+    flashcard_id: Uuid,
+    reviewed_at: DateTime<Utc>,
+    /// The graded answer, when the review was recorded
+    result: Option<ReviewResponse>,
+    /// Why the review was not recorded
+    error: Option<ErrorResponse>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct OfflineReviewsResponse {
+    /// One entry per review, oldest first
+    reviews: Vec<ReplayedReview>,
+}
+
+/// Upload answers given offline.
+///
+/// Each review is graded and scheduled as if it had been submitted at its
+/// `reviewed_at`, oldest first, and counts towards stats and streaks on the
+/// day it was given. Reviews are recorded one by one: a review that is not
+/// due at its time, predates one already recorded, or is more than 30 days
+/// old is rejected in the response without failing the others.
+#[utoipa::path(
+    post,
+    path = "/v1/sync/reviews",
+    tag = "sync",
+    security(("cookie_auth" = [])),
+    params(("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key get the first response again")),
+    request_body = OfflineReviewsRequest,
+    responses(
+        (status = 200, description = "Reviews recorded or rejected", body = OfflineReviewsResponse),
+        (status = 400, description = "No reviews or too many", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+async fn upload_reviews(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Idempotent(idempotency, ValidJson(payload)): Idempotent<ValidJson<OfflineReviewsRequest>>,
+) -> Response {
+    idempotency
+        .finish(replay_reviews(auth_user, &state, payload).await)
+        .await
+}
+
+async fn replay_reviews(
+    auth_user: AuthUser,
+    state: &ApiState,
+    mut payload: OfflineReviewsRequest,
+) -> Result<Json<OfflineReviewsResponse>, ApiError> {
+    payload.reviews.sort_by_key(|offline| offline.reviewed_at);
+
+    let now = state.clock.now();
+    let mut replayed = Vec::with_capacity(payload.reviews.len());
+    for offline in payload.reviews {
+        let (flashcard_id, reviewed_at) = (offline.flashcard_id, offline.reviewed_at);
+        let outcome = if reviewed_at > now + Duration::seconds(MAX_CLOCK_SKEW_SECS) {
+            Err(ApiError::Validation(
+                "This review is dated in the future".to_string(),
+            ))
+        } else if reviewed_at < now - Duration::days(MAX_OFFLINE_REVIEW_AGE_DAYS) {
+            Err(ApiError::Validation(
+                "This review is too old to record".to_string(),
+            ))
+        } else {
+            let submission = ReviewSubmission {
+                user_answer: offline.user_answer,
+                deck_id: offline.deck_id,
+                response_time_ms: offline.response_time_ms,
+                elapsed_ms: offline.elapsed_ms,
+                mode: offline.mode,
+            };
+            match submission.check_typed() {
+                Ok(()) => {
+                    review(
+                        auth_user.user_id,
+                        state,
+                        flashcard_id,
+                        submission,
+                        None,
+                        // Never later than the server's clock, so skew cannot push the schedule out
+                        Some(reviewed_at.min(now)),
+                    )
+                    .await
+                }
+                Err(error) => Err(error),
+            }
+        };
+
+        let (result, error) = match outcome {
+            Ok(Json(result)) => (Some(result), None),
+            Err(
+                error @ (ApiError::Validation(_) | ApiError::Conflict(_) | ApiError::NotFound(_)),
+            ) => (None, Some(error.into_error_response().1)),
+            Err(error) => return Err(error),
+        };
+        replayed.push(ReplayedReview {
+            flashcard_id,
+            reviewed_at,
+            result,
+            error,
+        });
+    }
+
+    Ok(Json(OfflineReviewsResponse { reviews: replayed }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn server_row(change_seq: i64, updated_at: DateTime<Utc>) -> SyncProgress {
+        SyncProgress {
+            flashcard_id: Uuid::nil(),
+            next_review_at: updated_at,
+            last_review_at: Some(updated_at),
+            times_correct: 1,
+            times_wrong: 0,
+            mastered_at: None,
+            updated_at: Some(updated_at),
+            change_seq,
+        }
+    }
+
+    fn change(
+        base_change_seq: Option<i64>,
+        updated_at: DateTime<Utc>,
+        on_conflict: ConflictPolicy,
+    ) -> ProgressChange {
+        ProgressChange {
+            flashcard_id: Uuid::nil(),
+            base_change_seq,
+            state: ProgressState {
+                next_review_at: updated_at,
+                last_review_at: Some(updated_at),
+                times_correct: 2,
+                times_wrong: 0,
+            },
+            updated_at,
+            on_conflict,
+        }
+    }
+
+    #[test]
+    fn test_no_conflict_when_based_on_current_row_or_new() {
+        let now = Utc::now();
+        let server = server_row(7, now);
+
+        let based = change(Some(7), now, ConflictPolicy::ServerWins);
+        assert_eq!(resolve(&based, Some(&server)), None);
+
+        let new = change(None, now, ConflictPolicy::ServerWins);
+        assert_eq!(resolve(&new, None), None);
+    }
+
+    #[test]
+    fn test_last_write_wins_compares_modification_times() {
+        let now = Utc::now();
+        let server = server_row(9, now);
+
+        let newer = change(
+            Some(7),
+            now + Duration::minutes(1),
+            ConflictPolicy::LastWriteWins,
+        );
+        assert_eq!(resolve(&newer, Some(&server)), Some(Resolution::ClientWins));
+
+        let older = change(
+            Some(7),
+            now - Duration::minutes(1),
+            ConflictPolicy::LastWriteWins,
+        );
+        assert_eq!(resolve(&older, Some(&server)), Some(Resolution::ServerWins));
+    }
+
+    #[test]
+    fn test_server_wins_ignores_modification_times() {
+        let now = Utc::now();
+        let server = server_row(9, now);
+
+        let newer = change(None, now + Duration::hours(1), ConflictPolicy::ServerWins);
+        assert_eq!(resolve(&newer, Some(&server)), Some(Resolution::ServerWins));
+    }
+}
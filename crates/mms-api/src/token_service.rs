@@ -0,0 +1,159 @@
+//! Single-use tokens delivered through email links.
+//!
+//! Email verification, password reset and magic-link login all follow the same
+//! lifecycle: generate a random token, store only its hash with an expiry, send
+//! the plain token to the user, and redeem it at most once. This module owns that
+//! lifecycle so individual flows only decide what happens after redemption.
+
+use chrono::{Duration, Utc};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::error::ApiError;
+
+use mms_db::repositories::token as token_repo;
+
+/// What a one-time token may be used for.
+///
+/// Tokens are scoped to a single purpose: a password reset token cannot be used
+/// to verify an email address and vice versa.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenPurpose {
+    EmailVerification,
+    PasswordReset,
+    MagicLink,
+}
+
+impl TokenPurpose {
+    /// Database representation of the purpose
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::EmailVerification => "email_verification",
+            Self::PasswordReset => "password_reset",
+            Self::MagicLink => "magic_link",
+        }
+    }
+
+    /// User-facing message when a token cannot be redeemed
+    const fn invalid_message(self) -> &'static str {
+        match self {
+            Self::EmailVerification => "Invalid or expired verification token",
+            Self::PasswordReset => "Invalid or expired reset token",
+            Self::MagicLink => "Invalid or expired login link",
+        }
+    }
+}
+
+/// Generate a secure random token
+#[must_use]
+pub fn generate_token() -> String {
+    let mut rng = rand::thread_rng();
+    let token_bytes: [u8; 32] = rng.r#gen();
+    hex::encode(token_bytes)
+}
+
+/// Hash a token for secure storage in the database
+#[must_use]
+pub fn hash_token(token: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(token.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// Issue a new token, invalidating any outstanding token for the same user and purpose.
+///
+/// Returns the plain token to embed in the email link.
+pub async fn issue(
+    pool: &PgPool,
+    user_id: Uuid,
+    purpose: TokenPurpose,
+    ttl: Duration,
+) -> Result<String, ApiError> {
+    let mut tx = pool.begin().await?;
+    let token = issue_tx(&mut tx, user_id, purpose, ttl).await?;
+    tx.commit().await?;
+
+    Ok(token)
+}
+
+/// Issue a new token within an existing transaction
+pub async fn issue_tx(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    purpose: TokenPurpose,
+    ttl: Duration,
+) -> Result<String, ApiError> {
+    let token = generate_token();
+    let token_hash = hash_token(&token);
+    let expires_at = Utc::now() + ttl;
+
+    // Only one live token per user and purpose
+    token_repo::invalidate_tokens(&mut **tx, user_id, purpose.as_str()).await?;
+    token_repo::insert_token(
+        &mut **tx,
+        user_id,
+        purpose.as_str(),
+        &token_hash,
+        expires_at,
+    )
+    .await?;
+
+    Ok(token)
+}
+
+/// Redeem a token, marking it as used.
+///
+/// Returns the owning user on success. Fails with [`ApiError::Auth`] if the token
+/// is unknown, expired, already used, or was issued for a different purpose.
+pub async fn consume<'e, E>(
+    executor: E,
+    purpose: TokenPurpose,
+    token: &str,
+) -> Result<Uuid, ApiError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    token_repo::consume_token(executor, purpose.as_str(), &hash_token(token))
+        .await?
+        .ok_or_else(|| ApiError::Auth(purpose.invalid_message().to_string()))
+}
+
+/// Delete expired and used tokens for a purpose
+pub async fn cleanup_expired(pool: &PgPool, purpose: TokenPurpose) -> Result<u64, ApiError> {
+    let rows = token_repo::cleanup_expired_tokens(pool, purpose.as_str()).await?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_token_is_random_hex() {
+        let a = generate_token();
+        let b = generate_token();
+        assert_eq!(a.len(), 64);
+        assert!(a.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_hash_token_is_deterministic() {
+        assert_eq!(hash_token("abc"), hash_token("abc"));
+        assert_ne!(hash_token("abc"), hash_token("abd"));
+        assert_ne!(hash_token("abc"), "abc");
+    }
+
+    #[test]
+    fn test_purpose_strings_match_schema() {
+        assert_eq!(
+            TokenPurpose::EmailVerification.as_str(),
+            "email_verification"
+        );
+        assert_eq!(TokenPurpose::PasswordReset.as_str(), "password_reset");
+        assert_eq!(TokenPurpose::MagicLink.as_str(), "magic_link");
+    }
+}
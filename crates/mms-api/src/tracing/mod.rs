@@ -0,0 +1,114 @@
+//! Tracing and logging configuration for the application
+//!
+//! This module provides structured logging with different configurations
+//! for development and production environments. Outside development every
+//! line is masked by [`redact`] before it is written.
+
+pub mod redact;
+
+use serde::Deserialize;
+use tracing_subscriber::{
+    EnvFilter, Layer, fmt::writer::BoxMakeWriter, layer::SubscriberExt, util::SubscriberInitExt,
+};
+
+use crate::config::Environment;
+use redact::RedactingWriter;
+
+/// How log lines are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Multi-line, colored, with source locations; for reading in a terminal
+    Pretty,
+    /// One JSON object per line; for log aggregation
+    Json,
+}
+
+impl LogFormat {
+    /// Pretty in development, JSON everywhere else
+    #[must_use]
+    pub fn default_for(env: &Environment) -> Self {
+        if env.is_development() {
+            Self::Pretty
+        } else {
+            Self::Json
+        }
+    }
+}
+
+/// Initialize tracing/logging based on the environment
+///
+/// # Development Mode
+/// - Pretty-printed, human-readable logs with colors
+/// - Default level: DEBUG
+/// - Shows file locations and line numbers
+/// - Not redacted, so emails logged by the console provider keep their links
+///
+/// # Production Mode
+/// - JSON-formatted structured logs
+/// - Default level: INFO
+/// - Optimized for log aggregation systems (ELK, Datadog, etc.)
+/// - Includes request IDs, user IDs, and other structured fields
+/// - Email addresses, tokens and credential headers are redacted
+///
+/// # Environment Variables
+/// - `RUST_LOG`: Override default log level (e.g., `RUST_LOG=debug,tower_http=trace`)
+/// - `LOG_FORMAT`: `pretty` or `json`, overriding the environment's default
+pub fn init_tracing(env: &Environment, format: Option<LogFormat>) {
+    let writer = if env.is_development() {
+        BoxMakeWriter::new(std::io::stdout)
+    } else {
+        redact::precompile_patterns();
+        BoxMakeWriter::new(RedactingWriter(std::io::stdout))
+    };
+
+    match format.unwrap_or_else(|| LogFormat::default_for(env)) {
+        LogFormat::Pretty => init_pretty_tracing(env, writer),
+        LogFormat::Json => init_json_tracing(env, writer),
+    }
+}
+
+fn env_filter(env: &Environment) -> EnvFilter {
+    EnvFilter::try_from_default_env().unwrap_or_else(|_| {
+        if env.is_development() {
+            EnvFilter::new("debug,tower_http=debug,sqlx=warn")
+        } else {
+            EnvFilter::new("info,tower_http=info,sqlx=warn")
+        }
+    })
+}
+
+/// Initialize development-friendly tracing with pretty output
+fn init_pretty_tracing(env: &Environment, writer: BoxMakeWriter) {
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(writer)
+                .with_target(true)
+                .with_line_number(true)
+                .with_file(true)
+                .pretty()
+                .with_filter(env_filter(env)),
+        )
+        .init();
+
+    tracing::info!("Tracing initialized with pretty output ({env:?})");
+}
+
+/// Initialize production tracing with JSON output
+fn init_json_tracing(env: &Environment, writer: BoxMakeWriter) {
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .json()
+                .with_writer(writer)
+                .with_current_span(true)
+                .with_span_list(true)
+                .flatten_event(true)
+                .with_target(true)
+                .with_filter(env_filter(env)),
+        )
+        .init();
+
+    tracing::info!("Tracing initialized with JSON output ({env:?})");
+}
@@ -0,0 +1,248 @@
+//! Masking of personal data and credentials in log output.
+//!
+//! Every formatted log line passes through [`RedactingWriter`] before it is
+//! written, whichever event or span produced it, so a field added later
+//! cannot leak what an earlier review caught. Email addresses, JWTs, bearer
+//! tokens, `token=`-style query parameters and fields named like tokens,
+//! passwords, secrets or cookies are replaced. Request spans also leave
+//! credential headers out with [`RedactedMakeSpan`], since the trace layer
+//! logs the headers of every request.
+
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::sync::LazyLock;
+
+use axum::http::{HeaderMap, HeaderValue, Request, header};
+use regex::Regex;
+use tower_http::trace::MakeSpan;
+use tracing::Span;
+use tracing_subscriber::fmt::MakeWriter;
+
+/// What replaces a masked value
+pub const REDACTED: &str = "[redacted]";
+
+/// Headers whose values are never logged
+const SENSITIVE_HEADERS: [header::HeaderName; 4] = [
+    header::AUTHORIZATION,
+    header::COOKIE,
+    header::PROXY_AUTHORIZATION,
+    header::SET_COOKIE,
+];
+
+/// Field names and parameters holding credentials, as a regex alternation
+const SECRET_NAMES: &str =
+    r"[a-z_]*token|[a-z_]*password|[a-z_]*secret|cookie|set-cookie|authorization|code|state";
+
+/// Escape sequences the pretty format colors field names with
+const ANSI: &str = r"(?:\x1b\[[0-9;]*m)*";
+
+static EMAIL_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)+").unwrap());
+static JWT_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"eyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+\.[A-Za-z0-9_-]*").unwrap());
+static BEARER_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)(bearer\s+)[^\s",\\]+"#).unwrap());
+/// `"token": "..."` in JSON output
+static JSON_FIELD_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r#"(?i)("(?:{SECRET_NAMES})"\s*:\s*)"(?:[^"\\]|\\.)*""#
+    ))
+    .unwrap()
+});
+/// The same inside a JSON string, such as a header map logged as a span field
+static NESTED_JSON_FIELD_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r#"(?i)(\\"(?:{SECRET_NAMES})\\"\s*:\s*)\\"(?:[^"\\]|\\\\\\["\\]|\\[^"\\])*\\""#
+    ))
+    .unwrap()
+});
+/// `token=...` in query strings and text output, `token: ...` in pretty output,
+/// where the name follows a color code rather than a word boundary
+static TEXT_FIELD_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r#"(?i)((?:^|[^a-z0-9_]|\x1b\[[0-9;]*m)(?:{SECRET_NAMES}){ANSI}(?:={ANSI}|{ANSI}:{ANSI}\s))[^\s&",}}\\\x1b]+"#
+    ))
+    .unwrap()
+});
+
+/// Mask personal data and credentials in one formatted log line
+#[must_use]
+pub fn redact(line: &str) -> Cow<'_, str> {
+    let mut line = Cow::Borrowed(line);
+    replace(&mut line, &JSON_FIELD_RE, &format!("${{1}}\"{REDACTED}\""));
+    replace(
+        &mut line,
+        &NESTED_JSON_FIELD_RE,
+        &format!("${{1}}\\\"{REDACTED}\\\""),
+    );
+    replace(&mut line, &TEXT_FIELD_RE, &format!("${{1}}{REDACTED}"));
+    replace(&mut line, &BEARER_RE, &format!("${{1}}{REDACTED}"));
+    replace(&mut line, &JWT_RE, REDACTED);
+    replace(&mut line, &EMAIL_RE, "[redacted-email]");
+    line
+}
+
+fn replace(line: &mut Cow<'_, str>, pattern: &Regex, replacement: &str) {
+    if let Cow::Owned(replaced) = pattern.replace_all(line, replacement) {
+        *line = Cow::Owned(replaced);
+    }
+}
+
+/// Copy of `headers` with credential values masked
+#[must_use]
+pub fn redact_headers(headers: &HeaderMap) -> HeaderMap {
+    let mut headers = headers.clone();
+    for name in SENSITIVE_HEADERS {
+        if headers.contains_key(&name) {
+            headers.insert(name, HeaderValue::from_static(REDACTED));
+        }
+    }
+    headers
+}
+
+/// Compile the redaction patterns ahead of the first log line
+pub(crate) fn precompile_patterns() {
+    LazyLock::force(&EMAIL_RE);
+    LazyLock::force(&JWT_RE);
+    LazyLock::force(&BEARER_RE);
+    LazyLock::force(&JSON_FIELD_RE);
+    LazyLock::force(&NESTED_JSON_FIELD_RE);
+    LazyLock::force(&TEXT_FIELD_RE);
+}
+
+/// Makes writers that mask each line before handing it to `M`'s writer
+#[derive(Debug, Clone, Copy)]
+pub struct RedactingWriter<M>(pub M);
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for RedactingWriter<M> {
+    type Writer = RedactedLine<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactedLine {
+            inner: self.0.make_writer(),
+            buffer: Vec::new(),
+        }
+    }
+}
+
+/// Collects one formatted event and writes it masked when dropped
+pub struct RedactedLine<W: Write> {
+    inner: W,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> Write for RedactedLine<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<W: Write> Drop for RedactedLine<W> {
+    fn drop(&mut self) {
+        let line = String::from_utf8_lossy(&self.buffer);
+        // Nowhere to report a failure to write a log line
+        let _ = self.inner.write_all(redact(&line).as_bytes());
+        let _ = self.inner.flush();
+    }
+}
+
+/// Request spans for the trace layer, with credential headers masked
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RedactedMakeSpan;
+
+impl<B> MakeSpan<B> for RedactedMakeSpan {
+    fn make_span(&mut self, request: &Request<B>) -> Span {
+        tracing::info_span!(
+            "request",
+            method = %request.method(),
+            uri = %request.uri(),
+            version = ?request.version(),
+            headers = ?redact_headers(request.headers()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_masks_emails_and_tokens_in_text() {
+        assert_eq!(
+            redact("Password reset requested email=ana@example.com token=abc123"),
+            "Password reset requested email=[redacted-email] token=[redacted]"
+        );
+        assert_eq!(
+            redact("GET /v1/users/verify-email?token=abc123&lang=es"),
+            "GET /v1/users/verify-email?token=[redacted]&lang=es"
+        );
+        assert_eq!(
+            redact("uri=/v1/webhooks/email/ses?token=abc123"),
+            "uri=/v1/webhooks/email/ses?token=[redacted]"
+        );
+        assert_eq!(
+            redact("Bearer abc123 rejected"),
+            "Bearer [redacted] rejected"
+        );
+        assert_eq!(
+            redact("Signed eyJhbGciOi.eyJzdWIiOi.c2ln for"),
+            "Signed [redacted] for"
+        );
+        assert_eq!(
+            redact("\x1b[3mtoken\x1b[0m\x1b[2m:\x1b[0m abc"),
+            "\x1b[3mtoken\x1b[0m\x1b[2m:\x1b[0m [redacted]"
+        );
+        assert_eq!(redact("Cards due: 12"), "Cards due: 12");
+    }
+
+    #[test]
+    fn test_masks_json_fields() {
+        assert_eq!(
+            redact(r#"{"message":"Sent","to":"ana@example.com","refresh_token":"x\"y","count":2}"#),
+            r#"{"message":"Sent","to":"[redacted-email]","refresh_token":"[redacted]","count":2}"#
+        );
+        // Header maps are logged as a string inside the span's JSON
+        assert_eq!(
+            redact(r#"{"headers":"{\"cookie\": \"auth_token=abc\", \"accept\": \"*/*\"}"}"#),
+            r#"{"headers":"{\"cookie\": \"[redacted]\", \"accept\": \"*/*\"}"}"#
+        );
+        assert_eq!(
+            redact(r#"{"fields":"{\"token\": \"a\\\"b\", \"n\": 1}"}"#),
+            r#"{"fields":"{\"token\": \"[redacted]\", \"n\": 1}"}"#
+        );
+    }
+
+    #[test]
+    fn test_credential_headers_are_masked() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::COOKIE, HeaderValue::from_static("auth_token=abc"));
+        headers.insert(header::ACCEPT, HeaderValue::from_static("*/*"));
+
+        let redacted = redact_headers(&headers);
+        assert_eq!(redacted[header::COOKIE], REDACTED);
+        assert_eq!(redacted[header::ACCEPT], "*/*");
+        assert!(!redacted.contains_key(header::AUTHORIZATION));
+    }
+
+    #[test]
+    fn test_writer_masks_each_line() {
+        let mut output = Vec::new();
+        {
+            let mut line = RedactedLine {
+                inner: &mut output,
+                buffer: Vec::new(),
+            };
+            line.write_all(b"to=ana@example.com ").unwrap();
+            line.write_all(b"password=hunter2\n").unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "to=[redacted-email] password=[redacted]\n"
+        );
+    }
+}
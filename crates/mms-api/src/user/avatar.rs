@@ -0,0 +1,228 @@
+//! Avatar uploads.
+//!
+//! An upload is decoded, cropped to a centred square and resized to each of
+//! [`AVATAR_SIZES`], then kept in the media store as JPEG under
+//! `avatars/{user_id}/{upload_id}/{size}.jpg`. The largest size becomes the
+//! user's profile picture. Every upload gets a new key, so cached copies of an
+//! earlier avatar never show up in place of a newer one.
+
+use std::io::Cursor;
+
+use image::{
+    DynamicImage, ImageDecoder, ImageError, ImageFormat, ImageReader, Limits, RgbImage,
+    codecs::jpeg::JpegEncoder, imageops::FilterType,
+};
+use sqlx::types::Uuid;
+
+use crate::{
+    error::ApiError,
+    media::{self, MediaStore},
+};
+
+/// Side lengths avatars are stored at, in pixels, largest first
+pub const AVATAR_SIZES: [u32; 2] = [256, 64];
+
+/// Smallest width and height accepted, in pixels
+const MIN_DIMENSION: u32 = 64;
+
+/// Largest width and height accepted, in pixels. Decoding is refused beyond it,
+/// so a small file cannot expand into an enormous image.
+const MAX_DIMENSION: u32 = 8192;
+
+const JPEG_QUALITY: u8 = 85;
+
+/// An avatar resized to one of [`AVATAR_SIZES`]
+#[derive(Debug)]
+pub struct ResizedAvatar {
+    pub size: u32,
+    pub jpeg: Vec<u8>,
+}
+
+/// Decode an uploaded image and resize it to every avatar size.
+///
+/// PNG, JPEG, WebP and GIF are accepted, recognised by their content rather than
+/// the declared content type. Only the first frame of an animation is kept.
+/// CPU-bound; run it off the async runtime.
+pub fn resize(upload: &[u8]) -> Result<Vec<ResizedAvatar>, ApiError> {
+    let unsupported =
+        || ApiError::Validation("Avatar must be a PNG, JPEG, WebP or GIF image".to_string());
+
+    let mut reader = ImageReader::new(Cursor::new(upload))
+        .with_guessed_format()
+        .map_err(|_| unsupported())?;
+    if !matches!(
+        reader.format(),
+        Some(ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::WebP | ImageFormat::Gif)
+    ) {
+        return Err(unsupported());
+    }
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    reader.limits(limits);
+
+    let mut decoder = reader.into_decoder().map_err(decode_error)?;
+    // Phone cameras store the rotation separately from the pixels
+    let orientation = decoder.orientation().map_err(decode_error)?;
+    let mut image = DynamicImage::from_decoder(decoder).map_err(decode_error)?;
+    image.apply_orientation(orientation);
+
+    let (width, height) = (image.width(), image.height());
+    if width < MIN_DIMENSION || height < MIN_DIMENSION {
+        return Err(ApiError::Validation(format!(
+            "Avatar must be at least {MIN_DIMENSION} pixels wide and tall"
+        )));
+    }
+    let side = width.min(height);
+    let square = image.crop_imm((width - side) / 2, (height - side) / 2, side, side);
+
+    AVATAR_SIZES
+        .iter()
+        .map(|&size| {
+            let resized = square.resize_exact(size, size, FilterType::Lanczos3);
+            Ok(ResizedAvatar {
+                size,
+                jpeg: encode_jpeg(&resized)?,
+            })
+        })
+        .collect()
+}
+
+fn decode_error(e: ImageError) -> ApiError {
+    match e {
+        ImageError::Limits(_) => ApiError::Validation(format!(
+            "Avatar must be at most {MAX_DIMENSION} pixels wide and tall"
+        )),
+        _ => ApiError::Validation("Avatar image could not be read".to_string()),
+    }
+}
+
+/// JPEG has no transparency, so transparent areas turn white
+fn encode_jpeg(image: &DynamicImage) -> Result<Vec<u8>, ApiError> {
+    let rgba = image.to_rgba8();
+    let rgb = RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let over_white = |channel: u8| {
+            ((u16::from(channel) * u16::from(a) + 255 * (255 - u16::from(a))) / 255) as u8
+        };
+        image::Rgb([over_white(r), over_white(g), over_white(b)])
+    });
+
+    let mut jpeg = Vec::new();
+    JpegEncoder::new_with_quality(&mut jpeg, JPEG_QUALITY)
+        .encode_image(&rgb)
+        .map_err(|e| ApiError::Media(format!("Failed to encode avatar: {e}")))?;
+    Ok(jpeg)
+}
+
+/// Key under which one upload's sizes are stored
+#[must_use]
+pub fn key(user_id: Uuid, upload_id: Uuid) -> String {
+    format!("avatars/{user_id}/{upload_id}")
+}
+
+/// Key of one size of an avatar
+#[must_use]
+pub fn object_key(avatar_key: &str, size: u32) -> String {
+    format!("{avatar_key}/{size}.jpg")
+}
+
+/// Path the largest size is served at, used as the profile picture
+#[must_use]
+pub fn picture_url(avatar_key: &str) -> String {
+    media::url(&object_key(avatar_key, AVATAR_SIZES[0]))
+}
+
+/// Store every size of an upload under `avatar_key`
+pub async fn store(
+    media: &dyn MediaStore,
+    avatar_key: &str,
+    sizes: Vec<ResizedAvatar>,
+) -> Result<(), ApiError> {
+    for avatar in sizes {
+        media
+            .put(
+                &object_key(avatar_key, avatar.size),
+                "image/jpeg",
+                avatar.jpeg,
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+/// Delete every size of an avatar
+pub async fn delete(media: &dyn MediaStore, avatar_key: &str) -> Result<(), ApiError> {
+    media.delete_prefix(&format!("{avatar_key}/")).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = RgbaImage::from_fn(width, height, |x, _| {
+            // Left half transparent, right half opaque red
+            if x < width / 2 {
+                Rgba([0, 0, 0, 0])
+            } else {
+                Rgba([255, 0, 0, 255])
+            }
+        });
+        let mut bytes = Vec::new();
+        DynamicImage::ImageRgba8(image)
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_resizes_to_square_jpegs() {
+        let sizes = resize(&png(600, 300)).unwrap();
+        assert_eq!(
+            sizes.iter().map(|avatar| avatar.size).collect::<Vec<_>>(),
+            AVATAR_SIZES
+        );
+        for avatar in &sizes {
+            let decoded = image::load_from_memory_with_format(&avatar.jpeg, ImageFormat::Jpeg)
+                .unwrap()
+                .to_rgb8();
+            assert_eq!(decoded.dimensions(), (avatar.size, avatar.size));
+            // The centred crop keeps both halves, and transparency turns white
+            let left = decoded.get_pixel(2, avatar.size / 2).0;
+            let right = decoded.get_pixel(avatar.size - 3, avatar.size / 2).0;
+            assert!(left.iter().all(|&channel| channel > 240), "{left:?}");
+            assert!(right[0] > 200 && right[1] < 60, "{right:?}");
+        }
+    }
+
+    #[test]
+    fn test_rejects_what_is_not_an_avatar() {
+        assert!(matches!(
+            resize(b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>"),
+            Err(ApiError::Validation(message)) if message.contains("PNG, JPEG, WebP or GIF")
+        ));
+        assert!(matches!(
+            resize(&png(MIN_DIMENSION - 1, 200)),
+            Err(ApiError::Validation(message)) if message.contains("at least")
+        ));
+        assert!(matches!(
+            resize(&png(MAX_DIMENSION + 1, 64)),
+            Err(ApiError::Validation(message)) if message.contains("at most")
+        ));
+        let mut truncated = png(100, 100);
+        truncated.truncate(truncated.len() / 2);
+        assert!(matches!(resize(&truncated), Err(ApiError::Validation(_))));
+    }
+
+    #[test]
+    fn test_keys() {
+        let avatar_key = key(Uuid::nil(), Uuid::max());
+        assert!(media::is_valid_key(&object_key(&avatar_key, 64)));
+        assert_eq!(
+            picture_url(&avatar_key),
+            format!("/v1/media/avatars/{}/{}/256.jpg", Uuid::nil(), Uuid::max())
+        );
+    }
+}
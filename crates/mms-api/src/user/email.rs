@@ -1,14 +1,25 @@
-use lettre::{
-    Message, SmtpTransport, Transport, message::Mailbox,
-    transport::smtp::authentication::Credentials,
-};
+use std::sync::Arc;
+
+use sqlx::PgPool;
 use sqlx::types::Uuid;
 use tokio::sync::mpsc;
 
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
 use crate::error::ApiError;
+use crate::jobs::queue::{self, Job};
+use crate::mailer::templates::{self, DEFAULT_LOCALE, Template};
+use crate::mailer::{EmailSender, OutgoingEmail, outbox};
+
+use mms_db::models::WeeklyDigest;
+use mms_db::repositories::user as user_repo;
 
 /// Email job variants for the background worker
-#[derive(Debug, Clone)]
+///
+/// Serialized into the job queue, tagged with the template it renders.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "template", rename_all = "snake_case")]
 pub enum EmailJob {
     Verification {
         to_email: String,
@@ -24,196 +35,193 @@ pub enum EmailJob {
         to_email: String,
         username: String,
     },
+    ReviewReminder {
+        to_email: String,
+        username: String,
+        cards_due: i64,
+    },
+    StreakReminder {
+        to_email: String,
+        username: String,
+        streak_days: i32,
+    },
+    WeeklyDigest {
+        digest: Box<WeeklyDigest>,
+        /// Token for the email preferences link
+        preferences_token: String,
+    },
+    GroupInvite {
+        to_email: String,
+        /// Username of the teacher who sent the invite
+        inviter: String,
+        group_name: String,
+        invite_code: String,
+    },
 }
 
-#[derive(Clone)]
-pub struct EmailService {
-    smtp_host: String,
-    smtp_username: String,
-    smtp_password: String,
-    from_email_str: String,
-    from_name: String,
-    frontend_url: String,
-}
-
-impl EmailService {
-    pub fn new(
-        smtp_host: &str,
-        smtp_username: &str,
-        smtp_password: &str,
-        from_email: &str,
-        from_name: &str,
-        frontend_url: &str,
-    ) -> Result<Self, ApiError> {
-        // Validate email format
-        let _from_mailbox: Mailbox = format!("{} <{}>", from_name, from_email)
-            .parse()
-            .map_err(|e| ApiError::Email(format!("Invalid from email: {e}")))?;
-
-        Ok(Self {
-            smtp_host: smtp_host.to_string(),
-            smtp_username: smtp_username.to_string(),
-            smtp_password: smtp_password.to_string(),
-            from_email_str: from_email.to_string(),
-            from_name: from_name.to_string(),
-            frontend_url: frontend_url.to_string(),
-        })
-    }
-
-    fn create_transport(&self) -> Result<SmtpTransport, ApiError> {
-        let credentials = Credentials::new(self.smtp_username.clone(), self.smtp_password.clone());
-
-        let transport = SmtpTransport::relay(&self.smtp_host)
-            .map_err(|e| ApiError::Email(format!("Failed to create SMTP transport: {e}")))?
-            .credentials(credentials)
-            .build();
-
-        Ok(transport)
+impl EmailJob {
+    /// Recipient address
+    #[must_use]
+    pub fn to_email(&self) -> &str {
+        match self {
+            EmailJob::Verification { to_email, .. }
+            | EmailJob::PasswordReset { to_email, .. }
+            | EmailJob::PasswordChanged { to_email, .. }
+            | EmailJob::ReviewReminder { to_email, .. }
+            | EmailJob::StreakReminder { to_email, .. }
+            | EmailJob::GroupInvite { to_email, .. } => to_email,
+            EmailJob::WeeklyDigest { digest, .. } => &digest.email,
+        }
     }
 
-    pub fn send_password_reset_email(
-        &self,
-        to_email: &str,
-        username: &str,
-        reset_token: &str,
-    ) -> Result<(), ApiError> {
-        let smtp_transport = self.create_transport()?;
-        let from_email: Mailbox = format!("{} <{}>", self.from_name, self.from_email_str)
-            .parse()
-            .map_err(|e| ApiError::Validation(format!("Invalid from email: {e}")))?;
-
-        let reset_url = format!("{}/reset-password?token={}", self.frontend_url, reset_token);
-
-        let body = format!(
-            "Hi {},\n\nYou requested to reset your password for your Matcha Time account.\n\nReset your password by clicking this link:\n{}\n\nThis link will expire in 1 hour.\n\nIf you didn't request this, you can safely ignore this email.",
-            username, reset_url
-        );
-
-        let email = Message::builder()
-            .from(from_email)
-            .to(to_email
-                .parse()
-                .map_err(|e| ApiError::Validation(format!("Invalid recipient email: {e}")))?)
-            .subject("Reset Your Matcha Time Password")
-            .body(body)
-            .map_err(|e| ApiError::Email(format!("Failed to build email: {e}")))?;
-
-        smtp_transport
-            .send(&email)
-            .map_err(|e| ApiError::Email(format!("Failed to send email: {e}")))?;
-
-        Ok(())
+    /// Template this job is rendered with
+    #[must_use]
+    pub fn template(&self) -> Template {
+        match self {
+            EmailJob::Verification { .. } => Template::Verification,
+            EmailJob::PasswordReset { .. } => Template::PasswordReset,
+            EmailJob::PasswordChanged { .. } => Template::PasswordChanged,
+            EmailJob::ReviewReminder { .. } => Template::ReviewReminder,
+            EmailJob::StreakReminder { .. } => Template::StreakReminder,
+            EmailJob::WeeklyDigest { .. } => Template::WeeklyDigest,
+            EmailJob::GroupInvite { .. } => Template::GroupInvite,
+        }
     }
 
-    pub fn send_verification_email(
-        &self,
-        to_email: &str,
-        username: &str,
-        verification_token: &str,
-    ) -> Result<(), ApiError> {
-        let smtp_transport = self.create_transport()?;
-        let from_email: Mailbox = format!("{} <{}>", self.from_name, self.from_email_str)
-            .parse()
-            .map_err(|e| ApiError::Validation(format!("Invalid from email: {e}")))?;
-
-        let verification_url = format!(
-            "{}/verify-email?token={}",
-            self.frontend_url, verification_token
-        );
-
-        let body = format!(
-            "Hi {},\n\nWelcome to Matcha Time! Please verify your email address to complete your registration.\n\nVerify your email by clicking this link:\n{}\n\nThis link will expire in 24 hours.\n\nIf you didn't create this account, you can safely ignore this email.",
-            username, verification_url
-        );
-
-        let email = Message::builder()
-            .from(from_email)
-            .to(to_email
-                .parse()
-                .map_err(|e| ApiError::Validation(format!("Invalid recipient email: {e}")))?)
-            .subject("Verify Your Matcha Time Email")
-            .body(body)
-            .map_err(|e| ApiError::Email(format!("Failed to build email: {e}")))?;
-
-        smtp_transport
-            .send(&email)
-            .map_err(|e| ApiError::Email(format!("Failed to send email: {e}")))?;
+    /// Render the email this job sends in `locale`, falling back to English
+    pub fn render(&self, frontend_url: &str, locale: &str) -> Result<OutgoingEmail, ApiError> {
+        let data = match self {
+            EmailJob::Verification {
+                username,
+                verification_token,
+                ..
+            } => json!({
+                "username": username,
+                "token": verification_token,
+                "frontend_url": frontend_url,
+            }),
+            EmailJob::PasswordReset {
+                username,
+                reset_token,
+                ..
+            } => json!({
+                "username": username,
+                "token": reset_token,
+                "frontend_url": frontend_url,
+            }),
+            EmailJob::PasswordChanged { username, .. } => json!({
+                "username": username,
+                "frontend_url": frontend_url,
+            }),
+            EmailJob::ReviewReminder {
+                username,
+                cards_due,
+                ..
+            } => json!({
+                "username": username,
+                "cards_due": cards_due,
+                "frontend_url": frontend_url,
+            }),
+            EmailJob::StreakReminder {
+                username,
+                streak_days,
+                ..
+            } => json!({
+                "username": username,
+                "streak_days": streak_days,
+                "frontend_url": frontend_url,
+            }),
+            EmailJob::WeeklyDigest {
+                digest,
+                preferences_token,
+            } => weekly_digest_data(digest, preferences_token, frontend_url, locale),
+            EmailJob::GroupInvite {
+                inviter,
+                group_name,
+                invite_code,
+                ..
+            } => json!({
+                "inviter": inviter,
+                "group_name": group_name,
+                "invite_code": invite_code,
+                "frontend_url": frontend_url,
+            }),
+        };
 
-        Ok(())
+        let (subject, body) = templates::render(self.template(), locale, &data)?;
+        Ok(OutgoingEmail {
+            to: self.to_email().to_string(),
+            subject,
+            body,
+        })
     }
+}
 
-    pub fn send_password_changed_email(
-        &self,
-        to_email: &str,
-        username: &str,
-    ) -> Result<(), ApiError> {
-        let smtp_transport = self.create_transport()?;
-        let from_email: Mailbox = format!("{} <{}>", self.from_name, self.from_email_str)
-            .parse()
-            .map_err(|e| ApiError::Validation(format!("Invalid from email: {e}")))?;
-
-        let body = format!(
-            "Hi {},\n\nYour Matcha Time password has been successfully changed.\n\nIf you did not make this change, please contact support immediately and secure your account.\n\nFor security, you can request a password reset at:\n{}/reset-password\n\nBest regards,\nMatcha Time Team",
-            username, self.frontend_url
-        );
-
-        let email = Message::builder()
-            .from(from_email)
-            .to(to_email
-                .parse()
-                .map_err(|e| ApiError::Validation(format!("Invalid recipient email: {e}")))?)
-            .subject("Your Matcha Time Password Has Been Changed")
-            .body(body)
-            .map_err(|e| ApiError::Email(format!("Failed to build email: {e}")))?;
-
-        smtp_transport
-            .send(&email)
-            .map_err(|e| ApiError::Email(format!("Failed to send email: {e}")))?;
-
-        Ok(())
-    }
+/// Template values of the weekly progress digest
+fn weekly_digest_data(
+    digest: &WeeklyDigest,
+    preferences_token: &str,
+    frontend_url: &str,
+    locale: &str,
+) -> serde_json::Value {
+    let WeeklyDigest {
+        username,
+        week_start,
+        reviews,
+        correct_reviews,
+        graded_reviews,
+        cards_learned,
+        streak_days,
+        ..
+    } = digest;
+
+    let week_end = *week_start + chrono::Days::new(6);
+    let accuracy = (*graded_reviews > 0)
+        .then(|| (correct_reviews * 100 + graded_reviews / 2) / graded_reviews);
+
+    json!({
+        "username": username,
+        "week_start": templates::month_day(*week_start, locale),
+        "week_end": templates::month_day(week_end, locale),
+        "reviews": reviews,
+        "has_accuracy": accuracy.is_some(),
+        "accuracy": accuracy,
+        "cards_learned": cards_learned,
+        "streak_days": streak_days,
+        "token": preferences_token,
+        "frontend_url": frontend_url,
+    })
 }
 
 /// Start the email worker background task
 /// Returns a sender channel for submitting email jobs
-pub fn start_email_worker(email_service: EmailService) -> mpsc::UnboundedSender<EmailJob> {
-    let (tx, mut rx) = mpsc::unbounded_channel();
+///
+/// Each job is written to the job queue, whose workers render and deliver it
+/// through the outbox; failed sends are retried by a second task started
+/// alongside the worker. When the queue cannot be written the email is sent
+/// right away instead.
+pub fn start_email_worker(
+    sender: Arc<dyn EmailSender>,
+    pool: PgPool,
+    frontend_url: String,
+) -> mpsc::UnboundedSender<EmailJob> {
+    let (tx, mut rx) = mpsc::unbounded_channel::<EmailJob>();
+
+    tokio::spawn(outbox::retry_loop(pool.clone(), sender.clone()));
 
     tokio::spawn(async move {
         tracing::info!("Email worker started");
 
-        while let Some(job) = rx.recv().await {
-            // Run blocking SMTP I/O off the async runtime
-            let service = email_service.clone();
-            let result = tokio::task::spawn_blocking(move || match &job {
-                EmailJob::Verification {
-                    to_email,
-                    username,
-                    verification_token,
-                } => service
-                    .send_verification_email(to_email, username, verification_token)
-                    .map_err(|e| (e, job)),
-                EmailJob::PasswordReset {
-                    to_email,
-                    username,
-                    reset_token,
-                } => service
-                    .send_password_reset_email(to_email, username, reset_token)
-                    .map_err(|e| (e, job)),
-                EmailJob::PasswordChanged { to_email, username } => service
-                    .send_password_changed_email(to_email, username)
-                    .map_err(|e| (e, job)),
-            })
-            .await;
-
-            match result {
-                Ok(Err((e, job))) => {
-                    tracing::error!(error = %e, job = ?job, "Failed to send email in background worker");
+        while let Some(email) = rx.recv().await {
+            let template = email.template();
+            let job = Job::Email {
+                email: email.clone(),
+            };
+            if let Err(e) = queue::enqueue(&pool, &job).await {
+                tracing::error!(error = %e, ?template, "Failed to queue email, sending it now");
+                if let Err(e) = deliver(&pool, sender.as_ref(), &frontend_url, &email).await {
+                    tracing::error!(error = %e, ?template, "Failed to render email");
                 }
-                Err(e) => {
-                    tracing::error!(error = %e, "Email send task panicked");
-                }
-                Ok(Ok(())) => {}
             }
         }
 
@@ -223,6 +231,26 @@ pub fn start_email_worker(email_service: EmailService) -> mpsc::UnboundedSender<
     tx
 }
 
+/// Render `job` in the recipient's language and hand it to the outbox
+pub async fn deliver(
+    pool: &PgPool,
+    sender: &dyn EmailSender,
+    frontend_url: &str,
+    job: &EmailJob,
+) -> Result<(), ApiError> {
+    let locale = match user_repo::find_native_language_by_email(pool, job.to_email()).await {
+        Ok(locale) => locale,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to look up email locale, using default");
+            None
+        }
+    };
+
+    let email = job.render(frontend_url, locale.as_deref().unwrap_or(DEFAULT_LOCALE))?;
+    outbox::deliver(pool, sender, &email).await;
+    Ok(())
+}
+
 /// Helper function to send verification email via the email worker channel
 /// Logs errors but doesn't fail - useful for registration and resend flows
 pub fn send_verification_email_if_available(
@@ -250,3 +278,118 @@ pub fn send_verification_email_if_available(
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRONTEND_URL: &str = "https://app.example.com";
+
+    fn digest() -> WeeklyDigest {
+        WeeklyDigest {
+            user_id: Uuid::new_v4(),
+            email: "ana@example.com".to_string(),
+            username: "ana".to_string(),
+            week_start: chrono::NaiveDate::from_ymd_opt(2026, 10, 5).unwrap(),
+            reviews: 42,
+            correct_reviews: 29,
+            graded_reviews: 40,
+            cards_learned: 6,
+            streak_days: 1,
+        }
+    }
+
+    #[test]
+    fn test_render_uses_recipient_and_frontend_url() {
+        let job = EmailJob::PasswordReset {
+            to_email: "ana@example.com".to_string(),
+            username: "ana".to_string(),
+            reset_token: "tok".to_string(),
+        };
+
+        let email = job.render(FRONTEND_URL, "en").unwrap();
+
+        assert_eq!(email.to, "ana@example.com");
+        assert_eq!(email.subject, "Reset Your Matcha Time Password");
+        assert!(
+            email
+                .body
+                .contains("https://app.example.com/reset-password?token=tok")
+        );
+    }
+
+    #[test]
+    fn test_review_reminder_text() {
+        let job = |cards_due| EmailJob::ReviewReminder {
+            to_email: "ana@example.com".to_string(),
+            username: "ana".to_string(),
+            cards_due,
+        };
+
+        let email = job(12).render(FRONTEND_URL, "en").unwrap();
+        assert_eq!(email.subject, "You have 12 cards due for review");
+        assert!(email.body.starts_with("Hi ana,"));
+        assert!(email.body.contains("https://app.example.com/practice"));
+        assert!(email.body.contains("https://app.example.com/settings"));
+
+        let email = job(1).render(FRONTEND_URL, "en").unwrap();
+        assert_eq!(email.subject, "You have 1 card due for review");
+    }
+
+    #[test]
+    fn test_streak_reminder_text() {
+        let job = EmailJob::StreakReminder {
+            to_email: "ana@example.com".to_string(),
+            username: "ana".to_string(),
+            streak_days: 7,
+        };
+
+        let email = job.render(FRONTEND_URL, "en").unwrap();
+        assert_eq!(email.subject, "Your 7-day streak ends at midnight");
+        assert!(email.body.starts_with("Hi ana,"));
+        assert!(email.body.contains("https://app.example.com/practice"));
+        assert!(email.body.contains("https://app.example.com/settings"));
+    }
+
+    #[test]
+    fn test_weekly_digest_text() {
+        let job = |digest| EmailJob::WeeklyDigest {
+            digest: Box::new(digest),
+            preferences_token: "tok".to_string(),
+        };
+
+        let email = job(digest()).render(FRONTEND_URL, "en").unwrap();
+        assert_eq!(email.to, "ana@example.com");
+        assert_eq!(email.subject, "Your week on Matcha Time: 42 reviews");
+        assert!(email.body.starts_with("Hi ana,"));
+        assert!(email.body.contains("October 5 to October 11"));
+        assert!(email.body.contains("- Accuracy: 73%"));
+        assert!(email.body.contains("- New cards learned: 6"));
+        assert!(email.body.contains("- Current streak: 1 day\n"));
+        assert!(
+            email
+                .body
+                .contains("https://app.example.com/email-preferences?token=tok")
+        );
+
+        let mut ungraded = digest();
+        ungraded.graded_reviews = 0;
+        let email = job(ungraded).render(FRONTEND_URL, "en").unwrap();
+        assert!(email.body.contains("- Accuracy: not available yet"));
+    }
+
+    #[test]
+    fn test_render_in_recipient_locale() {
+        let job = EmailJob::WeeklyDigest {
+            digest: Box::new(digest()),
+            preferences_token: "tok".to_string(),
+        };
+
+        let email = job.render(FRONTEND_URL, "es").unwrap();
+        assert_eq!(email.subject, "Tu semana en Matcha Time: 42 repasos");
+        assert!(email.body.contains("del 5 de octubre al 11 de octubre"));
+
+        let fallback = job.render(FRONTEND_URL, "fr").unwrap();
+        assert_eq!(fallback.subject, "Your week on Matcha Time: 42 reviews");
+    }
+}
@@ -0,0 +1,71 @@
+//! User data exports.
+//!
+//! `GET /users/me/export` streams the data straight to the client. Exports
+//! requested with `POST /users/me/exports` are built by the job queue instead,
+//! so a client on a poor connection can ask for one, poll until it is ready
+//! and download the finished file, which is kept for [`RETENTION_DAYS`].
+
+use futures_util::TryStreamExt;
+use serde::Serialize;
+use sqlx::PgPool;
+use sqlx::types::Uuid;
+use utoipa::ToSchema;
+
+use mms_db::models::{ActivityDay, CardProgressExport};
+use mms_db::repositories::{data_export as export_repo, user as user_repo};
+
+use crate::preferences::{self, Preferences};
+
+/// Days a requested export is kept
+pub const RETENTION_DAYS: i32 = 7;
+
+/// One record of a user data export
+#[derive(Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExportRecord {
+    Preferences(Preferences),
+    CardProgress(CardProgressExport),
+    Activity(ActivityDay),
+}
+
+/// Build `user_id`'s export as NDJSON: preferences first, then card progress,
+/// then activity days oldest first
+pub async fn build_ndjson(pool: &PgPool, user_id: Uuid) -> anyhow::Result<Vec<u8>> {
+    let mut body = Vec::new();
+
+    let preferences = preferences::load(pool, user_id).await?;
+    write_line(&mut body, &ExportRecord::Preferences(preferences))?;
+
+    let mut progress = user_repo::stream_card_progress(pool, user_id);
+    while let Some(row) = progress.try_next().await? {
+        write_line(&mut body, &ExportRecord::CardProgress(row))?;
+    }
+    drop(progress);
+
+    let mut activity = user_repo::stream_activity(pool, user_id);
+    while let Some(day) = activity.try_next().await? {
+        write_line(&mut body, &ExportRecord::Activity(day))?;
+    }
+    Ok(body)
+}
+
+fn write_line(body: &mut Vec<u8>, record: &ExportRecord) -> anyhow::Result<()> {
+    serde_json::to_writer(&mut *body, record)?;
+    body.push(b'\n');
+    Ok(())
+}
+
+/// Build a requested export and store it; run by the job queue
+pub async fn run(pool: &PgPool, export_id: Uuid, user_id: Uuid) -> anyhow::Result<()> {
+    let body = build_ndjson(pool, user_id).await?;
+    if !export_repo::complete(pool, export_id, &body).await? {
+        tracing::debug!(%export_id, "Export deleted before it was built");
+    }
+    Ok(())
+}
+
+/// Record that the export will not be built, once the queue gives up on it
+pub async fn give_up(pool: &PgPool, export_id: Uuid) -> anyhow::Result<()> {
+    export_repo::mark_failed(pool, export_id).await?;
+    Ok(())
+}
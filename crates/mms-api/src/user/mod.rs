@@ -1,7 +1,21 @@
+pub mod avatar;
 pub mod email;
 pub mod email_verification;
+pub mod export;
 pub mod password_reset;
 pub mod routes;
-pub mod token;
+pub mod service;
 
 pub use routes::routes;
+
+/// Days a deleted account can be restored before it is deleted for good
+pub const ACCOUNT_DELETION_GRACE_DAYS: i32 = 30;
+
+/// Days a username someone gave up stays reserved for other accounts
+pub const USERNAME_RESERVATION_DAYS: i32 = 30;
+
+/// Days a new account has to verify its email address before it is deleted
+pub const UNVERIFIED_ACCOUNT_DAYS: i32 = 7;
+
+/// Hours a count of failed logins for an email address lasts after its last failure
+pub const LOGIN_FAILURE_WINDOW_HOURS: i32 = 24;
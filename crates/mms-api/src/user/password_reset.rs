@@ -1,12 +1,11 @@
-use chrono::{Duration, Utc};
+use chrono::Duration;
 use sqlx::PgPool;
 use sqlx::types::Uuid;
 
-use super::token::{generate_token, hash_token};
 use crate::error::ApiError;
+use crate::token_service::{self, TokenPurpose};
 
 use mms_db::repositories::auth as auth_repo;
-use mms_db::repositories::token as token_repo;
 use mms_db::repositories::user as user_repo;
 
 /// Create a password reset token in the database
@@ -15,24 +14,13 @@ pub async fn create_reset_token(
     user_id: Uuid,
     expires_in_hours: i64,
 ) -> Result<String, ApiError> {
-    // Generate the token
-    let token = generate_token();
-    let token_hash = hash_token(&token);
-
-    // Calculate expiration time
-    let expires_at = Utc::now() + Duration::hours(expires_in_hours);
-
-    let mut tx = pool.begin().await?;
-
-    // Invalidate any existing unused tokens for this user
-    token_repo::invalidate_reset_tokens(&mut *tx, user_id).await?;
-
-    // Insert new token
-    token_repo::insert_reset_token(&mut *tx, user_id, &token_hash, expires_at).await?;
-
-    tx.commit().await?;
-
-    Ok(token)
+    token_service::issue(
+        pool,
+        user_id,
+        TokenPurpose::PasswordReset,
+        Duration::hours(expires_in_hours),
+    )
+    .await
 }
 
 /// Verify a reset token, update password, and mark token as used (all in one transaction)
@@ -42,19 +30,14 @@ pub async fn verify_and_reset_password(
     token: &str,
     new_password_hash: &str,
 ) -> Result<(String, String), ApiError> {
-    let token_hash = hash_token(token);
-
     // Start transaction to ensure atomicity
     let mut tx = pool.begin().await?;
 
     // Find the token and mark it as used
-    let user_id = token_repo::consume_reset_token(&mut *tx, &token_hash)
-        .await?
-        .ok_or_else(|| ApiError::Auth("Invalid or expired reset token".to_string()))?;
+    let user_id = token_service::consume(&mut *tx, TokenPurpose::PasswordReset, token).await?;
 
     // Update the user's password
-    let updated =
-        user_repo::update_password_for_email_user(&mut *tx, user_id, new_password_hash).await?;
+    let updated = user_repo::update_password(&mut *tx, user_id, new_password_hash).await?;
     if !updated {
         return Err(ApiError::NotFound("User not found".to_string()));
     }
@@ -74,6 +57,5 @@ pub async fn verify_and_reset_password(
 
 /// Clean up expired tokens (can be run periodically)
 pub async fn cleanup_expired_tokens(pool: &PgPool) -> Result<u64, ApiError> {
-    let rows = token_repo::cleanup_expired_reset_tokens(pool).await?;
-    Ok(rows)
+    token_service::cleanup_expired(pool, TokenPurpose::PasswordReset).await
 }
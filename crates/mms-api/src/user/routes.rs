@@ -1,35 +1,71 @@
 use axum::{
     Json, Router,
-    extract::{Query, State},
-    routing::{delete, get, patch, post},
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::{delete, get, patch, post, put},
 };
 use axum_extra::extract::{PrivateCookieJar, cookie::Cookie};
+use chrono::{DateTime, Utc};
+use futures_util::TryStreamExt;
 use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
 
 use crate::{
     ApiState,
-    auth::{self, AuthUser, cookies, jwt, routes::AuthResponse},
-    error::ApiError,
+    auth::{
+        self, AuthUser, Role, cookies, jwt,
+        routes::{AuthResponse, SessionResponse, TOKENS_IN_BODY_DEPRECATION},
+        validation::{validate_email, validate_password, validate_username},
+    },
+    captcha,
+    error::{ApiError, ErrorResponse},
+    goals::{self, DailyGoalStatus},
+    idempotency::Idempotent,
+    jobs::queue::{self, Job},
+    media,
     middleware::rate_limit,
-    user::{email_verification, password_reset},
+    preferences,
+    streaming::NDJSON_CONTENT_TYPE,
+    streaming::{StreamFormat, json_stream},
+    token_service,
+    user::{
+        ACCOUNT_DELETION_GRACE_DAYS, LOGIN_FAILURE_WINDOW_HOURS, USERNAME_RESERVATION_DAYS, avatar,
+        email_verification,
+        export::ExportRecord,
+        password_reset,
+        service::{self, is_unique_violation},
+    },
+    validation::{self, ValidJson},
+    versioning::{ApiVersion, Deprecation, deprecated},
+    xp::XpProgress,
 };
 
-use mms_db::models::{ActivityDay, UserStats};
+use mms_db::models::{ActivityDay, DataExport, UserCredentials, UserStats};
+use mms_db::repositories::data_export as export_repo;
+use mms_db::repositories::login_failure as login_failure_repo;
+use mms_db::repositories::profile as profile_repo;
 use mms_db::repositories::user as user_repo;
-
-/// Check if a SQLx error is a PostgreSQL unique constraint violation (error code 23505).
-fn is_unique_violation(e: &sqlx::Error) -> bool {
-    if let sqlx::Error::Database(db_err) = e {
-        db_err.code().as_deref() == Some("23505")
-    } else {
-        false
-    }
-}
+use mms_db::repositories::xp as xp_repo;
 
 /// Create the user routes
-pub fn routes() -> Router<ApiState> {
+pub fn routes(version: ApiVersion) -> Router<ApiState> {
     use crate::make_rate_limit_layer;
 
+    let login = match version {
+        ApiVersion::V1 => deprecated(
+            post(login_user),
+            Deprecation {
+                successor: Some("/v2/users/login"),
+                ..TOKENS_IN_BODY_DEPRECATION
+            },
+        ),
+        ApiVersion::V2 => post(login_user_v2),
+    };
+
     // Sensitive routes with very strict rate limiting and timing-safe middleware
     let sensitive_routes = Router::new()
         .route(
@@ -51,7 +87,8 @@ pub fn routes() -> Router<ApiState> {
     // Auth routes with strict rate limiting and timing-safe middleware
     let auth_routes = Router::new()
         .route("/users/register", post(create_user))
-        .route("/users/login", post(login_user))
+        .route("/users/login", login)
+        .route("/users/restore", post(restore_account))
         .route("/users/reset-password", post(reset_password))
         .layer(make_rate_limit_layer!(
             rate_limit::AUTH_RATE_PER_SECOND,
@@ -61,11 +98,33 @@ pub fn routes() -> Router<ApiState> {
             rate_limit::timing_safe_middleware,
         ));
 
+    // Public lookups with strict rate limiting to slow down enumeration
+    let lookup_routes = Router::new()
+        .route("/users/check-username", get(check_username))
+        .layer(make_rate_limit_layer!(
+            rate_limit::AUTH_RATE_PER_SECOND,
+            rate_limit::AUTH_BURST_SIZE
+        ));
+
     // General authenticated routes with moderate rate limiting
     let general_routes = Router::new()
         .route("/users/me/dashboard", get(get_user_dashboard))
-        .route("/users/me/password", patch(change_password))
+        .route("/users/me/export", get(export_user_data))
+        .route("/users/me/exports", post(request_export))
+        .route("/users/me/exports/{export_id}", get(get_export))
+        .route(
+            "/users/me/exports/{export_id}/download",
+            get(download_export),
+        )
+        .route(
+            "/users/me/password",
+            post(set_password).patch(change_password),
+        )
+        .route("/users/me/sign-in-methods", get(get_sign_in_methods))
         .route("/users/me/username", patch(change_username))
+        .route("/users/me/avatar", put(upload_avatar).delete(delete_avatar))
+        .route("/users/me/timezone", patch(change_timezone))
+        .route("/users/me/privacy", get(get_privacy).patch(update_privacy))
         .route("/users/me", delete(delete_user))
         .route("/users/verify-email", get(verify_email))
         .layer(make_rate_limit_layer!(
@@ -77,49 +136,281 @@ pub fn routes() -> Router<ApiState> {
     Router::new()
         .merge(sensitive_routes)
         .merge(auth_routes)
+        .merge(lookup_routes)
         .merge(general_routes)
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct UserDashboard {
     stats: UserStats,
     heatmap: Vec<ActivityDay>,
+    /// Account-wide, also when `profile_id` is given
+    xp: XpProgress,
+    /// Account-wide, also when `profile_id` is given
+    daily_goal: DailyGoalStatus,
 }
 
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct DashboardQuery {
+    /// Only count reviews in this learning profile; account-wide when omitted
+    #[serde(default)]
+    profile_id: Option<Uuid>,
+}
+
+/// Streaks, totals, XP, today's goal and the last year of daily review counts
+#[utoipa::path(
+    get,
+    path = "/v1/users/me/dashboard",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    params(DashboardQuery),
+    responses(
+        (status = 200, description = "Dashboard", body = UserDashboard),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Learning profile not found", body = ErrorResponse),
+    )
+)]
 async fn get_user_dashboard(
     auth: AuthUser,
     State(state): State<ApiState>,
+    Query(query): Query<DashboardQuery>,
 ) -> Result<Json<UserDashboard>, ApiError> {
     let user_id = auth.user_id;
 
+    if let Some(profile_id) = query.profile_id {
+        profile_repo::find_for_user(&state.pool, user_id, profile_id)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Learning profile not found".to_string()))?;
+
+        let stats = profile_repo::get_stats(&state.pool, profile_id).await?;
+        let heatmap = profile_repo::get_activity(&state.pool, profile_id, 365).await?;
+        let xp = xp_repo::get_total(&state.pool, user_id).await?.into();
+        let daily_goal = goals::status(&state.pool, user_id).await?;
+        return Ok(Json(UserDashboard {
+            stats,
+            heatmap,
+            xp,
+            daily_goal,
+        }));
+    }
+
     let stats = user_repo::get_user_stats(&state.pool, user_id).await?;
 
     let heatmap = user_repo::get_user_activity(&state.pool, user_id, 365).await?;
 
-    Ok(Json(UserDashboard { stats, heatmap }))
+    let xp = xp_repo::get_total(&state.pool, user_id).await?.into();
+
+    let daily_goal = goals::status(&state.pool, user_id).await?;
+
+    Ok(Json(UserDashboard {
+        stats,
+        heatmap,
+        xp,
+        daily_goal,
+    }))
+}
+
+/// Stream the user's card progress and full activity history.
+///
+/// Card progress records come first, then activity days oldest first. Each
+/// record carries a `type` field so NDJSON consumers can dispatch line by line.
+#[utoipa::path(
+    get,
+    path = "/v1/users/me/export",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    responses(
+        (status = 200, description = "Streamed export; NDJSON with `Accept: application/x-ndjson`", content(
+            (Vec<ExportRecord> = "application/json"),
+            (ExportRecord = "application/x-ndjson"),
+        )),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+async fn export_user_data(
+    auth: AuthUser,
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+) -> Response {
+    let user_id = auth.user_id;
+    let pool = state.pool.clone();
+
+    json_stream(StreamFormat::from_headers(&headers), move |tx| async move {
+        tx.send(ExportRecord::Preferences(
+            preferences::load(&pool, user_id).await?,
+        ))
+        .await?;
+
+        let mut progress = user_repo::stream_card_progress(&pool, user_id);
+        while let Some(row) = progress.try_next().await? {
+            tx.send(ExportRecord::CardProgress(row)).await?;
+        }
+        drop(progress);
+
+        let mut activity = user_repo::stream_activity(&pool, user_id);
+        while let Some(day) = activity.try_next().await? {
+            tx.send(ExportRecord::Activity(day)).await?;
+        }
+        Ok(())
+    })
+}
+
+/// Ask for an export to be built in the background.
+///
+/// The export holds the same records as `GET /users/me/export`. Poll it until
+/// it is `ready`, then download it; it is kept for 7 days.
+#[utoipa::path(
+    post,
+    path = "/v1/users/me/exports",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    responses(
+        (status = 202, description = "Export queued", body = DataExport),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+async fn request_export(
+    auth: AuthUser,
+    State(state): State<ApiState>,
+) -> Result<(StatusCode, Json<DataExport>), ApiError> {
+    let mut tx = state.pool.begin().await?;
+    let data_export = export_repo::create(&mut *tx, auth.user_id).await?;
+    let job = Job::DataExport {
+        export_id: data_export.id,
+        user_id: auth.user_id,
+    };
+    queue::enqueue(&mut *tx, &job).await?;
+    tx.commit().await?;
+
+    Ok((StatusCode::ACCEPTED, Json(data_export)))
 }
 
-#[derive(Debug, Deserialize)]
+/// Whether a requested export is ready
+#[utoipa::path(
+    get,
+    path = "/v1/users/me/exports/{export_id}",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    params(("export_id" = Uuid, Path)),
+    responses(
+        (status = 200, description = "The export", body = DataExport),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Export not found or expired", body = ErrorResponse),
+    )
+)]
+async fn get_export(
+    auth: AuthUser,
+    State(state): State<ApiState>,
+    Path(export_id): Path<Uuid>,
+) -> Result<Json<DataExport>, ApiError> {
+    export_repo::find(&state.pool, auth.user_id, export_id)
+        .await?
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound("Export not found".to_string()))
+}
+
+/// Download a ready export as NDJSON
+#[utoipa::path(
+    get,
+    path = "/v1/users/me/exports/{export_id}/download",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    params(("export_id" = Uuid, Path)),
+    responses(
+        (status = 200, description = "One record per line", body = ExportRecord, content_type = "application/x-ndjson"),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Export not found or expired", body = ErrorResponse),
+        (status = 409, description = "Export not ready yet, or failed", body = ErrorResponse),
+    )
+)]
+async fn download_export(
+    auth: AuthUser,
+    State(state): State<ApiState>,
+    Path(export_id): Path<Uuid>,
+) -> Result<Response, ApiError> {
+    let Some(body) = export_repo::find_body(&state.pool, auth.user_id, export_id).await? else {
+        return Err(
+            match export_repo::find(&state.pool, auth.user_id, export_id).await? {
+                Some(data_export) => {
+                    ApiError::Conflict(format!("Export is {}", data_export.status))
+                }
+                None => ApiError::NotFound("Export not found".to_string()),
+            },
+        );
+    };
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, NDJSON_CONTENT_TYPE.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"export-{export_id}.ndjson\""),
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 struct CreateUserRequest {
+    #[validate(custom(function = "validate_username"))]
     username: String,
+    #[validate(custom(function = "validate_email"))]
     email: String,
+    #[validate(custom(function = "validate_password"))]
     password: String,
+    #[serde(default)]
+    captcha_token: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 struct LoginRequest {
+    #[validate(length(max = 254, message = "Email must be at most 254 characters long"))]
     email: String,
+    /// Longer passwords are never accepted at registration, so cannot match
+    #[validate(length(max = 128, message = "Password must be at most 128 characters long"))]
     password: String,
+    /// Only required once the email address has too many consecutive failed logins
+    #[serde(default)]
+    captcha_token: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct RegisterResponse {
+    message: String,
+    email: String,
 }
 
+/// Create an email account and send a verification link.
+///
+/// Registering an existing email returns the same response (and resends the
+/// link if it is unverified) so the endpoint cannot be used to probe accounts.
+#[utoipa::path(
+    post,
+    path = "/v1/users/register",
+    tag = "users",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key get the first response again")),
+    request_body = CreateUserRequest,
+    responses(
+        (status = 200, description = "Registration accepted", body = RegisterResponse),
+        (status = 400, description = "Invalid email, password, username or captcha", body = ErrorResponse),
+        (status = 409, description = "Username or email already in use", body = ErrorResponse),
+    )
+)]
 async fn create_user(
     State(state): State<ApiState>,
-    Json(request): Json<CreateUserRequest>,
-) -> Result<Json<serde_json::Value>, ApiError> {
-    // Validate input
-    auth::validation::validate_email(&request.email)?;
-    auth::validation::validate_password(&request.password)?;
-    auth::validation::validate_username(&request.username)?;
+    Idempotent(idempotency, ValidJson(request)): Idempotent<ValidJson<CreateUserRequest>>,
+) -> Response {
+    idempotency.finish(register(&state, request).await).await
+}
+
+async fn register(
+    state: &ApiState,
+    request: CreateUserRequest,
+) -> Result<Json<RegisterResponse>, ApiError> {
+    captcha::require_captcha(state.captcha.as_deref(), request.captcha_token.as_deref()).await?;
 
     // Check if user already exists
     let existing_user = user_repo::find_existence_by_email(&state.pool, &request.email).await?;
@@ -142,22 +433,31 @@ async fn create_user(
         }
 
         // Return generic message regardless of verification status to prevent enumeration
-        return Ok(Json(serde_json::json!({
-            "message": "Registration successful. Please check your email to verify your account.",
-            "email": request.email
-        })));
+        return Ok(Json(RegisterResponse {
+            message: "Registration successful. Please check your email to verify your account."
+                .to_string(),
+            email: request.email,
+        }));
+    }
+
+    // Usernames given up recently stay with their previous owner
+    let status = user_repo::find_username_status(
+        &state.pool,
+        &request.username,
+        None,
+        USERNAME_RESERVATION_DAYS,
+    )
+    .await?;
+    if status.reserved {
+        return Err(ApiError::Conflict(
+            "Registration failed. This username or email may already be in use.".to_string(),
+        ));
     }
 
     // Start a transaction for user creation
     let mut tx = state.pool.begin().await?;
 
-    // Hash the password (CPU-intensive, run off the async runtime)
-    let password = request.password.clone();
-    let cost = state.auth.bcrypt_cost;
-    let password_hash = tokio::task::spawn_blocking(move || bcrypt::hash(password, cost))
-        .await
-        .map_err(|_| ApiError::Auth("Hashing failed".into()))?
-        .map_err(ApiError::Bcrypt)?;
+    let password_hash = service::hash_password(&request.password, state.auth.bcrypt_cost).await?;
 
     // Insert user into database
     let user_id =
@@ -197,38 +497,128 @@ async fn create_user(
         &verification_token,
     );
 
-    Ok(Json(serde_json::json!({
-        "message": "Registration successful. Please check your email to verify your account.",
-        "email": request.email
-    })))
+    Ok(Json(RegisterResponse {
+        message: "Registration successful. Please check your email to verify your account."
+            .to_string(),
+        email: request.email,
+    }))
 }
 
+/// Sign in with email and password; sets the `auth_token` and `refresh_token` cookies
+///
+/// Deprecated in favour of `/v2/users/login`, which does not echo the tokens in the body.
+#[utoipa::path(
+    post,
+    path = "/v1/users/login",
+    tag = "users",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Signed in", body = AuthResponse),
+        (status = 400, description = "Captcha required or invalid", body = ErrorResponse),
+        (status = 401, description = "Wrong credentials or unverified email", body = ErrorResponse),
+    )
+)]
 async fn login_user(
     State(state): State<ApiState>,
     jar: PrivateCookieJar,
-    Json(request): Json<LoginRequest>,
+    ValidJson(request): ValidJson<LoginRequest>,
 ) -> Result<(PrivateCookieJar, Json<AuthResponse>), ApiError> {
-    // Fetch user from database
-    let user = user_repo::find_credentials_by_email(&state.pool, &request.email)
-        .await?
-        .ok_or_else(|| ApiError::Auth("Invalid email or password".to_string()))?;
-
-    // Verify password exists and matches
-    let password_hash = user
-        .password_hash
-        .as_deref()
-        .ok_or_else(|| ApiError::Auth("Invalid email or password".to_string()))?;
-
-    let password = request.password.clone();
-    let hash = password_hash.to_owned();
-    let valid = tokio::task::spawn_blocking(move || bcrypt::verify(password, &hash))
-        .await
-        .map_err(|_| ApiError::Auth("Verification failed".into()))?
-        .map_err(ApiError::Bcrypt)?;
-    if !valid {
-        return Err(ApiError::Auth("Invalid email or password".to_string()));
+    let (jar, session) = sign_in(&state, jar, request).await?;
+    Ok((jar, Json(session)))
+}
+
+/// Sign in with email and password; the session is only returned as cookies
+#[utoipa::path(
+    post,
+    path = "/v2/users/login",
+    tag = "users",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Signed in", body = SessionResponse),
+        (status = 400, description = "Captcha required or invalid", body = ErrorResponse),
+        (status = 401, description = "Wrong credentials or unverified email", body = ErrorResponse),
+    )
+)]
+async fn login_user_v2(
+    State(state): State<ApiState>,
+    jar: PrivateCookieJar,
+    ValidJson(request): ValidJson<LoginRequest>,
+) -> Result<(PrivateCookieJar, Json<SessionResponse>), ApiError> {
+    let (jar, session) = sign_in(&state, jar, request).await?;
+    Ok((jar, Json(SessionResponse { user: session.user })))
+}
+
+/// Check credentials, issue tokens and set the session cookies
+async fn sign_in(
+    state: &ApiState,
+    jar: PrivateCookieJar,
+    request: LoginRequest,
+) -> Result<(PrivateCookieJar, AuthResponse), ApiError> {
+    let user = verify_credentials(state, &request).await?;
+
+    if user.deleted_at.is_some() {
+        return Err(ApiError::Forbidden(
+            "This account was deleted. Restore it to sign in again.".to_string(),
+        ));
+    }
+
+    start_session(state, jar, user).await
+}
+
+/// Look up the email account and check its password, behind a captcha after too many failures
+///
+/// Failures are counted per email address whether or not an account has it, and
+/// the captcha is required before the lookup, so the response does not tell
+/// which addresses have accounts.
+async fn verify_credentials(
+    state: &ApiState,
+    request: &LoginRequest,
+) -> Result<UserCredentials, ApiError> {
+    let email_hash = token_service::hash_token(&request.email.trim().to_lowercase());
+
+    // After too many consecutive failures the address is locked behind a captcha
+    let failures =
+        login_failure_repo::count(&state.pool, &email_hash, LOGIN_FAILURE_WINDOW_HOURS).await?;
+    if failures >= state.auth.login_captcha_threshold {
+        captcha::require_captcha(state.captcha.as_deref(), request.captcha_token.as_deref())
+            .await?;
     }
 
+    let user = user_repo::find_credentials_by_email(&state.pool, &request.email).await?;
+
+    // Verify the account exists, has a password and the password matches
+    let valid = match user.as_ref().and_then(|user| user.password_hash.clone()) {
+        Some(hash) => {
+            let password = request.password.clone();
+            tokio::task::spawn_blocking(move || bcrypt::verify(password, &hash))
+                .await
+                .map_err(|_| ApiError::Auth("Verification failed".into()))?
+                .map_err(ApiError::Bcrypt)?
+        }
+        None => false,
+    };
+    let user = match user {
+        Some(user) if valid => user,
+        _ => {
+            login_failure_repo::record(&state.pool, &email_hash, LOGIN_FAILURE_WINDOW_HOURS)
+                .await?;
+            return Err(ApiError::Auth("Invalid email or password".to_string()));
+        }
+    };
+
+    if failures > 0 {
+        login_failure_repo::clear(&state.pool, &email_hash).await?;
+    }
+
+    Ok(user)
+}
+
+/// Issue tokens for a user whose credentials were checked and set the session cookies
+async fn start_session(
+    state: &ApiState,
+    jar: PrivateCookieJar,
+    user: UserCredentials,
+) -> Result<(PrivateCookieJar, AuthResponse), ApiError> {
     // Check if email is verified
     if !user.email_verified {
         return Err(ApiError::Auth(
@@ -240,7 +630,8 @@ async fn login_user(
     let token = jwt::generate_jwt_token(
         user.id,
         user.email.clone(),
-        &state.auth.jwt_secret,
+        Role::from_db(&user.role),
+        &state.auth.jwt_keys,
         state.auth.jwt_expiry_hours,
     )?;
 
@@ -273,30 +664,43 @@ async fn login_user(
 
     Ok((
         jar,
-        Json(AuthResponse {
+        AuthResponse {
             token,
             refresh_token,
             user: user.into(),
-        }),
+        },
     ))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 struct RequestPasswordResetRequest {
+    #[validate(custom(function = "validate_email"))]
     email: String,
+    #[serde(default)]
+    captcha_token: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct RequestPasswordResetResponse {
     message: String,
 }
 
+/// Email a password reset link if the account exists
+#[utoipa::path(
+    post,
+    path = "/v1/users/request-password-reset",
+    tag = "users",
+    request_body = RequestPasswordResetRequest,
+    responses(
+        (status = 200, description = "Same response whether or not the account exists", body = RequestPasswordResetResponse),
+        (status = 400, description = "Invalid email or captcha", body = ErrorResponse),
+    )
+)]
 async fn request_password_reset(
     State(state): State<ApiState>,
-    Json(request): Json<RequestPasswordResetRequest>,
+    ValidJson(request): ValidJson<RequestPasswordResetRequest>,
 ) -> Result<Json<RequestPasswordResetResponse>, ApiError> {
-    // Validate email format
-    auth::validation::validate_email(&request.email)?;
+    captcha::require_captcha(state.captcha.as_deref(), request.captcha_token.as_deref()).await?;
 
     // Find user by email (only for email auth provider)
     let user = user_repo::find_id_and_name_by_email(&state.pool, &request.email).await?;
@@ -337,31 +741,36 @@ async fn request_password_reset(
     }))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 struct ResetPasswordRequest {
     token: String,
+    #[validate(custom(function = "validate_password"))]
     new_password: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct ResetPasswordResponse {
     message: String,
 }
 
+/// Set a new password using a reset token
+#[utoipa::path(
+    post,
+    path = "/v1/users/reset-password",
+    tag = "users",
+    request_body = ResetPasswordRequest,
+    responses(
+        (status = 200, description = "Password changed", body = ResetPasswordResponse),
+        (status = 400, description = "Password does not meet the requirements", body = ErrorResponse),
+        (status = 401, description = "Invalid or expired token", body = ErrorResponse),
+    )
+)]
 async fn reset_password(
     State(state): State<ApiState>,
-    Json(request): Json<ResetPasswordRequest>,
+    ValidJson(request): ValidJson<ResetPasswordRequest>,
 ) -> Result<Json<ResetPasswordResponse>, ApiError> {
-    // Validate new password
-    auth::validation::validate_password(&request.new_password)?;
-
-    // Hash the new password (CPU-intensive, run off the async runtime)
-    let new_password = request.new_password.clone();
-    let cost = state.auth.bcrypt_cost;
-    let password_hash = tokio::task::spawn_blocking(move || bcrypt::hash(new_password, cost))
-        .await
-        .map_err(|_| ApiError::Auth("Hashing failed".into()))?
-        .map_err(ApiError::Bcrypt)?;
+    let password_hash =
+        service::hash_password(&request.new_password, state.auth.bcrypt_cost).await?;
 
     // Verify token and reset password in a single transaction
     // This prevents token burn without password update
@@ -395,15 +804,34 @@ async fn reset_password(
     }))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct VerifyEmailQuery {
+    /// Token from the verification email
     token: String,
 }
 
+#[derive(Debug, Serialize, ToSchema)]
+struct VerifyEmailResponse {
+    message: String,
+    email: String,
+}
+
+/// Mark the email address behind a verification token as verified
+#[utoipa::path(
+    get,
+    path = "/v1/users/verify-email",
+    tag = "users",
+    params(VerifyEmailQuery),
+    responses(
+        (status = 200, description = "Email verified", body = VerifyEmailResponse),
+        (status = 400, description = "Invalid or expired token", body = ErrorResponse),
+    )
+)]
 async fn verify_email(
     State(state): State<ApiState>,
     Query(query): Query<VerifyEmailQuery>,
-) -> Result<Json<serde_json::Value>, ApiError> {
+) -> Result<Json<VerifyEmailResponse>, ApiError> {
     // Verify the token and mark the user's email as verified
     let (email, newly_verified) =
         email_verification::verify_email_token(&state.pool, &query.token).await?; // Propagate the error to return proper error codes
@@ -414,24 +842,38 @@ async fn verify_email(
         "Email verification processed successfully."
     };
 
-    Ok(Json(serde_json::json!({
-        "message": message,
-        "email": email
-    })))
+    Ok(Json(VerifyEmailResponse {
+        message: message.to_string(),
+        email,
+    }))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 struct ResendVerificationRequest {
+    #[validate(custom(function = "validate_email"))]
     email: String,
 }
 
+#[derive(Debug, Serialize, ToSchema)]
+struct ResendVerificationResponse {
+    message: String,
+}
+
+/// Send a new verification link to an unverified account
+#[utoipa::path(
+    post,
+    path = "/v1/users/resend-verification",
+    tag = "users",
+    request_body = ResendVerificationRequest,
+    responses(
+        (status = 200, description = "Same response whether or not the account exists", body = ResendVerificationResponse),
+        (status = 400, description = "Invalid email", body = ErrorResponse),
+    )
+)]
 async fn resend_verification_email(
     State(state): State<ApiState>,
-    Json(request): Json<ResendVerificationRequest>,
-) -> Result<Json<serde_json::Value>, ApiError> {
-    // Validate email format
-    auth::validation::validate_email(&request.email)?;
-
+    ValidJson(request): ValidJson<ResendVerificationRequest>,
+) -> Result<Json<ResendVerificationResponse>, ApiError> {
     // Find user by email (only for email auth provider)
     let user = user_repo::find_verification_info_by_email(&state.pool, &request.email).await?;
 
@@ -469,16 +911,32 @@ async fn resend_verification_email(
     }
 
     // Always return success to prevent email enumeration
-    Ok(Json(serde_json::json!({
-        "message": "If an unverified account exists with that email, a verification link has been sent."
-    })))
+    Ok(Json(ResendVerificationResponse {
+        message:
+            "If an unverified account exists with that email, a verification link has been sent."
+                .to_string(),
+    }))
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct DeleteUserResponse {
     message: String,
+    /// When the account and its data are deleted for good, unless restored before
+    purge_after: DateTime<Utc>,
 }
 
+/// Delete the account; it can be restored for 30 days, then it is deleted for good
+#[utoipa::path(
+    delete,
+    path = "/v1/users/me",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    responses(
+        (status = 200, description = "Account deleted; cookies cleared", body = DeleteUserResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
 async fn delete_user(
     auth: AuthUser,
     State(state): State<ApiState>,
@@ -486,16 +944,13 @@ async fn delete_user(
 ) -> Result<(PrivateCookieJar, Json<DeleteUserResponse>), ApiError> {
     let user_id = auth.user_id;
 
-    // Revoke all refresh tokens for this user
-    let _ = auth::refresh_token::revoke_all_user_tokens(&state.pool, user_id).await;
+    // Keep the data for the grace period; the purge job deletes it afterwards
+    let deleted_at = user_repo::soft_delete_user(&state.pool, user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
 
-    // Delete the user - cascade will handle all related data
-    let rows = user_repo::delete_user(&state.pool, user_id).await?;
-
-    // Check if user was actually deleted
-    if rows == 0 {
-        return Err(ApiError::NotFound("User not found".to_string()));
-    }
+    // Sign out every session
+    auth::refresh_token::revoke_all_user_tokens(&state.pool, user_id).await?;
 
     // Clear both auth and refresh token cookies
     let auth_cookie = Cookie::build(("auth_token", "")).path("/").build();
@@ -505,132 +960,455 @@ async fn delete_user(
     Ok((
         jar,
         Json(DeleteUserResponse {
-            message: "Account deleted successfully".to_string(),
+            message: format!(
+                "Account deleted. You can restore it within {ACCOUNT_DELETION_GRACE_DAYS} days."
+            ),
+            purge_after: deleted_at + chrono::Duration::days(ACCOUNT_DELETION_GRACE_DAYS.into()),
         }),
     ))
 }
 
-#[derive(Debug, Deserialize)]
+/// Restore an account deleted less than 30 days ago and sign in
+#[utoipa::path(
+    post,
+    path = "/v1/users/restore",
+    tag = "users",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Account restored and signed in", body = SessionResponse),
+        (status = 400, description = "Captcha required or invalid", body = ErrorResponse),
+        (status = 401, description = "Wrong credentials or unverified email", body = ErrorResponse),
+        (status = 409, description = "The account is not deleted, or its grace period is over", body = ErrorResponse),
+    )
+)]
+async fn restore_account(
+    State(state): State<ApiState>,
+    jar: PrivateCookieJar,
+    ValidJson(request): ValidJson<LoginRequest>,
+) -> Result<(PrivateCookieJar, Json<SessionResponse>), ApiError> {
+    let mut user = verify_credentials(&state, &request).await?;
+
+    if !user_repo::restore_user(&state.pool, user.id, ACCOUNT_DELETION_GRACE_DAYS).await? {
+        return Err(ApiError::Conflict(
+            "This account is not deleted or can no longer be restored".to_string(),
+        ));
+    }
+    user.deleted_at = None;
+    tracing::info!(user_id = %user.id, "Account restored");
+
+    let (jar, session) = start_session(&state, jar, user).await?;
+    Ok((jar, Json(SessionResponse { user: session.user })))
+}
+
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 struct ChangePasswordRequest {
     current_password: String,
+    #[validate(custom(function = "validate_password"))]
     new_password: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct ChangePasswordResponse {
     message: String,
 }
 
+/// Change the password of an email account
+#[utoipa::path(
+    patch,
+    path = "/v1/users/me/password",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    request_body = ChangePasswordRequest,
+    responses(
+        (status = 200, description = "Password changed", body = ChangePasswordResponse),
+        (status = 400, description = "No password yet, unchanged or invalid new password", body = ErrorResponse),
+        (status = 401, description = "Not signed in or wrong current password", body = ErrorResponse),
+    )
+)]
 async fn change_password(
     auth: AuthUser,
     State(state): State<ApiState>,
-    Json(request): Json<ChangePasswordRequest>,
+    ValidJson(request): ValidJson<ChangePasswordRequest>,
 ) -> Result<Json<ChangePasswordResponse>, ApiError> {
-    let user_id = auth.user_id;
-
-    // Get current user data
-    let user_info = user_repo::find_password_info(&state.pool, user_id)
-        .await?
-        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+    service::change_password(
+        &state,
+        auth.user_id,
+        &request.current_password,
+        &request.new_password,
+    )
+    .await?;
 
-    // Ensure this is an email auth user
-    if user_info.auth_provider != "email" {
-        return Err(ApiError::Validation(
-            "Password changes are only available for email authentication users".to_string(),
-        ));
-    }
+    Ok(Json(ChangePasswordResponse {
+        message: "Password changed successfully".to_string(),
+    }))
+}
 
-    // Verify current password
-    let password_hash_value = user_info.password_hash.ok_or_else(|| {
-        ApiError::Auth("Password authentication not available for this account".to_string())
-    })?;
-
-    let current_password = request.current_password.clone();
-    let hash = password_hash_value.clone();
-    let valid = tokio::task::spawn_blocking(move || bcrypt::verify(current_password, &hash))
-        .await
-        .map_err(|_| ApiError::Auth("Verification failed".into()))?
-        .map_err(ApiError::Bcrypt)?;
-    if !valid {
-        return Err(ApiError::Auth("Current password is incorrect".to_string()));
-    }
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+struct SetPasswordRequest {
+    #[validate(custom(function = "validate_password"))]
+    new_password: String,
+}
 
-    // Ensure new password is different from current password
-    if request.current_password == request.new_password {
-        return Err(ApiError::Validation(
-            "New password must be different from current password".to_string(),
-        ));
-    }
+/// Set a first password on an account that signs in with Google only
+///
+/// The account's email address must be verified, so the password belongs to
+/// whoever owns the address. A confirmation is sent to it.
+#[utoipa::path(
+    post,
+    path = "/v1/users/me/password",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    request_body = SetPasswordRequest,
+    responses(
+        (status = 200, description = "Password set; the account can sign in with email and password", body = ChangePasswordResponse),
+        (status = 400, description = "Invalid new password", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Email address not verified", body = ErrorResponse),
+        (status = 409, description = "The account already has a password", body = ErrorResponse),
+    )
+)]
+async fn set_password(
+    auth: AuthUser,
+    State(state): State<ApiState>,
+    ValidJson(request): ValidJson<SetPasswordRequest>,
+) -> Result<Json<ChangePasswordResponse>, ApiError> {
+    service::set_password(&state, auth.user_id, &request.new_password).await?;
 
-    // Validate new password
-    auth::validation::validate_password(&request.new_password)?;
-
-    // Hash the new password (CPU-intensive, run off the async runtime)
-    let new_password = request.new_password.clone();
-    let cost = state.auth.bcrypt_cost;
-    let new_password_hash = tokio::task::spawn_blocking(move || bcrypt::hash(new_password, cost))
-        .await
-        .map_err(|_| ApiError::Auth("Hashing failed".into()))?
-        .map_err(ApiError::Bcrypt)?;
-
-    // Update the password
-    let updated =
-        user_repo::update_password_for_email_user(&state.pool, user_id, &new_password_hash).await?;
-    if !updated {
-        return Err(ApiError::NotFound("User not found".to_string()));
-    }
+    Ok(Json(ChangePasswordResponse {
+        message: "Password set. You can now also sign in with your email and password.".to_string(),
+    }))
+}
 
-    // Send password change confirmation email via background worker
-    if let Some(email_tx) = &state.email_tx {
-        let job = crate::user::email::EmailJob::PasswordChanged {
-            to_email: user_info.email,
-            username: user_info.username,
-        };
+#[derive(Debug, Serialize, ToSchema)]
+struct SignInMethodsResponse {
+    /// Signs in with email and password
+    password: bool,
+    /// Signs in with Google
+    google: bool,
+}
 
-        if let Err(e) = email_tx.send(job) {
-            tracing::error!(error = %e, "Failed to queue password change confirmation email");
-        }
-    }
+/// How the account can sign in, to offer linking and unlinking
+#[utoipa::path(
+    get,
+    path = "/v1/users/me/sign-in-methods",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    responses(
+        (status = 200, description = "Sign-in methods of the account", body = SignInMethodsResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
+async fn get_sign_in_methods(
+    auth: AuthUser,
+    State(state): State<ApiState>,
+) -> Result<Json<SignInMethodsResponse>, ApiError> {
+    let methods = user_repo::find_sign_in_methods(&state.pool, auth.user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
 
-    Ok(Json(ChangePasswordResponse {
-        message: "Password changed successfully".to_string(),
+    Ok(Json(SignInMethodsResponse {
+        password: methods.has_password,
+        google: methods.google_id.is_some(),
     }))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 struct ChangeUsernameRequest {
+    #[validate(custom(function = "validate_username"))]
     username: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct ChangeUsernameResponse {
     message: String,
     username: String,
 }
 
+/// Change the username
+///
+/// The previous username is recorded in the username history and stays
+/// reserved for this account for 30 days, so nobody else can take it over.
+#[utoipa::path(
+    patch,
+    path = "/v1/users/me/username",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    request_body = ChangeUsernameRequest,
+    responses(
+        (status = 200, description = "Username changed", body = ChangeUsernameResponse),
+        (status = 400, description = "Invalid username", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+        (status = 409, description = "Username is taken or reserved", body = ErrorResponse),
+    )
+)]
 async fn change_username(
     auth: AuthUser,
     State(state): State<ApiState>,
-    Json(request): Json<ChangeUsernameRequest>,
+    ValidJson(request): ValidJson<ChangeUsernameRequest>,
 ) -> Result<Json<ChangeUsernameResponse>, ApiError> {
-    let user_id = auth.user_id;
-
-    // Validate username
-    auth::validation::validate_username(&request.username)?;
-
-    // Update the username
-    let username = user_repo::update_username(&state.pool, user_id, &request.username)
-        .await
-        .map_err(|e| {
-            if is_unique_violation(&e) {
-                ApiError::Conflict("Username is already taken".to_string())
-            } else {
-                ApiError::Database(e)
-            }
-        })?;
+    service::change_username(&state.pool, auth.user_id, &request.username).await?;
 
     Ok(Json(ChangeUsernameResponse {
         message: "Username changed successfully".to_string(),
-        username,
+        username: request.username,
+    }))
+}
+
+#[derive(Debug, Deserialize, IntoParams, Validate)]
+struct CheckUsernameParams {
+    /// Username to check
+    #[validate(custom(function = "validate_username"))]
+    name: String,
+}
+
+/// Why a username cannot be used
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+enum UsernameUnavailable {
+    /// An account has it
+    Taken,
+    /// Another account gave it up in the last 30 days
+    Reserved,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct UsernameAvailability {
+    username: String,
+    available: bool,
+    /// Set when the username is not available
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<UsernameUnavailable>,
+}
+
+/// Check whether a username is free to register or change to
+///
+/// Usernames are case-sensitive. A name another account gave up stays
+/// reserved for 30 days.
+#[utoipa::path(
+    get,
+    path = "/v1/users/check-username",
+    tag = "users",
+    params(CheckUsernameParams),
+    responses(
+        (status = 200, description = "Whether the username is available", body = UsernameAvailability),
+        (status = 400, description = "Invalid username", body = ErrorResponse),
+        (status = 429, description = "Too many checks", body = ErrorResponse),
+    )
+)]
+async fn check_username(
+    State(state): State<ApiState>,
+    Query(params): Query<CheckUsernameParams>,
+) -> Result<Json<UsernameAvailability>, ApiError> {
+    params.validate()?;
+
+    let status =
+        user_repo::find_username_status(&state.pool, &params.name, None, USERNAME_RESERVATION_DAYS)
+            .await?;
+    let reason = if status.taken {
+        Some(UsernameUnavailable::Taken)
+    } else if status.reserved {
+        Some(UsernameUnavailable::Reserved)
+    } else {
+        None
+    };
+
+    Ok(Json(UsernameAvailability {
+        username: params.name,
+        available: reason.is_none(),
+        reason,
+    }))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct AvatarSize {
+    /// Width and height in pixels
+    size: u32,
+    /// Path relative to the API origin
+    url: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct AvatarResponse {
+    /// The largest size, now the profile picture
+    profile_picture_url: String,
+    sizes: Vec<AvatarSize>,
+}
+
+/// Upload an avatar, replacing the profile picture
+///
+/// The image is cropped to a centred square and stored at 256 and 64 pixels.
+/// A picture from Google no longer replaces it at sign-in.
+#[utoipa::path(
+    put,
+    path = "/v1/users/me/avatar",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    request_body(content = Vec<u8>, content_type = "application/octet-stream", description = "PNG, JPEG, WebP or GIF image of at least 64×64 pixels"),
+    responses(
+        (status = 200, description = "Avatar stored", body = AvatarResponse),
+        (status = 400, description = "Not a supported image, or too small or too large", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+        (status = 413, description = "Upload larger than MAX_AVATAR_BODY_BYTES", body = ErrorResponse),
+    )
+)]
+async fn upload_avatar(
+    auth: AuthUser,
+    State(state): State<ApiState>,
+    upload: Bytes,
+) -> Result<Json<AvatarResponse>, ApiError> {
+    let avatar_key = service::replace_avatar(&state, auth.user_id, upload).await?;
+
+    let profile_picture_url = avatar::picture_url(&avatar_key);
+    let sizes = avatar::AVATAR_SIZES
+        .iter()
+        .map(|&size| AvatarSize {
+            size,
+            url: media::url(&avatar::object_key(&avatar_key, size)),
+        })
+        .collect();
+    Ok(Json(AvatarResponse {
+        profile_picture_url,
+        sizes,
+    }))
+}
+
+/// Remove the uploaded avatar
+///
+/// The profile picture is cleared; with Google linked, the Google picture
+/// comes back at the next Google sign-in.
+#[utoipa::path(
+    delete,
+    path = "/v1/users/me/avatar",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    responses(
+        (status = 204, description = "Avatar removed"),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "No avatar uploaded", body = ErrorResponse),
+    )
+)]
+async fn delete_avatar(
+    auth: AuthUser,
+    State(state): State<ApiState>,
+) -> Result<StatusCode, ApiError> {
+    service::remove_avatar(&state, auth.user_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+struct ChangeTimezoneRequest {
+    /// IANA timezone name, e.g. `America/New_York`
+    #[validate(length(min = 1, max = 64, message = "Unknown timezone"))]
+    timezone: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct ChangeTimezoneResponse {
+    message: String,
+    timezone: String,
+}
+
+/// Change the timezone that days, streaks and reminders follow
+///
+/// Reviews count towards the local day they happen on from now on; days
+/// already recorded keep their date.
+#[utoipa::path(
+    patch,
+    path = "/v1/users/me/timezone",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    request_body = ChangeTimezoneRequest,
+    responses(
+        (status = 200, description = "Timezone changed", body = ChangeTimezoneResponse),
+        (status = 400, description = "Unknown timezone", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
+async fn change_timezone(
+    auth: AuthUser,
+    State(state): State<ApiState>,
+    ValidJson(request): ValidJson<ChangeTimezoneRequest>,
+) -> Result<Json<ChangeTimezoneResponse>, ApiError> {
+    ApiError::check_fields([(
+        "timezone",
+        validation::validate_timezone(&state.pool, &request.timezone).await,
+    )])?;
+
+    let timezone = user_repo::update_timezone(&state.pool, auth.user_id, &request.timezone)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    Ok(Json(ChangeTimezoneResponse {
+        message: "Timezone changed successfully".to_string(),
+        timezone,
+    }))
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+struct PrivacySettings {
+    /// Leave the user out of every leaderboard, including their friends'
+    leaderboard_opt_out: bool,
+}
+
+/// The signed-in user's privacy settings
+#[utoipa::path(
+    get,
+    path = "/v1/users/me/privacy",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    responses(
+        (status = 200, description = "Privacy settings", body = PrivacySettings),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
+async fn get_privacy(
+    auth: AuthUser,
+    State(state): State<ApiState>,
+) -> Result<Json<PrivacySettings>, ApiError> {
+    let leaderboard_opt_out = user_repo::get_leaderboard_opt_out(&state.pool, auth.user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    Ok(Json(PrivacySettings {
+        leaderboard_opt_out,
+    }))
+}
+
+/// Change the signed-in user's privacy settings
+///
+/// Opting out of leaderboards takes effect immediately.
+#[utoipa::path(
+    patch,
+    path = "/v1/users/me/privacy",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    request_body = PrivacySettings,
+    responses(
+        (status = 200, description = "Privacy settings changed", body = PrivacySettings),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
+async fn update_privacy(
+    auth: AuthUser,
+    State(state): State<ApiState>,
+    ValidJson(request): ValidJson<PrivacySettings>,
+) -> Result<Json<PrivacySettings>, ApiError> {
+    let leaderboard_opt_out = user_repo::update_leaderboard_opt_out(
+        &state.pool,
+        auth.user_id,
+        request.leaderboard_opt_out,
+    )
+    .await?
+    .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    Ok(Json(PrivacySettings {
+        leaderboard_opt_out,
     }))
 }
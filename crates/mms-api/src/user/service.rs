@@ -0,0 +1,206 @@
+//! Account changes behind the `/users/me` endpoints.
+//!
+//! Each change has its own function, which checks what the change needs and
+//! returns the error the client sees. The handlers in [`super::routes`] only
+//! read requests and shape responses.
+
+use axum::body::Bytes;
+use sqlx::PgPool;
+use sqlx::types::Uuid;
+
+use mms_db::models::UserPasswordInfo;
+use mms_db::repositories::user as user_repo;
+
+use crate::{
+    ApiState,
+    error::ApiError,
+    user::{USERNAME_RESERVATION_DAYS, avatar, email::EmailJob},
+};
+
+/// Check if a SQLx error is a PostgreSQL unique constraint violation (error code 23505).
+pub(super) fn is_unique_violation(e: &sqlx::Error) -> bool {
+    if let sqlx::Error::Database(db_err) = e {
+        db_err.code().as_deref() == Some("23505")
+    } else {
+        false
+    }
+}
+
+/// Hash a password (CPU-intensive, run off the async runtime)
+pub async fn hash_password(password: &str, cost: u32) -> Result<String, ApiError> {
+    let password = password.to_string();
+    tokio::task::spawn_blocking(move || bcrypt::hash(password, cost))
+        .await
+        .map_err(|_| ApiError::Auth("Hashing failed".into()))?
+        .map_err(ApiError::Bcrypt)
+}
+
+/// Change the password of an account that has one, after checking the current one
+pub async fn change_password(
+    state: &ApiState,
+    user_id: Uuid,
+    current_password: &str,
+    new_password: &str,
+) -> Result<(), ApiError> {
+    let user_info = user_repo::find_password_info(&state.pool, user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    // Accounts that sign in with Google only set a first password instead
+    let hash = user_info.password_hash.clone().ok_or_else(|| {
+        ApiError::Validation(
+            "This account has no password yet. Set one with POST /v1/users/me/password."
+                .to_string(),
+        )
+    })?;
+
+    let current = current_password.to_string();
+    let valid = tokio::task::spawn_blocking(move || bcrypt::verify(current, &hash))
+        .await
+        .map_err(|_| ApiError::Auth("Verification failed".into()))?
+        .map_err(ApiError::Bcrypt)?;
+    if !valid {
+        return Err(ApiError::Auth("Current password is incorrect".to_string()));
+    }
+
+    if current_password == new_password {
+        return Err(ApiError::field(
+            "new_password",
+            "New password must be different from current password",
+        ));
+    }
+
+    let new_password_hash = hash_password(new_password, state.auth.bcrypt_cost).await?;
+    if !user_repo::update_password(&state.pool, user_id, &new_password_hash).await? {
+        return Err(ApiError::NotFound("User not found".to_string()));
+    }
+
+    queue_password_changed_email(state, user_info);
+    Ok(())
+}
+
+/// Set a first password on an account that signs in with Google only.
+///
+/// The email address must be verified, so the password belongs to whoever
+/// owns the address.
+pub async fn set_password(
+    state: &ApiState,
+    user_id: Uuid,
+    new_password: &str,
+) -> Result<(), ApiError> {
+    let user_info = user_repo::find_password_info(&state.pool, user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    if user_info.password_hash.is_some() {
+        return Err(ApiError::Conflict(
+            "This account already has a password. Change it with PATCH /v1/users/me/password."
+                .to_string(),
+        ));
+    }
+    if !user_info.email_verified {
+        return Err(ApiError::Forbidden(
+            "Verify your email address before setting a password".to_string(),
+        ));
+    }
+
+    let password_hash = hash_password(new_password, state.auth.bcrypt_cost).await?;
+    // Only sets the password if none was set meanwhile
+    if !user_repo::set_initial_password(&state.pool, user_id, &password_hash).await? {
+        return Err(ApiError::Conflict(
+            "This account already has a password".to_string(),
+        ));
+    }
+
+    // Tell the owner of the address, in case it was not them
+    queue_password_changed_email(state, user_info);
+    Ok(())
+}
+
+/// Send the password change confirmation via the background worker
+fn queue_password_changed_email(state: &ApiState, user_info: UserPasswordInfo) {
+    if let Some(email_tx) = &state.email_tx {
+        let job = EmailJob::PasswordChanged {
+            to_email: user_info.email,
+            username: user_info.username,
+        };
+
+        if let Err(e) = email_tx.send(job) {
+            tracing::error!(error = %e, "Failed to queue password change confirmation email");
+        }
+    }
+}
+
+/// Change the username and record the change in the username history.
+///
+/// Names other accounts gave up in the last [`USERNAME_RESERVATION_DAYS`] are
+/// refused; the account that gave one up can take it back.
+pub async fn change_username(pool: &PgPool, user_id: Uuid, username: &str) -> Result<(), ApiError> {
+    let mut tx = pool.begin().await?;
+
+    let status = user_repo::find_username_status(
+        &mut *tx,
+        username,
+        Some(user_id),
+        USERNAME_RESERVATION_DAYS,
+    )
+    .await?;
+    if status.reserved {
+        return Err(ApiError::Conflict(
+            "This username was recently used by another account".to_string(),
+        ));
+    }
+
+    let previous = user_repo::update_username(&mut *tx, user_id, username)
+        .await
+        .map_err(|e| {
+            if is_unique_violation(&e) {
+                ApiError::Conflict("Username is already taken".to_string())
+            } else {
+                ApiError::Database(e)
+            }
+        })?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    if previous != username {
+        user_repo::record_username_change(&mut *tx, user_id, &previous, username).await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Resize and store an uploaded avatar, make it the profile picture and delete
+/// the previous one. Returns the new avatar's key.
+pub async fn replace_avatar(
+    state: &ApiState,
+    user_id: Uuid,
+    upload: Bytes,
+) -> Result<String, ApiError> {
+    // Decoding and resizing is CPU-intensive, run it off the async runtime
+    let resized = tokio::task::spawn_blocking(move || avatar::resize(&upload))
+        .await
+        .map_err(|_| ApiError::Media("Avatar processing failed".into()))??;
+
+    let avatar_key = avatar::key(user_id, Uuid::new_v4());
+    avatar::store(state.media.as_ref(), &avatar_key, resized).await?;
+
+    let profile_picture_url = avatar::picture_url(&avatar_key);
+    let Some(previous) =
+        user_repo::set_avatar(&state.pool, user_id, &avatar_key, &profile_picture_url).await?
+    else {
+        avatar::delete(state.media.as_ref(), &avatar_key).await?;
+        return Err(ApiError::NotFound("User not found".to_string()));
+    };
+    if let Some(previous) = previous {
+        avatar::delete(state.media.as_ref(), &previous).await?;
+    }
+    Ok(avatar_key)
+}
+
+/// Clear the uploaded avatar and delete its images
+pub async fn remove_avatar(state: &ApiState, user_id: Uuid) -> Result<(), ApiError> {
+    let avatar_key = user_repo::clear_avatar(&state.pool, user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("No avatar uploaded".to_string()))?;
+    avatar::delete(state.media.as_ref(), &avatar_key).await
+}
@@ -1,14 +1,47 @@
 use axum::Router;
 
-use crate::{auth, deck, practice, roadmap, state::ApiState, user};
+use crate::{
+    achievements, admin, auth, calendar, client_errors, deck, email_preferences, flashcards, goals,
+    graphql, groups, home, known_words, leaderboards, live, mailer, media, notifications, openapi,
+    plans, practice, preferences, profile, public_api, reminders, reports, roadmap,
+    state::ApiState, stats, sync, user, versioning::ApiVersion, vocabulary, widgets, xp,
+};
 
 /// V1 API routes
 pub fn routes() -> Router<ApiState> {
     Router::new()
-        .merge(user::routes())
+        .merge(user::routes(ApiVersion::V1))
         .merge(deck::routes())
-        .merge(auth::routes())
+        .merge(achievements::routes())
+        .merge(auth::routes(ApiVersion::V1))
         .merge(auth::google::routes())
+        .merge(calendar::routes())
+        .merge(client_errors::routes())
+        .merge(email_preferences::routes())
+        .merge(flashcards::routes())
+        .merge(goals::routes())
+        .merge(graphql::routes())
+        .merge(groups::routes())
+        .merge(home::routes())
+        .merge(known_words::routes())
+        .merge(leaderboards::routes())
+        .merge(live::routes())
+        .merge(mailer::webhooks::routes())
+        .merge(media::routes())
+        .merge(notifications::routes())
+        .merge(plans::routes())
         .merge(roadmap::routes())
         .merge(practice::routes())
+        .merge(preferences::routes())
+        .merge(profile::routes())
+        .merge(public_api::routes())
+        .merge(reminders::routes())
+        .merge(reports::routes())
+        .merge(stats::routes())
+        .merge(sync::routes())
+        .merge(vocabulary::routes())
+        .merge(widgets::routes())
+        .merge(xp::routes())
+        .merge(admin::routes())
+        .merge(openapi::routes())
 }
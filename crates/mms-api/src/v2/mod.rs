@@ -0,0 +1,48 @@
+use axum::Router;
+
+use crate::{
+    achievements, admin, auth, calendar, client_errors, deck, email_preferences, goals, graphql,
+    groups, home, known_words, leaderboards, live, mailer, media, notifications, plans, practice,
+    preferences, profile, public_api, reminders, reports, roadmap, state::ApiState, stats, sync,
+    user, versioning::ApiVersion, vocabulary, widgets, xp,
+};
+
+/// V2 API routes
+///
+/// Sessions live in cookies only: login and refresh no longer return tokens in
+/// the body. Everything else is shared with v1.
+pub fn routes() -> Router<ApiState> {
+    Router::new()
+        .merge(user::routes(ApiVersion::V2))
+        .merge(deck::routes())
+        .merge(achievements::routes())
+        .merge(auth::routes(ApiVersion::V2))
+        .merge(auth::google::routes())
+        .merge(calendar::routes())
+        .merge(client_errors::routes())
+        .merge(email_preferences::routes())
+        .merge(goals::routes())
+        .merge(graphql::routes())
+        .merge(groups::routes())
+        .merge(home::routes())
+        .merge(known_words::routes())
+        .merge(leaderboards::routes())
+        .merge(live::routes())
+        .merge(mailer::webhooks::routes())
+        .merge(media::routes())
+        .merge(notifications::routes())
+        .merge(plans::routes())
+        .merge(roadmap::routes())
+        .merge(practice::routes())
+        .merge(preferences::routes())
+        .merge(profile::routes())
+        .merge(public_api::routes())
+        .merge(reminders::routes())
+        .merge(reports::routes())
+        .merge(stats::routes())
+        .merge(sync::routes())
+        .merge(vocabulary::routes())
+        .merge(widgets::routes())
+        .merge(xp::routes())
+        .merge(admin::routes())
+}
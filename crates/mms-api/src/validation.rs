@@ -1,4 +1,88 @@
-use crate::error::ApiError;
+//! Request validation.
+//!
+//! JSON request types derive [`Validate`] and declare their constraints on
+//! their fields; handlers take them through [`ValidJson`], which rejects the
+//! request with every failing field listed before the handler runs. Checks
+//! that need the database, such as [`validate_timezone`], stay in handlers.
+
+use std::borrow::Cow;
+
+use axum::{
+    Json,
+    extract::{FromRequest, Request},
+    response::{IntoResponse, Response},
+};
+use serde::de::DeserializeOwned;
+use sqlx::PgPool;
+use validator::{Validate, ValidationError, ValidationErrors, ValidationErrorsKind};
+
+use mms_db::repositories::user as user_repo;
+
+use crate::error::{ApiError, FieldError};
+
+/// A JSON body that passed the constraints its type declares
+pub struct ValidJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(request, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        value
+            .validate()
+            .map_err(|errors| ApiError::from(errors).into_response())?;
+        Ok(Self(value))
+    }
+}
+
+impl From<ValidationErrors> for ApiError {
+    fn from(errors: ValidationErrors) -> Self {
+        let mut fields = Vec::new();
+        collect_field_errors(&errors, "", &mut fields);
+        // The errors come from a map; keep the response stable
+        fields.sort_by(|a, b| a.field.cmp(&b.field));
+        ApiError::InvalidFields(fields)
+    }
+}
+
+/// Flatten nested errors into paths such as `progress[2].flashcard_id`
+fn collect_field_errors(errors: &ValidationErrors, prefix: &str, out: &mut Vec<FieldError>) {
+    for (field, kind) in errors.errors() {
+        let path = if prefix.is_empty() {
+            field.to_string()
+        } else {
+            format!("{prefix}.{field}")
+        };
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                out.extend(errors.iter().map(|error| {
+                    let message = error
+                        .message
+                        .clone()
+                        .unwrap_or_else(|| Cow::Owned(format!("Invalid {path}")));
+                    FieldError::new(path.clone(), message)
+                }));
+            }
+            ValidationErrorsKind::Struct(errors) => collect_field_errors(errors, &path, out),
+            ValidationErrorsKind::List(items) => {
+                for (index, errors) in items {
+                    collect_field_errors(errors, &format!("{path}[{index}]"), out);
+                }
+            }
+        }
+    }
+}
+
+/// A validation error with the message clients are shown
+pub fn invalid(code: &'static str, message: impl Into<Cow<'static, str>>) -> ValidationError {
+    ValidationError::new(code).with_message(message.into())
+}
 
 /// ISO 639-1 language codes
 const VALID_LANGUAGE_CODES: &[&str] = &[
@@ -18,19 +102,68 @@ const VALID_LANGUAGE_CODES: &[&str] = &[
 /// assert!(validate_language_code("invalid").is_err());
 /// ```
 pub fn validate_language_code(code: &str) -> Result<(), ApiError> {
+    language_code(code)
+        .map_err(|error| ApiError::Validation(error.message.unwrap_or_default().into_owned()))
+}
+
+/// Check an optional language pair filter; both languages or neither must be given
+pub fn language_pair<'a>(
+    language_from: Option<&'a str>,
+    language_to: Option<&'a str>,
+) -> Result<Option<(&'a str, &'a str)>, ApiError> {
+    match (language_from, language_to) {
+        (Some(language_from), Some(language_to)) => {
+            ApiError::check_fields([
+                ("language_from", validate_language_code(language_from)),
+                ("language_to", validate_language_code(language_to)),
+            ])?;
+            Ok(Some((language_from, language_to)))
+        }
+        (None, None) => Ok(None),
+        _ => Err(ApiError::Validation(
+            "language_from and language_to must be given together".to_string(),
+        )),
+    }
+}
+
+/// Language code constraint for request types: `#[validate(custom(function = "language_code"))]`
+pub fn language_code(code: &str) -> Result<(), ValidationError> {
     if code.is_empty() {
-        return Err(ApiError::Validation(
-            "Language code cannot be empty".to_string(),
-        ));
+        return Err(invalid("language_code", "Language code cannot be empty"));
     }
 
     // Normalize to lowercase for comparison
     let normalized = code.to_lowercase();
 
     if !VALID_LANGUAGE_CODES.contains(&normalized.as_str()) {
+        return Err(invalid(
+            "language_code",
+            format!(
+                "Invalid language code: '{}'. Must be a valid ISO 639-1 code (e.g., 'en', 'es', 'fr')",
+                code
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Constraint for text that needs more than whitespace: `#[validate(custom(function = "not_blank"))]`
+pub fn not_blank(text: &str) -> Result<(), ValidationError> {
+    if text.trim().is_empty() {
+        return Err(invalid("not_blank", "Cannot be empty"));
+    }
+    Ok(())
+}
+
+/// Validate an IANA timezone name such as `Europe/Madrid` against the zones Postgres knows
+pub async fn validate_timezone(pool: &PgPool, timezone: &str) -> Result<(), ApiError> {
+    if timezone.is_empty()
+        || timezone.len() > 64
+        || !user_repo::timezone_exists(pool, timezone).await?
+    {
         return Err(ApiError::Validation(format!(
-            "Invalid language code: '{}'. Must be a valid ISO 639-1 code (e.g., 'en', 'es', 'fr')",
-            code
+            "Unknown timezone: {timezone}"
         )));
     }
 
@@ -55,4 +188,37 @@ mod tests {
         assert!(validate_language_code("invalid").is_err());
         assert!(validate_language_code("123").is_err());
     }
+
+    #[derive(Debug, Validate)]
+    struct Pair {
+        #[validate(custom(function = "language_code"))]
+        language: String,
+        #[validate(nested)]
+        items: Vec<Item>,
+    }
+
+    #[derive(Debug, Validate)]
+    struct Item {
+        #[validate(range(min = 1, message = "Count must be positive"))]
+        count: i32,
+    }
+
+    #[test]
+    fn test_errors_name_every_failing_field() {
+        let pair = Pair {
+            language: "xx".to_string(),
+            items: vec![Item { count: 1 }, Item { count: 0 }],
+        };
+        let ApiError::InvalidFields(fields) = ApiError::from(pair.validate().unwrap_err()) else {
+            panic!("expected field errors");
+        };
+        let fields: Vec<_> = fields
+            .iter()
+            .map(|error| (error.field.as_str(), error.message.as_str()))
+            .collect();
+        assert_eq!(fields[0].0, "items[1].count");
+        assert_eq!(fields[0].1, "Count must be positive");
+        assert_eq!(fields[1].0, "language");
+        assert!(fields[1].1.starts_with("Invalid language code: 'xx'"));
+    }
 }
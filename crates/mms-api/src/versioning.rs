@@ -0,0 +1,179 @@
+//! API version registry.
+//!
+//! Every version in [`ApiVersion::ALL`] is mounted under its own prefix. Feature
+//! modules serve all versions from the same handlers unless a route is
+//! overridden: modules whose routes differ between versions take an
+//! [`ApiVersion`] in their `routes()` and pick the handler per version, and a
+//! version's `routes()` (`v1`, `v2`) decides which modules it includes.
+//!
+//! Deprecated routes announce it with a `Deprecation` header (RFC 9745), plus
+//! `Sunset` (RFC 8594) once a removal date is set and a `Link` to the
+//! replacement. Wrap a single route with [`deprecated`], or return a
+//! [`Deprecation`] from [`ApiVersion::deprecation`] to mark a whole version.
+
+use axum::{
+    Router,
+    http::{HeaderName, HeaderValue, header},
+    middleware::map_response,
+    response::Response,
+    routing::MethodRouter,
+};
+use chrono::DateTime;
+
+use crate::{ApiState, v1, v2};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiVersion {
+    V1,
+    /// Cookie-only sessions: tokens are no longer echoed in response bodies
+    V2,
+}
+
+impl ApiVersion {
+    /// Every mounted version, oldest first
+    pub const ALL: [Self; 2] = [Self::V1, Self::V2];
+
+    #[must_use]
+    pub const fn prefix(self) -> &'static str {
+        match self {
+            Self::V1 => "/v1",
+            Self::V2 => "/v2",
+        }
+    }
+
+    /// Set when the whole version is deprecated; applies to every route under its prefix
+    #[must_use]
+    pub const fn deprecation(self) -> Option<Deprecation> {
+        match self {
+            Self::V1 | Self::V2 => None,
+        }
+    }
+
+    fn routes(self) -> Router<ApiState> {
+        let routes = match self {
+            Self::V1 => v1::routes(),
+            Self::V2 => v2::routes(),
+        };
+        match self.deprecation() {
+            Some(deprecation) => routes.layer(map_response(move |response| {
+                deprecation_headers(deprecation, response)
+            })),
+            None => routes,
+        }
+    }
+}
+
+/// Remove the version prefix from a request path, if it has one
+#[must_use]
+pub fn strip_version_prefix(path: &str) -> Option<&str> {
+    ApiVersion::ALL.iter().find_map(|version| {
+        path.strip_prefix(version.prefix())
+            .filter(|rest| rest.is_empty() || rest.starts_with('/'))
+    })
+}
+
+/// Mount every API version under its prefix
+pub fn routes() -> Router<ApiState> {
+    ApiVersion::ALL
+        .into_iter()
+        .fold(Router::new(), |router, version| {
+            router.nest(version.prefix(), version.routes())
+        })
+}
+
+/// When and how a route is being retired
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deprecation {
+    /// Unix timestamp from which the route is deprecated
+    pub since: i64,
+    /// Unix timestamp after which the route may be removed
+    pub sunset: Option<i64>,
+    /// Path of the replacement, sent as `Link: <...>; rel="successor-version"`
+    pub successor: Option<&'static str>,
+}
+
+/// Mark a route as deprecated
+pub fn deprecated<S>(route: MethodRouter<S>, deprecation: Deprecation) -> MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    route.layer(map_response(move |response| {
+        deprecation_headers(deprecation, response)
+    }))
+}
+
+async fn deprecation_headers(deprecation: Deprecation, mut response: Response) -> Response {
+    let headers = response.headers_mut();
+
+    if let Ok(value) = HeaderValue::from_str(&format!("@{}", deprecation.since)) {
+        headers.insert(HeaderName::from_static("deprecation"), value);
+    }
+    if let Some(sunset) = deprecation
+        .sunset
+        .and_then(|ts| DateTime::from_timestamp(ts, 0))
+        && let Ok(value) =
+            HeaderValue::from_str(&sunset.format("%a, %d %b %Y %H:%M:%S GMT").to_string())
+    {
+        headers.insert(HeaderName::from_static("sunset"), value);
+    }
+    if let Some(successor) = deprecation.successor
+        && let Ok(value) =
+            HeaderValue::from_str(&format!("<{successor}>; rel=\"successor-version\""))
+    {
+        headers.append(header::LINK, value);
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request, routing::get};
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_strip_version_prefix() {
+        assert_eq!(
+            strip_version_prefix("/v1/users/login"),
+            Some("/users/login")
+        );
+        assert_eq!(strip_version_prefix("/v2/roadmaps"), Some("/roadmaps"));
+        assert_eq!(strip_version_prefix("/v2"), Some(""));
+        assert_eq!(strip_version_prefix("/v10/roadmaps"), None);
+        assert_eq!(strip_version_prefix("/health"), None);
+    }
+
+    #[tokio::test]
+    async fn test_deprecated_route_headers() {
+        let deprecation = Deprecation {
+            // 2026-10-17T00:00:00Z
+            since: 1_792_195_200,
+            // 2027-04-17T00:00:00Z
+            sunset: Some(1_807_920_000),
+            successor: Some("/v2/old"),
+        };
+        let app: Router = Router::new()
+            .route("/old", deprecated(get(|| async { "old" }), deprecation))
+            .route("/new", get(|| async { "new" }));
+
+        let response = app
+            .clone()
+            .oneshot(Request::get("/old").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let headers = response.headers();
+        assert_eq!(headers["deprecation"], "@1792195200");
+        assert_eq!(headers["sunset"], "Sat, 17 Apr 2027 00:00:00 GMT");
+        assert_eq!(
+            headers[header::LINK],
+            "</v2/old>; rel=\"successor-version\""
+        );
+
+        let response = app
+            .oneshot(Request::get("/new").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert!(!response.headers().contains_key("deprecation"));
+    }
+}
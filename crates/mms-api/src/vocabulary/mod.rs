@@ -0,0 +1,74 @@
+//! Reverse dictionary over the cards a learner has studied.
+//!
+//! Lets a learner check "have I learned this word, and how well?" by
+//! searching both sides of every card they have progress on. Matching uses
+//! [`normalize_for_comparison`], so accents, case and punctuation are ignored
+//! just as when answers are checked.
+
+pub mod routes;
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::normalization::normalize_for_comparison;
+
+pub use routes::routes;
+
+/// Side of a card a search matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchedSide {
+    Term,
+    Translation,
+}
+
+/// How closely a normalized text matches a normalized query; lower is closer
+fn rank(text: &str, query: &str) -> Option<u8> {
+    if text == query {
+        Some(0)
+    } else if text.split(' ').any(|word| word == query) {
+        Some(1)
+    } else if text.starts_with(query) {
+        Some(2)
+    } else if text.contains(query) {
+        Some(3)
+    } else {
+        None
+    }
+}
+
+/// Rank of the closer side of a card for `query`, which must already be normalized.
+///
+/// The term wins ties, so a card found on both sides reports its term.
+pub fn match_card(term: &str, translation: &str, query: &str) -> Option<(u8, MatchedSide)> {
+    let term = rank(&normalize_for_comparison(term), query).map(|r| (r, MatchedSide::Term));
+    let translation =
+        rank(&normalize_for_comparison(translation), query).map(|r| (r, MatchedSide::Translation));
+    match (term, translation) {
+        (Some(t), Some(tr)) => Some(if tr.0 < t.0 { tr } else { t }),
+        (t, tr) => t.or(tr),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_card_ranks_both_sides() {
+        let query = normalize_for_comparison("Café");
+        assert_eq!(
+            match_card("coffee", "el café", &query),
+            Some((1, MatchedSide::Translation))
+        );
+        assert_eq!(
+            match_card("cafe", "el café", &query),
+            Some((0, MatchedSide::Term))
+        );
+        assert_eq!(
+            match_card("cafeteria", "la cafetería", &query),
+            Some((2, MatchedSide::Term))
+        );
+        assert_eq!(match_card("tea", "el té", &query), None);
+    }
+}
@@ -0,0 +1,135 @@
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    routing::get,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    ApiState,
+    auth::{AuthUser, require_self},
+    error::{ApiError, ErrorResponse},
+    normalization::normalize_for_comparison,
+    vocabulary::{MatchedSide, match_card},
+};
+
+use mms_db::repositories::vocabulary as vocabulary_repo;
+
+/// Longest query accepted
+const MAX_QUERY_CHARS: usize = 100;
+
+/// Matches returned when no limit is given
+const DEFAULT_LIMIT: usize = 20;
+
+/// Matches returned at most
+const MAX_LIMIT: usize = 100;
+
+/// Create the vocabulary search routes
+pub fn routes() -> Router<ApiState> {
+    Router::new().route("/users/{user_id}/vocabulary/search", get(search_vocabulary))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct SearchQuery {
+    /// Word or phrase in either language; accents and case are ignored
+    q: String,
+    /// Matches returned, 1 to 100 (default 20)
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct VocabularyMatch {
+    flashcard_id: Uuid,
+    term: String,
+    translation: String,
+    language_from: String,
+    language_to: String,
+    /// Side of the card the query was found on
+    matched: MatchedSide,
+    /// Correct minus wrong answers
+    score: i32,
+    /// Whether `score` has reached the mastery threshold
+    mastered: bool,
+    times_correct: i32,
+    times_wrong: i32,
+    next_review_at: DateTime<Utc>,
+    last_review_at: Option<DateTime<Utc>>,
+}
+
+/// Search the cards the user has studied, in both directions
+///
+/// Exact matches come first, then whole-word, prefix and substring matches;
+/// within each, the most recently reviewed cards come first. Cards never
+/// started are not searched.
+#[utoipa::path(
+    get,
+    path = "/v1/users/{user_id}/vocabulary/search",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    params(
+        ("user_id" = Uuid, Path, description = "Must be the signed-in user"),
+        SearchQuery,
+    ),
+    responses(
+        (status = 200, description = "Matching studied cards, closest first", body = Vec<VocabularyMatch>),
+        (status = 400, description = "Empty or overlong query", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Another user's vocabulary", body = ErrorResponse),
+    )
+)]
+async fn search_vocabulary(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(user_id): Path<Uuid>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<Vec<VocabularyMatch>>, ApiError> {
+    require_self(&auth_user, user_id)?;
+    if query.q.chars().count() > MAX_QUERY_CHARS {
+        return Err(ApiError::Validation(format!(
+            "Query must be at most {MAX_QUERY_CHARS} characters"
+        )));
+    }
+    let needle = normalize_for_comparison(&query.q);
+    if needle.is_empty() {
+        return Err(ApiError::Validation(
+            "Query must contain a letter or digit".to_string(),
+        ));
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let cards = vocabulary_repo::list_studied_cards(&state.pool, user_id).await?;
+    let mut matches: Vec<_> = cards
+        .into_iter()
+        .filter_map(|card| {
+            match_card(&card.term, &card.translation, &needle).map(|found| (found, card))
+        })
+        .collect();
+    // Stable, so cards keep their most-recently-reviewed order within a rank
+    matches.sort_by_key(|((rank, _), _)| *rank);
+
+    Ok(Json(
+        matches
+            .into_iter()
+            .take(limit)
+            .map(|((_, matched), card)| VocabularyMatch {
+                flashcard_id: card.flashcard_id,
+                term: card.term,
+                translation: card.translation,
+                language_from: card.language_from,
+                language_to: card.language_to,
+                matched,
+                score: mms_srs::calculate_score(card.times_correct, card.times_wrong),
+                mastered: mms_srs::is_mastered(card.times_correct, card.times_wrong),
+                times_correct: card.times_correct,
+                times_wrong: card.times_wrong,
+                next_review_at: card.next_review_at,
+                last_review_at: card.last_review_at,
+            })
+            .collect(),
+    ))
+}
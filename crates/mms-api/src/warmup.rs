@@ -0,0 +1,81 @@
+//! Startup warm-up run before the server starts accepting traffic.
+//!
+//! Right after a deploy the first requests used to pay for opening database
+//! connections, preparing statements and compiling regexes. Warming up moves that
+//! cost before the listener is bound, so the instance only reports ready once it
+//! can serve at full speed.
+
+use std::time::Instant;
+
+use sqlx::{PgPool, Postgres, pool::PoolConnection};
+use tokio::task::JoinSet;
+
+use crate::{metrics, roadmap::routes::DEFAULT_PAGE_LIMIT, state::ApiState};
+
+use mms_db::repositories::roadmap as roadmap_repo;
+use mms_db::tenancy::Tenant;
+
+/// Warm up the instance. Failures are logged and never abort startup.
+pub async fn warm_up(state: &ApiState) {
+    let start = Instant::now();
+
+    metrics::precompile_patterns();
+
+    let connections = warm_up_pool(&state.pool).await;
+
+    tracing::info!(
+        connections,
+        elapsed_ms = start.elapsed().as_millis() as u64,
+        "Warm-up complete"
+    );
+}
+
+/// Open the whole pool and prime each connection with the hot public queries.
+///
+/// sqlx caches prepared statements per connection, so running the queries on
+/// every connection means no request has to pay for statement preparation. The
+/// reads also pull the roadmap and deck pages into Postgres' buffer cache.
+///
+/// Returns the number of connections that were warmed.
+async fn warm_up_pool(pool: &PgPool) -> usize {
+    let max = pool.options().get_max_connections() as usize;
+
+    // Acquire all connections concurrently and hold them, otherwise the pool would
+    // hand the same idle connection back every time
+    let mut acquiring = JoinSet::new();
+    for _ in 0..max {
+        let pool = pool.clone();
+        acquiring.spawn(async move { pool.acquire().await });
+    }
+
+    let mut connections: Vec<PoolConnection<Postgres>> = Vec::with_capacity(max);
+    while let Some(result) = acquiring.join_next().await {
+        match result {
+            Ok(Ok(conn)) => connections.push(conn),
+            Ok(Err(e)) => tracing::warn!(error = %e, "Warm-up failed to acquire a connection"),
+            Err(e) => tracing::warn!(error = %e, "Warm-up acquire task failed"),
+        }
+    }
+
+    for conn in &mut connections {
+        if let Err(e) = prime_connection(conn).await {
+            tracing::warn!(error = %e, "Warm-up query failed");
+        }
+    }
+
+    // Dropping returns the connections to the pool as idle
+    connections.len()
+}
+
+async fn prime_connection(conn: &mut PoolConnection<Postgres>) -> Result<(), sqlx::Error> {
+    let roadmaps =
+        roadmap_repo::list_all(&mut **conn, Tenant::Public, DEFAULT_PAGE_LIMIT, 0).await?;
+
+    // Newest roadmaps are the ones the landing page shows first
+    if let Some(roadmap) = roadmaps.first() {
+        roadmap_repo::get_metadata(&mut **conn, Tenant::Public, roadmap.id).await?;
+        roadmap_repo::get_nodes(&mut **conn, Tenant::Public, roadmap.id).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,90 @@
+//! Flat two-part SVG badges in the style of shields.io.
+
+/// Grey background of the label half
+const LABEL_COLOR: &str = "#555";
+
+/// Approximate advance of one character at 11px Verdana
+const CHAR_WIDTH: usize = 7;
+
+/// Horizontal padding around each half's text
+const PADDING: usize = 10;
+
+/// A badge reading `label | value`
+#[derive(Debug)]
+pub struct Badge<'a> {
+    pub label: &'a str,
+    pub value: &'a str,
+    /// Background of the value half, as a CSS color
+    pub color: &'a str,
+}
+
+impl Badge<'_> {
+    /// Render the badge as a standalone SVG document
+    pub fn render(&self) -> String {
+        let label_width = text_width(self.label);
+        let value_width = text_width(self.value);
+        let width = label_width + value_width;
+        let label_x = label_width / 2;
+        let value_x = label_width + value_width / 2;
+        let label = escape(self.label);
+        let value = escape(self.value);
+        let color = escape(self.color);
+
+        format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {value}"><title>{label}: {value}</title><linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient><clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath><g clip-path="url(#r)"><rect width="{label_width}" height="20" fill="{LABEL_COLOR}"/><rect x="{label_width}" width="{value_width}" height="20" fill="{color}"/><rect width="{width}" height="20" fill="url(#s)"/></g><g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11"><text x="{label_x}" y="14">{label}</text><text x="{value_x}" y="14">{value}</text></g></svg>"##
+        )
+    }
+}
+
+fn text_width(text: &str) -> usize {
+    text.chars().count() * CHAR_WIDTH + 2 * PADDING
+}
+
+/// Escape text for use in SVG element content and attribute values
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_sizes_halves_to_their_text() {
+        let svg = Badge {
+            label: "streak",
+            value: "12 days",
+            color: "#4c1",
+        }
+        .render();
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"131\""));
+        assert!(svg.contains("<rect width=\"62\" height=\"20\" fill=\"#555\"/>"));
+        assert!(svg.contains("<rect x=\"62\" width=\"69\" height=\"20\" fill=\"#4c1\"/>"));
+        assert!(svg.contains("<title>streak: 12 days</title>"));
+    }
+
+    #[test]
+    fn test_render_escapes_text() {
+        let svg = Badge {
+            label: "<b>",
+            value: "a & \"b\"",
+            color: "red",
+        }
+        .render();
+
+        assert!(svg.contains("<title>&lt;b&gt;: a &amp; &quot;b&quot;</title>"));
+        assert!(!svg.contains("<b>"));
+    }
+}
@@ -0,0 +1,11 @@
+//! Embeddable badges of a user's streak and learned cards, for blogs and profile pages.
+//!
+//! Widgets are fetched by browsers on other sites and by image proxies, so they
+//! are authorised by a signed token in the URL (see [`token`]) rather than
+//! cookies, and are cached publicly for an hour.
+
+pub mod badge;
+pub mod routes;
+pub mod token;
+
+pub use routes::routes;
@@ -0,0 +1,255 @@
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::header,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{
+    ApiState,
+    auth::AuthUser,
+    error::{ApiError, ErrorResponse},
+    widgets::{badge::Badge, token},
+};
+
+use mms_db::models::WidgetStats;
+use mms_db::repositories::user as user_repo;
+
+/// How long browsers and image proxies may reuse a widget; streaks move at most once a day
+const WIDGET_CACHE_CONTROL: &str = "public, max-age=3600, stale-while-revalidate=86400";
+
+/// Badge color while the streak is alive
+const ACTIVE_COLOR: &str = "#4c1";
+
+/// Badge color once the streak has lapsed
+const INACTIVE_COLOR: &str = "#9f9f9f";
+
+/// Badge color of the cards-learned counter
+const CARDS_COLOR: &str = "#007ec6";
+
+/// Create the widget routes
+pub fn routes() -> Router<ApiState> {
+    Router::new()
+        .route(
+            "/users/me/widget-token",
+            post(create_widget_token).delete(revoke_widget_token),
+        )
+        .route("/widgets/{token}/streak.svg", get(streak_badge))
+        .route(
+            "/widgets/{token}/cards-learned.svg",
+            get(cards_learned_badge),
+        )
+        .route("/widgets/{token}/stats.json", get(widget_stats))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct WidgetTokenResponse {
+    token: String,
+    /// Streak badge path with the token, relative to the API origin
+    streak_path: String,
+    /// Cards-learned badge path with the token, relative to the API origin
+    cards_learned_path: String,
+    /// Stats JSON path with the token, relative to the API origin
+    stats_path: String,
+}
+
+/// Turn widgets on and issue their URLs; URLs issued before stop working
+#[utoipa::path(
+    post,
+    path = "/v1/users/me/widget-token",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    responses(
+        (status = 200, description = "New widget token", body = WidgetTokenResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+async fn create_widget_token(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+) -> Result<Json<WidgetTokenResponse>, ApiError> {
+    let version = user_repo::bump_widget_token_version(&state.pool, auth_user.user_id).await?;
+    let token = token::issue(auth_user.user_id, version, &state.auth.feed_keys)?;
+
+    Ok(Json(WidgetTokenResponse {
+        streak_path: format!("/v1/widgets/{token}/streak.svg"),
+        cards_learned_path: format!("/v1/widgets/{token}/cards-learned.svg"),
+        stats_path: format!("/v1/widgets/{token}/stats.json"),
+        token,
+    }))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct RevokeWidgetTokenResponse {
+    message: String,
+}
+
+/// Turn widgets off; every widget URL issued so far stops working
+#[utoipa::path(
+    delete,
+    path = "/v1/users/me/widget-token",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    responses(
+        (status = 200, description = "Widget URLs revoked", body = RevokeWidgetTokenResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+async fn revoke_widget_token(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+) -> Result<Json<RevokeWidgetTokenResponse>, ApiError> {
+    user_repo::bump_widget_token_version(&state.pool, auth_user.user_id).await?;
+
+    Ok(Json(RevokeWidgetTokenResponse {
+        message: "Widgets revoked".to_string(),
+    }))
+}
+
+/// Stats of the user the token was issued to, if it has not been revoked
+async fn authorize(state: &ApiState, widget_token: &str) -> Result<WidgetStats, ApiError> {
+    let revoked = || ApiError::Auth("Invalid or revoked widget token".to_string());
+
+    let (user_id, version) =
+        token::verify(widget_token, &state.auth.feed_keys).map_err(|_| revoked())?;
+    let stats = user_repo::get_widget_stats(&state.pool, user_id)
+        .await?
+        .filter(|stats| stats.widget_token_version == version)
+        .ok_or_else(revoked)?;
+    Ok(stats)
+}
+
+fn svg_response(svg: String) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, "image/svg+xml; charset=utf-8"),
+            (header::CACHE_CONTROL, WIDGET_CACHE_CONTROL),
+        ],
+        svg,
+    )
+        .into_response()
+}
+
+fn days(n: i32) -> String {
+    match n {
+        1 => "1 day".to_string(),
+        n => format!("{n} days"),
+    }
+}
+
+/// Badge with the user's current streak
+#[utoipa::path(
+    get,
+    path = "/v1/widgets/{token}/streak.svg",
+    tag = "widgets",
+    params(("token" = String, Path, description = "Token from `POST /v1/users/me/widget-token`")),
+    responses(
+        (status = 200, description = "SVG badge", body = String, content_type = "image/svg+xml"),
+        (status = 401, description = "Invalid or revoked token", body = ErrorResponse),
+    )
+)]
+async fn streak_badge(
+    State(state): State<ApiState>,
+    Path(widget_token): Path<String>,
+) -> Result<Response, ApiError> {
+    let stats = authorize(&state, &widget_token).await?;
+    let value = days(stats.current_streak_days);
+
+    Ok(svg_response(
+        Badge {
+            label: "streak",
+            value: &value,
+            color: if stats.current_streak_days > 0 {
+                ACTIVE_COLOR
+            } else {
+                INACTIVE_COLOR
+            },
+        }
+        .render(),
+    ))
+}
+
+/// Badge with the number of cards the user has mastered
+#[utoipa::path(
+    get,
+    path = "/v1/widgets/{token}/cards-learned.svg",
+    tag = "widgets",
+    params(("token" = String, Path, description = "Token from `POST /v1/users/me/widget-token`")),
+    responses(
+        (status = 200, description = "SVG badge", body = String, content_type = "image/svg+xml"),
+        (status = 401, description = "Invalid or revoked token", body = ErrorResponse),
+    )
+)]
+async fn cards_learned_badge(
+    State(state): State<ApiState>,
+    Path(widget_token): Path<String>,
+) -> Result<Response, ApiError> {
+    let stats = authorize(&state, &widget_token).await?;
+    let value = stats.total_cards_learned.to_string();
+
+    Ok(svg_response(
+        Badge {
+            label: "cards learned",
+            value: &value,
+            color: CARDS_COLOR,
+        }
+        .render(),
+    ))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct WidgetStatsView {
+    username: String,
+    current_streak_days: i32,
+    longest_streak_days: i32,
+    /// Cards mastered so far
+    cards_learned: i32,
+}
+
+/// The widget numbers as JSON, for sites that draw their own badge
+#[utoipa::path(
+    get,
+    path = "/v1/widgets/{token}/stats.json",
+    tag = "widgets",
+    params(("token" = String, Path, description = "Token from `POST /v1/users/me/widget-token`")),
+    responses(
+        (status = 200, description = "Widget stats", body = WidgetStatsView),
+        (status = 401, description = "Invalid or revoked token", body = ErrorResponse),
+    )
+)]
+async fn widget_stats(
+    State(state): State<ApiState>,
+    Path(widget_token): Path<String>,
+) -> Result<Response, ApiError> {
+    let stats = authorize(&state, &widget_token).await?;
+
+    Ok((
+        [
+            (header::CACHE_CONTROL, WIDGET_CACHE_CONTROL),
+            // Any site may read the numbers; the token already says the user allowed it
+            (header::ACCESS_CONTROL_ALLOW_ORIGIN, "*"),
+        ],
+        Json(WidgetStatsView {
+            username: stats.username,
+            current_streak_days: stats.current_streak_days,
+            longest_streak_days: stats.longest_streak_days,
+            cards_learned: stats.total_cards_learned,
+        }),
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_days_pluralizes() {
+        assert_eq!(days(0), "0 days");
+        assert_eq!(days(1), "1 day");
+        assert_eq!(days(12), "12 days");
+    }
+}
@@ -0,0 +1,78 @@
+//! Signed tokens authorising a user's widget URLs.
+//!
+//! Tokens are signed with the feed token key, which does not rotate with the
+//! access token keys, and carry their own audience so they are not accepted in
+//! place of calendar tokens. They do not expire, since embedded badges are meant
+//! to keep working; each token carries the user's widget version and stops
+//! working once the version is bumped.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+
+use crate::{auth::jwt::JwtKeys, error::ApiError};
+
+/// Audience of widget tokens
+const AUDIENCE: &str = "widget";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct WidgetClaims {
+    sub: String,
+    aud: String,
+    iat: usize,
+    /// `users.widget_token_version` when the token was issued
+    ver: i32,
+}
+
+/// Sign a widget token for the user at the given widget version
+pub fn issue(user_id: Uuid, version: i32, keys: &JwtKeys) -> Result<String, ApiError> {
+    keys.sign(&WidgetClaims {
+        sub: user_id.to_string(),
+        aud: AUDIENCE.to_string(),
+        iat: Utc::now().timestamp() as usize,
+        ver: version,
+    })
+}
+
+/// Check the signature and audience; returns the user and widget version the token was issued for
+pub fn verify(token: &str, keys: &JwtKeys) -> Result<(Uuid, i32), ApiError> {
+    let claims: WidgetClaims = keys.verify_with(token, |validation| {
+        validation.set_audience(&[AUDIENCE]);
+        validation.set_required_spec_claims(&["aud", "sub"]);
+        validation.validate_exp = false;
+    })?;
+
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| ApiError::Auth("Invalid or expired token".to_string()))?;
+    Ok((user_id, claims.ver))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar;
+
+    const SECRET: &str = "test_jwt_secret_minimum_32_characters_long";
+
+    #[test]
+    fn test_issue_and_verify() {
+        let keys = JwtKeys::hmac(SECRET);
+        let user_id = Uuid::new_v4();
+
+        let token = issue(user_id, 2, &keys).unwrap();
+
+        assert_eq!(verify(&token, &keys).unwrap(), (user_id, 2));
+    }
+
+    #[test]
+    fn test_tokens_are_not_interchangeable_with_calendar_tokens() {
+        let keys = JwtKeys::hmac(SECRET);
+        let user_id = Uuid::new_v4();
+
+        let calendar = calendar::token::issue(user_id, 1, &keys).unwrap();
+        assert!(verify(&calendar, &keys).is_err());
+
+        let widget = issue(user_id, 1, &keys).unwrap();
+        assert!(calendar::token::verify(&widget, &keys).is_err());
+    }
+}
@@ -0,0 +1,86 @@
+//! Experience points and levels.
+//!
+//! Every plausible review earns XP ([`mms_srs::review_xp`]): a correct answer
+//! earns more than a wrong one, plus a bonus scaled by the card's global
+//! difficulty. The total determines the level ([`mms_srs::level_for_xp`]).
+//! The review handler calls [`award_for_review`] in its transaction; flagged
+//! reviews earn nothing.
+
+pub mod routes;
+
+use async_graphql::SimpleObject;
+use serde::Serialize;
+use sqlx::{Postgres, Transaction};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use mms_db::repositories::xp as xp_repo;
+
+pub use routes::routes;
+
+/// A user's XP and how far they are into their level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema, SimpleObject)]
+pub struct XpProgress {
+    /// Total XP earned
+    pub xp: i64,
+    pub level: i32,
+    /// Total XP at which the current level started
+    pub level_start_xp: i64,
+    /// Total XP at which the next level starts
+    pub next_level_xp: i64,
+}
+
+impl From<i64> for XpProgress {
+    fn from(xp: i64) -> Self {
+        let level = mms_srs::level_for_xp(xp);
+        XpProgress {
+            xp,
+            level,
+            level_start_xp: mms_srs::xp_for_level(level),
+            next_level_xp: mms_srs::xp_for_level(level + 1),
+        }
+    }
+}
+
+/// Award the XP for a review and keep the stored level in step.
+///
+/// Returns the user's progress afterwards, or `None` if they have no stats row.
+pub async fn award_for_review(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    is_correct: bool,
+    difficulty: Option<f64>,
+) -> Result<Option<XpProgress>, sqlx::Error> {
+    let earned = mms_srs::review_xp(is_correct, difficulty);
+    let Some(xp) = xp_repo::award(
+        &mut **tx,
+        user_id,
+        earned.correct,
+        earned.wrong,
+        earned.difficulty_bonus,
+    )
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let progress = XpProgress::from(xp);
+    if mms_srs::level_for_xp(xp - i64::from(earned.total())) != progress.level {
+        tracing::debug!(user_id = %user_id, level = progress.level, "Level reached");
+        xp_repo::set_level(&mut **tx, user_id, progress.level).await?;
+    }
+    Ok(Some(progress))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_within_level() {
+        let progress = XpProgress::from(150);
+        assert_eq!(progress.level, 2);
+        assert_eq!(progress.level_start_xp, 100);
+        assert_eq!(progress.next_level_xp, 300);
+    }
+}
@@ -0,0 +1,83 @@
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    routing::get,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    ApiState,
+    auth::{AuthUser, require_self},
+    error::{ApiError, ErrorResponse},
+    xp::XpProgress,
+};
+
+use mms_db::models::XpDay;
+use mms_db::repositories::xp as xp_repo;
+
+/// Days of history returned when none are asked for
+const DEFAULT_HISTORY_DAYS: i32 = 30;
+
+/// Most days of history returned
+const MAX_HISTORY_DAYS: i32 = 365;
+
+/// Create the XP routes
+pub fn routes() -> Router<ApiState> {
+    Router::new().route("/users/{user_id}/xp", get(get_xp_history))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct HistoryQuery {
+    /// Days of history including today, 1 to 365 (default 30)
+    #[serde(default)]
+    days: Option<i32>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct XpHistory {
+    progress: XpProgress,
+    /// Days with reviews, oldest first
+    days: Vec<XpDay>,
+}
+
+/// The user's XP and level with a per-day breakdown of where the XP came from
+#[utoipa::path(
+    get,
+    path = "/v1/users/{user_id}/xp",
+    tag = "users",
+    security(("cookie_auth" = [])),
+    params(
+        ("user_id" = Uuid, Path, description = "Must be the signed-in user"),
+        HistoryQuery,
+    ),
+    responses(
+        (status = 200, description = "XP, level and daily breakdown", body = XpHistory),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Another user's XP", body = ErrorResponse),
+    )
+)]
+async fn get_xp_history(
+    auth_user: AuthUser,
+    State(state): State<ApiState>,
+    Path(user_id): Path<Uuid>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<XpHistory>, ApiError> {
+    require_self(&auth_user, user_id)?;
+    let days = query
+        .days
+        .unwrap_or(DEFAULT_HISTORY_DAYS)
+        .clamp(1, MAX_HISTORY_DAYS);
+
+    let (xp, days) = tokio::try_join!(
+        xp_repo::get_total(&state.pool, user_id),
+        xp_repo::list_days(&state.pool, user_id, days),
+    )?;
+
+    Ok(Json(XpHistory {
+        progress: XpProgress::from(xp),
+        days,
+    }))
+}
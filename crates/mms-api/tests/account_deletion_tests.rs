@@ -0,0 +1,243 @@
+use crate::common::{self, TestClient, TestStateBuilder, admin_request};
+use axum::http::StatusCode;
+use mms_api::{router, user::ACCOUNT_DELETION_GRACE_DAYS};
+use mms_db::repositories::{friend as friend_repo, user as user_repo};
+use serde_json::json;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_deleted_account_cannot_sign_in_until_restored() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let email = common::test_data::unique_email("restore");
+    let user_id = common::db::create_verified_user(
+        &state.pool,
+        &email,
+        &common::test_data::unique_username("restore"),
+    )
+    .await
+    .expect("Failed to create test user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let credentials = json!({ "email": email, "password": "password123" });
+
+    client
+        .delete_with_auth("/v1/users/me", &token, &state.cookie.cookie_key)
+        .await
+        .assert_status(StatusCode::OK);
+
+    // The access token issued before the deletion no longer signs anyone in
+    client
+        .get_with_auth("/v1/users/me", &token, &state.cookie.cookie_key)
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+    client
+        .delete_with_auth("/v1/users/me", &token, &state.cookie.cookie_key)
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+
+    let response = client.post_json("/v2/users/login", &credentials).await;
+    response.assert_status(StatusCode::FORBIDDEN);
+    assert!(response.get_cookie("auth_token").is_none());
+
+    // Restoring takes the account's password
+    let response = client
+        .post_json(
+            "/v1/users/restore",
+            &json!({ "email": email, "password": "wrong-password" }),
+        )
+        .await;
+    response.assert_status(StatusCode::UNAUTHORIZED);
+
+    let response = client.post_json("/v1/users/restore", &credentials).await;
+    response.assert_status(StatusCode::OK);
+    assert!(response.get_cookie("auth_token").is_some());
+    let deleted_at = user_repo::find_deleted_at(&state.pool, user_id)
+        .await
+        .unwrap();
+    assert!(deleted_at.is_none(), "The account should be restored");
+
+    client
+        .post_json("/v2/users/login", &credentials)
+        .await
+        .assert_status(StatusCode::OK);
+    client
+        .post_json("/v1/users/restore", &credentials)
+        .await
+        .assert_status(StatusCode::CONFLICT);
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup test user");
+}
+
+#[tokio::test]
+async fn test_deleted_users_are_hidden_from_other_users() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let pool = &state.pool;
+    let viewer_email = common::test_data::unique_email("viewer");
+    let viewer = common::db::create_verified_user(
+        pool,
+        &viewer_email,
+        &common::test_data::unique_username("viewer"),
+    )
+    .await
+    .unwrap();
+    let friend_email = common::test_data::unique_email("leaving");
+    let friend_username = common::test_data::unique_username("leaving");
+    let friend = common::db::create_verified_user(pool, &friend_email, &friend_username)
+        .await
+        .unwrap();
+    friend_repo::add(pool, viewer, friend).await.unwrap();
+    assert_eq!(friend_repo::list(pool, viewer).await.unwrap().len(), 1);
+
+    user_repo::soft_delete_user(pool, friend)
+        .await
+        .unwrap()
+        .expect("The account should be deleted");
+
+    assert!(friend_repo::list(pool, viewer).await.unwrap().is_empty());
+    assert_eq!(
+        user_repo::find_id_by_username(pool, &friend_username)
+            .await
+            .unwrap(),
+        None
+    );
+    assert!(
+        user_repo::get_widget_stats(pool, friend)
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    // Restoring brings the user back
+    assert!(
+        user_repo::restore_user(pool, friend, ACCOUNT_DELETION_GRACE_DAYS)
+            .await
+            .unwrap()
+    );
+    assert_eq!(friend_repo::list(pool, viewer).await.unwrap().len(), 1);
+
+    common::db::delete_user_by_email(pool, &viewer_email)
+        .await
+        .unwrap();
+    common::db::delete_user_by_email(pool, &friend_email)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_accounts_are_purged_after_the_grace_period() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let pool = &state.pool;
+    let expired_email = common::test_data::unique_email("expired");
+    let expired = common::db::create_verified_user(
+        pool,
+        &expired_email,
+        &common::test_data::unique_username("expired"),
+    )
+    .await
+    .unwrap();
+    let recent_email = common::test_data::unique_email("recent");
+    let recent = common::db::create_verified_user(
+        pool,
+        &recent_email,
+        &common::test_data::unique_username("recent"),
+    )
+    .await
+    .unwrap();
+
+    user_repo::soft_delete_user(pool, recent).await.unwrap();
+    sqlx::query(
+        "UPDATE users SET deleted_at = NOW() - make_interval(days => $2 + 1) WHERE id = $1",
+    )
+    .bind(expired)
+    .bind(ACCOUNT_DELETION_GRACE_DAYS)
+    .execute(pool)
+    .await
+    .unwrap();
+
+    // Past the grace period the account can no longer be restored
+    assert!(
+        !user_repo::restore_user(pool, expired, ACCOUNT_DELETION_GRACE_DAYS)
+            .await
+            .unwrap()
+    );
+
+    let purged = user_repo::purge_deleted_users(pool, ACCOUNT_DELETION_GRACE_DAYS)
+        .await
+        .unwrap();
+    assert!(!purged.is_empty());
+    assert_eq!(
+        common::db::get_user_by_email(pool, &expired_email)
+            .await
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        common::db::get_user_by_email(pool, &recent_email)
+            .await
+            .unwrap(),
+        Some(recent),
+        "Accounts within the grace period are kept"
+    );
+
+    common::db::delete_user_by_email(pool, &recent_email)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_admin_restores_a_deleted_account() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let email = common::test_data::unique_email("admin_restore");
+    let user_id = common::db::create_verified_user(
+        &state.pool,
+        &email,
+        &common::test_data::unique_username("admin_restore"),
+    )
+    .await
+    .unwrap();
+    user_repo::soft_delete_user(&state.pool, user_id)
+        .await
+        .unwrap();
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let uri = format!("/v1/admin/users/{user_id}/restore");
+
+    let response = client
+        .request(admin_request("GET", &format!("/v1/admin/users/{user_id}")))
+        .await;
+    response.assert_status(StatusCode::OK);
+    assert!(response.json::<serde_json::Value>()["deleted_at"].is_string());
+
+    client
+        .request(admin_request("POST", &uri))
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    client
+        .request(admin_request("POST", &uri))
+        .await
+        .assert_status(StatusCode::CONFLICT);
+    client
+        .request(admin_request(
+            "POST",
+            &format!("/v1/admin/users/{}/restore", Uuid::new_v4()),
+        ))
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .unwrap();
+}
@@ -0,0 +1,209 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use mms_api::auth::google::service::{find_or_create_google_user, link_google_account};
+use mms_api::router;
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+/// A user created by signing in with Google, and an access token for them
+async fn google_user(state: &mms_api::ApiState, base: &str) -> (Uuid, String, String) {
+    let email = common::test_data::unique_email(base);
+    let google_id = format!("google_{}", Uuid::new_v4().simple());
+    let user = find_or_create_google_user(
+        &state.pool,
+        &google_id,
+        &email,
+        Some(&common::test_data::unique_username(base)),
+        None,
+    )
+    .await
+    .expect("Should create Google user");
+    let token = common::jwt::create_test_token(user.id, &email, &state.auth.jwt_keys);
+    (user.id, email, token)
+}
+
+#[tokio::test]
+async fn test_google_account_sets_a_password_and_signs_in_with_it() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let (_, email, token) = google_user(&state, "set_password").await;
+    let key = &state.cookie.cookie_key;
+    let client = TestClient::new(router::router().with_state(state.clone()));
+
+    let methods: Value = client
+        .get_with_auth("/v1/users/me/sign-in-methods", &token, key)
+        .await
+        .json();
+    assert_eq!(methods, json!({ "password": false, "google": true }));
+
+    // There is no current password to change
+    let response = client
+        .patch_json_with_auth(
+            "/v1/users/me/password",
+            &json!({ "current_password": "", "new_password": "NewSecureP@ss123" }),
+            &token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::BAD_REQUEST);
+
+    let password = json!({ "new_password": "NewSecureP@ss123" });
+    client
+        .post_json_with_auth("/v1/users/me/password", &password, &token, key)
+        .await
+        .assert_status(StatusCode::OK);
+    client
+        .post_json_with_auth("/v1/users/me/password", &password, &token, key)
+        .await
+        .assert_status(StatusCode::CONFLICT);
+
+    let methods: Value = client
+        .get_with_auth("/v1/users/me/sign-in-methods", &token, key)
+        .await
+        .json();
+    assert_eq!(methods, json!({ "password": true, "google": true }));
+
+    client
+        .post_json(
+            "/v2/users/login",
+            &json!({ "email": email, "password": "NewSecureP@ss123" }),
+        )
+        .await
+        .assert_status(StatusCode::OK);
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup test user");
+}
+
+#[tokio::test]
+async fn test_password_needs_a_verified_email() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let (user_id, email, token) = google_user(&state, "unverified_password").await;
+    sqlx::query("UPDATE users SET email_verified = FALSE WHERE id = $1")
+        .bind(user_id)
+        .execute(&state.pool)
+        .await
+        .unwrap();
+    let client = TestClient::new(router::router().with_state(state.clone()));
+
+    client
+        .post_json_with_auth(
+            "/v1/users/me/password",
+            &json!({ "new_password": "NewSecureP@ss123" }),
+            &token,
+            &state.cookie.cookie_key,
+        )
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup test user");
+}
+
+#[tokio::test]
+async fn test_unlinking_google_never_locks_the_account_out() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let (_, email, token) = google_user(&state, "unlink").await;
+    let key = &state.cookie.cookie_key;
+    let client = TestClient::new(router::router().with_state(state.clone()));
+
+    // Google is the only way in
+    client
+        .delete_with_auth("/v1/auth/google/link", &token, key)
+        .await
+        .assert_status(StatusCode::CONFLICT);
+
+    client
+        .post_json_with_auth(
+            "/v1/users/me/password",
+            &json!({ "new_password": "NewSecureP@ss123" }),
+            &token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::OK);
+    client
+        .delete_with_auth("/v1/auth/google/link", &token, key)
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    client
+        .delete_with_auth("/v1/auth/google/link", &token, key)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    let methods: Value = client
+        .get_with_auth("/v1/users/me/sign-in-methods", &token, key)
+        .await
+        .json();
+    assert_eq!(methods, json!({ "password": true, "google": false }));
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup test user");
+}
+
+#[tokio::test]
+async fn test_link_google_to_an_email_account() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let pool = &state.pool;
+    let email = common::test_data::unique_email("link");
+    let user_id =
+        common::db::create_verified_user(pool, &email, &common::test_data::unique_username("link"))
+            .await
+            .unwrap();
+    let (other_id, other_email, _) = google_user(&state, "link_other").await;
+    let google_id = format!("google_{}", Uuid::new_v4().simple());
+
+    link_google_account(pool, user_id, &google_id)
+        .await
+        .expect("Should link");
+    // Linking again is a no-op
+    link_google_account(pool, user_id, &google_id)
+        .await
+        .expect("Should stay linked");
+
+    // The Google account now signs in to the email account
+    let signed_in = find_or_create_google_user(pool, &google_id, "other@example.com", None, None)
+        .await
+        .unwrap();
+    assert_eq!(signed_in.id, user_id);
+
+    // One Google account per user, and one user per Google account
+    let taken = link_google_account(pool, other_id, &google_id).await;
+    assert!(matches!(taken, Err(mms_api::error::ApiError::Conflict(_))));
+    let second = link_google_account(pool, user_id, "google_second").await;
+    assert!(matches!(second, Err(mms_api::error::ApiError::Conflict(_))));
+
+    // Starting the flow needs a session
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    client
+        .get("/v1/auth/google/link")
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+    let response = client
+        .get_with_auth("/v1/auth/google/link", &token, &state.cookie.cookie_key)
+        .await;
+    response.assert_status(StatusCode::SEE_OTHER);
+    assert!(response.get_cookie("oidc_flow").is_some());
+
+    common::db::delete_user_by_email(pool, &email)
+        .await
+        .unwrap();
+    common::db::delete_user_by_email(pool, &other_email)
+        .await
+        .unwrap();
+}
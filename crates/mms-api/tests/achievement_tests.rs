@@ -0,0 +1,126 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use mms_api::{auth::Role, router};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_first_review_unlocks_achievement_and_notifies() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let pool = &state.pool;
+    let cookie_key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("achiever");
+    let user_id = common::db::create_verified_user(
+        pool,
+        &email,
+        &common::test_data::unique_username("achiever"),
+    )
+    .await
+    .unwrap();
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('Achievements', 'en', 'es') RETURNING id",
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    let card_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO flashcards (term, translation, language_from, language_to) VALUES ($1, 'hola', 'en', 'es') RETURNING id",
+    )
+    .bind(format!("hello {deck_id}"))
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    sqlx::query("INSERT INTO deck_flashcards (deck_id, flashcard_id) VALUES ($1, $2)")
+        .bind(deck_id)
+        .bind(card_id)
+        .execute(pool)
+        .await
+        .unwrap();
+
+    let uri = format!("/v1/users/{user_id}/achievements");
+    let achievements: Vec<Value> = client.get_with_auth(&uri, &token, cookie_key).await.json();
+    let first_review = &achievements[0];
+    assert_eq!(first_review["code"], "first_review");
+    assert_eq!(first_review["progress"], 0);
+    assert_eq!(first_review["unlocked_at"], Value::Null);
+
+    let review = json!({ "user_answer": "hola", "deck_id": deck_id });
+    client
+        .post_json_with_auth(
+            &format!("/v1/practice/{card_id}/review"),
+            &review,
+            &token,
+            cookie_key,
+        )
+        .await
+        .assert_status(StatusCode::OK);
+
+    let response = client.get_with_auth(&uri, &token, cookie_key).await;
+    response.assert_status(StatusCode::OK);
+    let achievements: Vec<Value> = response.json();
+    let by_code = |code: &str| {
+        achievements
+            .iter()
+            .find(|achievement| achievement["code"] == code)
+            .cloned()
+            .unwrap()
+    };
+    assert_ne!(by_code("first_review")["unlocked_at"], Value::Null);
+    assert_eq!(by_code("reviews_100")["progress"], 1);
+    assert_eq!(by_code("reviews_100")["unlocked_at"], Value::Null);
+
+    // Unlocking notifies once; a later review does not repeat it
+    sqlx::query("UPDATE user_card_progress SET next_review_at = NOW() WHERE user_id = $1")
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    client
+        .post_json_with_auth(
+            &format!("/v1/practice/{card_id}/review"),
+            &review,
+            &token,
+            cookie_key,
+        )
+        .await
+        .assert_status(StatusCode::OK);
+    let notifications: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND kind = 'achievement_unlocked'",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    assert_eq!(notifications, 1);
+
+    // Achievements are private
+    let other = common::account(&state, "other", Role::Learner).await;
+    client
+        .get_with_auth(&uri, &other.token, cookie_key)
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+
+    common::db::delete_user_by_email(pool, &other.email)
+        .await
+        .ok();
+    common::db::delete_user_by_email(pool, &email)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM flashcards WHERE id = $1")
+        .bind(card_id)
+        .execute(pool)
+        .await
+        .unwrap();
+}
@@ -0,0 +1,640 @@
+use crate::common::{self, ADMIN_TOKEN, TestClient, TestStateBuilder, admin_request};
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use mms_api::{auth::Role, router};
+use serde_json::json;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_index_report_requires_admin_token() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let client = TestClient::new(router::router().with_state(state));
+
+    let response = client.get("/v1/admin/index-report").await;
+    response.assert_status(StatusCode::UNAUTHORIZED);
+
+    let response = client
+        .request(
+            Request::builder()
+                .uri("/v1/admin/index-report")
+                .header("x-forwarded-for", "127.0.0.1")
+                .header(
+                    "authorization",
+                    "Bearer wrong_admin_token_minimum_32_characters",
+                )
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    response.assert_status(StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_operator_token_rejected_when_not_configured() {
+    let mut state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    state.auth.admin_api_token = None;
+    let client = TestClient::new(router::router().with_state(state));
+
+    let response = client
+        .request(admin_request("GET", "/v1/admin/index-report"))
+        .await;
+    response.assert_status(StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_admin_endpoints_follow_role_permissions() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let cookie_key = &state.cookie.cookie_key;
+    let user_id = Uuid::new_v4();
+    let token = |role| {
+        common::jwt::create_test_token_with_role(
+            user_id,
+            "staff@example.com",
+            role,
+            &state.auth.jwt_keys,
+        )
+    };
+
+    // Learners can do neither
+    let learner = token(Role::Learner);
+    client
+        .get_with_auth("/v1/admin/index-report", &learner, cookie_key)
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+    client
+        .post_json_with_auth("/v1/admin/content/ingest", &json!({}), &learner, cookie_key)
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+
+    // Authors can import content but not read operator reports
+    let author = token(Role::Author);
+    client
+        .get_with_auth("/v1/admin/index-report", &author, cookie_key)
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+    let response = client
+        .post_json_with_auth(
+            "/v1/admin/content/ingest",
+            &json!({"type": "bogus"}),
+            &author,
+            cookie_key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    assert_eq!(response.json::<serde_json::Value>()["rejected"], 1);
+
+    // Admins can do both
+    let admin = token(Role::Admin);
+    client
+        .get_with_auth("/v1/admin/index-report", &admin, cookie_key)
+        .await
+        .assert_status(StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_login_token_carries_role_from_database() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let email = common::test_data::unique_email("author");
+    let username = common::test_data::unique_username("author");
+    let password = "Password123!";
+    let password_hash =
+        bcrypt::hash(password, bcrypt::DEFAULT_COST).expect("Failed to hash password");
+    common::db::create_test_user(&state.pool, &email, &username, &password_hash)
+        .await
+        .expect("Failed to create user");
+    sqlx::query("UPDATE users SET role = 'author' WHERE email = $1")
+        .bind(&email)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to grant role");
+
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let response = client
+        .post_json(
+            "/v1/users/login",
+            &json!({ "email": email, "password": password }),
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["user"]["role"], "author");
+    let claims = state
+        .auth
+        .jwt_keys
+        .verify(body["token"].as_str().unwrap())
+        .expect("Token should verify");
+    assert_eq!(claims.role, Role::Author);
+    assert_eq!(claims.scopes, vec!["content:write".to_string()]);
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup user");
+}
+
+#[tokio::test]
+async fn test_index_report_on_migrated_database() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let client = TestClient::new(router::router().with_state(state));
+
+    let response = client
+        .request(admin_request("GET", "/v1/admin/index-report"))
+        .await;
+    response.assert_status(StatusCode::OK);
+
+    let report: serde_json::Value = response.json();
+    assert_eq!(
+        report["missing_indexes"],
+        serde_json::json!([]),
+        "Every expected index should exist after migrations"
+    );
+
+    // The plain index on refresh_tokens(token_hash) duplicates the UNIQUE constraint
+    let redundant = report["redundant_indexes"].as_array().unwrap();
+    assert!(
+        redundant
+            .iter()
+            .any(|r| r["index"] == "idx_refresh_tokens_hash"
+                && r["covered_by"] == "refresh_tokens_token_hash_key")
+    );
+    assert!(report["hot_queries"].is_array());
+}
+
+#[tokio::test]
+async fn test_ingest_content_ndjson() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let pool = state.pool.clone();
+    let client = TestClient::new(router::router().with_state(state));
+
+    let roadmap_id = Uuid::new_v4();
+    let deck_id = Uuid::new_v4();
+    let node_id = Uuid::new_v4();
+    let suffix = &Uuid::new_v4().to_string()[..8];
+    let lines = [
+        json!({"type": "roadmap", "id": roadmap_id, "title": format!("Ingest {suffix}"), "language_from": "en", "language_to": "es"}),
+        json!({"type": "deck", "id": deck_id, "title": "Ingested deck", "language_from": "EN", "language_to": "es"}),
+        json!({"type": "card", "deck_id": deck_id, "term": format!("cat_{suffix}"), "translation": "gato"}),
+        json!({"type": "card", "deck_id": deck_id, "term": format!("dog_{suffix}"), "translation": "perro"}),
+        json!({"type": "card", "deck_id": Uuid::new_v4(), "term": "orphan", "translation": "huérfano"}),
+        json!({"type": "node", "id": node_id, "roadmap_id": roadmap_id, "deck_id": deck_id, "pos_x": 1}),
+        json!({"type": "node", "id": Uuid::new_v4(), "roadmap_id": Uuid::new_v4(), "deck_id": deck_id}),
+    ];
+    let mut body: String = lines.iter().map(|line| format!("{line}\n")).collect();
+    body.push_str("\nnot json\n");
+
+    let request = |body: String| {
+        Request::builder()
+            .method("POST")
+            .uri("/v1/admin/content/ingest")
+            .header("x-forwarded-for", "127.0.0.1")
+            .header("authorization", format!("Bearer {ADMIN_TOKEN}"))
+            .header("content-type", "application/x-ndjson")
+            .body(Body::from(body))
+            .expect("Failed to build ingest request")
+    };
+
+    let response = client.request(request(body.clone())).await;
+    response.assert_status(StatusCode::OK);
+
+    let summary: serde_json::Value = response.json();
+    assert_eq!(summary["lines"], 9);
+    assert_eq!(summary["roadmaps"], 1);
+    assert_eq!(summary["decks"], 1);
+    assert_eq!(summary["cards"], 2);
+    assert_eq!(summary["nodes"], 1);
+    assert_eq!(summary["rejected"], 3);
+    let rejected_lines: Vec<u64> = summary["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["line"].as_u64().unwrap())
+        .collect();
+    assert_eq!(rejected_lines, vec![5, 7, 9]);
+
+    let card_count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM deck_flashcards WHERE deck_id = $1")
+            .bind(deck_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(card_count, 2);
+
+    // Re-sending the same file is idempotent
+    let response = client.request(request(body)).await;
+    response.assert_status(StatusCode::OK);
+    let card_count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM deck_flashcards WHERE deck_id = $1")
+            .bind(deck_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(card_count, 2);
+
+    let card_ids: Vec<Uuid> =
+        sqlx::query_scalar("SELECT flashcard_id FROM deck_flashcards WHERE deck_id = $1")
+            .bind(deck_id)
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    common::db::delete_roadmap_by_id(&pool, roadmap_id)
+        .await
+        .expect("Failed to cleanup roadmap");
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM flashcards WHERE id = ANY($1)")
+        .bind(&card_ids)
+        .execute(&pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_import_memrise_export_with_column_mapping() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let pool = state.pool.clone();
+    let client = TestClient::new(router::router().with_state(state));
+
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('Imported deck', 'en', 'es') RETURNING id",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let suffix = &Uuid::new_v4().to_string()[..8];
+    let export = format!(
+        "Level,Definition,Word\n1,\"cat_{suffix}, feline\",gato\n1,dog_{suffix},perro\n2,,sin traducción\n"
+    );
+
+    let request = |uri: String| {
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("x-forwarded-for", "127.0.0.1")
+            .header("authorization", format!("Bearer {ADMIN_TOKEN}"))
+            .header("content-type", "text/plain")
+            .body(Body::from(export.clone()))
+            .expect("Failed to build import request")
+    };
+
+    let response = client
+        .request(request(
+            "/v1/admin/content/import/preview?format=memrise".to_string(),
+        ))
+        .await;
+    response.assert_status(StatusCode::OK);
+    let preview: serde_json::Value = response.json();
+    assert_eq!(preview["total_rows"], 3);
+    assert_eq!(preview["columns"][2]["name"], "Word");
+    assert_eq!(preview["suggested_mapping"]["term_column"], 2);
+    assert_eq!(preview["suggested_mapping"]["translation_column"], 1);
+
+    // Cards are studied from the definition to the word here
+    let response = client
+        .request(request(format!(
+            "/v1/admin/content/import?format=memrise&deck_id={deck_id}&term_column=1&translation_column=2"
+        )))
+        .await;
+    response.assert_status(StatusCode::OK);
+    let summary: serde_json::Value = response.json();
+    assert_eq!(summary["rows"], 3);
+    assert_eq!(summary["cards"], 2);
+    assert_eq!(summary["rejected"], 1);
+    assert_eq!(summary["errors"][0]["line"], 4);
+
+    let terms: Vec<String> = sqlx::query_scalar(
+        "SELECT f.term FROM flashcards f JOIN deck_flashcards df ON df.flashcard_id = f.id WHERE df.deck_id = $1 ORDER BY f.term",
+    )
+    .bind(deck_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        terms,
+        vec![format!("cat_{suffix}, feline"), format!("dog_{suffix}")]
+    );
+
+    client
+        .request(request(format!(
+            "/v1/admin/content/import?format=memrise&deck_id={}&term_column=1&translation_column=2",
+            Uuid::new_v4()
+        )))
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    let card_ids: Vec<Uuid> =
+        sqlx::query_scalar("SELECT flashcard_id FROM deck_flashcards WHERE deck_id = $1")
+            .bind(deck_id)
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM flashcards WHERE id = ANY($1)")
+        .bind(&card_ids)
+        .execute(&pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_seed_bundle_upserts_by_slug() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let pool = state.pool.clone();
+    let client = TestClient::new(router::router().with_state(state));
+
+    let suffix = &Uuid::new_v4().simple().to_string()[..8];
+    let bundle = |title: &str, extra_card: &str, pos_x: i32| {
+        format!(
+            r#"
+decks:
+  - slug: basics-{suffix}
+    title: {title}
+    language_from: en
+    language_to: es
+    cards:
+      - {{ term: cat_{suffix}, translation: gato }}
+      - {{ term: dog_{suffix}, translation: perro }}
+{extra_card}
+  - slug: food-{suffix}
+    title: Food
+    language_from: en
+    language_to: es
+roadmaps:
+  - slug: a1-{suffix}
+    title: Seeded {suffix}
+    language_from: en
+    language_to: es
+    nodes:
+      - deck: basics-{suffix}
+      - {{ deck: food-{suffix}, parent: basics-{suffix}, pos_x: {pos_x} }}
+"#
+        )
+    };
+    let request = |uri: &str, body: String| {
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("x-forwarded-for", "127.0.0.1")
+            .header("authorization", format!("Bearer {ADMIN_TOKEN}"))
+            .header("content-type", "application/yaml")
+            .body(Body::from(body))
+            .expect("Failed to build seed request")
+    };
+    let deck_count = || async {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM decks WHERE slug LIKE '%-' || $1")
+            .bind(suffix)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+    };
+
+    // A dry run reports the changes and keeps none
+    let response = client
+        .request(request(
+            "/v1/admin/content/seed?dry_run=true",
+            bundle("Basics", "", 0),
+        ))
+        .await;
+    response.assert_status(StatusCode::OK);
+    let report: serde_json::Value = response.json();
+    assert_eq!(report["dry_run"], true);
+    assert_eq!(report["decks"][0]["action"], "created");
+    assert_eq!(report["decks"][0]["id"], serde_json::Value::Null);
+    assert_eq!(report["decks"][0]["cards_added"], 2);
+    assert_eq!(report["roadmaps"][0]["nodes_added"], 2);
+    assert_eq!(deck_count().await, 0);
+
+    let response = client
+        .request(request("/v1/admin/content/seed", bundle("Basics", "", 0)))
+        .await;
+    response.assert_status(StatusCode::OK);
+    let report: serde_json::Value = response.json();
+    let deck_id: Uuid = serde_json::from_value(report["decks"][0]["id"].clone()).unwrap();
+    let roadmap_id: Uuid = serde_json::from_value(report["roadmaps"][0]["id"].clone()).unwrap();
+    assert_eq!(deck_count().await, 2);
+
+    // Loading it again changes nothing
+    let response = client
+        .request(request("/v1/admin/content/seed", bundle("Basics", "", 0)))
+        .await;
+    response.assert_status(StatusCode::OK);
+    let report: serde_json::Value = response.json();
+    assert_eq!(report["decks"][0]["action"], "unchanged");
+    assert_eq!(report["decks"][0]["cards_unchanged"], 2);
+    assert_eq!(report["roadmaps"][0]["action"], "unchanged");
+    assert_eq!(report["roadmaps"][0]["nodes_unchanged"], 2);
+
+    // Edits are matched to the same rows
+    let extra = format!("      - {{ term: bird_{suffix}, translation: pájaro }}");
+    let response = client
+        .request(request(
+            "/v1/admin/content/seed",
+            bundle("Basics 1", &extra, 3),
+        ))
+        .await;
+    response.assert_status(StatusCode::OK);
+    let report: serde_json::Value = response.json();
+    assert_eq!(report["decks"][0]["action"], "updated");
+    assert_eq!(report["decks"][0]["id"], deck_id.to_string());
+    assert_eq!(report["decks"][0]["changed"], json!(["title"]));
+    assert_eq!(report["decks"][0]["cards_added"], 1);
+    assert_eq!(report["roadmaps"][0]["nodes_updated"], 1);
+    assert_eq!(report["roadmaps"][0]["nodes_unchanged"], 1);
+
+    let (title, cards): (String, i64) = sqlx::query_as(
+        "SELECT title, (SELECT COUNT(*) FROM deck_flashcards WHERE deck_id = $1) FROM decks WHERE id = $1",
+    )
+    .bind(deck_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!((title.as_str(), cards), ("Basics 1", 3));
+    let child_parent: Option<Uuid> = sqlx::query_scalar(
+        "SELECT parent_node_id FROM roadmap_nodes WHERE roadmap_id = $1 AND pos_x = 3",
+    )
+    .bind(roadmap_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert!(child_parent.is_some());
+
+    // Invalid bundles name every problem; unknown decks are found before writing
+    let response = client
+        .request(request(
+            "/v1/admin/content/seed",
+            format!(
+                "roadmaps:\n  - {{ slug: a1-{suffix}, title: T, language_from: en, language_to: es, nodes: [{{ deck: missing-{suffix} }}] }}\n"
+            ),
+        ))
+        .await;
+    response.assert_status(StatusCode::BAD_REQUEST);
+    let error: serde_json::Value = response.json();
+    assert_eq!(error["details"][0]["field"], "roadmaps[0].nodes[0].deck");
+    client
+        .request(request("/v1/admin/content/seed", "decks: [".to_string()))
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    let card_ids: Vec<Uuid> =
+        sqlx::query_scalar("SELECT flashcard_id FROM deck_flashcards WHERE deck_id = $1")
+            .bind(deck_id)
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    common::db::delete_roadmap_by_id(&pool, roadmap_id)
+        .await
+        .expect("Failed to cleanup roadmap");
+    sqlx::query("DELETE FROM decks WHERE slug LIKE '%-' || $1")
+        .bind(suffix)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM flashcards WHERE id = ANY($1)")
+        .bind(&card_ids)
+        .execute(&pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_import_detects_duplicate_terms_in_the_deck() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let pool = state.pool.clone();
+    let client = TestClient::new(router::router().with_state(state));
+
+    let suffix = &Uuid::new_v4().simple().to_string()[..8];
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('Duplicates ' || $1, 'en', 'es') RETURNING id",
+    )
+    .bind(suffix)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    let existing_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO flashcards (term, translation, language_from, language_to) VALUES ('Gato_' || $1, 'cat', 'en', 'es') RETURNING id",
+    )
+    .bind(suffix)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    sqlx::query("INSERT INTO deck_flashcards (deck_id, flashcard_id) VALUES ($1, $2)")
+        .bind(deck_id)
+        .bind(existing_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let import = |policy: &str, export: String| {
+        let request = Request::builder()
+            .method("POST")
+            .uri(format!(
+                "/v1/admin/content/import?format=quizlet&deck_id={deck_id}&term_column=0&translation_column=1{policy}"
+            ))
+            .header("x-forwarded-for", "127.0.0.1")
+            .header("authorization", format!("Bearer {ADMIN_TOKEN}"))
+            .header("content-type", "text/plain")
+            .body(Body::from(export))
+            .expect("Failed to build import request");
+        let client = &client;
+        async move {
+            let response = client.request(request).await;
+            response.assert_status(StatusCode::OK);
+            response.json::<serde_json::Value>()
+        }
+    };
+    let deck_size = || async {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM deck_flashcards WHERE deck_id = $1")
+            .bind(deck_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+    };
+
+    // Case, accents and punctuation are ignored; the exact card already in the deck is not a duplicate
+    let summary = import(
+        "&on_duplicate=reject",
+        format!(
+            "gato_{suffix}\tfeline\nperro_{suffix}\tdog\nPérro_{suffix}!\tdoggy\nGato_{suffix}\tcat\n"
+        ),
+    )
+    .await;
+    assert_eq!(summary["cards"], 2);
+    assert_eq!(summary["rejected"], 2);
+    assert_eq!(summary["duplicates"], 2);
+    let duplicates = summary["duplicate_rows"].as_array().unwrap();
+    assert_eq!(duplicates[0]["line"], 1);
+    assert_eq!(duplicates[0]["duplicate_of"], existing_id.to_string());
+    assert_eq!(duplicates[0]["existing_translation"], "cat");
+    assert_eq!(duplicates[1]["line"], 3);
+    assert_eq!(duplicates[1]["existing_term"], format!("perro_{suffix}"));
+    assert_eq!(summary["errors"][1]["line"], 3);
+    assert_eq!(deck_size().await, 2);
+
+    // Merging keeps the card already there
+    let summary = import("&on_duplicate=merge", format!("GATO_{suffix}\tkitty\n")).await;
+    assert_eq!(summary["cards"], 0);
+    assert_eq!(summary["rejected"], 0);
+    assert_eq!(summary["duplicates"], 1);
+    assert_eq!(deck_size().await, 2);
+
+    // Flagged duplicates are added and reported
+    let summary = import("", format!("gato_{suffix}\tfeline\n")).await;
+    assert_eq!(summary["cards"], 1);
+    assert_eq!(summary["duplicates"], 1);
+    assert_eq!(deck_size().await, 3);
+
+    let card_ids: Vec<Uuid> =
+        sqlx::query_scalar("SELECT flashcard_id FROM deck_flashcards WHERE deck_id = $1")
+            .bind(deck_id)
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM flashcards WHERE id = ANY($1)")
+        .bind(&card_ids)
+        .execute(&pool)
+        .await
+        .unwrap();
+}
@@ -0,0 +1,209 @@
+use crate::common::{self, TestClient, TestStateBuilder, auth_cookie};
+use axum::{
+    body::Body,
+    http::{Request, StatusCode, header},
+};
+use image::{DynamicImage, ImageFormat, Rgb, RgbImage};
+use mms_api::auth::google::service::find_or_create_google_user;
+use mms_api::router;
+use serde_json::Value;
+use std::io::Cursor;
+use uuid::Uuid;
+
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let image = RgbImage::from_pixel(width, height, Rgb([20, 120, 60]));
+    let mut bytes = Vec::new();
+    DynamicImage::ImageRgb8(image)
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        .unwrap();
+    bytes
+}
+
+fn upload(state: &mms_api::ApiState, token: &str, body: Vec<u8>) -> Request<Body> {
+    Request::builder()
+        .method("PUT")
+        .uri("/v1/users/me/avatar")
+        .header("x-forwarded-for", "127.0.0.1")
+        .header(header::COOKIE, auth_cookie(&state.cookie.cookie_key, token))
+        .header(header::CONTENT_TYPE, "image/png")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+async fn profile_picture_url(state: &mms_api::ApiState, user_id: Uuid) -> Option<String> {
+    sqlx::query_scalar("SELECT profile_picture_url FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(&state.pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_upload_replace_and_remove_avatar() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let email = common::test_data::unique_email("avatar");
+    let user_id = common::db::create_verified_user(
+        &state.pool,
+        &email,
+        &common::test_data::unique_username("avatar"),
+    )
+    .await
+    .expect("Failed to create test user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+    let client = TestClient::new(router::router().with_state(state.clone()));
+
+    let response = client.request(upload(&state, &token, png(400, 300))).await;
+    response.assert_status(StatusCode::OK);
+    let first: Value = response.json();
+    let first_url = first["profile_picture_url"].as_str().unwrap().to_string();
+    assert_eq!(first["sizes"][0]["size"], 256);
+    assert_eq!(first["sizes"][0]["url"], first_url.as_str());
+    assert_eq!(first["sizes"][1]["size"], 64);
+    assert_eq!(
+        profile_picture_url(&state, user_id).await.as_deref(),
+        Some(first_url.as_str())
+    );
+
+    let response = client.get(&first_url).await;
+    response.assert_status(StatusCode::OK);
+    assert_eq!(response.headers[header::CONTENT_TYPE], "image/jpeg");
+    assert!(
+        response.headers[header::CACHE_CONTROL]
+            .to_str()
+            .unwrap()
+            .contains("immutable")
+    );
+    let served = image::load_from_memory_with_format(&response.body, ImageFormat::Jpeg).unwrap();
+    assert_eq!((served.width(), served.height()), (256, 256));
+
+    // A new upload replaces the previous one, whose images are deleted
+    let response = client.request(upload(&state, &token, png(128, 128))).await;
+    response.assert_status(StatusCode::OK);
+    let second_url = response.json::<Value>()["profile_picture_url"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert_ne!(second_url, first_url);
+    client
+        .get(&first_url)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    client
+        .delete_with_auth("/v1/users/me/avatar", &token, &state.cookie.cookie_key)
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    assert_eq!(profile_picture_url(&state, user_id).await, None);
+    client
+        .get(&second_url)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    client
+        .delete_with_auth("/v1/users/me/avatar", &token, &state.cookie.cookie_key)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup test user");
+}
+
+#[tokio::test]
+async fn test_invalid_avatars_are_rejected() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let email = common::test_data::unique_email("bad_avatar");
+    let user_id = common::db::create_verified_user(
+        &state.pool,
+        &email,
+        &common::test_data::unique_username("bad_avatar"),
+    )
+    .await
+    .unwrap();
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+    let client = TestClient::new(router::router().with_state(state.clone()));
+
+    let response = client
+        .request(upload(
+            &state,
+            &token,
+            b"<svg onload=\"alert(1)\"/>".to_vec(),
+        ))
+        .await;
+    response.assert_status(StatusCode::BAD_REQUEST);
+    client
+        .request(upload(&state, &token, png(32, 32)))
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    assert_eq!(profile_picture_url(&state, user_id).await, None);
+
+    client
+        .get("/v1/media/avatars/../../etc/passwd")
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_google_picture_does_not_replace_an_uploaded_avatar() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let email = common::test_data::unique_email("google_avatar");
+    let google_id = format!("google_{}", Uuid::new_v4().simple());
+    let google_picture = "https://lh3.googleusercontent.com/a/picture";
+    let user = find_or_create_google_user(
+        &state.pool,
+        &google_id,
+        &email,
+        Some(&common::test_data::unique_username("google_avatar")),
+        Some(google_picture),
+    )
+    .await
+    .unwrap();
+    assert_eq!(user.profile_picture_url.as_deref(), Some(google_picture));
+    let token = common::jwt::create_test_token(user.id, &email, &state.auth.jwt_keys);
+    let client = TestClient::new(router::router().with_state(state.clone()));
+
+    let response = client.request(upload(&state, &token, png(100, 100))).await;
+    response.assert_status(StatusCode::OK);
+    let avatar_url = response.json::<Value>()["profile_picture_url"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let signed_in = find_or_create_google_user(
+        &state.pool,
+        &google_id,
+        &email,
+        None,
+        Some("https://lh3.googleusercontent.com/a/new-picture"),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        signed_in.profile_picture_url.as_deref(),
+        Some(avatar_url.as_str())
+    );
+    assert_eq!(
+        profile_picture_url(&state, user.id).await.as_deref(),
+        Some(avatar_url.as_str())
+    );
+
+    client
+        .delete_with_auth("/v1/users/me/avatar", &token, &state.cookie.cookie_key)
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .unwrap();
+}
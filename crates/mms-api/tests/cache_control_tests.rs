@@ -0,0 +1,75 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::{body::Body, http::Request, http::StatusCode};
+use mms_api::router;
+
+#[tokio::test]
+async fn test_public_content_is_cacheable_and_the_rest_is_not_stored() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+
+    for uri in ["/v1/roadmaps", "/v2/decks", "/v1/roadmaps/en/es"] {
+        let response = client.get(uri).await;
+        response.assert_status(StatusCode::OK);
+        assert_eq!(
+            response.headers["cache-control"], "public, max-age=300",
+            "{uri}"
+        );
+    }
+
+    // Personal or failed responses are never stored
+    let email = common::test_data::unique_email("nostore");
+    let user_id = common::db::create_verified_user(
+        &state.pool,
+        &email,
+        &common::test_data::unique_username("nostore"),
+    )
+    .await
+    .expect("Failed to create user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+    let response = client
+        .get_with_auth("/v1/users/me/daily-goal", &token, &state.cookie.cookie_key)
+        .await;
+    response.assert_status(StatusCode::OK);
+    assert_eq!(response.headers["cache-control"], "no-store");
+
+    let response = client.get("/v1/roadmaps/en/not-a-language").await;
+    response.assert_status(StatusCode::BAD_REQUEST);
+    assert_eq!(response.headers["cache-control"], "no-store");
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup user");
+}
+
+#[tokio::test]
+async fn test_responses_are_compressed_when_accepted() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+
+    let get = |encoding: &'static str| {
+        let request = Request::builder()
+            .uri("/v1/openapi.json")
+            .header("accept-encoding", encoding)
+            .body(Body::empty())
+            .unwrap();
+        client.request(request)
+    };
+
+    let plain = get("identity").await;
+    plain.assert_status(StatusCode::OK);
+    assert!(plain.headers.get("content-encoding").is_none());
+
+    let gzip = get("gzip").await;
+    gzip.assert_status(StatusCode::OK);
+    assert_eq!(gzip.headers["content-encoding"], "gzip");
+    assert!(gzip.body.len() < plain.body.len());
+
+    let brotli = get("br;q=1.0, gzip;q=0.5").await;
+    assert_eq!(brotli.headers["content-encoding"], "br");
+}
@@ -0,0 +1,107 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::{StatusCode, header};
+use mms_api::router;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_calendar_feed_lists_due_reviews() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+    let key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("calendar");
+    let username = common::test_data::unique_username("calendar");
+    let user_id = common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    let flashcard_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO flashcards (term, translation, language_from, language_to)
+        VALUES ($1, 'calendario', 'en', 'es')
+        RETURNING id
+        "#,
+    )
+    .bind(format!("calendar {}", Uuid::new_v4()))
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to create flashcard");
+    sqlx::query(
+        r#"
+        INSERT INTO user_card_progress (user_id, flashcard_id, next_review_at)
+        VALUES ($1, $2, NOW() - INTERVAL '1 day')
+        "#,
+    )
+    .bind(user_id)
+    .bind(flashcard_id)
+    .execute(&state.pool)
+    .await
+    .expect("Failed to create progress");
+
+    let response = client
+        .post_json_with_auth(
+            "/v1/users/me/calendar-token",
+            &serde_json::json!({}),
+            &token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let path = response.json::<serde_json::Value>()["path"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert!(path.starts_with(&format!("/v1/users/{user_id}/calendar.ics?token=")));
+
+    let response = client.get(&path).await;
+    response.assert_status(StatusCode::OK);
+    assert!(
+        response.headers[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("text/calendar")
+    );
+    let ics = response.text();
+    assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+    // The overdue card counts towards today
+    assert!(ics.contains("SUMMARY:1 review due\r\n"));
+    assert!(ics.contains("DESCRIPTION:en → es: 1\r\n"));
+
+    // The token only opens its own user's feed
+    let other_user = path.replace(&user_id.to_string(), &Uuid::new_v4().to_string());
+    client
+        .get(&other_user)
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+
+    // An access token is not a feed token
+    client
+        .get(&format!("/v1/users/{user_id}/calendar.ics?token={token}"))
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+
+    // Revoking invalidates every URL issued so far
+    client
+        .delete_with_auth("/v1/users/me/calendar-token", &token, key)
+        .await
+        .assert_status(StatusCode::OK);
+    client
+        .get(&path)
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup");
+    sqlx::query("DELETE FROM flashcards WHERE id = $1")
+        .bind(flashcard_id)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup flashcard");
+}
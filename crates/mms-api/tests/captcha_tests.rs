@@ -0,0 +1,247 @@
+use std::sync::Arc;
+
+use crate::common::{self, TestClient, TestStateBuilder, captcha::StubCaptcha};
+use axum::http::StatusCode;
+use mms_api::{router, state::ApiState, token_service};
+use serde_json::json;
+use sqlx::PgPool;
+
+async fn captcha_state() -> ApiState {
+    let mut state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    state.captcha = Some(Arc::new(StubCaptcha));
+    state
+}
+
+async fn set_failed_logins(pool: &PgPool, email: &str, attempts: i32) {
+    sqlx::query(
+        "INSERT INTO login_failures (email_hash, attempts) VALUES ($1, $2)
+         ON CONFLICT (email_hash) DO UPDATE SET attempts = $2, last_failed_at = NOW()",
+    )
+    .bind(token_service::hash_token(email))
+    .bind(attempts)
+    .execute(pool)
+    .await
+    .expect("Failed to set failed attempts");
+}
+
+async fn failed_logins(pool: &PgPool, email: &str) -> i32 {
+    sqlx::query_scalar::<_, i32>("SELECT attempts FROM login_failures WHERE email_hash = $1")
+        .bind(token_service::hash_token(email))
+        .fetch_optional(pool)
+        .await
+        .expect("Failed to read failed attempts")
+        .unwrap_or(0)
+}
+
+#[tokio::test]
+async fn test_register_requires_captcha_when_enabled() {
+    let state = captcha_state().await;
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+
+    let email = common::test_data::unique_email("captcha_register");
+    let username = common::test_data::unique_username("captcha");
+
+    // Missing token
+    let body = json!({
+        "username": username,
+        "email": email,
+        "password": "SecureP@ssw0rd123"
+    });
+    let response = client.post_json("/v1/users/register", &body).await;
+    response.assert_status(StatusCode::BAD_REQUEST);
+
+    // Rejected token
+    let body = json!({
+        "username": username,
+        "email": email,
+        "password": "SecureP@ssw0rd123",
+        "captcha_token": "bogus"
+    });
+    let response = client.post_json("/v1/users/register", &body).await;
+    response.assert_status(StatusCode::BAD_REQUEST);
+
+    let user = common::db::get_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to query user");
+    assert!(user.is_none(), "User must not be created without captcha");
+
+    // Valid token
+    let body = json!({
+        "username": username,
+        "email": email,
+        "password": "SecureP@ssw0rd123",
+        "captcha_token": common::captcha::VALID_TOKEN
+    });
+    let response = client.post_json("/v1/users/register", &body).await;
+    response.assert_status(StatusCode::OK);
+
+    // Cleanup
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup");
+}
+
+#[tokio::test]
+async fn test_password_reset_request_requires_captcha_when_enabled() {
+    let state = captcha_state().await;
+    let app = router::router().with_state(state);
+    let client = TestClient::new(app);
+
+    let email = common::test_data::unique_email("captcha_reset");
+
+    let response = client
+        .post_json(
+            "/v1/users/request-password-reset",
+            &json!({ "email": email }),
+        )
+        .await;
+    response.assert_status(StatusCode::BAD_REQUEST);
+
+    let response = client
+        .post_json(
+            "/v1/users/request-password-reset",
+            &json!({ "email": email, "captcha_token": common::captcha::VALID_TOKEN }),
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_login_requires_captcha_after_repeated_failures() {
+    let state = captcha_state().await;
+    let threshold = state.auth.login_captcha_threshold;
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+
+    let email = common::test_data::unique_email("captcha_login");
+    let username = common::test_data::unique_username("captcha_login");
+    common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+
+    // Below the threshold no captcha is needed
+    let response = client
+        .post_json(
+            "/v1/users/login",
+            &json!({ "email": email, "password": "password123" }),
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+
+    // Simulate an address that has hit the failure threshold
+    set_failed_logins(&state.pool, &email, threshold).await;
+
+    let response = client
+        .post_json(
+            "/v1/users/login",
+            &json!({ "email": email, "password": "password123" }),
+        )
+        .await;
+    response.assert_status(StatusCode::BAD_REQUEST);
+
+    let response = client
+        .post_json(
+            "/v1/users/login",
+            &json!({
+                "email": email,
+                "password": "password123",
+                "captcha_token": common::captcha::VALID_TOKEN
+            }),
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+
+    // A successful login clears the counter
+    assert_eq!(failed_logins(&state.pool, &email).await, 0);
+
+    // Cleanup
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup");
+}
+
+#[tokio::test]
+async fn test_failed_logins_are_counted() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+
+    let email = common::test_data::unique_email("failed_login");
+    let username = common::test_data::unique_username("failed_login");
+    common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+
+    for _ in 0..2 {
+        let response = client
+            .post_json(
+                "/v1/users/login",
+                &json!({ "email": email, "password": "wrong-password" }),
+            )
+            .await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    assert_eq!(failed_logins(&state.pool, &email).await, 2);
+
+    // Cleanup
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup");
+}
+
+#[tokio::test]
+async fn test_login_captcha_does_not_reveal_unknown_emails() {
+    let state = captcha_state().await;
+    let threshold = state.auth.login_captcha_threshold;
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+
+    let email = common::test_data::unique_email("captcha_unknown");
+
+    // Failures for an address without an account are counted too
+    let response = client
+        .post_json(
+            "/v1/users/login",
+            &json!({ "email": email, "password": "wrong-password" }),
+        )
+        .await;
+    response.assert_status(StatusCode::UNAUTHORIZED);
+    assert_eq!(failed_logins(&state.pool, &email).await, 1);
+
+    // Past the threshold it asks for a captcha like an existing account would
+    set_failed_logins(&state.pool, &email, threshold).await;
+    let response = client
+        .post_json(
+            "/v1/users/login",
+            &json!({ "email": email, "password": "wrong-password" }),
+        )
+        .await;
+    response.assert_status(StatusCode::BAD_REQUEST);
+
+    let response = client
+        .post_json(
+            "/v1/users/login",
+            &json!({
+                "email": email,
+                "password": "wrong-password",
+                "captcha_token": common::captcha::VALID_TOKEN
+            }),
+        )
+        .await;
+    response.assert_status(StatusCode::UNAUTHORIZED);
+
+    // Cleanup
+    sqlx::query("DELETE FROM login_failures WHERE email_hash = $1")
+        .bind(token_service::hash_token(&email))
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup");
+}
@@ -0,0 +1,244 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use mms_api::{auth::Role, router};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_move_and_merge_cards() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let pool = &state.pool;
+    let key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("cardmerge");
+    let user_id = common::db::create_verified_user(
+        pool,
+        &email,
+        &common::test_data::unique_username("cardmerge"),
+    )
+    .await
+    .expect("Failed to create user");
+    let learner = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+    let author = common::jwt::create_test_token_with_role(
+        user_id,
+        &email,
+        Role::Author,
+        &state.auth.jwt_keys,
+    );
+
+    let suffix = &Uuid::new_v4().simple().to_string()[..8];
+    let decks: Vec<Uuid> = sqlx::query_scalar(
+        r#"
+        INSERT INTO decks (title, language_from, language_to)
+        VALUES ('Merge A ' || $1, 'en', 'es'), ('Merge B ' || $1, 'en', 'es'), ('Merge C ' || $1, 'en', 'fr')
+        RETURNING id
+        "#,
+    )
+    .bind(suffix)
+    .fetch_all(pool)
+    .await
+    .expect("Failed to create decks");
+    let (deck_a, deck_b, deck_fr) = (decks[0], decks[1], decks[2]);
+
+    let card = |term: String, translation: &'static str, deck_id: Uuid| async move {
+        let id: Uuid = sqlx::query_scalar(
+            "INSERT INTO flashcards (term, translation, language_from, language_to) VALUES ($1, $2, 'en', 'es') RETURNING id",
+        )
+        .bind(term)
+        .bind(translation)
+        .fetch_one(pool)
+        .await
+        .expect("Failed to create card");
+        sqlx::query("INSERT INTO deck_flashcards (deck_id, flashcard_id) VALUES ($1, $2)")
+            .bind(deck_id)
+            .bind(id)
+            .execute(pool)
+            .await
+            .expect("Failed to link card");
+        id
+    };
+    let cat = card(format!("gato_{suffix}"), "cat", deck_a).await;
+    let kitty = card(format!("Gato_{suffix}"), "kitty", deck_b).await;
+    let dog = card(format!("perro_{suffix}"), "dog", deck_a).await;
+
+    // Another organization's deck is out of the author's reach
+    let org_id: Uuid =
+        sqlx::query_scalar("INSERT INTO organizations (name) VALUES ('Merge') RETURNING id")
+            .fetch_one(pool)
+            .await
+            .expect("Failed to create organization");
+    let deck_private: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to, org_id) VALUES ('Merge private ' || $1, 'en', 'es', $2) RETURNING id",
+    )
+    .bind(suffix)
+    .bind(org_id)
+    .fetch_one(pool)
+    .await
+    .expect("Failed to create private deck");
+    let secret = card(format!("secreto_{suffix}"), "secret", deck_private).await;
+
+    // The learner studied both duplicates and has started deck A
+    sqlx::query(
+        r#"
+        INSERT INTO user_card_progress (user_id, flashcard_id, next_review_at, last_review_at, times_correct, times_wrong)
+        VALUES ($1, $2, NOW() - INTERVAL '1 day', NOW() - INTERVAL '3 days', 3, 1),
+               ($1, $3, NOW() + INTERVAL '1 day', NOW() - INTERVAL '2 days', 2, 0)
+        "#,
+    )
+    .bind(user_id)
+    .bind(cat)
+    .bind(kitty)
+    .execute(pool)
+    .await
+    .expect("Failed to create progress");
+    sqlx::query("SELECT refresh_deck_progress($1, $2, 10)")
+        .bind(user_id)
+        .bind(deck_a)
+        .execute(pool)
+        .await
+        .expect("Failed to create deck progress");
+    let deck_total = |deck_id: Uuid| async move {
+        sqlx::query_scalar::<_, i32>(
+            "SELECT total_cards FROM user_deck_progress WHERE user_id = $1 AND deck_id = $2",
+        )
+        .bind(user_id)
+        .bind(deck_id)
+        .fetch_one(pool)
+        .await
+        .expect("Failed to read deck progress")
+    };
+    assert_eq!(deck_total(deck_a).await, 2);
+
+    // Moving the dog card to deck B
+    let uri = format!("/v1/admin/decks/{deck_a}/cards/move");
+    let move_to = |to_deck_id: Uuid, flashcard_ids: Vec<Uuid>| json!({ "to_deck_id": to_deck_id, "flashcard_ids": flashcard_ids });
+    client
+        .post_json_with_auth(&uri, &move_to(deck_b, vec![dog]), &learner, key)
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+    for (body, status) in [
+        (move_to(deck_a, vec![dog]), StatusCode::BAD_REQUEST),
+        (move_to(deck_fr, vec![dog]), StatusCode::BAD_REQUEST),
+        (move_to(deck_b, vec![]), StatusCode::BAD_REQUEST),
+        (move_to(deck_b, vec![kitty]), StatusCode::NOT_FOUND),
+        (move_to(Uuid::new_v4(), vec![dog]), StatusCode::NOT_FOUND),
+        (move_to(deck_private, vec![dog]), StatusCode::NOT_FOUND),
+    ] {
+        client
+            .post_json_with_auth(&uri, &body, &author, key)
+            .await
+            .assert_status(status);
+    }
+    let response = client
+        .post_json_with_auth(&uri, &move_to(deck_b, vec![dog, dog]), &author, key)
+        .await;
+    response.assert_status(StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["moved"], 1);
+    assert_eq!(body["already_in_target"], 0);
+    let dog_decks: Vec<Uuid> =
+        sqlx::query_scalar("SELECT deck_id FROM deck_flashcards WHERE flashcard_id = $1")
+            .bind(dog)
+            .fetch_all(pool)
+            .await
+            .unwrap();
+    assert_eq!(dog_decks, [deck_b]);
+    assert_eq!(deck_total(deck_a).await, 1);
+
+    // Merging kitty into cat
+    let merge = |source: Uuid, into: Uuid| {
+        let client = &client;
+        let author = &author;
+        async move {
+            client
+                .post_json_with_auth(
+                    &format!("/v1/admin/flashcards/{source}/merge"),
+                    &json!({ "into": into }),
+                    author,
+                    key,
+                )
+                .await
+        }
+    };
+    merge(cat, cat).await.assert_status(StatusCode::BAD_REQUEST);
+    merge(Uuid::new_v4(), cat)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    merge(secret, cat)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    merge(cat, secret)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    let response = merge(kitty, cat).await;
+    response.assert_status(StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["card"]["id"], cat.to_string());
+    assert_eq!(body["card"]["translation"], "cat");
+    assert_eq!(body["card"]["accepted_answers"], json!(["kitty"]));
+    assert_eq!(body["learners"], 1);
+    let mut merged_decks: Vec<Uuid> = body["decks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|id| id.as_str().unwrap().parse().unwrap())
+        .collect();
+    merged_decks.sort_unstable();
+    let mut expected = vec![deck_a, deck_b];
+    expected.sort_unstable();
+    assert_eq!(merged_decks, expected);
+
+    let kitty_left: bool =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM flashcards WHERE id = $1)")
+            .bind(kitty)
+            .fetch_one(pool)
+            .await
+            .unwrap();
+    assert!(!kitty_left);
+    let (times_correct, times_wrong, overdue): (i32, i32, bool) = sqlx::query_as(
+        "SELECT times_correct, times_wrong, next_review_at < NOW() FROM user_card_progress WHERE user_id = $1 AND flashcard_id = $2",
+    )
+    .bind(user_id)
+    .bind(cat)
+    .fetch_one(pool)
+    .await
+    .expect("Failed to read merged progress");
+    assert_eq!((times_correct, times_wrong, overdue), (5, 1, true));
+
+    // The merged card's translation is accepted from either deck
+    let response = client
+        .post_json_with_auth(
+            &format!("/v1/practice/{cat}/review"),
+            &json!({ "user_answer": "Kitty", "deck_id": deck_b }),
+            &learner,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["is_correct"], true);
+
+    sqlx::query("DELETE FROM decks WHERE id = ANY($1) OR id = $2")
+        .bind(&decks)
+        .bind(deck_private)
+        .execute(pool)
+        .await
+        .expect("Failed to cleanup decks");
+    sqlx::query("DELETE FROM organizations WHERE id = $1")
+        .bind(org_id)
+        .execute(pool)
+        .await
+        .expect("Failed to cleanup organization");
+    sqlx::query("DELETE FROM flashcards WHERE id = ANY($1)")
+        .bind([cat, dog, secret])
+        .execute(pool)
+        .await
+        .expect("Failed to cleanup cards");
+    common::db::delete_user_by_email(pool, &email)
+        .await
+        .expect("Failed to cleanup user");
+}
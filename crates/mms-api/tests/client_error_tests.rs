@@ -0,0 +1,109 @@
+use crate::common::{self, TestClient, TestStateBuilder, admin_request};
+use axum::http::StatusCode;
+use mms_api::{client_errors::Sampler, router};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_client_error_is_found_by_request_id() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let email = common::test_data::unique_email("crash");
+    let user_id = common::db::create_verified_user(
+        &state.pool,
+        &email,
+        &common::test_data::unique_username("crash"),
+    )
+    .await
+    .unwrap();
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+    let request_id = Uuid::new_v4().to_string();
+
+    client
+        .post_json_with_auth(
+            "/v1/client-errors",
+            &json!({
+                "request_id": request_id,
+                "platform": "web",
+                "app_version": "1.4.0",
+                "message": "TypeError: cards is undefined",
+                "stack": "at PracticeView (practice.tsx:42)",
+                "page": "/reset-password?token=secret",
+            }),
+            &token,
+            &state.cookie.cookie_key,
+        )
+        .await
+        .assert_status(StatusCode::ACCEPTED);
+
+    let response = client
+        .request(admin_request(
+            "GET",
+            &format!("/v1/admin/client-errors?request_id={request_id}"),
+        ))
+        .await;
+    response.assert_status(StatusCode::OK);
+    let reports: Vec<Value> = response.json();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0]["message"], "TypeError: cards is undefined");
+    assert_eq!(reports[0]["user_id"], user_id.to_string());
+    assert_eq!(reports[0]["platform"], "web");
+    // Query strings may carry tokens and are not stored
+    assert_eq!(reports[0]["page"], "/reset-password");
+
+    sqlx::query("DELETE FROM client_errors WHERE request_id = $1")
+        .bind(&request_id)
+        .execute(&state.pool)
+        .await
+        .unwrap();
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_client_errors_are_validated_and_sampled() {
+    let mut state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    // Nothing is stored once the hourly limit is reached
+    state.client_errors = Sampler::new(1.0, 0);
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let request_id = Uuid::new_v4().to_string();
+
+    client
+        .post_json(
+            "/v1/client-errors",
+            &json!({ "platform": "ios", "message": "  " }),
+        )
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    client
+        .post_json(
+            "/v1/client-errors",
+            &json!({ "platform": "ios", "message": "Crash", "request_id": "not an id" }),
+        )
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    // Reports from signed-out users are accepted, but this one is dropped
+    client
+        .post_json(
+            "/v1/client-errors",
+            &json!({ "platform": "android", "message": "Crash", "request_id": request_id }),
+        )
+        .await
+        .assert_status(StatusCode::ACCEPTED);
+
+    let stored: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM client_errors WHERE request_id = $1")
+            .bind(&request_id)
+            .fetch_one(&state.pool)
+            .await
+            .unwrap();
+    assert_eq!(stored, 0);
+}
@@ -5,10 +5,61 @@ use axum::{
 };
 use axum_extra::extract::cookie::Key;
 use http_body_util::BodyExt;
-use mms_api::{AuthConfig, CookieConfig, OidcConfig, config::Environment, state::ApiState};
+use mms_api::{
+    AuthConfig, CookieConfig, OidcConfig, auth::jwt::JwtKeys, config::Environment,
+    media::database::DatabaseStore, state::ApiState,
+};
+use mms_srs::clock::SystemClock;
 use serde::Deserialize;
+use std::sync::Arc;
 use tower::ServiceExt;
 
+/// Bearer token accepted by admin endpoints in tests
+pub const ADMIN_TOKEN: &str = "test_admin_token_minimum_32_characters_long";
+pub const WEBHOOK_SECRET: &str = "test_webhook_secret_minimum_32_characters";
+pub const FEED_TOKEN_SECRET: &str = "test_feed_token_secret_minimum_32_characters";
+
+/// A request without a body to an admin endpoint, signed with [`ADMIN_TOKEN`]
+pub fn admin_request(method: &str, uri: &str) -> Request<Body> {
+    Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("x-forwarded-for", "127.0.0.1")
+        .header("authorization", format!("Bearer {ADMIN_TOKEN}"))
+        .body(Body::empty())
+        .expect("Failed to build admin request")
+}
+
+/// `Cookie` header value carrying the session, as a browser would send it
+pub fn auth_cookie(key: &Key, token: &str) -> String {
+    use cookie::{CookieJar as RawCookieJar, Key as RawKey};
+
+    let raw_key = RawKey::try_from(key.master()).expect("Invalid key");
+    let mut raw_jar = RawCookieJar::new();
+    raw_jar
+        .private_mut(&raw_key)
+        .add(cookie::Cookie::new("auth_token", token.to_string()));
+    let encrypted = raw_jar.get("auth_token").expect("Cookie should exist");
+    format!("{}={}", encrypted.name(), encrypted.value())
+}
+
+/// A verified user and a session token for them
+pub struct Account {
+    pub id: uuid::Uuid,
+    pub email: String,
+    pub token: String,
+}
+
+/// Create a verified user with `role`, named after `prefix`
+pub async fn account(state: &ApiState, prefix: &str, role: mms_api::auth::Role) -> Account {
+    let email = test_data::unique_email(prefix);
+    let id = db::create_verified_user(&state.pool, &email, &test_data::unique_username(prefix))
+        .await
+        .expect("Failed to create user");
+    let token = jwt::create_test_token_with_role(id, &email, role, &state.auth.jwt_keys);
+    Account { id, email, token }
+}
+
 /// Test configuration
 pub struct TestConfig {
     pub database_url: String,
@@ -17,6 +68,7 @@ pub struct TestConfig {
     pub frontend_url: String,
     pub jwt_expiry_hours: i64,
     pub refresh_token_expiry_days: i64,
+    pub refresh_token_max_session_days: i64,
     pub oidc_flow_expiry_minutes: i64,
 }
 
@@ -32,6 +84,7 @@ impl Default for TestConfig {
             frontend_url: "http://localhost:8080".to_string(),
             jwt_expiry_hours: 24,
             refresh_token_expiry_days: 30,
+            refresh_token_max_session_days: 90,
             oidc_flow_expiry_minutes: 10,
         }
     }
@@ -70,10 +123,16 @@ impl TestStateBuilder {
 
         Ok(ApiState {
             auth: AuthConfig {
-                jwt_secret: self.config.jwt_secret.into(),
+                jwt_keys: JwtKeys::hmac(&self.config.jwt_secret),
+                feed_keys: JwtKeys::hmac(FEED_TOKEN_SECRET),
                 bcrypt_cost: 8,
                 jwt_expiry_hours: self.config.jwt_expiry_hours,
                 refresh_token_expiry_days: self.config.refresh_token_expiry_days,
+                refresh_token_sliding: true,
+                refresh_token_max_session_days: self.config.refresh_token_max_session_days,
+                login_captcha_threshold: 5,
+                admin_api_token: Some(ADMIN_TOKEN.into()),
+                email_webhook_secret: Some(WEBHOOK_SECRET.into()),
             },
             cookie: CookieConfig {
                 cookie_domain: "localhost".into(),
@@ -85,8 +144,20 @@ impl TestStateBuilder {
                 oidc_flow_expiry_minutes: self.config.oidc_flow_expiry_minutes,
                 frontend_url: self.config.frontend_url.into(),
             },
+            read_pool: pool.clone(),
+            media: Arc::new(DatabaseStore::new(pool.clone())),
             pool,
             email_tx: None, // No email worker in tests
+            mailer: None,
+            drain: Default::default(),
+            events: Default::default(),
+            captcha: None, // Captcha disabled unless a test installs a stub
+            speech: None,  // Pronunciation practice off unless a test installs a stub
+            public_cache: Default::default(),
+            health: Default::default(),
+            client_errors: Default::default(),
+            schedules: Default::default(),
+            clock: Arc::new(SystemClock),
         })
     }
 }
@@ -178,6 +249,21 @@ impl TestClient {
         self.request(request).await
     }
 
+    /// Send a PATCH request with JSON body
+    pub async fn patch_json<T: serde::Serialize>(&self, uri: &str, body: &T) -> TestResponse {
+        let json_body = serde_json::to_string(body).expect("Failed to serialize body");
+
+        let request = Request::builder()
+            .method("PATCH")
+            .uri(uri)
+            .header("content-type", "application/json")
+            .header("x-forwarded-for", "127.0.0.1") // Required for rate limiting in tests
+            .body(Body::from(json_body))
+            .expect("Failed to build request");
+
+        self.request(request).await
+    }
+
     /// Send a PATCH request with JSON body and authentication cookie
     pub async fn patch_json_with_auth<T: serde::Serialize>(
         &self,
@@ -185,6 +271,30 @@ impl TestClient {
         body: &T,
         token: &str,
         cookie_key: &Key,
+    ) -> TestResponse {
+        self.json_with_auth("PATCH", uri, body, token, cookie_key)
+            .await
+    }
+
+    /// Send a PUT request with JSON body and authentication cookie
+    pub async fn put_json_with_auth<T: serde::Serialize>(
+        &self,
+        uri: &str,
+        body: &T,
+        token: &str,
+        cookie_key: &Key,
+    ) -> TestResponse {
+        self.json_with_auth("PUT", uri, body, token, cookie_key)
+            .await
+    }
+
+    async fn json_with_auth<T: serde::Serialize>(
+        &self,
+        method: &str,
+        uri: &str,
+        body: &T,
+        token: &str,
+        cookie_key: &Key,
     ) -> TestResponse {
         use cookie::{CookieJar as RawCookieJar, Key as RawKey};
 
@@ -197,7 +307,7 @@ impl TestClient {
         let json_body = serde_json::to_string(body).expect("Failed to serialize body");
 
         let request = Request::builder()
-            .method("PATCH")
+            .method(method)
             .uri(uri)
             .header("content-type", "application/json")
             .header("x-forwarded-for", "127.0.0.1") // Required for rate limiting in tests
@@ -238,6 +348,18 @@ impl TestClient {
 
     /// Send a GET request with authentication cookie
     pub async fn get_with_auth(&self, uri: &str, token: &str, cookie_key: &Key) -> TestResponse {
+        self.get_with_auth_accepting(uri, token, cookie_key, "*/*")
+            .await
+    }
+
+    /// Send a GET request with authentication cookie and an `Accept` header
+    pub async fn get_with_auth_accepting(
+        &self,
+        uri: &str,
+        token: &str,
+        cookie_key: &Key,
+        accept: &str,
+    ) -> TestResponse {
         use cookie::{CookieJar as RawCookieJar, Key as RawKey};
 
         let raw_key = RawKey::try_from(cookie_key.master()).expect("Invalid key");
@@ -251,6 +373,7 @@ impl TestClient {
             .method("GET")
             .uri(uri)
             .header("x-forwarded-for", "127.0.0.1") // Required for rate limiting in tests
+            .header("accept", accept)
             .header(
                 "cookie",
                 format!("{}={}", encrypted.name(), encrypted.value()),
@@ -315,6 +438,19 @@ impl TestClient {
         body: &T,
         token: &str,
         cookie_key: &Key,
+    ) -> TestResponse {
+        self.post_json_with_auth_and_headers(uri, body, token, cookie_key, &[])
+            .await
+    }
+
+    /// Send a POST request with JSON body, authentication cookie and extra headers
+    pub async fn post_json_with_auth_and_headers<T: serde::Serialize>(
+        &self,
+        uri: &str,
+        body: &T,
+        token: &str,
+        cookie_key: &Key,
+        headers: &[(&str, &str)],
     ) -> TestResponse {
         use cookie::{CookieJar as RawCookieJar, Key as RawKey};
 
@@ -326,7 +462,7 @@ impl TestClient {
         let encrypted = raw_jar.get("auth_token").expect("Cookie should exist");
         let json_body = serde_json::to_string(body).expect("Failed to serialize body");
 
-        let request = Request::builder()
+        let mut request = Request::builder()
             .method("POST")
             .uri(uri)
             .header("content-type", "application/json")
@@ -334,7 +470,11 @@ impl TestClient {
             .header(
                 "cookie",
                 format!("{}={}", encrypted.name(), encrypted.value()),
-            )
+            );
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let request = request
             .body(Body::from(json_body))
             .expect("Failed to build authenticated request");
 
@@ -376,11 +516,11 @@ impl TestResponse {
     pub fn get_cookie(&self, name: &str) -> Option<String> {
         // Use get_all to handle multiple Set-Cookie headers
         for value in self.headers.get_all("set-cookie").iter() {
-            if let Ok(cookie_str) = value.to_str() {
-                if cookie_str.starts_with(&format!("{}=", name)) {
-                    let value = cookie_str.split(';').next()?.split('=').nth(1)?.to_string();
-                    return Some(value);
-                }
+            if let Ok(cookie_str) = value.to_str()
+                && cookie_str.starts_with(&format!("{}=", name))
+            {
+                let value = cookie_str.split(';').next()?.split('=').nth(1)?.to_string();
+                return Some(value);
             }
         }
         None
@@ -416,10 +556,7 @@ pub mod db {
         sqlx::query("DELETE FROM refresh_tokens")
             .execute(pool)
             .await?;
-        sqlx::query("DELETE FROM email_verification_tokens")
-            .execute(pool)
-            .await?;
-        sqlx::query("DELETE FROM password_reset_tokens")
+        sqlx::query("DELETE FROM one_time_tokens")
             .execute(pool)
             .await?;
         sqlx::query("DELETE FROM user_stats").execute(pool).await?;
@@ -521,12 +658,23 @@ pub mod db {
 
 /// JWT test helpers
 pub mod jwt {
-    use mms_api::auth::jwt::generate_jwt_token;
+    use mms_api::auth::Role;
+    use mms_api::auth::jwt::{JwtKeys, generate_jwt_token};
     use uuid::Uuid;
 
     /// Generate a test JWT token
-    pub fn create_test_token(user_id: Uuid, email: &str, jwt_secret: &str) -> String {
-        generate_jwt_token(user_id, email.to_string(), jwt_secret, 24)
+    pub fn create_test_token(user_id: Uuid, email: &str, jwt_keys: &JwtKeys) -> String {
+        create_test_token_with_role(user_id, email, Role::Learner, jwt_keys)
+    }
+
+    /// Generate a test JWT token carrying a role and its scopes
+    pub fn create_test_token_with_role(
+        user_id: Uuid,
+        email: &str,
+        role: Role,
+        jwt_keys: &JwtKeys,
+    ) -> String {
+        generate_jwt_token(user_id, email.to_string(), role, jwt_keys, 24)
             .expect("Failed to generate test JWT token")
     }
 }
@@ -576,3 +724,41 @@ pub mod verification {
             .map_err(|e| anyhow::anyhow!("Failed to create password reset token: {}", e))
     }
 }
+
+/// Captcha test helpers
+pub mod captcha {
+    use mms_api::captcha::{CaptchaVerifier, VerifyFuture};
+
+    /// Token accepted by [`StubCaptcha`]
+    pub const VALID_TOKEN: &str = "valid-captcha-token";
+
+    /// Captcha verifier accepting only [`VALID_TOKEN`], without network calls
+    #[derive(Debug)]
+    pub struct StubCaptcha;
+
+    impl CaptchaVerifier for StubCaptcha {
+        fn verify<'a>(&'a self, token: &'a str) -> VerifyFuture<'a> {
+            Box::pin(async move { Ok(token == VALID_TOKEN) })
+        }
+    }
+}
+
+/// Speech-to-text test helpers
+pub mod speech {
+    use mms_api::speech::{SpeechToText, TranscribeFuture};
+
+    /// Transcriber that "hears" the uploaded bytes as UTF-8 text, without network calls
+    #[derive(Debug)]
+    pub struct EchoSpeech;
+
+    impl SpeechToText for EchoSpeech {
+        fn transcribe<'a>(
+            &'a self,
+            audio: &'a [u8],
+            _content_type: &'a str,
+            _language: &'a str,
+        ) -> TranscribeFuture<'a> {
+            Box::pin(async move { Ok(String::from_utf8_lossy(audio).into_owned()) })
+        }
+    }
+}
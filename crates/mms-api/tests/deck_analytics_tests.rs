@@ -0,0 +1,171 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use mms_api::{auth::Role, router};
+use serde_json::Value;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_deck_analytics_aggregate_learners_after_the_nightly_run() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let pool = &state.pool;
+    let key = &state.cookie.cookie_key;
+
+    let mut emails = Vec::new();
+    let mut users = Vec::new();
+    for (prefix, role) in [
+        ("analytics_author", Role::Author),
+        ("analytics_learner", Role::Learner),
+    ] {
+        let email = common::test_data::unique_email(prefix);
+        let id = common::db::create_verified_user(
+            pool,
+            &email,
+            &common::test_data::unique_username(prefix),
+        )
+        .await
+        .expect("Failed to create user");
+        let token =
+            common::jwt::create_test_token_with_role(id, &email, role, &state.auth.jwt_keys);
+        emails.push(email);
+        users.push((id, token));
+    }
+    let (author_token, (learner_id, learner_token)) = (&users[0].1, &users[1]);
+
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('Analytics', 'fr', 'es') RETURNING id",
+    )
+    .fetch_one(pool)
+    .await
+    .expect("Failed to create deck");
+    let cards: Vec<Uuid> = sqlx::query_scalar(
+        r#"
+        INSERT INTO flashcards (term, translation, language_from, language_to)
+        SELECT 'analytics ' || n || ' ' || gen_random_uuid(), 'gato', 'fr', 'es' FROM generate_series(1, 3) AS n
+        RETURNING id
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .expect("Failed to create flashcards");
+    sqlx::query(
+        "INSERT INTO deck_flashcards (deck_id, flashcard_id) SELECT $1, unnest($2::uuid[])",
+    )
+    .bind(deck_id)
+    .bind(&cards)
+    .execute(pool)
+    .await
+    .expect("Failed to link flashcards");
+
+    // The first card is forgotten 4 times in 6, the second once in 5 and
+    // mastered 10 days after its first review; the third has too few answers
+    sqlx::query(
+        r#"
+        INSERT INTO user_card_progress (user_id, flashcard_id, times_correct, times_wrong, mastered_at)
+        VALUES ($1, $2, 3, 1, NULL), ($1, $3, 4, 0, NOW()), ($1, $4, 0, 2, NULL)
+        "#,
+    )
+    .bind(learner_id)
+    .bind(cards[0])
+    .bind(cards[1])
+    .bind(cards[2])
+    .execute(pool)
+    .await
+    .expect("Failed to seed progress");
+    sqlx::query(
+        r#"
+        INSERT INTO review_logs (user_id, flashcard_id, reviewed_at, is_correct, previous_interval_days, interval_days)
+        SELECT $1, $2, NOW(), n > 4, 3, 1 FROM generate_series(1, 6) AS n
+        UNION ALL SELECT $1, $3, NOW(), n > 1, 3, 7 FROM generate_series(1, 5) AS n
+        UNION ALL SELECT $1, $3, NOW() - INTERVAL '10 days', TRUE, NULL, 0.1
+        UNION ALL SELECT $1, $4, NOW(), FALSE, 3, 0.1 FROM generate_series(1, 2)
+        "#,
+    )
+    .bind(learner_id)
+    .bind(cards[0])
+    .bind(cards[1])
+    .bind(cards[2])
+    .execute(pool)
+    .await
+    .expect("Failed to log reviews");
+
+    let uri = format!("/v1/decks/{deck_id}/analytics");
+    let response = client.get_with_auth(&uri, author_token, key).await;
+    response.assert_status(StatusCode::OK);
+    let before = response.json::<Value>();
+    assert_eq!(before["cards"], 3);
+    assert_eq!(before["reviews"], 0);
+    assert!(before["computed_at"].is_null());
+
+    mms_api::deck::analytics::refresh(pool)
+        .await
+        .expect("Failed to refresh deck analytics");
+
+    let response = client.get_with_auth(&uri, author_token, key).await;
+    response.assert_status(StatusCode::OK);
+    let analytics = response.json::<Value>();
+    assert_eq!(analytics["reviews"], 10);
+    assert_eq!(analytics["accuracy"], 0.7);
+    assert_eq!(analytics["lapse_rate"], 7.0 / 13.0);
+    assert_eq!(analytics["mastered"], 1);
+    let days_to_master = analytics["average_days_to_master"].as_f64().unwrap();
+    assert!((days_to_master - 10.0).abs() < 0.01);
+    assert!(analytics["computed_at"].is_string());
+
+    let hardest = analytics["hardest_cards"].as_array().unwrap();
+    assert_eq!(hardest.len(), 2);
+    assert_eq!(hardest[0]["flashcard_id"], cards[0].to_string());
+    assert_eq!(hardest[0]["lapses"], 4);
+    assert_eq!(hardest[1]["flashcard_id"], cards[1].to_string());
+    assert_eq!(hardest[1]["lapse_rate"], 0.2);
+
+    // Learners cannot see analytics, and unknown decks are missing
+    client
+        .get_with_auth(&uri, learner_token, key)
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+    client
+        .get_with_auth(
+            &format!("/v1/decks/{}/analytics", Uuid::new_v4()),
+            author_token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    // Another organization's deck is missing too
+    let org_id: Uuid =
+        sqlx::query_scalar("INSERT INTO organizations (name) VALUES ('Analytics') RETURNING id")
+            .fetch_one(pool)
+            .await
+            .expect("Failed to create organization");
+    sqlx::query("UPDATE decks SET org_id = $2 WHERE id = $1")
+        .bind(deck_id)
+        .bind(org_id)
+        .execute(pool)
+        .await
+        .expect("Failed to move deck");
+    client
+        .get_with_auth(&uri, author_token, key)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    sqlx::query("DELETE FROM organizations WHERE id = $1")
+        .bind(org_id)
+        .execute(pool)
+        .await
+        .expect("Failed to cleanup organization");
+    sqlx::query("DELETE FROM flashcards WHERE id = ANY($1)")
+        .bind(&cards)
+        .execute(pool)
+        .await
+        .expect("Failed to cleanup flashcards");
+    for email in &emails {
+        common::db::delete_user_by_email(pool, email)
+            .await
+            .expect("Failed to cleanup user");
+    }
+}
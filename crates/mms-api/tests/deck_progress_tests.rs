@@ -0,0 +1,204 @@
+use crate::common::{self, TestClient, TestStateBuilder, admin_request};
+use axum::http::StatusCode;
+use mms_api::router;
+use mms_db::repositories::practice as practice_repo;
+use serde_json::Value;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Stored deck progress: total cards, mastered cards, practices and percentage
+type StoredProgress = (i32, i32, i32, f64);
+
+async fn insert_card(pool: &PgPool, deck_id: Uuid) -> Uuid {
+    let card_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO flashcards (term, translation, language_from, language_to) VALUES ($1, 'drift', 'en', 'es') RETURNING id",
+    )
+    .bind(format!("drift {}", Uuid::new_v4()))
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    sqlx::query("INSERT INTO deck_flashcards (deck_id, flashcard_id) VALUES ($1, $2)")
+        .bind(deck_id)
+        .bind(card_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    card_id
+}
+
+/// A deck of three cards the user has practised, with its progress refreshed
+async fn practised_deck(pool: &PgPool, user_id: Uuid) -> Uuid {
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('Drift', 'en', 'es') RETURNING id",
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    for times_correct in [3, 12, 0] {
+        let card_id = insert_card(pool, deck_id).await;
+        sqlx::query(
+            r#"
+            INSERT INTO user_card_progress
+                (user_id, flashcard_id, times_correct, times_wrong, last_review_at, mastered_at)
+            VALUES ($1, $2, $3, 1, NOW(), CASE WHEN $3 >= 10 THEN NOW() END)
+            "#,
+        )
+        .bind(user_id)
+        .bind(card_id)
+        .bind(times_correct)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+    practice_repo::refresh_deck_progress(pool, user_id, deck_id, mms_srs::MASTERY_THRESHOLD)
+        .await
+        .unwrap();
+    deck_id
+}
+
+async fn stored(pool: &PgPool, user_id: Uuid, deck_id: Uuid) -> StoredProgress {
+    sqlx::query_as(
+        r#"
+        SELECT total_cards, mastered_cards, total_practices, progress_percentage::float8
+        FROM user_deck_progress
+        WHERE user_id = $1 AND deck_id = $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(deck_id)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+async fn delete_deck(pool: &PgPool, deck_id: Uuid) {
+    let card_ids: Vec<Uuid> =
+        sqlx::query_scalar("SELECT flashcard_id FROM deck_flashcards WHERE deck_id = $1")
+            .bind(deck_id)
+            .fetch_all(pool)
+            .await
+            .unwrap();
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM flashcards WHERE id = ANY($1)")
+        .bind(&card_ids)
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_drifted_deck_progress_converges() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let pool = &state.pool;
+    let email = common::test_data::unique_email("drift");
+    let user_id = common::db::create_verified_user(
+        pool,
+        &email,
+        &common::test_data::unique_username("drift"),
+    )
+    .await
+    .unwrap();
+    let deck_id = practised_deck(pool, user_id).await;
+    let consistent = stored(pool, user_id, deck_id).await;
+    assert_eq!(consistent.0, 3);
+    assert_eq!(consistent.1, 1);
+
+    // Consistent rows are left alone
+    let corrected =
+        practice_repo::recompute_deck_progress(pool, Some(user_id), mms_srs::MASTERY_THRESHOLD)
+            .await
+            .unwrap();
+    assert_eq!(corrected, 0);
+
+    // A card added to the deck without a review, and a corrupted counter
+    insert_card(pool, deck_id).await;
+    sqlx::query(
+        "UPDATE user_deck_progress SET mastered_cards = 7 WHERE user_id = $1 AND deck_id = $2",
+    )
+    .bind(user_id)
+    .bind(deck_id)
+    .execute(pool)
+    .await
+    .unwrap();
+
+    let corrected =
+        practice_repo::recompute_deck_progress(pool, Some(user_id), mms_srs::MASTERY_THRESHOLD)
+            .await
+            .unwrap();
+    assert_eq!(corrected, 1);
+    let recomputed = stored(pool, user_id, deck_id).await;
+
+    // The recompute agrees with the per-review refresh, and a second run finds nothing
+    practice_repo::refresh_deck_progress(pool, user_id, deck_id, mms_srs::MASTERY_THRESHOLD)
+        .await
+        .unwrap();
+    assert_eq!(stored(pool, user_id, deck_id).await, recomputed);
+    assert_eq!(recomputed.0, 4);
+    assert_eq!(recomputed.1, 1);
+    let corrected =
+        practice_repo::recompute_deck_progress(pool, Some(user_id), mms_srs::MASTERY_THRESHOLD)
+            .await
+            .unwrap();
+    assert_eq!(corrected, 0, "The progress should already be consistent");
+
+    delete_deck(pool, deck_id).await;
+    common::db::delete_user_by_email(pool, &email)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_admin_recomputes_a_users_deck_progress() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let pool = state.pool.clone();
+    let client = TestClient::new(router::router().with_state(state));
+    let email = common::test_data::unique_email("drift_admin");
+    let user_id = common::db::create_verified_user(
+        &pool,
+        &email,
+        &common::test_data::unique_username("drift_admin"),
+    )
+    .await
+    .unwrap();
+    let deck_id = practised_deck(&pool, user_id).await;
+    let consistent = stored(&pool, user_id, deck_id).await;
+
+    sqlx::query("UPDATE user_deck_progress SET total_cards = 0, progress_percentage = 99 WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let recompute = |user_id: Uuid| {
+        admin_request(
+            "POST",
+            &format!("/v1/admin/users/{user_id}/deck-progress/recompute"),
+        )
+    };
+
+    let response = client.request(recompute(user_id)).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json::<Value>()["corrected"], 1);
+    assert_eq!(stored(&pool, user_id, deck_id).await, consistent);
+
+    let response = client.request(recompute(user_id)).await;
+    assert_eq!(response.json::<Value>()["corrected"], 0);
+
+    let response = client.request(recompute(Uuid::new_v4())).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    delete_deck(&pool, deck_id).await;
+    common::db::delete_user_by_email(&pool, &email)
+        .await
+        .unwrap();
+}
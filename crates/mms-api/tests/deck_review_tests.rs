@@ -0,0 +1,303 @@
+use crate::common::{self, TestClient, TestStateBuilder, account};
+use axum::http::StatusCode;
+use mms_api::{auth::Role, router, state::ApiState};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+async fn public_deck(state: &ApiState, title: &str) -> Uuid {
+    sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ($1, 'fr', 'es') RETURNING id",
+    )
+    .bind(title)
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to create deck")
+}
+
+#[tokio::test]
+async fn test_ratings_are_aggregated_on_the_deck_and_sort_the_catalogue() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let key = &state.cookie.cookie_key;
+
+    let first = account(&state, "rating_first", Role::Learner).await;
+    let second = account(&state, "rating_second", Role::Learner).await;
+    let liked = public_deck(&state, "Liked").await;
+    let disliked = public_deck(&state, "Disliked").await;
+
+    for (rater, deck_id, stars) in [
+        (&first, liked, 5),
+        (&second, liked, 2),
+        (&first, disliked, 2),
+    ] {
+        client
+            .put_json_with_auth(
+                &format!("/v1/decks/{deck_id}/rating"),
+                &json!({ "stars": stars }),
+                &rater.token,
+                key,
+            )
+            .await
+            .assert_status(StatusCode::OK);
+    }
+
+    // Rating again replaces the earlier rating
+    let response = client
+        .put_json_with_auth(
+            &format!("/v1/decks/{liked}/rating"),
+            &json!({ "stars": 4 }),
+            &second.token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let rating = response.json::<Value>();
+    assert_eq!(rating["rating_count"], 2);
+    assert_eq!(rating["rating_average"], 4.5);
+    assert_eq!(rating["my_stars"], 4);
+
+    client
+        .put_json_with_auth(
+            &format!("/v1/decks/{liked}/rating"),
+            &json!({ "stars": 6 }),
+            &second.token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    let response = client
+        .get("/v1/decks?sort=rating&language_from=fr&language_to=es&limit=100")
+        .await;
+    response.assert_status(StatusCode::OK);
+    let ids: Vec<String> = response
+        .json::<Vec<Value>>()
+        .iter()
+        .map(|deck| deck["id"].as_str().unwrap().to_string())
+        .collect();
+    let position = |deck_id: Uuid| ids.iter().position(|id| *id == deck_id.to_string());
+    assert!(position(liked).unwrap() < position(disliked).unwrap());
+
+    client
+        .get("/v1/decks?language_from=fr")
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    // Withdrawn ratings and deleted accounts leave the totals
+    client
+        .delete_with_auth(&format!("/v1/decks/{disliked}/rating"), &first.token, key)
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    common::db::delete_user_by_email(&state.pool, &second.email)
+        .await
+        .expect("Failed to cleanup");
+
+    let response = client
+        .get_with_auth(&format!("/v1/decks/{disliked}/rating"), &first.token, key)
+        .await;
+    response.assert_status(StatusCode::OK);
+    let rating = response.json::<Value>();
+    assert_eq!(rating["rating_count"], 0);
+    assert!(rating["rating_average"].is_null());
+    assert!(rating["my_stars"].is_null());
+
+    let (count, average): (i32, Option<f64>) =
+        sqlx::query_as("SELECT rating_count, rating_average FROM decks WHERE id = $1")
+            .bind(liked)
+            .fetch_one(&state.pool)
+            .await
+            .expect("Failed to read deck");
+    assert_eq!((count, average), (1, Some(5.0)));
+
+    sqlx::query("DELETE FROM decks WHERE id = ANY($1)")
+        .bind(vec![liked, disliked])
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup decks");
+    common::db::delete_user_by_email(&state.pool, &first.email)
+        .await
+        .expect("Failed to cleanup");
+}
+
+#[tokio::test]
+async fn test_comments_are_moderated_by_authors() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let key = &state.cookie.cookie_key;
+
+    let commenter = account(&state, "comment_learner", Role::Learner).await;
+    let other = account(&state, "comment_other", Role::Learner).await;
+    let author = account(&state, "comment_author", Role::Author).await;
+    let deck_id = public_deck(&state, "Discussed").await;
+
+    let response = client
+        .post_json_with_auth(
+            &format!("/v1/decks/{deck_id}/comments"),
+            &json!({ "body": "  Great for beginners  " }),
+            &commenter.token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::CREATED);
+    let comment = response.json::<Value>();
+    assert_eq!(comment["body"], "Great for beginners");
+    let comment_id = comment["id"].as_str().unwrap().to_string();
+    let comment_path = format!("/v1/decks/{deck_id}/comments/{comment_id}");
+
+    client
+        .put_json_with_auth(
+            &comment_path,
+            &json!({ "body": "Not mine" }),
+            &other.token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+    client
+        .delete_with_auth(&comment_path, &other.token, key)
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+
+    let response = client
+        .put_json_with_auth(
+            &comment_path,
+            &json!({ "body": "Great for beginners, a bit short" }),
+            &commenter.token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    assert_eq!(
+        response.json::<Value>()["body"],
+        "Great for beginners, a bit short"
+    );
+
+    let comments_path = format!("/v1/decks/{deck_id}/comments");
+    let response = client.get(&comments_path).await;
+    response.assert_status(StatusCode::OK);
+    assert_eq!(response.json::<Vec<Value>>().len(), 1);
+
+    // Only authors can hide comments, and hidden comments leave the listing
+    let hidden_path = format!("{comment_path}/hidden");
+    client
+        .put_json_with_auth(&hidden_path, &json!({}), &other.token, key)
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+    client
+        .put_json_with_auth(&hidden_path, &json!({}), &author.token, key)
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    assert!(
+        client
+            .get(&comments_path)
+            .await
+            .json::<Vec<Value>>()
+            .is_empty()
+    );
+
+    client
+        .delete_with_auth(&hidden_path, &author.token, key)
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    assert_eq!(
+        client.get(&comments_path).await.json::<Vec<Value>>().len(),
+        1
+    );
+
+    client
+        .delete_with_auth(&comment_path, &author.token, key)
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    assert!(
+        client
+            .get(&comments_path)
+            .await
+            .json::<Vec<Value>>()
+            .is_empty()
+    );
+
+    client
+        .post_json_with_auth(
+            &comments_path,
+            &json!({ "body": "   " }),
+            &commenter.token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup deck");
+    for account in [commenter, other, author] {
+        common::db::delete_user_by_email(&state.pool, &account.email)
+            .await
+            .expect("Failed to cleanup");
+    }
+}
+
+#[tokio::test]
+async fn test_organization_decks_cannot_be_rated_or_discussed() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let key = &state.cookie.cookie_key;
+
+    let learner = account(&state, "rating_private", Role::Learner).await;
+    let org_id: Uuid =
+        sqlx::query_scalar("INSERT INTO organizations (name) VALUES ('Class') RETURNING id")
+            .fetch_one(&state.pool)
+            .await
+            .expect("Failed to create organization");
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to, org_id) VALUES ('Class deck', 'fr', 'es', $1) RETURNING id",
+    )
+    .bind(org_id)
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to create deck");
+
+    client
+        .put_json_with_auth(
+            &format!("/v1/decks/{deck_id}/rating"),
+            &json!({ "stars": 5 }),
+            &learner.token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    client
+        .get(&format!("/v1/decks/{deck_id}/comments"))
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    let response = client
+        .get("/v1/decks?language_from=fr&language_to=es&limit=100")
+        .await;
+    response.assert_status(StatusCode::OK);
+    assert!(
+        !response
+            .json::<Vec<Value>>()
+            .iter()
+            .any(|deck| deck["id"] == deck_id.to_string())
+    );
+
+    sqlx::query("DELETE FROM organizations WHERE id = $1")
+        .bind(org_id)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup organization");
+    common::db::delete_user_by_email(&state.pool, &learner.email)
+        .await
+        .expect("Failed to cleanup");
+}
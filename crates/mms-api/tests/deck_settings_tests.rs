@@ -0,0 +1,170 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use chrono::{DateTime, Duration, Utc};
+use mms_api::{auth::Role, router};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_deck_settings_cap_the_next_interval() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let pool = &state.pool;
+    let key = &state.cookie.cookie_key;
+
+    let mut emails = Vec::new();
+    let mut users = Vec::new();
+    for (prefix, role) in [
+        ("settings_author", Role::Author),
+        ("settings_learner", Role::Learner),
+    ] {
+        let email = common::test_data::unique_email(prefix);
+        let id = common::db::create_verified_user(
+            pool,
+            &email,
+            &common::test_data::unique_username(prefix),
+        )
+        .await
+        .expect("Failed to create user");
+        let token =
+            common::jwt::create_test_token_with_role(id, &email, role, &state.auth.jwt_keys);
+        emails.push(email);
+        users.push((id, token));
+    }
+    let (author_token, (learner_id, learner_token)) = (&users[0].1, &users[1]);
+
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('Settings', 'en', 'es') RETURNING id",
+    )
+    .fetch_one(pool)
+    .await
+    .expect("Failed to create deck");
+    let card_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO flashcards (term, translation, language_from, language_to) VALUES ('settings ' || gen_random_uuid(), 'gato', 'en', 'es') RETURNING id",
+    )
+    .fetch_one(pool)
+    .await
+    .expect("Failed to create flashcard");
+    sqlx::query("INSERT INTO deck_flashcards (deck_id, flashcard_id) VALUES ($1, $2)")
+        .bind(deck_id)
+        .bind(card_id)
+        .execute(pool)
+        .await
+        .expect("Failed to add flashcard");
+    // A well known card, which would otherwise come back in two months
+    sqlx::query(
+        "INSERT INTO user_card_progress (user_id, flashcard_id, next_review_at, times_correct) VALUES ($1, $2, NOW() - INTERVAL '1 hour', 8)",
+    )
+    .bind(learner_id)
+    .bind(card_id)
+    .execute(pool)
+    .await
+    .expect("Failed to seed progress");
+
+    let uri = format!("/v1/decks/{deck_id}/settings");
+    let response = client.get_with_auth(&uri, learner_token, key).await;
+    response.assert_status(StatusCode::OK);
+    let body: Value = response.json();
+    assert!(body["settings"]["updated_at"].is_null());
+    assert_eq!(
+        body["effective"],
+        json!({ "max_interval_days": 90, "starting_ease": 1.0, "new_card_order": "easiest_first" })
+    );
+
+    // Only authors change settings, within range
+    let settings = json!({ "max_interval_days": 3, "new_card_order": "added" });
+    client
+        .put_json_with_auth(&uri, &settings, learner_token, key)
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+    for invalid in [
+        json!({ "max_interval_days": 0 }),
+        json!({ "starting_ease": 3.0 }),
+    ] {
+        client
+            .put_json_with_auth(&uri, &invalid, author_token, key)
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+    client
+        .put_json_with_auth(
+            &uri,
+            &json!({ "new_card_order": "alphabetical" }),
+            author_token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    let response = client
+        .put_json_with_auth(&uri, &settings, author_token, key)
+        .await;
+    response.assert_status(StatusCode::OK);
+    let saved: Value = response.json();
+    assert_eq!(saved["max_interval_days"], 3);
+    assert!(saved["starting_ease"].is_null());
+    assert_eq!(saved["new_card_order"], "added");
+
+    let before = Utc::now();
+    client
+        .post_json_with_auth(
+            &format!("/v1/practice/{card_id}/review"),
+            &json!({ "user_answer": "gato", "deck_id": deck_id }),
+            learner_token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::OK);
+    let next_review_at: DateTime<Utc> = sqlx::query_scalar(
+        "SELECT next_review_at FROM user_card_progress WHERE user_id = $1 AND flashcard_id = $2",
+    )
+    .bind(learner_id)
+    .bind(card_id)
+    .fetch_one(pool)
+    .await
+    .expect("Failed to read progress");
+    assert!(next_review_at >= before + Duration::days(3) - Duration::minutes(1));
+    assert!(next_review_at <= Utc::now() + Duration::days(3));
+
+    // The learner's own cap applies when shorter
+    client
+        .patch_json_with_auth(
+            "/v1/users/me/preferences",
+            &json!({ "max_interval_days": 1 }),
+            learner_token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::OK);
+    let body: Value = client.get_with_auth(&uri, learner_token, key).await.json();
+    assert_eq!(body["settings"]["max_interval_days"], 3);
+    assert_eq!(body["effective"]["max_interval_days"], 1);
+    assert_eq!(body["effective"]["new_card_order"], "added");
+
+    client
+        .get_with_auth(
+            &format!("/v1/decks/{}/settings", Uuid::new_v4()),
+            learner_token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(pool)
+        .await
+        .expect("Failed to cleanup deck");
+    sqlx::query("DELETE FROM flashcards WHERE id = $1")
+        .bind(card_id)
+        .execute(pool)
+        .await
+        .expect("Failed to cleanup flashcard");
+    for email in &emails {
+        common::db::delete_user_by_email(pool, email)
+            .await
+            .expect("Failed to cleanup user");
+    }
+}
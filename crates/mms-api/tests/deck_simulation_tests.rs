@@ -0,0 +1,121 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use mms_api::router;
+use serde_json::Value;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_deck_simulation_starts_from_the_users_progress() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let pool = &state.pool;
+    let key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("simulate");
+    let user_id = common::db::create_verified_user(
+        pool,
+        &email,
+        &common::test_data::unique_username("simulate"),
+    )
+    .await
+    .expect("Failed to create user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('Simulation', 'en', 'es') RETURNING id",
+    )
+    .fetch_one(pool)
+    .await
+    .expect("Failed to create deck");
+    let cards: Vec<Uuid> = sqlx::query_scalar(
+        r#"
+        INSERT INTO flashcards (term, translation, language_from, language_to)
+        SELECT 'simulate ' || n || ' ' || gen_random_uuid(), 'si', 'en', 'es' FROM generate_series(1, 3) AS n
+        RETURNING id
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .expect("Failed to create flashcards");
+    sqlx::query(
+        "INSERT INTO deck_flashcards (deck_id, flashcard_id) SELECT $1, UNNEST($2::uuid[])",
+    )
+    .bind(deck_id)
+    .bind(&cards)
+    .execute(pool)
+    .await
+    .expect("Failed to add flashcards");
+    // One card studied and due now; the other two are new
+    sqlx::query(
+        "INSERT INTO user_card_progress (user_id, flashcard_id, next_review_at, times_correct) VALUES ($1, $2, NOW() - INTERVAL '1 day', 5)",
+    )
+    .bind(user_id)
+    .bind(cards[0])
+    .execute(pool)
+    .await
+    .expect("Failed to seed progress");
+
+    let uri = format!("/v1/decks/{deck_id}/simulate");
+    let response = client
+        .get_with_auth(&format!("{uri}?days=10"), &token, key)
+        .await;
+    response.assert_status(StatusCode::OK);
+    let simulation: Value = response.json();
+    assert_eq!(simulation["studied_cards"], 1);
+    assert_eq!(simulation["new_cards"], 2);
+    assert_eq!(simulation["new_cards_per_day"], 20);
+    assert_eq!(simulation["config"]["max_interval_days"], 90);
+    let days = simulation["days"].as_array().unwrap();
+    assert_eq!(days.len(), 10);
+    assert_eq!(days[0]["new_cards"], 2);
+    assert_eq!(days[0]["reviews"], 1.0);
+    assert_eq!(days[0]["retention"], 0.9);
+    assert_eq!(days[1]["new_cards"], 0);
+    let total_reviews = simulation["total_reviews"].as_f64().unwrap();
+
+    // A shorter cap costs more reviews
+    let response = client
+        .get_with_auth(
+            &format!("{uri}?days=10&max_interval_days=1&recall=1"),
+            &token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let capped: Value = response.json();
+    assert_eq!(capped["config"]["max_interval_days"], 1);
+    assert_eq!(capped["retention"], 1.0);
+    assert!(capped["total_reviews"].as_f64().unwrap() > total_reviews);
+
+    for invalid in ["days=0", "days=366", "recall=1.5", "starting_ease=3"] {
+        client
+            .get_with_auth(&format!("{uri}?{invalid}"), &token, key)
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+    client
+        .get_with_auth(
+            &format!("/v1/decks/{}/simulate", Uuid::new_v4()),
+            &token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(pool)
+        .await
+        .expect("Failed to cleanup deck");
+    sqlx::query("DELETE FROM flashcards WHERE id = ANY($1)")
+        .bind(&cards)
+        .execute(pool)
+        .await
+        .expect("Failed to cleanup flashcards");
+    common::db::delete_user_by_email(pool, &email)
+        .await
+        .expect("Failed to cleanup user");
+}
@@ -0,0 +1,127 @@
+use crate::common::{self, TestStateBuilder};
+use mms_api::error::ApiError;
+use mms_api::mailer::outbox::{self, MAX_ATTEMPTS};
+use mms_api::mailer::{EmailSender, OutgoingEmail, SendFuture};
+use sqlx::PgPool;
+use std::sync::Mutex;
+
+/// Fails while `failing` is set, otherwise records what it sends
+#[derive(Debug, Default)]
+struct StubSender {
+    failing: Mutex<bool>,
+    sent: Mutex<Vec<OutgoingEmail>>,
+}
+
+impl StubSender {
+    fn set_failing(&self, failing: bool) {
+        *self.failing.lock().unwrap() = failing;
+    }
+
+    fn sent_to(&self, to: &str) -> usize {
+        self.sent
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|email| email.to == to)
+            .count()
+    }
+}
+
+impl EmailSender for StubSender {
+    fn send<'a>(&'a self, email: &'a OutgoingEmail) -> SendFuture<'a> {
+        Box::pin(async move {
+            if *self.failing.lock().unwrap() {
+                return Err(ApiError::Email("provider unavailable".to_string()));
+            }
+            self.sent.lock().unwrap().push(email.clone());
+            Ok(())
+        })
+    }
+}
+
+fn email_to(to: &str) -> OutgoingEmail {
+    OutgoingEmail {
+        to: to.to_string(),
+        subject: "Hello".to_string(),
+        body: "Hi there".to_string(),
+    }
+}
+
+/// Status and attempts of the outbox row for `subject`
+async fn outbox_row(pool: &PgPool, subject: &str) -> (String, i32) {
+    sqlx::query_as("SELECT status, attempts FROM email_outbox WHERE subject = $1")
+        .bind(subject)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+/// Make every pending retry of `subject` due now
+async fn make_due(pool: &PgPool, subject: &str) {
+    sqlx::query("UPDATE email_outbox SET next_attempt_at = NOW() WHERE subject = $1")
+        .bind(subject)
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_failed_email_is_retried_from_outbox() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let sender = StubSender::default();
+    let to = common::test_data::unique_email("outbox");
+    let mut email = email_to(&to);
+    email.subject = format!("Retry {to}");
+
+    // The first attempt fails and is scheduled for a retry, not immediately due
+    sender.set_failing(true);
+    outbox::deliver(&state.pool, &sender, &email).await;
+    assert_eq!(
+        outbox_row(&state.pool, &email.subject).await,
+        ("pending".to_string(), 1)
+    );
+    outbox::retry_due(&state.pool, &sender).await.unwrap();
+    assert_eq!(outbox_row(&state.pool, &email.subject).await.1, 1);
+
+    // Once due and the provider recovers, the retry goes out
+    sender.set_failing(false);
+    make_due(&state.pool, &email.subject).await;
+    outbox::retry_due(&state.pool, &sender).await.unwrap();
+    assert_eq!(sender.sent_to(&to), 1);
+    assert_eq!(
+        outbox_row(&state.pool, &email.subject).await,
+        ("sent".to_string(), 2)
+    );
+
+    // An email that keeps failing is given up on after the last attempt.
+    // Both cases run in one test because retries claim every due email.
+    let to = common::test_data::unique_email("outbox_fail");
+    let mut failing = email_to(&to);
+    failing.subject = format!("Give up {to}");
+
+    sender.set_failing(true);
+    outbox::deliver(&state.pool, &sender, &failing).await;
+    for _ in 1..MAX_ATTEMPTS {
+        make_due(&state.pool, &failing.subject).await;
+        outbox::retry_due(&state.pool, &sender).await.unwrap();
+    }
+    assert_eq!(
+        outbox_row(&state.pool, &failing.subject).await,
+        ("failed".to_string(), MAX_ATTEMPTS)
+    );
+
+    // Failed emails are never claimed again
+    sender.set_failing(false);
+    make_due(&state.pool, &failing.subject).await;
+    outbox::retry_due(&state.pool, &sender).await.unwrap();
+    assert_eq!(sender.sent_to(&to), 0);
+
+    sqlx::query("DELETE FROM email_outbox WHERE subject = ANY($1)")
+        .bind(vec![email.subject.clone(), failing.subject.clone()])
+        .execute(&state.pool)
+        .await
+        .unwrap();
+}
@@ -0,0 +1,38 @@
+use crate::common::{TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use mms_api::mailer::preview;
+use serde_json::Value;
+
+#[tokio::test]
+async fn test_email_preview_renders_in_requested_locale() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let client = TestClient::new(preview::routes().with_state(state));
+
+    let response = client.get("/dev/emails/password_reset?locale=es-MX").await;
+    response.assert_status(StatusCode::OK);
+    let preview: Value = response.json();
+    assert_eq!(preview["locale"], "es");
+    assert_eq!(
+        preview["subject"],
+        "Restablece tu contraseña de Matcha Time"
+    );
+    assert!(
+        preview["body"]
+            .as_str()
+            .unwrap()
+            .contains("/reset-password?token=sample-reset-token")
+    );
+
+    // Unknown locales fall back to English
+    let response = client.get("/dev/emails/weekly_digest?locale=fr").await;
+    response.assert_status(StatusCode::OK);
+    let preview: Value = response.json();
+    assert_eq!(preview["locale"], "en");
+    assert_eq!(preview["subject"], "Your week on Matcha Time: 42 reviews");
+
+    let response = client.get("/dev/emails/newsletter").await;
+    assert!(response.status.is_client_error());
+}
@@ -123,8 +123,8 @@ async fn test_email_verification_expired_token() {
     let expired_token = "expired_token_hash_12345678";
     sqlx::query(
         r#"
-        INSERT INTO email_verification_tokens (user_id, token_hash, expires_at, created_at)
-        VALUES ($1, $2, NOW() - INTERVAL '1 day', NOW() - INTERVAL '2 days')
+        INSERT INTO one_time_tokens (user_id, purpose, token_hash, expires_at, created_at)
+        VALUES ($1, 'email_verification', $2, NOW() - INTERVAL '1 day', NOW() - INTERVAL '2 days')
         "#,
     )
     .bind(user_id)
@@ -143,7 +143,7 @@ async fn test_email_verification_expired_token() {
 
     let json: serde_json::Value = response.json();
     assert!(
-        json["error"]
+        json["message"]
             .as_str()
             .unwrap()
             .contains("Invalid or expired verification token")
@@ -155,6 +155,53 @@ async fn test_email_verification_expired_token() {
         .expect("Failed to cleanup");
 }
 
+#[tokio::test]
+async fn test_email_verification_rejects_password_reset_token() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+
+    let email = common::test_data::unique_email("scopedtoken");
+    let username = common::test_data::unique_username("scoped");
+    let user_id = common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+
+    // Issue a password reset token and try to redeem it as a verification token
+    let reset_token = common::verification::create_test_password_reset_token(&state.pool, user_id)
+        .await
+        .expect("Failed to create reset token");
+
+    let response = client
+        .get(&format!("/v1/users/verify-email?token={}", reset_token))
+        .await;
+    response.assert_status(StatusCode::UNAUTHORIZED);
+
+    // The reset token must still be usable for its own purpose
+    let still_valid: bool = sqlx::query_scalar(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM one_time_tokens
+            WHERE user_id = $1 AND purpose = 'password_reset' AND used_at IS NULL
+        )
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to query tokens");
+    assert!(still_valid, "Reset token should not be consumed");
+
+    // Cleanup
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup");
+}
+
 #[tokio::test]
 async fn test_email_verification_already_used_token() {
     let state = TestStateBuilder::new()
@@ -198,7 +245,7 @@ async fn test_email_verification_already_used_token() {
 
     let json: serde_json::Value = second_response.json();
     assert!(
-        json["error"]
+        json["message"]
             .as_str()
             .unwrap()
             .contains("Invalid or expired verification token")
@@ -229,7 +276,7 @@ async fn test_email_verification_invalid_token_format() {
 
     let json: serde_json::Value = response.json();
     assert!(
-        json["error"]
+        json["message"]
             .as_str()
             .unwrap()
             .contains("Invalid or expired verification token")
@@ -265,8 +312,9 @@ async fn test_resend_verification_email_success() {
     let _tokens_before: i64 = sqlx::query_scalar(
         r#"
         SELECT COUNT(*)
-        FROM email_verification_tokens
+        FROM one_time_tokens
         WHERE user_id = (SELECT id FROM users WHERE email = $1)
+        AND purpose = 'email_verification'
         AND used_at IS NULL
         "#,
     )
@@ -288,8 +336,9 @@ async fn test_resend_verification_email_success() {
     let tokens_after: i64 = sqlx::query_scalar(
         r#"
         SELECT COUNT(*)
-        FROM email_verification_tokens
+        FROM one_time_tokens
         WHERE user_id = (SELECT id FROM users WHERE email = $1)
+        AND purpose = 'email_verification'
         AND used_at IS NULL
         "#,
     )
@@ -388,7 +437,7 @@ async fn test_resend_verification_invalid_email_format() {
 
     let json: serde_json::Value = response.json();
     assert!(
-        json["error"]
+        json["message"]
             .as_str()
             .unwrap()
             .to_lowercase()
@@ -0,0 +1,188 @@
+use crate::common::{self, TestClient, TestStateBuilder, WEBHOOK_SECRET, admin_request};
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use mms_api::jobs::queue::{self, JobContext};
+use mms_api::mailer::{EmailSender, OutgoingEmail, SendFuture, outbox};
+use mms_api::router;
+use serde_json::{Value, json};
+use std::sync::Mutex;
+
+/// Records what it sends
+#[derive(Debug, Default)]
+struct RecordingSender {
+    sent: Mutex<Vec<String>>,
+}
+
+impl EmailSender for RecordingSender {
+    fn send<'a>(&'a self, email: &'a OutgoingEmail) -> SendFuture<'a> {
+        Box::pin(async move {
+            self.sent.lock().unwrap().push(email.to.clone());
+            Ok(())
+        })
+    }
+}
+
+/// An SNS delivery, which carries credentials from the subscribed URL as basic auth
+fn sns_delivery(uri: &str, body: &Value) -> Request<Body> {
+    let credentials = STANDARD.encode(format!("webhook:{WEBHOOK_SECRET}"));
+    Request::builder()
+        .method("POST")
+        .uri(uri)
+        .header("content-type", "text/plain; charset=UTF-8")
+        .header("authorization", format!("Basic {credentials}"))
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_bounced_address_is_suppressed_until_support_clears_it() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let email = common::test_data::unique_email("bounce");
+    let user_id = common::db::create_verified_user(
+        &state.pool,
+        &email,
+        &common::test_data::unique_username("bounce"),
+    )
+    .await
+    .unwrap();
+
+    // A wrong token is rejected without recording anything
+    let events = json!([{ "email": email, "event": "bounce", "type": "bounce", "reason": "550 no such user" }]);
+    client
+        .post_json("/v1/webhooks/email/sendgrid?token=wrong", &events)
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+    client
+        .post_json("/v1/webhooks/email/sendgrid", &events)
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+
+    // Providers may report the address in another case
+    let events = json!([{
+        "email": email.to_uppercase(),
+        "event": "bounce",
+        "type": "bounce",
+        "reason": "550 no such user",
+    }]);
+    let response = client
+        .post_json(
+            &format!("/v1/webhooks/email/sendgrid?token={WEBHOOK_SECRET}"),
+            &events,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    assert_eq!(response.json::<Value>()["accepted"], 1);
+
+    // The suppression is recorded by the job queue
+    queue::run_due(&JobContext::from_state(&state))
+        .await
+        .unwrap();
+
+    // Support sees why the user gets no email
+    let response = client
+        .request(admin_request("GET", &format!("/v1/admin/users/{user_id}")))
+        .await;
+    response.assert_status(StatusCode::OK);
+    let view: Value = response.json();
+    assert_eq!(view["email"], email);
+    assert_eq!(view["email_suppression"]["reason"], "bounce");
+    assert_eq!(view["email_suppression"]["detail"], "550 no such user");
+
+    // Nothing is sent while the address is suppressed
+    let sender = RecordingSender::default();
+    let outgoing = OutgoingEmail {
+        to: email.clone(),
+        subject: format!("Suppressed {email}"),
+        body: "Hi there".to_string(),
+    };
+    outbox::deliver(&state.pool, &sender, &outgoing).await;
+    assert!(sender.sent.lock().unwrap().is_empty());
+
+    // Clearing the suppression lets email through again
+    client
+        .request(admin_request(
+            "DELETE",
+            &format!("/v1/admin/users/{user_id}/email-suppression"),
+        ))
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    client
+        .request(admin_request(
+            "DELETE",
+            &format!("/v1/admin/users/{user_id}/email-suppression"),
+        ))
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    let response = client
+        .request(admin_request("GET", &format!("/v1/admin/users/{user_id}")))
+        .await;
+    assert_eq!(response.json::<Value>()["email_suppression"], Value::Null);
+
+    outbox::deliver(&state.pool, &sender, &outgoing).await;
+    assert_eq!(*sender.sent.lock().unwrap(), vec![email.clone()]);
+
+    sqlx::query("DELETE FROM email_outbox WHERE subject = $1")
+        .bind(&outgoing.subject)
+        .execute(&state.pool)
+        .await
+        .unwrap();
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_ses_complaint_is_suppressed() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let email = common::test_data::unique_email("complaint");
+
+    let message = json!({
+        "notificationType": "Complaint",
+        "complaint": {
+            "complainedRecipients": [{ "emailAddress": email }],
+            "complaintFeedbackType": "abuse",
+        },
+    });
+    let notification = json!({ "Type": "Notification", "Message": message.to_string() });
+    let response = client
+        .request(sns_delivery("/v1/webhooks/email/ses", &notification))
+        .await;
+    response.assert_status(StatusCode::OK);
+    assert_eq!(response.json::<Value>()["accepted"], 1);
+    queue::run_due(&JobContext::from_state(&state))
+        .await
+        .unwrap();
+
+    let reason: String =
+        sqlx::query_scalar("SELECT reason FROM email_suppressions WHERE email = $1")
+            .bind(&email)
+            .fetch_one(&state.pool)
+            .await
+            .unwrap();
+    assert_eq!(reason, "complaint");
+
+    // Providers that do not send webhooks have no endpoint
+    client
+        .post_json(
+            &format!("/v1/webhooks/email/smtp?token={WEBHOOK_SECRET}"),
+            &json!([]),
+        )
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    sqlx::query("DELETE FROM email_suppressions WHERE email = $1")
+        .bind(&email)
+        .execute(&state.pool)
+        .await
+        .unwrap();
+}
@@ -0,0 +1,232 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use mms_api::{auth::Role, router};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_edit_history_and_revert() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let pool = &state.pool;
+    let key = &state.cookie.cookie_key;
+
+    let username = common::test_data::unique_username("cardedit");
+    let email = common::test_data::unique_email("cardedit");
+    let user_id = common::db::create_verified_user(pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+    let learner = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+    let author = common::jwt::create_test_token_with_role(
+        user_id,
+        &email,
+        Role::Author,
+        &state.auth.jwt_keys,
+    );
+
+    let suffix = &Uuid::new_v4().simple().to_string()[..8];
+    let term = format!("gato_{suffix}");
+    let cards: Vec<Uuid> = sqlx::query_scalar(
+        r#"
+        INSERT INTO flashcards (term, translation, language_from, language_to)
+        VALUES ($1, 'cta', 'es', 'en'), ($1, 'kitty', 'es', 'en')
+        RETURNING id
+        "#,
+    )
+    .bind(&term)
+    .fetch_all(pool)
+    .await
+    .expect("Failed to create cards");
+    let card = cards[0];
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('History ' || $1, 'es', 'en') RETURNING id",
+    )
+    .bind(suffix)
+    .fetch_one(pool)
+    .await
+    .expect("Failed to create deck");
+    sqlx::query(
+        "INSERT INTO deck_flashcards (deck_id, flashcard_id) SELECT $1, unnest($2::uuid[])",
+    )
+    .bind(deck_id)
+    .bind(&cards)
+    .execute(pool)
+    .await
+    .expect("Failed to link cards");
+
+    let uri = format!("/v1/flashcards/{card}");
+    let history_uri = format!("{uri}/history");
+    let edit = |translation: &str| json!({ "term": term, "translation": translation });
+    client
+        .patch_json_with_auth(&uri, &edit("cat"), &learner, key)
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+    client
+        .patch_json_with_auth(&uri, &edit("  "), &author, key)
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    client
+        .patch_json_with_auth(&uri, &edit("kitty"), &author, key)
+        .await
+        .assert_status(StatusCode::CONFLICT);
+    client
+        .patch_json_with_auth(
+            &format!("/v1/flashcards/{}", Uuid::new_v4()),
+            &edit("cat"),
+            &author,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    // Fixing the typo, then an unchanged save that records nothing
+    let response = client
+        .patch_json_with_auth(&uri, &edit(" cat "), &author, key)
+        .await;
+    response.assert_status(StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["translation"], "cat");
+    client
+        .patch_json_with_auth(&uri, &edit("cat"), &author, key)
+        .await
+        .assert_status(StatusCode::OK);
+
+    let response = client.get_with_auth(&history_uri, &learner, key).await;
+    response.assert_status(StatusCode::OK);
+    let body: Value = response.json();
+    let edits = body["edits"].as_array().unwrap();
+    assert_eq!(edits.len(), 1);
+    assert_eq!(edits[0]["old_translation"], "cta");
+    assert_eq!(edits[0]["new_translation"], "cat");
+    assert_eq!(edits[0]["edited_by"], user_id.to_string());
+    assert_eq!(edits[0]["editor_username"], username);
+    let edit_id = edits[0]["id"].as_str().unwrap().to_string();
+
+    // Reverting brings the typo back and is itself an edit
+    let revert_uri = format!("{history_uri}/{edit_id}/revert");
+    client
+        .post_json_with_auth(&revert_uri, &json!({}), &learner, key)
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+    client
+        .post_json_with_auth(
+            &format!("{history_uri}/{}/revert", Uuid::new_v4()),
+            &json!({}),
+            &author,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    let response = client
+        .post_json_with_auth(&revert_uri, &json!({}), &author, key)
+        .await;
+    response.assert_status(StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["translation"], "cta");
+
+    let response = client.get_with_auth(&history_uri, &learner, key).await;
+    let body: Value = response.json();
+    let edits = body["edits"].as_array().unwrap();
+    assert_eq!(edits.len(), 2);
+    assert!(
+        edits
+            .iter()
+            .any(|e| e["old_translation"] == "cat" && e["new_translation"] == "cta")
+    );
+
+    // Cards only in another organization's decks are out of reach
+    let org_id: Uuid =
+        sqlx::query_scalar("INSERT INTO organizations (name) VALUES ('History') RETURNING id")
+            .fetch_one(pool)
+            .await
+            .expect("Failed to create organization");
+    sqlx::query("UPDATE decks SET org_id = $2 WHERE id = $1")
+        .bind(deck_id)
+        .bind(org_id)
+        .execute(pool)
+        .await
+        .expect("Failed to make the deck private");
+    client
+        .get_with_auth(&history_uri, &learner, key)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    client
+        .patch_json_with_auth(&uri, &edit("cat"), &author, key)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    client
+        .post_json_with_auth(&revert_uri, &json!({}), &author, key)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    // Also being in a public deck does not open the other organization's deck
+    let public_deck: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('Shared ' || $1, 'es', 'en') RETURNING id",
+    )
+    .bind(suffix)
+    .fetch_one(pool)
+    .await
+    .expect("Failed to create deck");
+    sqlx::query("INSERT INTO deck_flashcards (deck_id, flashcard_id) VALUES ($1, $2)")
+        .bind(public_deck)
+        .bind(card)
+        .execute(pool)
+        .await
+        .expect("Failed to link card");
+    client
+        .get_with_auth(&history_uri, &learner, key)
+        .await
+        .assert_status(StatusCode::OK);
+    client
+        .patch_json_with_auth(&uri, &edit("cat"), &author, key)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    client
+        .post_json_with_auth(&revert_uri, &json!({}), &author, key)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(public_deck)
+        .execute(pool)
+        .await
+        .expect("Failed to cleanup deck");
+
+    sqlx::query("UPDATE decks SET org_id = NULL WHERE id = $1")
+        .bind(deck_id)
+        .execute(pool)
+        .await
+        .expect("Failed to make the deck public");
+
+    // Hidden cards have no visible history
+    sqlx::query("UPDATE flashcards SET hidden_at = NOW() WHERE id = $1")
+        .bind(card)
+        .execute(pool)
+        .await
+        .expect("Failed to hide card");
+    client
+        .get_with_auth(&history_uri, &learner, key)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(pool)
+        .await
+        .expect("Failed to cleanup deck");
+    sqlx::query("DELETE FROM organizations WHERE id = $1")
+        .bind(org_id)
+        .execute(pool)
+        .await
+        .expect("Failed to cleanup organization");
+    sqlx::query("DELETE FROM flashcards WHERE id = ANY($1)")
+        .bind(&cards)
+        .execute(pool)
+        .await
+        .expect("Failed to cleanup cards");
+    common::db::delete_user_by_email(pool, &email)
+        .await
+        .expect("Failed to cleanup user");
+}
@@ -0,0 +1,369 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use mms_api::router;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Cards due per day counted straight from the progress rows, for comparison
+async fn live_counts(pool: &PgPool, user_id: Uuid) -> Vec<i64> {
+    let rows: Vec<(i32, i64)> = sqlx::query_as(
+        r#"
+        SELECT GREATEST((next_review_at AT TIME ZONE 'UTC')::date - CURRENT_DATE, 0), COUNT(*)
+        FROM user_card_progress
+        WHERE user_id = $1
+        GROUP BY 1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .unwrap();
+    let mut counts = vec![0; 30];
+    for (day, due) in rows {
+        if let Some(slot) = counts.get_mut(day as usize) {
+            *slot = due;
+        }
+    }
+    counts
+}
+
+fn served_counts(forecast: &Value) -> Vec<i64> {
+    forecast["days"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|day| day["due"].as_i64().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_forecast_is_precomputed_and_follows_reviews() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let pool = &state.pool;
+    let cookie_key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("forecast");
+    let user_id = common::db::create_verified_user(
+        pool,
+        &email,
+        &common::test_data::unique_username("forecast"),
+    )
+    .await
+    .unwrap();
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('Forecast', 'en', 'es') RETURNING id",
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    let mut cards = Vec::new();
+    for (term, due_in) in [("overdue", "-1 hour"), ("later", "3 days")] {
+        let card_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO flashcards (term, translation, language_from, language_to) VALUES ($1, 'gato', 'en', 'es') RETURNING id",
+        )
+        .bind(format!("{term} {deck_id}"))
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO deck_flashcards (deck_id, flashcard_id) VALUES ($1, $2)")
+            .bind(deck_id)
+            .bind(card_id)
+            .execute(pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO user_card_progress (user_id, flashcard_id, next_review_at, times_correct) VALUES ($1, $2, NOW() + $3::interval, 4)",
+        )
+        .bind(user_id)
+        .bind(card_id)
+        .bind(due_in)
+        .execute(pool)
+        .await
+        .unwrap();
+        cards.push(card_id);
+    }
+
+    let forecast_uri = format!("/v1/users/{user_id}/forecast");
+    let response = client
+        .get_with_auth(&forecast_uri, &token, cookie_key)
+        .await;
+    response.assert_status(StatusCode::OK);
+    let forecast = response.json::<Value>();
+    let counts = served_counts(&forecast);
+    assert_eq!(counts.len(), 30);
+    assert_eq!(counts, live_counts(pool, user_id).await);
+    assert_eq!(counts[0], 1);
+
+    // The review moves the overdue card to its next day without a full recompute
+    client
+        .post_json_with_auth(
+            &format!("/v1/practice/{}/review", cards[0]),
+            &json!({ "user_answer": "gato", "deck_id": deck_id }),
+            &token,
+            cookie_key,
+        )
+        .await
+        .assert_status(StatusCode::OK);
+
+    let response = client
+        .get_with_auth(&forecast_uri, &token, cookie_key)
+        .await;
+    response.assert_status(StatusCode::OK);
+    let after = response.json::<Value>();
+    assert_eq!(after["computed_at"], forecast["computed_at"]);
+    assert_eq!(served_counts(&after), live_counts(pool, user_id).await);
+    assert_eq!(served_counts(&after)[0], 0);
+
+    // Another user's forecast is off limits
+    let other = Uuid::new_v4();
+    client
+        .get_with_auth(&format!("/v1/users/{other}/forecast"), &token, cookie_key)
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    common::db::delete_user_by_email(pool, &email)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_forecast_span_and_deck_filter() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let pool = &state.pool;
+    let cookie_key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("forecast-span");
+    let user_id = common::db::create_verified_user(
+        pool,
+        &email,
+        &common::test_data::unique_username("forecast-span"),
+    )
+    .await
+    .unwrap();
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    let mut decks = Vec::new();
+    for (title, due_in) in [
+        ("Forecast far", ["-1 hour", "40 days"]),
+        ("Forecast near", ["3 days", "3 days"]),
+    ] {
+        let deck_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO decks (title, language_from, language_to) VALUES ($1, 'en', 'es') RETURNING id",
+        )
+        .bind(title)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        for (i, due_in) in due_in.into_iter().enumerate() {
+            let card_id: Uuid = sqlx::query_scalar(
+                "INSERT INTO flashcards (term, translation, language_from, language_to) VALUES ($1, 'gato', 'en', 'es') RETURNING id",
+            )
+            .bind(format!("span {i} {deck_id}"))
+            .fetch_one(pool)
+            .await
+            .unwrap();
+            sqlx::query("INSERT INTO deck_flashcards (deck_id, flashcard_id) VALUES ($1, $2)")
+                .bind(deck_id)
+                .bind(card_id)
+                .execute(pool)
+                .await
+                .unwrap();
+            sqlx::query(
+                "INSERT INTO user_card_progress (user_id, flashcard_id, next_review_at, times_correct) VALUES ($1, $2, NOW() + $3::interval, 4)",
+            )
+            .bind(user_id)
+            .bind(card_id)
+            .bind(due_in)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+        decks.push(deck_id);
+    }
+
+    let forecast = |query: String| {
+        let client = &client;
+        let token = &token;
+        async move {
+            let response = client
+                .get_with_auth(
+                    &format!("/v1/users/{user_id}/forecast?{query}"),
+                    token,
+                    cookie_key,
+                )
+                .await;
+            response.assert_status(StatusCode::OK);
+            served_counts(&response.json::<Value>())
+        }
+    };
+
+    // Beyond the stored days the counts come from the progress rows
+    let long = forecast("days=60".to_string()).await;
+    assert_eq!(long.len(), 60);
+    assert_eq!((long[0], long[3], long[40]), (1, 2, 1));
+
+    let week = forecast("days=7".to_string()).await;
+    assert_eq!(week, vec![1, 0, 0, 2, 0, 0, 0]);
+
+    let near = forecast(format!("days=7&deck_id={}", decks[1])).await;
+    assert_eq!(near, vec![0, 0, 0, 2, 0, 0, 0]);
+    let far = forecast(format!("deck_id={}", decks[0])).await;
+    assert_eq!(far.len(), 30);
+    assert_eq!(far.iter().sum::<i64>(), 1);
+
+    assert_eq!(forecast("days=1000".to_string()).await.len(), 365);
+
+    sqlx::query("DELETE FROM decks WHERE id = ANY($1)")
+        .bind(&decks)
+        .execute(pool)
+        .await
+        .unwrap();
+    common::db::delete_user_by_email(pool, &email)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_due_counts_per_deck_and_total() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let pool = &state.pool;
+    let cookie_key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("due-counts");
+    let user_id = common::db::create_verified_user(
+        pool,
+        &email,
+        &common::test_data::unique_username("due-counts"),
+    )
+    .await
+    .unwrap();
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    let mut decks = Vec::new();
+    for title in ["Due counts A", "Due counts B"] {
+        let deck_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO decks (title, language_from, language_to) VALUES ($1, 'en', 'es') RETURNING id",
+        )
+        .bind(title)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO user_deck_progress (user_id, deck_id) VALUES ($1, $2)")
+            .bind(user_id)
+            .bind(deck_id)
+            .execute(pool)
+            .await
+            .unwrap();
+        decks.push(deck_id);
+    }
+
+    // (term, decks, last reviewed, next review); no progress means a new card
+    let cards = [
+        ("learning", vec![0, 1], Some(("-3 hours", "-1 hour"))),
+        ("review", vec![0], Some(("-3 days", "-1 hour"))),
+        ("new", vec![0], None),
+        ("hidden", vec![0], None),
+        ("later", vec![1], Some(("-1 day", "2 days"))),
+    ];
+    let mut card_ids = Vec::new();
+    for (term, in_decks, progress) in cards {
+        let card_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO flashcards (term, translation, language_from, language_to) VALUES ($1, 'gato', 'en', 'es') RETURNING id",
+        )
+        .bind(format!("{term} {}", decks[0]))
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        for deck in in_decks {
+            sqlx::query("INSERT INTO deck_flashcards (deck_id, flashcard_id) VALUES ($1, $2)")
+                .bind(decks[deck])
+                .bind(card_id)
+                .execute(pool)
+                .await
+                .unwrap();
+        }
+        if let Some((last_review, next_review)) = progress {
+            sqlx::query(
+                "INSERT INTO user_card_progress (user_id, flashcard_id, last_review_at, next_review_at) VALUES ($1, $2, NOW() + $3::interval, NOW() + $4::interval)",
+            )
+            .bind(user_id)
+            .bind(card_id)
+            .bind(last_review)
+            .bind(next_review)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+        card_ids.push(card_id);
+    }
+    sqlx::query("UPDATE flashcards SET hidden_at = NOW() WHERE id = $1")
+        .bind(card_ids[3])
+        .execute(pool)
+        .await
+        .unwrap();
+
+    let response = client
+        .get_with_auth(
+            &format!("/v1/users/{user_id}/due-counts"),
+            &token,
+            cookie_key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let counts: Value = response.json();
+    assert_eq!(
+        counts["decks"],
+        json!([
+            { "deck_id": decks[0], "title": "Due counts A", "new": 1, "learning": 1, "due": 1 },
+            { "deck_id": decks[1], "title": "Due counts B", "new": 0, "learning": 1, "due": 0 },
+        ])
+    );
+    // The learning card is in both decks but counts once in the total
+    assert_eq!(
+        counts["total"],
+        json!({ "new": 1, "learning": 1, "due": 1 })
+    );
+
+    client
+        .get_with_auth(
+            &format!("/v1/users/{}/due-counts", Uuid::new_v4()),
+            &token,
+            cookie_key,
+        )
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+
+    sqlx::query("DELETE FROM decks WHERE id = ANY($1)")
+        .bind(&decks)
+        .execute(pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM flashcards WHERE id = ANY($1)")
+        .bind(&card_ids)
+        .execute(pool)
+        .await
+        .unwrap();
+    common::db::delete_user_by_email(pool, &email)
+        .await
+        .unwrap();
+}
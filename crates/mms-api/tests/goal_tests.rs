@@ -0,0 +1,195 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use mms_api::router;
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_minutes_goal_is_reached_by_study_time() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let pool = &state.pool;
+    let cookie_key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("goal");
+    let user_id =
+        common::db::create_verified_user(pool, &email, &common::test_data::unique_username("goal"))
+            .await
+            .unwrap();
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    // 20 reviews until changed
+    let goal: Value = client
+        .get_with_auth("/v1/users/me/daily-goal", &token, cookie_key)
+        .await
+        .json();
+    assert_eq!(goal["kind"], "reviews");
+    assert_eq!(goal["goal"], 20);
+    assert_eq!(goal["reached"], false);
+
+    for invalid in [
+        json!({ "kind": "minutes", "target": 0 }),
+        json!({ "kind": "minutes", "target": 241 }),
+        json!({ "kind": "reviews", "target": 501 }),
+    ] {
+        client
+            .put_json_with_auth("/v1/users/me/daily-goal", &invalid, &token, cookie_key)
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    let response = client
+        .put_json_with_auth(
+            "/v1/users/me/daily-goal",
+            &json!({ "kind": "minutes", "target": 2 }),
+            &token,
+            cookie_key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let goal: Value = response.json();
+    assert_eq!(goal["kind"], "minutes");
+    assert_eq!(goal["goal"], 2);
+    assert_eq!(goal["minutes_today"], 0);
+
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('Goals', 'en', 'es') RETURNING id",
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    let card_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO flashcards (term, translation, language_from, language_to) VALUES ($1, 'hola', 'en', 'es') RETURNING id",
+    )
+    .bind(format!("hello {deck_id}"))
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    sqlx::query("INSERT INTO deck_flashcards (deck_id, flashcard_id) VALUES ($1, $2)")
+        .bind(deck_id)
+        .bind(card_id)
+        .execute(pool)
+        .await
+        .unwrap();
+
+    // Two long reviews make two minutes; the second reaches the goal
+    let review = json!({ "user_answer": "hola", "deck_id": deck_id, "elapsed_ms": 60_000 });
+    for reached in [false, true] {
+        sqlx::query("UPDATE user_card_progress SET next_review_at = NOW() WHERE user_id = $1")
+            .bind(user_id)
+            .execute(pool)
+            .await
+            .unwrap();
+        client
+            .post_json_with_auth(
+                &format!("/v1/practice/{card_id}/review"),
+                &review,
+                &token,
+                cookie_key,
+            )
+            .await
+            .assert_status(StatusCode::OK);
+
+        let goal: Value = client
+            .get_with_auth("/v1/users/me/daily-goal", &token, cookie_key)
+            .await
+            .json();
+        assert_eq!(goal["reached"], reached);
+    }
+
+    let dashboard: Value = client
+        .get_with_auth("/v1/users/me/dashboard", &token, cookie_key)
+        .await
+        .json();
+    assert_eq!(dashboard["daily_goal"]["minutes_today"], 2);
+    assert_eq!(dashboard["daily_goal"]["reviews_today"], 2);
+    assert_eq!(dashboard["daily_goal"]["reached"], true);
+
+    // The first day on goal unlocks an achievement and notifies once
+    let achievements: Vec<Value> = client
+        .get_with_auth(
+            &format!("/v1/users/{user_id}/achievements"),
+            &token,
+            cookie_key,
+        )
+        .await
+        .json();
+    let goal_reached = achievements
+        .iter()
+        .find(|achievement| achievement["code"] == "goal_reached")
+        .unwrap();
+    assert_ne!(goal_reached["unlocked_at"], Value::Null);
+    let notifications: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND title = 'Goal getter'",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    assert_eq!(notifications, 1);
+
+    common::db::delete_user_by_email(pool, &email).await.ok();
+}
+
+#[tokio::test]
+async fn test_lowering_the_goal_reaches_it_today() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let pool = &state.pool;
+    let cookie_key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("goal");
+    let user_id =
+        common::db::create_verified_user(pool, &email, &common::test_data::unique_username("goal"))
+            .await
+            .unwrap();
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    // Flagged reviews do not count towards the goal
+    sqlx::query(
+        "INSERT INTO user_activity (user_id, activity_date, reviews_count, flagged_reviews) VALUES ($1, user_local_date($1), 8, 2)",
+    )
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .unwrap();
+
+    let goal: Value = client
+        .put_json_with_auth(
+            "/v1/users/me/daily-goal",
+            &json!({ "kind": "reviews", "target": 7 }),
+            &token,
+            cookie_key,
+        )
+        .await
+        .json();
+    assert_eq!(goal["reviews_today"], 6);
+    assert_eq!(goal["reached"], false);
+
+    let response = client
+        .put_json_with_auth(
+            "/v1/users/me/daily-goal",
+            &json!({ "kind": "reviews", "target": 6 }),
+            &token,
+            cookie_key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let goal: Value = response.json();
+    assert_eq!(goal["reached"], true);
+
+    let home: Value = client
+        .get_with_auth(&format!("/v1/users/{user_id}/home"), &token, cookie_key)
+        .await
+        .json();
+    assert_eq!(home["daily_goal"]["goal"], 6);
+    assert_eq!(home["daily_goal"]["reached"], true);
+
+    common::db::delete_user_by_email(pool, &email).await.ok();
+}
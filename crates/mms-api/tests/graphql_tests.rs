@@ -0,0 +1,199 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use mms_api::router;
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_dashboard_and_roadmap_progress_in_one_query() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let pool = &state.pool;
+    let email = common::test_data::unique_email("graphql");
+    let username = common::test_data::unique_username("graphql");
+    let user_id = common::db::create_verified_user(pool, &email, &username)
+        .await
+        .expect("Failed to create test user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+    let client = TestClient::new(router::router().with_state(state.clone()));
+
+    let roadmap_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO roadmaps (title, language_from, language_to) VALUES ('GraphQL roadmap', 'en', 'es') RETURNING id",
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    let mut deck_ids = Vec::new();
+    let mut card_ids = Vec::new();
+    for (position, title) in ["Basics", "Travel"].into_iter().enumerate() {
+        let deck_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO decks (title, language_from, language_to) VALUES ($1, 'en', 'es') RETURNING id",
+        )
+        .bind(title)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO roadmap_nodes (roadmap_id, deck_id, pos_y) VALUES ($1, $2, $3)")
+            .bind(roadmap_id)
+            .bind(deck_id)
+            .bind(i32::try_from(position).unwrap())
+            .execute(pool)
+            .await
+            .unwrap();
+        for term in ["uno", "dos"] {
+            let card_id: Uuid = sqlx::query_scalar(
+                "INSERT INTO flashcards (term, translation, language_from, language_to) VALUES ($1, 'number', 'en', 'es') RETURNING id",
+            )
+            .bind(format!("{term} {deck_id}"))
+            .fetch_one(pool)
+            .await
+            .unwrap();
+            sqlx::query("INSERT INTO deck_flashcards (deck_id, flashcard_id) VALUES ($1, $2)")
+                .bind(deck_id)
+                .bind(card_id)
+                .execute(pool)
+                .await
+                .unwrap();
+            card_ids.push(card_id);
+        }
+        deck_ids.push(deck_id);
+    }
+
+    let query = r#"
+        query Dashboard($roadmapId: UUID!, $missing: UUID!) {
+            me {
+                username
+                stats { currentStreakDays totalReviews }
+                heatmap(days: 30) { activityDate reviewsCount }
+                xp { xp level }
+                dailyGoal { kind goal reached }
+                due { now today }
+            }
+            roadmap(id: $roadmapId) {
+                title
+                progress { totalNodes completedNodes progressPercentage }
+                nodes {
+                    posY
+                    deck {
+                        title
+                        progress { totalCards masteredCards cardsDue }
+                    }
+                }
+            }
+            deck(id: $missing) { title }
+        }
+    "#;
+    let response = client
+        .post_json_with_auth(
+            "/v1/graphql",
+            &json!({
+                "query": query,
+                "variables": { "roadmapId": roadmap_id, "missing": Uuid::new_v4() },
+            }),
+            &token,
+            &state.cookie.cookie_key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let body: Value = response.json();
+    assert!(body.get("errors").is_none(), "unexpected errors: {body}");
+    let data = &body["data"];
+
+    assert_eq!(data["me"]["username"], username);
+    assert_eq!(data["me"]["stats"]["totalReviews"], 0);
+    assert_eq!(data["me"]["heatmap"], json!([]));
+    assert_eq!(data["me"]["xp"]["xp"], 0);
+    assert_eq!(data["me"]["dailyGoal"]["kind"], "REVIEWS");
+    assert_eq!(data["me"]["due"]["now"], 0);
+
+    let roadmap = &data["roadmap"];
+    assert_eq!(roadmap["title"], "GraphQL roadmap");
+    assert_eq!(roadmap["progress"]["totalNodes"], 2);
+    assert_eq!(roadmap["progress"]["completedNodes"], 0);
+    let nodes = roadmap["nodes"].as_array().unwrap();
+    assert_eq!(nodes.len(), 2);
+    assert_eq!(nodes[0]["deck"]["title"], "Basics");
+    assert_eq!(nodes[1]["deck"]["title"], "Travel");
+    for node in nodes {
+        // Cards never reviewed are due but not counted as reviewed cards due
+        assert_eq!(node["deck"]["progress"]["totalCards"], 2);
+        assert_eq!(node["deck"]["progress"]["masteredCards"], 0);
+        assert_eq!(node["deck"]["progress"]["cardsDue"], 2);
+    }
+    assert!(data["deck"].is_null());
+
+    sqlx::query("DELETE FROM roadmaps WHERE id = $1")
+        .bind(roadmap_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM decks WHERE id = ANY($1)")
+        .bind(&deck_ids)
+        .execute(pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM flashcards WHERE id = ANY($1)")
+        .bind(&card_ids)
+        .execute(pool)
+        .await
+        .unwrap();
+    common::db::delete_user_by_email(pool, &email)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_graphql_errors() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let email = common::test_data::unique_email("graphql_errors");
+    let user_id = common::db::create_verified_user(
+        &state.pool,
+        &email,
+        &common::test_data::unique_username("graphql_errors"),
+    )
+    .await
+    .expect("Failed to create test user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+    let client = TestClient::new(router::router().with_state(state.clone()));
+
+    client
+        .post_json("/v1/graphql", &json!({ "query": "{ me { username } }" }))
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+
+    // Errors come back in the body, with the REST error code
+    let response = client
+        .post_json_with_auth(
+            "/v1/graphql",
+            &json!({ "query": r#"{ roadmaps(languageFrom: "en") { id } }"# }),
+            &token,
+            &state.cookie.cookie_key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["errors"][0]["extensions"]["code"], "validation_failed");
+
+    let response = client
+        .post_json_with_auth(
+            "/v1/graphql",
+            &json!({ "query": "{ me { heatmap(days: 5000) { reviewsCount } } }" }),
+            &token,
+            &state.cookie.cookie_key,
+        )
+        .await;
+    assert!(response.json::<Value>()["errors"].is_array());
+
+    let response = client.get("/v1/graphql/schema").await;
+    response.assert_status(StatusCode::OK);
+    assert!(response.text().contains("type Query"));
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .unwrap();
+}
@@ -0,0 +1,243 @@
+use crate::common::{self, Account, TestClient, TestStateBuilder, account};
+use axum::http::StatusCode;
+use axum_extra::extract::cookie::Key;
+use mms_api::{auth::Role, router};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+async fn create_group(client: &TestClient, teacher: &Account, key: &Key) -> Value {
+    let response = client
+        .post_json_with_auth(
+            "/v1/groups",
+            &json!({ "name": "Spanish 101" }),
+            &teacher.token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::CREATED);
+    response.json::<Value>()
+}
+
+#[tokio::test]
+async fn test_teacher_assigns_decks_and_follows_learners() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let key = &state.cookie.cookie_key;
+
+    let teacher = account(&state, "group_teacher", Role::Learner).await;
+    let learner = account(&state, "group_learner", Role::Learner).await;
+    let outsider = account(&state, "group_outsider", Role::Learner).await;
+
+    let group = create_group(&client, &teacher, key).await;
+    assert_eq!(group["role"], "owner");
+    let group_id = group["id"].as_str().unwrap().to_string();
+    let invite_code = group["invite_code"].as_str().unwrap().to_string();
+
+    // Codes are matched regardless of case
+    let response = client
+        .post_json_with_auth(
+            "/v1/groups/join",
+            &json!({ "invite_code": invite_code.to_lowercase() }),
+            &learner.token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let joined = response.json::<Value>();
+    assert_eq!(joined["role"], "member");
+    assert!(joined["invite_code"].is_null());
+
+    let response = client
+        .get_with_auth("/v1/groups", &learner.token, key)
+        .await;
+    response.assert_status(StatusCode::OK);
+    let groups = response.json::<Vec<Value>>();
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0]["member_count"], 2);
+
+    client
+        .get_with_auth(&format!("/v1/groups/{group_id}"), &outsider.token, key)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    client
+        .get_with_auth(
+            &format!("/v1/groups/{group_id}/dashboard"),
+            &learner.token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('Greetings', 'en', 'es') RETURNING id",
+    )
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to create deck");
+
+    client
+        .put_json_with_auth(
+            &format!("/v1/groups/{group_id}/decks/{deck_id}"),
+            &json!({}),
+            &learner.token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+    let response = client
+        .put_json_with_auth(
+            &format!("/v1/groups/{group_id}/decks/{deck_id}"),
+            &json!({}),
+            &teacher.token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    assert_eq!(response.json::<Value>()["title"], "Greetings");
+
+    let response = client
+        .get_with_auth(&format!("/v1/groups/{group_id}"), &learner.token, key)
+        .await;
+    response.assert_status(StatusCode::OK);
+    let view = response.json::<Value>();
+    assert_eq!(view["assignments"][0]["deck_id"], deck_id.to_string());
+    assert_eq!(view["members"].as_array().unwrap().len(), 2);
+
+    let response = client
+        .get_with_auth(
+            &format!("/v1/groups/{group_id}/dashboard"),
+            &teacher.token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let dashboard = response.json::<Vec<Value>>();
+    assert_eq!(dashboard.len(), 1);
+    assert_eq!(dashboard[0]["user_id"], learner.id.to_string());
+    assert_eq!(dashboard[0]["decks"][0]["deck_id"], deck_id.to_string());
+    assert_eq!(dashboard[0]["decks"][0]["mastered_cards"], 0);
+
+    // Teachers invite by email; learners cannot
+    client
+        .post_json_with_auth(
+            &format!("/v1/groups/{group_id}/invites"),
+            &json!({ "email": "friend@example.com" }),
+            &teacher.token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::ACCEPTED);
+    client
+        .post_json_with_auth(
+            &format!("/v1/groups/{group_id}/invites"),
+            &json!({ "email": "friend@example.com" }),
+            &learner.token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+
+    // Promoted learners become teachers and drop off the dashboard
+    client
+        .put_json_with_auth(
+            &format!("/v1/groups/{group_id}/members/{}", learner.id),
+            &json!({ "role": "teacher" }),
+            &teacher.token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    let response = client
+        .get_with_auth(
+            &format!("/v1/groups/{group_id}/dashboard"),
+            &learner.token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    assert!(response.json::<Vec<Value>>().is_empty());
+
+    // Nobody can remove the owner; members can leave
+    client
+        .delete_with_auth(
+            &format!("/v1/groups/{group_id}/members/{}", teacher.id),
+            &learner.token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+    client
+        .delete_with_auth(
+            &format!("/v1/groups/{group_id}/members/{}", learner.id),
+            &learner.token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+
+    sqlx::query("DELETE FROM organizations WHERE id = $1::uuid")
+        .bind(&group_id)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup group");
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup deck");
+    for account in [teacher, learner, outsider] {
+        common::db::delete_user_by_email(&state.pool, &account.email)
+            .await
+            .expect("Failed to cleanup");
+    }
+}
+
+#[tokio::test]
+async fn test_other_organizations_decks_cannot_be_assigned() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let key = &state.cookie.cookie_key;
+
+    let teacher = account(&state, "group_foreign", Role::Learner).await;
+    let group = create_group(&client, &teacher, key).await;
+    let group_id = group["id"].as_str().unwrap().to_string();
+
+    let other_org: Uuid =
+        sqlx::query_scalar("INSERT INTO organizations (name) VALUES ('Other') RETURNING id")
+            .fetch_one(&state.pool)
+            .await
+            .expect("Failed to create organization");
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to, org_id) VALUES ('Private', 'en', 'es', $1) RETURNING id",
+    )
+    .bind(other_org)
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to create deck");
+
+    client
+        .put_json_with_auth(
+            &format!("/v1/groups/{group_id}/decks/{deck_id}"),
+            &json!({}),
+            &teacher.token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    sqlx::query("DELETE FROM organizations WHERE id = ANY(ARRAY[$1::uuid, $2])")
+        .bind(&group_id)
+        .bind(other_org)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup organizations");
+    common::db::delete_user_by_email(&state.pool, &teacher.email)
+        .await
+        .expect("Failed to cleanup");
+}
@@ -0,0 +1,186 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::{Method, StatusCode};
+use mms_api::{idempotency, router};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_retried_review_is_replayed_not_graded_twice() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let pool = &state.pool;
+    let cookie_key = &state.cookie.cookie_key;
+
+    let mut users = Vec::new();
+    for _ in 0..2 {
+        let email = common::test_data::unique_email("retry");
+        let user_id = common::db::create_verified_user(
+            pool,
+            &email,
+            &common::test_data::unique_username("retry"),
+        )
+        .await
+        .unwrap();
+        let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+        users.push((user_id, email, token));
+    }
+
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('Retry deck', 'en', 'es') RETURNING id",
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    let card_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO flashcards (term, translation, language_from, language_to) VALUES ('retry ' || gen_random_uuid(), 'reintentar', 'en', 'es') RETURNING id",
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    sqlx::query("INSERT INTO deck_flashcards (deck_id, flashcard_id) VALUES ($1, $2)")
+        .bind(deck_id)
+        .bind(card_id)
+        .execute(pool)
+        .await
+        .unwrap();
+
+    let uri = format!("/v1/practice/{card_id}/review");
+    let answer = json!({ "user_answer": "reintentar", "deck_id": deck_id });
+    let key = Uuid::new_v4().to_string();
+    let headers = [("idempotency-key", key.as_str())];
+    let (user_id, _, token) = &users[0];
+
+    let first = client
+        .post_json_with_auth_and_headers(&uri, &answer, token, cookie_key, &headers)
+        .await;
+    first.assert_status(StatusCode::OK);
+    assert!(first.headers.get("idempotent-replayed").is_none());
+
+    // Without the key, the card is no longer due; with it, the grade comes back
+    let retry = client
+        .post_json_with_auth_and_headers(&uri, &answer, token, cookie_key, &headers)
+        .await;
+    retry.assert_status(StatusCode::OK);
+    assert_eq!(retry.headers["idempotent-replayed"], "true");
+    assert_eq!(retry.json::<Value>(), first.json::<Value>());
+
+    let times_correct: i32 = sqlx::query_scalar(
+        "SELECT times_correct FROM user_card_progress WHERE user_id = $1 AND flashcard_id = $2",
+    )
+    .bind(user_id)
+    .bind(card_id)
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    assert_eq!(times_correct, 1);
+
+    // The same key cannot be reused for a different request
+    let wrong = json!({ "user_answer": "nope", "deck_id": deck_id });
+    client
+        .post_json_with_auth_and_headers(&uri, &wrong, token, cookie_key, &headers)
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    // Keys belong to each user: another user's request with it runs normally
+    let (_, _, other_token) = &users[1];
+    let other = client
+        .post_json_with_auth_and_headers(&uri, &wrong, other_token, cookie_key, &headers)
+        .await;
+    other.assert_status(StatusCode::OK);
+    assert!(other.headers.get("idempotent-replayed").is_none());
+    assert_eq!(other.json::<Value>()["is_correct"], false);
+
+    client
+        .post_json_with_auth_and_headers(
+            &uri,
+            &answer,
+            token,
+            cookie_key,
+            &[("idempotency-key", "")],
+        )
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    for (_, email, _) in &users {
+        common::db::delete_user_by_email(pool, email).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_retries_get_client_errors_and_wait_for_running_requests() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let pool = &state.pool;
+    let cookie_key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("retrypush");
+    let user_id = common::db::create_verified_user(
+        pool,
+        &email,
+        &common::test_data::unique_username("retrypush"),
+    )
+    .await
+    .unwrap();
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+    let key = Uuid::new_v4().to_string();
+    let headers = [("idempotency-key", key.as_str())];
+
+    // Client errors are answers too, and retries get the same one
+    let invalid = json!({ "progress": "not a list" });
+    let first = client
+        .post_json_with_auth_and_headers("/v1/sync/push", &invalid, &token, cookie_key, &headers)
+        .await;
+    assert!(first.status.is_client_error());
+    let retry = client
+        .post_json_with_auth_and_headers("/v1/sync/push", &invalid, &token, cookie_key, &headers)
+        .await;
+    assert_eq!(retry.status, first.status);
+    assert_eq!(retry.headers["idempotent-replayed"], "true");
+
+    // A request still holding its key is not run a second time
+    let busy_key = Uuid::new_v4().to_string();
+    let empty = json!({ "progress": [] });
+    let hash = idempotency::request_hash(
+        state.cookie.cookie_key.signing(),
+        &Method::POST,
+        "/v1/sync/push",
+        empty.to_string().as_bytes(),
+    );
+    sqlx::query("INSERT INTO idempotency_keys (scope, key, request_hash) VALUES ($1, $2, $3)")
+        .bind(format!("user:{user_id}"))
+        .bind(&busy_key)
+        .bind(hash)
+        .execute(pool)
+        .await
+        .unwrap();
+    client
+        .post_json_with_auth_and_headers(
+            "/v1/sync/push",
+            &empty,
+            &token,
+            cookie_key,
+            &[("idempotency-key", busy_key.as_str())],
+        )
+        .await
+        .assert_status(StatusCode::CONFLICT);
+
+    sqlx::query("DELETE FROM idempotency_keys WHERE scope = $1")
+        .bind(format!("user:{user_id}"))
+        .execute(pool)
+        .await
+        .unwrap();
+    common::db::delete_user_by_email(pool, &email)
+        .await
+        .unwrap();
+}
@@ -1,10 +1,62 @@
+mod account_deletion_tests;
+mod account_linking_tests;
+mod achievement_tests;
+mod admin_tests;
 mod auth_tests;
+mod avatar_tests;
+mod cache_control_tests;
+mod calendar_tests;
+mod captcha_tests;
+mod card_merge_tests;
+mod client_error_tests;
 mod common;
+mod deck_analytics_tests;
+mod deck_progress_tests;
+mod deck_review_tests;
+mod deck_settings_tests;
+mod deck_simulation_tests;
+mod email_outbox_tests;
+mod email_preview_tests;
 mod email_verification_tests;
+mod email_webhook_tests;
+mod flashcard_history_tests;
+mod forecast_tests;
+mod goal_tests;
+mod graphql_tests;
+mod group_tests;
+mod idempotency_tests;
+mod job_queue_tests;
+mod known_words_tests;
+mod leaderboard_tests;
+mod listening_mode_tests;
+mod live_tests;
 mod load_tests;
+mod notification_tests;
+mod offline_review_tests;
+mod openapi_tests;
 mod password_reset_tests;
+mod plan_tests;
+mod pool_tests;
+mod practice_session_tests;
+mod preferences_tests;
+mod profile_tests;
+mod pronunciation_tests;
+mod public_api_tests;
 mod rate_limit_tests;
+mod read_replica_tests;
 mod refresh_token_tests;
+mod reminder_tests;
+mod report_tests;
+mod reschedule_tests;
 mod roadmap_deck_practice_tests;
 mod security_tests;
+mod slug_tests;
+mod smoke_tests;
+mod sync_tests;
+mod tenancy_tests;
 mod user_tests;
+mod username_tests;
+mod versioning_tests;
+mod vocabulary_tests;
+mod widget_tests;
+mod xp_tests;
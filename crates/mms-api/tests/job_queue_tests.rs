@@ -0,0 +1,391 @@
+use crate::common::{self, TestClient, TestStateBuilder, admin_request};
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use mms_api::jobs::queue::{self, Job, JobContext};
+use mms_api::router;
+use mms_api::user::{UNVERIFIED_ACCOUNT_DAYS, email::EmailJob};
+use mms_db::repositories::{job as job_repo, user as user_repo};
+use serde_json::Value;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+async fn job_state(pool: &PgPool, id: Uuid) -> (String, i32, Option<String>, DateTime<Utc>) {
+    sqlx::query_as("SELECT status, attempts, last_error, run_at FROM jobs WHERE id = $1")
+        .bind(id)
+        .fetch_one(pool)
+        .await
+        .expect("Failed to read job")
+}
+
+async fn delete_job(pool: &PgPool, id: Uuid) {
+    sqlx::query("DELETE FROM jobs WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await
+        .expect("Failed to delete job");
+}
+
+#[tokio::test]
+async fn test_scheduled_jobs_are_queued_once() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let ctx = JobContext::from_state(&state);
+    sqlx::query("DELETE FROM jobs WHERE kind = 'unverified_accounts_cleanup'")
+        .execute(&state.pool)
+        .await
+        .unwrap();
+
+    // Every instance queues the cleanup; only the first one counts
+    let id = queue::enqueue(&state.pool, &Job::UnverifiedAccountsCleanup)
+        .await
+        .unwrap()
+        .expect("The first cleanup should be queued");
+    let again = queue::enqueue(&state.pool, &Job::UnverifiedAccountsCleanup)
+        .await
+        .unwrap();
+    assert_eq!(again, None);
+
+    queue::run_due(&ctx).await.unwrap();
+    let (status, attempts, last_error, _) = job_state(&state.pool, id).await;
+    assert_eq!(status, "succeeded");
+    assert_eq!(attempts, 1);
+    assert_eq!(last_error, None);
+
+    // Once it ran, the next one can be queued
+    let next = queue::enqueue(&state.pool, &Job::UnverifiedAccountsCleanup)
+        .await
+        .unwrap()
+        .expect("A finished job should not block the next one");
+
+    delete_job(&state.pool, id).await;
+    delete_job(&state.pool, next).await;
+}
+
+#[tokio::test]
+async fn test_failing_jobs_back_off_then_dead_letter() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    // No email provider, so email jobs fail
+    let ctx = JobContext::from_state(&state);
+    let client = TestClient::new(router::router().with_state(state.clone()));
+
+    let job = Job::Email {
+        email: EmailJob::PasswordChanged {
+            to_email: common::test_data::unique_email("dead-letter"),
+            username: "ana".to_string(),
+        },
+    };
+    let id = queue::enqueue(&state.pool, &job).await.unwrap().unwrap();
+
+    queue::run_due(&ctx).await.unwrap();
+    let (status, attempts, last_error, run_at) = job_state(&state.pool, id).await;
+    assert_eq!(status, "pending");
+    assert_eq!(attempts, 1);
+    assert_eq!(
+        last_error.as_deref(),
+        Some("No email provider is configured")
+    );
+    assert!(run_at > Utc::now(), "The retry should wait for the backoff");
+
+    // The last attempt fails too
+    sqlx::query("UPDATE jobs SET attempts = $2 - 1, run_at = NOW() WHERE id = $1")
+        .bind(id)
+        .bind(queue::MAX_ATTEMPTS)
+        .execute(&state.pool)
+        .await
+        .unwrap();
+    queue::run_due(&ctx).await.unwrap();
+    let (status, attempts, _, _) = job_state(&state.pool, id).await;
+    assert_eq!(status, "dead");
+    assert_eq!(attempts, queue::MAX_ATTEMPTS);
+
+    // Operators see it, without the payload, and can run it again
+    let response = client.request(admin_request("GET", "/v1/admin/jobs")).await;
+    response.assert_status(StatusCode::OK);
+    let jobs: Vec<Value> = response.json();
+    let dead = jobs
+        .iter()
+        .find(|job| job["id"] == id.to_string())
+        .expect("The dead job should be listed");
+    assert_eq!(dead["kind"], "email");
+    assert_eq!(dead["last_error"], "No email provider is configured");
+    assert!(dead.get("payload").is_none());
+
+    client
+        .request(admin_request("POST", &format!("/v1/admin/jobs/{id}/retry")))
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    let (status, attempts, _, _) = job_state(&state.pool, id).await;
+    assert_eq!(status, "pending");
+    assert_eq!(attempts, 0);
+    client
+        .request(admin_request("POST", &format!("/v1/admin/jobs/{id}/retry")))
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    delete_job(&state.pool, id).await;
+}
+
+#[tokio::test]
+async fn test_data_export_is_built_in_the_background() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let ctx = JobContext::from_state(&state);
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("queued-export");
+    let user_id = common::db::create_verified_user(
+        &state.pool,
+        &email,
+        &common::test_data::unique_username("queued-export"),
+    )
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO user_activity (user_id, activity_date, reviews_count) VALUES ($1, CURRENT_DATE, 4)",
+    )
+    .bind(user_id)
+    .execute(&state.pool)
+    .await
+    .unwrap();
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    let response = client
+        .post_json_with_auth("/v1/users/me/exports", &Value::Null, &token, key)
+        .await;
+    response.assert_status(StatusCode::ACCEPTED);
+    let export: Value = response.json();
+    assert_eq!(export["status"], "pending");
+    let export_id = export["id"].as_str().unwrap().to_string();
+    let download = format!("/v1/users/me/exports/{export_id}/download");
+
+    client
+        .get_with_auth(&download, &token, key)
+        .await
+        .assert_status(StatusCode::CONFLICT);
+
+    // A worker that died mid-run leaves the job leased; it is claimed again once the lease runs out
+    sqlx::query(
+        "UPDATE jobs SET status = 'running', attempts = 1, locked_until = NOW() - INTERVAL '1 second' WHERE payload LIKE '%' || $1 || '%'",
+    )
+    .bind(&export_id)
+    .execute(&state.pool)
+    .await
+    .unwrap();
+    queue::run_due(&ctx).await.unwrap();
+
+    let response = client
+        .get_with_auth(&format!("/v1/users/me/exports/{export_id}"), &token, key)
+        .await;
+    response.assert_status(StatusCode::OK);
+    assert_eq!(response.json::<Value>()["status"], "ready");
+
+    let response = client.get_with_auth(&download, &token, key).await;
+    response.assert_status(StatusCode::OK);
+    let records: Vec<Value> = response
+        .text()
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("Each line should be JSON"))
+        .collect();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0]["type"], "preferences");
+    assert_eq!(records[1]["type"], "activity");
+    assert_eq!(records[1]["reviews_count"], 4);
+
+    // Nobody else can fetch it
+    let other_email = common::test_data::unique_email("queued-export-other");
+    let other_id = common::db::create_verified_user(
+        &state.pool,
+        &other_email,
+        &common::test_data::unique_username("queued-export-other"),
+    )
+    .await
+    .unwrap();
+    let other_token = common::jwt::create_test_token(other_id, &other_email, &state.auth.jwt_keys);
+    client
+        .get_with_auth(&download, &other_token, key)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    for email in [&email, &other_email] {
+        common::db::delete_user_by_email(&state.pool, email)
+            .await
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_scheduled_runs_are_queued_once_and_can_be_triggered() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let pool = state.pool.clone();
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    sqlx::query("DELETE FROM jobs WHERE kind = 'token_cleanup'")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let response = client
+        .request(admin_request("GET", "/v1/admin/schedules"))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let schedules: Value = response.json();
+    let token_cleanup = schedules
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|s| s["job"] == "token_cleanup")
+        .expect("Token cleanup should be scheduled");
+    assert_eq!(token_cleanup["cron"], "0 */6 * * *");
+    assert!(token_cleanup["next_run_at"].is_string());
+
+    // Replicas queueing the same run: only the first one counts
+    let slot = Utc::now() - chrono::Duration::hours(1);
+    let id = queue::enqueue_scheduled(&pool, &Job::TokenCleanup, slot)
+        .await
+        .unwrap()
+        .expect("The first replica should queue the run");
+    queue::run_due(&JobContext::from_state(&state))
+        .await
+        .unwrap();
+    let again = queue::enqueue_scheduled(&pool, &Job::TokenCleanup, slot)
+        .await
+        .unwrap();
+    assert_eq!(
+        again, None,
+        "A run that already happened is not queued again"
+    );
+
+    // Trigger by hand; a second trigger waits for the first
+    let response = client
+        .request(admin_request(
+            "POST",
+            "/v1/admin/schedules/token_cleanup/run",
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::ACCEPTED);
+    let manual: Uuid = response.json::<Value>()["job_id"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    let response = client
+        .request(admin_request(
+            "POST",
+            "/v1/admin/schedules/token_cleanup/run",
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+
+    let response = client
+        .request(admin_request("POST", "/v1/admin/schedules/email/run"))
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    delete_job(&pool, id).await;
+    delete_job(&pool, manual).await;
+}
+
+#[tokio::test]
+async fn test_periodic_runs_are_claimed_by_one_instance() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let pool = state.pool.clone();
+    let job = "test_periodic_claim";
+
+    // Two replicas ticking together: only one of them runs the job
+    let (first, second) = tokio::join!(
+        job_repo::claim_periodic_run(&pool, job, 3600.0),
+        job_repo::claim_periodic_run(&pool, job, 3600.0),
+    );
+    assert!(first.unwrap() ^ second.unwrap());
+
+    // A replica ticking later in the period finds it already done
+    assert!(
+        !job_repo::claim_periodic_run(&pool, job, 3600.0)
+            .await
+            .unwrap()
+    );
+
+    // Once the period is over, the next run is claimed again
+    sqlx::query(
+        "UPDATE periodic_job_runs SET started_at = NOW() - INTERVAL '2 hours' WHERE job = $1",
+    )
+    .bind(job)
+    .execute(&pool)
+    .await
+    .unwrap();
+    assert!(
+        job_repo::claim_periodic_run(&pool, job, 3600.0)
+            .await
+            .unwrap()
+    );
+
+    sqlx::query("DELETE FROM periodic_job_runs WHERE job = $1")
+        .bind(job)
+        .execute(&pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_unverified_accounts_cleanup_keeps_recent_accounts() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let pool = &state.pool;
+
+    let mut users = Vec::new();
+    for (name, days_old) in [("stale-unverified", 8), ("recent-unverified", 6)] {
+        let email = common::test_data::unique_email(name);
+        let user_id = common::db::create_verified_user(
+            pool,
+            &email,
+            &common::test_data::unique_username(name),
+        )
+        .await
+        .unwrap();
+        sqlx::query(
+            "UPDATE users SET email_verified = false, created_at = NOW() - make_interval(days => $2) WHERE id = $1",
+        )
+        .bind(user_id)
+        .bind(days_old)
+        .execute(pool)
+        .await
+        .unwrap();
+        users.push(email);
+    }
+
+    let deleted = user_repo::delete_unverified_users(pool, UNVERIFIED_ACCOUNT_DAYS)
+        .await
+        .unwrap();
+    assert!(deleted >= 1);
+    assert_eq!(
+        common::db::get_user_by_email(pool, &users[0])
+            .await
+            .unwrap(),
+        None
+    );
+    assert!(
+        common::db::get_user_by_email(pool, &users[1])
+            .await
+            .unwrap()
+            .is_some()
+    );
+
+    common::db::delete_user_by_email(pool, &users[1])
+        .await
+        .unwrap();
+}
@@ -0,0 +1,159 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use mms_api::router;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Insert a deck at `cefr_level` with one card and return (deck id, card id)
+async fn insert_deck_with_card(
+    pool: &PgPool,
+    cefr_level: Option<&str>,
+    translation: &str,
+) -> (Uuid, Uuid) {
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to, cefr_level) VALUES ('Known words', 'en', 'es', $1) RETURNING id",
+    )
+    .bind(cefr_level)
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    let card_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO flashcards (term, translation, language_from, language_to) VALUES ($1, $2, 'en', 'es') RETURNING id",
+    )
+    .bind(format!("term {deck_id}"))
+    .bind(translation)
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    sqlx::query("INSERT INTO deck_flashcards (deck_id, flashcard_id) VALUES ($1, $2)")
+        .bind(deck_id)
+        .bind(card_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    (deck_id, card_id)
+}
+
+/// Score and days until due of the user's progress on a card, if any
+async fn progress(pool: &PgPool, user_id: Uuid, card_id: Uuid) -> Option<(i32, f64)> {
+    sqlx::query_as(
+        r#"
+        SELECT times_correct - times_wrong,
+               EXTRACT(EPOCH FROM next_review_at - NOW())::float8 / 86400
+        FROM user_card_progress
+        WHERE user_id = $1 AND flashcard_id = $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(card_id)
+    .fetch_optional(pool)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_known_words_mark_matching_cards() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let pool = &state.pool;
+    let cookie_key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("known");
+    let user_id = common::db::create_verified_user(
+        pool,
+        &email,
+        &common::test_data::unique_username("known"),
+    )
+    .await
+    .unwrap();
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+    let uri = format!("/v1/users/{user_id}/known-words");
+
+    let suffix = Uuid::new_v4().simple().to_string();
+    let (known, unknown) = (format!("gato{suffix}"), format!("perro{suffix}"));
+    let (known_deck, known_card) = insert_deck_with_card(pool, None, &known).await;
+    let (mixed_deck, mixed_card) =
+        insert_deck_with_card(pool, None, &format!("{known} {unknown}")).await;
+    let (a2_deck, a2_card) = insert_deck_with_card(pool, Some("A2"), "casa").await;
+    let (c1_deck, c1_card) = insert_deck_with_card(pool, Some("C1"), "desasosiego").await;
+
+    // Only cards made entirely of known words are marked; case does not matter
+    let response = client
+        .post_json_with_auth(
+            &uri,
+            &json!({ "language": "es", "words": [known.to_uppercase()] }),
+            &token,
+            cookie_key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    assert_eq!(response.json::<Value>()["cards_marked"], 1);
+    let (score, days) = progress(pool, user_id, known_card).await.unwrap();
+    assert_eq!(score, mms_srs::ALREADY_KNOWN_SCORE);
+    assert!((20.0..=40.0).contains(&days), "due in {days} days");
+    assert!(progress(pool, user_id, mixed_card).await.is_none());
+
+    // A level marks every card of decks at or below it
+    client
+        .post_json_with_auth(
+            &uri,
+            &json!({ "language": "es", "cefr_level": "B1" }),
+            &token,
+            cookie_key,
+        )
+        .await
+        .assert_status(StatusCode::OK);
+    assert!(progress(pool, user_id, a2_card).await.is_some());
+    assert!(progress(pool, user_id, c1_card).await.is_none());
+
+    // Deck progress reflects the marked cards
+    let total_cards: Option<i32> = sqlx::query_scalar(
+        "SELECT total_cards FROM user_deck_progress WHERE user_id = $1 AND deck_id = $2",
+    )
+    .bind(user_id)
+    .bind(known_deck)
+    .fetch_optional(pool)
+    .await
+    .unwrap();
+    assert_eq!(total_cards, Some(1));
+
+    // Exactly one of words and cefr_level is required
+    client
+        .post_json_with_auth(&uri, &json!({ "language": "es" }), &token, cookie_key)
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    client
+        .post_json_with_auth(
+            &uri,
+            &json!({ "language": "es", "words": ["casa"], "cefr_level": "A1" }),
+            &token,
+            cookie_key,
+        )
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    common::db::delete_user_by_email(pool, &email)
+        .await
+        .unwrap();
+    for (deck_id, card_id) in [
+        (known_deck, known_card),
+        (mixed_deck, mixed_card),
+        (a2_deck, a2_card),
+        (c1_deck, c1_card),
+    ] {
+        sqlx::query("DELETE FROM decks WHERE id = $1")
+            .bind(deck_id)
+            .execute(pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM flashcards WHERE id = $1")
+            .bind(card_id)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+}
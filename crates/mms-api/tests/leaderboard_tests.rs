@@ -0,0 +1,150 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use mms_api::{leaderboards, router};
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Create a verified user who earned `xp` today; returns (email, username, id)
+async fn user_with_xp(pool: &PgPool, prefix: &str, xp: i32) -> (String, String, Uuid) {
+    let email = common::test_data::unique_email(prefix);
+    let username = common::test_data::unique_username(prefix);
+    let user_id = common::db::create_verified_user(pool, &email, &username)
+        .await
+        .unwrap();
+    sqlx::query(
+        "INSERT INTO user_activity (user_id, activity_date, reviews_count, correct_xp) VALUES ($1, CURRENT_DATE, 1, $2)",
+    )
+    .bind(user_id)
+    .bind(xp)
+    .execute(pool)
+    .await
+    .unwrap();
+    (email, username, user_id)
+}
+
+fn ranked_ids(leaderboard: &Value) -> Vec<String> {
+    leaderboard["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["user_id"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_leaderboards_rank_by_xp_with_friends_and_opt_out() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let pool = &state.pool;
+    let cookie_key = &state.cookie.cookie_key;
+
+    // Far above what other tests earn, so these users lead the global board
+    let (me_email, _, me) = user_with_xp(pool, "lbme", 900_000_000).await;
+    let (friend_email, friend_name, friend) = user_with_xp(pool, "lbfriend", 800_000_000).await;
+    let (other_email, _, other) = user_with_xp(pool, "lbother", 850_000_000).await;
+    let token = common::jwt::create_test_token(me, &me_email, &state.auth.jwt_keys);
+    let friend_token = common::jwt::create_test_token(friend, &friend_email, &state.auth.jwt_keys);
+    leaderboards::refresh(pool).await.unwrap();
+
+    let response = client
+        .get_with_auth("/v1/leaderboards?period=week&limit=2", &token, cookie_key)
+        .await;
+    response.assert_status(StatusCode::OK);
+    let board: Value = response.json();
+    assert_eq!(ranked_ids(&board), [me.to_string(), other.to_string()]);
+    assert_eq!(board["me"]["rank"], 1);
+    assert_eq!(board["me"]["xp"], 900_000_000);
+    assert!(board["refreshed_at"].is_string());
+
+    // The user's own entry is returned even below the limit
+    let board: Value = client
+        .get_with_auth("/v1/leaderboards?limit=1", &friend_token, cookie_key)
+        .await
+        .json();
+    assert_eq!(ranked_ids(&board), [me.to_string()]);
+    assert_eq!(board["me"]["rank"], 3);
+
+    let friends_uri = "/v1/users/me/friends";
+    client
+        .post_json_with_auth(
+            friends_uri,
+            &json!({ "username": friend_name }),
+            &token,
+            cookie_key,
+        )
+        .await
+        .assert_status(StatusCode::CREATED);
+    client
+        .post_json_with_auth(
+            friends_uri,
+            &json!({ "username": friend_name }),
+            &token,
+            cookie_key,
+        )
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    client
+        .post_json_with_auth(
+            friends_uri,
+            &json!({ "username": format!("nobody-{}", Uuid::new_v4()) }),
+            &token,
+            cookie_key,
+        )
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    let friends: Vec<Value> = client
+        .get_with_auth(friends_uri, &token, cookie_key)
+        .await
+        .json();
+    assert_eq!(friends.len(), 1);
+    assert_eq!(friends[0]["user_id"], friend.to_string());
+
+    let board: Value = client
+        .get_with_auth(
+            "/v1/leaderboards?scope=friends&period=month",
+            &token,
+            cookie_key,
+        )
+        .await
+        .json();
+    assert_eq!(ranked_ids(&board), [me.to_string(), friend.to_string()]);
+    assert_eq!(board["entries"][1]["rank"], 2);
+
+    // Opting out applies before the next refresh
+    let privacy: Value = client
+        .patch_json_with_auth(
+            "/v1/users/me/privacy",
+            &json!({ "leaderboard_opt_out": true }),
+            &token,
+            cookie_key,
+        )
+        .await
+        .json();
+    assert_eq!(privacy["leaderboard_opt_out"], true);
+    let board: Value = client
+        .get_with_auth("/v1/leaderboards?limit=1", &token, cookie_key)
+        .await
+        .json();
+    assert_eq!(ranked_ids(&board), [other.to_string()]);
+    assert_eq!(board["me"], Value::Null);
+
+    client
+        .delete_with_auth(&format!("{friends_uri}/{friend}"), &token, cookie_key)
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    client
+        .delete_with_auth(&format!("{friends_uri}/{friend}"), &token, cookie_key)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    for email in [me_email, friend_email, other_email] {
+        common::db::delete_user_by_email(pool, &email)
+            .await
+            .unwrap();
+    }
+    leaderboards::refresh(pool).await.unwrap();
+}
@@ -0,0 +1,239 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use mms_api::{auth::Role, router};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+/// The mode the practice queue serves each card in, by term
+fn queued_modes(queue: &Value) -> Vec<(String, String)> {
+    let mut modes: Vec<_> = queue
+        .as_array()
+        .expect("Queue should be an array")
+        .iter()
+        .map(|card| {
+            (
+                card["term"].as_str().unwrap().to_string(),
+                card["mode"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    modes.sort();
+    modes
+}
+
+#[tokio::test]
+async fn test_decks_serve_cards_in_their_weakest_enabled_mode() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let pool = &state.pool;
+    let key = &state.cookie.cookie_key;
+
+    let mut emails = Vec::new();
+    let mut users = Vec::new();
+    for (prefix, role) in [
+        ("listening_author", Role::Author),
+        ("listening_learner", Role::Learner),
+    ] {
+        let email = common::test_data::unique_email(prefix);
+        let id = common::db::create_verified_user(
+            pool,
+            &email,
+            &common::test_data::unique_username(prefix),
+        )
+        .await
+        .expect("Failed to create user");
+        let token =
+            common::jwt::create_test_token_with_role(id, &email, role, &state.auth.jwt_keys);
+        emails.push(email);
+        users.push((id, token));
+    }
+    let (author_token, (learner_id, learner_token)) = (&users[0].1, &users[1]);
+
+    let suffix = &Uuid::new_v4().simple().to_string()[..8];
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('Listening ' || $1, 'en', 'es') RETURNING id",
+    )
+    .bind(suffix)
+    .fetch_one(pool)
+    .await
+    .expect("Failed to create deck");
+    let cards: Vec<Uuid> = sqlx::query_scalar(
+        r#"
+        INSERT INTO flashcards (term, translation, language_from, language_to)
+        VALUES ('cat_' || $1, 'gato', 'en', 'es'), ('dog_' || $1, 'perro', 'en', 'es')
+        RETURNING id
+        "#,
+    )
+    .bind(suffix)
+    .fetch_all(pool)
+    .await
+    .expect("Failed to create cards");
+    sqlx::query(
+        "INSERT INTO deck_flashcards (deck_id, flashcard_id) SELECT $1, unnest($2::uuid[])",
+    )
+    .bind(deck_id)
+    .bind(&cards)
+    .execute(pool)
+    .await
+    .expect("Failed to link cards");
+    let (cat, dog) = (format!("cat_{suffix}"), format!("dog_{suffix}"));
+    let queue_uri = format!("/v1/decks/{deck_id}/practice");
+    let review_uri = |card: Uuid| format!("/v1/practice/{card}/review");
+
+    // Decks are practised in translation mode only by default
+    let queue: Value = client
+        .get_with_auth(&queue_uri, learner_token, key)
+        .await
+        .json();
+    assert_eq!(
+        queued_modes(&queue),
+        [
+            (cat.clone(), "translation".to_string()),
+            (dog.clone(), "translation".to_string()),
+        ]
+    );
+    client
+        .post_json_with_auth(
+            &review_uri(cards[0]),
+            &json!({ "user_answer": "gato", "deck_id": deck_id, "mode": "listening" }),
+            learner_token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    // At least one mode, and pronunciation only with a speech-to-text provider
+    let settings_uri = format!("/v1/decks/{deck_id}/settings");
+    for invalid in [
+        json!({ "modes": [] }),
+        json!({ "modes": ["pronunciation"] }),
+    ] {
+        client
+            .put_json_with_auth(&settings_uri, &invalid, author_token, key)
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+    let response = client
+        .put_json_with_auth(
+            &settings_uri,
+            &json!({ "modes": ["listening", "translation", "listening"] }),
+            author_token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let saved: Value = response.json();
+    assert_eq!(saved["modes"], json!(["translation", "listening"]));
+    let body: Value = client
+        .get_with_auth(&settings_uri, learner_token, key)
+        .await
+        .json();
+    assert_eq!(body["modes"], json!(["translation", "listening"]));
+
+    // Once a card is known in translation, it comes back in listening mode
+    client
+        .post_json_with_auth(
+            &review_uri(cards[0]),
+            &json!({ "user_answer": "gato", "deck_id": deck_id }),
+            learner_token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::OK);
+    sqlx::query(
+        "UPDATE user_card_progress SET next_review_at = NOW() - INTERVAL '1 minute' WHERE user_id = $1 AND flashcard_id = $2",
+    )
+    .bind(learner_id)
+    .bind(cards[0])
+    .execute(pool)
+    .await
+    .expect("Failed to make the card due");
+    let queue: Value = client
+        .get_with_auth(&queue_uri, learner_token, key)
+        .await
+        .json();
+    assert_eq!(
+        queued_modes(&queue),
+        [
+            (cat.clone(), "listening".to_string()),
+            (dog.clone(), "translation".to_string()),
+        ]
+    );
+
+    // Recordings cannot be submitted as typed answers
+    client
+        .post_json_with_auth(
+            &review_uri(cards[0]),
+            &json!({ "user_answer": "gato", "deck_id": deck_id, "mode": "pronunciation" }),
+            learner_token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    let response = client
+        .post_json_with_auth(
+            &review_uri(cards[0]),
+            &json!({ "user_answer": "pato", "deck_id": deck_id, "mode": "listening" }),
+            learner_token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["is_correct"], false);
+
+    let response = client
+        .get_with_auth(
+            &format!("/v1/decks/{deck_id}/progress/modes"),
+            learner_token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let progress: Value = response.json();
+    assert_eq!(
+        progress,
+        json!([
+            { "mode": "listening", "cards_practised": 1, "cards_mastered": 0, "times_correct": 0, "times_wrong": 1 },
+            { "mode": "translation", "cards_practised": 1, "cards_mastered": 0, "times_correct": 1, "times_wrong": 0 },
+        ])
+    );
+
+    let modes: Vec<String> = sqlx::query_scalar(
+        "SELECT mode FROM review_logs WHERE user_id = $1 AND flashcard_id = $2 ORDER BY reviewed_at",
+    )
+    .bind(learner_id)
+    .bind(cards[0])
+    .fetch_all(pool)
+    .await
+    .expect("Failed to read review logs");
+    assert_eq!(modes, ["translation", "listening"]);
+
+    client
+        .get_with_auth(
+            &format!("/v1/decks/{}/progress/modes", Uuid::new_v4()),
+            learner_token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(pool)
+        .await
+        .expect("Failed to cleanup deck");
+    sqlx::query("DELETE FROM flashcards WHERE id = ANY($1)")
+        .bind(&cards)
+        .execute(pool)
+        .await
+        .expect("Failed to cleanup cards");
+    for email in &emails {
+        common::db::delete_user_by_email(pool, email)
+            .await
+            .expect("Failed to cleanup user");
+    }
+}
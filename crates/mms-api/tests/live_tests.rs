@@ -0,0 +1,320 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use crate::common::{self, TestClient, TestStateBuilder, auth_cookie};
+use axum::body::Body;
+use axum::http::{Request, StatusCode, header};
+use futures_util::StreamExt;
+use http_body_util::BodyExt;
+use mms_api::goals::DailyGoalKind;
+use mms_api::live::LiveEvent;
+use mms_api::router;
+use serde_json::{Value, json};
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::{self, Message, client::IntoClientRequest};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+type Socket =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// WebSockets need a real connection, so serve the app on a local port
+async fn serve(app: axum::Router) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind");
+    let addr = listener.local_addr().expect("No local address");
+    tokio::spawn(async move {
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+        .expect("Server failed");
+    });
+    addr
+}
+
+async fn connect(addr: SocketAddr, cookie: Option<String>) -> Result<Socket, tungstenite::Error> {
+    let mut request = format!("ws://{addr}/v1/ws")
+        .into_client_request()
+        .expect("Invalid request");
+    if let Some(cookie) = cookie {
+        request
+            .headers_mut()
+            .insert("cookie", cookie.parse().expect("Invalid cookie header"));
+    }
+    tokio_tungstenite::connect_async(request)
+        .await
+        .map(|(socket, _)| socket)
+}
+
+/// Next JSON event, skipping pings
+async fn next_event(socket: &mut Socket) -> Value {
+    loop {
+        let message = tokio::time::timeout(Duration::from_secs(5), socket.next())
+            .await
+            .expect("No event within 5 seconds")
+            .expect("Socket closed")
+            .expect("Socket error");
+        if let Message::Text(text) = message {
+            return serde_json::from_str(&text).expect("Event is not JSON");
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_review_events_are_pushed_to_the_user() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app.clone());
+    let addr = serve(app).await;
+    let key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("live");
+    let username = common::test_data::unique_username("live");
+    let user_id = common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    // The handshake is refused without a session
+    match connect(addr, None).await {
+        Err(tungstenite::Error::Http(response)) => {
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+        other => panic!("Expected 401, got {other:?}"),
+    }
+
+    let mut socket = connect(addr, Some(auth_cookie(key, &token)))
+        .await
+        .expect("Failed to connect");
+
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('Live deck', 'en', 'es') RETURNING id",
+    )
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to create deck");
+    let card_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO flashcards (term, translation, language_from, language_to) VALUES ($1, 'gato', 'en', 'es') RETURNING id",
+    )
+    .bind(format!("term {}", Uuid::new_v4()))
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to create flashcard");
+    sqlx::query("INSERT INTO deck_flashcards (deck_id, flashcard_id) VALUES ($1, $2)")
+        .bind(deck_id)
+        .bind(card_id)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to link flashcard");
+
+    // Events for other users are not delivered
+    state.events.publish(
+        Uuid::new_v4(),
+        LiveEvent::DailyGoalReached {
+            kind: DailyGoalKind::Reviews,
+            goal: 20,
+            reviews_today: 20,
+            minutes_today: 12,
+        },
+    );
+
+    client
+        .post_json_with_auth(
+            &format!("/v1/practice/{card_id}/review"),
+            &json!({ "user_answer": "gato", "deck_id": deck_id }),
+            &token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::OK);
+
+    let event = next_event(&mut socket).await;
+    assert_eq!(event["type"], "review_recorded");
+    assert_eq!(event["flashcard_id"], card_id.to_string());
+    assert_eq!(event["is_correct"], true);
+
+    // The day's first review starts the streak
+    let event = next_event(&mut socket).await;
+    assert_eq!(event["type"], "streak_updated");
+    assert_eq!(event["current_streak_days"], 1);
+
+    // ...and unlocks the first achievement
+    let event = next_event(&mut socket).await;
+    assert_eq!(event["type"], "notification_created");
+    assert_eq!(event["kind"], "achievement_unlocked");
+
+    state.events.broadcast(LiveEvent::DeckUpdated { deck_id });
+    let event = next_event(&mut socket).await;
+    assert_eq!(event["type"], "deck_updated");
+    assert_eq!(event["deck_id"], deck_id.to_string());
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup");
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup deck");
+    sqlx::query("DELETE FROM flashcards WHERE id = $1")
+        .bind(card_id)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup flashcard");
+}
+
+#[tokio::test]
+async fn test_flat_event_samples() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+    let key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("livesample");
+    let username = common::test_data::unique_username("livesample");
+    let user_id = common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    let response = client
+        .get_with_auth("/v1/events/samples/streak_updated", &token, key)
+        .await;
+    response.assert_status(StatusCode::OK);
+    let sample: Value = response.json();
+    assert_eq!(sample["type"], "streak_updated");
+    assert_eq!(sample["user_id"], user_id.to_string());
+    assert!(sample["current_streak_days"].is_number());
+
+    let samples: Vec<Value> = client
+        .get_with_auth("/v1/events/samples", &token, key)
+        .await
+        .json();
+    assert_eq!(samples.len(), 5);
+
+    client
+        .get_with_auth("/v1/events/samples/lagged", &token, key)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup");
+}
+
+/// Next `(id, data)` event of a Server-Sent Events body, skipping keep-alive comments
+async fn next_sse_event(body: &mut Body, buffer: &mut String) -> (Option<String>, Value) {
+    loop {
+        if let Some(end) = buffer.find("\n\n") {
+            let block: String = buffer.drain(..end + 2).collect();
+            let mut id = None;
+            let mut data = None;
+            for line in block.lines() {
+                if let Some(value) = line.strip_prefix("id:") {
+                    id = Some(value.trim().to_string());
+                } else if let Some(value) = line.strip_prefix("data:") {
+                    data = Some(serde_json::from_str(value.trim()).expect("Data is not JSON"));
+                }
+            }
+            match data {
+                Some(data) => return (id, data),
+                None => continue,
+            }
+        }
+
+        let frame = tokio::time::timeout(Duration::from_secs(5), body.frame())
+            .await
+            .expect("No event within 5 seconds")
+            .expect("Stream ended")
+            .expect("Stream error");
+        if let Ok(bytes) = frame.into_data() {
+            buffer.push_str(std::str::from_utf8(&bytes).expect("Stream is not UTF-8"));
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_notification_stream_resumes_after_last_event_id() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let app = router::router().with_state(state.clone());
+    let key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("livesse");
+    let username = common::test_data::unique_username("livesse");
+    let user_id = common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+    let cookie = auth_cookie(key, &token);
+
+    let open = |last_event_id: Option<String>| {
+        let mut request = Request::get("/v1/notifications/stream").header(header::COOKIE, &cookie);
+        if let Some(id) = last_event_id {
+            request = request.header("last-event-id", id);
+        }
+        app.clone()
+            .oneshot(request.body(Body::empty()).expect("Invalid request"))
+    };
+
+    let response = open(None).await.expect("Request failed");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "text/event-stream"
+    );
+    let mut body = response.into_body();
+    let mut buffer = String::new();
+
+    let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+    state
+        .events
+        .broadcast(LiveEvent::DeckUpdated { deck_id: first });
+    state
+        .events
+        .broadcast(LiveEvent::DeckUpdated { deck_id: second });
+
+    let (seen, event) = next_sse_event(&mut body, &mut buffer).await;
+    assert_eq!(event["deck_id"], first.to_string());
+    // Disconnect after the first event; the second and a later one are replayed
+    drop(body);
+    let third = Uuid::new_v4();
+    state
+        .events
+        .broadcast(LiveEvent::DeckUpdated { deck_id: third });
+
+    let response = open(seen).await.expect("Request failed");
+    let mut body = response.into_body();
+    let mut buffer = String::new();
+    let (id, event) = next_sse_event(&mut body, &mut buffer).await;
+    assert!(id.is_some());
+    assert_eq!(event["deck_id"], second.to_string());
+    let (_, event) = next_sse_event(&mut body, &mut buffer).await;
+    assert_eq!(event["deck_id"], third.to_string());
+
+    // An id from before a restart cannot be resumed
+    let response = open(Some("0-1".to_string())).await.expect("Request failed");
+    let mut body = response.into_body();
+    let mut buffer = String::new();
+    let (id, event) = next_sse_event(&mut body, &mut buffer).await;
+    assert!(id.is_none());
+    assert_eq!(event, json!({ "type": "lagged" }));
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup");
+}
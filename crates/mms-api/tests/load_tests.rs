@@ -427,9 +427,7 @@ async fn load_test_practice_review_submission() {
     for (i, user_id) in user_ids.iter().enumerate() {
         let client = TestClient::new(app.clone());
         let user_id = *user_id;
-        let deck_id = deck_id;
-        let flashcard_id = flashcard_id;
-        let jwt_secret = state.auth.jwt_secret.clone();
+        let jwt_keys = state.auth.jwt_keys.clone();
         let cookie_key = state.cookie.cookie_key.clone();
 
         let handle = tokio::spawn(async move {
@@ -438,7 +436,7 @@ async fn load_test_practice_review_submission() {
             let token = common::jwt::create_test_token(
                 user_id,
                 &format!("practice{}@example.com", i),
-                &jwt_secret,
+                &jwt_keys,
             );
 
             for _ in 0..config.requests_per_client {
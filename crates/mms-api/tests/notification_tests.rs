@@ -0,0 +1,130 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use mms_api::live::LiveEvent;
+use mms_api::notifications::{self, NotificationKind};
+use mms_api::router;
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_notification_list_read_and_delete() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+    let key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("notify");
+    let username = common::test_data::unique_username("notify");
+    let user_id = common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    // Creating a notification also pushes it to open connections
+    let mut subscription = state.events.subscribe(user_id);
+    let mut ids = Vec::new();
+    for n in 0..3 {
+        let notification = notifications::notify(
+            &state.pool,
+            &state.events,
+            user_id,
+            NotificationKind::AchievementUnlocked,
+            &format!("Achievement {n}"),
+            "You unlocked an achievement",
+            Some("/achievements"),
+        )
+        .await
+        .expect("Failed to notify");
+        ids.push(notification.id);
+    }
+    assert!(matches!(
+        subscription.next().await.map(|d| d.event),
+        Some(LiveEvent::NotificationCreated { notification_id, .. }) if notification_id == ids[0]
+    ));
+
+    // Newest first, paged
+    let response = client
+        .get_with_auth("/v1/notifications?limit=2", &token, key)
+        .await;
+    response.assert_status(StatusCode::OK);
+    let page: Value = response.json();
+    assert_eq!(page["unread_count"], 3);
+    assert_eq!(page["notifications"][0]["id"], ids[2].to_string());
+    assert_eq!(page["notifications"][0]["kind"], "achievement_unlocked");
+    assert_eq!(page["next_before"], ids[1].to_string());
+
+    let page: Value = client
+        .get_with_auth(&format!("/v1/notifications?before={}", ids[1]), &token, key)
+        .await
+        .json();
+    assert_eq!(page["notifications"].as_array().unwrap().len(), 1);
+    assert_eq!(page["notifications"][0]["id"], ids[0].to_string());
+    assert!(page["next_before"].is_null());
+
+    let response = client
+        .post_json_with_auth(
+            &format!("/v1/notifications/{}/read", ids[0]),
+            &json!({}),
+            &token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let read: Value = response.json();
+    assert!(read["read_at"].is_string());
+
+    let page: Value = client
+        .get_with_auth("/v1/notifications?unread_only=true", &token, key)
+        .await
+        .json();
+    assert_eq!(page["unread_count"], 2);
+    assert_eq!(page["notifications"].as_array().unwrap().len(), 2);
+
+    let response = client
+        .post_json_with_auth("/v1/notifications/read-all", &json!({}), &token, key)
+        .await;
+    response.assert_status(StatusCode::OK);
+    assert_eq!(response.json::<Value>()["updated"], 2);
+
+    client
+        .delete_with_auth(&format!("/v1/notifications/{}", ids[2]), &token, key)
+        .await
+        .assert_status(StatusCode::OK);
+    let page: Value = client
+        .get_with_auth("/v1/notifications", &token, key)
+        .await
+        .json();
+    assert_eq!(page["notifications"].as_array().unwrap().len(), 2);
+    assert_eq!(page["unread_count"], 0);
+
+    // Another user's notifications are invisible
+    let other_email = common::test_data::unique_email("notifyother");
+    let other_username = common::test_data::unique_username("notifyother");
+    let other_id = common::db::create_verified_user(&state.pool, &other_email, &other_username)
+        .await
+        .expect("Failed to create user");
+    let other_token = common::jwt::create_test_token(other_id, &other_email, &state.auth.jwt_keys);
+    client
+        .delete_with_auth(&format!("/v1/notifications/{}", ids[0]), &other_token, key)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    client
+        .post_json_with_auth(
+            &format!("/v1/notifications/{}/read", Uuid::new_v4()),
+            &json!({}),
+            &token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    for email in [&email, &other_email] {
+        common::db::delete_user_by_email(&state.pool, email)
+            .await
+            .expect("Failed to cleanup");
+    }
+}
@@ -0,0 +1,191 @@
+use std::sync::Arc;
+
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use mms_api::router;
+use mms_srs::clock::FrozenClock;
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_frozen_clock_and_offline_reviews_schedule_from_review_time() {
+    let mut state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    // Whole seconds, so the times survive the round trip through Postgres
+    let start = DateTime::from_timestamp(Utc::now().timestamp(), 0).unwrap();
+    let clock = FrozenClock::new(start);
+    state.clock = Arc::new(clock.clone());
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let pool = &state.pool;
+    let key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("offline");
+    let user_id = common::db::create_verified_user(
+        pool,
+        &email,
+        &common::test_data::unique_username("offline"),
+    )
+    .await
+    .expect("Failed to create user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('Offline', 'en', 'es') RETURNING id",
+    )
+    .fetch_one(pool)
+    .await
+    .expect("Failed to create deck");
+    let cards: Vec<Uuid> = sqlx::query_scalar(
+        r#"
+        INSERT INTO flashcards (term, translation, language_from, language_to)
+        SELECT 'offline ' || n || ' ' || gen_random_uuid(), 'gato', 'en', 'es' FROM generate_series(1, 2) AS n
+        RETURNING id
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .expect("Failed to create flashcards");
+    sqlx::query(
+        "INSERT INTO deck_flashcards (deck_id, flashcard_id) SELECT $1, UNNEST($2::uuid[])",
+    )
+    .bind(deck_id)
+    .bind(&cards)
+    .execute(pool)
+    .await
+    .expect("Failed to add flashcards");
+
+    let progress = |flashcard_id: Uuid| async move {
+        sqlx::query_as::<_, (DateTime<Utc>, DateTime<Utc>, i32, i32)>(
+            "SELECT next_review_at, last_review_at, times_correct, times_wrong FROM user_card_progress WHERE user_id = $1 AND flashcard_id = $2",
+        )
+        .bind(user_id)
+        .bind(flashcard_id)
+        .fetch_one(pool)
+        .await
+        .expect("Failed to read progress")
+    };
+
+    // A live review is scheduled from the frozen clock, to the second
+    client
+        .post_json_with_auth(
+            &format!("/v1/practice/{}/review", cards[0]),
+            &json!({ "user_answer": "gato", "deck_id": deck_id }),
+            &token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::OK);
+    let (next_review_at, last_review_at, _, _) = progress(cards[0]).await;
+    assert_eq!(last_review_at, start);
+    assert_eq!(next_review_at, start + Duration::hours(4));
+
+    // Still not due an hour later
+    clock.advance(Duration::hours(1));
+    client
+        .post_json_with_auth(
+            &format!("/v1/practice/{}/review", cards[0]),
+            &json!({ "user_answer": "gato", "deck_id": deck_id }),
+            &token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    // Offline reviews are replayed oldest first, at their own times
+    let first = start - Duration::days(2);
+    let second = first + Duration::hours(5);
+    let upload = json!({ "reviews": [
+        { "flashcard_id": cards[1], "deck_id": deck_id, "user_answer": "perro", "reviewed_at": second },
+        { "flashcard_id": cards[1], "deck_id": deck_id, "user_answer": "gato", "reviewed_at": first },
+        { "flashcard_id": cards[1], "deck_id": deck_id, "user_answer": "gato", "reviewed_at": start + Duration::days(1) },
+        { "flashcard_id": cards[1], "deck_id": deck_id, "user_answer": "gato", "reviewed_at": start - Duration::days(40) },
+    ]});
+    let response = client
+        .post_json_with_auth("/v1/sync/reviews", &upload, &token, key)
+        .await;
+    response.assert_status(StatusCode::OK);
+    let body: Value = response.json();
+    let reviews = body["reviews"].as_array().unwrap();
+    assert_eq!(reviews.len(), 4);
+    assert_eq!(reviews[0]["error"]["code"], "validation_failed");
+    assert_eq!(reviews[1]["result"]["is_correct"], true);
+    assert_eq!(reviews[2]["result"]["is_correct"], false);
+    assert_eq!(reviews[2]["result"]["correct_answer"], "gato");
+    assert_eq!(reviews[3]["error"]["code"], "validation_failed");
+
+    // Scheduled from the wrong answer, not from when it was uploaded
+    let (next_review_at, last_review_at, times_correct, times_wrong) = progress(cards[1]).await;
+    assert_eq!((times_correct, times_wrong), (1, 1));
+    assert_eq!(last_review_at, second);
+    assert_eq!(next_review_at, mms_srs::compute_next_review(1, 1, second));
+
+    let logged: Vec<DateTime<Utc>> = sqlx::query_scalar(
+        "SELECT reviewed_at FROM review_logs WHERE user_id = $1 AND flashcard_id = $2 ORDER BY reviewed_at",
+    )
+    .bind(user_id)
+    .bind(cards[1])
+    .fetch_all(pool)
+    .await
+    .expect("Failed to read review logs");
+    assert_eq!(logged, [first, second]);
+
+    // The activity lands on the days the reviews were made
+    let days: Vec<(NaiveDate, i32)> = sqlx::query_as(
+        "SELECT activity_date, reviews_count FROM user_activity WHERE user_id = $1 ORDER BY activity_date",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .expect("Failed to read activity");
+    let expected_days: Vec<NaiveDate> = sqlx::query_scalar(
+        "SELECT DISTINCT (at AT TIME ZONE user_timezone($1))::date FROM UNNEST($2::timestamptz[]) AS at ORDER BY 1",
+    )
+    .bind(user_id)
+    .bind(vec![first, second, start])
+    .fetch_all(pool)
+    .await
+    .expect("Failed to compute local dates");
+    assert_eq!(
+        days.iter().map(|(date, _)| *date).collect::<Vec<_>>(),
+        expected_days
+    );
+    assert_eq!(days.iter().map(|(_, count)| count).sum::<i32>(), 3);
+
+    // A review older than one already recorded cannot be replayed
+    let response = client
+        .post_json_with_auth(
+            "/v1/sync/reviews",
+            &json!({ "reviews": [
+                { "flashcard_id": cards[1], "deck_id": deck_id, "user_answer": "gato", "reviewed_at": first + Duration::hours(1) },
+            ]}),
+            &token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["reviews"][0]["error"]["code"], "conflict");
+    assert!(body["reviews"][0]["result"].is_null());
+
+    client
+        .post_json_with_auth("/v1/sync/reviews", &json!({ "reviews": [] }), &token, key)
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(pool)
+        .await
+        .expect("Failed to cleanup deck");
+    sqlx::query("DELETE FROM flashcards WHERE id = ANY($1)")
+        .bind(&cards)
+        .execute(pool)
+        .await
+        .expect("Failed to cleanup flashcards");
+    common::db::delete_user_by_email(pool, &email)
+        .await
+        .expect("Failed to cleanup user");
+}
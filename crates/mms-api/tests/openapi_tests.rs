@@ -0,0 +1,58 @@
+use crate::common::{TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use mms_api::{openapi, router};
+
+#[tokio::test]
+async fn test_openapi_document_is_served() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let client = TestClient::new(router::router().with_state(state));
+
+    let response = client.get(openapi::SPEC_PATH).await;
+    response.assert_status(StatusCode::OK);
+
+    let spec: serde_json::Value = response.json();
+    assert!(spec["openapi"].as_str().unwrap().starts_with("3."));
+
+    let paths = spec["paths"].as_object().unwrap();
+    for path in [
+        "/health",
+        "/v1/users/register",
+        "/v1/users/me/export",
+        "/v1/roadmaps/{roadmap_id}/progress",
+        "/v1/decks/{deck_id}/practice",
+        "/v1/practice/{flashcard_id}/review",
+        "/v1/admin/index-report",
+        "/v1/public/decks",
+    ] {
+        assert!(paths.contains_key(path), "{path} is not documented");
+    }
+
+    let practice_card = &spec["components"]["schemas"]["PracticeCard"]["properties"];
+    assert!(practice_card["difficulty"].is_object());
+}
+
+#[tokio::test]
+async fn test_swagger_ui_points_at_the_document() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let app = router::router()
+        .merge(openapi::swagger_ui())
+        .with_state(state);
+    let client = TestClient::new(app);
+
+    let response = client
+        .get(&format!(
+            "{}/swagger-initializer.js",
+            openapi::SWAGGER_UI_PATH
+        ))
+        .await;
+    response.assert_status(StatusCode::OK);
+    assert!(response.text().contains(openapi::SPEC_PATH));
+}
@@ -130,7 +130,7 @@ async fn test_password_reset_request_nonexistent_user() {
 
     // Verify no token was created
     let token_count: i64 = sqlx::query_scalar(
-        "SELECT COUNT(*) FROM password_reset_tokens WHERE user_id = (SELECT id FROM users WHERE email = $1)",
+        "SELECT COUNT(*) FROM one_time_tokens WHERE purpose = 'password_reset' AND user_id = (SELECT id FROM users WHERE email = $1)",
     )
     .bind("nonexistent@example.com")
     .fetch_one(&state.pool)
@@ -165,7 +165,7 @@ async fn test_password_reset_invalid_email_format() {
 
     let json: serde_json::Value = response.json();
     assert!(
-        json["error"]
+        json["message"]
             .as_str()
             .unwrap()
             .to_lowercase()
@@ -194,8 +194,8 @@ async fn test_password_reset_expired_token() {
     let expired_token = "expired_reset_token_hash_12345";
     sqlx::query(
         r#"
-        INSERT INTO password_reset_tokens (user_id, token_hash, expires_at, created_at)
-        VALUES ($1, $2, NOW() - INTERVAL '2 hours', NOW() - INTERVAL '3 hours')
+        INSERT INTO one_time_tokens (user_id, purpose, token_hash, expires_at, created_at)
+        VALUES ($1, 'password_reset', $2, NOW() - INTERVAL '2 hours', NOW() - INTERVAL '3 hours')
         "#,
     )
     .bind(user_id)
@@ -216,7 +216,7 @@ async fn test_password_reset_expired_token() {
 
     let json: serde_json::Value = response.json();
     assert!(
-        json["error"]
+        json["message"]
             .as_str()
             .unwrap()
             .contains("invalid or expired")
@@ -288,7 +288,7 @@ async fn test_password_reset_already_used_token() {
 
     let json: serde_json::Value = second_response.json();
     assert!(
-        json["error"]
+        json["message"]
             .as_str()
             .unwrap()
             .contains("invalid or expired")
@@ -345,7 +345,7 @@ async fn test_password_reset_weak_new_password() {
 
     let json: serde_json::Value = response.json();
     assert!(
-        json["error"]
+        json["message"]
             .as_str()
             .unwrap()
             .to_lowercase()
@@ -379,7 +379,7 @@ async fn test_password_reset_invalid_token_format() {
 
     let json: serde_json::Value = response.json();
     assert!(
-        json["error"]
+        json["message"]
             .as_str()
             .unwrap()
             .contains("invalid or expired")
@@ -0,0 +1,183 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use chrono::{Days, Utc};
+use mms_api::{auth::Role, plans, router};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_study_plan_pace_and_behind_notification() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let pool = &state.pool;
+    let cookie_key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("planner");
+    let user_id = common::db::create_verified_user(
+        pool,
+        &email,
+        &common::test_data::unique_username("planner"),
+    )
+    .await
+    .unwrap();
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    let roadmap_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO roadmaps (title, language_from, language_to) VALUES ($1, 'en', 'es') RETURNING id",
+    )
+    .bind(format!("Plan {}", Uuid::new_v4()))
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('Plan deck', 'en', 'es') RETURNING id",
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    sqlx::query("INSERT INTO roadmap_nodes (roadmap_id, deck_id) VALUES ($1, $2)")
+        .bind(roadmap_id)
+        .bind(deck_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    let mut card_ids = Vec::new();
+    for word in ["uno", "dos", "tres"] {
+        let card_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO flashcards (term, translation, language_from, language_to) VALUES ($1, $2, 'en', 'es') RETURNING id",
+        )
+        .bind(format!("{word} {deck_id}"))
+        .bind(word)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO deck_flashcards (deck_id, flashcard_id) VALUES ($1, $2)")
+            .bind(deck_id)
+            .bind(card_id)
+            .execute(pool)
+            .await
+            .unwrap();
+        card_ids.push(card_id);
+    }
+
+    let uri = format!("/v1/users/{user_id}/plan");
+    client
+        .get_with_auth(&uri, &token, cookie_key)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    // New users are on UTC, so "today" is the UTC date
+    let today = Utc::now().date_naive();
+    client
+        .put_json_with_auth(
+            &uri,
+            &json!({ "roadmap_id": roadmap_id, "target_date": today }),
+            &token,
+            cookie_key,
+        )
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    client
+        .put_json_with_auth(
+            &uri,
+            &json!({ "roadmap_id": Uuid::new_v4(), "target_date": today + Days::new(5) }),
+            &token,
+            cookie_key,
+        )
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    let target_date = today + Days::new(1);
+    let response = client
+        .put_json_with_auth(
+            &uri,
+            &json!({ "roadmap_id": roadmap_id, "target_date": target_date }),
+            &token,
+            cookie_key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let plan: Value = response.json();
+    assert_eq!(plan["days_left"], 2);
+    assert_eq!(plan["remaining_cards"], 3);
+    assert_eq!(plan["required_daily_new_cards"], 2);
+    assert_eq!(plan["required_weekly_new_cards"], 14);
+    assert_eq!(plan["baseline_daily_new_cards"], 2);
+    assert_eq!(
+        plan["estimated_daily_reviews"],
+        2 * mms_srs::reviews_in_first_days(30)
+    );
+    assert_eq!(plan["status"], "on_track");
+
+    // Starting a card leaves less to do
+    sqlx::query("INSERT INTO user_card_progress (user_id, flashcard_id) VALUES ($1, $2)")
+        .bind(user_id)
+        .bind(card_ids[0])
+        .execute(pool)
+        .await
+        .unwrap();
+    let plan: Value = client.get_with_auth(&uri, &token, cookie_key).await.json();
+    assert_eq!(plan["remaining_cards"], 2);
+    assert_eq!(plan["required_daily_new_cards"], 1);
+
+    // Needing more new cards a day than planned is falling behind
+    sqlx::query("UPDATE study_plans SET baseline_daily_new_cards = 0 WHERE user_id = $1")
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    let plan: Value = client.get_with_auth(&uri, &token, cookie_key).await.json();
+    assert_eq!(plan["status"], "behind");
+
+    // The daily check notifies once, not again the next run
+    plans::notify_behind(pool, &state.events).await.unwrap();
+    plans::notify_behind(pool, &state.events).await.unwrap();
+    let notifications: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND kind = 'plan_behind'",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    assert_eq!(notifications, 1);
+
+    let other = common::account(&state, "other", Role::Learner).await;
+    client
+        .get_with_auth(&uri, &other.token, cookie_key)
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+
+    client
+        .delete_with_auth(&uri, &token, cookie_key)
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    client
+        .get_with_auth(&uri, &token, cookie_key)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    common::db::delete_user_by_email(pool, &other.email)
+        .await
+        .ok();
+    common::db::delete_user_by_email(pool, &email)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM roadmaps WHERE id = $1")
+        .bind(roadmap_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM flashcards WHERE id = ANY($1)")
+        .bind(&card_ids)
+        .execute(pool)
+        .await
+        .unwrap();
+}
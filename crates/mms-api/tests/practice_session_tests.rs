@@ -0,0 +1,368 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use mms_api::router;
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_practice_session_serves_each_card_once() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let pool = &state.pool;
+    let cookie_key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("session");
+    let user_id = common::db::create_verified_user(
+        pool,
+        &email,
+        &common::test_data::unique_username("session"),
+    )
+    .await
+    .unwrap();
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+    let other_email = common::test_data::unique_email("session_other");
+    let other_id = common::db::create_verified_user(
+        pool,
+        &other_email,
+        &common::test_data::unique_username("session_other"),
+    )
+    .await
+    .unwrap();
+    let other_token = common::jwt::create_test_token(other_id, &other_email, &state.auth.jwt_keys);
+
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('Session deck', 'en', 'es') RETURNING id",
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    let mut card_ids = Vec::new();
+    for term in ["uno", "dos", "tres"] {
+        let card_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO flashcards (term, translation, language_from, language_to) VALUES ($1 || ' ' || gen_random_uuid(), $1, 'en', 'es') RETURNING id",
+        )
+        .bind(term)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO deck_flashcards (deck_id, flashcard_id) VALUES ($1, $2)")
+            .bind(deck_id)
+            .bind(card_id)
+            .execute(pool)
+            .await
+            .unwrap();
+        card_ids.push(card_id);
+    }
+
+    let response = client
+        .post_json_with_auth(
+            "/v1/practice/sessions",
+            &json!({ "deck_id": deck_id }),
+            &token,
+            cookie_key,
+        )
+        .await;
+    response.assert_status(StatusCode::CREATED);
+    let session: Value = response.json();
+    assert_eq!(session["total_cards"], 3);
+    assert_eq!(session["remaining"], 3);
+    assert!(session["completed_at"].is_null());
+    let session_id = session["id"].as_str().unwrap().to_string();
+
+    // Starting again resumes the open session
+    let response = client
+        .post_json_with_auth(
+            "/v1/practice/sessions",
+            &json!({ "deck_id": deck_id }),
+            &token,
+            cookie_key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    assert_eq!(response.json::<Value>()["id"], session["id"]);
+
+    let next_uri = format!("/v1/practice/sessions/{session_id}/next");
+    let answer_uri = format!("/v1/practice/sessions/{session_id}/answer");
+
+    // The same card is served until it is answered
+    let first: Value = client
+        .get_with_auth(&next_uri, &token, cookie_key)
+        .await
+        .json();
+    let again: Value = client
+        .get_with_auth(&next_uri, &token, cookie_key)
+        .await
+        .json();
+    assert_eq!(first["position"], 1);
+    assert_eq!(first["id"], again["id"]);
+
+    let answer = json!({ "flashcard_id": first["id"], "user_answer": first["translation"] });
+    let response = client
+        .post_json_with_auth(&answer_uri, &answer, &token, cookie_key)
+        .await;
+    response.assert_status(StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["is_correct"], true);
+    assert_eq!(body["session"]["answered"], 1);
+    assert_eq!(body["session"]["correct"], 1);
+    assert_eq!(body["session"]["remaining"], 2);
+
+    // A second answer to the same card is refused, and not graded again
+    client
+        .post_json_with_auth(&answer_uri, &answer, &token, cookie_key)
+        .await
+        .assert_status(StatusCode::CONFLICT);
+    let times_correct: i32 = sqlx::query_scalar(
+        "SELECT times_correct FROM user_card_progress WHERE user_id = $1 AND flashcard_id = $2",
+    )
+    .bind(user_id)
+    .bind(Uuid::parse_str(first["id"].as_str().unwrap()).unwrap())
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    assert_eq!(times_correct, 1);
+
+    // A card reviewed outside the session is skipped
+    let second: Value = client
+        .get_with_auth(&next_uri, &token, cookie_key)
+        .await
+        .json();
+    assert_eq!(second["position"], 2);
+    client
+        .post_json_with_auth(
+            &format!("/v1/practice/{}/review", second["id"].as_str().unwrap()),
+            &json!({ "user_answer": second["translation"], "deck_id": deck_id }),
+            &token,
+            cookie_key,
+        )
+        .await
+        .assert_status(StatusCode::OK);
+    let third: Value = client
+        .get_with_auth(&next_uri, &token, cookie_key)
+        .await
+        .json();
+    assert_eq!(third["position"], 3);
+
+    let response = client
+        .post_json_with_auth(
+            &answer_uri,
+            &json!({ "flashcard_id": third["id"], "user_answer": "wrong" }),
+            &token,
+            cookie_key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["is_correct"], false);
+    assert_eq!(body["session"]["answered"], 2);
+    assert_eq!(body["session"]["correct"], 1);
+    assert_eq!(body["session"]["remaining"], 0);
+    assert!(body["session"]["completed_at"].is_string());
+
+    client
+        .get_with_auth(&next_uri, &token, cookie_key)
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+
+    // Sessions are private, and gone once expired
+    let session_uri = format!("/v1/practice/sessions/{session_id}");
+    client
+        .get_with_auth(&session_uri, &other_token, cookie_key)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    sqlx::query("UPDATE practice_sessions SET expires_at = NOW() WHERE id = $1::uuid")
+        .bind(&session_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    client
+        .get_with_auth(&session_uri, &token, cookie_key)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    // Nothing is due any more
+    client
+        .post_json_with_auth(
+            "/v1/practice/sessions",
+            &json!({ "deck_id": deck_id, "limit": 1 }),
+            &token,
+            cookie_key,
+        )
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM flashcards WHERE id = ANY($1)")
+        .bind(&card_ids)
+        .execute(pool)
+        .await
+        .unwrap();
+    common::db::delete_user_by_email(pool, &email)
+        .await
+        .unwrap();
+    common::db::delete_user_by_email(pool, &other_email)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_roadmap_session_interleaves_decks() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let pool = &state.pool;
+    let cookie_key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("interleave");
+    let user_id = common::db::create_verified_user(
+        pool,
+        &email,
+        &common::test_data::unique_username("interleave"),
+    )
+    .await
+    .unwrap();
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    let roadmap_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO roadmaps (title, language_from, language_to) VALUES ('Interleaved ' || gen_random_uuid(), 'en', 'es') RETURNING id",
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    let mut deck_ids = Vec::new();
+    let mut card_ids = Vec::new();
+    for (position, title) in ["Colours", "Animals"].into_iter().enumerate() {
+        let deck_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO decks (title, language_from, language_to) VALUES ($1, 'en', 'es') RETURNING id",
+        )
+        .bind(title)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO roadmap_nodes (roadmap_id, deck_id, pos_y) VALUES ($1, $2, $3)")
+            .bind(roadmap_id)
+            .bind(deck_id)
+            .bind(i32::try_from(position).unwrap())
+            .execute(pool)
+            .await
+            .unwrap();
+        for _ in 0..2 {
+            let card_id: Uuid = sqlx::query_scalar(
+                "INSERT INTO flashcards (term, translation, language_from, language_to) VALUES ('card ' || gen_random_uuid(), 'si', 'en', 'es') RETURNING id",
+            )
+            .fetch_one(pool)
+            .await
+            .unwrap();
+            sqlx::query("INSERT INTO deck_flashcards (deck_id, flashcard_id) VALUES ($1, $2)")
+                .bind(deck_id)
+                .bind(card_id)
+                .execute(pool)
+                .await
+                .unwrap();
+            card_ids.push(card_id);
+        }
+        deck_ids.push(deck_id);
+    }
+
+    for invalid in [
+        json!({}),
+        json!({ "deck_id": deck_ids[0], "roadmap_id": roadmap_id }),
+        json!({ "deck_ids": [] }),
+    ] {
+        client
+            .post_json_with_auth("/v1/practice/sessions", &invalid, &token, cookie_key)
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+    client
+        .post_json_with_auth(
+            "/v1/practice/sessions",
+            &json!({ "deck_ids": [deck_ids[0], Uuid::new_v4()] }),
+            &token,
+            cookie_key,
+        )
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    let response = client
+        .post_json_with_auth(
+            "/v1/practice/sessions",
+            &json!({ "roadmap_id": roadmap_id }),
+            &token,
+            cookie_key,
+        )
+        .await;
+    response.assert_status(StatusCode::CREATED);
+    let session: Value = response.json();
+    assert_eq!(session["roadmap_id"], json!(roadmap_id));
+    assert!(session["deck_id"].is_null());
+    assert_eq!(session["deck_ids"].as_array().unwrap().len(), 2);
+    assert_eq!(session["total_cards"], 4);
+    let session_id = session["id"].as_str().unwrap().to_string();
+
+    // Decks alternate, and each answer counts towards the card's own deck
+    let mut served_decks = Vec::new();
+    loop {
+        let response = client
+            .get_with_auth(
+                &format!("/v1/practice/sessions/{session_id}/next"),
+                &token,
+                cookie_key,
+            )
+            .await;
+        if response.status == StatusCode::NO_CONTENT {
+            break;
+        }
+        let card: Value = response.json();
+        served_decks.push(card["deck_id"].clone());
+        client
+            .post_json_with_auth(
+                &format!("/v1/practice/sessions/{session_id}/answer"),
+                &json!({ "flashcard_id": card["id"], "user_answer": "si" }),
+                &token,
+                cookie_key,
+            )
+            .await
+            .assert_status(StatusCode::OK);
+    }
+    let (first, second) = (json!(deck_ids[0]), json!(deck_ids[1]));
+    assert_eq!(served_decks, [first.clone(), second.clone(), first, second]);
+
+    for deck_id in &deck_ids {
+        let total_practices: i32 = sqlx::query_scalar(
+            "SELECT total_practices FROM user_deck_progress WHERE user_id = $1 AND deck_id = $2",
+        )
+        .bind(user_id)
+        .bind(deck_id)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        assert_eq!(total_practices, 2);
+    }
+
+    common::db::delete_roadmap_by_id(pool, roadmap_id)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM decks WHERE id = ANY($1)")
+        .bind(&deck_ids)
+        .execute(pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM flashcards WHERE id = ANY($1)")
+        .bind(&card_ids)
+        .execute(pool)
+        .await
+        .unwrap();
+    common::db::delete_user_by_email(pool, &email)
+        .await
+        .unwrap();
+}
@@ -0,0 +1,141 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use mms_api::router;
+use serde_json::{Value, json};
+
+#[tokio::test]
+async fn test_preferences_default_and_partial_update() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let key = &state.cookie.cookie_key;
+    let email = common::test_data::unique_email("prefs");
+    let user_id = common::db::create_verified_user(
+        &state.pool,
+        &email,
+        &common::test_data::unique_username("prefs"),
+    )
+    .await
+    .expect("Failed to create test user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+    let client = TestClient::new(router::router().with_state(state.clone()));
+
+    let response = client
+        .get_with_auth("/v1/users/me/preferences", &token, key)
+        .await;
+    response.assert_status(StatusCode::OK);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({
+            "theme": "system",
+            "audio_autoplay": true,
+            "answer_checking": "normal",
+            "new_cards_per_day": 20,
+            "reviews_per_day": 200,
+            "max_interval_days": 90,
+            "updated_at": null
+        })
+    );
+
+    let response = client
+        .patch_json_with_auth(
+            "/v1/users/me/preferences",
+            &json!({ "theme": "dark", "new_cards_per_day": 0 }),
+            &token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let updated: Value = response.json();
+    assert_eq!(updated["theme"], "dark");
+    assert_eq!(updated["new_cards_per_day"], 0);
+    assert_eq!(updated["answer_checking"], "normal");
+    assert!(updated["updated_at"].is_string());
+
+    // Omitted fields keep the value changed before
+    let response = client
+        .patch_json_with_auth(
+            "/v1/users/me/preferences",
+            &json!({ "audio_autoplay": false, "answer_checking": "lenient" }),
+            &token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let response = client
+        .get_with_auth("/v1/users/me/preferences", &token, key)
+        .await;
+    let stored: Value = response.json();
+    assert_eq!(stored["theme"], "dark");
+    assert_eq!(stored["new_cards_per_day"], 0);
+    assert_eq!(stored["audio_autoplay"], false);
+    assert_eq!(stored["answer_checking"], "lenient");
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup test user");
+}
+
+#[tokio::test]
+async fn test_invalid_preferences_are_rejected() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let key = &state.cookie.cookie_key;
+    let email = common::test_data::unique_email("bad_prefs");
+    let user_id = common::db::create_verified_user(
+        &state.pool,
+        &email,
+        &common::test_data::unique_username("bad_prefs"),
+    )
+    .await
+    .expect("Failed to create test user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+    let client = TestClient::new(router::router().with_state(state.clone()));
+
+    let response = client
+        .patch_json_with_auth(
+            "/v1/users/me/preferences",
+            &json!({ "new_cards_per_day": 501, "reviews_per_day": 0, "max_interval_days": 366 }),
+            &token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::BAD_REQUEST);
+    let fields: Vec<Value> = response.json::<Value>()["details"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|detail| detail["field"].clone())
+        .collect();
+    assert_eq!(
+        fields,
+        [
+            json!("max_interval_days"),
+            json!("new_cards_per_day"),
+            json!("reviews_per_day")
+        ]
+    );
+
+    let response = client
+        .patch_json_with_auth(
+            "/v1/users/me/preferences",
+            &json!({ "theme": "neon" }),
+            &token,
+            key,
+        )
+        .await;
+    assert!(response.status.is_client_error());
+
+    // Nothing was stored
+    let response = client
+        .get_with_auth("/v1/users/me/preferences", &token, key)
+        .await;
+    assert_eq!(response.json::<Value>()["updated_at"], Value::Null);
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup test user");
+}
@@ -0,0 +1,276 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use mms_api::router;
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Create a deck in a language pair with a single card; returns `(deck_id, flashcard_id)`
+async fn create_deck_with_card(
+    pool: &PgPool,
+    language_from: &str,
+    language_to: &str,
+    translation: &str,
+) -> (Uuid, Uuid) {
+    let deck_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO decks (title, language_from, language_to)
+        VALUES ('Profile test deck', $1, $2)
+        RETURNING id
+        "#,
+    )
+    .bind(language_from)
+    .bind(language_to)
+    .fetch_one(pool)
+    .await
+    .expect("Failed to create deck");
+
+    let flashcard_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO flashcards (term, translation, language_from, language_to)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id
+        "#,
+    )
+    .bind(format!("term {}", Uuid::new_v4()))
+    .bind(translation)
+    .bind(language_from)
+    .bind(language_to)
+    .fetch_one(pool)
+    .await
+    .expect("Failed to create flashcard");
+
+    sqlx::query("INSERT INTO deck_flashcards (deck_id, flashcard_id) VALUES ($1, $2)")
+        .bind(deck_id)
+        .bind(flashcard_id)
+        .execute(pool)
+        .await
+        .expect("Failed to link flashcard");
+
+    (deck_id, flashcard_id)
+}
+
+#[tokio::test]
+async fn test_learning_profile_crud() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+    let key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("profiles");
+    let username = common::test_data::unique_username("profiles");
+    let user_id = common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    let spanish = json!({ "native_language": "en", "learning_language": "es" });
+    let response = client
+        .post_json_with_auth("/v1/users/me/profiles", &spanish, &token, key)
+        .await;
+    response.assert_status(StatusCode::CREATED);
+    let created: serde_json::Value = response.json();
+    assert_eq!(created["learning_language"], "es");
+    assert_eq!(created["session_size"], 20);
+
+    client
+        .post_json_with_auth("/v1/users/me/profiles", &spanish, &token, key)
+        .await
+        .assert_status(StatusCode::CONFLICT);
+
+    let invalid = json!({ "native_language": "en", "learning_language": "fr", "session_size": 0 });
+    client
+        .post_json_with_auth("/v1/users/me/profiles", &invalid, &token, key)
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    let french = json!({ "native_language": "en", "learning_language": "fr", "session_size": 5 });
+    let response = client
+        .post_json_with_auth("/v1/users/me/profiles", &french, &token, key)
+        .await;
+    response.assert_status(StatusCode::CREATED);
+    let french_id = response.json::<serde_json::Value>()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let profiles: Vec<serde_json::Value> = client
+        .get_with_auth("/v1/users/me/profiles", &token, key)
+        .await
+        .json();
+    assert_eq!(profiles.len(), 2);
+
+    let response = client
+        .patch_json_with_auth(
+            &format!("/v1/users/me/profiles/{french_id}"),
+            &json!({ "session_size": 10 }),
+            &token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    assert_eq!(response.json::<serde_json::Value>()["session_size"], 10);
+
+    client
+        .delete_with_auth(&format!("/v1/users/me/profiles/{french_id}"), &token, key)
+        .await
+        .assert_status(StatusCode::OK);
+    client
+        .patch_json_with_auth(
+            &format!("/v1/users/me/profiles/{french_id}"),
+            &json!({ "session_size": 10 }),
+            &token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    let profiles: Vec<serde_json::Value> = client
+        .get_with_auth("/v1/users/me/profiles", &token, key)
+        .await
+        .json();
+    assert_eq!(profiles.len(), 1);
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup");
+}
+
+#[tokio::test]
+async fn test_dashboard_and_due_queue_are_scoped_by_profile() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+    let key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("profilescope");
+    let username = common::test_data::unique_username("profilescope");
+    let user_id = common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    let (es_deck, es_card) = create_deck_with_card(&state.pool, "en", "es", "gato").await;
+    let (fr_deck, fr_card) = create_deck_with_card(&state.pool, "en", "fr", "chat").await;
+
+    // Reviewing a card in a new language pair creates the matching profile
+    for (deck_id, card_id) in [(es_deck, es_card), (fr_deck, fr_card)] {
+        client
+            .post_json_with_auth(
+                &format!("/v1/practice/{card_id}/review"),
+                &json!({ "user_answer": "wrong", "deck_id": deck_id }),
+                &token,
+                key,
+            )
+            .await
+            .assert_status(StatusCode::OK);
+    }
+
+    let profiles: Vec<serde_json::Value> = client
+        .get_with_auth("/v1/users/me/profiles", &token, key)
+        .await
+        .json();
+    let profile_id = |language: &str| {
+        profiles
+            .iter()
+            .find(|p| p["learning_language"] == language)
+            .unwrap_or_else(|| panic!("No {language} profile"))["id"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    };
+    let es_profile = profile_id("es");
+    let fr_profile = profile_id("fr");
+
+    let account: serde_json::Value = client
+        .get_with_auth("/v1/users/me/dashboard", &token, key)
+        .await
+        .json();
+    assert_eq!(account["stats"]["total_reviews"], 2);
+
+    let response = client
+        .get_with_auth(
+            &format!("/v1/users/me/dashboard?profile_id={es_profile}"),
+            &token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let spanish: serde_json::Value = response.json();
+    assert_eq!(spanish["stats"]["total_reviews"], 1);
+    assert_eq!(spanish["stats"]["current_streak_days"], 1);
+    assert_eq!(spanish["heatmap"][0]["reviews_count"], 1);
+
+    // Make both cards due; each profile's queue only holds its own
+    sqlx::query(
+        "UPDATE user_card_progress SET next_review_at = NOW() - INTERVAL '1 hour' WHERE user_id = $1",
+    )
+    .bind(user_id)
+    .execute(&state.pool)
+    .await
+    .expect("Failed to backdate reviews");
+
+    let response = client
+        .get_with_auth(
+            &format!("/v1/users/me/profiles/{fr_profile}/due"),
+            &token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let due: Vec<serde_json::Value> = response.json();
+    assert_eq!(due.len(), 1);
+    assert_eq!(due[0]["id"], fr_card.to_string());
+    assert_eq!(due[0]["deck_id"], fr_deck.to_string());
+
+    // Practising a deck outside the profile's language pair is rejected
+    client
+        .get_with_auth(
+            &format!("/v1/decks/{es_deck}/practice?profile_id={fr_profile}"),
+            &token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    let response = client
+        .get_with_auth(
+            &format!("/v1/decks/{es_deck}/practice?profile_id={es_profile}"),
+            &token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    assert_eq!(response.json::<Vec<serde_json::Value>>().len(), 1);
+
+    // Profiles of other users are not visible
+    client
+        .get_with_auth(
+            &format!("/v1/users/me/dashboard?profile_id={}", Uuid::new_v4()),
+            &token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup");
+    sqlx::query("DELETE FROM decks WHERE id = ANY($1)")
+        .bind(vec![es_deck, fr_deck])
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup decks");
+    sqlx::query("DELETE FROM flashcards WHERE id = ANY($1)")
+        .bind(vec![es_card, fr_card])
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup flashcards");
+}
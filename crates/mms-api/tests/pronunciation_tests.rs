@@ -0,0 +1,155 @@
+use std::sync::Arc;
+
+use crate::common::{self, TestClient, TestStateBuilder, auth_cookie, speech::EchoSpeech};
+use axum::{
+    body::Body,
+    http::{Request, StatusCode, header},
+};
+use mms_api::router;
+use serde_json::Value;
+use uuid::Uuid;
+
+fn recording(uri: &str, cookie: &str, content_type: &str, spoken: &str) -> Request<Body> {
+    Request::builder()
+        .method("POST")
+        .uri(uri)
+        .header("x-forwarded-for", "127.0.0.1")
+        .header(header::COOKIE, cookie)
+        .header(header::CONTENT_TYPE, content_type)
+        .body(Body::from(spoken.to_string()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_pronunciation_is_graded_from_the_transcript() {
+    let mut state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let pool = state.pool.clone();
+    let cookie_key = state.cookie.cookie_key.clone();
+
+    let email = common::test_data::unique_email("pronounce");
+    let user_id = common::db::create_verified_user(
+        &pool,
+        &email,
+        &common::test_data::unique_username("pronounce"),
+    )
+    .await
+    .expect("Failed to create user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+    let cookie = auth_cookie(&cookie_key, &token);
+
+    let suffix = &Uuid::new_v4().simple().to_string()[..8];
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('Pronounce ' || $1, 'es', 'en') RETURNING id",
+    )
+    .bind(suffix)
+    .fetch_one(&pool)
+    .await
+    .expect("Failed to create deck");
+    let cards: Vec<Uuid> = sqlx::query_scalar(
+        r#"
+        INSERT INTO flashcards (term, translation, language_from, language_to)
+        VALUES ('gato_' || $1, 'The cat', 'es', 'en'), ('perro_' || $1, 'dog', 'es', 'en')
+        RETURNING id
+        "#,
+    )
+    .bind(suffix)
+    .fetch_all(&pool)
+    .await
+    .expect("Failed to create cards");
+    sqlx::query(
+        "INSERT INTO deck_flashcards (deck_id, flashcard_id) SELECT $1, unnest($2::uuid[])",
+    )
+    .bind(deck_id)
+    .bind(&cards)
+    .execute(&pool)
+    .await
+    .expect("Failed to link cards");
+    let uri = |card: Uuid| format!("/v1/practice/{card}/pronunciation?deck_id={deck_id}");
+
+    // Without a provider the mode is unavailable
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    client
+        .request(recording(&uri(cards[0]), &cookie, "audio/webm", "the cat"))
+        .await
+        .assert_status(StatusCode::SERVICE_UNAVAILABLE);
+
+    state.speech = Some(Arc::new(EchoSpeech));
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    for (content_type, spoken, status) in [
+        ("application/json", "\"the cat\"", StatusCode::BAD_REQUEST),
+        ("audio/webm", "", StatusCode::BAD_REQUEST),
+        ("audio/webm", " ... ", StatusCode::BAD_REQUEST),
+    ] {
+        client
+            .request(recording(&uri(cards[0]), &cookie, content_type, spoken))
+            .await
+            .assert_status(status);
+    }
+    client
+        .request(recording(
+            &uri(Uuid::new_v4()),
+            &cookie,
+            "audio/webm",
+            "cat",
+        ))
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    // Only decks that list the mode are practised aloud
+    client
+        .request(recording(&uri(cards[0]), &cookie, "audio/webm", "the cat"))
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    sqlx::query(
+        "INSERT INTO deck_settings (deck_id, modes) VALUES ($1, ARRAY['translation', 'pronunciation'])",
+    )
+    .bind(deck_id)
+    .execute(&pool)
+    .await
+    .expect("Failed to enable pronunciation");
+
+    // The transcript is normalized like a typed answer
+    let response = client
+        .request(recording(&uri(cards[0]), &cookie, "audio/webm", "the cat."))
+        .await;
+    response.assert_status(StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["is_correct"], true);
+    assert_eq!(body["correct_answer"], "The cat");
+    assert_eq!(body["transcript"], "the cat.");
+
+    let response = client
+        .request(recording(&uri(cards[1]), &cookie, "audio/ogg", "duck"))
+        .await;
+    response.assert_status(StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["is_correct"], false);
+    assert_eq!(body["transcript"], "duck");
+
+    let modes: Vec<String> = sqlx::query_scalar(
+        "SELECT mode FROM review_logs WHERE user_id = $1 AND flashcard_id = ANY($2)",
+    )
+    .bind(user_id)
+    .bind(&cards)
+    .fetch_all(&pool)
+    .await
+    .expect("Failed to read review logs");
+    assert_eq!(modes, ["pronunciation", "pronunciation"]);
+
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(&pool)
+        .await
+        .expect("Failed to cleanup deck");
+    common::db::delete_user_by_email(&pool, &email)
+        .await
+        .expect("Failed to cleanup user");
+    sqlx::query("DELETE FROM flashcards WHERE id = ANY($1)")
+        .bind(&cards)
+        .execute(&pool)
+        .await
+        .expect("Failed to cleanup cards");
+}
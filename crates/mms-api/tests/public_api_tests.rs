@@ -0,0 +1,322 @@
+use crate::common::{self, ADMIN_TOKEN, TestClient, TestResponse, TestStateBuilder};
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use mms_api::{ApiState, public_api::MAX_KEYS_PER_USER, router};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+struct Integrator {
+    email: String,
+    token: String,
+}
+
+async fn integrator(state: &ApiState, name: &str) -> Integrator {
+    let email = common::test_data::unique_email(name);
+    let user_id = common::db::create_verified_user(
+        &state.pool,
+        &email,
+        &common::test_data::unique_username(name),
+    )
+    .await
+    .expect("Failed to create test user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+    Integrator { email, token }
+}
+
+/// Create an API key and return its id and the key itself
+async fn create_key(client: &TestClient, state: &ApiState, token: &str) -> (String, String) {
+    let response = client
+        .post_json_with_auth(
+            "/v1/users/me/api-keys",
+            &json!({ "name": "Course site" }),
+            token,
+            &state.cookie.cookie_key,
+        )
+        .await;
+    response.assert_status(StatusCode::CREATED);
+    let created: Value = response.json();
+    let key = created["key"].as_str().unwrap().to_string();
+    assert!(key.starts_with(created["api_key"]["key_prefix"].as_str().unwrap()));
+    (created["api_key"]["id"].as_str().unwrap().to_string(), key)
+}
+
+async fn get_with_key(client: &TestClient, uri: &str, key: Option<&str>) -> TestResponse {
+    let mut request = Request::builder()
+        .uri(uri)
+        .header("x-forwarded-for", "127.0.0.1");
+    if let Some(key) = key {
+        request = request.header("x-api-key", key);
+    }
+    client.request(request.body(Body::empty()).unwrap()).await
+}
+
+#[tokio::test]
+async fn test_public_catalogue_with_card_counts() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let pool = &state.pool;
+    let user = integrator(&state, "catalogue").await;
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let (_, key) = create_key(&client, &state, &user.token).await;
+
+    let roadmap_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO roadmaps (title, language_from, language_to) VALUES ('Public API', 'en', 'es') RETURNING id",
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('Public API deck', 'en', 'es') RETURNING id",
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    sqlx::query("INSERT INTO roadmap_nodes (roadmap_id, deck_id) VALUES ($1, $2)")
+        .bind(roadmap_id)
+        .bind(deck_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    let mut card_ids = Vec::new();
+    for term in ["uno", "dos", "tres"] {
+        let card_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO flashcards (term, translation, language_from, language_to) VALUES ($1, 'number', 'en', 'es') RETURNING id",
+        )
+        .bind(format!("{term} {deck_id}"))
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO deck_flashcards (deck_id, flashcard_id) VALUES ($1, $2)")
+            .bind(deck_id)
+            .bind(card_id)
+            .execute(pool)
+            .await
+            .unwrap();
+        card_ids.push(card_id);
+    }
+    // Hidden cards are not counted
+    sqlx::query("UPDATE flashcards SET hidden_at = NOW() WHERE id = $1")
+        .bind(card_ids[2])
+        .execute(pool)
+        .await
+        .unwrap();
+
+    let response = get_with_key(
+        &client,
+        "/v1/public/decks?sort=newest&limit=100",
+        Some(&key),
+    )
+    .await;
+    response.assert_status(StatusCode::OK);
+    assert_eq!(response.headers["x-quota-limit"], "1000");
+    assert_eq!(response.headers["x-quota-remaining"], "999");
+    let decks: Vec<Value> = response.json();
+    let deck = decks
+        .iter()
+        .find(|deck| deck["id"] == deck_id.to_string())
+        .expect("Deck not listed");
+    assert_eq!(deck["card_count"], 2);
+
+    let response = get_with_key(
+        &client,
+        "/v1/public/roadmaps?language_from=en&language_to=es&limit=100",
+        Some(&key),
+    )
+    .await;
+    response.assert_status(StatusCode::OK);
+    assert_eq!(response.headers["x-quota-remaining"], "998");
+    let roadmaps: Vec<Value> = response.json();
+    let roadmap = roadmaps
+        .iter()
+        .find(|roadmap| roadmap["id"] == roadmap_id.to_string())
+        .expect("Roadmap not listed");
+    assert_eq!(roadmap["deck_count"], 1);
+    assert_eq!(roadmap["card_count"], 2);
+
+    get_with_key(&client, "/v1/public/roadmaps?language_from=en", Some(&key))
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    common::db::delete_roadmap_by_id(pool, roadmap_id)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM flashcards WHERE id = ANY($1)")
+        .bind(&card_ids)
+        .execute(pool)
+        .await
+        .unwrap();
+    common::db::delete_user_by_email(pool, &user.email)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_public_api_requires_an_active_key() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let key_cookie = &state.cookie.cookie_key;
+    let user = integrator(&state, "revoker").await;
+    let client = TestClient::new(router::router().with_state(state.clone()));
+
+    get_with_key(&client, "/v1/public/decks", None)
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+    get_with_key(&client, "/v1/public/decks", Some("mms_not_a_key"))
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+
+    let (api_key_id, key) = create_key(&client, &state, &user.token).await;
+    get_with_key(&client, "/v1/public/decks", Some(&key))
+        .await
+        .assert_status(StatusCode::OK);
+
+    let response = client
+        .get_with_auth("/v1/users/me/api-keys", &user.token, key_cookie)
+        .await;
+    response.assert_status(StatusCode::OK);
+    let keys: Vec<Value> = response.json();
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys[0]["name"], "Course site");
+    assert!(keys[0]["last_used_at"].is_string());
+    assert!(keys[0].get("key").is_none());
+
+    client
+        .delete_with_auth(
+            &format!("/v1/users/me/api-keys/{api_key_id}"),
+            &user.token,
+            key_cookie,
+        )
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    get_with_key(&client, "/v1/public/decks", Some(&key))
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+
+    // Another user cannot see or revoke the key
+    let (api_key_id, _) = create_key(&client, &state, &user.token).await;
+    let other = integrator(&state, "other_integrator").await;
+    client
+        .delete_with_auth(
+            &format!("/v1/users/me/api-keys/{api_key_id}"),
+            &other.token,
+            key_cookie,
+        )
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    client
+        .get_with_auth(
+            &format!("/v1/users/me/api-keys/{api_key_id}/usage"),
+            &other.token,
+            key_cookie,
+        )
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    common::db::delete_user_by_email(&state.pool, &user.email)
+        .await
+        .unwrap();
+    common::db::delete_user_by_email(&state.pool, &other.email)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_concurrent_key_creation_respects_the_limit() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let user = integrator(&state, "key_rush").await;
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let body = json!({ "name": "Course site" });
+
+    let responses = futures_util::future::join_all((0..MAX_KEYS_PER_USER + 5).map(|_| {
+        client.post_json_with_auth(
+            "/v1/users/me/api-keys",
+            &body,
+            &user.token,
+            &state.cookie.cookie_key,
+        )
+    }))
+    .await;
+
+    let created = responses
+        .iter()
+        .filter(|response| response.status == StatusCode::CREATED)
+        .count();
+    assert_eq!(created as i64, MAX_KEYS_PER_USER);
+    for response in responses.iter().filter(|r| r.status != StatusCode::CREATED) {
+        response.assert_status(StatusCode::CONFLICT);
+    }
+
+    common::db::delete_user_by_email(&state.pool, &user.email)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_daily_quota_and_usage_report() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let user = integrator(&state, "quota").await;
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let (api_key_id, key) = create_key(&client, &state, &user.token).await;
+
+    let response = client
+        .request(
+            Request::builder()
+                .method("PATCH")
+                .uri(format!("/v1/admin/api-keys/{api_key_id}"))
+                .header("x-forwarded-for", "127.0.0.1")
+                .header("authorization", format!("Bearer {ADMIN_TOKEN}"))
+                .header("content-type", "application/json")
+                .body(Body::from(json!({ "daily_quota": 2 }).to_string()))
+                .unwrap(),
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    assert_eq!(response.json::<Value>()["daily_quota"], 2);
+
+    for remaining in ["1", "0"] {
+        let response = get_with_key(&client, "/v1/public/roadmaps", Some(&key)).await;
+        response.assert_status(StatusCode::OK);
+        assert_eq!(response.headers["x-quota-remaining"], remaining);
+    }
+    let response = get_with_key(&client, "/v1/public/roadmaps", Some(&key)).await;
+    response.assert_status(StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.json::<Value>()["code"], "rate_limited");
+    assert!(response.headers.contains_key("retry-after"));
+    assert_eq!(response.headers["x-quota-remaining"], "0");
+
+    let response = client
+        .get_with_auth(
+            &format!("/v1/users/me/api-keys/{api_key_id}/usage?days=7"),
+            &user.token,
+            &state.cookie.cookie_key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let report: Value = response.json();
+    assert_eq!(report["api_key"]["daily_quota"], 2);
+    let days = report["days"].as_array().unwrap();
+    assert_eq!(days.len(), 1);
+    assert_eq!(days[0]["requests"], 3);
+    assert_eq!(days[0]["rejected"], 1);
+
+    common::db::delete_user_by_email(&state.pool, &user.email)
+        .await
+        .unwrap();
+}
@@ -323,20 +323,40 @@ async fn test_rate_limit_headers_present() {
     let app = router::router().with_state(state.clone());
     let client = TestClient::new(app);
 
-    let response = client.get("/v1/roadmaps").await;
-
-    // Check if rate limit headers are present (depends on implementation)
-    // Common headers: X-RateLimit-Limit, X-RateLimit-Remaining, X-RateLimit-Reset
-    println!("Response headers: {:?}", response.headers);
-
-    // This test documents whether rate limit headers are exposed
-    // Not all implementations expose these headers
-    let has_rate_limit_headers = response
-        .headers
-        .keys()
-        .any(|k| k.as_str().to_lowercase().contains("ratelimit"));
-
-    println!("Has rate limit headers: {}", has_rate_limit_headers);
+    let header = |response: &common::TestResponse, name: &str| -> u64 {
+        response
+            .headers
+            .get(name)
+            .unwrap_or_else(|| panic!("{name} missing from {:?}", response.headers))
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap()
+    };
+
+    // General tier: burst of 20, one request back every 10 seconds. Requests
+    // count before authentication, so an anonymous client is limited too.
+    let response = client.get("/v1/leaderboards").await;
+    assert_ne!(response.status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(header(&response, "x-ratelimit-limit"), 20);
+    assert_eq!(header(&response, "x-ratelimit-remaining"), 19);
+    assert_eq!(header(&response, "x-ratelimit-reset"), 10);
+    assert!(response.headers.get("retry-after").is_none());
+
+    let mut response = response;
+    for _ in 0..25 {
+        response = client.get("/v1/leaderboards").await;
+        if response.status == StatusCode::TOO_MANY_REQUESTS {
+            break;
+        }
+    }
+    response.assert_status(StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(header(&response, "x-ratelimit-limit"), 20);
+    assert_eq!(header(&response, "x-ratelimit-remaining"), 0);
+    let retry_after = header(&response, "retry-after");
+    assert!(retry_after <= 10, "retry-after was {retry_after}");
+    // The whole burst takes another 19 periods to come back after the next request
+    assert!(header(&response, "x-ratelimit-reset") >= retry_after + 190);
 }
 
 #[tokio::test]
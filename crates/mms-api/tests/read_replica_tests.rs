@@ -0,0 +1,110 @@
+use crate::common::{TestClient, TestConfig, TestStateBuilder};
+use axum::http::StatusCode;
+use mms_api::router;
+use mms_db::PoolSettings;
+use uuid::Uuid;
+
+fn small_pool() -> PoolSettings {
+    PoolSettings {
+        max_connections: 2,
+        ..PoolSettings::default()
+    }
+}
+
+async fn insert_roadmap(pool: &sqlx::PgPool, id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO roadmaps (id, title, description, language_from, language_to)
+        VALUES ($1, $2, 'Replica test', 'en', 'es')
+        "#,
+    )
+    .bind(id)
+    .bind(format!("Replica roadmap {id}"))
+    .execute(pool)
+    .await
+    .map(|_| ())
+}
+
+#[tokio::test]
+async fn test_replica_pool_is_read_only() {
+    let database_url = TestConfig::default().database_url;
+    // The test database stands in for its own replica
+    let pools = mms_db::create_pool_pair(&database_url, Some(&database_url), &small_pool())
+        .await
+        .expect("Failed to create pool pair");
+    assert!(pools.replica().is_some());
+
+    let id = Uuid::new_v4();
+    let error = insert_roadmap(pools.reader(), id)
+        .await
+        .expect_err("Writes through the reader should fail");
+    let code = error
+        .as_database_error()
+        .and_then(|e| e.code())
+        .map(|code| code.into_owned());
+    assert_eq!(code.as_deref(), Some("25006"), "read_only_sql_transaction");
+
+    insert_roadmap(pools.primary(), id)
+        .await
+        .expect("Writes to the primary should work");
+    let title: String = sqlx::query_scalar("SELECT title FROM roadmaps WHERE id = $1")
+        .bind(id)
+        .fetch_one(pools.reader())
+        .await
+        .expect("Reads through the reader should work");
+    assert_eq!(title, format!("Replica roadmap {id}"));
+
+    sqlx::query("DELETE FROM roadmaps WHERE id = $1")
+        .bind(id)
+        .execute(pools.primary())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_reader_falls_back_to_the_primary() {
+    let database_url = TestConfig::default().database_url;
+    let pools = mms_db::create_pool_pair(&database_url, None, &small_pool())
+        .await
+        .expect("Failed to create pool pair");
+    assert!(pools.replica().is_none());
+
+    // Without a replica the reader is the primary, writes included
+    let id = Uuid::new_v4();
+    insert_roadmap(pools.reader(), id)
+        .await
+        .expect("The reader should be the primary");
+    sqlx::query("DELETE FROM roadmaps WHERE id = $1")
+        .bind(id)
+        .execute(pools.primary())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_catalogue_reads_are_served_from_the_replica() {
+    let mut state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let database_url = TestConfig::default().database_url;
+    let pools = mms_db::create_pool_pair(&database_url, Some(&database_url), &small_pool())
+        .await
+        .expect("Failed to create pool pair");
+    state.read_pool = pools.reader().clone();
+
+    let id = Uuid::new_v4();
+    insert_roadmap(&state.pool, id).await.unwrap();
+    let client = TestClient::new(router::router().with_state(state.clone()));
+
+    let response = client.get(&format!("/v1/roadmaps/{id}/nodes")).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let response = client.get("/v1/roadmaps/en/es?limit=100").await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    sqlx::query("DELETE FROM roadmaps WHERE id = $1")
+        .bind(id)
+        .execute(&state.pool)
+        .await
+        .unwrap();
+}
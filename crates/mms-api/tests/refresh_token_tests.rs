@@ -166,7 +166,7 @@ async fn test_refresh_token_reuse_detection() {
     second_refresh.assert_status(StatusCode::UNAUTHORIZED);
 
     let error_json: serde_json::Value = second_refresh.json();
-    assert!(error_json["error"].as_str().is_some());
+    assert!(error_json["message"].as_str().is_some());
 
     // Cleanup
     common::db::delete_user_by_email(&state.pool, &email)
@@ -191,7 +191,7 @@ async fn test_refresh_token_missing_cookie() {
 
     let json: serde_json::Value = response.json();
     assert!(
-        json["error"]
+        json["message"]
             .as_str()
             .unwrap()
             .to_lowercase()
@@ -216,7 +216,7 @@ async fn test_refresh_token_invalid_token() {
         .await
         .expect("Failed to create user");
 
-    let access_token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_secret);
+    let access_token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
 
     // Try to refresh with invalid refresh token
     let response = client
@@ -445,7 +445,7 @@ async fn test_refresh_token_family_invalidation_on_breach() {
     let breach_attempt = client
         .post_with_auth_and_refresh(
             "/v1/auth/refresh",
-            &refresh1_json["token"].as_str().unwrap(),
+            refresh1_json["token"].as_str().unwrap(),
             token1,
             &state.cookie.cookie_key,
         )
@@ -508,7 +508,7 @@ async fn test_refresh_token_expiration() {
     .await
     .expect("Failed to insert expired token");
 
-    let access_token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_secret);
+    let access_token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
 
     // Try to use expired refresh token
     let response = client
@@ -546,3 +546,100 @@ async fn test_logout_without_refresh_token() {
     let json: serde_json::Value = response.json();
     assert!(json["message"].as_str().unwrap().contains("Logged out"));
 }
+
+#[tokio::test]
+async fn test_refresh_token_max_session_age() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+
+    let email = common::test_data::unique_email("sessionage");
+    let username = common::test_data::unique_username("sessionage");
+    let user_id = common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+    let access_token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    let insert_token = |token: &'static str, started_days_ago: i32| {
+        sqlx::query(
+            r#"
+            INSERT INTO refresh_tokens (user_id, token_hash, expires_at, session_started_at)
+            VALUES ($1, $2, NOW() + INTERVAL '10 days', NOW() - make_interval(days => $3))
+            "#,
+        )
+        .bind(user_id)
+        .bind(mms_api::token_service::hash_token(token))
+        .bind(started_days_ago)
+        .execute(&state.pool)
+    };
+
+    // One day left in the session: refreshing works, but the new token
+    // expires with the session instead of 30 days from now
+    insert_token("session_age_token_nearly_over_1234567", 89)
+        .await
+        .expect("Failed to insert token");
+    client
+        .post_with_auth_and_refresh(
+            "/v1/auth/refresh",
+            &access_token,
+            "session_age_token_nearly_over_1234567",
+            &state.cookie.cookie_key,
+        )
+        .await
+        .assert_status(StatusCode::OK);
+
+    let (expires_in_hours, started_days_ago): (f64, f64) = sqlx::query_as(
+        r#"
+        SELECT EXTRACT(EPOCH FROM expires_at - NOW())::FLOAT8 / 3600,
+               EXTRACT(EPOCH FROM NOW() - session_started_at)::FLOAT8 / 86400
+        FROM refresh_tokens
+        WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to read rotated token");
+    assert!(
+        (23.0..=24.0).contains(&expires_in_hours),
+        "Rotated token should expire with the session, got {expires_in_hours}h"
+    );
+    assert_eq!(started_days_ago.round(), 89.0);
+
+    // Past the maximum age: the session ends even though the token has not expired
+    insert_token("session_age_token_over_12345678901234", 91)
+        .await
+        .expect("Failed to insert token");
+    client
+        .post_with_auth_and_refresh(
+            "/v1/auth/refresh",
+            &access_token,
+            "session_age_token_over_12345678901234",
+            &state.cookie.cookie_key,
+        )
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+
+    let remaining: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM refresh_tokens WHERE user_id = $1 AND token_hash = $2",
+    )
+    .bind(user_id)
+    .bind(mms_api::token_service::hash_token(
+        "session_age_token_over_12345678901234",
+    ))
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to count tokens");
+    assert_eq!(
+        remaining, 0,
+        "An over-age session's token should be deleted"
+    );
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup");
+}
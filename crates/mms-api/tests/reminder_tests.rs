@@ -0,0 +1,413 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use mms_api::reminders::digest::send_weekly_digests;
+use mms_api::reminders::job::send_due_reminders;
+use mms_api::reminders::streak::send_streak_reminders;
+use mms_api::router;
+use mms_api::user::email::EmailJob;
+use serde_json::{Value, json};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+/// Reminder jobs queued for one address
+fn reminders_for(rx: &mut mpsc::UnboundedReceiver<EmailJob>, email: &str) -> Vec<i64> {
+    let mut cards = Vec::new();
+    while let Ok(job) = rx.try_recv() {
+        if let EmailJob::ReviewReminder {
+            to_email,
+            cards_due,
+            ..
+        } = job
+            && to_email == email
+        {
+            cards.push(cards_due);
+        }
+    }
+    cards
+}
+
+/// Streak reminder jobs queued for one address
+fn streak_reminders_for(rx: &mut mpsc::UnboundedReceiver<EmailJob>, email: &str) -> Vec<i32> {
+    let mut streaks = Vec::new();
+    while let Ok(job) = rx.try_recv() {
+        if let EmailJob::StreakReminder {
+            to_email,
+            streak_days,
+            ..
+        } = job
+            && to_email == email
+        {
+            streaks.push(streak_days);
+        }
+    }
+    streaks
+}
+
+/// Weekly digest jobs queued for one address, as (reviews, correct, graded, preferences token)
+fn digests_for(
+    rx: &mut mpsc::UnboundedReceiver<EmailJob>,
+    email: &str,
+) -> Vec<(i64, i64, i64, String)> {
+    let mut digests = Vec::new();
+    while let Ok(job) = rx.try_recv() {
+        if let EmailJob::WeeklyDigest {
+            digest,
+            preferences_token,
+        } = job
+            && digest.email == email
+        {
+            digests.push((
+                digest.reviews,
+                digest.correct_reviews,
+                digest.graded_reviews,
+                preferences_token,
+            ));
+        }
+    }
+    digests
+}
+
+fn at(timestamp: &str) -> DateTime<Utc> {
+    timestamp.parse().expect("valid timestamp")
+}
+
+#[tokio::test]
+async fn test_reminder_settings_and_daily_reminder() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+    let key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("remind");
+    let username = common::test_data::unique_username("remind");
+    let user_id = common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    let settings: Value = client
+        .get_with_auth("/v1/users/me/reminders", &token, key)
+        .await
+        .json();
+    assert_eq!(
+        settings,
+        json!({
+            "enabled": false,
+            "hour": 18,
+            "timezone": "UTC",
+            "streak_email": true,
+            "streak_in_app": true
+        })
+    );
+
+    client
+        .patch_json_with_auth(
+            "/v1/users/me/reminders",
+            &json!({ "timezone": "Mars/Olympus_Mons" }),
+            &token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    client
+        .patch_json_with_auth(
+            "/v1/users/me/reminders",
+            &json!({ "hour": 24 }),
+            &token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    let response = client
+        .patch_json_with_auth(
+            "/v1/users/me/reminders",
+            &json!({ "enabled": true, "timezone": "Asia/Kathmandu" }),
+            &token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let settings: Value = response.json();
+    assert_eq!(settings["enabled"], true);
+    assert_eq!(settings["timezone"], "Asia/Kathmandu");
+
+    // Make the current hour in Kathmandu the reminder hour
+    sqlx::query(
+        "UPDATE users SET reminder_hour = EXTRACT(HOUR FROM NOW() AT TIME ZONE timezone) WHERE id = $1",
+    )
+    .bind(user_id)
+    .execute(&state.pool)
+    .await
+    .expect("Failed to set reminder hour");
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    // Nothing due, no reminder
+    send_due_reminders(&state.pool, &tx)
+        .await
+        .expect("Failed to send reminders");
+    assert!(reminders_for(&mut rx, &email).is_empty());
+
+    let card_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO flashcards (term, translation, language_from, language_to) VALUES ($1, 'recordar', 'en', 'es') RETURNING id",
+    )
+    .bind(format!("remind {}", Uuid::new_v4()))
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to create flashcard");
+    sqlx::query(
+        "INSERT INTO user_card_progress (user_id, flashcard_id, next_review_at) VALUES ($1, $2, NOW() - INTERVAL '1 hour')",
+    )
+    .bind(user_id)
+    .bind(card_id)
+    .execute(&state.pool)
+    .await
+    .expect("Failed to create progress");
+
+    send_due_reminders(&state.pool, &tx)
+        .await
+        .expect("Failed to send reminders");
+    assert_eq!(reminders_for(&mut rx, &email), vec![1]);
+
+    // Only one reminder per local day
+    send_due_reminders(&state.pool, &tx)
+        .await
+        .expect("Failed to send reminders");
+    assert!(reminders_for(&mut rx, &email).is_empty());
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup");
+    sqlx::query("DELETE FROM flashcards WHERE id = $1")
+        .bind(card_id)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup flashcard");
+}
+
+#[tokio::test]
+async fn test_streak_at_risk_reminder() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+    let key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("streak");
+    let username = common::test_data::unique_username("streak");
+    let user_id = common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    client
+        .patch_json_with_auth(
+            "/v1/users/me/reminders",
+            &json!({ "timezone": "Asia/Tokyo" }),
+            &token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::OK);
+
+    // A 3-day streak, last reviewed at 15:00 on February 2nd in Tokyo (UTC+9).
+    // The clock is mocked far in the past so no other test's users qualify.
+    sqlx::query(
+        r#"
+        INSERT INTO user_stats (user_id, current_streak_days) VALUES ($1, 3)
+        ON CONFLICT (user_id) DO UPDATE SET current_streak_days = 3
+        "#,
+    )
+    .bind(user_id)
+    .execute(&state.pool)
+    .await
+    .expect("Failed to set streak");
+    let card_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO flashcards (term, translation, language_from, language_to) VALUES ($1, 'racha', 'en', 'es') RETURNING id",
+    )
+    .bind(format!("streak {}", Uuid::new_v4()))
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to create flashcard");
+    sqlx::query(
+        "INSERT INTO user_card_progress (user_id, flashcard_id, next_review_at, last_review_at) VALUES ($1, $2, $3, $3)",
+    )
+    .bind(user_id)
+    .bind(card_id)
+    .bind(at("2001-02-02T06:00:00Z"))
+    .execute(&state.pool)
+    .await
+    .expect("Failed to create progress");
+
+    let notifications = || async {
+        sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND kind = 'streak_reminder'",
+        )
+        .bind(user_id)
+        .fetch_one(&state.pool)
+        .await
+        .expect("Failed to count notifications")
+    };
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let run = |now| send_streak_reminders(&state.pool, &state.events, Some(&tx), 4, now);
+
+    // 19:30 in Tokyo is more than 4 hours before midnight
+    run(at("2001-02-03T10:30:00Z"))
+        .await
+        .expect("Failed to send streak reminders");
+    assert!(streak_reminders_for(&mut rx, &email).is_empty());
+    assert_eq!(notifications().await, 0);
+
+    // 21:30 in Tokyo: email and in-app notification, once
+    run(at("2001-02-03T12:30:00Z"))
+        .await
+        .expect("Failed to send streak reminders");
+    assert_eq!(streak_reminders_for(&mut rx, &email), vec![3]);
+    assert_eq!(notifications().await, 1);
+
+    run(at("2001-02-03T13:30:00Z"))
+        .await
+        .expect("Failed to send streak reminders");
+    assert!(streak_reminders_for(&mut rx, &email).is_empty());
+    assert_eq!(notifications().await, 1);
+
+    // Reviewed on the 3rd in Tokyo, so the 4th is at risk; email is now off
+    client
+        .patch_json_with_auth(
+            "/v1/users/me/reminders",
+            &json!({ "streak_email": false }),
+            &token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::OK);
+    sqlx::query("UPDATE user_card_progress SET last_review_at = $2 WHERE user_id = $1")
+        .bind(user_id)
+        .bind(at("2001-02-03T06:00:00Z"))
+        .execute(&state.pool)
+        .await
+        .expect("Failed to update progress");
+
+    run(at("2001-02-04T12:30:00Z"))
+        .await
+        .expect("Failed to send streak reminders");
+    assert!(streak_reminders_for(&mut rx, &email).is_empty());
+    assert_eq!(notifications().await, 2);
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup");
+    sqlx::query("DELETE FROM flashcards WHERE id = $1")
+        .bind(card_id)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup flashcard");
+}
+
+#[tokio::test]
+async fn test_weekly_digest_and_unsubscribe() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+    let key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("digest");
+    let username = common::test_data::unique_username("digest");
+    let user_id = common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    let response = client
+        .get_with_auth("/v1/users/me/email-preferences", &token, key)
+        .await;
+    response.assert_status(StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["weekly_digest"], true);
+
+    // Activity in the week of January 29th to February 4th, 2001; the last day
+    // predates correct answer counts. The clock is mocked far in the past so
+    // no other test's users qualify.
+    sqlx::query(
+        r#"
+        INSERT INTO user_activity (user_id, activity_date, reviews_count, correct_reviews)
+        VALUES ($1, '2001-01-29', 10, 8), ($1, '2001-02-01', 5, 4), ($1, '2001-02-04', 6, NULL),
+               ($1, '2001-02-05', 50, 50)
+        "#,
+    )
+    .bind(user_id)
+    .execute(&state.pool)
+    .await
+    .expect("Failed to seed activity");
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let run = |now| send_weekly_digests(&state.pool, &tx, &state.auth.feed_keys, now);
+
+    // Sunday, and Monday before 08:00, are too early
+    run(at("2001-02-04T09:00:00Z"))
+        .await
+        .expect("Failed to send digests");
+    run(at("2001-02-05T07:30:00Z"))
+        .await
+        .expect("Failed to send digests");
+    assert!(digests_for(&mut rx, &email).is_empty());
+
+    // Monday 08:30: one digest for the past seven days, only once
+    run(at("2001-02-05T08:30:00Z"))
+        .await
+        .expect("Failed to send digests");
+    let digests = digests_for(&mut rx, &email);
+    assert_eq!(digests.len(), 1);
+    let (reviews, correct, graded, preferences_token) = digests[0].clone();
+    assert_eq!((reviews, correct, graded), (21, 12, 15));
+
+    run(at("2001-02-05T12:00:00Z"))
+        .await
+        .expect("Failed to send digests");
+    assert!(digests_for(&mut rx, &email).is_empty());
+
+    // The link in the email unsubscribes without signing in
+    client
+        .get("/v1/email-preferences?token=invalid")
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+    client
+        .get(&format!("/v1/email-preferences?token={token}"))
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+
+    let path = format!("/v1/email-preferences?token={preferences_token}");
+    let response = client
+        .patch_json(&path, &json!({ "weekly_digest": false }))
+        .await;
+    response.assert_status(StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["weekly_digest"], false);
+    assert_eq!(body["streak_reminders"], true);
+
+    let body: Value = client.get(&path).await.json();
+    assert_eq!(body["weekly_digest"], false);
+
+    // The following Monday there is activity, but the digest is off
+    run(at("2001-02-12T09:00:00Z"))
+        .await
+        .expect("Failed to send digests");
+    assert!(digests_for(&mut rx, &email).is_empty());
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup");
+}
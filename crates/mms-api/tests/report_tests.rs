@@ -0,0 +1,268 @@
+use crate::common::{self, TestClient, TestStateBuilder, account};
+use axum::http::StatusCode;
+use mms_api::{auth::Role, router, state::ApiState};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+async fn public_deck_with_card(state: &ApiState, title: &str) -> (Uuid, Uuid) {
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ($1, 'fr', 'en') RETURNING id",
+    )
+    .bind(title)
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to create deck");
+    let card_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO flashcards (term, translation, language_from, language_to) VALUES ($1, 'cat', 'fr', 'en') RETURNING id",
+    )
+    .bind(format!("chat {deck_id}"))
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to create card");
+    sqlx::query("INSERT INTO deck_flashcards (deck_id, flashcard_id) VALUES ($1, $2)")
+        .bind(deck_id)
+        .bind(card_id)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to link card");
+    (deck_id, card_id)
+}
+
+#[tokio::test]
+async fn test_reported_comment_is_warned_and_reports_are_closed_together() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let key = &state.cookie.cookie_key;
+
+    let commenter = account(&state, "report_commenter", Role::Learner).await;
+    let first = account(&state, "report_first", Role::Learner).await;
+    let second = account(&state, "report_second", Role::Learner).await;
+    let moderator = account(&state, "report_moderator", Role::Admin).await;
+    let (deck_id, _) = public_deck_with_card(&state, "Reported comments").await;
+
+    let response = client
+        .post_json_with_auth(
+            &format!("/v1/decks/{deck_id}/comments"),
+            &json!({ "body": "Buy followers at example.com" }),
+            &commenter.token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::CREATED);
+    let comment_id = response.json::<Value>()["id"].as_str().unwrap().to_string();
+
+    let report = json!({ "target_type": "comment", "target_id": comment_id, "reason": "spam" });
+    let response = client
+        .post_json_with_auth("/v1/reports", &report, &first.token, key)
+        .await;
+    response.assert_status(StatusCode::CREATED);
+    let report_id = response.json::<Value>()["id"].as_str().unwrap().to_string();
+    client
+        .post_json_with_auth("/v1/reports", &report, &first.token, key)
+        .await
+        .assert_status(StatusCode::CONFLICT);
+    client
+        .post_json_with_auth("/v1/reports", &report, &second.token, key)
+        .await
+        .assert_status(StatusCode::CREATED);
+    client
+        .post_json_with_auth(
+            "/v1/reports",
+            &json!({ "target_type": "comment", "target_id": Uuid::new_v4(), "reason": "spam" }),
+            &first.token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    // Only moderators see the queue
+    client
+        .get_with_auth("/v1/admin/reports", &first.token, key)
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+    let response = client
+        .get_with_auth(
+            "/v1/admin/reports?target_type=comment&limit=200",
+            &moderator.token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let queue = response.json::<Vec<Value>>();
+    let queued = queue
+        .iter()
+        .find(|report| report["id"] == report_id.as_str())
+        .expect("Report missing from the queue");
+    assert_eq!(queued["open_reports"], 2);
+    assert_eq!(queued["target_preview"], "Buy followers at example.com");
+
+    // Warning the author closes both reports on the comment
+    let actions_path = format!("/v1/admin/reports/{report_id}/actions");
+    let response = client
+        .post_json_with_auth(
+            &actions_path,
+            &json!({ "action": "warn", "note": "No advertising, please" }),
+            &moderator.token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let outcome = response.json::<Value>();
+    assert_eq!(outcome["status"], "resolved");
+    assert_eq!(outcome["reports_closed"], 2);
+
+    let kinds: Vec<String> =
+        sqlx::query_scalar("SELECT kind FROM notifications WHERE user_id = $1")
+            .bind(commenter.id)
+            .fetch_all(&state.pool)
+            .await
+            .expect("Failed to read notifications");
+    assert_eq!(kinds, vec!["content_warning".to_string()]);
+
+    let response = client
+        .get_with_auth("/v1/reports", &second.token, key)
+        .await;
+    response.assert_status(StatusCode::OK);
+    let mine = response.json::<Vec<Value>>();
+    assert_eq!(mine.len(), 1);
+    assert_eq!(mine[0]["status"], "resolved");
+    assert_eq!(mine[0]["action"], "warn");
+
+    client
+        .post_json_with_auth(
+            &actions_path,
+            &json!({ "action": "dismiss" }),
+            &moderator.token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::CONFLICT);
+
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup deck");
+    for account in [commenter, first, second, moderator] {
+        common::db::delete_user_by_email(&state.pool, &account.email)
+            .await
+            .expect("Failed to cleanup");
+    }
+}
+
+#[tokio::test]
+async fn test_hidden_and_deleted_content_leaves_the_catalogue() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let key = &state.cookie.cookie_key;
+
+    let reporter = account(&state, "report_content", Role::Learner).await;
+    let moderator = account(&state, "report_content_mod", Role::Admin).await;
+    let (deck_id, card_id) = public_deck_with_card(&state, "Reported deck").await;
+
+    let mut report_ids = Vec::new();
+    for (target_type, target_id) in [("card", card_id), ("deck", deck_id)] {
+        let response = client
+            .post_json_with_auth(
+                "/v1/reports",
+                &json!({
+                    "target_type": target_type,
+                    "target_id": target_id,
+                    "reason": "incorrect",
+                    "details": "  Chat means cat, not dog  ",
+                }),
+                &reporter.token,
+                key,
+            )
+            .await;
+        response.assert_status(StatusCode::CREATED);
+        let report = response.json::<Value>();
+        assert_eq!(report["details"], "Chat means cat, not dog");
+        assert_eq!(report["status"], "open");
+        report_ids.push(report["id"].as_str().unwrap().to_string());
+    }
+
+    let act = |report_id: &str, action: &str| {
+        let path = format!("/v1/admin/reports/{report_id}/actions");
+        let body = json!({ "action": action });
+        let client = &client;
+        let token = &moderator.token;
+        async move { client.post_json_with_auth(&path, &body, token, key).await }
+    };
+
+    act(&report_ids[0], "warn")
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    act(&report_ids[0], "delete")
+        .await
+        .assert_status(StatusCode::OK);
+    let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM flashcards WHERE id = $1")
+        .bind(card_id)
+        .fetch_one(&state.pool)
+        .await
+        .expect("Failed to count cards");
+    assert_eq!(remaining, 0);
+
+    act(&report_ids[1], "hide")
+        .await
+        .assert_status(StatusCode::OK);
+    client
+        .get(&format!("/v1/decks/{deck_id}/comments"))
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    let response = client
+        .get("/v1/decks?language_from=fr&language_to=en&limit=100")
+        .await;
+    response.assert_status(StatusCode::OK);
+    assert!(
+        !response
+            .json::<Vec<Value>>()
+            .iter()
+            .any(|deck| deck["id"] == deck_id.to_string())
+    );
+
+    // Hidden content can no longer be reported
+    client
+        .post_json_with_auth(
+            "/v1/reports",
+            &json!({ "target_type": "deck", "target_id": deck_id, "reason": "spam" }),
+            &reporter.token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    let response = client
+        .get_with_auth(
+            "/v1/admin/reports?status=resolved&limit=200",
+            &moderator.token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let resolved = response.json::<Vec<Value>>();
+    let card_report = resolved
+        .iter()
+        .find(|report| report["id"] == report_ids[0].as_str())
+        .expect("Resolved report missing");
+    assert_eq!(card_report["action"], "delete");
+    assert!(card_report["target_preview"].is_null());
+    assert_eq!(card_report["resolved_by"], moderator.id.to_string());
+
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup deck");
+    for account in [reporter, moderator] {
+        common::db::delete_user_by_email(&state.pool, &account.email)
+            .await
+            .expect("Failed to cleanup");
+    }
+}
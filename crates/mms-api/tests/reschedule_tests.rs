@@ -0,0 +1,153 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use mms_api::{auth::Role, router};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_reschedule_strategies() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let pool = &state.pool;
+    let cookie_key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("returning");
+    let user_id = common::db::create_verified_user(
+        pool,
+        &email,
+        &common::test_data::unique_username("returning"),
+    )
+    .await
+    .unwrap();
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    // Four overdue cards, a leech due tomorrow and a card due in 80 days
+    let cards = [
+        ("-10 days", 5, 0),
+        ("-9 days", 1, 0),
+        ("-8 days", 3, 1),
+        ("-7 days", 0, 2),
+        ("1 day", 2, 9),
+        ("80 days", 9, 0),
+    ];
+    let mut card_ids = Vec::new();
+    for (due_in, times_correct, times_wrong) in cards {
+        let card_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO flashcards (term, translation, language_from, language_to) VALUES ('break ' || gen_random_uuid(), 'pausa', 'en', 'es') RETURNING id",
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO user_card_progress (user_id, flashcard_id, next_review_at, times_correct, times_wrong) VALUES ($1, $2, NOW() + $3::interval, $4, $5)",
+        )
+        .bind(user_id)
+        .bind(card_id)
+        .bind(due_in)
+        .bind(times_correct)
+        .bind(times_wrong)
+        .execute(pool)
+        .await
+        .unwrap();
+        card_ids.push(card_id);
+    }
+    let uri = format!("/v1/users/{user_id}/reschedule");
+    let due_in_days = |card_id: Uuid| async move {
+        sqlx::query_scalar::<_, f64>(
+            "SELECT EXTRACT(EPOCH FROM next_review_at - NOW())::float8 / 86400 FROM user_card_progress WHERE user_id = $1 AND flashcard_id = $2",
+        )
+        .bind(user_id)
+        .bind(card_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+        .round() as i64
+    };
+
+    for invalid in [
+        json!({ "strategy": "spread_overdue", "days": 0 }),
+        json!({ "strategy": "cap_interval", "max_days": 91 }),
+        json!({ "strategy": "reset_leeches", "min_lapses": 0 }),
+    ] {
+        client
+            .post_json_with_auth(&uri, &invalid, &token, cookie_key)
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    // The two weakest overdue cards stay due, the two strongest move a day
+    let response = client
+        .post_json_with_auth(
+            &uri,
+            &json!({ "strategy": "spread_overdue", "days": 2 }),
+            &token,
+            cookie_key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let result: Value = response.json();
+    assert_eq!(result["cards_rescheduled"], 4);
+    assert_eq!(due_in_days(card_ids[0]).await, 1);
+    assert_eq!(due_in_days(card_ids[1]).await, 0);
+    assert_eq!(due_in_days(card_ids[2]).await, 1);
+    assert_eq!(due_in_days(card_ids[3]).await, 0);
+
+    let result: Value = client
+        .post_json_with_auth(
+            &uri,
+            &json!({ "strategy": "reset_leeches" }),
+            &token,
+            cookie_key,
+        )
+        .await
+        .json();
+    assert_eq!(result["cards_rescheduled"], 1);
+    let (times_correct, times_wrong): (i32, i32) = sqlx::query_as(
+        "SELECT times_correct, times_wrong FROM user_card_progress WHERE user_id = $1 AND flashcard_id = $2",
+    )
+    .bind(user_id)
+    .bind(card_ids[4])
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    assert_eq!((times_correct, times_wrong), (0, 0));
+    assert_eq!(due_in_days(card_ids[4]).await, 0);
+
+    let result: Value = client
+        .post_json_with_auth(
+            &uri,
+            &json!({ "strategy": "cap_interval", "max_days": 30 }),
+            &token,
+            cookie_key,
+        )
+        .await
+        .json();
+    assert_eq!(result["cards_rescheduled"], 1);
+    assert_eq!(due_in_days(card_ids[5]).await, 30);
+
+    // The forecast is recomputed with the new schedule
+    let forecast: Value = client
+        .get_with_auth(&format!("/v1/users/{user_id}/forecast"), &token, cookie_key)
+        .await
+        .json();
+    assert_eq!(forecast["days"][0]["due"], 3);
+
+    let other = common::account(&state, "other", Role::Learner).await;
+    client
+        .post_json_with_auth(
+            &uri,
+            &json!({ "strategy": "reset_leeches" }),
+            &other.token,
+            cookie_key,
+        )
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+
+    common::db::delete_user_by_email(pool, &other.email)
+        .await
+        .ok();
+    common::db::delete_user_by_email(pool, &email).await.ok();
+}
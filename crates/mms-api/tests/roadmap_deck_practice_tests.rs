@@ -61,7 +61,7 @@ async fn create_test_roadmap_and_decks(pool: &PgPool) -> anyhow::Result<(Uuid, U
     // Create flashcards for deck 1 with unique IDs in content to avoid duplicates
     let flashcard1_id = Uuid::new_v4();
     let flashcard2_id = Uuid::new_v4();
-    let unique_suffix = format!("_{}", Uuid::new_v4().to_string()[..8].to_string());
+    let unique_suffix = format!("_{}", &Uuid::new_v4().to_string()[..8]);
 
     sqlx::query(
         r#"
@@ -276,6 +276,64 @@ async fn test_get_roadmap_nodes_public() {
         .expect("Failed to cleanup");
 }
 
+#[tokio::test]
+async fn test_public_listings_served_from_cache_while_database_is_down() {
+    // The pool of `state` is closed below, so data is managed through another one
+    let setup = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let (roadmap_id, _, _) = create_test_roadmap_and_decks(&setup.pool)
+        .await
+        .expect("Failed to create test data");
+
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+    let nodes_path = format!("/v1/roadmaps/{roadmap_id}/nodes");
+
+    let fresh = client.get(&nodes_path).await;
+    fresh.assert_status(StatusCode::OK);
+    assert!(fresh.headers.get("warning").is_none());
+    let body: serde_json::Value = client.get("/health").await.json();
+    assert_eq!(body["status"], "healthy");
+
+    state.pool.close().await;
+
+    // Cached listings keep being served, marked stale
+    let stale = client.get(&nodes_path).await;
+    stale.assert_status(StatusCode::OK);
+    assert!(
+        stale.headers["warning"]
+            .to_str()
+            .unwrap()
+            .starts_with("111")
+    );
+    assert_eq!(stale.body, fresh.body);
+
+    // Requests never served before still fail
+    client
+        .get(&format!("/v1/roadmaps/{}/nodes", Uuid::new_v4()))
+        .await
+        .assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+
+    let body: serde_json::Value = client.get("/health").await.json();
+    assert_eq!(body["status"], "degraded");
+    let ready = client.get("/health/ready").await;
+    ready.assert_status(StatusCode::OK);
+    let body: serde_json::Value = ready.json();
+    assert_eq!(body["status"], "degraded");
+    assert_eq!(body["checks"]["database"]["status"], "down");
+
+    common::db::delete_roadmap_by_id(&setup.pool, roadmap_id)
+        .await
+        .expect("Failed to cleanup");
+}
+
 #[tokio::test]
 async fn test_get_roadmap_with_progress_authenticated() {
     let state = TestStateBuilder::new()
@@ -308,7 +366,7 @@ async fn test_get_roadmap_with_progress_authenticated() {
     .await
     .expect("Failed to create progress");
 
-    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_secret);
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
 
     let app = router::router().with_state(state.clone());
     let client = TestClient::new(app);
@@ -440,7 +498,7 @@ async fn test_get_practice_session_for_deck() {
         .await
         .expect("Failed to create test data");
 
-    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_secret);
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
 
     let app = router::router().with_state(state.clone());
     let client = TestClient::new(app);
@@ -505,6 +563,92 @@ async fn test_get_practice_session_unauthenticated() {
         .expect("Failed to cleanup roadmap");
 }
 
+#[tokio::test]
+async fn test_export_deck_streams_flashcards() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let email = common::test_data::unique_email("deckexport");
+    let username = common::test_data::unique_username("deckexport");
+    let user_id = common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+
+    let (roadmap_id, deck_id, empty_deck_id) = create_test_roadmap_and_decks(&state.pool)
+        .await
+        .expect("Failed to create test data");
+
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+
+    // Default format is a single JSON array
+    let response = client
+        .get_with_auth(
+            &format!("/v1/decks/{}/export", deck_id),
+            &token,
+            &state.cookie.cookie_key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    // Streamed, so never hashed for an ETag
+    assert!(response.headers.get("etag").is_none());
+    let cards: Vec<serde_json::Value> = response.json();
+    assert_eq!(cards.len(), 2);
+    assert!(cards.iter().all(|c| c["language_to"] == "es"));
+
+    // NDJSON on request, one flashcard per line
+    let response = client
+        .get_with_auth_accepting(
+            &format!("/v1/decks/{}/export", deck_id),
+            &token,
+            &state.cookie.cookie_key,
+            "application/x-ndjson",
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    assert_eq!(
+        response.headers.get("content-type").unwrap(),
+        "application/x-ndjson"
+    );
+    let lines: Vec<serde_json::Value> = response
+        .text()
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("Each line should be JSON"))
+        .collect();
+    assert_eq!(lines, cards);
+
+    // A deck without cards is an empty array, an unknown deck is a 404
+    let response = client
+        .get_with_auth(
+            &format!("/v1/decks/{}/export", empty_deck_id),
+            &token,
+            &state.cookie.cookie_key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    assert_eq!(response.text(), "[]");
+
+    let response = client
+        .get_with_auth(
+            &format!("/v1/decks/{}/export", Uuid::new_v4()),
+            &token,
+            &state.cookie.cookie_key,
+        )
+        .await;
+    response.assert_status(StatusCode::NOT_FOUND);
+
+    common::db::delete_roadmap_by_id(&state.pool, roadmap_id)
+        .await
+        .expect("Failed to cleanup roadmap");
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup user");
+}
+
 #[tokio::test]
 async fn test_submit_review_correct_answer() {
     let state = TestStateBuilder::new()
@@ -538,7 +682,7 @@ async fn test_submit_review_correct_answer() {
     .await
     .expect("Failed to get flashcard");
 
-    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_secret);
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
 
     let app = router::router().with_state(state.clone());
     let client = TestClient::new(app);
@@ -657,7 +801,7 @@ async fn test_submit_review_wrong_answer() {
     .await
     .expect("Failed to get flashcard");
 
-    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_secret);
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
 
     let app = router::router().with_state(state.clone());
     let client = TestClient::new(app);
@@ -749,7 +893,7 @@ async fn test_submit_review_updates_stats() {
             .await
             .expect("Failed to get initial stats");
 
-    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_secret);
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
 
     let app = router::router().with_state(state.clone());
     let client = TestClient::new(app);
@@ -842,3 +986,479 @@ async fn test_submit_review_unauthenticated() {
         .await
         .expect("Failed to cleanup roadmap");
 }
+
+#[tokio::test]
+async fn test_difficulty_score_exposed_and_shortens_learning_interval() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let (roadmap_id, deck_id, _) = create_test_roadmap_and_decks(&state.pool)
+        .await
+        .expect("Failed to create test data");
+
+    let flashcard_id: Uuid =
+        sqlx::query_scalar("SELECT flashcard_id FROM deck_flashcards WHERE deck_id = $1 LIMIT 1")
+            .bind(deck_id)
+            .fetch_one(&state.pool)
+            .await
+            .expect("Failed to get flashcard");
+
+    // Four learners struggled with the card: 20 reviews, 12 wrong, 12s per answer
+    let mut emails = Vec::new();
+    for _ in 0..4 {
+        let email = common::test_data::unique_email("difficulty");
+        let username = common::test_data::unique_username("difficultyuser");
+        let user_id = common::db::create_verified_user(&state.pool, &email, &username)
+            .await
+            .expect("Failed to create user");
+        sqlx::query(
+            r#"
+            INSERT INTO user_card_progress
+                (user_id, flashcard_id, next_review_at, times_correct, times_wrong, total_response_ms, timed_reviews)
+            VALUES ($1, $2, NOW(), 2, 3, 60000, 5)
+            "#,
+        )
+        .bind(user_id)
+        .bind(flashcard_id)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to seed progress");
+        emails.push(email);
+    }
+
+    mms_api::difficulty::refresh_difficulty_scores(&state.pool)
+        .await
+        .expect("Failed to refresh difficulty");
+
+    let (score, reviews, learners): (f32, i32, i32) = sqlx::query_as(
+        "SELECT score, reviews, learners FROM flashcard_difficulty WHERE flashcard_id = $1",
+    )
+    .bind(flashcard_id)
+    .fetch_one(&state.pool)
+    .await
+    .expect("Difficulty should be stored");
+    assert!((score - 0.7).abs() < 1e-4, "Unexpected score {score}");
+    assert_eq!(reviews, 20);
+    assert_eq!(learners, 4);
+
+    // A new learner sees the score in the practice payload
+    let email = common::test_data::unique_email("difficulty");
+    let username = common::test_data::unique_username("difficultyuser");
+    let user_id = common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+    emails.push(email.clone());
+
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+    let client = TestClient::new(router::router().with_state(state.clone()));
+
+    let response = client
+        .get_with_auth(
+            &format!("/v1/decks/{}/practice", deck_id),
+            &token,
+            &state.cookie.cookie_key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let cards: Vec<serde_json::Value> = response.json();
+    let card = cards
+        .iter()
+        .find(|card| card["id"] == flashcard_id.to_string())
+        .expect("Card should be due");
+    assert!(card["difficulty"].as_f64().is_some());
+    let other = cards
+        .iter()
+        .find(|card| card["id"] != flashcard_id.to_string())
+        .expect("Second card should be due");
+    assert!(other["difficulty"].is_null());
+
+    // A wrong answer on a hard card comes back sooner than the usual 2 hours
+    let response = client
+        .post_json_with_auth(
+            &format!("/v1/practice/{}/review", flashcard_id),
+            &json!({
+                "user_answer": "wrong",
+                "deck_id": deck_id,
+                "response_time_ms": 90_000
+            }),
+            &token,
+            &state.cookie.cookie_key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+
+    let (minutes, total_response_ms, timed_reviews): (f64, i64, i32) = sqlx::query_as(
+        r#"
+        SELECT EXTRACT(EPOCH FROM next_review_at - NOW())::FLOAT8 / 60, total_response_ms, timed_reviews
+        FROM user_card_progress
+        WHERE user_id = $1 AND flashcard_id = $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(flashcard_id)
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to get progress");
+    assert!(
+        minutes < 90.0,
+        "Expected a shortened interval, got {minutes} minutes"
+    );
+    // Response times are capped at a minute
+    assert_eq!(total_response_ms, 60_000);
+    assert_eq!(timed_reviews, 1);
+
+    common::db::delete_roadmap_by_id(&state.pool, roadmap_id)
+        .await
+        .expect("Failed to cleanup roadmap");
+    for email in &emails {
+        common::db::delete_user_by_email(&state.pool, email)
+            .await
+            .expect("Failed to cleanup user");
+    }
+}
+
+/// Insert a card into a deck and return its id
+async fn insert_deck_card(pool: &PgPool, deck_id: Uuid, translation: &str) -> Uuid {
+    let id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO flashcards (term, translation, language_from, language_to)
+        VALUES ($1, $2, 'en', 'es')
+        RETURNING id
+        "#,
+    )
+    .bind(format!("term for {translation}"))
+    .bind(translation)
+    .fetch_one(pool)
+    .await
+    .expect("Failed to create flashcard");
+
+    sqlx::query("INSERT INTO deck_flashcards (deck_id, flashcard_id) VALUES ($1, $2)")
+        .bind(deck_id)
+        .bind(id)
+        .execute(pool)
+        .await
+        .expect("Failed to link flashcard");
+
+    id
+}
+
+#[tokio::test]
+async fn test_practice_session_introduces_cards_with_known_words_first() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let email = common::test_data::unique_email("iplusone");
+    let username = common::test_data::unique_username("iplusone");
+    let user_id = common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+
+    let (roadmap_id, known_deck_id, new_deck_id) = create_test_roadmap_and_decks(&state.pool)
+        .await
+        .expect("Failed to create test data");
+
+    // Invented words keep other tests' cards from matching
+    let suffix = &Uuid::new_v4().simple().to_string()[..8];
+    let [el, gato, come, casa, roja] =
+        ["el", "gato", "come", "casa", "roja"].map(|w| format!("{w}{suffix}"));
+
+    // The user knows "el gato" well enough, and is still learning "casa"
+    for (translation, times_correct) in [(format!("{el} {gato}"), 3), (casa.clone(), 1)] {
+        let id = insert_deck_card(&state.pool, known_deck_id, &translation).await;
+        sqlx::query(
+            r#"
+            INSERT INTO user_card_progress (user_id, flashcard_id, next_review_at, times_correct, times_wrong)
+            VALUES ($1, $2, NOW() + INTERVAL '1 day', $3, 0)
+            "#,
+        )
+        .bind(user_id)
+        .bind(id)
+        .bind(times_correct)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to seed progress");
+    }
+
+    // Inserted hardest first so the order cannot come from insertion
+    let expected = [
+        format!("{gato}, {el} {gato}!"),
+        format!("{el} {gato} {come}"),
+        format!("{el} {casa} {roja}"),
+    ];
+    for translation in expected.iter().rev() {
+        insert_deck_card(&state.pool, new_deck_id, translation).await;
+    }
+
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+    let client = TestClient::new(router::router().with_state(state.clone()));
+
+    let response = client
+        .get_with_auth(
+            &format!("/v1/decks/{}/practice", new_deck_id),
+            &token,
+            &state.cookie.cookie_key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+
+    let cards: Vec<serde_json::Value> = response.json();
+    let order: Vec<&str> = cards
+        .iter()
+        .map(|card| card["translation"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        order,
+        expected.iter().map(String::as_str).collect::<Vec<_>>()
+    );
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup user");
+    common::db::delete_roadmap_by_id(&state.pool, roadmap_id)
+        .await
+        .expect("Failed to cleanup roadmap");
+}
+
+#[tokio::test]
+async fn test_implausible_reviews_are_graded_but_flagged() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let email = common::test_data::unique_email("pace");
+    let username = common::test_data::unique_username("paceuser");
+    let user_id = common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+    let client = TestClient::new(router::router().with_state(state.clone()));
+
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('Pace deck', 'en', 'es') RETURNING id",
+    )
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to create deck");
+    let suffix = Uuid::new_v4();
+    let burst_card = insert_deck_card(&state.pool, deck_id, &format!("rapido {suffix}")).await;
+    let fast_card = insert_deck_card(&state.pool, deck_id, &format!("veloz {suffix}")).await;
+
+    let review = |flashcard_id: Uuid, answer: String, response_time_ms: u32| {
+        let client = &client;
+        let token = &token;
+        let key = &state.cookie.cookie_key;
+        async move {
+            client
+                .post_json_with_auth(
+                    &format!("/v1/practice/{flashcard_id}/review"),
+                    &json!({
+                        "user_answer": answer,
+                        "deck_id": deck_id,
+                        "response_time_ms": response_time_ms
+                    }),
+                    token,
+                    key,
+                )
+                .await
+                .assert_status(StatusCode::OK);
+        }
+    };
+    let flagged = || async {
+        let activity: i32 = sqlx::query_scalar(
+            "SELECT flagged_reviews FROM user_activity WHERE user_id = $1 AND activity_date = CURRENT_DATE",
+        )
+        .bind(user_id)
+        .fetch_one(&state.pool)
+        .await
+        .expect("Failed to read activity");
+        let stats: i32 =
+            sqlx::query_scalar("SELECT flagged_reviews FROM user_stats WHERE user_id = $1")
+                .bind(user_id)
+                .fetch_one(&state.pool)
+                .await
+                .expect("Failed to read stats");
+        (activity, stats)
+    };
+
+    // The window is already full: the next review is part of a burst
+    sqlx::query(
+        "INSERT INTO review_pace (user_id, window_started_at, reviews) VALUES ($1, NOW(), 20)",
+    )
+    .bind(user_id)
+    .execute(&state.pool)
+    .await
+    .expect("Failed to fill pace window");
+    review(burst_card, format!("rapido {suffix}"), 2_000).await;
+    assert_eq!(flagged().await, (1, 1));
+
+    // An expired window starts over; a correct answer in 50ms is still implausible
+    sqlx::query(
+        "UPDATE review_pace SET window_started_at = NOW() - INTERVAL '1 minute' WHERE user_id = $1",
+    )
+    .bind(user_id)
+    .execute(&state.pool)
+    .await
+    .expect("Failed to expire pace window");
+    review(fast_card, format!("veloz {suffix}"), 50).await;
+    assert_eq!(flagged().await, (2, 2));
+
+    // Both reviews still count and scheduled their cards, but flagged times are not kept
+    let (reviews, timed): (i32, i64) = sqlx::query_as(
+        r#"
+        SELECT
+            (SELECT reviews_count FROM user_activity WHERE user_id = $1 AND activity_date = CURRENT_DATE),
+            (SELECT SUM(timed_reviews) FROM user_card_progress WHERE user_id = $1)
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to read progress");
+    assert_eq!((reviews, timed), (2, 0));
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup user");
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup deck");
+    sqlx::query("DELETE FROM flashcards WHERE id = ANY($1)")
+        .bind(vec![burst_card, fast_card])
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup flashcards");
+}
+
+#[tokio::test]
+async fn test_unchanged_roadmap_is_not_modified() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+
+    let (roadmap_id, _, _) = create_test_roadmap_and_decks(&state.pool)
+        .await
+        .expect("Failed to create test data");
+    let uri = format!("/v1/roadmaps/{roadmap_id}/nodes");
+    let get_if_none_match = |tag: String| {
+        let uri = uri.clone();
+        let client = &client;
+        async move {
+            let request = axum::http::Request::builder()
+                .uri(uri)
+                .header("if-none-match", tag)
+                .body(axum::body::Body::empty())
+                .unwrap();
+            client.request(request).await
+        }
+    };
+
+    let response = client.get(&uri).await;
+    response.assert_status(StatusCode::OK);
+    let etag = response
+        .headers
+        .get("etag")
+        .expect("Roadmap nodes should carry an ETag")
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    let response = get_if_none_match(etag.clone()).await;
+    response.assert_status(StatusCode::NOT_MODIFIED);
+    assert!(response.body.is_empty());
+    assert_eq!(response.headers["etag"], etag.as_str());
+
+    // A stale tag gets the full body again
+    let response = get_if_none_match("W/\"stale\"".to_string()).await;
+    response.assert_status(StatusCode::OK);
+    assert_eq!(response.headers["etag"], etag.as_str());
+    assert!(!response.body.is_empty());
+
+    common::db::delete_roadmap_by_id(&state.pool, roadmap_id)
+        .await
+        .expect("Failed to cleanup roadmap");
+}
+
+#[tokio::test]
+async fn test_concurrent_reviews_of_a_card_count_once() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let email = common::test_data::unique_email("concurrent");
+    let user_id = common::db::create_verified_user(
+        &state.pool,
+        &email,
+        &common::test_data::unique_username("concurrent"),
+    )
+    .await
+    .expect("Failed to create user");
+    let (roadmap_id, deck_id, _) = create_test_roadmap_and_decks(&state.pool)
+        .await
+        .expect("Failed to create test data");
+    let (flashcard_id, translation): (Uuid, String) = sqlx::query_as(
+        r#"
+        SELECT f.id, f.translation FROM flashcards f
+        JOIN deck_flashcards df ON f.id = df.flashcard_id
+        WHERE df.deck_id = $1
+        LIMIT 1
+        "#,
+    )
+    .bind(deck_id)
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to get flashcard");
+
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let uri = format!("/v1/practice/{flashcard_id}/review");
+    let body = json!({ "user_answer": translation, "deck_id": deck_id });
+
+    // The same answer submitted from several devices at once
+    let responses = futures_util::future::join_all(
+        (0..6).map(|_| client.post_json_with_auth(&uri, &body, &token, &state.cookie.cookie_key)),
+    )
+    .await;
+
+    let graded = responses
+        .iter()
+        .filter(|response| response.status == StatusCode::OK)
+        .count();
+    assert_eq!(graded, 1, "Only one submission of a due card is graded");
+    for response in responses.iter().filter(|r| r.status != StatusCode::OK) {
+        response.assert_status(StatusCode::BAD_REQUEST);
+        assert!(response.text().contains("not due"));
+    }
+
+    let (times_correct, total_reviews, reviews_today): (i32, i32, i32) = sqlx::query_as(
+        r#"
+        SELECT p.times_correct, s.total_reviews, a.reviews_count
+        FROM user_card_progress p
+        JOIN user_stats s ON s.user_id = p.user_id
+        JOIN user_activity a ON a.user_id = p.user_id
+        WHERE p.user_id = $1 AND p.flashcard_id = $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(flashcard_id)
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to read progress");
+    assert_eq!((times_correct, total_reviews, reviews_today), (1, 1, 1));
+
+    common::db::delete_roadmap_by_id(&state.pool, roadmap_id)
+        .await
+        .expect("Failed to cleanup roadmap");
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup user");
+}
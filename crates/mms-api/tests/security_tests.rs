@@ -1,6 +1,6 @@
 use crate::common::{self, TestClient, TestStateBuilder};
 use axum::http::StatusCode;
-use mms_api::router;
+use mms_api::{auth::jwt::JwtKeys, router};
 use serde_json::json;
 
 // ============================================================================
@@ -162,7 +162,7 @@ async fn test_xss_in_username() {
     let app = router::router().with_state(state.clone());
     let client = TestClient::new(app);
 
-    let xss_payloads = vec![
+    let xss_payloads = [
         "<script>alert('XSS')</script>",
         "<img src=x onerror=alert('XSS')>",
         "javascript:alert('XSS')",
@@ -226,7 +226,7 @@ async fn test_xss_in_profile_update() {
         .await
         .expect("Failed to create user");
 
-    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_secret);
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
 
     // Try to update with XSS payload
     let body = json!({
@@ -315,7 +315,7 @@ async fn test_auth_bypass_wrong_user_token() {
         .expect("Failed to create user1");
 
     // Get token for user1
-    let user1_token = common::jwt::create_test_token(user1_id, &email1, &state.auth.jwt_secret);
+    let user1_token = common::jwt::create_test_token(user1_id, &email1, &state.auth.jwt_keys);
 
     // Access user1's own dashboard with their token (should succeed since /me resolves from JWT)
     let response = client
@@ -378,8 +378,11 @@ async fn test_auth_bypass_wrong_secret() {
         .expect("Failed to create user");
 
     // Create token with wrong secret
-    let wrong_token =
-        common::jwt::create_test_token(user_id, &email, "wrong_secret_that_doesnt_match_12345");
+    let wrong_token = common::jwt::create_test_token(
+        user_id,
+        &email,
+        &JwtKeys::hmac("wrong_secret_that_doesnt_match_12345"),
+    );
 
     let response = client
         .get_with_auth(
@@ -460,7 +463,7 @@ async fn test_idor_profile_access() {
         .await
         .expect("Failed to create user2");
 
-    let user1_token = common::jwt::create_test_token(user1_id, &email1, &state.auth.jwt_secret);
+    let user1_token = common::jwt::create_test_token(user1_id, &email1, &state.auth.jwt_keys);
 
     // User1 updates via /me endpoint - this only affects user1, not user2
     // With the new /me routes, IDOR is impossible since user_id comes from JWT
@@ -518,7 +521,7 @@ async fn test_idor_practice_submission() {
         .await
         .expect("Failed to create user1");
 
-    let user1_token = common::jwt::create_test_token(user1_id, &email1, &state.auth.jwt_secret);
+    let user1_token = common::jwt::create_test_token(user1_id, &email1, &state.auth.jwt_keys);
 
     // With /me routes, IDOR is impossible since user_id comes from JWT.
     // Test that submitting a review with a non-existent flashcard returns an error.
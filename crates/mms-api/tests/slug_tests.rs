@@ -0,0 +1,162 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use mms_api::{auth::Role, router};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_roadmaps_and_decks_resolve_by_slug() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let pool = &state.pool;
+    let key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("slugs");
+    let user_id = common::db::create_verified_user(
+        pool,
+        &email,
+        &common::test_data::unique_username("slugs"),
+    )
+    .await
+    .expect("Failed to create user");
+    let learner = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+    let author = common::jwt::create_test_token_with_role(
+        user_id,
+        &email,
+        Role::Author,
+        &state.auth.jwt_keys,
+    );
+
+    let suffix = &Uuid::new_v4().simple().to_string()[..8];
+    // Titles that slugify alike: the second roadmap gets a numbered slug
+    let roadmaps: Vec<(Uuid, String)> = sqlx::query_as(
+        r#"
+        INSERT INTO roadmaps (title, language_from, language_to)
+        VALUES ('Slug Roadmap ' || $1, 'en', 'es'), ('Slug roadmap: ' || $1, 'en', 'es')
+        RETURNING id, slug
+        "#,
+    )
+    .bind(suffix)
+    .fetch_all(pool)
+    .await
+    .expect("Failed to create roadmaps");
+    let mut slugs: Vec<&str> = roadmaps.iter().map(|(_, slug)| slug.as_str()).collect();
+    slugs.sort_unstable();
+    let roadmap_slug = format!("slug-roadmap-{suffix}");
+    assert_eq!(slugs, [roadmap_slug.clone(), format!("{roadmap_slug}-2")]);
+    let roadmap_id = roadmaps
+        .iter()
+        .find(|(_, slug)| *slug == roadmap_slug)
+        .unwrap()
+        .0;
+
+    let (deck_id, deck_slug): (Uuid, String) = sqlx::query_as(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('Café  Básico! ' || $1, 'en', 'es') RETURNING id, slug",
+    )
+    .bind(suffix)
+    .fetch_one(pool)
+    .await
+    .expect("Failed to create deck");
+    assert_eq!(deck_slug, format!("cafe-basico-{suffix}"));
+    sqlx::query(
+        "INSERT INTO roadmap_nodes (roadmap_id, deck_id, pos_x, pos_y) VALUES ($1, $2, 0, 0)",
+    )
+    .bind(roadmap_id)
+    .bind(deck_id)
+    .execute(pool)
+    .await
+    .expect("Failed to create node");
+
+    // The slug and the id name the same roadmap
+    for roadmap in [roadmap_slug.clone(), roadmap_id.to_string()] {
+        let response = client.get(&format!("/v1/roadmaps/{roadmap}/nodes")).await;
+        response.assert_status(StatusCode::OK);
+        let body: Value = response.json();
+        assert_eq!(body["roadmap"]["id"], roadmap_id.to_string());
+        assert_eq!(body["roadmap"]["slug"], roadmap_slug.as_str());
+        assert_eq!(body["nodes"][0]["deck_id"], deck_id.to_string());
+    }
+    client
+        .get_with_auth(
+            &format!("/v1/roadmaps/{roadmap_slug}/progress"),
+            &learner,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::OK);
+    client
+        .get(&format!("/v1/roadmaps/no-such-roadmap-{suffix}/nodes"))
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    client
+        .get_with_auth(&format!("/v1/decks/{deck_slug}/practice"), &learner, key)
+        .await
+        .assert_status(StatusCode::OK);
+    client
+        .get_with_auth(&format!("/v1/decks/{deck_id}/export"), &learner, key)
+        .await
+        .assert_status(StatusCode::OK);
+
+    // Admins rename slugs; the old one stops resolving
+    let renamed = format!("spanish-cafe-{suffix}");
+    let uri = format!("/v1/admin/decks/{deck_id}/slug");
+    client
+        .put_json_with_auth(&uri, &json!({ "slug": renamed }), &learner, key)
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+    let response = client
+        .put_json_with_auth(&uri, &json!({ "slug": renamed }), &author, key)
+        .await;
+    response.assert_status(StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["slug"], renamed.as_str());
+    client
+        .get_with_auth(&format!("/v1/decks/{renamed}/export"), &learner, key)
+        .await
+        .assert_status(StatusCode::OK);
+    client
+        .get_with_auth(&format!("/v1/decks/{deck_slug}/export"), &learner, key)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    client
+        .put_json_with_auth(&uri, &json!({ "slug": "Not A Slug" }), &author, key)
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    client
+        .put_json_with_auth(
+            &format!("/v1/admin/roadmaps/{roadmap_id}/slug"),
+            &json!({ "slug": format!("{roadmap_slug}-2") }),
+            &author,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::CONFLICT);
+    client
+        .put_json_with_auth(
+            &format!("/v1/admin/roadmaps/{}/slug", Uuid::new_v4()),
+            &json!({ "slug": format!("missing-{suffix}") }),
+            &author,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    for (roadmap_id, _) in roadmaps {
+        common::db::delete_roadmap_by_id(pool, roadmap_id)
+            .await
+            .expect("Failed to cleanup roadmap");
+    }
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(pool)
+        .await
+        .expect("Failed to cleanup deck");
+    common::db::delete_user_by_email(pool, &email)
+        .await
+        .expect("Failed to cleanup user");
+}
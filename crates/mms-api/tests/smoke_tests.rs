@@ -0,0 +1,41 @@
+use crate::common::{TestConfig, TestStateBuilder};
+use mms_api::smoke::run_journey;
+
+#[tokio::test]
+async fn test_smoke_journey_passes_in_scratch_schema() {
+    let database_url = TestConfig::default().database_url;
+    let schema = format!(
+        "smoke_test_{}",
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    );
+
+    let pool = mms_db::create_schema_pool(&database_url, 5, &schema)
+        .await
+        .expect("Failed to create scratch schema");
+    mms_db::ensure_db_and_migrate(&database_url, &pool, false)
+        .await
+        .expect("Failed to migrate scratch schema");
+
+    let mut state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    state.pool = pool.clone();
+
+    let result = run_journey(state).await;
+
+    pool.close().await;
+    let admin = mms_db::create_pool(&database_url, 1).await.unwrap();
+    mms_db::drop_schema(&admin, &schema).await.unwrap();
+
+    let steps = result.expect("Smoke journey failed");
+    assert_eq!(steps.first(), Some(&"register"));
+    assert_eq!(steps.last(), Some(&"load dashboard"));
+
+    // The journey's user and deck went to the scratch schema only
+    let leaked: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM decks WHERE title = 'Smoke test'")
+        .fetch_one(&admin)
+        .await
+        .unwrap();
+    assert_eq!(leaked, 0);
+}
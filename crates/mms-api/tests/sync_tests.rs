@@ -0,0 +1,270 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use axum_extra::extract::cookie::Key;
+use mms_api::router;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// A cursor for "now", so a test only sees changes made after it
+async fn current_cursor(pool: &PgPool) -> String {
+    let watermark: i64 =
+        sqlx::query_scalar("SELECT pg_snapshot_xmin(pg_current_snapshot())::text::bigint")
+            .fetch_one(pool)
+            .await
+            .expect("Failed to read watermark");
+    watermark.to_string()
+}
+
+/// Follow the cursor until caught up; returns every page and the final cursor
+async fn pull(client: &TestClient, token: &str, key: &Key, since: &str) -> (Vec<Value>, String) {
+    let mut cursor = since.to_string();
+    let mut pages = Vec::new();
+    loop {
+        let response = client
+            .get_with_auth(
+                &format!("/v1/sync/changes?since={cursor}&limit=2"),
+                token,
+                key,
+            )
+            .await;
+        response.assert_status(StatusCode::OK);
+        let page: Value = response.json();
+        cursor = page["cursor"].as_str().unwrap().to_string();
+        let has_more = page["has_more"].as_bool().unwrap();
+        pages.push(page);
+        if !has_more {
+            return (pages, cursor);
+        }
+    }
+}
+
+fn items<'a>(pages: &'a [Value], kind: &str) -> Vec<&'a Value> {
+    pages
+        .iter()
+        .flat_map(|page| page[kind].as_array().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_pull_changes_and_push_offline_progress() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+    let key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("sync");
+    let username = common::test_data::unique_username("sync");
+    let user_id = common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    let since = current_cursor(&state.pool).await;
+
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('Sync deck', 'en', 'es') RETURNING id",
+    )
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to create deck");
+    let mut card_ids = Vec::new();
+    for translation in ["gato", "perro"] {
+        let card_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO flashcards (term, translation, language_from, language_to) VALUES ($1, $2, 'en', 'es') RETURNING id",
+        )
+        .bind(format!("term {}", Uuid::new_v4()))
+        .bind(translation)
+        .fetch_one(&state.pool)
+        .await
+        .expect("Failed to create flashcard");
+        sqlx::query("INSERT INTO deck_flashcards (deck_id, flashcard_id) VALUES ($1, $2)")
+            .bind(deck_id)
+            .bind(card_id)
+            .execute(&state.pool)
+            .await
+            .expect("Failed to link flashcard");
+        card_ids.push(card_id);
+    }
+
+    // New content arrives across pages, the deck listing both its cards
+    let (pages, cursor) = pull(&client, &token, key, &since).await;
+    let deck = items(&pages, "decks")
+        .into_iter()
+        .rfind(|d| d["id"] == deck_id.to_string())
+        .expect("Deck not synced");
+    assert_eq!(deck["flashcard_ids"].as_array().unwrap().len(), 2);
+    let synced_cards = items(&pages, "cards");
+    for card_id in &card_ids {
+        assert!(synced_cards.iter().any(|c| c["id"] == card_id.to_string()));
+    }
+
+    // A review online shows up as progress on the next pull
+    client
+        .post_json_with_auth(
+            &format!("/v1/practice/{}/review", card_ids[0]),
+            &json!({ "user_answer": "gato", "deck_id": deck_id }),
+            &token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::OK);
+    let (pages, cursor) = pull(&client, &token, key, &cursor).await;
+    let progress = items(&pages, "progress");
+    assert_eq!(progress.len(), 1);
+    assert_eq!(progress[0]["flashcard_id"], card_ids[0].to_string());
+    assert_eq!(progress[0]["times_correct"], 1);
+    let base_seq = progress[0]["change_seq"].as_i64().unwrap();
+
+    // Offline progress on top of the synced row and on a new card is applied
+    let now = chrono::Utc::now();
+    let push = json!({ "progress": [
+        {
+            "flashcard_id": card_ids[0],
+            "base_change_seq": base_seq,
+            "next_review_at": now + chrono::Duration::days(3),
+            "last_review_at": now,
+            "times_correct": 2,
+            "times_wrong": 0,
+            "updated_at": now,
+        },
+        {
+            "flashcard_id": card_ids[1],
+            "next_review_at": now,
+            "last_review_at": now,
+            "times_correct": 0,
+            "times_wrong": 1,
+            "updated_at": now,
+        },
+    ]});
+    let response = client
+        .post_json_with_auth("/v1/sync/push", &push, &token, key)
+        .await;
+    response.assert_status(StatusCode::OK);
+    let result: Value = response.json();
+    assert_eq!(result["applied"].as_array().unwrap().len(), 2);
+    assert!(result["conflicts"].as_array().unwrap().is_empty());
+
+    // A stale copy loses under last-write-wins when the server row is newer
+    let stale = json!({ "progress": [{
+        "flashcard_id": card_ids[0],
+        "base_change_seq": base_seq,
+        "next_review_at": now,
+        "times_correct": 0,
+        "times_wrong": 5,
+        "updated_at": now - chrono::Duration::hours(1),
+    }]});
+    let result: Value = client
+        .post_json_with_auth("/v1/sync/push", &stale, &token, key)
+        .await
+        .json();
+    assert!(result["applied"].as_array().unwrap().is_empty());
+    assert_eq!(result["conflicts"][0]["resolution"], "server_wins");
+    assert_eq!(result["conflicts"][0]["server"]["times_correct"], 2);
+
+    // ...and wins when it is newer, unless the client asks the server to win
+    let mut newer = stale.clone();
+    newer["progress"][0]["updated_at"] = json!(chrono::Utc::now() + chrono::Duration::minutes(1));
+    newer["progress"][0]["on_conflict"] = json!("server_wins");
+    let result: Value = client
+        .post_json_with_auth("/v1/sync/push", &newer, &token, key)
+        .await
+        .json();
+    assert_eq!(result["conflicts"][0]["resolution"], "server_wins");
+
+    newer["progress"][0]["on_conflict"] = json!("last_write_wins");
+    let result: Value = client
+        .post_json_with_auth("/v1/sync/push", &newer, &token, key)
+        .await
+        .json();
+    assert_eq!(result["conflicts"][0]["resolution"], "client_wins");
+    assert_eq!(result["applied"][0]["times_wrong"], 5);
+
+    // Deleting the deck is synced as a tombstone
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to delete deck");
+    let (pages, _) = pull(&client, &token, key, &cursor).await;
+    assert!(
+        items(&pages, "deleted")
+            .iter()
+            .any(|t| t["entity"] == "deck" && t["id"] == deck_id.to_string())
+    );
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup");
+    sqlx::query("DELETE FROM flashcards WHERE id = ANY($1)")
+        .bind(&card_ids)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup flashcards");
+}
+
+#[tokio::test]
+async fn test_rejects_invalid_cursor_and_duplicate_changes() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+    let key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("syncbad");
+    let username = common::test_data::unique_username("syncbad");
+    let user_id = common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    client
+        .get_with_auth("/v1/sync/changes?since=not-a-cursor", &token, key)
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    let change = json!({
+        "flashcard_id": Uuid::new_v4(),
+        "next_review_at": chrono::Utc::now(),
+        "times_correct": 1,
+        "times_wrong": 0,
+        "updated_at": chrono::Utc::now(),
+    });
+    let response = client
+        .post_json_with_auth(
+            "/v1/sync/push",
+            &json!({ "progress": [change, change] }),
+            &token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::BAD_REQUEST);
+    let error: Value = response.json();
+    assert_eq!(error["code"], "validation_failed");
+    assert_eq!(error["details"][0]["field"], "progress");
+
+    // A card that no longer exists is reported, not written
+    let result: Value = client
+        .post_json_with_auth(
+            "/v1/sync/push",
+            &json!({ "progress": [change] }),
+            &token,
+            key,
+        )
+        .await
+        .json();
+    assert!(result["applied"].as_array().unwrap().is_empty());
+    assert_eq!(result["conflicts"][0]["resolution"], "server_wins");
+    assert!(result["conflicts"][0]["server"].is_null());
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup");
+}
@@ -0,0 +1,130 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use mms_api::router;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_organization_content_is_hidden_from_outsiders() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+    let key = &state.cookie.cookie_key;
+
+    let member_email = common::test_data::unique_email("tenant_member");
+    let member_id = common::db::create_verified_user(
+        &state.pool,
+        &member_email,
+        &common::test_data::unique_username("tenant_member"),
+    )
+    .await
+    .expect("Failed to create member");
+    let member_token =
+        common::jwt::create_test_token(member_id, &member_email, &state.auth.jwt_keys);
+
+    let outsider_email = common::test_data::unique_email("tenant_outsider");
+    let outsider_id = common::db::create_verified_user(
+        &state.pool,
+        &outsider_email,
+        &common::test_data::unique_username("tenant_outsider"),
+    )
+    .await
+    .expect("Failed to create outsider");
+    let outsider_token =
+        common::jwt::create_test_token(outsider_id, &outsider_email, &state.auth.jwt_keys);
+
+    let org_id: Uuid =
+        sqlx::query_scalar("INSERT INTO organizations (name) VALUES ('Classroom') RETURNING id")
+            .fetch_one(&state.pool)
+            .await
+            .expect("Failed to create organization");
+    sqlx::query("INSERT INTO organization_members (org_id, user_id) VALUES ($1, $2)")
+        .bind(org_id)
+        .bind(member_id)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to add member");
+
+    let roadmap_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO roadmaps (title, language_from, language_to, org_id) VALUES ('Classroom roadmap', 'en', 'es', $1) RETURNING id",
+    )
+    .bind(org_id)
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to create roadmap");
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to, org_id) VALUES ('Classroom deck', 'en', 'es', $1) RETURNING id",
+    )
+    .bind(org_id)
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to create deck");
+    sqlx::query(
+        "INSERT INTO roadmap_nodes (roadmap_id, deck_id, pos_x, pos_y) VALUES ($1, $2, 0, 0)",
+    )
+    .bind(roadmap_id)
+    .bind(deck_id)
+    .execute(&state.pool)
+    .await
+    .expect("Failed to create node");
+
+    // Members see their organization's roadmap and decks
+    let response = client
+        .get_with_auth(
+            &format!("/v1/roadmaps/{roadmap_id}/progress"),
+            &member_token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let body = response.json::<serde_json::Value>();
+    assert_eq!(body["nodes"][0]["deck_id"], deck_id.to_string());
+    client
+        .get_with_auth(&format!("/v1/decks/{deck_id}/practice"), &member_token, key)
+        .await
+        .assert_status(StatusCode::OK);
+
+    // Outsiders and signed-out visitors do not
+    client
+        .get_with_auth(
+            &format!("/v1/roadmaps/{roadmap_id}/progress"),
+            &outsider_token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    client
+        .get_with_auth(
+            &format!("/v1/decks/{deck_id}/practice"),
+            &outsider_token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    client
+        .get_with_auth(&format!("/v1/decks/{deck_id}/export"), &outsider_token, key)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    client
+        .get(&format!("/v1/roadmaps/{roadmap_id}/nodes"))
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    let response = client.get("/v1/roadmaps?limit=100").await;
+    response.assert_status(StatusCode::OK);
+    let roadmaps = response.json::<Vec<serde_json::Value>>();
+    assert!(roadmaps.iter().all(|r| r["id"] != roadmap_id.to_string()));
+
+    sqlx::query("DELETE FROM organizations WHERE id = $1")
+        .bind(org_id)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup organization");
+    for email in [&member_email, &outsider_email] {
+        common::db::delete_user_by_email(&state.pool, email)
+            .await
+            .expect("Failed to cleanup");
+    }
+}
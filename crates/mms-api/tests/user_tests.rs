@@ -125,11 +125,56 @@ async fn test_user_registration_invalid_email() {
     response.assert_status(StatusCode::BAD_REQUEST);
 
     let json: serde_json::Value = response.json();
-    assert!(json["error"].as_str().unwrap().contains("email"));
+    assert_eq!(json["code"], "validation_failed");
+    assert!(json["message"].as_str().unwrap().contains("email"));
+    assert_eq!(json["details"][0]["field"], "email");
 
     // No cleanup needed - user was never created
 }
 
+#[tokio::test]
+async fn test_user_registration_reports_every_invalid_field() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let client = TestClient::new(router::router().with_state(state.clone()));
+
+    let body = json!({
+        "username": "x",
+        "email": "invalid-email",
+        "password": "weak"
+    });
+    let response = client.post_json("/v1/users/register", &body).await;
+    response.assert_status(StatusCode::BAD_REQUEST);
+    let json: serde_json::Value = response.json();
+    let fields: Vec<&str> = json["details"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|detail| detail["field"].as_str().unwrap())
+        .collect();
+    assert_eq!(fields, ["email", "password", "username"]);
+
+    // Rejections by the JSON extractor have the same shape
+    let response = client
+        .post_json(
+            "/v1/users/register",
+            &json!({ "email": "a@example.com", "username": "someone" }),
+        )
+        .await;
+    assert!(response.status.is_client_error());
+    let json: serde_json::Value = response.json();
+    assert_eq!(json["code"], "validation_failed");
+    assert!(json["message"].as_str().unwrap().contains("password"));
+    assert_eq!(json["details"], json!([]));
+
+    let response = client.get("/v1/no-such-route").await;
+    response.assert_status(StatusCode::NOT_FOUND);
+    assert_eq!(response.json::<serde_json::Value>()["code"], "not_found");
+}
+
 #[tokio::test]
 async fn test_user_registration_weak_password() {
     let state = TestStateBuilder::new()
@@ -151,7 +196,7 @@ async fn test_user_registration_weak_password() {
     response.assert_status(StatusCode::BAD_REQUEST);
 
     let json: serde_json::Value = response.json();
-    let error_msg = json["error"].as_str().unwrap();
+    let error_msg = json["message"].as_str().unwrap();
     assert!(
         error_msg.to_lowercase().contains("password"),
         "Expected error to contain 'password', got: {}",
@@ -255,7 +300,7 @@ async fn test_user_login_invalid_credentials() {
 
     let json: serde_json::Value = response.json();
     assert!(
-        json["error"]
+        json["message"]
             .as_str()
             .unwrap()
             .contains("Invalid email or password")
@@ -288,7 +333,7 @@ async fn test_user_login_nonexistent_user() {
 
     let json: serde_json::Value = response.json();
     assert!(
-        json["error"]
+        json["message"]
             .as_str()
             .unwrap()
             .contains("Invalid email or password")
@@ -312,7 +357,7 @@ async fn test_get_user_dashboard() {
 
     // Generate auth token
     let token =
-        common::jwt::create_test_token(user_id, "dashboard@example.com", &state.auth.jwt_secret);
+        common::jwt::create_test_token(user_id, "dashboard@example.com", &state.auth.jwt_keys);
 
     let app = router::router().with_state(state.clone());
     let client = TestClient::new(app);
@@ -374,11 +419,8 @@ async fn test_update_user_profile() {
     .expect("Failed to create test user");
 
     // Generate auth token
-    let token = common::jwt::create_test_token(
-        user_id,
-        "update_profile@example.com",
-        &state.auth.jwt_secret,
-    );
+    let token =
+        common::jwt::create_test_token(user_id, "update_profile@example.com", &state.auth.jwt_keys);
 
     let app = router::router().with_state(state.clone());
     let client = TestClient::new(app);
@@ -433,7 +475,7 @@ async fn test_delete_user() {
 
     // Generate auth token
     let token =
-        common::jwt::create_test_token(user_id, "delete_user@example.com", &state.auth.jwt_secret);
+        common::jwt::create_test_token(user_id, "delete_user@example.com", &state.auth.jwt_keys);
 
     let app = router::router().with_state(state.clone());
     let client = TestClient::new(app);
@@ -447,18 +489,21 @@ async fn test_delete_user() {
 
     let json: serde_json::Value = response.json();
     assert!(json["message"].as_str().unwrap().contains("deleted"));
+    assert!(json["purge_after"].is_string());
 
-    // Verify user was deleted from database
-    let user_exists = common::db::get_user_by_email(&state.pool, "delete_user@example.com")
-        .await
-        .expect("Failed to query user");
-
-    assert!(
-        user_exists.is_none(),
-        "User should be deleted from database"
-    );
+    // The account is kept for the grace period, marked deleted
+    let deleted_at: Option<chrono::DateTime<chrono::Utc>> =
+        sqlx::query_scalar("SELECT deleted_at FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(&state.pool)
+            .await
+            .expect("User should still exist during the grace period");
+    assert!(deleted_at.is_some(), "User should be marked deleted");
 
-    // No cleanup needed - user was already deleted by the endpoint
+    // Cleanup
+    common::db::delete_user_by_email(&state.pool, "delete_user@example.com")
+        .await
+        .expect("Failed to cleanup test user");
 }
 
 #[tokio::test]
@@ -502,3 +547,709 @@ async fn test_user_registration_creates_stats() {
         .await
         .expect("Failed to cleanup test user");
 }
+
+#[tokio::test]
+async fn test_export_user_data() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let email = common::test_data::unique_email("export");
+    let username = common::test_data::unique_username("export");
+    let user_id = common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create test user");
+
+    sqlx::query(
+        r#"
+        INSERT INTO user_activity (user_id, activity_date, reviews_count)
+        VALUES ($1, CURRENT_DATE - 400, 3), ($1, CURRENT_DATE, 5)
+        "#,
+    )
+    .bind(user_id)
+    .execute(&state.pool)
+    .await
+    .expect("Failed to insert activity");
+
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+
+    let response = client
+        .get_with_auth_accepting(
+            "/v1/users/me/export",
+            &token,
+            &state.cookie.cookie_key,
+            "application/x-ndjson",
+        )
+        .await;
+
+    response.assert_status(StatusCode::OK);
+    let records: Vec<serde_json::Value> = response
+        .text()
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("Each line should be JSON"))
+        .collect();
+
+    // Preferences, then the full history, not just the dashboard's last year
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[0]["type"], "preferences");
+    assert!(records[1..].iter().all(|r| r["type"] == "activity"));
+    assert_eq!(records[1]["reviews_count"], 3);
+    assert_eq!(records[2]["reviews_count"], 5);
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup test user");
+}
+
+#[tokio::test]
+async fn test_get_user_home() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+    let key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("home");
+    let username = common::test_data::unique_username("home");
+    let user_id = common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    // One overdue card in a Spanish profile, one due later today or tomorrow
+    let card_ids: Vec<uuid::Uuid> = sqlx::query_scalar(
+        r#"
+        INSERT INTO flashcards (term, translation, language_from, language_to)
+        SELECT 'home ' || gen_random_uuid(), 'casa', 'en', 'es' FROM generate_series(1, 2)
+        RETURNING id
+        "#,
+    )
+    .fetch_all(&state.pool)
+    .await
+    .expect("Failed to create flashcards");
+    sqlx::query(
+        r#"
+        INSERT INTO user_card_progress (user_id, flashcard_id, next_review_at, times_correct, times_wrong)
+        VALUES ($1, $2, NOW() - INTERVAL '1 hour', 1, 0), ($1, $3, NOW() + INTERVAL '30 days', 5, 0)
+        "#,
+    )
+    .bind(user_id)
+    .bind(card_ids[0])
+    .bind(card_ids[1])
+    .execute(&state.pool)
+    .await
+    .expect("Failed to create progress");
+    sqlx::query(
+        "INSERT INTO learning_profiles (user_id, native_language, learning_language) VALUES ($1, 'en', 'es')",
+    )
+    .bind(user_id)
+    .execute(&state.pool)
+    .await
+    .expect("Failed to create profile");
+    // Both cards in a deck on a roadmap the user has practised
+    let deck_id: uuid::Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('Home deck', 'en', 'es') RETURNING id",
+    )
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to create deck");
+    sqlx::query(
+        "INSERT INTO deck_flashcards (deck_id, flashcard_id) SELECT $1, UNNEST($2::uuid[])",
+    )
+    .bind(deck_id)
+    .bind(&card_ids)
+    .execute(&state.pool)
+    .await
+    .expect("Failed to add cards");
+    let roadmap_id: uuid::Uuid = sqlx::query_scalar(
+        "INSERT INTO roadmaps (title, language_from, language_to) VALUES ('Home ' || gen_random_uuid(), 'en', 'es') RETURNING id",
+    )
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to create roadmap");
+    sqlx::query("INSERT INTO roadmap_nodes (roadmap_id, deck_id) VALUES ($1, $2)")
+        .bind(roadmap_id)
+        .bind(deck_id)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to create node");
+    sqlx::query(
+        r#"
+        INSERT INTO user_deck_progress (user_id, deck_id, total_cards, mastered_cards, last_practiced_at)
+        VALUES ($1, $2, 2, 2, NOW())
+        "#,
+    )
+    .bind(user_id)
+    .bind(deck_id)
+    .execute(&state.pool)
+    .await
+    .expect("Failed to create deck progress");
+    sqlx::query(
+        "INSERT INTO user_activity (user_id, activity_date, reviews_count) VALUES ($1, CURRENT_DATE - 2, 3)",
+    )
+    .bind(user_id)
+    .execute(&state.pool)
+    .await
+    .expect("Failed to create activity");
+    mms_api::notifications::notify(
+        &state.pool,
+        &state.events,
+        user_id,
+        mms_api::notifications::NotificationKind::StreakReminder,
+        "Keep it up",
+        "Review today to keep your streak",
+        None,
+    )
+    .await
+    .expect("Failed to notify");
+
+    let response = client
+        .get_with_auth(&format!("/v1/users/{user_id}/home"), &token, key)
+        .await;
+    response.assert_status(StatusCode::OK);
+    let home: serde_json::Value = response.json();
+    assert_eq!(home["version"], 1);
+    assert_eq!(home["user"]["username"], username);
+    assert_eq!(home["stats"]["total_reviews"], 0);
+    assert_eq!(home["daily_goal"]["reviews_today"], 0);
+    assert_eq!(home["daily_goal"]["reached"], false);
+    assert_eq!(home["due"]["now"], 1);
+    assert_eq!(home["due"]["today"], 1);
+    assert_eq!(home["profiles"][0]["learning_language"], "es");
+    assert_eq!(home["profiles"][0]["due_now"], 1);
+    assert_eq!(home["unread_notifications"], 1);
+    assert_eq!(home["heatmap"][0]["reviews_count"], 3);
+    assert_eq!(home["decks_due"].as_array().unwrap().len(), 1);
+    assert_eq!(home["decks_due"][0]["deck_id"], deck_id.to_string());
+    assert_eq!(home["decks_due"][0]["due_now"], 1);
+    assert_eq!(home["roadmaps"].as_array().unwrap().len(), 1);
+    assert_eq!(home["roadmaps"][0]["id"], roadmap_id.to_string());
+    assert_eq!(home["roadmaps"][0]["completed_nodes"], 1);
+    assert_eq!(home["roadmaps"][0]["progress_percentage"], 100.0);
+
+    // Another user's home is off limits
+    client
+        .get_with_auth(
+            &format!("/v1/users/{}/home", uuid::Uuid::new_v4()),
+            &token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup");
+    common::db::delete_roadmap_by_id(&state.pool, roadmap_id)
+        .await
+        .expect("Failed to cleanup roadmap");
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup deck");
+    sqlx::query("DELETE FROM flashcards WHERE id = ANY($1)")
+        .bind(&card_ids)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup flashcards");
+}
+
+#[tokio::test]
+async fn test_get_interval_stats() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+    let key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("intervals");
+    let username = common::test_data::unique_username("intervals");
+    let user_id = common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    // Half a day, ten days, and a card never reviewed
+    let card_ids: Vec<uuid::Uuid> = sqlx::query_scalar(
+        r#"
+        INSERT INTO flashcards (term, translation, language_from, language_to)
+        SELECT 'intervals ' || gen_random_uuid(), 'x', 'en', 'es' FROM generate_series(1, 3)
+        RETURNING id
+        "#,
+    )
+    .fetch_all(&state.pool)
+    .await
+    .expect("Failed to create flashcards");
+    sqlx::query(
+        r#"
+        INSERT INTO user_card_progress (user_id, flashcard_id, next_review_at, last_review_at, times_correct, times_wrong)
+        VALUES
+            ($1, $2, NOW() + INTERVAL '12 hours', NOW(), 1, 0),
+            ($1, $3, NOW() + INTERVAL '10 days', NOW(), 4, 0),
+            ($1, $4, NOW(), NULL, 0, 0)
+        "#,
+    )
+    .bind(user_id)
+    .bind(card_ids[0])
+    .bind(card_ids[1])
+    .bind(card_ids[2])
+    .execute(&state.pool)
+    .await
+    .expect("Failed to create progress");
+
+    mms_api::stats::intervals::snapshot_intervals(&state.pool)
+        .await
+        .expect("Failed to snapshot intervals");
+
+    let response = client
+        .get_with_auth(&format!("/v1/users/{user_id}/stats/intervals"), &token, key)
+        .await;
+    response.assert_status(StatusCode::OK);
+    let stats: serde_json::Value = response.json();
+    let buckets = stats["buckets"].as_array().unwrap();
+    assert_eq!(buckets[0]["min_days"], 0);
+    assert!(buckets.last().unwrap()["max_days"].is_null());
+    assert_eq!(stats["current"], json!([1, 0, 0, 0, 1, 0, 0, 0, 0]));
+    assert_eq!(stats["median_days"].as_f64().unwrap().round(), 5.0);
+    let history = stats["history"].as_array().unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0]["cards"], stats["current"]);
+
+    // Another user's statistics are off limits
+    client
+        .get_with_auth(
+            &format!("/v1/users/{}/stats/intervals", uuid::Uuid::new_v4()),
+            &token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup");
+    sqlx::query("DELETE FROM flashcards WHERE id = ANY($1)")
+        .bind(&card_ids)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup flashcards");
+}
+
+#[tokio::test]
+async fn test_get_detailed_stats() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+    let key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("detailed");
+    let username = common::test_data::unique_username("detailed");
+    let user_id = common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    let deck_id: uuid::Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('Detailed', 'en', 'es') RETURNING id",
+    )
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to create deck");
+    let card_ids: Vec<uuid::Uuid> = sqlx::query_scalar(
+        r#"
+        INSERT INTO flashcards (term, translation, language_from, language_to)
+        SELECT 'detailed ' || gen_random_uuid(), 'gato', 'en', 'es' FROM generate_series(1, 2)
+        RETURNING id
+        "#,
+    )
+    .fetch_all(&state.pool)
+    .await
+    .expect("Failed to create flashcards");
+    sqlx::query(
+        "INSERT INTO deck_flashcards (deck_id, flashcard_id) SELECT $1, unnest($2::uuid[])",
+    )
+    .bind(deck_id)
+    .bind(&card_ids)
+    .execute(&state.pool)
+    .await
+    .expect("Failed to link flashcards");
+
+    // A first review through the API, then earlier reviews of a young and a
+    // mature card and an implausible one, logged directly
+    client
+        .post_json_with_auth(
+            &format!("/v1/practice/{}/review", card_ids[0]),
+            &json!({
+                "user_answer": "gato",
+                "deck_id": deck_id,
+                "response_time_ms": 2000,
+                "elapsed_ms": 9000
+            }),
+            &token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::OK);
+    sqlx::query(
+        r#"
+        INSERT INTO review_logs (user_id, flashcard_id, is_correct, previous_interval_days, interval_days, response_time_ms, flagged)
+        VALUES ($1, $2, TRUE, 3, 7, 4000, FALSE), ($1, $2, FALSE, 30, 1, 6000, FALSE), ($1, $2, TRUE, 30, 60, 100, TRUE)
+        "#,
+    )
+    .bind(user_id)
+    .bind(card_ids[1])
+    .execute(&state.pool)
+    .await
+    .expect("Failed to log reviews");
+
+    let response = client
+        .get_with_auth(
+            &format!("/v1/users/{user_id}/stats/detailed?days=7"),
+            &token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let stats: serde_json::Value = response.json();
+    assert_eq!(stats["days"], 7);
+
+    let accuracy = stats["accuracy"].as_array().unwrap();
+    assert_eq!(accuracy.len(), 1);
+    assert_eq!(accuracy[0]["reviews"], 3);
+    assert_eq!(accuracy[0]["correct"], 2);
+
+    // The first review was a learning step, so only the logged ones count
+    assert_eq!(stats["true_retention"]["reviews"], 2);
+    assert_eq!(stats["true_retention"]["rate"], 0.5);
+    assert_eq!(stats["mature_retention"]["reviews"], 1);
+    assert_eq!(stats["mature_retention"]["rate"], 0.0);
+
+    let by_weekday = stats["by_weekday"].as_array().unwrap();
+    let by_hour = stats["by_hour"].as_array().unwrap();
+    assert_eq!(by_weekday.len(), 7);
+    assert_eq!(by_hour.len(), 24);
+    let total = |slots: &[serde_json::Value]| {
+        slots
+            .iter()
+            .map(|slot| slot["reviews"].as_i64().unwrap())
+            .sum::<i64>()
+    };
+    assert_eq!(total(by_weekday), 3);
+    assert_eq!(total(by_hour), 3);
+
+    let maturity = &stats["maturity"];
+    assert_eq!(maturity["new"], 1);
+    assert_eq!(
+        maturity["learning"].as_i64().unwrap()
+            + maturity["young"].as_i64().unwrap()
+            + maturity["mature"].as_i64().unwrap(),
+        1
+    );
+    assert!(stats["average_interval_days"].as_f64().unwrap() > 0.0);
+
+    // Answer times come from the reviews that reported one; the logged reviews
+    // have no deck
+    assert_eq!(stats["average_answer_ms"], 4000.0);
+    let by_deck = stats["answer_time_by_deck"].as_array().unwrap();
+    assert_eq!(by_deck.len(), 2);
+    assert!(by_deck[0]["deck_id"].is_null());
+    assert_eq!(by_deck[0]["average_ms"], 5000.0);
+    assert_eq!(by_deck[1]["deck_id"], deck_id.to_string());
+    assert_eq!(by_deck[1]["title"], "Detailed");
+    let slowest = stats["slowest_cards"].as_array().unwrap();
+    assert_eq!(slowest.len(), 1);
+    assert_eq!(slowest[0]["flashcard_id"], card_ids[1].to_string());
+
+    // Time on the card, not just to answer, counts as study time
+    let (elapsed_ms, study_ms): (Option<i32>, i64) = sqlx::query_as(
+        r#"
+        SELECT l.elapsed_ms, a.study_ms
+        FROM review_logs l
+        JOIN user_activity a ON a.user_id = l.user_id AND a.activity_date = user_local_date(l.user_id)
+        WHERE l.user_id = $1 AND l.flashcard_id = $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(card_ids[0])
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to read study time");
+    assert_eq!((elapsed_ms, study_ms), (Some(9000), 9000));
+
+    client
+        .get_with_auth(
+            &format!("/v1/users/{}/stats/detailed", uuid::Uuid::new_v4()),
+            &token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup");
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup deck");
+    sqlx::query("DELETE FROM flashcards WHERE id = ANY($1)")
+        .bind(&card_ids)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup flashcards");
+}
+
+#[tokio::test]
+async fn test_get_activity_by_range_and_granularity() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+    let key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("heatmap");
+    let username = common::test_data::unique_username("heatmap");
+    let user_id = common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    let today: chrono::NaiveDate = sqlx::query_scalar("SELECT user_local_date($1)")
+        .bind(user_id)
+        .fetch_one(&state.pool)
+        .await
+        .expect("Failed to read local date");
+    sqlx::query(
+        r#"
+        INSERT INTO user_activity (user_id, activity_date, reviews_count, correct_reviews, study_ms, new_cards)
+        VALUES ($1, $2::date - 1, 5, 4, 120000, 2), ($1, $2::date - 2, 3, 3, 60000, 1), ($1, $2::date - 400, 7, 7, 0, 0)
+        "#,
+    )
+    .bind(user_id)
+    .bind(today)
+    .execute(&state.pool)
+    .await
+    .expect("Failed to seed activity");
+
+    // A first review adds a new card and its answer time to today
+    let deck_id: uuid::Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('Heatmap', 'en', 'es') RETURNING id",
+    )
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to create deck");
+    let card_id: uuid::Uuid = sqlx::query_scalar(
+        "INSERT INTO flashcards (term, translation, language_from, language_to) VALUES ('heatmap ' || gen_random_uuid(), 'gato', 'en', 'es') RETURNING id",
+    )
+    .fetch_one(&state.pool)
+    .await
+    .expect("Failed to create flashcard");
+    sqlx::query("INSERT INTO deck_flashcards (deck_id, flashcard_id) VALUES ($1, $2)")
+        .bind(deck_id)
+        .bind(card_id)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to link flashcard");
+    client
+        .post_json_with_auth(
+            &format!("/v1/practice/{card_id}/review"),
+            &json!({ "user_answer": "gato", "deck_id": deck_id, "response_time_ms": 3000 }),
+            &token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::OK);
+
+    let uri = format!("/v1/users/{user_id}/activity");
+    let response = client.get_with_auth(&uri, &token, key).await;
+    response.assert_status(StatusCode::OK);
+    let activity: serde_json::Value = response.json();
+    assert_eq!(activity["granularity"], "day");
+    assert_eq!(activity["to"], today.to_string());
+    assert_eq!(
+        activity["from"],
+        (today - chrono::Duration::days(364)).to_string()
+    );
+    let periods = activity["periods"].as_array().unwrap();
+    assert_eq!(periods.len(), 3);
+    assert_eq!(periods[1]["minutes_studied"], 2.0);
+    assert_eq!(periods[1]["new_cards"], 2);
+    assert_eq!(periods[2]["start"], today.to_string());
+    assert_eq!(periods[2]["reviews"], 1);
+    assert_eq!(periods[2]["new_cards"], 1);
+    assert_eq!(periods[2]["minutes_studied"], 0.1);
+
+    let from = today - chrono::Duration::days(400);
+    for granularity in ["week", "month"] {
+        let response = client
+            .get_with_auth(
+                &format!("{uri}?from={from}&granularity={granularity}"),
+                &token,
+                key,
+            )
+            .await;
+        response.assert_status(StatusCode::OK);
+        let activity: serde_json::Value = response.json();
+        let periods = activity["periods"].as_array().unwrap();
+        let reviews: i64 = periods.iter().map(|p| p["reviews"].as_i64().unwrap()).sum();
+        let new_cards: i64 = periods
+            .iter()
+            .map(|p| p["new_cards"].as_i64().unwrap())
+            .sum();
+        assert_eq!((reviews, new_cards), (16, 4));
+        assert!(periods.len() >= 2 && periods.len() <= 4);
+    }
+
+    for query in [
+        format!("from={today}&to={}", today - chrono::Duration::days(1)),
+        format!("from={}", today - chrono::Duration::days(1096)),
+    ] {
+        client
+            .get_with_auth(&format!("{uri}?{query}"), &token, key)
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+    client
+        .get_with_auth(
+            &format!("/v1/users/{}/activity", uuid::Uuid::new_v4()),
+            &token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup");
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup deck");
+    sqlx::query("DELETE FROM flashcards WHERE id = $1")
+        .bind(card_id)
+        .execute(&state.pool)
+        .await
+        .expect("Failed to cleanup flashcard");
+}
+
+#[tokio::test]
+async fn test_activity_and_streak_follow_user_timezone() {
+    use chrono::Timelike;
+    use mms_db::repositories::practice as practice_repo;
+
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+    let key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("timezone");
+    let username = common::test_data::unique_username("timezone");
+    let user_id = common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    client
+        .patch_json_with_auth(
+            "/v1/users/me/timezone",
+            &json!({ "timezone": "Mars/Olympus_Mons" }),
+            &token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    // Pick a zone whose date differs from UTC right now: UTC+14 from 10:00 UTC,
+    // UTC-11 before 11:00 UTC
+    let timezone = if chrono::Utc::now().hour() >= 11 {
+        "Pacific/Kiritimati"
+    } else {
+        "Pacific/Pago_Pago"
+    };
+    let response = client
+        .patch_json_with_auth(
+            "/v1/users/me/timezone",
+            &json!({ "timezone": timezone }),
+            &token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    assert_eq!(response.json::<serde_json::Value>()["timezone"], timezone);
+
+    let (local_today, utc_today): (chrono::NaiveDate, chrono::NaiveDate) =
+        sqlx::query_as("SELECT (NOW() AT TIME ZONE $1)::date, (NOW() AT TIME ZONE 'UTC')::date")
+            .bind(timezone)
+            .fetch_one(&state.pool)
+            .await
+            .expect("Failed to read dates");
+    assert_ne!(local_today, utc_today);
+
+    // Reviewed on the two previous local days, then today
+    sqlx::query(
+        r#"
+        INSERT INTO user_activity (user_id, activity_date, reviews_count)
+        VALUES ($1, $2::date - 2, 5), ($1, $2::date - 1, 5)
+        "#,
+    )
+    .bind(user_id)
+    .bind(local_today)
+    .execute(&state.pool)
+    .await
+    .expect("Failed to seed activity");
+    practice_repo::record_activity(
+        &state.pool,
+        user_id,
+        true,
+        false,
+        0,
+        false,
+        chrono::Utc::now(),
+    )
+    .await
+    .expect("Failed to record activity");
+    practice_repo::update_streak(&state.pool, user_id)
+        .await
+        .expect("Failed to update streak");
+
+    let response = client
+        .get_with_auth("/v1/users/me/dashboard", &token, key)
+        .await;
+    response.assert_status(StatusCode::OK);
+    let dashboard: serde_json::Value = response.json();
+    assert_eq!(dashboard["stats"]["current_streak_days"], 3);
+    let heatmap = dashboard["heatmap"].as_array().unwrap();
+    assert_eq!(
+        heatmap.last().unwrap()["activity_date"],
+        local_today.to_string()
+    );
+    assert_eq!(heatmap.last().unwrap()["reviews_count"], 1);
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup");
+}
@@ -0,0 +1,148 @@
+use crate::common::{self, ADMIN_TOKEN, TestClient, TestStateBuilder};
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use mms_api::router;
+use serde_json::{Value, json};
+
+#[tokio::test]
+async fn test_check_username() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let email = common::test_data::unique_email("check");
+    let username = common::test_data::unique_username("check");
+    common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create test user");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+
+    let response = client
+        .get(&format!("/v1/users/check-username?name={username}"))
+        .await;
+    response.assert_status(StatusCode::OK);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({ "username": username, "available": false, "reason": "taken" })
+    );
+
+    let free = common::test_data::unique_username("free");
+    let response = client
+        .get(&format!("/v1/users/check-username?name={free}"))
+        .await;
+    assert_eq!(
+        response.json::<Value>(),
+        json!({ "username": free, "available": true })
+    );
+
+    let response = client.get("/v1/users/check-username?name=a%3Cb").await;
+    response.assert_status(StatusCode::BAD_REQUEST);
+    assert_eq!(response.json::<Value>()["details"][0]["field"], "name");
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup test user");
+}
+
+#[tokio::test]
+async fn test_previous_username_is_reserved_and_recorded() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+    let pool = &state.pool;
+    let key = &state.cookie.cookie_key;
+    let email = common::test_data::unique_email("renamer");
+    let original = common::test_data::unique_username("renamer");
+    let user_id = common::db::create_verified_user(pool, &email, &original)
+        .await
+        .unwrap();
+    let other_email = common::test_data::unique_email("impostor");
+    let other_id = common::db::create_verified_user(
+        pool,
+        &other_email,
+        &common::test_data::unique_username("impostor"),
+    )
+    .await
+    .unwrap();
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+    let other_token = common::jwt::create_test_token(other_id, &other_email, &state.auth.jwt_keys);
+    let client = TestClient::new(router::router().with_state(state.clone()));
+
+    let renamed = common::test_data::unique_username("renamed");
+    client
+        .patch_json_with_auth(
+            "/v1/users/me/username",
+            &json!({ "username": renamed }),
+            &token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::OK);
+
+    // Nobody else can take the name that was given up
+    let response = client
+        .get(&format!("/v1/users/check-username?name={original}"))
+        .await;
+    assert_eq!(response.json::<Value>()["reason"], "reserved");
+    client
+        .patch_json_with_auth(
+            "/v1/users/me/username",
+            &json!({ "username": original }),
+            &other_token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::CONFLICT);
+
+    // But its previous owner can go back to it
+    client
+        .patch_json_with_auth(
+            "/v1/users/me/username",
+            &json!({ "username": original }),
+            &token,
+            key,
+        )
+        .await
+        .assert_status(StatusCode::OK);
+
+    let response = client
+        .request(
+            Request::builder()
+                .uri(format!("/v1/admin/users/{user_id}"))
+                .header("x-forwarded-for", "127.0.0.1")
+                .header("authorization", format!("Bearer {ADMIN_TOKEN}"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let history = response.json::<Value>()["username_history"].clone();
+    let changes: Vec<(&str, &str)> = history
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|change| {
+            (
+                change["old_username"].as_str().unwrap(),
+                change["new_username"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            (renamed.as_str(), original.as_str()),
+            (original.as_str(), renamed.as_str())
+        ]
+    );
+
+    common::db::delete_user_by_email(pool, &email)
+        .await
+        .unwrap();
+    common::db::delete_user_by_email(pool, &other_email)
+        .await
+        .unwrap();
+}
@@ -0,0 +1,140 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::{StatusCode, header};
+use mms_api::router;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_v1_login_is_deprecated_in_favour_of_v2() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+
+    let email = common::test_data::unique_email("v1login");
+    let username = common::test_data::unique_username("v1login");
+    let password_hash = bcrypt::hash("password123", bcrypt::DEFAULT_COST).unwrap();
+    common::db::create_test_user(&state.pool, &email, &username, &password_hash)
+        .await
+        .expect("Failed to create user");
+
+    let body = json!({ "email": &email, "password": "password123" });
+    let response = client.post_json("/v1/users/login", &body).await;
+    response.assert_status(StatusCode::OK);
+
+    assert!(response.headers.contains_key("deprecation"));
+    assert_eq!(
+        response.headers[header::LINK],
+        "</v2/users/login>; rel=\"successor-version\""
+    );
+    let json: serde_json::Value = response.json();
+    assert!(json["token"].is_string(), "v1 still returns the token");
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup");
+}
+
+#[tokio::test]
+async fn test_v2_login_keeps_tokens_in_cookies_only() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+
+    let email = common::test_data::unique_email("v2login");
+    let username = common::test_data::unique_username("v2login");
+    let password_hash = bcrypt::hash("password123", bcrypt::DEFAULT_COST).unwrap();
+    common::db::create_test_user(&state.pool, &email, &username, &password_hash)
+        .await
+        .expect("Failed to create user");
+
+    let body = json!({ "email": &email, "password": "password123" });
+    let response = client.post_json("/v2/users/login", &body).await;
+    response.assert_status(StatusCode::OK);
+
+    assert!(!response.headers.contains_key("deprecation"));
+    assert!(response.get_cookie("auth_token").is_some());
+    assert!(response.get_cookie("refresh_token").is_some());
+
+    let json: serde_json::Value = response.json();
+    assert!(json.get("token").is_none());
+    assert!(json.get("refresh_token").is_none());
+    assert_eq!(json["user"]["email"], email.as_str());
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup");
+}
+
+#[tokio::test]
+async fn test_v2_refresh_does_not_echo_token() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+
+    let email = common::test_data::unique_email("v2refresh");
+    let username = common::test_data::unique_username("v2refresh");
+    let password_hash = bcrypt::hash("password123", bcrypt::DEFAULT_COST).unwrap();
+    common::db::create_test_user(&state.pool, &email, &username, &password_hash)
+        .await
+        .expect("Failed to create user");
+
+    let body = json!({ "email": &email, "password": "password123" });
+    let login: serde_json::Value = client.post_json("/v1/users/login", &body).await.json();
+
+    let response = client
+        .post_with_auth_and_refresh(
+            "/v2/auth/refresh",
+            login["token"].as_str().unwrap(),
+            login["refresh_token"].as_str().unwrap(),
+            &state.cookie.cookie_key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+
+    assert!(!response.headers.contains_key("deprecation"));
+    assert!(response.get_cookie("auth_token").is_some());
+    let json: serde_json::Value = response.json();
+    assert!(json.get("token").is_none());
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup");
+}
+
+#[tokio::test]
+async fn test_shared_routes_are_served_under_every_version() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let app = router::router().with_state(state);
+    let client = TestClient::new(app);
+
+    for uri in ["/v1/roadmaps", "/v2/roadmaps"] {
+        let response = client.get(uri).await;
+        response.assert_status(StatusCode::OK);
+        assert!(!response.headers.contains_key("deprecation"));
+    }
+
+    // The OpenAPI document covers every version and is only served once
+    client
+        .get("/v1/openapi.json")
+        .await
+        .assert_status(StatusCode::OK);
+    client
+        .get("/v2/openapi.json")
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,112 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use mms_api::{auth::Role, router};
+use serde_json::Value;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_vocabulary_search_finds_studied_cards_in_both_directions() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let pool = &state.pool;
+    let cookie_key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("vocab");
+    let user_id = common::db::create_verified_user(
+        pool,
+        &email,
+        &common::test_data::unique_username("vocab"),
+    )
+    .await
+    .unwrap();
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    // A unique word keeps other tests' cards out of the results
+    let word = format!("cafe{}", Uuid::new_v4().simple());
+    let mut card_ids = Vec::new();
+    for (term, translation, progress) in [
+        (
+            format!("{word} au lait"),
+            "café con leche".to_string(),
+            Some((12, 1)),
+        ),
+        (
+            "coffee".to_string(),
+            format!("el Café{}", &word[4..]),
+            Some((2, 0)),
+        ),
+        (format!("{word}s"), "cafés".to_string(), None),
+    ] {
+        let card_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO flashcards (term, translation, language_from, language_to) VALUES ($1, $2, 'en', 'es') RETURNING id",
+        )
+        .bind(&term)
+        .bind(&translation)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        if let Some((correct, wrong)) = progress {
+            sqlx::query(
+                "INSERT INTO user_card_progress (user_id, flashcard_id, next_review_at, times_correct, times_wrong, last_review_at) VALUES ($1, $2, NOW(), $3, $4, NOW())",
+            )
+            .bind(user_id)
+            .bind(card_id)
+            .bind(correct)
+            .bind(wrong)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+        card_ids.push(card_id);
+    }
+
+    // Accents and case are ignored; cards never studied are not searched
+    let uri = format!("/v1/users/{user_id}/vocabulary/search");
+    let response = client
+        .get_with_auth(
+            &format!("{uri}?q={}", word.to_uppercase()),
+            &token,
+            cookie_key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let matches: Vec<Value> = response.json();
+    assert_eq!(matches.len(), 2);
+    // The whole-word match on the translation outranks the prefix match on the term
+    assert_eq!(matches[0]["flashcard_id"], card_ids[1].to_string());
+    assert_eq!(matches[0]["matched"], "translation");
+    assert_eq!(matches[0]["mastered"], false);
+    assert_eq!(matches[1]["flashcard_id"], card_ids[0].to_string());
+    assert_eq!(matches[1]["matched"], "term");
+    assert_eq!(matches[1]["score"], 11);
+    assert_eq!(matches[1]["mastered"], true);
+
+    client
+        .get_with_auth(&format!("{uri}?q=%20!%20"), &token, cookie_key)
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    // Vocabulary is private
+    let other = common::account(&state, "other", Role::Learner).await;
+    client
+        .get_with_auth(&format!("{uri}?q={word}"), &other.token, cookie_key)
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+
+    common::db::delete_user_by_email(pool, &other.email)
+        .await
+        .ok();
+    common::db::delete_user_by_email(pool, &email)
+        .await
+        .unwrap();
+    for card_id in card_ids {
+        sqlx::query("DELETE FROM flashcards WHERE id = $1")
+            .bind(card_id)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+}
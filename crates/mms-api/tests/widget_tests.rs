@@ -0,0 +1,105 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::{StatusCode, header};
+use mms_api::router;
+
+#[tokio::test]
+async fn test_widgets_show_stats_until_revoked() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to create test state");
+
+    let app = router::router().with_state(state.clone());
+    let client = TestClient::new(app);
+    let key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("widget");
+    let username = common::test_data::unique_username("widget");
+    let user_id = common::db::create_verified_user(&state.pool, &email, &username)
+        .await
+        .expect("Failed to create user");
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    sqlx::query(
+        r#"
+        UPDATE user_stats
+        SET current_streak_days = 12, longest_streak_days = 30, total_cards_learned = 345
+        WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .execute(&state.pool)
+    .await
+    .expect("Failed to set stats");
+
+    let response = client
+        .post_json_with_auth(
+            "/v1/users/me/widget-token",
+            &serde_json::json!({}),
+            &token,
+            key,
+        )
+        .await;
+    response.assert_status(StatusCode::OK);
+    let body = response.json::<serde_json::Value>();
+    let streak_path = body["streak_path"].as_str().unwrap().to_string();
+    let cards_path = body["cards_learned_path"].as_str().unwrap().to_string();
+    let stats_path = body["stats_path"].as_str().unwrap().to_string();
+
+    let response = client.get(&streak_path).await;
+    response.assert_status(StatusCode::OK);
+    assert!(
+        response.headers[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("image/svg+xml")
+    );
+    assert!(
+        response.headers[header::CACHE_CONTROL]
+            .to_str()
+            .unwrap()
+            .starts_with("public")
+    );
+    assert!(response.text().contains("<title>streak: 12 days</title>"));
+
+    let response = client.get(&cards_path).await;
+    response.assert_status(StatusCode::OK);
+    assert!(
+        response
+            .text()
+            .contains("<title>cards learned: 345</title>")
+    );
+
+    let response = client.get(&stats_path).await;
+    response.assert_status(StatusCode::OK);
+    assert_eq!(response.headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+    let stats = response.json::<serde_json::Value>();
+    assert_eq!(stats["username"], username);
+    assert_eq!(stats["current_streak_days"], 12);
+    assert_eq!(stats["longest_streak_days"], 30);
+    assert_eq!(stats["cards_learned"], 345);
+
+    // An access token is not a widget token
+    client
+        .get(&format!("/v1/widgets/{token}/streak.svg"))
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+
+    // Turning widgets off invalidates every URL issued so far
+    client
+        .delete_with_auth("/v1/users/me/widget-token", &token, key)
+        .await
+        .assert_status(StatusCode::OK);
+    client
+        .get(&streak_path)
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+    client
+        .get(&stats_path)
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+
+    common::db::delete_user_by_email(&state.pool, &email)
+        .await
+        .expect("Failed to cleanup");
+}
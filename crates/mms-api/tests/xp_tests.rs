@@ -0,0 +1,125 @@
+use crate::common::{self, TestClient, TestStateBuilder};
+use axum::http::StatusCode;
+use mms_api::{auth::Role, router};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_reviews_award_xp_and_levels() {
+    let state = TestStateBuilder::new()
+        .build()
+        .await
+        .expect("Failed to build test state");
+    let client = TestClient::new(router::router().with_state(state.clone()));
+    let pool = &state.pool;
+    let cookie_key = &state.cookie.cookie_key;
+
+    let email = common::test_data::unique_email("xp");
+    let user_id =
+        common::db::create_verified_user(pool, &email, &common::test_data::unique_username("xp"))
+            .await
+            .unwrap();
+    let token = common::jwt::create_test_token(user_id, &email, &state.auth.jwt_keys);
+
+    let deck_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO decks (title, language_from, language_to) VALUES ('XP', 'en', 'es') RETURNING id",
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    let card_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO flashcards (term, translation, language_from, language_to) VALUES ($1, 'perro', 'en', 'es') RETURNING id",
+    )
+    .bind(format!("dog {deck_id}"))
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    sqlx::query("INSERT INTO deck_flashcards (deck_id, flashcard_id) VALUES ($1, $2)")
+        .bind(deck_id)
+        .bind(card_id)
+        .execute(pool)
+        .await
+        .unwrap();
+
+    // 95 XP is 5 short of level 2
+    sqlx::query("UPDATE user_stats SET xp = 95 WHERE user_id = $1")
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .unwrap();
+
+    let review_uri = format!("/v1/practice/{card_id}/review");
+    client
+        .post_json_with_auth(
+            &review_uri,
+            &json!({ "user_answer": "perro", "deck_id": deck_id }),
+            &token,
+            cookie_key,
+        )
+        .await
+        .assert_status(StatusCode::OK);
+    sqlx::query("UPDATE user_card_progress SET next_review_at = NOW() WHERE user_id = $1")
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    client
+        .post_json_with_auth(
+            &review_uri,
+            &json!({ "user_answer": "gato", "deck_id": deck_id }),
+            &token,
+            cookie_key,
+        )
+        .await
+        .assert_status(StatusCode::OK);
+
+    let level: i32 = sqlx::query_scalar("SELECT level FROM user_stats WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+    assert_eq!(level, 2);
+
+    let dashboard: Value = client
+        .get_with_auth("/v1/users/me/dashboard", &token, cookie_key)
+        .await
+        .json();
+    assert_eq!(
+        dashboard["xp"],
+        json!({ "xp": 107, "level": 2, "level_start_xp": 100, "next_level_xp": 300 })
+    );
+
+    let uri = format!("/v1/users/{user_id}/xp");
+    let response = client.get_with_auth(&uri, &token, cookie_key).await;
+    response.assert_status(StatusCode::OK);
+    let history: Value = response.json();
+    assert_eq!(history["progress"]["xp"], 107);
+    let days = history["days"].as_array().unwrap();
+    assert_eq!(days.len(), 1);
+    assert_eq!(days[0]["correct_xp"], mms_srs::CORRECT_REVIEW_XP);
+    assert_eq!(days[0]["wrong_xp"], mms_srs::WRONG_REVIEW_XP);
+    assert_eq!(days[0]["difficulty_bonus_xp"], 0);
+
+    let other = common::account(&state, "other", Role::Learner).await;
+    client
+        .get_with_auth(&uri, &other.token, cookie_key)
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+
+    common::db::delete_user_by_email(pool, &other.email)
+        .await
+        .ok();
+    common::db::delete_user_by_email(pool, &email)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM decks WHERE id = $1")
+        .bind(deck_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM flashcards WHERE id = $1")
+        .bind(card_id)
+        .execute(pool)
+        .await
+        .unwrap();
+}
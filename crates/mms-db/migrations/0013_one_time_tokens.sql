@@ -0,0 +1,127 @@
+-- Migration: Unify single-use email tokens into one table
+--
+-- Email verification and password reset tokens used to live in two identical
+-- tables with duplicated cleanup functions and triggers. They are now stored in
+-- a single `one_time_tokens` table, scoped by `purpose`, so that new link-based
+-- flows (e.g. magic-link login) only need a new purpose value.
+
+CREATE TABLE one_time_tokens (
+    id            UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id       UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    purpose       TEXT NOT NULL
+        CHECK (purpose IN ('email_verification', 'password_reset', 'magic_link')),
+    token_hash    TEXT NOT NULL UNIQUE,
+    expires_at    TIMESTAMPTZ NOT NULL,
+    used_at       TIMESTAMPTZ,
+    created_at    TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Lookup of a user's outstanding tokens for a given purpose (invalidation on reissue)
+CREATE INDEX idx_one_time_tokens_user_purpose
+    ON one_time_tokens(user_id, purpose)
+    WHERE used_at IS NULL;
+
+-- Efficient expiration cleanup
+CREATE INDEX idx_one_time_tokens_expires_at
+    ON one_time_tokens(expires_at)
+    WHERE used_at IS NULL;
+
+-- Carry over tokens that are still usable so in-flight emails keep working
+INSERT INTO one_time_tokens (user_id, purpose, token_hash, expires_at, created_at)
+SELECT user_id, 'email_verification', token_hash, expires_at, COALESCE(created_at, NOW())
+FROM email_verification_tokens
+WHERE used_at IS NULL AND expires_at > NOW();
+
+INSERT INTO one_time_tokens (user_id, purpose, token_hash, expires_at, created_at)
+SELECT user_id, 'password_reset', token_hash, expires_at, COALESCE(created_at, NOW())
+FROM password_reset_tokens
+WHERE used_at IS NULL AND expires_at > NOW();
+
+-- Drop the per-flow tables along with their triggers and cleanup functions
+DROP TRIGGER IF EXISTS trigger_cleanup_password_reset_tokens ON password_reset_tokens;
+DROP TRIGGER IF EXISTS trigger_cleanup_email_verification_tokens ON email_verification_tokens;
+
+DROP FUNCTION IF EXISTS cleanup_all_expired_tokens();
+DROP FUNCTION IF EXISTS cleanup_expired_password_reset_tokens();
+DROP FUNCTION IF EXISTS cleanup_expired_email_verification_tokens();
+
+DROP TABLE password_reset_tokens;
+DROP TABLE email_verification_tokens;
+
+-- Function to clean up expired or used one-time tokens
+CREATE OR REPLACE FUNCTION cleanup_expired_one_time_tokens()
+RETURNS TABLE(purpose TEXT, cleaned INTEGER) AS $$
+BEGIN
+    RETURN QUERY
+    WITH deleted AS (
+        DELETE FROM one_time_tokens t
+        WHERE t.expires_at < NOW() OR t.used_at IS NOT NULL
+        RETURNING t.purpose
+    )
+    SELECT d.purpose, COUNT(*)::INTEGER
+    FROM deleted d
+    GROUP BY d.purpose;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Master cleanup function, keeping the column layout used by the background job
+CREATE OR REPLACE FUNCTION cleanup_all_expired_tokens()
+RETURNS TABLE(
+    password_reset_cleaned INTEGER,
+    email_verification_cleaned INTEGER,
+    refresh_tokens_cleaned INTEGER,
+    total_cleaned INTEGER
+) AS $$
+DECLARE
+    pr_count INTEGER := 0;
+    ev_count INTEGER := 0;
+    other_count INTEGER := 0;
+    rt_count INTEGER;
+    rec RECORD;
+BEGIN
+    FOR rec IN SELECT * FROM cleanup_expired_one_time_tokens() LOOP
+        IF rec.purpose = 'password_reset' THEN
+            pr_count := rec.cleaned;
+        ELSIF rec.purpose = 'email_verification' THEN
+            ev_count := rec.cleaned;
+        ELSE
+            other_count := other_count + rec.cleaned;
+        END IF;
+    END LOOP;
+
+    rt_count := cleanup_expired_refresh_tokens();
+
+    RETURN QUERY SELECT
+        pr_count,
+        ev_count,
+        rt_count,
+        (pr_count + ev_count + other_count + rt_count);
+END;
+$$ LANGUAGE plpgsql;
+
+-- Opportunistic cleanup on insert, replacing the per-table branches
+CREATE OR REPLACE FUNCTION trigger_cleanup_expired_tokens()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_TABLE_NAME = 'one_time_tokens' THEN
+        DELETE FROM one_time_tokens
+        WHERE expires_at < NOW() OR used_at IS NOT NULL;
+    ELSIF TG_TABLE_NAME = 'refresh_tokens' THEN
+        DELETE FROM refresh_tokens
+        WHERE expires_at < NOW();
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_cleanup_one_time_tokens
+    AFTER INSERT ON one_time_tokens
+    FOR EACH STATEMENT
+    EXECUTE FUNCTION trigger_cleanup_expired_tokens();
+
+COMMENT ON TABLE one_time_tokens IS
+'Single-use, expiring tokens delivered by email links. Only the SHA-256 hash is stored.';
+
+COMMENT ON FUNCTION cleanup_expired_one_time_tokens() IS
+'Removes expired and used one-time tokens and returns the number removed per purpose.';
@@ -0,0 +1,9 @@
+-- Migration: Track consecutive failed password logins
+--
+-- Once a user accumulates too many consecutive failures, the API requires a
+-- captcha on further login attempts for that account. A successful login resets
+-- the counter.
+
+ALTER TABLE users
+    ADD COLUMN failed_login_attempts INT NOT NULL DEFAULT 0,
+    ADD COLUMN last_failed_login_at TIMESTAMPTZ;
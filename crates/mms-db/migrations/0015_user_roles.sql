@@ -0,0 +1,16 @@
+-- Migration: User roles
+--
+-- Access tokens carry the user's role and the permission scopes it grants.
+-- Every account starts as a learner; authors can import course content and
+-- admins can additionally use the maintenance endpoints. Roles are granted
+-- manually:
+--
+--   UPDATE users SET role = 'admin' WHERE email = '...';
+--
+-- A role change takes effect the next time the user's access token is issued
+-- (login or refresh).
+
+CREATE TYPE user_role AS ENUM ('learner', 'author', 'admin');
+
+ALTER TABLE users
+    ADD COLUMN role user_role NOT NULL DEFAULT 'learner';
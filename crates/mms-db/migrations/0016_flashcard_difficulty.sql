@@ -0,0 +1,23 @@
+-- Migration: Global flashcard difficulty
+--
+-- Reviews can now report how long the learner took to answer. Progress rows keep
+-- running totals so the nightly difficulty job can aggregate across all users
+-- without a per-review log.
+--
+-- flashcard_difficulty holds the derived score (0 = easy, 1 = hard), rebuilt by
+-- the job for every card with enough reviews. Cards without a row have no score
+-- yet.
+
+ALTER TABLE user_card_progress
+    ADD COLUMN total_response_ms BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN timed_reviews     INT    NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS flashcard_difficulty (
+    flashcard_id    UUID PRIMARY KEY REFERENCES flashcards(id) ON DELETE CASCADE,
+    score           REAL NOT NULL CHECK (score BETWEEN 0 AND 1),
+    failure_rate    REAL NOT NULL,
+    avg_response_ms INT,
+    reviews         INT  NOT NULL,
+    learners        INT  NOT NULL,
+    updated_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
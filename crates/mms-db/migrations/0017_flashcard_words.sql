@@ -0,0 +1,42 @@
+-- Migration: Word index for new-card ordering
+--
+-- flashcard_words lists the distinct words of each card's translation (the
+-- side the learner has to produce), lowercased. Practice sessions use it to
+-- introduce new sentence cards in "i+1" order: cards made of words the learner
+-- already knows come before cards with several unknown words.
+--
+-- The index is maintained by a trigger on flashcards, so every insert path
+-- (migrations, content ingestion, manual SQL) keeps it current.
+
+CREATE TABLE IF NOT EXISTS flashcard_words (
+    flashcard_id UUID NOT NULL REFERENCES flashcards(id) ON DELETE CASCADE,
+    language     CHAR(2) NOT NULL,
+    word         TEXT NOT NULL,
+    PRIMARY KEY (flashcard_id, word)
+);
+
+CREATE OR REPLACE FUNCTION index_flashcard_words()
+RETURNS TRIGGER AS $$
+BEGIN
+    DELETE FROM flashcard_words WHERE flashcard_id = NEW.id;
+
+    INSERT INTO flashcard_words (flashcard_id, language, word)
+    SELECT DISTINCT NEW.id, NEW.language_to, word
+    FROM regexp_split_to_table(lower(NEW.translation), '\W+') AS word
+    WHERE word <> '';
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_flashcards_index_words
+    AFTER INSERT OR UPDATE OF translation, language_to ON flashcards
+    FOR EACH ROW EXECUTE FUNCTION index_flashcard_words();
+
+-- Backfill existing cards
+INSERT INTO flashcard_words (flashcard_id, language, word)
+SELECT DISTINCT f.id, f.language_to, word
+FROM flashcards f,
+     regexp_split_to_table(lower(f.translation), '\W+') AS word
+WHERE word <> ''
+ON CONFLICT DO NOTHING;
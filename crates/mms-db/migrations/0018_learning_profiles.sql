@@ -0,0 +1,55 @@
+-- Migration: Learning profiles
+--
+-- users.native_language / learning_language describe a single target language.
+-- A learning profile is one language pair a user studies, with its own settings
+-- and activity, so Spanish and Japanese each get their own dashboard and due
+-- queue. Cards belong to the profile matching their language pair; card
+-- progress itself stays per user.
+--
+-- profile_activity mirrors user_activity per profile. Reviews keep writing
+-- user_activity and user_stats, which remain the account-wide totals.
+
+CREATE TABLE IF NOT EXISTS learning_profiles (
+    id                UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id           UUID    NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    native_language   CHAR(2) NOT NULL,
+    learning_language CHAR(2) NOT NULL,
+    session_size      INT     NOT NULL DEFAULT 20 CHECK (session_size BETWEEN 1 AND 50),
+    created_at        TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at        TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, native_language, learning_language)
+);
+
+CREATE TRIGGER trg_learning_profiles_updated_at
+    BEFORE UPDATE ON learning_profiles
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+CREATE TABLE IF NOT EXISTS profile_activity (
+    profile_id    UUID NOT NULL REFERENCES learning_profiles(id) ON DELETE CASCADE,
+    activity_date DATE NOT NULL,
+    reviews_count INT  NOT NULL DEFAULT 0,
+    PRIMARY KEY (profile_id, activity_date)
+);
+
+-- Backfill: the language preferences each user already set ...
+INSERT INTO learning_profiles (user_id, native_language, learning_language)
+SELECT id, native_language, learning_language
+FROM users
+WHERE native_language IS NOT NULL AND learning_language IS NOT NULL
+ON CONFLICT DO NOTHING;
+
+-- ... and every language pair they have already practised
+INSERT INTO learning_profiles (user_id, native_language, learning_language)
+SELECT DISTINCT ucp.user_id, f.language_from, f.language_to
+FROM user_card_progress ucp
+JOIN flashcards f ON f.id = ucp.flashcard_id
+ON CONFLICT DO NOTHING;
+
+-- Activity history can only be attributed when the user has a single profile
+INSERT INTO profile_activity (profile_id, activity_date, reviews_count)
+SELECT lp.id, ua.activity_date, ua.reviews_count
+FROM user_activity ua
+JOIN learning_profiles lp ON lp.user_id = ua.user_id
+WHERE ua.user_id IN (
+    SELECT user_id FROM learning_profiles GROUP BY user_id HAVING COUNT(*) = 1
+);
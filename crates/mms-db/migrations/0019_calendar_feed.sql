@@ -0,0 +1,9 @@
+-- Migration: Calendar feed tokens
+--
+-- Calendar apps cannot send cookies, so the iCal feed is authorised by a signed
+-- token in its URL. Tokens carry the version current when they were issued;
+-- issuing a new token or revoking the feed bumps the version, which invalidates
+-- every earlier URL. 0 means no feed has been issued.
+
+ALTER TABLE users
+    ADD COLUMN calendar_token_version INT NOT NULL DEFAULT 0;
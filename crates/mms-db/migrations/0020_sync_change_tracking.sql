@@ -0,0 +1,108 @@
+-- Migration: Change tracking for delta sync
+--
+-- Offline clients pull everything that changed since their last sync. Every
+-- insert or update of a synced row stamps it with:
+--   change_seq  a value from one global sequence, used to order and page changes
+--   change_xid  the writing transaction's id, used as the sync watermark
+--
+-- Sequence values are allocated before commit, so a slow transaction can
+-- commit a lower change_seq after a higher one was already served. Cursors
+-- therefore advance on transaction ids instead: every transaction not yet
+-- committed when a snapshot is taken has an id >= that snapshot's xmin, so
+-- pulling rows with change_xid >= the previous xmin never misses a change.
+-- Rows may be served twice; clients apply them idempotently.
+--
+-- Deck membership changes bump the deck, whose payload lists its cards.
+-- Deletions are recorded in sync_tombstones.
+
+CREATE SEQUENCE IF NOT EXISTS sync_change_seq;
+
+CREATE OR REPLACE FUNCTION set_sync_change()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.change_seq = nextval('sync_change_seq');
+    NEW.change_xid = pg_current_xact_id()::text::bigint;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Existing rows get a sequence value but xid 0, so a first sync returns them all
+ALTER TABLE decks
+    ADD COLUMN change_seq BIGINT NOT NULL DEFAULT nextval('sync_change_seq'),
+    ADD COLUMN change_xid BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE flashcards
+    ADD COLUMN change_seq BIGINT NOT NULL DEFAULT nextval('sync_change_seq'),
+    ADD COLUMN change_xid BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE user_card_progress
+    ADD COLUMN change_seq BIGINT NOT NULL DEFAULT nextval('sync_change_seq'),
+    ADD COLUMN change_xid BIGINT NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS idx_decks_change_xid ON decks(change_xid);
+CREATE INDEX IF NOT EXISTS idx_flashcards_change_xid ON flashcards(change_xid);
+CREATE INDEX IF NOT EXISTS idx_ucp_user_change_xid ON user_card_progress(user_id, change_xid);
+
+CREATE TRIGGER trg_decks_sync_change
+    BEFORE INSERT OR UPDATE ON decks
+    FOR EACH ROW EXECUTE FUNCTION set_sync_change();
+
+CREATE TRIGGER trg_flashcards_sync_change
+    BEFORE INSERT OR UPDATE ON flashcards
+    FOR EACH ROW EXECUTE FUNCTION set_sync_change();
+
+CREATE TRIGGER trg_user_card_progress_sync_change
+    BEFORE INSERT OR UPDATE ON user_card_progress
+    FOR EACH ROW EXECUTE FUNCTION set_sync_change();
+
+-- Adding or removing a card changes the deck's card list
+CREATE OR REPLACE FUNCTION touch_deck_on_membership_change()
+RETURNS TRIGGER AS $$
+BEGIN
+    -- trg_decks_sync_change assigns the new change_seq
+    UPDATE decks SET change_seq = change_seq
+    WHERE id = CASE WHEN TG_OP = 'DELETE' THEN OLD.deck_id ELSE NEW.deck_id END;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_deck_flashcards_sync_change
+    AFTER INSERT OR DELETE ON deck_flashcards
+    FOR EACH ROW EXECUTE FUNCTION touch_deck_on_membership_change();
+
+-- Deleted rows; user_id is NULL for shared content (decks and cards)
+CREATE TABLE IF NOT EXISTS sync_tombstones (
+    entity     TEXT   NOT NULL CHECK (entity IN ('deck', 'card', 'progress')),
+    entity_id  UUID   NOT NULL,
+    user_id    UUID,
+    change_seq BIGINT NOT NULL DEFAULT nextval('sync_change_seq'),
+    change_xid BIGINT NOT NULL DEFAULT pg_current_xact_id()::text::bigint
+);
+
+CREATE INDEX IF NOT EXISTS idx_sync_tombstones_change_xid ON sync_tombstones(change_xid);
+
+CREATE OR REPLACE FUNCTION record_sync_tombstone()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_TABLE_NAME = 'decks' THEN
+        INSERT INTO sync_tombstones (entity, entity_id) VALUES ('deck', OLD.id);
+    ELSIF TG_TABLE_NAME = 'flashcards' THEN
+        INSERT INTO sync_tombstones (entity, entity_id) VALUES ('card', OLD.id);
+    -- Progress removed along with its account has nobody left to sync it
+    ELSIF EXISTS (SELECT 1 FROM users WHERE id = OLD.user_id) THEN
+        INSERT INTO sync_tombstones (entity, entity_id, user_id)
+        VALUES ('progress', OLD.flashcard_id, OLD.user_id);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_decks_sync_tombstone
+    AFTER DELETE ON decks
+    FOR EACH ROW EXECUTE FUNCTION record_sync_tombstone();
+
+CREATE TRIGGER trg_flashcards_sync_tombstone
+    AFTER DELETE ON flashcards
+    FOR EACH ROW EXECUTE FUNCTION record_sync_tombstone();
+
+CREATE TRIGGER trg_user_card_progress_sync_tombstone
+    AFTER DELETE ON user_card_progress
+    FOR EACH ROW EXECUTE FUNCTION record_sync_tombstone();
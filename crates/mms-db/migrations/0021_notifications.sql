@@ -0,0 +1,25 @@
+-- Migration: In-app notifications
+--
+-- Notifications are produced by the server (streak reminders, decks shared
+-- with the user, unlocked achievements) and kept until the user deletes them
+-- or they age out. They are also pushed to open WebSocket and SSE connections
+-- when created; this table is what a client reads after being offline.
+
+CREATE TABLE IF NOT EXISTS notifications (
+    id         UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id    UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind       TEXT NOT NULL CHECK (kind IN ('streak_reminder', 'deck_shared', 'achievement_unlocked')),
+    title      TEXT NOT NULL,
+    body       TEXT NOT NULL,
+    -- Client route to open when the notification is tapped
+    link       TEXT,
+    read_at    TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Newest first, with the id breaking ties for keyset paging
+CREATE INDEX IF NOT EXISTS idx_notifications_user_created
+    ON notifications(user_id, created_at DESC, id DESC);
+
+CREATE INDEX IF NOT EXISTS idx_notifications_user_unread
+    ON notifications(user_id) WHERE read_at IS NULL;
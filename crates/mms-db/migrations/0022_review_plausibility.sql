@@ -0,0 +1,21 @@
+-- Migration: Review plausibility tracking
+--
+-- Reviews submitted faster than a person can read and type (scripts, macros)
+-- distort stats. Such reviews are still graded and scheduled, but counted in
+-- flagged_reviews so rankings can use reviews_count - flagged_reviews instead
+-- of the raw count.
+--
+-- review_pace holds one fixed window per user: the window restarts with the
+-- first review after it expires, and reviews counts submissions within it.
+
+ALTER TABLE user_activity
+    ADD COLUMN flagged_reviews INT NOT NULL DEFAULT 0;
+
+ALTER TABLE user_stats
+    ADD COLUMN flagged_reviews INT NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS review_pace (
+    user_id           UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    window_started_at TIMESTAMPTZ NOT NULL,
+    reviews           INT NOT NULL
+);
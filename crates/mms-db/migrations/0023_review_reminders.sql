@@ -0,0 +1,16 @@
+-- Migration: Daily review reminder emails
+--
+-- Users opt in to one reminder email a day, sent at reminder_hour in their
+-- timezone (an IANA name) when they have cards due. reminder_sent_on is the
+-- user's local date of the last reminder; the job claims a user by setting it,
+-- so each user gets at most one reminder per local day even with several
+-- instances running the job.
+
+ALTER TABLE users
+    ADD COLUMN timezone         TEXT     NOT NULL DEFAULT 'UTC',
+    ADD COLUMN reminder_enabled BOOLEAN  NOT NULL DEFAULT FALSE,
+    ADD COLUMN reminder_hour    SMALLINT NOT NULL DEFAULT 18 CHECK (reminder_hour BETWEEN 0 AND 23),
+    ADD COLUMN reminder_sent_on DATE;
+
+CREATE INDEX IF NOT EXISTS idx_users_reminder_enabled
+    ON users(reminder_hour) WHERE reminder_enabled;
@@ -0,0 +1,15 @@
+-- Migration: Weekly snapshots of card interval distributions
+--
+-- A card's interval is the time between its last review and its next one.
+-- The snapshot job buckets every reviewed card's interval once a day and keeps
+-- one row per user, ISO week (starting Monday) and bucket, overwriting the
+-- current week so the stored week ends up with its last state. Bucket indexes
+-- are defined by the API (stats::intervals::BUCKETS).
+
+CREATE TABLE IF NOT EXISTS interval_snapshots (
+    user_id    UUID     NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    week_start DATE     NOT NULL,
+    bucket     SMALLINT NOT NULL CHECK (bucket >= 0),
+    cards      INT      NOT NULL CHECK (cards > 0),
+    PRIMARY KEY (user_id, week_start, bucket)
+);
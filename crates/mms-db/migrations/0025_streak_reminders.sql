@@ -0,0 +1,12 @@
+-- Migration: Streak-at-risk reminders
+--
+-- In the evening before local midnight, users who reviewed yesterday but not
+-- yet today get a reminder that their streak is about to end, by email and as
+-- an in-app notification. Both are on by default and can be turned off
+-- separately. streak_reminder_sent_on is the user's local date of the last
+-- reminder and is set when the job claims the user, as with reminder_sent_on.
+
+ALTER TABLE users
+    ADD COLUMN streak_reminder_email   BOOLEAN NOT NULL DEFAULT TRUE,
+    ADD COLUMN streak_reminder_in_app  BOOLEAN NOT NULL DEFAULT TRUE,
+    ADD COLUMN streak_reminder_sent_on DATE;
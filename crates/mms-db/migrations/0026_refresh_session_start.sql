@@ -0,0 +1,15 @@
+-- Migration: Absolute lifetime for refresh token sessions
+--
+-- Rotation replaces a refresh token with a new row, so created_at only tells
+-- when the latest token was issued. session_started_at is carried over on
+-- every rotation and records the sign-in that started the session, so the
+-- session can be capped at a maximum age. Existing tokens count from their
+-- own creation.
+
+ALTER TABLE refresh_tokens ADD COLUMN session_started_at TIMESTAMPTZ;
+
+UPDATE refresh_tokens SET session_started_at = COALESCE(created_at, NOW());
+
+ALTER TABLE refresh_tokens
+    ALTER COLUMN session_started_at SET DEFAULT NOW(),
+    ALTER COLUMN session_started_at SET NOT NULL;
@@ -0,0 +1,59 @@
+-- Migration: Activity dates and streaks in the user's timezone
+--
+-- Activity days, streaks and "today" used to follow the server's UTC date, so
+-- a review at 23:30 in New York counted towards the next day. They now follow
+-- users.timezone (added in 0023). These helpers give a user's timezone and
+-- current local date for use in queries; dates already stored are left as
+-- they were recorded.
+
+CREATE OR REPLACE FUNCTION user_timezone(p_user_id UUID)
+RETURNS TEXT AS $$
+    SELECT COALESCE((SELECT timezone FROM users WHERE id = p_user_id), 'UTC')
+$$ LANGUAGE sql STABLE;
+
+CREATE OR REPLACE FUNCTION user_local_date(p_user_id UUID)
+RETURNS DATE AS $$
+    SELECT (NOW() AT TIME ZONE user_timezone(p_user_id))::date
+$$ LANGUAGE sql STABLE;
+
+-- Same as 0009, anchored at the user's local today instead of CURRENT_DATE
+CREATE OR REPLACE FUNCTION calculate_and_update_streak(p_user_id UUID)
+RETURNS void AS $$
+DECLARE
+    v_streak INT := 0;
+    v_today DATE := user_local_date(p_user_id);
+    v_activity_date DATE;
+    v_expected_date DATE;
+BEGIN
+    -- Start from today: if user reviewed today, that's the anchor.
+    -- If not, check yesterday (streak is still alive but user hasn't reviewed yet today).
+    v_expected_date := v_today;
+
+    FOR v_activity_date IN
+        SELECT activity_date
+        FROM user_activity
+        WHERE user_id = p_user_id
+          AND activity_date <= v_today
+        ORDER BY activity_date DESC
+    LOOP
+        IF v_activity_date = v_expected_date THEN
+            -- Consecutive day found
+            v_streak := v_streak + 1;
+            v_expected_date := v_expected_date - 1;
+        ELSIF v_streak = 0 AND v_activity_date = v_today - 1 THEN
+            -- No activity today, but yesterday counts as alive
+            v_streak := 1;
+            v_expected_date := v_activity_date - 1;
+        ELSE
+            -- Gap found, stop counting
+            EXIT;
+        END IF;
+    END LOOP;
+
+    UPDATE user_stats
+    SET current_streak_days = v_streak,
+        longest_streak_days = GREATEST(longest_streak_days, v_streak),
+        updated_at = NOW()
+    WHERE user_id = p_user_id;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,21 @@
+-- Migration: Weekly progress digest emails
+--
+-- On Monday morning in their timezone, users who reviewed during the past week
+-- get a summary email: reviews, accuracy, cards learned and their streak. The
+-- digest is on by default and can be turned off from a link in every email.
+-- weekly_digest_sent_on is the user's local date of the last digest and is set
+-- when the job claims the user, as with reminder_sent_on.
+--
+-- Accuracy needs the number of correct answers per day. Rows written before
+-- this migration have no count, so correct_reviews stays NULL for them and
+-- those days are left out of accuracy.
+
+ALTER TABLE users
+    ADD COLUMN weekly_digest_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    ADD COLUMN weekly_digest_sent_on DATE;
+
+ALTER TABLE user_activity
+    ADD COLUMN correct_reviews INT;
+
+ALTER TABLE user_activity
+    ALTER COLUMN correct_reviews SET DEFAULT 0;
@@ -0,0 +1,27 @@
+-- Migration: Email outbox
+--
+-- Every email is recorded here before it is sent, so a failed send is retried
+-- by a background job instead of being lost. A row stays 'pending' until it is
+-- sent or runs out of attempts ('failed'); next_attempt_at is pushed forward
+-- while a send is in flight, so only one worker picks the row up at a time.
+-- Recipient and body are encrypted by the application (they carry addresses
+-- and sign-in links). Finished rows are deleted after a retention period.
+
+CREATE TABLE IF NOT EXISTS email_outbox (
+    id              UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    to_email        TEXT NOT NULL,
+    subject         TEXT NOT NULL,
+    body            TEXT NOT NULL,
+    status          TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'sent', 'failed')),
+    attempts        INT NOT NULL DEFAULT 0,
+    last_error      TEXT,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    sent_at         TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_email_outbox_due
+    ON email_outbox(next_attempt_at) WHERE status = 'pending';
+
+CREATE INDEX IF NOT EXISTS idx_email_outbox_finished
+    ON email_outbox(created_at) WHERE status <> 'pending';
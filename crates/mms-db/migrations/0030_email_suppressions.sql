@@ -0,0 +1,16 @@
+-- Migration: Email suppressions
+--
+-- Addresses the email provider reported as undeliverable, through its bounce
+-- and complaint webhooks. Nothing is sent to a suppressed address until support
+-- clears it. Only permanent bounces and spam complaints are recorded; temporary
+-- failures are left to the outbox retries. Addresses are stored lowercased.
+
+CREATE TABLE IF NOT EXISTS email_suppressions (
+    email      TEXT PRIMARY KEY,
+    reason     TEXT NOT NULL CHECK (reason IN ('bounce', 'complaint')),
+    provider   TEXT NOT NULL,
+    detail     TEXT,
+    events     INT NOT NULL DEFAULT 1,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
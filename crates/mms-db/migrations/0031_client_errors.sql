@@ -0,0 +1,25 @@
+-- Migration: Client error reports
+--
+-- Crash reports submitted by the web and mobile apps. request_id is the
+-- X-Request-ID of the API response the client was handling when it failed, so
+-- a report can be matched with the server logs of that request. Reports are
+-- sampled and capped per hour by the API, and deleted after a retention period.
+
+CREATE TABLE IF NOT EXISTS client_errors (
+    id          UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    request_id  TEXT,
+    user_id     UUID REFERENCES users(id) ON DELETE SET NULL,
+    platform    TEXT NOT NULL CHECK (platform IN ('web', 'ios', 'android')),
+    app_version TEXT,
+    message     TEXT NOT NULL,
+    stack       TEXT,
+    page        TEXT,
+    user_agent  TEXT,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_client_errors_request_id
+    ON client_errors(request_id) WHERE request_id IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_client_errors_created_at
+    ON client_errors(created_at);
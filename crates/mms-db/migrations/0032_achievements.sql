@@ -0,0 +1,53 @@
+-- Migration: Achievements
+--
+-- achievements defines what can be unlocked: each achievement unlocks once a
+-- user's metric reaches its threshold. Metrics are read from existing tables:
+--   reviews        user_stats.total_reviews - flagged_reviews (plausible reviews)
+--   streak_days    user_stats.longest_streak_days
+--   decks_mastered user_deck_progress rows whose cards are all mastered
+-- New achievements are added by inserting rows; the API evaluates them after
+-- every review. Existing users are backfilled below without notifications.
+
+CREATE TABLE IF NOT EXISTS achievements (
+    code        TEXT PRIMARY KEY,
+    title       TEXT NOT NULL,
+    description TEXT NOT NULL,
+    metric      TEXT NOT NULL CHECK (metric IN ('reviews', 'streak_days', 'decks_mastered')),
+    threshold   INT NOT NULL CHECK (threshold > 0),
+    sort_order  INT NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS user_achievements (
+    user_id          UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    achievement_code TEXT NOT NULL REFERENCES achievements(code) ON DELETE CASCADE,
+    unlocked_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, achievement_code)
+);
+
+INSERT INTO achievements (code, title, description, metric, threshold, sort_order) VALUES
+    ('first_review', 'First review', 'Review your first card', 'reviews', 1, 10),
+    ('reviews_100', 'Century', 'Review 100 cards', 'reviews', 100, 20),
+    ('streak_30', 'Month streak', 'Practise 30 days in a row', 'streak_days', 30, 30),
+    ('deck_mastered', 'Deck master', 'Master every card in a deck', 'decks_mastered', 1, 40)
+ON CONFLICT (code) DO NOTHING;
+
+-- Metric values per user, shared by the backfill and the API's evaluation
+CREATE OR REPLACE FUNCTION achievement_metrics(p_user_id UUID)
+RETURNS TABLE (metric TEXT, value INT) AS $$
+    SELECT 'reviews', total_reviews - flagged_reviews
+    FROM user_stats WHERE user_id = p_user_id
+    UNION ALL
+    SELECT 'streak_days', longest_streak_days
+    FROM user_stats WHERE user_id = p_user_id
+    UNION ALL
+    SELECT 'decks_mastered', COUNT(*)::int
+    FROM user_deck_progress
+    WHERE user_id = p_user_id AND total_cards > 0 AND mastered_cards >= total_cards
+$$ LANGUAGE sql STABLE;
+
+INSERT INTO user_achievements (user_id, achievement_code)
+SELECT u.id, a.code
+FROM users u
+CROSS JOIN LATERAL achievement_metrics(u.id) m
+JOIN achievements a ON a.metric = m.metric AND m.value >= a.threshold
+ON CONFLICT DO NOTHING;
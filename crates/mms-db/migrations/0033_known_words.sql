@@ -0,0 +1,13 @@
+-- Migration: Deck CEFR levels for known-word imports
+--
+-- Experienced learners can mark words they already know, either as a word list
+-- or as a CEFR level; the API then gives the matching cards progress as if they
+-- had been reviewed a few times, skipping the new-card steps. cefr_level tags a
+-- deck with the level of its vocabulary, so "I am B1" marks the cards of every
+-- A1 to B1 deck in that language. Decks without a level are never matched.
+
+ALTER TABLE decks
+    ADD COLUMN cefr_level TEXT CHECK (cefr_level IN ('A1', 'A2', 'B1', 'B2', 'C1', 'C2'));
+
+CREATE INDEX IF NOT EXISTS idx_decks_cefr_level
+    ON decks(language_to, cefr_level) WHERE cefr_level IS NOT NULL;
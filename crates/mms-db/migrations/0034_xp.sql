@@ -0,0 +1,44 @@
+-- Migration: Experience points and levels
+--
+-- Every plausible review earns XP: more for a correct answer, with a bonus for
+-- hard cards (see mms_srs::review_xp). user_stats holds the running total and
+-- the level it reaches; the level is derived from xp by the API, which owns
+-- the level curve. user_activity splits each day's XP by source for the
+-- history endpoint. Flagged reviews earn nothing.
+--
+-- Existing users are credited for past plausible reviews at the base rates
+-- (10 XP correct, 2 XP wrong). Days from before per-day correct counts were
+-- kept count every plausible review as correct. No difficulty bonus is
+-- backfilled. The level follows mms_srs::level_for_xp with a 100 XP step.
+
+ALTER TABLE user_stats
+    ADD COLUMN xp BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN level INT NOT NULL DEFAULT 1;
+
+ALTER TABLE user_activity
+    ADD COLUMN correct_xp INT NOT NULL DEFAULT 0,
+    ADD COLUMN wrong_xp INT NOT NULL DEFAULT 0,
+    ADD COLUMN difficulty_bonus_xp INT NOT NULL DEFAULT 0;
+
+WITH plausible AS (
+    SELECT user_id, activity_date,
+           reviews_count - flagged_reviews AS reviews,
+           LEAST(COALESCE(correct_reviews, reviews_count), reviews_count - flagged_reviews)
+               AS correct
+    FROM user_activity
+)
+UPDATE user_activity ua
+SET correct_xp = 10 * GREATEST(p.correct, 0),
+    wrong_xp = 2 * GREATEST(p.reviews - GREATEST(p.correct, 0), 0)
+FROM plausible p
+WHERE p.user_id = ua.user_id AND p.activity_date = ua.activity_date;
+
+UPDATE user_stats s
+SET xp = totals.xp,
+    level = floor(0.5 + sqrt(0.25 + 2.0 * totals.xp / 100))::int
+FROM (
+    SELECT user_id, SUM(correct_xp + wrong_xp)::bigint AS xp
+    FROM user_activity
+    GROUP BY user_id
+) totals
+WHERE totals.user_id = s.user_id;
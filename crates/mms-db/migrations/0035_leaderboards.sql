@@ -0,0 +1,44 @@
+-- Migration: XP leaderboards
+--
+-- leaderboard_totals holds each user's XP for the current week and month, so
+-- ranking does not aggregate user_activity on every request. A background job
+-- refreshes it every few minutes; CONCURRENTLY needs the unique index. Periods
+-- start on Monday and on the 1st in UTC, and days count by the activity_date
+-- the review was recorded on (the user's local date).
+--
+-- Users with leaderboard_opt_out set are filtered out when reading, not in the
+-- view, so opting out takes effect immediately.
+--
+-- friendships is one-way: a user's friends leaderboard shows them and the
+-- users they added, whether or not those users added them back.
+
+ALTER TABLE users
+    ADD COLUMN leaderboard_opt_out BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE IF NOT EXISTS friendships (
+    user_id    UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    friend_id  UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, friend_id),
+    CHECK (user_id <> friend_id)
+);
+
+CREATE MATERIALIZED VIEW leaderboard_totals AS
+SELECT
+    ua.user_id,
+    p.period,
+    SUM(ua.correct_xp + ua.wrong_xp + ua.difficulty_bonus_xp)::bigint AS xp,
+    NOW() AS refreshed_at
+FROM (
+    VALUES ('week', date_trunc('week', CURRENT_DATE)::date),
+           ('month', date_trunc('month', CURRENT_DATE)::date)
+) AS p(period, starts_on)
+JOIN user_activity ua ON ua.activity_date >= p.starts_on
+GROUP BY ua.user_id, p.period
+HAVING SUM(ua.correct_xp + ua.wrong_xp + ua.difficulty_bonus_xp) > 0;
+
+CREATE UNIQUE INDEX idx_leaderboard_totals_period_user
+    ON leaderboard_totals(period, user_id);
+
+CREATE INDEX idx_leaderboard_totals_period_xp
+    ON leaderboard_totals(period, xp DESC);
@@ -0,0 +1,22 @@
+-- Migration: Study plans
+--
+-- A user can set one target: finish a roadmap by a date. The API derives the
+-- pace from the roadmap's cards the user has not started and the days left,
+-- so nothing here goes stale as they study. baseline_daily_new_cards is the
+-- pace needed when the plan was set; needing noticeably more later means the
+-- user has fallen behind. behind_notified_on is the user's local date of the
+-- last "behind" notification, so the daily check alerts at most once a week.
+
+CREATE TABLE IF NOT EXISTS study_plans (
+    user_id                  UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    roadmap_id               UUID NOT NULL REFERENCES roadmaps(id) ON DELETE CASCADE,
+    target_date              DATE NOT NULL,
+    baseline_daily_new_cards INT NOT NULL,
+    behind_notified_on       DATE,
+    created_at               TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at               TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE notifications DROP CONSTRAINT notifications_kind_check;
+ALTER TABLE notifications ADD CONSTRAINT notifications_kind_check
+    CHECK (kind IN ('streak_reminder', 'deck_shared', 'achievement_unlocked', 'plan_behind'));
@@ -0,0 +1,11 @@
+-- Migration: Embeddable widgets
+--
+-- Streak and cards-learned badges are embedded in blogs and profile pages,
+-- which fetch them without cookies, so they are authorised by a signed token
+-- in the URL. Like calendar feed tokens, widget tokens carry the version
+-- current when they were issued; issuing a new token or turning widgets off
+-- bumps the version, which invalidates every earlier URL. 0 means the user
+-- never turned widgets on.
+
+ALTER TABLE users
+    ADD COLUMN widget_token_version INT NOT NULL DEFAULT 0;
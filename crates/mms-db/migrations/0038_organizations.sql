@@ -0,0 +1,46 @@
+-- Migration: Organizations and tenant-scoped content
+--
+-- Classrooms and other organizations get their own roadmaps and decks, which
+-- only their members may see. Content with a NULL org_id is the public
+-- catalogue and stays visible to everyone. Repositories scope every read of a
+-- tenant-scoped table with org_visible(); see mms_db::tenancy.
+
+CREATE TABLE IF NOT EXISTS organizations (
+    id         UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name       TEXT        NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS organization_members (
+    org_id    UUID        NOT NULL REFERENCES organizations (id) ON DELETE CASCADE,
+    user_id   UUID        NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    role      TEXT        NOT NULL DEFAULT 'member' CHECK (role IN ('owner', 'member')),
+    joined_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (org_id, user_id)
+);
+
+-- org_visible() looks memberships up by user
+CREATE INDEX IF NOT EXISTS idx_organization_members_user ON organization_members (user_id);
+
+ALTER TABLE roadmaps
+    ADD COLUMN org_id UUID REFERENCES organizations (id) ON DELETE CASCADE;
+ALTER TABLE decks
+    ADD COLUMN org_id UUID REFERENCES organizations (id) ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS idx_roadmaps_org ON roadmaps (org_id) WHERE org_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_decks_org ON decks (org_id) WHERE org_id IS NOT NULL;
+
+-- Whether a row owned by `row_org_id` is visible to `viewer_id`: public rows to
+-- everyone, an organization's rows to its members. A NULL viewer (signed out)
+-- only sees public rows. Plain SQL so the planner inlines it.
+CREATE OR REPLACE FUNCTION org_visible(row_org_id UUID, viewer_id UUID)
+RETURNS BOOLEAN
+LANGUAGE sql
+STABLE
+AS $$
+    SELECT row_org_id IS NULL
+        OR EXISTS (
+            SELECT 1 FROM organization_members m
+            WHERE m.org_id = row_org_id AND m.user_id = viewer_id
+        )
+$$;
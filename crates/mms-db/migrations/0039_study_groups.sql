@@ -0,0 +1,33 @@
+-- Migration: Study groups
+--
+-- A study group (a classroom, a club) is an organization: its members see its
+-- content like any other organization's. Teachers invite learners with the
+-- group's code, assign decks and roadmaps, and follow each learner's progress
+-- on them. The creator is the owner; owners and teachers run the group.
+
+ALTER TABLE organizations
+    ADD COLUMN created_by UUID REFERENCES users (id) ON DELETE SET NULL,
+    -- 8 hex digits, shared by teachers; anyone with the code can join
+    ADD COLUMN invite_code TEXT NOT NULL UNIQUE
+        DEFAULT upper(substr(replace(uuid_generate_v4()::text, '-', ''), 1, 8));
+
+ALTER TABLE organization_members
+    DROP CONSTRAINT organization_members_role_check,
+    ADD CONSTRAINT organization_members_role_check
+        CHECK (role IN ('owner', 'teacher', 'member'));
+
+CREATE TABLE IF NOT EXISTS group_assignments (
+    id          UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    org_id      UUID        NOT NULL REFERENCES organizations (id) ON DELETE CASCADE,
+    deck_id     UUID REFERENCES decks (id) ON DELETE CASCADE,
+    roadmap_id  UUID REFERENCES roadmaps (id) ON DELETE CASCADE,
+    assigned_by UUID REFERENCES users (id) ON DELETE SET NULL,
+    assigned_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Either a deck or a roadmap, whose decks all count as assigned
+    CHECK ((deck_id IS NULL) <> (roadmap_id IS NULL))
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_group_assignments_deck
+    ON group_assignments (org_id, deck_id) WHERE deck_id IS NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_group_assignments_roadmap
+    ON group_assignments (org_id, roadmap_id) WHERE roadmap_id IS NOT NULL;
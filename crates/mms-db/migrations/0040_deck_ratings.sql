@@ -0,0 +1,85 @@
+-- Migration: Ratings and comments on public decks
+--
+-- Learners rate public decks from 1 to 5 stars and leave comments. The deck
+-- keeps the number of ratings and their sum, maintained by a trigger so that
+-- concurrent ratings and ratings removed with their user's account are always
+-- counted; the average is derived from both and is what decks sort by.
+-- Authors moderate comments by hiding them, which keeps them for the record.
+
+CREATE TABLE IF NOT EXISTS deck_ratings (
+    deck_id    UUID        NOT NULL REFERENCES decks (id) ON DELETE CASCADE,
+    user_id    UUID        NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    stars      SMALLINT    NOT NULL CHECK (stars BETWEEN 1 AND 5),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (deck_id, user_id)
+);
+
+-- Account deletion cascades by user
+CREATE INDEX IF NOT EXISTS idx_deck_ratings_user ON deck_ratings (user_id);
+
+CREATE TRIGGER trg_deck_ratings_updated_at
+    BEFORE UPDATE ON deck_ratings
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+CREATE TABLE IF NOT EXISTS deck_comments (
+    id         UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    deck_id    UUID        NOT NULL REFERENCES decks (id) ON DELETE CASCADE,
+    user_id    UUID        NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    body       TEXT        NOT NULL CHECK (char_length(body) BETWEEN 1 AND 2000),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Set when the author edits the text, not when it is hidden
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    hidden_at  TIMESTAMPTZ,
+    hidden_by  UUID REFERENCES users (id) ON DELETE SET NULL
+);
+
+-- A deck's comments, newest first
+CREATE INDEX IF NOT EXISTS idx_deck_comments_deck_created
+    ON deck_comments (deck_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_deck_comments_user ON deck_comments (user_id);
+
+ALTER TABLE decks
+    ADD COLUMN rating_count INT NOT NULL DEFAULT 0,
+    ADD COLUMN rating_sum   INT NOT NULL DEFAULT 0,
+    ADD COLUMN rating_average DOUBLE PRECISION GENERATED ALWAYS AS (
+        CASE WHEN rating_count > 0 THEN rating_sum::DOUBLE PRECISION / rating_count END
+    ) STORED;
+
+-- Public decks by rating, for the catalogue
+CREATE INDEX IF NOT EXISTS idx_decks_public_rating
+    ON decks (rating_average DESC NULLS LAST, rating_count DESC)
+    WHERE org_id IS NULL;
+
+-- Adjust the totals by the difference each change makes. Updating the deck row
+-- waits for concurrent raters and then re-reads it, so no rating is lost.
+CREATE OR REPLACE FUNCTION apply_deck_rating_change()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP IN ('UPDATE', 'DELETE') THEN
+        UPDATE decks
+        SET rating_count = rating_count - 1,
+            rating_sum   = rating_sum - OLD.stars
+        WHERE id = OLD.deck_id;
+    END IF;
+    IF TG_OP IN ('INSERT', 'UPDATE') THEN
+        UPDATE decks
+        SET rating_count = rating_count + 1,
+            rating_sum   = rating_sum + NEW.stars
+        WHERE id = NEW.deck_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_deck_ratings_totals
+    AFTER INSERT OR UPDATE OF stars OR DELETE ON deck_ratings
+    FOR EACH ROW EXECUTE FUNCTION apply_deck_rating_change();
+
+-- Ratings do not change what offline clients store, so only the synced columns
+-- (and the explicit bump from touch_deck_on_membership_change) mark a deck changed
+DROP TRIGGER IF EXISTS trg_decks_sync_change ON decks;
+CREATE TRIGGER trg_decks_sync_change
+    BEFORE INSERT OR UPDATE OF title, description, language_from, language_to, cefr_level, org_id, change_seq
+    ON decks
+    FOR EACH ROW EXECUTE FUNCTION set_sync_change();
@@ -0,0 +1,18 @@
+-- Migration: Precomputed review forecasts
+--
+-- Counting a user's scheduled cards per day scans their whole collection, so
+-- the forecast is precomputed: a nightly job stores each active user's counts
+-- and every review moves one card from its old due day to its new one. The
+-- forecast endpoint then reads a single row.
+--
+-- due_counts[1] holds the cards due on starts_on (the user's local date when
+-- the row was computed) or earlier, due_counts[2] those due the day after, and
+-- so on. A few more days are stored than served, so a row stays usable until
+-- the next nightly run even after the user's date has moved on.
+
+CREATE TABLE IF NOT EXISTS review_forecasts (
+    user_id     UUID        PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    starts_on   DATE        NOT NULL,
+    due_counts  INT[]       NOT NULL,
+    computed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
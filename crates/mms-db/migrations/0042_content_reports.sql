@@ -0,0 +1,53 @@
+-- Migration: Content reports
+--
+-- Users flag public decks, cards and comments; moderators work through the
+-- open reports and resolve them by hiding or deleting the content, warning
+-- the comment's author, or dismissing the report. Resolving acts on the
+-- content, so every open report on the same target is closed together.
+--
+-- Reports outlive their target: a deleted deck or comment keeps its reports
+-- for the record, which is why target_id has no foreign key.
+
+-- A hidden deck leaves the public catalogue; a hidden card is no longer practised
+ALTER TABLE decks ADD COLUMN hidden_at TIMESTAMPTZ;
+ALTER TABLE flashcards ADD COLUMN hidden_at TIMESTAMPTZ;
+
+CREATE TABLE IF NOT EXISTS content_reports (
+    id              UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    reporter_id     UUID REFERENCES users (id) ON DELETE SET NULL,
+    target_type     TEXT        NOT NULL CHECK (target_type IN ('deck', 'card', 'comment')),
+    target_id       UUID        NOT NULL,
+    reason          TEXT        NOT NULL
+        CHECK (reason IN ('spam', 'offensive', 'incorrect', 'copyright', 'other')),
+    details         TEXT CHECK (char_length(details) <= 1000),
+    status          TEXT        NOT NULL DEFAULT 'open'
+        CHECK (status IN ('open', 'resolved', 'dismissed')),
+    -- What the moderator did; NULL while open or when dismissed
+    action          TEXT CHECK (action IN ('hide', 'delete', 'warn')),
+    resolved_by     UUID REFERENCES users (id) ON DELETE SET NULL,
+    resolved_at     TIMESTAMPTZ,
+    resolution_note TEXT,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- One open report per user and target; reporting again after a resolution is allowed
+CREATE UNIQUE INDEX IF NOT EXISTS idx_content_reports_open_reporter
+    ON content_reports (reporter_id, target_type, target_id)
+    WHERE status = 'open';
+
+-- The moderation queue, oldest first within a status
+CREATE INDEX IF NOT EXISTS idx_content_reports_status_created
+    ON content_reports (status, created_at);
+
+-- Resolving closes every open report on the target
+CREATE INDEX IF NOT EXISTS idx_content_reports_target
+    ON content_reports (target_type, target_id);
+
+-- The reporter's own reports
+CREATE INDEX IF NOT EXISTS idx_content_reports_reporter
+    ON content_reports (reporter_id, created_at);
+
+ALTER TABLE notifications DROP CONSTRAINT notifications_kind_check;
+ALTER TABLE notifications ADD CONSTRAINT notifications_kind_check
+    CHECK (kind IN ('streak_reminder', 'deck_shared', 'achievement_unlocked', 'plan_behind',
+                    'content_warning'));
@@ -0,0 +1,28 @@
+-- Migration: Review log
+--
+-- user_card_progress only holds each card's current state, so statistics over
+-- time (accuracy per day, retention, when a user studies) need every review.
+-- The practice endpoint appends one row per graded answer. Reviews made
+-- before this migration are not in the log.
+--
+-- previous_interval_days is the interval the card was reviewed at: days from
+-- its previous review to the review it scheduled, NULL on a card's first
+-- review. interval_days is the interval this review scheduled.
+
+CREATE TABLE IF NOT EXISTS review_logs (
+    id                     BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    user_id                UUID             NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    flashcard_id           UUID             NOT NULL REFERENCES flashcards (id) ON DELETE CASCADE,
+    deck_id                UUID REFERENCES decks (id) ON DELETE SET NULL,
+    reviewed_at            TIMESTAMPTZ      NOT NULL DEFAULT NOW(),
+    is_correct             BOOLEAN          NOT NULL,
+    previous_interval_days DOUBLE PRECISION,
+    interval_days          DOUBLE PRECISION NOT NULL,
+    response_time_ms       INT,
+    -- Implausible reviews are logged but left out of statistics
+    flagged                BOOLEAN          NOT NULL DEFAULT FALSE
+);
+
+-- Every statistic reads one user's reviews over a time range
+CREATE INDEX IF NOT EXISTS idx_review_logs_user_reviewed
+    ON review_logs (user_id, reviewed_at);
@@ -0,0 +1,8 @@
+-- Migration: Index scheduled reviews by due time
+--
+-- The forecast counts a user's cards due before a cutoff. idx_practice_session
+-- leads with flashcard_id after user_id, so it cannot serve a range on
+-- next_review_at; without this index the query reads every progress row.
+
+CREATE INDEX IF NOT EXISTS idx_progress_user_due
+    ON user_card_progress (user_id, next_review_at);
@@ -0,0 +1,24 @@
+-- Migration: Flashcard analytics for content authors
+--
+-- Deck analytics aggregate every learner's progress and review log. Doing that
+-- per request would scan both tables, so a nightly job stores one row per
+-- reviewed card and a deck's figures are summed from its cards' rows.
+--
+-- reviews and correct are progress totals, so they cover every answer ever
+-- given. recall_reviews (answers to cards scheduled a day or more ahead),
+-- lapses (the wrong ones) and the mastery times come from the review log and
+-- only cover reviews since it was introduced. avg_days_to_master averages the
+-- mastery_samples learners whose first review of the card is in the log.
+
+CREATE TABLE IF NOT EXISTS flashcard_analytics (
+    flashcard_id       UUID PRIMARY KEY REFERENCES flashcards (id) ON DELETE CASCADE,
+    learners           INT              NOT NULL,
+    reviews            BIGINT           NOT NULL,
+    correct            BIGINT           NOT NULL,
+    recall_reviews     INT              NOT NULL,
+    lapses             INT              NOT NULL,
+    mastered           INT              NOT NULL,
+    mastery_samples    INT              NOT NULL,
+    avg_days_to_master DOUBLE PRECISION,
+    computed_at        TIMESTAMPTZ      NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,10 @@
+-- Migration: Study time and new cards per day
+--
+-- The activity heatmap can show minutes studied and cards started next to the
+-- review count. study_ms adds up the answer times reported with plausible
+-- reviews, capped per review like the difficulty totals; new_cards counts
+-- first reviews. Days before this migration keep zeroes.
+
+ALTER TABLE user_activity
+    ADD COLUMN study_ms  BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN new_cards INT    NOT NULL DEFAULT 0;
@@ -0,0 +1,9 @@
+-- Migration: Time spent per review
+--
+-- Clients can report elapsed_ms, the time spent on a card from showing it to
+-- moving on, feedback included. It is logged next to response_time_ms (the
+-- time to answer) and, when present, is what a review adds to the day's
+-- study time in user_activity.study_ms.
+
+ALTER TABLE review_logs
+    ADD COLUMN elapsed_ms INT;
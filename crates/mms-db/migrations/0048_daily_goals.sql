@@ -0,0 +1,51 @@
+-- Migration: Configurable daily goals
+--
+-- Users pick a daily goal of a number of reviews or minutes of study; the
+-- default matches the fixed goal of 20 reviews used so far. Each activity day
+-- records whether the goal was reached, which feeds the new goal_days
+-- achievement metric. Past days are marked against the old fixed goal.
+
+ALTER TABLE users
+    ADD COLUMN daily_goal_kind   TEXT NOT NULL DEFAULT 'reviews'
+        CHECK (daily_goal_kind IN ('reviews', 'minutes')),
+    ADD COLUMN daily_goal_target INT  NOT NULL DEFAULT 20
+        CHECK (daily_goal_target > 0);
+
+ALTER TABLE user_activity
+    ADD COLUMN goal_reached BOOLEAN NOT NULL DEFAULT FALSE;
+
+UPDATE user_activity SET goal_reached = TRUE WHERE reviews_count - flagged_reviews >= 20;
+
+ALTER TABLE achievements DROP CONSTRAINT achievements_metric_check;
+ALTER TABLE achievements ADD CONSTRAINT achievements_metric_check
+    CHECK (metric IN ('reviews', 'streak_days', 'decks_mastered', 'goal_days'));
+
+INSERT INTO achievements (code, title, description, metric, threshold, sort_order) VALUES
+    ('goal_reached', 'Goal getter', 'Reach your daily goal', 'goal_days', 1, 15),
+    ('goal_days_30', 'Goal keeper', 'Reach your daily goal on 30 days', 'goal_days', 30, 35)
+ON CONFLICT (code) DO NOTHING;
+
+CREATE OR REPLACE FUNCTION achievement_metrics(p_user_id UUID)
+RETURNS TABLE (metric TEXT, value INT) AS $$
+    SELECT 'reviews', total_reviews - flagged_reviews
+    FROM user_stats WHERE user_id = p_user_id
+    UNION ALL
+    SELECT 'streak_days', longest_streak_days
+    FROM user_stats WHERE user_id = p_user_id
+    UNION ALL
+    SELECT 'decks_mastered', COUNT(*)::int
+    FROM user_deck_progress
+    WHERE user_id = p_user_id AND total_cards > 0 AND mastered_cards >= total_cards
+    UNION ALL
+    SELECT 'goal_days', COUNT(*)::int
+    FROM user_activity
+    WHERE user_id = p_user_id AND goal_reached
+$$ LANGUAGE sql STABLE;
+
+INSERT INTO user_achievements (user_id, achievement_code)
+SELECT u.id, a.code
+FROM users u
+CROSS JOIN LATERAL achievement_metrics(u.id) m
+JOIN achievements a ON a.metric = m.metric AND m.value >= a.threshold
+WHERE a.metric = 'goal_days'
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,22 @@
+-- Migration: Idempotency keys
+--
+-- Responses to POSTs sent with an Idempotency-Key header, replayed when the
+-- client retries with the same key. scope is the signed-in user, or the
+-- client IP before sign-in, so clients never see each other's responses.
+-- request_hash fingerprints the method, path and body: reusing a key for a
+-- different request is refused. status is NULL while the first request is
+-- still running. Rows are deleted after a retention period.
+
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    scope        TEXT NOT NULL,
+    key          TEXT NOT NULL,
+    request_hash BYTEA NOT NULL,
+    status       SMALLINT,
+    content_type TEXT,
+    body         BYTEA,
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (scope, key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_idempotency_keys_created_at
+    ON idempotency_keys(created_at);
@@ -0,0 +1,53 @@
+-- Migration: Job queue
+--
+-- Work that must not be lost when an instance restarts is recorded here and
+-- run by the job workers of any instance. A worker claims a 'pending' row by
+-- moving it to 'running' and setting locked_until; a row still 'running'
+-- after its lease has expired belonged to a worker that died and is claimed
+-- again. Failed runs go back to 'pending' with run_at pushed out by a
+-- backoff, until max_attempts is reached and the row is left 'dead' for
+-- an operator to look at. The payload is JSON encrypted by the application
+-- (email jobs carry addresses and sign-in links). unique_key keeps a job from
+-- being queued twice while one is waiting or running.
+
+CREATE TABLE IF NOT EXISTS jobs (
+    id           UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    kind         TEXT NOT NULL,
+    payload      TEXT NOT NULL,
+    unique_key   TEXT,
+    status       TEXT NOT NULL DEFAULT 'pending'
+                 CHECK (status IN ('pending', 'running', 'succeeded', 'dead')),
+    attempts     INT NOT NULL DEFAULT 0,
+    max_attempts INT NOT NULL,
+    run_at       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    locked_until TIMESTAMPTZ,
+    last_error   TEXT,
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at  TIMESTAMPTZ
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_jobs_unique_key
+    ON jobs(unique_key) WHERE status IN ('pending', 'running');
+
+CREATE INDEX IF NOT EXISTS idx_jobs_due
+    ON jobs(run_at) WHERE status = 'pending';
+
+CREATE INDEX IF NOT EXISTS idx_jobs_running
+    ON jobs(locked_until) WHERE status = 'running';
+
+CREATE INDEX IF NOT EXISTS idx_jobs_finished
+    ON jobs(finished_at) WHERE status IN ('succeeded', 'dead');
+
+-- Data exports built by a job; the file is kept until it expires
+
+CREATE TABLE IF NOT EXISTS data_exports (
+    id           UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id      UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    status       TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'ready', 'failed')),
+    body         BYTEA,
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_data_exports_user
+    ON data_exports(user_id, created_at DESC);
@@ -0,0 +1,10 @@
+-- Migration: Scheduled job runs
+--
+-- Every replica queues the maintenance jobs on the same cron schedule.
+-- scheduled_for records which run of the schedule a job is, so each run is
+-- queued once however many replicas try; jobs queued by hand leave it NULL.
+
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS scheduled_for TIMESTAMPTZ;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_jobs_scheduled_for
+    ON jobs(kind, scheduled_for) WHERE scheduled_for IS NOT NULL;
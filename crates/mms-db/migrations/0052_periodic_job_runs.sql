@@ -0,0 +1,10 @@
+-- Migration: Periodic job runs
+--
+-- Every replica runs the same periodic jobs on its own timer. Before a run,
+-- an instance claims it here; the claim only succeeds when the job last
+-- started long enough ago, so each run happens on one replica.
+
+CREATE TABLE IF NOT EXISTS periodic_job_runs (
+    job TEXT PRIMARY KEY,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
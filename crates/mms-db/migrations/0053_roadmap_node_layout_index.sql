@@ -0,0 +1,12 @@
+-- Migration: Covering index for roadmap pages
+--
+-- Roadmap pages read every node of a roadmap in layout order. Keying the
+-- index on the layout and carrying the other columns the page needs lets
+-- Postgres return the nodes already sorted without visiting the table.
+-- It supersedes the plain roadmap_id index.
+
+CREATE INDEX IF NOT EXISTS idx_nodes_roadmap_layout
+    ON roadmap_nodes(roadmap_id, pos_y, pos_x)
+    INCLUDE (id, deck_id, parent_node_id);
+
+DROP INDEX IF EXISTS idx_nodes_roadmap;
@@ -0,0 +1,11 @@
+-- Migration: Soft-deleted users
+--
+-- Deleting an account sets deleted_at instead of removing the row. The account
+-- cannot sign in and is left out of what other users see, but can be restored
+-- during the grace period; a scheduled job deletes it for good afterwards.
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_users_deleted_at
+    ON users(deleted_at)
+    WHERE deleted_at IS NOT NULL;
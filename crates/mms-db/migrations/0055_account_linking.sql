@@ -0,0 +1,11 @@
+-- Migration: Linked sign-in methods
+--
+-- An account can now have a password, a Google account, or both, whichever
+-- provider it was created with; auth_provider only records that provider.
+-- The account must keep at least one way to sign in.
+
+ALTER TABLE users DROP CONSTRAINT IF EXISTS check_auth_credentials;
+
+ALTER TABLE users
+    ADD CONSTRAINT check_sign_in_method
+    CHECK (password_hash IS NOT NULL OR google_id IS NOT NULL);
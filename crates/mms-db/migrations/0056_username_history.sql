@@ -0,0 +1,19 @@
+-- Migration: Username history
+--
+-- Every username change is recorded. A username someone gave up stays reserved
+-- for other accounts for a while, so nobody can take it over and impersonate
+-- its previous owner.
+
+CREATE TABLE IF NOT EXISTS username_history (
+    id           UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id      UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    old_username TEXT NOT NULL,
+    new_username TEXT NOT NULL,
+    changed_at   TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_username_history_user
+    ON username_history(user_id, changed_at DESC);
+
+CREATE INDEX IF NOT EXISTS idx_username_history_old_username
+    ON username_history(old_username, changed_at);
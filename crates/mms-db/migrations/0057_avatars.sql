@@ -0,0 +1,15 @@
+-- Migration: Uploaded avatars
+--
+-- Users upload an avatar instead of pointing at an arbitrary image URL. The
+-- resized images are kept in the media store; avatar_key names them, and while
+-- it is set a picture from Google no longer replaces profile_picture_url.
+-- media_objects backs the default, database media store.
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS avatar_key TEXT;
+
+CREATE TABLE IF NOT EXISTS media_objects (
+    key          TEXT PRIMARY KEY,
+    content_type TEXT NOT NULL,
+    bytes        BYTEA NOT NULL,
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
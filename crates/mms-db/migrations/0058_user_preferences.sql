@@ -0,0 +1,16 @@
+-- Migration: User preferences
+--
+-- Settings the apps apply on the client: theme, audio autoplay, how strictly
+-- typed answers are checked and how many new cards and reviews to serve a
+-- day. A user has a row once they change a preference; until then the API
+-- returns the defaults.
+
+CREATE TABLE IF NOT EXISTS user_preferences (
+    user_id           UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    theme             TEXT        NOT NULL CHECK (theme IN ('system', 'light', 'dark')),
+    audio_autoplay    BOOLEAN     NOT NULL,
+    answer_checking   TEXT        NOT NULL CHECK (answer_checking IN ('strict', 'normal', 'lenient')),
+    new_cards_per_day INT         NOT NULL CHECK (new_cards_per_day >= 0),
+    reviews_per_day   INT         NOT NULL CHECK (reviews_per_day > 0),
+    updated_at        TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
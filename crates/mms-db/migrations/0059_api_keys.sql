@@ -0,0 +1,31 @@
+-- Migration: API keys
+--
+-- Integrators read the public catalogue under /v1/public with an API key a
+-- user created. Only a hash of the key is stored, with its first characters
+-- so the owner can tell keys apart. Each key has its own daily quota; requests
+-- are counted per key and UTC day, which also serves as the usage report.
+
+CREATE TABLE IF NOT EXISTS api_keys (
+    id           UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id      UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name         TEXT NOT NULL,
+    key_prefix   TEXT NOT NULL,
+    key_hash     TEXT NOT NULL UNIQUE,
+    daily_quota  INT  NOT NULL CHECK (daily_quota > 0),
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMPTZ,
+    revoked_at   TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_keys_user
+    ON api_keys(user_id, created_at DESC);
+
+CREATE TABLE IF NOT EXISTS api_key_usage (
+    api_key_id UUID NOT NULL REFERENCES api_keys(id) ON DELETE CASCADE,
+    day        DATE NOT NULL,
+    -- Every request made with the key, including refused ones
+    requests   INT  NOT NULL,
+    -- Requests refused because the quota was used up
+    rejected   INT  NOT NULL DEFAULT 0,
+    PRIMARY KEY (api_key_id, day)
+);
@@ -0,0 +1,34 @@
+-- Migration: Practice sessions
+--
+-- A practice session freezes a deck's due cards into a queue when it starts.
+-- Cards are served from the queue in position order and answered once each,
+-- so two tabs or a retried request cannot serve or grade the same card twice.
+-- A session stays open for a while after its last activity and can be resumed
+-- until then; expired sessions are deleted.
+
+CREATE TABLE IF NOT EXISTS practice_sessions (
+    id           UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id      UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    deck_id      UUID NOT NULL REFERENCES decks(id) ON DELETE CASCADE,
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at   TIMESTAMPTZ NOT NULL,
+    -- Set once no card is left to serve
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_practice_sessions_user_deck
+    ON practice_sessions(user_id, deck_id, expires_at DESC);
+
+CREATE INDEX IF NOT EXISTS idx_practice_sessions_expires_at
+    ON practice_sessions(expires_at);
+
+CREATE TABLE IF NOT EXISTS practice_session_cards (
+    session_id   UUID NOT NULL REFERENCES practice_sessions(id) ON DELETE CASCADE,
+    position     INT  NOT NULL,
+    flashcard_id UUID NOT NULL REFERENCES flashcards(id) ON DELETE CASCADE,
+    served_at    TIMESTAMPTZ,
+    answered_at  TIMESTAMPTZ,
+    is_correct   BOOLEAN,
+    PRIMARY KEY (session_id, position),
+    UNIQUE (session_id, flashcard_id)
+);
@@ -0,0 +1,31 @@
+-- Migration: Practice sessions over several decks
+--
+-- A session can now span several decks or a whole roadmap, with their cards
+-- interleaved in one queue. deck_ids lists the decks the session covers,
+-- sorted, and roadmap_id is set when the session was started on a roadmap;
+-- together they identify the open session a new start resumes. Each queued
+-- card keeps the deck it was drawn from, and its answer counts towards that
+-- deck's progress.
+
+ALTER TABLE practice_sessions
+    ADD COLUMN IF NOT EXISTS deck_ids   UUID[],
+    ADD COLUMN IF NOT EXISTS roadmap_id UUID REFERENCES roadmaps(id) ON DELETE CASCADE;
+
+ALTER TABLE practice_session_cards
+    ADD COLUMN IF NOT EXISTS deck_id UUID REFERENCES decks(id) ON DELETE CASCADE;
+
+UPDATE practice_sessions SET deck_ids = ARRAY[deck_id] WHERE deck_ids IS NULL;
+
+UPDATE practice_session_cards c
+SET deck_id = s.deck_id
+FROM practice_sessions s
+WHERE s.id = c.session_id AND c.deck_id IS NULL;
+
+ALTER TABLE practice_sessions ALTER COLUMN deck_ids SET NOT NULL;
+ALTER TABLE practice_session_cards ALTER COLUMN deck_id SET NOT NULL;
+
+DROP INDEX IF EXISTS idx_practice_sessions_user_deck;
+ALTER TABLE practice_sessions DROP COLUMN IF EXISTS deck_id;
+
+CREATE INDEX IF NOT EXISTS idx_practice_sessions_user
+    ON practice_sessions(user_id, expires_at DESC);
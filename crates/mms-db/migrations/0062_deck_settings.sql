@@ -0,0 +1,19 @@
+-- Migration: Deck scheduling settings
+--
+-- A deck can override how its cards are scheduled: the longest interval, an
+-- ease that scales the day-based intervals, and the order new cards are
+-- introduced in. A NULL setting, or a deck without a row, keeps the default.
+-- Learners get their own longest interval; the shorter of theirs and the
+-- deck's applies.
+
+CREATE TABLE IF NOT EXISTS deck_settings (
+    deck_id           UUID PRIMARY KEY REFERENCES decks(id) ON DELETE CASCADE,
+    max_interval_days INT CHECK (max_interval_days BETWEEN 1 AND 365),
+    starting_ease     DOUBLE PRECISION CHECK (starting_ease BETWEEN 0.5 AND 2.0),
+    new_card_order    TEXT CHECK (new_card_order IN ('easiest_first', 'added', 'random')),
+    updated_at        TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE user_preferences
+    ADD COLUMN IF NOT EXISTS max_interval_days INT NOT NULL DEFAULT 90
+        CHECK (max_interval_days BETWEEN 1 AND 365);
@@ -0,0 +1,13 @@
+-- Migration: Content slugs
+--
+-- Seed bundles name roadmaps and decks by a stable slug, so loading a bundle
+-- again updates the rows it created instead of adding copies. Content created
+-- any other way has no slug.
+
+ALTER TABLE roadmaps
+    ADD COLUMN IF NOT EXISTS slug TEXT CHECK (slug ~ '^[a-z0-9]+(-[a-z0-9]+)*$');
+CREATE UNIQUE INDEX IF NOT EXISTS idx_roadmaps_slug ON roadmaps(slug);
+
+ALTER TABLE decks
+    ADD COLUMN IF NOT EXISTS slug TEXT CHECK (slug ~ '^[a-z0-9]+(-[a-z0-9]+)*$');
+CREATE UNIQUE INDEX IF NOT EXISTS idx_decks_slug ON decks(slug);
@@ -0,0 +1,104 @@
+-- Migration: Generated slugs for every roadmap and deck
+--
+-- Shared links name roadmaps and decks by slug, so every row needs one. A row
+-- inserted without a slug gets one made from its title: lowercased, common
+-- accents dropped, runs of anything else turned into single dashes, cut to 80
+-- characters. A slug already taken gets -2, -3, ... appended. Titles with
+-- nothing left after that fall back to 'roadmap' or 'deck'.
+--
+-- Admins may change a slug later; the old one stops resolving.
+
+-- replace() rather than translate() or unaccent, so accented letters are
+-- matched as whole strings whatever the database encoding
+CREATE OR REPLACE FUNCTION slugify(input TEXT)
+RETURNS TEXT AS $$
+DECLARE
+    result TEXT := input;
+    plain TEXT;
+    accented TEXT;
+    letter TEXT;
+BEGIN
+    FOR plain, accented IN SELECT * FROM (VALUES
+        ('a', 'á à â ä ã å ā Á À Â Ä Ã Å Ā'),
+        ('e', 'é è ê ë ē É È Ê Ë Ē'),
+        ('i', 'í ì î ï ī Í Ì Î Ï Ī'),
+        ('o', 'ó ò ô ö õ ø ō Ó Ò Ô Ö Õ Ø Ō'),
+        ('u', 'ú ù û ü ū Ú Ù Û Ü Ū'),
+        ('n', 'ñ Ñ'),
+        ('c', 'ç Ç'),
+        ('y', 'ý ÿ Ý Ÿ'),
+        ('ss', 'ß'),
+        ('ae', 'æ Æ'),
+        ('oe', 'œ Œ')
+    ) AS letters (plain, accented) LOOP
+        FOREACH letter IN ARRAY string_to_array(accented, ' ') LOOP
+            result := replace(result, letter, plain);
+        END LOOP;
+    END LOOP;
+
+    result := trim(BOTH '-' FROM regexp_replace(lower(result), '[^a-z0-9]+', '-', 'g'));
+    RETURN NULLIF(trim(BOTH '-' FROM left(result, 80)), '');
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+-- The first slug for `title` not yet used in `tbl`. A transaction-level
+-- advisory lock on the table and base slug makes a second insert of the same
+-- title wait for the first and then see its slug taken. It does not cover
+-- every clash (a title like "x-2" or an admin-set slug), so inserts that rely
+-- on a generated slug still retry on a unique violation.
+CREATE OR REPLACE FUNCTION next_content_slug(tbl TEXT, title TEXT, fallback TEXT)
+RETURNS TEXT AS $$
+DECLARE
+    base TEXT := COALESCE(slugify(title), fallback);
+    candidate TEXT := base;
+    suffix INT := 1;
+    taken BOOLEAN;
+BEGIN
+    PERFORM pg_advisory_xact_lock(hashtext(tbl || ':' || base));
+    LOOP
+        EXECUTE format('SELECT EXISTS (SELECT 1 FROM %I WHERE slug = $1)', tbl)
+            INTO taken USING candidate;
+        EXIT WHEN NOT taken;
+        suffix := suffix + 1;
+        candidate := base || '-' || suffix;
+    END LOOP;
+    RETURN candidate;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION set_content_slug()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.slug IS NULL THEN
+        NEW.slug = next_content_slug(TG_TABLE_NAME, NEW.title, TG_ARGV[0]);
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_roadmaps_slug
+    BEFORE INSERT ON roadmaps
+    FOR EACH ROW EXECUTE FUNCTION set_content_slug('roadmap');
+
+CREATE TRIGGER trg_decks_slug
+    BEFORE INSERT ON decks
+    FOR EACH ROW EXECUTE FUNCTION set_content_slug('deck');
+
+-- Oldest first, so the earliest of several same-titled rows keeps the plain slug
+DO $$
+DECLARE
+    row_id UUID;
+BEGIN
+    FOR row_id IN SELECT id FROM roadmaps WHERE slug IS NULL ORDER BY created_at, id LOOP
+        UPDATE roadmaps SET slug = next_content_slug('roadmaps', title, 'roadmap')
+        WHERE id = row_id;
+    END LOOP;
+    FOR row_id IN SELECT id FROM decks WHERE slug IS NULL ORDER BY created_at, id LOOP
+        UPDATE decks SET slug = next_content_slug('decks', title, 'deck')
+        WHERE id = row_id;
+    END LOOP;
+END;
+$$;
+
+ALTER TABLE roadmaps ALTER COLUMN slug SET NOT NULL;
+ALTER TABLE decks ALTER COLUMN slug SET NOT NULL;
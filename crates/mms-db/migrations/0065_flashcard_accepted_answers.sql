@@ -0,0 +1,9 @@
+-- Migration: Accepted answers
+--
+-- A card's translation is the answer shown to learners; accepted_answers
+-- lists other answers graded as correct too. Merging two cards keeps one and
+-- adds the other's translation and accepted answers here, so learners who
+-- studied either card are not marked wrong for the answer they learned.
+
+ALTER TABLE flashcards
+    ADD COLUMN IF NOT EXISTS accepted_answers TEXT[] NOT NULL DEFAULT '{}';
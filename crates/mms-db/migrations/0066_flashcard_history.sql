@@ -0,0 +1,20 @@
+-- Migration: Flashcard history
+--
+-- Every edit of a card's term or translation is recorded with who made it,
+-- so a bad edit can be spotted and reverted. A revert is an edit too and
+-- shows up in the history like any other.
+
+CREATE TABLE IF NOT EXISTS flashcard_history (
+    id              UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    flashcard_id    UUID NOT NULL REFERENCES flashcards(id) ON DELETE CASCADE,
+    old_term        TEXT NOT NULL,
+    old_translation TEXT NOT NULL,
+    new_term        TEXT NOT NULL,
+    new_translation TEXT NOT NULL,
+    -- NULL for edits made with the admin API token, or by a deleted account
+    edited_by       UUID REFERENCES users(id) ON DELETE SET NULL,
+    edited_at       TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_flashcard_history_card
+    ON flashcard_history(flashcard_id, edited_at DESC);
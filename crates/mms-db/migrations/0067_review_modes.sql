@@ -0,0 +1,9 @@
+-- Migration: Review modes
+--
+-- A review log records how the card was practised: typing the translation,
+-- or saying it aloud for pronunciation practice, graded from a transcript of
+-- the recording. Reviews logged before this were all typed.
+
+ALTER TABLE review_logs
+    ADD COLUMN IF NOT EXISTS mode TEXT NOT NULL DEFAULT 'translation'
+        CHECK (mode IN ('translation', 'pronunciation'));
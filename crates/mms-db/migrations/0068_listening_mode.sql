@@ -0,0 +1,27 @@
+-- Migration: Listening mode and per-mode progress
+--
+-- In listening (dictation) mode the learner only hears the card's translation
+-- and types what they heard. A deck lists the modes its cards are practised
+-- in; NULL keeps the default of translation only. Each mode keeps its own
+-- score per card, so the practice queue can serve the mode a learner is
+-- weakest in, while user_card_progress still schedules the card as a whole.
+
+ALTER TABLE review_logs DROP CONSTRAINT IF EXISTS review_logs_mode_check;
+ALTER TABLE review_logs
+    ADD CONSTRAINT review_logs_mode_check
+        CHECK (mode IN ('translation', 'listening', 'pronunciation'));
+
+ALTER TABLE deck_settings
+    ADD COLUMN IF NOT EXISTS modes TEXT[]
+        CHECK (cardinality(modes) > 0
+            AND modes <@ ARRAY['translation', 'listening', 'pronunciation']);
+
+CREATE TABLE IF NOT EXISTS user_card_mode_progress (
+    user_id        UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    flashcard_id   UUID NOT NULL REFERENCES flashcards(id) ON DELETE CASCADE,
+    mode           TEXT NOT NULL CHECK (mode IN ('translation', 'listening', 'pronunciation')),
+    times_correct  INT NOT NULL DEFAULT 0,
+    times_wrong    INT NOT NULL DEFAULT 0,
+    last_review_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (user_id, flashcard_id, mode)
+);
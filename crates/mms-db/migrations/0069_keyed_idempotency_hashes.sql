@@ -0,0 +1,8 @@
+-- Migration: Keyed idempotency fingerprints
+--
+-- request_hash is now an HMAC keyed with a server secret rather than a plain
+-- SHA-256 of the request: registration bodies carry a password, and a plain
+-- hash kept for a day could be brute-forced offline. Drop the rows stored
+-- with plain hashes; they only serve retries within the retention period.
+
+DELETE FROM idempotency_keys;
@@ -0,0 +1,29 @@
+-- Migration: Count failed logins per email address
+--
+-- The login captcha used to depend on the account's failed_login_attempts,
+-- so an unknown address and a locked account answered differently. Failures
+-- are now counted per address whether or not an account has it, and the
+-- captcha is required before the account is looked up. email_hash is the
+-- hex SHA-256 of the trimmed, lowercased address. A count lapses a day after
+-- its last failure, and the token cleanup job deletes lapsed rows.
+
+CREATE TABLE IF NOT EXISTS login_failures (
+    email_hash     TEXT PRIMARY KEY,
+    attempts       INT NOT NULL,
+    last_failed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_login_failures_last_failed_at
+    ON login_failures(last_failed_at);
+
+INSERT INTO login_failures (email_hash, attempts, last_failed_at)
+SELECT encode(sha256(convert_to(lower(trim(email)), 'UTF8')), 'hex'),
+       failed_login_attempts,
+       COALESCE(last_failed_login_at, NOW())
+FROM users
+WHERE failed_login_attempts > 0
+ON CONFLICT (email_hash) DO NOTHING;
+
+ALTER TABLE users
+    DROP COLUMN IF EXISTS failed_login_attempts,
+    DROP COLUMN IF EXISTS last_failed_login_at;
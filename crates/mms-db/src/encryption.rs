@@ -0,0 +1,304 @@
+//! Application-level encryption for PII columns.
+//!
+//! [`EncryptedString`] is stored in a TEXT column as
+//! `enc:v1:<key id>:<base64(nonce || ciphertext)>`, using AES-256-GCM with a
+//! random 96-bit nonce per write. The key id is derived from the key itself, so
+//! values written under a previous key still decrypt after a rotation as long
+//! as that key stays in the [`Keyring`]; they are re-encrypted under the
+//! current key the next time they are written.
+//!
+//! Values without the `enc:` prefix are read back unchanged, so rows written
+//! before encryption was enabled keep working. The keyring is process-wide
+//! ([`install_keyring`]) because sqlx encodes and decodes without context;
+//! until one is installed, values are written in plain text.
+
+use std::fmt;
+use std::sync::OnceLock;
+
+use aes_gcm::{
+    Aes256Gcm, Nonce,
+    aead::{Aead, KeyInit},
+};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use sqlx::{
+    Decode, Encode, Postgres, Type,
+    encode::IsNull,
+    error::BoxDynError,
+    postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef},
+};
+
+/// Length of an AES-256 key in bytes
+pub const KEY_LEN: usize = 32;
+
+const PREFIX: &str = "enc:v1:";
+const NONCE_LEN: usize = 12;
+
+static KEYRING: OnceLock<Keyring> = OnceLock::new();
+
+#[derive(Debug, thiserror::Error)]
+pub enum EncryptionError {
+    #[error("Encryption keys must be {KEY_LEN} bytes, base64 encoded")]
+    InvalidKey,
+    #[error("Value is encrypted but no encryption key is configured")]
+    NoKeyring,
+    #[error("Value was encrypted with unknown key {0}")]
+    UnknownKey(String),
+    #[error("Malformed encrypted value")]
+    Malformed,
+    #[error("Encrypted value failed authentication")]
+    Tampered,
+}
+
+/// The key new values are encrypted with, plus previous keys still accepted for decryption
+pub struct Keyring {
+    current: (String, Aes256Gcm),
+    previous: Vec<(String, Aes256Gcm)>,
+}
+
+impl fmt::Debug for Keyring {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Keyring")
+            .field("current", &self.current.0)
+            .field(
+                "previous",
+                &self.previous.iter().map(|(id, _)| id).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl Keyring {
+    /// Build a keyring from base64-encoded 32-byte keys
+    pub fn from_base64(current: &str, previous: &[&str]) -> Result<Self, EncryptionError> {
+        Ok(Self {
+            current: decode_key(current)?,
+            previous: previous
+                .iter()
+                .map(|key| decode_key(key))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Encrypt under the current key
+    pub fn encrypt(&self, plaintext: &str) -> String {
+        let (key_id, cipher) = &self.current;
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill(&mut nonce);
+
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_bytes())
+            .expect("AES-GCM encryption of an in-memory buffer cannot fail");
+
+        let mut payload = nonce.to_vec();
+        payload.extend_from_slice(&ciphertext);
+        format!("{PREFIX}{key_id}:{}", BASE64.encode(payload))
+    }
+
+    /// Decrypt a stored value; values without the `enc:` prefix are returned unchanged
+    pub fn decrypt(&self, stored: &str) -> Result<String, EncryptionError> {
+        let Some(rest) = stored.strip_prefix(PREFIX) else {
+            return Ok(stored.to_string());
+        };
+        let (key_id, payload) = rest.split_once(':').ok_or(EncryptionError::Malformed)?;
+
+        let cipher = std::iter::once(&self.current)
+            .chain(&self.previous)
+            .find(|(id, _)| id == key_id)
+            .map(|(_, cipher)| cipher)
+            .ok_or_else(|| EncryptionError::UnknownKey(key_id.to_string()))?;
+
+        let payload = BASE64
+            .decode(payload)
+            .map_err(|_| EncryptionError::Malformed)?;
+        if payload.len() < NONCE_LEN {
+            return Err(EncryptionError::Malformed);
+        }
+        let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| EncryptionError::Tampered)?;
+        String::from_utf8(plaintext).map_err(|_| EncryptionError::Malformed)
+    }
+
+    /// Whether a stored value is already encrypted under the current key
+    #[must_use]
+    pub fn is_current(&self, stored: &str) -> bool {
+        stored
+            .strip_prefix(PREFIX)
+            .and_then(|rest| rest.split_once(':'))
+            .is_some_and(|(key_id, _)| key_id == self.current.0)
+    }
+}
+
+/// Decode a key and derive its id: the first 4 bytes of its SHA-256, in hex
+fn decode_key(key: &str) -> Result<(String, Aes256Gcm), EncryptionError> {
+    let bytes = BASE64
+        .decode(key.trim())
+        .map_err(|_| EncryptionError::InvalidKey)?;
+    if bytes.len() != KEY_LEN {
+        return Err(EncryptionError::InvalidKey);
+    }
+
+    let key_id = Sha256::digest(&bytes)[..4]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    let cipher = Aes256Gcm::new_from_slice(&bytes).map_err(|_| EncryptionError::InvalidKey)?;
+    Ok((key_id, cipher))
+}
+
+/// Install the process-wide keyring used by [`EncryptedString`].
+///
+/// Returns `false` if a keyring was already installed; the first one stays.
+pub fn install_keyring(keyring: Keyring) -> bool {
+    KEYRING.set(keyring).is_ok()
+}
+
+/// The installed keyring, if any
+pub fn keyring() -> Option<&'static Keyring> {
+    KEYRING.get()
+}
+
+/// A string column encrypted at rest.
+///
+/// Holds the plaintext in memory; encryption happens when the value is bound
+/// to a query and decryption when it is read. `Debug` never shows the value.
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptedString(String);
+
+impl EncryptedString {
+    pub fn new(plaintext: impl Into<String>) -> Self {
+        Self(plaintext.into())
+    }
+
+    /// The decrypted value
+    #[must_use]
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    #[must_use]
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl fmt::Debug for EncryptedString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EncryptedString(<redacted>)")
+    }
+}
+
+impl From<String> for EncryptedString {
+    fn from(plaintext: String) -> Self {
+        Self(plaintext)
+    }
+}
+
+impl From<&str> for EncryptedString {
+    fn from(plaintext: &str) -> Self {
+        Self(plaintext.to_string())
+    }
+}
+
+impl Type<Postgres> for EncryptedString {
+    fn type_info() -> PgTypeInfo {
+        <String as Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <String as Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl Encode<'_, Postgres> for EncryptedString {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        let stored = match keyring() {
+            Some(keyring) => keyring.encrypt(&self.0),
+            None => self.0.clone(),
+        };
+        <String as Encode<'_, Postgres>>::encode(stored, buf)
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for EncryptedString {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        let stored = <&str as Decode<'r, Postgres>>::decode(value)?;
+        if !stored.starts_with(PREFIX) {
+            return Ok(Self(stored.to_string()));
+        }
+
+        let keyring = keyring().ok_or(EncryptionError::NoKeyring)?;
+        Ok(Self(keyring.decrypt(stored)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY_A: &str = "MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=";
+    const KEY_B: &str = "ZmVkY2JhOTg3NjU0MzIxMGZlZGNiYTk4NzY1NDMyMTA=";
+
+    #[test]
+    fn round_trips_with_a_fresh_nonce_each_time() {
+        let keyring = Keyring::from_base64(KEY_A, &[]).unwrap();
+
+        let first = keyring.encrypt("203.0.113.7");
+        let second = keyring.encrypt("203.0.113.7");
+        assert!(first.starts_with(PREFIX));
+        assert_ne!(first, second);
+        assert_eq!(keyring.decrypt(&first).unwrap(), "203.0.113.7");
+        assert!(keyring.is_current(&first));
+    }
+
+    #[test]
+    fn previous_keys_still_decrypt_after_rotation() {
+        let old = Keyring::from_base64(KEY_A, &[]).unwrap();
+        let stored = old.encrypt("ana@example.com");
+
+        let rotated = Keyring::from_base64(KEY_B, &[KEY_A]).unwrap();
+        assert_eq!(rotated.decrypt(&stored).unwrap(), "ana@example.com");
+        assert!(!rotated.is_current(&stored));
+
+        let dropped = Keyring::from_base64(KEY_B, &[]).unwrap();
+        assert!(matches!(
+            dropped.decrypt(&stored),
+            Err(EncryptionError::UnknownKey(_))
+        ));
+    }
+
+    #[test]
+    fn tampering_is_detected() {
+        let keyring = Keyring::from_base64(KEY_A, &[]).unwrap();
+        let stored = keyring.encrypt("secret");
+        let (head, payload) = stored.rsplit_once(':').unwrap();
+        let mut bytes = BASE64.decode(payload).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        let tampered = format!("{head}:{}", BASE64.encode(bytes));
+
+        assert!(matches!(
+            keyring.decrypt(&tampered),
+            Err(EncryptionError::Tampered)
+        ));
+    }
+
+    #[test]
+    fn plain_text_passes_through_and_bad_keys_are_rejected() {
+        let keyring = Keyring::from_base64(KEY_A, &[]).unwrap();
+        assert_eq!(keyring.decrypt("legacy value").unwrap(), "legacy value");
+
+        assert!(Keyring::from_base64("c2hvcnQ=", &[]).is_err());
+        assert!(Keyring::from_base64("not base64!", &[]).is_err());
+    }
+
+    #[test]
+    fn debug_output_is_redacted() {
+        let value = EncryptedString::new("ana@example.com");
+        assert!(!format!("{value:?}").contains("ana"));
+    }
+}
@@ -0,0 +1,149 @@
+//! Indexes the API's hot queries depend on.
+//!
+//! This is the source of truth the index advisor checks the live database
+//! against. When a migration adds an index for a new query, add it here too so a
+//! dropped or never-applied index shows up in the report instead of as a slow
+//! endpoint.
+
+/// An index a repository query relies on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExpectedIndex {
+    /// Name given in the migration
+    pub name: &'static str,
+    pub table: &'static str,
+    /// Key columns, in order. Any index whose key starts with these columns
+    /// satisfies the expectation, whatever its name.
+    pub columns: &'static [&'static str],
+    /// Query that needs the index
+    pub used_by: &'static str,
+}
+
+/// Indexes expected to exist after all migrations have run
+pub const EXPECTED_INDEXES: &[ExpectedIndex] = &[
+    ExpectedIndex {
+        name: "idx_users_email",
+        table: "users",
+        columns: &["email"],
+        used_by: "user::find_credentials_by_email",
+    },
+    ExpectedIndex {
+        name: "idx_users_google_id",
+        table: "users",
+        columns: &["google_id"],
+        used_by: "auth::find_by_google_id",
+    },
+    ExpectedIndex {
+        name: "idx_users_deleted_at",
+        table: "users",
+        columns: &["deleted_at"],
+        used_by: "user::purge_deleted_users",
+    },
+    ExpectedIndex {
+        name: "idx_username_history_old_username",
+        table: "username_history",
+        columns: &["old_username", "changed_at"],
+        used_by: "user::find_username_status",
+    },
+    ExpectedIndex {
+        name: "idx_username_history_user",
+        table: "username_history",
+        columns: &["user_id", "changed_at"],
+        used_by: "user::list_username_history",
+    },
+    ExpectedIndex {
+        name: "idx_roadmaps_langs",
+        table: "roadmaps",
+        columns: &["language_from", "language_to"],
+        used_by: "roadmap::list_by_language",
+    },
+    ExpectedIndex {
+        name: "idx_nodes_roadmap_layout",
+        table: "roadmap_nodes",
+        columns: &["roadmap_id", "pos_y", "pos_x"],
+        used_by: "roadmap::get_nodes_with_progress",
+    },
+    ExpectedIndex {
+        name: "idx_flashcards_lookup",
+        table: "flashcards",
+        columns: &["language_from", "language_to", "term"],
+        used_by: "flashcard lookup by language pair and term",
+    },
+    ExpectedIndex {
+        name: "idx_df_deck",
+        table: "deck_flashcards",
+        columns: &["deck_id"],
+        used_by: "practice::get_practice_cards",
+    },
+    ExpectedIndex {
+        name: "idx_practice_session",
+        table: "user_card_progress",
+        columns: &["user_id", "flashcard_id", "next_review_at"],
+        used_by: "practice::get_practice_cards",
+    },
+    ExpectedIndex {
+        name: "idx_progress_user_mastered",
+        table: "user_card_progress",
+        columns: &["user_id"],
+        used_by: "practice::refresh_deck_progress",
+    },
+    ExpectedIndex {
+        name: "idx_progress_user_due",
+        table: "user_card_progress",
+        columns: &["user_id", "next_review_at"],
+        used_by: "practice::due_forecast",
+    },
+    ExpectedIndex {
+        name: "idx_udp_user",
+        table: "user_deck_progress",
+        columns: &["user_id"],
+        used_by: "roadmap::get_nodes_with_progress",
+    },
+    ExpectedIndex {
+        name: "idx_activity_user_date",
+        table: "user_activity",
+        columns: &["user_id", "activity_date"],
+        used_by: "practice::update_streak",
+    },
+    ExpectedIndex {
+        name: "idx_refresh_tokens_user",
+        table: "refresh_tokens",
+        columns: &["user_id"],
+        used_by: "auth::delete_all_user_refresh_tokens",
+    },
+    ExpectedIndex {
+        name: "idx_refresh_tokens_expires_at",
+        table: "refresh_tokens",
+        columns: &["expires_at"],
+        used_by: "auth::cleanup_expired_refresh_tokens",
+    },
+    ExpectedIndex {
+        name: "idx_one_time_tokens_user_purpose",
+        table: "one_time_tokens",
+        columns: &["user_id", "purpose"],
+        used_by: "token::invalidate_tokens",
+    },
+    ExpectedIndex {
+        name: "idx_one_time_tokens_expires_at",
+        table: "one_time_tokens",
+        columns: &["expires_at"],
+        used_by: "cleanup_expired_one_time_tokens()",
+    },
+    ExpectedIndex {
+        name: "idx_organization_members_user",
+        table: "organization_members",
+        columns: &["user_id"],
+        used_by: "org_visible()",
+    },
+    ExpectedIndex {
+        name: "idx_deck_comments_deck_created",
+        table: "deck_comments",
+        columns: &["deck_id", "created_at"],
+        used_by: "deck_review::list_comments",
+    },
+    ExpectedIndex {
+        name: "idx_review_logs_user_reviewed",
+        table: "review_logs",
+        columns: &["user_id", "reviewed_at"],
+        used_by: "review_log::daily_counts",
+    },
+];
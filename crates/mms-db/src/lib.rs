@@ -1,19 +1,212 @@
+pub mod encryption;
+pub mod indexes;
 pub mod models;
 pub mod repositories;
+pub mod tenancy;
 
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::Context;
-use sqlx::{PgPool, Postgres, migrate::MigrateDatabase, postgres::PgPoolOptions};
+use sqlx::{
+    PgPool, Postgres,
+    migrate::MigrateDatabase,
+    postgres::{PgConnectOptions, PgPoolOptions},
+};
 
-/// Create a PostgreSQL connection pool.
+/// How the connection pools are sized and how long their connections may wait
+#[derive(Clone, Debug)]
+pub struct PoolSettings {
+    pub max_connections: u32,
+    /// Connections kept open while idle
+    pub min_connections: u32,
+    /// Longest a query waits for a connection before failing
+    pub acquire_timeout: Duration,
+    /// Idle connections above `min_connections` are closed after this long; `None` keeps them
+    pub idle_timeout: Option<Duration>,
+    /// Statements running longer are cancelled by Postgres; `None` keeps the server's setting
+    pub statement_timeout: Option<Duration>,
+    /// Shown in `pg_stat_activity` and the Postgres logs
+    pub application_name: String,
+    /// How long to keep trying to reach the database when the pool is created
+    pub connect_timeout: Duration,
+}
+
+impl Default for PoolSettings {
+    fn default() -> Self {
+        Self {
+            max_connections: 10,
+            min_connections: 1,
+            acquire_timeout: Duration::from_secs(5),
+            idle_timeout: Some(Duration::from_secs(600)),
+            statement_timeout: None,
+            application_name: "mms-api".to_string(),
+            connect_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+impl PoolSettings {
+    fn pool_options(&self) -> PgPoolOptions {
+        PgPoolOptions::new()
+            .max_connections(self.max_connections)
+            .min_connections(self.min_connections)
+            .acquire_timeout(self.acquire_timeout)
+            .idle_timeout(self.idle_timeout)
+            .max_lifetime(Duration::from_secs(1800))
+    }
+
+    fn connect_options(&self, database_url: &str) -> anyhow::Result<PgConnectOptions> {
+        let mut options = database_url
+            .parse::<PgConnectOptions>()
+            .context("invalid database URL")?
+            .application_name(&self.application_name);
+        if let Some(timeout) = self.statement_timeout {
+            options = options.options([("statement_timeout", timeout.as_millis().to_string())]);
+        }
+        Ok(options)
+    }
+}
+
+/// Create a PostgreSQL connection pool with the default settings and `max_connections`.
 pub async fn create_pool(database_url: &str, max_connections: u32) -> anyhow::Result<PgPool> {
+    let settings = PoolSettings {
+        max_connections,
+        ..PoolSettings::default()
+    };
+    create_pool_with(database_url, &settings).await
+}
+
+/// Create a PostgreSQL connection pool.
+///
+/// Fails when no connection could be opened within `settings.connect_timeout`,
+/// so an instance that cannot reach its database never reports ready.
+pub async fn create_pool_with(
+    database_url: &str,
+    settings: &PoolSettings,
+) -> anyhow::Result<PgPool> {
+    let options = settings.connect_options(database_url)?;
+    connect(settings, options).await
+}
+
+async fn connect(settings: &PoolSettings, options: PgConnectOptions) -> anyhow::Result<PgPool> {
+    let pool = settings.pool_options().connect_lazy_with(options);
+    let deadline = Instant::now() + settings.connect_timeout;
+
+    loop {
+        match pool.acquire().await {
+            Ok(_) => return Ok(pool),
+            // Each acquire retries refused connections until the acquire timeout
+            Err(sqlx::Error::PoolTimedOut) if Instant::now() < deadline => continue,
+            Err(e) => {
+                pool.close().await;
+                return Err(e).with_context(|| {
+                    format!(
+                        "failed to connect to database within {}s",
+                        settings.connect_timeout.as_secs()
+                    )
+                });
+            }
+        }
+    }
+}
+
+/// A primary pool for writes and an optional read replica.
+///
+/// Queries that only read and can tolerate replication lag go to
+/// [`PoolPair::reader`]; everything else, including reads that must see a
+/// write just made, stays on [`PoolPair::primary`].
+#[derive(Clone, Debug)]
+pub struct PoolPair {
+    primary: PgPool,
+    replica: Option<PgPool>,
+}
+
+impl PoolPair {
+    #[must_use]
+    pub fn new(primary: PgPool, replica: Option<PgPool>) -> Self {
+        Self { primary, replica }
+    }
+
+    /// Pool for writes and for reads that must see the latest writes
+    #[must_use]
+    pub fn primary(&self) -> &PgPool {
+        &self.primary
+    }
+
+    /// The replica, if one is configured
+    #[must_use]
+    pub fn replica(&self) -> Option<&PgPool> {
+        self.replica.as_ref()
+    }
+
+    /// Pool for read-only queries: the replica, falling back to the primary when there is none
+    #[must_use]
+    pub fn reader(&self) -> &PgPool {
+        self.replica.as_ref().unwrap_or(&self.primary)
+    }
+}
+
+/// Create the primary pool and, when `replica_url` is set, a pool on the read replica.
+///
+/// Both pools use `settings`. Replica connections open every transaction
+/// read-only, so a write routed there by mistake fails even against a
+/// database that would accept it.
+pub async fn create_pool_pair(
+    primary_url: &str,
+    replica_url: Option<&str>,
+    settings: &PoolSettings,
+) -> anyhow::Result<PoolPair> {
+    let primary = create_pool_with(primary_url, settings).await?;
+
+    let replica = match replica_url {
+        Some(url) => {
+            let options = settings
+                .connect_options(url)
+                .context("invalid replica database URL")?
+                .options([("default_transaction_read_only", "on")]);
+            let pool = connect(settings, options)
+                .await
+                .context("failed to connect to replica database")?;
+            Some(pool)
+        }
+        None => None,
+    };
+
+    Ok(PoolPair::new(primary, replica))
+}
+
+/// Create a pool whose connections work in `schema`, creating the schema first.
+///
+/// `public` stays on the search path so extensions installed there still
+/// resolve. Used for throwaway schemas, e.g. by the smoke test.
+pub async fn create_schema_pool(
+    database_url: &str,
+    max_connections: u32,
+    schema: &str,
+) -> anyhow::Result<PgPool> {
+    anyhow::ensure!(
+        is_plain_identifier(schema),
+        "schema name must be lowercase letters, digits and underscores: {schema}"
+    );
+
+    let admin = create_pool(database_url, 1).await?;
+    sqlx::query(&format!("CREATE SCHEMA IF NOT EXISTS {schema}"))
+        .execute(&admin)
+        .await
+        .with_context(|| format!("failed to create schema {schema}"))?;
+    admin.close().await;
+
+    let search_path = format!("SET search_path TO {schema}, public");
     let pool = PgPoolOptions::new()
         .max_connections(max_connections)
-        .min_connections(1)
         .acquire_timeout(Duration::from_secs(5))
-        .idle_timeout(Duration::from_secs(600))
-        .max_lifetime(Duration::from_secs(1800))
+        .after_connect(move |conn, _meta| {
+            let search_path = search_path.clone();
+            Box::pin(async move {
+                sqlx::query(&search_path).execute(conn).await?;
+                Ok(())
+            })
+        })
         .connect(database_url)
         .await
         .context("failed to connect to database")?;
@@ -21,6 +214,30 @@ pub async fn create_pool(database_url: &str, max_connections: u32) -> anyhow::Re
     Ok(pool)
 }
 
+/// Drop `schema` and everything in it
+pub async fn drop_schema(pool: &PgPool, schema: &str) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        is_plain_identifier(schema),
+        "schema name must be lowercase letters, digits and underscores: {schema}"
+    );
+
+    sqlx::query(&format!("DROP SCHEMA IF EXISTS {schema} CASCADE"))
+        .execute(pool)
+        .await
+        .with_context(|| format!("failed to drop schema {schema}"))?;
+
+    Ok(())
+}
+
+/// Whether `name` can be spliced into SQL as an identifier without quoting
+fn is_plain_identifier(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
 /// Ensure the database exists and run migrations in this crate's `migrations/` folder.
 ///
 /// When `create_if_missing` is true, the database will be created automatically if it
@@ -43,3 +260,22 @@ pub async fn ensure_db_and_migrate(
 
     Ok(())
 }
+
+/// Versions of the migrations bundled with this build that the database has not applied.
+///
+/// Migrations the database has but this build does not know, e.g. while an
+/// older build is still running after a deploy, are not reported.
+pub async fn pending_migrations(pool: &PgPool) -> Result<Vec<i64>, sqlx::Error> {
+    // language=PostgreSQL
+    let applied: Vec<i64> =
+        sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success")
+            .fetch_all(pool)
+            .await?;
+
+    Ok(sqlx::migrate!()
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .map(|migration| migration.version)
+        .filter(|version| !applied.contains(version))
+        .collect())
+}
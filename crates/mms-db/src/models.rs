@@ -1,7 +1,10 @@
 use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
+pub use crate::encryption::EncryptedString;
+
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct User {
     pub id: Uuid,
@@ -12,25 +15,27 @@ pub struct User {
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, ToSchema, Deserialize, sqlx::FromRow)]
 pub struct Roadmap {
     pub id: Uuid,
+    pub slug: String,
     pub title: String,
     pub description: Option<String>,
     pub language_from: String,
     pub language_to: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Deck {
     pub id: Uuid,
+    pub slug: String,
     pub title: String,
     pub description: Option<String>,
     pub language_from: String,
     pub language_to: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Serialize, ToSchema, Deserialize, sqlx::FromRow)]
 pub struct Flashcard {
     pub id: Uuid,
     pub term: String,
@@ -39,7 +44,33 @@ pub struct Flashcard {
     pub language_to: String,
 }
 
-#[derive(Debug, Serialize, sqlx::FromRow)]
+/// A flashcard with every answer graded as correct
+#[derive(Debug, Clone, Serialize, ToSchema, sqlx::FromRow)]
+pub struct FlashcardWithAnswers {
+    pub id: Uuid,
+    pub term: String,
+    pub translation: String,
+    pub language_from: String,
+    pub language_to: String,
+    /// Answers graded as correct besides the translation
+    pub accepted_answers: Vec<String>,
+}
+
+/// One edit of a flashcard's term or translation
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct FlashcardEdit {
+    pub id: Uuid,
+    pub old_term: String,
+    pub old_translation: String,
+    pub new_term: String,
+    pub new_translation: String,
+    /// Who made the edit; null for the admin API token or a deleted account
+    pub edited_by: Option<Uuid>,
+    pub editor_username: Option<String>,
+    pub edited_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
 pub struct RoadmapNodeWithProgress {
     pub node_id: Uuid,
     pub parent_node_id: Option<Uuid>,
@@ -57,15 +88,42 @@ pub struct RoadmapNodeWithProgress {
     pub next_practice_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Serialize)]
+/// A deck's place on a roadmap
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct RoadmapNode {
+    pub id: Uuid,
+    pub roadmap_id: Uuid,
+    pub parent_node_id: Option<Uuid>,
+    pub pos_x: i32,
+    pub pos_y: i32,
+    pub deck_id: Uuid,
+}
+
+/// A user's progress on one deck
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DeckProgress {
+    pub deck_id: Uuid,
+    pub total_cards: i32,
+    pub mastered_cards: i32,
+    /// Cards due now, new cards included
+    pub cards_due: i32,
+    pub total_practices: i32,
+    pub last_practiced_at: Option<DateTime<Utc>>,
+    pub progress_percentage: f64,
+    /// When the next card falls due; `None` while cards are due
+    pub next_practice_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct RoadmapWithProgress {
     pub roadmap: RoadmapMetadata,
     pub nodes: Vec<RoadmapNodeWithProgress>,
 }
 
-#[derive(Debug, Serialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, ToSchema, sqlx::FromRow)]
 pub struct RoadmapMetadata {
     pub id: Uuid,
+    pub slug: String,
     pub title: String,
     pub description: Option<String>,
     pub language_from: String,
@@ -87,7 +145,21 @@ pub struct FlashcardWithProgress {
     pub mastered_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Serialize, sqlx::FromRow)]
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct CardProgressExport {
+    pub flashcard_id: Uuid,
+    pub term: String,
+    pub translation: String,
+    pub language_from: String,
+    pub language_to: String,
+    pub next_review_at: DateTime<Utc>,
+    pub last_review_at: Option<DateTime<Utc>>,
+    pub times_correct: i32,
+    pub times_wrong: i32,
+    pub mastered_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
 pub struct UserStats {
     pub current_streak_days: i32,
     pub longest_streak_days: i32,
@@ -96,12 +168,57 @@ pub struct UserStats {
     pub last_review_date: Option<NaiveDate>,
 }
 
-#[derive(Debug, Serialize, sqlx::FromRow)]
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
 pub struct ActivityDay {
     pub activity_date: NaiveDate,
     pub reviews_count: i32,
 }
 
+/// Activity summed over a day, week or month
+#[derive(Debug, sqlx::FromRow)]
+pub struct ActivityPeriod {
+    /// First day of the period: the day, the week's Monday or the month's 1st
+    pub period_start: NaiveDate,
+    pub reviews: i64,
+    pub correct: i64,
+    pub study_ms: i64,
+    pub new_cards: i64,
+}
+
+/// Cards of one language pair coming due on a day
+#[derive(Debug, sqlx::FromRow)]
+pub struct DueReviewDay {
+    pub due_date: NaiveDate,
+    pub language_from: String,
+    pub language_to: String,
+    pub cards: i64,
+}
+
+/// Counts shown on the home screen, read together
+#[derive(Debug, sqlx::FromRow)]
+pub struct HomeCounts {
+    /// Reviewed cards due now
+    pub due_now: i64,
+    /// Reviewed cards due before the end of the user's local day, including `due_now`
+    pub due_today: i64,
+    pub unread_notifications: i64,
+}
+
+/// Reviewed cards due now in one learning profile
+#[derive(Debug, sqlx::FromRow)]
+pub struct ProfileDueCount {
+    pub profile_id: Uuid,
+    pub due_now: i64,
+}
+
+/// Reviewed cards due now in one deck
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct DeckDueCount {
+    pub deck_id: Uuid,
+    pub title: String,
+    pub due_now: i64,
+}
+
 // --- Query-specific structs (replacing tuple queries) ---
 
 #[derive(Debug, Serialize, sqlx::FromRow)]
@@ -112,6 +229,7 @@ pub struct UserProfile {
     pub profile_picture_url: Option<String>,
     pub native_language: Option<String>,
     pub learning_language: Option<String>,
+    pub role: String,
 }
 
 #[derive(Debug, sqlx::FromRow)]
@@ -123,6 +241,7 @@ pub struct UserWithGoogleId {
     pub profile_picture_url: Option<String>,
     pub native_language: Option<String>,
     pub learning_language: Option<String>,
+    pub role: String,
 }
 
 #[derive(Debug, sqlx::FromRow)]
@@ -135,6 +254,9 @@ pub struct UserCredentials {
     pub email_verified: bool,
     pub native_language: Option<String>,
     pub learning_language: Option<String>,
+    pub role: String,
+    /// When the user deleted the account; it cannot sign in until restored
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, sqlx::FromRow)]
@@ -143,6 +265,31 @@ pub struct UserPasswordInfo {
     pub username: String,
     pub password_hash: Option<String>,
     pub auth_provider: String,
+    pub email_verified: bool,
+}
+
+/// The ways a user can sign in
+#[derive(Debug, sqlx::FromRow)]
+pub struct SignInMethods {
+    pub has_password: bool,
+    pub google_id: Option<String>,
+}
+
+/// Whether a username can be given to an account
+#[derive(Debug, sqlx::FromRow)]
+pub struct UsernameStatus {
+    /// An account, possibly a deleted one, has the username
+    pub taken: bool,
+    /// Another account gave the username up recently
+    pub reserved: bool,
+}
+
+/// A recorded username change
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct UsernameChange {
+    pub old_username: String,
+    pub new_username: String,
+    pub changed_at: DateTime<Utc>,
 }
 
 #[derive(Debug, sqlx::FromRow)]
@@ -174,6 +321,7 @@ pub struct UserEmailAndName {
 pub struct EmailVerifiedStatus {
     pub email: String,
     pub email_verified: bool,
+    pub role: String,
 }
 
 #[derive(Debug, sqlx::FromRow)]
@@ -181,22 +329,1074 @@ pub struct RefreshTokenRecord {
     pub id: Uuid,
     pub user_id: Uuid,
     pub expires_at: DateTime<Utc>,
-    pub device_info: Option<String>,
-    pub ip_address: Option<String>,
+    /// Sign-in that started the session, carried over on rotation
+    pub session_started_at: DateTime<Utc>,
+    pub device_info: Option<EncryptedString>,
+    pub ip_address: Option<EncryptedString>,
 }
 
 #[derive(Debug, sqlx::FromRow)]
 pub struct CardProgress {
     pub next_review_at: DateTime<Utc>,
+    pub last_review_at: Option<DateTime<Utc>>,
     pub times_correct: i32,
     pub times_wrong: i32,
 }
 
-#[derive(Debug, Serialize, sqlx::FromRow)]
+/// The progress a review leaves a card with
+#[derive(Debug, Clone, Copy)]
+pub struct ReviewedProgress {
+    pub next_review_at: DateTime<Utc>,
+    pub times_correct: i32,
+    pub times_wrong: i32,
+    pub mastered: bool,
+    /// When the answer was given
+    pub reviewed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
 pub struct PracticeCard {
     pub id: Uuid,
     pub term: String,
     pub translation: String,
     pub times_correct: i32,
     pub times_wrong: i32,
+    /// Global difficulty (0 = easy, 1 = hard); `None` until enough users reviewed the card
+    pub difficulty: Option<f32>,
+    /// How to practise the card: `translation`, `listening` (play the
+    /// translation's audio only) or `pronunciation`
+    pub mode: String,
+}
+
+/// A reviewed card that is due, with one of the decks it can be practised in
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct DueCard {
+    /// Deck to submit the review against
+    pub deck_id: Uuid,
+    pub id: Uuid,
+    pub term: String,
+    pub translation: String,
+    pub times_correct: i32,
+    pub times_wrong: i32,
+    pub difficulty: Option<f32>,
+    pub next_review_at: DateTime<Utc>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct ReviewFlashcard {
+    pub translation: String,
+    /// Other answers graded as correct
+    pub accepted_answers: Vec<String>,
+    pub difficulty: Option<f32>,
+}
+
+/// Tokens removed by one pass of `cleanup_all_expired_tokens()`
+#[derive(Debug, sqlx::FromRow)]
+pub struct ExpiredTokenCleanup {
+    pub password_reset_cleaned: i32,
+    pub email_verification_cleaned: i32,
+    pub refresh_tokens_cleaned: i32,
+    /// Includes one-time tokens of other purposes
+    pub total_cleaned: i32,
+}
+
+// --- Learning profiles ---
+
+/// One language pair a user studies, with its own settings
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct LearningProfile {
+    pub id: Uuid,
+    pub native_language: String,
+    pub learning_language: String,
+    /// Cards per practice session when the request does not set a limit
+    pub session_size: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+// --- Review reminders ---
+
+/// When the user wants a daily reminder of due cards, and how to warn them of a streak at risk
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct ReminderSettings {
+    pub enabled: bool,
+    /// Local hour, 0 to 23
+    pub hour: i16,
+    /// IANA timezone name, e.g. `Europe/Madrid`
+    pub timezone: String,
+    /// Email when the streak is about to end
+    pub streak_email: bool,
+    /// In-app notification when the streak is about to end
+    pub streak_in_app: bool,
+}
+
+/// A user claimed for today's reminder
+#[derive(Debug, sqlx::FromRow)]
+pub struct ReminderRecipient {
+    pub user_id: Uuid,
+    pub email: String,
+    pub username: String,
+    pub cards_due: i64,
+}
+
+/// A user claimed for today's streak-at-risk reminder
+#[derive(Debug, sqlx::FromRow)]
+pub struct StreakAtRisk {
+    pub user_id: Uuid,
+    pub email: String,
+    pub username: String,
+    pub streak_days: i32,
+    /// Whether to email them: opted in, verified and email available
+    pub send_email: bool,
+    pub send_in_app: bool,
+}
+
+/// A user claimed for this week's digest, with their numbers for the past week
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WeeklyDigest {
+    pub user_id: Uuid,
+    pub email: String,
+    pub username: String,
+    /// First day of the summarised week, in the user's timezone
+    pub week_start: NaiveDate,
+    pub reviews: i64,
+    /// Correct answers among `graded_reviews`
+    pub correct_reviews: i64,
+    /// Reviews on days where correct answers were counted
+    pub graded_reviews: i64,
+    /// Cards mastered during the week
+    pub cards_learned: i64,
+    pub streak_days: i32,
+}
+
+/// Which emails the user receives
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct EmailPreferences {
+    /// Weekly progress digest
+    pub weekly_digest: bool,
+    /// Daily review reminder
+    pub review_reminders: bool,
+    /// Email when the streak is about to end
+    pub streak_reminders: bool,
+}
+
+// --- Email suppressions ---
+
+/// An address the email provider reported as undeliverable; nothing is sent to it
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct EmailSuppression {
+    pub email: String,
+    /// `bounce` or `complaint`
+    pub reason: String,
+    /// Provider whose webhook reported it
+    pub provider: String,
+    /// Provider's explanation, e.g. the SMTP diagnostic
+    pub detail: Option<String>,
+    /// Reports received for the address
+    pub events: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Account details support looks at to help a user
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct AdminUserSummary {
+    pub id: Uuid,
+    pub username: String,
+    pub email: String,
+    pub role: String,
+    pub auth_provider: String,
+    pub email_verified: bool,
+    pub created_at: Option<DateTime<Utc>>,
+    /// When the user deleted the account; it is purged once the grace period ends
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+// --- Email outbox ---
+
+/// An email claimed from the outbox for another attempt
+#[derive(Debug, sqlx::FromRow)]
+pub struct OutboxEmail {
+    pub id: Uuid,
+    pub to_email: EncryptedString,
+    pub subject: String,
+    pub body: EncryptedString,
+    /// Attempts made before this one
+    pub attempts: i32,
+}
+
+// --- Client error reports ---
+
+/// A crash report from a client, as stored
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct ClientError {
+    pub id: Uuid,
+    /// X-Request-ID of the API response the client was handling
+    pub request_id: Option<String>,
+    pub user_id: Option<Uuid>,
+    /// `web`, `ios` or `android`
+    pub platform: String,
+    pub app_version: Option<String>,
+    pub message: String,
+    pub stack: Option<String>,
+    /// Page or screen the error happened on
+    pub page: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A crash report to store
+#[derive(Debug)]
+pub struct NewClientError<'a> {
+    pub request_id: Option<&'a str>,
+    pub user_id: Option<Uuid>,
+    pub platform: &'a str,
+    pub app_version: Option<&'a str>,
+    pub message: &'a str,
+    pub stack: Option<&'a str>,
+    pub page: Option<&'a str>,
+    pub user_agent: Option<&'a str>,
+}
+
+// --- Notifications ---
+
+/// A message shown in the user's notification list
+#[derive(Debug, Clone, Serialize, ToSchema, sqlx::FromRow)]
+pub struct Notification {
+    pub id: Uuid,
+    /// `streak_reminder`, `deck_shared` or `achievement_unlocked`
+    pub kind: String,
+    pub title: String,
+    pub body: String,
+    /// Client route to open, e.g. `/decks/{id}`
+    pub link: Option<String>,
+    pub read_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+// --- Achievements ---
+
+/// An achievement with the user's progress towards it
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct UserAchievement {
+    pub code: String,
+    pub title: String,
+    pub description: String,
+    /// `reviews`, `streak_days` or `decks_mastered`
+    pub metric: String,
+    pub threshold: i32,
+    /// The user's current value of `metric`
+    pub progress: i32,
+    pub unlocked_at: Option<DateTime<Utc>>,
+}
+
+/// An achievement a user just unlocked
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct UnlockedAchievement {
+    pub code: String,
+    pub title: String,
+    pub description: String,
+}
+
+// --- Experience points ---
+
+/// XP a user earned on one local day, by source
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct XpDay {
+    pub activity_date: NaiveDate,
+    /// Base XP for correct answers
+    pub correct_xp: i32,
+    /// XP for wrong answers
+    pub wrong_xp: i32,
+    /// Extra XP for correct answers on hard cards
+    pub difficulty_bonus_xp: i32,
+}
+
+// --- Leaderboards ---
+
+/// A user's place on a leaderboard
+#[derive(Debug, Clone, Serialize, ToSchema, sqlx::FromRow)]
+pub struct LeaderboardEntry {
+    /// 1-based; users with the same XP share a rank
+    pub rank: i64,
+    pub user_id: Uuid,
+    pub username: String,
+    pub profile_picture_url: Option<String>,
+    /// XP earned in the period
+    pub xp: i64,
+    pub current_streak_days: i32,
+}
+
+/// A user someone added as a friend
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct Friend {
+    pub user_id: Uuid,
+    pub username: String,
+    pub profile_picture_url: Option<String>,
+    pub added_at: DateTime<Utc>,
+}
+
+// --- Deck ratings and comments ---
+
+/// A deck in the public catalogue with its rating
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct PublicDeck {
+    pub id: Uuid,
+    pub slug: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub language_from: String,
+    pub language_to: String,
+    pub cefr_level: Option<String>,
+    /// Mean of the ratings, absent until the deck is rated
+    pub rating_average: Option<f64>,
+    pub rating_count: i32,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// A deck in the public API's catalogue
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct CatalogDeck {
+    pub id: Uuid,
+    pub slug: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub language_from: String,
+    pub language_to: String,
+    pub cefr_level: Option<String>,
+    /// Cards in the deck, not counting hidden ones
+    pub card_count: i64,
+    /// Mean of the ratings, absent until the deck is rated
+    pub rating_average: Option<f64>,
+    pub rating_count: i32,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// A roadmap in the public API's catalogue
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct CatalogRoadmap {
+    pub id: Uuid,
+    pub slug: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub language_from: String,
+    pub language_to: String,
+    /// Public decks on the roadmap
+    pub deck_count: i64,
+    /// Distinct cards in those decks, not counting hidden ones
+    pub card_count: i64,
+}
+
+/// A deck's rating as seen by one user
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct DeckRating {
+    pub rating_average: Option<f64>,
+    pub rating_count: i32,
+    /// The user's own rating, if they gave one
+    pub my_stars: Option<i16>,
+}
+
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct DeckComment {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub username: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    /// Later than `created_at` once the comment was edited
+    pub updated_at: DateTime<Utc>,
+}
+
+// --- Content reports ---
+
+/// A report as seen by the user who filed it
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct ContentReport {
+    pub id: Uuid,
+    /// `deck`, `card` or `comment`
+    pub target_type: String,
+    pub target_id: Uuid,
+    /// `spam`, `offensive`, `incorrect`, `copyright` or `other`
+    pub reason: String,
+    pub details: Option<String>,
+    /// `open`, `resolved` or `dismissed`
+    pub status: String,
+    /// What the moderator did: `hide`, `delete` or `warn`
+    pub action: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+/// A report in the moderation queue
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct ModerationReport {
+    pub id: Uuid,
+    pub target_type: String,
+    pub target_id: Uuid,
+    /// Deck title, card term or comment text; absent once the content is deleted
+    pub target_preview: Option<String>,
+    /// Open reports on the same target, this one included
+    pub open_reports: i64,
+    pub reason: String,
+    pub details: Option<String>,
+    /// Absent once the reporter deleted their account
+    pub reporter_username: Option<String>,
+    pub status: String,
+    pub action: Option<String>,
+    pub resolved_by: Option<Uuid>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub resolution_note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What a report is about, for acting on it
+#[derive(Debug, sqlx::FromRow)]
+pub struct ReportTarget {
+    pub target_type: String,
+    pub target_id: Uuid,
+    pub status: String,
+}
+
+// --- Study groups ---
+
+/// A study group the user belongs to
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct GroupSummary {
+    pub id: Uuid,
+    pub name: String,
+    /// The user's role: `owner`, `teacher` or `member`
+    pub role: String,
+    pub member_count: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A study group as stored
+#[derive(Debug, sqlx::FromRow)]
+pub struct Group {
+    pub id: Uuid,
+    pub name: String,
+    pub invite_code: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct GroupMember {
+    pub user_id: Uuid,
+    pub username: String,
+    /// `owner`, `teacher` or `member`
+    pub role: String,
+    pub joined_at: DateTime<Utc>,
+}
+
+/// A deck or roadmap a group's teachers assigned
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct GroupAssignment {
+    /// Set for deck assignments
+    pub deck_id: Option<Uuid>,
+    /// Set for roadmap assignments
+    pub roadmap_id: Option<Uuid>,
+    /// Title of the deck or roadmap
+    pub title: String,
+    pub assigned_at: DateTime<Utc>,
+}
+
+/// One learner's progress on one assigned deck
+#[derive(Debug, sqlx::FromRow)]
+pub struct MemberDeckProgress {
+    pub user_id: Uuid,
+    pub username: String,
+    pub deck_id: Uuid,
+    pub deck_title: String,
+    pub total_cards: i32,
+    pub mastered_cards: i32,
+    pub progress_percentage: f64,
+    pub last_practiced_at: Option<DateTime<Utc>>,
+}
+
+// --- Study plans ---
+
+/// A user's target of finishing a roadmap by a date
+#[derive(Debug, sqlx::FromRow)]
+pub struct StudyPlan {
+    pub user_id: Uuid,
+    pub roadmap_id: Uuid,
+    pub target_date: NaiveDate,
+    pub baseline_daily_new_cards: i32,
+    pub behind_notified_on: Option<NaiveDate>,
+}
+
+/// Where a user stands on a roadmap, for computing their study pace
+#[derive(Debug, sqlx::FromRow)]
+pub struct RoadmapPlanCounts {
+    pub roadmap_title: String,
+    /// The user's local date
+    pub today: NaiveDate,
+    pub total_cards: i64,
+    /// Cards of the roadmap the user has not started
+    pub remaining_cards: i64,
+    /// The user's cards, in any roadmap, due within the next 7 local days
+    pub reviews_due_next_week: i64,
+}
+
+// --- Review forecasts ---
+
+/// A user's forecast counts with their current local date, either precomputed
+/// or counted on request
+#[derive(Debug, sqlx::FromRow)]
+pub struct StoredForecast {
+    /// Local date of `due_counts[0]`, which also holds overdue cards
+    pub starts_on: NaiveDate,
+    /// The user's local date now
+    pub today: NaiveDate,
+    /// Cards due on each day from `starts_on`
+    pub due_counts: Vec<i32>,
+    pub computed_at: DateTime<Utc>,
+}
+
+/// Where a user stands with a card, as a workload simulation starts from it;
+/// both are None for a card the user has not studied
+#[derive(Debug, sqlx::FromRow)]
+pub struct CardState {
+    /// times_correct - times_wrong
+    pub score: Option<i32>,
+    /// Local days until the card is due, 0 when due today or overdue
+    pub due_in_days: Option<i32>,
+}
+
+// --- Widgets ---
+
+/// Public stats shown on a user's embeddable widgets
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct WidgetStats {
+    pub username: String,
+    /// `users.widget_token_version`, to check the widget token against
+    pub widget_token_version: i32,
+    pub current_streak_days: i32,
+    pub longest_streak_days: i32,
+    pub total_cards_learned: i32,
+}
+
+// --- Vocabulary search ---
+
+/// A card the user has progress on, as scanned by vocabulary search
+#[derive(Debug, sqlx::FromRow)]
+pub struct StudiedCard {
+    pub flashcard_id: Uuid,
+    pub term: String,
+    pub translation: String,
+    pub language_from: String,
+    pub language_to: String,
+    pub times_correct: i32,
+    pub times_wrong: i32,
+    pub next_review_at: DateTime<Utc>,
+    pub last_review_at: Option<DateTime<Utc>>,
+}
+
+// --- Delta sync ---
+
+/// A deck as sent to offline clients, with the ids of its cards
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct SyncDeck {
+    pub id: Uuid,
+    pub title: String,
+    pub description: Option<String>,
+    pub language_from: String,
+    pub language_to: String,
+    pub flashcard_ids: Vec<Uuid>,
+    pub change_seq: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct SyncCard {
+    pub id: Uuid,
+    pub term: String,
+    pub translation: String,
+    pub language_from: String,
+    pub language_to: String,
+    pub change_seq: i64,
+}
+
+/// The user's scheduling state for one card
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct SyncProgress {
+    pub flashcard_id: Uuid,
+    pub next_review_at: DateTime<Utc>,
+    pub last_review_at: Option<DateTime<Utc>>,
+    pub times_correct: i32,
+    pub times_wrong: i32,
+    pub mastered_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub change_seq: i64,
+}
+
+/// Scheduling state of a card as reported by a client
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ProgressState {
+    pub next_review_at: DateTime<Utc>,
+    pub last_review_at: Option<DateTime<Utc>>,
+    pub times_correct: i32,
+    pub times_wrong: i32,
+}
+
+/// A deleted deck, card or progress row
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct SyncTombstone {
+    /// `deck`, `card` or `progress` (whose id is the flashcard id)
+    pub entity: String,
+    pub id: Uuid,
+    pub change_seq: i64,
+}
+
+/// Where a page of changes ends
+#[derive(Debug, sqlx::FromRow)]
+pub struct SyncPageBounds {
+    /// Highest change in the page; `None` if nothing changed
+    pub last_seq: Option<i64>,
+    /// More changes follow the page
+    pub has_more: bool,
+}
+
+// --- Flashcard difficulty ---
+
+/// Review totals for one card across all users
+#[derive(Debug, sqlx::FromRow)]
+pub struct CardReviewTotals {
+    pub flashcard_id: Uuid,
+    pub reviews: i64,
+    pub failures: i64,
+    pub learners: i64,
+    pub total_response_ms: i64,
+    pub timed_reviews: i64,
+}
+
+// --- Index advisor ---
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct IndexStats {
+    pub table_name: String,
+    pub index_name: String,
+    /// Key columns in index order (expression columns are omitted)
+    pub columns: Vec<String>,
+    pub is_unique: bool,
+    pub is_primary: bool,
+    /// Partial indexes only cover rows matching their `WHERE` clause
+    pub is_partial: bool,
+    pub scans: i64,
+    pub size_bytes: i64,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct TableScanStats {
+    pub table_name: String,
+    pub seq_scans: i64,
+    pub seq_rows_read: i64,
+    pub index_scans: i64,
+    pub live_rows: i64,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct StatementStats {
+    pub query: String,
+    pub calls: i64,
+    pub total_exec_ms: f64,
+    pub mean_exec_ms: f64,
+    pub rows: i64,
+    pub shared_blocks_hit: i64,
+    pub shared_blocks_read: i64,
+}
+
+// --- Interval statistics ---
+
+/// Reviewed cards whose interval falls in one bucket
+#[derive(Debug, sqlx::FromRow)]
+pub struct IntervalBucketCount {
+    pub bucket: i32,
+    pub cards: i64,
+}
+
+/// One bucket of a stored weekly interval snapshot
+#[derive(Debug, sqlx::FromRow)]
+pub struct IntervalSnapshot {
+    pub week_start: NaiveDate,
+    pub bucket: i16,
+    pub cards: i32,
+}
+
+/// New, learning and due cards of one deck, or of all the user's decks when
+/// `deck_id` is `None`
+#[derive(Debug, sqlx::FromRow)]
+pub struct DeckCardCounts {
+    pub deck_id: Option<Uuid>,
+    pub title: Option<String>,
+    pub new_cards: i64,
+    pub learning: i64,
+    pub due: i64,
+}
+
+// --- Review log ---
+
+/// A graded answer to append to the review log
+#[derive(Debug)]
+pub struct NewReviewLog {
+    pub user_id: Uuid,
+    pub flashcard_id: Uuid,
+    pub deck_id: Uuid,
+    pub is_correct: bool,
+    /// Interval the card was reviewed at, None on its first review
+    pub previous_interval_days: Option<f64>,
+    /// Interval the review scheduled
+    pub interval_days: f64,
+    pub response_time_ms: Option<i32>,
+    /// Time spent on the card, feedback included
+    pub elapsed_ms: Option<i32>,
+    pub flagged: bool,
+    /// When the answer was given; earlier than now for reviews made offline
+    pub reviewed_at: DateTime<Utc>,
+    /// How the card was practised, e.g. `translation` or `pronunciation`
+    pub mode: &'static str,
+}
+
+/// Reviews and correct answers on one local day
+#[derive(Debug, sqlx::FromRow)]
+pub struct DailyReviewCounts {
+    pub date: NaiveDate,
+    pub reviews: i64,
+    pub correct: i64,
+}
+
+/// Reviews and correct answers in one weekday or hour of the day
+#[derive(Debug, sqlx::FromRow)]
+pub struct SlotReviewCounts {
+    /// ISO weekday (1 = Monday) or hour (0 to 23), in the user's timezone
+    pub slot: i32,
+    pub reviews: i64,
+    pub correct: i64,
+}
+
+/// Reviews of cards that had left the learning phase, split by maturity
+#[derive(Debug, Default, sqlx::FromRow)]
+pub struct RetentionCounts {
+    pub young_reviews: i64,
+    pub young_correct: i64,
+    pub mature_reviews: i64,
+    pub mature_correct: i64,
+}
+
+/// A user's average answer time in one deck
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct DeckAnswerTime {
+    /// None for reviews in decks deleted since
+    pub deck_id: Option<Uuid>,
+    /// None when the deck was deleted or is no longer visible to the user
+    pub title: Option<String>,
+    /// Reviews that reported an answer time
+    pub reviews: i64,
+    pub average_ms: f64,
+}
+
+/// A user's average answer time on one card
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct CardAnswerTime {
+    pub flashcard_id: Uuid,
+    pub term: String,
+    /// Reviews that reported an answer time
+    pub reviews: i64,
+    pub average_ms: f64,
+}
+
+/// The user's cards by how well they are learned
+#[derive(Debug, Default, sqlx::FromRow)]
+pub struct MaturityCounts {
+    /// In decks the user practises, never reviewed
+    pub new_cards: i64,
+    pub learning: i64,
+    pub young: i64,
+    pub mature: i64,
+}
+
+// --- Deck analytics ---
+
+/// A deck's figures summed over its cards' stored analytics
+#[derive(Debug, sqlx::FromRow)]
+pub struct DeckAnalyticsTotals {
+    /// Cards in the deck, analysed or not
+    pub cards: i64,
+    pub reviews: i64,
+    pub correct: i64,
+    pub recall_reviews: i64,
+    pub lapses: i64,
+    /// Cards mastered, counted once per learner
+    pub mastered: i64,
+    pub avg_days_to_master: Option<f64>,
+    /// Last nightly run covering the deck; None before the first
+    pub computed_at: Option<DateTime<Utc>>,
+}
+
+/// A card learners keep forgetting
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct HardCard {
+    pub flashcard_id: Uuid,
+    pub term: String,
+    pub translation: String,
+    pub learners: i32,
+    /// Answers to the card once it was scheduled a day or more ahead
+    pub recall_reviews: i32,
+    /// The wrong ones among `recall_reviews`
+    pub lapses: i32,
+    pub lapse_rate: f64,
+}
+
+// --- Daily goals ---
+
+/// A user's daily goal and today's progress towards it
+#[derive(Debug, sqlx::FromRow)]
+pub struct DailyGoalProgress {
+    /// `reviews` or `minutes`
+    pub kind: String,
+    pub target: i32,
+    /// Plausible reviews today, in the user's timezone
+    pub reviews_today: i32,
+    pub study_ms_today: i64,
+    pub reached: bool,
+}
+
+// --- Idempotency keys ---
+
+/// The request holding an idempotency key
+#[derive(Debug, sqlx::FromRow)]
+pub struct IdempotentResponse {
+    /// SHA-256 of the request's method, path and body
+    pub request_hash: Vec<u8>,
+    /// Absent while the request is still running
+    pub status: Option<i16>,
+    pub content_type: Option<String>,
+    pub body: Option<Vec<u8>>,
+}
+
+// --- Job queue ---
+
+/// A job claimed from the queue for a run
+#[derive(Debug, sqlx::FromRow)]
+pub struct ClaimedJob {
+    pub id: Uuid,
+    pub kind: String,
+    /// JSON arguments of the job
+    pub payload: EncryptedString,
+    /// Runs started so far, this one included
+    pub attempts: i32,
+    pub max_attempts: i32,
+}
+
+/// A job as operators see it; the payload is left out
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct JobSummary {
+    pub id: Uuid,
+    pub kind: String,
+    /// `pending`, `running`, `succeeded` or `dead`
+    pub status: String,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    /// When the job runs next, for pending jobs
+    pub run_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+// --- Data exports ---
+
+/// A data export requested by a user; the file itself is fetched separately
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct DataExport {
+    pub id: Uuid,
+    /// `pending` while it is being built, then `ready` or `failed`
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+// --- Media ---
+
+/// A stored media object, such as a resized avatar
+#[derive(Debug, sqlx::FromRow)]
+pub struct MediaObject {
+    pub content_type: String,
+    pub bytes: Vec<u8>,
+}
+
+// --- User preferences ---
+
+/// Preferences of a user who changed at least one
+#[derive(Debug, sqlx::FromRow)]
+pub struct UserPreferences {
+    /// `system`, `light` or `dark`
+    pub theme: String,
+    pub audio_autoplay: bool,
+    /// `strict`, `normal` or `lenient`
+    pub answer_checking: String,
+    pub new_cards_per_day: i32,
+    pub reviews_per_day: i32,
+    pub max_interval_days: i32,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Preferences to store
+#[derive(Debug)]
+pub struct NewUserPreferences<'a> {
+    pub theme: &'a str,
+    pub audio_autoplay: bool,
+    pub answer_checking: &'a str,
+    pub new_cards_per_day: i32,
+    pub reviews_per_day: i32,
+    pub max_interval_days: i32,
+}
+
+// --- Deck settings ---
+
+/// Scheduling overrides of a deck; `None` keeps the default
+#[derive(Debug, Default, sqlx::FromRow)]
+pub struct DeckSettings {
+    pub max_interval_days: Option<i32>,
+    pub starting_ease: Option<f64>,
+    /// `easiest_first`, `added` or `random`
+    pub new_card_order: Option<String>,
+    /// Any of `translation`, `listening` and `pronunciation`
+    pub modes: Option<Vec<String>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Scheduling overrides to store for a deck
+#[derive(Debug)]
+pub struct NewDeckSettings<'a> {
+    pub max_interval_days: Option<i32>,
+    pub starting_ease: Option<f64>,
+    pub new_card_order: Option<&'a str>,
+    pub modes: Option<Vec<&'a str>>,
+}
+
+/// A learner's progress on a deck's cards in one practice mode
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct ModeProgress {
+    /// `translation`, `listening` or `pronunciation`
+    pub mode: String,
+    /// Cards answered at least once in this mode
+    pub cards_practised: i64,
+    /// Cards whose score in this mode reached the mastery threshold
+    pub cards_mastered: i64,
+    pub times_correct: i64,
+    pub times_wrong: i64,
+}
+
+// --- Content seeding ---
+
+/// A roadmap as a seed bundle compares it
+#[derive(Debug, sqlx::FromRow)]
+pub struct SeededRoadmap {
+    pub id: Uuid,
+    pub title: String,
+    pub description: Option<String>,
+    pub language_from: String,
+    pub language_to: String,
+}
+
+/// A deck as a seed bundle compares it
+#[derive(Debug, sqlx::FromRow)]
+pub struct SeededDeck {
+    pub id: Uuid,
+    pub title: String,
+    pub description: Option<String>,
+    pub language_from: String,
+    pub language_to: String,
+    pub cefr_level: Option<String>,
+}
+
+/// A roadmap node as a seed bundle compares it
+#[derive(Debug, sqlx::FromRow)]
+pub struct SeededNode {
+    pub id: Uuid,
+    pub parent_node_id: Option<Uuid>,
+    pub pos_x: i32,
+    pub pos_y: i32,
+}
+
+// --- API keys ---
+
+/// An API key as its owner sees it; the key itself is only shown once
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub name: String,
+    /// First characters of the key, to tell keys apart
+    pub key_prefix: String,
+    /// Requests allowed per UTC day
+    pub daily_quota: i32,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// A request counted against an API key's daily quota
+#[derive(Debug, sqlx::FromRow)]
+pub struct ApiKeyRequest {
+    pub api_key_id: Uuid,
+    pub daily_quota: i32,
+    /// Requests made with the key today, this one included
+    pub requests: i32,
+    /// Requests refused today, this one not included
+    pub rejected: i32,
+}
+
+/// Requests made with an API key on one UTC day
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct ApiKeyUsage {
+    pub day: NaiveDate,
+    /// Every request made with the key, refused ones included
+    pub requests: i32,
+    /// Requests refused because the quota was used up
+    pub rejected: i32,
+}
+
+// --- Practice sessions ---
+
+/// A practice session with its progress through the queue
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct PracticeSession {
+    pub id: Uuid,
+    /// The session's deck; null when it spans several decks
+    pub deck_id: Option<Uuid>,
+    /// Every deck the session draws cards from
+    pub deck_ids: Vec<Uuid>,
+    /// Set when the session was started on a roadmap
+    pub roadmap_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    /// Pushed back on every card served or answered
+    pub expires_at: DateTime<Utc>,
+    /// Set once no card is left to serve
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Cards queued when the session started
+    pub total_cards: i64,
+    pub answered: i64,
+    pub correct: i64,
+    /// Cards still to serve; cards reviewed elsewhere, hidden or taken out of
+    /// the deck since the session started are skipped
+    pub remaining: i64,
+}
+
+/// The card a session serves next
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct SessionCard {
+    /// Place in the queue, from 1
+    pub position: i32,
+    /// The deck the card was drawn from; its answer counts towards this deck
+    pub deck_id: Uuid,
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub card: PracticeCard,
+}
+
+/// A due card and the deck it is practised in
+#[derive(Debug, sqlx::FromRow)]
+pub struct DeckPracticeCard {
+    pub deck_id: Uuid,
+    #[sqlx(flatten)]
+    pub card: PracticeCard,
 }
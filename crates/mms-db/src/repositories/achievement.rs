@@ -0,0 +1,59 @@
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::models::{UnlockedAchievement, UserAchievement};
+
+/// Every achievement with the user's progress, unlocked or not
+pub async fn list_for_user<'e, E>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<Vec<UserAchievement>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT a.code, a.title, a.description, a.metric, a.threshold,
+                   COALESCE(m.value, 0) AS progress, ua.unlocked_at
+            FROM achievements a
+            LEFT JOIN achievement_metrics($1) m ON m.metric = a.metric
+            LEFT JOIN user_achievements ua
+                ON ua.achievement_code = a.code AND ua.user_id = $1
+            ORDER BY a.sort_order, a.code
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Unlock every achievement whose threshold the user now meets; returns the newly unlocked ones
+pub async fn unlock_reached<'e, E>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<Vec<UnlockedAchievement>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            WITH unlocked AS (
+                INSERT INTO user_achievements (user_id, achievement_code)
+                SELECT $1, a.code
+                FROM achievement_metrics($1) m
+                JOIN achievements a ON a.metric = m.metric AND m.value >= a.threshold
+                ON CONFLICT DO NOTHING
+                RETURNING achievement_code
+            )
+            SELECT a.code, a.title, a.description
+            FROM unlocked u
+            JOIN achievements a ON a.code = u.achievement_code
+            ORDER BY a.sort_order, a.code
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(executor)
+    .await
+}
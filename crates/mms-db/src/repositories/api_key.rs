@@ -0,0 +1,242 @@
+use chrono::NaiveDate;
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::models::{ApiKey, ApiKeyRequest, ApiKeyUsage};
+
+/// Lock the user so concurrent key creations count their keys one after the
+/// other. `false` when the user does not exist or was deleted.
+pub async fn lock_user<'e, E>(executor: E, user_id: Uuid) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let locked: Option<Uuid> = sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT id FROM users WHERE id = $1 AND deleted_at IS NULL FOR NO KEY UPDATE
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await?;
+    Ok(locked.is_some())
+}
+
+/// Keys the user has not revoked
+pub async fn count_active<'e, E>(executor: E, user_id: Uuid) -> Result<i64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT COUNT(*) FROM api_keys WHERE user_id = $1 AND revoked_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(executor)
+    .await
+}
+
+/// Store a new key; only its hash and prefix are kept
+pub async fn create<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    name: &str,
+    key_prefix: &str,
+    key_hash: &str,
+    daily_quota: i32,
+) -> Result<ApiKey, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO api_keys (user_id, name, key_prefix, key_hash, daily_quota)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, name, key_prefix, daily_quota, created_at, last_used_at, revoked_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(name)
+    .bind(key_prefix)
+    .bind(key_hash)
+    .bind(daily_quota)
+    .fetch_one(executor)
+    .await
+}
+
+/// The user's keys, revoked ones included, newest first
+pub async fn list<'e, E>(executor: E, user_id: Uuid) -> Result<Vec<ApiKey>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT id, name, key_prefix, daily_quota, created_at, last_used_at, revoked_at
+            FROM api_keys
+            WHERE user_id = $1
+            ORDER BY created_at DESC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// One of the user's keys
+pub async fn find<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    api_key_id: Uuid,
+) -> Result<Option<ApiKey>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT id, name, key_prefix, daily_quota, created_at, last_used_at, revoked_at
+            FROM api_keys
+            WHERE id = $1 AND user_id = $2
+        "#,
+    )
+    .bind(api_key_id)
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Revoke one of the user's keys; `false` if there is no such key or it was already revoked
+pub async fn revoke<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    api_key_id: Uuid,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE api_keys SET revoked_at = NOW()
+            WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+        "#,
+    )
+    .bind(api_key_id)
+    .bind(user_id)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Change the daily quota of any key; `None` if it does not exist
+pub async fn set_daily_quota<'e, E>(
+    executor: E,
+    api_key_id: Uuid,
+    daily_quota: i32,
+) -> Result<Option<ApiKey>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            UPDATE api_keys SET daily_quota = $2
+            WHERE id = $1
+            RETURNING id, name, key_prefix, daily_quota, created_at, last_used_at, revoked_at
+        "#,
+    )
+    .bind(api_key_id)
+    .bind(daily_quota)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Count a request made with the key whose hash is `key_hash` on `day`.
+///
+/// `None` if no such key is active or its owner deleted their account. Every
+/// request is counted; the caller decides from the counts whether it is within
+/// the quota and records it with [`record_rejection`] if not.
+pub async fn record_request<'e, E>(
+    executor: E,
+    key_hash: &str,
+    day: NaiveDate,
+) -> Result<Option<ApiKeyRequest>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            WITH key AS (
+                UPDATE api_keys k SET last_used_at = NOW()
+                FROM users u
+                WHERE k.key_hash = $1
+                  AND k.revoked_at IS NULL
+                  AND u.id = k.user_id
+                  AND u.deleted_at IS NULL
+                RETURNING k.id, k.daily_quota
+            )
+            INSERT INTO api_key_usage (api_key_id, day, requests)
+            SELECT id, $2, 1 FROM key
+            ON CONFLICT (api_key_id, day)
+                DO UPDATE SET requests = api_key_usage.requests + 1
+            RETURNING api_key_id, (SELECT daily_quota FROM key) AS daily_quota, requests, rejected
+        "#,
+    )
+    .bind(key_hash)
+    .bind(day)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Record that a request counted on `day` was refused
+pub async fn record_rejection<'e, E>(
+    executor: E,
+    api_key_id: Uuid,
+    day: NaiveDate,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE api_key_usage SET rejected = rejected + 1
+            WHERE api_key_id = $1 AND day = $2
+        "#,
+    )
+    .bind(api_key_id)
+    .bind(day)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Requests per day made with a key since `since`, oldest first; days without
+/// requests are left out
+pub async fn usage<'e, E>(
+    executor: E,
+    api_key_id: Uuid,
+    since: NaiveDate,
+) -> Result<Vec<ApiKeyUsage>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT day, requests, rejected
+            FROM api_key_usage
+            WHERE api_key_id = $1 AND day >= $2
+            ORDER BY day
+        "#,
+    )
+    .bind(api_key_id)
+    .bind(since)
+    .fetch_all(executor)
+    .await
+}
@@ -2,7 +2,7 @@ use chrono::{DateTime, Utc};
 use sqlx::{Executor, Postgres};
 use uuid::Uuid;
 
-use crate::models::{RefreshTokenRecord, UserProfile, UserWithGoogleId};
+use crate::models::{EncryptedString, RefreshTokenRecord, UserProfile, UserWithGoogleId};
 
 pub async fn find_by_google_id<'e, E>(
     executor: E,
@@ -14,7 +14,7 @@ where
     sqlx::query_as(
         // language=PostgreSQL
         r#"
-            SELECT id, username, email, profile_picture_url, native_language, learning_language
+            SELECT id, username, email, profile_picture_url, native_language, learning_language, role::text
             FROM users
             WHERE google_id = $1
         "#,
@@ -34,7 +34,8 @@ where
     sqlx::query_as(
         // language=PostgreSQL
         r#"
-            SELECT id, username, email, google_id, profile_picture_url, native_language, learning_language
+            SELECT id, username, email, google_id, profile_picture_url, native_language, learning_language,
+                   role::text
             FROM users
             WHERE email = $1
         "#,
@@ -44,11 +45,11 @@ where
     .await
 }
 
+/// Link the Google account that signed in with the user's email, which also verifies the email
 pub async fn link_google_account<'e, E>(
     executor: E,
     user_id: Uuid,
     google_id: &str,
-    picture: Option<&str>,
 ) -> Result<bool, sqlx::Error>
 where
     E: Executor<'e, Database = Postgres>,
@@ -57,18 +58,64 @@ where
         // language=PostgreSQL
         r#"
             UPDATE users
-            SET google_id = $1, profile_picture_url = COALESCE($2, profile_picture_url), email_verified = TRUE
-            WHERE id = $3
+            SET google_id = $1, email_verified = TRUE
+            WHERE id = $2
         "#,
     )
     .bind(google_id)
-    .bind(picture)
     .bind(user_id)
     .execute(executor)
     .await?;
     Ok(result.rows_affected() > 0)
 }
 
+/// Link a Google account to the user; false if the user already has one linked
+pub async fn link_google_id<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    google_id: &str,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE users
+            SET google_id = $2
+            WHERE id = $1 AND google_id IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .bind(google_id)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Remove the user's Google account, unless it is their only way to sign in
+///
+/// Returns false when no Google account is linked or the user has no password.
+pub async fn unlink_google_id<'e, E>(executor: E, user_id: Uuid) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE users
+            SET google_id = NULL
+            WHERE id = $1 AND google_id IS NOT NULL AND password_hash IS NOT NULL
+        "#,
+    )
+    .bind(user_id)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Use the picture from the user's Google account; false if the user does not
+/// exist or uploaded an avatar, which takes precedence
 pub async fn update_profile_picture<'e, E>(
     executor: E,
     user_id: Uuid,
@@ -82,7 +129,7 @@ where
         r#"
             UPDATE users
             SET profile_picture_url = $1
-            WHERE id = $2
+            WHERE id = $2 AND avatar_key IS NULL
         "#,
     )
     .bind(picture_url)
@@ -118,6 +165,9 @@ where
     .await
 }
 
+/// Store a refresh token; `session_started_at` is `None` for a new sign-in
+/// and the previous token's value when rotating. Device info and IP address
+/// are encrypted at rest.
 pub async fn store_refresh_token<'e, E>(
     executor: E,
     user_id: Uuid,
@@ -125,6 +175,7 @@ pub async fn store_refresh_token<'e, E>(
     device_info: Option<&str>,
     ip_address: Option<&str>,
     expires_at: DateTime<Utc>,
+    session_started_at: Option<DateTime<Utc>>,
 ) -> Result<Uuid, sqlx::Error>
 where
     E: Executor<'e, Database = Postgres>,
@@ -132,16 +183,18 @@ where
     sqlx::query_scalar(
         // language=PostgreSQL
         r#"
-            INSERT INTO refresh_tokens (user_id, token_hash, device_info, ip_address, expires_at)
-            VALUES ($1, $2, $3, $4, $5)
+            INSERT INTO refresh_tokens
+                (user_id, token_hash, device_info, ip_address, expires_at, session_started_at)
+            VALUES ($1, $2, $3, $4, $5, COALESCE($6, NOW()))
             RETURNING id
         "#,
     )
     .bind(user_id)
     .bind(token_hash)
-    .bind(device_info)
-    .bind(ip_address)
+    .bind(device_info.map(EncryptedString::from))
+    .bind(ip_address.map(EncryptedString::from))
     .bind(expires_at)
+    .bind(session_started_at)
     .fetch_one(executor)
     .await
 }
@@ -156,7 +209,7 @@ where
     sqlx::query_as(
         // language=PostgreSQL
         r#"
-            SELECT id, user_id, expires_at, device_info, ip_address
+            SELECT id, user_id, expires_at, session_started_at, device_info, ip_address
             FROM refresh_tokens
             WHERE token_hash = $1
             FOR UPDATE
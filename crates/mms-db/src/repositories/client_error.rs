@@ -0,0 +1,72 @@
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::models::{ClientError, NewClientError};
+
+pub async fn insert<'e, E>(executor: E, report: &NewClientError<'_>) -> Result<Uuid, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO client_errors
+                (request_id, user_id, platform, app_version, message, stack, page, user_agent)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING id
+        "#,
+    )
+    .bind(report.request_id)
+    .bind(report.user_id)
+    .bind(report.platform)
+    .bind(report.app_version)
+    .bind(report.message)
+    .bind(report.stack)
+    .bind(report.page)
+    .bind(report.user_agent)
+    .fetch_one(executor)
+    .await
+}
+
+/// Most recent reports, optionally only those tagged with `request_id`
+pub async fn list_recent<'e, E>(
+    executor: E,
+    request_id: Option<&str>,
+    limit: i64,
+) -> Result<Vec<ClientError>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT id, request_id, user_id, platform, app_version, message, stack, page,
+                   user_agent, created_at
+            FROM client_errors
+            WHERE $1::text IS NULL OR request_id = $1
+            ORDER BY created_at DESC, id DESC
+            LIMIT $2
+        "#,
+    )
+    .bind(request_id)
+    .bind(limit)
+    .fetch_all(executor)
+    .await
+}
+
+pub async fn delete_older_than<'e, E>(executor: E, days: i32) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM client_errors WHERE created_at < NOW() - make_interval(days => $1)
+        "#,
+    )
+    .bind(days)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
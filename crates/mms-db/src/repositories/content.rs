@@ -0,0 +1,455 @@
+//! Bulk content writes used by course ingestion and seed bundles.
+//!
+//! Every statement is an upsert keyed on the record's id (or, for flashcards,
+//! on the `unique_flashcard` constraint), so re-running an import after a
+//! partial failure is safe. Seed bundles find their roadmaps and decks by
+//! slug first, then write through the same upserts. Admins rename slugs here
+//! too.
+//!
+//! New roadmaps and decks written without a slug get one generated by the
+//! database. Another transaction can take that slug between generating and
+//! inserting it, so the upserts retry the insert in a savepoint when the slug
+//! index rejects it.
+
+use sqlx::{Connection, Executor, PgConnection, Postgres};
+use uuid::Uuid;
+
+use crate::models::{Flashcard, SeededDeck, SeededNode, SeededRoadmap};
+
+pub async fn upsert_roadmap(
+    conn: &mut PgConnection,
+    id: Uuid,
+    title: &str,
+    description: Option<&str>,
+    language_from: &str,
+    language_to: &str,
+) -> Result<(), sqlx::Error> {
+    let mut attempt = 1;
+    loop {
+        let mut savepoint = conn.begin().await?;
+        let result = sqlx::query(
+            // language=PostgreSQL
+            r#"
+                INSERT INTO roadmaps (id, title, description, language_from, language_to)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (id) DO UPDATE
+                SET title = EXCLUDED.title,
+                    description = EXCLUDED.description,
+                    language_from = EXCLUDED.language_from,
+                    language_to = EXCLUDED.language_to
+            "#,
+        )
+        .bind(id)
+        .bind(title)
+        .bind(description)
+        .bind(language_from)
+        .bind(language_to)
+        .execute(&mut *savepoint)
+        .await;
+        match result {
+            Ok(_) => return savepoint.commit().await,
+            Err(e) if attempt < SLUG_ATTEMPTS && is_slug_conflict(&e) => {
+                savepoint.rollback().await?;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+pub async fn upsert_deck(
+    conn: &mut PgConnection,
+    id: Uuid,
+    title: &str,
+    description: Option<&str>,
+    language_from: &str,
+    language_to: &str,
+    cefr_level: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    let mut attempt = 1;
+    loop {
+        let mut savepoint = conn.begin().await?;
+        let result = sqlx::query(
+            // language=PostgreSQL
+            r#"
+                INSERT INTO decks (id, title, description, language_from, language_to, cefr_level)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                ON CONFLICT (id) DO UPDATE
+                SET title = EXCLUDED.title,
+                    description = EXCLUDED.description,
+                    language_from = EXCLUDED.language_from,
+                    language_to = EXCLUDED.language_to,
+                    cefr_level = EXCLUDED.cefr_level
+            "#,
+        )
+        .bind(id)
+        .bind(title)
+        .bind(description)
+        .bind(language_from)
+        .bind(language_to)
+        .bind(cefr_level)
+        .execute(&mut *savepoint)
+        .await;
+        match result {
+            Ok(_) => return savepoint.commit().await,
+            Err(e) if attempt < SLUG_ATTEMPTS && is_slug_conflict(&e) => {
+                savepoint.rollback().await?;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Times an upsert is tried before a clash on a generated slug is returned
+const SLUG_ATTEMPTS: u32 = 3;
+
+/// Whether the error is the slug index rejecting a generated slug
+fn is_slug_conflict(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::Database(db) => {
+            db.is_unique_violation()
+                && matches!(
+                    db.constraint(),
+                    Some("idx_roadmaps_slug" | "idx_decks_slug")
+                )
+        }
+        _ => false,
+    }
+}
+
+/// Whether the deck exists, whichever organization it belongs to
+pub async fn deck_exists<'e, E>(executor: E, deck_id: Uuid) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            -- tenancy: all tenants (operators import into any organization's decks)
+            SELECT EXISTS (SELECT 1 FROM decks WHERE id = $1)
+        "#,
+    )
+    .bind(deck_id)
+    .fetch_one(executor)
+    .await
+}
+
+/// The deck's cards, except hidden ones, oldest first
+pub async fn list_deck_cards<'e, E>(
+    executor: E,
+    deck_id: Uuid,
+) -> Result<Vec<Flashcard>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT f.id, f.term, f.translation, f.language_from, f.language_to
+            FROM deck_flashcards df
+            JOIN flashcards f ON f.id = df.flashcard_id
+            WHERE df.deck_id = $1 AND f.hidden_at IS NULL
+            ORDER BY f.created_at, f.id
+        "#,
+    )
+    .bind(deck_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Create (or reuse) a flashcard in the deck's language pair and link it to the deck.
+///
+/// Returns the flashcard id, or `None` when the deck does not exist.
+pub async fn upsert_deck_flashcard<'e, E>(
+    executor: E,
+    deck_id: Uuid,
+    term: &str,
+    translation: &str,
+) -> Result<Option<Uuid>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            -- tenancy: all tenants (operators import into any organization's decks)
+            WITH deck AS (
+                SELECT language_from, language_to FROM decks WHERE id = $1
+            ),
+            card AS (
+                INSERT INTO flashcards (term, translation, language_from, language_to)
+                SELECT $2, $3, language_from, language_to FROM deck
+                -- No-op update so RETURNING yields the existing card's id
+                ON CONFLICT ON CONSTRAINT unique_flashcard DO UPDATE SET term = EXCLUDED.term
+                RETURNING id
+            ),
+            link AS (
+                INSERT INTO deck_flashcards (deck_id, flashcard_id)
+                SELECT $1, id FROM card
+                ON CONFLICT DO NOTHING
+            )
+            SELECT id FROM card
+        "#,
+    )
+    .bind(deck_id)
+    .bind(term)
+    .bind(translation)
+    .fetch_optional(executor)
+    .await
+}
+
+pub async fn upsert_roadmap_node<'e, E>(
+    executor: E,
+    id: Uuid,
+    roadmap_id: Uuid,
+    deck_id: Uuid,
+    parent_node_id: Option<Uuid>,
+    pos_x: i32,
+    pos_y: i32,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO roadmap_nodes (id, roadmap_id, deck_id, parent_node_id, pos_x, pos_y)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (id) DO UPDATE
+            SET roadmap_id = EXCLUDED.roadmap_id,
+                deck_id = EXCLUDED.deck_id,
+                parent_node_id = EXCLUDED.parent_node_id,
+                pos_x = EXCLUDED.pos_x,
+                pos_y = EXCLUDED.pos_y
+        "#,
+    )
+    .bind(id)
+    .bind(roadmap_id)
+    .bind(deck_id)
+    .bind(parent_node_id)
+    .bind(pos_x)
+    .bind(pos_y)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// The roadmap with the slug, locked until the transaction ends
+pub async fn find_roadmap_by_slug<'e, E>(
+    executor: E,
+    slug: &str,
+) -> Result<Option<SeededRoadmap>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            -- tenancy: all tenants (operators seed any organization's roadmaps)
+            SELECT id, title, description, language_from, language_to
+            FROM roadmaps
+            WHERE slug = $1
+            FOR UPDATE
+        "#,
+    )
+    .bind(slug)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Create a roadmap named by `slug`; returns its id
+pub async fn create_roadmap<'e, E>(
+    executor: E,
+    slug: &str,
+    title: &str,
+    description: Option<&str>,
+    language_from: &str,
+    language_to: &str,
+) -> Result<Uuid, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO roadmaps (slug, title, description, language_from, language_to)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id
+        "#,
+    )
+    .bind(slug)
+    .bind(title)
+    .bind(description)
+    .bind(language_from)
+    .bind(language_to)
+    .fetch_one(executor)
+    .await
+}
+
+/// The deck with the slug, whichever organization it belongs to, locked until
+/// the transaction ends
+pub async fn find_deck_by_slug<'e, E>(
+    executor: E,
+    slug: &str,
+) -> Result<Option<SeededDeck>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            -- tenancy: all tenants (operators seed any organization's decks)
+            SELECT id, title, description, language_from, language_to, cefr_level
+            FROM decks
+            WHERE slug = $1
+            FOR UPDATE
+        "#,
+    )
+    .bind(slug)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Create a deck named by `slug`; returns its id
+pub async fn create_deck<'e, E>(
+    executor: E,
+    slug: &str,
+    title: &str,
+    description: Option<&str>,
+    language_from: &str,
+    language_to: &str,
+    cefr_level: Option<&str>,
+) -> Result<Uuid, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO decks (slug, title, description, language_from, language_to, cefr_level)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id
+        "#,
+    )
+    .bind(slug)
+    .bind(title)
+    .bind(description)
+    .bind(language_from)
+    .bind(language_to)
+    .bind(cefr_level)
+    .fetch_one(executor)
+    .await
+}
+
+/// Create (or reuse) a flashcard in the deck's language pair and link it to
+/// the deck; returns whether the deck did not have it yet
+pub async fn link_deck_flashcard<'e, E>(
+    executor: E,
+    deck_id: Uuid,
+    term: &str,
+    translation: &str,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            -- tenancy: all tenants (operators seed any organization's decks)
+            WITH deck AS (
+                SELECT language_from, language_to FROM decks WHERE id = $1
+            ),
+            card AS (
+                INSERT INTO flashcards (term, translation, language_from, language_to)
+                SELECT $2, $3, language_from, language_to FROM deck
+                -- No-op update so RETURNING yields the existing card's id
+                ON CONFLICT ON CONSTRAINT unique_flashcard DO UPDATE SET term = EXCLUDED.term
+                RETURNING id
+            ),
+            link AS (
+                INSERT INTO deck_flashcards (deck_id, flashcard_id)
+                SELECT $1, id FROM card
+                ON CONFLICT DO NOTHING
+                RETURNING flashcard_id
+            )
+            SELECT EXISTS (SELECT 1 FROM link)
+        "#,
+    )
+    .bind(deck_id)
+    .bind(term)
+    .bind(translation)
+    .fetch_one(executor)
+    .await
+}
+
+/// The node of the roadmap that shows the deck
+pub async fn find_roadmap_node<'e, E>(
+    executor: E,
+    roadmap_id: Uuid,
+    deck_id: Uuid,
+) -> Result<Option<SeededNode>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT id, parent_node_id, pos_x, pos_y
+            FROM roadmap_nodes
+            WHERE roadmap_id = $1 AND deck_id = $2
+            ORDER BY created_at
+            LIMIT 1
+        "#,
+    )
+    .bind(roadmap_id)
+    .bind(deck_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Rename a roadmap's slug; returns whether the roadmap exists
+pub async fn set_roadmap_slug<'e, E>(
+    executor: E,
+    roadmap_id: Uuid,
+    slug: &str,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            -- tenancy: all tenants (operators edit any organization's roadmaps)
+            UPDATE roadmaps SET slug = $2 WHERE id = $1
+        "#,
+    )
+    .bind(roadmap_id)
+    .bind(slug)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Rename a deck's slug; returns whether the deck exists
+pub async fn set_deck_slug<'e, E>(
+    executor: E,
+    deck_id: Uuid,
+    slug: &str,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            -- tenancy: all tenants (operators edit any organization's decks)
+            UPDATE decks SET slug = $2 WHERE id = $1
+        "#,
+    )
+    .bind(deck_id)
+    .bind(slug)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
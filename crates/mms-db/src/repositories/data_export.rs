@@ -0,0 +1,126 @@
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::models::DataExport;
+
+/// Record a requested export, to be built by a job
+pub async fn create<'e, E>(executor: E, user_id: Uuid) -> Result<DataExport, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO data_exports (user_id)
+            VALUES ($1)
+            RETURNING id, status, created_at, completed_at
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(executor)
+    .await
+}
+
+/// One of `user_id`'s exports
+pub async fn find<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    export_id: Uuid,
+) -> Result<Option<DataExport>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT id, status, created_at, completed_at
+            FROM data_exports
+            WHERE id = $1 AND user_id = $2
+        "#,
+    )
+    .bind(export_id)
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// The file of one of `user_id`'s exports, once it is ready
+pub async fn find_body<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    export_id: Uuid,
+) -> Result<Option<Vec<u8>>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT body
+            FROM data_exports
+            WHERE id = $1 AND user_id = $2 AND status = 'ready'
+        "#,
+    )
+    .bind(export_id)
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Store the built file; false if the export no longer exists
+pub async fn complete<'e, E>(executor: E, export_id: Uuid, body: &[u8]) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE data_exports
+            SET status = 'ready', body = $2, completed_at = NOW()
+            WHERE id = $1
+        "#,
+    )
+    .bind(export_id)
+    .bind(body)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Record that the export could not be built
+pub async fn mark_failed<'e, E>(executor: E, export_id: Uuid) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE data_exports
+            SET status = 'failed', completed_at = NOW()
+            WHERE id = $1
+        "#,
+    )
+    .bind(export_id)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Delete exports requested more than `days` ago
+pub async fn delete_older_than<'e, E>(executor: E, days: i32) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM data_exports
+            WHERE created_at < NOW() - make_interval(days => $1)
+        "#,
+    )
+    .bind(days)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
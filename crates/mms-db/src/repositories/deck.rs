@@ -1,14 +1,83 @@
+use futures_util::stream::BoxStream;
 use sqlx::{Executor, Postgres};
 use uuid::Uuid;
 
-use crate::models::PracticeCard;
+use crate::{
+    models::{CatalogDeck, Deck, DeckDueCount, DeckProgress, Flashcard, PublicDeck},
+    tenancy::{Tenant, TenantQuery},
+};
 
-pub async fn get_practice_cards<'e, E>(
+/// The deck, if it exists and the tenant may see it
+pub async fn find_by_id<'e, E>(
     executor: E,
+    tenant: Tenant,
     deck_id: Uuid,
+) -> Result<Option<Deck>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let mut query = TenantQuery::new(
+        tenant,
+        // language=PostgreSQL
+        r#"
+            SELECT d.id, d.slug, d.title, d.description, d.language_from, d.language_to
+            FROM decks d
+            WHERE d.id = "#,
+    );
+    query.push_bind(deck_id).push(" AND ").push_visible("d");
+    query.build_query_as().fetch_optional(executor).await
+}
+
+/// The id of the deck with the slug, if it exists and the tenant may see it
+pub async fn find_id_by_slug<'e, E>(
+    executor: E,
+    tenant: Tenant,
+    slug: &str,
+) -> Result<Option<Uuid>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let mut query = TenantQuery::new(
+        tenant,
+        // language=PostgreSQL
+        r#"
+            SELECT d.id
+            FROM decks d
+            WHERE d.slug = "#,
+    );
+    query.push_bind(slug).push(" AND ").push_visible("d");
+    query.build_query_scalar().fetch_optional(executor).await
+}
+
+/// The decks among `deck_ids` the user may see
+pub async fn list_by_ids<'e, E>(
+    executor: E,
+    deck_ids: &[Uuid],
     user_id: Uuid,
-    limit: i64,
-) -> Result<Vec<PracticeCard>, sqlx::Error>
+) -> Result<Vec<Deck>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT d.id, d.slug, d.title, d.description, d.language_from, d.language_to
+            FROM decks d
+            WHERE d.id = ANY($1) AND org_visible(d.org_id, $2)
+        "#,
+    )
+    .bind(deck_ids)
+    .bind(user_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// The user's progress on each deck among `deck_ids` they may see, practised or not
+pub async fn list_progress<'e, E>(
+    executor: E,
+    deck_ids: &[Uuid],
+    user_id: Uuid,
+) -> Result<Vec<DeckProgress>, sqlx::Error>
 where
     E: Executor<'e, Database = Postgres>,
 {
@@ -16,24 +85,182 @@ where
         // language=PostgreSQL
         r#"
             SELECT
-                f.id,
-                f.term,
-                f.translation,
-                COALESCE(ucp.times_correct, 0) as times_correct,
-                COALESCE(ucp.times_wrong, 0) as times_wrong
-            FROM deck_flashcards df
-            JOIN flashcards f ON f.id = df.flashcard_id
-            LEFT JOIN user_card_progress ucp
-                ON ucp.flashcard_id = f.id AND ucp.user_id = $2
-            WHERE df.deck_id = $1
-                AND (ucp.next_review_at IS NULL OR ucp.next_review_at <= NOW())
-            ORDER BY ucp.next_review_at NULLS FIRST
-            LIMIT $3
+                d.id as deck_id,
+                COALESCE(udp.total_cards, cards.total) as total_cards,
+                COALESCE(udp.mastered_cards, 0) as mastered_cards,
+                cards.due as cards_due,
+                COALESCE(udp.total_practices, 0) as total_practices,
+                udp.last_practiced_at,
+                COALESCE(udp.progress_percentage, 0.0)::float8 as progress_percentage,
+                CASE WHEN cards.due > 0 THEN NULL ELSE cards.next_review_at END as next_practice_at
+            FROM decks d
+            LEFT JOIN user_deck_progress udp
+                ON udp.deck_id = d.id AND udp.user_id = $2
+            CROSS JOIN LATERAL (
+                SELECT
+                    COUNT(*)::int as total,
+                    COUNT(*) FILTER (
+                        WHERE ucp.next_review_at IS NULL OR ucp.next_review_at <= NOW()
+                    )::int as due,
+                    MIN(ucp.next_review_at) as next_review_at
+                FROM deck_flashcards df
+                LEFT JOIN user_card_progress ucp
+                    ON ucp.flashcard_id = df.flashcard_id AND ucp.user_id = $2
+                WHERE df.deck_id = d.id
+            ) cards
+            WHERE d.id = ANY($1) AND org_visible(d.org_id, $2)
+        "#,
+    )
+    .bind(deck_ids)
+    .bind(user_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Reviewed cards due now per deck the user may see, most due first; decks
+/// with nothing due are left out. A card in several decks counts in each.
+pub async fn count_due_by_deck<'e, E>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<Vec<DeckDueCount>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT d.id AS deck_id, d.title, COUNT(*) AS due_now
+            FROM user_card_progress ucp
+            JOIN deck_flashcards df ON df.flashcard_id = ucp.flashcard_id
+            JOIN decks d ON d.id = df.deck_id
+            WHERE ucp.user_id = $1
+                AND ucp.next_review_at <= NOW()
+                AND org_visible(d.org_id, $1)
+            GROUP BY d.id, d.title
+            ORDER BY due_now DESC, d.title
         "#,
     )
-    .bind(deck_id)
     .bind(user_id)
-    .bind(limit)
     .fetch_all(executor)
     .await
 }
+
+/// How the public catalogue is ordered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublicDeckOrder {
+    /// Highest average first, then most rated; unrated decks last
+    Rating,
+    Newest,
+    Title,
+}
+
+impl PublicDeckOrder {
+    fn sql(self) -> &'static str {
+        match self {
+            Self::Rating => " ORDER BY d.rating_average DESC NULLS LAST, d.rating_count DESC, d.id",
+            Self::Newest => " ORDER BY d.created_at DESC NULLS LAST, d.id",
+            Self::Title => " ORDER BY d.title, d.id",
+        }
+    }
+}
+
+/// One page of the public catalogue, optionally for one language pair
+pub async fn list_public<'e, E>(
+    executor: E,
+    languages: Option<(&str, &str)>,
+    order: PublicDeckOrder,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<PublicDeck>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let mut query = TenantQuery::new(
+        Tenant::Public,
+        // language=PostgreSQL
+        r#"
+            SELECT d.id, d.slug, d.title, d.description, d.language_from, d.language_to, d.cefr_level,
+                   d.rating_average, d.rating_count, d.created_at
+            FROM decks d
+            WHERE d.hidden_at IS NULL AND "#,
+    );
+    query.push_visible("d");
+    if let Some((language_from, language_to)) = languages {
+        query
+            .push(" AND d.language_from = ")
+            .push_bind(language_from)
+            .push(" AND d.language_to = ")
+            .push_bind(language_to);
+    }
+    query
+        .push(order.sql())
+        .push(" LIMIT ")
+        .push_bind(limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+    query.build_query_as().fetch_all(executor).await
+}
+
+/// One page of the public catalogue with each deck's card count, optionally
+/// for one language pair
+pub async fn list_catalog<'e, E>(
+    executor: E,
+    languages: Option<(&str, &str)>,
+    order: PublicDeckOrder,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<CatalogDeck>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let mut query = TenantQuery::new(
+        Tenant::Public,
+        // language=PostgreSQL
+        r#"
+            SELECT d.id, d.slug, d.title, d.description, d.language_from, d.language_to, d.cefr_level,
+                   (SELECT COUNT(*)
+                    FROM deck_flashcards df
+                    JOIN flashcards f ON f.id = df.flashcard_id
+                    WHERE df.deck_id = d.id AND f.hidden_at IS NULL) AS card_count,
+                   d.rating_average, d.rating_count, d.created_at
+            FROM decks d
+            WHERE d.hidden_at IS NULL AND "#,
+    );
+    query.push_visible("d");
+    if let Some((language_from, language_to)) = languages {
+        query
+            .push(" AND d.language_from = ")
+            .push_bind(language_from)
+            .push(" AND d.language_to = ")
+            .push_bind(language_to);
+    }
+    query
+        .push(order.sql())
+        .push(" LIMIT ")
+        .push_bind(limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+    query.build_query_as().fetch_all(executor).await
+}
+
+/// Stream every flashcard in a deck, except hidden ones, without buffering the result set
+pub fn stream_flashcards<'e, E>(
+    executor: E,
+    deck_id: Uuid,
+) -> BoxStream<'e, Result<Flashcard, sqlx::Error>>
+where
+    E: Executor<'e, Database = Postgres> + 'e,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT f.id, f.term, f.translation, f.language_from, f.language_to
+            FROM deck_flashcards df
+            JOIN flashcards f ON f.id = df.flashcard_id
+            WHERE df.deck_id = $1 AND f.hidden_at IS NULL
+            ORDER BY f.created_at, f.id
+        "#,
+    )
+    .bind(deck_id)
+    .fetch(executor)
+}
@@ -0,0 +1,166 @@
+//! Per-card analytics across all learners, rebuilt nightly, and the deck
+//! figures summed from them.
+
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::models::{DeckAnalyticsTotals, HardCard};
+
+/// Recompute the analytics of every card someone has reviewed. Reviews at an
+/// interval of at least `recall_days` count towards lapses; flagged reviews are
+/// left out.
+///
+/// Returns the number of cards written.
+pub async fn refresh<'e, E>(executor: E, recall_days: f64) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            WITH progress AS (
+                SELECT
+                    p.flashcard_id,
+                    COUNT(*)::int AS learners,
+                    SUM(p.times_correct + p.times_wrong)::bigint AS reviews,
+                    SUM(p.times_correct)::bigint AS correct,
+                    COUNT(p.mastered_at)::int AS mastered
+                FROM user_card_progress p
+                GROUP BY p.flashcard_id
+            ),
+            recall AS (
+                SELECT
+                    l.flashcard_id,
+                    COUNT(*)::int AS recall_reviews,
+                    COUNT(*) FILTER (WHERE NOT l.is_correct)::int AS lapses
+                FROM review_logs l
+                WHERE NOT l.flagged AND l.previous_interval_days >= $1
+                GROUP BY l.flashcard_id
+            ),
+            mastery AS (
+                SELECT
+                    p.flashcard_id,
+                    COUNT(*)::int AS samples,
+                    AVG(EXTRACT(EPOCH FROM p.mastered_at - s.started_at) / 86400) AS avg_days
+                FROM user_card_progress p
+                CROSS JOIN LATERAL (
+                    SELECT MAX(l.reviewed_at) AS started_at
+                    FROM review_logs l
+                    WHERE l.user_id = p.user_id
+                      AND l.flashcard_id = p.flashcard_id
+                      AND l.previous_interval_days IS NULL
+                      AND l.reviewed_at <= p.mastered_at
+                ) s
+                WHERE p.mastered_at IS NOT NULL AND s.started_at IS NOT NULL
+                GROUP BY p.flashcard_id
+            )
+            INSERT INTO flashcard_analytics
+                (flashcard_id, learners, reviews, correct, recall_reviews, lapses,
+                 mastered, mastery_samples, avg_days_to_master, computed_at)
+            SELECT
+                p.flashcard_id, p.learners, p.reviews, p.correct,
+                COALESCE(r.recall_reviews, 0), COALESCE(r.lapses, 0),
+                p.mastered, COALESCE(m.samples, 0), m.avg_days, NOW()
+            FROM progress p
+            LEFT JOIN recall r ON r.flashcard_id = p.flashcard_id
+            LEFT JOIN mastery m ON m.flashcard_id = p.flashcard_id
+            ON CONFLICT (flashcard_id) DO UPDATE
+            SET learners = EXCLUDED.learners,
+                reviews = EXCLUDED.reviews,
+                correct = EXCLUDED.correct,
+                recall_reviews = EXCLUDED.recall_reviews,
+                lapses = EXCLUDED.lapses,
+                mastered = EXCLUDED.mastered,
+                mastery_samples = EXCLUDED.mastery_samples,
+                avg_days_to_master = EXCLUDED.avg_days_to_master,
+                computed_at = EXCLUDED.computed_at
+        "#,
+    )
+    .bind(recall_days)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Drop the rows of cards nobody has progress on any more, i.e. those the
+/// refresh in the same transaction did not write
+pub async fn delete_stale<'e, E>(executor: E) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM flashcard_analytics WHERE computed_at < NOW()
+        "#,
+    )
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// The deck's figures summed over its cards; the average time to master is
+/// weighted by each card's samples
+pub async fn totals<'e, E>(executor: E, deck_id: Uuid) -> Result<DeckAnalyticsTotals, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                COUNT(*) AS cards,
+                COALESCE(SUM(a.reviews), 0)::bigint AS reviews,
+                COALESCE(SUM(a.correct), 0)::bigint AS correct,
+                COALESCE(SUM(a.recall_reviews), 0)::bigint AS recall_reviews,
+                COALESCE(SUM(a.lapses), 0)::bigint AS lapses,
+                COALESCE(SUM(a.mastered), 0)::bigint AS mastered,
+                SUM(a.avg_days_to_master * a.mastery_samples) / NULLIF(SUM(a.mastery_samples), 0)
+                    AS avg_days_to_master,
+                MAX(a.computed_at) AS computed_at
+            FROM deck_flashcards df
+            LEFT JOIN flashcard_analytics a ON a.flashcard_id = df.flashcard_id
+            WHERE df.deck_id = $1
+        "#,
+    )
+    .bind(deck_id)
+    .fetch_one(executor)
+    .await
+}
+
+/// The deck's cards with the highest lapse rate, among those with at least
+/// `min_recall_reviews` answers to count
+pub async fn hardest_cards<'e, E>(
+    executor: E,
+    deck_id: Uuid,
+    min_recall_reviews: i32,
+    limit: i64,
+) -> Result<Vec<HardCard>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                f.id AS flashcard_id,
+                f.term,
+                f.translation,
+                a.learners,
+                a.recall_reviews,
+                a.lapses,
+                a.lapses::float8 / a.recall_reviews AS lapse_rate
+            FROM deck_flashcards df
+            JOIN flashcard_analytics a ON a.flashcard_id = df.flashcard_id
+            JOIN flashcards f ON f.id = df.flashcard_id
+            WHERE df.deck_id = $1 AND a.recall_reviews >= GREATEST($2, 1)
+            ORDER BY lapse_rate DESC, a.lapses DESC, f.id
+            LIMIT $3
+        "#,
+    )
+    .bind(deck_id)
+    .bind(min_recall_reviews)
+    .bind(limit)
+    .fetch_all(executor)
+    .await
+}
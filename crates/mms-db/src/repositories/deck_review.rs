@@ -0,0 +1,259 @@
+//! Ratings and comments on public decks. The deck's rating totals are kept by
+//! the `trg_deck_ratings_totals` trigger, not here.
+
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::models::{DeckComment, DeckRating};
+
+/// Whether the deck is in the public catalogue: public and not hidden by a moderator
+pub async fn is_public<'e, E>(executor: E, deck_id: Uuid) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT EXISTS (
+                SELECT 1 FROM decks d
+                WHERE d.id = $1 AND d.hidden_at IS NULL AND org_visible(d.org_id, NULL)
+            )
+        "#,
+    )
+    .bind(deck_id)
+    .fetch_one(executor)
+    .await
+}
+
+/// A public deck's rating with the user's own, or None if the deck is not public
+pub async fn find_rating<'e, E>(
+    executor: E,
+    deck_id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<DeckRating>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT d.rating_average, d.rating_count, r.stars AS my_stars
+            FROM decks d
+            LEFT JOIN deck_ratings r ON r.deck_id = d.id AND r.user_id = $2
+            WHERE d.id = $1 AND d.hidden_at IS NULL AND org_visible(d.org_id, NULL)
+        "#,
+    )
+    .bind(deck_id)
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Rate a deck, replacing the user's earlier rating
+pub async fn rate<'e, E>(
+    executor: E,
+    deck_id: Uuid,
+    user_id: Uuid,
+    stars: i16,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO deck_ratings (deck_id, user_id, stars)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (deck_id, user_id) DO UPDATE SET stars = EXCLUDED.stars
+            WHERE deck_ratings.stars IS DISTINCT FROM EXCLUDED.stars
+        "#,
+    )
+    .bind(deck_id)
+    .bind(user_id)
+    .bind(stars)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Withdraw the user's rating; false if they had not rated the deck
+pub async fn unrate<'e, E>(executor: E, deck_id: Uuid, user_id: Uuid) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM deck_ratings WHERE deck_id = $1 AND user_id = $2
+        "#,
+    )
+    .bind(deck_id)
+    .bind(user_id)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// A page of a deck's visible comments, newest first, starting before `before`
+pub async fn list_comments<'e, E>(
+    executor: E,
+    deck_id: Uuid,
+    before: Option<DateTime<Utc>>,
+    limit: i64,
+) -> Result<Vec<DeckComment>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT c.id, c.user_id, u.username, c.body, c.created_at, c.updated_at
+            FROM deck_comments c
+            JOIN users u ON u.id = c.user_id AND u.deleted_at IS NULL
+            WHERE c.deck_id = $1
+              AND c.hidden_at IS NULL
+              AND ($2::timestamptz IS NULL OR c.created_at < $2)
+            ORDER BY c.created_at DESC, c.id
+            LIMIT $3
+        "#,
+    )
+    .bind(deck_id)
+    .bind(before)
+    .bind(limit)
+    .fetch_all(executor)
+    .await
+}
+
+pub async fn create_comment<'e, E>(
+    executor: E,
+    deck_id: Uuid,
+    user_id: Uuid,
+    body: &str,
+) -> Result<DeckComment, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            WITH c AS (
+                INSERT INTO deck_comments (deck_id, user_id, body)
+                VALUES ($1, $2, $3)
+                RETURNING id, user_id, body, created_at, updated_at
+            )
+            SELECT c.id, c.user_id, u.username, c.body, c.created_at, c.updated_at
+            FROM c
+            JOIN users u ON u.id = c.user_id
+        "#,
+    )
+    .bind(deck_id)
+    .bind(user_id)
+    .bind(body)
+    .fetch_one(executor)
+    .await
+}
+
+/// Who wrote a comment on the deck, or None if there is no such comment
+pub async fn find_comment_author<'e, E>(
+    executor: E,
+    deck_id: Uuid,
+    comment_id: Uuid,
+) -> Result<Option<Uuid>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT user_id FROM deck_comments WHERE id = $1 AND deck_id = $2
+        "#,
+    )
+    .bind(comment_id)
+    .bind(deck_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Change the text of the user's own comment; None if they did not write it
+pub async fn update_comment<'e, E>(
+    executor: E,
+    deck_id: Uuid,
+    comment_id: Uuid,
+    user_id: Uuid,
+    body: &str,
+) -> Result<Option<DeckComment>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            WITH c AS (
+                UPDATE deck_comments
+                SET body = $4, updated_at = NOW()
+                WHERE id = $1 AND deck_id = $2 AND user_id = $3
+                RETURNING id, user_id, body, created_at, updated_at
+            )
+            SELECT c.id, c.user_id, u.username, c.body, c.created_at, c.updated_at
+            FROM c
+            JOIN users u ON u.id = c.user_id
+        "#,
+    )
+    .bind(comment_id)
+    .bind(deck_id)
+    .bind(user_id)
+    .bind(body)
+    .fetch_optional(executor)
+    .await
+}
+
+pub async fn delete_comment<'e, E>(
+    executor: E,
+    deck_id: Uuid,
+    comment_id: Uuid,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM deck_comments WHERE id = $1 AND deck_id = $2
+        "#,
+    )
+    .bind(comment_id)
+    .bind(deck_id)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Hide a comment, recording the moderator, or show it again
+pub async fn set_comment_hidden<'e, E>(
+    executor: E,
+    deck_id: Uuid,
+    comment_id: Uuid,
+    moderator_id: Option<Uuid>,
+    hidden: bool,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE deck_comments
+            SET hidden_at = CASE WHEN $3 THEN COALESCE(hidden_at, NOW()) END,
+                hidden_by = CASE WHEN $3 THEN $4 END
+            WHERE id = $1 AND deck_id = $2
+        "#,
+    )
+    .bind(comment_id)
+    .bind(deck_id)
+    .bind(hidden)
+    .bind(moderator_id)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,56 @@
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::models::{DeckSettings, NewDeckSettings};
+
+/// The scheduling overrides of a deck, `None` while it has none
+pub async fn find<'e, E>(executor: E, deck_id: Uuid) -> Result<Option<DeckSettings>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT max_interval_days, starting_ease, new_card_order, modes, updated_at
+            FROM deck_settings
+            WHERE deck_id = $1
+        "#,
+    )
+    .bind(deck_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Store the scheduling overrides of a deck, replacing the previous ones
+pub async fn save<'e, E>(
+    executor: E,
+    deck_id: Uuid,
+    settings: &NewDeckSettings<'_>,
+) -> Result<DeckSettings, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO deck_settings (
+                deck_id, max_interval_days, starting_ease, new_card_order, modes
+            )
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (deck_id) DO UPDATE
+            SET max_interval_days = EXCLUDED.max_interval_days,
+                starting_ease = EXCLUDED.starting_ease,
+                new_card_order = EXCLUDED.new_card_order,
+                modes = EXCLUDED.modes,
+                updated_at = NOW()
+            RETURNING max_interval_days, starting_ease, new_card_order, modes, updated_at
+        "#,
+    )
+    .bind(deck_id)
+    .bind(settings.max_interval_days)
+    .bind(settings.starting_ease)
+    .bind(settings.new_card_order)
+    .bind(&settings.modes)
+    .fetch_one(executor)
+    .await
+}
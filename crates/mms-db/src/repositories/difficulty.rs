@@ -0,0 +1,76 @@
+//! Global per-card difficulty, aggregated across all users.
+
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::models::CardReviewTotals;
+
+/// Review totals for every card reviewed at least `min_reviews` times
+pub async fn list_card_review_totals<'e, E>(
+    executor: E,
+    min_reviews: i64,
+) -> Result<Vec<CardReviewTotals>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                flashcard_id,
+                SUM(times_correct + times_wrong)::BIGINT AS reviews,
+                SUM(times_wrong)::BIGINT AS failures,
+                COUNT(*) AS learners,
+                SUM(total_response_ms)::BIGINT AS total_response_ms,
+                SUM(timed_reviews)::BIGINT AS timed_reviews
+            FROM user_card_progress
+            GROUP BY flashcard_id
+            HAVING SUM(times_correct + times_wrong) >= $1
+        "#,
+    )
+    .bind(min_reviews)
+    .fetch_all(executor)
+    .await
+}
+
+/// Replace the stored difficulty of a batch of cards.
+///
+/// All slices are parallel: element `i` of each belongs to `flashcard_ids[i]`.
+pub async fn upsert_difficulty_scores<'e, E>(
+    executor: E,
+    flashcard_ids: &[Uuid],
+    scores: &[f32],
+    failure_rates: &[f32],
+    avg_response_ms: &[Option<i32>],
+    reviews: &[i32],
+    learners: &[i32],
+) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO flashcard_difficulty
+                (flashcard_id, score, failure_rate, avg_response_ms, reviews, learners, updated_at)
+            SELECT *, NOW()
+            FROM UNNEST($1::UUID[], $2::REAL[], $3::REAL[], $4::INT[], $5::INT[], $6::INT[])
+            ON CONFLICT (flashcard_id) DO UPDATE
+            SET score = EXCLUDED.score,
+                failure_rate = EXCLUDED.failure_rate,
+                avg_response_ms = EXCLUDED.avg_response_ms,
+                reviews = EXCLUDED.reviews,
+                learners = EXCLUDED.learners,
+                updated_at = EXCLUDED.updated_at
+        "#,
+    )
+    .bind(flashcard_ids)
+    .bind(scores)
+    .bind(failure_rates)
+    .bind(avg_response_ms)
+    .bind(reviews)
+    .bind(learners)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected())
+}
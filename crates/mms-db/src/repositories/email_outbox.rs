@@ -0,0 +1,140 @@
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::models::{EncryptedString, OutboxEmail};
+
+/// Record an email about to be sent; it is not retried before `lease_until`
+pub async fn insert<'e, E>(
+    executor: E,
+    to_email: &str,
+    subject: &str,
+    body: &str,
+    lease_until: DateTime<Utc>,
+) -> Result<Uuid, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO email_outbox (to_email, subject, body, next_attempt_at)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id
+        "#,
+    )
+    .bind(EncryptedString::from(to_email))
+    .bind(subject)
+    .bind(EncryptedString::from(body))
+    .bind(lease_until)
+    .fetch_one(executor)
+    .await
+}
+
+/// Claim up to `limit` pending emails whose next attempt is due.
+///
+/// Pushes their next attempt to `lease_until`, so concurrent callers never
+/// claim the same email while it is being sent.
+pub async fn claim_due<'e, E>(
+    executor: E,
+    lease_until: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<OutboxEmail>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            WITH due AS (
+                SELECT id
+                FROM email_outbox
+                WHERE status = 'pending' AND next_attempt_at <= NOW()
+                ORDER BY next_attempt_at
+                LIMIT $2
+                FOR UPDATE SKIP LOCKED
+            )
+            UPDATE email_outbox o
+            SET next_attempt_at = $1
+            FROM due
+            WHERE o.id = due.id
+            RETURNING o.id, o.to_email, o.subject, o.body, o.attempts
+        "#,
+    )
+    .bind(lease_until)
+    .bind(limit)
+    .fetch_all(executor)
+    .await
+}
+
+pub async fn mark_sent<'e, E>(executor: E, id: Uuid) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE email_outbox
+            SET status = 'sent', attempts = attempts + 1, last_error = NULL, sent_at = NOW()
+            WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Record a failed attempt; retried at `retry_at`, or given up on when it is `None`
+pub async fn mark_failed<'e, E>(
+    executor: E,
+    id: Uuid,
+    error: &str,
+    retry_at: Option<DateTime<Utc>>,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE email_outbox
+            SET attempts = attempts + 1,
+                last_error = $2,
+                status = CASE WHEN $3::timestamptz IS NULL THEN 'failed' ELSE 'pending' END,
+                next_attempt_at = COALESCE($3, next_attempt_at)
+            WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(error)
+    .bind(retry_at)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Delete sent emails older than `sent_days` and failed ones older than `failed_days`
+pub async fn delete_finished<'e, E>(
+    executor: E,
+    sent_days: i32,
+    failed_days: i32,
+) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM email_outbox
+            WHERE (status = 'sent' AND created_at < NOW() - make_interval(days => $1))
+               OR (status = 'failed' AND created_at < NOW() - make_interval(days => $2))
+        "#,
+    )
+    .bind(sent_days)
+    .bind(failed_days)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
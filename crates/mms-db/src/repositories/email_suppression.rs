@@ -0,0 +1,86 @@
+use sqlx::{Executor, Postgres};
+
+use crate::models::EmailSuppression;
+
+/// Record a bounce or complaint for an address, suppressing it from now on
+pub async fn record<'e, E>(
+    executor: E,
+    email: &str,
+    reason: &str,
+    provider: &str,
+    detail: Option<&str>,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO email_suppressions (email, reason, provider, detail)
+            VALUES (lower($1), $2, $3, $4)
+            ON CONFLICT (email) DO UPDATE
+            SET reason = EXCLUDED.reason,
+                provider = EXCLUDED.provider,
+                detail = EXCLUDED.detail,
+                events = email_suppressions.events + 1,
+                updated_at = NOW()
+        "#,
+    )
+    .bind(email)
+    .bind(reason)
+    .bind(provider)
+    .bind(detail)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+pub async fn is_suppressed<'e, E>(executor: E, email: &str) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT EXISTS (SELECT 1 FROM email_suppressions WHERE email = lower($1))
+        "#,
+    )
+    .bind(email)
+    .fetch_one(executor)
+    .await
+}
+
+pub async fn find<'e, E>(executor: E, email: &str) -> Result<Option<EmailSuppression>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT email, reason, provider, detail, events, created_at, updated_at
+            FROM email_suppressions
+            WHERE email = lower($1)
+        "#,
+    )
+    .bind(email)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Clear a suppression so the address gets email again; returns whether there was one
+pub async fn delete<'e, E>(executor: E, email: &str) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM email_suppressions WHERE email = lower($1)
+        "#,
+    )
+    .bind(email)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,494 @@
+//! Editing flashcards, moving them between decks and merging duplicates.
+//!
+//! Each function is one statement; callers run them in a single transaction
+//! so an edit, move or merge is never half applied.
+
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::models::{Flashcard, FlashcardEdit, FlashcardWithAnswers};
+use crate::tenancy::Tenant;
+
+/// The deck's language pair, whichever organization it belongs to, locked
+/// until the transaction ends
+pub async fn lock_deck_languages<'e, E>(
+    executor: E,
+    deck_id: Uuid,
+) -> Result<Option<(String, String)>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            -- tenancy: all tenants (operators edit any organization's decks)
+            SELECT language_from, language_to FROM decks WHERE id = $1 FOR UPDATE
+        "#,
+    )
+    .bind(deck_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// The cards among `flashcard_ids` that exist, locked until the transaction ends
+pub async fn lock_cards<'e, E>(
+    executor: E,
+    flashcard_ids: &[Uuid],
+) -> Result<Vec<FlashcardWithAnswers>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT id, term, translation, language_from, language_to, accepted_answers
+            FROM flashcards
+            WHERE id = ANY($1)
+            ORDER BY id
+            FOR UPDATE
+        "#,
+    )
+    .bind(flashcard_ids)
+    .fetch_all(executor)
+    .await
+}
+
+/// The cards among `flashcard_ids` the deck contains
+pub async fn cards_in_deck<'e, E>(
+    executor: E,
+    deck_id: Uuid,
+    flashcard_ids: &[Uuid],
+) -> Result<Vec<Uuid>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT flashcard_id FROM deck_flashcards
+            WHERE deck_id = $1 AND flashcard_id = ANY($2)
+        "#,
+    )
+    .bind(deck_id)
+    .bind(flashcard_ids)
+    .fetch_all(executor)
+    .await
+}
+
+/// Decks containing any of the cards
+pub async fn decks_containing<'e, E>(
+    executor: E,
+    flashcard_ids: &[Uuid],
+) -> Result<Vec<Uuid>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT DISTINCT deck_id FROM deck_flashcards WHERE flashcard_id = ANY($1)
+        "#,
+    )
+    .bind(flashcard_ids)
+    .fetch_all(executor)
+    .await
+}
+
+/// Decks containing any of the cards, share-locking the links so they cannot
+/// be moved or removed before the transaction ends
+pub async fn lock_decks_containing<'e, E>(
+    executor: E,
+    flashcard_ids: &[Uuid],
+) -> Result<Vec<Uuid>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let mut decks: Vec<Uuid> = sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT deck_id
+            FROM deck_flashcards
+            WHERE flashcard_id = ANY($1)
+            ORDER BY deck_id, flashcard_id
+            FOR SHARE
+        "#,
+    )
+    .bind(flashcard_ids)
+    .fetch_all(executor)
+    .await?;
+    decks.dedup();
+    Ok(decks)
+}
+
+/// Unlink the cards from `from_deck_id` and link them to `to_deck_id`.
+///
+/// Returns how many cards were newly linked; cards the target deck already
+/// had are only unlinked from the source.
+pub async fn move_cards<'e, E>(
+    executor: E,
+    from_deck_id: Uuid,
+    to_deck_id: Uuid,
+    flashcard_ids: &[Uuid],
+) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            WITH unlinked AS (
+                DELETE FROM deck_flashcards
+                WHERE deck_id = $1 AND flashcard_id = ANY($3)
+                RETURNING flashcard_id
+            )
+            INSERT INTO deck_flashcards (deck_id, flashcard_id)
+            SELECT $2, flashcard_id FROM unlinked
+            ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(from_deck_id)
+    .bind(to_deck_id)
+    .bind(flashcard_ids)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Replace the answers graded as correct besides the card's translation
+pub async fn set_accepted_answers<'e, E>(
+    executor: E,
+    flashcard_id: Uuid,
+    accepted_answers: &[String],
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE flashcards SET accepted_answers = $2 WHERE id = $1
+        "#,
+    )
+    .bind(flashcard_id)
+    .bind(accepted_answers)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Link `target_id` to every deck `source_id` is in, and unlink `source_id`
+pub async fn relink_decks<'e, E>(
+    executor: E,
+    source_id: Uuid,
+    target_id: Uuid,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            WITH unlinked AS (
+                DELETE FROM deck_flashcards WHERE flashcard_id = $1 RETURNING deck_id
+            )
+            INSERT INTO deck_flashcards (deck_id, flashcard_id)
+            SELECT deck_id, $2 FROM unlinked
+            ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(source_id)
+    .bind(target_id)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Move learners' progress on `source_id` to `target_id`.
+///
+/// A learner who studied both cards keeps one record: counts and response
+/// times are added up, the latest review and the earliest due date are kept,
+/// and the card is mastered when the combined counts reach
+/// `mastery_threshold`. Per-mode scores are added up the same way. Returns how
+/// many learners had progress on the source.
+pub async fn merge_progress<'e, E>(
+    executor: E,
+    source_id: Uuid,
+    target_id: Uuid,
+    mastery_threshold: i32,
+) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            WITH removed_modes AS (
+                DELETE FROM user_card_mode_progress WHERE flashcard_id = $1
+                RETURNING user_id, mode, times_correct, times_wrong, last_review_at
+            ), merged_modes AS (
+                INSERT INTO user_card_mode_progress (
+                    user_id, flashcard_id, mode, times_correct, times_wrong, last_review_at
+                )
+                SELECT user_id, $2, mode, times_correct, times_wrong, last_review_at
+                FROM removed_modes
+                ON CONFLICT (user_id, flashcard_id, mode) DO UPDATE SET
+                    times_correct = user_card_mode_progress.times_correct + EXCLUDED.times_correct,
+                    times_wrong = user_card_mode_progress.times_wrong + EXCLUDED.times_wrong,
+                    last_review_at = GREATEST(
+                        user_card_mode_progress.last_review_at, EXCLUDED.last_review_at
+                    )
+            ), removed AS (
+                DELETE FROM user_card_progress WHERE flashcard_id = $1
+                RETURNING user_id, next_review_at, last_review_at, times_correct, times_wrong,
+                          mastered_at, total_response_ms, timed_reviews
+            )
+            INSERT INTO user_card_progress (
+                user_id, flashcard_id, next_review_at, last_review_at, times_correct,
+                times_wrong, mastered_at, total_response_ms, timed_reviews
+            )
+            SELECT user_id, $2, next_review_at, last_review_at, times_correct, times_wrong,
+                   mastered_at, total_response_ms, timed_reviews
+            FROM removed
+            ON CONFLICT (user_id, flashcard_id) DO UPDATE SET
+                next_review_at = LEAST(user_card_progress.next_review_at, EXCLUDED.next_review_at),
+                last_review_at = GREATEST(user_card_progress.last_review_at, EXCLUDED.last_review_at),
+                times_correct = user_card_progress.times_correct + EXCLUDED.times_correct,
+                times_wrong = user_card_progress.times_wrong + EXCLUDED.times_wrong,
+                mastered_at = CASE
+                    WHEN (user_card_progress.times_correct + EXCLUDED.times_correct)
+                        - (user_card_progress.times_wrong + EXCLUDED.times_wrong) >= $3
+                    THEN LEAST(user_card_progress.mastered_at, EXCLUDED.mastered_at, NOW())
+                END,
+                total_response_ms = user_card_progress.total_response_ms + EXCLUDED.total_response_ms,
+                timed_reviews = user_card_progress.timed_reviews + EXCLUDED.timed_reviews
+        "#,
+    )
+    .bind(source_id)
+    .bind(target_id)
+    .bind(mastery_threshold)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Point review history, practice sessions and reports about `source_id` at
+/// `target_id`.
+///
+/// Sessions that served both cards and learners who reported both keep only
+/// the target's entry; the source's go when the source card is deleted.
+pub async fn reassign_history<'e, E>(
+    executor: E,
+    source_id: Uuid,
+    target_id: Uuid,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            WITH logs AS (
+                UPDATE review_logs SET flashcard_id = $2 WHERE flashcard_id = $1
+            ),
+            sessions AS (
+                UPDATE practice_session_cards psc SET flashcard_id = $2
+                WHERE psc.flashcard_id = $1
+                  AND NOT EXISTS (
+                      SELECT 1 FROM practice_session_cards other
+                      WHERE other.session_id = psc.session_id AND other.flashcard_id = $2
+                  )
+            )
+            UPDATE content_reports cr SET target_id = $2
+            WHERE cr.target_type = 'card' AND cr.target_id = $1
+              AND NOT (cr.status = 'open' AND EXISTS (
+                  SELECT 1 FROM content_reports other
+                  WHERE other.target_type = 'card' AND other.target_id = $2
+                    AND other.status = 'open' AND other.reporter_id = cr.reporter_id
+              ))
+        "#,
+    )
+    .bind(source_id)
+    .bind(target_id)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// The card, unless it does not exist or was hidden by a moderator
+pub async fn find_visible_card<'e, E>(
+    executor: E,
+    flashcard_id: Uuid,
+) -> Result<Option<Flashcard>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT id, term, translation, language_from, language_to
+            FROM flashcards
+            WHERE id = $1 AND hidden_at IS NULL
+        "#,
+    )
+    .bind(flashcard_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Whether the card is in a deck `tenant` may see and has not been hidden by a
+/// moderator
+pub async fn card_visible<'e, E>(
+    executor: E,
+    flashcard_id: Uuid,
+    tenant: Tenant,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT EXISTS (
+                SELECT 1
+                FROM flashcards f
+                JOIN deck_flashcards df ON df.flashcard_id = f.id
+                JOIN decks d ON d.id = df.deck_id
+                WHERE f.id = $1 AND f.hidden_at IS NULL AND org_visible(d.org_id, $2)
+            )
+        "#,
+    )
+    .bind(flashcard_id)
+    .bind(tenant.viewer())
+    .fetch_one(executor)
+    .await
+}
+
+/// Whether the card is in a deck `tenant` may see, hidden or not
+pub async fn card_in_visible_deck<'e, E>(
+    executor: E,
+    flashcard_id: Uuid,
+    tenant: Tenant,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT EXISTS (
+                SELECT 1
+                FROM deck_flashcards df
+                JOIN decks d ON d.id = df.deck_id
+                WHERE df.flashcard_id = $1 AND org_visible(d.org_id, $2)
+            )
+        "#,
+    )
+    .bind(flashcard_id)
+    .bind(tenant.viewer())
+    .fetch_one(executor)
+    .await
+}
+
+/// Change the card's term and translation
+pub async fn set_text<'e, E>(
+    executor: E,
+    flashcard_id: Uuid,
+    term: &str,
+    translation: &str,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE flashcards SET term = $2, translation = $3 WHERE id = $1
+        "#,
+    )
+    .bind(flashcard_id)
+    .bind(term)
+    .bind(translation)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Record an edit of the card's term or translation
+pub async fn record_edit<'e, E>(
+    executor: E,
+    old: &FlashcardWithAnswers,
+    new_term: &str,
+    new_translation: &str,
+    edited_by: Option<Uuid>,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO flashcard_history (
+                flashcard_id, old_term, old_translation, new_term, new_translation, edited_by
+            )
+            VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+    )
+    .bind(old.id)
+    .bind(&old.term)
+    .bind(&old.translation)
+    .bind(new_term)
+    .bind(new_translation)
+    .bind(edited_by)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// The card's edits, newest first
+pub async fn list_history<'e, E>(
+    executor: E,
+    flashcard_id: Uuid,
+) -> Result<Vec<FlashcardEdit>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT h.id, h.old_term, h.old_translation, h.new_term, h.new_translation,
+                   h.edited_by, u.username AS editor_username, h.edited_at
+            FROM flashcard_history h
+            LEFT JOIN users u ON u.id = h.edited_by AND u.deleted_at IS NULL
+            WHERE h.flashcard_id = $1
+            ORDER BY h.edited_at DESC, h.id
+        "#,
+    )
+    .bind(flashcard_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// One edit of the card
+pub async fn find_edit<'e, E>(
+    executor: E,
+    flashcard_id: Uuid,
+    edit_id: Uuid,
+) -> Result<Option<FlashcardEdit>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT h.id, h.old_term, h.old_translation, h.new_term, h.new_translation,
+                   h.edited_by, u.username AS editor_username, h.edited_at
+            FROM flashcard_history h
+            LEFT JOIN users u ON u.id = h.edited_by AND u.deleted_at IS NULL
+            WHERE h.flashcard_id = $1 AND h.id = $2
+        "#,
+    )
+    .bind(flashcard_id)
+    .bind(edit_id)
+    .fetch_optional(executor)
+    .await
+}
@@ -0,0 +1,145 @@
+//! Precomputed review forecasts: cards due per local day, one row per user.
+
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::models::StoredForecast;
+
+/// Recompute the forecasts of users who practised in the last `active_days`
+/// days, or of `user_id` alone, storing `days` days from each user's local today.
+///
+/// Returns the number of forecasts written.
+pub async fn refresh<'e, E>(
+    executor: E,
+    user_id: Option<Uuid>,
+    days: i32,
+    active_days: i32,
+) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            WITH target AS (
+                SELECT u.id AS user_id, u.timezone AS tz, (NOW() AT TIME ZONE u.timezone)::date AS today
+                FROM users u
+                WHERE CASE
+                    WHEN $1::uuid IS NULL THEN EXISTS (
+                        SELECT 1 FROM user_activity a
+                        WHERE a.user_id = u.id AND a.activity_date >= CURRENT_DATE - $3::int
+                    )
+                    ELSE u.id = $1
+                END
+            ),
+            counts AS (
+                SELECT
+                    t.user_id,
+                    GREATEST((p.next_review_at AT TIME ZONE t.tz)::date - t.today, 0) AS day,
+                    COUNT(*)::int AS due
+                FROM target t
+                JOIN user_card_progress p ON p.user_id = t.user_id
+                WHERE p.next_review_at < (t.today + $2::int)::timestamp AT TIME ZONE t.tz
+                GROUP BY 1, 2
+            )
+            INSERT INTO review_forecasts (user_id, starts_on, due_counts, computed_at)
+            SELECT t.user_id, t.today, array_agg(COALESCE(c.due, 0) ORDER BY s.day), NOW()
+            FROM target t
+            CROSS JOIN generate_series(0, $2::int - 1) AS s(day)
+            LEFT JOIN counts c ON c.user_id = t.user_id AND c.day = s.day
+            GROUP BY t.user_id, t.today
+            ON CONFLICT (user_id) DO UPDATE
+            SET starts_on = EXCLUDED.starts_on,
+                due_counts = EXCLUDED.due_counts,
+                computed_at = EXCLUDED.computed_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(days)
+    .bind(active_days)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// The user's stored forecast, looked up by primary key
+pub async fn find<'e, E>(executor: E, user_id: Uuid) -> Result<Option<StoredForecast>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT f.starts_on, user_local_date(f.user_id) AS today, f.due_counts, f.computed_at
+            FROM review_forecasts f
+            WHERE f.user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Move a reviewed card from the day it was due (None for a new card) to the
+/// day it is due next. Users without a stored forecast are left alone.
+pub async fn apply_review<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    previous_due: Option<DateTime<Utc>>,
+    next_due: DateTime<Utc>,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            WITH slot AS (
+                SELECT
+                    GREATEST(($2::timestamptz AT TIME ZONE u.timezone)::date - f.starts_on, 0) + 1 AS previous,
+                    GREATEST(($3::timestamptz AT TIME ZONE u.timezone)::date - f.starts_on, 0) + 1 AS next
+                FROM review_forecasts f
+                JOIN users u ON u.id = f.user_id
+                WHERE f.user_id = $1
+            )
+            UPDATE review_forecasts f
+            SET due_counts = ARRAY(
+                SELECT GREATEST(
+                    c.due
+                        - CASE WHEN c.day = slot.previous THEN 1 ELSE 0 END
+                        + CASE WHEN c.day = slot.next THEN 1 ELSE 0 END,
+                    0
+                )
+                FROM unnest(f.due_counts) WITH ORDINALITY AS c(due, day)
+                ORDER BY c.day
+            )
+            FROM slot
+            WHERE f.user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .bind(previous_due)
+    .bind(next_due)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Drop the user's stored forecast after many cards were rescheduled at once;
+/// the next read recomputes it
+pub async fn invalidate<'e, E>(executor: E, user_id: Uuid) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM review_forecasts WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::models::Friend;
+
+/// Add `friend_id` to the user's friends; returns false if already added
+pub async fn add<'e, E>(executor: E, user_id: Uuid, friend_id: Uuid) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO friendships (user_id, friend_id)
+            VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(user_id)
+    .bind(friend_id)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Remove `friend_id` from the user's friends; returns false if they were not a friend
+pub async fn remove<'e, E>(executor: E, user_id: Uuid, friend_id: Uuid) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM friendships WHERE user_id = $1 AND friend_id = $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(friend_id)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// The users the user added, by username
+pub async fn list<'e, E>(executor: E, user_id: Uuid) -> Result<Vec<Friend>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT u.id AS user_id, u.username, u.profile_picture_url, f.created_at AS added_at
+            FROM friendships f
+            JOIN users u ON u.id = f.friend_id AND u.deleted_at IS NULL
+            WHERE f.user_id = $1
+            ORDER BY u.username
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(executor)
+    .await
+}
@@ -0,0 +1,404 @@
+//! Study groups, stored as organizations with an invite code.
+
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::models::{Group, GroupAssignment, GroupMember, GroupSummary, MemberDeckProgress};
+
+/// Create a group owned by `owner_id`
+pub async fn create<'e, E>(executor: E, name: &str, owner_id: Uuid) -> Result<Group, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            WITH g AS (
+                INSERT INTO organizations (name, created_by)
+                VALUES ($1, $2)
+                RETURNING id, name, invite_code, created_at
+            ),
+            owner AS (
+                INSERT INTO organization_members (org_id, user_id, role)
+                SELECT id, $2, 'owner' FROM g
+            )
+            SELECT id, name, invite_code, created_at FROM g
+        "#,
+    )
+    .bind(name)
+    .bind(owner_id)
+    .fetch_one(executor)
+    .await
+}
+
+pub async fn find<'e, E>(executor: E, group_id: Uuid) -> Result<Option<Group>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT id, name, invite_code, created_at FROM organizations WHERE id = $1
+        "#,
+    )
+    .bind(group_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// The group an invite code opens, ignoring case
+pub async fn find_by_invite_code<'e, E>(
+    executor: E,
+    invite_code: &str,
+) -> Result<Option<Group>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT id, name, invite_code, created_at FROM organizations WHERE invite_code = upper($1)
+        "#,
+    )
+    .bind(invite_code)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Groups the user belongs to, by name
+pub async fn list_for_user<'e, E>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<Vec<GroupSummary>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                o.id,
+                o.name,
+                m.role,
+                (SELECT COUNT(*) FROM organization_members c WHERE c.org_id = o.id) AS member_count,
+                o.created_at
+            FROM organization_members m
+            JOIN organizations o ON o.id = m.org_id
+            WHERE m.user_id = $1
+            ORDER BY o.name, o.id
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// The user's role in the group, or `None` if they are not a member
+pub async fn find_role<'e, E>(
+    executor: E,
+    group_id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<String>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT role FROM organization_members WHERE org_id = $1 AND user_id = $2
+        "#,
+    )
+    .bind(group_id)
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Add the user as a member; returns false if they already belong to the group
+pub async fn add_member<'e, E>(
+    executor: E,
+    group_id: Uuid,
+    user_id: Uuid,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO organization_members (org_id, user_id, role)
+            VALUES ($1, $2, 'member')
+            ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(group_id)
+    .bind(user_id)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Members of the group: owner first, then teachers, then learners by name
+pub async fn list_members<'e, E>(
+    executor: E,
+    group_id: Uuid,
+) -> Result<Vec<GroupMember>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT m.user_id, u.username, m.role, m.joined_at
+            FROM organization_members m
+            JOIN users u ON u.id = m.user_id AND u.deleted_at IS NULL
+            WHERE m.org_id = $1
+            ORDER BY
+                CASE m.role WHEN 'owner' THEN 0 WHEN 'teacher' THEN 1 ELSE 2 END,
+                u.username
+        "#,
+    )
+    .bind(group_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Change a member's role; the owner's role cannot change. Returns false if no such member.
+pub async fn update_member_role<'e, E>(
+    executor: E,
+    group_id: Uuid,
+    user_id: Uuid,
+    role: &str,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE organization_members
+            SET role = $3
+            WHERE org_id = $1 AND user_id = $2 AND role <> 'owner'
+        "#,
+    )
+    .bind(group_id)
+    .bind(user_id)
+    .bind(role)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Remove a member other than the owner; returns false if no such member
+pub async fn remove_member<'e, E>(
+    executor: E,
+    group_id: Uuid,
+    user_id: Uuid,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM organization_members
+            WHERE org_id = $1 AND user_id = $2 AND role <> 'owner'
+        "#,
+    )
+    .bind(group_id)
+    .bind(user_id)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Assign a deck `assigned_by` can see and the group's members will too.
+///
+/// Assigning a deck twice keeps the first assignment. Returns `None` when the
+/// deck does not exist or belongs to another organization.
+pub async fn assign_deck<'e, E>(
+    executor: E,
+    group_id: Uuid,
+    deck_id: Uuid,
+    assigned_by: Uuid,
+) -> Result<Option<GroupAssignment>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            WITH assignment AS (
+                INSERT INTO group_assignments (org_id, deck_id, assigned_by)
+                SELECT $1, d.id, $3
+                FROM decks d
+                WHERE d.id = $2
+                    AND (d.org_id IS NULL OR d.org_id = $1)
+                    AND org_visible(d.org_id, $3)
+                ON CONFLICT (org_id, deck_id) WHERE deck_id IS NOT NULL
+                    DO UPDATE SET assigned_at = group_assignments.assigned_at
+                RETURNING deck_id, roadmap_id, assigned_at
+            )
+            SELECT a.deck_id, a.roadmap_id, d.title, a.assigned_at
+            FROM assignment a
+            JOIN decks d ON d.id = a.deck_id AND org_visible(d.org_id, $3)
+        "#,
+    )
+    .bind(group_id)
+    .bind(deck_id)
+    .bind(assigned_by)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Assign a roadmap; works like [`assign_deck`]
+pub async fn assign_roadmap<'e, E>(
+    executor: E,
+    group_id: Uuid,
+    roadmap_id: Uuid,
+    assigned_by: Uuid,
+) -> Result<Option<GroupAssignment>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            WITH assignment AS (
+                INSERT INTO group_assignments (org_id, roadmap_id, assigned_by)
+                SELECT $1, r.id, $3
+                FROM roadmaps r
+                WHERE r.id = $2
+                    AND (r.org_id IS NULL OR r.org_id = $1)
+                    AND org_visible(r.org_id, $3)
+                ON CONFLICT (org_id, roadmap_id) WHERE roadmap_id IS NOT NULL
+                    DO UPDATE SET assigned_at = group_assignments.assigned_at
+                RETURNING deck_id, roadmap_id, assigned_at
+            )
+            SELECT a.deck_id, a.roadmap_id, r.title, a.assigned_at
+            FROM assignment a
+            JOIN roadmaps r ON r.id = a.roadmap_id AND org_visible(r.org_id, $3)
+        "#,
+    )
+    .bind(group_id)
+    .bind(roadmap_id)
+    .bind(assigned_by)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Unassign a deck; returns false if it was not assigned
+pub async fn unassign_deck<'e, E>(
+    executor: E,
+    group_id: Uuid,
+    deck_id: Uuid,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM group_assignments WHERE org_id = $1 AND deck_id = $2
+        "#,
+    )
+    .bind(group_id)
+    .bind(deck_id)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Unassign a roadmap; returns false if it was not assigned
+pub async fn unassign_roadmap<'e, E>(
+    executor: E,
+    group_id: Uuid,
+    roadmap_id: Uuid,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM group_assignments WHERE org_id = $1 AND roadmap_id = $2
+        "#,
+    )
+    .bind(group_id)
+    .bind(roadmap_id)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Decks and roadmaps assigned to the group, oldest assignment first
+pub async fn list_assignments<'e, E>(
+    executor: E,
+    group_id: Uuid,
+    viewer_id: Uuid,
+) -> Result<Vec<GroupAssignment>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT ga.deck_id, ga.roadmap_id, COALESCE(d.title, r.title) AS title, ga.assigned_at
+            FROM group_assignments ga
+            LEFT JOIN decks d ON d.id = ga.deck_id AND org_visible(d.org_id, $2)
+            LEFT JOIN roadmaps r ON r.id = ga.roadmap_id AND org_visible(r.org_id, $2)
+            WHERE ga.org_id = $1 AND (d.id IS NOT NULL OR r.id IS NOT NULL)
+            ORDER BY ga.assigned_at, ga.id
+        "#,
+    )
+    .bind(group_id)
+    .bind(viewer_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Each learner's progress on every assigned deck, including the decks of assigned roadmaps
+pub async fn member_progress<'e, E>(
+    executor: E,
+    group_id: Uuid,
+    viewer_id: Uuid,
+) -> Result<Vec<MemberDeckProgress>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            WITH assigned AS (
+                SELECT ga.deck_id FROM group_assignments ga
+                WHERE ga.org_id = $1 AND ga.deck_id IS NOT NULL
+                UNION
+                SELECT rn.deck_id FROM group_assignments ga
+                JOIN roadmap_nodes rn ON rn.roadmap_id = ga.roadmap_id
+                WHERE ga.org_id = $1
+            )
+            SELECT
+                m.user_id,
+                u.username,
+                d.id AS deck_id,
+                d.title AS deck_title,
+                COALESCE(udp.total_cards, (
+                    SELECT COUNT(*)::int FROM deck_flashcards df WHERE df.deck_id = d.id
+                )) AS total_cards,
+                COALESCE(udp.mastered_cards, 0) AS mastered_cards,
+                COALESCE(udp.progress_percentage, 0.0)::float8 AS progress_percentage,
+                udp.last_practiced_at
+            FROM organization_members m
+            JOIN users u ON u.id = m.user_id AND u.deleted_at IS NULL
+            CROSS JOIN assigned a
+            JOIN decks d ON d.id = a.deck_id
+            LEFT JOIN user_deck_progress udp ON udp.user_id = m.user_id AND udp.deck_id = d.id
+            WHERE m.org_id = $1 AND m.role = 'member' AND org_visible(d.org_id, $2)
+            ORDER BY u.username, m.user_id, d.title, d.id
+        "#,
+    )
+    .bind(group_id)
+    .bind(viewer_id)
+    .fetch_all(executor)
+    .await
+}
@@ -0,0 +1,129 @@
+use sqlx::{Executor, Postgres};
+
+use crate::models::IdempotentResponse;
+
+/// Reserve `key` for a request with `request_hash`.
+///
+/// Returns false when the key is already taken, unless the request holding it
+/// started more than `abandoned_after_secs` ago and never finished, in which
+/// case this request takes it over.
+pub async fn claim<'e, E>(
+    executor: E,
+    scope: &str,
+    key: &str,
+    request_hash: &[u8],
+    abandoned_after_secs: i32,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO idempotency_keys (scope, key, request_hash)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (scope, key) DO UPDATE
+                SET request_hash = EXCLUDED.request_hash, created_at = NOW()
+                WHERE idempotency_keys.status IS NULL
+                  AND idempotency_keys.created_at < NOW() - make_interval(secs => $4)
+        "#,
+    )
+    .bind(scope)
+    .bind(key)
+    .bind(request_hash)
+    .bind(abandoned_after_secs)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// The request holding `key`, and its response once it finished
+pub async fn find<'e, E>(
+    executor: E,
+    scope: &str,
+    key: &str,
+) -> Result<Option<IdempotentResponse>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT request_hash, status, content_type, body
+            FROM idempotency_keys
+            WHERE scope = $1 AND key = $2
+        "#,
+    )
+    .bind(scope)
+    .bind(key)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Store the response to the request holding `key`
+pub async fn complete<'e, E>(
+    executor: E,
+    scope: &str,
+    key: &str,
+    status: i16,
+    content_type: Option<&str>,
+    body: &[u8],
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE idempotency_keys
+            SET status = $3, content_type = $4, body = $5
+            WHERE scope = $1 AND key = $2
+        "#,
+    )
+    .bind(scope)
+    .bind(key)
+    .bind(status)
+    .bind(content_type)
+    .bind(body)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Free `key` so a retry runs the request again
+pub async fn release<'e, E>(executor: E, scope: &str, key: &str) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM idempotency_keys WHERE scope = $1 AND key = $2
+        "#,
+    )
+    .bind(scope)
+    .bind(key)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete_older_than<'e, E>(executor: E, hours: i32) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM idempotency_keys WHERE created_at < NOW() - make_interval(hours => $1)
+        "#,
+    )
+    .bind(hours)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,226 @@
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::models::{ClaimedJob, EncryptedString, JobSummary};
+
+/// Queue a job to run at `run_at`.
+///
+/// With a `unique_key`, nothing is queued while a job with the same key is
+/// pending or running; with `scheduled_for`, nothing is queued if that run of
+/// the schedule already was. Returns the new job's id, or `None` in those cases.
+pub async fn enqueue<'e, E>(
+    executor: E,
+    kind: &str,
+    payload: &str,
+    unique_key: Option<&str>,
+    scheduled_for: Option<DateTime<Utc>>,
+    max_attempts: i32,
+    run_at: DateTime<Utc>,
+) -> Result<Option<Uuid>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO jobs (kind, payload, unique_key, scheduled_for, max_attempts, run_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT DO NOTHING
+            RETURNING id
+        "#,
+    )
+    .bind(kind)
+    .bind(EncryptedString::from(payload))
+    .bind(unique_key)
+    .bind(scheduled_for)
+    .bind(max_attempts)
+    .bind(run_at)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Claim up to `limit` jobs that are due, or whose previous worker's lease ran out.
+///
+/// Claimed jobs are `running` until `lease_until` and count the run as an
+/// attempt, so a job that keeps crashing its worker still ends up dead.
+pub async fn claim_due<'e, E>(
+    executor: E,
+    lease_until: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<ClaimedJob>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            WITH due AS (
+                SELECT id
+                FROM jobs
+                WHERE (status = 'pending' AND run_at <= NOW())
+                   OR (status = 'running' AND locked_until < NOW())
+                ORDER BY run_at
+                LIMIT $2
+                FOR UPDATE SKIP LOCKED
+            )
+            UPDATE jobs j
+            SET status = 'running', locked_until = $1, attempts = j.attempts + 1
+            FROM due
+            WHERE j.id = due.id
+            RETURNING j.id, j.kind, j.payload, j.attempts, j.max_attempts
+        "#,
+    )
+    .bind(lease_until)
+    .bind(limit)
+    .fetch_all(executor)
+    .await
+}
+
+pub async fn mark_succeeded<'e, E>(executor: E, id: Uuid) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE jobs
+            SET status = 'succeeded', locked_until = NULL, last_error = NULL, finished_at = NOW()
+            WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Record a failed run; retried at `retry_at`, or dead-lettered when it is `None`
+pub async fn mark_failed<'e, E>(
+    executor: E,
+    id: Uuid,
+    error: &str,
+    retry_at: Option<DateTime<Utc>>,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE jobs
+            SET last_error = $2,
+                locked_until = NULL,
+                status = CASE WHEN $3::timestamptz IS NULL THEN 'dead' ELSE 'pending' END,
+                run_at = COALESCE($3, run_at),
+                finished_at = CASE WHEN $3::timestamptz IS NULL THEN NOW() END
+            WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(error)
+    .bind(retry_at)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Jobs in `status`, newest first
+pub async fn list_by_status<'e, E>(
+    executor: E,
+    status: &str,
+    limit: i64,
+) -> Result<Vec<JobSummary>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT id, kind, status, attempts, max_attempts, run_at, last_error,
+                   created_at, finished_at
+            FROM jobs
+            WHERE status = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+        "#,
+    )
+    .bind(status)
+    .bind(limit)
+    .fetch_all(executor)
+    .await
+}
+
+/// Give a dead job a fresh set of attempts, starting now; false if it is not dead
+pub async fn retry_dead<'e, E>(executor: E, id: Uuid) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE jobs
+            SET status = 'pending', attempts = 0, run_at = NOW(), finished_at = NULL
+            WHERE id = $1 AND status = 'dead'
+        "#,
+    )
+    .bind(id)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Delete succeeded jobs finished more than `succeeded_days` ago and dead ones more than `dead_days` ago
+pub async fn delete_finished<'e, E>(
+    executor: E,
+    succeeded_days: i32,
+    dead_days: i32,
+) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM jobs
+            WHERE (status = 'succeeded' AND finished_at < NOW() - make_interval(days => $1))
+               OR (status = 'dead' AND finished_at < NOW() - make_interval(days => $2))
+        "#,
+    )
+    .bind(succeeded_days)
+    .bind(dead_days)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Claim a run of the periodic job `job`.
+///
+/// Returns false when the job already started less than `min_gap_secs` ago,
+/// on this instance or another.
+pub async fn claim_periodic_run<'e, E>(
+    executor: E,
+    job: &str,
+    min_gap_secs: f64,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO periodic_job_runs (job)
+            VALUES ($1)
+            ON CONFLICT (job) DO UPDATE
+                SET started_at = NOW()
+                WHERE periodic_job_runs.started_at <= NOW() - make_interval(secs => $2)
+        "#,
+    )
+    .bind(job)
+    .bind(min_gap_secs)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
@@ -0,0 +1,105 @@
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+/// Give the user progress on every card in `language` made only of `words`.
+///
+/// Cards the user already has progress on are left alone. Each card gets
+/// `score` correct answers and is first due after between one and two times
+/// `interval_hours`, so a large import does not come due on a single day.
+/// Returns the ids of the cards marked.
+pub async fn mark_cards_with_words<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    language: &str,
+    words: &[String],
+    score: i32,
+    interval_hours: i64,
+) -> Result<Vec<Uuid>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO user_card_progress (user_id, flashcard_id, next_review_at, times_correct)
+            SELECT $1, fw.flashcard_id,
+                   NOW() + make_interval(hours => $5::int) * (1 + random()), $4
+            FROM flashcard_words fw
+            WHERE fw.language = $2
+            GROUP BY fw.flashcard_id
+            HAVING bool_and(fw.word = ANY($3))
+            ON CONFLICT (user_id, flashcard_id) DO NOTHING
+            RETURNING flashcard_id
+        "#,
+    )
+    .bind(user_id)
+    .bind(language)
+    .bind(words)
+    .bind(score)
+    .bind(interval_hours)
+    .fetch_all(executor)
+    .await
+}
+
+/// Give the user progress on every card of the decks in `language` tagged with one of `levels`.
+///
+/// Scheduling works as in [`mark_cards_with_words`].
+pub async fn mark_cards_at_levels<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    language: &str,
+    levels: &[&str],
+    score: i32,
+    interval_hours: i64,
+) -> Result<Vec<Uuid>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO user_card_progress (user_id, flashcard_id, next_review_at, times_correct)
+            SELECT DISTINCT ON (df.flashcard_id) $1, df.flashcard_id,
+                   NOW() + make_interval(hours => $5::int) * (1 + random()), $4
+            FROM decks d
+            JOIN deck_flashcards df ON df.deck_id = d.id
+            WHERE d.language_to = $2 AND d.cefr_level = ANY($3) AND org_visible(d.org_id, $1)
+            ON CONFLICT (user_id, flashcard_id) DO NOTHING
+            RETURNING flashcard_id
+        "#,
+    )
+    .bind(user_id)
+    .bind(language)
+    .bind(levels)
+    .bind(score)
+    .bind(interval_hours)
+    .fetch_all(executor)
+    .await
+}
+
+/// Recompute the user's progress on every deck containing one of `flashcard_ids`
+pub async fn refresh_decks_containing<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    flashcard_ids: &[Uuid],
+    mastery_threshold: i32,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            SELECT refresh_deck_progress($1, d.deck_id, $3)
+            FROM (
+                SELECT DISTINCT deck_id FROM deck_flashcards WHERE flashcard_id = ANY($2)
+            ) d
+        "#,
+    )
+    .bind(user_id)
+    .bind(flashcard_ids)
+    .bind(mastery_threshold)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
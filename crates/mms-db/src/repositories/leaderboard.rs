@@ -0,0 +1,86 @@
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::models::LeaderboardEntry;
+
+/// Recompute the week and month XP totals without blocking readers
+pub async fn refresh<'e, E>(executor: E) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            REFRESH MATERIALIZED VIEW CONCURRENTLY leaderboard_totals
+        "#,
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// When the totals were last computed; `None` if nobody earned XP in either period
+pub async fn refreshed_at<'e, E>(executor: E) -> Result<Option<DateTime<Utc>>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT MAX(refreshed_at) FROM leaderboard_totals
+        "#,
+    )
+    .fetch_one(executor)
+    .await
+}
+
+/// The top `limit` users of `period` (`week` or `month`) by XP, plus the user's own entry.
+///
+/// With `friends_only`, only the user and the users they added are ranked.
+/// Users who opted out or deleted their account are left out, including the user themselves.
+pub async fn list<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    period: &str,
+    friends_only: bool,
+    limit: i64,
+) -> Result<Vec<LeaderboardEntry>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            WITH ranked AS (
+                SELECT
+                    RANK() OVER (ORDER BY lt.xp DESC) AS rank,
+                    ROW_NUMBER() OVER (ORDER BY lt.xp DESC, u.username) AS position,
+                    lt.user_id,
+                    u.username,
+                    u.profile_picture_url,
+                    lt.xp,
+                    COALESCE(s.current_streak_days, 0) AS current_streak_days
+                FROM leaderboard_totals lt
+                JOIN users u ON u.id = lt.user_id AND NOT u.leaderboard_opt_out AND u.deleted_at IS NULL
+                LEFT JOIN user_stats s ON s.user_id = lt.user_id
+                WHERE lt.period = $2
+                    AND (
+                        NOT $3
+                        OR lt.user_id = $1
+                        OR lt.user_id IN (SELECT friend_id FROM friendships WHERE user_id = $1)
+                    )
+            )
+            SELECT rank, user_id, username, profile_picture_url, xp, current_streak_days
+            FROM ranked
+            WHERE position <= $4 OR user_id = $1
+            ORDER BY position
+        "#,
+    )
+    .bind(user_id)
+    .bind(period)
+    .bind(friends_only)
+    .bind(limit)
+    .fetch_all(executor)
+    .await
+}
@@ -0,0 +1,98 @@
+//! Consecutive failed logins per email address.
+//!
+//! Counted whether or not an account has the address, so requiring a captcha
+//! does not reveal which addresses have accounts. Addresses are stored as
+//! SHA-256 hashes of their normalized form. A count lapses `window_hours`
+//! after its last failure.
+
+use sqlx::{Executor, Postgres};
+
+/// Recent consecutive failures for the address
+pub async fn count<'e, E>(
+    executor: E,
+    email_hash: &str,
+    window_hours: i32,
+) -> Result<i32, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let attempts: Option<i32> = sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT attempts
+            FROM login_failures
+            WHERE email_hash = $1 AND last_failed_at > NOW() - make_interval(hours => $2)
+        "#,
+    )
+    .bind(email_hash)
+    .bind(window_hours)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(attempts.unwrap_or(0))
+}
+
+/// Count a failure, starting over when the previous count lapsed; returns the new count
+pub async fn record<'e, E>(
+    executor: E,
+    email_hash: &str,
+    window_hours: i32,
+) -> Result<i32, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO login_failures (email_hash, attempts, last_failed_at)
+            VALUES ($1, 1, NOW())
+            ON CONFLICT (email_hash) DO UPDATE
+            SET attempts = CASE
+                    WHEN login_failures.last_failed_at > NOW() - make_interval(hours => $2)
+                        THEN login_failures.attempts + 1
+                    ELSE 1
+                END,
+                last_failed_at = NOW()
+            RETURNING attempts
+        "#,
+    )
+    .bind(email_hash)
+    .bind(window_hours)
+    .fetch_one(executor)
+    .await
+}
+
+/// Forget the failures after a successful login
+pub async fn clear<'e, E>(executor: E, email_hash: &str) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM login_failures WHERE email_hash = $1
+        "#,
+    )
+    .bind(email_hash)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Delete counts that lapsed; returns how many were deleted
+pub async fn delete_lapsed<'e, E>(executor: E, window_hours: i32) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM login_failures
+            WHERE last_failed_at <= NOW() - make_interval(hours => $1)
+        "#,
+    )
+    .bind(window_hours)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,124 @@
+use sqlx::{Executor, Postgres};
+
+use crate::models::{IndexStats, StatementStats, TableScanStats};
+
+/// Check that the database answers
+pub async fn ping<'e, E>(executor: E) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query("SELECT 1").execute(executor).await?;
+    Ok(())
+}
+
+// --- Catalog and statistics views used by the index advisor ---
+//
+// All queries are scoped to the current schema so indexes on system tables and
+// other applications sharing the database never show up in reports.
+
+pub async fn list_index_stats<'e, E>(executor: E) -> Result<Vec<IndexStats>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                t.relname::text AS table_name,
+                i.relname::text AS index_name,
+                ARRAY(
+                    SELECT a.attname::text
+                    FROM unnest(ix.indkey::int2[]) WITH ORDINALITY AS k(attnum, ord)
+                    JOIN pg_attribute a ON a.attrelid = t.oid AND a.attnum = k.attnum
+                    WHERE k.ord <= ix.indnkeyatts
+                    ORDER BY k.ord
+                ) AS columns,
+                ix.indisunique AS is_unique,
+                ix.indisprimary AS is_primary,
+                ix.indpred IS NOT NULL AS is_partial,
+                COALESCE(s.idx_scan, 0) AS scans,
+                pg_relation_size(i.oid) AS size_bytes
+            FROM pg_index ix
+            JOIN pg_class i ON i.oid = ix.indexrelid
+            JOIN pg_class t ON t.oid = ix.indrelid
+            JOIN pg_namespace n ON n.oid = t.relnamespace
+            LEFT JOIN pg_stat_user_indexes s ON s.indexrelid = ix.indexrelid
+            WHERE n.nspname = current_schema()
+                AND t.relname NOT LIKE '\_sqlx%'
+            ORDER BY t.relname, i.relname
+        "#,
+    )
+    .fetch_all(executor)
+    .await
+}
+
+pub async fn list_table_scan_stats<'e, E>(executor: E) -> Result<Vec<TableScanStats>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                relname::text AS table_name,
+                COALESCE(seq_scan, 0) AS seq_scans,
+                COALESCE(seq_tup_read, 0) AS seq_rows_read,
+                COALESCE(idx_scan, 0) AS index_scans,
+                COALESCE(n_live_tup, 0) AS live_rows
+            FROM pg_stat_user_tables
+            WHERE schemaname = current_schema()
+                AND relname NOT LIKE '\_sqlx%'
+            ORDER BY relname
+        "#,
+    )
+    .fetch_all(executor)
+    .await
+}
+
+/// Whether `pg_stat_statements` is installed in this database.
+///
+/// The extension also has to be in `shared_preload_libraries`; callers should
+/// treat errors from [`list_hot_statements`] as "not available" too.
+pub async fn statement_stats_installed<'e, E>(executor: E) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'pg_stat_statements')
+        "#,
+    )
+    .fetch_one(executor)
+    .await
+}
+
+/// Statements in the current database ordered by total execution time
+pub async fn list_hot_statements<'e, E>(
+    executor: E,
+    limit: i64,
+) -> Result<Vec<StatementStats>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                query,
+                calls,
+                total_exec_time AS total_exec_ms,
+                mean_exec_time AS mean_exec_ms,
+                rows,
+                shared_blks_hit AS shared_blocks_hit,
+                shared_blks_read AS shared_blocks_read
+            FROM pg_stat_statements
+            WHERE dbid = (SELECT oid FROM pg_database WHERE datname = current_database())
+            ORDER BY total_exec_time DESC
+            LIMIT $1
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(executor)
+    .await
+}
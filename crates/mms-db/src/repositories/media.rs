@@ -0,0 +1,64 @@
+use sqlx::{Executor, Postgres};
+
+use crate::models::MediaObject;
+
+/// Store an object, replacing any object with the same key
+pub async fn put<'e, E>(
+    executor: E,
+    key: &str,
+    content_type: &str,
+    bytes: &[u8],
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO media_objects (key, content_type, bytes)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (key) DO UPDATE
+            SET content_type = EXCLUDED.content_type,
+                bytes = EXCLUDED.bytes,
+                created_at = NOW()
+        "#,
+    )
+    .bind(key)
+    .bind(content_type)
+    .bind(bytes)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+pub async fn get<'e, E>(executor: E, key: &str) -> Result<Option<MediaObject>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT content_type, bytes FROM media_objects WHERE key = $1
+        "#,
+    )
+    .bind(key)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Delete every object whose key starts with `prefix`, returning how many were deleted
+pub async fn delete_prefix<'e, E>(executor: E, prefix: &str) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM media_objects WHERE starts_with(key, $1)
+        "#,
+    )
+    .bind(prefix)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected())
+}
@@ -1,9 +1,42 @@
 // All repository functions are generic over `E: Executor<'e, Database = Postgres>`
 // so they accept both a `&PgPool` (direct query) and a `&mut Transaction` (atomic operations).
 
+pub mod achievement;
+pub mod api_key;
 pub mod auth;
+pub mod client_error;
+pub mod content;
+pub mod data_export;
 pub mod deck;
+pub mod deck_analytics;
+pub mod deck_review;
+pub mod deck_settings;
+pub mod difficulty;
+pub mod email_outbox;
+pub mod email_suppression;
+pub mod flashcard;
+pub mod forecast;
+pub mod friend;
+pub mod group;
+pub mod idempotency;
+pub mod job;
+pub mod known_word;
+pub mod leaderboard;
+pub mod login_failure;
+pub mod maintenance;
+pub mod media;
+pub mod notification;
+pub mod plan;
 pub mod practice;
+pub mod practice_session;
+pub mod preference;
+pub mod profile;
+pub mod report;
+pub mod review_log;
 pub mod roadmap;
+pub mod stats;
+pub mod sync;
 pub mod token;
 pub mod user;
+pub mod vocabulary;
+pub mod xp;
@@ -0,0 +1,167 @@
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::models::Notification;
+
+pub async fn create<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    kind: &str,
+    title: &str,
+    body: &str,
+    link: Option<&str>,
+) -> Result<Notification, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO notifications (user_id, kind, title, body, link)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, kind, title, body, link, read_at, created_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(kind)
+    .bind(title)
+    .bind(body)
+    .bind(link)
+    .fetch_one(executor)
+    .await
+}
+
+/// The user's notifications, newest first, starting after `before` when given
+pub async fn list_for_user<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    unread_only: bool,
+    before: Option<Uuid>,
+    limit: i64,
+) -> Result<Vec<Notification>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT id, kind, title, body, link, read_at, created_at
+            FROM notifications
+            WHERE user_id = $1
+              AND (NOT $2 OR read_at IS NULL)
+              AND (
+                  $3::uuid IS NULL
+                  OR (created_at, id) < (
+                      SELECT created_at, id FROM notifications WHERE id = $3 AND user_id = $1
+                  )
+              )
+            ORDER BY created_at DESC, id DESC
+            LIMIT $4
+        "#,
+    )
+    .bind(user_id)
+    .bind(unread_only)
+    .bind(before)
+    .bind(limit)
+    .fetch_all(executor)
+    .await
+}
+
+pub async fn count_unread<'e, E>(executor: E, user_id: Uuid) -> Result<i64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND read_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(executor)
+    .await
+}
+
+/// Mark a notification read, keeping the first read time; `None` if the user has no such notification
+pub async fn mark_read<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    notification_id: Uuid,
+) -> Result<Option<Notification>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            UPDATE notifications
+            SET read_at = COALESCE(read_at, NOW())
+            WHERE id = $2 AND user_id = $1
+            RETURNING id, kind, title, body, link, read_at, created_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(notification_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Mark every unread notification read; returns how many changed
+pub async fn mark_all_read<'e, E>(executor: E, user_id: Uuid) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE notifications SET read_at = NOW()
+            WHERE user_id = $1 AND read_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Returns `false` if the user has no such notification
+pub async fn delete<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    notification_id: Uuid,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM notifications WHERE id = $2 AND user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .bind(notification_id)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Delete notifications created more than `days` ago
+pub async fn delete_older_than<'e, E>(executor: E, days: i32) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM notifications WHERE created_at < NOW() - make_interval(days => $1)
+        "#,
+    )
+    .bind(days)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
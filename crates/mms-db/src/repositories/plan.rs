@@ -0,0 +1,157 @@
+use chrono::NaiveDate;
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::models::{RoadmapPlanCounts, StudyPlan};
+
+/// The user's study plan, if they set one
+pub async fn find<'e, E>(executor: E, user_id: Uuid) -> Result<Option<StudyPlan>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT user_id, roadmap_id, target_date, baseline_daily_new_cards, behind_notified_on
+            FROM study_plans
+            WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Set the user's study plan, replacing any previous one
+pub async fn upsert<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    roadmap_id: Uuid,
+    target_date: NaiveDate,
+    baseline_daily_new_cards: i32,
+) -> Result<StudyPlan, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO study_plans (user_id, roadmap_id, target_date, baseline_daily_new_cards)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (user_id) DO UPDATE SET
+                roadmap_id = EXCLUDED.roadmap_id,
+                target_date = EXCLUDED.target_date,
+                baseline_daily_new_cards = EXCLUDED.baseline_daily_new_cards,
+                behind_notified_on = NULL,
+                created_at = NOW(),
+                updated_at = NOW()
+            RETURNING user_id, roadmap_id, target_date, baseline_daily_new_cards, behind_notified_on
+        "#,
+    )
+    .bind(user_id)
+    .bind(roadmap_id)
+    .bind(target_date)
+    .bind(baseline_daily_new_cards)
+    .fetch_one(executor)
+    .await
+}
+
+/// Delete the user's study plan; returns false if they had none
+pub async fn delete<'e, E>(executor: E, user_id: Uuid) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM study_plans WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Card counts of a roadmap for the user; `None` if the roadmap does not exist
+pub async fn roadmap_counts<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    roadmap_id: Uuid,
+) -> Result<Option<RoadmapPlanCounts>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            WITH cards AS (
+                SELECT DISTINCT df.flashcard_id
+                FROM roadmap_nodes rn
+                JOIN deck_flashcards df ON df.deck_id = rn.deck_id
+                WHERE rn.roadmap_id = $2
+            )
+            SELECT
+                r.title AS roadmap_title,
+                user_local_date($1) AS today,
+                (SELECT COUNT(*) FROM cards) AS total_cards,
+                (
+                    SELECT COUNT(*) FROM cards c
+                    WHERE NOT EXISTS (
+                        SELECT 1 FROM user_card_progress ucp
+                        WHERE ucp.user_id = $1 AND ucp.flashcard_id = c.flashcard_id
+                    )
+                ) AS remaining_cards,
+                (
+                    SELECT COUNT(*) FROM user_card_progress ucp
+                    WHERE ucp.user_id = $1
+                        AND ucp.next_review_at
+                            < (user_local_date($1) + 7)::timestamp AT TIME ZONE user_timezone($1)
+                ) AS reviews_due_next_week
+            FROM roadmaps r
+            WHERE r.id = $2 AND org_visible(r.org_id, $1)
+        "#,
+    )
+    .bind(user_id)
+    .bind(roadmap_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Users with a plan who were not told they are behind in the last `days` local days
+pub async fn list_users_to_check<'e, E>(executor: E, days: i32) -> Result<Vec<Uuid>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT user_id
+            FROM study_plans
+            WHERE behind_notified_on IS NULL
+                OR behind_notified_on <= user_local_date(user_id) - $1
+        "#,
+    )
+    .bind(days)
+    .fetch_all(executor)
+    .await
+}
+
+/// Record that the user was told today that they are behind
+pub async fn mark_behind_notified<'e, E>(executor: E, user_id: Uuid) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE study_plans
+            SET behind_notified_on = user_local_date($1), updated_at = NOW()
+            WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
@@ -2,7 +2,192 @@ use chrono::{DateTime, Utc};
 use sqlx::{Executor, Postgres};
 use uuid::Uuid;
 
-use crate::models::CardProgress;
+use crate::models::{
+    CardProgress, CardState, DailyGoalProgress, DeckPracticeCard, ModeProgress, PracticeCard,
+    ReviewFlashcard, ReviewedProgress, StoredForecast,
+};
+
+/// Cards of a deck that are due for the user, new cards first.
+///
+/// New cards are introduced in the deck's `new_card_order`, or in
+/// `default_new_card_order` when the deck has none:
+/// - `easiest_first`: "i+1" order. A word counts as known once the user reaches
+///   `known_word_score` on any card containing it, and cards with the fewest
+///   unknown words (then the fewest words) come first
+/// - `added`: oldest card first
+/// - `random`: shuffled on every call
+///
+/// Cards already in review follow, most overdue first.
+///
+/// Each card comes with the mode to practise it in: of the deck's `modes`
+/// (translation only by default), the one the user has the lowest score in
+/// on the card, then the one practised least recently, then the first listed.
+pub async fn get_practice_cards<'e, E>(
+    executor: E,
+    deck_id: Uuid,
+    user_id: Uuid,
+    limit: i64,
+    known_word_score: i32,
+    default_new_card_order: &str,
+) -> Result<Vec<PracticeCard>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            WITH known_words AS (
+                SELECT DISTINCT fw.language, fw.word
+                FROM user_card_progress ucp
+                JOIN flashcard_words fw ON fw.flashcard_id = ucp.flashcard_id
+                WHERE ucp.user_id = $2
+                    AND ucp.times_correct - ucp.times_wrong >= $4
+            )
+            SELECT
+                f.id,
+                f.term,
+                f.translation,
+                COALESCE(ucp.times_correct, 0) as times_correct,
+                COALESCE(ucp.times_wrong, 0) as times_wrong,
+                fd.score as difficulty,
+                pm.mode
+            FROM deck_flashcards df
+            JOIN flashcards f ON f.id = df.flashcard_id
+            LEFT JOIN user_card_progress ucp
+                ON ucp.flashcard_id = f.id AND ucp.user_id = $2
+            LEFT JOIN flashcard_difficulty fd ON fd.flashcard_id = f.id
+            LEFT JOIN LATERAL (
+                SELECT
+                    COUNT(*) FILTER (WHERE kw.word IS NULL) AS unknown_words,
+                    COUNT(*) AS words
+                FROM flashcard_words fw
+                LEFT JOIN known_words kw
+                    ON kw.language = fw.language AND kw.word = fw.word
+                WHERE fw.flashcard_id = f.id
+            ) wc ON ucp.flashcard_id IS NULL
+            LEFT JOIN deck_settings ds ON ds.deck_id = df.deck_id
+            LEFT JOIN LATERAL (
+                SELECT m.mode
+                FROM UNNEST(COALESCE(ds.modes, ARRAY['translation'])) WITH ORDINALITY AS m(mode, ord)
+                LEFT JOIN user_card_mode_progress mp
+                    ON mp.user_id = $2 AND mp.flashcard_id = f.id AND mp.mode = m.mode
+                ORDER BY
+                    COALESCE(mp.times_correct - mp.times_wrong, 0),
+                    mp.last_review_at NULLS FIRST,
+                    m.ord
+                LIMIT 1
+            ) pm ON true
+            WHERE df.deck_id = $1
+                AND f.hidden_at IS NULL
+                AND (ucp.next_review_at IS NULL OR ucp.next_review_at <= NOW())
+            ORDER BY
+                ucp.next_review_at NULLS FIRST,
+                CASE COALESCE(ds.new_card_order, $5) WHEN 'random' THEN random() END,
+                CASE COALESCE(ds.new_card_order, $5) WHEN 'added' THEN f.created_at END,
+                wc.unknown_words,
+                wc.words
+            LIMIT $3
+        "#,
+    )
+    .bind(deck_id)
+    .bind(user_id)
+    .bind(limit)
+    .bind(known_word_score)
+    .bind(default_new_card_order)
+    .fetch_all(executor)
+    .await
+}
+
+/// Due cards of each of `deck_ids`, at most `limit_per_deck` per deck, in the
+/// order [`get_practice_cards`] gives within each deck.
+///
+/// Rows come grouped by deck in `deck_ids` order. A card in several of the
+/// decks is returned once per deck, with the mode chosen for that deck.
+pub async fn get_practice_cards_for_decks<'e, E>(
+    executor: E,
+    deck_ids: &[Uuid],
+    user_id: Uuid,
+    limit_per_deck: i64,
+    known_word_score: i32,
+    default_new_card_order: &str,
+) -> Result<Vec<DeckPracticeCard>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            WITH known_words AS (
+                SELECT DISTINCT fw.language, fw.word
+                FROM user_card_progress ucp
+                JOIN flashcard_words fw ON fw.flashcard_id = ucp.flashcard_id
+                WHERE ucp.user_id = $2
+                    AND ucp.times_correct - ucp.times_wrong >= $4
+            ), ranked AS (
+                SELECT
+                    df.deck_id,
+                    d.ord,
+                    f.id,
+                    f.term,
+                    f.translation,
+                    COALESCE(ucp.times_correct, 0) as times_correct,
+                    COALESCE(ucp.times_wrong, 0) as times_wrong,
+                    fd.score as difficulty,
+                    pm.mode,
+                    ROW_NUMBER() OVER (
+                        PARTITION BY df.deck_id
+                        ORDER BY
+                            ucp.next_review_at NULLS FIRST,
+                            CASE COALESCE(ds.new_card_order, $5) WHEN 'random' THEN random() END,
+                            CASE COALESCE(ds.new_card_order, $5) WHEN 'added' THEN f.created_at END,
+                            wc.unknown_words,
+                            wc.words,
+                            f.id
+                    ) AS rank
+                FROM UNNEST($1::uuid[]) WITH ORDINALITY AS d(deck_id, ord)
+                JOIN deck_flashcards df ON df.deck_id = d.deck_id
+                JOIN flashcards f ON f.id = df.flashcard_id
+                LEFT JOIN user_card_progress ucp
+                    ON ucp.flashcard_id = f.id AND ucp.user_id = $2
+                LEFT JOIN flashcard_difficulty fd ON fd.flashcard_id = f.id
+                LEFT JOIN LATERAL (
+                    SELECT
+                        COUNT(*) FILTER (WHERE kw.word IS NULL) AS unknown_words,
+                        COUNT(*) AS words
+                    FROM flashcard_words fw
+                    LEFT JOIN known_words kw
+                        ON kw.language = fw.language AND kw.word = fw.word
+                    WHERE fw.flashcard_id = f.id
+                ) wc ON ucp.flashcard_id IS NULL
+                LEFT JOIN deck_settings ds ON ds.deck_id = df.deck_id
+                LEFT JOIN LATERAL (
+                    SELECT m.mode
+                    FROM UNNEST(COALESCE(ds.modes, ARRAY['translation'])) WITH ORDINALITY AS m(mode, ord)
+                    LEFT JOIN user_card_mode_progress mp
+                        ON mp.user_id = $2 AND mp.flashcard_id = f.id AND mp.mode = m.mode
+                    ORDER BY
+                        COALESCE(mp.times_correct - mp.times_wrong, 0),
+                        mp.last_review_at NULLS FIRST,
+                        m.ord
+                    LIMIT 1
+                ) pm ON true
+                WHERE f.hidden_at IS NULL
+                    AND (ucp.next_review_at IS NULL OR ucp.next_review_at <= NOW())
+            )
+            SELECT deck_id, id, term, translation, times_correct, times_wrong, difficulty, mode
+            FROM ranked
+            WHERE rank <= $3
+            ORDER BY ord, rank
+        "#,
+    )
+    .bind(deck_ids)
+    .bind(user_id)
+    .bind(limit_per_deck)
+    .bind(known_word_score)
+    .bind(default_new_card_order)
+    .fetch_all(executor)
+    .await
+}
 
 /// Verify that a flashcard belongs to a given deck.
 pub async fn flashcard_belongs_to_deck<'e, E>(
@@ -29,19 +214,21 @@ where
     Ok(exists)
 }
 
-pub async fn get_flashcard_translation<'e, E>(
+/// Fetch what grading and scheduling need: the answers and the card's global difficulty.
+pub async fn get_flashcard_for_review<'e, E>(
     executor: E,
     flashcard_id: Uuid,
-) -> Result<String, sqlx::Error>
+) -> Result<ReviewFlashcard, sqlx::Error>
 where
     E: Executor<'e, Database = Postgres>,
 {
-    sqlx::query_scalar(
+    sqlx::query_as(
         // language=PostgreSQL
         r#"
-            SELECT translation
-            FROM flashcards
-            WHERE id = $1
+            SELECT f.translation, f.accepted_answers, fd.score AS difficulty
+            FROM flashcards f
+            LEFT JOIN flashcard_difficulty fd ON fd.flashcard_id = f.id
+            WHERE f.id = $1
         "#,
     )
     .bind(flashcard_id)
@@ -49,6 +236,116 @@ where
     .await
 }
 
+/// Count the user's cards due on each of `days` local days from today,
+/// optionally only those in `deck_id`, straight from their progress. The first
+/// day includes overdue cards. Returns None for an unknown user.
+///
+/// Reads `idx_progress_user_due`; unlike the stored forecast this is computed
+/// on every call, for spans and filters the stored row does not cover.
+pub async fn due_forecast<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    days: i32,
+    deck_id: Option<Uuid>,
+) -> Result<Option<StoredForecast>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            WITH target AS (
+                SELECT u.timezone AS tz, user_local_date(u.id) AS today
+                FROM users u
+                WHERE u.id = $1
+            ),
+            counts AS (
+                SELECT
+                    GREATEST((p.next_review_at AT TIME ZONE t.tz)::date - t.today, 0) AS day,
+                    COUNT(*)::int AS due
+                FROM target t
+                JOIN user_card_progress p ON p.user_id = $1
+                WHERE p.next_review_at < (t.today + $2::int)::timestamp AT TIME ZONE t.tz
+                  AND ($3::uuid IS NULL OR EXISTS (
+                      SELECT 1 FROM deck_flashcards df
+                      WHERE df.deck_id = $3 AND df.flashcard_id = p.flashcard_id
+                  ))
+                GROUP BY 1
+            )
+            SELECT
+                t.today AS starts_on,
+                t.today,
+                array_agg(COALESCE(c.due, 0) ORDER BY s.day) AS due_counts,
+                NOW() AS computed_at
+            FROM target t
+            CROSS JOIN generate_series(0, $2::int - 1) AS s(day)
+            LEFT JOIN counts c ON c.day = s.day
+            GROUP BY t.today
+        "#,
+    )
+    .bind(user_id)
+    .bind(days)
+    .bind(deck_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// The user's score and due day for each visible card of a deck, None for the
+/// cards they have not studied. Returns no rows for an unknown user.
+pub async fn card_states<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    deck_id: Uuid,
+) -> Result<Vec<CardState>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            WITH target AS (
+                SELECT u.timezone AS tz, user_local_date(u.id) AS today
+                FROM users u
+                WHERE u.id = $1
+            )
+            SELECT
+                p.times_correct - p.times_wrong AS score,
+                GREATEST((p.next_review_at AT TIME ZONE t.tz)::date - t.today, 0) AS due_in_days
+            FROM target t
+            JOIN deck_flashcards df ON df.deck_id = $2
+            JOIN flashcards f ON f.id = df.flashcard_id AND f.hidden_at IS NULL
+            LEFT JOIN user_card_progress p
+                ON p.user_id = $1 AND p.flashcard_id = df.flashcard_id
+        "#,
+    )
+    .bind(user_id)
+    .bind(deck_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Lock the user's reviews for the rest of the transaction.
+///
+/// Reviews of one user then run one after the other, so two submissions of
+/// the same card cannot both read its progress before either writes it. The
+/// user row is locked `FOR NO KEY UPDATE`, which does not block inserts of
+/// rows that reference the user. Returns `false` when the user does not exist.
+pub async fn lock_user_reviews<'e, E>(executor: E, user_id: Uuid) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let locked: Option<Uuid> = sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT id FROM users WHERE id = $1 FOR NO KEY UPDATE
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await?;
+    Ok(locked.is_some())
+}
+
 pub async fn get_card_progress<'e, E>(
     executor: E,
     user_id: Uuid,
@@ -60,7 +357,7 @@ where
     sqlx::query_as(
         // language=PostgreSQL
         r#"
-            SELECT next_review_at, times_correct, times_wrong
+            SELECT next_review_at, last_review_at, times_correct, times_wrong
             FROM user_card_progress
             WHERE user_id = $1 AND flashcard_id = $2
         "#,
@@ -75,10 +372,7 @@ pub async fn upsert_card_progress<'e, E>(
     executor: E,
     user_id: Uuid,
     flashcard_id: Uuid,
-    next_review_at: DateTime<Utc>,
-    times_correct: i32,
-    times_wrong: i32,
-    mastered: bool,
+    progress: &ReviewedProgress,
 ) -> Result<(), sqlx::Error>
 where
     E: Executor<'e, Database = Postgres>,
@@ -87,23 +381,129 @@ where
         // language=PostgreSQL
         r#"
             INSERT INTO user_card_progress (user_id, flashcard_id, next_review_at, last_review_at, times_correct, times_wrong, mastered_at)
-            VALUES ($1, $2, $3, NOW(), $4, $5, CASE WHEN $6 THEN NOW() ELSE NULL END)
+            VALUES ($1, $2, $3, $7, $4, $5, CASE WHEN $6 THEN $7 ELSE NULL END)
             ON CONFLICT (user_id, flashcard_id)
             DO UPDATE SET
                 next_review_at = $3,
-                last_review_at = NOW(),
+                last_review_at = $7,
                 times_correct = $4,
                 times_wrong = $5,
-                mastered_at = CASE WHEN $6 THEN COALESCE(user_card_progress.mastered_at, NOW()) ELSE NULL END,
+                mastered_at = CASE WHEN $6 THEN COALESCE(user_card_progress.mastered_at, $7) ELSE NULL END,
                 updated_at = NOW()
         "#,
     )
     .bind(user_id)
     .bind(flashcard_id)
-    .bind(next_review_at)
-    .bind(times_correct)
-    .bind(times_wrong)
-    .bind(mastered)
+    .bind(progress.next_review_at)
+    .bind(progress.times_correct)
+    .bind(progress.times_wrong)
+    .bind(progress.mastered)
+    .bind(progress.reviewed_at)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Count an answer towards the user's score on the card in `mode`
+pub async fn record_mode_review<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    flashcard_id: Uuid,
+    mode: &str,
+    is_correct: bool,
+    reviewed_at: DateTime<Utc>,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO user_card_mode_progress (
+                user_id, flashcard_id, mode, times_correct, times_wrong, last_review_at
+            )
+            VALUES ($1, $2, $3, $4::int, 1 - $4::int, $5)
+            ON CONFLICT (user_id, flashcard_id, mode) DO UPDATE SET
+                times_correct = user_card_mode_progress.times_correct + EXCLUDED.times_correct,
+                times_wrong = user_card_mode_progress.times_wrong + EXCLUDED.times_wrong,
+                last_review_at = GREATEST(user_card_mode_progress.last_review_at, $5)
+        "#,
+    )
+    .bind(user_id)
+    .bind(flashcard_id)
+    .bind(mode)
+    .bind(is_correct)
+    .bind(reviewed_at)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// The user's progress on a deck's visible cards in each mode they practised
+/// it in, modes in alphabetical order.
+///
+/// A card counts as mastered in a mode once its score there reaches
+/// `mastery_threshold`.
+pub async fn progress_by_mode<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    deck_id: Uuid,
+    mastery_threshold: i32,
+) -> Result<Vec<ModeProgress>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                mp.mode,
+                COUNT(*) AS cards_practised,
+                COUNT(*) FILTER (
+                    WHERE mp.times_correct - mp.times_wrong >= $3
+                ) AS cards_mastered,
+                SUM(mp.times_correct)::bigint AS times_correct,
+                SUM(mp.times_wrong)::bigint AS times_wrong
+            FROM deck_flashcards df
+            JOIN flashcards f ON f.id = df.flashcard_id
+            JOIN user_card_mode_progress mp
+                ON mp.flashcard_id = f.id AND mp.user_id = $1
+            WHERE df.deck_id = $2 AND f.hidden_at IS NULL
+            GROUP BY mp.mode
+            ORDER BY mp.mode
+        "#,
+    )
+    .bind(user_id)
+    .bind(deck_id)
+    .bind(mastery_threshold)
+    .fetch_all(executor)
+    .await
+}
+
+/// Add a reported answer time to the card's running totals.
+///
+/// Must run after [`upsert_card_progress`] so the progress row exists.
+pub async fn record_response_time<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    flashcard_id: Uuid,
+    response_time_ms: i32,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE user_card_progress
+            SET total_response_ms = total_response_ms + $3,
+                timed_reviews = timed_reviews + 1
+            WHERE user_id = $1 AND flashcard_id = $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(flashcard_id)
+    .bind(i64::from(response_time_ms))
     .execute(executor)
     .await?;
     Ok(())
@@ -132,20 +532,343 @@ where
     Ok(())
 }
 
-pub async fn record_activity<'e, E>(executor: E, user_id: Uuid) -> Result<(), sqlx::Error>
+/// Refresh the stored progress of everyone who has started any of the decks,
+/// after cards were added to or removed from them
+pub async fn refresh_learners_deck_progress<'e, E>(
+    executor: E,
+    deck_ids: &[Uuid],
+    mastery_threshold: i32,
+) -> Result<(), sqlx::Error>
 where
     E: Executor<'e, Database = Postgres>,
 {
     sqlx::query(
         // language=PostgreSQL
         r#"
-            INSERT INTO user_activity (user_id, activity_date, reviews_count)
-            VALUES ($1, CURRENT_DATE, 1)
+            SELECT refresh_deck_progress(user_id, deck_id, $2)
+            FROM user_deck_progress
+            WHERE deck_id = ANY($1)
+        "#,
+    )
+    .bind(deck_ids)
+    .bind(mastery_threshold)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Recompute the stored deck progress of `user_id`, or of every user when `None`,
+/// from their card progress.
+///
+/// Computes what `refresh_deck_progress` would store for every existing row and
+/// only rewrites the rows that differ, so running it again changes nothing.
+/// Returns how many rows had drifted.
+pub async fn recompute_deck_progress<'e, E>(
+    executor: E,
+    user_id: Option<Uuid>,
+    mastery_threshold: i32,
+) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            WITH expected AS (
+                SELECT
+                    udp.user_id,
+                    udp.deck_id,
+                    COUNT(df.flashcard_id)::int AS total_cards,
+                    COUNT(ucp.mastered_at)::int AS mastered_cards,
+                    COALESCE(SUM(ucp.times_correct + ucp.times_wrong), 0)::int AS total_practices,
+                    (CASE
+                        WHEN COUNT(df.flashcard_id) > 0 THEN LEAST(
+                            100.00,
+                            COALESCE(SUM(GREATEST(0, ucp.times_correct - ucp.times_wrong)), 0)::DECIMAL
+                                / (COUNT(df.flashcard_id) * $2) * 100
+                        )
+                        ELSE 0.00
+                    END)::DECIMAL(5,2) AS progress_percentage,
+                    MAX(ucp.last_review_at) AS last_practiced_at
+                FROM user_deck_progress udp
+                LEFT JOIN deck_flashcards df ON df.deck_id = udp.deck_id
+                LEFT JOIN user_card_progress ucp
+                    ON ucp.flashcard_id = df.flashcard_id AND ucp.user_id = udp.user_id
+                WHERE $1::uuid IS NULL OR udp.user_id = $1
+                GROUP BY udp.user_id, udp.deck_id
+            )
+            UPDATE user_deck_progress udp
+            SET total_cards = e.total_cards,
+                mastered_cards = e.mastered_cards,
+                total_practices = e.total_practices,
+                progress_percentage = e.progress_percentage,
+                last_practiced_at = e.last_practiced_at,
+                updated_at = NOW()
+            FROM expected e
+            WHERE udp.user_id = e.user_id
+              AND udp.deck_id = e.deck_id
+              AND (udp.total_cards, udp.mastered_cards, udp.total_practices,
+                   udp.progress_percentage, udp.last_practiced_at)
+                  IS DISTINCT FROM
+                  (e.total_cards, e.mastered_cards, e.total_practices,
+                   e.progress_percentage, e.last_practiced_at)
+        "#,
+    )
+    .bind(user_id)
+    .bind(mastery_threshold)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Spread the user's overdue cards evenly over the next `days` days.
+///
+/// The weakest cards (lowest score) stay due now and the strongest move
+/// furthest; cards due on the same day keep their relative order. Returns the
+/// number of cards rescheduled.
+pub async fn spread_overdue<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    days: i32,
+) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            WITH overdue AS (
+                SELECT flashcard_id,
+                       ROW_NUMBER() OVER (
+                           ORDER BY times_correct - times_wrong, next_review_at, flashcard_id
+                       ) - 1 AS position,
+                       COUNT(*) OVER () AS total
+                FROM user_card_progress
+                WHERE user_id = $1 AND next_review_at <= NOW()
+            )
+            UPDATE user_card_progress p
+            SET next_review_at = NOW() + make_interval(days => (o.position * $2 / o.total)::int)
+            FROM overdue o
+            WHERE p.user_id = $1 AND p.flashcard_id = o.flashcard_id
+        "#,
+    )
+    .bind(user_id)
+    .bind(i64::from(days))
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Start the user's leeches over: unmastered cards answered wrong at least
+/// `min_lapses` times go back to a score of 0 and are due now.
+///
+/// Returns the cards reset.
+pub async fn reset_leeches<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    min_lapses: i32,
+) -> Result<Vec<Uuid>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            UPDATE user_card_progress
+            SET times_correct = 0, times_wrong = 0, mastered_at = NULL, next_review_at = NOW()
+            WHERE user_id = $1 AND times_wrong >= $2 AND mastered_at IS NULL
+            RETURNING flashcard_id
+        "#,
+    )
+    .bind(user_id)
+    .bind(min_lapses)
+    .fetch_all(executor)
+    .await
+}
+
+/// Bring forward every review of the user's scheduled more than `max_days` days from now.
+///
+/// Returns the number of cards rescheduled.
+pub async fn cap_intervals<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    max_days: i32,
+) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE user_card_progress
+            SET next_review_at = NOW() + make_interval(days => $2)
+            WHERE user_id = $1 AND next_review_at > NOW() + make_interval(days => $2)
+        "#,
+    )
+    .bind(user_id)
+    .bind(max_days)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Count a review towards the user's current pace window; returns the reviews in it, this one included
+pub async fn record_review_pace<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    window_secs: i32,
+) -> Result<i32, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO review_pace (user_id, window_started_at, reviews)
+            VALUES ($1, NOW(), 1)
+            ON CONFLICT (user_id) DO UPDATE SET
+                window_started_at = CASE
+                    WHEN review_pace.window_started_at <= NOW() - make_interval(secs => $2)
+                    THEN NOW()
+                    ELSE review_pace.window_started_at
+                END,
+                reviews = CASE
+                    WHEN review_pace.window_started_at <= NOW() - make_interval(secs => $2)
+                    THEN 1
+                    ELSE review_pace.reviews + 1
+                END
+            RETURNING reviews
+        "#,
+    )
+    .bind(user_id)
+    .bind(window_secs)
+    .fetch_one(executor)
+    .await
+}
+
+/// Count a review towards the activity of the day it was made, `reviewed_at`,
+/// in the user's timezone, adding `study_ms` of study time and the card if it
+/// is `new_card`; returns that day's review count
+pub async fn record_activity<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    correct: bool,
+    flagged: bool,
+    study_ms: i32,
+    new_card: bool,
+    reviewed_at: DateTime<Utc>,
+) -> Result<i32, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO user_activity
+                (user_id, activity_date, reviews_count, correct_reviews, flagged_reviews, study_ms, new_cards)
+            VALUES ($1, ($6 AT TIME ZONE user_timezone($1))::date, 1, $2::int, $3::int, $4, $5::int)
             ON CONFLICT (user_id, activity_date)
-            DO UPDATE SET reviews_count = user_activity.reviews_count + 1
+            DO UPDATE SET
+                reviews_count = user_activity.reviews_count + 1,
+                correct_reviews = user_activity.correct_reviews + $2::int,
+                flagged_reviews = user_activity.flagged_reviews + $3::int,
+                study_ms = user_activity.study_ms + $4,
+                new_cards = user_activity.new_cards + $5::int
+            RETURNING reviews_count
         "#,
     )
     .bind(user_id)
+    .bind(correct)
+    .bind(flagged)
+    .bind(i64::from(study_ms))
+    .bind(new_card)
+    .bind(reviewed_at)
+    .fetch_one(executor)
+    .await
+}
+
+/// Mark today's daily goal reached if today's activity meets it.
+///
+/// Returns the progress only when this call reached the goal, so each day
+/// reports it once. Flagged reviews do not count towards a reviews goal.
+pub async fn mark_goal_reached<'e, E>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<Option<DailyGoalProgress>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            UPDATE user_activity ua
+            SET goal_reached = TRUE
+            FROM users u
+            WHERE ua.user_id = $1
+                AND ua.activity_date = user_local_date($1)
+                AND NOT ua.goal_reached
+                AND u.id = ua.user_id
+                AND CASE u.daily_goal_kind
+                        WHEN 'minutes' THEN ua.study_ms >= u.daily_goal_target::bigint * 60000
+                        ELSE ua.reviews_count - ua.flagged_reviews >= u.daily_goal_target
+                    END
+            RETURNING u.daily_goal_kind AS kind, u.daily_goal_target AS target,
+                      ua.reviews_count - ua.flagged_reviews AS reviews_today,
+                      ua.study_ms AS study_ms_today, ua.goal_reached AS reached
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Count a review made at `reviewed_at` towards the learning profile of the
+/// card's language pair.
+///
+/// Creates the profile with default settings on the first review in a new pair.
+pub async fn record_profile_activity<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    flashcard_id: Uuid,
+    reviewed_at: DateTime<Utc>,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            WITH card AS (
+                SELECT language_from, language_to FROM flashcards WHERE id = $2
+            ),
+            created AS (
+                INSERT INTO learning_profiles (user_id, native_language, learning_language)
+                SELECT $1, language_from, language_to FROM card
+                ON CONFLICT (user_id, native_language, learning_language) DO NOTHING
+                RETURNING id
+            ),
+            profile AS (
+                SELECT id FROM created
+                UNION ALL
+                SELECT lp.id
+                FROM learning_profiles lp
+                JOIN card
+                    ON lp.native_language = card.language_from
+                    AND lp.learning_language = card.language_to
+                WHERE lp.user_id = $1
+            )
+            INSERT INTO profile_activity (profile_id, activity_date, reviews_count)
+            SELECT id, ($3 AT TIME ZONE user_timezone($1))::date, 1 FROM profile
+            ON CONFLICT (profile_id, activity_date)
+            DO UPDATE SET reviews_count = profile_activity.reviews_count + 1
+        "#,
+    )
+    .bind(user_id)
+    .bind(flashcard_id)
+    .bind(reviewed_at)
     .execute(executor)
     .await?;
     Ok(())
@@ -155,6 +878,7 @@ pub async fn increment_review_stats<'e, E>(
     executor: E,
     user_id: Uuid,
     newly_mastered: bool,
+    flagged: bool,
 ) -> Result<bool, sqlx::Error>
 where
     E: Executor<'e, Database = Postgres>,
@@ -165,13 +889,15 @@ where
             UPDATE user_stats
             SET total_reviews = total_reviews + 1,
                 total_cards_learned = total_cards_learned + CASE WHEN $2 THEN 1 ELSE 0 END,
-                last_review_date = CURRENT_DATE,
+                flagged_reviews = flagged_reviews + $3::int,
+                last_review_date = user_local_date($1),
                 updated_at = NOW()
             WHERE user_id = $1
         "#,
     )
     .bind(user_id)
     .bind(newly_mastered)
+    .bind(flagged)
     .execute(executor)
     .await?;
     Ok(result.rows_affected() > 0)
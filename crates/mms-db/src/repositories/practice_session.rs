@@ -0,0 +1,316 @@
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::models::{PracticeSession, SessionCard};
+
+/// Start a session over `deck_ids`, sorted, with the cards queued in order, each
+/// with the deck it is practised in, open for `ttl_hours`. Returns the session id.
+pub async fn create<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    deck_ids: &[Uuid],
+    roadmap_id: Option<Uuid>,
+    cards: &[(Uuid, Uuid)],
+    ttl_hours: i32,
+) -> Result<Uuid, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            WITH session AS (
+                INSERT INTO practice_sessions (user_id, deck_ids, roadmap_id, expires_at)
+                VALUES ($1, $2, $3, NOW() + make_interval(hours => $6))
+                RETURNING id
+            ), queued AS (
+                INSERT INTO practice_session_cards (session_id, position, flashcard_id, deck_id)
+                SELECT session.id, q.position::int, q.flashcard_id, q.deck_id
+                FROM session,
+                    UNNEST($4::uuid[], $5::uuid[]) WITH ORDINALITY AS q(flashcard_id, deck_id, position)
+            )
+            SELECT id FROM session
+        "#,
+    )
+    .bind(user_id)
+    .bind(deck_ids)
+    .bind(roadmap_id)
+    .bind(cards.iter().map(|&(flashcard_id, _)| flashcard_id).collect::<Vec<_>>())
+    .bind(cards.iter().map(|&(_, deck_id)| deck_id).collect::<Vec<_>>())
+    .bind(ttl_hours)
+    .fetch_one(executor)
+    .await
+}
+
+/// The user's open session over the same sorted `deck_ids` and roadmap: not
+/// expired and not completed, if any.
+pub async fn find_open<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    deck_ids: &[Uuid],
+    roadmap_id: Option<Uuid>,
+) -> Result<Option<Uuid>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT id
+            FROM practice_sessions
+            WHERE user_id = $1 AND deck_ids = $2 AND roadmap_id IS NOT DISTINCT FROM $3
+                AND expires_at > NOW()
+                AND completed_at IS NULL
+            ORDER BY expires_at DESC
+            LIMIT 1
+        "#,
+    )
+    .bind(user_id)
+    .bind(deck_ids)
+    .bind(roadmap_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// A session of the user with its counts; None once it has expired.
+///
+/// A card still counts as remaining while it is unanswered, visible, still in
+/// the deck it was drawn from and due.
+pub async fn find<'e, E>(
+    executor: E,
+    session_id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<PracticeSession>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                s.id,
+                CASE WHEN cardinality(s.deck_ids) = 1 THEN s.deck_ids[1] END AS deck_id,
+                s.deck_ids,
+                s.roadmap_id,
+                s.created_at,
+                s.expires_at,
+                s.completed_at,
+                COUNT(c.flashcard_id) AS total_cards,
+                COUNT(c.answered_at) AS answered,
+                COUNT(*) FILTER (WHERE c.is_correct) AS correct,
+                COUNT(*) FILTER (
+                    WHERE c.answered_at IS NULL
+                        AND f.hidden_at IS NULL
+                        AND df.flashcard_id IS NOT NULL
+                        AND (ucp.next_review_at IS NULL OR ucp.next_review_at <= NOW())
+                ) AS remaining
+            FROM practice_sessions s
+            LEFT JOIN practice_session_cards c ON c.session_id = s.id
+            LEFT JOIN flashcards f ON f.id = c.flashcard_id
+            LEFT JOIN deck_flashcards df
+                ON df.deck_id = c.deck_id AND df.flashcard_id = c.flashcard_id
+            LEFT JOIN user_card_progress ucp
+                ON ucp.user_id = s.user_id AND ucp.flashcard_id = c.flashcard_id
+            WHERE s.id = $1 AND s.user_id = $2 AND s.expires_at > NOW()
+            GROUP BY s.id
+        "#,
+    )
+    .bind(session_id)
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Serve the first remaining card of a session, stamping when it was first
+/// served. Serving again returns the same card until it is answered. None when
+/// no card is left or the session is not the user's open session.
+pub async fn next_card<'e, E>(
+    executor: E,
+    session_id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<SessionCard>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            WITH next AS (
+                SELECT c.session_id, c.position, c.deck_id
+                FROM practice_sessions s
+                JOIN practice_session_cards c ON c.session_id = s.id
+                JOIN flashcards f ON f.id = c.flashcard_id
+                JOIN deck_flashcards df
+                    ON df.deck_id = c.deck_id AND df.flashcard_id = c.flashcard_id
+                LEFT JOIN user_card_progress ucp
+                    ON ucp.user_id = s.user_id AND ucp.flashcard_id = c.flashcard_id
+                WHERE s.id = $1 AND s.user_id = $2 AND s.expires_at > NOW()
+                    AND c.answered_at IS NULL
+                    AND f.hidden_at IS NULL
+                    AND (ucp.next_review_at IS NULL OR ucp.next_review_at <= NOW())
+                ORDER BY c.position
+                LIMIT 1
+            )
+            UPDATE practice_session_cards c
+            SET served_at = COALESCE(c.served_at, NOW())
+            FROM next
+            JOIN flashcards f ON true
+            LEFT JOIN user_card_progress ucp
+                ON ucp.user_id = $2 AND ucp.flashcard_id = f.id
+            LEFT JOIN flashcard_difficulty fd ON fd.flashcard_id = f.id
+            LEFT JOIN deck_settings ds ON ds.deck_id = next.deck_id
+            LEFT JOIN LATERAL (
+                SELECT m.mode
+                FROM UNNEST(COALESCE(ds.modes, ARRAY['translation'])) WITH ORDINALITY AS m(mode, ord)
+                LEFT JOIN user_card_mode_progress mp
+                    ON mp.user_id = $2 AND mp.flashcard_id = f.id AND mp.mode = m.mode
+                ORDER BY
+                    COALESCE(mp.times_correct - mp.times_wrong, 0),
+                    mp.last_review_at NULLS FIRST,
+                    m.ord
+                LIMIT 1
+            ) pm ON true
+            WHERE c.session_id = next.session_id
+                AND c.position = next.position
+                AND f.id = c.flashcard_id
+            RETURNING
+                c.position,
+                c.deck_id,
+                f.id,
+                f.term,
+                f.translation,
+                COALESCE(ucp.times_correct, 0) AS times_correct,
+                COALESCE(ucp.times_wrong, 0) AS times_wrong,
+                fd.score AS difficulty,
+                pm.mode
+        "#,
+    )
+    .bind(session_id)
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// The deck a card of the session was drawn from; None when it is not queued.
+pub async fn card_deck<'e, E>(
+    executor: E,
+    session_id: Uuid,
+    flashcard_id: Uuid,
+) -> Result<Option<Uuid>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT deck_id FROM practice_session_cards
+            WHERE session_id = $1 AND flashcard_id = $2
+        "#,
+    )
+    .bind(session_id)
+    .bind(flashcard_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Whether a card is queued in the session and not answered yet.
+pub async fn is_unanswered<'e, E>(
+    executor: E,
+    session_id: Uuid,
+    flashcard_id: Uuid,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT EXISTS(
+                SELECT 1 FROM practice_session_cards
+                WHERE session_id = $1 AND flashcard_id = $2 AND answered_at IS NULL
+            )
+        "#,
+    )
+    .bind(session_id)
+    .bind(flashcard_id)
+    .fetch_one(executor)
+    .await
+}
+
+/// Record the answer to a queued card.
+pub async fn record_answer<'e, E>(
+    executor: E,
+    session_id: Uuid,
+    flashcard_id: Uuid,
+    is_correct: bool,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE practice_session_cards
+            SET answered_at = NOW(),
+                is_correct = $3,
+                served_at = COALESCE(served_at, NOW())
+            WHERE session_id = $1 AND flashcard_id = $2
+        "#,
+    )
+    .bind(session_id)
+    .bind(flashcard_id)
+    .bind(is_correct)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Keep a session open for another `ttl_hours`, and mark it completed once no
+/// card is left to serve.
+pub async fn touch<'e, E>(executor: E, session_id: Uuid, ttl_hours: i32) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE practice_sessions s
+            SET expires_at = NOW() + make_interval(hours => $2),
+                completed_at = COALESCE(s.completed_at, CASE WHEN NOT EXISTS (
+                    SELECT 1
+                    FROM practice_session_cards c
+                    JOIN flashcards f ON f.id = c.flashcard_id
+                    JOIN deck_flashcards df
+                        ON df.deck_id = c.deck_id AND df.flashcard_id = c.flashcard_id
+                    LEFT JOIN user_card_progress ucp
+                        ON ucp.user_id = s.user_id AND ucp.flashcard_id = c.flashcard_id
+                    WHERE c.session_id = s.id
+                        AND c.answered_at IS NULL
+                        AND f.hidden_at IS NULL
+                        AND (ucp.next_review_at IS NULL OR ucp.next_review_at <= NOW())
+                ) THEN NOW() END)
+            WHERE s.id = $1
+        "#,
+    )
+    .bind(session_id)
+    .bind(ttl_hours)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Delete expired sessions and their queues
+pub async fn delete_expired<'e, E>(executor: E) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM practice_sessions WHERE expires_at <= NOW()
+        "#,
+    )
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,81 @@
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::models::{NewUserPreferences, UserPreferences};
+
+/// The user's preferences, `None` while they never changed one
+pub async fn find<'e, E>(executor: E, user_id: Uuid) -> Result<Option<UserPreferences>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT theme, audio_autoplay, answer_checking, new_cards_per_day, reviews_per_day,
+                   max_interval_days, updated_at
+            FROM user_preferences
+            WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Lock the user so concurrent changes of their preferences apply one after the
+/// other; the preferences themselves may not exist yet. `false` when the user
+/// does not exist or was deleted.
+pub async fn lock_user<'e, E>(executor: E, user_id: Uuid) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let locked: Option<Uuid> = sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT id FROM users WHERE id = $1 AND deleted_at IS NULL FOR NO KEY UPDATE
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await?;
+    Ok(locked.is_some())
+}
+
+/// Store the user's preferences, replacing the previous ones
+pub async fn save<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    preferences: &NewUserPreferences<'_>,
+) -> Result<UserPreferences, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO user_preferences
+                (user_id, theme, audio_autoplay, answer_checking, new_cards_per_day, reviews_per_day,
+                 max_interval_days)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (user_id) DO UPDATE
+            SET theme = EXCLUDED.theme,
+                audio_autoplay = EXCLUDED.audio_autoplay,
+                answer_checking = EXCLUDED.answer_checking,
+                new_cards_per_day = EXCLUDED.new_cards_per_day,
+                reviews_per_day = EXCLUDED.reviews_per_day,
+                max_interval_days = EXCLUDED.max_interval_days,
+                updated_at = NOW()
+            RETURNING theme, audio_autoplay, answer_checking, new_cards_per_day, reviews_per_day,
+                      max_interval_days, updated_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(preferences.theme)
+    .bind(preferences.audio_autoplay)
+    .bind(preferences.answer_checking)
+    .bind(preferences.new_cards_per_day)
+    .bind(preferences.reviews_per_day)
+    .bind(preferences.max_interval_days)
+    .fetch_one(executor)
+    .await
+}
@@ -0,0 +1,301 @@
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::models::{ActivityDay, DueCard, LearningProfile, ProfileDueCount, UserStats};
+
+pub async fn list_for_user<'e, E>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<Vec<LearningProfile>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT id, native_language, learning_language, session_size, created_at
+            FROM learning_profiles
+            WHERE user_id = $1
+            ORDER BY created_at, id
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Fetch a profile, only if it belongs to the user
+pub async fn find_for_user<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    profile_id: Uuid,
+) -> Result<Option<LearningProfile>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT id, native_language, learning_language, session_size, created_at
+            FROM learning_profiles
+            WHERE id = $2 AND user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .bind(profile_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Create a profile; returns `None` if the user already has one for the language pair
+pub async fn create<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    native_language: &str,
+    learning_language: &str,
+    session_size: i32,
+) -> Result<Option<LearningProfile>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO learning_profiles (user_id, native_language, learning_language, session_size)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (user_id, native_language, learning_language) DO NOTHING
+            RETURNING id, native_language, learning_language, session_size, created_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(native_language)
+    .bind(learning_language)
+    .bind(session_size)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Create a profile with default settings unless the user already has one for the pair
+pub async fn ensure_exists<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    native_language: &str,
+    learning_language: &str,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO learning_profiles (user_id, native_language, learning_language)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (user_id, native_language, learning_language) DO NOTHING
+        "#,
+    )
+    .bind(user_id)
+    .bind(native_language)
+    .bind(learning_language)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+pub async fn update_settings<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    profile_id: Uuid,
+    session_size: i32,
+) -> Result<Option<LearningProfile>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            UPDATE learning_profiles
+            SET session_size = $3
+            WHERE id = $2 AND user_id = $1
+            RETURNING id, native_language, learning_language, session_size, created_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(profile_id)
+    .bind(session_size)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Delete a profile and its activity history; card progress is kept
+pub async fn delete<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    profile_id: Uuid,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM learning_profiles
+            WHERE id = $2 AND user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .bind(profile_id)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Streaks and totals for one profile, in the same shape as the account-wide stats.
+///
+/// Streaks are runs of consecutive activity days; the current streak is the run
+/// ending today or yesterday in the user's timezone. Cards learned counts the profile's cards that are
+/// currently mastered.
+pub async fn get_stats<'e, E>(executor: E, profile_id: Uuid) -> Result<UserStats, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            WITH today AS (
+                SELECT user_local_date(user_id) AS day FROM learning_profiles WHERE id = $1
+            ),
+            runs AS (
+                SELECT MAX(activity_date) AS last_day, COUNT(*)::int AS days
+                FROM (
+                    SELECT
+                        activity_date,
+                        activity_date - (ROW_NUMBER() OVER (ORDER BY activity_date))::int AS run
+                    FROM profile_activity
+                    WHERE profile_id = $1 AND activity_date <= (SELECT day FROM today)
+                ) d
+                GROUP BY run
+            )
+            SELECT
+                COALESCE((
+                    SELECT MAX(days) FROM runs WHERE last_day >= (SELECT day FROM today) - 1
+                ), 0)
+                    AS current_streak_days,
+                COALESCE((SELECT MAX(days) FROM runs), 0) AS longest_streak_days,
+                COALESCE((
+                    SELECT SUM(reviews_count) FROM profile_activity WHERE profile_id = $1
+                ), 0)::int AS total_reviews,
+                (
+                    SELECT COUNT(*)
+                    FROM learning_profiles lp
+                    JOIN user_card_progress ucp
+                        ON ucp.user_id = lp.user_id AND ucp.mastered_at IS NOT NULL
+                    JOIN flashcards f
+                        ON f.id = ucp.flashcard_id
+                        AND f.language_from = lp.native_language
+                        AND f.language_to = lp.learning_language
+                    WHERE lp.id = $1
+                )::int AS total_cards_learned,
+                (SELECT MAX(last_day) FROM runs) AS last_review_date
+        "#,
+    )
+    .bind(profile_id)
+    .fetch_one(executor)
+    .await
+}
+
+pub async fn get_activity<'e, E>(
+    executor: E,
+    profile_id: Uuid,
+    days: i32,
+) -> Result<Vec<ActivityDay>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT activity_date, reviews_count
+            FROM profile_activity
+            WHERE profile_id = $1
+                AND activity_date >= (
+                    SELECT user_local_date(user_id) FROM learning_profiles WHERE id = $1
+                ) - $2
+            ORDER BY activity_date
+        "#,
+    )
+    .bind(profile_id)
+    .bind(days)
+    .fetch_all(executor)
+    .await
+}
+
+/// Due reviewed cards per learning profile of the user; profiles with none are omitted
+pub async fn count_due_by_profile<'e, E>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<Vec<ProfileDueCount>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT lp.id AS profile_id, COUNT(*) AS due_now
+            FROM learning_profiles lp
+            JOIN user_card_progress ucp
+                ON ucp.user_id = lp.user_id AND ucp.next_review_at <= NOW()
+            JOIN flashcards f
+                ON f.id = ucp.flashcard_id
+                AND f.language_from = lp.native_language
+                AND f.language_to = lp.learning_language
+            WHERE lp.user_id = $1
+            GROUP BY lp.id
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// Reviewed cards of the profile's language pair that are due, most overdue first.
+///
+/// New cards are not included; they are introduced through deck practice.
+pub async fn get_due_cards<'e, E>(
+    executor: E,
+    profile_id: Uuid,
+    limit: i64,
+) -> Result<Vec<DueCard>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT DISTINCT ON (ucp.next_review_at, f.id)
+                df.deck_id,
+                f.id,
+                f.term,
+                f.translation,
+                ucp.times_correct,
+                ucp.times_wrong,
+                fd.score AS difficulty,
+                ucp.next_review_at
+            FROM learning_profiles lp
+            JOIN user_card_progress ucp
+                ON ucp.user_id = lp.user_id AND ucp.next_review_at <= NOW()
+            JOIN flashcards f
+                ON f.id = ucp.flashcard_id
+                AND f.language_from = lp.native_language
+                AND f.language_to = lp.learning_language
+            JOIN deck_flashcards df ON df.flashcard_id = f.id
+            LEFT JOIN flashcard_difficulty fd ON fd.flashcard_id = f.id
+            WHERE lp.id = $1
+            ORDER BY ucp.next_review_at, f.id, df.deck_id
+            LIMIT $2
+        "#,
+    )
+    .bind(profile_id)
+    .bind(limit)
+    .fetch_all(executor)
+    .await
+}
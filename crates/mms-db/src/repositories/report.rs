@@ -0,0 +1,375 @@
+//! Content reports and the moderation actions that resolve them.
+//!
+//! Targets are identified by `target_type` (`deck`, `card` or `comment`) and
+//! id. Only public content can be reported, so the deck lookups here scope to
+//! the public tenant.
+
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::models::{ContentReport, ModerationReport, ReportTarget};
+
+/// Whether the target exists, is public and is not hidden: a deck in the
+/// catalogue, a card in one of its decks, or a visible comment on one
+pub async fn target_is_public<'e, E>(
+    executor: E,
+    target_type: &str,
+    target_id: Uuid,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT CASE $1
+                WHEN 'deck' THEN EXISTS (
+                    SELECT 1 FROM decks d
+                    WHERE d.id = $2 AND d.hidden_at IS NULL AND org_visible(d.org_id, NULL)
+                )
+                WHEN 'card' THEN EXISTS (
+                    SELECT 1
+                    FROM flashcards f
+                    JOIN deck_flashcards df ON df.flashcard_id = f.id
+                    JOIN decks d ON d.id = df.deck_id
+                    WHERE f.id = $2 AND f.hidden_at IS NULL
+                      AND d.hidden_at IS NULL AND org_visible(d.org_id, NULL)
+                )
+                WHEN 'comment' THEN EXISTS (
+                    SELECT 1
+                    FROM deck_comments c
+                    JOIN decks d ON d.id = c.deck_id
+                    WHERE c.id = $2 AND c.hidden_at IS NULL
+                      AND d.hidden_at IS NULL AND org_visible(d.org_id, NULL)
+                )
+                ELSE FALSE
+            END
+        "#,
+    )
+    .bind(target_type)
+    .bind(target_id)
+    .fetch_one(executor)
+    .await
+}
+
+/// File a report; None if the user already has an open report on the target
+pub async fn create<'e, E>(
+    executor: E,
+    reporter_id: Uuid,
+    target_type: &str,
+    target_id: Uuid,
+    reason: &str,
+    details: Option<&str>,
+) -> Result<Option<ContentReport>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO content_reports (reporter_id, target_type, target_id, reason, details)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT DO NOTHING
+            RETURNING id, target_type, target_id, reason, details, status, action,
+                      created_at, resolved_at
+        "#,
+    )
+    .bind(reporter_id)
+    .bind(target_type)
+    .bind(target_id)
+    .bind(reason)
+    .bind(details)
+    .fetch_optional(executor)
+    .await
+}
+
+/// The user's most recent reports, newest first
+pub async fn list_for_reporter<'e, E>(
+    executor: E,
+    reporter_id: Uuid,
+    limit: i64,
+) -> Result<Vec<ContentReport>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT id, target_type, target_id, reason, details, status, action,
+                   created_at, resolved_at
+            FROM content_reports
+            WHERE reporter_id = $1
+            ORDER BY created_at DESC, id
+            LIMIT $2
+        "#,
+    )
+    .bind(reporter_id)
+    .bind(limit)
+    .fetch_all(executor)
+    .await
+}
+
+/// Reports with the given status, optionally for one target type. Open
+/// reports come oldest first, as a queue; closed ones newest first.
+pub async fn list_queue<'e, E>(
+    executor: E,
+    status: &str,
+    target_type: Option<&str>,
+    limit: i64,
+) -> Result<Vec<ModerationReport>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                r.id,
+                r.target_type,
+                r.target_id,
+                CASE r.target_type
+                    WHEN 'deck' THEN (
+                        SELECT d.title FROM decks d
+                        WHERE d.id = r.target_id AND org_visible(d.org_id, NULL)
+                    )
+                    WHEN 'card' THEN (
+                        SELECT f.term || ' → ' || f.translation FROM flashcards f
+                        WHERE f.id = r.target_id
+                    )
+                    WHEN 'comment' THEN (
+                        SELECT c.body FROM deck_comments c WHERE c.id = r.target_id
+                    )
+                END AS target_preview,
+                (
+                    SELECT COUNT(*) FROM content_reports o
+                    WHERE o.target_type = r.target_type AND o.target_id = r.target_id
+                      AND o.status = 'open'
+                ) AS open_reports,
+                r.reason,
+                r.details,
+                u.username AS reporter_username,
+                r.status,
+                r.action,
+                r.resolved_by,
+                r.resolved_at,
+                r.resolution_note,
+                r.created_at
+            FROM content_reports r
+            LEFT JOIN users u ON u.id = r.reporter_id
+            WHERE r.status = $1 AND ($2::text IS NULL OR r.target_type = $2)
+            ORDER BY
+                CASE WHEN r.status = 'open' THEN r.created_at END,
+                r.created_at DESC,
+                r.id
+            LIMIT $3
+        "#,
+    )
+    .bind(status)
+    .bind(target_type)
+    .bind(limit)
+    .fetch_all(executor)
+    .await
+}
+
+/// The target and status of a report
+pub async fn find_target<'e, E>(
+    executor: E,
+    report_id: Uuid,
+) -> Result<Option<ReportTarget>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT target_type, target_id, status FROM content_reports WHERE id = $1
+        "#,
+    )
+    .bind(report_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Close every open report on a target. `action` is None when dismissing.
+///
+/// Returns the number of reports closed.
+pub async fn resolve<'e, E>(
+    executor: E,
+    target_type: &str,
+    target_id: Uuid,
+    status: &str,
+    action: Option<&str>,
+    moderator_id: Option<Uuid>,
+    note: Option<&str>,
+) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE content_reports
+            SET status = $3,
+                action = $4,
+                resolved_by = $5,
+                resolved_at = NOW(),
+                resolution_note = $6
+            WHERE target_type = $1 AND target_id = $2 AND status = 'open'
+        "#,
+    )
+    .bind(target_type)
+    .bind(target_id)
+    .bind(status)
+    .bind(action)
+    .bind(moderator_id)
+    .bind(note)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Take a deck out of the public catalogue
+pub async fn hide_deck<'e, E>(executor: E, deck_id: Uuid) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE decks SET hidden_at = COALESCE(hidden_at, NOW()) WHERE id = $1
+        "#,
+    )
+    .bind(deck_id)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Stop a card from being practised
+pub async fn hide_card<'e, E>(executor: E, card_id: Uuid) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE flashcards SET hidden_at = COALESCE(hidden_at, NOW()) WHERE id = $1
+        "#,
+    )
+    .bind(card_id)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Hide a comment from its deck's listing, recording the moderator
+pub async fn hide_comment<'e, E>(
+    executor: E,
+    comment_id: Uuid,
+    moderator_id: Option<Uuid>,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE deck_comments
+            SET hidden_at = COALESCE(hidden_at, NOW()), hidden_by = $2
+            WHERE id = $1
+        "#,
+    )
+    .bind(comment_id)
+    .bind(moderator_id)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Whether a roadmap places the deck, which keeps it from being deleted
+pub async fn deck_in_roadmap<'e, E>(executor: E, deck_id: Uuid) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT EXISTS (SELECT 1 FROM roadmap_nodes WHERE deck_id = $1)
+        "#,
+    )
+    .bind(deck_id)
+    .fetch_one(executor)
+    .await
+}
+
+/// Delete a public deck; its cards stay in any other decks
+pub async fn delete_deck<'e, E>(executor: E, deck_id: Uuid) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM decks d WHERE d.id = $1 AND org_visible(d.org_id, NULL)
+        "#,
+    )
+    .bind(deck_id)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Delete a card from every deck and the learners' progress on it
+pub async fn delete_card<'e, E>(executor: E, card_id: Uuid) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            WITH unlinked AS (
+                DELETE FROM deck_flashcards WHERE flashcard_id = $1
+            )
+            DELETE FROM flashcards WHERE id = $1
+        "#,
+    )
+    .bind(card_id)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn delete_comment<'e, E>(executor: E, comment_id: Uuid) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM deck_comments WHERE id = $1
+        "#,
+    )
+    .bind(comment_id)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Who wrote a comment, or None if it no longer exists
+pub async fn comment_author<'e, E>(
+    executor: E,
+    comment_id: Uuid,
+) -> Result<Option<Uuid>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT user_id FROM deck_comments WHERE id = $1
+        "#,
+    )
+    .bind(comment_id)
+    .fetch_optional(executor)
+    .await
+}
@@ -0,0 +1,247 @@
+//! The review log: one row per graded answer, for statistics over time.
+//!
+//! Every aggregate skips flagged reviews and covers the last `days` local days
+//! of the user, today included.
+
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::models::{
+    CardAnswerTime, DailyReviewCounts, DeckAnswerTime, NewReviewLog, RetentionCounts,
+    SlotReviewCounts,
+};
+
+pub async fn insert<'e, E>(executor: E, review: &NewReviewLog) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO review_logs
+                (user_id, flashcard_id, deck_id, is_correct, previous_interval_days,
+                 interval_days, response_time_ms, elapsed_ms, flagged, reviewed_at, mode)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        "#,
+    )
+    .bind(review.user_id)
+    .bind(review.flashcard_id)
+    .bind(review.deck_id)
+    .bind(review.is_correct)
+    .bind(review.previous_interval_days)
+    .bind(review.interval_days)
+    .bind(review.response_time_ms)
+    .bind(review.elapsed_ms)
+    .bind(review.flagged)
+    .bind(review.reviewed_at)
+    .bind(review.mode)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Reviews and correct answers per local day, oldest first; days without reviews are omitted
+pub async fn daily_counts<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    days: i32,
+) -> Result<Vec<DailyReviewCounts>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                (l.reviewed_at AT TIME ZONE u.timezone)::date AS date,
+                COUNT(*) AS reviews,
+                COUNT(*) FILTER (WHERE l.is_correct) AS correct
+            FROM users u
+            JOIN review_logs l ON l.user_id = u.id
+            WHERE u.id = $1
+              AND NOT l.flagged
+              AND l.reviewed_at >= (user_local_date(u.id) - ($2::int - 1))::timestamp AT TIME ZONE u.timezone
+            GROUP BY 1
+            ORDER BY 1
+        "#,
+    )
+    .bind(user_id)
+    .bind(days)
+    .fetch_all(executor)
+    .await
+}
+
+/// Reviews and correct answers per ISO weekday (1 = Monday) in the user's
+/// timezone; weekdays without reviews are omitted
+pub async fn weekday_counts<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    days: i32,
+) -> Result<Vec<SlotReviewCounts>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                EXTRACT(ISODOW FROM l.reviewed_at AT TIME ZONE u.timezone)::int AS slot,
+                COUNT(*) AS reviews,
+                COUNT(*) FILTER (WHERE l.is_correct) AS correct
+            FROM users u
+            JOIN review_logs l ON l.user_id = u.id
+            WHERE u.id = $1
+              AND NOT l.flagged
+              AND l.reviewed_at >= (user_local_date(u.id) - ($2::int - 1))::timestamp AT TIME ZONE u.timezone
+            GROUP BY 1
+        "#,
+    )
+    .bind(user_id)
+    .bind(days)
+    .fetch_all(executor)
+    .await
+}
+
+/// Reviews and correct answers per hour of the day (0 to 23) in the user's
+/// timezone; hours without reviews are omitted
+pub async fn hour_counts<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    days: i32,
+) -> Result<Vec<SlotReviewCounts>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                EXTRACT(HOUR FROM l.reviewed_at AT TIME ZONE u.timezone)::int AS slot,
+                COUNT(*) AS reviews,
+                COUNT(*) FILTER (WHERE l.is_correct) AS correct
+            FROM users u
+            JOIN review_logs l ON l.user_id = u.id
+            WHERE u.id = $1
+              AND NOT l.flagged
+              AND l.reviewed_at >= (user_local_date(u.id) - ($2::int - 1))::timestamp AT TIME ZONE u.timezone
+            GROUP BY 1
+        "#,
+    )
+    .bind(user_id)
+    .bind(days)
+    .fetch_all(executor)
+    .await
+}
+
+/// Answers to cards reviewed at an interval of at least `learning_days`, i.e.
+/// recalled from memory rather than during learning. Those reviewed at
+/// `mature_days` or more count as mature, the rest as young.
+pub async fn retention_counts<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    days: i32,
+    learning_days: f64,
+    mature_days: f64,
+) -> Result<RetentionCounts, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                COUNT(*) FILTER (WHERE l.previous_interval_days < $4) AS young_reviews,
+                COUNT(*) FILTER (WHERE l.previous_interval_days < $4 AND l.is_correct) AS young_correct,
+                COUNT(*) FILTER (WHERE l.previous_interval_days >= $4) AS mature_reviews,
+                COUNT(*) FILTER (WHERE l.previous_interval_days >= $4 AND l.is_correct) AS mature_correct
+            FROM users u
+            JOIN review_logs l ON l.user_id = u.id
+            WHERE u.id = $1
+              AND NOT l.flagged
+              AND l.previous_interval_days >= $3
+              AND l.reviewed_at >= (user_local_date(u.id) - ($2::int - 1))::timestamp AT TIME ZONE u.timezone
+        "#,
+    )
+    .bind(user_id)
+    .bind(days)
+    .bind(learning_days)
+    .bind(mature_days)
+    .fetch_one(executor)
+    .await
+}
+
+/// Average answer time per deck the user reviewed in, most reviewed first,
+/// from the reviews that reported one
+pub async fn answer_times_by_deck<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    days: i32,
+) -> Result<Vec<DeckAnswerTime>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                l.deck_id,
+                d.title,
+                COUNT(*) AS reviews,
+                AVG(l.response_time_ms)::float8 AS average_ms
+            FROM users u
+            JOIN review_logs l ON l.user_id = u.id
+            LEFT JOIN decks d ON d.id = l.deck_id AND org_visible(d.org_id, u.id)
+            WHERE u.id = $1
+              AND NOT l.flagged
+              AND l.response_time_ms IS NOT NULL
+              AND l.reviewed_at >= (user_local_date(u.id) - ($2::int - 1))::timestamp AT TIME ZONE u.timezone
+            GROUP BY l.deck_id, d.title
+            ORDER BY reviews DESC, l.deck_id
+        "#,
+    )
+    .bind(user_id)
+    .bind(days)
+    .fetch_all(executor)
+    .await
+}
+
+/// The cards the user takes longest to answer, among those answered with a
+/// reported time at least `min_reviews` times
+pub async fn slowest_cards<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    days: i32,
+    min_reviews: i64,
+    limit: i64,
+) -> Result<Vec<CardAnswerTime>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                l.flashcard_id,
+                f.term,
+                COUNT(*) AS reviews,
+                AVG(l.response_time_ms)::float8 AS average_ms
+            FROM users u
+            JOIN review_logs l ON l.user_id = u.id
+            JOIN flashcards f ON f.id = l.flashcard_id
+            WHERE u.id = $1
+              AND NOT l.flagged
+              AND l.response_time_ms IS NOT NULL
+              AND l.reviewed_at >= (user_local_date(u.id) - ($2::int - 1))::timestamp AT TIME ZONE u.timezone
+            GROUP BY l.flashcard_id, f.term
+            HAVING COUNT(*) >= $3
+            ORDER BY average_ms DESC, l.flashcard_id
+            LIMIT $4
+        "#,
+    )
+    .bind(user_id)
+    .bind(days)
+    .bind(min_reviews)
+    .bind(limit)
+    .fetch_all(executor)
+    .await
+}
@@ -1,33 +1,42 @@
 use sqlx::{Executor, Postgres};
 use uuid::Uuid;
 
-use crate::models::{Roadmap, RoadmapMetadata, RoadmapNodeWithProgress};
+use crate::{
+    models::{CatalogRoadmap, Roadmap, RoadmapMetadata, RoadmapNode, RoadmapNodeWithProgress},
+    tenancy::{Tenant, TenantQuery},
+};
 
+/// One page of the roadmaps the tenant may see, newest first
 pub async fn list_all<'e, E>(
     executor: E,
+    tenant: Tenant,
     limit: i64,
     offset: i64,
 ) -> Result<Vec<Roadmap>, sqlx::Error>
 where
     E: Executor<'e, Database = Postgres>,
 {
-    sqlx::query_as(
+    let mut query = TenantQuery::new(
+        tenant,
         // language=PostgreSQL
         r#"
-            SELECT id, title, description, language_from, language_to
-            FROM roadmaps
-            ORDER BY created_at DESC
-            LIMIT $1 OFFSET $2
-        "#,
-    )
-    .bind(limit)
-    .bind(offset)
-    .fetch_all(executor)
-    .await
+            SELECT r.id, r.slug, r.title, r.description, r.language_from, r.language_to
+            FROM roadmaps r
+            WHERE "#,
+    );
+    query
+        .push_visible("r")
+        .push(" ORDER BY r.created_at DESC LIMIT ")
+        .push_bind(limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+    query.build_query_as().fetch_all(executor).await
 }
 
+/// One page of the roadmaps for a language pair the tenant may see, newest first
 pub async fn list_by_language<'e, E>(
     executor: E,
+    tenant: Tenant,
     language_from: &str,
     language_to: &str,
     limit: i64,
@@ -36,36 +45,107 @@ pub async fn list_by_language<'e, E>(
 where
     E: Executor<'e, Database = Postgres>,
 {
-    sqlx::query_as(
+    let mut query = TenantQuery::new(
+        tenant,
         // language=PostgreSQL
         r#"
-            SELECT id, title, description, language_from, language_to
-            FROM roadmaps
-            WHERE language_from = $1 AND language_to = $2
-            ORDER BY created_at DESC
-            LIMIT $3 OFFSET $4
-        "#,
-    )
-    .bind(language_from)
-    .bind(language_to)
-    .bind(limit)
-    .bind(offset)
-    .fetch_all(executor)
-    .await
+            SELECT r.id, r.slug, r.title, r.description, r.language_from, r.language_to
+            FROM roadmaps r
+            WHERE r.language_from = "#,
+    );
+    query
+        .push_bind(language_from)
+        .push(" AND r.language_to = ")
+        .push_bind(language_to)
+        .push(" AND ")
+        .push_visible("r")
+        .push(" ORDER BY r.created_at DESC LIMIT ")
+        .push_bind(limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+    query.build_query_as().fetch_all(executor).await
+}
+
+/// One page of the public roadmaps with their deck and card counts, newest
+/// first, optionally for one language pair
+pub async fn list_catalog<'e, E>(
+    executor: E,
+    languages: Option<(&str, &str)>,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<CatalogRoadmap>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let mut query = TenantQuery::new(
+        Tenant::Public,
+        // language=PostgreSQL
+        r#"
+            SELECT r.id, r.slug, r.title, r.description, r.language_from, r.language_to,
+                   COUNT(DISTINCT d.id) AS deck_count,
+                   COUNT(DISTINCT f.id) AS card_count
+            FROM roadmaps r
+            LEFT JOIN roadmap_nodes rn ON rn.roadmap_id = r.id
+            LEFT JOIN decks d ON d.id = rn.deck_id AND d.hidden_at IS NULL AND "#,
+    );
+    query.push_visible("d").push(
+        r#"
+            LEFT JOIN deck_flashcards df ON df.deck_id = d.id
+            LEFT JOIN flashcards f ON f.id = df.flashcard_id AND f.hidden_at IS NULL
+            WHERE "#,
+    );
+    query.push_visible("r");
+    if let Some((language_from, language_to)) = languages {
+        query
+            .push(" AND r.language_from = ")
+            .push_bind(language_from)
+            .push(" AND r.language_to = ")
+            .push_bind(language_to);
+    }
+    query
+        .push(" GROUP BY r.id ORDER BY r.created_at DESC, r.id LIMIT ")
+        .push_bind(limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+    query.build_query_as().fetch_all(executor).await
+}
+
+/// The id of the roadmap with the slug, if it exists and the tenant may see it
+pub async fn find_id_by_slug<'e, E>(
+    executor: E,
+    tenant: Tenant,
+    slug: &str,
+) -> Result<Option<Uuid>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let mut query = TenantQuery::new(
+        tenant,
+        // language=PostgreSQL
+        r#"
+            SELECT r.id
+            FROM roadmaps r
+            WHERE r.slug = "#,
+    );
+    query.push_bind(slug).push(" AND ").push_visible("r");
+    query.build_query_scalar().fetch_optional(executor).await
 }
 
 pub async fn get_metadata<'e, E>(
     executor: E,
+    tenant: Tenant,
     roadmap_id: Uuid,
 ) -> Result<RoadmapMetadata, sqlx::Error>
 where
     E: Executor<'e, Database = Postgres>,
 {
-    sqlx::query_as(
+    let mut query = TenantQuery::new(
+        tenant,
         // language=PostgreSQL
         r#"
             SELECT
                 r.id,
+                r.slug,
                 r.title,
                 r.description,
                 r.language_from,
@@ -75,23 +155,26 @@ where
                 0.0::float8 as progress_percentage
             FROM roadmaps r
             LEFT JOIN roadmap_nodes rn ON rn.roadmap_id = r.id
-            WHERE r.id = $1
-            GROUP BY r.id, r.title, r.description, r.language_from, r.language_to
-        "#,
-    )
-    .bind(roadmap_id)
-    .fetch_one(executor)
-    .await
+            WHERE r.id = "#,
+    );
+    query
+        .push_bind(roadmap_id)
+        .push(" AND ")
+        .push_visible("r")
+        .push(" GROUP BY r.id, r.title, r.description, r.language_from, r.language_to");
+    query.build_query_as().fetch_one(executor).await
 }
 
 pub async fn get_nodes<'e, E>(
     executor: E,
+    tenant: Tenant,
     roadmap_id: Uuid,
 ) -> Result<Vec<RoadmapNodeWithProgress>, sqlx::Error>
 where
     E: Executor<'e, Database = Postgres>,
 {
-    sqlx::query_as(
+    let mut query = TenantQuery::new(
+        tenant,
         // language=PostgreSQL
         r#"
             SELECT
@@ -111,13 +194,14 @@ where
                 NULL::timestamptz as next_practice_at
             FROM roadmap_nodes rn
             JOIN decks d ON d.id = rn.deck_id
-            WHERE rn.roadmap_id = $1
-            ORDER BY rn.pos_y, rn.pos_x
-        "#,
-    )
-    .bind(roadmap_id)
-    .fetch_all(executor)
-    .await
+            WHERE rn.roadmap_id = "#,
+    );
+    query
+        .push_bind(roadmap_id)
+        .push(" AND ")
+        .push_visible("d")
+        .push(" ORDER BY rn.pos_y, rn.pos_x");
+    query.build_query_as().fetch_all(executor).await
 }
 
 pub async fn get_metadata_with_progress<'e, E>(
@@ -133,6 +217,7 @@ where
         r#"
             SELECT
                 r.id,
+                r.slug,
                 r.title,
                 r.description,
                 r.language_from,
@@ -154,7 +239,7 @@ where
             LEFT JOIN roadmap_nodes rn ON rn.roadmap_id = r.id
             LEFT JOIN user_deck_progress udp
                 ON udp.deck_id = rn.deck_id AND udp.user_id = $2
-            WHERE r.id = $1
+            WHERE r.id = $1 AND org_visible(r.org_id, $2)
             GROUP BY r.id, r.title, r.description, r.language_from, r.language_to
         "#,
     )
@@ -164,6 +249,10 @@ where
     .await
 }
 
+/// The roadmap's deck nodes with the user's progress on each deck.
+///
+/// The cards of every deck are counted in one lateral aggregate per node, a
+/// single pass over the deck's cards and the user's progress on them.
 pub async fn get_nodes_with_progress<'e, E>(
     executor: E,
     roadmap_id: Uuid,
@@ -183,38 +272,30 @@ where
                 d.id as deck_id,
                 d.title as deck_title,
                 d.description as deck_description,
-                COALESCE(udp.total_cards, (
-                    SELECT COUNT(*)::int FROM deck_flashcards df WHERE df.deck_id = d.id
-                )) as total_cards,
+                COALESCE(udp.total_cards, cards.total) as total_cards,
                 COALESCE(udp.mastered_cards, 0) as mastered_cards,
-                (
-                    SELECT COUNT(*)::int
-                    FROM deck_flashcards df2
-                    LEFT JOIN user_card_progress ucp2
-                        ON ucp2.flashcard_id = df2.flashcard_id AND ucp2.user_id = $2
-                    WHERE df2.deck_id = d.id
-                        AND (ucp2.next_review_at IS NULL OR ucp2.next_review_at <= NOW())
-                ) as cards_due_today,
+                cards.due as cards_due_today,
                 COALESCE(udp.total_practices, 0) as total_practices,
                 udp.last_practiced_at,
                 COALESCE(udp.progress_percentage, 0.0)::float8 as progress_percentage,
-                (
-                    SELECT CASE
-                        WHEN COUNT(*) FILTER (
-                            WHERE ucp3.next_review_at IS NULL OR ucp3.next_review_at <= NOW()
-                        ) > 0 THEN NULL
-                        ELSE MIN(ucp3.next_review_at)
-                    END
-                    FROM deck_flashcards df3
-                    LEFT JOIN user_card_progress ucp3
-                        ON ucp3.flashcard_id = df3.flashcard_id AND ucp3.user_id = $2
-                    WHERE df3.deck_id = d.id
-                )::timestamptz as next_practice_at
+                CASE WHEN cards.due > 0 THEN NULL ELSE cards.next_review_at END as next_practice_at
             FROM roadmap_nodes rn
             JOIN decks d ON d.id = rn.deck_id
             LEFT JOIN user_deck_progress udp
                 ON udp.deck_id = d.id AND udp.user_id = $2
-            WHERE rn.roadmap_id = $1
+            CROSS JOIN LATERAL (
+                SELECT
+                    COUNT(*)::int as total,
+                    COUNT(*) FILTER (
+                        WHERE ucp.next_review_at IS NULL OR ucp.next_review_at <= NOW()
+                    )::int as due,
+                    MIN(ucp.next_review_at) as next_review_at
+                FROM deck_flashcards df
+                LEFT JOIN user_card_progress ucp
+                    ON ucp.flashcard_id = df.flashcard_id AND ucp.user_id = $2
+                WHERE df.deck_id = d.id
+            ) cards
+            WHERE rn.roadmap_id = $1 AND org_visible(d.org_id, $2)
             ORDER BY rn.pos_y, rn.pos_x
         "#,
     )
@@ -223,3 +304,142 @@ where
     .fetch_all(executor)
     .await
 }
+
+/// The roadmaps among `roadmap_ids` the user may see
+pub async fn list_by_ids<'e, E>(
+    executor: E,
+    roadmap_ids: &[Uuid],
+    user_id: Uuid,
+) -> Result<Vec<Roadmap>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT r.id, r.slug, r.title, r.description, r.language_from, r.language_to
+            FROM roadmaps r
+            WHERE r.id = ANY($1) AND org_visible(r.org_id, $2)
+        "#,
+    )
+    .bind(roadmap_ids)
+    .bind(user_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// [`get_metadata_with_progress`] for several roadmaps at once; roadmaps the
+/// user may not see are left out
+pub async fn list_metadata_with_progress<'e, E>(
+    executor: E,
+    roadmap_ids: &[Uuid],
+    user_id: Uuid,
+) -> Result<Vec<RoadmapMetadata>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                r.id,
+                r.slug,
+                r.title,
+                r.description,
+                r.language_from,
+                r.language_to,
+                COUNT(rn.id)::int as total_nodes,
+                COUNT(rn.id) FILTER (
+                    WHERE udp.mastered_cards > 0
+                    AND udp.mastered_cards = udp.total_cards
+                )::int as completed_nodes,
+                CASE
+                    WHEN COUNT(rn.id) > 0 THEN
+                        (COUNT(rn.id) FILTER (
+                            WHERE udp.mastered_cards > 0
+                            AND udp.mastered_cards = udp.total_cards
+                        )::float8 / COUNT(rn.id)::float8 * 100.0)
+                    ELSE 0.0
+                END as progress_percentage
+            FROM roadmaps r
+            LEFT JOIN roadmap_nodes rn ON rn.roadmap_id = r.id
+            LEFT JOIN user_deck_progress udp
+                ON udp.deck_id = rn.deck_id AND udp.user_id = $2
+            WHERE r.id = ANY($1) AND org_visible(r.org_id, $2)
+            GROUP BY r.id, r.title, r.description, r.language_from, r.language_to
+        "#,
+    )
+    .bind(roadmap_ids)
+    .bind(user_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// The nodes of several roadmaps whose decks the user may see, each roadmap's
+/// ordered top to bottom, left to right
+pub async fn list_nodes<'e, E>(
+    executor: E,
+    roadmap_ids: &[Uuid],
+    user_id: Uuid,
+) -> Result<Vec<RoadmapNode>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT rn.id, rn.roadmap_id, rn.parent_node_id, rn.pos_x, rn.pos_y, rn.deck_id
+            FROM roadmap_nodes rn
+            JOIN decks d ON d.id = rn.deck_id
+            WHERE rn.roadmap_id = ANY($1) AND org_visible(d.org_id, $2)
+            ORDER BY rn.roadmap_id, rn.pos_y, rn.pos_x
+        "#,
+    )
+    .bind(roadmap_ids)
+    .bind(user_id)
+    .fetch_all(executor)
+    .await
+}
+
+/// The roadmaps the user has practised a deck of, with their progress, most
+/// recently practised first
+pub async fn list_active_with_progress<'e, E>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<Vec<RoadmapMetadata>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                r.id,
+                r.slug,
+                r.title,
+                r.description,
+                r.language_from,
+                r.language_to,
+                COUNT(rn.id)::int as total_nodes,
+                COUNT(rn.id) FILTER (
+                    WHERE udp.mastered_cards > 0
+                    AND udp.mastered_cards = udp.total_cards
+                )::int as completed_nodes,
+                (COUNT(rn.id) FILTER (
+                    WHERE udp.mastered_cards > 0
+                    AND udp.mastered_cards = udp.total_cards
+                )::float8 / COUNT(rn.id)::float8 * 100.0) as progress_percentage
+            FROM roadmaps r
+            JOIN roadmap_nodes rn ON rn.roadmap_id = r.id
+            LEFT JOIN user_deck_progress udp
+                ON udp.deck_id = rn.deck_id AND udp.user_id = $1
+            WHERE org_visible(r.org_id, $1)
+            GROUP BY r.id, r.title, r.description, r.language_from, r.language_to
+            HAVING MAX(udp.last_practiced_at) IS NOT NULL
+            ORDER BY MAX(udp.last_practiced_at) DESC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(executor)
+    .await
+}
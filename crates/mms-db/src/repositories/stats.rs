@@ -0,0 +1,258 @@
+//! Learning statistics computed from review progress.
+
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::models::{DeckCardCounts, IntervalBucketCount, IntervalSnapshot, MaturityCounts};
+
+/// Count a user's reviewed cards per interval bucket.
+///
+/// `lower_bounds_days` holds the ascending lower bound of every bucket after
+/// the first; a card lands in bucket `i` when `i` bounds are at or below its
+/// interval. Empty buckets are omitted.
+pub async fn interval_distribution<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    lower_bounds_days: &[f64],
+) -> Result<Vec<IntervalBucketCount>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                width_bucket(
+                    EXTRACT(EPOCH FROM next_review_at - last_review_at)::FLOAT8 / 86400,
+                    $2::FLOAT8[]
+                ) AS bucket,
+                COUNT(*) AS cards
+            FROM user_card_progress
+            WHERE user_id = $1
+              AND last_review_at IS NOT NULL
+            GROUP BY bucket
+            ORDER BY bucket
+        "#,
+    )
+    .bind(user_id)
+    .bind(lower_bounds_days)
+    .fetch_all(executor)
+    .await
+}
+
+/// Median interval in days across a user's reviewed cards, `None` before any review
+pub async fn median_interval_days<'e, E>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<Option<f64>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT percentile_cont(0.5) WITHIN GROUP (
+                ORDER BY EXTRACT(EPOCH FROM next_review_at - last_review_at)::FLOAT8 / 86400
+            )
+            FROM user_card_progress
+            WHERE user_id = $1
+              AND last_review_at IS NOT NULL
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(executor)
+    .await
+}
+
+/// Mean interval in days across a user's reviewed cards, `None` before any review
+pub async fn mean_interval_days<'e, E>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<Option<f64>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT AVG(EXTRACT(EPOCH FROM next_review_at - last_review_at)::FLOAT8 / 86400)
+            FROM user_card_progress
+            WHERE user_id = $1
+              AND last_review_at IS NOT NULL
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(executor)
+    .await
+}
+
+/// Count a user's cards by maturity: never reviewed cards of the decks they
+/// practise, then cards whose interval is under `learning_days` (learning),
+/// under `mature_days` (young) or longer (mature). Cards marked as known
+/// without a review are counted by the interval they were given.
+pub async fn maturity_counts<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    learning_days: f64,
+    mature_days: f64,
+) -> Result<MaturityCounts, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            WITH intervals AS (
+                SELECT EXTRACT(EPOCH FROM next_review_at - COALESCE(last_review_at, updated_at))::FLOAT8 / 86400 AS days
+                FROM user_card_progress
+                WHERE user_id = $1
+            )
+            SELECT
+                (
+                    SELECT COUNT(DISTINCT df.flashcard_id)
+                    FROM user_deck_progress udp
+                    JOIN deck_flashcards df ON df.deck_id = udp.deck_id
+                    JOIN flashcards f ON f.id = df.flashcard_id AND f.hidden_at IS NULL
+                    LEFT JOIN user_card_progress p
+                        ON p.user_id = udp.user_id AND p.flashcard_id = df.flashcard_id
+                    WHERE udp.user_id = $1 AND p.flashcard_id IS NULL
+                ) AS new_cards,
+                COUNT(*) FILTER (WHERE days < $2) AS learning,
+                COUNT(*) FILTER (WHERE days >= $2 AND days < $3) AS young,
+                COUNT(*) FILTER (WHERE days >= $3) AS mature
+            FROM intervals
+        "#,
+    )
+    .bind(user_id)
+    .bind(learning_days)
+    .bind(mature_days)
+    .fetch_one(executor)
+    .await
+}
+
+/// New cards and cards due now in each deck the user has started, plus a
+/// row for all of them with `deck_id` `None`, in one grouped pass.
+///
+/// Due cards at an interval shorter than `learning_days` count as learning,
+/// the others as due. Hidden cards are left out; the total counts a card in
+/// several decks once.
+pub async fn due_counts_by_deck<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    learning_days: f64,
+) -> Result<Vec<DeckCardCounts>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                d.id AS deck_id,
+                d.title,
+                COUNT(DISTINCT f.id) FILTER (WHERE p.flashcard_id IS NULL) AS new_cards,
+                COUNT(DISTINCT f.id) FILTER (
+                    WHERE p.next_review_at <= NOW()
+                    AND p.next_review_at - COALESCE(p.last_review_at, p.updated_at)
+                        < make_interval(secs => $2 * 86400)
+                ) AS learning,
+                COUNT(DISTINCT f.id) FILTER (
+                    WHERE p.next_review_at <= NOW()
+                    AND p.next_review_at - COALESCE(p.last_review_at, p.updated_at)
+                        >= make_interval(secs => $2 * 86400)
+                ) AS due
+            FROM user_deck_progress udp
+            JOIN decks d ON d.id = udp.deck_id
+            LEFT JOIN deck_flashcards df ON df.deck_id = d.id
+            LEFT JOIN flashcards f ON f.id = df.flashcard_id AND f.hidden_at IS NULL
+            LEFT JOIN user_card_progress p
+                ON p.user_id = udp.user_id AND p.flashcard_id = f.id
+            WHERE udp.user_id = $1 AND org_visible(d.org_id, $1)
+            GROUP BY GROUPING SETS ((d.id, d.title), ())
+            ORDER BY d.title NULLS FIRST, d.id
+        "#,
+    )
+    .bind(user_id)
+    .bind(learning_days)
+    .fetch_all(executor)
+    .await
+}
+
+/// Stored snapshots of the last `weeks` weeks, including the current one, oldest first
+pub async fn list_interval_snapshots<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    weeks: i32,
+) -> Result<Vec<IntervalSnapshot>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT week_start, bucket, cards
+            FROM interval_snapshots
+            WHERE user_id = $1
+              AND week_start > date_trunc('week', NOW() AT TIME ZONE 'UTC')::DATE - 7 * $2
+            ORDER BY week_start, bucket
+        "#,
+    )
+    .bind(user_id)
+    .bind(weeks)
+    .fetch_all(executor)
+    .await
+}
+
+/// Delete every user's snapshot of the current week, before it is rewritten
+pub async fn delete_current_week_snapshots<'e, E>(executor: E) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM interval_snapshots
+            WHERE week_start = date_trunc('week', NOW() AT TIME ZONE 'UTC')::DATE
+        "#,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Snapshot every user's interval distribution as the current week's.
+///
+/// Buckets are computed as in [`interval_distribution`]. Run after
+/// [`delete_current_week_snapshots`] in the same transaction. Returns the
+/// number of rows written.
+pub async fn insert_current_week_snapshots<'e, E>(
+    executor: E,
+    lower_bounds_days: &[f64],
+) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO interval_snapshots (user_id, week_start, bucket, cards)
+            SELECT
+                user_id,
+                date_trunc('week', NOW() AT TIME ZONE 'UTC')::DATE,
+                width_bucket(
+                    EXTRACT(EPOCH FROM next_review_at - last_review_at)::FLOAT8 / 86400,
+                    $1::FLOAT8[]
+                ) AS bucket,
+                COUNT(*)
+            FROM user_card_progress
+            WHERE last_review_at IS NOT NULL
+            GROUP BY user_id, bucket
+        "#,
+    )
+    .bind(lower_bounds_days)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,307 @@
+// Changes are selected by `change_xid >= watermark AND change_seq > after`, and
+// pages end at `upto`; see migration 0020 for why the watermark is a transaction id.
+
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::models::{
+    ProgressState, SyncCard, SyncDeck, SyncPageBounds, SyncProgress, SyncTombstone,
+};
+
+/// Make the transaction read one snapshot throughout, so every table is read
+/// as of the same moment. Must be the first statement of the transaction.
+pub async fn begin_snapshot<'e, E>(executor: E) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        "SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY",
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Oldest transaction still running; every change not yet visible will have an id at or above it
+pub async fn current_watermark<'e, E>(executor: E) -> Result<i64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT pg_snapshot_xmin(pg_current_snapshot())::text::bigint
+        "#,
+    )
+    .fetch_one(executor)
+    .await
+}
+
+/// Find where a page of at most `limit` changes visible to the user ends
+pub async fn page_bounds<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    watermark: i64,
+    after: i64,
+    limit: i64,
+) -> Result<SyncPageBounds, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            WITH changes AS (
+                SELECT d.change_seq FROM decks d
+                WHERE d.change_xid >= $2 AND d.change_seq > $3 AND org_visible(d.org_id, $1)
+                UNION ALL
+                SELECT change_seq FROM flashcards
+                WHERE change_xid >= $2 AND change_seq > $3
+                UNION ALL
+                SELECT change_seq FROM user_card_progress
+                WHERE user_id = $1 AND change_xid >= $2 AND change_seq > $3
+                UNION ALL
+                SELECT change_seq FROM sync_tombstones
+                WHERE (user_id IS NULL OR user_id = $1) AND change_xid >= $2 AND change_seq > $3
+                ORDER BY change_seq
+                LIMIT $4 + 1
+            ),
+            numbered AS (
+                SELECT change_seq, ROW_NUMBER() OVER (ORDER BY change_seq) AS n
+                FROM changes
+            )
+            SELECT
+                MAX(change_seq) FILTER (WHERE n <= $4) AS last_seq,
+                COUNT(*) > $4 AS has_more
+            FROM numbered
+        "#,
+    )
+    .bind(user_id)
+    .bind(watermark)
+    .bind(after)
+    .bind(limit)
+    .fetch_one(executor)
+    .await
+}
+
+pub async fn changed_decks<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    watermark: i64,
+    after: i64,
+    upto: i64,
+) -> Result<Vec<SyncDeck>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                d.id, d.title, d.description,
+                d.language_from::text AS language_from,
+                d.language_to::text AS language_to,
+                COALESCE(
+                    ARRAY_AGG(df.flashcard_id ORDER BY df.flashcard_id)
+                        FILTER (WHERE df.flashcard_id IS NOT NULL),
+                    '{}'
+                ) AS flashcard_ids,
+                d.change_seq
+            FROM decks d
+            LEFT JOIN deck_flashcards df ON df.deck_id = d.id
+            WHERE d.change_xid >= $2 AND d.change_seq > $3 AND d.change_seq <= $4
+                AND org_visible(d.org_id, $1)
+            GROUP BY d.id
+            ORDER BY d.change_seq
+        "#,
+    )
+    .bind(user_id)
+    .bind(watermark)
+    .bind(after)
+    .bind(upto)
+    .fetch_all(executor)
+    .await
+}
+
+pub async fn changed_cards<'e, E>(
+    executor: E,
+    watermark: i64,
+    after: i64,
+    upto: i64,
+) -> Result<Vec<SyncCard>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                id, term, translation,
+                language_from::text AS language_from,
+                language_to::text AS language_to,
+                change_seq
+            FROM flashcards
+            WHERE change_xid >= $1 AND change_seq > $2 AND change_seq <= $3
+            ORDER BY change_seq
+        "#,
+    )
+    .bind(watermark)
+    .bind(after)
+    .bind(upto)
+    .fetch_all(executor)
+    .await
+}
+
+pub async fn changed_progress<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    watermark: i64,
+    after: i64,
+    upto: i64,
+) -> Result<Vec<SyncProgress>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                flashcard_id, next_review_at, last_review_at, times_correct, times_wrong,
+                mastered_at, updated_at, change_seq
+            FROM user_card_progress
+            WHERE user_id = $1 AND change_xid >= $2 AND change_seq > $3 AND change_seq <= $4
+            ORDER BY change_seq
+        "#,
+    )
+    .bind(user_id)
+    .bind(watermark)
+    .bind(after)
+    .bind(upto)
+    .fetch_all(executor)
+    .await
+}
+
+pub async fn tombstones<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    watermark: i64,
+    after: i64,
+    upto: i64,
+) -> Result<Vec<SyncTombstone>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT entity, entity_id AS id, change_seq
+            FROM sync_tombstones
+            WHERE (user_id IS NULL OR user_id = $1)
+              AND change_xid >= $2 AND change_seq > $3 AND change_seq <= $4
+            ORDER BY change_seq
+        "#,
+    )
+    .bind(user_id)
+    .bind(watermark)
+    .bind(after)
+    .bind(upto)
+    .fetch_all(executor)
+    .await
+}
+
+/// Lock and fetch the user's progress on a card
+pub async fn get_progress_for_update<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    flashcard_id: Uuid,
+) -> Result<Option<SyncProgress>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                flashcard_id, next_review_at, last_review_at, times_correct, times_wrong,
+                mastered_at, updated_at, change_seq
+            FROM user_card_progress
+            WHERE user_id = $1 AND flashcard_id = $2
+            FOR UPDATE
+        "#,
+    )
+    .bind(user_id)
+    .bind(flashcard_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Overwrite the user's progress on a card with a client's state.
+///
+/// A card that stays mastered keeps its original `mastered_at`. Returns `None`
+/// if the card no longer exists.
+pub async fn put_progress<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    flashcard_id: Uuid,
+    state: &ProgressState,
+    mastered: bool,
+) -> Result<Option<SyncProgress>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO user_card_progress (
+                user_id, flashcard_id, next_review_at, last_review_at,
+                times_correct, times_wrong, mastered_at
+            )
+            SELECT $1, f.id, $3, $4, $5, $6,
+                   CASE WHEN $7 THEN COALESCE($4, NOW()) END
+            FROM flashcards f
+            WHERE f.id = $2
+            ON CONFLICT (user_id, flashcard_id) DO UPDATE SET
+                next_review_at = EXCLUDED.next_review_at,
+                last_review_at = EXCLUDED.last_review_at,
+                times_correct = EXCLUDED.times_correct,
+                times_wrong = EXCLUDED.times_wrong,
+                mastered_at = CASE
+                    WHEN $7 THEN COALESCE(user_card_progress.mastered_at, EXCLUDED.mastered_at)
+                END
+            RETURNING
+                flashcard_id, next_review_at, last_review_at, times_correct, times_wrong,
+                mastered_at, updated_at, change_seq
+        "#,
+    )
+    .bind(user_id)
+    .bind(flashcard_id)
+    .bind(state.next_review_at)
+    .bind(state.last_review_at)
+    .bind(state.times_correct)
+    .bind(state.times_wrong)
+    .bind(mastered)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Decks containing any of the cards
+pub async fn deck_ids_for_cards<'e, E>(
+    executor: E,
+    flashcard_ids: &[Uuid],
+) -> Result<Vec<Uuid>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT DISTINCT deck_id
+            FROM deck_flashcards
+            WHERE flashcard_id = ANY($1)
+        "#,
+    )
+    .bind(flashcard_ids)
+    .fetch_all(executor)
+    .await
+}
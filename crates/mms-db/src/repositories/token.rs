@@ -2,11 +2,17 @@ use chrono::{DateTime, Utc};
 use sqlx::{Executor, Postgres};
 use uuid::Uuid;
 
-// --- Email verification tokens ---
+use crate::models::ExpiredTokenCleanup;
 
-pub async fn invalidate_verification_tokens<'e, E>(
+// --- One-time tokens (email verification, password reset, magic links) ---
+//
+// Every query is scoped by `purpose` so a token issued for one flow can never be
+// redeemed by another, even though they share the same table.
+
+pub async fn invalidate_tokens<'e, E>(
     executor: E,
     user_id: Uuid,
+    purpose: &str,
 ) -> Result<(), sqlx::Error>
 where
     E: Executor<'e, Database = Postgres>,
@@ -14,20 +20,22 @@ where
     sqlx::query(
         // language=PostgreSQL
         r#"
-            UPDATE email_verification_tokens
+            UPDATE one_time_tokens
             SET used_at = NOW()
-            WHERE user_id = $1 AND used_at IS NULL
+            WHERE user_id = $1 AND purpose = $2 AND used_at IS NULL
         "#,
     )
     .bind(user_id)
+    .bind(purpose)
     .execute(executor)
     .await?;
     Ok(())
 }
 
-pub async fn insert_verification_token<'e, E>(
+pub async fn insert_token<'e, E>(
     executor: E,
     user_id: Uuid,
+    purpose: &str,
     token_hash: &str,
     expires_at: DateTime<Utc>,
 ) -> Result<(), sqlx::Error>
@@ -37,11 +45,12 @@ where
     sqlx::query(
         // language=PostgreSQL
         r#"
-            INSERT INTO email_verification_tokens (user_id, token_hash, expires_at)
-            VALUES ($1, $2, $3)
+            INSERT INTO one_time_tokens (user_id, purpose, token_hash, expires_at)
+            VALUES ($1, $2, $3, $4)
         "#,
     )
     .bind(user_id)
+    .bind(purpose)
     .bind(token_hash)
     .bind(expires_at)
     .execute(executor)
@@ -49,8 +58,13 @@ where
     Ok(())
 }
 
-pub async fn consume_verification_token<'e, E>(
+/// Atomically mark a token as used and return its owner.
+///
+/// Returns `None` if the token does not exist, belongs to another purpose,
+/// has expired, or was already used.
+pub async fn consume_token<'e, E>(
     executor: E,
+    purpose: &str,
     token_hash: &str,
 ) -> Result<Option<Uuid>, sqlx::Error>
 where
@@ -59,114 +73,53 @@ where
     sqlx::query_scalar(
         // language=PostgreSQL
         r#"
-            UPDATE email_verification_tokens
+            UPDATE one_time_tokens
             SET used_at = NOW()
             WHERE token_hash = $1
+                AND purpose = $2
                 AND used_at IS NULL
                 AND expires_at > NOW()
             RETURNING user_id
         "#,
     )
     .bind(token_hash)
+    .bind(purpose)
     .fetch_optional(executor)
     .await
 }
 
-pub async fn cleanup_expired_verification_tokens<'e, E>(executor: E) -> Result<u64, sqlx::Error>
+pub async fn cleanup_expired_tokens<'e, E>(executor: E, purpose: &str) -> Result<u64, sqlx::Error>
 where
     E: Executor<'e, Database = Postgres>,
 {
     let result = sqlx::query(
         // language=PostgreSQL
         r#"
-            DELETE FROM email_verification_tokens
-            WHERE expires_at < NOW() OR used_at IS NOT NULL
+            DELETE FROM one_time_tokens
+            WHERE purpose = $1 AND (expires_at < NOW() OR used_at IS NOT NULL)
         "#,
     )
+    .bind(purpose)
     .execute(executor)
     .await?;
     Ok(result.rows_affected())
 }
 
-// --- Password reset tokens ---
-
-pub async fn invalidate_reset_tokens<'e, E>(executor: E, user_id: Uuid) -> Result<(), sqlx::Error>
-where
-    E: Executor<'e, Database = Postgres>,
-{
-    sqlx::query(
-        // language=PostgreSQL
-        r#"
-            UPDATE password_reset_tokens
-            SET used_at = NOW()
-            WHERE user_id = $1 AND used_at IS NULL
-        "#,
-    )
-    .bind(user_id)
-    .execute(executor)
-    .await?;
-    Ok(())
-}
-
-pub async fn insert_reset_token<'e, E>(
+/// Delete expired and used tokens of every purpose, and expired refresh tokens
+pub async fn cleanup_all_expired_tokens<'e, E>(
     executor: E,
-    user_id: Uuid,
-    token_hash: &str,
-    expires_at: DateTime<Utc>,
-) -> Result<(), sqlx::Error>
+) -> Result<ExpiredTokenCleanup, sqlx::Error>
 where
     E: Executor<'e, Database = Postgres>,
 {
-    sqlx::query(
+    sqlx::query_as(
         // language=PostgreSQL
         r#"
-            INSERT INTO password_reset_tokens (user_id, token_hash, expires_at)
-            VALUES ($1, $2, $3)
+            SELECT password_reset_cleaned, email_verification_cleaned, refresh_tokens_cleaned,
+                   total_cleaned
+            FROM cleanup_all_expired_tokens()
         "#,
     )
-    .bind(user_id)
-    .bind(token_hash)
-    .bind(expires_at)
-    .execute(executor)
-    .await?;
-    Ok(())
-}
-
-pub async fn consume_reset_token<'e, E>(
-    executor: E,
-    token_hash: &str,
-) -> Result<Option<Uuid>, sqlx::Error>
-where
-    E: Executor<'e, Database = Postgres>,
-{
-    sqlx::query_scalar(
-        // language=PostgreSQL
-        r#"
-            UPDATE password_reset_tokens
-            SET used_at = NOW()
-            WHERE token_hash = $1
-                AND used_at IS NULL
-                AND expires_at > NOW()
-            RETURNING user_id
-        "#,
-    )
-    .bind(token_hash)
-    .fetch_optional(executor)
+    .fetch_one(executor)
     .await
 }
-
-pub async fn cleanup_expired_reset_tokens<'e, E>(executor: E) -> Result<u64, sqlx::Error>
-where
-    E: Executor<'e, Database = Postgres>,
-{
-    let result = sqlx::query(
-        // language=PostgreSQL
-        r#"
-            DELETE FROM password_reset_tokens
-            WHERE expires_at < NOW() OR used_at IS NOT NULL
-        "#,
-    )
-    .execute(executor)
-    .await?;
-    Ok(result.rows_affected())
-}
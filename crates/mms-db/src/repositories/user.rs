@@ -1,9 +1,14 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use futures_util::stream::BoxStream;
 use sqlx::{Executor, Postgres};
 use uuid::Uuid;
 
 use crate::models::{
-    ActivityDay, EmailVerifiedStatus, UserCredentials, UserEmailAndName, UserExistenceCheck,
-    UserIdAndName, UserPasswordInfo, UserProfile, UserStats, UserVerificationInfo,
+    ActivityDay, ActivityPeriod, AdminUserSummary, CardProgressExport, DailyGoalProgress,
+    DueReviewDay, EmailPreferences, EmailVerifiedStatus, HomeCounts, ReminderRecipient,
+    ReminderSettings, SignInMethods, StreakAtRisk, UserCredentials, UserEmailAndName,
+    UserExistenceCheck, UserIdAndName, UserPasswordInfo, UserProfile, UserStats,
+    UserVerificationInfo, UsernameChange, UsernameStatus, WeeklyDigest, WidgetStats,
 };
 
 pub async fn find_profile_by_id<'e, E>(
@@ -16,7 +21,28 @@ where
     sqlx::query_as(
         // language=PostgreSQL
         r#"
-            SELECT id, username, email, profile_picture_url, native_language, learning_language
+            SELECT id, username, email, profile_picture_url, native_language, learning_language, role::text
+            FROM users
+            WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+}
+
+pub async fn find_admin_summary<'e, E>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<Option<AdminUserSummary>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT id, username, email, role::text, auth_provider::text, email_verified, created_at,
+                   deleted_at
             FROM users
             WHERE id = $1
         "#,
@@ -36,9 +62,10 @@ where
     sqlx::query_as(
         // language=PostgreSQL
         r#"
-            SELECT id, username, email, password_hash, profile_picture_url, email_verified, native_language, learning_language
+            SELECT id, username, email, password_hash, profile_picture_url, email_verified, native_language, learning_language,
+                   role::text, deleted_at
             FROM users
-            WHERE email = $1 AND auth_provider = 'email'
+            WHERE email = $1 AND password_hash IS NOT NULL
         "#,
     )
     .bind(email)
@@ -66,6 +93,30 @@ where
     .await
 }
 
+/// Native language of the user with this email, used as the locale of emails to them
+pub async fn find_native_language_by_email<'e, E>(
+    executor: E,
+    email: &str,
+) -> Result<Option<String>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let language: Option<Option<String>> = sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT native_language
+            FROM users
+            WHERE email = $1
+            LIMIT 1
+        "#,
+    )
+    .bind(email)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(language.flatten())
+}
+
 pub async fn create_email_user<'e, E>(
     executor: E,
     username: &str,
@@ -119,7 +170,7 @@ where
         r#"
             SELECT id, username
             FROM users
-            WHERE email = $1 AND auth_provider = 'email'
+            WHERE email = $1 AND password_hash IS NOT NULL
         "#,
     )
     .bind(email)
@@ -147,6 +198,7 @@ where
     .await
 }
 
+/// Email, verification status and role; `None` if the user does not exist or deleted their account
 pub async fn find_email_verified_status<'e, E>(
     executor: E,
     user_id: Uuid,
@@ -157,9 +209,9 @@ where
     sqlx::query_as(
         // language=PostgreSQL
         r#"
-            SELECT email, email_verified
+            SELECT email, email_verified, role::text
             FROM users
-            WHERE id = $1
+            WHERE id = $1 AND deleted_at IS NULL
         "#,
     )
     .bind(user_id)
@@ -177,7 +229,7 @@ where
     sqlx::query_as(
         // language=PostgreSQL
         r#"
-            SELECT email, username, password_hash, auth_provider::text
+            SELECT email, username, password_hash, auth_provider::text, email_verified
             FROM users
             WHERE id = $1
         "#,
@@ -202,7 +254,8 @@ where
             UPDATE users
             SET native_language = $1, learning_language = $2
             WHERE id = $3
-            RETURNING id, username, email, profile_picture_url, native_language, learning_language
+            RETURNING id, username, email, profile_picture_url, native_language, learning_language,
+                      role::text
         "#,
     )
     .bind(native_language)
@@ -212,7 +265,32 @@ where
     .await
 }
 
-pub async fn update_password_for_email_user<'e, E>(
+/// Replace the password of an account that has one
+pub async fn update_password<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    password_hash: &str,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE users
+            SET password_hash = $1
+            WHERE id = $2 AND password_hash IS NOT NULL
+        "#,
+    )
+    .bind(password_hash)
+    .bind(user_id)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Give a password to an account that signs in with Google only; false if it already has one
+pub async fn set_initial_password<'e, E>(
     executor: E,
     user_id: Uuid,
     password_hash: &str,
@@ -225,7 +303,7 @@ where
         r#"
             UPDATE users
             SET password_hash = $1
-            WHERE id = $2 AND auth_provider = 'email'
+            WHERE id = $2 AND password_hash IS NULL
         "#,
     )
     .bind(password_hash)
@@ -235,11 +313,118 @@ where
     Ok(result.rows_affected() > 0)
 }
 
+/// Whether the user has a password and which Google account is linked; `None` if the user does not exist
+pub async fn find_sign_in_methods<'e, E>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<Option<SignInMethods>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT password_hash IS NOT NULL AS has_password, google_id
+            FROM users
+            WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Set the user's IANA timezone; `None` if the user does not exist
+pub async fn update_timezone<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    timezone: &str,
+) -> Result<Option<String>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            UPDATE users
+            SET timezone = $2
+            WHERE id = $1
+            RETURNING timezone
+        "#,
+    )
+    .bind(user_id)
+    .bind(timezone)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Id of the user with exactly this username, unless they deleted their account
+pub async fn find_id_by_username<'e, E>(
+    executor: E,
+    username: &str,
+) -> Result<Option<Uuid>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT id FROM users WHERE username = $1 AND deleted_at IS NULL
+        "#,
+    )
+    .bind(username)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Whether the user is left out of leaderboards; `None` if the user does not exist
+pub async fn get_leaderboard_opt_out<'e, E>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<Option<bool>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT leaderboard_opt_out FROM users WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+}
+
+pub async fn update_leaderboard_opt_out<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    opt_out: bool,
+) -> Result<Option<bool>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            UPDATE users
+            SET leaderboard_opt_out = $2
+            WHERE id = $1
+            RETURNING leaderboard_opt_out
+        "#,
+    )
+    .bind(user_id)
+    .bind(opt_out)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Change the username, returning the previous one; `None` if the user does not exist
 pub async fn update_username<'e, E>(
     executor: E,
     user_id: Uuid,
     username: &str,
-) -> Result<String, sqlx::Error>
+) -> Result<Option<String>, sqlx::Error>
 where
     E: Executor<'e, Database = Postgres>,
 {
@@ -248,16 +433,97 @@ where
         r#"
             UPDATE users
             SET username = $1
-            WHERE id = $2
-            RETURNING username
+            FROM (SELECT username FROM users WHERE id = $2 FOR UPDATE) AS previous
+            WHERE users.id = $2
+            RETURNING previous.username
+        "#,
+    )
+    .bind(username)
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+}
+
+pub async fn record_username_change<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    old_username: &str,
+    new_username: &str,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            INSERT INTO username_history (user_id, old_username, new_username)
+            VALUES ($1, $2, $3)
+        "#,
+    )
+    .bind(user_id)
+    .bind(old_username)
+    .bind(new_username)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Whether a username is in use, or was given up by an account other than
+/// `user_id` within the last `reserved_days` days
+pub async fn find_username_status<'e, E>(
+    executor: E,
+    username: &str,
+    user_id: Option<Uuid>,
+    reserved_days: i32,
+) -> Result<UsernameStatus, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                EXISTS (
+                    SELECT 1 FROM users WHERE username = $1
+                ) AS taken,
+                EXISTS (
+                    SELECT 1 FROM username_history
+                    WHERE old_username = $1
+                      AND user_id IS DISTINCT FROM $2
+                      AND changed_at > NOW() - make_interval(days => $3)
+                ) AS reserved
         "#,
     )
     .bind(username)
     .bind(user_id)
+    .bind(reserved_days)
     .fetch_one(executor)
     .await
 }
 
+/// The user's username changes, newest first
+pub async fn list_username_history<'e, E>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<Vec<UsernameChange>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT old_username, new_username, changed_at
+            FROM username_history
+            WHERE user_id = $1
+            ORDER BY changed_at DESC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(executor)
+    .await
+}
+
 pub async fn mark_email_verified<'e, E>(executor: E, user_id: Uuid) -> Result<bool, sqlx::Error>
 where
     E: Executor<'e, Database = Postgres>,
@@ -292,15 +558,37 @@ where
     Ok(result.rows_affected())
 }
 
-pub async fn get_user_stats<'e, E>(executor: E, user_id: Uuid) -> Result<UserStats, sqlx::Error>
+/// Mark the account deleted; returns when, or `None` if it does not exist or is already deleted
+pub async fn soft_delete_user<'e, E>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<Option<DateTime<Utc>>, sqlx::Error>
 where
     E: Executor<'e, Database = Postgres>,
 {
-    sqlx::query_as(
+    sqlx::query_scalar(
         // language=PostgreSQL
         r#"
-            SELECT current_streak_days, longest_streak_days, total_reviews, total_cards_learned, last_review_date
-            FROM user_stats WHERE user_id = $1
+            UPDATE users
+            SET deleted_at = NOW()
+            WHERE id = $1 AND deleted_at IS NULL
+            RETURNING deleted_at
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Whether the user exists and has not deleted their account
+pub async fn is_active<'e, E>(executor: E, user_id: Uuid) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT EXISTS (SELECT 1 FROM users WHERE id = $1 AND deleted_at IS NULL)
         "#,
     )
     .bind(user_id)
@@ -308,29 +596,802 @@ where
     .await
 }
 
-pub async fn get_user_activity<'e, E>(
+/// When the user deleted the account; `None` if they did not or it does not exist
+pub async fn find_deleted_at<'e, E>(
     executor: E,
     user_id: Uuid,
-    days: i32,
-) -> Result<Vec<ActivityDay>, sqlx::Error>
+) -> Result<Option<DateTime<Utc>>, sqlx::Error>
 where
     E: Executor<'e, Database = Postgres>,
 {
-    sqlx::query_as(
+    let deleted_at: Option<Option<DateTime<Utc>>> = sqlx::query_scalar(
         // language=PostgreSQL
         r#"
-            SELECT activity_date, reviews_count
-            FROM user_activity
-            WHERE user_id = $1 AND activity_date >= CURRENT_DATE - $2
-            ORDER BY activity_date
+            SELECT deleted_at FROM users WHERE id = $1
         "#,
     )
     .bind(user_id)
-    .bind(days)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(deleted_at.flatten())
+}
+
+/// Undo the deletion of an account deleted less than `grace_days` ago.
+///
+/// Returns false when the account is not deleted or its grace period is over.
+pub async fn restore_user<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    grace_days: i32,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE users
+            SET deleted_at = NULL
+            WHERE id = $1
+                AND deleted_at IS NOT NULL
+                AND deleted_at > NOW() - make_interval(days => $2)
+        "#,
+    )
+    .bind(user_id)
+    .bind(grace_days)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Permanently delete accounts deleted at least `grace_days` ago, with everything attached
+///
+/// Returns the avatar key of each purged account, if it had one, so the images
+/// can be removed from the media store.
+pub async fn purge_deleted_users<'e, E>(
+    executor: E,
+    grace_days: i32,
+) -> Result<Vec<Option<String>>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM users
+            WHERE deleted_at <= NOW() - make_interval(days => $1)
+            RETURNING avatar_key
+        "#,
+    )
+    .bind(grace_days)
     .fetch_all(executor)
     .await
 }
 
+/// Delete accounts whose email address was not verified within `days` of registering
+pub async fn delete_unverified_users<'e, E>(executor: E, days: i32) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            DELETE FROM users
+            WHERE email_verified = false
+              AND created_at < NOW() - make_interval(days => $1)
+        "#,
+    )
+    .bind(days)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Point the user's profile picture at a newly uploaded avatar
+///
+/// Returns the key of the avatar it replaces, or `None` if the user does not exist.
+pub async fn set_avatar<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    avatar_key: &str,
+    picture_url: &str,
+) -> Result<Option<Option<String>>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            UPDATE users
+            SET avatar_key = $2, profile_picture_url = $3
+            FROM (SELECT avatar_key FROM users WHERE id = $1 FOR UPDATE) AS previous
+            WHERE users.id = $1
+            RETURNING previous.avatar_key
+        "#,
+    )
+    .bind(user_id)
+    .bind(avatar_key)
+    .bind(picture_url)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Remove the user's uploaded avatar, returning its key; `None` if there was none
+pub async fn clear_avatar<'e, E>(executor: E, user_id: Uuid) -> Result<Option<String>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            UPDATE users
+            SET avatar_key = NULL, profile_picture_url = NULL
+            FROM (SELECT avatar_key FROM users WHERE id = $1 FOR UPDATE) AS previous
+            WHERE users.id = $1 AND previous.avatar_key IS NOT NULL
+            RETURNING previous.avatar_key
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+}
+
+pub async fn get_user_stats<'e, E>(executor: E, user_id: Uuid) -> Result<UserStats, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT current_streak_days, longest_streak_days, total_reviews, total_cards_learned, last_review_date
+            FROM user_stats WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(executor)
+    .await
+}
+
+pub async fn get_user_activity<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    days: i32,
+) -> Result<Vec<ActivityDay>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT activity_date, reviews_count
+            FROM user_activity
+            WHERE user_id = $1 AND activity_date >= user_local_date($1) - $2
+            ORDER BY activity_date
+        "#,
+    )
+    .bind(user_id)
+    .bind(days)
+    .fetch_all(executor)
+    .await
+}
+
+/// The user's current date in their timezone, or None for an unknown user
+pub async fn local_date<'e, E>(executor: E, user_id: Uuid) -> Result<Option<NaiveDate>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT user_local_date(u.id) FROM users u WHERE u.id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Activity from `from` to `to` (inclusive local dates) summed per `unit`
+/// (`day`, `week` or `month`), oldest first; periods without activity are omitted
+pub async fn get_activity_periods<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    from: NaiveDate,
+    to: NaiveDate,
+    unit: &str,
+) -> Result<Vec<ActivityPeriod>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                date_trunc($4, activity_date::timestamp)::date AS period_start,
+                SUM(reviews_count)::bigint AS reviews,
+                SUM(COALESCE(correct_reviews, 0))::bigint AS correct,
+                SUM(study_ms)::bigint AS study_ms,
+                SUM(new_cards)::bigint AS new_cards
+            FROM user_activity
+            WHERE user_id = $1 AND activity_date BETWEEN $2 AND $3
+            GROUP BY 1
+            ORDER BY 1
+        "#,
+    )
+    .bind(user_id)
+    .bind(from)
+    .bind(to)
+    .bind(unit)
+    .fetch_all(executor)
+    .await
+}
+
+/// Cards coming due per local day and language pair over the next `days` days.
+///
+/// Overdue cards count towards today.
+pub async fn count_due_reviews_by_day<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    days: i32,
+) -> Result<Vec<DueReviewDay>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                (GREATEST(ucp.next_review_at, NOW()) AT TIME ZONE user_timezone($1))::date AS due_date,
+                f.language_from,
+                f.language_to,
+                COUNT(*) AS cards
+            FROM user_card_progress ucp
+            JOIN flashcards f ON f.id = ucp.flashcard_id
+            WHERE ucp.user_id = $1
+                AND ucp.next_review_at < (user_local_date($1) + $2)::timestamp AT TIME ZONE user_timezone($1)
+            GROUP BY 1, 2, 3
+            ORDER BY 1, 2, 3
+        "#,
+    )
+    .bind(user_id)
+    .bind(days)
+    .fetch_all(executor)
+    .await
+}
+
+pub async fn get_home_counts<'e, E>(executor: E, user_id: Uuid) -> Result<HomeCounts, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                COUNT(*) FILTER (WHERE ucp.next_review_at <= NOW()) AS due_now,
+                COUNT(*) AS due_today,
+                (SELECT COUNT(*) FROM notifications
+                 WHERE user_id = $1 AND read_at IS NULL) AS unread_notifications
+            FROM user_card_progress ucp
+            WHERE ucp.user_id = $1
+                AND ucp.next_review_at < (user_local_date($1) + 1)::timestamp AT TIME ZONE user_timezone($1)
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(executor)
+    .await
+}
+
+/// Whether Postgres knows an IANA timezone name
+pub async fn timezone_exists<'e, E>(executor: E, timezone: &str) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT EXISTS (SELECT 1 FROM pg_timezone_names WHERE name = $1)
+        "#,
+    )
+    .bind(timezone)
+    .fetch_one(executor)
+    .await
+}
+
+/// The user's daily goal and today's progress; `None` if the user does not exist
+pub async fn get_daily_goal<'e, E>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<Option<DailyGoalProgress>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT u.daily_goal_kind AS kind, u.daily_goal_target AS target,
+                   COALESCE(ua.reviews_count - ua.flagged_reviews, 0) AS reviews_today,
+                   COALESCE(ua.study_ms, 0) AS study_ms_today,
+                   COALESCE(ua.goal_reached, FALSE) AS reached
+            FROM users u
+            LEFT JOIN user_activity ua
+                ON ua.user_id = u.id AND ua.activity_date = user_local_date(u.id)
+            WHERE u.id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Change the user's daily goal; false if the user does not exist
+pub async fn update_daily_goal<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    kind: &str,
+    target: i32,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE users
+            SET daily_goal_kind = $2, daily_goal_target = $3
+            WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .bind(kind)
+    .bind(target)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn get_reminder_settings<'e, E>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<Option<ReminderSettings>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT reminder_enabled AS enabled, reminder_hour AS hour, timezone,
+                   streak_reminder_email AS streak_email, streak_reminder_in_app AS streak_in_app
+            FROM users
+            WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Change reminder settings; `None` fields keep their value
+pub async fn update_reminder_settings<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    enabled: Option<bool>,
+    hour: Option<i16>,
+    timezone: Option<&str>,
+    streak_email: Option<bool>,
+    streak_in_app: Option<bool>,
+) -> Result<Option<ReminderSettings>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            UPDATE users
+            SET reminder_enabled = COALESCE($2, reminder_enabled),
+                reminder_hour = COALESCE($3, reminder_hour),
+                timezone = COALESCE($4, timezone),
+                streak_reminder_email = COALESCE($5, streak_reminder_email),
+                streak_reminder_in_app = COALESCE($6, streak_reminder_in_app)
+            WHERE id = $1
+            RETURNING reminder_enabled AS enabled, reminder_hour AS hour, timezone,
+                      streak_reminder_email AS streak_email, streak_reminder_in_app AS streak_in_app
+        "#,
+    )
+    .bind(user_id)
+    .bind(enabled)
+    .bind(hour)
+    .bind(timezone)
+    .bind(streak_email)
+    .bind(streak_in_app)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Claim up to `limit` users whose reminder hour has come and who have cards due.
+///
+/// Marks each as reminded for their local day, so concurrent callers never
+/// claim the same user twice.
+pub async fn claim_due_reminders<'e, E>(
+    executor: E,
+    limit: i64,
+) -> Result<Vec<ReminderRecipient>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            WITH candidates AS (
+                SELECT u.id, (NOW() AT TIME ZONE u.timezone)::date AS local_date
+                FROM users u
+                WHERE u.reminder_enabled
+                    AND u.email_verified
+                    AND u.deleted_at IS NULL
+                    AND EXTRACT(HOUR FROM NOW() AT TIME ZONE u.timezone) = u.reminder_hour
+                    AND (u.reminder_sent_on IS NULL
+                         OR u.reminder_sent_on < (NOW() AT TIME ZONE u.timezone)::date)
+                    AND EXISTS (
+                        SELECT 1 FROM user_card_progress ucp
+                        WHERE ucp.user_id = u.id AND ucp.next_review_at <= NOW()
+                    )
+                LIMIT $1
+                FOR UPDATE OF u SKIP LOCKED
+            )
+            UPDATE users u
+            SET reminder_sent_on = c.local_date
+            FROM candidates c
+            WHERE u.id = c.id
+            RETURNING
+                u.id AS user_id,
+                u.email,
+                u.username,
+                (SELECT COUNT(*) FROM user_card_progress ucp
+                 WHERE ucp.user_id = u.id AND ucp.next_review_at <= NOW()) AS cards_due
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(executor)
+    .await
+}
+
+/// Claim up to `limit` users whose streak ends at local midnight unless they review.
+///
+/// At `now`, a user is at risk when it is at most `hours_before_midnight`
+/// hours to their local midnight, their streak is running and their last
+/// review was yesterday in their timezone. Users who only want emails are
+/// skipped when `email_available` is false. Marks each as reminded for their
+/// local day, so concurrent callers never claim the same user twice.
+pub async fn claim_streaks_at_risk<'e, E>(
+    executor: E,
+    now: DateTime<Utc>,
+    hours_before_midnight: i32,
+    email_available: bool,
+    limit: i64,
+) -> Result<Vec<StreakAtRisk>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            WITH candidates AS (
+                SELECT u.id, ($1 AT TIME ZONE u.timezone)::date AS local_date
+                FROM users u
+                JOIN user_stats s ON s.user_id = u.id
+                WHERE s.current_streak_days > 0
+                    AND u.deleted_at IS NULL
+                    AND (u.streak_reminder_in_app
+                         OR ($3 AND u.streak_reminder_email AND u.email_verified))
+                    AND EXTRACT(HOUR FROM $1 AT TIME ZONE u.timezone) >= 24 - $2
+                    AND (u.streak_reminder_sent_on IS NULL
+                         OR u.streak_reminder_sent_on < ($1 AT TIME ZONE u.timezone)::date)
+                    AND (
+                        SELECT (MAX(ucp.last_review_at) AT TIME ZONE u.timezone)::date
+                        FROM user_card_progress ucp
+                        WHERE ucp.user_id = u.id
+                    ) = ($1 AT TIME ZONE u.timezone)::date - 1
+                LIMIT $4
+                FOR UPDATE OF u SKIP LOCKED
+            )
+            UPDATE users u
+            SET streak_reminder_sent_on = c.local_date
+            FROM candidates c
+            WHERE u.id = c.id
+            RETURNING
+                u.id AS user_id,
+                u.email,
+                u.username,
+                (SELECT current_streak_days FROM user_stats WHERE user_id = u.id) AS streak_days,
+                $3 AND u.streak_reminder_email AND u.email_verified AS send_email,
+                u.streak_reminder_in_app AS send_in_app
+        "#,
+    )
+    .bind(now)
+    .bind(hours_before_midnight)
+    .bind(email_available)
+    .bind(limit)
+    .fetch_all(executor)
+    .await
+}
+
+/// Claim up to `limit` users whose weekly digest is due at `now`, with their past week's numbers.
+///
+/// The digest goes out on Monday from `hour` in the user's timezone and covers
+/// the seven local days before it. Users who did not review that week are
+/// skipped. Marks each as sent for their local day, so concurrent callers
+/// never claim the same user twice.
+pub async fn claim_weekly_digests<'e, E>(
+    executor: E,
+    now: DateTime<Utc>,
+    hour: i32,
+    limit: i64,
+) -> Result<Vec<WeeklyDigest>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            WITH candidates AS (
+                SELECT u.id, ($1 AT TIME ZONE u.timezone)::date AS local_date
+                FROM users u
+                WHERE u.weekly_digest_enabled
+                    AND u.email_verified
+                    AND u.deleted_at IS NULL
+                    AND EXTRACT(ISODOW FROM $1 AT TIME ZONE u.timezone) = 1
+                    AND EXTRACT(HOUR FROM $1 AT TIME ZONE u.timezone) >= $2
+                    AND (u.weekly_digest_sent_on IS NULL
+                         OR u.weekly_digest_sent_on < ($1 AT TIME ZONE u.timezone)::date)
+                    AND EXISTS (
+                        SELECT 1 FROM user_activity a
+                        WHERE a.user_id = u.id
+                            AND a.activity_date >= ($1 AT TIME ZONE u.timezone)::date - 7
+                            AND a.activity_date < ($1 AT TIME ZONE u.timezone)::date
+                    )
+                LIMIT $3
+                FOR UPDATE OF u SKIP LOCKED
+            )
+            UPDATE users u
+            SET weekly_digest_sent_on = c.local_date
+            FROM candidates c
+            WHERE u.id = c.id
+            RETURNING
+                u.id AS user_id,
+                u.email,
+                u.username,
+                c.local_date - 7 AS week_start,
+                (SELECT COALESCE(SUM(a.reviews_count), 0)::bigint
+                 FROM user_activity a
+                 WHERE a.user_id = u.id
+                     AND a.activity_date >= c.local_date - 7
+                     AND a.activity_date < c.local_date) AS reviews,
+                (SELECT COALESCE(SUM(a.correct_reviews), 0)::bigint
+                 FROM user_activity a
+                 WHERE a.user_id = u.id
+                     AND a.activity_date >= c.local_date - 7
+                     AND a.activity_date < c.local_date) AS correct_reviews,
+                (SELECT COALESCE(SUM(a.reviews_count) FILTER (WHERE a.correct_reviews IS NOT NULL), 0)::bigint
+                 FROM user_activity a
+                 WHERE a.user_id = u.id
+                     AND a.activity_date >= c.local_date - 7
+                     AND a.activity_date < c.local_date) AS graded_reviews,
+                (SELECT COUNT(*)
+                 FROM user_card_progress ucp
+                 WHERE ucp.user_id = u.id
+                     AND (ucp.mastered_at AT TIME ZONE u.timezone)::date >= c.local_date - 7
+                     AND (ucp.mastered_at AT TIME ZONE u.timezone)::date < c.local_date) AS cards_learned,
+                COALESCE((SELECT current_streak_days FROM user_stats WHERE user_id = u.id), 0) AS streak_days
+        "#,
+    )
+    .bind(now)
+    .bind(hour)
+    .bind(limit)
+    .fetch_all(executor)
+    .await
+}
+
+/// Which emails the user receives; `None` if the user does not exist
+pub async fn get_email_preferences<'e, E>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<Option<EmailPreferences>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                weekly_digest_enabled AS weekly_digest,
+                reminder_enabled AS review_reminders,
+                streak_reminder_email AS streak_reminders
+            FROM users
+            WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Turn emails on or off, leaving `None` fields unchanged; `None` if the user does not exist
+pub async fn update_email_preferences<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    weekly_digest: Option<bool>,
+    review_reminders: Option<bool>,
+    streak_reminders: Option<bool>,
+) -> Result<Option<EmailPreferences>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            UPDATE users
+            SET weekly_digest_enabled = COALESCE($2, weekly_digest_enabled),
+                reminder_enabled = COALESCE($3, reminder_enabled),
+                streak_reminder_email = COALESCE($4, streak_reminder_email)
+            WHERE id = $1
+            RETURNING
+                weekly_digest_enabled AS weekly_digest,
+                reminder_enabled AS review_reminders,
+                streak_reminder_email AS streak_reminders
+        "#,
+    )
+    .bind(user_id)
+    .bind(weekly_digest)
+    .bind(review_reminders)
+    .bind(streak_reminders)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Current calendar feed token version; `None` if the user does not exist or deleted their account
+pub async fn get_calendar_token_version<'e, E>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<Option<i32>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT calendar_token_version FROM users WHERE id = $1 AND deleted_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Invalidate every calendar feed token of the user and return the new version
+pub async fn bump_calendar_token_version<'e, E>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<i32, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            UPDATE users
+            SET calendar_token_version = calendar_token_version + 1
+            WHERE id = $1
+            RETURNING calendar_token_version
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(executor)
+    .await
+}
+
+/// Invalidate every widget token of the user and return the new version
+pub async fn bump_widget_token_version<'e, E>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<i32, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            UPDATE users
+            SET widget_token_version = widget_token_version + 1
+            WHERE id = $1
+            RETURNING widget_token_version
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(executor)
+    .await
+}
+
+/// Username, streak and mastered cards shown on the user's widgets; `None` once they deleted their account
+pub async fn get_widget_stats<'e, E>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<Option<WidgetStats>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                u.username,
+                u.widget_token_version,
+                COALESCE(s.current_streak_days, 0) AS current_streak_days,
+                COALESCE(s.longest_streak_days, 0) AS longest_streak_days,
+                COALESCE(s.total_cards_learned, 0) AS total_cards_learned
+            FROM users u
+            LEFT JOIN user_stats s ON s.user_id = u.id
+            WHERE u.id = $1 AND u.deleted_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Stream the user's progress on every card they have reviewed
+pub fn stream_card_progress<'e, E>(
+    executor: E,
+    user_id: Uuid,
+) -> BoxStream<'e, Result<CardProgressExport, sqlx::Error>>
+where
+    E: Executor<'e, Database = Postgres> + 'e,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT
+                f.id AS flashcard_id,
+                f.term,
+                f.translation,
+                f.language_from,
+                f.language_to,
+                ucp.next_review_at,
+                ucp.last_review_at,
+                ucp.times_correct,
+                ucp.times_wrong,
+                ucp.mastered_at
+            FROM user_card_progress ucp
+            JOIN flashcards f ON f.id = ucp.flashcard_id
+            WHERE ucp.user_id = $1
+            ORDER BY ucp.flashcard_id
+        "#,
+    )
+    .bind(user_id)
+    .fetch(executor)
+}
+
+/// Stream the user's full activity history, oldest first
+pub fn stream_activity<'e, E>(
+    executor: E,
+    user_id: Uuid,
+) -> BoxStream<'e, Result<ActivityDay, sqlx::Error>>
+where
+    E: Executor<'e, Database = Postgres> + 'e,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT activity_date, reviews_count
+            FROM user_activity
+            WHERE user_id = $1
+            ORDER BY activity_date
+        "#,
+    )
+    .bind(user_id)
+    .fetch(executor)
+}
+
 pub async fn find_email_and_name<'e, E>(
     executor: E,
     user_id: Uuid,
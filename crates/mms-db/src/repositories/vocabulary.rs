@@ -0,0 +1,34 @@
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::models::StudiedCard;
+
+/// Every card the user has progress on, most recently reviewed first.
+///
+/// Matching happens in Rust with the same normalization as answer checking,
+/// which Postgres cannot reproduce without extensions. A learner's studied
+/// cards number in the thousands at most, so one indexed scan per search is cheap.
+pub async fn list_studied_cards<'e, E>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<Vec<StudiedCard>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT f.id AS flashcard_id, f.term, f.translation,
+                   f.language_from, f.language_to,
+                   ucp.times_correct, ucp.times_wrong,
+                   ucp.next_review_at, ucp.last_review_at
+            FROM user_card_progress ucp
+            JOIN flashcards f ON f.id = ucp.flashcard_id
+            WHERE ucp.user_id = $1
+            ORDER BY ucp.last_review_at DESC NULLS LAST, f.id
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(executor)
+    .await
+}
@@ -0,0 +1,101 @@
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::models::XpDay;
+
+/// Add a review's XP to today's activity and the user's total.
+///
+/// Must run after `practice::record_activity`, which creates today's row.
+/// Returns the new total, or `None` if the user has no stats row.
+pub async fn award<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    correct_xp: i32,
+    wrong_xp: i32,
+    difficulty_bonus_xp: i32,
+) -> Result<Option<i64>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            WITH day AS (
+                UPDATE user_activity
+                SET correct_xp = correct_xp + $2,
+                    wrong_xp = wrong_xp + $3,
+                    difficulty_bonus_xp = difficulty_bonus_xp + $4
+                WHERE user_id = $1 AND activity_date = user_local_date($1)
+            )
+            UPDATE user_stats
+            SET xp = xp + $2 + $3 + $4
+            WHERE user_id = $1
+            RETURNING xp
+        "#,
+    )
+    .bind(user_id)
+    .bind(correct_xp)
+    .bind(wrong_xp)
+    .bind(difficulty_bonus_xp)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Store the level the user's XP reaches
+pub async fn set_level<'e, E>(executor: E, user_id: Uuid, level: i32) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        // language=PostgreSQL
+        r#"
+            UPDATE user_stats SET level = $2 WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .bind(level)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// The user's total XP; 0 if the user has no stats row
+pub async fn get_total<'e, E>(executor: E, user_id: Uuid) -> Result<i64, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let xp: Option<i64> = sqlx::query_scalar(
+        // language=PostgreSQL
+        r#"
+            SELECT xp FROM user_stats WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await?;
+    Ok(xp.unwrap_or(0))
+}
+
+/// XP per local day over the last `days` days, oldest first; days without reviews are omitted
+pub async fn list_days<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    days: i32,
+) -> Result<Vec<XpDay>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as(
+        // language=PostgreSQL
+        r#"
+            SELECT activity_date, correct_xp, wrong_xp, difficulty_bonus_xp
+            FROM user_activity
+            WHERE user_id = $1 AND activity_date > user_local_date($1) - $2
+            ORDER BY activity_date
+        "#,
+    )
+    .bind(user_id)
+    .bind(days)
+    .fetch_all(executor)
+    .await
+}
@@ -0,0 +1,258 @@
+//! Guardrails keeping one organization's content out of another's queries.
+//!
+//! Roadmaps and decks carry an `org_id`: NULL for the public catalogue, or the
+//! organization whose members alone may see them. Every read of those tables
+//! has to be limited with the `org_visible(org_id, viewer)` SQL function:
+//!
+//! - Queries assembled at runtime go through [`TenantQuery`], which pushes the
+//!   condition for each alias and, in debug builds, panics when the finished SQL
+//!   still reads a scoped table without it.
+//! - Fixed queries in `repositories/` call `org_visible` themselves; a unit test
+//!   scans every repository and rejects the ones that do not. Operator queries
+//!   that must see every tenant say so with an [`ALL_TENANTS`] comment.
+
+use sqlx::{
+    Encode, FromRow, Postgres, QueryBuilder, Type,
+    postgres::{PgArguments, PgRow},
+    query::{QueryAs, QueryScalar},
+};
+use uuid::Uuid;
+
+/// Tables whose rows belong to an organization or to the public catalogue
+pub const SCOPED_TABLES: &[&str] = &["roadmaps", "decks"];
+
+/// SQL comment exempting an operator query from tenant scoping
+pub const ALL_TENANTS: &str = "-- tenancy: all tenants";
+
+/// Who content is being read for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tenant {
+    /// Signed out: the public catalogue only
+    Public,
+    /// A signed-in user: the public catalogue plus their organizations' content
+    Member(Uuid),
+}
+
+impl Tenant {
+    /// Viewer bound to `org_visible`; NULL limits rows to the public catalogue
+    pub fn viewer(self) -> Option<Uuid> {
+        match self {
+            Tenant::Public => None,
+            Tenant::Member(user_id) => Some(user_id),
+        }
+    }
+}
+
+/// A query builder that limits scoped tables to what its tenant may see
+pub struct TenantQuery<'args> {
+    builder: QueryBuilder<'args, Postgres>,
+    tenant: Tenant,
+}
+
+impl<'args> TenantQuery<'args> {
+    pub fn new(tenant: Tenant, sql: &str) -> Self {
+        Self {
+            builder: QueryBuilder::new(sql),
+            tenant,
+        }
+    }
+
+    pub fn push(&mut self, sql: &str) -> &mut Self {
+        self.builder.push(sql);
+        self
+    }
+
+    pub fn push_bind<T>(&mut self, value: T) -> &mut Self
+    where
+        T: 'args + Encode<'args, Postgres> + Type<Postgres>,
+    {
+        self.builder.push_bind(value);
+        self
+    }
+
+    /// Push the condition limiting rows of `alias` to those the tenant may see
+    pub fn push_visible(&mut self, alias: &str) -> &mut Self {
+        self.builder.push(format!("org_visible({alias}.org_id, "));
+        self.builder.push_bind(self.tenant.viewer());
+        self.builder.push(")");
+        self
+    }
+
+    /// Finish the query; in debug builds, panics if a scoped table was left unscoped
+    pub fn build_query_as<'q, T>(&'q mut self) -> QueryAs<'q, Postgres, T, PgArguments>
+    where
+        T: FromRow<'q, PgRow>,
+    {
+        debug_assert_scoped(self.builder.sql());
+        self.builder.build_query_as()
+    }
+
+    /// Like [`Self::build_query_as`], for a query selecting a single column
+    pub fn build_query_scalar<'q, T>(&'q mut self) -> QueryScalar<'q, Postgres, T, PgArguments>
+    where
+        (T,): for<'r> FromRow<'r, PgRow>,
+    {
+        debug_assert_scoped(self.builder.sql());
+        self.builder.build_query_scalar()
+    }
+}
+
+/// Panic in debug builds when `sql` reads a scoped table without `org_visible`
+pub fn debug_assert_scoped(sql: &str) {
+    if cfg!(debug_assertions) {
+        let unscoped = unscoped_tables(sql);
+        assert!(
+            unscoped.is_empty(),
+            "query reads {unscoped:?} without scoping by tenant:\n{sql}"
+        );
+    }
+}
+
+/// Scoped tables `sql` reads without passing their alias's `org_id` to `org_visible`
+pub fn unscoped_tables(sql: &str) -> Vec<&'static str> {
+    let lowered = sql.to_lowercase();
+    if lowered.contains(ALL_TENANTS) {
+        return Vec::new();
+    }
+
+    let tokens: Vec<&str> = lowered
+        .split(|c: char| c.is_whitespace() || matches!(c, '(' | ')' | ','))
+        .filter(|token| !token.is_empty())
+        .collect();
+    let compact: String = lowered.chars().filter(|c| !c.is_whitespace()).collect();
+
+    let mut unscoped = Vec::new();
+    for (i, window) in tokens.windows(2).enumerate() {
+        if !matches!(window[0], "from" | "join") {
+            continue;
+        }
+        let Some(&table) = SCOPED_TABLES.iter().find(|&&table| table == window[1]) else {
+            continue;
+        };
+        let alias = match tokens.get(i + 2) {
+            Some(&"as") => tokens.get(i + 3).copied().unwrap_or(table),
+            Some(&next) if !is_keyword(next) => next,
+            _ => table,
+        };
+        if !compact.contains(&format!("org_visible({alias}.org_id,")) && !unscoped.contains(&table)
+        {
+            unscoped.push(table);
+        }
+    }
+    unscoped
+}
+
+/// Words that can follow a table name in place of an alias
+fn is_keyword(word: &str) -> bool {
+    matches!(
+        word,
+        "where"
+            | "join"
+            | "left"
+            | "right"
+            | "inner"
+            | "full"
+            | "cross"
+            | "on"
+            | "group"
+            | "order"
+            | "limit"
+            | "union"
+            | "returning"
+            | "set"
+            | "for"
+    ) || word.ends_with(';')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unscoped_tables() {
+        assert_eq!(
+            unscoped_tables("SELECT id FROM decks WHERE id = $1"),
+            ["decks"]
+        );
+        assert_eq!(
+            unscoped_tables(
+                "SELECT d.id FROM roadmaps AS r JOIN decks d ON TRUE WHERE org_visible(r.org_id, $1)"
+            ),
+            ["decks"]
+        );
+        assert!(
+            unscoped_tables(
+                "SELECT d.id FROM decks d WHERE d.id = $1 AND org_visible( d.org_id, $2 )"
+            )
+            .is_empty()
+        );
+        assert!(
+            unscoped_tables("SELECT id FROM decks WHERE org_visible(decks.org_id, $1)").is_empty()
+        );
+        assert!(unscoped_tables("SELECT id FROM deck_flashcards WHERE deck_id = $1").is_empty());
+        assert!(unscoped_tables("-- tenancy: all tenants\nSELECT id FROM decks").is_empty());
+    }
+
+    #[test]
+    fn test_tenant_query_scopes_each_alias() {
+        let mut query = TenantQuery::new(Tenant::Public, "SELECT r.id FROM roadmaps r WHERE ");
+        query
+            .push_visible("r")
+            .push(" AND r.id = ")
+            .push_bind(Uuid::nil());
+
+        assert_eq!(
+            query.builder.sql(),
+            "SELECT r.id FROM roadmaps r WHERE org_visible(r.org_id, $1) AND r.id = $2"
+        );
+        assert!(unscoped_tables(query.builder.sql()).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "without scoping by tenant")]
+    fn test_tenant_query_rejects_unscoped_tables() {
+        let mut query = TenantQuery::new(Tenant::Public, "SELECT id FROM decks");
+        let _ = query.build_query_as::<(Uuid,)>();
+    }
+
+    /// Every fixed query in `repositories/` reading a scoped table calls `org_visible`
+    #[test]
+    fn test_repository_queries_are_scoped() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/src/repositories");
+        let mut failures = Vec::new();
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let source = std::fs::read_to_string(&path).unwrap();
+            for sql in fixed_queries(&source) {
+                let unscoped = unscoped_tables(sql);
+                if !unscoped.is_empty() {
+                    failures.push(format!("{}: {unscoped:?} in\n{sql}", path.display()));
+                }
+            }
+        }
+        assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+    }
+
+    /// Raw SQL strings passed straight to `sqlx::query*`; [`TenantQuery`] fragments check themselves
+    fn fixed_queries(source: &str) -> Vec<&str> {
+        let mut queries = Vec::new();
+        let mut rest = source;
+        while let Some(start) = rest.find("r#\"") {
+            let before = rest[..start].trim_end();
+            let before = before
+                .strip_suffix("// language=PostgreSQL")
+                .unwrap_or(before)
+                .trim_end();
+            let body = &rest[start + 3..];
+            let end = body.find("\"#").unwrap();
+            if ["sqlx::query(", "sqlx::query_as(", "sqlx::query_scalar("]
+                .iter()
+                .any(|call| before.ends_with(call))
+            {
+                queries.push(&body[..end]);
+            }
+            rest = &body[end + 2..];
+        }
+        queries
+    }
+}
@@ -0,0 +1,216 @@
+//! Invariants every scheduler must keep.
+//!
+//! [`check_scheduler_invariants`] takes a scheduler as a function from a
+//! card's answer counts to the interval it gets, and checks it around one
+//! card. Property tests call it over many cards and settings; a new schedule
+//! or setting should pass it before it ships.
+
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+
+/// A scheduler as seen by the checks: the interval, in minutes, after a
+/// review that leaves a card with `times_correct` and `times_wrong`
+pub trait IntervalFn: Fn(i32, i32) -> i64 {}
+
+impl<F: Fn(i32, i32) -> i64> IntervalFn for F {}
+
+/// Adapt a `compute_next_review*` style function, which returns a date, to
+/// an [`IntervalFn`]
+pub fn interval_of(
+    next_review: impl Fn(i32, i32, DateTime<Utc>) -> DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> impl IntervalFn {
+    move |times_correct, times_wrong| {
+        (next_review(times_correct, times_wrong, now) - now).num_minutes()
+    }
+}
+
+/// An invariant a scheduler broke, with the card it broke it on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvariantViolation {
+    pub invariant: &'static str,
+    pub times_correct: i32,
+    pub times_wrong: i32,
+    pub detail: String,
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} broken at {} correct / {} wrong: {}",
+            self.invariant, self.times_correct, self.times_wrong, self.detail
+        )
+    }
+}
+
+impl std::error::Error for InvariantViolation {}
+
+/// Check a scheduler around a card answered `times_correct` and `times_wrong`
+/// times:
+///
+/// * the interval is positive,
+/// * it is at most `max_interval_days`,
+/// * one more correct answer never shortens it,
+/// * one more wrong answer never lengthens it, and gives a shorter interval
+///   than a correct answer would, unless the schedule has topped out and a
+///   correct answer would not lengthen it either.
+pub fn check_scheduler_invariants(
+    interval: impl IntervalFn,
+    max_interval_days: i32,
+    times_correct: i32,
+    times_wrong: i32,
+) -> Result<(), InvariantViolation> {
+    let violation = |invariant, detail: String| InvariantViolation {
+        invariant,
+        times_correct,
+        times_wrong,
+        detail,
+    };
+
+    let current = interval(times_correct, times_wrong);
+    let after_success = interval(times_correct + 1, times_wrong);
+    let after_lapse = interval(times_correct, times_wrong + 1);
+
+    if current <= 0 {
+        return Err(violation("positive interval", format!("{current} minutes")));
+    }
+    let max_minutes = i64::from(max_interval_days) * 24 * 60;
+    if current > max_minutes {
+        return Err(violation(
+            "bounded by the max interval",
+            format!("{current} minutes over {max_minutes}"),
+        ));
+    }
+    if after_success < current {
+        return Err(violation(
+            "monotonic with successes",
+            format!("{current} minutes, then {after_success} after a correct answer"),
+        ));
+    }
+    if after_lapse > current {
+        return Err(violation(
+            "lapse never lengthens",
+            format!("{current} minutes, then {after_lapse} after a wrong answer"),
+        ));
+    }
+    if after_success > current && after_lapse >= after_success {
+        return Err(violation(
+            "lapse shortens",
+            format!(
+                "{after_lapse} minutes after a wrong answer, {after_success} after a correct one"
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        MAX_INTERVAL_DAYS, ScheduleOptions, compute_next_review,
+        compute_next_review_with_difficulty, compute_next_review_with_options,
+    };
+    use chrono::TimeZone;
+    use proptest::prelude::*;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap()
+    }
+
+    /// Answer counts from brand new to long past mastery, lapses included
+    fn answers() -> impl Strategy<Value = (i32, i32)> {
+        (0..40i32, 0..40i32)
+    }
+
+    /// Settings the API accepts for a deck or learner
+    fn options() -> impl Strategy<Value = ScheduleOptions> {
+        (0.5..=2.0f64, 1..=365i32).prop_map(|(ease, max_interval_days)| ScheduleOptions {
+            ease,
+            max_interval_days,
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_plain_schedule_keeps_invariants((correct, wrong) in answers()) {
+            let interval = interval_of(compute_next_review, now());
+            check_scheduler_invariants(interval, MAX_INTERVAL_DAYS, correct, wrong)
+                .map_err(|violation| TestCaseError::fail(violation.to_string()))?;
+        }
+
+        #[test]
+        fn test_difficulty_schedule_keeps_invariants(
+            (correct, wrong) in answers(),
+            difficulty in proptest::option::of(0.0..=1.0f64),
+        ) {
+            let interval = interval_of(
+                |correct, wrong, now| {
+                    compute_next_review_with_difficulty(correct, wrong, difficulty, now)
+                },
+                now(),
+            );
+            check_scheduler_invariants(interval, MAX_INTERVAL_DAYS, correct, wrong)
+                .map_err(|violation| TestCaseError::fail(violation.to_string()))?;
+        }
+
+        #[test]
+        fn test_configured_schedule_keeps_invariants(
+            (correct, wrong) in answers(),
+            difficulty in proptest::option::of(0.0..=1.0f64),
+            options in options(),
+        ) {
+            let interval = interval_of(
+                |correct, wrong, now| {
+                    compute_next_review_with_options(correct, wrong, difficulty, options, now)
+                },
+                now(),
+            );
+            check_scheduler_invariants(interval, options.max_interval_days, correct, wrong)
+                .map_err(|violation| TestCaseError::fail(violation.to_string()))?;
+        }
+    }
+
+    #[test]
+    fn test_violations_are_reported() {
+        // Forgetting a card pushes it further out
+        let backwards = |correct: i32, wrong: i32| i64::from(60 * (1 + correct + wrong));
+        let violation = check_scheduler_invariants(backwards, 90, 2, 1).unwrap_err();
+        assert_eq!(violation.invariant, "lapse never lengthens");
+        assert_eq!((violation.times_correct, violation.times_wrong), (2, 1));
+
+        let unbounded = |correct: i32, _wrong: i32| i64::from(correct) * 24 * 60;
+        assert_eq!(
+            check_scheduler_invariants(unbounded, 3, 4, 0)
+                .unwrap_err()
+                .invariant,
+            "bounded by the max interval"
+        );
+        assert_eq!(
+            check_scheduler_invariants(unbounded, 3, 0, 0)
+                .unwrap_err()
+                .invariant,
+            "positive interval"
+        );
+
+        // Too low an ease pulls the first day-based step below the last learning step
+        let low_ease = ScheduleOptions {
+            ease: 0.25,
+            ..ScheduleOptions::default()
+        };
+        let interval = interval_of(
+            |correct, wrong, now| {
+                compute_next_review_with_options(correct, wrong, None, low_ease, now)
+            },
+            now(),
+        );
+        assert_eq!(
+            check_scheduler_invariants(interval, MAX_INTERVAL_DAYS, 2, 0)
+                .unwrap_err()
+                .invariant,
+            "monotonic with successes"
+        );
+    }
+}
@@ -5,12 +5,28 @@
 
 use chrono::{DateTime, Duration, Utc};
 
+pub mod clock;
+pub mod invariants;
+pub mod simulate;
+
 /// The score at which a card is considered mastered.
 ///
 /// When `times_correct - times_wrong >= MASTERY_THRESHOLD`, the card reaches
 /// its maximum review interval and is flagged as mastered.
 pub const MASTERY_THRESHOLD: i32 = 10;
 
+/// The score at which the words of a card count as known.
+///
+/// This is where a card leaves the hour-based learning intervals. New sentence
+/// cards built from known words are introduced before ones with unfamiliar words.
+pub const KNOWN_WORD_SCORE: i32 = 3;
+
+/// The score given to cards the learner marks as already known.
+///
+/// High enough for a 20-day interval, below [`MASTERY_THRESHOLD`] so a few
+/// reviews still confirm the learner really knows the card.
+pub const ALREADY_KNOWN_SCORE: i32 = 7;
+
 /// SRS intervals in hours, indexed by score.
 ///
 /// Scores 0-2 use hour-based intervals for aggressive early practice,
@@ -67,6 +83,139 @@ pub fn compute_next_review(
     now + Duration::hours(hours)
 }
 
+/// Compute the next review date, shortening the learning phase for hard cards.
+///
+/// While a card is still in its hour-based intervals (score ≤ 2), the interval
+/// is scaled down by up to half for cards with a high global
+/// [difficulty score](difficulty_score), so learners see them again sooner.
+/// Day-based intervals are unchanged: once a learner has a card down, its
+/// global difficulty no longer says much about them.
+///
+/// # Arguments
+///
+/// * `times_correct` - Number of times the card was answered correctly
+/// * `times_wrong` - Number of times the card was answered incorrectly
+/// * `difficulty` - Global difficulty in `0.0..=1.0`, `None` when not yet known
+/// * `now` - The current time, for deterministic scheduling
+pub fn compute_next_review_with_difficulty(
+    times_correct: i32,
+    times_wrong: i32,
+    difficulty: Option<f64>,
+    now: DateTime<Utc>,
+) -> DateTime<Utc> {
+    compute_next_review_with_options(
+        times_correct,
+        times_wrong,
+        difficulty,
+        ScheduleOptions::default(),
+        now,
+    )
+}
+
+/// Largest share of a learning-phase interval removed for the hardest cards
+const MAX_LEARNING_INTERVAL_REDUCTION: f64 = 0.5;
+
+/// Longest interval of the default schedule, reached at [`MASTERY_THRESHOLD`]
+pub const MAX_INTERVAL_DAYS: i32 = 90;
+
+/// Ease of the default schedule: day-based intervals as in the table
+pub const DEFAULT_EASE: f64 = 1.0;
+
+/// Scheduling settings a deck or learner can change.
+///
+/// The defaults give the plain schedule of [`compute_next_review_with_difficulty`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScheduleOptions {
+    /// Multiplies day-based intervals; the hour-based learning intervals are
+    /// left alone so new cards still come back the same day
+    pub ease: f64,
+    /// No review is scheduled further ahead than this, ease included
+    pub max_interval_days: i32,
+}
+
+impl Default for ScheduleOptions {
+    fn default() -> Self {
+        Self {
+            ease: DEFAULT_EASE,
+            max_interval_days: MAX_INTERVAL_DAYS,
+        }
+    }
+}
+
+/// Compute the next review date with a deck's or learner's [`ScheduleOptions`].
+///
+/// Hard cards come back sooner during the learning phase as in
+/// [`compute_next_review_with_difficulty`]; day-based intervals are scaled by
+/// the ease, and every interval is capped at the maximum interval.
+///
+/// # Arguments
+///
+/// * `times_correct` - Number of times the card was answered correctly
+/// * `times_wrong` - Number of times the card was answered incorrectly
+/// * `difficulty` - Global difficulty in `0.0..=1.0`, `None` when not yet known
+/// * `options` - Ease and maximum interval
+/// * `now` - The current time, for deterministic scheduling
+pub fn compute_next_review_with_options(
+    times_correct: i32,
+    times_wrong: i32,
+    difficulty: Option<f64>,
+    options: ScheduleOptions,
+    now: DateTime<Utc>,
+) -> DateTime<Utc> {
+    let score = calculate_score(times_correct, times_wrong);
+    now + Duration::minutes(interval_minutes(score, difficulty, options))
+}
+
+/// The interval after a review that leaves a card at `score`, in minutes.
+///
+/// See [`compute_next_review_with_options`].
+pub fn interval_minutes(score: i32, difficulty: Option<f64>, options: ScheduleOptions) -> i64 {
+    let hours = get_interval_for_score(score);
+    let factor = if hours >= 24 {
+        options.ease.max(0.0)
+    } else {
+        difficulty.map_or(1.0, |difficulty| {
+            1.0 - MAX_LEARNING_INTERVAL_REDUCTION * difficulty.clamp(0.0, 1.0)
+        })
+    };
+    let minutes = (hours as f64 * 60.0 * factor).round() as i64;
+    let max_minutes = i64::from(options.max_interval_days.max(1)) * 24 * 60;
+    minutes.min(max_minutes)
+}
+
+/// Reviews a card needs across all users before it gets a difficulty score
+pub const MIN_DIFFICULTY_REVIEWS: i64 = 20;
+
+/// Answers at or below this time do not add to a card's difficulty
+const FAST_ANSWER_MS: f64 = 2_000.0;
+
+/// Answers at or above this time count as maximally slow
+const SLOW_ANSWER_MS: f64 = 12_000.0;
+
+/// Share of the difficulty score taken from the failure rate; the rest comes
+/// from answer latency when it is known
+const FAILURE_RATE_WEIGHT: f64 = 0.75;
+
+/// Global difficulty of a card in `0.0..=1.0` (0 = easy, 1 = hard).
+///
+/// Combines the share of wrong answers across all users with how long users
+/// take to answer. Without latency data the failure rate is used alone.
+///
+/// # Arguments
+///
+/// * `failure_rate` - Wrong answers divided by all answers, `0.0..=1.0`
+/// * `avg_response_ms` - Mean time to answer, if any reviews reported it
+pub fn difficulty_score(failure_rate: f64, avg_response_ms: Option<f64>) -> f64 {
+    let failure_rate = failure_rate.clamp(0.0, 1.0);
+    let Some(avg_response_ms) = avg_response_ms else {
+        return failure_rate;
+    };
+
+    let slowness =
+        ((avg_response_ms - FAST_ANSWER_MS) / (SLOW_ANSWER_MS - FAST_ANSWER_MS)).clamp(0.0, 1.0);
+    FAILURE_RATE_WEIGHT * failure_rate + (1.0 - FAILURE_RATE_WEIGHT) * slowness
+}
+
 /// Calculate the current SRS score for a card.
 ///
 /// # Arguments
@@ -100,6 +249,92 @@ pub fn get_interval_for_score(score: i32) -> i64 {
     INTERVALS_HOURS[index]
 }
 
+/// Reviews a new card gets in its first `days` days if every answer is correct.
+///
+/// Counts the first review plus each scheduled one. Multiplied by a daily
+/// number of new cards, this is the review load those new cards settle into.
+pub fn reviews_in_first_days(days: i64) -> i64 {
+    let horizon_hours = days * 24;
+    let mut elapsed_hours = 0;
+    let mut reviews = 0;
+    while elapsed_hours < horizon_hours {
+        reviews += 1;
+        elapsed_hours += get_interval_for_score(reviews as i32);
+    }
+    reviews
+}
+
+/// XP for a correct answer, before the difficulty bonus
+pub const CORRECT_REVIEW_XP: i32 = 10;
+
+/// XP for a wrong answer: showing up still counts, but far less
+pub const WRONG_REVIEW_XP: i32 = 2;
+
+/// Extra XP for a correct answer on a card of difficulty 1.0; easier cards get
+/// a proportional share, cards without a difficulty score get none
+pub const MAX_DIFFICULTY_BONUS_XP: i32 = 10;
+
+/// Each level costs this much more XP than the one before
+pub const LEVEL_XP_STEP: i64 = 100;
+
+/// XP earned by one review, split by where it came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ReviewXp {
+    pub correct: i32,
+    pub wrong: i32,
+    pub difficulty_bonus: i32,
+}
+
+impl ReviewXp {
+    pub fn total(&self) -> i32 {
+        self.correct + self.wrong + self.difficulty_bonus
+    }
+}
+
+/// XP earned by a review.
+///
+/// # Arguments
+///
+/// * `is_correct` - Whether the answer was correct
+/// * `difficulty` - Global difficulty in `0.0..=1.0`, `None` when not yet known
+pub fn review_xp(is_correct: bool, difficulty: Option<f64>) -> ReviewXp {
+    if !is_correct {
+        return ReviewXp {
+            wrong: WRONG_REVIEW_XP,
+            ..ReviewXp::default()
+        };
+    }
+    let bonus = difficulty.map_or(0.0, |d| d.clamp(0.0, 1.0) * MAX_DIFFICULTY_BONUS_XP as f64);
+    ReviewXp {
+        correct: CORRECT_REVIEW_XP,
+        difficulty_bonus: bonus.round() as i32,
+        ..ReviewXp::default()
+    }
+}
+
+/// Total XP needed to reach `level`; level 1 starts at 0.
+///
+/// Going from level `n` to `n + 1` costs `n * LEVEL_XP_STEP`, so level 2 is
+/// at 100 XP, level 3 at 300 and level 10 at 4,500.
+pub fn xp_for_level(level: i32) -> i64 {
+    let n = i64::from(level.max(1));
+    LEVEL_XP_STEP * n * (n - 1) / 2
+}
+
+/// The level reached with `xp` total XP
+pub fn level_for_xp(xp: i64) -> i32 {
+    // Solve xp_for_level(n) <= xp for n, then correct for float rounding
+    let estimate = (0.5 + (0.25 + 2.0 * xp.max(0) as f64 / LEVEL_XP_STEP as f64).sqrt()) as i32;
+    let mut level = estimate.max(1);
+    while xp_for_level(level) > xp && level > 1 {
+        level -= 1;
+    }
+    while xp_for_level(level + 1) <= xp {
+        level += 1;
+    }
+    level
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -192,4 +427,139 @@ mod tests {
         let next = compute_next_review(3, 0, now);
         assert_eq!(next, Utc.with_ymd_and_hms(2025, 6, 16, 12, 0, 0).unwrap());
     }
+
+    #[test]
+    fn test_difficulty_score() {
+        assert_eq!(difficulty_score(0.0, None), 0.0);
+        assert_eq!(difficulty_score(0.4, None), 0.4);
+
+        // Fast answers only count the failure rate's share
+        assert!((difficulty_score(0.4, Some(1_000.0)) - 0.3).abs() < 1e-9);
+        // Very slow answers add the full latency share
+        assert!((difficulty_score(0.4, Some(60_000.0)) - 0.55).abs() < 1e-9);
+        assert_eq!(difficulty_score(1.0, Some(60_000.0)), 1.0);
+
+        // Out-of-range input is clamped
+        assert_eq!(difficulty_score(1.5, None), 1.0);
+    }
+
+    #[test]
+    fn test_difficulty_shortens_learning_intervals_only() {
+        let now = fixed_now();
+
+        // Unknown difficulty matches the plain schedule
+        assert_eq!(
+            compute_next_review_with_difficulty(1, 0, None, now),
+            compute_next_review(1, 0, now)
+        );
+
+        // Hardest card: 4 hours becomes 2 hours, 2 hours becomes 1 hour
+        let next = compute_next_review_with_difficulty(1, 0, Some(1.0), now);
+        assert_eq!((next - now).num_minutes(), 120);
+        let next = compute_next_review_with_difficulty(0, 0, Some(1.0), now);
+        assert_eq!((next - now).num_minutes(), 60);
+
+        // Medium card: 8 hours becomes 6 hours
+        let next = compute_next_review_with_difficulty(2, 0, Some(0.5), now);
+        assert_eq!((next - now).num_minutes(), 360);
+
+        // Day-based intervals are left alone
+        assert_eq!(
+            compute_next_review_with_difficulty(3, 0, Some(1.0), now),
+            compute_next_review(3, 0, now)
+        );
+    }
+
+    #[test]
+    fn test_default_options_match_the_plain_schedule() {
+        let now = fixed_now();
+        for times_correct in 0..12 {
+            for difficulty in [None, Some(0.7)] {
+                assert_eq!(
+                    compute_next_review_with_options(
+                        times_correct,
+                        0,
+                        difficulty,
+                        ScheduleOptions::default(),
+                        now
+                    ),
+                    compute_next_review_with_difficulty(times_correct, 0, difficulty, now)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_ease_scales_day_intervals_up_to_the_cap() {
+        let now = fixed_now();
+        let options = ScheduleOptions {
+            ease: 1.5,
+            max_interval_days: 30,
+        };
+
+        // 2 days becomes 3 days; learning intervals are left alone
+        let next = compute_next_review_with_options(4, 0, None, options, now);
+        assert_eq!((next - now).num_hours(), 72);
+        let next = compute_next_review_with_options(1, 0, None, options, now);
+        assert_eq!((next - now).num_hours(), 4);
+
+        // 20 days would become 30, 40 days would become 60: both capped at 30
+        let next = compute_next_review_with_options(7, 0, None, options, now);
+        assert_eq!((next - now).num_days(), 30);
+        let next = compute_next_review_with_options(8, 0, None, options, now);
+        assert_eq!((next - now).num_days(), 30);
+
+        // A cap alone shortens only the intervals above it
+        let capped = ScheduleOptions {
+            max_interval_days: 7,
+            ..ScheduleOptions::default()
+        };
+        let next = compute_next_review_with_options(5, 0, None, capped, now);
+        assert_eq!((next - now).num_days(), 5);
+        let next = compute_next_review_with_options(10, 0, None, capped, now);
+        assert_eq!((next - now).num_days(), 7);
+    }
+
+    #[test]
+    fn test_max_interval_matches_interval_table() {
+        assert_eq!(
+            get_interval_for_score(MASTERY_THRESHOLD),
+            i64::from(MAX_INTERVAL_DAYS) * 24
+        );
+    }
+
+    #[test]
+    fn test_review_xp() {
+        assert_eq!(review_xp(false, Some(1.0)).total(), WRONG_REVIEW_XP);
+        assert_eq!(review_xp(true, None).total(), CORRECT_REVIEW_XP);
+        let hard = review_xp(true, Some(0.55));
+        assert_eq!(hard.correct, CORRECT_REVIEW_XP);
+        assert_eq!(hard.difficulty_bonus, 6);
+    }
+
+    #[test]
+    fn test_level_curve() {
+        assert_eq!(xp_for_level(1), 0);
+        assert_eq!(xp_for_level(2), 100);
+        assert_eq!(xp_for_level(3), 300);
+        assert_eq!(xp_for_level(10), 4_500);
+        assert_eq!(level_for_xp(0), 1);
+        assert_eq!(level_for_xp(99), 1);
+        assert_eq!(level_for_xp(100), 2);
+        assert_eq!(level_for_xp(299), 2);
+        assert_eq!(level_for_xp(4_500), 10);
+        for level in 1..500 {
+            assert_eq!(level_for_xp(xp_for_level(level)), level);
+            assert_eq!(level_for_xp(xp_for_level(level + 1) - 1), level);
+        }
+    }
+
+    #[test]
+    fn test_reviews_in_first_days() {
+        assert_eq!(reviews_in_first_days(0), 0);
+        // At 0h, 4h, 12h and 36h
+        assert_eq!(reviews_in_first_days(2), 4);
+        // Then 84h, 204h, 444h; the next is 924h
+        assert_eq!(reviews_in_first_days(30), 7);
+    }
 }
@@ -0,0 +1,345 @@
+//! Workload and retention forecasts for a schedule.
+//!
+//! [`simulate`] plays a schedule forward day by day for a synthetic learner
+//! whose answers follow an [`AccuracyModel`]. Rather than drawing random
+//! answers, each review splits a card into its expected correct and wrong
+//! shares, so a forecast is deterministic and its counts are expected values.
+//!
+//! The learner studies once a day and clears every due card: intervals are
+//! rounded up to whole days, so hour-based learning steps come back the next
+//! day. Daily limits and backlogs are not modelled.
+
+use std::array;
+
+use crate::{MASTERY_THRESHOLD, ScheduleOptions, get_interval_for_score, interval_minutes};
+
+/// Lowest score tracked; cards answered wrong more often count as this score
+const MIN_SCORE: i32 = -5;
+
+/// Scores tracked, from [`MIN_SCORE`] to [`MASTERY_THRESHOLD`]
+const SCORES: usize = (MASTERY_THRESHOLD - MIN_SCORE + 1) as usize;
+
+/// How likely the synthetic learner is to answer correctly
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AccuracyModel {
+    /// Chance the first answer to a new card is correct
+    pub new_card: f64,
+    /// Chance of recalling a card reviewed after its default interval.
+    ///
+    /// Recall decays exponentially with the time since the last review, so a
+    /// card left longer than the default schedule would leave it is recalled
+    /// less often, and one reviewed sooner more often.
+    pub recall: f64,
+}
+
+impl Default for AccuracyModel {
+    fn default() -> Self {
+        Self {
+            new_card: 0.6,
+            recall: 0.9,
+        }
+    }
+}
+
+impl AccuracyModel {
+    /// Chance of recalling a card at `score` reviewed `elapsed_days` after the
+    /// review that left it there
+    pub fn recall_probability(&self, score: i32, elapsed_days: f64) -> f64 {
+        // Learning steps shorter than a day count as a day, as they are studied daily
+        let stability_days = (get_interval_for_score(score) as f64 / 24.0).max(1.0);
+        self.recall
+            .clamp(0.0, 1.0)
+            .powf(elapsed_days.max(0.0) / stability_days)
+    }
+}
+
+/// A card the learner has studied before the simulation starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StudiedCard {
+    pub score: i32,
+    /// Days until the card is due, 0 when due today or overdue
+    pub due_in_days: u32,
+}
+
+/// What to simulate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Simulation {
+    pub options: ScheduleOptions,
+    pub accuracy: AccuracyModel,
+    /// Days to simulate, today included
+    pub days: u32,
+    /// Cards not studied yet
+    pub new_cards: u32,
+    /// New cards introduced each day until none are left
+    pub new_cards_per_day: u32,
+}
+
+/// Expected figures of one simulated day
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SimulatedDay {
+    /// Days from the start, 0 being today
+    pub day: u32,
+    /// Cards studied for the first time
+    pub new_cards: u32,
+    /// Reviews of cards studied on an earlier day
+    pub reviews: f64,
+    /// Expected correct answers among `reviews`
+    pub correct: f64,
+    /// Cards at the mastery threshold at the end of the day
+    pub mastered: f64,
+}
+
+impl SimulatedDay {
+    /// Share of the day's reviews answered correctly; None without reviews
+    pub fn retention(&self) -> Option<f64> {
+        (self.reviews > 0.0).then(|| self.correct / self.reviews)
+    }
+}
+
+/// Forecast the daily workload and retention of a schedule.
+///
+/// # Arguments
+///
+/// * `simulation` - Schedule, learner and horizon
+/// * `studied` - Cards studied before, with their score and due date
+///
+/// # Returns
+///
+/// One [`SimulatedDay`] per simulated day, today first
+pub fn simulate(simulation: &Simulation, studied: &[StudiedCard]) -> Vec<SimulatedDay> {
+    let days = simulation.days as usize;
+    let interval_days: [usize; SCORES] =
+        array::from_fn(|index| interval_days(score_of(index), simulation.options));
+    let mut queue = Queue {
+        due: vec![[0.0; SCORES]; days],
+        by_score: [0.0; SCORES],
+        interval_days,
+    };
+    for card in studied {
+        let index = score_index(card.score);
+        queue.by_score[index] += 1.0;
+        if let Some(due) = queue.due.get_mut(card.due_in_days as usize) {
+            due[index] += 1.0;
+        }
+    }
+
+    let new_card_accuracy = simulation.accuracy.new_card.clamp(0.0, 1.0);
+    let mut new_cards_left = simulation.new_cards;
+    let mut forecast = Vec::with_capacity(days);
+    for day in 0..days {
+        let mut result = SimulatedDay {
+            day: day as u32,
+            ..SimulatedDay::default()
+        };
+
+        let due = std::mem::take(&mut queue.due[day]);
+        for (index, cards) in due.into_iter().enumerate() {
+            if cards == 0.0 {
+                continue;
+            }
+            let score = score_of(index);
+            let recall = simulation
+                .accuracy
+                .recall_probability(score, interval_days[index] as f64);
+            result.reviews += cards;
+            result.correct += cards * recall;
+            queue.by_score[index] -= cards;
+            queue.schedule(day, score + 1, cards * recall);
+            queue.schedule(day, score - 1, cards * (1.0 - recall));
+        }
+
+        let introduced = new_cards_left.min(simulation.new_cards_per_day);
+        if introduced > 0 {
+            new_cards_left -= introduced;
+            let cards = f64::from(introduced);
+            queue.schedule(day, 1, cards * new_card_accuracy);
+            queue.schedule(day, -1, cards * (1.0 - new_card_accuracy));
+        }
+        result.new_cards = introduced;
+        result.mastered = queue.by_score[score_index(MASTERY_THRESHOLD)];
+        forecast.push(result);
+    }
+    forecast
+}
+
+/// Expected cards due each day and held at each score
+struct Queue {
+    due: Vec<[f64; SCORES]>,
+    by_score: [f64; SCORES],
+    interval_days: [usize; SCORES],
+}
+
+impl Queue {
+    /// Move `cards` answered on `day` to `score`, due again after its interval
+    fn schedule(&mut self, day: usize, score: i32, cards: f64) {
+        let index = score_index(score);
+        self.by_score[index] += cards;
+        if let Some(due) = self.due.get_mut(day + self.interval_days[index]) {
+            due[index] += cards;
+        }
+    }
+}
+
+fn score_index(score: i32) -> usize {
+    (score.clamp(MIN_SCORE, MASTERY_THRESHOLD) - MIN_SCORE) as usize
+}
+
+fn score_of(index: usize) -> i32 {
+    index as i32 + MIN_SCORE
+}
+
+/// Whole days until a card at `score` is studied again, at least one
+fn interval_days(score: i32, options: ScheduleOptions) -> usize {
+    let minutes = interval_minutes(score, None, options);
+    (minutes.max(1) as usize).div_ceil(24 * 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn perfect_learner() -> AccuracyModel {
+        AccuracyModel {
+            new_card: 1.0,
+            recall: 1.0,
+        }
+    }
+
+    fn simulation(options: ScheduleOptions, accuracy: AccuracyModel) -> Simulation {
+        Simulation {
+            options,
+            accuracy,
+            days: 180,
+            new_cards: 200,
+            new_cards_per_day: 10,
+        }
+    }
+
+    fn totals(forecast: &[SimulatedDay]) -> (f64, f64) {
+        let reviews: f64 = forecast.iter().map(|day| day.reviews).sum();
+        let correct: f64 = forecast.iter().map(|day| day.correct).sum();
+        (reviews, correct / reviews)
+    }
+
+    #[test]
+    fn test_perfect_learner_follows_the_schedule() {
+        let forecast = simulate(
+            &Simulation {
+                days: 40,
+                new_cards: 1,
+                new_cards_per_day: 1,
+                ..simulation(ScheduleOptions::default(), perfect_learner())
+            },
+            &[],
+        );
+        assert_eq!(forecast.len(), 40);
+        assert_eq!(forecast[0].new_cards, 1);
+        assert_eq!(forecast[0].reviews, 0.0);
+        assert_eq!(forecast[0].retention(), None);
+
+        // 4 hours, 8 hours, then 1, 2, 5, 10 and 20 days
+        let review_days: Vec<u32> = forecast
+            .iter()
+            .filter(|day| day.reviews > 0.0)
+            .map(|day| day.day)
+            .collect();
+        assert_eq!(review_days, [1, 2, 3, 5, 10, 20]);
+        assert!(forecast.iter().all(|day| day.correct == day.reviews));
+    }
+
+    #[test]
+    fn test_new_cards_run_out() {
+        let forecast = simulate(
+            &simulation(ScheduleOptions::default(), perfect_learner()),
+            &[],
+        );
+        let introduced: u32 = forecast.iter().map(|day| day.new_cards).sum();
+        assert_eq!(introduced, 200);
+        assert_eq!(forecast[19].new_cards, 10);
+        assert_eq!(forecast[20].new_cards, 0);
+        assert_eq!(forecast[179].mastered, 200.0);
+    }
+
+    #[test]
+    fn test_studied_cards_come_due() {
+        let studied = [
+            StudiedCard {
+                score: 5,
+                due_in_days: 0,
+            },
+            StudiedCard {
+                score: 3,
+                due_in_days: 2,
+            },
+            StudiedCard {
+                score: 4,
+                due_in_days: 500,
+            },
+        ];
+        let run = |accuracy| {
+            simulate(
+                &Simulation {
+                    days: 3,
+                    new_cards: 0,
+                    ..simulation(ScheduleOptions::default(), accuracy)
+                },
+                &studied,
+            )
+        };
+        let forecast = run(perfect_learner());
+        assert_eq!(forecast[0].reviews, 1.0);
+        assert_eq!(forecast[1].reviews, 0.0);
+        assert_eq!(forecast[2].reviews, 1.0);
+        assert_eq!(forecast[0].retention(), Some(1.0));
+
+        // A forgotten card comes back sooner
+        let forecast = run(AccuracyModel::default());
+        assert!((forecast[0].retention().unwrap() - 0.9).abs() < 1e-9);
+        assert!((forecast[2].reviews - 1.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_shorter_cap_costs_reviews_and_buys_retention() {
+        let accuracy = AccuracyModel::default();
+        let (default_reviews, default_retention) = totals(&simulate(
+            &simulation(ScheduleOptions::default(), accuracy),
+            &[],
+        ));
+        let (capped_reviews, capped_retention) = totals(&simulate(
+            &simulation(
+                ScheduleOptions {
+                    max_interval_days: 7,
+                    ..ScheduleOptions::default()
+                },
+                accuracy,
+            ),
+            &[],
+        ));
+        let (easy_reviews, easy_retention) = totals(&simulate(
+            &simulation(
+                ScheduleOptions {
+                    ease: 2.0,
+                    ..ScheduleOptions::default()
+                },
+                accuracy,
+            ),
+            &[],
+        ));
+
+        assert!(capped_reviews > default_reviews);
+        assert!(capped_retention > default_retention);
+        assert!(easy_reviews < default_reviews);
+        assert!(easy_retention < default_retention);
+    }
+
+    #[test]
+    fn test_recall_decays_past_the_default_interval() {
+        let accuracy = AccuracyModel::default();
+        // Score 5 is a 5-day interval
+        assert!((accuracy.recall_probability(5, 5.0) - 0.9).abs() < 1e-9);
+        assert!(accuracy.recall_probability(5, 10.0) < 0.9);
+        assert!(accuracy.recall_probability(5, 1.0) > 0.9);
+        // Learning steps count as a day
+        assert!((accuracy.recall_probability(0, 1.0) - 0.9).abs() < 1e-9);
+    }
+}